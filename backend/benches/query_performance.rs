@@ -0,0 +1,406 @@
+//! Query performance benchmarks for the Coffee Quality Management Platform
+//!
+//! Seeds a large synthetic dataset (100k harvests and 1M inventory transactions
+//! by default) into a dedicated benchmark business and measures the endpoints
+//! whose service SQL is most sensitive to data volume:
+//! - Dashboard summary (`GET /api/v1/reports/dashboard`)
+//! - Public traceability view (`GET /api/v1/trace/:code`)
+//! - Inventory balance (`GET /api/v1/inventory/lots/:lot_id/balance`)
+//!
+//! The benchmarks run against a live server so that the full request path
+//! (auth middleware, handler, service queries) is measured.
+//!
+//! Environment:
+//! - `CQM_BENCH_DATABASE_URL` - database used by the running server (required)
+//! - `CQM_BENCH_BASE_URL` - server base URL, e.g. `http://localhost:3000` (required)
+//! - `CQM_BENCH_HARVESTS` - number of harvests to seed (default 100000)
+//! - `CQM_BENCH_TRANSACTIONS` - number of inventory transactions to seed (default 1000000)
+//! - `CQM_BENCH_RESEED` - set to `1` to drop and reseed the benchmark business
+//! - `CQM__JWT__SECRET` - must match the server's JWT secret
+//!
+//! Run with:
+//! ```text
+//! cargo bench -p coffee-quality-management-backend --bench query_performance
+//! ```
+//!
+//! When the required variables are not set the benchmark is skipped.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use criterion::Criterion;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::Serialize;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+/// Business code reserved for benchmark data
+const BENCH_BUSINESS_CODE: &str = "BENCH";
+
+/// Number of plots harvests are spread across
+const BENCH_PLOTS: i64 = 50;
+
+/// Average number of harvests recorded per lot
+const HARVESTS_PER_LOT: i64 = 10;
+
+/// Benchmark configuration read from the environment
+struct BenchEnv {
+    database_url: String,
+    base_url: String,
+    jwt_secret: String,
+    harvests: i64,
+    transactions: i64,
+    reseed: bool,
+}
+
+impl BenchEnv {
+    fn from_env() -> Option<Self> {
+        let database_url = std::env::var("CQM_BENCH_DATABASE_URL").ok()?;
+        let base_url = std::env::var("CQM_BENCH_BASE_URL").ok()?;
+
+        let jwt_secret = std::env::var("CQM__JWT__SECRET")
+            .or_else(|_| std::env::var("CQM_JWT_SECRET"))
+            .unwrap_or_else(|_| "development-secret-key".to_string());
+
+        Some(Self {
+            database_url,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            jwt_secret,
+            harvests: env_i64("CQM_BENCH_HARVESTS", 100_000),
+            transactions: env_i64("CQM_BENCH_TRANSACTIONS", 1_000_000),
+            reseed: std::env::var("CQM_BENCH_RESEED").map(|v| v == "1").unwrap_or(false),
+        })
+    }
+}
+
+fn env_i64(key: &str, default: i64) -> i64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default)
+}
+
+/// Identifiers needed by the benchmarked requests
+struct Fixture {
+    access_token: String,
+    traceability_code: String,
+    lot_id: Uuid,
+}
+
+/// JWT claims accepted by the auth middleware
+#[derive(Serialize)]
+struct Claims {
+    sub: String,
+    business_id: String,
+    role_id: String,
+    permissions: Vec<String>,
+    exp: i64,
+    iat: i64,
+}
+
+/// Seed the benchmark business (reusing existing data when it is large enough)
+async fn seed(pool: &PgPool, env: &BenchEnv) -> Result<Fixture, sqlx::Error> {
+    if env.reseed {
+        sqlx::query("DELETE FROM businesses WHERE business_code = $1")
+            .bind(BENCH_BUSINESS_CODE)
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO businesses (name, business_type, business_code, province)
+        VALUES ('Benchmark Coffee Estate', 'multi', $1, 'Chiang Rai')
+        ON CONFLICT (business_code) DO NOTHING
+        "#,
+    )
+    .bind(BENCH_BUSINESS_CODE)
+    .execute(pool)
+    .await?;
+
+    let business_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM businesses WHERE business_code = $1",
+    )
+    .bind(BENCH_BUSINESS_CODE)
+    .fetch_one(pool)
+    .await?;
+
+    let role_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM roles WHERE business_id = $1 AND name = 'owner'",
+    )
+    .bind(business_id)
+    .fetch_one(pool)
+    .await?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO users (business_id, role_id, email, password_hash, name)
+        VALUES ($1, $2, 'bench@example.com', '!', 'Benchmark Owner')
+        ON CONFLICT (business_id, email) DO UPDATE SET name = EXCLUDED.name
+        RETURNING id
+        "#,
+    )
+    .bind(business_id)
+    .bind(role_id)
+    .fetch_one(pool)
+    .await?;
+
+    let (harvest_count, transaction_count) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM harvests WHERE business_id = $1),
+            (SELECT COUNT(*) FROM inventory_transactions WHERE business_id = $1)
+        "#,
+    )
+    .bind(business_id)
+    .fetch_one(pool)
+    .await?;
+
+    if harvest_count < env.harvests || transaction_count < env.transactions {
+        seed_dataset(pool, business_id, env).await?;
+    }
+
+    let (lot_id, traceability_code) = sqlx::query_as::<_, (Uuid, String)>(
+        r#"
+        SELECT id, traceability_code
+        FROM lots
+        WHERE business_id = $1
+        ORDER BY traceability_code
+        LIMIT 1
+        "#,
+    )
+    .bind(business_id)
+    .fetch_one(pool)
+    .await?;
+
+    let permissions = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT CONCAT(p.resource, ':', p.action)
+        FROM role_permissions rp
+        JOIN permissions p ON p.id = rp.permission_id
+        WHERE rp.role_id = $1
+        "#,
+    )
+    .bind(role_id)
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        business_id: business_id.to_string(),
+        role_id: role_id.to_string(),
+        permissions,
+        exp: now + 24 * 3600,
+        iat: now,
+    };
+    let access_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(env.jwt_secret.as_bytes()),
+    )
+    .expect("failed to sign benchmark token");
+
+    Ok(Fixture {
+        access_token,
+        traceability_code,
+        lot_id,
+    })
+}
+
+/// Replace the benchmark business's plots, lots, harvests and transactions
+async fn seed_dataset(pool: &PgPool, business_id: Uuid, env: &BenchEnv) -> Result<(), sqlx::Error> {
+    let lots = (env.harvests / HARVESTS_PER_LOT).max(1);
+
+    println!(
+        "Seeding {} harvests, {} lots and {} inventory transactions...",
+        env.harvests, lots, env.transactions
+    );
+
+    let mut tx = pool.begin().await?;
+
+    // Lots cascade to harvests and inventory transactions
+    sqlx::query("DELETE FROM lots WHERE business_id = $1")
+        .bind(business_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM plots WHERE business_id = $1")
+        .bind(business_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO plots (business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent)
+        SELECT $1, 'Bench Plot ' || g, 19.9 + g * 0.001, 99.8 + g * 0.001, 5 + (g % 10), 1000 + g * 10, g % 100
+        FROM generate_series(1, $2) g
+        "#,
+    )
+    .bind(business_id)
+    .bind(BENCH_PLOTS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO lots (business_id, traceability_code, name, stage, current_weight_kg)
+        SELECT $1,
+               'CQM-2024-' || $2 || '-' || LPAD(g::text, 7, '0'),
+               'Bench Lot ' || g,
+               (ARRAY['cherry', 'parchment', 'green_bean', 'roasted_bean'])[1 + g % 4],
+               100 + (g % 400)
+        FROM generate_series(1, $3) g
+        "#,
+    )
+    .bind(business_id)
+    .bind(BENCH_BUSINESS_CODE)
+    .bind(lots)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        WITH l AS (
+            SELECT id, ROW_NUMBER() OVER (ORDER BY traceability_code) - 1 AS rn
+            FROM lots WHERE business_id = $1
+        ),
+        p AS (
+            SELECT id, ROW_NUMBER() OVER (ORDER BY name) - 1 AS rn
+            FROM plots WHERE business_id = $1
+        )
+        INSERT INTO harvests (
+            lot_id, plot_id, business_id, harvest_date, picker_name, cherry_weight_kg,
+            underripe_percent, ripe_percent, overripe_percent
+        )
+        SELECT l.id, p.id, $1,
+               DATE '2024-01-01' + (g % 365)::int,
+               'Picker ' || (g % 40),
+               10 + (g % 50),
+               g % 20, 80, 20 - g % 20
+        FROM generate_series(0, $2 - 1) g
+        JOIN l ON l.rn = g % $3
+        JOIN p ON p.rn = g % $4
+        "#,
+    )
+    .bind(business_id)
+    .bind(env.harvests)
+    .bind(lots)
+    .bind(BENCH_PLOTS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        WITH l AS (
+            SELECT id, stage, ROW_NUMBER() OVER (ORDER BY traceability_code) - 1 AS rn
+            FROM lots WHERE business_id = $1
+        )
+        INSERT INTO inventory_transactions (
+            business_id, lot_id, transaction_type, quantity_kg, direction, stage,
+            counterparty_name, unit_price, total_price, transaction_date
+        )
+        SELECT $1, l.id,
+               CASE WHEN g % 4 = 3 THEN 'sale'::inventory_transaction_type
+                    ELSE 'harvest_in'::inventory_transaction_type END,
+               1 + (g % 25),
+               CASE WHEN g % 4 = 3 THEN 'out' ELSE 'in' END,
+               l.stage,
+               CASE WHEN g % 4 = 3 THEN 'Buyer ' || (g % 100) END,
+               CASE WHEN g % 4 = 3 THEN 150 + (g % 200) END,
+               CASE WHEN g % 4 = 3 THEN (1 + (g % 25)) * (150 + (g % 200)) END,
+               DATE '2024-01-01' + (g % 365)::int
+        FROM generate_series(0, $2 - 1) g
+        JOIN l ON l.rn = g % $3
+        "#,
+    )
+    .bind(business_id)
+    .bind(env.transactions)
+    .bind(lots)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // Refresh planner statistics so benchmarks reflect steady-state plans
+    for table in ["plots", "lots", "harvests", "inventory_transactions"] {
+        sqlx::query(&format!("ANALYZE {}", table)).execute(pool).await?;
+    }
+
+    Ok(())
+}
+
+/// Benchmark a single authenticated GET endpoint
+fn bench_get(c: &mut Criterion, rt: &Runtime, client: &reqwest::Client, name: &str, url: String, token: &str) {
+    // Fail fast on misconfiguration instead of timing error responses
+    let status = rt
+        .block_on(client.get(&url).bearer_auth(token).send())
+        .unwrap_or_else(|e| panic!("{} request failed: {}", name, e))
+        .status();
+    assert!(status.is_success(), "{} returned {}", name, status);
+
+    c.bench_function(name, |b| {
+        b.to_async(rt).iter(|| async {
+            let response = client
+                .get(&url)
+                .bearer_auth(token)
+                .send()
+                .await
+                .expect("request failed");
+            response.bytes().await.expect("failed to read body")
+        })
+    });
+}
+
+fn main() {
+    let Some(env) = BenchEnv::from_env() else {
+        eprintln!("CQM_BENCH_DATABASE_URL and CQM_BENCH_BASE_URL are not set, skipping query benchmarks");
+        return;
+    };
+
+    let rt = Runtime::new().expect("failed to start tokio runtime");
+
+    let fixture = rt.block_on(async {
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .acquire_timeout(Duration::from_secs(30))
+            .connect(&env.database_url)
+            .await
+            .expect("failed to connect to benchmark database");
+        seed(&pool, &env).await.expect("failed to seed benchmark data")
+    });
+
+    let client = reqwest::Client::new();
+    let mut criterion = Criterion::default()
+        .measurement_time(Duration::from_secs(10))
+        .configure_from_args();
+
+    bench_get(
+        &mut criterion,
+        &rt,
+        &client,
+        "reports_dashboard",
+        format!("{}/api/v1/reports/dashboard", env.base_url),
+        &fixture.access_token,
+    );
+    bench_get(
+        &mut criterion,
+        &rt,
+        &client,
+        "traceability_view",
+        format!("{}/api/v1/trace/{}", env.base_url, fixture.traceability_code),
+        &fixture.access_token,
+    );
+    bench_get(
+        &mut criterion,
+        &rt,
+        &client,
+        "inventory_balance",
+        format!("{}/api/v1/inventory/lots/{}/balance", env.base_url, fixture.lot_id),
+        &fixture.access_token,
+    );
+
+    criterion.final_summary();
+}
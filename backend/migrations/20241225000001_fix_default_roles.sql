@@ -0,0 +1,45 @@
+-- Fix default role creation for new businesses
+-- The original trigger omitted the is_system_role value, so every business
+-- insert (including registration) failed.
+
+CREATE OR REPLACE FUNCTION create_default_roles()
+RETURNS TRIGGER AS $$
+DECLARE
+    owner_role_id UUID;
+    manager_role_id UUID;
+    worker_role_id UUID;
+BEGIN
+    -- Create Owner role (all permissions)
+    INSERT INTO roles (business_id, name, name_th, description, description_th, is_system_role)
+    VALUES (NEW.id, 'owner', 'เจ้าของ', 'Full access to all features', 'เข้าถึงทุกฟีเจอร์', TRUE)
+    RETURNING id INTO owner_role_id;
+
+    -- Grant all permissions to owner
+    INSERT INTO role_permissions (role_id, permission_id)
+    SELECT owner_role_id, id FROM permissions;
+
+    -- Create Manager role (most permissions except role/business management)
+    INSERT INTO roles (business_id, name, name_th, description, description_th, is_system_role)
+    VALUES (NEW.id, 'manager', 'ผู้จัดการ', 'Manage operations and users', 'จัดการการดำเนินงานและผู้ใช้', TRUE)
+    RETURNING id INTO manager_role_id;
+
+    -- Grant manager permissions (exclude role and business management)
+    INSERT INTO role_permissions (role_id, permission_id)
+    SELECT manager_role_id, id FROM permissions
+    WHERE resource NOT IN ('role', 'business') OR action = 'view';
+
+    -- Create Worker role (basic operational permissions)
+    INSERT INTO roles (business_id, name, name_th, description, description_th, is_system_role)
+    VALUES (NEW.id, 'worker', 'พนักงาน', 'Basic operational access', 'เข้าถึงการดำเนินงานพื้นฐาน', TRUE)
+    RETURNING id INTO worker_role_id;
+
+    -- Grant worker permissions (view and create only for operational resources)
+    INSERT INTO role_permissions (role_id, permission_id)
+    SELECT worker_role_id, id FROM permissions
+    WHERE (resource IN ('plot', 'harvest', 'processing', 'grading', 'cupping', 'inventory', 'roast_profile')
+           AND action IN ('view', 'create'))
+       OR (resource = 'report' AND action = 'view');
+
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
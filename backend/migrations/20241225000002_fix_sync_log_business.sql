@@ -0,0 +1,61 @@
+-- Fix sync logging for tables without a business_id column
+-- processing_records and green_bean_grades belong to a business through their
+-- lot, so reading NEW.business_id directly failed every insert and update.
+-- Rows deleted by a cascading business or lot delete can no longer be
+-- resolved and are not logged.
+
+CREATE OR REPLACE FUNCTION log_entity_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_business_id UUID;
+    v_operation VARCHAR(20);
+    v_data JSONB;
+    v_new_version BIGINT;
+BEGIN
+    -- Determine operation type
+    IF TG_OP = 'INSERT' THEN
+        v_operation := 'create';
+        v_data := to_jsonb(NEW);
+        v_new_version := nextval('global_sync_version');
+        NEW.entity_version := v_new_version;
+    ELSIF TG_OP = 'UPDATE' THEN
+        v_operation := 'update';
+        v_data := to_jsonb(NEW);
+        v_new_version := nextval('global_sync_version');
+        NEW.entity_version := v_new_version;
+    ELSIF TG_OP = 'DELETE' THEN
+        v_operation := 'delete';
+        v_data := to_jsonb(OLD);
+        v_new_version := nextval('global_sync_version');
+    END IF;
+
+    -- Resolve the owning business directly or through the lot
+    v_business_id := (v_data->>'business_id')::UUID;
+    IF v_business_id IS NULL AND v_data ? 'lot_id' THEN
+        SELECT business_id INTO v_business_id FROM lots WHERE id = (v_data->>'lot_id')::UUID;
+    END IF;
+
+    -- Rows removed by a cascading business or lot delete have no owner left to sync to
+    IF TG_OP = 'DELETE' AND NOT EXISTS (SELECT 1 FROM businesses WHERE id = v_business_id) THEN
+        v_business_id := NULL;
+    END IF;
+
+    IF v_business_id IS NOT NULL THEN
+        INSERT INTO sync_log (business_id, entity_type, entity_id, operation, entity_version, data)
+        VALUES (
+            v_business_id,
+            TG_TABLE_NAME,
+            CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END,
+            v_operation,
+            v_new_version,
+            v_data
+        );
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        RETURN OLD;
+    ELSE
+        RETURN NEW;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
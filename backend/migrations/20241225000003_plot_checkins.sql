@@ -0,0 +1,28 @@
+-- Plot Check-ins Migration
+-- Locations shared through LINE, matched to the nearest plot and optionally
+-- attached to a harvest or used for a weather snapshot
+
+CREATE TABLE plot_checkins (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Nearest plot within the check-in radius (NULL if none matched)
+    plot_id UUID REFERENCES plots(id) ON DELETE SET NULL,
+    -- Shared location
+    latitude DECIMAL(10, 7) NOT NULL,
+    longitude DECIMAL(10, 7) NOT NULL,
+    location_name VARCHAR(255),
+    -- Distance to the matched plot in meters
+    distance_meters INTEGER,
+    -- Follow-up actions chosen from the quick reply
+    harvest_id UUID REFERENCES harvests(id) ON DELETE SET NULL,
+    weather_snapshot_id UUID REFERENCES weather_snapshots(id) ON DELETE SET NULL,
+    -- Source of the check-in
+    source VARCHAR(20) NOT NULL DEFAULT 'line',
+    -- Timestamps
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_plot_checkins_business_id ON plot_checkins(business_id);
+CREATE INDEX idx_plot_checkins_plot_id ON plot_checkins(plot_id);
@@ -0,0 +1,22 @@
+-- Rainfall Observations Migration
+-- Manual rain gauge readings per plot, merged with API weather data in the
+-- daily weather aggregates
+
+CREATE TABLE rainfall_observations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    plot_id UUID NOT NULL REFERENCES plots(id) ON DELETE CASCADE,
+    -- Day the rain fell (local date)
+    observed_on DATE NOT NULL,
+    -- Gauge reading in millimeters
+    rainfall_mm DECIMAL(6, 2) NOT NULL CHECK (rainfall_mm >= 0),
+    -- Source: manual (app) or line (chatbot)
+    source VARCHAR(20) NOT NULL DEFAULT 'manual',
+    recorded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    notes TEXT,
+    -- Timestamps
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_rainfall_observations_business_date ON rainfall_observations(business_id, observed_on);
+CREATE INDEX idx_rainfall_observations_plot_id ON rainfall_observations(plot_id);
@@ -0,0 +1,46 @@
+-- Notification Escalation Migration
+-- Escalate high-priority notifications that are not read/acknowledged in time
+-- to the business owner or a backup contact via an alternate channel
+
+CREATE TABLE escalation_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Which notifications the policy applies to
+    notification_type notification_type, -- NULL = any type
+    min_priority INT NOT NULL DEFAULT 2,
+    -- How long to wait for a read/acknowledgement
+    escalate_after_hours INT NOT NULL DEFAULT 4 CHECK (escalate_after_hours > 0),
+    -- Who to escalate to (NULL = business owner)
+    escalate_to_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Channel for the escalation (NULL = alternate of the original channel)
+    channel notification_channel,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    -- Timestamps
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_escalation_policies_business ON escalation_policies(business_id) WHERE is_active = true;
+
+-- Track priority, read state of the paired in-app notification and escalations
+ALTER TABLE notification_log
+ADD COLUMN IF NOT EXISTS priority INT NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS in_app_notification_id UUID REFERENCES in_app_notifications(id) ON DELETE SET NULL,
+ADD COLUMN IF NOT EXISTS escalated_at TIMESTAMPTZ,
+ADD COLUMN IF NOT EXISTS escalation_of UUID REFERENCES notification_log(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_notification_log_unacknowledged ON notification_log(business_id, sent_at)
+    WHERE read_at IS NULL AND escalated_at IS NULL AND escalation_of IS NULL;
+
+-- Business owner: earliest active user holding the owner role
+CREATE OR REPLACE FUNCTION business_owner_id(p_business_id UUID)
+RETURNS UUID AS $$
+    SELECT u.id
+    FROM users u
+    JOIN roles r ON r.id = u.role_id
+    WHERE u.business_id = p_business_id
+      AND u.is_active = true
+      AND r.name = 'owner'
+    ORDER BY u.created_at
+    LIMIT 1
+$$ LANGUAGE sql STABLE;
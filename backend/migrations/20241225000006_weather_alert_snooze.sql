@@ -0,0 +1,10 @@
+-- Weather Alert Snooze Migration
+-- Lets farmers mute repetitive alerts (e.g. rain during the monsoon) for a
+-- while; an alert unmutes automatically once muted_until has passed
+
+ALTER TABLE weather_alerts
+    ADD COLUMN IF NOT EXISTS muted_until TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS muted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_weather_alerts_muted
+    ON weather_alerts(business_id, muted_until) WHERE muted_until IS NOT NULL;
@@ -0,0 +1,156 @@
+-- Role Templates Migration
+-- Built-in role templates (Owner, Farm Manager, Processor, Roaster, Cupper,
+-- Viewer) seeded for every new business. Template permissions are stored as
+-- resource/action rules with '*' wildcards so that re-applying the templates
+-- grants permissions introduced after the business was created.
+
+CREATE TABLE role_templates (
+    key VARCHAR(50) PRIMARY KEY,
+    name_th VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL,
+    description_th TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE role_template_permissions (
+    template_key VARCHAR(50) NOT NULL REFERENCES role_templates(key) ON DELETE CASCADE,
+    -- '*' matches every resource / action, including ones added later
+    resource VARCHAR(50) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    PRIMARY KEY (template_key, resource, action)
+);
+
+INSERT INTO role_templates (key, name_th, description, description_th, sort_order) VALUES
+    ('owner', 'เจ้าของ', 'Full access to all features', 'เข้าถึงทุกฟีเจอร์', 1),
+    ('farm_manager', 'ผู้จัดการฟาร์ม', 'Manage plots, harvests and farm operations', 'จัดการแปลง การเก็บเกี่ยว และการดำเนินงานในฟาร์ม', 2),
+    ('processor', 'ผู้แปรรูป', 'Process cherries, grade green beans and manage inventory', 'แปรรูปเชอร์รี่ คัดเกรดสารกาแฟ และจัดการสินค้าคงคลัง', 3),
+    ('roaster', 'ผู้คั่ว', 'Roast coffee and manage roast profiles', 'คั่วกาแฟและจัดการโปรไฟล์การคั่ว', 4),
+    ('cupper', 'นักชิมกาแฟ', 'Run cupping sessions and record scores', 'จัดการการคัปปิ้งและบันทึกคะแนน', 5),
+    ('viewer', 'ผู้ดูข้อมูล', 'Read-only access to all records', 'ดูข้อมูลได้อย่างเดียว', 6);
+
+INSERT INTO role_template_permissions (template_key, resource, action) VALUES
+    ('owner', '*', '*'),
+    -- Farm Manager: runs the farm, reads downstream quality data
+    ('farm_manager', 'plot', '*'),
+    ('farm_manager', 'harvest', '*'),
+    ('farm_manager', 'processing', '*'),
+    ('farm_manager', 'inventory', '*'),
+    ('farm_manager', 'certification', '*'),
+    ('farm_manager', 'report', '*'),
+    ('farm_manager', 'grading', 'view'),
+    ('farm_manager', 'cupping', 'view'),
+    ('farm_manager', 'roast_profile', 'view'),
+    ('farm_manager', 'user', 'view'),
+    ('farm_manager', 'role', 'view'),
+    ('farm_manager', 'business', 'view'),
+    -- Processor: cherry intake through green bean grading
+    ('processor', 'processing', '*'),
+    ('processor', 'grading', '*'),
+    ('processor', 'inventory', '*'),
+    ('processor', 'harvest', 'view'),
+    ('processor', 'harvest', 'create'),
+    ('processor', 'plot', 'view'),
+    ('processor', 'report', 'view'),
+    -- Roaster
+    ('roaster', 'roast_profile', '*'),
+    ('roaster', 'inventory', 'view'),
+    ('roaster', 'inventory', 'create'),
+    ('roaster', 'inventory', 'edit'),
+    ('roaster', 'cupping', 'view'),
+    ('roaster', 'cupping', 'create'),
+    ('roaster', 'grading', 'view'),
+    ('roaster', 'report', 'view'),
+    -- Cupper
+    ('cupper', 'cupping', '*'),
+    ('cupper', 'grading', 'view'),
+    ('cupper', 'grading', 'create'),
+    ('cupper', 'roast_profile', 'view'),
+    ('cupper', 'inventory', 'view'),
+    ('cupper', 'report', 'view'),
+    -- Viewer
+    ('viewer', '*', 'view');
+
+-- Roles created from a template remember it so templates can be re-applied
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS template_key VARCHAR(50)
+    REFERENCES role_templates(key) ON DELETE SET NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_business_template
+    ON roles(business_id, template_key) WHERE template_key IS NOT NULL;
+
+-- Existing owner roles already match the owner template
+UPDATE roles SET template_key = 'owner' WHERE name = 'owner' AND is_system_role = TRUE;
+
+-- Create missing template roles for a business and grant any template
+-- permissions they lack. Permissions are only ever added, so customizations
+-- made by the business are preserved. A template is skipped when the business
+-- already has an unrelated role with the same name.
+CREATE OR REPLACE FUNCTION apply_role_templates(p_business_id UUID)
+RETURNS TABLE (
+    applied_template VARCHAR(50),
+    applied_role_id UUID,
+    status VARCHAR(20),
+    permissions_added INTEGER
+) AS $$
+DECLARE
+    t RECORD;
+    v_role_id UUID;
+    v_created BOOLEAN;
+    v_added INTEGER;
+BEGIN
+    FOR t IN SELECT * FROM role_templates ORDER BY sort_order, key LOOP
+        v_created := FALSE;
+
+        SELECT r.id INTO v_role_id
+        FROM roles r
+        WHERE r.business_id = p_business_id AND r.template_key = t.key;
+
+        IF v_role_id IS NULL THEN
+            IF EXISTS (
+                SELECT 1 FROM roles r
+                WHERE r.business_id = p_business_id AND LOWER(r.name) = t.key
+            ) THEN
+                applied_template := t.key;
+                applied_role_id := NULL;
+                status := 'skipped';
+                permissions_added := 0;
+                RETURN NEXT;
+                CONTINUE;
+            END IF;
+
+            INSERT INTO roles (business_id, name, name_th, description, description_th, is_system_role, template_key)
+            VALUES (p_business_id, t.key, t.name_th, t.description, t.description_th, TRUE, t.key)
+            RETURNING id INTO v_role_id;
+            v_created := TRUE;
+        END IF;
+
+        INSERT INTO role_permissions (role_id, permission_id)
+        SELECT DISTINCT v_role_id, p.id
+        FROM role_template_permissions tp
+        JOIN permissions p
+          ON (tp.resource = '*' OR tp.resource = p.resource)
+         AND (tp.action = '*' OR tp.action = p.action)
+        WHERE tp.template_key = t.key
+        ON CONFLICT DO NOTHING;
+        GET DIAGNOSTICS v_added = ROW_COUNT;
+
+        applied_template := t.key;
+        applied_role_id := v_role_id;
+        status := CASE
+            WHEN v_created THEN 'created'
+            WHEN v_added > 0 THEN 'updated'
+            ELSE 'unchanged'
+        END;
+        permissions_added := v_added;
+        RETURN NEXT;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- New businesses start with every template role
+CREATE OR REPLACE FUNCTION create_default_roles()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM apply_role_templates(NEW.id);
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
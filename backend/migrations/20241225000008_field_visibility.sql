@@ -0,0 +1,19 @@
+-- Field Visibility Migration
+-- Per-role policies hiding sensitive response fields (prices, costs,
+-- counterparty contacts) from users such as buyers with shared access
+
+CREATE TABLE field_visibility_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    -- API resource the policy applies to (first path segment, e.g. inventory)
+    -- or '*' for every endpoint
+    resource VARCHAR(50) NOT NULL DEFAULT '*',
+    -- JSON field hidden from responses, at any nesting depth
+    field VARCHAR(100) NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(role_id, resource, field)
+);
+
+CREATE INDEX idx_field_visibility_role ON field_visibility_policies(role_id);
@@ -0,0 +1,21 @@
+-- Saved Reports Migration
+-- Report builder configurations (entity, columns, filters, grouping) saved
+-- per business so they can be re-run or delivered on a schedule
+
+CREATE TABLE saved_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    name VARCHAR(200) NOT NULL,
+    description TEXT,
+    -- Validated ReportDefinition; identifiers are checked against the
+    -- builder catalogue again on every run
+    definition JSONB NOT NULL,
+    default_format VARCHAR(10) NOT NULL DEFAULT 'json'
+        CHECK (default_format IN ('json', 'csv', 'xlsx')),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(business_id, name)
+);
+
+CREATE INDEX idx_saved_reports_business ON saved_reports(business_id);
@@ -0,0 +1,59 @@
+-- Report Schedules Migration
+-- Scheduled delivery of saved reports (e.g. weekly inventory summary every
+-- Monday 07:00) by email attachment or LINE download link, executed by the
+-- server's background job runner
+
+CREATE TABLE report_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    saved_report_id UUID NOT NULL REFERENCES saved_reports(id) ON DELETE CASCADE,
+    frequency VARCHAR(10) NOT NULL CHECK (frequency IN ('daily', 'weekly', 'monthly')),
+    -- ISO weekday (1 = Monday) for weekly schedules
+    day_of_week SMALLINT CHECK (day_of_week BETWEEN 1 AND 7),
+    -- Day of month for monthly schedules; capped at 28 so every month has it
+    day_of_month SMALLINT CHECK (day_of_month BETWEEN 1 AND 28),
+    -- Local delivery time, at utc_offset_minutes (default Thailand, UTC+7)
+    time_of_day TIME NOT NULL,
+    utc_offset_minutes INTEGER NOT NULL DEFAULT 420,
+    format VARCHAR(10) NOT NULL DEFAULT 'xlsx' CHECK (format IN ('csv', 'xlsx')),
+    channel VARCHAR(10) NOT NULL CHECK (channel IN ('email', 'line')),
+    -- Business users receiving the report (email address or LINE account)
+    recipient_user_ids UUID[] NOT NULL DEFAULT '{}',
+    -- Additional email addresses outside the business (email channel only)
+    recipient_emails TEXT[] NOT NULL DEFAULT '{}',
+    language VARCHAR(2) NOT NULL DEFAULT 'th' CHECK (language IN ('th', 'en')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    last_status VARCHAR(20),
+    -- Reports run with this user's role so field visibility policies apply
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((frequency = 'weekly') = (day_of_week IS NOT NULL)),
+    CHECK ((frequency = 'monthly') = (day_of_month IS NOT NULL))
+);
+
+CREATE INDEX idx_report_schedules_business ON report_schedules(business_id);
+CREATE INDEX idx_report_schedules_due ON report_schedules(next_run_at) WHERE is_active = TRUE;
+
+CREATE TABLE report_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    schedule_id UUID NOT NULL REFERENCES report_schedules(id) ON DELETE CASCADE,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('sent', 'partial', 'failed')),
+    row_count INTEGER,
+    recipients_sent INTEGER NOT NULL DEFAULT 0,
+    recipients_failed INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT,
+    file_name VARCHAR(255),
+    content_type VARCHAR(100),
+    -- Generated file kept for LINE download links until expires_at
+    file_data BYTEA,
+    download_token VARCHAR(64) UNIQUE,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_report_deliveries_schedule ON report_deliveries(schedule_id, created_at DESC);
+CREATE INDEX idx_report_deliveries_expiry ON report_deliveries(expires_at) WHERE file_data IS NOT NULL;
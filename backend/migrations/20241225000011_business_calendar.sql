@@ -0,0 +1,8 @@
+-- Business Calendar Preference Migration
+-- Dates in generated PDFs, exports and notification texts can be shown in
+-- Buddhist Era years (B.E. = C.E. + 543) as required on Thai government
+-- paperwork. Stored dates are unaffected.
+
+ALTER TABLE businesses
+    ADD COLUMN calendar_system VARCHAR(10) NOT NULL DEFAULT 'gregorian'
+        CHECK (calendar_system IN ('gregorian', 'buddhist'));
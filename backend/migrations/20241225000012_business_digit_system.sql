@@ -0,0 +1,7 @@
+-- Business Digit System Migration
+-- Numbers in Thai-language PDFs, reports and notification texts can be
+-- written with Thai digits (๐-๙). Stored values are unaffected.
+
+ALTER TABLE businesses
+    ADD COLUMN digit_system VARCHAR(10) NOT NULL DEFAULT 'arabic'
+        CHECK (digit_system IN ('arabic', 'thai'));
@@ -0,0 +1,56 @@
+-- Code Sequences Migration
+-- One counter table for every per-business code: lot traceability numbers
+-- (per year) and cupping sample numbers (per session). Values are handed out
+-- by a single upsert, so concurrent requests never receive the same number.
+-- Numbers may have gaps when the insert that used them fails.
+
+CREATE TABLE code_sequences (
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- What is being numbered: 'lot', 'cupping_sample'
+    scope VARCHAR(50) NOT NULL,
+    -- Counter partition within the scope: the year for lots, the session
+    -- id for cupping samples
+    scope_key VARCHAR(100) NOT NULL DEFAULT '',
+    last_value BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (business_id, scope, scope_key)
+);
+
+-- Allocate the next value. `p_floor` is the highest value already in use;
+-- the counter never returns a value at or below it, which keeps numbering
+-- correct for rows written before the counter existed.
+CREATE OR REPLACE FUNCTION next_code_sequence(
+    p_business_id UUID,
+    p_scope VARCHAR,
+    p_scope_key VARCHAR,
+    p_floor BIGINT DEFAULT 0
+)
+RETURNS BIGINT AS $$
+    INSERT INTO code_sequences (business_id, scope, scope_key, last_value)
+    VALUES (p_business_id, p_scope, p_scope_key, GREATEST(p_floor, 0) + 1)
+    ON CONFLICT (business_id, scope, scope_key)
+    DO UPDATE SET last_value = GREATEST(code_sequences.last_value + 1, EXCLUDED.last_value),
+                  updated_at = NOW()
+    RETURNING last_value;
+$$ LANGUAGE sql;
+
+-- Carry over lot counters
+INSERT INTO code_sequences (business_id, scope, scope_key, last_value)
+SELECT business_id, 'lot', year::TEXT, last_sequence
+FROM lot_sequences;
+
+-- Carry over cupping sample numbers already used in each session
+INSERT INTO code_sequences (business_id, scope, scope_key, last_value)
+SELECT s.business_id, 'cupping_sample', s.id::TEXT, MAX(cs.sample_number)
+FROM cupping_sessions s
+JOIN cupping_samples cs ON cs.session_id = s.id
+GROUP BY s.business_id, s.id;
+
+-- Keep the lot helper for existing callers, backed by the shared counters
+CREATE OR REPLACE FUNCTION get_next_lot_sequence(p_business_id UUID, p_year INTEGER)
+RETURNS INTEGER AS $$
+    SELECT next_code_sequence(p_business_id, 'lot', p_year::TEXT)::INTEGER;
+$$ LANGUAGE sql;
+
+DROP TABLE lot_sequences;
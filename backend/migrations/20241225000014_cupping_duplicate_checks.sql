@@ -0,0 +1,10 @@
+-- Cupping Duplicate Checks Migration
+-- Per-business handling of repeated lots within a cupping session and of
+-- score rows identical to another sample (usually a copy-paste mistake).
+-- 'warn' and the identical-score check refuse the sample until the client
+-- resends it with confirm_duplicate; 'block' always refuses a repeated lot.
+
+ALTER TABLE businesses
+    ADD COLUMN cupping_duplicate_lot_policy VARCHAR(10) NOT NULL DEFAULT 'warn'
+        CHECK (cupping_duplicate_lot_policy IN ('allow', 'warn', 'block')),
+    ADD COLUMN cupping_flag_identical_scores BOOLEAN NOT NULL DEFAULT TRUE;
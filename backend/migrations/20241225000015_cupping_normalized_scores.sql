@@ -0,0 +1,9 @@
+-- Cupping Normalized Scores Migration
+-- Final score with the cupper's systematic bias removed, so samples scored by
+-- different cuppers can be compared across sessions. Recomputed by the
+-- application whenever samples are added; NULL while the cupper has too few
+-- lots in common with other cuppers to estimate a bias.
+
+ALTER TABLE cupping_samples
+    ADD COLUMN normalized_score DECIMAL(5,2)
+        CHECK (normalized_score IS NULL OR (normalized_score >= 0 AND normalized_score <= 100));
@@ -0,0 +1,27 @@
+-- Water Quality Migration
+-- Water used for cupping and processing, measured against the SCA water
+-- standard. A measurement can be attached to a cupping session, a
+-- processing record, or stand alone as a routine check of a source.
+
+CREATE TABLE water_quality_measurements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    measured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    source VARCHAR(100) NOT NULL,
+    tds_ppm DECIMAL(6,1) NOT NULL CHECK (tds_ppm >= 0),
+    ph DECIMAL(4,2) NOT NULL CHECK (ph >= 0 AND ph <= 14),
+    cupping_session_id UUID REFERENCES cupping_sessions(id) ON DELETE SET NULL,
+    processing_record_id UUID REFERENCES processing_records(id) ON DELETE SET NULL,
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_water_quality_business ON water_quality_measurements(business_id, measured_at DESC);
+CREATE INDEX idx_water_quality_session ON water_quality_measurements(cupping_session_id)
+    WHERE cupping_session_id IS NOT NULL;
+CREATE INDEX idx_water_quality_processing ON water_quality_measurements(processing_record_id)
+    WHERE processing_record_id IS NOT NULL;
+
+COMMENT ON TABLE water_quality_measurements IS 'TDS and pH of water used for cupping and processing';
+COMMENT ON COLUMN water_quality_measurements.source IS 'Free text, e.g. lab filter, well, municipal';
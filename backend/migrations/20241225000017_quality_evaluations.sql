@@ -0,0 +1,48 @@
+-- Quality Evaluations Migration
+-- Business-defined quality specs and evaluations bundling a green grading and
+-- a cupping sample of the same lot into one pass/fail decision. Each check is
+-- stored with the limits in force at evaluation time, so later spec changes
+-- do not rewrite past decisions.
+
+CREATE TABLE quality_specs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    min_cupping_score DECIMAL(5,2) CHECK (min_cupping_score BETWEEN 0 AND 100),
+    max_category1_defects INTEGER CHECK (max_category1_defects >= 0),
+    max_category2_defects INTEGER CHECK (max_category2_defects >= 0),
+    min_moisture_percent DECIMAL(5,2) CHECK (min_moisture_percent BETWEEN 0 AND 100),
+    max_moisture_percent DECIMAL(5,2) CHECK (max_moisture_percent BETWEEN 0 AND 100),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (business_id, name),
+    CHECK (min_moisture_percent IS NULL OR max_moisture_percent IS NULL
+           OR min_moisture_percent <= max_moisture_percent)
+);
+
+CREATE TRIGGER update_quality_specs_updated_at
+    BEFORE UPDATE ON quality_specs
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE quality_evaluations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    grading_id UUID NOT NULL REFERENCES green_bean_grades(id) ON DELETE CASCADE,
+    cupping_sample_id UUID NOT NULL REFERENCES cupping_samples(id) ON DELETE CASCADE,
+    spec_id UUID REFERENCES quality_specs(id) ON DELETE SET NULL,
+    spec_name VARCHAR(100) NOT NULL,
+    passed BOOLEAN NOT NULL,
+    checks JSONB NOT NULL,
+    notes TEXT,
+    evaluated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    evaluated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_quality_evaluations_lot ON quality_evaluations(lot_id, evaluated_at DESC);
+CREATE INDEX idx_quality_evaluations_business ON quality_evaluations(business_id, evaluated_at DESC);
+
+COMMENT ON COLUMN quality_evaluations.checks IS 'Array of {criterion, actual, min, max, passed} at evaluation time';
+COMMENT ON COLUMN quality_evaluations.spec_name IS 'Spec name at evaluation time; kept when the spec is deleted';
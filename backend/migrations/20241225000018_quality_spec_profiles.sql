@@ -0,0 +1,14 @@
+-- Quality Spec Profiles Migration
+-- Screen size requirements on quality specs (a minimum share of the sample
+-- retained on a given screen) and assignment of specs to buyers and markets.
+-- Buyers and markets are free text; a spec with neither applies generally.
+
+ALTER TABLE quality_specs
+    ADD COLUMN min_screen_size SMALLINT CHECK (min_screen_size BETWEEN 14 AND 18),
+    ADD COLUMN min_screen_percent DECIMAL(5,2) CHECK (min_screen_percent BETWEEN 0 AND 100),
+    ADD COLUMN buyers TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN markets TEXT[] NOT NULL DEFAULT '{}',
+    ADD CONSTRAINT quality_specs_screen_pair
+        CHECK ((min_screen_size IS NULL) = (min_screen_percent IS NULL));
+
+COMMENT ON COLUMN quality_specs.min_screen_percent IS 'Minimum % of the sample retained on min_screen_size or larger';
@@ -0,0 +1,22 @@
+-- Lot Reservations Migration
+-- Quantities of a lot held for a buyer while an offer or contract is
+-- prepared. Active reservations (no end date, or ending today or later) are
+-- subtracted from a lot's weight when recommending lots for new orders.
+
+CREATE TABLE lot_reservations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    quantity_kg DECIMAL(10,3) NOT NULL CHECK (quantity_kg > 0),
+    reserved_for VARCHAR(255) NOT NULL,
+    reserved_until DATE,
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_lot_reservations_business ON lot_reservations(business_id);
+CREATE INDEX idx_lot_reservations_lot ON lot_reservations(lot_id);
+
+COMMENT ON TABLE lot_reservations IS 'Lot quantities held for buyers, excluded from order recommendations';
+COMMENT ON COLUMN lot_reservations.reserved_until IS 'Last day of the hold; NULL holds until released';
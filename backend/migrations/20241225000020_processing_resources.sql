@@ -0,0 +1,26 @@
+-- Processing Resources Migration
+-- Fermentation tanks and drying beds with the cherry weight each holds, used
+-- to plan processing capacity against expected harvest volumes.
+
+CREATE TABLE processing_resources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    resource_type VARCHAR(30) NOT NULL CHECK (resource_type IN ('fermentation_tank', 'drying_bed')),
+    capacity_kg DECIMAL(10,3) NOT NULL CHECK (capacity_kg > 0),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (business_id, name)
+);
+
+CREATE INDEX idx_processing_resources_business ON processing_resources(business_id);
+
+CREATE TRIGGER update_processing_resources_updated_at
+    BEFORE UPDATE ON processing_resources
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE processing_resources IS 'Fermentation tanks and drying beds for capacity planning';
+COMMENT ON COLUMN processing_resources.capacity_kg IS 'Cherry weight the tank or bed holds at once';
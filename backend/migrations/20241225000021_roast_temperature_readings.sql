@@ -0,0 +1,28 @@
+-- Roast Temperature Readings Migration
+-- Roast curves move out of the roast_sessions.temperature_log JSONB array,
+-- which grew unbounded with second-by-second logging, into one row per
+-- reading. Curves are downsampled on read.
+
+CREATE TABLE roast_temperature_readings (
+    session_id UUID NOT NULL REFERENCES roast_sessions(id) ON DELETE CASCADE,
+    time_seconds INTEGER NOT NULL CHECK (time_seconds >= 0),
+    temp_celsius DECIMAL(5,1) NOT NULL,
+    notes TEXT,
+    PRIMARY KEY (session_id, time_seconds)
+);
+
+-- Keep the last reading logged for a second, as the JSONB log did on read
+INSERT INTO roast_temperature_readings (session_id, time_seconds, temp_celsius, notes)
+SELECT DISTINCT ON (rs.id, (r.value->>'time_seconds')::INT)
+       rs.id, (r.value->>'time_seconds')::INT, (r.value->>'temp_celsius')::DECIMAL(5,1), r.value->>'notes'
+FROM roast_sessions rs
+CROSS JOIN LATERAL jsonb_array_elements(COALESCE(rs.temperature_log, '[]'::jsonb)) WITH ORDINALITY AS r(value, position)
+WHERE jsonb_typeof(rs.temperature_log) = 'array'
+  AND r.value->>'time_seconds' IS NOT NULL
+  AND r.value->>'temp_celsius' IS NOT NULL
+  AND (r.value->>'time_seconds')::INT >= 0
+ORDER BY rs.id, (r.value->>'time_seconds')::INT, r.position DESC;
+
+ALTER TABLE roast_sessions DROP COLUMN temperature_log;
+
+COMMENT ON TABLE roast_temperature_readings IS 'Roast curve: bean temperature by seconds since charge';
@@ -0,0 +1,40 @@
+-- Lab Results Migration
+-- Third-party analyses of a lot (moisture, water activity, ochratoxin A,
+-- pesticide residue). Each result stores the limits it was judged against,
+-- so a later change of the default thresholds does not rewrite history.
+-- The lab report itself is referenced like certification documents.
+
+CREATE TABLE lab_results (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    analysis VARCHAR(50) NOT NULL
+        CHECK (analysis IN ('moisture', 'water_activity', 'ochratoxin_a', 'pesticide_residue')),
+    -- Pesticide residue results name the compound tested for
+    analyte VARCHAR(100),
+    value DECIMAL(12,4) NOT NULL CHECK (value >= 0),
+    unit VARCHAR(20) NOT NULL,
+    min_limit DECIMAL(12,4),
+    max_limit DECIMAL(12,4),
+    passed BOOLEAN NOT NULL,
+    lab_name VARCHAR(255) NOT NULL,
+    report_number VARCHAR(100),
+    tested_on DATE NOT NULL,
+
+    -- Lab report document
+    document_name VARCHAR(255),
+    file_url TEXT,
+    file_size_bytes BIGINT,
+    mime_type VARCHAR(100),
+
+    include_in_buyer_pack BOOLEAN NOT NULL DEFAULT TRUE,
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_lab_results_lot ON lab_results(lot_id, tested_on DESC);
+CREATE INDEX idx_lab_results_business ON lab_results(business_id, tested_on DESC);
+
+COMMENT ON TABLE lab_results IS 'Third-party lab analyses of a lot with pass/fail against limits';
+COMMENT ON COLUMN lab_results.include_in_buyer_pack IS 'Shown on the lot spec sheet given to buyers';
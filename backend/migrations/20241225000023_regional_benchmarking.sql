@@ -0,0 +1,11 @@
+-- Regional Benchmarking Migration
+-- Businesses that opt in contribute anonymized lot metrics (cupping score,
+-- processing yield, sale price band) to regional benchmarks by province,
+-- variety and process, and in return see where they rank. Opting out
+-- removes their lots from every benchmark immediately.
+
+ALTER TABLE businesses
+    ADD COLUMN benchmarking_opt_in BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN benchmarking_opted_in_at TIMESTAMPTZ;
+
+CREATE INDEX idx_businesses_benchmarking ON businesses(province) WHERE benchmarking_opt_in;
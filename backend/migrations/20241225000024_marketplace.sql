@@ -0,0 +1,63 @@
+-- Marketplace Migration
+-- Producers that opt in appear in a public directory and can list lots for
+-- sale with an indicative price. Buyers browse listings without an account
+-- and send inquiries, which arrive as sales leads for the producer.
+
+ALTER TABLE businesses
+    ADD COLUMN marketplace_opt_in BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN marketplace_description TEXT,
+    ADD COLUMN marketplace_description_th TEXT;
+
+CREATE TABLE marketplace_listings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL UNIQUE REFERENCES lots(id) ON DELETE CASCADE,
+    quantity_kg DECIMAL(10,3) NOT NULL CHECK (quantity_kg > 0),
+    indicative_price_per_kg DECIMAL(10,2) CHECK (indicative_price_per_kg IS NULL OR indicative_price_per_kg > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    notes TEXT,
+    notes_th TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_marketplace_listings_business ON marketplace_listings(business_id);
+CREATE INDEX idx_marketplace_listings_active ON marketplace_listings(is_active) WHERE is_active;
+
+CREATE TRIGGER update_marketplace_listings_updated_at
+    BEFORE UPDATE ON marketplace_listings
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE sales_leads (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    source VARCHAR(50) NOT NULL DEFAULT 'marketplace',
+    listing_id UUID REFERENCES marketplace_listings(id) ON DELETE SET NULL,
+    lot_id UUID REFERENCES lots(id) ON DELETE SET NULL,
+    buyer_name VARCHAR(255) NOT NULL,
+    buyer_company VARCHAR(255),
+    buyer_email VARCHAR(255),
+    buyer_phone VARCHAR(50),
+    buyer_country VARCHAR(100),
+    requested_quantity_kg DECIMAL(10,3) CHECK (requested_quantity_kg IS NULL OR requested_quantity_kg > 0),
+    message TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'new'
+        CHECK (status IN ('new', 'contacted', 'qualified', 'won', 'lost')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT sales_lead_contact CHECK (buyer_email IS NOT NULL OR buyer_phone IS NOT NULL)
+);
+
+CREATE INDEX idx_sales_leads_business_status ON sales_leads(business_id, status);
+
+CREATE TRIGGER update_sales_leads_updated_at
+    BEFORE UPDATE ON sales_leads
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON COLUMN marketplace_listings.quantity_kg IS 'Quantity offered; the public listing shows at most the unreserved weight of the lot';
+COMMENT ON COLUMN sales_leads.source IS 'Where the lead came from, e.g. marketplace';
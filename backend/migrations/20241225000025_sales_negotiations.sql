@@ -0,0 +1,68 @@
+-- Sales Negotiations Migration
+-- Buyers and producers exchange offers (quantity and price per kg) on a
+-- sales lead. Each new offer counters the open one from the other side;
+-- accepting an offer closes the lead as won and creates a sales order.
+-- Buyers follow their thread through a private link holding an access token.
+
+ALTER TABLE sales_leads
+    ADD COLUMN buyer_access_token VARCHAR(64) UNIQUE;
+
+ALTER TABLE sales_leads DROP CONSTRAINT sales_leads_status_check;
+ALTER TABLE sales_leads
+    ADD CONSTRAINT sales_leads_status_check
+        CHECK (status IN ('new', 'contacted', 'qualified', 'negotiating', 'won', 'lost'));
+
+CREATE TABLE sales_offers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    lead_id UUID NOT NULL REFERENCES sales_leads(id) ON DELETE CASCADE,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Who made the offer
+    party VARCHAR(10) NOT NULL CHECK (party IN ('buyer', 'producer')),
+    quantity_kg DECIMAL(10,3) NOT NULL CHECK (quantity_kg > 0),
+    price_per_kg DECIMAL(10,2) NOT NULL CHECK (price_per_kg > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    message TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'countered', 'accepted', 'rejected', 'withdrawn')),
+    responded_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sales_offers_lead ON sales_offers(lead_id, created_at);
+-- At most one offer awaits an answer on a lead
+CREATE UNIQUE INDEX idx_sales_offers_one_open ON sales_offers(lead_id) WHERE status = 'open';
+
+CREATE TABLE sales_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- SO-YYYY-NNNN, numbered per business and year
+    order_number VARCHAR(50) NOT NULL,
+    lead_id UUID UNIQUE REFERENCES sales_leads(id) ON DELETE SET NULL,
+    offer_id UUID REFERENCES sales_offers(id) ON DELETE SET NULL,
+    lot_id UUID REFERENCES lots(id) ON DELETE SET NULL,
+    buyer_name VARCHAR(255) NOT NULL,
+    buyer_company VARCHAR(255),
+    buyer_email VARCHAR(255),
+    buyer_phone VARCHAR(50),
+    quantity_kg DECIMAL(10,3) NOT NULL CHECK (quantity_kg > 0),
+    price_per_kg DECIMAL(10,2) NOT NULL CHECK (price_per_kg > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    total_amount DECIMAL(12,2) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'confirmed'
+        CHECK (status IN ('confirmed', 'shipped', 'completed', 'cancelled')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_sales_order_number UNIQUE (business_id, order_number)
+);
+
+CREATE INDEX idx_sales_orders_business ON sales_orders(business_id, created_at DESC);
+
+CREATE TRIGGER update_sales_orders_updated_at
+    BEFORE UPDATE ON sales_orders
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON COLUMN sales_leads.buyer_access_token IS 'Secret in the buyer''s link to follow and answer the negotiation';
+COMMENT ON COLUMN sales_offers.status IS 'open until answered; countered when the other side makes a new offer';
@@ -0,0 +1,9 @@
+-- Plot Boundaries Migration
+-- Plot outlines imported from GIS tools (QGIS, GeoJSON exports), stored as
+-- GeoJSON Polygon or MultiPolygon geometries in WGS84 longitude/latitude.
+-- Validity and overlaps are checked by the application on import.
+
+ALTER TABLE plots
+    ADD COLUMN boundary JSONB;
+
+COMMENT ON COLUMN plots.boundary IS 'GeoJSON Polygon/MultiPolygon geometry (WGS84); latitude/longitude hold its centroid when imported';
@@ -0,0 +1,9 @@
+-- Cooperative Membership Migration
+-- Businesses belonging to the same cooperative share a cooperative code.
+-- Plot validation can then check boundaries across all member businesses,
+-- catching the same land registered by two farmers.
+
+ALTER TABLE businesses
+    ADD COLUMN cooperative_code VARCHAR(50);
+
+CREATE INDEX idx_businesses_cooperative_code ON businesses(cooperative_code) WHERE cooperative_code IS NOT NULL;
@@ -0,0 +1,19 @@
+-- Account Recovery Migration
+-- Single-use tokens emailed for password resets and email verification.
+-- Only a SHA-256 hash of each token is stored; a verification token is tied
+-- to the address it was sent to so it cannot verify a later address.
+
+CREATE TABLE auth_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(30) NOT NULL CHECK (purpose IN ('password_reset', 'email_verification')),
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    -- Address the token was sent to
+    email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_auth_tokens_user_purpose ON auth_tokens(user_id, purpose, created_at DESC);
+CREATE INDEX idx_auth_tokens_expires_at ON auth_tokens(expires_at);
@@ -0,0 +1,85 @@
+-- Farm Activities Migration
+-- Field work logged per plot. Fertilizer and pesticide applications name
+-- the product used; while the business holds an organic certification
+-- covering the plot, the product is checked against the allowed-substances
+-- list and a product not on it is logged as a compliance issue on the
+-- certification.
+
+CREATE TABLE farm_activities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    plot_id UUID NOT NULL REFERENCES plots(id) ON DELETE CASCADE,
+    activity_type VARCHAR(30) NOT NULL
+        CHECK (activity_type IN ('fertilizer', 'pesticide', 'pruning', 'weeding', 'other')),
+    activity_date DATE NOT NULL,
+    -- Required for fertilizer and pesticide applications
+    product_name VARCHAR(255),
+    quantity DECIMAL(12,3) CHECK (quantity IS NULL OR quantity > 0),
+    unit VARCHAR(20),
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_farm_activities_business ON farm_activities(business_id, activity_date DESC);
+CREATE INDEX idx_farm_activities_plot ON farm_activities(plot_id, activity_date DESC);
+
+-- Substances allowed under organic certification. Rows without a business
+-- are the default list; businesses add the products their certifier approved.
+CREATE TABLE organic_allowed_inputs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID REFERENCES businesses(id) ON DELETE CASCADE,
+    input_type VARCHAR(30) NOT NULL CHECK (input_type IN ('fertilizer', 'pesticide')),
+    substance VARCHAR(255) NOT NULL,
+    substance_th VARCHAR(255),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_organic_allowed_inputs_unique ON organic_allowed_inputs(
+    COALESCE(business_id, '00000000-0000-0000-0000-000000000000'::UUID), input_type, LOWER(substance)
+);
+
+-- Compliance issues found on a certification; issues stay when the farm
+-- activity that raised them is deleted
+CREATE TABLE certification_compliance_issues (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    certification_id UUID NOT NULL REFERENCES certifications(id) ON DELETE CASCADE,
+    requirement_id UUID REFERENCES certification_requirements(id) ON DELETE SET NULL,
+    farm_activity_id UUID REFERENCES farm_activities(id) ON DELETE SET NULL,
+    description TEXT NOT NULL,
+    description_th TEXT,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_certification_compliance_issues_cert
+    ON certification_compliance_issues(certification_id, detected_at DESC);
+
+-- Default allowed substances (Organic Thailand / IFOAM permitted inputs)
+INSERT INTO organic_allowed_inputs (input_type, substance, substance_th) VALUES
+('fertilizer', 'Compost', 'ปุ๋ยหมัก'),
+('fertilizer', 'Vermicompost', 'ปุ๋ยมูลไส้เดือน'),
+('fertilizer', 'Animal manure', 'ปุ๋ยคอก'),
+('fertilizer', 'Green manure', 'ปุ๋ยพืชสด'),
+('fertilizer', 'Bokashi', 'โบกาฉิ'),
+('fertilizer', 'Bio-extract', 'น้ำหมักชีวภาพ'),
+('fertilizer', 'Coffee pulp compost', 'ปุ๋ยหมักเปลือกกาแฟ'),
+('fertilizer', 'Rock phosphate', 'หินฟอสเฟต'),
+('fertilizer', 'Dolomite', 'โดโลไมท์'),
+('fertilizer', 'Agricultural lime', 'ปูนมาร์ล'),
+('fertilizer', 'Wood ash', 'ขี้เถ้าไม้'),
+('fertilizer', 'Biochar', 'ถ่านชีวภาพ'),
+('pesticide', 'Neem extract', 'สารสกัดสะเดา'),
+('pesticide', 'Bacillus thuringiensis', 'บาซิลลัส ทูริงเจนซิส'),
+('pesticide', 'Beauveria bassiana', 'เชื้อราบิวเวอร์เรีย'),
+('pesticide', 'Metarhizium anisopliae', 'เชื้อราเมตาไรเซียม'),
+('pesticide', 'Trichoderma', 'เชื้อราไตรโคเดอร์มา'),
+('pesticide', 'Copper hydroxide', 'คอปเปอร์ไฮดรอกไซด์'),
+('pesticide', 'Sulfur', 'กำมะถัน'),
+('pesticide', 'Kaolin clay', 'ดินขาวเคโอลิน'),
+('pesticide', 'Pheromone trap', 'กับดักฟีโรโมน');
+
+COMMENT ON TABLE farm_activities IS 'Field work per plot, including fertilizer and pesticide applications';
+COMMENT ON TABLE organic_allowed_inputs IS 'Substances allowed under organic certification; business_id NULL for the default list';
+COMMENT ON TABLE certification_compliance_issues IS 'Compliance issues raised against a certification, such as non-organic inputs';
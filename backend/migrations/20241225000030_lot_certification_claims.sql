@@ -0,0 +1,9 @@
+-- Lot Certification Claims Migration
+-- A lot's certification claims are derived from the plots its harvests came
+-- from and the lots blended into it. Blending lots that differ in a claim is
+-- only allowed when the user drops that claim from the blend; the dropped
+-- claims are kept here (and in the audit log) so the blend never regains them.
+
+ALTER TABLE lots ADD COLUMN downgraded_claims TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN lots.downgraded_claims IS 'Certification types (certification_type values) the lot may not claim after blending certified with non-certified lots';
@@ -0,0 +1,29 @@
+-- Member Invitations Migration
+-- Owners invite coworkers into their business by email or by a link shared
+-- over LINE. The invitee accepts with the link's token, which creates their
+-- user with the role chosen on the invitation. Only the SHA-256 of the token
+-- is stored.
+
+CREATE TABLE member_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    channel VARCHAR(10) NOT NULL CHECK (channel IN ('email', 'line')),
+    -- Required for email invitations; LINE invitees give theirs on accepting
+    email VARCHAR(255),
+    name VARCHAR(255),
+    preferred_language VARCHAR(5) NOT NULL DEFAULT 'th' CHECK (preferred_language IN ('th', 'en')),
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    accepted_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT email_invitation_has_email CHECK (channel <> 'email' OR email IS NOT NULL)
+);
+
+CREATE INDEX idx_member_invitations_business ON member_invitations(business_id, created_at DESC);
+
+COMMENT ON TABLE member_invitations IS 'Invitations for coworkers to join a business with a role';
@@ -0,0 +1,71 @@
+-- Lot Insurance Migration
+-- Insurance policies covering a lot in storage or in transit, optionally
+-- for one shipment (sales order). The business owner is reminded before a
+-- policy lapses, and policies marked for buyers print on the lot spec sheet.
+
+CREATE TABLE lot_insurance_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    -- Set when the policy covers one shipment of the lot
+    sales_order_id UUID REFERENCES sales_orders(id) ON DELETE SET NULL,
+    policy_number VARCHAR(100) NOT NULL,
+    insurer VARCHAR(255) NOT NULL,
+    coverage_type VARCHAR(20) NOT NULL
+        CHECK (coverage_type IN ('storage', 'transit', 'all_risk')),
+    insured_amount DECIMAL(14,2) NOT NULL CHECK (insured_amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    deductible_amount DECIMAL(14,2) CHECK (deductible_amount IS NULL OR deductible_amount >= 0),
+    valid_from DATE NOT NULL,
+    valid_until DATE NOT NULL,
+
+    -- Policy document
+    document_name VARCHAR(255),
+    file_url TEXT,
+
+    include_in_buyer_pack BOOLEAN NOT NULL DEFAULT TRUE,
+    notes TEXT,
+    -- Set when the owner was reminded of the upcoming expiry
+    expiry_alert_sent_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT lot_insurance_validity CHECK (valid_until >= valid_from),
+    CONSTRAINT unique_lot_insurance_policy UNIQUE (business_id, lot_id, policy_number)
+);
+
+CREATE INDEX idx_lot_insurance_lot ON lot_insurance_policies(lot_id, valid_until DESC);
+CREATE INDEX idx_lot_insurance_business ON lot_insurance_policies(business_id, valid_until);
+
+-- Expiry reminders follow the certification expiry preference
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'insurance_expiring';
+
+CREATE OR REPLACE FUNCTION is_notification_enabled(
+    p_user_id UUID,
+    p_notification_type notification_type
+)
+RETURNS BOOLEAN AS $$
+DECLARE
+    v_enabled BOOLEAN;
+BEGIN
+    SELECT
+        CASE p_notification_type::text
+            WHEN 'low_inventory' THEN low_inventory_enabled
+            WHEN 'certification_expiring' THEN certification_expiring_enabled
+            WHEN 'insurance_expiring' THEN certification_expiring_enabled
+            WHEN 'processing_milestone' THEN processing_milestone_enabled
+            WHEN 'weather_alert' THEN weather_alert_enabled
+            WHEN 'harvest_reminder' THEN harvest_reminder_enabled
+            WHEN 'quality_alert' THEN quality_alert_enabled
+            ELSE true
+        END
+    INTO v_enabled
+    FROM notification_preferences
+    WHERE user_id = p_user_id;
+
+    RETURN COALESCE(v_enabled, true);
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON TABLE lot_insurance_policies IS 'Insurance policies per lot or shipment with validity for expiry reminders';
+COMMENT ON COLUMN lot_insurance_policies.include_in_buyer_pack IS 'Shown on the lot spec sheet given to buyers while valid';
@@ -0,0 +1,21 @@
+-- Refresh Token Sessions Migration
+-- Each sign-in starts a session. Refresh tokens rotate on every use and the
+-- new token keeps the session id and device metadata, so a user can list
+-- their signed-in devices and revoke one. Presenting an already-rotated token
+-- revokes the whole session, since the token must have been copied.
+
+ALTER TABLE refresh_tokens
+    ADD COLUMN session_id UUID,
+    ADD COLUMN session_started_at TIMESTAMPTZ,
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN ip_address VARCHAR(45);
+
+CREATE INDEX idx_refresh_tokens_session ON refresh_tokens(session_id, created_at DESC);
+
+-- Tokens are now stored as SHA-256 hashes; earlier tokens can no longer be
+-- looked up, so their holders sign in again
+UPDATE refresh_tokens SET revoked_at = NOW() WHERE revoked_at IS NULL;
+
+COMMENT ON COLUMN refresh_tokens.session_id IS 'Shared by all rotations of one sign-in';
+COMMENT ON COLUMN refresh_tokens.device_info IS 'Device label shown in the session list';
+COMMENT ON COLUMN refresh_tokens.ip_address IS 'Client address when this token was issued';
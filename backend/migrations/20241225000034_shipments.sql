@@ -0,0 +1,76 @@
+-- Shipments Migration
+-- A shipment groups packages of one or more lots (optionally for a sales
+-- order) moving with one carrier between two ports. Its progress is a set of
+-- milestones (booked, loaded, departed, arrived, cleared) entered by hand or
+-- posted by the carrier to a webhook holding the shipment's webhook token.
+-- Buyers follow the shipment through a link holding its share token.
+
+CREATE TABLE shipments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- SH-YYYY-NNNN, numbered per business and year
+    shipment_number VARCHAR(50) NOT NULL,
+    carrier VARCHAR(255) NOT NULL,
+    mode VARCHAR(20) NOT NULL CHECK (mode IN ('sea', 'air', 'road', 'courier')),
+    -- Booking, bill of lading, air waybill or courier tracking number
+    tracking_number VARCHAR(100),
+    -- Vessel and voyage, or flight number
+    vessel VARCHAR(255),
+    departure_port VARCHAR(255) NOT NULL,
+    arrival_port VARCHAR(255) NOT NULL,
+    etd DATE,
+    eta DATE,
+    -- The furthest milestone reached
+    status VARCHAR(20) NOT NULL DEFAULT 'planned'
+        CHECK (status IN ('planned', 'booked', 'loaded', 'departed', 'arrived', 'cleared')),
+    share_token VARCHAR(64) NOT NULL UNIQUE,
+    webhook_token VARCHAR(64) NOT NULL UNIQUE,
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_shipment_number UNIQUE (business_id, shipment_number)
+);
+
+CREATE INDEX idx_shipments_business ON shipments(business_id, created_at DESC);
+
+CREATE TRIGGER update_shipments_updated_at
+    BEFORE UPDATE ON shipments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE shipment_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    shipment_id UUID NOT NULL REFERENCES shipments(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    sales_order_id UUID REFERENCES sales_orders(id) ON DELETE SET NULL,
+    package_count INTEGER NOT NULL CHECK (package_count > 0),
+    -- e.g. 60 kg jute bag, GrainPro bag, vacuum box
+    package_type VARCHAR(100),
+    quantity_kg DECIMAL(10,3) NOT NULL CHECK (quantity_kg > 0)
+);
+
+CREATE INDEX idx_shipment_items_shipment ON shipment_items(shipment_id);
+CREATE INDEX idx_shipment_items_lot ON shipment_items(lot_id);
+CREATE INDEX idx_shipment_items_order ON shipment_items(sales_order_id);
+
+CREATE TABLE shipment_milestones (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    shipment_id UUID NOT NULL REFERENCES shipments(id) ON DELETE CASCADE,
+    milestone VARCHAR(20) NOT NULL
+        CHECK (milestone IN ('booked', 'loaded', 'departed', 'arrived', 'cleared')),
+    occurred_at TIMESTAMPTZ NOT NULL,
+    location VARCHAR(255),
+    note TEXT,
+    source VARCHAR(10) NOT NULL CHECK (source IN ('manual', 'webhook')),
+    recorded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- A repeated update corrects the milestone rather than adding another
+    CONSTRAINT unique_shipment_milestone UNIQUE (shipment_id, milestone)
+);
+
+COMMENT ON COLUMN shipments.share_token IS 'Secret in the buyer''s link to follow the shipment';
+COMMENT ON COLUMN shipments.webhook_token IS 'Secret in the carrier''s status webhook URL';
+COMMENT ON COLUMN shipment_milestones.source IS 'manual when entered by a user; webhook when posted by the carrier';
@@ -0,0 +1,24 @@
+-- Two-Factor Authentication Migration
+-- Optional TOTP second factor per user. Enrolling stores a secret that only
+-- takes effect once a code from the authenticator app confirms it. Backup
+-- codes for a lost phone are stored as SHA-256 hashes and work once each.
+
+ALTER TABLE users
+    ADD COLUMN totp_secret VARCHAR(64),
+    ADD COLUMN totp_enabled_at TIMESTAMPTZ,
+    ADD COLUMN totp_last_step BIGINT;
+
+CREATE TABLE user_backup_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_user_backup_code UNIQUE (user_id, code_hash)
+);
+
+CREATE INDEX idx_user_backup_codes_user ON user_backup_codes(user_id) WHERE used_at IS NULL;
+
+COMMENT ON COLUMN users.totp_secret IS 'Base32 TOTP secret; pending until totp_enabled_at is set';
+COMMENT ON COLUMN users.totp_last_step IS 'Last 30-second step a code was accepted for, so a code works once';
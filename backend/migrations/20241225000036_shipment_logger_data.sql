@@ -0,0 +1,36 @@
+-- Shipment Logger Data Migration
+-- Containers carry temperature/humidity data loggers. Their CSV exports are
+-- imported against the shipment, one import per logger, with the limits the
+-- readings are judged against. Excursions outside the limits make up the
+-- transit-conditions report used in arrival-quality disputes.
+
+CREATE TABLE shipment_logger_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    shipment_id UUID NOT NULL REFERENCES shipments(id) ON DELETE CASCADE,
+    logger_serial VARCHAR(100) NOT NULL,
+    file_name VARCHAR(255),
+    -- Limits the readings are judged against
+    temp_min_c DECIMAL(5,2) NOT NULL,
+    temp_max_c DECIMAL(5,2) NOT NULL,
+    humidity_max_pct DECIMAL(5,2) NOT NULL,
+    reading_count INTEGER NOT NULL,
+    first_reading_at TIMESTAMPTZ NOT NULL,
+    last_reading_at TIMESTAMPTZ NOT NULL,
+    imported_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT shipment_logger_limits CHECK (temp_max_c > temp_min_c),
+    -- Importing a logger again replaces its readings
+    CONSTRAINT unique_shipment_logger UNIQUE (shipment_id, logger_serial)
+);
+
+CREATE TABLE shipment_logger_readings (
+    import_id UUID NOT NULL REFERENCES shipment_logger_imports(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    temperature_c DECIMAL(5,2) NOT NULL,
+    relative_humidity DECIMAL(5,2),
+    PRIMARY KEY (import_id, recorded_at)
+);
+
+COMMENT ON TABLE shipment_logger_imports IS 'Data logger exports attached to a shipment, one per logger';
+COMMENT ON COLUMN shipment_logger_readings.relative_humidity IS 'Percent; null for temperature-only loggers';
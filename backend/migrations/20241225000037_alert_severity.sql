@@ -0,0 +1,39 @@
+-- Alert Severity Migration
+-- Alert definitions carry a severity (info/warning/critical), and each user
+-- routes every severity to its own channels: by default critical alerts go to
+-- LINE and email, warnings to LINE and info stays in the in-app inbox. The
+-- in-app notification is always created; the routing picks the push channels.
+
+CREATE TYPE alert_severity AS ENUM (
+    'info',
+    'warning',
+    'critical'
+);
+
+ALTER TABLE inventory_alerts
+ADD COLUMN IF NOT EXISTS severity alert_severity NOT NULL DEFAULT 'warning';
+
+ALTER TABLE weather_alerts
+ADD COLUMN IF NOT EXISTS severity alert_severity NOT NULL DEFAULT 'warning';
+
+-- Notifications without a severity take it from their priority
+-- (0 = info, 1 = warning, 2 and above = critical)
+ALTER TABLE notification_queue
+ADD COLUMN IF NOT EXISTS severity alert_severity NOT NULL DEFAULT 'info';
+
+ALTER TABLE notification_log
+ADD COLUMN IF NOT EXISTS severity alert_severity NOT NULL DEFAULT 'info';
+
+UPDATE notification_queue
+SET severity = CASE WHEN priority >= 2 THEN 'critical' WHEN priority = 1 THEN 'warning' ELSE 'info' END::alert_severity;
+
+UPDATE notification_log
+SET severity = CASE WHEN priority >= 2 THEN 'critical' WHEN priority = 1 THEN 'warning' ELSE 'info' END::alert_severity;
+
+-- Per-severity routing; channels switched off entirely (line_enabled,
+-- email_enabled) are skipped whatever the routing says
+ALTER TABLE notification_preferences
+ADD COLUMN IF NOT EXISTS email_enabled BOOLEAN NOT NULL DEFAULT true,
+ADD COLUMN IF NOT EXISTS info_channels notification_channel[] NOT NULL DEFAULT '{in_app}',
+ADD COLUMN IF NOT EXISTS warning_channels notification_channel[] NOT NULL DEFAULT '{line,in_app}',
+ADD COLUMN IF NOT EXISTS critical_channels notification_channel[] NOT NULL DEFAULT '{line,email,in_app}';
@@ -0,0 +1,33 @@
+-- KPI Targets Migration
+-- Owners set targets per crop season (cherry harvested, average cupping
+-- score, share of specialty samples, sales revenue). The KPI report compares
+-- the season's actuals against them, and a summary of the month just ended
+-- is sent to the owner once a month.
+
+CREATE TABLE kpi_targets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Crop season by the year it starts in
+    season INTEGER NOT NULL,
+    target_cherry_kg DECIMAL(12,3) CHECK (target_cherry_kg > 0),
+    target_avg_score DECIMAL(5,2) CHECK (target_avg_score > 0 AND target_avg_score <= 100),
+    target_specialty_pct DECIMAL(5,2) CHECK (target_specialty_pct > 0 AND target_specialty_pct <= 100),
+    target_revenue DECIMAL(14,2) CHECK (target_revenue > 0),
+    -- Only sales orders in this currency count towards the revenue target
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    -- First day of the month the last monthly summary covered
+    summary_sent_for DATE,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_kpi_target_season UNIQUE (business_id, season)
+);
+
+CREATE TRIGGER update_kpi_targets_updated_at
+    BEFORE UPDATE ON kpi_targets
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE kpi_targets IS 'Seasonal KPI targets; a null target is not tracked';
+COMMENT ON COLUMN kpi_targets.target_specialty_pct IS 'Percent of cupping samples scoring 80 or more';
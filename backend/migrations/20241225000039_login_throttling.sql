@@ -0,0 +1,27 @@
+-- Login Throttling Migration
+-- Every sign-in attempt is recorded with the client address. Repeated wrong
+-- passwords (or two-factor codes) lock the account for a while, twice as long
+-- with each lockout until a successful sign-in; too many failures from one
+-- address are refused for a while whichever accounts they target. Admins can
+-- unlock a member early.
+
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS failed_login_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS lockout_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+
+CREATE TABLE login_attempts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email VARCHAR(255) NOT NULL,
+    -- Null when no account uses the email
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    ip_address VARCHAR(45),
+    succeeded BOOLEAN NOT NULL,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_login_attempts_ip_failures ON login_attempts(ip_address, attempted_at) WHERE NOT succeeded;
+CREATE INDEX idx_login_attempts_user ON login_attempts(user_id, attempted_at DESC);
+
+COMMENT ON COLUMN users.failed_login_count IS 'Wrong passwords or codes since the last sign-in or lockout';
+COMMENT ON COLUMN users.lockout_count IS 'Lockouts since the last sign-in; each doubles the next lockout';
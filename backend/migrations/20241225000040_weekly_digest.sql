@@ -0,0 +1,51 @@
+-- Weekly Digest Migration
+-- Business owners get a digest of the week just ended every Monday morning:
+-- volumes harvested, processed and roasted, notable cupping results, alerts
+-- raised, upcoming expirations and open work. Each user chooses whether to
+-- get it and on which channels; each week is sent once per business.
+
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'weekly_digest';
+
+ALTER TABLE notification_preferences
+ADD COLUMN IF NOT EXISTS weekly_digest_enabled BOOLEAN NOT NULL DEFAULT true,
+ADD COLUMN IF NOT EXISTS digest_channels notification_channel[] NOT NULL DEFAULT '{line,email,in_app}';
+
+CREATE OR REPLACE FUNCTION is_notification_enabled(
+    p_user_id UUID,
+    p_notification_type notification_type
+)
+RETURNS BOOLEAN AS $$
+DECLARE
+    v_enabled BOOLEAN;
+BEGIN
+    SELECT
+        CASE p_notification_type::text
+            WHEN 'low_inventory' THEN low_inventory_enabled
+            WHEN 'certification_expiring' THEN certification_expiring_enabled
+            WHEN 'insurance_expiring' THEN certification_expiring_enabled
+            WHEN 'processing_milestone' THEN processing_milestone_enabled
+            WHEN 'weather_alert' THEN weather_alert_enabled
+            WHEN 'harvest_reminder' THEN harvest_reminder_enabled
+            WHEN 'quality_alert' THEN quality_alert_enabled
+            WHEN 'weekly_digest' THEN weekly_digest_enabled
+            ELSE true
+        END
+    INTO v_enabled
+    FROM notification_preferences
+    WHERE user_id = p_user_id;
+
+    RETURN COALESCE(v_enabled, true);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE weekly_digests (
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Monday of the week covered
+    week_start DATE NOT NULL,
+    -- Null when the owner has the digest switched off
+    notification_id UUID REFERENCES notification_queue(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (business_id, week_start)
+);
+
+COMMENT ON TABLE weekly_digests IS 'Weeks whose digest was generated, so each is sent once';
@@ -0,0 +1,40 @@
+-- Storage Locations Migration
+-- Warehouses and stores where lots are kept, with their coordinates so the
+-- weather forecast can be checked for them. Green coffee kept without
+-- climate control suffers in long hot spells, so the owner is advised when
+-- the forecast shows several days above 32°C at such a location, with the
+-- lots stored there.
+
+CREATE TABLE storage_locations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    latitude DECIMAL(10, 7) NOT NULL,
+    longitude DECIMAL(10, 7) NOT NULL,
+    climate_controlled BOOLEAN NOT NULL DEFAULT false,
+    notes TEXT,
+    -- Last forecast check by the background job
+    heat_checked_at TIMESTAMPTZ,
+    -- Last day of the hot spell the owner was advised of
+    heat_advised_through DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_storage_location_name UNIQUE (business_id, name)
+);
+
+CREATE INDEX idx_storage_locations_heat_check
+    ON storage_locations(heat_checked_at NULLS FIRST) WHERE NOT climate_controlled;
+
+CREATE TRIGGER update_storage_locations_updated_at
+    BEFORE UPDATE ON storage_locations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE lots
+ADD COLUMN IF NOT EXISTS storage_location_id UUID REFERENCES storage_locations(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_lots_storage_location ON lots(storage_location_id) WHERE storage_location_id IS NOT NULL;
+
+COMMENT ON TABLE storage_locations IS 'Warehouses where lots are stored, checked against heat forecasts';
+COMMENT ON COLUMN lots.storage_location_id IS 'Where the lot is currently stored';
@@ -0,0 +1,16 @@
+-- LINE Registration Migration
+-- Farmers without an email address can sign up and sign in with LINE Login
+-- alone: the business and its owner are created from the LINE profile. Such
+-- accounts have no email or password until they add one.
+
+ALTER TABLE users
+ALTER COLUMN email DROP NOT NULL,
+ALTER COLUMN password_hash DROP NOT NULL;
+
+ALTER TABLE users
+ADD CONSTRAINT users_sign_in_method CHECK (
+    (email IS NOT NULL AND password_hash IS NOT NULL)
+    OR (email IS NULL AND password_hash IS NULL)
+);
+
+COMMENT ON COLUMN users.email IS 'Null for accounts that sign in with LINE only';
@@ -0,0 +1,57 @@
+-- Sale Prices Migration
+-- Prices a business actually realized for its lots, by grade, and market
+-- reference prices entered by hand (ICE "C" price, Thai FOB quotes). Both
+-- keep the price as entered and normalized to THB per kg, so the pricing
+-- report can show each lot's premium over the market on its sale date.
+
+CREATE TABLE lot_sale_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    -- The sale the price was realized in, when closed through a sales order
+    sales_order_id UUID UNIQUE REFERENCES sales_orders(id) ON DELETE SET NULL,
+    -- SCA grade of the coffee sold
+    grade VARCHAR(50),
+    sale_date DATE NOT NULL,
+    buyer_name VARCHAR(255),
+    quantity_kg DECIMAL(10, 3) NOT NULL CHECK (quantity_kg > 0),
+    price_per_kg DECIMAL(10, 2) NOT NULL CHECK (price_per_kg > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB' CHECK (currency IN ('THB', 'USD')),
+    thb_per_usd DECIMAL(10, 4) CHECK (thb_per_usd IS NULL OR thb_per_usd > 0),
+    price_thb_per_kg DECIMAL(12, 2) NOT NULL,
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT valid_sale_grade CHECK (grade IS NULL OR grade IN (
+        'specialty_grade', 'premium_grade', 'exchange_grade', 'below_standard', 'off_grade'
+    ))
+);
+
+CREATE INDEX idx_lot_sale_prices_business_date ON lot_sale_prices(business_id, sale_date DESC);
+CREATE INDEX idx_lot_sale_prices_lot ON lot_sale_prices(lot_id);
+
+CREATE TABLE market_reference_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- c_price (ICE Arabica futures), thai_fob or other
+    benchmark VARCHAR(20) NOT NULL CHECK (benchmark IN ('c_price', 'thai_fob', 'other')),
+    reference_date DATE NOT NULL,
+    price DECIMAL(12, 4) NOT NULL CHECK (price > 0),
+    -- usc_per_lb (how the C price is quoted), usd_per_kg or thb_per_kg
+    unit VARCHAR(20) NOT NULL CHECK (unit IN ('usc_per_lb', 'usd_per_kg', 'thb_per_kg')),
+    thb_per_usd DECIMAL(10, 4) CHECK (thb_per_usd IS NULL OR thb_per_usd > 0),
+    price_thb_per_kg DECIMAL(12, 2) NOT NULL,
+    source VARCHAR(255),
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_market_reference UNIQUE (business_id, benchmark, reference_date)
+);
+
+CREATE INDEX idx_market_reference_prices_lookup
+    ON market_reference_prices(business_id, benchmark, reference_date DESC);
+
+COMMENT ON COLUMN lot_sale_prices.price_thb_per_kg IS 'Realized price converted with thb_per_usd when sold in USD';
+COMMENT ON COLUMN market_reference_prices.price_thb_per_kg IS 'Reference price converted to THB per kg of green coffee';
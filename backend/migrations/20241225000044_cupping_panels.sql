@@ -0,0 +1,42 @@
+-- Cupping Panels Migration
+-- Panel cuppings: several cuppers score the same sample. Each cupper's score
+-- sheet is kept here; the sample row itself holds the panel consensus (mean
+-- of every attribute), so reports and specs keep reading cupping_samples.
+-- The session's cupper_name stays as the head cupper, whose scores are the
+-- sample's scores until other cuppers are added.
+
+CREATE TABLE cupping_cupper_scores (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sample_id UUID NOT NULL REFERENCES cupping_samples(id) ON DELETE CASCADE,
+    cupper_name VARCHAR(255) NOT NULL,
+    fragrance_aroma DECIMAL(4, 2) NOT NULL,
+    flavor DECIMAL(4, 2) NOT NULL,
+    aftertaste DECIMAL(4, 2) NOT NULL,
+    acidity DECIMAL(4, 2) NOT NULL,
+    body DECIMAL(4, 2) NOT NULL,
+    balance DECIMAL(4, 2) NOT NULL,
+    uniformity DECIMAL(4, 2) NOT NULL,
+    clean_cup DECIMAL(4, 2) NOT NULL,
+    sweetness DECIMAL(4, 2) NOT NULL,
+    overall DECIMAL(4, 2) NOT NULL,
+    total_score DECIMAL(5, 2) NOT NULL,
+    defects_taint INTEGER NOT NULL DEFAULT 0,
+    defects_fault INTEGER NOT NULL DEFAULT 0,
+    final_score DECIMAL(5, 2) NOT NULL,
+    tasting_notes TEXT,
+    tasting_notes_th TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One score sheet per cupper and sample; names compare like the bias report
+CREATE UNIQUE INDEX idx_cupping_cupper_scores_unique
+    ON cupping_cupper_scores(sample_id, LOWER(cupper_name));
+
+CREATE TRIGGER update_cupping_cupper_scores_updated_at
+    BEFORE UPDATE ON cupping_cupper_scores
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE cupping_cupper_scores IS 'Score sheet of each panel cupper for a cupping sample';
+COMMENT ON COLUMN cupping_sessions.cupper_name IS 'Head cupper; other panel cuppers score samples in cupping_cupper_scores';
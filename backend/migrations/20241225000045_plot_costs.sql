@@ -0,0 +1,32 @@
+-- Plot Costs Migration
+-- Production costs per crop season (October to September), entered by hand.
+-- A cost names the plot it was spent on; costs shared by the farm (a
+-- pulper, land tax, a truck) leave the plot empty and are spread over the
+-- plots either by area or by the cherry each plot picked that season. The
+-- plot profitability report sets them against the revenue of the lots each
+-- plot's cherry went into.
+
+CREATE TABLE plot_costs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Empty for a cost shared by all plots
+    plot_id UUID REFERENCES plots(id) ON DELETE CASCADE,
+    -- Crop season by the year it starts in
+    season INTEGER NOT NULL,
+    category VARCHAR(20) NOT NULL
+        CHECK (category IN ('labor', 'inputs', 'equipment', 'land', 'transport', 'processing', 'other')),
+    -- Amount in THB
+    amount DECIMAL(12, 2) NOT NULL CHECK (amount > 0),
+    cost_date DATE,
+    description TEXT,
+    -- How a shared cost is spread over the plots: area or cherry
+    allocation VARCHAR(10) NOT NULL DEFAULT 'area' CHECK (allocation IN ('area', 'cherry')),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_plot_costs_business_season ON plot_costs(business_id, season);
+CREATE INDEX idx_plot_costs_plot ON plot_costs(plot_id);
+
+COMMENT ON TABLE plot_costs IS 'Production costs per plot and crop season, or shared by all plots when plot_id is empty';
+COMMENT ON COLUMN plot_costs.allocation IS 'Spreads a shared cost by plot area (area) or by cherry picked in the season (cherry)';
@@ -0,0 +1,19 @@
+-- Blind Cupping Migration
+-- Blind sessions hide which lot each sample is: samples get a random 3-digit
+-- code when added, and the lot stays out of API responses until someone
+-- reveals the session. Who revealed it and when is kept with the session.
+
+ALTER TABLE cupping_sessions
+    ADD COLUMN is_blind BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN revealed_at TIMESTAMPTZ,
+    ADD COLUMN revealed_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE cupping_samples
+    ADD COLUMN blind_code VARCHAR(10);
+
+CREATE UNIQUE INDEX idx_cupping_samples_blind_code
+    ON cupping_samples(session_id, blind_code)
+    WHERE blind_code IS NOT NULL;
+
+COMMENT ON COLUMN cupping_sessions.revealed_at IS 'When the lots of a blind session were revealed; NULL while still blind';
+COMMENT ON COLUMN cupping_samples.blind_code IS 'Anonymous code cuppers see for the sample in a blind session';
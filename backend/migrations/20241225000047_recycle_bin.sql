@@ -0,0 +1,32 @@
+-- Recycle Bin Migration
+-- Deleting a plot, harvest, farm activity, certification, lab result,
+-- shipment, insurance policy or water quality measurement first keeps a
+-- snapshot of it: the row, the rows its delete cascades to, and the rows
+-- whose reference to it is cleared. A snapshot can be restored until the
+-- business's retention period has passed, after which a background job
+-- purges it.
+
+CREATE TABLE deleted_records (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    entity VARCHAR(30) NOT NULL
+        CHECK (entity IN ('plot', 'harvest', 'farm_activity', 'certification', 'lab_result',
+                          'shipment', 'insurance_policy', 'water_quality')),
+    -- Id of the deleted row; not a foreign key since the row is gone
+    record_id UUID NOT NULL,
+    label TEXT NOT NULL,
+    -- {"rows": [{"table", "row"}], "links": [{"table", "column", "target", "ids"}]}
+    snapshot JSONB NOT NULL,
+    deleted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_deleted_records_business ON deleted_records(business_id, deleted_at DESC);
+CREATE INDEX idx_deleted_records_deleted_at ON deleted_records(deleted_at);
+
+ALTER TABLE businesses
+    ADD COLUMN recycle_bin_retention_days INTEGER NOT NULL DEFAULT 30
+        CHECK (recycle_bin_retention_days BETWEEN 1 AND 365);
+
+COMMENT ON TABLE deleted_records IS 'Snapshots of deleted records that can be restored until the retention period passes';
+COMMENT ON COLUMN businesses.recycle_bin_retention_days IS 'Days a deleted record stays restorable before it is purged';
@@ -0,0 +1,11 @@
+-- Flavor Descriptors Migration
+-- Cupping samples record flavors as codes from the SCA flavor wheel
+-- (e.g. 'fruity.berry.blueberry'); the wheel itself lives in the shared
+-- crate. Free-text tasting notes stay for anything the wheel does not cover.
+
+ALTER TABLE cupping_samples
+    ADD COLUMN flavor_descriptors TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_cupping_samples_flavor_descriptors ON cupping_samples USING GIN (flavor_descriptors);
+
+COMMENT ON COLUMN cupping_samples.flavor_descriptors IS 'SCA flavor wheel codes, category.group.descriptor';
@@ -0,0 +1,96 @@
+-- Weight Units Migration
+-- Farmers weigh cherry in local units: a tang (ถัง, a 20 litre bucket) or a
+-- kasop (กระสอบ, a sack), as well as pounds for some buyers. Harvests and
+-- inventory transactions keep the weight as entered together with its unit
+-- and the kilograms it converts to. Local units vary between villages, so a
+-- business can set its own kilograms for them; kilograms and pounds are
+-- fixed.
+
+CREATE TABLE weight_units (
+    code VARCHAR(20) PRIMARY KEY,
+    name VARCHAR(50) NOT NULL,
+    name_th VARCHAR(50) NOT NULL,
+    kg_per_unit DECIMAL(12, 6) NOT NULL CHECK (kg_per_unit > 0),
+    -- Exact units a business cannot override
+    fixed BOOLEAN NOT NULL DEFAULT false,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO weight_units (code, name, name_th, kg_per_unit, fixed, sort_order) VALUES
+    ('kg', 'Kilogram', 'กิโลกรัม', 1, true, 1),
+    ('lb', 'Pound', 'ปอนด์', 0.453592, true, 2),
+    ('tang', 'Tang (20 L bucket of cherry)', 'ถัง', 12, false, 3),
+    ('kasop', 'Kasop (sack of cherry)', 'กระสอบ', 50, false, 4);
+
+CREATE TABLE business_weight_units (
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    unit_code VARCHAR(20) NOT NULL REFERENCES weight_units(code) ON DELETE CASCADE,
+    kg_per_unit DECIMAL(12, 6) NOT NULL CHECK (kg_per_unit > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (business_id, unit_code)
+);
+
+ALTER TABLE harvests
+    ADD COLUMN cherry_weight_original DECIMAL(10, 3),
+    ADD COLUMN cherry_weight_unit VARCHAR(20) NOT NULL DEFAULT 'kg' REFERENCES weight_units(code);
+
+UPDATE harvests SET cherry_weight_original = cherry_weight_kg;
+
+ALTER TABLE harvests
+    ALTER COLUMN cherry_weight_original SET NOT NULL,
+    ADD CONSTRAINT harvests_cherry_weight_original_positive CHECK (cherry_weight_original > 0);
+
+ALTER TABLE inventory_transactions
+    ADD COLUMN quantity_original DECIMAL(10, 3),
+    ADD COLUMN quantity_unit VARCHAR(20) NOT NULL DEFAULT 'kg' REFERENCES weight_units(code);
+
+UPDATE inventory_transactions SET quantity_original = quantity_kg;
+
+ALTER TABLE inventory_transactions
+    ALTER COLUMN quantity_original SET NOT NULL;
+
+-- Writers that only set kilograms (offline sync, seeding) record the
+-- weight as entered in kilograms
+CREATE OR REPLACE FUNCTION default_harvest_weight_original()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.cherry_weight_original IS NULL
+       OR (TG_OP = 'UPDATE' AND NEW.cherry_weight_kg IS DISTINCT FROM OLD.cherry_weight_kg
+           AND NEW.cherry_weight_original IS NOT DISTINCT FROM OLD.cherry_weight_original
+           AND NEW.cherry_weight_unit IS NOT DISTINCT FROM OLD.cherry_weight_unit) THEN
+        NEW.cherry_weight_original := NEW.cherry_weight_kg;
+        NEW.cherry_weight_unit := 'kg';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER harvests_weight_original
+    BEFORE INSERT OR UPDATE ON harvests
+    FOR EACH ROW
+    EXECUTE FUNCTION default_harvest_weight_original();
+
+CREATE OR REPLACE FUNCTION default_inventory_quantity_original()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.quantity_original IS NULL
+       OR (TG_OP = 'UPDATE' AND NEW.quantity_kg IS DISTINCT FROM OLD.quantity_kg
+           AND NEW.quantity_original IS NOT DISTINCT FROM OLD.quantity_original
+           AND NEW.quantity_unit IS NOT DISTINCT FROM OLD.quantity_unit) THEN
+        NEW.quantity_original := NEW.quantity_kg;
+        NEW.quantity_unit := 'kg';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER inventory_transactions_quantity_original
+    BEFORE INSERT OR UPDATE ON inventory_transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION default_inventory_quantity_original();
+
+COMMENT ON TABLE weight_units IS 'Units weights can be entered in and their kilograms';
+COMMENT ON TABLE business_weight_units IS 'Kilograms a business uses for a local unit instead of the default';
+COMMENT ON COLUMN harvests.cherry_weight_original IS 'Cherry weight as entered, in cherry_weight_unit';
+COMMENT ON COLUMN inventory_transactions.quantity_original IS 'Quantity as entered, in quantity_unit';
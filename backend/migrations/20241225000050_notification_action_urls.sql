@@ -0,0 +1,44 @@
+-- Notification Action URLs Migration
+-- Notifications carry the client path they open (a lot, a certification,
+-- a roast session...) so an alert can be followed straight to its page. The
+-- path is set when the notification is queued and copied to the in-app
+-- notification when it is sent.
+
+ALTER TABLE notification_queue
+ADD COLUMN IF NOT EXISTS action_url VARCHAR(500);
+
+-- Existing notifications link to the entity they were raised for, as
+-- `notification_action_url` in the notification service does
+CREATE FUNCTION backfill_notification_action_url(
+    p_notification_type notification_type,
+    p_entity_type VARCHAR,
+    p_entity_id UUID
+)
+RETURNS VARCHAR AS $$
+    SELECT CASE
+        WHEN p_notification_type = 'weekly_digest' THEN '/reports/weekly-digest'
+        WHEN p_entity_type = 'kpi_targets' THEN '/reports/kpi'
+        WHEN p_entity_id IS NULL THEN NULL
+        WHEN p_entity_type = 'lot' THEN '/lots/' || p_entity_id
+        WHEN p_entity_type = 'plot' THEN '/plots/' || p_entity_id
+        WHEN p_entity_type = 'certification' THEN '/certifications/' || p_entity_id
+        WHEN p_entity_type = 'lot_insurance_policy' THEN '/insurance-policies/' || p_entity_id
+        WHEN p_entity_type = 'storage_location' THEN '/storage-locations/' || p_entity_id
+        WHEN p_entity_type = 'sales_lead' THEN '/sales/leads/' || p_entity_id
+        WHEN p_entity_type = 'roast_session' THEN '/roasting/sessions/' || p_entity_id
+        WHEN p_entity_type = 'cupping_session' THEN '/cupping/sessions/' || p_entity_id
+        WHEN p_entity_type = 'shipment' THEN '/shipments/' || p_entity_id
+    END
+$$ LANGUAGE sql IMMUTABLE;
+
+UPDATE in_app_notifications
+SET action_url = backfill_notification_action_url(notification_type, entity_type, entity_id)
+WHERE action_url IS NULL;
+
+UPDATE notification_queue
+SET action_url = backfill_notification_action_url(notification_type, entity_type, entity_id)
+WHERE status = 'pending';
+
+DROP FUNCTION backfill_notification_action_url(notification_type, VARCHAR, UUID);
+
+COMMENT ON COLUMN notification_queue.action_url IS 'Client path the notification opens';
@@ -0,0 +1,24 @@
+-- Cupping Sample Edits Migration
+-- Samples can be corrected and deleted. Every correction or delete records
+-- the fields it changed with their values before and after, who made it and
+-- why, so a mistyped score can be fixed without losing what was first
+-- recorded. The history stays when the sample is deleted.
+
+CREATE TABLE cupping_sample_edits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES cupping_sessions(id) ON DELETE CASCADE,
+    -- No foreign key: the history outlives a deleted sample
+    sample_id UUID NOT NULL,
+    sample_number INTEGER NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('update', 'delete')),
+    changes JSONB NOT NULL,
+    reason TEXT,
+    edited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    edited_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_cupping_sample_edits_sample ON cupping_sample_edits(sample_id, edited_at DESC);
+CREATE INDEX idx_cupping_sample_edits_session ON cupping_sample_edits(session_id);
+
+COMMENT ON TABLE cupping_sample_edits IS 'Edit history of cupping samples';
+COMMENT ON COLUMN cupping_sample_edits.changes IS 'Array of {field, from, to}; to is null for a delete';
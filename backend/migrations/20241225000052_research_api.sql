@@ -0,0 +1,41 @@
+-- Research API Migration
+-- Universities and other research partners read de-identified quality,
+-- processing and weather datasets with an API key. Only businesses that opt
+-- in contribute; opting out removes their records from every dataset
+-- immediately. Keys are stored as SHA-256 hashes, and each partner gets its
+-- own salt so lot pseudonyms cannot be linked across partners. Every request
+-- is logged, which also enforces the partner's hourly request limit.
+
+ALTER TABLE businesses
+    ADD COLUMN research_opt_in BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN research_opted_in_at TIMESTAMPTZ;
+
+CREATE INDEX idx_businesses_research ON businesses(province) WHERE research_opt_in;
+
+CREATE TABLE research_partners (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    institution VARCHAR(255) NOT NULL,
+    contact_email VARCHAR(255),
+    -- First characters of the key, to tell keys apart
+    key_prefix VARCHAR(12) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    pseudonym_salt VARCHAR(64) NOT NULL,
+    requests_per_hour INTEGER NOT NULL DEFAULT 100 CHECK (requests_per_hour > 0),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE research_api_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    partner_id UUID NOT NULL REFERENCES research_partners(id) ON DELETE CASCADE,
+    dataset VARCHAR(30) NOT NULL,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_research_api_requests_partner ON research_api_requests(partner_id, requested_at DESC);
+
+COMMENT ON COLUMN research_partners.pseudonym_salt IS 'HMAC key for the lot pseudonyms this partner sees';
+COMMENT ON COLUMN research_partners.requests_per_hour IS 'Dataset requests allowed in any rolling hour';
@@ -0,0 +1,51 @@
+-- Cupping Triangle Tests Migration
+-- A triangle session checks whether tasters can tell two lots apart, e.g.
+-- before and after a process change. Each set serves three coded cups, two
+-- of one lot and one of the other, rotating through the six balanced serving
+-- orders; tasters pick the odd cup. The share of correct answers is tested
+-- against the one-in-three chance of guessing.
+
+ALTER TABLE cupping_sessions
+    ADD COLUMN session_type VARCHAR(20) NOT NULL DEFAULT 'scoring'
+        CHECK (session_type IN ('scoring', 'triangle'));
+
+CREATE TABLE cupping_triangle_tests (
+    session_id UUID PRIMARY KEY REFERENCES cupping_sessions(id) ON DELETE CASCADE,
+    control_lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    test_lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    significance_level DECIMAL(4, 3) NOT NULL DEFAULT 0.05
+        CHECK (significance_level > 0 AND significance_level <= 0.2),
+    CONSTRAINT triangle_lots_differ CHECK (control_lot_id <> test_lot_id)
+);
+
+CREATE TABLE cupping_triangle_sets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES cupping_sessions(id) ON DELETE CASCADE,
+    set_number INTEGER NOT NULL,
+    -- Cup codes in serving order
+    cup_codes VARCHAR(10)[] NOT NULL CHECK (array_length(cup_codes, 1) = 3),
+    -- Serving position (1-3) of the odd cup
+    odd_position INTEGER NOT NULL CHECK (odd_position BETWEEN 1 AND 3),
+    -- Whether the odd cup is the test lot (otherwise the control lot)
+    odd_is_test BOOLEAN NOT NULL,
+    UNIQUE (session_id, set_number)
+);
+
+CREATE TABLE cupping_triangle_answers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES cupping_sessions(id) ON DELETE CASCADE,
+    set_id UUID NOT NULL REFERENCES cupping_triangle_sets(id) ON DELETE CASCADE,
+    taster_name VARCHAR(255) NOT NULL,
+    chosen_code VARCHAR(10) NOT NULL,
+    correct BOOLEAN NOT NULL,
+    comments TEXT,
+    recorded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_cupping_triangle_answers_taster
+    ON cupping_triangle_answers(set_id, LOWER(taster_name));
+CREATE INDEX idx_cupping_triangle_answers_session ON cupping_triangle_answers(session_id);
+
+COMMENT ON COLUMN cupping_sessions.session_type IS 'scoring (SCA scores per sample) or triangle (odd-one-out discrimination test)';
+COMMENT ON COLUMN cupping_triangle_tests.significance_level IS 'Alpha the share of correct answers is tested at';
@@ -0,0 +1,197 @@
+-- Activity Feed Migration
+-- A per-business feed of what happened to its lots: harvests recorded, lots
+-- created and moved between stages, processing started, gradings, cupping
+-- scores and sales. Triggers append to the feed as the records are written,
+-- so the feed is cheap to page through and keeps an entry after the record
+-- it describes is deleted. Each member's last visit is kept so the app can
+-- show what happened since.
+
+CREATE TABLE activity_feed (
+    -- Increasing id, used as the paging cursor
+    id BIGSERIAL PRIMARY KEY,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    activity_type VARCHAR(30) NOT NULL
+        CHECK (activity_type IN ('lot_created', 'lot_stage_changed', 'harvest_recorded',
+                                 'processing_started', 'grading_recorded', 'cupping_scored',
+                                 'sale_recorded')),
+    -- Record the activity is about; not a foreign key so entries outlive it
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id UUID NOT NULL,
+    lot_id UUID REFERENCES lots(id) ON DELETE CASCADE,
+    -- Key figures of the record at the time (weight, grade, score, stages...)
+    details JSONB NOT NULL DEFAULT '{}'::JSONB,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_activity_feed_business ON activity_feed(business_id, id DESC);
+CREATE INDEX idx_activity_feed_lot ON activity_feed(lot_id) WHERE lot_id IS NOT NULL;
+-- A record restored from the recycle bin is inserted again but happened once
+CREATE UNIQUE INDEX idx_activity_feed_record
+    ON activity_feed(entity_type, entity_id)
+    WHERE activity_type <> 'lot_stage_changed';
+
+-- When each member last looked at the feed of a business
+CREATE TABLE activity_feed_reads (
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (business_id, user_id)
+);
+
+-- ============================================================================
+-- FUNCTION: Append an activity for a new or changed record
+-- ============================================================================
+CREATE OR REPLACE FUNCTION record_activity()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_business_id UUID;
+    v_activity_type VARCHAR(30);
+    v_entity_type VARCHAR(50);
+    v_lot_id UUID;
+    v_details JSONB;
+BEGIN
+    IF TG_TABLE_NAME = 'lots' THEN
+        v_business_id := NEW.business_id;
+        v_entity_type := 'lot';
+        v_lot_id := NEW.id;
+        IF TG_OP = 'INSERT' THEN
+            v_activity_type := 'lot_created';
+            v_details := jsonb_build_object('stage', NEW.stage);
+        ELSE
+            v_activity_type := 'lot_stage_changed';
+            v_details := jsonb_build_object('from_stage', OLD.stage, 'to_stage', NEW.stage);
+        END IF;
+    ELSIF TG_TABLE_NAME = 'harvests' THEN
+        v_business_id := NEW.business_id;
+        v_activity_type := 'harvest_recorded';
+        v_entity_type := 'harvest';
+        v_lot_id := NEW.lot_id;
+        v_details := jsonb_build_object(
+            'plot_id', NEW.plot_id,
+            'harvest_date', NEW.harvest_date,
+            'cherry_weight_kg', NEW.cherry_weight_kg
+        );
+    ELSIF TG_TABLE_NAME = 'processing_records' THEN
+        v_activity_type := 'processing_started';
+        v_entity_type := 'processing_record';
+        v_lot_id := NEW.lot_id;
+        v_details := jsonb_build_object('method', NEW.method, 'start_date', NEW.start_date);
+    ELSIF TG_TABLE_NAME = 'green_bean_grades' THEN
+        v_activity_type := 'grading_recorded';
+        v_entity_type := 'green_bean_grade';
+        v_lot_id := NEW.lot_id;
+        v_details := jsonb_build_object('grade', NEW.grade, 'grading_date', NEW.grading_date);
+    ELSIF TG_TABLE_NAME = 'cupping_samples' THEN
+        v_activity_type := 'cupping_scored';
+        v_entity_type := 'cupping_sample';
+        v_lot_id := NEW.lot_id;
+        v_details := jsonb_build_object(
+            'session_id', NEW.session_id,
+            'sample_number', NEW.sample_number,
+            'final_score', NEW.final_score
+        );
+        SELECT business_id INTO v_business_id FROM cupping_sessions WHERE id = NEW.session_id;
+    ELSIF TG_TABLE_NAME = 'inventory_transactions' THEN
+        v_business_id := NEW.business_id;
+        v_activity_type := 'sale_recorded';
+        v_entity_type := 'inventory_transaction';
+        v_lot_id := NEW.lot_id;
+        v_details := jsonb_build_object(
+            'quantity_kg', NEW.quantity_kg,
+            'total_price', NEW.total_price,
+            'currency', NEW.currency,
+            'counterparty_name', NEW.counterparty_name,
+            'transaction_date', NEW.transaction_date
+        );
+    END IF;
+
+    -- Processing records and gradings belong to a business through their lot
+    IF v_business_id IS NULL THEN
+        SELECT business_id INTO v_business_id FROM lots WHERE id = v_lot_id;
+    END IF;
+
+    IF v_business_id IS NOT NULL THEN
+        INSERT INTO activity_feed (business_id, activity_type, entity_type, entity_id, lot_id, details)
+        VALUES (v_business_id, v_activity_type, v_entity_type, NEW.id, v_lot_id, v_details)
+        ON CONFLICT DO NOTHING;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- ============================================================================
+-- TRIGGERS
+-- ============================================================================
+CREATE TRIGGER trg_lots_activity AFTER INSERT ON lots
+    FOR EACH ROW EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_lots_stage_activity AFTER UPDATE OF stage ON lots
+    FOR EACH ROW WHEN (OLD.stage IS DISTINCT FROM NEW.stage)
+    EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_harvests_activity AFTER INSERT ON harvests
+    FOR EACH ROW EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_processing_records_activity AFTER INSERT ON processing_records
+    FOR EACH ROW EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_green_bean_grades_activity AFTER INSERT ON green_bean_grades
+    FOR EACH ROW EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_cupping_samples_activity AFTER INSERT ON cupping_samples
+    FOR EACH ROW EXECUTE FUNCTION record_activity();
+
+CREATE TRIGGER trg_inventory_sales_activity AFTER INSERT ON inventory_transactions
+    FOR EACH ROW WHEN (NEW.transaction_type = 'sale')
+    EXECUTE FUNCTION record_activity();
+
+-- ============================================================================
+-- BACKFILL: existing records, oldest first so ids follow time. Past stage
+-- changes were never recorded and start with this migration.
+-- ============================================================================
+INSERT INTO activity_feed (business_id, activity_type, entity_type, entity_id, lot_id, details, occurred_at)
+SELECT business_id, activity_type, entity_type, entity_id, lot_id, details, occurred_at
+FROM (
+    SELECT l.business_id, 'lot_created' AS activity_type, 'lot' AS entity_type, l.id AS entity_id,
+           l.id AS lot_id, jsonb_build_object('stage', l.stage) AS details, l.created_at AS occurred_at
+    FROM lots l
+    UNION ALL
+    SELECT h.business_id, 'harvest_recorded', 'harvest', h.id, h.lot_id,
+           jsonb_build_object('plot_id', h.plot_id, 'harvest_date', h.harvest_date,
+                              'cherry_weight_kg', h.cherry_weight_kg),
+           h.created_at
+    FROM harvests h
+    UNION ALL
+    SELECT l.business_id, 'processing_started', 'processing_record', p.id, p.lot_id,
+           jsonb_build_object('method', p.method, 'start_date', p.start_date),
+           p.created_at
+    FROM processing_records p
+    JOIN lots l ON l.id = p.lot_id
+    UNION ALL
+    SELECT l.business_id, 'grading_recorded', 'green_bean_grade', g.id, g.lot_id,
+           jsonb_build_object('grade', g.grade, 'grading_date', g.grading_date),
+           g.created_at
+    FROM green_bean_grades g
+    JOIN lots l ON l.id = g.lot_id
+    UNION ALL
+    SELECT cs.business_id, 'cupping_scored', 'cupping_sample', s.id, s.lot_id,
+           jsonb_build_object('session_id', s.session_id, 'sample_number', s.sample_number,
+                              'final_score', s.final_score),
+           s.created_at
+    FROM cupping_samples s
+    JOIN cupping_sessions cs ON cs.id = s.session_id
+    UNION ALL
+    SELECT t.business_id, 'sale_recorded', 'inventory_transaction', t.id, t.lot_id,
+           jsonb_build_object('quantity_kg', t.quantity_kg, 'total_price', t.total_price,
+                              'currency', t.currency, 'counterparty_name', t.counterparty_name,
+                              'transaction_date', t.transaction_date),
+           t.created_at
+    FROM inventory_transactions t
+    WHERE t.transaction_type = 'sale'
+) existing
+ORDER BY occurred_at;
+
+COMMENT ON TABLE activity_feed IS 'Per-business feed of lot activity, appended by triggers';
+COMMENT ON COLUMN activity_feed.details IS 'Key figures of the record when the activity happened';
+COMMENT ON TABLE activity_feed_reads IS 'When each member last viewed the activity feed';
@@ -0,0 +1,14 @@
+-- Reporting Calendar Migration
+-- Crop seasons and fiscal years run twelve months from a month each
+-- business chooses. Seasons keep starting in October and fiscal years in
+-- January unless changed; Thai harvests run from about November to March,
+-- so seasons always span two calendar years and are named like 2024/25.
+
+ALTER TABLE businesses
+    ADD COLUMN season_start_month INTEGER NOT NULL DEFAULT 10
+        CHECK (season_start_month BETWEEN 1 AND 12),
+    ADD COLUMN fiscal_year_start_month INTEGER NOT NULL DEFAULT 1
+        CHECK (fiscal_year_start_month BETWEEN 1 AND 12);
+
+COMMENT ON COLUMN businesses.season_start_month IS 'Month (1-12) crop seasons start in; a season is named after the year it starts in';
+COMMENT ON COLUMN businesses.fiscal_year_start_month IS 'Month (1-12) fiscal years start in';
@@ -0,0 +1,13 @@
+-- Lot Stage Reconciliation Migration
+-- A lot's stage is moved on by the services that complete processing and
+-- roasting. A background job re-derives the stage each lot should have
+-- reached from its records and moves lagging lots on; this column keeps
+-- when each business was last reconciled so the job can spread the work.
+
+ALTER TABLE businesses
+    ADD COLUMN lot_stages_checked_at TIMESTAMPTZ;
+
+CREATE INDEX idx_businesses_lot_stages_checked
+    ON businesses(lot_stages_checked_at NULLS FIRST);
+
+COMMENT ON COLUMN businesses.lot_stages_checked_at IS 'When lot stages were last reconciled with processing, grading and roasting records';
@@ -0,0 +1,19 @@
+-- Cupping Brew Measurements Migration
+-- Refractometer and water activity readings taken with a cupping sample, so
+-- the lab can correlate extraction and sample moisture to scores. The roast
+-- date is that of the coffee cupped, which need not come from a recorded
+-- roast session.
+
+ALTER TABLE cupping_samples
+    ADD COLUMN tds_percent DECIMAL(5, 2)
+        CHECK (tds_percent IS NULL OR (tds_percent > 0 AND tds_percent <= 25)),
+    ADD COLUMN extraction_percent DECIMAL(5, 2)
+        CHECK (extraction_percent IS NULL OR (extraction_percent > 0 AND extraction_percent <= 30)),
+    ADD COLUMN water_activity DECIMAL(4, 3)
+        CHECK (water_activity IS NULL OR (water_activity >= 0 AND water_activity <= 1)),
+    ADD COLUMN roast_date DATE;
+
+COMMENT ON COLUMN cupping_samples.tds_percent IS 'Total dissolved solids of the brew, percent';
+COMMENT ON COLUMN cupping_samples.extraction_percent IS 'Extraction yield of the brew, percent';
+COMMENT ON COLUMN cupping_samples.water_activity IS 'Water activity (aw) of the coffee cupped';
+COMMENT ON COLUMN cupping_samples.roast_date IS 'Roast date of the coffee cupped';
@@ -0,0 +1,23 @@
+-- Cupping Session Sign-off Migration
+-- Sessions move from draft to in_progress once something is recorded and
+-- are finalized when a cupper signs them off. Finalized sessions lock their
+-- samples, panel sheets and triangle answers, and only their scores are
+-- shown on the public traceability page.
+
+ALTER TABLE cupping_sessions
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'draft'
+        CHECK (status IN ('draft', 'in_progress', 'finalized')),
+    ADD COLUMN finalized_at TIMESTAMPTZ,
+    ADD COLUMN finalized_by UUID REFERENCES users(id),
+    ADD COLUMN signed_by VARCHAR(255);
+
+-- Sessions already holding scores or answers await sign-off
+UPDATE cupping_sessions s
+SET status = 'in_progress'
+WHERE EXISTS (SELECT 1 FROM cupping_samples cs WHERE cs.session_id = s.id)
+   OR EXISTS (SELECT 1 FROM cupping_triangle_answers a WHERE a.session_id = s.id);
+
+CREATE INDEX idx_cupping_sessions_status ON cupping_sessions(business_id, status);
+
+COMMENT ON COLUMN cupping_sessions.status IS 'draft, in_progress or finalized';
+COMMENT ON COLUMN cupping_sessions.signed_by IS 'Cupper who signed the session off';
@@ -0,0 +1,16 @@
+-- Green Grading Physical Analysis Migration
+-- Screen analysis (percent retained on screens 19 down to 13), water
+-- activity and bulk density alongside the moisture already recorded with a
+-- grading. The screen analysis also fills screen_size_distribution, whose
+-- coarser bands quality specs check.
+
+ALTER TABLE green_bean_grades
+    ADD COLUMN screen_analysis JSONB,
+    ADD COLUMN water_activity DECIMAL(4, 3)
+        CHECK (water_activity IS NULL OR (water_activity >= 0 AND water_activity <= 1)),
+    ADD COLUMN bulk_density_g_per_l DECIMAL(6, 1)
+        CHECK (bulk_density_g_per_l IS NULL OR bulk_density_g_per_l > 0);
+
+COMMENT ON COLUMN green_bean_grades.screen_analysis IS 'Percent retained on screens 19 to 13: {"screen_19": 5.0, ..., "screen_13": 1.0}';
+COMMENT ON COLUMN green_bean_grades.water_activity IS 'Water activity (aw) of the green sample';
+COMMENT ON COLUMN green_bean_grades.bulk_density_g_per_l IS 'Free-flow bulk density of the green sample, g/L';
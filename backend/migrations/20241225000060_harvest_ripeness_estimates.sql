@@ -0,0 +1,31 @@
+-- Harvest Ripeness Estimates Migration
+-- A photo of a cherry basket is sent to the AI service, which estimates the
+-- underripe, ripe and overripe shares. The estimate pre-fills the ripeness
+-- of the harvest entered next (web form or LINE chatbot); the percentages
+-- recorded on the harvest are what the picker confirmed, and a harvest
+-- whose percentages differ from its estimate is marked as overridden.
+
+CREATE TABLE ripeness_estimates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    source VARCHAR(10) NOT NULL CHECK (source IN ('web', 'line')),
+    ai_request_id VARCHAR(255) NOT NULL,
+    image_url TEXT,
+    cherries_counted INTEGER CHECK (cherries_counted IS NULL OR cherries_counted >= 0),
+    underripe_percent INTEGER NOT NULL CHECK (underripe_percent BETWEEN 0 AND 100),
+    ripe_percent INTEGER NOT NULL CHECK (ripe_percent BETWEEN 0 AND 100),
+    overripe_percent INTEGER NOT NULL CHECK (overripe_percent BETWEEN 0 AND 100),
+    confidence_score REAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT ripeness_estimates_sum_100 CHECK (underripe_percent + ripe_percent + overripe_percent = 100)
+);
+
+CREATE INDEX idx_ripeness_estimates_user ON ripeness_estimates(business_id, user_id, created_at DESC);
+
+ALTER TABLE harvests
+    ADD COLUMN ripeness_estimate_id UUID UNIQUE REFERENCES ripeness_estimates(id) ON DELETE SET NULL,
+    ADD COLUMN ripeness_overridden BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN harvests.ripeness_estimate_id IS 'Photo estimate the ripeness was pre-filled from';
+COMMENT ON COLUMN harvests.ripeness_overridden IS 'Ripeness entered differs from the photo estimate';
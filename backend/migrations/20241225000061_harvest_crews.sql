@@ -0,0 +1,11 @@
+-- Harvest Crews Migration
+-- Pickers are recorded by name on each harvest; a crew name groups them for
+-- the per-picker quality report, which scores pickers or crews over a season
+-- on the ripeness they pick and the defects graded in their lots.
+
+ALTER TABLE harvests
+    ADD COLUMN crew_name VARCHAR(100);
+
+CREATE INDEX idx_harvests_picker ON harvests(business_id, LOWER(picker_name));
+
+COMMENT ON COLUMN harvests.crew_name IS 'Picking crew the picker worked in';
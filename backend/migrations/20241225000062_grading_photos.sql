@@ -0,0 +1,45 @@
+-- Grading Photos Migration
+-- Bean tray photos are attached to a grading record and stored in S3. The
+-- photos can be re-analyzed by the AI defect detection service at any time
+-- (for example after a model update); each run adds a new version of the
+-- photo's analysis, leaving the grading and earlier versions as they were.
+
+CREATE TABLE grading_photos (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    grading_id UUID NOT NULL REFERENCES green_bean_grades(id) ON DELETE CASCADE,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    s3_key TEXT NOT NULL,
+    content_type VARCHAR(50) NOT NULL,
+    file_size_bytes BIGINT NOT NULL CHECK (file_size_bytes > 0),
+    sha256 CHAR(64) NOT NULL,
+    caption TEXT,
+    uploaded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    uploaded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT grading_photos_unique_image UNIQUE (grading_id, sha256)
+);
+
+CREATE INDEX idx_grading_photos_grading ON grading_photos(grading_id, uploaded_at);
+
+CREATE TABLE grading_photo_analyses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    photo_id UUID NOT NULL REFERENCES grading_photos(id) ON DELETE CASCADE,
+    grading_id UUID NOT NULL REFERENCES green_bean_grades(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL CHECK (version > 0),
+    ai_request_id VARCHAR(255) NOT NULL,
+    detected_beans INTEGER NOT NULL,
+    defect_breakdown JSONB NOT NULL,
+    category1_count INTEGER NOT NULL,
+    category2_count INTEGER NOT NULL,
+    grade VARCHAR(50) NOT NULL,
+    suggested_grade VARCHAR(50) NOT NULL,
+    confidence_score REAL NOT NULL,
+    annotated_image_url TEXT,
+    analyzed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT grading_photo_analyses_unique_version UNIQUE (photo_id, version)
+);
+
+CREATE INDEX idx_grading_photo_analyses_grading ON grading_photo_analyses(grading_id, created_at DESC);
+
+COMMENT ON COLUMN grading_photo_analyses.category1_count IS 'Primary full defect equivalents of the detected breakdown';
+COMMENT ON COLUMN grading_photo_analyses.suggested_grade IS 'Grade as suggested by the AI service';
@@ -0,0 +1,104 @@
+-- Knowledge Base Migration
+-- Bilingual SOP articles and short training items. Built-in items (no
+-- business) are shared by every business; a business's admins add their own.
+-- Each item names the role templates it is meant for, and members' reads are
+-- tracked against the item's version so an edited SOP is recommended again.
+
+CREATE TABLE knowledge_articles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- NULL for built-in items shared by every business
+    business_id UUID REFERENCES businesses(id) ON DELETE CASCADE,
+    slug VARCHAR(100) NOT NULL CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('sop', 'training')),
+    category VARCHAR(30) NOT NULL CHECK (category IN (
+        'harvest', 'processing', 'grading', 'cupping', 'roasting', 'storage', 'safety', 'general'
+    )),
+    title VARCHAR(255) NOT NULL,
+    title_th VARCHAR(255) NOT NULL,
+    summary TEXT NOT NULL DEFAULT '',
+    summary_th TEXT NOT NULL DEFAULT '',
+    body TEXT NOT NULL,
+    body_th TEXT NOT NULL,
+    -- Role template keys the item is meant for; empty for everyone
+    audience_roles TEXT[] NOT NULL DEFAULT '{}',
+    reading_minutes INTEGER NOT NULL DEFAULT 1 CHECK (reading_minutes > 0),
+    published BOOLEAN NOT NULL DEFAULT TRUE,
+    version INTEGER NOT NULL DEFAULT 1 CHECK (version > 0),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_knowledge_articles_slug
+    ON knowledge_articles(COALESCE(business_id, '00000000-0000-0000-0000-000000000000'::uuid), slug);
+CREATE INDEX idx_knowledge_articles_business ON knowledge_articles(business_id, category);
+
+CREATE TABLE knowledge_article_reads (
+    article_id UUID NOT NULL REFERENCES knowledge_articles(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- Article version last read
+    version_read INTEGER NOT NULL,
+    first_read_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_read_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (article_id, user_id)
+);
+
+CREATE INDEX idx_knowledge_article_reads_business ON knowledge_article_reads(business_id, article_id);
+
+INSERT INTO knowledge_articles (slug, kind, category, title, title_th, summary, summary_th, body, body_th, audience_roles, reading_minutes) VALUES
+(
+    'selective-picking', 'training', 'harvest',
+    'Selective picking of ripe cherry', 'การเก็บเชอร์รี่สุกแบบคัดเลือก',
+    'Pick only fully ripe cherry and keep underripe below 5% of each basket.',
+    'เก็บเฉพาะเชอร์รี่ที่สุกเต็มที่ และให้มีผลดิบไม่เกิน 5% ต่อตะกร้า',
+    E'1. Pick only cherry that is evenly red (or yellow for yellow varieties) and gives slightly when pressed.\n2. Leave green and yellow-green cherry on the branch for the next pass.\n3. Do not strip branches; damaged nodes will not flower next season.\n4. Keep baskets in the shade and deliver cherry to the mill within 6 hours.\n5. Record the picker, crew and the ripe percentage of every basket.',
+    E'1. เก็บเฉพาะเชอร์รี่ที่แดงทั่วทั้งผล (หรือเหลืองสำหรับสายพันธุ์ผลเหลือง) และนิ่มเล็กน้อยเมื่อกด\n2. ปล่อยผลเขียวและเหลืองอมเขียวไว้บนกิ่งสำหรับรอบถัดไป\n3. ห้ามรูดกิ่ง ข้อที่เสียหายจะไม่ออกดอกในฤดูถัดไป\n4. วางตะกร้าไว้ในที่ร่มและส่งเชอร์รี่ถึงโรงแปรรูปภายใน 6 ชั่วโมง\n5. บันทึกชื่อผู้เก็บ ทีม และเปอร์เซ็นต์ผลสุกของทุกตะกร้า',
+    '{farm_manager}', 2
+),
+(
+    'washed-process-sop', 'sop', 'processing',
+    'Washed process', 'การแปรรูปแบบเปียก (วอช)',
+    'Float, pulp, ferment, wash and dry cherry to 10-12% moisture.',
+    'ลอยน้ำ สีเปลือก หมัก ล้าง และตากให้ความชื้นเหลือ 10-12%',
+    E'1. Float the cherry and remove floaters before pulping.\n2. Pulp the same day as picking and check the pulper for nipped beans.\n3. Ferment in clean tanks for 12-36 hours; check pH every 6 hours and stop at pH 4.0-4.5 or when the parchment feels gritty.\n4. Wash with clean water until the mucilage is gone.\n5. Dry on raised beds, turning every hour, to 10-12% moisture.\n6. Record every step against the lot so the grading can be traced back.',
+    E'1. ลอยน้ำเชอร์รี่และคัดผลลอยออกก่อนสีเปลือก\n2. สีเปลือกภายในวันที่เก็บ และตรวจเครื่องสีว่าไม่มีเมล็ดแตก\n3. หมักในถังที่สะอาด 12-36 ชั่วโมง วัดค่า pH ทุก 6 ชั่วโมง และหยุดหมักเมื่อ pH 4.0-4.5 หรือเมื่อกะลาสากมือ\n4. ล้างด้วยน้ำสะอาดจนเมือกหมด\n5. ตากบนแคร่ยกพื้น พลิกทุกชั่วโมง จนความชื้นเหลือ 10-12%\n6. บันทึกทุกขั้นตอนในล็อตเพื่อให้ตรวจย้อนกลับจากผลการคัดเกรดได้',
+    '{processor,farm_manager}', 3
+),
+(
+    'natural-drying-sop', 'sop', 'processing',
+    'Natural process drying', 'การตากกาแฟแบบแห้ง (เนเชอรัล)',
+    'Dry whole cherry in thin layers on raised beds and never let it rewet.',
+    'ตากเชอร์รี่ทั้งผลเป็นชั้นบางบนแคร่ยกพื้น และห้ามให้เปียกซ้ำ',
+    E'1. Sort out floaters and underripe cherry before drying.\n2. Spread cherry no more than 4 cm deep on raised beds.\n3. Turn every hour for the first 3 days; cover at night and in rain.\n4. Measure moisture daily and stop at 10-12%.\n5. Rest the dried cherry for 2-4 weeks before hulling.',
+    E'1. คัดผลลอยและผลดิบออกก่อนตาก\n2. เกลี่ยเชอร์รี่บนแคร่ยกพื้นให้หนาไม่เกิน 4 ซม.\n3. พลิกทุกชั่วโมงใน 3 วันแรก คลุมในเวลากลางคืนและเมื่อฝนตก\n4. วัดความชื้นทุกวันและหยุดตากเมื่อเหลือ 10-12%\n5. พักเชอร์รี่แห้ง 2-4 สัปดาห์ก่อนสีกะลา',
+    '{processor,farm_manager}', 2
+),
+(
+    'green-bean-grading', 'training', 'grading',
+    'Grading a green bean sample', 'การคัดเกรดตัวอย่างสารกาแฟ',
+    'Count defects in a 350 g sample and convert them to full defects.',
+    'นับข้อบกพร่องในตัวอย่าง 350 กรัม และแปลงเป็นจำนวนข้อบกพร่องเต็ม',
+    E'1. Weigh a 350 g sample and measure its moisture first.\n2. Sort primary defects (full black, full sour, pod, fungus, foreign matter, severe insect damage) from secondary ones.\n3. Count each defect type; several secondary defects make up one full defect.\n4. Specialty grade allows no primary defects and at most 5 full defects.\n5. Photograph the tray so it can be re-analyzed later.',
+    E'1. ชั่งตัวอย่าง 350 กรัม และวัดความชื้นก่อน\n2. แยกข้อบกพร่องหลัก (ดำทั้งเมล็ด เปรี้ยวทั้งเมล็ด ผลแห้ง รา สิ่งแปลกปลอม แมลงเจาะรุนแรง) ออกจากข้อบกพร่องรอง\n3. นับข้อบกพร่องแต่ละชนิด ข้อบกพร่องรองหลายเมล็ดนับเป็นหนึ่งข้อบกพร่องเต็ม\n4. เกรดพิเศษต้องไม่มีข้อบกพร่องหลัก และมีข้อบกพร่องเต็มไม่เกิน 5\n5. ถ่ายรูปถาดตัวอย่างไว้เพื่อวิเคราะห์ซ้ำภายหลัง',
+    '{processor,cupper}', 2
+),
+(
+    'sca-cupping-protocol', 'sop', 'cupping',
+    'SCA cupping protocol', 'ขั้นตอนการคัปปิ้งตามมาตรฐาน SCA',
+    'Roast, grind, brew and score samples the same way every session.',
+    'คั่ว บด ชง และให้คะแนนตัวอย่างด้วยวิธีเดียวกันทุกครั้ง',
+    E'1. Roast samples light to medium-light within 24 hours of cupping and rest them at least 8 hours.\n2. Use 8.25 g of coffee per 150 ml of water at 93 °C; grind just before cupping.\n3. Smell the dry fragrance, pour, and break the crust after 4 minutes.\n4. Taste from about 70 °C down to room temperature, scoring each attribute as the cup cools.\n5. Cup samples blind with their codes, and calibrate with the panel before scoring.',
+    E'1. คั่วตัวอย่างระดับอ่อนถึงกลางอ่อนภายใน 24 ชั่วโมงก่อนคัปปิ้ง และพักอย่างน้อย 8 ชั่วโมง\n2. ใช้กาแฟ 8.25 กรัมต่อน้ำ 150 มล. ที่ 93 °C และบดก่อนคัปปิ้งทันที\n3. ดมกลิ่นผงกาแฟ เทน้ำ และเปิดผิวหน้าหลัง 4 นาที\n4. ชิมตั้งแต่ประมาณ 70 °C จนถึงอุณหภูมิห้อง และให้คะแนนแต่ละด้านขณะกาแฟเย็นลง\n5. ชิมแบบปิดชื่อด้วยรหัสตัวอย่าง และปรับมาตรฐานกับทีมก่อนให้คะแนน',
+    '{cupper,roaster}', 3
+),
+(
+    'green-coffee-storage', 'training', 'storage',
+    'Storing green coffee', 'การเก็บรักษาสารกาแฟ',
+    'Keep green coffee cool, dry and off the floor.',
+    'เก็บสารกาแฟในที่เย็น แห้ง และไม่วางกับพื้น',
+    E'1. Store bags on pallets away from walls, below 25 °C and 60% relative humidity.\n2. Use hermetic liners for specialty lots.\n3. Keep coffee away from fuel, fertilizer and spices.\n4. Check moisture monthly; above 12.5% risks mould.',
+    E'1. วางกระสอบบนพาเลทห่างจากผนัง อุณหภูมิต่ำกว่า 25 °C และความชื้นสัมพัทธ์ต่ำกว่า 60%\n2. ใช้ถุงกันอากาศสำหรับล็อตเกรดพิเศษ\n3. เก็บห่างจากน้ำมันเชื้อเพลิง ปุ๋ย และเครื่องเทศ\n4. ตรวจความชื้นทุกเดือน หากเกิน 12.5% เสี่ยงต่อการขึ้นรา',
+    '{}', 1
+);
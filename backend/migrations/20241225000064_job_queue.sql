@@ -0,0 +1,28 @@
+-- Job Queue Migration
+-- Work handed to background workers instead of being done while a request
+-- waits, starting with AI defect detection of grading tray photos. A worker
+-- claims due jobs, and a job that fails is queued again after a growing
+-- delay until it runs out of attempts.
+
+CREATE TABLE jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    job_type VARCHAR(50) NOT NULL CHECK (job_type IN ('ai_grading')),
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+    -- Input of the job; large inputs such as images are cleared once it ends
+    payload JSONB NOT NULL,
+    result JSONB,
+    attempts INTEGER NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+    max_attempts INTEGER NOT NULL DEFAULT 5 CHECK (max_attempts > 0),
+    run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_jobs_due ON jobs(job_type, run_after) WHERE status IN ('queued', 'running');
+CREATE INDEX idx_jobs_business ON jobs(business_id, created_at DESC);
@@ -0,0 +1,61 @@
+-- SOP Checklists Migration
+-- Standard operating procedures turned into checklists workers tick off in
+-- the app or through the LINE chatbot (for example the daily drying bed
+-- turning routine). Each run of a checklist is an execution, optionally for
+-- a lot; every tick is kept with who made it and when, as the compliance
+-- record of the SOP.
+
+CREATE TABLE sop_checklists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    -- SOP the checklist was made from
+    article_id UUID REFERENCES knowledge_articles(id) ON DELETE SET NULL,
+    name VARCHAR(200) NOT NULL,
+    name_th VARCHAR(200) NOT NULL,
+    frequency VARCHAR(20) NOT NULL DEFAULT 'as_needed'
+        CHECK (frequency IN ('daily', 'per_lot', 'as_needed')),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sop_checklists_business ON sop_checklists(business_id, active);
+
+CREATE TABLE sop_checklist_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    checklist_id UUID NOT NULL REFERENCES sop_checklists(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0),
+    text TEXT NOT NULL,
+    text_th TEXT NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT TRUE,
+    CONSTRAINT sop_checklist_items_unique_position UNIQUE (checklist_id, position)
+);
+
+CREATE TABLE sop_executions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    checklist_id UUID NOT NULL REFERENCES sop_checklists(id) ON DELETE CASCADE,
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID REFERENCES lots(id) ON DELETE SET NULL,
+    execution_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    started_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    source VARCHAR(20) NOT NULL DEFAULT 'app' CHECK (source IN ('app', 'line')),
+    notes TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set when every required item is ticked
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_sop_executions_checklist ON sop_executions(checklist_id, execution_date DESC);
+CREATE INDEX idx_sop_executions_lot ON sop_executions(lot_id) WHERE lot_id IS NOT NULL;
+CREATE INDEX idx_sop_executions_open ON sop_executions(business_id, started_by) WHERE completed_at IS NULL;
+
+CREATE TABLE sop_execution_checks (
+    execution_id UUID NOT NULL REFERENCES sop_executions(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES sop_checklist_items(id) ON DELETE CASCADE,
+    checked_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    source VARCHAR(20) NOT NULL DEFAULT 'app' CHECK (source IN ('app', 'line')),
+    note TEXT,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (execution_id, item_id)
+);
@@ -0,0 +1,36 @@
+-- Lot Transfers Migration
+-- Sales of a lot between two businesses on the platform, such as a farm
+-- selling green bean to a roaster. The seller offers the lot to the buyer's
+-- business; once the buyer accepts, the coffee becomes a new lot of the
+-- buyer that keeps a link to the seller's lot and its traceability record,
+-- and the sale and purchase are recorded in both inventories.
+
+CREATE TABLE lot_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    buyer_business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    quantity_kg DECIMAL(12, 2) NOT NULL CHECK (quantity_kg > 0),
+    -- Stage of the coffee when offered; the buyer's lot starts at it
+    stage VARCHAR(50) NOT NULL,
+    unit_price DECIMAL(12, 2),
+    currency VARCHAR(3) NOT NULL DEFAULT 'THB',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined', 'cancelled')),
+    notes TEXT,
+    response_notes TEXT,
+    -- Lot created in the buyer's business on acceptance
+    buyer_lot_id UUID REFERENCES lots(id) ON DELETE SET NULL,
+    initiated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    responded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    responded_at TIMESTAMPTZ,
+    CONSTRAINT lot_transfers_distinct_businesses CHECK (seller_business_id <> buyer_business_id)
+);
+
+CREATE INDEX idx_lot_transfers_seller ON lot_transfers(seller_business_id, created_at DESC);
+CREATE INDEX idx_lot_transfers_buyer ON lot_transfers(buyer_business_id, created_at DESC);
+CREATE INDEX idx_lot_transfers_lot ON lot_transfers(lot_id);
+
+-- Lot of another business a lot was bought from through a transfer
+ALTER TABLE lots ADD COLUMN transferred_from_lot_id UUID REFERENCES lots(id) ON DELETE SET NULL;
@@ -0,0 +1,31 @@
+-- Grading Standards Migration
+-- Buyers grade green coffee against different standards (SCA, Thai FDA,
+-- EU buyers' specifications). A business keeps its own grading standards,
+-- each with the defect and moisture limits of every grade, and picks one
+-- when recording a grading; without one the business default applies, or
+-- the SCA rules when the business has none.
+
+CREATE TABLE grading_standards (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    name_th VARCHAR(100) NOT NULL,
+    -- Market or buyer the standard is used for, such as 'EU'
+    market VARCHAR(50),
+    -- Array of {grade, max_total_defects, max_category1_defects,
+    -- min_moisture_percent, max_moisture_percent}, best grade first
+    thresholds JSONB NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT grading_standards_unique_name UNIQUE (business_id, name)
+);
+
+CREATE INDEX idx_grading_standards_business ON grading_standards(business_id, active);
+CREATE UNIQUE INDEX idx_grading_standards_default ON grading_standards(business_id) WHERE is_default;
+
+-- Standard the grade was classified under; NULL for the SCA rules
+ALTER TABLE green_bean_grades
+    ADD COLUMN grading_standard_id UUID REFERENCES grading_standards(id) ON DELETE SET NULL;
@@ -0,0 +1,31 @@
+-- Moisture Readings Migration
+-- Moisture meter readings taken on a lot over time, from drying cherry and
+-- parchment to green beans in storage. Each reading keeps the stage the
+-- coffee was in and the device used, so a lot's drying and storage can be
+-- charted; green beans above 12.5% are flagged.
+
+CREATE TABLE moisture_readings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    measured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    stage VARCHAR(20) NOT NULL
+        CHECK (stage IN ('cherry', 'parchment', 'green_bean', 'roasted_bean')),
+    moisture_percent DECIMAL(5,2) NOT NULL CHECK (moisture_percent >= 0 AND moisture_percent <= 100),
+    device VARCHAR(100),
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_moisture_readings_lot ON moisture_readings(lot_id, measured_at);
+CREATE INDEX idx_moisture_readings_business ON moisture_readings(business_id, measured_at DESC);
+
+COMMENT ON COLUMN moisture_readings.device IS 'Free text, e.g. the moisture meter model or its serial number';
+
+-- Readings can be deleted to the recycle bin
+ALTER TABLE deleted_records DROP CONSTRAINT deleted_records_entity_check;
+ALTER TABLE deleted_records
+    ADD CONSTRAINT deleted_records_entity_check
+        CHECK (entity IN ('plot', 'harvest', 'farm_activity', 'certification', 'lab_result',
+                          'shipment', 'insurance_policy', 'water_quality', 'moisture_reading'));
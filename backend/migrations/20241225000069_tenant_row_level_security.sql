@@ -0,0 +1,90 @@
+-- Tenant Row-Level Security Migration
+-- Every query already filters on business_id; these policies make the
+-- database enforce it too, so a query missing the filter cannot read or
+-- change another business's rows. The server sets `app.business_id` on each
+-- connection it takes from the pool for an authenticated request. Without
+-- the setting (public routes, background jobs, migrations, and the few
+-- features that read across businesses on purpose) all rows stay visible.
+--
+-- Superusers and roles with BYPASSRLS skip the policies, so the server must
+-- connect as an ordinary role for them to apply.
+
+-- Business of the current request, NULL outside one
+CREATE OR REPLACE FUNCTION app_business_id()
+RETURNS UUID AS $$
+    SELECT NULLIF(current_setting('app.business_id', true), '')::UUID
+$$ LANGUAGE sql STABLE;
+
+-- Isolate a table by its business_id column. Rows with a NULL business_id
+-- are shared by every business (such as built-in content) and stay
+-- readable, but a request can only write rows of its own business.
+CREATE OR REPLACE FUNCTION enable_tenant_isolation(p_table REGCLASS)
+RETURNS VOID AS $$
+DECLARE
+    v_shared BOOLEAN;
+BEGIN
+    SELECT NOT attnotnull INTO v_shared
+    FROM pg_attribute
+    WHERE attrelid = p_table AND attname = 'business_id' AND NOT attisdropped;
+    IF v_shared IS NULL THEN
+        RAISE EXCEPTION '% has no business_id column', p_table;
+    END IF;
+
+    EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', p_table);
+    EXECUTE format('ALTER TABLE %s FORCE ROW LEVEL SECURITY', p_table);
+    EXECUTE format(
+        'CREATE POLICY tenant_isolation ON %s
+            USING (app_business_id() IS NULL OR business_id = app_business_id()%s)
+            WITH CHECK (app_business_id() IS NULL OR business_id = app_business_id())',
+        p_table,
+        CASE WHEN v_shared THEN ' OR business_id IS NULL' ELSE '' END
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Isolate a table without a business_id through the parent row it belongs
+-- to; the parent's own policy decides whether that row is visible
+CREATE OR REPLACE FUNCTION enable_tenant_isolation_via(p_table REGCLASS, p_column TEXT, p_parent REGCLASS)
+RETURNS VOID AS $$
+DECLARE
+    v_parent_visible TEXT := format(
+        'EXISTS (SELECT 1 FROM %s parent WHERE parent.id = %s.%I)', p_parent, p_table, p_column
+    );
+BEGIN
+    EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', p_table);
+    EXECUTE format('ALTER TABLE %s FORCE ROW LEVEL SECURITY', p_table);
+    EXECUTE format(
+        'CREATE POLICY tenant_isolation ON %s
+            USING (app_business_id() IS NULL OR %s)
+            WITH CHECK (app_business_id() IS NULL OR %s)',
+        p_table, v_parent_visible, v_parent_visible
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Every existing table with a business_id; tables added later call
+-- enable_tenant_isolation in their own migration
+DO $$
+DECLARE
+    v_table REGCLASS;
+BEGIN
+    FOR v_table IN
+        SELECT c.oid::REGCLASS
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attname = 'business_id' AND NOT a.attisdropped
+        WHERE n.nspname = 'public' AND c.relkind = 'r'
+    LOOP
+        PERFORM enable_tenant_isolation(v_table);
+    END LOOP;
+END $$;
+
+-- Records of lots, plots, sessions, certifications and shipments
+SELECT enable_tenant_isolation_via('processing_records', 'lot_id', 'lots');
+SELECT enable_tenant_isolation_via('green_bean_grades', 'lot_id', 'lots');
+SELECT enable_tenant_isolation_via('lot_sources', 'lot_id', 'lots');
+SELECT enable_tenant_isolation_via('plot_varieties', 'plot_id', 'plots');
+SELECT enable_tenant_isolation_via('cupping_samples', 'session_id', 'cupping_sessions');
+SELECT enable_tenant_isolation_via('roast_temperature_readings', 'session_id', 'roast_sessions');
+SELECT enable_tenant_isolation_via('certification_documents', 'certification_id', 'certifications');
+SELECT enable_tenant_isolation_via('shipment_items', 'shipment_id', 'shipments');
@@ -0,0 +1,35 @@
+-- Weather Stations Migration
+-- Farms with their own stations (Davis, Ecowitt or others) push readings to
+-- a webhook holding the station's token. Each station belongs to a plot and
+-- maps fields of its payload, with their units, onto weather snapshot
+-- fields; every accepted payload becomes a snapshot tied to the plot.
+
+CREATE TABLE weather_stations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    plot_id UUID NOT NULL REFERENCES plots(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- Template the mapping started from: davis, ecowitt or custom
+    vendor VARCHAR(20) NOT NULL CHECK (vendor IN ('davis', 'ecowitt', 'custom')),
+    -- Array of {field, path, unit}; path is dot-separated into the payload
+    field_mapping JSONB NOT NULL,
+    webhook_token VARCHAR(64) NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_received_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT weather_stations_unique_name UNIQUE (business_id, name)
+);
+
+CREATE INDEX idx_weather_stations_plot ON weather_stations(plot_id);
+
+SELECT enable_tenant_isolation('weather_stations');
+
+-- Snapshots received from a station, tied to its plot
+ALTER TABLE weather_snapshots
+    ADD COLUMN plot_id UUID REFERENCES plots(id) ON DELETE SET NULL,
+    ADD COLUMN weather_station_id UUID REFERENCES weather_stations(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_weather_snapshots_plot ON weather_snapshots(plot_id, recorded_at DESC)
+    WHERE plot_id IS NOT NULL;
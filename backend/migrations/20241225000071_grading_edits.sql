@@ -0,0 +1,26 @@
+-- Grading Edits Migration
+-- Gradings can be corrected and deleted. Every correction or delete records
+-- the fields it changed with their values before and after, who made it and
+-- why, so a mistyped defect count can be fixed without losing what was
+-- first recorded. The history stays when the grading is deleted.
+
+CREATE TABLE grading_edits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    -- No foreign key: the history outlives a deleted grading
+    grading_id UUID NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('update', 'delete')),
+    changes JSONB NOT NULL,
+    reason TEXT,
+    edited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    edited_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_grading_edits_grading ON grading_edits(grading_id, edited_at DESC);
+CREATE INDEX idx_grading_edits_business ON grading_edits(business_id, edited_at DESC);
+
+SELECT enable_tenant_isolation('grading_edits');
+
+COMMENT ON TABLE grading_edits IS 'Edit history of green bean gradings';
+COMMENT ON COLUMN grading_edits.changes IS 'Array of {field, from, to}; to is null for a delete';
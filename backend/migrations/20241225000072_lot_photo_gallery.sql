@@ -0,0 +1,24 @@
+-- Lot Photo Gallery Migration
+-- Photos can be attached to a lot as well as to its plots, harvests and
+-- records. A lot's photos form its gallery: they are kept in the order the
+-- business sets, each is public or internal, and one public photo may be
+-- the cover. The public traceability page shows only the public photos,
+-- cover first, so defect and working photos stay internal.
+
+ALTER TABLE media DROP CONSTRAINT valid_entity_type;
+ALTER TABLE media ADD CONSTRAINT valid_entity_type
+    CHECK (entity_type IN ('plot', 'harvest', 'processing', 'grading', 'certification', 'lot'));
+
+ALTER TABLE media
+    ADD COLUMN visibility VARCHAR(10) NOT NULL DEFAULT 'internal' CHECK (visibility IN ('public', 'internal')),
+    ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN is_cover BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN sha256 VARCHAR(64),
+    ADD COLUMN uploaded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD CONSTRAINT media_cover_is_public CHECK (NOT is_cover OR visibility = 'public');
+
+-- One cover per lot (or other entity)
+CREATE UNIQUE INDEX idx_media_cover ON media(entity_type, entity_id) WHERE is_cover;
+
+COMMENT ON COLUMN media.visibility IS 'public photos appear on the traceability page; internal ones only in the app';
+COMMENT ON COLUMN media.sort_order IS 'Position in the gallery of the entity, lowest first';
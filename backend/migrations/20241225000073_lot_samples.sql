@@ -0,0 +1,33 @@
+-- Lot Samples Migration
+-- Physical samples drawn from a lot for grading, cupping or a buyer. Each
+-- sample records how much was drawn, why and for whom, and moves through
+-- pending grading, graded, cupped and sent to buyer. Drawing a sample takes
+-- its weight off the lot with a 'sample' inventory transaction.
+
+CREATE TABLE lot_samples (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    business_id UUID NOT NULL REFERENCES businesses(id) ON DELETE CASCADE,
+    lot_id UUID NOT NULL REFERENCES lots(id) ON DELETE CASCADE,
+    weight_grams INTEGER NOT NULL CHECK (weight_grams > 0),
+    purpose VARCHAR(20) NOT NULL CHECK (purpose IN ('grading', 'cupping', 'buyer', 'other')),
+    recipient VARCHAR(255),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending_grading'
+        CHECK (status IN ('pending_grading', 'graded', 'cupped', 'sent_to_buyer')),
+    inventory_transaction_id UUID REFERENCES inventory_transactions(id) ON DELETE SET NULL,
+    grading_id UUID REFERENCES green_bean_grades(id) ON DELETE SET NULL,
+    cupping_sample_id UUID REFERENCES cupping_samples(id) ON DELETE SET NULL,
+    notes TEXT,
+    drawn_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_lot_samples_lot ON lot_samples(lot_id, drawn_at DESC);
+CREATE INDEX idx_lot_samples_status ON lot_samples(business_id, status, drawn_at DESC);
+
+SELECT enable_tenant_isolation('lot_samples');
+
+COMMENT ON TABLE lot_samples IS 'Physical samples drawn from lots for the lab and buyers';
+COMMENT ON COLUMN lot_samples.inventory_transaction_id IS 'The sample inventory transaction that took the weight off the lot';
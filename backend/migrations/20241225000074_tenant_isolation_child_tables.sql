@@ -0,0 +1,40 @@
+-- Tenant Isolation of Child Tables Migration
+-- Records without a business_id of their own that belong to a business
+-- through their parent row: certification compliance and alerts, shipment
+-- milestones and logger data, panel and triangle cupping records, sample
+-- edit history, grading photo analyses, SOP checklist items and checks,
+-- and role permissions. Each follows its parent's tenant_isolation policy,
+-- so a query missing the business filter cannot reach another business's
+-- rows through them either.
+--
+-- Tables still without a policy are shared catalogs (permissions, weight
+-- units, certification requirements, role templates), account and sign-in
+-- records read before a business is known, and records spanning businesses
+-- on purpose (businesses, lot transfers, research partners).
+
+-- Certifications
+SELECT enable_tenant_isolation_via('certification_compliance', 'certification_id', 'certifications');
+SELECT enable_tenant_isolation_via('certification_alerts', 'certification_id', 'certifications');
+SELECT enable_tenant_isolation_via('certification_compliance_issues', 'certification_id', 'certifications');
+
+-- Shipments
+SELECT enable_tenant_isolation_via('shipment_milestones', 'shipment_id', 'shipments');
+SELECT enable_tenant_isolation_via('shipment_logger_imports', 'shipment_id', 'shipments');
+SELECT enable_tenant_isolation_via('shipment_logger_readings', 'import_id', 'shipment_logger_imports');
+
+-- Cupping
+SELECT enable_tenant_isolation_via('cupping_cupper_scores', 'sample_id', 'cupping_samples');
+SELECT enable_tenant_isolation_via('cupping_sample_edits', 'session_id', 'cupping_sessions');
+SELECT enable_tenant_isolation_via('cupping_triangle_tests', 'session_id', 'cupping_sessions');
+SELECT enable_tenant_isolation_via('cupping_triangle_sets', 'session_id', 'cupping_sessions');
+SELECT enable_tenant_isolation_via('cupping_triangle_answers', 'session_id', 'cupping_sessions');
+
+-- Grading
+SELECT enable_tenant_isolation_via('grading_photo_analyses', 'photo_id', 'grading_photos');
+
+-- SOP checklists
+SELECT enable_tenant_isolation_via('sop_checklist_items', 'checklist_id', 'sop_checklists');
+SELECT enable_tenant_isolation_via('sop_execution_checks', 'execution_id', 'sop_executions');
+
+-- Roles
+SELECT enable_tenant_isolation_via('role_permissions', 'role_id', 'roles');
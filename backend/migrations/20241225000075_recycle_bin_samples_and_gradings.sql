@@ -0,0 +1,10 @@
+-- Recycle Bin Samples and Gradings Migration
+-- Cupping samples and gradings can be deleted to the recycle bin. Lot
+-- photos are not: their file is removed from storage with them.
+
+ALTER TABLE deleted_records DROP CONSTRAINT deleted_records_entity_check;
+ALTER TABLE deleted_records
+    ADD CONSTRAINT deleted_records_entity_check
+        CHECK (entity IN ('plot', 'harvest', 'farm_activity', 'certification', 'lab_result',
+                          'shipment', 'insurance_policy', 'water_quality', 'moisture_reading',
+                          'cupping_sample', 'grading'));
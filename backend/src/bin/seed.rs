@@ -0,0 +1,818 @@
+//! Demo data generator for the Coffee Quality Management Platform
+//!
+//! Generates realistic, deterministic demo data for local development and
+//! demos: businesses with users, plots and varieties, seasons of harvests,
+//! processing, grading, roasting, cupping and inventory movements.
+//!
+//! Usage:
+//! ```text
+//! cargo run --bin seed -- [--scale small|demo|large] [--businesses N] [--plots N]
+//!                         [--seasons N] [--year YYYY] [--seed N] [--reset]
+//! ```
+//!
+//! The database is taken from `CQM__DATABASE__URL` (or `DATABASE_URL`), and
+//! the same seed and year always produce the same data. Demo businesses use
+//! the codes `DEMO1`, `DEMO2`, ... and every user's password is `demo1234`.
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use shared::{
+    calculate_dtr, calculate_processing_yield, calculate_weight_loss, classify_grade,
+    CuppingScores, DefectCount, GradeClassification,
+};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Password for every generated user
+const DEMO_PASSWORD: &str = "demo1234";
+
+/// Harvest passes per plot each season
+const PICKS_PER_PLOT: u32 = 3;
+
+const BUSINESS_NAMES: &[(&str, &str, f64, f64)] = &[
+    ("Doi Chang Highland Coffee", "Chiang Rai", 20.05, 99.63),
+    ("Mae Wang Valley Estate", "Chiang Mai", 18.62, 98.72),
+    ("Pang Khon Cooperative", "Chiang Rai", 19.93, 99.71),
+    ("Doi Pha Hom Pok Farm", "Chiang Mai", 20.06, 99.15),
+    ("Bo Kluea Coffee Garden", "Nan", 19.15, 101.15),
+    ("Doi Tung Hillside", "Chiang Rai", 20.33, 99.83),
+];
+
+const PLOT_NAMES: &[&str] = &[
+    "Upper Ridge", "Spring Terrace", "Bamboo Slope", "Old Mango", "North Face",
+    "Pine Shade", "River Bend", "Tea Border", "Misty Hollow", "Stone Wall",
+    "Sunrise Block", "Plum Orchard",
+];
+
+const VARIETIES: &[(&str, &str)] = &[
+    ("Typica", "ทิปิก้า"),
+    ("Catimor", "คาติมอร์"),
+    ("Chiangmai 80", "เชียงใหม่ 80"),
+    ("Caturra", "คาทูร่า"),
+    ("SL28", "เอสแอล 28"),
+    ("Geisha", "เกอิชา"),
+];
+
+const PICKERS: &[&str] = &["Ai", "Mee", "Nok", "Jaew", "Somchai", "Ploy", "Aju", "Naw"];
+
+const TASTING_NOTES: &[(&str, &str)] = &[
+    ("Brown sugar, orange, milk chocolate", "น้ำตาลทรายแดง ส้ม ช็อกโกแลตนม"),
+    ("Jasmine, lemon, black tea", "มะลิ เลมอน ชาดำ"),
+    ("Red apple, caramel, almond", "แอปเปิ้ลแดง คาราเมล อัลมอนด์"),
+    ("Stone fruit, honey, cocoa", "ผลไม้เมล็ดแข็ง น้ำผึ้ง โกโก้"),
+    ("Nutty, dark chocolate, low acidity", "ถั่ว ดาร์กช็อกโกแลต เปรี้ยวน้อย"),
+    ("Berry, hibiscus, winey", "เบอร์รี่ กระเจี๊ยบ กลิ่นไวน์"),
+];
+
+const BUYERS: &[&str] = &["Bangkok Roasters Co.", "Nimman Cafe", "Phuket Beach Coffee", "Osaka Green Imports"];
+
+/// Deterministic pseudo-random generator (SplitMix64)
+struct SeedRng(u64);
+
+impl SeedRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in [low, high]
+    fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % (high - low + 1) as u64) as i64
+    }
+
+    /// Decimal in [low, high] with the given number of decimal places
+    fn decimal(&mut self, low: i64, high: i64, scale: u32) -> Decimal {
+        let factor = 10i64.pow(scale);
+        Decimal::new(self.range(low * factor, high * factor), scale)
+    }
+
+    fn chance(&mut self, percent: i64) -> bool {
+        self.range(1, 100) <= percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as i64 - 1) as usize]
+    }
+}
+
+/// Generator settings
+struct SeedOptions {
+    businesses: u32,
+    plots: u32,
+    seasons: u32,
+    year: i32,
+    seed: u64,
+    reset: bool,
+}
+
+impl SeedOptions {
+    fn from_args() -> Result<Self, String> {
+        let today = Utc::now().date_naive();
+        // Most recent completed harvest season (Nov - Feb) ends in this year
+        let latest_season = if today.month() >= 3 { today.year() } else { today.year() - 1 };
+
+        let mut options = Self::scale("demo", latest_season)?;
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value", name));
+            match arg.as_str() {
+                "--scale" => {
+                    let scale = value("--scale")?;
+                    options = Self {
+                        seed: options.seed,
+                        reset: options.reset,
+                        ..Self::scale(&scale, options.year)?
+                    };
+                }
+                "--businesses" => options.businesses = parse(&value("--businesses")?, "--businesses")?,
+                "--plots" => options.plots = parse(&value("--plots")?, "--plots")?,
+                "--seasons" => options.seasons = parse(&value("--seasons")?, "--seasons")?,
+                "--year" => options.year = parse(&value("--year")?, "--year")?,
+                "--seed" => options.seed = parse(&value("--seed")?, "--seed")?,
+                "--reset" => options.reset = true,
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                other => return Err(format!("Unknown argument: {}\n\n{}", other, USAGE)),
+            }
+        }
+
+        if options.businesses == 0 || options.businesses as usize > BUSINESS_NAMES.len() {
+            return Err(format!("--businesses must be between 1 and {}", BUSINESS_NAMES.len()));
+        }
+        if options.plots == 0 || options.plots as usize > PLOT_NAMES.len() {
+            return Err(format!("--plots must be between 1 and {}", PLOT_NAMES.len()));
+        }
+        if options.seasons == 0 {
+            return Err("--seasons must be at least 1".to_string());
+        }
+
+        Ok(options)
+    }
+
+    fn scale(name: &str, year: i32) -> Result<Self, String> {
+        let (businesses, plots, seasons) = match name {
+            "small" => (1, 3, 1),
+            "demo" => (2, 5, 2),
+            "large" => (6, 12, 4),
+            other => return Err(format!("Unknown scale '{}', expected small, demo or large", other)),
+        };
+        Ok(Self { businesses, plots, seasons, year, seed: 42, reset: false })
+    }
+}
+
+const USAGE: &str = "Usage: seed [--scale small|demo|large] [--businesses N] [--plots N] \
+[--seasons N] [--year YYYY] [--seed N] [--reset]";
+
+fn parse<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+/// Running totals for the summary
+#[derive(Default)]
+struct SeedSummary {
+    users: u32,
+    plots: u32,
+    lots: u32,
+    harvests: u32,
+    processing: u32,
+    gradings: u32,
+    roasts: u32,
+    cupping_sessions: u32,
+    cupping_samples: u32,
+    transactions: u32,
+}
+
+/// A lot created during a season, with data needed for cupping
+struct SeededLot {
+    id: Uuid,
+    quality: i64,
+    roast_session_id: Option<Uuid>,
+    green_date: NaiveDate,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let options = match SeedOptions::from_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let database_url = std::env::var("CQM__DATABASE__URL")
+        .or_else(|_| std::env::var("DATABASE_URL"))
+        .map_err(|_| anyhow::anyhow!("Set CQM__DATABASE__URL or DATABASE_URL"))?;
+
+    let pool = PgPoolOptions::new().max_connections(2).connect(&database_url).await?;
+    sqlx::migrate!("./migrations").run(&pool).await?;
+
+    let codes: Vec<String> = (1..=options.businesses).map(|i| format!("DEMO{}", i)).collect();
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM businesses WHERE business_code LIKE 'DEMO%'",
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    if existing > 0 {
+        if !options.reset {
+            anyhow::bail!("Demo businesses already exist, rerun with --reset to replace them");
+        }
+        reset_demo_data(&pool).await?;
+    }
+
+    let password_hash = bcrypt::hash(DEMO_PASSWORD, bcrypt::DEFAULT_COST)?;
+    let mut rng = SeedRng(options.seed);
+    let mut summary = SeedSummary::default();
+
+    for (index, code) in codes.iter().enumerate() {
+        let mut tx = pool.begin().await?;
+        seed_business(&mut tx, &mut rng, &options, index, code, &password_hash, &mut summary).await?;
+        tx.commit().await?;
+        println!("Seeded business {} ({})", code, BUSINESS_NAMES[index].0);
+    }
+
+    println!();
+    println!("Users:              {} (password: {})", summary.users, DEMO_PASSWORD);
+    println!("Plots:              {}", summary.plots);
+    println!("Lots:               {}", summary.lots);
+    println!("Harvests:           {}", summary.harvests);
+    println!("Processing records: {}", summary.processing);
+    println!("Gradings:           {}", summary.gradings);
+    println!("Roast sessions:     {}", summary.roasts);
+    println!("Cupping sessions:   {} ({} samples)", summary.cupping_sessions, summary.cupping_samples);
+    println!("Inventory txns:     {}", summary.transactions);
+    println!();
+    println!("Log in as owner@demo1.example.com / {}", DEMO_PASSWORD);
+
+    Ok(())
+}
+
+/// Remove previously generated demo businesses
+async fn reset_demo_data(pool: &PgPool) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+
+    // Lots first: harvests restrict plot deletion
+    sqlx::query(
+        "DELETE FROM lots WHERE business_id IN (SELECT id FROM businesses WHERE business_code LIKE 'DEMO%')",
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM businesses WHERE business_code LIKE 'DEMO%'")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    println!("Removed existing demo businesses");
+    Ok(())
+}
+
+async fn seed_business(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut SeedRng,
+    options: &SeedOptions,
+    index: usize,
+    code: &str,
+    password_hash: &str,
+    summary: &mut SeedSummary,
+) -> anyhow::Result<()> {
+    let (name, province, latitude, longitude) = BUSINESS_NAMES[index];
+
+    // Roles are created from the role templates by the create_default_roles trigger
+    let business_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO businesses (name, business_type, business_code, phone, email, province, latitude, longitude, preferred_language)
+        VALUES ($1, 'multi', $2, $3, $4, $5, $6, $7, 'th')
+        RETURNING id
+        "#,
+    )
+    .bind(name)
+    .bind(code)
+    .bind(format!("08{:08}", rng.range(10_000_000, 99_999_999)))
+    .bind(format!("contact@{}.example.com", code.to_lowercase()))
+    .bind(province)
+    .bind(Decimal::try_from(latitude)?)
+    .bind(Decimal::try_from(longitude)?)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let mut owner_id = None;
+    for (login, role, person) in [
+        ("owner", "owner", "Owner"),
+        ("manager", "farm_manager", "Manager"),
+        ("worker", "processor", "Worker"),
+    ] {
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO users (business_id, role_id, email, password_hash, name, email_verified)
+            SELECT $1, id, $2, $3, $4, true FROM roles WHERE business_id = $1 AND name = $5
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(format!("{}@{}.example.com", login, code.to_lowercase()))
+        .bind(password_hash)
+        .bind(format!("{} {}", code, person))
+        .bind(role)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        sqlx::query("INSERT INTO notification_preferences (user_id) VALUES ($1)")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        owner_id.get_or_insert(user_id);
+        summary.users += 1;
+    }
+    let owner_id = owner_id.expect("owner user created");
+
+    // Plots with varieties
+    let mut plots = Vec::new();
+    for plot_name in PLOT_NAMES.iter().take(options.plots as usize) {
+        let altitude = rng.range(900, 1600) as i32;
+        let plot_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO plots (business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(plot_name)
+        .bind(Decimal::try_from(latitude)? + rng.decimal(-20, 20, 3) / Decimal::from(1000))
+        .bind(Decimal::try_from(longitude)? + rng.decimal(-20, 20, 3) / Decimal::from(1000))
+        .bind(rng.decimal(2, 15, 1))
+        .bind(altitude)
+        .bind(rng.range(20, 80) as i32)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let variety_count = rng.range(1, 2) as usize;
+        let first = rng.range(0, VARIETIES.len() as i64 - 1) as usize;
+        for offset in 0..variety_count {
+            let (variety, variety_th) = VARIETIES[(first + offset) % VARIETIES.len()];
+            sqlx::query(
+                r#"
+                INSERT INTO plot_varieties (plot_id, variety, variety_th, planting_date, tree_count)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(plot_id)
+            .bind(variety)
+            .bind(variety_th)
+            .bind(NaiveDate::from_ymd_opt(options.year - rng.range(5, 15) as i32, 6, 1))
+            .bind(rng.range(300, 2500) as i32)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Higher plots tend to cup better
+        plots.push((plot_id, (altitude as i64 - 900) / 140));
+        summary.plots += 1;
+    }
+
+    let roast_template_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO roast_profile_templates (
+            business_id, name, name_th, target_first_crack_time_seconds, target_first_crack_temp_celsius,
+            target_development_time_seconds, target_end_temp_celsius, target_total_time_seconds,
+            target_weight_loss_percent, roast_level, recommended_equipment, created_by
+        )
+        VALUES ($1, 'Filter Medium-Light', 'คั่วกลางอ่อนสำหรับดริป', 480, 196, 90, 206, 570, 14.5, 'medium_light', 'Probat P5', $2)
+        RETURNING id
+        "#,
+    )
+    .bind(business_id)
+    .bind(owner_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    for season in 0..options.seasons as i32 {
+        let season_end = options.year - (options.seasons as i32 - 1) + season;
+        let season_start = NaiveDate::from_ymd_opt(season_end - 1, 11, 15).expect("valid date");
+        let mut season_lots = Vec::new();
+
+        for (plot_id, plot_quality) in &plots {
+            for pick in 0..PICKS_PER_PLOT {
+                let first_harvest = season_start + Duration::days(pick as i64 * 30 + rng.range(0, 10));
+                let lot = seed_lot(
+                    tx, rng, business_id, code, owner_id, roast_template_id,
+                    *plot_id, *plot_quality, first_harvest, summary,
+                )
+                .await?;
+                season_lots.push(lot);
+            }
+        }
+
+        seed_cupping_sessions(tx, rng, business_id, code, &season_lots, summary).await?;
+    }
+
+    Ok(())
+}
+
+/// Create a lot with harvests, processing, grading and optionally a roast
+#[allow(clippy::too_many_arguments)]
+async fn seed_lot(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut SeedRng,
+    business_id: Uuid,
+    code: &str,
+    owner_id: Uuid,
+    roast_template_id: Uuid,
+    plot_id: Uuid,
+    plot_quality: i64,
+    first_harvest: NaiveDate,
+    summary: &mut SeedSummary,
+) -> anyhow::Result<SeededLot> {
+    let sequence = sqlx::query_scalar::<_, i32>("SELECT get_next_lot_sequence($1, $2)")
+        .bind(business_id)
+        .bind(first_harvest.year())
+        .fetch_one(&mut **tx)
+        .await?;
+    let traceability_code = format!("CQM-{}-{}-{:04}", first_harvest.year(), code, sequence);
+
+    let lot_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO lots (business_id, traceability_code, name, stage, current_weight_kg)
+        VALUES ($1, $2, $3, 'cherry', 0)
+        RETURNING id
+        "#,
+    )
+    .bind(business_id)
+    .bind(&traceability_code)
+    .bind(format!("{} Cherry {}", first_harvest.format("%b %Y"), sequence))
+    .fetch_one(&mut **tx)
+    .await?;
+    summary.lots += 1;
+
+    // Harvests over a few days
+    let mut cherry_total = Decimal::ZERO;
+    let mut ripe_total = 0;
+    let harvest_days = rng.range(2, 4);
+    for day in 0..harvest_days {
+        let harvest_date = first_harvest + Duration::days(day);
+        let cherry_kg = rng.decimal(60, 350, 1);
+        let underripe = rng.range(0, 15) as i32;
+        let overripe = rng.range(0, 10) as i32;
+        ripe_total += 100 - underripe - overripe;
+
+        sqlx::query(
+            r#"
+            INSERT INTO harvests (
+                lot_id, plot_id, business_id, harvest_date, picker_name, cherry_weight_kg,
+                underripe_percent, ripe_percent, overripe_percent
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(lot_id)
+        .bind(plot_id)
+        .bind(business_id)
+        .bind(harvest_date)
+        .bind(*rng.pick(PICKERS))
+        .bind(cherry_kg)
+        .bind(underripe)
+        .bind(100 - underripe - overripe)
+        .bind(overripe)
+        .execute(&mut **tx)
+        .await?;
+
+        record_transaction(tx, business_id, lot_id, "harvest_in", cherry_kg, "in", "cherry", harvest_date, None, owner_id).await?;
+        cherry_total += cherry_kg;
+        summary.harvests += 1;
+        summary.transactions += 1;
+    }
+
+    // Better ripeness and higher plots produce better coffee
+    let quality = plot_quality + (ripe_total / harvest_days as i32 - 75) as i64 / 5 + rng.range(-1, 1);
+
+    // Processing
+    let start_date = first_harvest + Duration::days(harvest_days);
+    let drying_days = rng.range(12, 25);
+    let end_date = start_date + Duration::days(drying_days + 2);
+    let green_kg = (cherry_total * rng.decimal(15, 20, 2) / Decimal::from(100)).round_dp(1);
+    let final_moisture = rng.decimal(10, 12, 1);
+    let (method, method_details, fermentation_hours) = match rng.range(0, 3) {
+        0 => ("washed", None, Some(rng.range(24, 48))),
+        1 => ("natural", None, None),
+        2 => ("honey", Some(serde_json::json!({ "mucilage_percent": rng.range(2, 10) * 10 })), None),
+        _ => ("anaerobic", Some(serde_json::json!({ "hours": rng.range(48, 96) })), Some(rng.range(48, 96))),
+    };
+    let fermentation_log = fermentation_hours.map(|hours| {
+        serde_json::json!({ "duration_hours": hours, "temperature_readings": [], "ph_readings": [] })
+    });
+    let drying_log = serde_json::json!({
+        "method": if rng.chance(70) { "raised_bed" } else { "greenhouse" },
+        "start_date": start_date + Duration::days(2),
+        "end_date": end_date,
+        "duration_days": drying_days,
+        "target_moisture_percent": "11.0",
+        "moisture_readings": [],
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO processing_records (
+            lot_id, method, method_details, start_date, end_date, responsible_person,
+            fermentation_log, drying_log, final_moisture_percent, green_bean_weight_kg,
+            cherry_weight_kg, processing_yield_percent
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        "#,
+    )
+    .bind(lot_id)
+    .bind(method)
+    .bind(method_details)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(format!("{} Manager", code))
+    .bind(fermentation_log)
+    .bind(drying_log)
+    .bind(final_moisture)
+    .bind(green_kg)
+    .bind(cherry_total)
+    .bind(calculate_processing_yield(cherry_total, green_kg).round_dp(2))
+    .execute(&mut **tx)
+    .await?;
+    summary.processing += 1;
+
+    record_transaction(tx, business_id, lot_id, "processing_out", cherry_total, "out", "cherry", end_date, None, owner_id).await?;
+    record_transaction(tx, business_id, lot_id, "processing_in", green_kg, "in", "green_bean", end_date, None, owner_id).await?;
+    summary.transactions += 2;
+
+    sqlx::query("UPDATE lots SET stage = 'green_bean', current_weight_kg = $1 WHERE id = $2")
+        .bind(green_kg)
+        .bind(lot_id)
+        .execute(&mut **tx)
+        .await?;
+
+    // Grading
+    let category1 = if quality >= 3 { 0 } else { rng.range(0, 2) as i32 };
+    let category2 = (rng.range(0, 12) - quality).max(0) as i32;
+    let grade = classify_grade(&DefectCount {
+        category1_count: category1,
+        category2_count: category2,
+        defect_breakdown: None,
+    });
+    let grade = match grade {
+        GradeClassification::SpecialtyGrade => "specialty_grade",
+        GradeClassification::PremiumGrade => "premium_grade",
+        GradeClassification::ExchangeGrade => "exchange_grade",
+        GradeClassification::BelowStandard => "below_standard",
+        GradeClassification::OffGrade => "off_grade",
+    };
+    let screen_18 = rng.range(15, 35);
+    let screen_17 = rng.range(20, 30);
+    let screen_16 = rng.range(15, 25);
+    let screen_15 = rng.range(5, 15);
+
+    sqlx::query(
+        r#"
+        INSERT INTO green_bean_grades (
+            lot_id, grading_date, grader_name, sample_weight_grams, category1_count, category2_count,
+            moisture_percent, density, screen_size_distribution, grade
+        )
+        VALUES ($1, $2, $3, 350, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(lot_id)
+    .bind(end_date + Duration::days(3))
+    .bind(format!("{} Manager", code))
+    .bind(category1)
+    .bind(category2)
+    .bind(final_moisture)
+    .bind(rng.decimal(680, 760, 0))
+    .bind(serde_json::json!({
+        "screen_18_plus": screen_18.to_string(),
+        "screen_17": screen_17.to_string(),
+        "screen_16": screen_16.to_string(),
+        "screen_15": screen_15.to_string(),
+        "screen_14_below": (100 - screen_18 - screen_17 - screen_16 - screen_15).to_string(),
+    }))
+    .bind(grade)
+    .execute(&mut **tx)
+    .await?;
+    summary.gradings += 1;
+
+    // Roast about a third of the lots in full, selling part of the roasted coffee
+    let green_date = end_date + Duration::days(3);
+    let mut roast_session_id = None;
+    if rng.chance(35) {
+        let roast_date = green_date + Duration::days(rng.range(7, 30));
+        let drop_time = rng.range(540, 660) as i32;
+        let first_crack = drop_time - rng.range(75, 120) as i32;
+        let roasted_kg = (green_kg * rng.decimal(82, 88, 2) / Decimal::from(100)).round_dp(2);
+        let roast_level = if drop_time - first_crack > 100 { "medium" } else { "medium_light" };
+
+        let session_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO roast_sessions (
+                business_id, lot_id, template_id, session_date, roaster_name, equipment,
+                green_bean_weight_kg, initial_moisture_percent, charge_temp_celsius,
+                turning_point_time_seconds, turning_point_temp_celsius,
+                first_crack_time_seconds, first_crack_temp_celsius, drop_time_seconds, drop_temp_celsius,
+                roasted_weight_kg, weight_loss_percent, development_time_seconds, development_time_ratio,
+                roast_level, color_value, status, completed_at, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, 'Probat P5', $6, $7, 200, 90, 95, $8, 196, $9, 207,
+                    $10, $11, $12, $13, $14, $15, 'completed', $4::date + TIME '15:00', $16)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(lot_id)
+        .bind(roast_template_id)
+        .bind(roast_date)
+        .bind(format!("{} Owner", code))
+        .bind(green_kg)
+        .bind(final_moisture)
+        .bind(first_crack)
+        .bind(drop_time)
+        .bind(roasted_kg)
+        .bind(calculate_weight_loss(green_kg, roasted_kg).round_dp(2))
+        .bind(drop_time - first_crack)
+        .bind(calculate_dtr(drop_time - first_crack, drop_time).round_dp(2))
+        .bind(roast_level)
+        .bind(rng.decimal(55, 75, 0))
+        .bind(owner_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO roast_temperature_readings (session_id, time_seconds, temp_celsius, notes)
+            VALUES ($1, 0, 200, NULL), ($1, 90, 95, NULL), ($1, 240, 150, NULL),
+                   ($1, $2, 196, 'First crack'), ($1, $3, 207, NULL)
+            "#,
+        )
+        .bind(session_id)
+        .bind(first_crack)
+        .bind(drop_time)
+        .execute(&mut **tx)
+        .await?;
+        summary.roasts += 1;
+
+        record_transaction(tx, business_id, lot_id, "roasting_out", green_kg, "out", "green_bean", roast_date, None, owner_id).await?;
+        record_transaction(tx, business_id, lot_id, "roasting_in", roasted_kg, "in", "roasted_bean", roast_date, None, owner_id).await?;
+
+        let sold_kg = (roasted_kg * rng.decimal(30, 60, 2) / Decimal::from(100)).round_dp(2);
+        let sale = (*rng.pick(BUYERS), Decimal::from(rng.range(60, 95) * 10));
+        record_transaction(
+            tx, business_id, lot_id, "sale", sold_kg, "out", "roasted_bean",
+            roast_date + Duration::days(rng.range(3, 20)), Some(sale), owner_id,
+        )
+        .await?;
+        summary.transactions += 3;
+
+        sqlx::query("UPDATE lots SET stage = 'roasted_bean', current_weight_kg = $1 WHERE id = $2")
+            .bind(roasted_kg - sold_kg)
+            .bind(lot_id)
+            .execute(&mut **tx)
+            .await?;
+
+        roast_session_id = Some(session_id);
+    }
+
+    Ok(SeededLot {
+        id: lot_id,
+        quality,
+        roast_session_id,
+        green_date,
+    })
+}
+
+/// Record an inventory movement (with optional buyer and unit price for sales)
+#[allow(clippy::too_many_arguments)]
+async fn record_transaction(
+    tx: &mut Transaction<'_, Postgres>,
+    business_id: Uuid,
+    lot_id: Uuid,
+    transaction_type: &str,
+    quantity_kg: Decimal,
+    direction: &str,
+    stage: &str,
+    transaction_date: NaiveDate,
+    sale: Option<(&str, Decimal)>,
+    created_by: Uuid,
+) -> anyhow::Result<()> {
+    let (counterparty, unit_price) = sale.unzip();
+
+    sqlx::query(
+        r#"
+        INSERT INTO inventory_transactions (
+            business_id, lot_id, transaction_type, quantity_kg, direction, stage,
+            counterparty_name, unit_price, total_price, transaction_date, created_by
+        )
+        VALUES ($1, $2, $3::inventory_transaction_type, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+    )
+    .bind(business_id)
+    .bind(lot_id)
+    .bind(transaction_type)
+    .bind(quantity_kg)
+    .bind(direction)
+    .bind(stage)
+    .bind(counterparty)
+    .bind(unit_price)
+    .bind(unit_price.map(|price| (price * quantity_kg).round_dp(2)))
+    .bind(transaction_date)
+    .bind(created_by)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Cup every lot of the season across a few sessions
+async fn seed_cupping_sessions(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut SeedRng,
+    business_id: Uuid,
+    code: &str,
+    lots: &[SeededLot],
+    summary: &mut SeedSummary,
+) -> anyhow::Result<()> {
+    for flight in lots.chunks(6) {
+        let session_date = flight.iter().map(|lot| lot.green_date).max().expect("non-empty flight")
+            + Duration::days(rng.range(5, 14));
+
+        let session_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO cupping_sessions
+                (business_id, session_date, cupper_name, location, status, finalized_at, signed_by)
+            VALUES ($1, $2, $3, 'Farm cupping lab', 'finalized', NOW(), $3)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(session_date)
+        .bind(format!("{} Owner", code))
+        .fetch_one(&mut **tx)
+        .await?;
+        summary.cupping_sessions += 1;
+
+        for (number, lot) in flight.iter().enumerate() {
+            // Attribute scores centred around 7.25-8.25 depending on lot quality
+            let base = 29 + lot.quality.clamp(0, 4);
+            let mut attribute = || Decimal::new((base + rng.range(-1, 2)).clamp(24, 40) * 25, 2);
+            let scores = CuppingScores {
+                fragrance_aroma: attribute(),
+                flavor: attribute(),
+                aftertaste: attribute(),
+                acidity: attribute(),
+                body: attribute(),
+                balance: attribute(),
+                overall: attribute(),
+                uniformity: Decimal::from(10),
+                clean_cup: Decimal::from(10),
+                sweetness: Decimal::from(10),
+            };
+            let taints = if lot.quality <= 0 && rng.chance(30) { 1 } else { 0 };
+            let total = scores.total();
+            let final_score = total - Decimal::from(taints * 2);
+            let (notes, notes_th) = *rng.pick(TASTING_NOTES);
+
+            sqlx::query(
+                r#"
+                INSERT INTO cupping_samples (
+                    session_id, lot_id, sample_number, fragrance_aroma, flavor, aftertaste, acidity,
+                    body, balance, uniformity, clean_cup, sweetness, overall, total_score,
+                    tasting_notes, tasting_notes_th, defects_taint, defects_fault, final_score, roast_session_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, 0, $18, $19)
+                "#,
+            )
+            .bind(session_id)
+            .bind(lot.id)
+            .bind(number as i32 + 1)
+            .bind(scores.fragrance_aroma)
+            .bind(scores.flavor)
+            .bind(scores.aftertaste)
+            .bind(scores.acidity)
+            .bind(scores.body)
+            .bind(scores.balance)
+            .bind(scores.uniformity)
+            .bind(scores.clean_cup)
+            .bind(scores.sweetness)
+            .bind(scores.overall)
+            .bind(total)
+            .bind(notes)
+            .bind(notes_th)
+            .bind(taints)
+            .bind(final_score)
+            .bind(lot.roast_session_id)
+            .execute(&mut **tx)
+            .await?;
+            summary.cupping_samples += 1;
+        }
+    }
+
+    Ok(())
+}
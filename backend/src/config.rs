@@ -31,6 +31,18 @@ pub struct Config {
 
     /// Weather API configuration
     pub weather: WeatherConfig,
+
+    /// Outgoing email (SMTP) configuration
+    pub email: EmailConfig,
+
+    /// Background job configuration
+    pub jobs: JobsConfig,
+
+    /// PDF document configuration
+    pub pdf: PdfConfig,
+
+    /// Research partner API configuration
+    pub research: ResearchConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -40,6 +52,9 @@ pub struct ServerConfig {
 
     /// Server host
     pub host: String,
+
+    /// Public base URL used in links sent to users (e.g. report downloads)
+    pub public_url: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -102,6 +117,53 @@ pub struct WeatherConfig {
     pub api_key: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct EmailConfig {
+    /// SMTP relay host; email delivery is disabled when empty
+    pub smtp_host: String,
+
+    /// SMTP port
+    pub smtp_port: u16,
+
+    /// Require STARTTLS; disable only for local mail catchers
+    pub starttls: bool,
+
+    /// SMTP username
+    pub smtp_username: String,
+
+    /// SMTP password
+    pub smtp_password: String,
+
+    /// Sender address for outgoing email
+    pub from_address: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobsConfig {
+    /// Run background jobs in this server process
+    pub enabled: bool,
+
+    /// Seconds between job polls
+    pub poll_interval_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PdfConfig {
+    /// TrueType font for PDF text; must cover Thai script to print Thai.
+    /// Built-in Helvetica (Latin only) is used when empty
+    pub font_path: String,
+
+    /// Bold variant of `font_path`; falls back to `font_path` when empty
+    pub bold_font_path: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResearchConfig {
+    /// Token operators send as `X-Research-Admin-Token` to issue and revoke
+    /// partner API keys; key management is disabled when empty
+    pub admin_token: String,
+}
+
 impl Config {
     /// Load configuration from files and environment variables
     pub fn load() -> Result<Self, ConfigError> {
@@ -112,11 +174,23 @@ impl Config {
             .set_default("environment", environment.clone())?
             .set_default("server.port", 3000)?
             .set_default("server.host", "0.0.0.0")?
+            .set_default("server.public_url", "http://localhost:3000")?
             .set_default("database.max_connections", 10)?
             .set_default("database.min_connections", 2)?
             .set_default("jwt.access_token_expiry", 3600)?
             .set_default("jwt.refresh_token_expiry", 604800)?
             .set_default("aws.region", "ap-southeast-1")?
+            .set_default("email.smtp_host", "")?
+            .set_default("email.smtp_port", 587)?
+            .set_default("email.starttls", true)?
+            .set_default("email.smtp_username", "")?
+            .set_default("email.smtp_password", "")?
+            .set_default("email.from_address", "noreply@coffee-qm.local")?
+            .set_default("jobs.enabled", true)?
+            .set_default("jobs.poll_interval_seconds", 60)?
+            .set_default("pdf.font_path", "")?
+            .set_default("pdf.bold_font_path", "")?
+            .set_default("research.admin_token", "")?
             // Load environment-specific config file
             .add_source(File::with_name(&format!("config/{}", environment)).required(false))
             // Override with environment variables (CQM_ prefix)
@@ -136,6 +210,7 @@ impl Default for ServerConfig {
         Self {
             port: 3000,
             host: "0.0.0.0".to_string(),
+            public_url: "http://localhost:3000".to_string(),
         }
     }
 }
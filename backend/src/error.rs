@@ -3,7 +3,7 @@
 //! Provides consistent error responses in Thai and English
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -26,6 +26,15 @@ pub enum AppError {
     #[error("Insufficient permissions")]
     InsufficientPermissions,
 
+    #[error("Two-factor code required")]
+    TwoFactorRequired,
+
+    #[error("Too many sign-in attempts; retry in {retry_after_secs}s")]
+    TooManyLoginAttempts { retry_after_secs: i64 },
+
+    #[error("Request limit reached; retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: i64 },
+
     #[error("Unauthorized: {message}")]
     Unauthorized {
         message: String,
@@ -91,6 +100,12 @@ pub enum AppError {
         conflict: crate::services::sync::SyncConflict,
     },
 
+    // Harvest errors
+    #[error("Possible duplicate harvest")]
+    DuplicateHarvest {
+        duplicate: crate::services::harvest::SameDayHarvest,
+    },
+
     // Database errors
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
@@ -118,6 +133,20 @@ pub struct ErrorDetail {
     pub field: Option<String>,
 }
 
+/// Error response for a possible duplicate harvest, naming the harvest it
+/// may repeat so a client can link to it
+#[derive(Serialize)]
+struct DuplicateHarvestResponse<'a> {
+    error: DuplicateHarvestDetail<'a>,
+}
+
+#[derive(Serialize)]
+struct DuplicateHarvestDetail<'a> {
+    #[serde(flatten)]
+    detail: ErrorDetail,
+    duplicate: &'a crate::services::harvest::SameDayHarvest,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_detail) = match &self {
@@ -157,6 +186,45 @@ impl IntoResponse for AppError {
                     field: None,
                 },
             ),
+            AppError::TwoFactorRequired => (
+                StatusCode::UNAUTHORIZED,
+                ErrorDetail {
+                    code: "TWO_FACTOR_REQUIRED".to_string(),
+                    message_en: "Enter the code from your authenticator app or a backup code".to_string(),
+                    message_th: "กรุณากรอกรหัสจากแอปยืนยันตัวตนหรือรหัสสำรอง".to_string(),
+                    field: Some("otp_code".to_string()),
+                },
+            ),
+            AppError::TooManyLoginAttempts { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorDetail {
+                    code: "TOO_MANY_LOGIN_ATTEMPTS".to_string(),
+                    message_en: format!(
+                        "Too many failed sign-in attempts. Try again in {} minutes",
+                        retry_after_minutes(*retry_after_secs)
+                    ),
+                    message_th: format!(
+                        "เข้าสู่ระบบไม่สำเร็จหลายครั้งเกินไป กรุณาลองใหม่ในอีก {} นาที",
+                        retry_after_minutes(*retry_after_secs)
+                    ),
+                    field: None,
+                },
+            ),
+            AppError::RateLimited { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorDetail {
+                    code: "RATE_LIMITED".to_string(),
+                    message_en: format!(
+                        "Request limit reached. Try again in {} minutes",
+                        retry_after_minutes(*retry_after_secs)
+                    ),
+                    message_th: format!(
+                        "ส่งคำขอครบจำนวนที่กำหนดแล้ว กรุณาลองใหม่ในอีก {} นาที",
+                        retry_after_minutes(*retry_after_secs)
+                    ),
+                    field: None,
+                },
+            ),
             AppError::Unauthorized { message, message_th } => (
                 StatusCode::UNAUTHORIZED,
                 ErrorDetail {
@@ -302,6 +370,24 @@ impl IntoResponse for AppError {
                     field: None,
                 },
             ),
+            AppError::DuplicateHarvest { duplicate } => {
+                let picker = duplicate.picker_name.as_deref().unwrap_or("-");
+                (
+                    StatusCode::CONFLICT,
+                    ErrorDetail {
+                        code: "DUPLICATE_HARVEST".to_string(),
+                        message_en: format!(
+                            "Possible duplicate of harvest {} ({} kg, picker {}) on the same plot and date; set confirm_duplicate to record it anyway",
+                            duplicate.id, duplicate.cherry_weight_kg, picker
+                        ),
+                        message_th: format!(
+                            "อาจซ้ำกับการเก็บเกี่ยว {} ({} กก. ผู้เก็บ {}) ในแปลงและวันเดียวกัน กรุณายืนยันหากต้องการบันทึก",
+                            duplicate.id, duplicate.cherry_weight_kg, picker
+                        ),
+                        field: Some("cherry_weight_kg".to_string()),
+                    },
+                )
+            }
             AppError::DatabaseError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ErrorDetail {
@@ -334,9 +420,24 @@ impl IntoResponse for AppError {
         // Log the error for debugging
         tracing::error!("Error: {:?}", self);
 
-        (status, Json(ErrorResponse { error: error_detail })).into_response()
+        let mut response = match &self {
+            AppError::DuplicateHarvest { duplicate } => {
+                let error = DuplicateHarvestDetail { detail: error_detail, duplicate };
+                (status, Json(DuplicateHarvestResponse { error })).into_response()
+            }
+            _ => (status, Json(ErrorResponse { error: error_detail })).into_response(),
+        };
+        if let AppError::TooManyLoginAttempts { retry_after_secs } | AppError::RateLimited { retry_after_secs } = self {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs.max(1)));
+        }
+        response
     }
 }
 
+/// Whole minutes to wait, rounded up
+fn retry_after_minutes(retry_after_secs: i64) -> i64 {
+    (retry_after_secs.max(1) + 59) / 60
+}
+
 /// Result type alias for handlers
 pub type AppResult<T> = Result<T, AppError>;
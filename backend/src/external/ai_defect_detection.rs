@@ -1,6 +1,7 @@
 //! AI Defect Detection Client
 //!
-//! Client for the AWS-hosted AI defect detection microservice.
+//! Client for the AWS-hosted AI defect detection microservice, which also
+//! estimates cherry ripeness from harvest basket photos.
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -53,6 +54,12 @@ pub struct DefectBreakdownResponse {
     pub full_black: i32,
     pub full_sour: i32,
     pub pod_cherry: i32,
+    #[serde(default)]
+    pub fungus_damaged: i32,
+    #[serde(default)]
+    pub foreign_matter: i32,
+    #[serde(default)]
+    pub severe_insect_damage: i32,
     pub large_stones: i32,
     pub medium_stones: i32,
     pub large_sticks: i32,
@@ -78,6 +85,9 @@ impl From<DefectBreakdownResponse> for DefectBreakdown {
             full_black: r.full_black,
             full_sour: r.full_sour,
             pod_cherry: r.pod_cherry,
+            fungus_damaged: r.fungus_damaged,
+            foreign_matter: r.foreign_matter,
+            severe_insect_damage: r.severe_insect_damage,
             large_stones: r.large_stones,
             medium_stones: r.medium_stones,
             large_sticks: r.large_sticks,
@@ -114,6 +124,25 @@ impl From<AiDetectionResult> for AiDefectDetection {
     }
 }
 
+/// Request to estimate the ripeness of cherries in a basket photo
+#[derive(Debug, Serialize)]
+pub struct EstimateRipenessRequest {
+    pub image_base64: String,
+}
+
+/// Response from the ripeness estimation API; shares are percentages of the
+/// cherries counted and need not be whole numbers
+#[derive(Debug, Deserialize)]
+pub struct EstimateRipenessResponse {
+    pub request_id: String,
+    pub image_url: Option<String>,
+    pub cherries_counted: Option<i32>,
+    pub underripe_percent: f64,
+    pub ripe_percent: f64,
+    pub overripe_percent: f64,
+    pub confidence_score: f32,
+}
+
 /// Detection status for async processing
 #[derive(Debug, Deserialize)]
 pub struct DetectionStatus {
@@ -181,6 +210,43 @@ impl AiDefectDetectionClient {
         Ok(result)
     }
 
+    /// Send a cherry basket photo for ripeness estimation
+    pub async fn estimate_ripeness(
+        &self,
+        request: EstimateRipenessRequest,
+    ) -> AppResult<EstimateRipenessResponse> {
+        let url = format!("{}/ripeness", self.api_endpoint);
+
+        let response = self
+            .http_client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AppError::AiDetectionError(format!("Request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::AiDetectionError(format!(
+                "API returned {}: {}",
+                status, body
+            )));
+        }
+
+        let result: EstimateRipenessResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::AiDetectionError(format!("Failed to parse response: {}", e)))?;
+
+        Ok(result)
+    }
+
     /// Get detection status for async processing
     pub async fn get_detection_status(&self, request_id: &str) -> AppResult<DetectionStatus> {
         let url = format!("{}/status/{}", self.api_endpoint, request_id);
@@ -263,6 +329,9 @@ mod tests {
             full_black: 1,
             full_sour: 2,
             pod_cherry: 0,
+            fungus_damaged: 0,
+            foreign_matter: 0,
+            severe_insect_damage: 0,
             large_stones: 0,
             medium_stones: 0,
             large_sticks: 0,
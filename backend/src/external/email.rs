@@ -0,0 +1,110 @@
+//! SMTP Email Client
+//!
+//! Client for sending transactional email (e.g. scheduled reports) through an
+//! SMTP relay, using STARTTLS unless disabled for a local mail catcher.
+
+use lettre::{
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::config::EmailConfig;
+use crate::error::{AppError, AppResult};
+
+/// File attached to an outgoing email
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Client for an SMTP relay
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// Create a mailer from configuration; `None` when no SMTP host is set
+    pub fn from_config(config: &EmailConfig) -> Option<Self> {
+        if config.smtp_host.is_empty() {
+            return None;
+        }
+
+        let from = match config.from_address.parse::<Mailbox>() {
+            Ok(from) => from,
+            Err(e) => {
+                tracing::error!("Invalid email from_address '{}': {}", config.from_address, e);
+                return None;
+            }
+        };
+
+        let mut builder = if config.starttls {
+            match AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host) {
+                Ok(builder) => builder,
+                Err(e) => {
+                    tracing::error!("Invalid SMTP host '{}': {}", config.smtp_host, e);
+                    return None;
+                }
+            }
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+        .port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+            ));
+        }
+
+        Some(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    /// Send a plain-text email with an optional attachment to one recipient
+    pub async fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        attachment: Option<&EmailAttachment>,
+    ) -> AppResult<()> {
+        let to = to
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::ExternalService(format!("Invalid recipient '{}': {}", to, e)))?;
+
+        let builder = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject);
+
+        let message = match attachment {
+            Some(attachment) => {
+                let content_type = ContentType::parse(&attachment.content_type)
+                    .map_err(|e| AppError::Internal(format!("Invalid attachment type: {}", e)))?;
+                builder.multipart(
+                    MultiPart::mixed()
+                        .singlepart(SinglePart::plain(body.to_string()))
+                        .singlepart(
+                            Attachment::new(attachment.filename.clone())
+                                .body(attachment.data.clone(), content_type),
+                        ),
+                )
+            }
+            None => builder.body(body.to_string()),
+        }
+        .map_err(|e| AppError::Internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::ExternalService(format!("Failed to send email: {}", e)))?;
+
+        Ok(())
+    }
+}
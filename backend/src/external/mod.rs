@@ -1,7 +1,11 @@
 //! External API integrations
 
 pub mod ai_defect_detection;
+pub mod email;
+pub mod s3;
 pub mod weather;
 
 pub use ai_defect_detection::AiDefectDetectionClient;
+pub use email::SmtpMailer;
+pub use s3::S3Client;
 pub use weather::WeatherClient;
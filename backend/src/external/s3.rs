@@ -0,0 +1,203 @@
+//! S3 Client
+//!
+//! Minimal client for storing media (grading tray photos) in the configured
+//! S3 bucket. Requests are signed with AWS Signature Version 4 using the
+//! standard `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` credentials.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Client for one S3 bucket
+#[derive(Clone)]
+pub struct S3Client {
+    region: String,
+    bucket: String,
+    access_key_id: String,
+    secret_access_key: String,
+    http_client: Client,
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Hex SHA-256 of a payload
+pub fn payload_hash(payload: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(payload))
+}
+
+/// Key for signing requests to `service` in `region` on `date` (YYYYMMDD)
+pub fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    let region_key = hmac(&date_key, region);
+    let service_key = hmac(&region_key, service);
+    hmac(&service_key, "aws4_request")
+}
+
+/// URI-encode an object key, keeping `/` between path segments
+pub fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Canonical request of an S3 call signing `host`, `x-amz-content-sha256`
+/// and `x-amz-date`
+pub fn canonical_request(method: &str, encoded_key: &str, host: &str, content_hash: &str, amz_date: &str) -> String {
+    format!(
+        "{}\n/{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method, encoded_key, host, content_hash, amz_date, content_hash
+    )
+}
+
+impl S3Client {
+    /// Create a client for a bucket
+    pub fn new(region: String, bucket: String, access_key_id: String, secret_access_key: String) -> Self {
+        let http_client = Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            region,
+            bucket,
+            access_key_id,
+            secret_access_key,
+            http_client,
+        }
+    }
+
+    /// Create a client from environment variables; None when no bucket or
+    /// credentials are set
+    pub fn from_env() -> Option<Self> {
+        let bucket = std::env::var("CQM__AWS__S3_BUCKET").ok().filter(|b| !b.is_empty())?;
+        let region = std::env::var("CQM__AWS__REGION").unwrap_or_else(|_| "ap-southeast-1".to_string());
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID").ok()?;
+        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY").ok()?;
+
+        Some(Self::new(region, bucket, access_key_id, secret_access_key))
+    }
+
+    fn host(&self) -> String {
+        format!("{}.s3.{}.amazonaws.com", self.bucket, self.region)
+    }
+
+    /// URL of an object in the bucket
+    pub fn object_url(&self, key: &str) -> String {
+        format!("https://{}/{}", self.host(), encode_key(key))
+    }
+
+    /// Signed headers for a request made at `now`
+    fn signed_headers(&self, method: &str, key: &str, content_hash: &str, now: DateTime<Utc>) -> Vec<(&'static str, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = self.host();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+        let canonical = canonical_request(method, &encode_key(key), &host, content_hash, &amz_date);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            payload_hash(canonical.as_bytes())
+        );
+        let signature = hmac(
+            &signing_key(&self.secret_access_key, &date, &self.region, "s3"),
+            &string_to_sign,
+        )
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+        vec![
+            (
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.access_key_id, scope, signature
+                ),
+            ),
+            ("x-amz-content-sha256", content_hash.to_string()),
+            ("x-amz-date", amz_date),
+        ]
+    }
+
+    /// Store an object
+    pub async fn put_object(&self, key: &str, body: Vec<u8>, content_type: &str) -> AppResult<()> {
+        let headers = self.signed_headers("PUT", key, &payload_hash(&body), Utc::now());
+        let mut request = self
+            .http_client
+            .put(self.object_url(key))
+            .header("Content-Type", content_type)
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 upload failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalService(format!("S3 returned {}: {}", status, body)));
+        }
+        Ok(())
+    }
+
+    /// Fetch an object
+    pub async fn get_object(&self, key: &str) -> AppResult<Vec<u8>> {
+        let headers = self.signed_headers("GET", key, &payload_hash(&[]), Utc::now());
+        let mut request = self.http_client.get(self.object_url(key));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 download failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalService(format!("S3 returned {}: {}", status, body)));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 download failed: {}", e)))?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Remove an object
+    pub async fn delete_object(&self, key: &str) -> AppResult<()> {
+        let headers = self.signed_headers("DELETE", key, &payload_hash(&[]), Utc::now());
+        let mut request = self.http_client.delete(self.object_url(key));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 delete failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalService(format!("S3 returned {}: {}", status, body)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! HTTP handlers for the business activity feed
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::activity_feed::{ActivityFeed, ActivityFeedQuery, ActivityFeedSeen},
+    services::ActivityFeedService,
+    AppState,
+};
+
+/// Page of the activity feed, newest first, optionally for one activity
+/// type, entity type or lot
+pub async fn get_activity_feed(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ActivityFeedQuery>,
+) -> AppResult<Json<ActivityFeed>> {
+    let service = ActivityFeedService::new(state.db);
+    let feed = service
+        .list(current_user.0.business_id, current_user.0.user_id, &query)
+        .await?;
+    Ok(Json(feed))
+}
+
+/// Mark the activity feed as viewed up to now
+pub async fn mark_activity_feed_seen(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<ActivityFeedSeen>> {
+    let service = ActivityFeedService::new(state.db);
+    let seen = service
+        .mark_seen(current_user.0.business_id, current_user.0.user_id)
+        .await?;
+    Ok(Json(seen))
+}
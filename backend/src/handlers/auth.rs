@@ -1,16 +1,33 @@
 //! Authentication handlers
 
-use axum::{extract::State, http::StatusCode, Json};
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Path, State},
+    http::{header::USER_AGENT, HeaderMap, StatusCode},
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::middleware::CurrentUser;
+use crate::services::auth::{AuthSession, BackupCodes, SessionDevice, TwoFactorEnrollment, TwoFactorStatus};
 use crate::services::AuthService;
 use crate::AppState;
 
+/// Longest user agent kept for a session
+const MAX_USER_AGENT_CHARS: usize = 500;
+
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// Label for the session, e.g. "Packing room tablet"
+    pub device_name: Option<String>,
+    /// Authenticator app or backup code, for accounts with two-factor
+    /// authentication
+    pub otp_code: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -32,6 +49,7 @@ pub struct RegisterRequest {
     pub phone: Option<String>,
     pub province: Option<String>,
     pub preferred_language: Option<String>,
+    pub device_name: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -49,13 +67,76 @@ pub struct RefreshRequest {
     pub refresh_token: String,
 }
 
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorCodeRequest {
+    /// Authenticator app code; a backup code is also accepted except when
+    /// confirming enrollment
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct AuthMessageResponse {
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Device metadata of the client signing in, read from its request
+/// headers; the address is the first hop of `X-Forwarded-For` (set by the
+/// reverse proxy) or `X-Real-IP`
+pub fn session_device(headers: &HeaderMap, device_name: Option<String>) -> SessionDevice {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let ip_address = header("x-forwarded-for")
+        .and_then(|v| v.split(',').next())
+        .or_else(|| header("x-real-ip"))
+        .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_string());
+
+    SessionDevice {
+        device_name,
+        user_agent: header(USER_AGENT.as_str()).map(|ua| ua.chars().take(MAX_USER_AGENT_CHARS).collect()),
+        ip_address,
+    }
+}
+
 /// Login endpoint handler
 pub async fn login(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
+    let device = session_device(&headers, body.device_name);
     let auth_service = AuthService::new(state.db.clone(), &state.config);
-    let tokens = auth_service.login(&body.email, &body.password).await?;
+    let tokens = auth_service
+        .login(&body.email, &body.password, body.otp_code.as_deref(), &device)
+        .await?;
 
     Ok(Json(LoginResponse {
         access_token: tokens.access_token,
@@ -68,6 +149,7 @@ pub async fn login(
 /// Register business endpoint handler
 pub async fn register(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<RegisterRequest>,
 ) -> Result<(StatusCode, Json<RegisterResponse>), AppError> {
     use crate::services::auth::RegisterBusinessInput;
@@ -90,8 +172,9 @@ pub async fn register(
         preferred_language: language,
     };
 
+    let device = session_device(&headers, body.device_name);
     let auth_service = AuthService::new(state.db.clone(), &state.config);
-    let result = auth_service.register_business(input).await?;
+    let result = auth_service.register_business(input, &device).await?;
 
     Ok((
         StatusCode::CREATED,
@@ -109,10 +192,12 @@ pub async fn register(
 /// Refresh token endpoint handler
 pub async fn refresh(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<RefreshRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
+    let device = session_device(&headers, None);
     let auth_service = AuthService::new(state.db.clone(), &state.config);
-    let tokens = auth_service.refresh_token(&body.refresh_token).await?;
+    let tokens = auth_service.refresh_token(&body.refresh_token, &device).await?;
 
     Ok(Json(LoginResponse {
         access_token: tokens.access_token,
@@ -121,3 +206,149 @@ pub async fn refresh(
         expires_in: tokens.expires_in,
     }))
 }
+
+/// Forgot password endpoint handler; the response does not reveal whether
+/// the email is registered
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(body): Json<ForgotPasswordRequest>,
+) -> Result<(StatusCode, Json<AuthMessageResponse>), AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service.forgot_password(&body.email).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(AuthMessageResponse {
+            message: "If an account uses this email, a password reset link has been sent".to_string(),
+            message_th: "หากอีเมลนี้มีบัญชีอยู่ เราได้ส่งลิงก์ตั้งรหัสผ่านใหม่ไปแล้ว".to_string(),
+        }),
+    ))
+}
+
+/// Reset password endpoint handler
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(body): Json<ResetPasswordRequest>,
+) -> Result<Json<AuthMessageResponse>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service.reset_password(&body.token, &body.new_password).await?;
+
+    Ok(Json(AuthMessageResponse {
+        message: "Password changed; sign in with the new password".to_string(),
+        message_th: "เปลี่ยนรหัสผ่านแล้ว กรุณาเข้าสู่ระบบด้วยรหัสผ่านใหม่".to_string(),
+    }))
+}
+
+/// Send email verification endpoint handler
+pub async fn send_email_verification(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<(StatusCode, Json<AuthMessageResponse>), AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service.send_email_verification(user.user_id).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(AuthMessageResponse {
+            message: "Verification link sent".to_string(),
+            message_th: "ส่งลิงก์ยืนยันอีเมลแล้ว".to_string(),
+        }),
+    ))
+}
+
+/// Verify email endpoint handler
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(body): Json<VerifyEmailRequest>,
+) -> Result<Json<AuthMessageResponse>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service.verify_email(&body.token).await?;
+
+    Ok(Json(AuthMessageResponse {
+        message: "Email verified".to_string(),
+        message_th: "ยืนยันอีเมลแล้ว".to_string(),
+    }))
+}
+
+/// List the current user's signed-in sessions
+pub async fn list_auth_sessions(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<Vec<AuthSession>>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let sessions = auth_service.list_sessions(user.user_id, user.session_id).await?;
+
+    Ok(Json(sessions))
+}
+
+/// Revoke one of the current user's sessions, e.g. on a lost phone
+pub async fn revoke_auth_session(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service.revoke_session(user.user_id, session_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Two-factor status of the current user
+pub async fn get_two_factor_status(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<TwoFactorStatus>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let status = auth_service.two_factor_status(user.user_id).await?;
+
+    Ok(Json(status))
+}
+
+/// Start two-factor enrollment; returns the secret and otpauth URI
+pub async fn enroll_two_factor(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<TwoFactorEnrollment>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let enrollment = auth_service.enroll_two_factor(user.user_id).await?;
+
+    Ok(Json(enrollment))
+}
+
+/// Confirm enrollment with a code from the app; returns the backup codes
+pub async fn confirm_two_factor(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(body): Json<TwoFactorCodeRequest>,
+) -> Result<Json<BackupCodes>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let codes = auth_service.confirm_two_factor(user.user_id, &body.code).await?;
+
+    Ok(Json(codes))
+}
+
+/// Turn two-factor authentication off
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(body): Json<DisableTwoFactorRequest>,
+) -> Result<StatusCode, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    auth_service
+        .disable_two_factor(user.user_id, &body.password, &body.code)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace the backup codes of the current user
+pub async fn regenerate_backup_codes(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(body): Json<TwoFactorCodeRequest>,
+) -> Result<Json<BackupCodes>, AppError> {
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let codes = auth_service.regenerate_backup_codes(user.user_id, &body.code).await?;
+
+    Ok(Json(codes))
+}
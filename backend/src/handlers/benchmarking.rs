@@ -0,0 +1,29 @@
+//! Regional benchmarking handlers
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::error::AppError;
+use crate::middleware::CurrentUser;
+use crate::services::benchmarking::{BenchmarkQuery, RegionalBenchmarks};
+use crate::services::BenchmarkingService;
+use crate::AppState;
+
+/// Regional percentile benchmarks for the current business; requires the
+/// business to have opted in to benchmarking
+pub async fn get_regional_benchmarks(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Query(query): Query<BenchmarkQuery>,
+) -> Result<Json<RegionalBenchmarks>, AppError> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = BenchmarkingService::new(state.db.clone());
+    let benchmarks = service.get_benchmarks(user.business_id, &query).await?;
+
+    Ok(Json(benchmarks))
+}
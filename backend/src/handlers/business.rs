@@ -0,0 +1,40 @@
+//! Business settings handlers
+
+use axum::{extract::State, Json};
+
+use crate::error::AppError;
+use crate::middleware::CurrentUser;
+use crate::services::business::{BusinessSettings, UpdateBusinessSettingsInput};
+use crate::services::BusinessService;
+use crate::AppState;
+
+/// Get settings of the current business
+pub async fn get_business_settings(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<BusinessSettings>, AppError> {
+    if !user.has_permission("business", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = BusinessService::new(state.db.clone());
+    let settings = service.get_settings(user.business_id).await?;
+
+    Ok(Json(settings))
+}
+
+/// Update settings of the current business
+pub async fn update_business_settings(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(input): Json<UpdateBusinessSettingsInput>,
+) -> Result<Json<BusinessSettings>, AppError> {
+    if !user.has_permission("business", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = BusinessService::new(state.db.clone());
+    let settings = service.update_settings(user.business_id, input).await?;
+
+    Ok(Json(settings))
+}
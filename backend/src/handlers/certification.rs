@@ -2,6 +2,8 @@
 
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     Json,
 };
 use serde::Deserialize;
@@ -10,11 +12,13 @@ use uuid::Uuid;
 use crate::error::AppResult;
 use crate::middleware::CurrentUser;
 use crate::services::certification::{
-    Certification, CertificationCompliance, CertificationDocument, CertificationRequirement,
-    CertificationService, CertificationType, CertificationWithCompliance,
-    CreateCertificationInput, ExpiringCertification, UpdateCertificationInput,
+    Certification, CertificationCompliance, CertificationComplianceIssue, CertificationDocument,
+    CertificationRequirement, CertificationService, CertificationType, CertificationWithCompliance,
+    ComplianceIssueQuery, CreateCertificationInput, ExpiringCertification, UpdateCertificationInput,
     UpdateComplianceInput, UploadDocumentInput,
 };
+use crate::services::gap_export::GapExportQuery;
+use crate::services::GapExportService;
 use crate::AppState;
 
 // ============================================================================
@@ -89,7 +93,7 @@ pub async fn delete_certification(
 ) -> AppResult<Json<()>> {
     let service = CertificationService::new(state.db);
     service
-        .delete_certification(current_user.0.business_id, certification_id)
+        .delete_certification(current_user.0.business_id, current_user.0.user_id, certification_id)
         .await?;
     Ok(Json(()))
 }
@@ -204,6 +208,34 @@ pub async fn update_compliance(
     Ok(Json(compliance))
 }
 
+/// List compliance issues of a certification, such as non-organic inputs
+/// logged in farm activities
+pub async fn list_compliance_issues(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(certification_id): Path<Uuid>,
+    Query(query): Query<ComplianceIssueQuery>,
+) -> AppResult<Json<Vec<CertificationComplianceIssue>>> {
+    let service = CertificationService::new(state.db);
+    let issues = service
+        .list_compliance_issues(current_user.0.business_id, certification_id, &query)
+        .await?;
+    Ok(Json(issues))
+}
+
+/// Mark a compliance issue resolved
+pub async fn resolve_compliance_issue(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((certification_id, issue_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<CertificationComplianceIssue>> {
+    let service = CertificationService::new(state.db);
+    let issue = service
+        .resolve_compliance_issue(current_user.0.business_id, certification_id, issue_id)
+        .await?;
+    Ok(Json(issue))
+}
+
 // ============================================================================
 // Expiration Alerts
 // ============================================================================
@@ -300,3 +332,48 @@ fn parse_certification_type(s: &str) -> AppResult<CertificationType> {
         }),
     }
 }
+
+// ============================================================================
+// Thai GAP Submission
+// ============================================================================
+
+/// Download the Thai GAP application workbook for a crop season
+pub async fn export_gap_submission_xlsx(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<GapExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    let service = GapExportService::new(state.db.clone(), &state.config);
+    let bytes = service
+        .xlsx(current_user.0.business_id, &query, query.language.as_deref() != Some("en"))
+        .await?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"thai_gap_submission.xlsx\"".to_string()),
+        ],
+        bytes,
+    ))
+}
+
+/// Download the Thai GAP application as a PDF for a crop season
+pub async fn export_gap_submission_pdf(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<GapExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    let service = GapExportService::new(state.db.clone(), &state.config);
+    let bytes = service
+        .pdf(current_user.0.business_id, &query, query.language.as_deref() != Some("en"))
+        .await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"thai_gap_submission.pdf\"".to_string()),
+        ],
+        bytes,
+    ))
+}
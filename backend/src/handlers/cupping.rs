@@ -1,19 +1,35 @@
 //! HTTP handlers for cupping session and score management
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use shared::FlavorDescriptorEntry;
 use uuid::Uuid;
 
 use crate::{
     error::AppResult,
     middleware::CurrentUser,
+    services::cupper_calibration::{CalibrationQuery, CupperCalibration},
     services::cupping::{
-        AddCuppingSampleInput, CreateCuppingSessionInput, CuppingSample, CuppingSession,
-        CuppingTrend,
+        suggest_flavor_descriptors, AddCuppingSampleInput, CreateCuppingSessionInput, CuppingSample,
+        CuppingSampleEdit, CuppingSession, CuppingTrend, DeleteCuppingSampleQuery, FinalizeCuppingSessionInput,
+        FlavorDescriptorQuery, UpdateCuppingSampleInput,
+    },
+    services::cupping_analytics::{
+        CupperBias, CupperBiasQuery, CuppingAttributeAnalytics, CuppingAttributeQuery, DEFAULT_MIN_SHARED_LOTS,
+    },
+    services::cupping_flight::{FlightLayout, FlightLayoutQuery},
+    services::cupping_import::{CuppingImportQuery, CuppingImportResult},
+    services::cupping_panel::{RecordCupperScoresInput, SamplePanel, SessionPanel},
+    services::cupping_report::CuppingReportQuery,
+    services::cupping_triangle::{RecordTriangleAnswerInput, TriangleAnswer, TriangleResults, TriangleTest},
+    services::{
+        CupperCalibrationService, CuppingAnalyticsService, CuppingFlightService, CuppingImportService, CuppingPanelService,
+        CuppingReportService, CuppingService,
     },
-    services::CuppingService,
     AppState,
 };
 
@@ -28,6 +44,13 @@ pub async fn create_cupping_session(
     Ok(Json(session))
 }
 
+/// Flavor wheel descriptors matching `q`, for autocomplete
+pub async fn list_flavor_descriptors(
+    Query(query): Query<FlavorDescriptorQuery>,
+) -> Json<Vec<FlavorDescriptorEntry>> {
+    Json(suggest_flavor_descriptors(&query))
+}
+
 /// Add a sample to a cupping session
 pub async fn add_cupping_sample(
     State(state): State<AppState>,
@@ -40,6 +63,160 @@ pub async fn add_cupping_sample(
     Ok(Json(sample))
 }
 
+/// Correct a sample; total and final scores are recalculated
+pub async fn update_cupping_sample(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, sample_id)): Path<(Uuid, Uuid)>,
+    Json(input): Json<UpdateCuppingSampleInput>,
+) -> AppResult<Json<CuppingSample>> {
+    let service = CuppingService::new(state.db);
+    let sample = service
+        .update_sample(current_user.0.business_id, current_user.0.user_id, session_id, sample_id, input)
+        .await?;
+    Ok(Json(sample))
+}
+
+/// Delete a sample; its edit history is kept
+pub async fn delete_cupping_sample(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, sample_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<DeleteCuppingSampleQuery>,
+) -> AppResult<StatusCode> {
+    let service = CuppingService::new(state.db);
+    service
+        .delete_sample(current_user.0.business_id, current_user.0.user_id, session_id, sample_id, query.reason)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Edit history of a sample, newest first
+pub async fn get_cupping_sample_history(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, sample_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<Vec<CuppingSampleEdit>>> {
+    let service = CuppingService::new(state.db);
+    let history = service.sample_history(current_user.0.business_id, session_id, sample_id).await?;
+    Ok(Json(history))
+}
+
+/// Reveal the lots of a blind session
+pub async fn reveal_cupping_session(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+) -> AppResult<Json<CuppingSession>> {
+    let service = CuppingService::new(state.db);
+    let session = service
+        .reveal_session(current_user.0.business_id, current_user.0.user_id, session_id)
+        .await?;
+    Ok(Json(session))
+}
+
+/// Sign a session off, locking its samples
+pub async fn finalize_cupping_session(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Json(input): Json<FinalizeCuppingSessionInput>,
+) -> AppResult<Json<CuppingSession>> {
+    let service = CuppingService::new(state.db);
+    let session = service
+        .finalize_session(current_user.0.business_id, current_user.0.user_id, session_id, input)
+        .await?;
+    Ok(Json(session))
+}
+
+/// Triangle test of a session as served to tasters
+pub async fn get_triangle_test(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+) -> AppResult<Json<TriangleTest>> {
+    let service = CuppingService::new(state.db);
+    let test = service.get_triangle_test(current_user.0.business_id, session_id).await?;
+    Ok(Json(test))
+}
+
+/// Record a taster's pick of the odd cup in a triangle set
+pub async fn record_triangle_answer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Json(input): Json<RecordTriangleAnswerInput>,
+) -> AppResult<(StatusCode, Json<TriangleAnswer>)> {
+    let service = CuppingService::new(state.db);
+    let answer = service
+        .record_triangle_answer(current_user.0.business_id, current_user.0.user_id, session_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(answer)))
+}
+
+/// Remove a triangle answer
+pub async fn delete_triangle_answer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, answer_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let service = CuppingService::new(state.db);
+    service
+        .delete_triangle_answer(current_user.0.business_id, session_id, answer_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Answer key, answers and significance of a triangle session
+pub async fn get_triangle_results(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+) -> AppResult<Json<TriangleResults>> {
+    let service = CuppingService::new(state.db);
+    let results = service.triangle_results(current_user.0.business_id, session_id).await?;
+    Ok(Json(results))
+}
+
+/// Record one panel cupper's scores for a sample; the sample's scores
+/// become the panel consensus
+pub async fn record_cupper_scores(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, sample_id)): Path<(Uuid, Uuid)>,
+    Json(input): Json<RecordCupperScoresInput>,
+) -> AppResult<Json<SamplePanel>> {
+    let service = CuppingPanelService::new(state.db);
+    let panel = service
+        .record_scores(current_user.0.business_id, session_id, sample_id, input)
+        .await?;
+    Ok(Json(panel))
+}
+
+/// Remove a panel cupper's scores from a sample
+pub async fn delete_cupper_scores(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((session_id, sample_id, score_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> AppResult<Json<SamplePanel>> {
+    let service = CuppingPanelService::new(state.db);
+    let panel = service
+        .delete_scores(current_user.0.business_id, session_id, sample_id, score_id)
+        .await?;
+    Ok(Json(panel))
+}
+
+/// Panel statistics and outlier scores of a session
+pub async fn get_cupping_panel(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+) -> AppResult<Json<SessionPanel>> {
+    let service = CuppingPanelService::new(state.db);
+    let panel = service.session_panel(current_user.0.business_id, session_id).await?;
+    Ok(Json(panel))
+}
+
 /// Get a cupping session with all samples
 pub async fn get_cupping_session(
     State(state): State<AppState>,
@@ -82,3 +259,122 @@ pub async fn get_lot_cupping_trend(
     let trend = service.get_lot_cupping_trend(current_user.0.business_id, lot_id).await?;
     Ok(Json(trend))
 }
+
+/// Attribute statistics grouped by lot, plot, variety and/or process
+pub async fn get_cupping_attribute_analytics(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<CuppingAttributeQuery>,
+) -> AppResult<Json<CuppingAttributeAnalytics>> {
+    let service = CuppingAnalyticsService::new(state.db);
+    let analytics = service.attribute_analytics(current_user.0.business_id, &query).await?;
+    Ok(Json(analytics))
+}
+
+/// Per-cupper scoring bias relative to the rest of the panel
+pub async fn get_cupper_biases(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<CupperBiasQuery>,
+) -> AppResult<Json<Vec<CupperBias>>> {
+    let service = CuppingAnalyticsService::new(state.db);
+    let min_shared_lots = query.min_shared_lots.unwrap_or(DEFAULT_MIN_SHARED_LOTS);
+    let biases = service.get_cupper_biases(current_user.0.business_id, min_shared_lots).await?;
+    Ok(Json(biases))
+}
+
+/// A cupper's deviation from the rest of the panel, per attribute and month
+pub async fn get_cupper_calibration(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(name): Path<String>,
+    Query(query): Query<CalibrationQuery>,
+) -> AppResult<Json<CupperCalibration>> {
+    let service = CupperCalibrationService::new(state.db);
+    let calibration = service.get_calibration(current_user.0.business_id, &name, &query).await?;
+    Ok(Json(calibration))
+}
+
+/// Recompute normalized scores for the samples of the business's open sessions
+pub async fn refresh_normalized_scores(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<serde_json::Value>> {
+    let service = CuppingAnalyticsService::new(state.db);
+    let normalized = service.refresh_normalized_scores(current_user.0.business_id).await?;
+    Ok(Json(serde_json::json!({ "normalized_samples": normalized })))
+}
+
+/// Generate a randomized table layout with blind codes for a session
+pub async fn get_cupping_flight_layout(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<FlightLayoutQuery>,
+) -> AppResult<Json<FlightLayout>> {
+    let service = CuppingFlightService::new(state.db.clone(), &state.config);
+    let layout = service.generate_layout(current_user.0.business_id, session_id, &query).await?;
+    Ok(Json(layout))
+}
+
+/// Download bowl labels for a layout; pass the layout's seed to print
+/// labels matching a layout generated earlier
+pub async fn get_cupping_bowl_labels(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<FlightLayoutQuery>,
+) -> AppResult<Response> {
+    let service = CuppingFlightService::new(state.db.clone(), &state.config);
+    let (layout, pdf) = service.generate_labels(current_user.0.business_id, session_id, &query).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"bowl-labels-{}-{}.pdf\"", session_id, layout.seed),
+            ),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+/// Download a session's score sheet report; `language=th` for Thai labels
+pub async fn get_cupping_session_report(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<CuppingReportQuery>,
+) -> AppResult<Response> {
+    let service = CuppingReportService::new(state.db.clone(), &state.config);
+    let pdf = service.generate(current_user.0.business_id, session_id, &query).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"cupping-report-{}.pdf\"", session_id),
+            ),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+/// Import historical cupping data from a score sheet CSV export (the request
+/// body); `dry_run=true` previews the sessions and rows without writing
+pub async fn import_cupping_sheet(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<CuppingImportQuery>,
+    body: String,
+) -> AppResult<Json<CuppingImportResult>> {
+    let service = CuppingImportService::new(state.db);
+    let result = service.import(current_user.0.business_id, &body, &query).await?;
+    Ok(Json(result))
+}
@@ -0,0 +1,38 @@
+//! HTTP handlers for lot record completeness (data health)
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::data_quality::{DataQualityDashboard, DataQualityQuery, LotDataQuality},
+    services::DataQualityService,
+    AppState,
+};
+
+/// Data health of unsold lots: average score, the biggest gaps across the
+/// business and the least complete lots
+pub async fn get_data_quality_dashboard(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<DataQualityQuery>,
+) -> AppResult<Json<DataQualityDashboard>> {
+    let service = DataQualityService::new(state.db);
+    let dashboard = service.get_dashboard(current_user.0.business_id, &query).await?;
+    Ok(Json(dashboard))
+}
+
+/// Completeness score of one lot with the records it is missing
+pub async fn get_lot_data_quality(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+) -> AppResult<Json<LotDataQuality>> {
+    let service = DataQualityService::new(state.db);
+    let quality = service.get_lot(current_user.0.business_id, lot_id).await?;
+    Ok(Json(quality))
+}
@@ -0,0 +1,76 @@
+//! Conditional GET support for heavy read endpoints
+//!
+//! Responses carry a strong `ETag`; a request whose `If-None-Match` holds
+//! the current tag gets `304 Not Modified` with no body, so mobile clients
+//! on poor connections skip payloads they already have. Where a cheap
+//! fingerprint (row count, latest `updated_at`, highest sync version) covers
+//! the payload, the tag is computed from it before loading anything.
+//! Otherwise the tag is a hash of the serialized payload.
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Strong ETag from the SHA-256 of `content`
+pub fn etag_for(content: &[u8]) -> String {
+    let hex: String = Sha256::digest(content)
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("\"{}\"", hex)
+}
+
+/// Strong ETag for a JSON payload
+pub fn etag_for_json<T: Serialize>(value: &T) -> String {
+    etag_for(&serde_json::to_vec(value).unwrap_or_default())
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`). Weak tags compare equal
+/// to their strong form, as GET only needs weak comparison.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// `304 Not Modified` carrying the current tag
+pub fn not_modified(etag: &str) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    insert_etag(&mut response, etag);
+    response
+}
+
+/// `200 OK` JSON response tagged with `etag`
+pub fn json_with_etag<T: Serialize>(etag: &str, value: T) -> Response {
+    let mut response = (StatusCode::OK, Json(value)).into_response();
+    insert_etag(&mut response, etag);
+    response
+}
+
+/// Tag a JSON payload by its content and answer `If-None-Match`
+pub fn conditional_json<T: Serialize>(headers: &HeaderMap, value: T) -> Response {
+    let etag = etag_for_json(&value);
+    if if_none_match(headers, &etag) {
+        return not_modified(&etag);
+    }
+    json_with_etag(&etag, value)
+}
+
+fn insert_etag(response: &mut Response, etag: &str) {
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    // Revalidate before reuse so a changed payload is never served stale
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+}
@@ -0,0 +1,103 @@
+//! HTTP handlers for the farm activity log
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::farm_activity::{
+        AddOrganicAllowedInput, FarmActivity, FarmActivityQuery, OrganicAllowedInput,
+        RecordFarmActivityInput,
+    },
+    services::FarmActivityService,
+    AppState,
+};
+
+/// Record a farm activity; the response lists organic violations logged
+/// against the business's certifications
+pub async fn record_farm_activity(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordFarmActivityInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = FarmActivityService::new(state.db);
+    let activity = service
+        .record(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(activity)))
+}
+
+/// List farm activities, optionally for one plot, type or date range
+pub async fn list_farm_activities(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<FarmActivityQuery>,
+) -> AppResult<Json<Vec<FarmActivity>>> {
+    let service = FarmActivityService::new(state.db);
+    let activities = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(activities))
+}
+
+/// Get a farm activity
+pub async fn get_farm_activity(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(activity_id): Path<Uuid>,
+) -> AppResult<Json<FarmActivity>> {
+    let service = FarmActivityService::new(state.db);
+    let activity = service.get(current_user.0.business_id, activity_id).await?;
+    Ok(Json(activity))
+}
+
+/// Delete a farm activity
+pub async fn delete_farm_activity(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(activity_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = FarmActivityService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, activity_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List substances allowed under organic certification
+pub async fn list_organic_allowed_inputs(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<OrganicAllowedInput>>> {
+    let service = FarmActivityService::new(state.db);
+    let allowed = service.list_allowed_inputs(current_user.0.business_id).await?;
+    Ok(Json(allowed))
+}
+
+/// Add a product the business's certifier approved
+pub async fn add_organic_allowed_input(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<AddOrganicAllowedInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = FarmActivityService::new(state.db);
+    let allowed = service
+        .add_allowed_input(current_user.0.business_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(allowed)))
+}
+
+/// Remove one of the business's approved products
+pub async fn delete_organic_allowed_input(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(allowed_input_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = FarmActivityService::new(state.db);
+    service
+        .delete_allowed_input(current_user.0.business_id, allowed_input_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
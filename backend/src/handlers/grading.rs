@@ -1,16 +1,25 @@
 //! HTTP handlers for green bean grading endpoints
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     Json,
 };
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppResult;
 use crate::middleware::CurrentUser;
 use crate::services::grading::{
-    GradingComparison, GradingRecord, GradingService, RecordGradingInput, RecordGradingWithAiInput,
+    parse_compared_lot_ids, DeleteGradingQuery, GradingComparison, GradingEdit, GradingRecord, GradingService,
+    LotGradingComparison, PhysicalAnalysis, QueueAiGradingInput, RecordGradingInput, RecordGradingWithAiInput,
+    RecordPhysicalAnalysisInput, UpdateGradingInput,
 };
+use crate::services::grading_photo::{
+    GradingPhoto, GradingPhotoAnalysis, GradingReanalysis, ReanalyzeGradingInput, UploadGradingPhotoInput,
+};
+use crate::services::job_queue::Job;
+use crate::services::GradingPhotoService;
 use crate::AppState;
 
 /// Record a green bean grading (manual entry)
@@ -37,6 +46,33 @@ pub async fn record_grading_with_ai(
     Ok(Json(grading))
 }
 
+/// Queue AI defect detection of a tray photo; the grading is recorded in
+/// the background and followed through the returned job
+pub async fn queue_grading_with_ai(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<QueueAiGradingInput>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let service = GradingService::new(state.db);
+    let job = service
+        .queue_grading_with_ai(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Status of a queued AI grading, with the grading once recorded
+pub async fn get_ai_grading_job(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<Job>> {
+    let service = GradingService::new(state.db);
+    let job = service
+        .get_ai_grading_job(current_user.0.business_id, job_id)
+        .await?;
+    Ok(Json(job))
+}
+
 /// Get grading record by ID
 pub async fn get_grading(
     State(state): State<AppState>,
@@ -50,6 +86,33 @@ pub async fn get_grading(
     Ok(Json(grading))
 }
 
+/// Get the physical analysis of a graded sample
+pub async fn get_physical_analysis(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+) -> AppResult<Json<PhysicalAnalysis>> {
+    let service = GradingService::new(state.db);
+    let analysis = service
+        .get_physical_analysis(current_user.0.business_id, grading_id)
+        .await?;
+    Ok(Json(analysis))
+}
+
+/// Record the physical analysis of a graded sample
+pub async fn record_physical_analysis(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+    Json(input): Json<RecordPhysicalAnalysisInput>,
+) -> AppResult<Json<PhysicalAnalysis>> {
+    let service = GradingService::new(state.db);
+    let analysis = service
+        .record_physical_analysis(current_user.0.business_id, grading_id, input)
+        .await?;
+    Ok(Json(analysis))
+}
+
 /// Get grading history for a lot
 pub async fn get_grading_history(
     State(state): State<AppState>,
@@ -63,6 +126,47 @@ pub async fn get_grading_history(
     Ok(Json(gradings))
 }
 
+/// Correct a grading record
+pub async fn update_grading(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+    Json(input): Json<UpdateGradingInput>,
+) -> AppResult<Json<GradingRecord>> {
+    let service = GradingService::new(state.db);
+    let grading = service
+        .update_grading(current_user.0.business_id, current_user.0.user_id, grading_id, input)
+        .await?;
+    Ok(Json(grading))
+}
+
+/// Delete a grading record
+pub async fn delete_grading(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+    Query(query): Query<DeleteGradingQuery>,
+) -> AppResult<StatusCode> {
+    let service = GradingService::new(state.db);
+    service
+        .delete_grading(current_user.0.business_id, current_user.0.user_id, grading_id, query.reason)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Edit history of a grading record
+pub async fn get_grading_edit_history(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+) -> AppResult<Json<Vec<GradingEdit>>> {
+    let service = GradingService::new(state.db);
+    let history = service
+        .grading_edit_history(current_user.0.business_id, grading_id)
+        .await?;
+    Ok(Json(history))
+}
+
 /// List all grading records for the business
 pub async fn list_gradings(
     State(state): State<AppState>,
@@ -85,3 +189,75 @@ pub async fn get_grading_comparison(
         .await?;
     Ok(Json(comparison))
 }
+
+/// Query parameters for comparing lots
+#[derive(Debug, Deserialize)]
+pub struct CompareLotGradingsQuery {
+    /// Comma-separated lot ids
+    pub lot_ids: String,
+}
+
+/// Compare several lots' latest gradings and cupping scores side by side
+pub async fn compare_lot_gradings(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<CompareLotGradingsQuery>,
+) -> AppResult<Json<LotGradingComparison>> {
+    let lot_ids = parse_compared_lot_ids(&query.lot_ids)?;
+    let service = GradingService::new(state.db);
+    let comparison = service
+        .compare_lots(current_user.0.business_id, &lot_ids)
+        .await?;
+    Ok(Json(comparison))
+}
+
+/// Attach a bean tray photo to a grading
+pub async fn upload_grading_photo(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+    Json(input): Json<UploadGradingPhotoInput>,
+) -> AppResult<Json<GradingPhoto>> {
+    let service = GradingPhotoService::new(state.db);
+    let photo = service
+        .upload_photo(current_user.0.business_id, grading_id, current_user.0.user_id, input)
+        .await?;
+    Ok(Json(photo))
+}
+
+/// List the photos of a grading
+pub async fn list_grading_photos(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+) -> AppResult<Json<Vec<GradingPhoto>>> {
+    let service = GradingPhotoService::new(state.db);
+    let photos = service.list_photos(current_user.0.business_id, grading_id).await?;
+    Ok(Json(photos))
+}
+
+/// List every analysis version of a grading's photos
+pub async fn list_grading_photo_analyses(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+) -> AppResult<Json<Vec<GradingPhotoAnalysis>>> {
+    let service = GradingPhotoService::new(state.db);
+    let analyses = service.list_analyses(current_user.0.business_id, grading_id).await?;
+    Ok(Json(analyses))
+}
+
+/// Re-run AI defect detection on a grading's stored photos
+pub async fn reanalyze_grading(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(grading_id): Path<Uuid>,
+    input: Option<Json<ReanalyzeGradingInput>>,
+) -> AppResult<Json<GradingReanalysis>> {
+    let service = GradingPhotoService::new(state.db);
+    let input = input.map(|Json(input)| input).unwrap_or_default();
+    let reanalysis = service
+        .reanalyze(current_user.0.business_id, grading_id, current_user.0.user_id, input)
+        .await?;
+    Ok(Json(reanalysis))
+}
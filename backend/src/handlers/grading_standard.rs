@@ -0,0 +1,83 @@
+//! HTTP handlers for grading standards
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use shared::{sca_thresholds, GradeThreshold};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::grading_standard::{CreateGradingStandardInput, GradingStandard, UpdateGradingStandardInput},
+    services::GradingStandardService,
+    AppState,
+};
+
+/// Filters for listing grading standards
+#[derive(Debug, Deserialize)]
+pub struct GradingStandardListQuery {
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+/// Grading standards of the business
+pub async fn list_grading_standards(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<GradingStandardListQuery>,
+) -> AppResult<Json<Vec<GradingStandard>>> {
+    let service = GradingStandardService::new(state.db);
+    let standards = service
+        .list_standards(current_user.0.business_id, query.include_inactive)
+        .await?;
+    Ok(Json(standards))
+}
+
+/// Thresholds of the SCA rules, used when the business has no default
+pub async fn get_sca_grading_thresholds() -> Json<Vec<GradeThreshold>> {
+    Json(sca_thresholds())
+}
+
+/// A grading standard with its thresholds
+pub async fn get_grading_standard(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(standard_id): Path<Uuid>,
+) -> AppResult<Json<GradingStandard>> {
+    let service = GradingStandardService::new(state.db);
+    let standard = service
+        .get_standard(current_user.0.business_id, standard_id)
+        .await?;
+    Ok(Json(standard))
+}
+
+/// Add a grading standard
+pub async fn create_grading_standard(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateGradingStandardInput>,
+) -> AppResult<(StatusCode, Json<GradingStandard>)> {
+    let service = GradingStandardService::new(state.db);
+    let standard = service
+        .create_standard(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(standard)))
+}
+
+/// Edit a grading standard
+pub async fn update_grading_standard(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(standard_id): Path<Uuid>,
+    Json(input): Json<UpdateGradingStandardInput>,
+) -> AppResult<Json<GradingStandard>> {
+    let service = GradingStandardService::new(state.db);
+    let standard = service
+        .update_standard(current_user.0.business_id, standard_id, input)
+        .await?;
+    Ok(Json(standard))
+}
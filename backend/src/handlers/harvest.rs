@@ -8,8 +8,10 @@ use axum::{
 };
 use uuid::Uuid;
 
+use crate::external::AiDefectDetectionClient;
 use crate::middleware::CurrentUser;
 use crate::services::harvest::{HarvestService, RecordHarvestInput, UpdateHarvestInput};
+use crate::services::ripeness_estimate::{EstimateRipenessInput, RipenessEstimateService};
 use crate::AppState;
 
 /// List all harvests for the current business
@@ -101,8 +103,43 @@ pub async fn delete_harvest(
 ) -> impl IntoResponse {
     let service = HarvestService::new(state.db.clone());
     
-    match service.delete_harvest(current_user.0.business_id, harvest_id).await {
+    match service.delete_harvest(current_user.0.business_id, current_user.0.user_id, harvest_id).await {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
 }
+
+/// Estimate cherry ripeness from a basket photo to pre-fill a harvest
+pub async fn estimate_harvest_ripeness(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Json(input): Json<EstimateRipenessInput>,
+) -> impl IntoResponse {
+    let ai_client = AiDefectDetectionClient::new(
+        state.config.aws.ai_detection_endpoint.clone(),
+        state.config.aws.ai_detection_api_key.clone(),
+    );
+    let service = RipenessEstimateService::with_client(state.db.clone(), ai_client);
+
+    match service
+        .estimate(current_user.0.business_id, Some(current_user.0.user_id), "web", input)
+        .await
+    {
+        Ok(estimate) => (StatusCode::CREATED, Json(estimate)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Get a ripeness estimate
+pub async fn get_ripeness_estimate(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(estimate_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let service = RipenessEstimateService::new(state.db.clone());
+
+    match service.get_estimate(current_user.0.business_id, estimate_id).await {
+        Ok(estimate) => (StatusCode::OK, Json(estimate)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
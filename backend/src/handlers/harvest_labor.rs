@@ -0,0 +1,51 @@
+//! HTTP handlers for harvest labor planning
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::harvest_labor::{LaborPlan, LaborPlanQuery},
+    services::HarvestLaborService,
+    AppState,
+};
+
+/// Labor plan notifications queued
+#[derive(Debug, Serialize)]
+pub struct LaborPlanNotifyResponse {
+    pub notifications_queued: i32,
+}
+
+/// Live forecasts when a weather API key is configured, cached ones otherwise
+fn labor_service(state: AppState) -> HarvestLaborService {
+    match std::env::var("CQM_WEATHER_API_KEY") {
+        Ok(api_key) if !api_key.is_empty() => HarvestLaborService::with_weather_client(state.db, api_key),
+        _ => HarvestLaborService::new(state.db),
+    }
+}
+
+/// Picker-days per plot per week for the coming weeks
+pub async fn get_harvest_labor_plan(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<LaborPlanQuery>,
+) -> AppResult<Json<LaborPlan>> {
+    let service = labor_service(state);
+    let plan = service.plan(current_user.0.business_id, &query).await?;
+    Ok(Json(plan))
+}
+
+/// Send the labor plan to the farm managers as harvest reminders
+pub async fn notify_harvest_labor_plan(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<LaborPlanQuery>,
+) -> AppResult<Json<LaborPlanNotifyResponse>> {
+    let service = labor_service(state);
+    let notifications_queued = service.notify(current_user.0.business_id, &query).await?;
+    Ok(Json(LaborPlanNotifyResponse { notifications_queued }))
+}
@@ -0,0 +1,134 @@
+//! HTTP handlers for the knowledge base of SOPs and training items
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::knowledge_base::{
+        ArticleQuery, ArticleRead, ArticleReadership, ArticleRecommendations, ArticleSummary, ArticleView,
+        CreateArticleInput, UpdateArticleInput,
+    },
+    services::KnowledgeBaseService,
+    AppState,
+};
+
+/// Knowledge base items with the member's read status, optionally for one
+/// kind, category or role; drafts are listed only for admins
+pub async fn list_knowledge_articles(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(mut query): Query<ArticleQuery>,
+) -> AppResult<Json<Vec<ArticleSummary>>> {
+    query.include_drafts = query.include_drafts && current_user.0.has_permission("business", "edit");
+    let service = KnowledgeBaseService::new(state.db);
+    let articles = service
+        .list_articles(current_user.0.business_id, current_user.0.user_id, &query)
+        .await?;
+    Ok(Json(articles))
+}
+
+/// A knowledge base item in both languages
+pub async fn get_knowledge_article(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(article_id): Path<Uuid>,
+) -> AppResult<Json<ArticleView>> {
+    let service = KnowledgeBaseService::new(state.db);
+    let article = service
+        .get_article(
+            current_user.0.business_id,
+            current_user.0.user_id,
+            article_id,
+            current_user.0.has_permission("business", "edit"),
+        )
+        .await?;
+    Ok(Json(article))
+}
+
+/// Record that the member read the current version of an item
+pub async fn mark_knowledge_article_read(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(article_id): Path<Uuid>,
+) -> AppResult<Json<ArticleRead>> {
+    let service = KnowledgeBaseService::new(state.db);
+    let read = service
+        .mark_read(current_user.0.business_id, current_user.0.user_id, article_id)
+        .await?;
+    Ok(Json(read))
+}
+
+/// Unread or updated items recommended for the member's role
+pub async fn get_knowledge_recommendations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<ArticleRecommendations>> {
+    let service = KnowledgeBaseService::new(state.db);
+    let recommendations = service
+        .recommendations(
+            current_user.0.business_id,
+            current_user.0.user_id,
+            current_user.0.role_id,
+        )
+        .await?;
+    Ok(Json(recommendations))
+}
+
+/// Add a knowledge base item for the business
+pub async fn create_knowledge_article(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateArticleInput>,
+) -> AppResult<(StatusCode, Json<ArticleView>)> {
+    let service = KnowledgeBaseService::new(state.db);
+    let article = service
+        .create_article(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(article)))
+}
+
+/// Edit a knowledge base item of the business
+pub async fn update_knowledge_article(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(article_id): Path<Uuid>,
+    Json(input): Json<UpdateArticleInput>,
+) -> AppResult<Json<ArticleView>> {
+    let service = KnowledgeBaseService::new(state.db);
+    let article = service
+        .update_article(current_user.0.business_id, current_user.0.user_id, article_id, input)
+        .await?;
+    Ok(Json(article))
+}
+
+/// Remove a knowledge base item of the business
+pub async fn delete_knowledge_article(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(article_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = KnowledgeBaseService::new(state.db);
+    service
+        .delete_article(current_user.0.business_id, current_user.0.user_id, article_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Which members of the item's audience have read it
+pub async fn get_knowledge_article_readership(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(article_id): Path<Uuid>,
+) -> AppResult<Json<ArticleReadership>> {
+    let service = KnowledgeBaseService::new(state.db);
+    let readership = service
+        .readership(current_user.0.business_id, current_user.0.user_id, article_id)
+        .await?;
+    Ok(Json(readership))
+}
@@ -0,0 +1,52 @@
+//! HTTP handlers for third-party lab results
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::lab_result::{LabResult, LabResultQuery, RecordLabResultInput},
+    services::LabResultService,
+    AppState,
+};
+
+/// Record a lab result for a lot; the response carries the pass/fail verdict
+pub async fn record_lab_result(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordLabResultInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = LabResultService::new(state.db);
+    let result = service
+        .record(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// List lab results, optionally for one lot, one analysis or failures only
+pub async fn list_lab_results(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<LabResultQuery>,
+) -> AppResult<Json<Vec<LabResult>>> {
+    let service = LabResultService::new(state.db);
+    let results = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(results))
+}
+
+/// Delete a lab result
+pub async fn delete_lab_result(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(result_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = LabResultService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, result_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,36 @@
+//! HTTP handlers for bulk label printing
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::label_sheet::PrintLabelsInput,
+    services::LabelSheetService,
+    AppState,
+};
+
+/// Print-ready PDF of labels for packages and samples
+pub async fn print_labels(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<PrintLabelsInput>,
+) -> AppResult<Response> {
+    let service = LabelSheetService::new(state.db.clone(), &state.config);
+    let pdf = service.generate(current_user.0.business_id, &input).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"labels.pdf\"".to_string()),
+        ],
+        pdf,
+    )
+        .into_response())
+}
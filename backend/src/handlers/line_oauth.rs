@@ -2,6 +2,7 @@
 
 use axum::{
     extract::{Query, State},
+    http::{HeaderMap, StatusCode},
     response::Redirect,
     Json,
 };
@@ -9,9 +10,13 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::handlers::auth::{session_device, LoginResponse, RegisterResponse};
 use crate::middleware::CurrentUser;
+use crate::services::auth::RegisterLineBusinessInput;
 use crate::services::line_oauth::{LineConnection, LineOAuthConfig, LineOAuthResult, LineOAuthService};
+use crate::services::AuthService;
 use crate::AppState;
+use shared::types::Language;
 
 // ============================================================================
 // Request/Response Types
@@ -24,6 +29,27 @@ pub struct OAuthCallbackQuery {
     pub state: Option<String>,
 }
 
+/// Sign up with LINE: the authorization code plus optional business details
+#[derive(Debug, Deserialize)]
+pub struct LineRegisterRequest {
+    pub code: String,
+    pub business_name: Option<String>,
+    pub business_type: Option<String>,
+    pub business_code: Option<String>,
+    pub owner_name: Option<String>,
+    pub phone: Option<String>,
+    pub province: Option<String>,
+    pub preferred_language: Option<String>,
+    pub device_name: Option<String>,
+}
+
+/// Sign in with LINE
+#[derive(Debug, Deserialize)]
+pub struct LineLoginRequest {
+    pub code: String,
+    pub device_name: Option<String>,
+}
+
 /// Response for authorization URL
 #[derive(Debug, Serialize)]
 pub struct AuthorizationUrlResponse {
@@ -101,6 +127,68 @@ pub async fn handle_public_callback(
     Ok(Json(result))
 }
 
+/// Register a new business and owner from a LINE account
+/// POST /auth/line/register
+pub async fn register_with_line(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<LineRegisterRequest>,
+) -> AppResult<(StatusCode, Json<RegisterResponse>)> {
+    let service = get_line_service(&state)?;
+    let line = service.authenticate(&body.code).await?;
+
+    let input = RegisterLineBusinessInput {
+        business_name: body.business_name,
+        business_type: body.business_type,
+        business_code: body.business_code,
+        owner_name: body.owner_name,
+        phone: body.phone,
+        province: body.province,
+        preferred_language: body.preferred_language.as_deref().map(|l| match l {
+            "en" => Language::English,
+            _ => Language::Thai,
+        }),
+    };
+
+    let device = session_device(&headers, body.device_name);
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let result = auth_service.register_with_line(input, &line, &device).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RegisterResponse {
+            business_id: result.business_id.to_string(),
+            user_id: result.user_id.to_string(),
+            access_token: result.access_token,
+            refresh_token: result.refresh_token,
+            token_type: result.token_type,
+            expires_in: result.expires_in,
+        }),
+    ))
+}
+
+/// Sign in with a registered LINE account
+/// POST /auth/line/login
+pub async fn login_with_line(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<LineLoginRequest>,
+) -> AppResult<Json<LoginResponse>> {
+    let service = get_line_service(&state)?;
+    let line = service.authenticate(&body.code).await?;
+
+    let device = session_device(&headers, body.device_name);
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let tokens = auth_service.login_with_line(&line, &device).await?;
+
+    Ok(Json(LoginResponse {
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        token_type: tokens.token_type,
+        expires_in: tokens.expires_in,
+    }))
+}
+
 /// Get LINE connection status for current user
 /// GET /auth/line/status
 pub async fn get_connection_status(
@@ -1,26 +1,40 @@
 //! Lot management HTTP handlers
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Extension, Json,
 };
+use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::handlers::etag;
 use crate::middleware::CurrentUser;
 use crate::services::lot::{BlendLotsInput, CreateLotInput, LotService, UpdateLotInput};
+use crate::services::lot_stage::RepairStagesInput;
+use crate::services::{LotStageService, SpecSheetService, TraceabilityCheckService};
 use crate::AppState;
 
 /// List all lots for the current business
 pub async fn list_lots(
     State(state): State<AppState>,
     Extension(current_user): Extension<CurrentUser>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let service = LotService::new(state.db.clone());
-    
+
+    // The role is part of the tag as field visibility differs per role
+    let etag = match service.get_lots_fingerprint(current_user.0.business_id).await {
+        Ok(fingerprint) => etag::etag_for(format!("lots:{}:{}", current_user.0.role_id, fingerprint).as_bytes()),
+        Err(e) => return e.into_response(),
+    };
+    if etag::if_none_match(&headers, &etag) {
+        return etag::not_modified(&etag);
+    }
+
     match service.get_lots(current_user.0.business_id).await {
-        Ok(lots) => (StatusCode::OK, Json(serde_json::json!({ "lots": lots }))).into_response(),
+        Ok(lots) => etag::json_with_etag(&etag, serde_json::json!({ "lots": lots })),
         Err(e) => e.into_response(),
     }
 }
@@ -83,7 +97,7 @@ pub async fn blend_lots(
         Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
     
-    match service.blend_lots(current_user.0.business_id, &business_code, input).await {
+    match service.blend_lots(current_user.0.business_id, current_user.0.user_id, &business_code, input).await {
         Ok(lot) => (StatusCode::CREATED, Json(lot)).into_response(),
         Err(e) => e.into_response(),
     }
@@ -116,3 +130,81 @@ pub async fn get_lot_by_code(
         Err(e) => e.into_response(),
     }
 }
+
+#[derive(Deserialize)]
+pub struct SpecSheetQuery {
+    pub language: Option<String>, // "en" (default) or "th"
+}
+
+/// Download the buyer-facing spec sheet (lot passport) PDF
+pub async fn get_lot_spec_sheet(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+    Query(query): Query<SpecSheetQuery>,
+) -> impl IntoResponse {
+    let service = SpecSheetService::new(state.db.clone(), &state.config);
+    let thai = query.language.as_deref() == Some("th");
+
+    match service.generate(current_user.0.business_id, lot_id, thai).await {
+        Ok(pdf) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/pdf".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("inline; filename=\"spec-sheet-{}.pdf\"", lot_id),
+                ),
+            ],
+            pdf,
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Check the lot's harvest, processing, grading, cupping and roast dates for
+/// impossible sequences before they show on the public traceability page
+pub async fn check_lot_traceability(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let service = TraceabilityCheckService::new(state.db.clone());
+
+    match service.check_lot(current_user.0.business_id, lot_id).await {
+        Ok(check) => Json(check).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Lots whose stage lags behind their processing, grading and roasting
+/// records, with the stage the records show
+pub async fn check_lot_stages(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> impl IntoResponse {
+    let service = LotStageService::new(state.db.clone());
+
+    match service.check(current_user.0.business_id).await {
+        Ok(check) => Json(check).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Move lagging lots on to the stage their records show
+pub async fn repair_lot_stages(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RepairStagesInput>,
+) -> impl IntoResponse {
+    let service = LotStageService::new(state.db.clone());
+
+    match service
+        .repair(current_user.0.business_id, Some(current_user.0.user_id), &input)
+        .await
+    {
+        Ok(repair) => Json(repair).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,93 @@
+//! HTTP handlers for lot photo gallery endpoints
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::middleware::CurrentUser;
+use crate::services::lot_gallery::{LotPhoto, ReorderLotPhotosInput, UpdateLotPhotoInput, UploadLotPhotoInput};
+use crate::services::LotGalleryService;
+use crate::AppState;
+
+/// List a lot's gallery in order
+pub async fn list_lot_photos(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+) -> AppResult<Json<Vec<LotPhoto>>> {
+    let service = LotGalleryService::new(state.db);
+    let photos = service.list_photos(current_user.0.business_id, lot_id).await?;
+    Ok(Json(photos))
+}
+
+/// Add a photo to a lot's gallery
+pub async fn upload_lot_photo(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+    Json(input): Json<UploadLotPhotoInput>,
+) -> AppResult<(StatusCode, Json<LotPhoto>)> {
+    let service = LotGalleryService::new(state.db);
+    let photo = service
+        .upload_photo(current_user.0.business_id, lot_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(photo)))
+}
+
+/// Edit a gallery photo's descriptions or visibility
+pub async fn update_lot_photo(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((lot_id, photo_id)): Path<(Uuid, Uuid)>,
+    Json(input): Json<UpdateLotPhotoInput>,
+) -> AppResult<Json<LotPhoto>> {
+    let service = LotGalleryService::new(state.db);
+    let photo = service
+        .update_photo(current_user.0.business_id, lot_id, photo_id, input)
+        .await?;
+    Ok(Json(photo))
+}
+
+/// Make a photo the lot's cover
+pub async fn set_lot_cover_photo(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((lot_id, photo_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<LotPhoto>> {
+    let service = LotGalleryService::new(state.db);
+    let photo = service
+        .set_cover(current_user.0.business_id, lot_id, photo_id)
+        .await?;
+    Ok(Json(photo))
+}
+
+/// Put a lot's photos in a new order
+pub async fn reorder_lot_photos(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+    Json(input): Json<ReorderLotPhotosInput>,
+) -> AppResult<Json<Vec<LotPhoto>>> {
+    let service = LotGalleryService::new(state.db);
+    let photos = service
+        .reorder_photos(current_user.0.business_id, lot_id, input)
+        .await?;
+    Ok(Json(photos))
+}
+
+/// Remove a photo from a lot's gallery
+pub async fn delete_lot_photo(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((lot_id, photo_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let service = LotGalleryService::new(state.db);
+    service
+        .delete_photo(current_user.0.business_id, lot_id, photo_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,63 @@
+//! HTTP handlers for lot insurance policies
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::lot_insurance::{InsurancePolicy, InsurancePolicyQuery, RecordInsurancePolicyInput},
+    services::LotInsuranceService,
+    AppState,
+};
+
+/// Record an insurance policy for a lot or one of its shipments
+pub async fn record_insurance_policy(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordInsurancePolicyInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = LotInsuranceService::new(state.db);
+    let policy = service
+        .record(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+/// List insurance policies, optionally for one lot, shipment or status
+pub async fn list_insurance_policies(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<InsurancePolicyQuery>,
+) -> AppResult<Json<Vec<InsurancePolicy>>> {
+    let service = LotInsuranceService::new(state.db);
+    let policies = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(policies))
+}
+
+/// Get an insurance policy
+pub async fn get_insurance_policy(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(policy_id): Path<Uuid>,
+) -> AppResult<Json<InsurancePolicy>> {
+    let service = LotInsuranceService::new(state.db);
+    let policy = service.get(current_user.0.business_id, policy_id).await?;
+    Ok(Json(policy))
+}
+
+/// Delete an insurance policy
+pub async fn delete_insurance_policy(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(policy_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = LotInsuranceService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, policy_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,111 @@
+//! HTTP handlers for lot transfers between businesses
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::lot_transfer::{InitiateTransferInput, LotTransfer, RespondTransferInput, TransferQuery},
+    services::traceability::TraceabilityView,
+    services::LotTransferService,
+    AppState,
+};
+
+/// Transfers the business sold or bought
+pub async fn list_lot_transfers(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<TransferQuery>,
+) -> AppResult<Json<Vec<LotTransfer>>> {
+    let service = LotTransferService::new(state.db);
+    let transfers = service
+        .list_transfers(current_user.0.business_id, query)
+        .await?;
+    Ok(Json(transfers))
+}
+
+/// A transfer the business is a party to
+pub async fn get_lot_transfer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(transfer_id): Path<Uuid>,
+) -> AppResult<Json<LotTransfer>> {
+    let service = LotTransferService::new(state.db);
+    let transfer = service
+        .get_transfer(current_user.0.business_id, transfer_id)
+        .await?;
+    Ok(Json(transfer))
+}
+
+/// Offer a lot to another business on the platform
+pub async fn initiate_lot_transfer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<InitiateTransferInput>,
+) -> AppResult<(StatusCode, Json<LotTransfer>)> {
+    let service = LotTransferService::new(state.db);
+    let transfer = service
+        .initiate_transfer(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(transfer)))
+}
+
+/// Accept a lot offered to the business
+pub async fn accept_lot_transfer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(transfer_id): Path<Uuid>,
+    Json(input): Json<RespondTransferInput>,
+) -> AppResult<Json<LotTransfer>> {
+    let service = LotTransferService::new(state.db);
+    let transfer = service
+        .accept_transfer(current_user.0.business_id, current_user.0.user_id, transfer_id, input)
+        .await?;
+    Ok(Json(transfer))
+}
+
+/// Decline a lot offered to the business
+pub async fn decline_lot_transfer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(transfer_id): Path<Uuid>,
+    Json(input): Json<RespondTransferInput>,
+) -> AppResult<Json<LotTransfer>> {
+    let service = LotTransferService::new(state.db);
+    let transfer = service
+        .decline_transfer(current_user.0.business_id, current_user.0.user_id, transfer_id, input)
+        .await?;
+    Ok(Json(transfer))
+}
+
+/// Withdraw a pending offer of the business
+pub async fn cancel_lot_transfer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(transfer_id): Path<Uuid>,
+    Json(input): Json<RespondTransferInput>,
+) -> AppResult<Json<LotTransfer>> {
+    let service = LotTransferService::new(state.db);
+    let transfer = service
+        .cancel_transfer(current_user.0.business_id, current_user.0.user_id, transfer_id, input)
+        .await?;
+    Ok(Json(transfer))
+}
+
+/// Read-only traceability record of the seller's lot
+pub async fn get_lot_transfer_origin(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(transfer_id): Path<Uuid>,
+) -> AppResult<Json<TraceabilityView>> {
+    let service = LotTransferService::new(state.db);
+    let origin = service
+        .origin_traceability(current_user.0.business_id, transfer_id)
+        .await?;
+    Ok(Json(origin))
+}
@@ -0,0 +1,225 @@
+//! HTTP handlers for marketplace listings, the public producer directory,
+//! sales leads and offer negotiation
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::marketplace::{
+        CreateListingInput, InquiryInput, ListingSearchQuery, MarketplaceListing, ProducerSearchQuery,
+        PublicListing, PublicProducer, UpdateListingInput,
+    },
+    services::sales::{LeadQuery, SalesLead, UpdateLeadStatusInput},
+    services::sales_negotiation::{NegotiationThread, OfferInput, RespondOfferInput, SalesOrder},
+    services::{MarketplaceService, SalesNegotiationService, SalesService},
+    AppState,
+};
+
+/// List a lot on the marketplace
+pub async fn create_marketplace_listing(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateListingInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = MarketplaceService::new(state.db);
+    let listing = service
+        .create_listing(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(listing)))
+}
+
+/// List the business's marketplace listings
+pub async fn list_marketplace_listings(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<MarketplaceListing>>> {
+    let service = MarketplaceService::new(state.db);
+    let listings = service.list_listings(current_user.0.business_id).await?;
+    Ok(Json(listings))
+}
+
+/// Change a marketplace listing
+pub async fn update_marketplace_listing(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(listing_id): Path<Uuid>,
+    Json(input): Json<UpdateListingInput>,
+) -> AppResult<Json<MarketplaceListing>> {
+    let service = MarketplaceService::new(state.db);
+    let listing = service
+        .update_listing(current_user.0.business_id, listing_id, input)
+        .await?;
+    Ok(Json(listing))
+}
+
+/// Remove a marketplace listing
+pub async fn delete_marketplace_listing(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(listing_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = MarketplaceService::new(state.db);
+    service.delete_listing(current_user.0.business_id, listing_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Search public listings
+/// This endpoint is unauthenticated
+pub async fn search_public_listings(
+    State(state): State<AppState>,
+    Query(query): Query<ListingSearchQuery>,
+) -> AppResult<Json<Vec<PublicListing>>> {
+    let service = MarketplaceService::new(state.db);
+    let listings = service.search_listings(&query).await?;
+    Ok(Json(listings))
+}
+
+/// Get one public listing
+/// This endpoint is unauthenticated
+pub async fn get_public_listing(
+    State(state): State<AppState>,
+    Path(listing_id): Path<Uuid>,
+) -> AppResult<Json<PublicListing>> {
+    let service = MarketplaceService::new(state.db);
+    let listing = service.get_public_listing(listing_id).await?;
+    Ok(Json(listing))
+}
+
+/// Public producer directory
+/// This endpoint is unauthenticated
+pub async fn list_public_producers(
+    State(state): State<AppState>,
+    Query(query): Query<ProducerSearchQuery>,
+) -> AppResult<Json<Vec<PublicProducer>>> {
+    let service = MarketplaceService::new(state.db);
+    let producers = service.list_producers(&query).await?;
+    Ok(Json(producers))
+}
+
+/// Send an inquiry on a listing to its producer
+/// This endpoint is unauthenticated
+pub async fn submit_listing_inquiry(
+    State(state): State<AppState>,
+    Path(listing_id): Path<Uuid>,
+    Json(input): Json<InquiryInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = MarketplaceService::new(state.db);
+    let receipt = service.submit_inquiry(listing_id, input).await?;
+    Ok((StatusCode::CREATED, Json(receipt)))
+}
+
+/// List sales leads, optionally by status
+pub async fn list_sales_leads(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<LeadQuery>,
+) -> AppResult<Json<Vec<SalesLead>>> {
+    let service = SalesService::new(state.db);
+    let leads = service.list_leads(current_user.0.business_id, &query).await?;
+    Ok(Json(leads))
+}
+
+/// Move a sales lead to another status
+pub async fn update_sales_lead_status(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lead_id): Path<Uuid>,
+    Json(input): Json<UpdateLeadStatusInput>,
+) -> AppResult<Json<SalesLead>> {
+    let service = SalesService::new(state.db);
+    let lead = service
+        .update_lead_status(current_user.0.business_id, lead_id, input)
+        .await?;
+    Ok(Json(lead))
+}
+
+/// Negotiation thread of a lead
+pub async fn get_sales_lead_thread(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lead_id): Path<Uuid>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service.get_thread(current_user.0.business_id, lead_id).await?;
+    Ok(Json(thread))
+}
+
+/// Make or counter an offer to the buyer
+pub async fn make_producer_offer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lead_id): Path<Uuid>,
+    Json(input): Json<OfferInput>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service
+        .producer_offer(current_user.0.business_id, current_user.0.user_id, lead_id, input)
+        .await?;
+    Ok(Json(thread))
+}
+
+/// Accept or reject the buyer's open offer
+pub async fn respond_to_buyer_offer(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((lead_id, offer_id)): Path<(Uuid, Uuid)>,
+    Json(input): Json<RespondOfferInput>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service
+        .producer_respond(current_user.0.business_id, lead_id, offer_id, input)
+        .await?;
+    Ok(Json(thread))
+}
+
+/// List sales orders
+pub async fn list_sales_orders(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<SalesOrder>>> {
+    let service = SalesNegotiationService::new(state.db);
+    let orders = service.list_orders(current_user.0.business_id).await?;
+    Ok(Json(orders))
+}
+
+/// Negotiation behind a buyer's inquiry link
+/// This endpoint is unauthenticated - the token is the buyer's credential
+pub async fn get_buyer_inquiry(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service.get_buyer_thread(&token).await?;
+    Ok(Json(thread))
+}
+
+/// Buyer makes or counters an offer
+/// This endpoint is unauthenticated - the token is the buyer's credential
+pub async fn make_buyer_offer(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(input): Json<OfferInput>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service.buyer_offer(&token, input).await?;
+    Ok(Json(thread))
+}
+
+/// Buyer accepts or rejects the producer's open offer
+/// This endpoint is unauthenticated - the token is the buyer's credential
+pub async fn respond_to_producer_offer(
+    State(state): State<AppState>,
+    Path((token, offer_id)): Path<(String, Uuid)>,
+    Json(input): Json<RespondOfferInput>,
+) -> AppResult<Json<NegotiationThread>> {
+    let service = SalesNegotiationService::new(state.db);
+    let thread = service.buyer_respond(&token, offer_id, input).await?;
+    Ok(Json(thread))
+}
@@ -0,0 +1,182 @@
+//! Team member and invitation handlers
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::handlers::auth::{session_device, LoginResponse};
+use crate::middleware::CurrentUser;
+use crate::services::member::{
+    AcceptInvitationInput, CreatedInvitation, InvitationPreview, InviteMemberInput, Member,
+    MemberInvitation, UpdateMemberInput,
+};
+use crate::services::{AuthService, MemberService};
+use crate::AppState;
+
+/// Response for list of members
+#[derive(Serialize)]
+pub struct MembersResponse {
+    pub members: Vec<Member>,
+}
+
+/// Response for list of pending invitations
+#[derive(Serialize)]
+pub struct InvitationsResponse {
+    pub invitations: Vec<MemberInvitation>,
+}
+
+#[derive(Deserialize)]
+pub struct InvitationTokenRequest {
+    pub token: String,
+}
+
+/// Response for an accepted invitation
+#[derive(Serialize)]
+pub struct AcceptInvitationResponse {
+    pub business_id: String,
+    pub user_id: String,
+    #[serde(flatten)]
+    pub tokens: LoginResponse,
+}
+
+/// List members of the current business
+pub async fn list_members(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<MembersResponse>, AppError> {
+    if !user.has_permission("user", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let members = service.list_members(user.business_id).await?;
+
+    Ok(Json(MembersResponse { members }))
+}
+
+/// Change a member's role or deactivate them
+pub async fn update_member(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(member_id): Path<Uuid>,
+    Json(input): Json<UpdateMemberInput>,
+) -> Result<Json<Member>, AppError> {
+    if !user.has_permission("user", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let member = service
+        .update_member(user.business_id, user.user_id, member_id, input)
+        .await?;
+
+    Ok(Json(member))
+}
+
+/// Lift a member's sign-in lockout
+pub async fn unlock_member(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(member_id): Path<Uuid>,
+) -> Result<Json<Member>, AppError> {
+    if !user.has_permission("user", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let member = service.unlock_member(user.business_id, member_id).await?;
+
+    Ok(Json(member))
+}
+
+/// Invite a coworker by email or LINE
+pub async fn invite_member(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(input): Json<InviteMemberInput>,
+) -> Result<(StatusCode, Json<CreatedInvitation>), AppError> {
+    if !user.has_permission("user", "create") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let invitation = service.invite(user.business_id, user.user_id, input).await?;
+
+    Ok((StatusCode::CREATED, Json(invitation)))
+}
+
+/// List pending invitations of the current business
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<InvitationsResponse>, AppError> {
+    if !user.has_permission("user", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let invitations = service.list_pending(user.business_id).await?;
+
+    Ok(Json(InvitationsResponse { invitations }))
+}
+
+/// Revoke a pending invitation
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(invitation_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if !user.has_permission("user", "delete") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let service = MemberService::new(state.db.clone(), &state.config);
+    service.revoke(user.business_id, invitation_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Show an invitation to the invitee (public)
+pub async fn preview_invitation(
+    State(state): State<AppState>,
+    Json(body): Json<InvitationTokenRequest>,
+) -> Result<Json<InvitationPreview>, AppError> {
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let preview = service.preview(&body.token).await?;
+
+    Ok(Json(preview))
+}
+
+/// Accept an invitation and sign the new member in (public)
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(input): Json<AcceptInvitationInput>,
+) -> Result<(StatusCode, Json<AcceptInvitationResponse>), AppError> {
+    let password = input.password.clone();
+    let service = MemberService::new(state.db.clone(), &state.config);
+    let accepted = service.accept(input).await?;
+
+    let auth_service = AuthService::new(state.db.clone(), &state.config);
+    let device = session_device(&headers, None);
+    let tokens = auth_service.login(&accepted.email, &password, None, &device).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AcceptInvitationResponse {
+            business_id: accepted.business_id.to_string(),
+            user_id: accepted.user_id.to_string(),
+            tokens: LoginResponse {
+                access_token: tokens.access_token,
+                refresh_token: tokens.refresh_token,
+                token_type: tokens.token_type,
+                expires_in: tokens.expires_in,
+            },
+        }),
+    ))
+}
@@ -1,41 +1,108 @@
 //! HTTP request handlers for the Coffee Quality Management Platform
 
+pub mod activity_feed;
 pub mod auth;
+pub mod benchmarking;
+pub mod business;
 pub mod certification;
 pub mod cupping;
+pub mod data_quality;
+pub mod etag;
+pub mod farm_activity;
 pub mod grading;
+pub mod grading_standard;
 pub mod harvest;
+pub mod harvest_labor;
 pub mod health;
 pub mod inventory;
+pub mod knowledge_base;
+pub mod lab_result;
+pub mod label_sheet;
 pub mod line_chatbot;
 pub mod line_oauth;
 pub mod lot;
+pub mod lot_gallery;
+pub mod lot_insurance;
+pub mod lot_transfer;
+pub mod marketplace;
+pub mod member;
+pub mod moisture;
 pub mod notification;
+pub mod order;
 pub mod plot;
+pub mod plot_cost;
+pub mod pricing;
 pub mod processing;
+pub mod processing_capacity;
+pub mod quality;
+pub mod recycle_bin;
+pub mod reference_data;
 pub mod reporting;
+pub mod research;
 pub mod roasting;
 pub mod role;
+pub mod sample;
+pub mod shipment;
+pub mod sop_checklist;
+pub mod storage;
 pub mod sync;
 pub mod traceability;
+pub mod water_quality;
 pub mod weather;
+pub mod weather_station;
+pub mod weight_unit;
 
-pub use auth::{login, register, refresh};
+pub use activity_feed::*;
+pub use auth::{
+    confirm_two_factor, disable_two_factor, enroll_two_factor, forgot_password, get_two_factor_status,
+    list_auth_sessions, login, refresh, regenerate_backup_codes, register, reset_password, revoke_auth_session,
+    send_email_verification, verify_email,
+};
+pub use benchmarking::*;
+pub use business::*;
 pub use certification::*;
 pub use cupping::*;
+pub use data_quality::*;
+pub use farm_activity::*;
 pub use grading::*;
+pub use grading_standard::*;
 pub use health::*;
 pub use harvest::*;
+pub use harvest_labor::*;
 pub use inventory::*;
+pub use knowledge_base::*;
+pub use lab_result::*;
+pub use label_sheet::*;
 pub use line_chatbot::*;
 pub use line_oauth::*;
 pub use lot::*;
+pub use lot_gallery::*;
+pub use lot_insurance::*;
+pub use lot_transfer::*;
+pub use marketplace::*;
+pub use member::*;
+pub use moisture::*;
 pub use notification::*;
+pub use order::*;
 pub use plot::*;
+pub use plot_cost::*;
+pub use pricing::*;
 pub use processing::*;
+pub use processing_capacity::*;
+pub use quality::*;
+pub use recycle_bin::*;
+pub use reference_data::*;
 pub use reporting::*;
+pub use research::*;
 pub use roasting::*;
 pub use role::*;
+pub use sample::*;
+pub use shipment::*;
+pub use sop_checklist::*;
+pub use storage::*;
 pub use sync::*;
 pub use traceability::*;
+pub use water_quality::*;
 pub use weather::*;
+pub use weather_station::*;
+pub use weight_unit::*;
@@ -0,0 +1,74 @@
+//! HTTP handlers for the moisture reading log
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::moisture::{MoistureAlert, MoistureQuery, MoistureReading, MoistureTimeline, RecordMoistureInput},
+    services::MoistureService,
+    AppState,
+};
+
+/// Record a moisture reading on a lot; the response carries a warning when
+/// green beans are above 12.5%
+pub async fn record_moisture_reading(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordMoistureInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = MoistureService::new(state.db);
+    let reading = service
+        .record(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(reading)))
+}
+
+/// List moisture readings, optionally for one lot, stage or period
+pub async fn list_moisture_readings(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<MoistureQuery>,
+) -> AppResult<Json<Vec<MoistureReading>>> {
+    let service = MoistureService::new(state.db);
+    let readings = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(readings))
+}
+
+/// A lot's moisture readings per stage, for charting
+pub async fn get_moisture_timeline(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(lot_id): Path<Uuid>,
+) -> AppResult<Json<MoistureTimeline>> {
+    let service = MoistureService::new(state.db);
+    let timeline = service.timeline(current_user.0.business_id, lot_id).await?;
+    Ok(Json(timeline))
+}
+
+/// Lots whose latest green bean reading is above 12.5%
+pub async fn list_moisture_alerts(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<MoistureAlert>>> {
+    let service = MoistureService::new(state.db);
+    let alerts = service.alerts(current_user.0.business_id).await?;
+    Ok(Json(alerts))
+}
+
+/// Delete a moisture reading
+pub async fn delete_moisture_reading(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(reading_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = MoistureService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, reading_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -4,15 +4,20 @@ use axum::{
     extract::{Path, Query, State},
     Json,
 };
+use chrono::Utc;
 use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppResult;
+use crate::external::SmtpMailer;
 use crate::middleware::CurrentUser;
 use crate::services::notification::{
-    CreateNotificationInput, InAppNotification, NotificationLogEntry,
-    NotificationPreferences, NotificationService, UpdatePreferencesInput,
+    CreateEscalationPolicyInput, CreateNotificationInput, EscalationPolicy, InAppNotification,
+    NotificationLogEntry, NotificationPreferences, NotificationService, UpdatePreferencesInput,
 };
+use crate::services::report_schedule::DEFAULT_UTC_OFFSET_MINUTES;
+use crate::services::weekly_digest::digest_week;
+use crate::services::WeeklyDigestService;
 use crate::AppState;
 
 // ============================================================================
@@ -162,7 +167,7 @@ pub async fn send_notification(
     current_user: CurrentUser,
     Json(input): Json<CreateNotificationInput>,
 ) -> AppResult<Json<SendNotificationResponse>> {
-    let service = NotificationService::new(state.db);
+    let service = NotificationService::with_mailer(state.db, SmtpMailer::from_config(&state.config.email));
     
     // Queue the notification
     let queued = service
@@ -243,12 +248,59 @@ pub async fn trigger_weather_alerts(
     Ok(Json(TriggerResponse { notifications_queued: count }))
 }
 
+/// Trigger lot insurance expiry alerts
+pub async fn trigger_insurance_alerts(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<TriggerResponse>> {
+    let service = NotificationService::new(state.db);
+    let count = service
+        .trigger_insurance_expiry_alerts(current_user.0.business_id)
+        .await?;
+    Ok(Json(TriggerResponse { notifications_queued: count }))
+}
+
+/// Send the owner the KPI summary of the month just ended
+pub async fn trigger_kpi_summary(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<TriggerResponse>> {
+    let service = NotificationService::new(state.db);
+    let count = service
+        .trigger_kpi_summary(current_user.0.business_id)
+        .await?;
+    Ok(Json(TriggerResponse { notifications_queued: count }))
+}
+
+/// Send the owner last week's digest if it has not gone out yet
+pub async fn trigger_weekly_digest(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<TriggerResponse>> {
+    let service = WeeklyDigestService::new(state.db, &state.config);
+    let week = digest_week(Utc::now(), DEFAULT_UTC_OFFSET_MINUTES);
+    let sent = service.send(current_user.0.business_id, week).await?;
+    Ok(Json(TriggerResponse { notifications_queued: sent as i32 }))
+}
+
+/// Escalate unacknowledged high-priority notifications
+pub async fn trigger_escalations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<TriggerResponse>> {
+    let service = NotificationService::with_mailer(state.db, SmtpMailer::from_config(&state.config.email));
+    let count = service
+        .run_escalations(current_user.0.business_id)
+        .await?;
+    Ok(Json(TriggerResponse { notifications_queued: count }))
+}
+
 /// Run all notification triggers
 pub async fn run_all_triggers(
     State(state): State<AppState>,
     current_user: CurrentUser,
 ) -> AppResult<Json<TriggerResponse>> {
-    let service = NotificationService::new(state.db);
+    let service = NotificationService::with_mailer(state.db, SmtpMailer::from_config(&state.config.email));
     let count = service
         .run_all_triggers(current_user.0.business_id)
         .await?;
@@ -260,7 +312,7 @@ pub async fn process_queue(
     State(state): State<AppState>,
     _current_user: CurrentUser,
 ) -> AppResult<Json<ProcessQueueResponse>> {
-    let service = NotificationService::new(state.db);
+    let service = NotificationService::with_mailer(state.db, SmtpMailer::from_config(&state.config.email));
     let sent = service.process_notification_queue(100).await?;
     Ok(Json(ProcessQueueResponse { notifications_sent: sent }))
 }
@@ -270,3 +322,45 @@ pub async fn process_queue(
 pub struct ProcessQueueResponse {
     pub notifications_sent: i32,
 }
+
+// ============================================================================
+// Escalation Policies
+// ============================================================================
+
+/// List escalation policies
+pub async fn list_escalation_policies(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<EscalationPolicy>>> {
+    let service = NotificationService::new(state.db);
+    let policies = service
+        .list_escalation_policies(current_user.0.business_id)
+        .await?;
+    Ok(Json(policies))
+}
+
+/// Create an escalation policy
+pub async fn create_escalation_policy(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateEscalationPolicyInput>,
+) -> AppResult<Json<EscalationPolicy>> {
+    let service = NotificationService::new(state.db);
+    let policy = service
+        .create_escalation_policy(current_user.0.business_id, input)
+        .await?;
+    Ok(Json(policy))
+}
+
+/// Delete an escalation policy
+pub async fn delete_escalation_policy(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(policy_id): Path<Uuid>,
+) -> AppResult<Json<()>> {
+    let service = NotificationService::new(state.db);
+    service
+        .delete_escalation_policy(current_user.0.business_id, policy_id)
+        .await?;
+    Ok(Json(()))
+}
@@ -0,0 +1,79 @@
+//! HTTP handlers for order lot recommendations, blend optimization and
+//! reservations
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::blend_optimizer::{BlendProposal, OptimizeBlendInput},
+    services::lot_recommendation::{
+        CreateReservationInput, LotRecommendations, LotReservation, OrderSpec, ReservationQuery,
+    },
+    services::{BlendOptimizerService, LotRecommendationService},
+    AppState,
+};
+
+/// Rank the lots and blends that can fill an order
+pub async fn recommend_lots(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(spec): Json<OrderSpec>,
+) -> AppResult<Json<LotRecommendations>> {
+    let service = LotRecommendationService::new(state.db);
+    let recommendations = service.recommend(current_user.0.business_id, &spec).await?;
+    Ok(Json(recommendations))
+}
+
+/// Propose the cheapest ratios of the selected lots that land the blend in
+/// a target score band
+pub async fn optimize_blend(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<OptimizeBlendInput>,
+) -> AppResult<Json<Vec<BlendProposal>>> {
+    let service = BlendOptimizerService::new(state.db);
+    let proposals = service.optimize(current_user.0.business_id, &input).await?;
+    Ok(Json(proposals))
+}
+
+/// Reserve part of a lot for a buyer
+pub async fn create_lot_reservation(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateReservationInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = LotRecommendationService::new(state.db);
+    let reservation = service
+        .create_reservation(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(reservation)))
+}
+
+/// List active lot reservations, optionally for one lot
+pub async fn list_lot_reservations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ReservationQuery>,
+) -> AppResult<Json<Vec<LotReservation>>> {
+    let service = LotRecommendationService::new(state.db);
+    let reservations = service.list_reservations(current_user.0.business_id, &query).await?;
+    Ok(Json(reservations))
+}
+
+/// Release a lot reservation
+pub async fn delete_lot_reservation(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(reservation_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = LotRecommendationService::new(state.db);
+    service.delete_reservation(current_user.0.business_id, reservation_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,7 +1,7 @@
 //! Plot management HTTP handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Extension, Json,
@@ -10,6 +10,9 @@ use uuid::Uuid;
 
 use crate::middleware::CurrentUser;
 use crate::services::plot::{CreatePlotInput, CreateVarietyInput, PlotService, UpdatePlotInput};
+use crate::services::plot_import::PlotImportQuery;
+use crate::services::plot_validation::PlotValidationQuery;
+use crate::services::{PlotImportService, PlotValidationService};
 use crate::AppState;
 
 /// List all plots for the current business
@@ -53,6 +56,38 @@ pub async fn create_plot(
     }
 }
 
+/// Import plots from a GeoJSON FeatureCollection (the request body);
+/// `dry_run=true` previews the plots, validation errors and overlaps
+/// without writing
+pub async fn import_plots(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(query): Query<PlotImportQuery>,
+    Json(body): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let service = PlotImportService::new(state.db.clone());
+
+    match service.import(current_user.0.business_id, &body, &query).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Check plots for overlapping outlines and harvests above a plausible
+/// yield per rai
+pub async fn validate_plots(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(query): Query<PlotValidationQuery>,
+) -> impl IntoResponse {
+    let service = PlotValidationService::new(state.db.clone());
+
+    match service.validate(current_user.0.business_id, &query).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
 /// Update a plot
 pub async fn update_plot(
     State(state): State<AppState>,
@@ -76,7 +111,7 @@ pub async fn delete_plot(
 ) -> impl IntoResponse {
     let service = PlotService::new(state.db.clone());
     
-    match service.delete_plot(current_user.0.business_id, plot_id).await {
+    match service.delete_plot(current_user.0.business_id, current_user.0.user_id, plot_id).await {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
@@ -0,0 +1,52 @@
+//! HTTP handlers for production costs per plot and crop season
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::plot_profitability::{PlotCost, PlotCostQuery, RecordPlotCostInput},
+    services::PlotProfitabilityService,
+    AppState,
+};
+
+/// Record a production cost for a plot, or shared by all plots
+pub async fn record_plot_cost(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordPlotCostInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = PlotProfitabilityService::new(state.db);
+    let cost = service
+        .record_cost(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(cost)))
+}
+
+/// List production costs, optionally for one season or plot
+pub async fn list_plot_costs(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<PlotCostQuery>,
+) -> AppResult<Json<Vec<PlotCost>>> {
+    let service = PlotProfitabilityService::new(state.db);
+    let costs = service.list_costs(current_user.0.business_id, &query).await?;
+    Ok(Json(costs))
+}
+
+/// Delete a production cost
+pub async fn delete_plot_cost(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(cost_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = PlotProfitabilityService::new(state.db);
+    service.delete_cost(current_user.0.business_id, cost_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,90 @@
+//! HTTP handlers for realized sale prices and market reference prices
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::pricing::{
+        MarketPriceQuery, MarketReferencePrice, RecordMarketPriceInput, RecordSalePriceInput, SalePrice,
+        SalePriceQuery,
+    },
+    services::PricingService,
+    AppState,
+};
+
+/// Record the price a lot sold for, optionally from a sales order
+pub async fn record_sale_price(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordSalePriceInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = PricingService::new(state.db);
+    let price = service
+        .record_sale_price(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(price)))
+}
+
+/// List realized sale prices, optionally for one lot, grade or period
+pub async fn list_sale_prices(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<SalePriceQuery>,
+) -> AppResult<Json<Vec<SalePrice>>> {
+    let service = PricingService::new(state.db);
+    let prices = service.list_sale_prices(current_user.0.business_id, &query).await?;
+    Ok(Json(prices))
+}
+
+/// Delete a realized sale price
+pub async fn delete_sale_price(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(price_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = PricingService::new(state.db);
+    service.delete_sale_price(current_user.0.business_id, price_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Record a market reference price for a day
+pub async fn record_market_price(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordMarketPriceInput>,
+) -> AppResult<Json<MarketReferencePrice>> {
+    let service = PricingService::new(state.db);
+    let reference = service
+        .record_market_price(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok(Json(reference))
+}
+
+/// List market reference prices, optionally for one benchmark or period
+pub async fn list_market_prices(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<MarketPriceQuery>,
+) -> AppResult<Json<Vec<MarketReferencePrice>>> {
+    let service = PricingService::new(state.db);
+    let prices = service.list_market_prices(current_user.0.business_id, &query).await?;
+    Ok(Json(prices))
+}
+
+/// Delete a market reference price
+pub async fn delete_market_price(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(reference_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = PricingService::new(state.db);
+    service.delete_market_price(current_user.0.business_id, reference_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
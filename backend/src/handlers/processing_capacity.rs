@@ -0,0 +1,77 @@
+//! HTTP handlers for processing resources and capacity planning
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::processing_capacity::{
+        CapacityPlan, CapacityPlanQuery, ProcessingResource, ProcessingResourceInput,
+    },
+    services::ProcessingCapacityService,
+    AppState,
+};
+
+/// Add a fermentation tank or drying bed
+pub async fn create_processing_resource(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<ProcessingResourceInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = ProcessingCapacityService::new(state.db);
+    let resource = service.create_resource(current_user.0.business_id, input).await?;
+    Ok((StatusCode::CREATED, Json(resource)))
+}
+
+/// List fermentation tanks and drying beds
+pub async fn list_processing_resources(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<ProcessingResource>>> {
+    let service = ProcessingCapacityService::new(state.db);
+    let resources = service.list_resources(current_user.0.business_id).await?;
+    Ok(Json(resources))
+}
+
+/// Replace a fermentation tank or drying bed
+pub async fn update_processing_resource(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(resource_id): Path<Uuid>,
+    Json(input): Json<ProcessingResourceInput>,
+) -> AppResult<Json<ProcessingResource>> {
+    let service = ProcessingCapacityService::new(state.db);
+    let resource = service
+        .update_resource(current_user.0.business_id, resource_id, input)
+        .await?;
+    Ok(Json(resource))
+}
+
+/// Delete a fermentation tank or drying bed
+pub async fn delete_processing_resource(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(resource_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = ProcessingCapacityService::new(state.db);
+    service.delete_resource(current_user.0.business_id, resource_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Weekly tank and bed load for the coming weeks, with a warning for each
+/// week over capacity
+pub async fn get_processing_capacity_plan(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<CapacityPlanQuery>,
+) -> AppResult<Json<CapacityPlan>> {
+    let service = ProcessingCapacityService::new(state.db);
+    let plan = service.plan(current_user.0.business_id, &query).await?;
+    Ok(Json(plan))
+}
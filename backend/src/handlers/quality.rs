@@ -0,0 +1,120 @@
+//! HTTP handlers for quality specs and evaluations
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::middleware::CurrentUser;
+use crate::services::quality::{
+    ConformityQuery, CreateQualityEvaluationInput, LotConformity, QualityEvaluation,
+    QualityEvaluationQuery, QualitySpec, QualitySpecInput,
+};
+use crate::services::QualityService;
+use crate::AppState;
+
+/// Create a quality spec
+pub async fn create_quality_spec(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<QualitySpecInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = QualityService::new(state.db);
+    let spec = service.create_spec(current_user.0.business_id, input).await?;
+    Ok((StatusCode::CREATED, Json(spec)))
+}
+
+/// List quality specs
+pub async fn list_quality_specs(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<QualitySpec>>> {
+    let service = QualityService::new(state.db);
+    let specs = service.list_specs(current_user.0.business_id).await?;
+    Ok(Json(specs))
+}
+
+/// Get a quality spec
+pub async fn get_quality_spec(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(spec_id): Path<Uuid>,
+) -> AppResult<Json<QualitySpec>> {
+    let service = QualityService::new(state.db);
+    let spec = service.get_spec(current_user.0.business_id, spec_id).await?;
+    Ok(Json(spec))
+}
+
+/// Replace a quality spec
+pub async fn update_quality_spec(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(spec_id): Path<Uuid>,
+    Json(input): Json<QualitySpecInput>,
+) -> AppResult<Json<QualitySpec>> {
+    let service = QualityService::new(state.db);
+    let spec = service.update_spec(current_user.0.business_id, spec_id, input).await?;
+    Ok(Json(spec))
+}
+
+/// Delete a quality spec
+pub async fn delete_quality_spec(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(spec_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = QualityService::new(state.db);
+    service.delete_spec(current_user.0.business_id, spec_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Evaluate a lot's grading and cupping sample against a spec
+pub async fn create_quality_evaluation(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateQualityEvaluationInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = QualityService::new(state.db);
+    let evaluation = service
+        .evaluate(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(evaluation)))
+}
+
+/// List quality evaluations, optionally for one lot
+pub async fn list_quality_evaluations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<QualityEvaluationQuery>,
+) -> AppResult<Json<Vec<QualityEvaluation>>> {
+    let service = QualityService::new(state.db);
+    let evaluations = service.list_evaluations(current_user.0.business_id, &query).await?;
+    Ok(Json(evaluations))
+}
+
+/// Get a quality evaluation
+pub async fn get_quality_evaluation(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(evaluation_id): Path<Uuid>,
+) -> AppResult<Json<QualityEvaluation>> {
+    let service = QualityService::new(state.db);
+    let evaluation = service.get_evaluation(current_user.0.business_id, evaluation_id).await?;
+    Ok(Json(evaluation))
+}
+
+/// Which lots in stock currently meet which specs; filter specs by
+/// `spec_id`, `buyer` or `market`
+pub async fn get_quality_conformity(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ConformityQuery>,
+) -> AppResult<Json<Vec<LotConformity>>> {
+    let service = QualityService::new(state.db);
+    let lots = service.check_conformity(current_user.0.business_id, &query).await?;
+    Ok(Json(lots))
+}
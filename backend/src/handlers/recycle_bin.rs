@@ -0,0 +1,49 @@
+//! HTTP handlers for the recycle bin
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::recycle_bin::{DeletedRecord, RecycleBinQuery, RestoredRecord},
+    services::RecycleBinService,
+    AppState,
+};
+
+/// List deleted records that can still be restored, optionally of one kind
+pub async fn list_recycle_bin(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<RecycleBinQuery>,
+) -> AppResult<Json<Vec<DeletedRecord>>> {
+    let service = RecycleBinService::new(state.db);
+    let records = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(records))
+}
+
+/// Restore a deleted record with everything its delete removed
+pub async fn restore_deleted_record(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(deleted_id): Path<Uuid>,
+) -> AppResult<Json<RestoredRecord>> {
+    let service = RecycleBinService::new(state.db);
+    let restored = service.restore(current_user.0.business_id, deleted_id).await?;
+    Ok(Json(restored))
+}
+
+/// Remove a deleted record from the recycle bin for good
+pub async fn purge_deleted_record(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(deleted_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = RecycleBinService::new(state.db);
+    service.purge(current_user.0.business_id, deleted_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,34 @@
+//! HTTP handlers for reference data bundles
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::reference_data::ReferenceDataBundle,
+    services::ReferenceDataService,
+    AppState,
+};
+
+/// Bundle version the client already holds
+#[derive(Debug, Deserialize)]
+pub struct ReferenceDataQuery {
+    pub since: Option<String>,
+}
+
+/// All reference catalogs, with items only for catalogs changed since `since`
+pub async fn get_reference_data(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ReferenceDataQuery>,
+) -> AppResult<Json<ReferenceDataBundle>> {
+    let service = ReferenceDataService::new(state.db);
+    let bundle = service
+        .get_bundle(current_user.0.business_id, query.since.as_deref())
+        .await?;
+    Ok(Json(bundle))
+}
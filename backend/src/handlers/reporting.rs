@@ -1,18 +1,36 @@
 //! Reporting handlers for analytics and data export
 
 use axum::{
-    extract::{Query, State},
-    http::header,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Extension, Json,
 };
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
+use shared::CalendarSystem;
+use uuid::Uuid;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::handlers::etag;
 use crate::middleware::auth::AuthUser;
-use crate::services::reporting::{
-    DashboardMetrics, HarvestYieldReport, ProcessingEfficiencyReport, QualityTrendPoint,
-    ReportFilter, ReportingService,
+use crate::services::kpi::{KpiQuery, KpiReport, KpiTargets, KpiTargetsInput};
+use crate::services::picker_quality::{PickerQualityQuery, PickerQualityReport};
+use crate::services::plot_profitability::{PlotProfitabilityQuery, PlotProfitabilityReport};
+use crate::services::pricing::{PricingReport, PricingReportQuery};
+use crate::services::report_builder::{
+    ReportDefinition, ReportEntity, ReportFormat, ReportResult, SaveReportInput, SavedReport,
+    REPORT_ENTITIES,
+};
+use crate::services::reporting::{ReportFilter, ReportingService};
+use crate::services::reporting_period::resolve_date_range;
+use crate::services::report_schedule::{
+    ReportDelivery, ReportSchedule, ReportScheduleInput, DEFAULT_UTC_OFFSET_MINUTES,
+};
+use crate::services::weekly_digest::{digest_week, week_start, WeeklyDigest, WeeklyDigestQuery};
+use crate::services::{
+    BusinessService, KpiService, PickerQualityService, PlotProfitabilityService, PricingService, ReportBuilderService,
+    ReportScheduleService, WeeklyDigestService, XlsxTemplateService,
 };
 use crate::AppState;
 
@@ -20,25 +38,159 @@ use crate::AppState;
 pub struct ReportQuery {
     pub start_date: Option<String>,
     pub end_date: Option<String>,
+    /// Report period such as `season:2024/25`; overrides the dates
+    pub period: Option<String>,
     pub format: Option<String>, // "json" or "csv"
 }
 
+#[derive(Deserialize)]
+pub struct ReportBuilderQuery {
+    pub format: Option<ReportFormat>,
+    pub language: Option<String>, // "th" for Thai column headers
+}
+
+#[derive(Deserialize)]
+pub struct XlsxExportQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    /// Report period such as `fy:2024`; overrides the dates
+    pub period: Option<String>,
+    pub language: Option<String>, // "th" (default) or "en"
+}
+
 #[derive(Deserialize)]
 pub struct QualityTrendQuery {
     pub start_date: Option<String>,
     pub end_date: Option<String>,
+    /// Report period such as `season:2024/25`; overrides the dates
+    pub period: Option<String>,
     pub group_by: Option<String>, // "month", "quarter", "year"
     pub format: Option<String>,
 }
 
+/// Dates of a `period` parameter resolved against the business's
+/// reporting calendar, else the start and end dates given
+async fn report_dates(
+    state: &AppState,
+    business_id: Uuid,
+    period: Option<&str>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> AppResult<(Option<NaiveDate>, Option<NaiveDate>)> {
+    if period.is_none() {
+        return Ok((start_date, end_date));
+    }
+    let calendar = BusinessService::new(state.db.clone()).get_reporting_calendar(business_id).await?;
+    resolve_date_range(period, start_date, end_date, &calendar, Utc::now().date_naive())
+}
+
 /// Get dashboard metrics
 pub async fn get_dashboard(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
-) -> AppResult<Json<DashboardMetrics>> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
     let service = ReportingService::new(state.db.clone());
     let metrics = service.get_dashboard_metrics(user.business_id).await?;
-    Ok(Json(metrics))
+    Ok(etag::conditional_json(&headers, metrics))
+}
+
+/// Get the season's KPIs against their targets
+pub async fn get_kpi_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<KpiQuery>,
+) -> AppResult<Json<KpiReport>> {
+    let service = KpiService::new(state.db.clone());
+    let report = service.report(user.business_id, &query).await?;
+    Ok(Json(report))
+}
+
+/// List KPI targets of every season
+pub async fn list_kpi_targets(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> AppResult<Json<Vec<KpiTargets>>> {
+    let service = KpiService::new(state.db.clone());
+    let targets = service.list_targets(user.business_id).await?;
+    Ok(Json(targets))
+}
+
+/// Set the KPI targets of a season
+pub async fn set_kpi_targets(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(season): Path<i32>,
+    Json(input): Json<KpiTargetsInput>,
+) -> AppResult<Json<KpiTargets>> {
+    if !user.has_permission("business", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = KpiService::new(state.db.clone());
+    let targets = service.set_targets(user.business_id, user.user_id, season, input).await?;
+    Ok(Json(targets))
+}
+
+/// Remove the KPI targets of a season
+pub async fn delete_kpi_targets(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(season): Path<i32>,
+) -> AppResult<StatusCode> {
+    if !user.has_permission("business", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = KpiService::new(state.db.clone());
+    service.delete_targets(user.business_id, season).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Realized sale prices per lot and grade against a market reference
+pub async fn get_pricing_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<PricingReportQuery>,
+) -> AppResult<Json<PricingReport>> {
+    let service = PricingService::new(state.db.clone());
+    let report = service.report(user.business_id, &query).await?;
+    Ok(Json(report))
+}
+
+/// Revenue, costs and profit per plot over a crop season
+pub async fn get_plot_profitability_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<PlotProfitabilityQuery>,
+) -> AppResult<Json<PlotProfitabilityReport>> {
+    let service = PlotProfitabilityService::new(state.db.clone());
+    let report = service.report(user.business_id, &query).await?;
+    Ok(Json(report))
+}
+
+/// Quality score per picker or crew over a crop season, with bonuses
+pub async fn get_picker_quality_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<PickerQualityQuery>,
+) -> AppResult<Json<PickerQualityReport>> {
+    let service = PickerQualityService::new(state.db.clone());
+    let report = service.report(user.business_id, &query).await?;
+    Ok(Json(report))
+}
+
+/// Preview the weekly owner digest (last week by default)
+pub async fn get_weekly_digest(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<WeeklyDigestQuery>,
+) -> AppResult<Json<WeeklyDigest>> {
+    let week = match query.week {
+        Some(day) => week_start(day),
+        None => digest_week(Utc::now(), DEFAULT_UTC_OFFSET_MINUTES),
+    };
+    let service = WeeklyDigestService::new(state.db.clone(), &state.config);
+    let digest = service.build(user.business_id, week).await?;
+    Ok(Json(digest))
 }
 
 /// Get harvest yield report
@@ -49,9 +201,17 @@ pub async fn get_harvest_yield_report(
 ) -> AppResult<impl IntoResponse> {
     let service = ReportingService::new(state.db.clone());
 
+    let (start_date, end_date) = report_dates(
+        &state,
+        user.business_id,
+        query.period.as_deref(),
+        query.start_date.and_then(|s| s.parse().ok()),
+        query.end_date.and_then(|s| s.parse().ok()),
+    )
+    .await?;
     let filter = ReportFilter {
-        start_date: query.start_date.and_then(|s| s.parse().ok()),
-        end_date: query.end_date.and_then(|s| s.parse().ok()),
+        start_date,
+        end_date,
         plot_ids: None,
         varieties: None,
         processing_methods: None,
@@ -78,9 +238,17 @@ pub async fn get_quality_trend_report(
 ) -> AppResult<impl IntoResponse> {
     let service = ReportingService::new(state.db.clone());
 
+    let (start_date, end_date) = report_dates(
+        &state,
+        user.business_id,
+        query.period.as_deref(),
+        query.start_date.and_then(|s| s.parse().ok()),
+        query.end_date.and_then(|s| s.parse().ok()),
+    )
+    .await?;
     let filter = ReportFilter {
-        start_date: query.start_date.and_then(|s| s.parse().ok()),
-        end_date: query.end_date.and_then(|s| s.parse().ok()),
+        start_date,
+        end_date,
         plot_ids: None,
         varieties: None,
         processing_methods: None,
@@ -108,9 +276,17 @@ pub async fn get_processing_efficiency_report(
 ) -> AppResult<impl IntoResponse> {
     let service = ReportingService::new(state.db.clone());
 
+    let (start_date, end_date) = report_dates(
+        &state,
+        user.business_id,
+        query.period.as_deref(),
+        query.start_date.and_then(|s| s.parse().ok()),
+        query.end_date.and_then(|s| s.parse().ok()),
+    )
+    .await?;
     let filter = ReportFilter {
-        start_date: query.start_date.and_then(|s| s.parse().ok()),
-        end_date: query.end_date.and_then(|s| s.parse().ok()),
+        start_date,
+        end_date,
         plot_ids: None,
         varieties: None,
         processing_methods: None,
@@ -128,3 +304,371 @@ pub async fn get_processing_efficiency_report(
         Ok(Json(data).into_response())
     }
 }
+
+/// List entities and columns available in the report builder
+pub async fn list_report_entities(
+    Extension(user): Extension<AuthUser>,
+) -> AppResult<Json<&'static [ReportEntity]>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    Ok(Json(REPORT_ENTITIES))
+}
+
+/// Run an ad-hoc report builder definition
+pub async fn run_report_builder(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ReportBuilderQuery>,
+    Json(definition): Json<ReportDefinition>,
+) -> AppResult<impl IntoResponse> {
+    let format = query.format.unwrap_or_default();
+    if !can_access_report(&user, format) {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    let result = service.run_report(user.business_id, user.role_id, &definition).await?;
+    let display = BusinessService::new(state.db.clone()).get_display_format(user.business_id).await?;
+    render_report(result, format, query.language.as_deref(), &definition.entity, display.calendar)
+}
+
+/// List saved report configurations
+pub async fn list_saved_reports(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> AppResult<Json<Vec<SavedReport>>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    let reports = service.list_saved_reports(user.business_id).await?;
+    Ok(Json(reports))
+}
+
+/// Get a saved report configuration
+pub async fn get_saved_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(report_id): Path<Uuid>,
+) -> AppResult<Json<SavedReport>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    let report = service.get_saved_report(user.business_id, report_id).await?;
+    Ok(Json(report))
+}
+
+/// Save a report configuration
+pub async fn create_saved_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(input): Json<SaveReportInput>,
+) -> AppResult<(StatusCode, Json<SavedReport>)> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    let report = service.save_report(user.business_id, user.user_id, input).await?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Update a saved report configuration
+pub async fn update_saved_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(report_id): Path<Uuid>,
+    Json(input): Json<SaveReportInput>,
+) -> AppResult<Json<SavedReport>> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    let report = service.update_saved_report(user.business_id, report_id, input).await?;
+    Ok(Json(report))
+}
+
+/// Delete a saved report configuration
+pub async fn delete_saved_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(report_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportBuilderService::new(state.db.clone());
+    service.delete_saved_report(user.business_id, report_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Run a saved report configuration, in its default format unless overridden
+pub async fn run_saved_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(report_id): Path<Uuid>,
+    Query(query): Query<ReportBuilderQuery>,
+) -> AppResult<impl IntoResponse> {
+    let service = ReportBuilderService::new(state.db.clone());
+    let saved = service.get_saved_report(user.business_id, report_id).await?;
+    let format = query.format.unwrap_or(match saved.default_format.as_str() {
+        "csv" => ReportFormat::Csv,
+        "xlsx" => ReportFormat::Xlsx,
+        _ => ReportFormat::Json,
+    });
+    if !can_access_report(&user, format) {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let result = service.run_report(user.business_id, user.role_id, &saved.definition).await?;
+    let display = BusinessService::new(state.db.clone()).get_display_format(user.business_id).await?;
+    render_report(result, format, query.language.as_deref(), &saved.name, display.calendar)
+}
+
+/// Viewing a report needs report:view; file downloads need report:export
+fn can_access_report(user: &AuthUser, format: ReportFormat) -> bool {
+    let required = match format {
+        ReportFormat::Json => "view",
+        ReportFormat::Csv | ReportFormat::Xlsx => "export",
+    };
+    user.has_permission("report", required)
+}
+
+/// Render a report result in the requested format
+fn render_report(
+    result: ReportResult,
+    format: ReportFormat,
+    language: Option<&str>,
+    name: &str,
+    calendar: CalendarSystem,
+) -> AppResult<axum::response::Response> {
+    let thai = language == Some("th");
+    let filename: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    match format {
+        ReportFormat::Json => Ok(Json(result).into_response()),
+        ReportFormat::Csv => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", filename)),
+            ],
+            result.to_csv(thai, calendar)?,
+        )
+            .into_response()),
+        ReportFormat::Xlsx => Ok((
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+                ),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.xlsx\"", filename)),
+            ],
+            result.to_xlsx(&result.entity, thai, calendar)?,
+        )
+            .into_response()),
+    }
+}
+
+/// List report delivery schedules
+pub async fn list_report_schedules(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> AppResult<Json<Vec<ReportSchedule>>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let schedules = service.list_schedules(user.business_id).await?;
+    Ok(Json(schedules))
+}
+
+/// Get a report delivery schedule
+pub async fn get_report_schedule(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(schedule_id): Path<Uuid>,
+) -> AppResult<Json<ReportSchedule>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let schedule = service.get_schedule(user.business_id, schedule_id).await?;
+    Ok(Json(schedule))
+}
+
+/// Schedule delivery of a saved report
+pub async fn create_report_schedule(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(input): Json<ReportScheduleInput>,
+) -> AppResult<(StatusCode, Json<ReportSchedule>)> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let schedule = service.create_schedule(user.business_id, user.user_id, input).await?;
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// Update a report delivery schedule
+pub async fn update_report_schedule(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(schedule_id): Path<Uuid>,
+    Json(input): Json<ReportScheduleInput>,
+) -> AppResult<Json<ReportSchedule>> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let schedule = service.update_schedule(user.business_id, schedule_id, input).await?;
+    Ok(Json(schedule))
+}
+
+/// Delete a report delivery schedule
+pub async fn delete_report_schedule(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(schedule_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    service.delete_schedule(user.business_id, schedule_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Deliver a scheduled report immediately without changing its schedule
+pub async fn run_report_schedule(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(schedule_id): Path<Uuid>,
+) -> AppResult<Json<ReportDelivery>> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let schedule = service.get_schedule(user.business_id, schedule_id).await?;
+    let delivery = service.deliver(&schedule).await?;
+    Ok(Json(delivery))
+}
+
+/// Delivery history of a schedule
+pub async fn list_report_deliveries(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(schedule_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ReportDelivery>>> {
+    if !user.has_permission("report", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let deliveries = service.list_deliveries(user.business_id, schedule_id).await?;
+    Ok(Json(deliveries))
+}
+
+/// Download a delivered report from a LINE link (public, token-protected)
+pub async fn download_scheduled_report(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let service = ReportScheduleService::new(state.db.clone(), &state.config);
+    let download = service.get_download(&token).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, download.content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", download.file_name),
+            ),
+        ],
+        download.file_data,
+    ))
+}
+
+/// Export the inventory summary workbook (balances by lot and stage, movements)
+pub async fn export_inventory_summary(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<XlsxExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let (start_date, end_date) =
+        report_dates(&state, user.business_id, query.period.as_deref(), query.start_date, query.end_date).await?;
+    let service = XlsxTemplateService::new(state.db.clone());
+    let bytes = service
+        .inventory_summary(
+            user.business_id,
+            user.role_id,
+            start_date,
+            end_date,
+            query.language.as_deref() != Some("en"),
+        )
+        .await?;
+    Ok(xlsx_attachment(bytes, "inventory_summary"))
+}
+
+/// Export the results workbook of a cupping session
+pub async fn export_cupping_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<XlsxExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let service = XlsxTemplateService::new(state.db.clone());
+    let bytes = service
+        .cupping_session(
+            user.business_id,
+            user.role_id,
+            session_id,
+            query.language.as_deref() != Some("en"),
+        )
+        .await?;
+    Ok(xlsx_attachment(bytes, &format!("cupping_session_{}", session_id)))
+}
+
+/// Export the financials workbook (monthly summary, sales, purchases)
+pub async fn export_financials(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<XlsxExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    if !user.has_permission("report", "export") {
+        return Err(AppError::InsufficientPermissions);
+    }
+    let (start_date, end_date) =
+        report_dates(&state, user.business_id, query.period.as_deref(), query.start_date, query.end_date).await?;
+    let service = XlsxTemplateService::new(state.db.clone());
+    let bytes = service
+        .financials(
+            user.business_id,
+            user.role_id,
+            start_date,
+            end_date,
+            query.language.as_deref() != Some("en"),
+        )
+        .await?;
+    Ok(xlsx_attachment(bytes, "financials"))
+}
+
+fn xlsx_attachment(bytes: Vec<u8>, filename: &str) -> axum::response::Response {
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.xlsx\"", filename)),
+        ],
+        bytes,
+    )
+        .into_response()
+}
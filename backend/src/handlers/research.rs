@@ -0,0 +1,117 @@
+//! Research partner API handlers
+//!
+//! Datasets are read with a partner API key in the `X-Api-Key` header;
+//! partners are managed with the configured admin token in the
+//! `X-Research-Admin-Token` header. Neither uses user sessions.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::research::{
+    admin_token_matches, CreateResearchPartnerInput, IssuedResearchKey, ProcessingRecord, QualityRecord,
+    ResearchDataset, ResearchDatasetQuery, ResearchPartner, WeatherRecord,
+};
+use crate::services::ResearchService;
+use crate::AppState;
+
+const API_KEY_HEADER: &str = "x-api-key";
+const ADMIN_TOKEN_HEADER: &str = "x-research-admin-token";
+
+/// API key sent by a research partner
+fn api_key(headers: &HeaderMap) -> AppResult<&str> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| AppError::Unauthorized {
+            message: "Send the research API key in the X-Api-Key header".to_string(),
+            message_th: "กรุณาส่งคีย์ API สำหรับงานวิจัยในส่วนหัว X-Api-Key".to_string(),
+        })
+}
+
+/// Refuse requests without the configured admin token
+fn require_admin(state: &AppState, headers: &HeaderMap) -> AppResult<()> {
+    let presented = headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if admin_token_matches(&state.config.research.admin_token, presented) {
+        Ok(())
+    } else {
+        Err(AppError::InsufficientPermissions)
+    }
+}
+
+/// De-identified cupping samples of opted-in businesses
+pub async fn get_research_quality_dataset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ResearchDatasetQuery>,
+) -> AppResult<Json<ResearchDataset<QualityRecord>>> {
+    let service = ResearchService::new(state.db.clone());
+    let dataset = service.quality_dataset(api_key(&headers)?, &query).await?;
+    Ok(Json(dataset))
+}
+
+/// De-identified processing batches of opted-in businesses
+pub async fn get_research_processing_dataset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ResearchDatasetQuery>,
+) -> AppResult<Json<ResearchDataset<ProcessingRecord>>> {
+    let service = ResearchService::new(state.db.clone());
+    let dataset = service.processing_dataset(api_key(&headers)?, &query).await?;
+    Ok(Json(dataset))
+}
+
+/// Monthly province weather of opted-in businesses
+pub async fn get_research_weather_dataset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ResearchDatasetQuery>,
+) -> AppResult<Json<ResearchDataset<WeatherRecord>>> {
+    let service = ResearchService::new(state.db.clone());
+    let dataset = service.weather_dataset(api_key(&headers)?, &query).await?;
+    Ok(Json(dataset))
+}
+
+/// Register a research partner; the response holds its API key once
+pub async fn create_research_partner(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(input): Json<CreateResearchPartnerInput>,
+) -> AppResult<(StatusCode, Json<IssuedResearchKey>)> {
+    require_admin(&state, &headers)?;
+    let service = ResearchService::new(state.db.clone());
+    let issued = service.create_partner(input).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+/// List research partners
+pub async fn list_research_partners(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<ResearchPartner>>> {
+    require_admin(&state, &headers)?;
+    let service = ResearchService::new(state.db.clone());
+    let partners = service.list_partners().await?;
+    Ok(Json(partners))
+}
+
+/// Revoke a research partner's API key
+pub async fn revoke_research_partner(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(partner_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_admin(&state, &headers)?;
+    let service = ResearchService::new(state.db.clone());
+    service.revoke_partner(partner_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
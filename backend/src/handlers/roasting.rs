@@ -12,7 +12,7 @@ use crate::middleware::CurrentUser;
 use crate::services::roasting::{
     CompleteRoastInput, CreateTemplateInput, CuppingSampleSummary, LogMilestonesInput,
     LogTemperatureInput, RoastProfileTemplate, RoastSession, RoastingService,
-    StartRoastSessionInput, UpdateTemplateInput,
+    StartRoastSessionInput, TemperatureCurve, UpdateTemplateInput,
 };
 use crate::AppState;
 
@@ -160,6 +160,27 @@ pub async fn log_temperature(
     Ok(Json(session))
 }
 
+/// Query parameters for reading a roast curve
+#[derive(Debug, Deserialize)]
+pub struct TemperatureLogQuery {
+    /// Bucket width to average readings over, e.g. `10s` or `1m`
+    pub resolution: Option<String>,
+}
+
+/// Get the roast curve, optionally downsampled
+pub async fn get_temperature_log(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<TemperatureLogQuery>,
+) -> AppResult<Json<TemperatureCurve>> {
+    let service = RoastingService::new(state.db);
+    let curve = service
+        .get_temperature_log(current_user.0.business_id, session_id, query.resolution.as_deref())
+        .await?;
+    Ok(Json(curve))
+}
+
 /// Log roast milestones
 pub async fn log_milestones(
     State(state): State<AppState>,
@@ -10,7 +10,10 @@ use uuid::Uuid;
 
 use crate::error::AppError;
 use crate::middleware::CurrentUser;
-use crate::services::role::{CreateRoleInput, Permission, Role, RoleWithPermissions, UpdateRoleInput};
+use crate::services::role::{
+    AppliedRoleTemplate, CreateFieldVisibilityInput, CreateRoleInput, FieldVisibilityPolicy,
+    Permission, Role, RoleTemplate, RoleWithPermissions, UpdateRoleInput,
+};
 use crate::services::RoleService;
 use crate::AppState;
 
@@ -26,6 +29,24 @@ pub struct PermissionsResponse {
     pub permissions: Vec<Permission>,
 }
 
+/// Response for list of role templates
+#[derive(Serialize)]
+pub struct RoleTemplatesResponse {
+    pub templates: Vec<RoleTemplate>,
+}
+
+/// Response for applying role templates
+#[derive(Serialize)]
+pub struct ApplyRoleTemplatesResponse {
+    pub templates: Vec<AppliedRoleTemplate>,
+}
+
+/// Response for list of field visibility policies
+#[derive(Serialize)]
+pub struct FieldVisibilityPoliciesResponse {
+    pub policies: Vec<FieldVisibilityPolicy>,
+}
+
 /// Get all roles for the current business
 pub async fn list_roles(
     State(state): State<AppState>,
@@ -124,3 +145,87 @@ pub async fn delete_role(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Get the built-in role templates
+pub async fn list_role_templates(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<RoleTemplatesResponse>, AppError> {
+    if !user.has_permission("role", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let role_service = RoleService::new(state.db.clone());
+    let templates = role_service.get_role_templates().await?;
+
+    Ok(Json(RoleTemplatesResponse { templates }))
+}
+
+/// Re-apply role templates, creating missing roles and granting new permissions
+pub async fn apply_role_templates(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<ApplyRoleTemplatesResponse>, AppError> {
+    if !user.has_permission("role", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let role_service = RoleService::new(state.db.clone());
+    let templates = role_service.apply_role_templates(user.business_id).await?;
+
+    Ok(Json(ApplyRoleTemplatesResponse { templates }))
+}
+
+/// List field visibility policies for the current business
+pub async fn list_field_visibility_policies(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<FieldVisibilityPoliciesResponse>, AppError> {
+    if !user.has_permission("role", "view") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let role_service = RoleService::new(state.db.clone());
+    let policies = role_service
+        .get_field_visibility_policies(user.business_id)
+        .await?;
+
+    Ok(Json(FieldVisibilityPoliciesResponse { policies }))
+}
+
+/// Hide a response field from a role
+pub async fn create_field_visibility_policy(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(role_id): Path<Uuid>,
+    Json(input): Json<CreateFieldVisibilityInput>,
+) -> Result<(StatusCode, Json<FieldVisibilityPolicy>), AppError> {
+    if !user.has_permission("role", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let role_service = RoleService::new(state.db.clone());
+    let policy = role_service
+        .create_field_visibility_policy(user.business_id, user.user_id, role_id, input)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+/// Remove a field visibility policy
+pub async fn delete_field_visibility_policy(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(policy_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if !user.has_permission("role", "edit") {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let role_service = RoleService::new(state.db.clone());
+    role_service
+        .delete_field_visibility_policy(user.business_id, policy_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
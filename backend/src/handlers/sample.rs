@@ -0,0 +1,63 @@
+//! HTTP handlers for lab sample endpoints
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::middleware::CurrentUser;
+use crate::services::sample::{DrawSampleInput, LotSample, SampleQuery, UpdateSampleStatusInput};
+use crate::services::SampleService;
+use crate::AppState;
+
+/// List samples, optionally of one lot or in one status
+pub async fn list_samples(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<SampleQuery>,
+) -> AppResult<Json<Vec<LotSample>>> {
+    let service = SampleService::new(state.db);
+    let samples = service.list_samples(current_user.0.business_id, query).await?;
+    Ok(Json(samples))
+}
+
+/// Draw a sample from a lot
+pub async fn draw_sample(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<DrawSampleInput>,
+) -> AppResult<(StatusCode, Json<LotSample>)> {
+    let service = SampleService::new(state.db);
+    let sample = service
+        .draw_sample(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(sample)))
+}
+
+/// Get a sample
+pub async fn get_sample(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(sample_id): Path<Uuid>,
+) -> AppResult<Json<LotSample>> {
+    let service = SampleService::new(state.db);
+    let sample = service.get_sample(current_user.0.business_id, sample_id).await?;
+    Ok(Json(sample))
+}
+
+/// Move a sample on in the lab workflow
+pub async fn update_sample_status(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(sample_id): Path<Uuid>,
+    Json(input): Json<UpdateSampleStatusInput>,
+) -> AppResult<Json<LotSample>> {
+    let service = SampleService::new(state.db);
+    let sample = service
+        .update_status(current_user.0.business_id, sample_id, input)
+        .await?;
+    Ok(Json(sample))
+}
@@ -0,0 +1,159 @@
+//! HTTP handlers for shipments and logistics tracking
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::shipment::{
+        BuyerShipmentView, CarrierStatusAck, CarrierStatusUpdate, CreateShipmentInput, RecordMilestoneInput,
+        Shipment, ShipmentQuery, UpdateShipmentInput,
+    },
+    services::shipment_conditions::{LoggerImportQuery, LoggerImportResult, TransitConditionsReport},
+    services::{ShipmentConditionsService, ShipmentService},
+    AppState,
+};
+
+/// Create a shipment of lot packages
+pub async fn create_shipment(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateShipmentInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = ShipmentService::new(state.db);
+    let shipment = service
+        .create(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(shipment)))
+}
+
+/// List shipments, optionally by status, lot or sales order
+pub async fn list_shipments(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ShipmentQuery>,
+) -> AppResult<Json<Vec<Shipment>>> {
+    let service = ShipmentService::new(state.db);
+    let shipments = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(shipments))
+}
+
+/// Get a shipment with its packages and milestones
+pub async fn get_shipment(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+) -> AppResult<Json<Shipment>> {
+    let service = ShipmentService::new(state.db);
+    let shipment = service.get(current_user.0.business_id, shipment_id).await?;
+    Ok(Json(shipment))
+}
+
+/// Update the carrier details of a shipment
+pub async fn update_shipment(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+    Json(input): Json<UpdateShipmentInput>,
+) -> AppResult<Json<Shipment>> {
+    let service = ShipmentService::new(state.db);
+    let shipment = service
+        .update(current_user.0.business_id, shipment_id, input)
+        .await?;
+    Ok(Json(shipment))
+}
+
+/// Delete a shipment
+pub async fn delete_shipment(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = ShipmentService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, shipment_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Record a shipment milestone by hand
+pub async fn record_shipment_milestone(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+    Json(input): Json<RecordMilestoneInput>,
+) -> AppResult<Json<Shipment>> {
+    let service = ShipmentService::new(state.db);
+    let shipment = service
+        .record_milestone(current_user.0.business_id, shipment_id, current_user.0.user_id, input)
+        .await?;
+    Ok(Json(shipment))
+}
+
+/// Import a data logger CSV export for a shipment, or preview it with
+/// `dry_run`
+pub async fn import_shipment_logger_data(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+    Query(query): Query<LoggerImportQuery>,
+    body: String,
+) -> AppResult<impl IntoResponse> {
+    let service = ShipmentConditionsService::new(state.db);
+    let result: LoggerImportResult = service
+        .import(current_user.0.business_id, shipment_id, current_user.0.user_id, &body, &query)
+        .await?;
+    let status = if result.dry_run { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(result)))
+}
+
+/// Delete a data logger import
+pub async fn delete_shipment_logger_data(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((shipment_id, import_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let service = ShipmentConditionsService::new(state.db);
+    service
+        .delete_import(current_user.0.business_id, shipment_id, import_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Transit-conditions report from the shipment's data loggers
+pub async fn get_shipment_conditions(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(shipment_id): Path<Uuid>,
+) -> AppResult<Json<TransitConditionsReport>> {
+    let service = ShipmentConditionsService::new(state.db);
+    let report = service.report(current_user.0.business_id, shipment_id).await?;
+    Ok(Json(report))
+}
+
+/// Carrier status update
+/// This endpoint is unauthenticated - the token is the carrier's credential
+pub async fn handle_shipment_webhook(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(update): Json<CarrierStatusUpdate>,
+) -> AppResult<Json<CarrierStatusAck>> {
+    let service = ShipmentService::new(state.db);
+    let ack = service.carrier_update(&token, update).await?;
+    Ok(Json(ack))
+}
+
+/// Buyer's view of a shipment
+/// This endpoint is unauthenticated - the token is the buyer's credential
+pub async fn get_shared_shipment(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Json<BuyerShipmentView>> {
+    let service = ShipmentService::new(state.db);
+    let view = service.buyer_view(&token).await?;
+    Ok(Json(view))
+}
@@ -0,0 +1,173 @@
+//! HTTP handlers for SOP checklists and their execution records
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::sop_checklist::{
+        CheckItemsInput, ChecklistCompliance, ChecklistFromSopInput, ComplianceQuery, CreateChecklistInput,
+        ExecutionQuery, ExecutionSummary, SopChecklist, SopExecution, StartExecutionInput, UpdateChecklistInput,
+    },
+    services::SopChecklistService,
+    AppState,
+};
+
+/// Filters for listing checklists
+#[derive(Debug, Deserialize)]
+pub struct ChecklistListQuery {
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+/// Checklists of the business with their steps
+pub async fn list_sop_checklists(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ChecklistListQuery>,
+) -> AppResult<Json<Vec<SopChecklist>>> {
+    let service = SopChecklistService::new(state.db);
+    let checklists = service
+        .list_checklists(current_user.0.business_id, query.include_inactive)
+        .await?;
+    Ok(Json(checklists))
+}
+
+/// A checklist with its steps
+pub async fn get_sop_checklist(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(checklist_id): Path<Uuid>,
+) -> AppResult<Json<SopChecklist>> {
+    let service = SopChecklistService::new(state.db);
+    let checklist = service
+        .get_checklist(current_user.0.business_id, checklist_id)
+        .await?;
+    Ok(Json(checklist))
+}
+
+/// Write a checklist
+pub async fn create_sop_checklist(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateChecklistInput>,
+) -> AppResult<(StatusCode, Json<SopChecklist>)> {
+    let service = SopChecklistService::new(state.db);
+    let checklist = service
+        .create_checklist(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(checklist)))
+}
+
+/// Make a checklist from the numbered steps of an SOP
+pub async fn create_sop_checklist_from_article(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<ChecklistFromSopInput>,
+) -> AppResult<(StatusCode, Json<SopChecklist>)> {
+    let service = SopChecklistService::new(state.db);
+    let checklist = service
+        .create_from_sop(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(checklist)))
+}
+
+/// Edit a checklist
+pub async fn update_sop_checklist(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(checklist_id): Path<Uuid>,
+    Json(input): Json<UpdateChecklistInput>,
+) -> AppResult<Json<SopChecklist>> {
+    let service = SopChecklistService::new(state.db);
+    let checklist = service
+        .update_checklist(current_user.0.business_id, checklist_id, input)
+        .await?;
+    Ok(Json(checklist))
+}
+
+/// Start a run of a checklist, or carry on with the open one
+pub async fn start_sop_execution(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(checklist_id): Path<Uuid>,
+    Json(input): Json<StartExecutionInput>,
+) -> AppResult<Json<SopExecution>> {
+    let service = SopChecklistService::new(state.db);
+    let execution = service
+        .start_execution(
+            current_user.0.business_id,
+            current_user.0.user_id,
+            checklist_id,
+            input,
+            "app",
+        )
+        .await?;
+    Ok(Json(execution))
+}
+
+/// How well a checklist was kept over a period
+pub async fn get_sop_checklist_compliance(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(checklist_id): Path<Uuid>,
+    Query(query): Query<ComplianceQuery>,
+) -> AppResult<Json<ChecklistCompliance>> {
+    let service = SopChecklistService::new(state.db);
+    let compliance = service
+        .compliance(current_user.0.business_id, checklist_id, &query)
+        .await?;
+    Ok(Json(compliance))
+}
+
+/// Execution records, optionally for one checklist, lot or period
+pub async fn list_sop_executions(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<ExecutionQuery>,
+) -> AppResult<Json<Vec<ExecutionSummary>>> {
+    let service = SopChecklistService::new(state.db);
+    let executions = service
+        .list_executions(current_user.0.business_id, &query)
+        .await?;
+    Ok(Json(executions))
+}
+
+/// An execution with every step and who ticked it when
+pub async fn get_sop_execution(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(execution_id): Path<Uuid>,
+) -> AppResult<Json<SopExecution>> {
+    let service = SopChecklistService::new(state.db);
+    let execution = service
+        .get_execution(current_user.0.business_id, execution_id)
+        .await?;
+    Ok(Json(execution))
+}
+
+/// Tick steps of an execution
+pub async fn check_sop_execution_items(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(execution_id): Path<Uuid>,
+    Json(input): Json<CheckItemsInput>,
+) -> AppResult<Json<SopExecution>> {
+    let service = SopChecklistService::new(state.db);
+    let execution = service
+        .check_items(
+            current_user.0.business_id,
+            current_user.0.user_id,
+            execution_id,
+            input,
+            "app",
+        )
+        .await?;
+    Ok(Json(execution))
+}
@@ -0,0 +1,149 @@
+//! HTTP handlers for storage locations and heat advisories
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::storage::{HeatAdvisory, StorageLocation, StorageLocationInput, StoreLotsInput, StoredLot},
+    services::StorageService,
+    AppState,
+};
+
+/// Heat advisories queued
+#[derive(Debug, Serialize)]
+pub struct HeatAdvisoryNotifyResponse {
+    pub notifications_queued: i32,
+}
+
+/// Live forecasts when a weather API key is configured, cached ones otherwise
+fn storage_service(state: AppState) -> StorageService {
+    match std::env::var("CQM_WEATHER_API_KEY") {
+        Ok(api_key) if !api_key.is_empty() => StorageService::with_weather_client(state.db, api_key),
+        _ => StorageService::new(state.db),
+    }
+}
+
+/// List storage locations
+pub async fn list_storage_locations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<StorageLocation>>> {
+    let service = StorageService::new(state.db);
+    let locations = service.list(current_user.0.business_id).await?;
+    Ok(Json(locations))
+}
+
+/// Record a storage location
+pub async fn create_storage_location(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<StorageLocationInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = StorageService::new(state.db);
+    let location = service.create(current_user.0.business_id, input).await?;
+    Ok((StatusCode::CREATED, Json(location)))
+}
+
+/// Get a storage location
+pub async fn get_storage_location(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(location_id): Path<Uuid>,
+) -> AppResult<Json<StorageLocation>> {
+    let service = StorageService::new(state.db);
+    let location = service.get(current_user.0.business_id, location_id).await?;
+    Ok(Json(location))
+}
+
+/// Update a storage location
+pub async fn update_storage_location(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(location_id): Path<Uuid>,
+    Json(input): Json<StorageLocationInput>,
+) -> AppResult<Json<StorageLocation>> {
+    let service = StorageService::new(state.db);
+    let location = service
+        .update(current_user.0.business_id, location_id, input)
+        .await?;
+    Ok(Json(location))
+}
+
+/// Delete a storage location
+pub async fn delete_storage_location(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(location_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = StorageService::new(state.db);
+    service.delete(current_user.0.business_id, location_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the lots stored at a location
+pub async fn list_stored_lots(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(location_id): Path<Uuid>,
+) -> AppResult<Json<Vec<StoredLot>>> {
+    let service = StorageService::new(state.db);
+    let lots = service.list_lots(current_user.0.business_id, location_id).await?;
+    Ok(Json(lots))
+}
+
+/// Move lots into a location
+pub async fn store_lots(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(location_id): Path<Uuid>,
+    Json(input): Json<StoreLotsInput>,
+) -> AppResult<Json<Vec<StoredLot>>> {
+    let service = StorageService::new(state.db);
+    let lots = service
+        .store_lots(current_user.0.business_id, location_id, input)
+        .await?;
+    Ok(Json(lots))
+}
+
+/// Take a lot out of a location
+pub async fn remove_stored_lot(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path((location_id, lot_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let service = StorageService::new(state.db);
+    service
+        .remove_lot(current_user.0.business_id, location_id, lot_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Hot spells forecast at locations without climate control
+pub async fn get_heat_advisories(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<HeatAdvisory>>> {
+    let service = storage_service(state);
+    let advisories = service.heat_advisories(current_user.0.business_id).await?;
+    Ok(Json(advisories))
+}
+
+/// Advise the owner of new hot spells now
+pub async fn notify_heat_advisories(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<HeatAdvisoryNotifyResponse>> {
+    let service = storage_service(state);
+    let notifications_queued = service
+        .notify_heat_advisories(current_user.0.business_id)
+        .await?;
+    Ok(Json(HeatAdvisoryNotifyResponse { notifications_queued }))
+}
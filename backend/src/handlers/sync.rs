@@ -55,12 +55,18 @@ pub async fn get_changes(
 ) -> AppResult<Json<GetChangesResponse>> {
     let sync_service = SyncService::new(state.db.clone());
 
-    let changes = sync_service
+    let mut changes = sync_service
         .get_changes_since(user.business_id, body.since_version, body.limit)
         .await?;
 
     let server_version = changes.last().map(|c| c.entity_version).unwrap_or(body.since_version);
 
+    // Only send records the user's role may view
+    changes.retain(|c| {
+        SyncService::entity_resource(&c.entity_type)
+            .is_none_or(|resource| user.has_permission(resource, "view"))
+    });
+
     // Update sync state
     sync_service
         .update_sync_state(user.user_id, &body.device_id, server_version)
@@ -78,6 +84,15 @@ pub async fn apply_changes(
     Extension(user): Extension<AuthUser>,
     Json(body): Json<ApplyChangesRequest>,
 ) -> AppResult<Json<ApplyChangesResponse>> {
+    // Every change must be allowed for the user's role, or none are applied
+    for change in &body.changes {
+        if let Some(resource) = SyncService::entity_resource(&change.entity_type) {
+            if !user.has_permission(resource, SyncService::operation_action(&change.operation)) {
+                return Err(AppError::InsufficientPermissions);
+            }
+        }
+    }
+
     let sync_service = SyncService::new(state.db.clone());
 
     let result = sync_service
@@ -121,6 +136,7 @@ pub async fn resolve_conflict(
             let data = body.merged_data.ok_or(AppError::Validation {
                 field: "merged_data".to_string(),
                 message: "merged_data required for merge resolution".to_string(),
+                message_th: "ต้องระบุ merged_data สำหรับการรวมข้อมูล".to_string(),
             })?;
             ConflictResolution::Merge(data)
         }
@@ -128,6 +144,7 @@ pub async fn resolve_conflict(
             return Err(AppError::Validation {
                 field: "resolution".to_string(),
                 message: "Invalid resolution type".to_string(),
+                message_th: "ประเภทการแก้ไขความขัดแย้งไม่ถูกต้อง".to_string(),
             })
         }
     };
@@ -2,13 +2,15 @@
 
 use axum::{
     extract::{Path, Query, State},
-    Json,
+    http::HeaderMap,
+    response::Response,
 };
 use serde::Deserialize;
 
 use crate::{
     error::AppResult,
-    services::traceability::{TraceabilityService, TraceabilityView},
+    handlers::etag,
+    services::traceability::TraceabilityService,
     AppState,
 };
 
@@ -25,10 +27,11 @@ pub async fn get_traceability_view(
     State(state): State<AppState>,
     Path(code): Path<String>,
     Query(query): Query<TraceabilityQuery>,
-) -> AppResult<Json<TraceabilityView>> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
     let service = TraceabilityService::new(state.db);
     let view = service
         .get_traceability_view(&code, query.lang.as_deref())
         .await?;
-    Ok(Json(view))
+    Ok(etag::conditional_json(&headers, view))
 }
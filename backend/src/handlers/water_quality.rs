@@ -0,0 +1,56 @@
+//! HTTP handlers for the water quality log
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::water_quality::{
+        RecordWaterQualityInput, WaterQualityMeasurement, WaterQualityQuery,
+    },
+    services::WaterQualityService,
+    AppState,
+};
+
+/// Record a water quality measurement; the response carries warnings when
+/// TDS or pH is outside the SCA range
+pub async fn record_water_quality(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordWaterQualityInput>,
+) -> AppResult<impl IntoResponse> {
+    let service = WaterQualityService::new(state.db);
+    let measurement = service
+        .record(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(measurement)))
+}
+
+/// List water quality measurements, optionally for one cupping session or
+/// processing record
+pub async fn list_water_quality(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<WaterQualityQuery>,
+) -> AppResult<Json<Vec<WaterQualityMeasurement>>> {
+    let service = WaterQualityService::new(state.db);
+    let measurements = service.list(current_user.0.business_id, &query).await?;
+    Ok(Json(measurements))
+}
+
+/// Delete a water quality measurement
+pub async fn delete_water_quality(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(measurement_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = WaterQualityService::new(state.db);
+    service.delete(current_user.0.business_id, current_user.0.user_id, measurement_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
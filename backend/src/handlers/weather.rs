@@ -12,7 +12,8 @@ use uuid::Uuid;
 use crate::error::AppResult;
 use crate::middleware::CurrentUser;
 use crate::services::weather::{
-    CreateWeatherAlertInput, StoreWeatherInput, WeatherAlert, WeatherService, WeatherSnapshot,
+    CreateWeatherAlertInput, DailyWeatherSummary, RainfallObservation, RecordRainfallInput,
+    SnoozeWeatherAlertInput, StoreWeatherInput, WeatherAlert, WeatherService, WeatherSnapshot,
 };
 use crate::external::weather::WeatherForecast;
 use crate::AppState;
@@ -171,6 +172,68 @@ pub async fn get_weather_forecast(
     Ok(Json(forecast))
 }
 
+/// Query parameters for daily weather and rainfall
+#[derive(Debug, Deserialize)]
+pub struct DailyWeatherQuery {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub plot_id: Option<Uuid>,
+}
+
+/// Record a manual rain gauge reading
+pub async fn record_rainfall(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<RecordRainfallInput>,
+) -> AppResult<Json<RainfallObservation>> {
+    let service = WeatherService::new(state.db);
+    let observation = service
+        .record_rainfall(
+            current_user.0.business_id,
+            Some(current_user.0.user_id),
+            "manual",
+            input,
+        )
+        .await?;
+    Ok(Json(observation))
+}
+
+/// List manual rain gauge readings
+pub async fn list_rainfall(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<DailyWeatherQuery>,
+) -> AppResult<Json<Vec<RainfallObservation>>> {
+    let service = WeatherService::new(state.db);
+    let observations = service
+        .list_rainfall(
+            current_user.0.business_id,
+            query.start_date,
+            query.end_date,
+            query.plot_id,
+        )
+        .await?;
+    Ok(Json(observations))
+}
+
+/// Get daily weather aggregates (API snapshots merged with rain gauges)
+pub async fn get_daily_weather(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<DailyWeatherQuery>,
+) -> AppResult<Json<Vec<DailyWeatherSummary>>> {
+    let service = WeatherService::new(state.db);
+    let days = service
+        .get_daily_weather(
+            current_user.0.business_id,
+            query.start_date,
+            query.end_date,
+            query.plot_id,
+        )
+        .await?;
+    Ok(Json(days))
+}
+
 /// Create a weather alert
 pub async fn create_weather_alert(
     State(state): State<AppState>,
@@ -207,6 +270,47 @@ pub async fn delete_weather_alert(
     Ok(Json(()))
 }
 
+/// Snooze a weather alert for a number of days or until a date
+pub async fn snooze_weather_alert(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(alert_id): Path<Uuid>,
+    Json(input): Json<SnoozeWeatherAlertInput>,
+) -> AppResult<Json<WeatherAlert>> {
+    let service = WeatherService::new(state.db);
+    let alert = service
+        .snooze_alert(current_user.0.business_id, current_user.0.user_id, alert_id, input)
+        .await?;
+    Ok(Json(alert))
+}
+
+/// Unmute a snoozed weather alert
+pub async fn unmute_weather_alert(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(alert_id): Path<Uuid>,
+) -> AppResult<Json<WeatherAlert>> {
+    let service = WeatherService::new(state.db);
+    let alert = service
+        .unmute_alert(current_user.0.business_id, alert_id)
+        .await?;
+    Ok(Json(alert))
+}
+
+/// Snooze all weather alerts for a plot
+pub async fn snooze_plot_weather_alerts(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(plot_id): Path<Uuid>,
+    Json(input): Json<SnoozeWeatherAlertInput>,
+) -> AppResult<Json<Vec<WeatherAlert>>> {
+    let service = WeatherService::new(state.db);
+    let alerts = service
+        .snooze_plot_alerts(current_user.0.business_id, current_user.0.user_id, plot_id, input)
+        .await?;
+    Ok(Json(alerts))
+}
+
 /// Check rain alerts response
 #[derive(Debug, serde::Serialize)]
 pub struct RainAlertResponse {
@@ -0,0 +1,147 @@
+//! HTTP handlers for farm weather station endpoints
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{FromRequest, Path, Request, State},
+    http::{header, StatusCode},
+    Form, Json,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::CurrentUser;
+use crate::services::weather_station::{
+    station_template, CreateWeatherStationInput, StationTemplate, StationUploadAck, StationVendor,
+    UpdateWeatherStationInput, WeatherStation,
+};
+use crate::services::WeatherStationService;
+use crate::AppState;
+
+/// List the business's weather stations
+pub async fn list_weather_stations(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<WeatherStation>>> {
+    let service = WeatherStationService::new(state.db);
+    let stations = service.list_stations(current_user.0.business_id).await?;
+    Ok(Json(stations))
+}
+
+/// Field mapping templates of the supported station makes
+pub async fn list_weather_station_templates(_current_user: CurrentUser) -> Json<Vec<StationTemplate>> {
+    Json(
+        StationVendor::ALL
+            .iter()
+            .map(|vendor| StationTemplate {
+                vendor: *vendor,
+                field_mapping: station_template(*vendor),
+            })
+            .collect(),
+    )
+}
+
+/// Add a weather station to a plot
+pub async fn create_weather_station(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(input): Json<CreateWeatherStationInput>,
+) -> AppResult<(StatusCode, Json<WeatherStation>)> {
+    let service = WeatherStationService::new(state.db);
+    let station = service
+        .create_station(current_user.0.business_id, current_user.0.user_id, input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(station)))
+}
+
+/// Get a weather station
+pub async fn get_weather_station(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(station_id): Path<Uuid>,
+) -> AppResult<Json<WeatherStation>> {
+    let service = WeatherStationService::new(state.db);
+    let station = service
+        .get_station(current_user.0.business_id, station_id)
+        .await?;
+    Ok(Json(station))
+}
+
+/// Edit a weather station
+pub async fn update_weather_station(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(station_id): Path<Uuid>,
+    Json(input): Json<UpdateWeatherStationInput>,
+) -> AppResult<Json<WeatherStation>> {
+    let service = WeatherStationService::new(state.db);
+    let station = service
+        .update_station(current_user.0.business_id, station_id, input)
+        .await?;
+    Ok(Json(station))
+}
+
+/// Remove a weather station
+pub async fn delete_weather_station(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(station_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let service = WeatherStationService::new(state.db);
+    service
+        .delete_station(current_user.0.business_id, station_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issue a new webhook token for a weather station
+pub async fn rotate_weather_station_token(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(station_id): Path<Uuid>,
+) -> AppResult<Json<WeatherStation>> {
+    let service = WeatherStationService::new(state.db);
+    let station = service
+        .rotate_token(current_user.0.business_id, station_id)
+        .await?;
+    Ok(Json(station))
+}
+
+/// Receive a station upload, as JSON or as a form post (Ecowitt)
+/// This endpoint is unauthenticated - the token identifies the station
+pub async fn handle_weather_station_upload(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    request: Request,
+) -> AppResult<Json<StationUploadAck>> {
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+
+    let payload = if is_form {
+        let Form(fields) = Form::<HashMap<String, String>>::from_request(request, &state)
+            .await
+            .map_err(|e| invalid_payload(&e.body_text()))?;
+        Value::Object(fields.into_iter().map(|(key, value)| (key, Value::String(value))).collect())
+    } else {
+        let Json(payload) = Json::<Value>::from_request(request, &state)
+            .await
+            .map_err(|e| invalid_payload(&e.body_text()))?;
+        payload
+    };
+
+    let service = WeatherStationService::new(state.db);
+    let ack = service.receive_upload(&token, &payload).await?;
+    Ok(Json(ack))
+}
+
+fn invalid_payload(reason: &str) -> AppError {
+    AppError::Validation {
+        field: "payload".to_string(),
+        message: format!("Unreadable station upload: {}", reason),
+        message_th: format!("อ่านข้อมูลจากสถานีไม่ได้: {}", reason),
+    }
+}
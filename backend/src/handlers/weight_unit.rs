@@ -0,0 +1,47 @@
+//! HTTP handlers for weight units and their kilograms
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{
+    error::AppResult,
+    middleware::CurrentUser,
+    services::weight_unit::{SetWeightUnitInput, WeightUnit},
+    services::WeightUnitService,
+    AppState,
+};
+
+/// List weight units with the kilograms the business uses for them
+pub async fn list_weight_units(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> AppResult<Json<Vec<WeightUnit>>> {
+    let service = WeightUnitService::new(state.db);
+    let units = service.list(current_user.0.business_id).await?;
+    Ok(Json(units))
+}
+
+/// Set the kilograms the business uses for a local unit
+pub async fn set_weight_unit(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(code): Path<String>,
+    Json(input): Json<SetWeightUnitInput>,
+) -> AppResult<Json<WeightUnit>> {
+    let service = WeightUnitService::new(state.db);
+    let unit = service.set_kg_per_unit(current_user.0.business_id, &code, input).await?;
+    Ok(Json(unit))
+}
+
+/// Go back to the default kilograms for a unit
+pub async fn reset_weight_unit(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(code): Path<String>,
+) -> AppResult<Json<WeightUnit>> {
+    let service = WeightUnitService::new(state.db);
+    let unit = service.reset_kg_per_unit(current_user.0.business_id, &code).await?;
+    Ok(Json(unit))
+}
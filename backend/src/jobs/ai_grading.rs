@@ -0,0 +1,70 @@
+//! Queued AI grading job
+
+use serde_json::json;
+
+use crate::error::{AppError, AppResult};
+use crate::external::ai_defect_detection::AiDefectDetectionClient;
+use crate::jobs::BackgroundJob;
+use crate::services::grading::QueueAiGradingInput;
+use crate::services::job_queue::{JobStatus, JOB_TYPE_AI_GRADING};
+use crate::services::{GradingService, JobQueueService};
+use crate::AppState;
+
+/// Jobs claimed at a time
+const BATCH_SIZE: i64 = 10;
+
+/// Sends queued tray photos to AI defect detection and records their
+/// gradings, retrying jobs the detection service fails on
+pub struct AiGradingJob;
+
+#[axum::async_trait]
+impl BackgroundJob for AiGradingJob {
+    fn name(&self) -> &'static str {
+        "ai_grading"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        // Jobs are only queued while the service is configured
+        let Some(ai_client) = AiDefectDetectionClient::from_env() else {
+            return Ok(0);
+        };
+        let queue = JobQueueService::new(state.db.clone());
+        let grading = GradingService::new(state.db.clone());
+
+        let mut processed = 0;
+        loop {
+            let jobs = queue.claim_due(JOB_TYPE_AI_GRADING, BATCH_SIZE).await?;
+            if jobs.is_empty() {
+                return Ok(processed);
+            }
+            for job in jobs {
+                let result = match serde_json::from_value::<QueueAiGradingInput>(job.payload.clone()) {
+                    Ok(input) => grading.run_queued_ai_grading(&ai_client, job.business_id, input).await,
+                    Err(e) => Err(AppError::Internal(format!("Invalid job payload: {}", e))),
+                };
+                match result {
+                    Ok(record) => {
+                        let request_id = record.ai_detection.as_ref().map(|d| d.request_id.clone());
+                        queue
+                            .complete(
+                                job.id,
+                                json!({
+                                    "grading_id": record.id,
+                                    "grade": record.grade,
+                                    "ai_request_id": request_id,
+                                }),
+                            )
+                            .await?;
+                    }
+                    Err(e) => {
+                        let status = queue.fail(&job, &e).await?;
+                        if status == JobStatus::Failed {
+                            tracing::warn!("AI grading job {} failed after {} attempt(s): {}", job.id, job.attempts, e);
+                        }
+                    }
+                }
+                processed += 1;
+            }
+        }
+    }
+}
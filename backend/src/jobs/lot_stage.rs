@@ -0,0 +1,23 @@
+//! Lot stage reconciliation job
+
+use chrono::Utc;
+
+use crate::error::AppResult;
+use crate::jobs::BackgroundJob;
+use crate::services::LotStageService;
+use crate::AppState;
+
+/// Moves lots whose stage lags behind their processing, grading and
+/// roasting records on to the stage the records show
+pub struct LotStageReconciliationJob;
+
+#[axum::async_trait]
+impl BackgroundJob for LotStageReconciliationJob {
+    fn name(&self) -> &'static str {
+        "lot_stage_reconciliation"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        LotStageService::new(state.db.clone()).reconcile_due(Utc::now()).await
+    }
+}
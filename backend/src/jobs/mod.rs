@@ -0,0 +1,99 @@
+//! Background job framework
+//!
+//! Jobs run on a fixed polling interval inside the server process. Each run
+//! holds a Postgres advisory lock keyed by the job name, so when several
+//! server instances are deployed only one of them executes a job at a time.
+
+pub mod ai_grading;
+pub mod lot_stage;
+pub mod recycle_bin;
+pub mod scheduled_reports;
+pub mod storage_heat;
+pub mod weekly_digest;
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::time::MissedTickBehavior;
+
+use crate::error::AppResult;
+use crate::AppState;
+
+pub use ai_grading::AiGradingJob;
+pub use lot_stage::LotStageReconciliationJob;
+pub use recycle_bin::RecycleBinPurgeJob;
+pub use scheduled_reports::ScheduledReportJob;
+pub use storage_heat::StorageHeatJob;
+pub use weekly_digest::WeeklyDigestJob;
+
+/// Periodic background job
+#[axum::async_trait]
+pub trait BackgroundJob: Send + Sync {
+    /// Unique job name, also used as the advisory lock key
+    fn name(&self) -> &'static str;
+
+    /// Perform one pass of work, returning the number of items processed
+    async fn run(&self, state: &AppState) -> AppResult<usize>;
+}
+
+/// Start every registered job on its own task
+pub fn spawn_background_jobs(state: AppState) {
+    if !state.config.jobs.enabled {
+        tracing::info!("Background jobs disabled");
+        return;
+    }
+
+    let interval = Duration::from_secs(state.config.jobs.poll_interval_seconds.max(1));
+    let jobs: Vec<Arc<dyn BackgroundJob>> = vec![
+        Arc::new(ScheduledReportJob),
+        Arc::new(WeeklyDigestJob),
+        Arc::new(StorageHeatJob),
+        Arc::new(RecycleBinPurgeJob),
+        Arc::new(LotStageReconciliationJob),
+        Arc::new(AiGradingJob),
+    ];
+
+    for job in jobs {
+        tracing::info!("Starting background job '{}' every {:?}", job.name(), interval);
+        tokio::spawn(run_job_loop(state.clone(), job, interval));
+    }
+}
+
+async fn run_job_loop(state: AppState, job: Arc<dyn BackgroundJob>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        match run_exclusive(&state, job.as_ref()).await {
+            Ok(Some(processed)) if processed > 0 => {
+                tracing::info!("Background job '{}' processed {} item(s)", job.name(), processed);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Background job '{}' failed: {}", job.name(), e),
+        }
+    }
+}
+
+/// Run a job once if no other instance holds its lock; `None` when skipped
+async fn run_exclusive(state: &AppState, job: &dyn BackgroundJob) -> AppResult<Option<usize>> {
+    // Session-level advisory locks belong to the connection, so hold one
+    // connection for the whole run
+    let mut conn = state.db.acquire().await?;
+
+    let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock(hashtext($1))")
+        .bind(job.name())
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let result = job.run(state).await;
+
+    sqlx::query("SELECT pg_advisory_unlock(hashtext($1))")
+        .bind(job.name())
+        .execute(&mut *conn)
+        .await?;
+
+    result.map(Some)
+}
@@ -0,0 +1,20 @@
+//! Recycle bin purge job
+
+use crate::error::AppResult;
+use crate::jobs::BackgroundJob;
+use crate::services::RecycleBinService;
+use crate::AppState;
+
+/// Purges deleted records once their business's retention period has passed
+pub struct RecycleBinPurgeJob;
+
+#[axum::async_trait]
+impl BackgroundJob for RecycleBinPurgeJob {
+    fn name(&self) -> &'static str {
+        "recycle_bin_purge"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        RecycleBinService::new(state.db.clone()).purge_expired().await
+    }
+}
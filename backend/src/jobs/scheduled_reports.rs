@@ -0,0 +1,22 @@
+//! Scheduled report delivery job
+
+use crate::error::AppResult;
+use crate::jobs::BackgroundJob;
+use crate::services::ReportScheduleService;
+use crate::AppState;
+
+/// Delivers saved reports whose schedule is due
+pub struct ScheduledReportJob;
+
+#[axum::async_trait]
+impl BackgroundJob for ScheduledReportJob {
+    fn name(&self) -> &'static str {
+        "scheduled_reports"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        ReportScheduleService::new(state.db.clone(), &state.config)
+            .run_due_schedules()
+            .await
+    }
+}
@@ -0,0 +1,30 @@
+//! Hot-season storage advisory job
+
+use chrono::Utc;
+
+use crate::error::AppResult;
+use crate::jobs::BackgroundJob;
+use crate::services::StorageService;
+use crate::AppState;
+
+/// Checks the forecast at storage locations holding green coffee and
+/// advises owners of hot spells
+pub struct StorageHeatJob;
+
+#[axum::async_trait]
+impl BackgroundJob for StorageHeatJob {
+    fn name(&self) -> &'static str {
+        "storage_heat"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        // Without an API key only cached forecasts could be used
+        let api_key = match std::env::var("CQM_WEATHER_API_KEY") {
+            Ok(api_key) if !api_key.is_empty() => api_key,
+            _ => return Ok(0),
+        };
+        StorageService::with_weather_client(state.db.clone(), api_key)
+            .check_due_locations(Utc::now())
+            .await
+    }
+}
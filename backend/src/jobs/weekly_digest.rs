@@ -0,0 +1,24 @@
+//! Weekly owner digest job
+
+use chrono::Utc;
+
+use crate::error::AppResult;
+use crate::jobs::BackgroundJob;
+use crate::services::WeeklyDigestService;
+use crate::AppState;
+
+/// Sends last week's digest to business owners once it is due
+pub struct WeeklyDigestJob;
+
+#[axum::async_trait]
+impl BackgroundJob for WeeklyDigestJob {
+    fn name(&self) -> &'static str {
+        "weekly_digest"
+    }
+
+    async fn run(&self, state: &AppState) -> AppResult<usize> {
+        WeeklyDigestService::new(state.db.clone(), &state.config)
+            .send_due_digests(Utc::now())
+            .await
+    }
+}
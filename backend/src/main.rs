@@ -16,6 +16,7 @@ mod config;
 mod error;
 mod external;
 mod handlers;
+mod jobs;
 mod middleware;
 mod models;
 mod routes;
@@ -54,6 +55,11 @@ async fn main() -> anyhow::Result<()> {
         .max_connections(config.database.max_connections)
         .min_connections(config.database.min_connections)
         .acquire_timeout(Duration::from_secs(30))
+        // Each connection carries the tenant of the task using it
+        .after_connect(|conn, _meta| Box::pin(middleware::tenant::apply_tenant(conn)))
+        .before_acquire(|conn, _meta| {
+            Box::pin(async move { middleware::tenant::apply_tenant(conn).await.map(|_| true) })
+        })
         .connect(&config.database.url)
         .await?;
 
@@ -72,6 +78,9 @@ async fn main() -> anyhow::Result<()> {
         config: Arc::new(config.clone()),
     };
 
+    // Start background jobs (scheduled report delivery, weekly digest, storage heat advisories)
+    jobs::spawn_background_jobs(state.clone());
+
     // Build application
     let app = create_app(state);
 
@@ -96,7 +105,13 @@ fn create_app(state: AppState) -> Router {
     Router::new()
         .route("/", get(root))
         .route("/health", get(health_check))
-        .nest("/api/v1", routes::api_routes())
+        .nest(
+            "/api/v1",
+            routes::api_routes().layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                middleware::field_visibility_middleware,
+            )),
+        )
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .with_state(state)
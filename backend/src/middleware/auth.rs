@@ -2,10 +2,12 @@
 //!
 //! JWT authentication and role-based access control middleware
 
+use std::{future::Future, pin::Pin};
+
 use axum::{
     body::Body,
     extract::Request,
-    http::{header::AUTHORIZATION, StatusCode},
+    http::{header::AUTHORIZATION, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
@@ -20,6 +22,8 @@ pub struct AuthUser {
     pub business_id: uuid::Uuid,
     pub role_id: uuid::Uuid,
     pub permissions: Vec<String>,
+    /// Sign-in session the access token belongs to
+    pub session_id: Option<uuid::Uuid>,
 }
 
 /// Extension trait for extracting auth user from request
@@ -93,11 +97,17 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
         business_id,
         role_id,
         permissions: claims.permissions,
+        // Tokens issued before sessions were tracked carry no session
+        session_id: claims.sid.and_then(|sid| uuid::Uuid::parse_str(&sid).ok()),
     };
 
-    request.extensions_mut().insert(auth_user);
+    request.extensions_mut().insert(auth_user.clone());
 
-    next.run(request).await
+    // Run the request with the business as tenant for row-level security,
+    // and expose the user to outer response layers (field visibility filtering)
+    let mut response = super::with_tenant(business_id, next.run(request)).await;
+    response.extensions_mut().insert(auth_user);
+    response
 }
 
 /// JWT claims structure
@@ -107,6 +117,8 @@ struct Claims {
     business_id: String,
     role_id: String,
     permissions: Vec<String>,
+    #[serde(default)]
+    sid: Option<String>,
     exp: i64,
     iat: i64,
 }
@@ -199,3 +211,58 @@ pub fn check_permission(user: &AuthUser, resource: &str, action: &str) -> Result
         )))
     }
 }
+
+/// Action a request performs on a resource, derived from its HTTP method
+pub fn action_for_method(method: &Method) -> &'static str {
+    match *method {
+        Method::POST => "create",
+        Method::PUT | Method::PATCH => "edit",
+        Method::DELETE => "delete",
+        _ => "view",
+    }
+}
+
+/// Resolve a permission spec to the `(resource, action)` a request needs
+///
+/// A spec is either `resource:action`, or a bare resource whose action
+/// follows the request method.
+pub fn required_permission<'a>(permission: &'a str, method: &Method) -> (&'a str, &'a str) {
+    match permission.split_once(':') {
+        Some((resource, action)) => (resource, action),
+        None => (permission, action_for_method(method)),
+    }
+}
+
+/// Permission enforcement layer for protected routers
+///
+/// Must run after [`auth_middleware`]: the permissions checked are the ones
+/// granted to the user's role in their business, carried in the access token
+/// (role changes apply on the next token refresh).
+///
+/// ```ignore
+/// Router::new()
+///     .route("/sessions", get(list).post(create))
+///     .route_layer(middleware::from_fn(require_permission("cupping")))
+///     .route_layer(middleware::from_fn(auth_middleware))
+/// ```
+pub fn require_permission(
+    permission: &'static str,
+) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send>> + Clone + Send + Sync + 'static
+{
+    move |request: Request, next: Next| {
+        Box::pin(async move {
+            let denied = match request.extensions().get::<AuthUser>() {
+                Some(user) => {
+                    let (resource, action) = required_permission(permission, request.method());
+                    check_permission(user, resource, action).err()
+                }
+                None => Some(unauthorized_response("Authentication required")),
+            };
+
+            match denied {
+                Some(response) => response,
+                None => next.run(request).await,
+            }
+        })
+    }
+}
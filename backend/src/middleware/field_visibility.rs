@@ -0,0 +1,116 @@
+//! Field visibility middleware
+//!
+//! Response-filtering layer enforcing per-role field visibility policies:
+//! fields hidden from the caller's role (e.g. `unit_price` for buyers with
+//! shared access) are removed from JSON response bodies at any depth, so
+//! list and detail endpoints don't each need to know about the policies.
+
+use std::collections::HashSet;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request, State},
+    http::header::{CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::AppError;
+use crate::middleware::AuthUser;
+use crate::services::RoleService;
+use crate::AppState;
+
+/// Largest response body the layer will buffer for filtering
+const MAX_FILTERED_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Remove hidden fields from a JSON response for the authenticated user's role
+///
+/// The auth middleware copies the [`AuthUser`] into the response extensions,
+/// so this layer can wrap every nested router. Public endpoints, errors and
+/// non-JSON responses pass through untouched.
+pub async fn field_visibility_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let response = next.run(request).await;
+
+    let Some(user) = response.extensions().get::<AuthUser>().cloned() else {
+        return response;
+    };
+
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let resource = resource_from_path(&path);
+    let hidden = match RoleService::new(state.db.clone())
+        .get_hidden_fields(user.role_id, resource)
+        .await
+    {
+        Ok(fields) if fields.is_empty() => return response,
+        Ok(fields) => fields.into_iter().collect::<HashSet<_>>(),
+        Err(e) => {
+            // Fail closed: never leak hidden fields because the lookup failed
+            tracing::error!("Failed to load field visibility policies: {}", e);
+            return AppError::Internal("Failed to apply field visibility".to_string())
+                .into_response();
+        }
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_FILTERED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for field visibility: {}", e);
+            return AppError::Internal("Failed to apply field visibility".to_string())
+                .into_response();
+        }
+    };
+
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    redact_fields(&mut value, &hidden);
+
+    let filtered = serde_json::to_vec(&value).unwrap_or_default();
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(filtered))
+}
+
+/// API resource of a request path: the first segment after `/api/v1`
+pub fn resource_from_path(path: &str) -> &str {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    path.trim_start_matches('/').split('/').next().unwrap_or("")
+}
+
+/// Remove hidden keys from every object in a JSON value
+pub fn redact_fields(value: &mut serde_json::Value, hidden: &HashSet<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|key, _| !hidden.contains(key));
+            for nested in map.values_mut() {
+                redact_fields(nested, hidden);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_fields(item, hidden);
+            }
+        }
+        _ => {}
+    }
+}
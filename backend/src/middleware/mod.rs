@@ -1,5 +1,9 @@
 //! Middleware for the Coffee Quality Management Platform
 
 pub mod auth;
+pub mod field_visibility;
+pub mod tenant;
 
-pub use auth::{auth_middleware, AuthUser, CurrentUser};
+pub use auth::{auth_middleware, require_permission, AuthUser, CurrentUser};
+pub use field_visibility::field_visibility_middleware;
+pub use tenant::{cross_tenant_middleware, with_tenant, without_tenant};
@@ -0,0 +1,53 @@
+//! Tenant scope for row-level security
+//!
+//! The auth middleware runs each authenticated request with its business
+//! as the tenant. Every connection taken from the pool during the request
+//! gets the tenant as the `app.business_id` setting, and the row-level
+//! security policies of tenant tables then hide other businesses' rows.
+//! Public routes and background jobs run without a tenant, as do the few
+//! features that read across businesses on purpose (lot transfers,
+//! regional benchmarks, cooperative plot checks).
+
+use std::future::Future;
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+tokio::task_local! {
+    static TENANT: Option<Uuid>;
+}
+
+/// Business the current task runs for, if any
+pub fn current_tenant() -> Option<Uuid> {
+    TENANT.try_with(|tenant| *tenant).ok().flatten()
+}
+
+/// Run a future with a business as the tenant
+pub async fn with_tenant<F: Future>(business_id: Uuid, future: F) -> F::Output {
+    TENANT.scope(Some(business_id), future).await
+}
+
+/// Run a future without a tenant, so its queries see every business's rows.
+/// Callers check access to what they read themselves.
+pub async fn without_tenant<F: Future>(future: F) -> F::Output {
+    TENANT.scope(None, future).await
+}
+
+/// Layer for routes whose handlers read and write the rows of more than
+/// one business; goes inside the auth middleware
+pub async fn cross_tenant_middleware(request: Request, next: Next) -> Response {
+    without_tenant(next.run(request)).await
+}
+
+/// Set the connection's `app.business_id` to the current tenant, or clear
+/// it. Runs on every connection the pool hands out, since a pooled
+/// connection may still carry the setting of an earlier request.
+pub async fn apply_tenant(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let business_id = current_tenant().map(|id| id.to_string()).unwrap_or_default();
+    sqlx::query("SELECT set_config('app.business_id', $1, false)")
+        .bind(business_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
@@ -6,7 +6,11 @@ use axum::{
     Router,
 };
 
-use crate::{handlers, middleware::auth_middleware, AppState};
+use crate::{
+    handlers,
+    middleware::{auth_middleware, cross_tenant_middleware, require_permission},
+    AppState,
+};
 
 /// Create API routes
 pub fn api_routes() -> Router<AppState> {
@@ -17,14 +21,34 @@ pub fn api_routes() -> Router<AppState> {
         .nest("/auth", auth_routes())
         // LINE webhook (public - for LINE Messaging API)
         .route("/webhook/line", post(handlers::handle_line_webhook))
+        // Carrier shipment status webhook (public - the token identifies the shipment)
+        .route("/webhook/shipments/:token", post(handlers::handle_shipment_webhook))
+        // Weather station uploads (public - the token identifies the station)
+        .route("/webhook/weather-stations/:token", post(handlers::handle_weather_station_upload))
         // Public traceability routes (unauthenticated - for QR code scanning)
         .route("/trace/:code", get(handlers::get_traceability_view))
+        // Public scheduled report downloads (token links sent via LINE)
+        .route("/report-downloads/:token", get(handlers::download_scheduled_report))
+        // Public shipment tracking (token links shared with buyers)
+        .route("/shipment-tracking/:token", get(handlers::get_shared_shipment))
+        // Public marketplace (unauthenticated - producer directory, listings and inquiries)
+        .nest("/marketplace", marketplace_routes())
+        // Research partner API (partner API key or admin token, not user sessions)
+        .nest("/research", research_routes())
+        // Protected routes - business settings
+        .nest("/business", business_routes())
+        // Protected routes - recycle bin of deleted records
+        .nest("/recycle-bin", recycle_bin_routes())
         // Protected routes - role management
         .nest("/roles", role_routes())
+        // Team members and invitations (accepting is public)
+        .nest("/members", member_routes())
         // Protected routes - plot management
         .nest("/plots", plot_routes())
         // Protected routes - lot management
         .nest("/lots", lot_routes())
+        // Protected routes - lot transfers between businesses
+        .nest("/lot-transfers", lot_transfer_routes())
         // Protected routes - harvest management
         .nest("/harvests", harvest_routes())
         // Protected routes - processing management
@@ -33,8 +57,34 @@ pub fn api_routes() -> Router<AppState> {
         .nest("/gradings", grading_routes())
         // Protected routes - cupping management
         .nest("/cupping", cupping_routes())
+        // Protected routes - quality specs and evaluations
+        .nest("/quality", quality_routes())
+        // Protected routes - order lot recommendations and reservations
+        .nest("/orders", order_routes())
+        // Protected routes - marketplace listings of the business
+        .nest("/listings", listing_routes())
+        // Protected routes - sales leads, negotiations, orders and prices
+        .nest("/sales", sales_routes())
+        // Protected routes - shipments and logistics tracking
+        .nest("/shipments", shipment_routes())
+        // Protected routes - farm activity log
+        .nest("/farm-activities", farm_activity_routes())
+        // Protected routes - water quality log
+        .nest("/water-quality", water_quality_routes())
+        // Protected routes - moisture readings of lots
+        .nest("/moisture-readings", moisture_routes())
+        // Protected routes - third-party lab results
+        .nest("/lab-results", lab_result_routes())
+        // Protected routes - lot insurance policies
+        .nest("/insurance-policies", insurance_routes())
+        // Protected routes - storage locations and heat advisories
+        .nest("/storage-locations", storage_routes())
         // Protected routes - inventory management
         .nest("/inventory", inventory_routes())
+        // Protected routes - lab samples drawn from lots
+        .nest("/samples", sample_routes())
+        // Protected routes - package and sample labels
+        .nest("/labels", label_routes())
         // Protected routes - roasting management
         .nest("/roasting", roasting_routes())
         // Protected routes - weather management
@@ -43,8 +93,18 @@ pub fn api_routes() -> Router<AppState> {
         .nest("/certifications", certification_routes())
         // Protected routes - notification management
         .nest("/notifications", notification_routes())
+        // Protected routes - business activity feed
+        .nest("/activity", activity_feed_routes())
+        // Protected routes - SOP and training knowledge base
+        .nest("/knowledge", knowledge_routes())
+        // Protected routes - SOP checklists and execution records
+        .nest("/checklists", checklist_routes())
         // Protected routes - sync (offline support)
         .nest("/sync", sync_routes())
+        // Protected routes - weight units and the kilograms of local units
+        .nest("/weight-units", weight_unit_routes())
+        // Protected routes - reference catalogs for offline clients
+        .nest("/reference-data", reference_data_routes())
         // Protected routes - reporting
         .nest("/reports", reporting_routes())
 }
@@ -55,13 +115,49 @@ fn auth_routes() -> Router<AppState> {
         .route("/register", post(handlers::register))
         .route("/login", post(handlers::login))
         .route("/refresh", post(handlers::refresh))
+        // Account recovery (public endpoints)
+        .route("/forgot-password", post(handlers::forgot_password))
+        .route("/reset-password", post(handlers::reset_password))
+        .route("/verify-email", post(handlers::verify_email))
+        // Account recovery, sessions and two-factor (protected endpoints)
+        .merge(email_verification_routes())
+        .merge(session_routes())
+        .merge(two_factor_routes())
         // LINE OAuth (public endpoints)
         .route("/line", get(handlers::get_authorization_url))
         .route("/line/callback/public", get(handlers::handle_public_callback))
+        .route("/line/register", post(handlers::register_with_line))
+        .route("/line/login", post(handlers::login_with_line))
         // LINE OAuth (protected endpoints)
         .nest("/line", line_oauth_routes())
 }
 
+/// Email verification routes (protected)
+fn email_verification_routes() -> Router<AppState> {
+    Router::new()
+        .route("/verify-email/send", post(handlers::send_email_verification))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Signed-in session routes (protected; a user's own sessions)
+fn session_routes() -> Router<AppState> {
+    Router::new()
+        .route("/sessions", get(handlers::list_auth_sessions))
+        .route("/sessions/:session_id", delete(handlers::revoke_auth_session))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Two-factor authentication routes (protected; the user's own account)
+fn two_factor_routes() -> Router<AppState> {
+    Router::new()
+        .route("/2fa", get(handlers::get_two_factor_status))
+        .route("/2fa/enroll", post(handlers::enroll_two_factor))
+        .route("/2fa/confirm", post(handlers::confirm_two_factor))
+        .route("/2fa/disable", post(handlers::disable_two_factor))
+        .route("/2fa/backup-codes", post(handlers::regenerate_backup_codes))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
 /// LINE OAuth routes (protected)
 fn line_oauth_routes() -> Router<AppState> {
     Router::new()
@@ -72,24 +168,69 @@ fn line_oauth_routes() -> Router<AppState> {
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
+/// Business settings routes (protected)
+fn business_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/settings",
+            get(handlers::get_business_settings).put(handlers::update_business_settings),
+        )
+        .route_layer(middleware::from_fn(require_permission("business")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Recycle bin routes (protected)
+fn recycle_bin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_recycle_bin))
+        .route("/:deleted_id", delete(handlers::purge_deleted_record))
+        .route("/:deleted_id/restore", post(handlers::restore_deleted_record))
+        .route_layer(middleware::from_fn(require_permission("business")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
 /// Role management routes (protected)
 fn role_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::list_roles).post(handlers::create_role))
         .route("/permissions", get(handlers::list_permissions))
+        .route("/templates", get(handlers::list_role_templates))
+        .route("/templates/apply", post(handlers::apply_role_templates))
+        .route("/field-visibility", get(handlers::list_field_visibility_policies))
+        .route("/field-visibility/:policy_id", delete(handlers::delete_field_visibility_policy))
         .route(
             "/:role_id",
             get(handlers::get_role)
                 .put(handlers::update_role)
                 .delete(handlers::delete_role),
         )
+        .route("/:role_id/field-visibility", post(handlers::create_field_visibility_policy))
+        .route_layer(middleware::from_fn(require_permission("role")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Team member routes; the invitee's preview and accept are public
+fn member_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_members))
+        .route("/:member_id", put(handlers::update_member))
+        .route("/:member_id/unlock", post(handlers::unlock_member))
+        .route("/invitations", get(handlers::list_invitations).post(handlers::invite_member))
+        .route("/invitations/:invitation_id", delete(handlers::revoke_invitation))
+        .route_layer(middleware::from_fn(require_permission("user")))
         .route_layer(middleware::from_fn(auth_middleware))
+        .route("/invitations/preview", post(handlers::preview_invitation))
+        .route("/invitations/accept", post(handlers::accept_invitation))
 }
 
 /// Plot management routes (protected)
 fn plot_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::list_plots).post(handlers::create_plot))
+        .route("/import", post(handlers::import_plots))
+        .route("/validation", get(handlers::validate_plots))
+        .route("/costs", get(handlers::list_plot_costs).post(handlers::record_plot_cost))
+        .route("/costs/:cost_id", delete(handlers::delete_plot_cost))
         .route(
             "/:plot_id",
             get(handlers::get_plot)
@@ -105,6 +246,7 @@ fn plot_routes() -> Router<AppState> {
             "/:plot_id/varieties/:variety_id",
             delete(handlers::remove_variety),
         )
+        .route_layer(middleware::from_fn(require_permission("plot")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -113,6 +255,8 @@ fn lot_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::list_lots).post(handlers::create_lot))
         .route("/blend", post(handlers::blend_lots))
+        .route("/stage-check", get(handlers::check_lot_stages))
+        .route("/stage-check/repair", post(handlers::repair_lot_stages))
         .route(
             "/:lot_id",
             get(handlers::get_lot)
@@ -122,6 +266,33 @@ fn lot_routes() -> Router<AppState> {
         .route("/:lot_id/processing", get(handlers::get_processing_by_lot))
         .route("/:lot_id/gradings", get(handlers::get_grading_history))
         .route("/:lot_id/gradings/compare", get(handlers::get_grading_comparison))
+        .route("/:lot_id/spec-sheet.pdf", get(handlers::get_lot_spec_sheet))
+        .route("/:lot_id/traceability-check", get(handlers::check_lot_traceability))
+        .route("/:lot_id/data-quality", get(handlers::get_lot_data_quality))
+        // Photo gallery
+        .route("/:lot_id/photos", get(handlers::list_lot_photos).post(handlers::upload_lot_photo))
+        .route("/:lot_id/photos/order", put(handlers::reorder_lot_photos))
+        .route(
+            "/:lot_id/photos/:photo_id",
+            put(handlers::update_lot_photo).delete(handlers::delete_lot_photo),
+        )
+        .route("/:lot_id/photos/:photo_id/cover", post(handlers::set_lot_cover_photo))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Lot transfer routes (protected; the business is the seller or buyer)
+fn lot_transfer_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_lot_transfers).post(handlers::initiate_lot_transfer))
+        .route("/:transfer_id", get(handlers::get_lot_transfer))
+        .route("/:transfer_id/accept", post(handlers::accept_lot_transfer))
+        .route("/:transfer_id/decline", post(handlers::decline_lot_transfer))
+        .route("/:transfer_id/cancel", post(handlers::cancel_lot_transfer))
+        .route("/:transfer_id/origin", get(handlers::get_lot_transfer_origin))
+        // Transfers touch the lots and inventory of both businesses
+        .route_layer(middleware::from_fn(cross_tenant_middleware))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -129,12 +300,19 @@ fn lot_routes() -> Router<AppState> {
 fn harvest_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::list_harvests).post(handlers::record_harvest))
+        // Labor planning
+        .route("/labor-plan", get(handlers::get_harvest_labor_plan))
+        .route("/labor-plan/notify", post(handlers::notify_harvest_labor_plan))
+        // Ripeness from a basket photo
+        .route("/ripeness-estimates", post(handlers::estimate_harvest_ripeness))
+        .route("/ripeness-estimates/:estimate_id", get(handlers::get_ripeness_estimate))
         .route(
             "/:harvest_id",
             get(handlers::get_harvest)
                 .put(handlers::update_harvest)
                 .delete(handlers::delete_harvest),
         )
+        .route_layer(middleware::from_fn(require_permission("harvest")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -149,6 +327,14 @@ fn processing_routes() -> Router<AppState> {
         .route("/:processing_id/fermentation", post(handlers::log_fermentation))
         .route("/:processing_id/drying", post(handlers::log_drying))
         .route("/:processing_id/complete", post(handlers::complete_processing))
+        // Capacity planning
+        .route("/resources", get(handlers::list_processing_resources).post(handlers::create_processing_resource))
+        .route(
+            "/resources/:resource_id",
+            put(handlers::update_processing_resource).delete(handlers::delete_processing_resource),
+        )
+        .route("/capacity-plan", get(handlers::get_processing_capacity_plan))
+        .route_layer(middleware::from_fn(require_permission("processing")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -157,7 +343,35 @@ fn grading_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::list_gradings).post(handlers::record_grading))
         .route("/ai", post(handlers::record_grading_with_ai))
-        .route("/:grading_id", get(handlers::get_grading))
+        .route("/ai/jobs", post(handlers::queue_grading_with_ai))
+        .route("/ai/jobs/:job_id", get(handlers::get_ai_grading_job))
+        // Lots side by side
+        .route("/compare", get(handlers::compare_lot_gradings))
+        // Grading standards
+        .route("/standards", get(handlers::list_grading_standards).post(handlers::create_grading_standard))
+        .route("/standards/sca", get(handlers::get_sca_grading_thresholds))
+        .route(
+            "/standards/:standard_id",
+            get(handlers::get_grading_standard).put(handlers::update_grading_standard),
+        )
+        .route(
+            "/:grading_id",
+            get(handlers::get_grading)
+                .put(handlers::update_grading)
+                .delete(handlers::delete_grading),
+        )
+        .route("/:grading_id/history", get(handlers::get_grading_edit_history))
+        .route(
+            "/:grading_id/physical-analysis",
+            get(handlers::get_physical_analysis).put(handlers::record_physical_analysis),
+        )
+        .route(
+            "/:grading_id/photos",
+            get(handlers::list_grading_photos).post(handlers::upload_grading_photo),
+        )
+        .route("/:grading_id/analyses", get(handlers::list_grading_photo_analyses))
+        .route("/:grading_id/reanalyze", post(handlers::reanalyze_grading))
+        .route_layer(middleware::from_fn(require_permission("grading")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -166,9 +380,42 @@ fn cupping_routes() -> Router<AppState> {
     Router::new()
         .route("/sessions", get(handlers::list_cupping_sessions).post(handlers::create_cupping_session))
         .route("/sessions/:session_id", get(handlers::get_cupping_session))
+        .route("/import", post(handlers::import_cupping_sheet))
+        .route("/descriptors", get(handlers::list_flavor_descriptors))
         .route("/sessions/:session_id/samples", post(handlers::add_cupping_sample))
+        .route(
+            "/sessions/:session_id/samples/:sample_id",
+            put(handlers::update_cupping_sample).delete(handlers::delete_cupping_sample),
+        )
+        .route("/sessions/:session_id/samples/:sample_id/history", get(handlers::get_cupping_sample_history))
+        .route("/sessions/:session_id/reveal", post(handlers::reveal_cupping_session))
+        .route("/sessions/:session_id/finalize", post(handlers::finalize_cupping_session))
+        .route("/sessions/:session_id/triangle", get(handlers::get_triangle_test))
+        .route("/sessions/:session_id/triangle/answers", post(handlers::record_triangle_answer))
+        .route(
+            "/sessions/:session_id/triangle/answers/:answer_id",
+            delete(handlers::delete_triangle_answer),
+        )
+        .route("/sessions/:session_id/triangle/results", get(handlers::get_triangle_results))
+        .route(
+            "/sessions/:session_id/samples/:sample_id/scores",
+            post(handlers::record_cupper_scores),
+        )
+        .route(
+            "/sessions/:session_id/samples/:sample_id/scores/:score_id",
+            delete(handlers::delete_cupper_scores),
+        )
+        .route("/sessions/:session_id/panel", get(handlers::get_cupping_panel))
+        .route("/sessions/:session_id/layout", get(handlers::get_cupping_flight_layout))
+        .route("/sessions/:session_id/layout/labels.pdf", get(handlers::get_cupping_bowl_labels))
+        .route("/sessions/:session_id/report.pdf", get(handlers::get_cupping_session_report))
         .route("/lots/:lot_id/history", get(handlers::get_lot_cupping_history))
         .route("/lots/:lot_id/trend", get(handlers::get_lot_cupping_trend))
+        .route("/analytics", get(handlers::get_cupping_attribute_analytics))
+        .route("/analytics/cupper-bias", get(handlers::get_cupper_biases))
+        .route("/cuppers/:name/calibration", get(handlers::get_cupper_calibration))
+        .route("/analytics/normalized-scores/refresh", post(handlers::refresh_normalized_scores))
+        .route_layer(middleware::from_fn(require_permission("cupping")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -189,6 +436,17 @@ fn inventory_routes() -> Router<AppState> {
         )
         // Summary
         .route("/summary", get(handlers::get_inventory_summary))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Lab sample routes (protected)
+fn sample_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_samples).post(handlers::draw_sample))
+        .route("/:sample_id", get(handlers::get_sample))
+        .route("/:sample_id/status", put(handlers::update_sample_status))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -206,13 +464,224 @@ fn roasting_routes() -> Router<AppState> {
         // Roast sessions
         .route("/sessions", get(handlers::list_sessions).post(handlers::start_session))
         .route("/sessions/:session_id", get(handlers::get_session))
-        .route("/sessions/:session_id/temperature", post(handlers::log_temperature))
+        .route(
+            "/sessions/:session_id/temperature",
+            get(handlers::get_temperature_log).post(handlers::log_temperature),
+        )
         .route("/sessions/:session_id/milestones", post(handlers::log_milestones))
         .route("/sessions/:session_id/complete", post(handlers::complete_session))
         .route("/sessions/:session_id/fail", post(handlers::fail_session))
         .route("/sessions/:session_id/cuppings", get(handlers::get_session_cuppings))
         // Sessions by lot
         .route("/lots/:lot_id/sessions", get(handlers::get_sessions_by_lot))
+        .route_layer(middleware::from_fn(require_permission("roast_profile")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Quality spec and evaluation routes (protected)
+fn quality_routes() -> Router<AppState> {
+    Router::new()
+        .route("/specs", get(handlers::list_quality_specs).post(handlers::create_quality_spec))
+        .route(
+            "/specs/:spec_id",
+            get(handlers::get_quality_spec)
+                .put(handlers::update_quality_spec)
+                .delete(handlers::delete_quality_spec),
+        )
+        .route("/evaluations", get(handlers::list_quality_evaluations).post(handlers::create_quality_evaluation))
+        .route("/evaluations/:evaluation_id", get(handlers::get_quality_evaluation))
+        .route("/conformity", get(handlers::get_quality_conformity))
+        .route_layer(middleware::from_fn(require_permission("grading")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Order recommendation, blend optimizer and lot reservation routes (protected)
+fn order_routes() -> Router<AppState> {
+    Router::new()
+        .route("/reservations", get(handlers::list_lot_reservations).post(handlers::create_lot_reservation))
+        .route("/reservations/:reservation_id", delete(handlers::delete_lot_reservation))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        // Read-only queries sent as POST
+        .route(
+            "/recommendations",
+            post(handlers::recommend_lots)
+                .route_layer(middleware::from_fn(require_permission("inventory:view"))),
+        )
+        .route(
+            "/blends/optimize",
+            post(handlers::optimize_blend)
+                .route_layer(middleware::from_fn(require_permission("inventory:view"))),
+        )
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Public marketplace routes (unauthenticated)
+fn marketplace_routes() -> Router<AppState> {
+    Router::new()
+        .route("/producers", get(handlers::list_public_producers))
+        .route("/listings", get(handlers::search_public_listings))
+        .route("/listings/:listing_id", get(handlers::get_public_listing))
+        .route("/listings/:listing_id/inquiries", post(handlers::submit_listing_inquiry))
+        .route("/inquiries/:token", get(handlers::get_buyer_inquiry))
+        .route("/inquiries/:token/offers", post(handlers::make_buyer_offer))
+        .route("/inquiries/:token/offers/:offer_id/respond", post(handlers::respond_to_producer_offer))
+}
+
+/// Research partner API routes (API key or admin token)
+fn research_routes() -> Router<AppState> {
+    Router::new()
+        .route("/datasets/quality", get(handlers::get_research_quality_dataset))
+        .route("/datasets/processing", get(handlers::get_research_processing_dataset))
+        .route("/datasets/weather", get(handlers::get_research_weather_dataset))
+        .route("/partners", get(handlers::list_research_partners).post(handlers::create_research_partner))
+        .route("/partners/:partner_id", delete(handlers::revoke_research_partner))
+}
+
+/// Marketplace listing management routes (protected)
+fn listing_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_marketplace_listings).post(handlers::create_marketplace_listing))
+        .route(
+            "/:listing_id",
+            put(handlers::update_marketplace_listing).delete(handlers::delete_marketplace_listing),
+        )
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Sales lead, negotiation, order and price routes (protected)
+fn sales_routes() -> Router<AppState> {
+    Router::new()
+        .route("/leads", get(handlers::list_sales_leads))
+        .route("/leads/:lead_id", get(handlers::get_sales_lead_thread))
+        .route("/leads/:lead_id/status", put(handlers::update_sales_lead_status))
+        .route("/leads/:lead_id/offers", post(handlers::make_producer_offer))
+        .route("/leads/:lead_id/offers/:offer_id/respond", post(handlers::respond_to_buyer_offer))
+        .route("/orders", get(handlers::list_sales_orders))
+        .route("/prices", get(handlers::list_sale_prices).post(handlers::record_sale_price))
+        .route("/prices/:price_id", delete(handlers::delete_sale_price))
+        .route("/market-prices", get(handlers::list_market_prices).post(handlers::record_market_price))
+        .route("/market-prices/:reference_id", delete(handlers::delete_market_price))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Shipment routes (protected)
+fn shipment_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_shipments).post(handlers::create_shipment))
+        .route(
+            "/:shipment_id",
+            get(handlers::get_shipment)
+                .put(handlers::update_shipment)
+                .delete(handlers::delete_shipment),
+        )
+        .route("/:shipment_id/milestones", post(handlers::record_shipment_milestone))
+        .route("/:shipment_id/logger-data", post(handlers::import_shipment_logger_data))
+        .route(
+            "/:shipment_id/logger-data/:import_id",
+            delete(handlers::delete_shipment_logger_data),
+        )
+        .route("/:shipment_id/conditions", get(handlers::get_shipment_conditions))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Farm activity routes (protected)
+fn farm_activity_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_farm_activities).post(handlers::record_farm_activity))
+        .route("/:activity_id", get(handlers::get_farm_activity).delete(handlers::delete_farm_activity))
+        .route_layer(middleware::from_fn(require_permission("plot")))
+        .merge(organic_input_routes())
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Organic allowed-substance list, managed with the certifications
+fn organic_input_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/organic-inputs",
+            get(handlers::list_organic_allowed_inputs).post(handlers::add_organic_allowed_input),
+        )
+        .route("/organic-inputs/:allowed_input_id", delete(handlers::delete_organic_allowed_input))
+        .route_layer(middleware::from_fn(require_permission("certification")))
+}
+
+/// Water quality log routes (protected)
+fn water_quality_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_water_quality).post(handlers::record_water_quality))
+        .route("/:measurement_id", delete(handlers::delete_water_quality))
+        .route_layer(middleware::from_fn(require_permission("processing")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Moisture reading log routes (protected)
+fn moisture_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_moisture_readings).post(handlers::record_moisture_reading))
+        .route("/alerts", get(handlers::list_moisture_alerts))
+        .route("/lots/:lot_id", get(handlers::get_moisture_timeline))
+        .route("/:reading_id", delete(handlers::delete_moisture_reading))
+        .route_layer(middleware::from_fn(require_permission("processing")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Label printing routes (protected)
+fn label_routes() -> Router<AppState> {
+    Router::new()
+        .route("/print", post(handlers::print_labels))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Weight unit routes (protected; every member enters weights, changing
+/// the kilograms of a unit is a business setting)
+fn weight_unit_routes() -> Router<AppState> {
+    Router::new()
+        .route("/:code", put(handlers::set_weight_unit).delete(handlers::reset_weight_unit))
+        .route_layer(middleware::from_fn(require_permission("business")))
+        .route("/", get(handlers::list_weight_units))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Lab result routes (protected)
+fn lab_result_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_lab_results).post(handlers::record_lab_result))
+        .route("/:result_id", delete(handlers::delete_lab_result))
+        .route_layer(middleware::from_fn(require_permission("grading")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Lot insurance policy routes (protected)
+fn insurance_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_insurance_policies).post(handlers::record_insurance_policy))
+        .route(
+            "/:policy_id",
+            get(handlers::get_insurance_policy).delete(handlers::delete_insurance_policy),
+        )
+        .route_layer(middleware::from_fn(require_permission("inventory")))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Storage location routes (protected)
+fn storage_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_storage_locations).post(handlers::create_storage_location))
+        .route("/heat-advisories", get(handlers::get_heat_advisories))
+        .route("/heat-advisories/notify", post(handlers::notify_heat_advisories))
+        .route(
+            "/:location_id",
+            get(handlers::get_storage_location)
+                .put(handlers::update_storage_location)
+                .delete(handlers::delete_storage_location),
+        )
+        .route("/:location_id/lots", get(handlers::list_stored_lots).post(handlers::store_lots))
+        .route("/:location_id/lots/:lot_id", delete(handlers::remove_stored_lot))
+        .route_layer(middleware::from_fn(require_permission("inventory")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -223,6 +692,9 @@ fn weather_routes() -> Router<AppState> {
         .route("/snapshots", get(handlers::get_weather_snapshots_by_range).post(handlers::store_weather_snapshot))
         .route("/snapshots/:snapshot_id", get(handlers::get_weather_snapshot))
         .route("/snapshots/location", get(handlers::get_weather_snapshots_by_location))
+        // Daily aggregates and manual rain gauges
+        .route("/daily", get(handlers::get_daily_weather))
+        .route("/rainfall", get(handlers::list_rainfall).post(handlers::record_rainfall))
         // Current weather and forecast (from API)
         .route("/current", get(handlers::fetch_current_weather))
         .route("/forecast", get(handlers::get_weather_forecast))
@@ -233,7 +705,20 @@ fn weather_routes() -> Router<AppState> {
         // Alerts
         .route("/alerts", get(handlers::list_weather_alerts).post(handlers::create_weather_alert))
         .route("/alerts/:alert_id", delete(handlers::delete_weather_alert))
+        .route("/alerts/:alert_id/snooze", post(handlers::snooze_weather_alert).delete(handlers::unmute_weather_alert))
+        .route("/plots/:plot_id/alerts/snooze", post(handlers::snooze_plot_weather_alerts))
         .route("/alerts/check-rain", get(handlers::check_rain_alerts))
+        // Farm weather stations
+        .route("/stations", get(handlers::list_weather_stations).post(handlers::create_weather_station))
+        .route("/stations/templates", get(handlers::list_weather_station_templates))
+        .route(
+            "/stations/:station_id",
+            get(handlers::get_weather_station)
+                .put(handlers::update_weather_station)
+                .delete(handlers::delete_weather_station),
+        )
+        .route("/stations/:station_id/token", post(handlers::rotate_weather_station_token))
+        .route_layer(middleware::from_fn(require_permission("plot")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
@@ -256,6 +741,8 @@ fn certification_routes() -> Router<AppState> {
         // Compliance
         .route("/:certification_id/compliance", get(handlers::get_compliance))
         .route("/:certification_id/compliance/:requirement_id", put(handlers::update_compliance))
+        .route("/:certification_id/issues", get(handlers::list_compliance_issues))
+        .route("/:certification_id/issues/:issue_id/resolve", put(handlers::resolve_compliance_issue))
         // Requirements (by type)
         .route("/requirements/:cert_type", get(handlers::get_requirements))
         // Expiration alerts
@@ -263,10 +750,14 @@ fn certification_routes() -> Router<AppState> {
         .route("/alerts/check", get(handlers::check_expiration_alerts))
         // Traceability integration
         .route("/for-lot", get(handlers::get_certifications_for_lot))
+        // Thai GAP application
+        .route("/thai-gap/submission.xlsx", get(handlers::export_gap_submission_xlsx))
+        .route("/thai-gap/submission.pdf", get(handlers::export_gap_submission_pdf))
+        .route_layer(middleware::from_fn(require_permission("certification")))
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
-/// Notification management routes (protected)
+/// Notification management routes (protected; a user's own notifications)
 fn notification_routes() -> Router<AppState> {
     Router::new()
         // Preferences
@@ -279,20 +770,88 @@ fn notification_routes() -> Router<AppState> {
         .route("/:notification_id/dismiss", post(handlers::dismiss_notification))
         // History
         .route("/history", get(handlers::get_notification_history))
+        .merge(notification_admin_routes())
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// Notification sending, trigger and escalation routes (business admins)
+fn notification_admin_routes() -> Router<AppState> {
+    Router::new()
         // Send (for testing/admin)
         .route("/send", post(handlers::send_notification))
         // Triggers
         .route("/triggers/inventory", post(handlers::trigger_inventory_alerts))
         .route("/triggers/certifications", post(handlers::trigger_certification_alerts))
         .route("/triggers/weather", post(handlers::trigger_weather_alerts))
+        .route("/triggers/insurance", post(handlers::trigger_insurance_alerts))
+        .route("/triggers/escalations", post(handlers::trigger_escalations))
+        .route("/triggers/kpi-summary", post(handlers::trigger_kpi_summary))
+        .route("/triggers/weekly-digest", post(handlers::trigger_weekly_digest))
         .route("/triggers/all", post(handlers::run_all_triggers))
+        // Escalation policies
+        .route("/escalation-policies", get(handlers::list_escalation_policies).post(handlers::create_escalation_policy))
+        .route("/escalation-policies/:policy_id", delete(handlers::delete_escalation_policy))
         // Queue processing
         .route("/queue/process", post(handlers::process_queue))
+        .route_layer(middleware::from_fn(require_permission("business:edit")))
+}
+
+/// Knowledge base routes (protected; readable by every member)
+fn knowledge_routes() -> Router<AppState> {
+    Router::new()
+        .route("/articles", get(handlers::list_knowledge_articles))
+        .route("/articles/:article_id", get(handlers::get_knowledge_article))
+        .route("/articles/:article_id/read", post(handlers::mark_knowledge_article_read))
+        .route("/recommended", get(handlers::get_knowledge_recommendations))
+        .merge(knowledge_admin_routes())
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
+/// Knowledge base management routes (business admins)
+fn knowledge_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/articles", post(handlers::create_knowledge_article))
+        .route(
+            "/articles/:article_id",
+            put(handlers::update_knowledge_article).delete(handlers::delete_knowledge_article),
+        )
+        .route("/articles/:article_id/reads", get(handlers::get_knowledge_article_readership))
+        .route_layer(middleware::from_fn(require_permission("business:edit")))
+}
+
+/// SOP checklist routes (protected; run by every member)
+fn checklist_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_sop_checklists))
+        .route("/executions", get(handlers::list_sop_executions))
+        .route("/executions/:execution_id", get(handlers::get_sop_execution))
+        .route("/executions/:execution_id/checks", post(handlers::check_sop_execution_items))
+        .route("/:checklist_id", get(handlers::get_sop_checklist))
+        .route("/:checklist_id/executions", post(handlers::start_sop_execution))
+        .route("/:checklist_id/compliance", get(handlers::get_sop_checklist_compliance))
+        .merge(checklist_admin_routes())
+        .route_layer(middleware::from_fn(auth_middleware))
+}
 
-/// Sync routes for offline support (protected)
+/// SOP checklist management routes (business admins)
+fn checklist_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(handlers::create_sop_checklist))
+        .route("/from-sop", post(handlers::create_sop_checklist_from_article))
+        .route("/:checklist_id", put(handlers::update_sop_checklist))
+        .route_layer(middleware::from_fn(require_permission("business:edit")))
+}
+
+/// Activity feed routes (protected; readable by every member)
+fn activity_feed_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::get_activity_feed))
+        .route("/seen", post(handlers::mark_activity_feed_seen))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+
+/// Sync routes for offline support (protected; changes are checked per entity)
 fn sync_routes() -> Router<AppState> {
     Router::new()
         .route("/changes", post(handlers::get_changes))
@@ -302,12 +861,61 @@ fn sync_routes() -> Router<AppState> {
         .route_layer(middleware::from_fn(auth_middleware))
 }
 
+/// Reference data routes (protected; catalogs are readable by every member)
+fn reference_data_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::get_reference_data))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
 /// Reporting routes (protected)
 fn reporting_routes() -> Router<AppState> {
     Router::new()
         .route("/dashboard", get(handlers::get_dashboard))
+        .route("/kpi", get(handlers::get_kpi_report))
+        .route("/kpi-targets", get(handlers::list_kpi_targets))
+        .route(
+            "/kpi-targets/:season",
+            put(handlers::set_kpi_targets).delete(handlers::delete_kpi_targets),
+        )
+        .route("/weekly-digest", get(handlers::get_weekly_digest))
+        .route("/pricing", get(handlers::get_pricing_report))
+        .route("/plot-profitability", get(handlers::get_plot_profitability_report))
+        .route("/picker-quality", get(handlers::get_picker_quality_report))
+        .route("/data-quality", get(handlers::get_data_quality_dashboard))
+        .route("/benchmarks", get(handlers::get_regional_benchmarks))
         .route("/harvest-yield", get(handlers::get_harvest_yield_report))
         .route("/quality-trend", get(handlers::get_quality_trend_report))
         .route("/processing-efficiency", get(handlers::get_processing_efficiency_report))
+        .route("/builder/entities", get(handlers::list_report_entities))
+        .route("/builder/run", post(handlers::run_report_builder))
+        .route("/saved", get(handlers::list_saved_reports).post(handlers::create_saved_report))
+        .route(
+            "/saved/:report_id",
+            get(handlers::get_saved_report)
+                .put(handlers::update_saved_report)
+                .delete(handlers::delete_saved_report),
+        )
+        .route("/saved/:report_id/run", get(handlers::run_saved_report))
+        .route_layer(middleware::from_fn(require_permission("report:view")))
+        .merge(report_export_routes())
         .route_layer(middleware::from_fn(auth_middleware))
 }
+
+/// Report export and scheduled delivery routes
+fn report_export_routes() -> Router<AppState> {
+    Router::new()
+        .route("/schedules", get(handlers::list_report_schedules).post(handlers::create_report_schedule))
+        .route(
+            "/schedules/:schedule_id",
+            get(handlers::get_report_schedule)
+                .put(handlers::update_report_schedule)
+                .delete(handlers::delete_report_schedule),
+        )
+        .route("/schedules/:schedule_id/run", post(handlers::run_report_schedule))
+        .route("/export/inventory-summary", get(handlers::export_inventory_summary))
+        .route("/export/cupping-sessions/:session_id", get(handlers::export_cupping_session))
+        .route("/export/financials", get(handlers::export_financials))
+        .route("/schedules/:schedule_id/deliveries", get(handlers::list_report_deliveries))
+        .route_layer(middleware::from_fn(require_permission("report:export")))
+}
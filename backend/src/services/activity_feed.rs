@@ -0,0 +1,264 @@
+//! Business activity feed
+//!
+//! Database triggers append an entry whenever a lot is created or changes
+//! stage, or a harvest, processing record, grading, cupping score or sale is
+//! recorded (see the activity feed migration). The feed is paged newest
+//! first with the id of the last entry seen as the cursor. Each member's last
+//! visit is kept, so the app can show what happened while they were away.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Activity types recorded in the feed
+pub const ACTIVITY_TYPES: [&str; 7] = [
+    "lot_created",
+    "lot_stage_changed",
+    "harvest_recorded",
+    "processing_started",
+    "grading_recorded",
+    "cupping_scored",
+    "sale_recorded",
+];
+
+/// Entity types activities are recorded for
+pub const ENTITY_TYPES: [&str; 6] = [
+    "lot",
+    "harvest",
+    "processing_record",
+    "green_bean_grade",
+    "cupping_sample",
+    "inventory_transaction",
+];
+
+/// Entries per page when no limit is given
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Most entries per page
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+/// Activity feed service
+#[derive(Clone)]
+pub struct ActivityFeedService {
+    db: PgPool,
+}
+
+/// Activity feed filters and paging
+#[derive(Debug, Default, Deserialize)]
+pub struct ActivityFeedQuery {
+    pub activity_type: Option<String>,
+    pub entity_type: Option<String>,
+    pub lot_id: Option<Uuid>,
+    /// Only activity after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Cursor: only entries older than this entry id (`next_before` of the
+    /// previous page)
+    pub before: Option<i64>,
+    /// Entries per page (default 50, at most 200)
+    pub limit: Option<i64>,
+}
+
+/// Feed entry as stored, with its lot
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ActivityRow {
+    pub id: i64,
+    pub activity_type: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub lot_id: Option<Uuid>,
+    pub lot_name: Option<String>,
+    pub traceability_code: Option<String>,
+    pub details: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Feed entry
+#[derive(Debug, Serialize)]
+pub struct ActivityItem {
+    pub id: i64,
+    pub activity_type: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub lot_id: Option<Uuid>,
+    pub lot_name: Option<String>,
+    pub traceability_code: Option<String>,
+    pub details: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+    /// Client path of the page the activity is shown on
+    pub action_url: Option<String>,
+    /// Happened after the member last viewed the feed
+    pub unseen: bool,
+}
+
+/// Page of the activity feed
+#[derive(Debug, Serialize)]
+pub struct ActivityFeed {
+    pub items: Vec<ActivityItem>,
+    /// Cursor for the next (older) page; None on the last page
+    pub next_before: Option<i64>,
+    /// When the member last viewed the feed; None if never
+    pub seen_at: Option<DateTime<Utc>>,
+    /// Activities since then, across the whole feed
+    pub unseen_count: i64,
+}
+
+/// When the member last viewed the feed
+#[derive(Debug, Serialize)]
+pub struct ActivityFeedSeen {
+    pub seen_at: DateTime<Utc>,
+}
+
+fn validate_filter(field: &str, value: Option<&str>, allowed: &[&str]) -> AppResult<()> {
+    match value {
+        Some(value) if !allowed.contains(&value) => Err(AppError::Validation {
+            field: field.to_string(),
+            message: format!("Must be one of: {}", allowed.join(", ")),
+            message_th: format!("ต้องเป็นหนึ่งใน: {}", allowed.join(", ")),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Entries per page for a requested limit
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Cut rows fetched one past the page size down to the page, with the
+/// cursor for the next page when there are more
+pub fn paginate<T>(mut rows: Vec<T>, page_size: i64, id: impl Fn(&T) -> i64) -> (Vec<T>, Option<i64>) {
+    let page_size = page_size.max(0) as usize;
+    if rows.len() <= page_size {
+        return (rows, None);
+    }
+    rows.truncate(page_size);
+    let next_before = rows.last().map(id);
+    (rows, next_before)
+}
+
+/// Client path of the page an activity is shown on: the cupping session of
+/// a score, otherwise the lot
+pub fn activity_action_url(entity_type: &str, lot_id: Option<Uuid>, details: &serde_json::Value) -> Option<String> {
+    if entity_type == "cupping_sample" {
+        let session_id = details.get("session_id")?.as_str()?;
+        return Some(format!("/cupping/sessions/{}", session_id));
+    }
+    lot_id.map(|lot_id| format!("/lots/{}", lot_id))
+}
+
+/// Whether an activity happened after the member last viewed the feed
+pub fn is_unseen(occurred_at: DateTime<Utc>, seen_at: Option<DateTime<Utc>>) -> bool {
+    seen_at.is_none_or(|seen_at| occurred_at > seen_at)
+}
+
+impl ActivityFeedService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Page of the feed, newest first
+    pub async fn list(&self, business_id: Uuid, user_id: Uuid, query: &ActivityFeedQuery) -> AppResult<ActivityFeed> {
+        validate_filter("activity_type", query.activity_type.as_deref(), &ACTIVITY_TYPES)?;
+        validate_filter("entity_type", query.entity_type.as_deref(), &ENTITY_TYPES)?;
+        let page_size = page_size(query.limit);
+
+        // The lot of a blind cupping score stays hidden until the session
+        // is revealed, as in the cupping endpoints
+        let rows = sqlx::query_as::<_, ActivityRow>(
+            r#"
+            SELECT f.id, f.activity_type, f.entity_type, f.entity_id,
+                   CASE WHEN cs.is_blind AND cs.revealed_at IS NULL THEN NULL ELSE f.lot_id END AS lot_id,
+                   CASE WHEN cs.is_blind AND cs.revealed_at IS NULL THEN NULL ELSE l.name END AS lot_name,
+                   CASE WHEN cs.is_blind AND cs.revealed_at IS NULL THEN NULL ELSE l.traceability_code END
+                       AS traceability_code,
+                   f.details, f.occurred_at
+            FROM activity_feed f
+            LEFT JOIN lots l ON l.id = f.lot_id
+            LEFT JOIN cupping_sessions cs
+                ON f.entity_type = 'cupping_sample' AND cs.id = (f.details->>'session_id')::UUID
+            WHERE f.business_id = $1
+              AND ($2::TEXT IS NULL OR f.activity_type = $2)
+              AND ($3::TEXT IS NULL OR f.entity_type = $3)
+              AND ($4::UUID IS NULL OR (f.lot_id = $4 AND NOT COALESCE(cs.is_blind AND cs.revealed_at IS NULL, FALSE)))
+              AND ($5::TIMESTAMPTZ IS NULL OR f.occurred_at > $5)
+              AND ($6::BIGINT IS NULL OR f.id < $6)
+            ORDER BY f.id DESC
+            LIMIT $7
+            "#,
+        )
+        .bind(business_id)
+        .bind(&query.activity_type)
+        .bind(&query.entity_type)
+        .bind(query.lot_id)
+        .bind(query.since)
+        .bind(query.before)
+        .bind(page_size + 1)
+        .fetch_all(&self.db)
+        .await?;
+        let (rows, next_before) = paginate(rows, page_size, |row| row.id);
+
+        let seen_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT seen_at FROM activity_feed_reads WHERE business_id = $1 AND user_id = $2",
+        )
+        .bind(business_id)
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        let unseen_count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM activity_feed
+            WHERE business_id = $1 AND ($2::TIMESTAMPTZ IS NULL OR occurred_at > $2)
+            "#,
+        )
+        .bind(business_id)
+        .bind(seen_at)
+        .fetch_one(&self.db)
+        .await?;
+
+        let items = rows
+            .into_iter()
+            .map(|row| ActivityItem {
+                action_url: activity_action_url(&row.entity_type, row.lot_id, &row.details),
+                unseen: is_unseen(row.occurred_at, seen_at),
+                id: row.id,
+                activity_type: row.activity_type,
+                entity_type: row.entity_type,
+                entity_id: row.entity_id,
+                lot_id: row.lot_id,
+                lot_name: row.lot_name,
+                traceability_code: row.traceability_code,
+                details: row.details,
+                occurred_at: row.occurred_at,
+            })
+            .collect();
+
+        Ok(ActivityFeed {
+            items,
+            next_before,
+            seen_at,
+            unseen_count,
+        })
+    }
+
+    /// Record that the member has viewed the feed up to now
+    pub async fn mark_seen(&self, business_id: Uuid, user_id: Uuid) -> AppResult<ActivityFeedSeen> {
+        let seen_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            INSERT INTO activity_feed_reads (business_id, user_id, seen_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (business_id, user_id) DO UPDATE SET seen_at = EXCLUDED.seen_at
+            RETURNING seen_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(ActivityFeedSeen { seen_at })
+    }
+}
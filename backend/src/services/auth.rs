@@ -1,16 +1,61 @@
 //! Authentication service for user registration, login, and token management
 
 use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::error::{AppError, AppResult};
+use crate::external::SmtpMailer;
+use crate::services::line_oauth::LineSignIn;
+use crate::services::totp::{
+    generate_backup_codes, generate_secret, looks_like_totp, normalize_backup_code, otpauth_uri, verify_totp,
+};
 use shared::types::Language;
 
+/// How long a password reset link stays valid
+pub const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+
+/// How long an email verification link stays valid
+pub const EMAIL_VERIFICATION_TTL_HOURS: i64 = 48;
+
+/// Minimum time between two reset emails to the same account
+pub const RESET_REQUEST_COOLDOWN_SECONDS: i64 = 120;
+
+/// Shortest password accepted on reset
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Longest password bcrypt hashes in full
+pub const MAX_PASSWORD_BYTES: usize = 72;
+
+/// Longest device label kept for a session
+pub const MAX_DEVICE_NAME_CHARS: usize = 100;
+
+/// Wrong passwords or codes in a row that lock an account
+pub const MAX_FAILED_LOGINS: i32 = 5;
+
+/// Length of the first lockout; each further lockout doubles it
+pub const LOCKOUT_BASE_MINUTES: i64 = 5;
+
+/// Longest lockout
+pub const MAX_LOCKOUT_MINUTES: i64 = 24 * 60;
+
+/// Failed sign-ins from one address refused within the throttle window
+pub const MAX_FAILED_LOGINS_PER_IP: i64 = 20;
+
+/// Window over which failed sign-ins from one address are counted
+pub const IP_THROTTLE_WINDOW_MINUTES: i64 = 15;
+
+/// Business type of owners signing up with LINE, mostly smallholders
+pub const DEFAULT_LINE_BUSINESS_TYPE: &str = "farmer";
+
+/// Tries at a free generated business code
+const GENERATED_CODE_ATTEMPTS: usize = 5;
+
 /// Authentication service
 #[derive(Clone)]
 pub struct AuthService {
@@ -18,6 +63,8 @@ pub struct AuthService {
     jwt_secret: String,
     access_token_expiry: i64,
     refresh_token_expiry: i64,
+    mailer: Option<SmtpMailer>,
+    public_url: String,
 }
 
 /// Input for registering a new business with owner account
@@ -45,6 +92,43 @@ pub struct RegisterResponse {
     pub expires_in: i64,
 }
 
+/// Input for registering a new business whose owner signs in with LINE
+#[derive(Debug, Default)]
+pub struct RegisterLineBusinessInput {
+    /// Defaults to the owner name
+    pub business_name: Option<String>,
+    /// Defaults to `farmer`
+    pub business_type: Option<String>,
+    /// Generated when not given
+    pub business_code: Option<String>,
+    /// Defaults to the LINE display name
+    pub owner_name: Option<String>,
+    pub phone: Option<String>,
+    pub province: Option<String>,
+    pub preferred_language: Option<Language>,
+}
+
+/// Business and owner being created
+struct NewBusinessOwner<'a> {
+    business_name: &'a str,
+    business_type: &'a str,
+    business_code: &'a str,
+    owner_name: &'a str,
+    /// Both unset for LINE-only owners
+    email: Option<&'a str>,
+    password_hash: Option<&'a str>,
+    phone: Option<&'a str>,
+    province: Option<&'a str>,
+    language: Language,
+}
+
+/// Business and owner just created
+struct BusinessOwner {
+    business_id: Uuid,
+    user_id: Uuid,
+    role_id: Uuid,
+}
+
 /// JWT claims structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -52,6 +136,9 @@ pub struct Claims {
     pub business_id: String,
     pub role_id: String,
     pub permissions: Vec<String>,
+    /// Session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
     pub exp: i64,
     pub iat: i64,
 }
@@ -76,6 +163,283 @@ pub struct UserRow {
     pub name: String,
     pub preferred_language: String,
     pub is_active: bool,
+    pub two_factor_enabled: bool,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Client a session is signed in from
+#[derive(Debug, Clone, Default)]
+pub struct SessionDevice {
+    /// Label chosen by the client, e.g. "Packing room tablet"
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// Signed-in session as listed to its user
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuthSession {
+    pub id: Uuid,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// When the refresh token was last rotated
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Session of the access token making the request
+    pub current: bool,
+}
+
+/// Refresh token looked up on refresh
+#[derive(Debug, sqlx::FromRow)]
+struct RefreshTokenRow {
+    id: Uuid,
+    user_id: Uuid,
+    business_id: Uuid,
+    role_id: Uuid,
+    is_active: bool,
+    session_id: Option<Uuid>,
+    session_started_at: Option<DateTime<Utc>>,
+    device_info: Option<String>,
+    user_agent: Option<String>,
+    expires_at: DateTime<Utc>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Two-factor state of an account
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// Unused backup codes left
+    pub backup_codes_remaining: i64,
+}
+
+/// Secret to add to an authenticator app, pending confirmation
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// otpauth URI to show as a QR code
+    pub otpauth_uri: String,
+}
+
+/// Backup codes, shown once when issued
+#[derive(Debug, Serialize)]
+pub struct BackupCodes {
+    pub backup_codes: Vec<String>,
+}
+
+/// TOTP settings of an account
+#[derive(Debug, sqlx::FromRow)]
+struct TotpRow {
+    /// Null for LINE-only accounts
+    email: Option<String>,
+    password_hash: Option<String>,
+    totp_secret: Option<String>,
+    totp_enabled_at: Option<DateTime<Utc>>,
+    totp_last_step: Option<i64>,
+}
+
+/// What an emailed token is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+        }
+    }
+
+    /// Frontend page the emailed link opens
+    fn path(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "reset-password",
+            TokenPurpose::EmailVerification => "verify-email",
+        }
+    }
+
+    fn lifetime(&self) -> Duration {
+        match self {
+            TokenPurpose::PasswordReset => Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+            TokenPurpose::EmailVerification => Duration::hours(EMAIL_VERIFICATION_TTL_HOURS),
+        }
+    }
+}
+
+/// Account a recovery email goes to
+#[derive(Debug, sqlx::FromRow)]
+struct RecoveryUser {
+    id: Uuid,
+    email: String,
+    name: String,
+    preferred_language: String,
+    email_verified: bool,
+}
+
+/// Check a new password; bcrypt ignores everything past 72 bytes
+pub fn validate_new_password(password: &str) -> AppResult<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH || password.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: "new_password".to_string(),
+            message: format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH),
+            message_th: format!("รหัสผ่านต้องมีอย่างน้อย {} ตัวอักษร", MIN_PASSWORD_LENGTH),
+        });
+    }
+    if password.len() > MAX_PASSWORD_BYTES {
+        return Err(AppError::Validation {
+            field: "new_password".to_string(),
+            message: format!("Password must be at most {} bytes", MAX_PASSWORD_BYTES),
+            message_th: format!("รหัสผ่านต้องยาวไม่เกิน {} ไบต์", MAX_PASSWORD_BYTES),
+        });
+    }
+    Ok(())
+}
+
+/// SHA-256 of an emailed or refresh token, as stored
+pub fn hash_action_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+}
+
+/// Subject and body of a recovery email
+fn recovery_email(purpose: TokenPurpose, name: &str, link: &str, thai: bool) -> (String, String) {
+    match (purpose, thai) {
+        (TokenPurpose::PasswordReset, true) => (
+            "ตั้งรหัสผ่านใหม่".to_string(),
+            format!(
+                "สวัสดีคุณ {}\n\nมีการขอตั้งรหัสผ่านใหม่สำหรับบัญชีของคุณ เปิดลิงก์นี้ภายใน {} นาทีเพื่อตั้งรหัสผ่านใหม่:\n{}\n\nหากคุณไม่ได้ขอ ไม่ต้องดำเนินการใด ๆ รหัสผ่านเดิมยังใช้ได้",
+                name, PASSWORD_RESET_TTL_MINUTES, link
+            ),
+        ),
+        (TokenPurpose::PasswordReset, false) => (
+            "Reset your password".to_string(),
+            format!(
+                "Hello {},\n\nA password reset was requested for your account. Open this link within {} minutes to choose a new password:\n{}\n\nIf you did not ask for this, ignore this email; your password is unchanged.",
+                name, PASSWORD_RESET_TTL_MINUTES, link
+            ),
+        ),
+        (TokenPurpose::EmailVerification, true) => (
+            "ยืนยันอีเมลของคุณ".to_string(),
+            format!(
+                "สวัสดีคุณ {}\n\nเปิดลิงก์นี้ภายใน {} ชั่วโมงเพื่อยืนยันอีเมลของคุณ:\n{}",
+                name, EMAIL_VERIFICATION_TTL_HOURS, link
+            ),
+        ),
+        (TokenPurpose::EmailVerification, false) => (
+            "Verify your email".to_string(),
+            format!(
+                "Hello {},\n\nOpen this link within {} hours to verify your email address:\n{}",
+                name, EMAIL_VERIFICATION_TTL_HOURS, link
+            ),
+        ),
+    }
+}
+
+/// Label shown for a session: the client's name for the device, else the
+/// browser and platform read from the user agent
+pub fn device_label(device_name: Option<&str>, user_agent: Option<&str>) -> Option<String> {
+    let named = device_name
+        .map(|n| n.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|n| !n.is_empty())
+        .map(|n| n.chars().take(MAX_DEVICE_NAME_CHARS).collect());
+    named.or_else(|| user_agent.and_then(describe_user_agent))
+}
+
+/// "Chrome on Android" from a user agent; `None` when neither part is known
+pub fn describe_user_agent(user_agent: &str) -> Option<String> {
+    // Order matters: Edge and the LINE in-app browser also claim Chrome and
+    // Safari, and Chrome claims Safari
+    let browser = [
+        (" Line/", "LINE"),
+        ("Edg/", "Edge"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("CriOS/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| user_agent.contains(marker))
+    .map(|(_, name)| name);
+    let platform = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| user_agent.contains(marker))
+    .map(|(_, name)| name);
+
+    match (browser, platform) {
+        (Some(browser), Some(platform)) => Some(format!("{} on {}", browser, platform)),
+        (Some(name), None) | (None, Some(name)) => Some(name.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Length of an account's lockout after `lockout_count` earlier lockouts
+pub fn lockout_minutes(lockout_count: i32) -> i64 {
+    let doublings = lockout_count.clamp(0, 16) as u32;
+    (LOCKOUT_BASE_MINUTES << doublings).min(MAX_LOCKOUT_MINUTES)
+}
+
+/// Seconds an address must wait, given its failed sign-ins within the
+/// throttle window and when the oldest of them was made
+pub fn ip_retry_after(failures: i64, oldest_failure: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<i64> {
+    if failures < MAX_FAILED_LOGINS_PER_IP {
+        return None;
+    }
+    let frees_at = oldest_failure? + Duration::minutes(IP_THROTTLE_WINDOW_MINUTES);
+    Some((frees_at - now).num_seconds().max(1))
+}
+
+/// Business code for an owner who did not choose one: `L` and six
+/// uppercase hex digits
+pub fn generated_business_code(seed: Uuid) -> String {
+    format!("L{}", &seed.simple().to_string()[..6]).to_uppercase()
+}
+
+fn invalid_login() -> AppError {
+    AppError::Unauthorized {
+        message: "Invalid email or password".to_string(),
+        message_th: "อีเมลหรือรหัสผ่านไม่ถูกต้อง".to_string(),
+    }
+}
+
+fn account_locked(locked_until: DateTime<Utc>) -> AppError {
+    AppError::TooManyLoginAttempts {
+        retry_after_secs: (locked_until - Utc::now()).num_seconds().max(1),
+    }
+}
+
+fn invalid_refresh_token() -> AppError {
+    AppError::Unauthorized {
+        message: "Invalid or expired refresh token".to_string(),
+        message_th: "โทเค็นรีเฟรชไม่ถูกต้องหรือหมดอายุ".to_string(),
+    }
+}
+
+fn invalid_second_factor() -> AppError {
+    AppError::Unauthorized {
+        message: "Invalid two-factor code".to_string(),
+        message_th: "รหัสยืนยันตัวตนสองขั้นตอนไม่ถูกต้อง".to_string(),
+    }
+}
+
+fn invalid_link() -> AppError {
+    AppError::Validation {
+        field: "token".to_string(),
+        message: "This link is invalid or has expired; request a new one".to_string(),
+        message_th: "ลิงก์ไม่ถูกต้องหรือหมดอายุแล้ว กรุณาขอลิงก์ใหม่".to_string(),
+    }
 }
 
 impl AuthService {
@@ -86,6 +450,8 @@ impl AuthService {
             jwt_secret: config.jwt.secret.clone(),
             access_token_expiry: config.jwt.access_token_expiry,
             refresh_token_expiry: config.jwt.refresh_token_expiry,
+            mailer: SmtpMailer::from_config(&config.email),
+            public_url: config.server.public_url.clone(),
         }
     }
 
@@ -93,55 +459,273 @@ impl AuthService {
     pub async fn register_business(
         &self,
         input: RegisterBusinessInput,
+        device: &SessionDevice,
     ) -> AppResult<RegisterResponse> {
-        // Validate business code format (3-10 uppercase alphanumeric)
-        if !Self::is_valid_business_code(&input.business_code) {
+        Self::validate_business_code(&input.business_code)?;
+        Self::validate_business_type(&input.business_type)?;
+        self.ensure_business_code_free(&input.business_code).await?;
+
+        // Hash password
+        let password_hash = hash(&input.password, DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))?;
+
+        let mut tx = self.db.begin().await?;
+        let owner = Self::create_business_owner(
+            &mut tx,
+            NewBusinessOwner {
+                business_name: &input.business_name,
+                business_type: &input.business_type,
+                business_code: &input.business_code,
+                owner_name: &input.owner_name,
+                email: Some(&input.email),
+                password_hash: Some(&password_hash),
+                phone: input.phone.as_deref(),
+                province: input.province.as_deref(),
+                language: input.preferred_language.unwrap_or(Language::Thai),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        // Verification email is best effort; the owner can request another
+        if self.mailer.is_some() {
+            if let Err(e) = self.send_email_verification(owner.user_id).await {
+                tracing::warn!("Verification email to user {} failed: {}", owner.user_id, e);
+            }
+        }
+
+        self.start_owner_session(owner, device).await
+    }
+
+    /// Register a new business whose owner signs in with LINE only. The
+    /// business and owner names default to the LINE display name and a
+    /// business code is generated when none is given
+    pub async fn register_with_line(
+        &self,
+        input: RegisterLineBusinessInput,
+        line: &LineSignIn,
+        device: &SessionDevice,
+    ) -> AppResult<RegisterResponse> {
+        let display_name = line.profile.display_name.trim();
+        let business_type = input.business_type.as_deref().unwrap_or(DEFAULT_LINE_BUSINESS_TYPE);
+        Self::validate_business_type(business_type)?;
+        let business_code = match input.business_code.as_deref() {
+            Some(code) => {
+                Self::validate_business_code(code)?;
+                self.ensure_business_code_free(code).await?;
+                code.to_string()
+            }
+            None => self.generate_business_code().await?,
+        };
+        let owner_name = input
+            .owner_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(display_name);
+        let business_name = input
+            .business_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(owner_name);
+        if business_name.is_empty() {
+            return Err(AppError::Validation {
+                field: "business_name".to_string(),
+                message: "Business name is required".to_string(),
+                message_th: "ต้องระบุชื่อธุรกิจ".to_string(),
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        // A LINE account belongs to one user
+        let registered = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM line_connections WHERE line_user_id = $1)",
+        )
+        .bind(&line.profile.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if registered {
+            return Err(AppError::Conflict {
+                resource: "line_connection".to_string(),
+                message: "This LINE account is already registered; sign in with LINE".to_string(),
+                message_th: "บัญชี LINE นี้ลงทะเบียนแล้ว กรุณาเข้าสู่ระบบด้วย LINE".to_string(),
+            });
+        }
+
+        let owner = Self::create_business_owner(
+            &mut tx,
+            NewBusinessOwner {
+                business_name,
+                business_type,
+                business_code: &business_code,
+                owner_name,
+                email: None,
+                password_hash: None,
+                phone: input.phone.as_deref(),
+                province: input.province.as_deref(),
+                language: input.preferred_language.unwrap_or(Language::Thai),
+            },
+        )
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO line_connections (
+                user_id, line_user_id, display_name, picture_url,
+                access_token, refresh_token, token_expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(owner.user_id)
+        .bind(&line.profile.user_id)
+        .bind(display_name)
+        .bind(&line.profile.picture_url)
+        .bind(&line.tokens.access_token)
+        .bind(&line.tokens.refresh_token)
+        .bind(Utc::now() + Duration::seconds(line.tokens.expires_in))
+        .execute(&mut *tx)
+        .await?;
+
+        // No address to email; notifications go to LINE
+        sqlx::query("UPDATE notification_preferences SET email_enabled = false WHERE user_id = $1")
+            .bind(owner.user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        self.start_owner_session(owner, device).await
+    }
+
+    /// Sign in with a LINE account connected to a user
+    pub async fn login_with_line(&self, line: &LineSignIn, device: &SessionDevice) -> AppResult<AuthTokens> {
+        let user = sqlx::query_as::<_, (Uuid, Uuid, Uuid, Uuid, bool, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT lc.id, u.id, u.business_id, u.role_id, u.is_active, u.locked_until
+            FROM line_connections lc
+            JOIN users u ON u.id = lc.user_id
+            WHERE lc.line_user_id = $1
+            "#,
+        )
+        .bind(&line.profile.user_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some((connection_id, user_id, business_id, role_id, is_active, locked_until)) = user else {
+            return Err(AppError::Unauthorized {
+                message: "This LINE account is not registered; sign up with LINE first".to_string(),
+                message_th: "บัญชี LINE นี้ยังไม่ได้ลงทะเบียน กรุณาสมัครด้วย LINE ก่อน".to_string(),
+            });
+        };
+        if let Some(locked_until) = locked_until.filter(|until| *until > Utc::now()) {
+            return Err(account_locked(locked_until));
+        }
+        if !is_active {
+            return Err(AppError::Unauthorized {
+                message: "Account is disabled".to_string(),
+                message_th: "บัญชีถูกปิดใช้งาน".to_string(),
+            });
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE line_connections
+            SET display_name = $2, picture_url = $3, access_token = $4,
+                refresh_token = COALESCE($5, refresh_token), token_expires_at = $6, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(connection_id)
+        .bind(&line.profile.display_name)
+        .bind(&line.profile.picture_url)
+        .bind(&line.tokens.access_token)
+        .bind(&line.tokens.refresh_token)
+        .bind(Utc::now() + Duration::seconds(line.tokens.expires_in))
+        .execute(&self.db)
+        .await?;
+        sqlx::query("UPDATE users SET last_login_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.db)
+            .await?;
+
+        let permissions = self.get_user_permissions(user_id).await?;
+        let session_id = Uuid::new_v4();
+        let tokens = self.generate_tokens(user_id, business_id, role_id, &permissions, session_id)?;
+        self.store_refresh_token(user_id, &tokens.refresh_token, session_id, Utc::now(), device)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    /// Validate business code format (3-10 uppercase alphanumeric)
+    fn validate_business_code(business_code: &str) -> AppResult<()> {
+        if !Self::is_valid_business_code(business_code) {
             return Err(AppError::Validation {
                 field: "business_code".to_string(),
                 message: "Business code must be 3-10 uppercase alphanumeric characters".to_string(),
                 message_th: "รหัสธุรกิจต้องเป็นตัวอักษรพิมพ์ใหญ่หรือตัวเลข 3-10 ตัว".to_string(),
             });
         }
+        Ok(())
+    }
 
-        // Validate business type
+    /// Validate business type
+    fn validate_business_type(business_type: &str) -> AppResult<()> {
         let valid_types = ["farmer", "processor", "roaster", "multi"];
-        if !valid_types.contains(&input.business_type.as_str()) {
+        if !valid_types.contains(&business_type) {
             return Err(AppError::Validation {
                 field: "business_type".to_string(),
                 message: "Invalid business type".to_string(),
                 message_th: "ประเภทธุรกิจไม่ถูกต้อง".to_string(),
             });
         }
+        Ok(())
+    }
 
-        // Check if business code already exists
+    async fn business_code_taken(&self, business_code: &str) -> AppResult<bool> {
         let existing = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM businesses WHERE business_code = $1",
         )
-        .bind(&input.business_code)
+        .bind(business_code)
         .fetch_one(&self.db)
         .await?;
+        Ok(existing > 0)
+    }
 
-        if existing > 0 {
+    async fn ensure_business_code_free(&self, business_code: &str) -> AppResult<()> {
+        if self.business_code_taken(business_code).await? {
             return Err(AppError::Conflict {
                 resource: "business".to_string(),
                 message: "Business code already exists".to_string(),
                 message_th: "รหัสธุรกิจนี้มีอยู่แล้ว".to_string(),
             });
         }
+        Ok(())
+    }
 
-        // Hash password
-        let password_hash = hash(&input.password, DEFAULT_COST)
-            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))?;
+    /// Pick an unused business code for an owner who did not choose one
+    async fn generate_business_code(&self) -> AppResult<String> {
+        for _ in 0..GENERATED_CODE_ATTEMPTS {
+            let code = generated_business_code(Uuid::new_v4());
+            if !self.business_code_taken(&code).await? {
+                return Ok(code);
+            }
+        }
+        Err(AppError::Internal("Could not generate a free business code".to_string()))
+    }
 
-        let language = input.preferred_language.unwrap_or(Language::Thai);
-        let language_str = match language {
+    /// Create a business and its owner; triggers create the default roles
+    async fn create_business_owner(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        owner: NewBusinessOwner<'_>,
+    ) -> AppResult<BusinessOwner> {
+        let language_str = match owner.language {
             Language::Thai => "th",
             Language::English => "en",
         };
 
-        // Start transaction
-        let mut tx = self.db.begin().await?;
-
         // Create business (triggers will create default roles)
         let business_id = sqlx::query_scalar::<_, Uuid>(
             r#"
@@ -150,21 +734,21 @@ impl AuthService {
             RETURNING id
             "#,
         )
-        .bind(&input.business_name)
-        .bind(&input.business_type)
-        .bind(&input.business_code)
-        .bind(&input.phone)
-        .bind(&input.province)
+        .bind(owner.business_name)
+        .bind(owner.business_type)
+        .bind(owner.business_code)
+        .bind(owner.phone)
+        .bind(owner.province)
         .bind(language_str)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
         // Get the owner role (created by trigger)
-        let owner_role_id = sqlx::query_scalar::<_, Uuid>(
+        let role_id = sqlx::query_scalar::<_, Uuid>(
             "SELECT id FROM roles WHERE business_id = $1 AND name = 'owner'",
         )
         .bind(business_id)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
         // Create owner user
@@ -176,13 +760,13 @@ impl AuthService {
             "#,
         )
         .bind(business_id)
-        .bind(owner_role_id)
-        .bind(&input.email)
-        .bind(&password_hash)
-        .bind(&input.owner_name)
-        .bind(&input.phone)
+        .bind(role_id)
+        .bind(owner.email)
+        .bind(owner.password_hash)
+        .bind(owner.owner_name)
+        .bind(owner.phone)
         .bind(language_str)
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut **tx)
         .await?;
 
         // Create notification preferences with defaults
@@ -190,24 +774,32 @@ impl AuthService {
             "INSERT INTO notification_preferences (user_id) VALUES ($1)",
         )
         .bind(user_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
 
-        // Commit transaction
-        tx.commit().await?;
+        Ok(BusinessOwner {
+            business_id,
+            user_id,
+            role_id,
+        })
+    }
 
+    /// Sign a newly registered owner in
+    async fn start_owner_session(&self, owner: BusinessOwner, device: &SessionDevice) -> AppResult<RegisterResponse> {
         // Get user permissions for token
-        let permissions = self.get_user_permissions(user_id).await?;
+        let permissions = self.get_user_permissions(owner.user_id).await?;
 
-        // Generate tokens
-        let tokens = self.generate_tokens(user_id, business_id, owner_role_id, &permissions)?;
+        // Generate tokens for a new session
+        let session_id = Uuid::new_v4();
+        let tokens = self.generate_tokens(owner.user_id, owner.business_id, owner.role_id, &permissions, session_id)?;
 
         // Store refresh token
-        self.store_refresh_token(user_id, &tokens.refresh_token).await?;
+        self.store_refresh_token(owner.user_id, &tokens.refresh_token, session_id, Utc::now(), device)
+            .await?;
 
         Ok(RegisterResponse {
-            business_id,
-            user_id,
+            business_id: owner.business_id,
+            user_id: owner.user_id,
             access_token: tokens.access_token,
             refresh_token: tokens.refresh_token,
             token_type: tokens.token_type,
@@ -215,23 +807,42 @@ impl AuthService {
         })
     }
 
-    /// Authenticate user with email and password
-    pub async fn login(&self, email: &str, password: &str) -> AppResult<AuthTokens> {
+    /// Authenticate user with email and password, plus a TOTP or backup
+    /// code when the account has two-factor authentication enabled. Locked
+    /// accounts and throttled addresses are refused before any check
+    pub async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        otp_code: Option<&str>,
+        device: &SessionDevice,
+    ) -> AppResult<AuthTokens> {
+        if let Some(ip_address) = device.ip_address.as_deref() {
+            self.check_ip_throttle(ip_address).await?;
+        }
+
         // Find user by email
         let user = sqlx::query_as::<_, UserRow>(
             r#"
-            SELECT id, business_id, role_id, email, password_hash, name, preferred_language, is_active
+            SELECT id, business_id, role_id, email, password_hash, name, preferred_language, is_active,
+                   totp_enabled_at IS NOT NULL AS two_factor_enabled, locked_until
             FROM users
             WHERE email = $1
             "#,
         )
         .bind(email)
         .fetch_optional(&self.db)
-        .await?
-        .ok_or_else(|| AppError::Unauthorized {
-            message: "Invalid email or password".to_string(),
-            message_th: "อีเมลหรือรหัสผ่านไม่ถูกต้อง".to_string(),
-        })?;
+        .await?;
+
+        let Some(user) = user else {
+            self.record_login_attempt(email, None, device, false).await?;
+            return Err(invalid_login());
+        };
+
+        if let Some(locked_until) = user.locked_until.filter(|until| *until > Utc::now()) {
+            self.record_login_attempt(email, Some(user.id), device, false).await?;
+            return Err(account_locked(locked_until));
+        }
 
         // Check if user is active
         if !user.is_active {
@@ -246,81 +857,637 @@ impl AuthService {
             .map_err(|e| AppError::Internal(format!("Password verification failed: {}", e)))?;
 
         if !valid {
-            return Err(AppError::Unauthorized {
-                message: "Invalid email or password".to_string(),
-                message_th: "อีเมลหรือรหัสผ่านไม่ถูกต้อง".to_string(),
-            });
+            self.record_failed_login(email, user.id, device).await?;
+            return Err(invalid_login());
         }
 
-        // Update last login
-        sqlx::query("UPDATE users SET last_login_at = NOW() WHERE id = $1")
-            .bind(user.id)
-            .execute(&self.db)
-            .await?;
+        // Second factor, asked for only once the password is right
+        if user.two_factor_enabled {
+            let code = otp_code
+                .filter(|c| !c.trim().is_empty())
+                .ok_or(AppError::TwoFactorRequired)?;
+            if !self.check_second_factor(user.id, code).await? {
+                self.record_failed_login(email, user.id, device).await?;
+                return Err(invalid_second_factor());
+            }
+        }
+
+        // Update last login; a sign-in clears the failures and lockouts
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET last_login_at = NOW(), failed_login_count = 0, lockout_count = 0, locked_until = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(user.id)
+        .execute(&self.db)
+        .await?;
+        self.record_login_attempt(email, Some(user.id), device, true).await?;
 
         // Get permissions
         let permissions = self.get_user_permissions(user.id).await?;
 
-        // Generate tokens
-        let tokens = self.generate_tokens(user.id, user.business_id, user.role_id, &permissions)?;
+        // Generate tokens for a new session
+        let session_id = Uuid::new_v4();
+        let tokens = self.generate_tokens(user.id, user.business_id, user.role_id, &permissions, session_id)?;
 
         // Store refresh token
-        self.store_refresh_token(user.id, &tokens.refresh_token).await?;
+        self.store_refresh_token(user.id, &tokens.refresh_token, session_id, Utc::now(), device)
+            .await?;
 
         Ok(tokens)
     }
 
-    /// Refresh access token using refresh token
-    pub async fn refresh_token(&self, refresh_token: &str) -> AppResult<AuthTokens> {
-        // Hash the refresh token to look up
-        let token_hash = Self::hash_token(refresh_token);
+    /// Refuse an address with too many recent failed sign-ins
+    async fn check_ip_throttle(&self, ip_address: &str) -> AppResult<()> {
+        let (failures, oldest) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT COUNT(*), MIN(attempted_at)
+            FROM login_attempts
+            WHERE ip_address = $1 AND NOT succeeded
+              AND attempted_at > NOW() - make_interval(mins => $2)
+            "#,
+        )
+        .bind(ip_address)
+        .bind(IP_THROTTLE_WINDOW_MINUTES as i32)
+        .fetch_one(&self.db)
+        .await?;
+
+        match ip_retry_after(failures, oldest, Utc::now()) {
+            Some(retry_after_secs) => Err(AppError::TooManyLoginAttempts { retry_after_secs }),
+            None => Ok(()),
+        }
+    }
 
-        // Find valid refresh token
-        let token_record = sqlx::query_as::<_, (Uuid, Uuid)>(
+    async fn record_login_attempt(
+        &self,
+        email: &str,
+        user_id: Option<Uuid>,
+        device: &SessionDevice,
+        succeeded: bool,
+    ) -> AppResult<()> {
+        sqlx::query("INSERT INTO login_attempts (email, user_id, ip_address, succeeded) VALUES ($1, $2, $3, $4)")
+            .bind(email.chars().take(255).collect::<String>())
+            .bind(user_id)
+            .bind(device.ip_address.as_deref())
+            .bind(succeeded)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Count a wrong password or code, locking the account once
+    /// `MAX_FAILED_LOGINS` are reached. The lockout is returned as the error
+    /// of the attempt that caused it
+    async fn record_failed_login(&self, email: &str, user_id: Uuid, device: &SessionDevice) -> AppResult<()> {
+        self.record_login_attempt(email, Some(user_id), device, false).await?;
+
+        let (failed, lockouts) = sqlx::query_as::<_, (i32, i32)>(
+            r#"
+            UPDATE users SET failed_login_count = failed_login_count + 1
+            WHERE id = $1
+            RETURNING failed_login_count, lockout_count
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+        if failed < MAX_FAILED_LOGINS {
+            return Ok(());
+        }
+
+        let locked_until = sqlx::query_scalar::<_, DateTime<Utc>>(
             r#"
-            SELECT rt.user_id, u.business_id
+            UPDATE users
+            SET failed_login_count = 0,
+                lockout_count = lockout_count + 1,
+                locked_until = NOW() + make_interval(mins => $3)
+            WHERE id = $1 AND failed_login_count >= $2
+            RETURNING locked_until
+            "#,
+        )
+        .bind(user_id)
+        .bind(MAX_FAILED_LOGINS)
+        .bind(lockout_minutes(lockouts) as i32)
+        .fetch_optional(&self.db)
+        .await?;
+
+        match locked_until {
+            Some(locked_until) => {
+                tracing::warn!("Account {} locked after {} failed sign-ins", user_id, failed);
+                Err(account_locked(locked_until))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Rotate a refresh token: the presented token is revoked and a new one
+    /// is issued for the same session. Presenting an already-rotated token
+    /// means it was copied, so the whole session is revoked
+    pub async fn refresh_token(&self, refresh_token: &str, device: &SessionDevice) -> AppResult<AuthTokens> {
+        let token = sqlx::query_as::<_, RefreshTokenRow>(
+            r#"
+            SELECT rt.id, rt.user_id, u.business_id, u.role_id, u.is_active, rt.session_id,
+                   rt.session_started_at, rt.device_info, rt.user_agent, rt.expires_at, rt.revoked_at
             FROM refresh_tokens rt
             JOIN users u ON u.id = rt.user_id
             WHERE rt.token_hash = $1
-              AND rt.expires_at > NOW()
-              AND rt.revoked_at IS NULL
-              AND u.is_active = true
             "#,
         )
-        .bind(&token_hash)
+        .bind(hash_action_token(refresh_token))
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(invalid_refresh_token)?;
+
+        if token.revoked_at.is_some() {
+            if let Some(session_id) = token.session_id {
+                let revoked = self.revoke_session_tokens(token.user_id, session_id).await?;
+                if revoked > 0 {
+                    tracing::warn!(
+                        "Rotated refresh token reused; revoked session {} of user {}",
+                        session_id,
+                        token.user_id
+                    );
+                }
+            }
+            return Err(invalid_refresh_token());
+        }
+        if token.expires_at <= Utc::now() || !token.is_active {
+            return Err(invalid_refresh_token());
+        }
+
+        // Revoke the old token; of two concurrent refreshes only one wins
+        let rotated = sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(token.id)
+            .execute(&self.db)
+            .await?;
+        if rotated.rows_affected() == 0 {
+            return Err(invalid_refresh_token());
+        }
+
+        // Get permissions
+        let permissions = self.get_user_permissions(token.user_id).await?;
+
+        // Generate new tokens for the same session
+        let session_id = token.session_id.unwrap_or_else(Uuid::new_v4);
+        let tokens = self.generate_tokens(token.user_id, token.business_id, token.role_id, &permissions, session_id)?;
+
+        // The device keeps its label; the address is where it is now
+        let device = SessionDevice {
+            device_name: token.device_info,
+            user_agent: device.user_agent.clone().or(token.user_agent),
+            ip_address: device.ip_address.clone(),
+        };
+        let started_at = token.session_started_at.unwrap_or_else(Utc::now);
+        self.store_refresh_token(token.user_id, &tokens.refresh_token, session_id, started_at, &device)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    /// Active sessions of a user, most recently used first
+    pub async fn list_sessions(&self, user_id: Uuid, current_session: Option<Uuid>) -> AppResult<Vec<AuthSession>> {
+        let sessions = sqlx::query_as::<_, AuthSession>(
+            r#"
+            SELECT session_id AS id, device_info AS device_name, user_agent, ip_address,
+                   COALESCE(session_started_at, created_at) AS started_at,
+                   created_at AS last_used_at, expires_at,
+                   COALESCE(session_id = $2, false) AS current
+            FROM refresh_tokens
+            WHERE user_id = $1 AND session_id IS NOT NULL
+              AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(current_session)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Sign a session out; its refresh token stops working immediately and
+    /// access tokens already issued expire on their own
+    pub async fn revoke_session(&self, user_id: Uuid, session_id: Uuid) -> AppResult<()> {
+        if self.revoke_session_tokens(user_id, session_id).await? == 0 {
+            return Err(AppError::NotFound("Session".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn revoke_session_tokens(&self, user_id: Uuid, session_id: Uuid) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND session_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(session_id)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Whether two-factor authentication is on and how many backup codes
+    /// are left
+    pub async fn two_factor_status(&self, user_id: Uuid) -> AppResult<TwoFactorStatus> {
+        let status = sqlx::query_as::<_, TwoFactorStatus>(
+            r#"
+            SELECT u.totp_enabled_at IS NOT NULL AS enabled, u.totp_enabled_at AS enabled_at,
+                   (SELECT COUNT(*) FROM user_backup_codes b
+                    WHERE b.user_id = u.id AND b.used_at IS NULL) AS backup_codes_remaining
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
         .fetch_optional(&self.db)
         .await?
-        .ok_or_else(|| AppError::Unauthorized {
-            message: "Invalid or expired refresh token".to_string(),
-            message_th: "โทเค็นรีเฟรชไม่ถูกต้องหรือหมดอายุ".to_string(),
+        .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+
+        Ok(status)
+    }
+
+    /// Start enrolling in two-factor authentication. The new secret is
+    /// pending until confirmed with a code; enrolling again replaces it
+    pub async fn enroll_two_factor(&self, user_id: Uuid) -> AppResult<TwoFactorEnrollment> {
+        let totp = self.totp_row(user_id).await?;
+        let Some(email) = totp.email.as_deref() else {
+            return Err(AppError::Validation {
+                field: "password".to_string(),
+                message: "Two-factor authentication needs an email and password sign-in".to_string(),
+                message_th: "การยืนยันตัวตนสองขั้นตอนใช้ได้กับบัญชีที่เข้าสู่ระบบด้วยอีเมลและรหัสผ่าน".to_string(),
+            });
+        };
+        if totp.totp_enabled_at.is_some() {
+            return Err(AppError::Conflict {
+                resource: "two_factor".to_string(),
+                message: "Two-factor authentication is already enabled".to_string(),
+                message_th: "เปิดใช้การยืนยันตัวตนสองขั้นตอนอยู่แล้ว".to_string(),
+            });
+        }
+
+        let secret = generate_secret();
+        sqlx::query(
+            "UPDATE users SET totp_secret = $2, totp_last_step = NULL WHERE id = $1 AND totp_enabled_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(&secret)
+        .execute(&self.db)
+        .await?;
+
+        Ok(TwoFactorEnrollment {
+            otpauth_uri: otpauth_uri(&secret, email),
+            secret,
+        })
+    }
+
+    /// Turn two-factor authentication on with a code from the app showing
+    /// the pending secret works; returns the first backup codes
+    pub async fn confirm_two_factor(&self, user_id: Uuid, code: &str) -> AppResult<BackupCodes> {
+        let totp = self.totp_row(user_id).await?;
+        let secret = match (&totp.totp_secret, totp.totp_enabled_at) {
+            (Some(secret), None) => secret,
+            (_, Some(_)) => {
+                return Err(AppError::Conflict {
+                    resource: "two_factor".to_string(),
+                    message: "Two-factor authentication is already enabled".to_string(),
+                    message_th: "เปิดใช้การยืนยันตัวตนสองขั้นตอนอยู่แล้ว".to_string(),
+                })
+            }
+            (None, None) => {
+                return Err(AppError::Validation {
+                    field: "code".to_string(),
+                    message: "Start two-factor enrollment first".to_string(),
+                    message_th: "กรุณาเริ่มตั้งค่าการยืนยันตัวตนสองขั้นตอนก่อน".to_string(),
+                })
+            }
+        };
+        let step = verify_totp(secret, code, Utc::now().timestamp() as u64, None).ok_or_else(|| {
+            AppError::Validation {
+                field: "code".to_string(),
+                message: "The code does not match; check the time on your phone and try again".to_string(),
+                message_th: "รหัสไม่ถูกต้อง กรุณาตรวจสอบเวลาบนโทรศัพท์แล้วลองอีกครั้ง".to_string(),
+            }
         })?;
 
-        let (user_id, business_id) = token_record;
+        let mut tx = self.db.begin().await?;
+        sqlx::query("UPDATE users SET totp_enabled_at = NOW(), totp_last_step = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(step as i64)
+            .execute(&mut *tx)
+            .await?;
+        let codes = Self::replace_backup_codes(&mut tx, user_id).await?;
+        tx.commit().await?;
 
-        // Get user's role
-        let role_id = sqlx::query_scalar::<_, Uuid>(
-            "SELECT role_id FROM users WHERE id = $1",
+        Ok(BackupCodes { backup_codes: codes })
+    }
+
+    /// Turn two-factor authentication off; needs the password and a code
+    pub async fn disable_two_factor(&self, user_id: Uuid, password: &str, code: &str) -> AppResult<()> {
+        let totp = self.totp_row(user_id).await?;
+        let valid = match &totp.password_hash {
+            Some(password_hash) => verify(password, password_hash)
+                .map_err(|e| AppError::Internal(format!("Password verification failed: {}", e)))?,
+            None => false,
+        };
+        if !valid {
+            return Err(AppError::Unauthorized {
+                message: "Incorrect password".to_string(),
+                message_th: "รหัสผ่านไม่ถูกต้อง".to_string(),
+            });
+        }
+        if totp.totp_enabled_at.is_none() {
+            return Err(AppError::NotFound("Two-factor authentication".to_string()));
+        }
+        if !self.check_second_factor(user_id, code).await? {
+            return Err(invalid_second_factor());
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            "UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = $1",
         )
         .bind(user_id)
-        .fetch_one(&self.db)
+        .execute(&mut *tx)
         .await?;
+        sqlx::query("DELETE FROM user_backup_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replace all backup codes, e.g. when few are left; needs a code
+    pub async fn regenerate_backup_codes(&self, user_id: Uuid, code: &str) -> AppResult<BackupCodes> {
+        let totp = self.totp_row(user_id).await?;
+        if totp.totp_enabled_at.is_none() {
+            return Err(AppError::NotFound("Two-factor authentication".to_string()));
+        }
+        if !self.check_second_factor(user_id, code).await? {
+            return Err(invalid_second_factor());
+        }
 
-        // Revoke old refresh token
-        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE token_hash = $1")
-            .bind(&token_hash)
+        let mut tx = self.db.begin().await?;
+        let codes = Self::replace_backup_codes(&mut tx, user_id).await?;
+        tx.commit().await?;
+
+        Ok(BackupCodes { backup_codes: codes })
+    }
+
+    async fn totp_row(&self, user_id: Uuid) -> AppResult<TotpRow> {
+        sqlx::query_as::<_, TotpRow>(
+            r#"
+            SELECT email, password_hash, totp_secret, totp_enabled_at, totp_last_step
+            FROM users
+            WHERE id = $1 AND is_active = true
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User".to_string()))
+    }
+
+    /// Check a TOTP or backup code of an enrolled account and use it up: a
+    /// TOTP step or backup code is accepted once
+    async fn check_second_factor(&self, user_id: Uuid, code: &str) -> AppResult<bool> {
+        let totp = self.totp_row(user_id).await?;
+        let Some(secret) = totp.totp_secret.filter(|_| totp.totp_enabled_at.is_some()) else {
+            return Ok(false);
+        };
+
+        if looks_like_totp(code) {
+            let last_step = totp.totp_last_step.map(|s| s as u64);
+            let Some(step) = verify_totp(&secret, code, Utc::now().timestamp() as u64, last_step) else {
+                return Ok(false);
+            };
+            // Of two sign-ins with the same code only one gets the step
+            let claimed = sqlx::query(
+                r#"
+                UPDATE users SET totp_last_step = $2
+                WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+                "#,
+            )
+            .bind(user_id)
+            .bind(step as i64)
             .execute(&self.db)
             .await?;
+            return Ok(claimed.rows_affected() > 0);
+        }
 
-        // Get permissions
-        let permissions = self.get_user_permissions(user_id).await?;
+        let used = sqlx::query(
+            r#"
+            UPDATE user_backup_codes SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(hash_action_token(&normalize_backup_code(code)))
+        .execute(&self.db)
+        .await?;
+        if used.rows_affected() > 0 {
+            tracing::info!("Backup code used by user {}", user_id);
+        }
+        Ok(used.rows_affected() > 0)
+    }
+
+    /// Issue a new set of backup codes, invalidating the old ones
+    async fn replace_backup_codes(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> AppResult<Vec<String>> {
+        sqlx::query("DELETE FROM user_backup_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
 
-        // Generate new tokens
-        let tokens = self.generate_tokens(user_id, business_id, role_id, &permissions)?;
+        let codes = generate_backup_codes();
+        for code in &codes {
+            sqlx::query("INSERT INTO user_backup_codes (user_id, code_hash) VALUES ($1, $2)")
+                .bind(user_id)
+                .bind(hash_action_token(&normalize_backup_code(code)))
+                .execute(&mut **tx)
+                .await?;
+        }
 
-        // Store new refresh token
-        self.store_refresh_token(user_id, &tokens.refresh_token).await?;
+        Ok(codes)
+    }
 
-        Ok(tokens)
+    /// Email a password reset link to every active account with this
+    /// address. Succeeds whether or not an account exists, so the endpoint
+    /// does not reveal which addresses are registered
+    pub async fn forgot_password(&self, email: &str) -> AppResult<()> {
+        let users = sqlx::query_as::<_, RecoveryUser>(
+            r#"
+            SELECT u.id, u.email, u.name, u.preferred_language, u.email_verified
+            FROM users u
+            WHERE u.email = $1 AND u.is_active = true
+              AND NOT EXISTS (
+                  SELECT 1 FROM auth_tokens t
+                  WHERE t.user_id = u.id AND t.purpose = 'password_reset'
+                    AND t.created_at > NOW() - make_interval(secs => $2)
+              )
+            "#,
+        )
+        .bind(email.trim())
+        .bind(RESET_REQUEST_COOLDOWN_SECONDS as f64)
+        .fetch_all(&self.db)
+        .await?;
+
+        for user in &users {
+            if let Err(e) = self.send_token(user, TokenPurpose::PasswordReset).await {
+                tracing::warn!("Password reset email to user {} failed: {}", user.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set a new password with a reset token; signs the account out
+    /// everywhere and marks the email verified
+    pub async fn reset_password(&self, token: &str, new_password: &str) -> AppResult<()> {
+        validate_new_password(new_password)?;
+        let password_hash = hash(new_password, DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))?;
+
+        let mut tx = self.db.begin().await?;
+        let (user_id, email) = self
+            .consume_token(&mut tx, token, TokenPurpose::PasswordReset)
+            .await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $2,
+                email_verified = email_verified OR email = $3,
+                updated_at = NOW()
+            WHERE id = $1 AND is_active = true
+            "#,
+        )
+        .bind(user_id)
+        .bind(&password_hash)
+        .bind(&email)
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(invalid_link());
+        }
+
+        // Old sessions and other outstanding reset links stop working
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE auth_tokens SET used_at = NOW() WHERE user_id = $1 AND purpose = 'password_reset' AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Email a verification link to a user's current address
+    pub async fn send_email_verification(&self, user_id: Uuid) -> AppResult<()> {
+        let user = sqlx::query_as::<_, RecoveryUser>(
+            r#"
+            SELECT id, email, name, preferred_language, email_verified
+            FROM users
+            WHERE id = $1 AND is_active = true AND email IS NOT NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+
+        if user.email_verified {
+            return Err(AppError::Conflict {
+                resource: "email".to_string(),
+                message: "Email is already verified".to_string(),
+                message_th: "ยืนยันอีเมลแล้ว".to_string(),
+            });
+        }
+        if self.mailer.is_none() {
+            return Err(AppError::Configuration("Email delivery is not configured".to_string()));
+        }
+        self.send_token(&user, TokenPurpose::EmailVerification).await
+    }
+
+    /// Mark an email verified with a verification token; the token only
+    /// counts for the address it was sent to
+    pub async fn verify_email(&self, token: &str) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        let (user_id, email) = self
+            .consume_token(&mut tx, token, TokenPurpose::EmailVerification)
+            .await?;
+
+        let updated = sqlx::query(
+            "UPDATE users SET email_verified = true, updated_at = NOW() WHERE id = $1 AND email = $2",
+        )
+        .bind(user_id)
+        .bind(&email)
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(invalid_link());
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Store a new token for the user and email its link
+    async fn send_token(&self, user: &RecoveryUser, purpose: TokenPurpose) -> AppResult<()> {
+        let Some(mailer) = &self.mailer else {
+            tracing::warn!("Email delivery is not configured; {} email not sent", purpose.as_str());
+            return Ok(());
+        };
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        sqlx::query(
+            r#"
+            INSERT INTO auth_tokens (user_id, purpose, token_hash, email, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(user.id)
+        .bind(purpose.as_str())
+        .bind(hash_action_token(&token))
+        .bind(&user.email)
+        .bind(Utc::now() + purpose.lifetime())
+        .execute(&self.db)
+        .await?;
+
+        let link = format!("{}/{}?token={}", self.public_url.trim_end_matches('/'), purpose.path(), token);
+        let (subject, body) = recovery_email(purpose, &user.name, &link, user.preferred_language == "th");
+        mailer.send(&user.email, &subject, &body, None).await
+    }
+
+    /// Mark an unexpired, unused token used; returns its user and address
+    async fn consume_token(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        token: &str,
+        purpose: TokenPurpose,
+    ) -> AppResult<(Uuid, String)> {
+        sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            UPDATE auth_tokens SET used_at = NOW()
+            WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id, email
+            "#,
+        )
+        .bind(hash_action_token(token))
+        .bind(purpose.as_str())
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(invalid_link)
     }
 
     /// Validate access token and return claims
@@ -363,6 +1530,7 @@ impl AuthService {
         business_id: Uuid,
         role_id: Uuid,
         permissions: &[String],
+        session_id: Uuid,
     ) -> AppResult<AuthTokens> {
         let now = Utc::now();
         let access_exp = now + Duration::seconds(self.access_token_expiry);
@@ -374,6 +1542,7 @@ impl AuthService {
             business_id: business_id.to_string(),
             role_id: role_id.to_string(),
             permissions: permissions.to_vec(),
+            sid: Some(session_id.to_string()),
             exp: access_exp.timestamp(),
             iat: now.timestamp(),
         };
@@ -396,35 +1565,41 @@ impl AuthService {
         })
     }
 
-    /// Store refresh token in database
-    async fn store_refresh_token(&self, user_id: Uuid, token: &str) -> AppResult<()> {
-        let token_hash = Self::hash_token(token);
+    /// Store a session's current refresh token
+    async fn store_refresh_token(
+        &self,
+        user_id: Uuid,
+        token: &str,
+        session_id: Uuid,
+        session_started_at: DateTime<Utc>,
+        device: &SessionDevice,
+    ) -> AppResult<()> {
         let expires_at = Utc::now() + Duration::seconds(self.refresh_token_expiry);
+        let label = device_label(device.device_name.as_deref(), device.user_agent.as_deref());
 
         sqlx::query(
             r#"
-            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO refresh_tokens (
+                user_id, token_hash, expires_at, session_id, session_started_at,
+                device_info, user_agent, ip_address
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
         )
         .bind(user_id)
-        .bind(&token_hash)
+        .bind(hash_action_token(token))
         .bind(expires_at)
+        .bind(session_id)
+        .bind(session_started_at)
+        .bind(label)
+        .bind(&device.user_agent)
+        .bind(&device.ip_address)
         .execute(&self.db)
         .await?;
 
         Ok(())
     }
 
-    /// Hash a token for storage
-    fn hash_token(token: &str) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        let mut hasher = DefaultHasher::new();
-        token.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
-    }
-
     /// Validate business code format
     fn is_valid_business_code(code: &str) -> bool {
         code.len() >= 3
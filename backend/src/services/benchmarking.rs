@@ -0,0 +1,291 @@
+//! Anonymous regional benchmarking
+//!
+//! Businesses that opt in contribute their lot metrics to cohorts of the
+//! same province, variety and processing method, and see the percentile
+//! they rank at within each cohort they produce in. Nothing identifying a
+//! business leaves this service: cohorts report quartiles only once enough
+//! businesses contribute, and sale prices are only compared as bands.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::without_tenant;
+
+/// Contributing businesses a metric needs before it is reported
+pub const MIN_COHORT_BUSINESSES: usize = 5;
+
+/// Width of a sale price band in THB per kg
+pub const PRICE_BAND_WIDTH: Decimal = Decimal::from_parts(50, 0, 0, false, 0);
+
+/// Months of lots compared when none are given
+pub const DEFAULT_BENCHMARK_MONTHS: i32 = 12;
+
+const QUARTER: Decimal = Decimal::from_parts(25, 0, 0, false, 2);
+const HALF: Decimal = Decimal::from_parts(5, 0, 0, false, 1);
+const THREE_QUARTERS: Decimal = Decimal::from_parts(75, 0, 0, false, 2);
+
+/// Benchmarking service
+#[derive(Clone)]
+pub struct BenchmarkingService {
+    db: PgPool,
+}
+
+/// Sale price band in THB per kg, `min` inclusive and `max` exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PriceBand {
+    pub min: Decimal,
+    pub max: Decimal,
+}
+
+/// Band a price per kg falls in
+pub fn price_band(price_per_kg: Decimal) -> PriceBand {
+    let min = (price_per_kg / PRICE_BAND_WIDTH).floor() * PRICE_BAND_WIDTH;
+    PriceBand {
+        min,
+        max: min + PRICE_BAND_WIDTH,
+    }
+}
+
+/// Share (0-100) of `values` below `value`, counting ties as half
+pub fn percentile_rank(value: Decimal, values: &[Decimal]) -> i32 {
+    if values.is_empty() {
+        return 0;
+    }
+    let below = values.iter().filter(|v| **v < value).count();
+    let equal = values.iter().filter(|v| **v == value).count();
+    ((below * 2 + equal) * 100 + values.len()) as i32 / (values.len() * 2) as i32
+}
+
+/// Linearly interpolated quantile `q` (0-1) of ascending `sorted` values
+pub fn quantile(sorted: &[Decimal], q: Decimal) -> Decimal {
+    match sorted.len() {
+        0 => Decimal::ZERO,
+        1 => sorted[0],
+        len => {
+            let position = q * Decimal::from(len - 1);
+            let lower = position.floor();
+            let index = lower.to_usize().unwrap_or(0).min(len - 1);
+            let next = sorted[(index + 1).min(len - 1)];
+            sorted[index] + (next - sorted[index]) * (position - lower)
+        }
+    }
+}
+
+/// Query parameters for regional benchmarks
+#[derive(Debug, Deserialize)]
+pub struct BenchmarkQuery {
+    /// Compare lots created in the last this many months (1-36)
+    pub months: Option<i32>,
+}
+
+/// Where the business ranks on one metric
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricBenchmark {
+    /// The business's own average
+    pub value: Decimal,
+    /// Share of contributing businesses below it (0-100)
+    pub percentile: i32,
+    pub p25: Decimal,
+    pub median: Decimal,
+    pub p75: Decimal,
+    pub businesses: usize,
+}
+
+/// Where the business's sale price band ranks
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceBandBenchmark {
+    pub band: PriceBand,
+    pub percentile: i32,
+    pub median_band: PriceBand,
+    pub businesses: usize,
+}
+
+/// Benchmarks of one province/variety/process cohort; a metric is left
+/// out while fewer than `MIN_COHORT_BUSINESSES` businesses report it
+#[derive(Debug, Clone, Serialize)]
+pub struct CohortBenchmark {
+    pub region: String,
+    pub variety: String,
+    pub process: String,
+    pub businesses: usize,
+    pub cupping_score: Option<MetricBenchmark>,
+    pub yield_percent: Option<MetricBenchmark>,
+    pub price_band: Option<PriceBandBenchmark>,
+}
+
+/// Regional benchmarks for the cohorts a business produces in
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionalBenchmarks {
+    pub months: i32,
+    pub min_cohort_businesses: usize,
+    pub cohorts: Vec<CohortBenchmark>,
+}
+
+/// One business's averages in one cohort
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Contribution {
+    pub business_id: Uuid,
+    pub region: String,
+    pub variety: String,
+    pub process: String,
+    pub cupping_score: Option<Decimal>,
+    pub yield_percent: Option<Decimal>,
+    pub price_per_kg: Option<Decimal>,
+}
+
+/// Rank `own` against every contributed value, if enough businesses report it
+fn metric_benchmark(own: Option<Decimal>, values: &[Decimal]) -> Option<MetricBenchmark> {
+    let value = own?;
+    if values.len() < MIN_COHORT_BUSINESSES {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    Some(MetricBenchmark {
+        value: value.round_dp(2),
+        percentile: percentile_rank(value, &sorted),
+        p25: quantile(&sorted, QUARTER).round_dp(2),
+        median: quantile(&sorted, HALF).round_dp(2),
+        p75: quantile(&sorted, THREE_QUARTERS).round_dp(2),
+        businesses: sorted.len(),
+    })
+}
+
+/// Rank the band of `own` against the contributed bands
+fn price_band_benchmark(own: Option<Decimal>, prices: &[Decimal]) -> Option<PriceBandBenchmark> {
+    let band = price_band(own?);
+    if prices.len() < MIN_COHORT_BUSINESSES {
+        return None;
+    }
+    let mut bands: Vec<Decimal> = prices.iter().map(|p| price_band(*p).min).collect();
+    bands.sort();
+    Some(PriceBandBenchmark {
+        band,
+        percentile: percentile_rank(band.min, &bands),
+        median_band: price_band(bands[(bands.len() - 1) / 2]),
+        businesses: bands.len(),
+    })
+}
+
+/// Benchmarks for every cohort `business_id` contributes to
+pub fn benchmark_cohorts(business_id: Uuid, contributions: &[Contribution]) -> Vec<CohortBenchmark> {
+    let mut cohorts: Vec<CohortBenchmark> = contributions
+        .iter()
+        .filter(|own| own.business_id == business_id)
+        .map(|own| {
+            let cohort: Vec<&Contribution> = contributions
+                .iter()
+                .filter(|c| c.region == own.region && c.variety == own.variety && c.process == own.process)
+                .collect();
+            let values = |metric: fn(&Contribution) -> Option<Decimal>| -> Vec<Decimal> {
+                cohort.iter().filter_map(|c| metric(c)).collect()
+            };
+            CohortBenchmark {
+                region: own.region.clone(),
+                variety: own.variety.clone(),
+                process: own.process.clone(),
+                businesses: cohort.len(),
+                cupping_score: metric_benchmark(own.cupping_score, &values(|c| c.cupping_score)),
+                yield_percent: metric_benchmark(own.yield_percent, &values(|c| c.yield_percent)),
+                price_band: price_band_benchmark(own.price_per_kg, &values(|c| c.price_per_kg)),
+            }
+        })
+        .collect();
+    cohorts.sort_by(|a, b| {
+        (&a.region, &a.variety, &a.process).cmp(&(&b.region, &b.variety, &b.process))
+    });
+    cohorts
+}
+
+impl BenchmarkingService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Regional benchmarks for an opted-in business
+    pub async fn get_benchmarks(&self, business_id: Uuid, query: &BenchmarkQuery) -> AppResult<RegionalBenchmarks> {
+        let months = query.months.unwrap_or(DEFAULT_BENCHMARK_MONTHS);
+        if !(1..=36).contains(&months) {
+            return Err(AppError::Validation {
+                field: "months".to_string(),
+                message: "Months must be between 1 and 36".to_string(),
+                message_th: "จำนวนเดือนต้องอยู่ระหว่าง 1 ถึง 36".to_string(),
+            });
+        }
+
+        let opted_in = sqlx::query_scalar::<_, bool>("SELECT benchmarking_opt_in FROM businesses WHERE id = $1")
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Business".to_string()))?;
+        if !opted_in {
+            return Err(AppError::Conflict {
+                resource: "benchmarking".to_string(),
+                message: "Opt in to benchmarking in business settings to see regional benchmarks".to_string(),
+                message_th: "เปิดการเข้าร่วมเปรียบเทียบในการตั้งค่าธุรกิจเพื่อดูค่าเปรียบเทียบระดับภูมิภาค"
+                    .to_string(),
+            });
+        }
+
+        // Variety is the single variety planted on the lot's harvested
+        // plots, or "mixed"; process is the lot's latest processing method.
+        // Other opted-in businesses are outside the request's tenant.
+        let contributions = without_tenant(sqlx::query_as::<_, Contribution>(
+            r#"
+            WITH lot_metrics AS (
+                SELECT l.business_id, b.province AS region,
+                       (SELECT CASE WHEN COUNT(DISTINCT pv.variety) = 1 THEN MIN(pv.variety) ELSE 'mixed' END
+                        FROM harvests h JOIN plot_varieties pv ON pv.plot_id = h.plot_id
+                        WHERE h.lot_id = l.id
+                        HAVING COUNT(pv.variety) > 0) AS variety,
+                       latest.method AS process,
+                       (SELECT AVG(COALESCE(cs.normalized_score, cs.final_score))
+                        FROM cupping_samples cs WHERE cs.lot_id = l.id) AS cupping_score,
+                       latest.processing_yield_percent AS yield_percent,
+                       (SELECT SUM(ABS(it.total_price)) / NULLIF(SUM(ABS(it.quantity_kg)), 0)
+                        FROM inventory_transactions it
+                        WHERE it.lot_id = l.id AND it.transaction_type = 'sale'
+                          AND it.total_price IS NOT NULL AND COALESCE(it.currency, 'THB') = 'THB') AS price_per_kg
+                FROM lots l
+                JOIN businesses b ON b.id = l.business_id
+                JOIN LATERAL (
+                    SELECT pr.method, pr.processing_yield_percent
+                    FROM processing_records pr
+                    WHERE pr.lot_id = l.id
+                    ORDER BY pr.start_date DESC
+                    LIMIT 1
+                ) latest ON TRUE
+                WHERE b.benchmarking_opt_in AND b.province IS NOT NULL
+                  AND l.created_at >= NOW() - make_interval(months => $2)
+            ),
+            contributions AS (
+                SELECT business_id, region, variety, process,
+                       AVG(cupping_score) AS cupping_score,
+                       AVG(yield_percent) AS yield_percent,
+                       AVG(price_per_kg) AS price_per_kg
+                FROM lot_metrics
+                WHERE variety IS NOT NULL
+                GROUP BY business_id, region, variety, process
+            )
+            SELECT business_id, region, variety, process, cupping_score, yield_percent, price_per_kg
+            FROM contributions
+            WHERE (region, variety, process) IN
+                (SELECT region, variety, process FROM contributions WHERE business_id = $1)
+            "#,
+        )
+        .bind(business_id)
+        .bind(months)
+        .fetch_all(&self.db))
+        .await?;
+
+        Ok(RegionalBenchmarks {
+            months,
+            min_cohort_businesses: MIN_COHORT_BUSINESSES,
+            cohorts: benchmark_cohorts(business_id, &contributions),
+        })
+    }
+}
@@ -0,0 +1,322 @@
+//! Blend optimizer
+//!
+//! Proposes blend ratios from lots picked by the user that land the blended
+//! cupping score inside a target band at the lowest cost per kg. Costs come
+//! from the inventory ledger (weighted average price of incoming
+//! transactions) unless given with the request, and scores from each lot's
+//! latest cupping sample. Ratios move in 5% steps, which keeps the search
+//! exhaustive and the proposals practical to weigh out.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::lot_recommendation::combinations;
+
+/// Resolution of proposed ratios
+pub const RATIO_STEP_PERCENT: u32 = 5;
+
+pub const DEFAULT_MAX_COMPONENTS: usize = 3;
+pub const MAX_COMPONENTS: usize = 4;
+
+/// Lots that can be offered to the optimizer in one request
+pub const MAX_SELECTED_LOTS: usize = 10;
+
+/// Proposals returned, cheapest first
+const MAX_PROPOSALS: usize = 5;
+
+/// Blend optimizer service
+#[derive(Clone)]
+pub struct BlendOptimizerService {
+    db: PgPool,
+}
+
+/// A lot offered to the optimizer
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlendLotInput {
+    pub lot_id: Uuid,
+    /// Overrides the ledger cost per kg
+    pub unit_cost: Option<Decimal>,
+}
+
+/// Optimization request
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptimizeBlendInput {
+    pub lots: Vec<BlendLotInput>,
+    pub min_score: Decimal,
+    pub max_score: Decimal,
+    /// When set, each component must have enough unreserved stock
+    pub quantity_kg: Option<Decimal>,
+    pub max_components: Option<usize>,
+}
+
+/// Score, cost and stock of a selected lot
+#[derive(Debug, Clone)]
+pub struct BlendLot {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub cupping_score: Decimal,
+    pub unit_cost: Decimal,
+    pub available_kg: Decimal,
+}
+
+/// Database row for a selected lot
+#[derive(Debug, sqlx::FromRow)]
+struct BlendLotRow {
+    lot_id: Uuid,
+    traceability_code: String,
+    name: String,
+    cupping_score: Option<Decimal>,
+    ledger_cost: Option<Decimal>,
+    available_kg: Decimal,
+}
+
+/// A lot's share of a proposed blend
+#[derive(Debug, Clone, Serialize)]
+pub struct BlendRatio {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub proportion_percent: Decimal,
+    pub quantity_kg: Option<Decimal>,
+    pub cupping_score: Decimal,
+    pub unit_cost: Decimal,
+}
+
+/// A blend inside the target band
+#[derive(Debug, Clone, Serialize)]
+pub struct BlendProposal {
+    pub blended_score: Decimal,
+    pub cost_per_kg: Decimal,
+    pub total_cost: Option<Decimal>,
+    pub components: Vec<BlendRatio>,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Every way to split `units` into `parts` positive whole numbers
+fn compositions(units: u32, parts: usize) -> Vec<Vec<u32>> {
+    if parts == 1 {
+        return if units > 0 { vec![vec![units]] } else { Vec::new() };
+    }
+    let mut result = Vec::new();
+    for first in 1..units {
+        for mut rest in compositions(units - first, parts - 1) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// Cheapest ratios per combination of up to `max_components` lots whose
+/// blended score lies within `[min_score, max_score]`, cheapest first.
+/// Ties go to fewer components, then to the higher score.
+pub fn optimize_blend(
+    lots: &[BlendLot],
+    min_score: Decimal,
+    max_score: Decimal,
+    quantity_kg: Option<Decimal>,
+    max_components: usize,
+) -> Vec<BlendProposal> {
+    let units = 100 / RATIO_STEP_PERCENT;
+    let hundred = Decimal::from(100);
+    let mut proposals: Vec<BlendProposal> = Vec::new();
+
+    for size in 1..=max_components.min(lots.len()) {
+        let splits = compositions(units, size);
+        for combo in combinations(lots.len(), size) {
+            let mut best: Option<(Decimal, Decimal, &Vec<u32>)> = None;
+            for split in &splits {
+                let mut score = Decimal::ZERO;
+                let mut cost = Decimal::ZERO;
+                let mut in_stock = true;
+                for (&i, &share) in combo.iter().zip(split) {
+                    let percent = Decimal::from(share * RATIO_STEP_PERCENT);
+                    score += lots[i].cupping_score * percent / hundred;
+                    cost += lots[i].unit_cost * percent / hundred;
+                    if let Some(quantity) = quantity_kg {
+                        in_stock &= quantity * percent / hundred <= lots[i].available_kg;
+                    }
+                }
+                if !in_stock || score < min_score || score > max_score {
+                    continue;
+                }
+                let better = match best {
+                    Some((best_cost, best_score, _)) => {
+                        cost < best_cost || (cost == best_cost && score > best_score)
+                    }
+                    None => true,
+                };
+                if better {
+                    best = Some((cost, score, split));
+                }
+            }
+
+            if let Some((cost, score, split)) = best {
+                let components = combo
+                    .iter()
+                    .zip(split)
+                    .map(|(&i, &share)| {
+                        let percent = Decimal::from(share * RATIO_STEP_PERCENT);
+                        BlendRatio {
+                            lot_id: lots[i].lot_id,
+                            traceability_code: lots[i].traceability_code.clone(),
+                            name: lots[i].name.clone(),
+                            proportion_percent: percent,
+                            quantity_kg: quantity_kg.map(|q| (q * percent / hundred).round_dp(3)),
+                            cupping_score: lots[i].cupping_score,
+                            unit_cost: lots[i].unit_cost,
+                        }
+                    })
+                    .collect();
+                proposals.push(BlendProposal {
+                    blended_score: score.round_dp(2),
+                    cost_per_kg: cost.round_dp(2),
+                    total_cost: quantity_kg.map(|q| (q * cost).round_dp(2)),
+                    components,
+                });
+            }
+        }
+    }
+
+    proposals.sort_by(|a, b| {
+        a.cost_per_kg
+            .cmp(&b.cost_per_kg)
+            .then(a.components.len().cmp(&b.components.len()))
+            .then(b.blended_score.cmp(&a.blended_score))
+    });
+    proposals.truncate(MAX_PROPOSALS);
+    proposals
+}
+
+impl BlendOptimizerService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Propose the cheapest blends of the selected lots within the score band
+    pub async fn optimize(
+        &self,
+        business_id: Uuid,
+        input: &OptimizeBlendInput,
+    ) -> AppResult<Vec<BlendProposal>> {
+        if input.lots.is_empty() || input.lots.len() > MAX_SELECTED_LOTS {
+            return Err(validation(
+                "lots",
+                &format!("Select 1 to {} lots", MAX_SELECTED_LOTS),
+                &format!("เลือกล็อตได้ 1 ถึง {} ล็อต", MAX_SELECTED_LOTS),
+            ));
+        }
+        let lot_ids: Vec<Uuid> = input.lots.iter().map(|l| l.lot_id).collect();
+        if lot_ids.iter().collect::<HashSet<_>>().len() != lot_ids.len() {
+            return Err(validation("lots", "Each lot can be selected once", "เลือกแต่ละล็อตได้ครั้งเดียว"));
+        }
+        if input.min_score > input.max_score
+            || input.min_score < Decimal::ZERO
+            || input.max_score > Decimal::from(100)
+        {
+            return Err(validation(
+                "min_score",
+                "Score band must be within 0-100 with min_score not above max_score",
+                "ช่วงคะแนนต้องอยู่ระหว่าง 0-100 และ min_score ต้องไม่เกิน max_score",
+            ));
+        }
+        if input.quantity_kg.is_some_and(|q| q <= Decimal::ZERO) {
+            return Err(validation(
+                "quantity_kg",
+                "Blend quantity must be greater than 0",
+                "ปริมาณเบลนด์ต้องมากกว่า 0",
+            ));
+        }
+        if input.lots.iter().any(|l| l.unit_cost.is_some_and(|c| c < Decimal::ZERO)) {
+            return Err(validation("unit_cost", "Cost cannot be negative", "ต้นทุนต้องไม่ติดลบ"));
+        }
+        let max_components = input.max_components.unwrap_or(DEFAULT_MAX_COMPONENTS);
+        if !(1..=MAX_COMPONENTS).contains(&max_components) {
+            return Err(validation(
+                "max_components",
+                &format!("Blends can have 1 to {} components", MAX_COMPONENTS),
+                &format!("เบลนด์มีได้ 1 ถึง {} ล็อต", MAX_COMPONENTS),
+            ));
+        }
+
+        let rows = sqlx::query_as::<_, BlendLotRow>(
+            r#"
+            SELECT l.id AS lot_id, l.traceability_code, l.name,
+                   c.final_score AS cupping_score,
+                   (
+                       SELECT SUM(total_price) / NULLIF(SUM(quantity_kg), 0)
+                       FROM inventory_transactions
+                       WHERE lot_id = l.id AND direction = 'in' AND unit_price IS NOT NULL
+                   ) AS ledger_cost,
+                   l.current_weight_kg - COALESCE((
+                       SELECT SUM(quantity_kg) FROM lot_reservations
+                       WHERE lot_id = l.id AND (reserved_until IS NULL OR reserved_until >= CURRENT_DATE)
+                   ), 0) AS available_kg
+            FROM lots l
+            LEFT JOIN LATERAL (
+                SELECT cs.final_score
+                FROM cupping_samples cs
+                JOIN cupping_sessions s ON s.id = cs.session_id
+                WHERE cs.lot_id = l.id
+                ORDER BY s.session_date DESC, cs.created_at DESC
+                LIMIT 1
+            ) c ON TRUE
+            WHERE l.business_id = $1 AND l.id = ANY($2)
+            "#,
+        )
+        .bind(business_id)
+        .bind(&lot_ids)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut lots = Vec::with_capacity(input.lots.len());
+        for selected in &input.lots {
+            let row = rows
+                .iter()
+                .find(|r| r.lot_id == selected.lot_id)
+                .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+            let cupping_score = row.cupping_score.ok_or_else(|| {
+                validation(
+                    "lots",
+                    &format!("Lot {} has not been cupped", row.traceability_code),
+                    &format!("ล็อต {} ยังไม่มีผลคัปปิ้ง", row.traceability_code),
+                )
+            })?;
+            let unit_cost = selected.unit_cost.or(row.ledger_cost).ok_or_else(|| {
+                validation(
+                    "unit_cost",
+                    &format!("Lot {} has no cost in the ledger; provide unit_cost", row.traceability_code),
+                    &format!("ล็อต {} ไม่มีต้นทุนในบัญชี กรุณาระบุ unit_cost", row.traceability_code),
+                )
+            })?;
+            lots.push(BlendLot {
+                lot_id: row.lot_id,
+                traceability_code: row.traceability_code.clone(),
+                name: row.name.clone(),
+                cupping_score,
+                unit_cost,
+                available_kg: row.available_kg,
+            });
+        }
+
+        Ok(optimize_blend(
+            &lots,
+            input.min_score,
+            input.max_score,
+            input.quantity_kg,
+            max_components,
+        ))
+    }
+}
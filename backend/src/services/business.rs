@@ -0,0 +1,229 @@
+//! Business settings service
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{CalendarSystem, DigitSystem, DisplayFormat};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::DuplicateLotPolicy;
+use crate::services::recycle_bin::validate_retention_days;
+use crate::services::reporting_period::{validate_start_month, ReportingCalendar};
+
+/// Business settings service
+#[derive(Clone)]
+pub struct BusinessService {
+    db: PgPool,
+}
+
+/// Business-wide display settings
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BusinessSettings {
+    pub id: Uuid,
+    pub name: String,
+    pub business_code: String,
+    pub preferred_language: String,
+    pub timezone: String,
+    /// "gregorian" or "buddhist"; applies to dates in PDFs, exports and
+    /// notification texts
+    pub calendar_system: String,
+    /// "arabic" or "thai"; applies to numbers in Thai-language output
+    pub digit_system: String,
+    /// "allow", "warn" or "block" adding a lot twice to a cupping session
+    pub cupping_duplicate_lot_policy: String,
+    /// Require confirmation for cupping scores identical to another sample
+    pub cupping_flag_identical_scores: bool,
+    /// Contribute anonymized lot metrics to regional benchmarks and see them
+    pub benchmarking_opt_in: bool,
+    pub benchmarking_opted_in_at: Option<DateTime<Utc>>,
+    /// Contribute de-identified quality, processing and weather records to
+    /// the research partner API
+    pub research_opt_in: bool,
+    pub research_opted_in_at: Option<DateTime<Utc>>,
+    /// Appear in the public producer directory with marketplace listings
+    pub marketplace_opt_in: bool,
+    pub marketplace_description: Option<String>,
+    pub marketplace_description_th: Option<String>,
+    /// Code shared by the businesses of one cooperative
+    pub cooperative_code: Option<String>,
+    /// Days deleted records stay restorable in the recycle bin
+    pub recycle_bin_retention_days: i32,
+    /// Month (1-12) crop seasons start in
+    pub season_start_month: i32,
+    /// Month (1-12) fiscal years start in
+    pub fiscal_year_start_month: i32,
+}
+
+/// Input for updating business settings
+#[derive(Debug, Deserialize)]
+pub struct UpdateBusinessSettingsInput {
+    pub preferred_language: Option<String>,
+    pub calendar_system: Option<CalendarSystem>,
+    pub digit_system: Option<DigitSystem>,
+    pub cupping_duplicate_lot_policy: Option<DuplicateLotPolicy>,
+    pub cupping_flag_identical_scores: Option<bool>,
+    pub benchmarking_opt_in: Option<bool>,
+    pub research_opt_in: Option<bool>,
+    pub marketplace_opt_in: Option<bool>,
+    pub marketplace_description: Option<String>,
+    pub marketplace_description_th: Option<String>,
+    /// Blank leaves the cooperative
+    pub cooperative_code: Option<String>,
+    pub recycle_bin_retention_days: Option<i32>,
+    pub season_start_month: Option<i32>,
+    pub fiscal_year_start_month: Option<i32>,
+}
+
+impl BusinessService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Get settings of a business
+    pub async fn get_settings(&self, business_id: Uuid) -> AppResult<BusinessSettings> {
+        sqlx::query_as::<_, BusinessSettings>(
+            r#"
+            SELECT id, name, business_code, preferred_language, timezone, calendar_system,
+                   digit_system, cupping_duplicate_lot_policy, cupping_flag_identical_scores,
+                   benchmarking_opt_in, benchmarking_opted_in_at, research_opt_in,
+                   research_opted_in_at, marketplace_opt_in, marketplace_description,
+                   marketplace_description_th, cooperative_code, recycle_bin_retention_days,
+                   season_start_month, fiscal_year_start_month
+            FROM businesses
+            WHERE id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business".to_string()))
+    }
+
+    /// Update settings of a business
+    pub async fn update_settings(
+        &self,
+        business_id: Uuid,
+        input: UpdateBusinessSettingsInput,
+    ) -> AppResult<BusinessSettings> {
+        if let Some(language) = &input.preferred_language {
+            if language != "th" && language != "en" {
+                return Err(AppError::Validation {
+                    field: "preferred_language".to_string(),
+                    message: "Language must be 'th' or 'en'".to_string(),
+                    message_th: "ภาษาต้องเป็น 'th' หรือ 'en'".to_string(),
+                });
+            }
+        }
+
+        if input.cooperative_code.as_ref().is_some_and(|c| c.trim().chars().count() > 50) {
+            return Err(AppError::Validation {
+                field: "cooperative_code".to_string(),
+                message: "Cooperative code must be at most 50 characters".to_string(),
+                message_th: "รหัสสหกรณ์ต้องไม่เกิน 50 ตัวอักษร".to_string(),
+            });
+        }
+
+        if let Some(days) = input.recycle_bin_retention_days {
+            validate_retention_days(days)?;
+        }
+        if let Some(month) = input.season_start_month {
+            validate_start_month("season_start_month", month)?;
+        }
+        if let Some(month) = input.fiscal_year_start_month {
+            validate_start_month("fiscal_year_start_month", month)?;
+        }
+
+        sqlx::query_as::<_, BusinessSettings>(
+            r#"
+            UPDATE businesses
+            SET preferred_language = COALESCE($2, preferred_language),
+                calendar_system = COALESCE($3, calendar_system),
+                digit_system = COALESCE($4, digit_system),
+                cupping_duplicate_lot_policy = COALESCE($5, cupping_duplicate_lot_policy),
+                cupping_flag_identical_scores = COALESCE($6, cupping_flag_identical_scores),
+                benchmarking_opted_in_at = CASE
+                    WHEN $7 IS NULL OR $7 = benchmarking_opt_in THEN benchmarking_opted_in_at
+                    WHEN $7 THEN NOW()
+                    ELSE NULL
+                END,
+                benchmarking_opt_in = COALESCE($7, benchmarking_opt_in),
+                marketplace_opt_in = COALESCE($8, marketplace_opt_in),
+                marketplace_description = COALESCE($9, marketplace_description),
+                marketplace_description_th = COALESCE($10, marketplace_description_th),
+                cooperative_code = CASE WHEN $11::text IS NULL THEN cooperative_code
+                                        ELSE NULLIF(UPPER(TRIM($11)), '') END,
+                recycle_bin_retention_days = COALESCE($12, recycle_bin_retention_days),
+                research_opted_in_at = CASE
+                    WHEN $13 IS NULL OR $13 = research_opt_in THEN research_opted_in_at
+                    WHEN $13 THEN NOW()
+                    ELSE NULL
+                END,
+                research_opt_in = COALESCE($13, research_opt_in),
+                season_start_month = COALESCE($14, season_start_month),
+                fiscal_year_start_month = COALESCE($15, fiscal_year_start_month),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, name, business_code, preferred_language, timezone, calendar_system,
+                      digit_system, cupping_duplicate_lot_policy, cupping_flag_identical_scores,
+                      benchmarking_opt_in, benchmarking_opted_in_at, research_opt_in,
+                      research_opted_in_at, marketplace_opt_in, marketplace_description,
+                      marketplace_description_th, cooperative_code, recycle_bin_retention_days,
+                      season_start_month, fiscal_year_start_month
+            "#,
+        )
+        .bind(business_id)
+        .bind(&input.preferred_language)
+        .bind(input.calendar_system.map(|c| c.code()))
+        .bind(input.digit_system.map(|d| d.code()))
+        .bind(input.cupping_duplicate_lot_policy.map(|p| p.as_str()))
+        .bind(input.cupping_flag_identical_scores)
+        .bind(input.benchmarking_opt_in)
+        .bind(input.marketplace_opt_in)
+        .bind(&input.marketplace_description)
+        .bind(&input.marketplace_description_th)
+        .bind(&input.cooperative_code)
+        .bind(input.recycle_bin_retention_days)
+        .bind(input.research_opt_in)
+        .bind(input.season_start_month)
+        .bind(input.fiscal_year_start_month)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business".to_string()))
+    }
+
+    /// Calendar and digits used for displayed dates and numbers; defaults
+    /// if the business is gone
+    pub async fn get_display_format(&self, business_id: Uuid) -> AppResult<DisplayFormat> {
+        let codes = sqlx::query_as::<_, (String, String)>(
+            "SELECT calendar_system, digit_system FROM businesses WHERE id = $1",
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(codes
+            .map(|(calendar, digits)| {
+                DisplayFormat::new(
+                    CalendarSystem::from_code(&calendar).unwrap_or_default(),
+                    DigitSystem::from_code(&digits).unwrap_or_default(),
+                )
+            })
+            .unwrap_or_default())
+    }
+
+    /// Season and fiscal year boundaries used by reports; defaults if the
+    /// business is gone
+    pub async fn get_reporting_calendar(&self, business_id: Uuid) -> AppResult<ReportingCalendar> {
+        let months = sqlx::query_as::<_, (i32, i32)>(
+            "SELECT season_start_month, fiscal_year_start_month FROM businesses WHERE id = $1",
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(months
+            .map(|(season, fiscal_year)| ReportingCalendar::new(season, fiscal_year))
+            .unwrap_or_default())
+    }
+}
@@ -6,6 +6,7 @@ use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
 
 /// Certification service for managing certifications
 #[derive(Clone)]
@@ -186,6 +187,28 @@ pub struct UpdateComplianceInput {
     pub evidence_url: Option<String>,
 }
 
+/// Compliance issue raised against a certification
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CertificationComplianceIssue {
+    pub id: Uuid,
+    pub certification_id: Uuid,
+    pub requirement_id: Option<Uuid>,
+    pub requirement_code: Option<String>,
+    pub farm_activity_id: Option<Uuid>,
+    pub description: String,
+    pub description_th: Option<String>,
+    pub detected_at: chrono::DateTime<Utc>,
+    pub resolved_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// Query parameters for listing compliance issues
+#[derive(Debug, Default, Deserialize)]
+pub struct ComplianceIssueQuery {
+    /// Include resolved issues
+    #[serde(default)]
+    pub include_resolved: bool,
+}
+
 /// Expiring certification info
 #[derive(Debug, Clone, Serialize)]
 pub struct ExpiringCertification {
@@ -401,25 +424,16 @@ impl CertificationService {
         Ok(certification)
     }
 
-    /// Delete a certification
+    /// Delete a certification, keeping it in the recycle bin
     pub async fn delete_certification(
         &self,
         business_id: Uuid,
+        user_id: Uuid,
         certification_id: Uuid,
     ) -> AppResult<()> {
-        let result = sqlx::query(
-            "DELETE FROM certifications WHERE id = $1 AND business_id = $2",
-        )
-        .bind(certification_id)
-        .bind(business_id)
-        .execute(&self.db)
-        .await?;
-
-        if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Certification".to_string()));
-        }
-
-        Ok(())
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::Certification, certification_id)
+            .await
     }
 
 
@@ -616,6 +630,66 @@ impl CertificationService {
         Ok(compliance)
     }
 
+    /// List compliance issues of a certification, newest first
+    pub async fn list_compliance_issues(
+        &self,
+        business_id: Uuid,
+        certification_id: Uuid,
+        query: &ComplianceIssueQuery,
+    ) -> AppResult<Vec<CertificationComplianceIssue>> {
+        // Validate certification exists
+        let _ = self.get_certification(business_id, certification_id).await?;
+
+        let issues = sqlx::query_as::<_, CertificationComplianceIssue>(
+            r#"
+            SELECT i.id, i.certification_id, i.requirement_id, r.requirement_code,
+                   i.farm_activity_id, i.description, i.description_th,
+                   i.detected_at, i.resolved_at
+            FROM certification_compliance_issues i
+            LEFT JOIN certification_requirements r ON r.id = i.requirement_id
+            WHERE i.certification_id = $1 AND ($2 OR i.resolved_at IS NULL)
+            ORDER BY i.detected_at DESC
+            "#,
+        )
+        .bind(certification_id)
+        .bind(query.include_resolved)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(issues)
+    }
+
+    /// Mark a compliance issue resolved
+    pub async fn resolve_compliance_issue(
+        &self,
+        business_id: Uuid,
+        certification_id: Uuid,
+        issue_id: Uuid,
+    ) -> AppResult<CertificationComplianceIssue> {
+        // Validate certification exists
+        let _ = self.get_certification(business_id, certification_id).await?;
+
+        let issue = sqlx::query_as::<_, CertificationComplianceIssue>(
+            r#"
+            UPDATE certification_compliance_issues i
+            SET resolved_at = COALESCE(i.resolved_at, NOW())
+            FROM certification_compliance_issues src
+            LEFT JOIN certification_requirements r ON r.id = src.requirement_id
+            WHERE i.id = src.id AND i.id = $1 AND i.certification_id = $2
+            RETURNING i.id, i.certification_id, i.requirement_id, r.requirement_code,
+                      i.farm_activity_id, i.description, i.description_th,
+                      i.detected_at, i.resolved_at
+            "#,
+        )
+        .bind(issue_id)
+        .bind(certification_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Compliance issue".to_string()))?;
+
+        Ok(issue)
+    }
+
     // ========================================================================
     // Expiration Alerts
     // ========================================================================
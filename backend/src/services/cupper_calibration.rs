@@ -0,0 +1,313 @@
+//! Cupper calibration against the panel
+//!
+//! On every panel sample a cupper scored with at least one other cupper,
+//! each of their attribute scores (and their final score) is compared with
+//! the mean of the other cuppers' scores on that sample, leaving the cupper
+//! out as the bias report does. Per attribute the report gives the average
+//! deviation (a cupper's habit of scoring high or low), the average
+//! absolute deviation (how far off they are either way) and how often they
+//! land further from the panel than the outlier threshold of panel reports.
+//! Deviations are also averaged per month so a head judge can see whether
+//! coaching is paying off.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::CuppingScores;
+use crate::services::cupping_analytics::cupper_key;
+use crate::services::cupping_panel::{
+    attribute_scores, std_dev, ATTRIBUTE_OUTLIER_POINTS, FINAL_SCORE_OUTLIER_POINTS,
+};
+use crate::services::reporting_period::resolve_date_range;
+use crate::services::BusinessService;
+
+/// Cupper calibration service
+#[derive(Clone)]
+pub struct CupperCalibrationService {
+    db: PgPool,
+}
+
+/// A cupper's score sheet on a panel sample
+#[derive(Debug, Clone)]
+pub struct PanelSheet {
+    pub sample_id: Uuid,
+    pub session_id: Uuid,
+    pub session_date: NaiveDate,
+    pub cupper_name: String,
+    pub scores: CuppingScores,
+    pub final_score: Decimal,
+}
+
+/// How far one cupper's sheet is from the rest of the panel on a sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleDeviation {
+    pub sample_id: Uuid,
+    pub session_id: Uuid,
+    pub session_date: NaiveDate,
+    /// Own score minus the other cuppers' mean, per attribute in SCA order
+    pub attributes: [(&'static str, Decimal); 10],
+    pub final_score: Decimal,
+}
+
+/// Calibration of one attribute (or the final score)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributeCalibration {
+    pub attribute: &'static str,
+    /// Average points above (+) or below (-) the other cuppers
+    pub bias: Decimal,
+    /// Average distance from the other cuppers either way
+    pub mean_absolute_deviation: Decimal,
+    /// Spread of the deviations; `None` with a single sample
+    pub std_dev: Option<Decimal>,
+    /// Samples where the score was further from the others than the panel
+    /// outlier threshold
+    pub outliers: usize,
+}
+
+/// Deviations of one month of sessions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalibrationMonth {
+    /// First day of the month
+    pub month: NaiveDate,
+    pub samples: usize,
+    pub final_score_bias: Decimal,
+    /// Average absolute deviation over all ten attributes
+    pub attribute_mean_absolute_deviation: Decimal,
+}
+
+/// Calibration report of a cupper
+#[derive(Debug, Clone, Serialize)]
+pub struct CupperCalibration {
+    pub cupper_name: String,
+    pub sessions: usize,
+    /// Panel samples scored with at least one other cupper
+    pub samples: usize,
+    pub attributes: Vec<AttributeCalibration>,
+    pub final_score: AttributeCalibration,
+    pub months: Vec<CalibrationMonth>,
+}
+
+/// Query parameters for the calibration report
+#[derive(Debug, Deserialize)]
+pub struct CalibrationQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Report period such as `season:2024/25`; overrides `from` and `to`
+    pub period: Option<String>,
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    values.iter().sum::<Decimal>() / Decimal::from(values.len())
+}
+
+/// Deviations of a cupper from the other cuppers on every sample they
+/// share, ordered by session date
+pub fn sample_deviations(cupper_name: &str, sheets: &[PanelSheet]) -> Vec<SampleDeviation> {
+    let key = cupper_key(cupper_name);
+    let mut by_sample: BTreeMap<Uuid, Vec<&PanelSheet>> = BTreeMap::new();
+    for sheet in sheets {
+        by_sample.entry(sheet.sample_id).or_default().push(sheet);
+    }
+
+    let mut deviations: Vec<SampleDeviation> = by_sample
+        .values()
+        .filter_map(|panel| {
+            let own = panel.iter().find(|s| cupper_key(&s.cupper_name) == key)?;
+            let others: Vec<&&PanelSheet> = panel.iter().filter(|s| cupper_key(&s.cupper_name) != key).collect();
+            if others.is_empty() {
+                return None;
+            }
+
+            let own_attributes = attribute_scores(&own.scores);
+            let other_attributes: Vec<[(&'static str, Decimal); 10]> =
+                others.iter().map(|s| attribute_scores(&s.scores)).collect();
+            let attributes = std::array::from_fn(|i| {
+                let values: Vec<Decimal> = other_attributes.iter().map(|a| a[i].1).collect();
+                (own_attributes[i].0, own_attributes[i].1 - mean(&values))
+            });
+            let other_finals: Vec<Decimal> = others.iter().map(|s| s.final_score).collect();
+
+            Some(SampleDeviation {
+                sample_id: own.sample_id,
+                session_id: own.session_id,
+                session_date: own.session_date,
+                attributes,
+                final_score: own.final_score - mean(&other_finals),
+            })
+        })
+        .collect();
+    deviations.sort_by_key(|d| (d.session_date, d.sample_id));
+    deviations
+}
+
+/// Calibration statistics of one attribute's deviations
+pub fn attribute_calibration(attribute: &'static str, deviations: &[Decimal], threshold: Decimal) -> AttributeCalibration {
+    let absolute: Vec<Decimal> = deviations.iter().map(|d| d.abs()).collect();
+    AttributeCalibration {
+        attribute,
+        bias: mean(deviations).round_dp(2),
+        mean_absolute_deviation: mean(&absolute).round_dp(2),
+        std_dev: std_dev(deviations).map(|s| s.round_dp(2)),
+        outliers: absolute.iter().filter(|d| **d > threshold).count(),
+    }
+}
+
+/// Deviations averaged per calendar month of the session date
+pub fn monthly_calibration(deviations: &[SampleDeviation]) -> Vec<CalibrationMonth> {
+    let mut months: BTreeMap<NaiveDate, Vec<&SampleDeviation>> = BTreeMap::new();
+    for deviation in deviations {
+        let month = deviation.session_date.with_day(1).unwrap_or(deviation.session_date);
+        months.entry(month).or_default().push(deviation);
+    }
+
+    months
+        .into_iter()
+        .map(|(month, samples)| {
+            let finals: Vec<Decimal> = samples.iter().map(|s| s.final_score).collect();
+            let absolute: Vec<Decimal> = samples
+                .iter()
+                .flat_map(|s| s.attributes.iter().map(|(_, d)| d.abs()))
+                .collect();
+            CalibrationMonth {
+                month,
+                samples: samples.len(),
+                final_score_bias: mean(&finals).round_dp(2),
+                attribute_mean_absolute_deviation: mean(&absolute).round_dp(2),
+            }
+        })
+        .collect()
+}
+
+/// Full calibration report from a cupper's deviations
+pub fn cupper_calibration(cupper_name: &str, deviations: &[SampleDeviation]) -> CupperCalibration {
+    let attributes = (0..10)
+        .map(|i| {
+            let name = deviations.first().map(|d| d.attributes[i].0).unwrap_or_default();
+            let values: Vec<Decimal> = deviations.iter().map(|d| d.attributes[i].1).collect();
+            attribute_calibration(name, &values, ATTRIBUTE_OUTLIER_POINTS)
+        })
+        .collect();
+    let finals: Vec<Decimal> = deviations.iter().map(|d| d.final_score).collect();
+    let mut sessions: Vec<Uuid> = deviations.iter().map(|d| d.session_id).collect();
+    sessions.sort();
+    sessions.dedup();
+
+    CupperCalibration {
+        cupper_name: cupper_name.split_whitespace().collect::<Vec<_>>().join(" "),
+        sessions: sessions.len(),
+        samples: deviations.len(),
+        attributes,
+        final_score: attribute_calibration("final_score", &finals, FINAL_SCORE_OUTLIER_POINTS),
+        months: monthly_calibration(deviations),
+    }
+}
+
+/// Database row for a panel score sheet
+#[derive(Debug, sqlx::FromRow)]
+struct PanelSheetRow {
+    sample_id: Uuid,
+    session_id: Uuid,
+    session_date: NaiveDate,
+    cupper_name: String,
+    fragrance_aroma: Decimal,
+    flavor: Decimal,
+    aftertaste: Decimal,
+    acidity: Decimal,
+    body: Decimal,
+    balance: Decimal,
+    uniformity: Decimal,
+    clean_cup: Decimal,
+    sweetness: Decimal,
+    overall: Decimal,
+    final_score: Decimal,
+}
+
+impl From<PanelSheetRow> for PanelSheet {
+    fn from(row: PanelSheetRow) -> Self {
+        PanelSheet {
+            sample_id: row.sample_id,
+            session_id: row.session_id,
+            session_date: row.session_date,
+            cupper_name: row.cupper_name,
+            scores: CuppingScores {
+                fragrance_aroma: row.fragrance_aroma,
+                flavor: row.flavor,
+                aftertaste: row.aftertaste,
+                acidity: row.acidity,
+                body: row.body,
+                balance: row.balance,
+                uniformity: row.uniformity,
+                clean_cup: row.clean_cup,
+                sweetness: row.sweetness,
+                overall: row.overall,
+            },
+            final_score: row.final_score,
+        }
+    }
+}
+
+impl CupperCalibrationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Calibration of a cupper over the panel samples they scored
+    pub async fn get_calibration(
+        &self,
+        business_id: Uuid,
+        cupper_name: &str,
+        query: &CalibrationQuery,
+    ) -> AppResult<CupperCalibration> {
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let (from, to) =
+            resolve_date_range(query.period.as_deref(), query.from, query.to, &calendar, Utc::now().date_naive())?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(AppError::Validation {
+                    field: "from".to_string(),
+                    message: "Start date must not be after end date".to_string(),
+                    message_th: "วันที่เริ่มต้นต้องไม่อยู่หลังวันที่สิ้นสุด".to_string(),
+                });
+            }
+        }
+
+        // Every sheet on samples with a panel in the period; the cupper is
+        // picked out by name key, as the bias report does
+        let rows = sqlx::query_as::<_, PanelSheetRow>(
+            r#"
+            SELECT cc.sample_id, s.id AS session_id, s.session_date, cc.cupper_name,
+                   cc.fragrance_aroma, cc.flavor, cc.aftertaste, cc.acidity, cc.body, cc.balance,
+                   cc.uniformity, cc.clean_cup, cc.sweetness, cc.overall, cc.final_score
+            FROM cupping_cupper_scores cc
+            JOIN cupping_samples cs ON cs.id = cc.sample_id
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            WHERE s.business_id = $1
+              AND ($2::date IS NULL OR s.session_date >= $2)
+              AND ($3::date IS NULL OR s.session_date <= $3)
+            "#,
+        )
+        .bind(business_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db)
+        .await?;
+        let sheets: Vec<PanelSheet> = rows.into_iter().map(PanelSheet::from).collect();
+
+        let key = cupper_key(cupper_name);
+        if key.is_empty() || !sheets.iter().any(|s| cupper_key(&s.cupper_name) == key) {
+            return Err(AppError::NotFound("Cupper".to_string()));
+        }
+
+        let deviations = sample_deviations(cupper_name, &sheets);
+        Ok(cupper_calibration(cupper_name, &deviations))
+    }
+}
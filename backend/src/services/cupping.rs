@@ -1,14 +1,41 @@
 //! Cupping session and score management service
 //!
-//! Implements SCA cupping protocol with 10 attributes.
+//! Implements SCA cupping protocol with 10 attributes. Blind sessions give
+//! each sample a random 3-digit code and keep its lot out of responses until
+//! the session is revealed. Flavors are recorded as descriptors from the
+//! SCA flavor wheel alongside free-text tasting notes. Correcting or
+//! deleting a sample records the values it had in the sample's edit history.
+//! Triangle sessions hold an odd-one-out discrimination test between two
+//! lots instead of scored samples (see `cupping_triangle`). A session is a
+//! draft until something is recorded in it and in progress until a cupper
+//! signs it off; a finalized session is locked and only finalized scores
+//! appear on the public traceability page. Samples can carry
+//! the brew's refractometer readings, the water activity and roast date of
+//! the coffee cupped, to set extraction against the scores.
+
+use std::collections::HashSet;
 
 use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use serde_json::Value;
+use shared::{
+    flavor_descriptor, search_flavor_descriptors, FlavorDescriptor, FlavorDescriptorEntry, FLAVOR_WHEEL,
+};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::cupping_analytics::cupper_key;
+use crate::services::cupping_panel::attribute_scores;
+use crate::services::cupping_triangle::{
+    triangle_arrangement, triangle_summary, CreateTriangleTestInput, RecordTriangleAnswerInput,
+    TasterTriangleResult, TriangleAnswer, TriangleResults, TriangleSet, TriangleSetResult, TriangleTest,
+    DEFAULT_SIGNIFICANCE_LEVEL, MAX_TRIANGLE_SETS, TRIANGLE_CUPS,
+};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+use crate::services::sequence::SequenceScope;
+use crate::services::{CuppingAnalyticsService, SequenceService};
 
 /// Cupping service for managing cupping sessions and scores
 #[derive(Clone)]
@@ -26,10 +53,28 @@ struct CuppingSessionRow {
     location: Option<String>,
     notes: Option<String>,
     notes_th: Option<String>,
+    session_type: String,
+    is_blind: bool,
+    revealed_at: Option<DateTime<Utc>>,
+    revealed_by: Option<Uuid>,
+    status: String,
+    finalized_at: Option<DateTime<Utc>>,
+    finalized_by: Option<Uuid>,
+    signed_by: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
+/// Database row for a triangle set
+#[derive(Debug, sqlx::FromRow)]
+struct TriangleSetRow {
+    id: Uuid,
+    set_number: i32,
+    cup_codes: Vec<String>,
+    odd_position: i32,
+    odd_is_test: bool,
+}
+
 /// Database row for cupping sample
 #[derive(Debug, sqlx::FromRow)]
 struct CuppingSampleRow {
@@ -37,6 +82,7 @@ struct CuppingSampleRow {
     session_id: Uuid,
     lot_id: Uuid,
     sample_number: i32,
+    blind_code: Option<String>,
     fragrance_aroma: Decimal,
     flavor: Decimal,
     aftertaste: Decimal,
@@ -50,9 +96,16 @@ struct CuppingSampleRow {
     total_score: Decimal,
     tasting_notes: Option<String>,
     tasting_notes_th: Option<String>,
+    flavor_descriptors: Vec<String>,
     defects_taint: i32,
     defects_fault: i32,
     final_score: Decimal,
+    normalized_score: Option<Decimal>,
+    roast_session_id: Option<Uuid>,
+    tds_percent: Option<Decimal>,
+    extraction_percent: Option<Decimal>,
+    water_activity: Option<Decimal>,
+    roast_date: Option<NaiveDate>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -67,6 +120,16 @@ pub struct CuppingSession {
     pub location: Option<String>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
+    pub session_type: CuppingSessionType,
+    /// Lots stay hidden until the session is revealed
+    pub blind: bool,
+    pub revealed_at: Option<DateTime<Utc>>,
+    pub revealed_by: Option<Uuid>,
+    pub status: CuppingSessionStatus,
+    pub finalized_at: Option<DateTime<Utc>>,
+    pub finalized_by: Option<Uuid>,
+    /// Cupper who signed the session off
+    pub signed_by: Option<String>,
     pub samples: Vec<CuppingSample>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -77,34 +140,41 @@ pub struct CuppingSession {
 pub struct CuppingSample {
     pub id: Uuid,
     pub session_id: Uuid,
-    pub lot_id: Uuid,
+    /// None while the session is blind and not yet revealed
+    pub lot_id: Option<Uuid>,
     pub sample_number: i32,
+    /// Code cuppers see in a blind session
+    pub blind_code: Option<String>,
     pub scores: CuppingScores,
     pub total_score: Decimal,
     pub tasting_notes: Option<String>,
     pub tasting_notes_th: Option<String>,
+    /// Flavors from the SCA flavor wheel
+    pub flavor_descriptors: Vec<FlavorDescriptorEntry>,
     pub defects: CuppingDefects,
     pub final_score: Decimal,
+    /// Final score with the cupper's bias removed (see `cupping_analytics`)
+    pub normalized_score: Option<Decimal>,
     pub classification: CoffeeClassification,
+    /// Roast session the cupped coffee came from; None while the session
+    /// is blind and not yet revealed
+    pub roast_session_id: Option<Uuid>,
+    pub measurements: BrewMeasurements,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-/// SCA Cupping Protocol Scores
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CuppingScores {
-    pub fragrance_aroma: Decimal,
-    pub flavor: Decimal,
-    pub aftertaste: Decimal,
-    pub acidity: Decimal,
-    pub body: Decimal,
-    pub balance: Decimal,
-    pub uniformity: Decimal,
-    pub clean_cup: Decimal,
-    pub sweetness: Decimal,
-    pub overall: Decimal,
+impl CuppingSample {
+    /// Drop the lot and its roast while the session is blind
+    fn conceal_lot(&mut self) {
+        self.lot_id = None;
+        self.roast_session_id = None;
+    }
 }
 
+/// SCA Cupping Protocol Scores (shared with the WASM client)
+pub use shared::CuppingScores;
+
 /// Cupping defects
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CuppingDefects {
@@ -118,6 +188,67 @@ impl CuppingDefects {
     }
 }
 
+/// Readings taken with a sample; all optional
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrewMeasurements {
+    /// Total dissolved solids of the brew, percent
+    pub tds_percent: Option<Decimal>,
+    /// Extraction yield of the brew, percent
+    pub extraction_percent: Option<Decimal>,
+    /// Water activity (aw) of the coffee cupped
+    pub water_activity: Option<Decimal>,
+    /// Roast date of the coffee cupped
+    pub roast_date: Option<NaiveDate>,
+}
+
+/// Highest brew TDS accepted, covering espresso as well as cupping strength
+pub const MAX_TDS_PERCENT: Decimal = Decimal::from_parts(25, 0, 0, false, 0);
+
+/// Highest extraction yield accepted; beyond it the coffee is spent
+pub const MAX_EXTRACTION_PERCENT: Decimal = Decimal::from_parts(30, 0, 0, false, 0);
+
+/// Check readings are within range and the coffee was roasted by the
+/// session date
+pub fn validate_measurements(measurements: &BrewMeasurements, session_date: NaiveDate) -> AppResult<()> {
+    let percentages = [
+        ("tds_percent", "TDS", "ค่า TDS", measurements.tds_percent, MAX_TDS_PERCENT),
+        (
+            "extraction_percent",
+            "Extraction",
+            "ค่าการสกัด",
+            measurements.extraction_percent,
+            MAX_EXTRACTION_PERCENT,
+        ),
+    ];
+    for (field, name, name_th, value, max) in percentages {
+        if value.is_some_and(|value| value <= Decimal::ZERO || value > max) {
+            return Err(AppError::Validation {
+                field: field.to_string(),
+                message: format!("{} must be above 0 and at most {}%", name, max),
+                message_th: format!("{}ต้องมากกว่า 0 และไม่เกิน {}%", name_th, max),
+            });
+        }
+    }
+    if measurements
+        .water_activity
+        .is_some_and(|aw| aw < Decimal::ZERO || aw > Decimal::ONE)
+    {
+        return Err(AppError::Validation {
+            field: "water_activity".to_string(),
+            message: "Water activity must be between 0 and 1".to_string(),
+            message_th: "ค่าวอเตอร์แอคทิวิตี้ต้องอยู่ระหว่าง 0 ถึง 1".to_string(),
+        });
+    }
+    if measurements.roast_date.is_some_and(|date| date > session_date) {
+        return Err(AppError::Validation {
+            field: "roast_date".to_string(),
+            message: "The coffee cannot be roasted after the session date".to_string(),
+            message_th: "วันที่คั่วต้องไม่อยู่หลังวันที่ชิม".to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Coffee classification based on cupping score
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -139,6 +270,114 @@ impl std::fmt::Display for CoffeeClassification {
     }
 }
 
+/// Kind of cupping session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CuppingSessionType {
+    /// SCA scores per sample
+    #[default]
+    Scoring,
+    /// Odd-one-out discrimination test between two lots
+    Triangle,
+}
+
+impl CuppingSessionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CuppingSessionType::Scoring => "scoring",
+            CuppingSessionType::Triangle => "triangle",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "scoring" => Some(CuppingSessionType::Scoring),
+            "triangle" => Some(CuppingSessionType::Triangle),
+            _ => None,
+        }
+    }
+}
+
+/// Where a session is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CuppingSessionStatus {
+    /// Nothing recorded yet
+    #[default]
+    Draft,
+    /// Samples, panel sheets or answers are being recorded
+    InProgress,
+    /// Signed off; locked and shown on the public traceability page
+    Finalized,
+}
+
+impl CuppingSessionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CuppingSessionStatus::Draft => "draft",
+            CuppingSessionStatus::InProgress => "in_progress",
+            CuppingSessionStatus::Finalized => "finalized",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(CuppingSessionStatus::Draft),
+            "in_progress" => Some(CuppingSessionStatus::InProgress),
+            "finalized" => Some(CuppingSessionStatus::Finalized),
+            _ => None,
+        }
+    }
+}
+
+/// Input for signing a session off
+#[derive(Debug, Default, Deserialize)]
+pub struct FinalizeCuppingSessionInput {
+    /// The session's cupper or a cupper on its panel; the session's cupper
+    /// when omitted
+    pub signed_by: Option<String>,
+}
+
+/// Refuse changes to the samples, panel sheets and answers of a finalized
+/// session
+pub fn ensure_session_open(status: CuppingSessionStatus) -> AppResult<()> {
+    if status == CuppingSessionStatus::Finalized {
+        return Err(AppError::Conflict {
+            resource: "cupping_session".to_string(),
+            message: "The session has been finalized and can no longer be changed".to_string(),
+            message_th: "รอบการชิมนี้ได้รับการรับรองแล้ว จึงแก้ไขไม่ได้".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Check a session has something recorded and is not finalized yet
+pub fn check_finalize(status: CuppingSessionStatus) -> AppResult<()> {
+    ensure_session_open(status)?;
+    if status == CuppingSessionStatus::Draft {
+        return Err(AppError::Validation {
+            field: "session_id".to_string(),
+            message: "Record samples or answers before finalizing the session".to_string(),
+            message_th: "ต้องบันทึกตัวอย่างหรือคำตอบก่อนรับรองรอบการชิม".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Name of the cupper signing a session off, as recorded in the session:
+/// the requested cupper when they are the session's cupper or on its panel,
+/// else None; the session's cupper when none is requested
+pub fn signing_cupper(head_cupper: &str, panel_cuppers: &[String], requested: Option<&str>) -> Option<String> {
+    let Some(requested) = requested.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Some(head_cupper.to_string());
+    };
+    let key = cupper_key(requested);
+    std::iter::once(head_cupper)
+        .chain(panel_cuppers.iter().map(String::as_str))
+        .find(|name| cupper_key(name) == key)
+        .map(str::to_string)
+}
+
 /// Input for creating a cupping session
 #[derive(Debug, Deserialize)]
 pub struct CreateCuppingSessionInput {
@@ -147,6 +386,13 @@ pub struct CreateCuppingSessionInput {
     pub location: Option<String>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
+    /// Hide the lots behind blind codes until the session is revealed
+    #[serde(default)]
+    pub blind: bool,
+    #[serde(default)]
+    pub session_type: CuppingSessionType,
+    /// Lots and sets of a triangle session
+    pub triangle: Option<CreateTriangleTestInput>,
 }
 
 /// Input for adding a cupping sample
@@ -156,7 +402,374 @@ pub struct AddCuppingSampleInput {
     pub scores: CuppingScores,
     pub tasting_notes: Option<String>,
     pub tasting_notes_th: Option<String>,
+    /// SCA flavor wheel codes, e.g. `fruity.berry.blueberry`
+    #[serde(default)]
+    pub flavor_descriptors: Vec<String>,
+    pub defects: Option<CuppingDefects>,
+    /// Roast session of the lot the cupped coffee came from
+    pub roast_session_id: Option<Uuid>,
+    #[serde(default)]
+    pub measurements: BrewMeasurements,
+    /// Add the sample even though it repeats a lot or scores already in the
+    /// session (ignored when the business blocks repeated lots)
+    #[serde(default)]
+    pub confirm_duplicate: bool,
+}
+
+/// Input for correcting a cupping sample; fields left out stay as they are
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateCuppingSampleInput {
+    pub scores: Option<CuppingScores>,
     pub defects: Option<CuppingDefects>,
+    /// An empty string clears the notes
+    pub tasting_notes: Option<String>,
+    pub tasting_notes_th: Option<String>,
+    pub flavor_descriptors: Option<Vec<String>>,
+    /// Replaces all readings; omitted readings are cleared
+    pub measurements: Option<BrewMeasurements>,
+    /// Why the sample was corrected, kept in its edit history
+    pub reason: Option<String>,
+    /// Save scores identical to another sample in the session
+    #[serde(default)]
+    pub confirm_duplicate: bool,
+}
+
+/// Input for deleting a cupping sample
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteCuppingSampleQuery {
+    pub reason: Option<String>,
+}
+
+/// What an entry in a sample's edit history did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleEditAction {
+    Update,
+    Delete,
+}
+
+impl SampleEditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// A field of a sample changed by an edit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleFieldChange {
+    pub field: String,
+    pub from: Value,
+    /// Null when the sample was deleted
+    pub to: Value,
+}
+
+/// An entry in a sample's edit history
+#[derive(Debug, Clone, Serialize)]
+pub struct CuppingSampleEdit {
+    pub id: Uuid,
+    pub sample_id: Uuid,
+    pub sample_number: i32,
+    pub action: SampleEditAction,
+    pub changes: Vec<SampleFieldChange>,
+    pub reason: Option<String>,
+    pub edited_by: Option<Uuid>,
+    pub edited_by_name: Option<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Database row for a sample edit
+#[derive(Debug, sqlx::FromRow)]
+struct CuppingSampleEditRow {
+    id: Uuid,
+    sample_id: Uuid,
+    sample_number: i32,
+    action: String,
+    changes: sqlx::types::Json<Vec<SampleFieldChange>>,
+    reason: Option<String>,
+    edited_by: Option<Uuid>,
+    edited_by_name: Option<String>,
+    edited_at: DateTime<Utc>,
+}
+
+/// What happens when a lot is added to a session it is already in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateLotPolicy {
+    Allow,
+    /// Require `confirm_duplicate`
+    #[default]
+    Warn,
+    Block,
+}
+
+impl DuplicateLotPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateLotPolicy::Allow => "allow",
+            DuplicateLotPolicy::Warn => "warn",
+            DuplicateLotPolicy::Block => "block",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "allow" => Some(DuplicateLotPolicy::Allow),
+            "warn" => Some(DuplicateLotPolicy::Warn),
+            "block" => Some(DuplicateLotPolicy::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Per-business duplicate sample checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DuplicateSampleSettings {
+    pub lot_policy: DuplicateLotPolicy,
+    /// Require confirmation when all ten scores match another sample in the
+    /// session, which usually means a copy-pasted row
+    pub flag_identical_scores: bool,
+}
+
+/// Why a new sample was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateSample {
+    /// The lot is already this sample number and repeats are blocked
+    LotBlocked(i32),
+    /// The lot is already this sample number; needs confirmation
+    LotRepeated(i32),
+    /// Scores match this sample number exactly; needs confirmation
+    IdenticalScores(i32),
+}
+
+impl From<DuplicateSample> for AppError {
+    fn from(duplicate: DuplicateSample) -> Self {
+        let (resource, message, message_th) = match duplicate {
+            DuplicateSample::LotBlocked(number) => (
+                "lot_id",
+                format!("This lot is already sample #{} in the session", number),
+                format!("ล็อตนี้เป็นตัวอย่างที่ {} ในเซสชันนี้แล้ว", number),
+            ),
+            DuplicateSample::LotRepeated(number) => (
+                "lot_id",
+                format!(
+                    "This lot is already sample #{} in the session; set confirm_duplicate to add it again",
+                    number
+                ),
+                format!(
+                    "ล็อตนี้เป็นตัวอย่างที่ {} ในเซสชันนี้แล้ว กรุณายืนยันหากต้องการเพิ่มซ้ำ",
+                    number
+                ),
+            ),
+            DuplicateSample::IdenticalScores(number) => (
+                "scores",
+                format!(
+                    "Scores are identical to sample #{}; set confirm_duplicate if this is intended",
+                    number
+                ),
+                format!(
+                    "คะแนนเหมือนกับตัวอย่างที่ {} ทุกรายการ กรุณายืนยันหากถูกต้อง",
+                    number
+                ),
+            ),
+        };
+        AppError::Conflict {
+            resource: resource.to_string(),
+            message,
+            message_th,
+        }
+    }
+}
+
+/// Check a new sample against the samples already in its session
+pub fn check_duplicate_sample(
+    settings: &DuplicateSampleSettings,
+    lot_id: Uuid,
+    scores: &CuppingScores,
+    existing: &[CuppingSample],
+    confirmed: bool,
+) -> Option<DuplicateSample> {
+    if let Some(same_lot) = existing.iter().find(|s| s.lot_id == Some(lot_id)) {
+        match settings.lot_policy {
+            DuplicateLotPolicy::Block => return Some(DuplicateSample::LotBlocked(same_lot.sample_number)),
+            DuplicateLotPolicy::Warn if !confirmed => {
+                return Some(DuplicateSample::LotRepeated(same_lot.sample_number))
+            }
+            _ => {}
+        }
+    }
+
+    if settings.flag_identical_scores && !confirmed {
+        if let Some(identical) = existing.iter().find(|s| s.scores == *scores) {
+            return Some(DuplicateSample::IdenticalScores(identical.sample_number));
+        }
+    }
+
+    None
+}
+
+/// Most flavor wheel descriptors one sample can carry
+pub const MAX_FLAVOR_DESCRIPTORS: usize = 12;
+
+/// Check flavor wheel codes, dropping repeats but keeping the cupper's order
+pub fn validate_flavor_descriptors(codes: &[String]) -> AppResult<Vec<String>> {
+    let mut descriptors: Vec<String> = Vec::new();
+    for code in codes {
+        let code = code.trim();
+        if flavor_descriptor(code).is_none() {
+            return Err(AppError::Validation {
+                field: "flavor_descriptors".to_string(),
+                message: format!("'{}' is not a flavor wheel descriptor", code),
+                message_th: format!("'{}' ไม่ใช่คำบรรยายรสชาติในวงล้อรสชาติ", code),
+            });
+        }
+        if !descriptors.iter().any(|d| d == code) {
+            descriptors.push(code.to_string());
+        }
+    }
+    if descriptors.len() > MAX_FLAVOR_DESCRIPTORS {
+        return Err(AppError::Validation {
+            field: "flavor_descriptors".to_string(),
+            message: format!("A sample can have at most {} flavor descriptors", MAX_FLAVOR_DESCRIPTORS),
+            message_th: format!("ตัวอย่างหนึ่งมีคำบรรยายรสชาติได้ไม่เกิน {} คำ", MAX_FLAVOR_DESCRIPTORS),
+        });
+    }
+    Ok(descriptors)
+}
+
+/// Query parameters for flavor descriptor autocomplete
+#[derive(Debug, Deserialize)]
+pub struct FlavorDescriptorQuery {
+    /// Text typed so far, English or Thai; blank lists the whole wheel
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Suggestions returned when none is asked for
+const DEFAULT_DESCRIPTOR_SUGGESTIONS: usize = 20;
+
+/// Flavor wheel entries for autocomplete
+pub fn suggest_flavor_descriptors(query: &FlavorDescriptorQuery) -> Vec<FlavorDescriptorEntry> {
+    match query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(q) => search_flavor_descriptors(q, query.limit.unwrap_or(DEFAULT_DESCRIPTOR_SUGGESTIONS).clamp(1, 100))
+            .into_iter()
+            .map(FlavorDescriptor::entry)
+            .collect(),
+        None => FLAVOR_WHEEL.iter().map(FlavorDescriptor::entry).collect(),
+    }
+}
+
+/// Distinct 3-digit blind codes in a session
+pub const MAX_BLIND_CODES: usize = 900;
+
+/// A 3-digit blind code (100-999) not used yet in the session, starting
+/// from a random point; None once all codes are taken
+pub fn next_blind_code(used: &HashSet<String>, entropy: u64) -> Option<String> {
+    let start = entropy % MAX_BLIND_CODES as u64;
+    (0..MAX_BLIND_CODES as u64)
+        .map(|offset| (100 + (start + offset) % MAX_BLIND_CODES as u64).to_string())
+        .find(|code| !used.contains(code))
+}
+
+/// Values of a sample kept in its edit history, by field; the lot is left
+/// out so the history of a blind session does not give it away
+pub fn sample_values(sample: &CuppingSample) -> Vec<(&'static str, Value)> {
+    let mut values: Vec<(&'static str, Value)> = attribute_scores(&sample.scores)
+        .into_iter()
+        .map(|(name, score)| (name, serde_json::json!(score)))
+        .collect();
+    values.extend([
+        ("defects_taint", serde_json::json!(sample.defects.taint_count)),
+        ("defects_fault", serde_json::json!(sample.defects.fault_count)),
+        ("total_score", serde_json::json!(sample.total_score)),
+        ("final_score", serde_json::json!(sample.final_score)),
+        ("tasting_notes", serde_json::json!(sample.tasting_notes)),
+        ("tasting_notes_th", serde_json::json!(sample.tasting_notes_th)),
+        (
+            "flavor_descriptors",
+            serde_json::json!(sample.flavor_descriptors.iter().map(|d| &d.code).collect::<Vec<_>>()),
+        ),
+        ("tds_percent", serde_json::json!(sample.measurements.tds_percent)),
+        ("extraction_percent", serde_json::json!(sample.measurements.extraction_percent)),
+        ("water_activity", serde_json::json!(sample.measurements.water_activity)),
+        ("roast_date", serde_json::json!(sample.measurements.roast_date)),
+    ]);
+    values
+}
+
+/// Fields that differ between a sample's values before and after an edit;
+/// with no values after (a delete) every field is listed
+pub fn sample_changes(before: &[(&'static str, Value)], after: Option<&[(&'static str, Value)]>) -> Vec<SampleFieldChange> {
+    before
+        .iter()
+        .filter_map(|(field, from)| {
+            let to = match after {
+                Some(after) => after.iter().find(|(f, _)| f == field).map(|(_, v)| v.clone()).unwrap_or(Value::Null),
+                None => Value::Null,
+            };
+            if after.is_some() && *from == to {
+                return None;
+            }
+            Some(SampleFieldChange { field: field.to_string(), from: from.clone(), to })
+        })
+        .collect()
+}
+
+/// Notes as stored: trimmed, with blank notes cleared
+fn clean_notes(notes: String) -> Option<String> {
+    let notes = notes.trim();
+    (!notes.is_empty()).then(|| notes.to_string())
+}
+
+/// Roast session a sample names, as the business and lot it roasted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub struct SampleRoast {
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+}
+
+/// Check the roast session a sample names: a sample may name none;
+/// otherwise the roast (None when no roast has the id) must belong to the
+/// business and have roasted the sample's lot
+pub fn validate_sample_roast(
+    roast_session_id: Option<Uuid>,
+    roast: Option<SampleRoast>,
+    business_id: Uuid,
+    lot_id: Uuid,
+) -> AppResult<()> {
+    if roast_session_id.is_none() {
+        return Ok(());
+    }
+    let roast = roast
+        .filter(|r| r.business_id == business_id)
+        .ok_or_else(|| AppError::NotFound("Roast session".to_string()))?;
+    if roast.lot_id != lot_id {
+        return Err(AppError::Validation {
+            field: "roast_session_id".to_string(),
+            message: "The roast session roasted a different lot".to_string(),
+            message_th: "รอบการคั่วนี้คั่วล็อตอื่น".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl CuppingSession {
+    /// Whether the session's lots are still hidden
+    pub fn is_concealed(&self) -> bool {
+        self.blind && self.revealed_at.is_none()
+    }
+
+    /// Drop the lots from the samples while the session is blind
+    pub fn conceal(mut self) -> Self {
+        if self.is_concealed() {
+            for sample in &mut self.samples {
+                sample.conceal_lot();
+            }
+        }
+        self
+    }
 }
 
 /// Cupping trend data
@@ -198,11 +811,29 @@ impl CuppingService {
             });
         }
 
+        let triangle = match (input.session_type, &input.triangle) {
+            (CuppingSessionType::Triangle, Some(triangle)) => {
+                Some(self.validate_triangle_test(business_id, triangle).await?)
+            }
+            (CuppingSessionType::Triangle, None) => {
+                return Err(AppError::Validation {
+                    field: "triangle".to_string(),
+                    message: "A triangle session needs a control lot, a test lot and the number of sets".to_string(),
+                    message_th: "รอบการทดสอบสามถ้วยต้องระบุล็อตควบคุม ล็อตทดสอบ และจำนวนชุด".to_string(),
+                });
+            }
+            (CuppingSessionType::Scoring, _) => None,
+        };
+
+        let mut tx = self.db.begin().await?;
         let row = sqlx::query_as::<_, CuppingSessionRow>(
             r#"
-            INSERT INTO cupping_sessions (business_id, session_date, cupper_name, location, notes, notes_th)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, business_id, session_date, cupper_name, location, notes, notes_th, created_at, updated_at
+            INSERT INTO cupping_sessions
+                (business_id, session_date, cupper_name, location, notes, notes_th, is_blind, session_type)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, business_id, session_date, cupper_name, location, notes, notes_th,
+                      session_type, is_blind, revealed_at, revealed_by,
+                      status, finalized_at, finalized_by, signed_by, created_at, updated_at
             "#,
         )
         .bind(business_id)
@@ -211,21 +842,17 @@ impl CuppingService {
         .bind(&input.location)
         .bind(&input.notes)
         .bind(&input.notes_th)
-        .fetch_one(&self.db)
+        .bind(input.blind)
+        .bind(input.session_type.as_str())
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(CuppingSession {
-            id: row.id,
-            business_id: row.business_id,
-            session_date: row.session_date,
-            cupper_name: row.cupper_name,
-            location: row.location,
-            notes: row.notes,
-            notes_th: row.notes_th,
-            samples: vec![],
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-        })
+        if let (Some(triangle), Some(significance_level)) = (&input.triangle, triangle) {
+            Self::create_triangle_sets(&mut tx, row.id, triangle, significance_level).await?;
+        }
+        tx.commit().await?;
+
+        Ok(Self::row_to_session(row, vec![]))
     }
 
     /// Add a sample to a cupping session
@@ -236,13 +863,37 @@ impl CuppingService {
         input: AddCuppingSampleInput,
     ) -> AppResult<CuppingSample> {
         // Validate session exists and belongs to business
-        self.validate_session_access(business_id, session_id).await?;
+        let (blind, concealed) = self.validate_session_access(business_id, session_id).await?;
+        self.ensure_open(session_id).await?;
+        if self.session_type(business_id, session_id).await? == CuppingSessionType::Triangle {
+            return Err(AppError::Validation {
+                field: "session_id".to_string(),
+                message: "Triangle sessions take answers, not scored samples".to_string(),
+                message_th: "รอบการทดสอบสามถ้วยบันทึกคำตอบ ไม่ใช่คะแนนตัวอย่าง".to_string(),
+            });
+        }
 
         // Validate lot exists and belongs to business
         self.validate_lot_access(business_id, input.lot_id).await?;
+        self.validate_roast_session(business_id, input.roast_session_id, input.lot_id).await?;
 
         // Validate scores
-        self.validate_scores(&input.scores)?;
+        Self::validate_scores(&input.scores)?;
+        let flavor_descriptors = validate_flavor_descriptors(&input.flavor_descriptors)?;
+        validate_measurements(&input.measurements, self.session_date(session_id).await?)?;
+
+        // Refuse repeated lots and copy-pasted score rows per business settings
+        let settings = self.duplicate_settings(business_id).await?;
+        let existing = self.session_samples(session_id).await?;
+        if let Some(duplicate) = check_duplicate_sample(
+            &settings,
+            input.lot_id,
+            &input.scores,
+            &existing,
+            input.confirm_duplicate,
+        ) {
+            return Err(duplicate.into());
+        }
 
         // Calculate total score
         let total_score = Self::calculate_total_score(&input.scores);
@@ -253,13 +904,44 @@ impl CuppingService {
         // Calculate final score
         let final_score = total_score - defects.total_deduction();
 
-        // Get next sample number
-        let sample_number = sqlx::query_scalar::<_, i64>(
-            "SELECT COALESCE(MAX(sample_number), 0) + 1 FROM cupping_samples WHERE session_id = $1",
+        // Allocate the next sample number atomically; the current maximum
+        // covers samples written without the counter
+        let highest = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(MAX(sample_number), 0)::BIGINT FROM cupping_samples WHERE session_id = $1",
         )
         .bind(session_id)
         .fetch_one(&self.db)
-        .await? as i32;
+        .await?;
+        let sample_number = SequenceService::new(self.db.clone())
+            .next_above(business_id, SequenceScope::CuppingSample, &session_id.to_string(), highest)
+            .await? as i32;
+
+        // Lock the session so concurrent adds can't pick the same blind code
+        let mut tx = self.db.begin().await?;
+        sqlx::query("SELECT id FROM cupping_sessions WHERE id = $1 FOR UPDATE")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Random without pulling in an RNG crate
+        let blind_code = if blind {
+            let used: HashSet<String> = sqlx::query_scalar::<_, String>(
+                "SELECT blind_code FROM cupping_samples WHERE session_id = $1 AND blind_code IS NOT NULL",
+            )
+            .bind(session_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+            let code = next_blind_code(&used, Uuid::new_v4().as_u128() as u64).ok_or_else(|| AppError::Validation {
+                field: "session_id".to_string(),
+                message: format!("A blind session can hold at most {} samples", MAX_BLIND_CODES),
+                message_th: format!("รอบการชิมแบบปิดตาเพิ่มตัวอย่างได้ไม่เกิน {} ตัวอย่าง", MAX_BLIND_CODES),
+            })?;
+            Some(code)
+        } else {
+            None
+        };
 
         let row = sqlx::query_as::<_, CuppingSampleRow>(
             r#"
@@ -267,15 +949,18 @@ impl CuppingService {
                 session_id, lot_id, sample_number,
                 fragrance_aroma, flavor, aftertaste, acidity, body, balance,
                 uniformity, clean_cup, sweetness, overall,
-                total_score, tasting_notes, tasting_notes_th,
-                defects_taint, defects_fault, final_score
+                total_score, tasting_notes, tasting_notes_th, flavor_descriptors,
+                defects_taint, defects_fault, final_score, blind_code, roast_session_id,
+                tds_percent, extraction_percent, water_activity, roast_date
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
-            RETURNING id, session_id, lot_id, sample_number,
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22,
+                    $23, $24, $25, $26)
+            RETURNING id, session_id, lot_id, sample_number, blind_code,
                       fragrance_aroma, flavor, aftertaste, acidity, body, balance,
                       uniformity, clean_cup, sweetness, overall,
-                      total_score, tasting_notes, tasting_notes_th,
-                      defects_taint, defects_fault, final_score,
+                      total_score, tasting_notes, tasting_notes_th, flavor_descriptors,
+                      defects_taint, defects_fault, final_score, normalized_score, roast_session_id,
+                      tds_percent, extraction_percent, water_activity, roast_date,
                       created_at, updated_at
             "#,
         )
@@ -295,16 +980,272 @@ impl CuppingService {
         .bind(total_score)
         .bind(&input.tasting_notes)
         .bind(&input.tasting_notes_th)
+        .bind(&flavor_descriptors)
+        .bind(defects.taint_count)
+        .bind(defects.fault_count)
+        .bind(final_score)
+        .bind(&blind_code)
+        .bind(input.roast_session_id)
+        .bind(input.measurements.tds_percent)
+        .bind(input.measurements.extraction_percent)
+        .bind(input.measurements.water_activity)
+        .bind(input.measurements.roast_date)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        self.mark_in_progress(session_id).await?;
+
+        let mut sample = self.row_to_sample(row);
+        if concealed {
+            sample.conceal_lot();
+        }
+        Ok(sample)
+    }
+
+    /// Correct a sample's scores, defects, notes or descriptors; the total
+    /// and final scores are recalculated and the values it had are kept in
+    /// its edit history. Scores of a sample scored by a panel are the panel
+    /// consensus and are corrected through the cuppers' sheets instead
+    pub async fn update_sample(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+        sample_id: Uuid,
+        input: UpdateCuppingSampleInput,
+    ) -> AppResult<CuppingSample> {
+        let (_, concealed) = self.validate_session_access(business_id, session_id).await?;
+        self.ensure_open(session_id).await?;
+        let existing = self.session_samples(session_id).await?;
+        let before = existing
+            .iter()
+            .find(|s| s.id == sample_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Cupping sample".to_string()))?;
+
+        if input.scores.is_some() || input.defects.is_some() {
+            let has_sheets = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM cupping_cupper_scores WHERE sample_id = $1)",
+            )
+            .bind(sample_id)
+            .fetch_one(&self.db)
+            .await?;
+            if has_sheets {
+                return Err(AppError::Conflict {
+                    resource: "scores".to_string(),
+                    message: "Scores of this sample are the panel consensus; correct the cupper's score sheet instead"
+                        .to_string(),
+                    message_th: "คะแนนของตัวอย่างนี้มาจากค่าเฉลี่ยของคณะผู้ชิม กรุณาแก้ไขที่แบบบันทึกคะแนนของผู้ชิม"
+                        .to_string(),
+                });
+            }
+        }
+
+        let scores = input.scores.unwrap_or_else(|| before.scores.clone());
+        Self::validate_scores(&scores)?;
+        if scores != before.scores {
+            // The lot is unchanged, so only copy-pasted scores are checked
+            let settings = DuplicateSampleSettings {
+                lot_policy: DuplicateLotPolicy::Allow,
+                ..self.duplicate_settings(business_id).await?
+            };
+            let others: Vec<CuppingSample> = existing.into_iter().filter(|s| s.id != sample_id).collect();
+            let lot_id = before.lot_id.unwrap_or_default();
+            if let Some(duplicate) = check_duplicate_sample(&settings, lot_id, &scores, &others, input.confirm_duplicate) {
+                return Err(duplicate.into());
+            }
+        }
+        let defects = input.defects.unwrap_or_else(|| before.defects.clone());
+        if defects.taint_count < 0 || defects.fault_count < 0 {
+            return Err(AppError::Validation {
+                field: "defects".to_string(),
+                message: "Defect counts cannot be negative".to_string(),
+                message_th: "จำนวนข้อบกพร่องต้องไม่ติดลบ".to_string(),
+            });
+        }
+        let flavor_descriptors = match &input.flavor_descriptors {
+            Some(codes) => validate_flavor_descriptors(codes)?,
+            None => before.flavor_descriptors.iter().map(|d| d.code.clone()).collect(),
+        };
+        let tasting_notes = input.tasting_notes.map(clean_notes).unwrap_or_else(|| before.tasting_notes.clone());
+        let tasting_notes_th = input
+            .tasting_notes_th
+            .map(clean_notes)
+            .unwrap_or_else(|| before.tasting_notes_th.clone());
+        let measurements = input.measurements.unwrap_or_else(|| before.measurements.clone());
+        validate_measurements(&measurements, self.session_date(session_id).await?)?;
+        let total_score = Self::calculate_total_score(&scores);
+        let final_score = total_score - defects.total_deduction();
+
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query_as::<_, CuppingSampleRow>(
+            r#"
+            UPDATE cupping_samples
+            SET fragrance_aroma = $2, flavor = $3, aftertaste = $4, acidity = $5, body = $6,
+                balance = $7, uniformity = $8, clean_cup = $9, sweetness = $10, overall = $11,
+                total_score = $12, tasting_notes = $13, tasting_notes_th = $14, flavor_descriptors = $15,
+                defects_taint = $16, defects_fault = $17, final_score = $18,
+                tds_percent = $19, extraction_percent = $20, water_activity = $21, roast_date = $22
+            WHERE id = $1
+            RETURNING id, session_id, lot_id, sample_number, blind_code,
+                      fragrance_aroma, flavor, aftertaste, acidity, body, balance,
+                      uniformity, clean_cup, sweetness, overall,
+                      total_score, tasting_notes, tasting_notes_th, flavor_descriptors,
+                      defects_taint, defects_fault, final_score, normalized_score, roast_session_id,
+                      tds_percent, extraction_percent, water_activity, roast_date,
+                      created_at, updated_at
+            "#,
+        )
+        .bind(sample_id)
+        .bind(scores.fragrance_aroma)
+        .bind(scores.flavor)
+        .bind(scores.aftertaste)
+        .bind(scores.acidity)
+        .bind(scores.body)
+        .bind(scores.balance)
+        .bind(scores.uniformity)
+        .bind(scores.clean_cup)
+        .bind(scores.sweetness)
+        .bind(scores.overall)
+        .bind(total_score)
+        .bind(&tasting_notes)
+        .bind(&tasting_notes_th)
+        .bind(&flavor_descriptors)
         .bind(defects.taint_count)
         .bind(defects.fault_count)
         .bind(final_score)
+        .bind(measurements.tds_percent)
+        .bind(measurements.extraction_percent)
+        .bind(measurements.water_activity)
+        .bind(measurements.roast_date)
+        .fetch_one(&mut *tx)
+        .await?;
+        let after = self.row_to_sample(row);
+
+        let changes = sample_changes(&sample_values(&before), Some(&sample_values(&after)));
+        if !changes.is_empty() {
+            Self::record_edit(&mut tx, &before, SampleEditAction::Update, &changes, input.reason, user_id).await?;
+        }
+        tx.commit().await?;
+
+        let mut sample = self
+            .session_samples(session_id)
+            .await?
+            .into_iter()
+            .find(|s| s.id == sample_id)
+            .ok_or_else(|| AppError::NotFound("Cupping sample".to_string()))?;
+        if concealed {
+            sample.conceal_lot();
+        }
+        Ok(sample)
+    }
+
+    /// Delete a sample and its panel score sheets, keeping it in the recycle
+    /// bin and the values it had in its edit history. Samples a quality
+    /// evaluation rests on are kept
+    pub async fn delete_sample(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+        sample_id: Uuid,
+        reason: Option<String>,
+    ) -> AppResult<()> {
+        self.validate_session_access(business_id, session_id).await?;
+        self.ensure_open(session_id).await?;
+        let sample = self
+            .session_samples(session_id)
+            .await?
+            .into_iter()
+            .find(|s| s.id == sample_id)
+            .ok_or_else(|| AppError::NotFound("Cupping sample".to_string()))?;
+
+        let evaluations = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM quality_evaluations WHERE cupping_sample_id = $1",
+        )
+        .bind(sample_id)
         .fetch_one(&self.db)
         .await?;
+        if evaluations > 0 {
+            return Err(AppError::Conflict {
+                resource: "sample_id".to_string(),
+                message: format!(
+                    "Sample #{} is used by {} quality evaluation(s) and cannot be deleted",
+                    sample.sample_number, evaluations
+                ),
+                message_th: format!(
+                    "ตัวอย่างที่ {} ถูกใช้ในการประเมินคุณภาพ {} รายการ จึงลบไม่ได้",
+                    sample.sample_number, evaluations
+                ),
+            });
+        }
 
-        Ok(self.row_to_sample(row))
+        let mut tx = self.db.begin().await?;
+        let changes = sample_changes(&sample_values(&sample), None);
+        Self::record_edit(&mut tx, &sample, SampleEditAction::Delete, &changes, reason, user_id).await?;
+        RecycleBinService::capture(&mut tx, business_id, Some(user_id), DeletedEntity::CuppingSample, sample_id).await?;
+        sqlx::query("DELETE FROM cupping_samples WHERE id = $1")
+            .bind(sample_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
     }
 
-    /// Get a cupping session with all samples
+    /// Edit history of a sample, newest first; kept after the sample is
+    /// deleted
+    pub async fn sample_history(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        sample_id: Uuid,
+    ) -> AppResult<Vec<CuppingSampleEdit>> {
+        self.validate_session_access(business_id, session_id).await?;
+
+        let rows = sqlx::query_as::<_, CuppingSampleEditRow>(
+            r#"
+            SELECT e.id, e.sample_id, e.sample_number, e.action, e.changes, e.reason,
+                   e.edited_by, u.name AS edited_by_name, e.edited_at
+            FROM cupping_sample_edits e
+            LEFT JOIN users u ON u.id = e.edited_by
+            WHERE e.session_id = $1 AND e.sample_id = $2
+            ORDER BY e.edited_at DESC
+            "#,
+        )
+        .bind(session_id)
+        .bind(sample_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM cupping_samples WHERE id = $1 AND session_id = $2)",
+        )
+        .bind(sample_id)
+        .bind(session_id)
+        .fetch_one(&self.db)
+        .await?;
+        if rows.is_empty() && !exists {
+            return Err(AppError::NotFound("Cupping sample".to_string()));
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| CuppingSampleEdit {
+                id: row.id,
+                sample_id: row.sample_id,
+                sample_number: row.sample_number,
+                action: if row.action == "delete" { SampleEditAction::Delete } else { SampleEditAction::Update },
+                changes: row.changes.0,
+                reason: row.reason,
+                edited_by: row.edited_by,
+                edited_by_name: row.edited_by_name,
+                edited_at: row.edited_at,
+            })
+            .collect())
+    }
+
+    /// Get a cupping session with all samples; the lots of a blind session
+    /// stay hidden until it is revealed
     pub async fn get_session(
         &self,
         business_id: Uuid,
@@ -312,7 +1253,9 @@ impl CuppingService {
     ) -> AppResult<CuppingSession> {
         let session_row = sqlx::query_as::<_, CuppingSessionRow>(
             r#"
-            SELECT id, business_id, session_date, cupper_name, location, notes, notes_th, created_at, updated_at
+            SELECT id, business_id, session_date, cupper_name, location, notes, notes_th,
+                   session_type, is_blind, revealed_at, revealed_by,
+                   status, finalized_at, finalized_by, signed_by, created_at, updated_at
             FROM cupping_sessions
             WHERE id = $1 AND business_id = $2
             "#,
@@ -323,39 +1266,274 @@ impl CuppingService {
         .await?
         .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))?;
 
-        let sample_rows = sqlx::query_as::<_, CuppingSampleRow>(
+        let samples = self.session_samples(session_id).await?;
+
+        Ok(Self::row_to_session(session_row, samples).conceal())
+    }
+
+    /// Reveal the lots of a blind session, recording who revealed it
+    pub async fn reveal_session(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> AppResult<CuppingSession> {
+        let (blind, concealed) = self.validate_session_access(business_id, session_id).await?;
+        if !blind {
+            return Err(AppError::Validation {
+                field: "session_id".to_string(),
+                message: "Only blind sessions can be revealed".to_string(),
+                message_th: "เปิดเผยได้เฉพาะรอบการชิมแบบปิดตา".to_string(),
+            });
+        }
+        if !concealed {
+            return Err(AppError::Conflict {
+                resource: "cupping_session".to_string(),
+                message: "The session has already been revealed".to_string(),
+                message_th: "รอบการชิมนี้เปิดเผยแล้ว".to_string(),
+            });
+        }
+
+        sqlx::query(
             r#"
-            SELECT id, session_id, lot_id, sample_number,
-                   fragrance_aroma, flavor, aftertaste, acidity, body, balance,
-                   uniformity, clean_cup, sweetness, overall,
-                   total_score, tasting_notes, tasting_notes_th,
-                   defects_taint, defects_fault, final_score,
-                   created_at, updated_at
-            FROM cupping_samples
-            WHERE session_id = $1
-            ORDER BY sample_number
+            UPDATE cupping_sessions
+            SET revealed_at = NOW(), revealed_by = $2
+            WHERE id = $1 AND revealed_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+
+        self.get_session(business_id, session_id).await
+    }
+
+    /// Sign a session off: its samples, panel sheets and answers are locked
+    /// and its scores appear on the public traceability page
+    pub async fn finalize_session(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+        input: FinalizeCuppingSessionInput,
+    ) -> AppResult<CuppingSession> {
+        self.validate_session_access(business_id, session_id).await?;
+        check_finalize(self.session_status(session_id).await?)?;
+
+        let head_cupper = sqlx::query_scalar::<_, String>("SELECT cupper_name FROM cupping_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_one(&self.db)
+            .await?;
+        let panel_cuppers = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT cc.cupper_name
+            FROM cupping_cupper_scores cc
+            JOIN cupping_samples cs ON cs.id = cc.sample_id
+            WHERE cs.session_id = $1
             "#,
         )
         .bind(session_id)
         .fetch_all(&self.db)
         .await?;
+        let signed_by = signing_cupper(&head_cupper, &panel_cuppers, input.signed_by.as_deref()).ok_or_else(|| {
+            AppError::Validation {
+                field: "signed_by".to_string(),
+                message: "The session must be signed off by its cupper or a cupper on its panel".to_string(),
+                message_th: "ผู้รับรองต้องเป็นผู้ชิมของรอบนี้หรือผู้ชิมในคณะ".to_string(),
+            }
+        })?;
 
-        let samples: Vec<CuppingSample> = sample_rows
+        // Normalized scores are signed off with the session, so bring them
+        // up to date while it is still open
+        let mut tx = self.db.begin().await?;
+        CuppingAnalyticsService::renormalize(&mut tx, business_id).await?;
+        let finalized = sqlx::query(
+            r#"
+            UPDATE cupping_sessions
+            SET status = 'finalized', finalized_at = NOW(), finalized_by = $2, signed_by = $3, updated_at = NOW()
+            WHERE id = $1 AND status = 'in_progress'
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(&signed_by)
+        .execute(&mut *tx)
+        .await?;
+        if finalized.rows_affected() == 0 {
+            // Finalized by someone else since the check
+            drop(tx);
+            check_finalize(self.session_status(session_id).await?)?;
+        } else {
+            tx.commit().await?;
+        }
+
+        self.get_session(business_id, session_id).await
+    }
+
+    /// Triangle test of a session as served: cup codes per set, without
+    /// which cup is odd
+    pub async fn get_triangle_test(&self, business_id: Uuid, session_id: Uuid) -> AppResult<TriangleTest> {
+        let (control_lot_id, test_lot_id, significance_level) = self.triangle_test(business_id, session_id).await?;
+        let answers = self.triangle_answers(session_id).await?;
+        let sets = self
+            .triangle_sets(session_id)
+            .await?
             .into_iter()
-            .map(|r| self.row_to_sample(r))
+            .map(|set| TriangleSet {
+                answers: answers.iter().filter(|a| a.set_number == set.set_number).count() as i64,
+                set_number: set.set_number,
+                cup_codes: set.cup_codes,
+            })
             .collect();
 
-        Ok(CuppingSession {
-            id: session_row.id,
-            business_id: session_row.business_id,
-            session_date: session_row.session_date,
-            cupper_name: session_row.cupper_name,
-            location: session_row.location,
-            notes: session_row.notes,
-            notes_th: session_row.notes_th,
-            samples,
-            created_at: session_row.created_at,
-            updated_at: session_row.updated_at,
+        Ok(TriangleTest {
+            session_id,
+            control_lot_id,
+            test_lot_id,
+            significance_level,
+            sets,
+        })
+    }
+
+    /// Record which cup a taster picked as the odd one in a set
+    pub async fn record_triangle_answer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+        input: RecordTriangleAnswerInput,
+    ) -> AppResult<TriangleAnswer> {
+        self.triangle_test(business_id, session_id).await?;
+        self.ensure_open(session_id).await?;
+
+        let taster_name = input.taster_name.trim();
+        if taster_name.is_empty() {
+            return Err(AppError::Validation {
+                field: "taster_name".to_string(),
+                message: "Taster name is required".to_string(),
+                message_th: "ต้องระบุชื่อผู้ชิม".to_string(),
+            });
+        }
+
+        let set = self
+            .triangle_sets(session_id)
+            .await?
+            .into_iter()
+            .find(|set| set.set_number == input.set_number)
+            .ok_or_else(|| AppError::NotFound("Triangle set".to_string()))?;
+        let chosen_code = input.chosen_code.trim();
+        if !set.cup_codes.iter().any(|code| code == chosen_code) {
+            return Err(AppError::Validation {
+                field: "chosen_code".to_string(),
+                message: format!("Pick one of the set's cups: {}", set.cup_codes.join(", ")),
+                message_th: format!("กรุณาเลือกถ้วยในชุดนี้: {}", set.cup_codes.join(", ")),
+            });
+        }
+        let correct = set.cup_codes[(set.odd_position - 1) as usize] == chosen_code;
+
+        let answer = sqlx::query_as::<_, TriangleAnswer>(
+            r#"
+            INSERT INTO cupping_triangle_answers
+                (session_id, set_id, taster_name, chosen_code, correct, comments, recorded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (set_id, LOWER(taster_name)) DO NOTHING
+            RETURNING id, $8::INTEGER AS set_number, taster_name, chosen_code, correct, comments,
+                      recorded_by, created_at
+            "#,
+        )
+        .bind(session_id)
+        .bind(set.id)
+        .bind(taster_name)
+        .bind(chosen_code)
+        .bind(correct)
+        .bind(input.comments.and_then(clean_notes))
+        .bind(user_id)
+        .bind(set.set_number)
+        .fetch_optional(&self.db)
+        .await?;
+        if answer.is_some() {
+            self.mark_in_progress(session_id).await?;
+        }
+
+        answer.ok_or_else(|| AppError::Conflict {
+            resource: "triangle_answer".to_string(),
+            message: format!("{} has already answered set {}", taster_name, set.set_number),
+            message_th: format!("{} ตอบชุดที่ {} แล้ว", taster_name, set.set_number),
+        })
+    }
+
+    /// Remove a taster's answer, e.g. one recorded on the wrong set
+    pub async fn delete_triangle_answer(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        answer_id: Uuid,
+    ) -> AppResult<()> {
+        self.triangle_test(business_id, session_id).await?;
+        self.ensure_open(session_id).await?;
+
+        let deleted = sqlx::query("DELETE FROM cupping_triangle_answers WHERE id = $1 AND session_id = $2")
+            .bind(answer_id)
+            .bind(session_id)
+            .execute(&self.db)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(AppError::NotFound("Triangle answer".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Answer key, answers and significance of a triangle session
+    pub async fn triangle_results(&self, business_id: Uuid, session_id: Uuid) -> AppResult<TriangleResults> {
+        let (control_lot_id, test_lot_id, significance_level) = self.triangle_test(business_id, session_id).await?;
+        let answers = self.triangle_answers(session_id).await?;
+
+        let sets = self
+            .triangle_sets(session_id)
+            .await?
+            .into_iter()
+            .map(|set| {
+                let set_answers: Vec<&TriangleAnswer> =
+                    answers.iter().filter(|a| a.set_number == set.set_number).collect();
+                TriangleSetResult {
+                    set_number: set.set_number,
+                    odd_code: set.cup_codes[(set.odd_position - 1) as usize].clone(),
+                    cup_codes: set.cup_codes,
+                    odd_is_test: set.odd_is_test,
+                    answers: set_answers.len() as i64,
+                    correct: set_answers.iter().filter(|a| a.correct).count() as i64,
+                }
+            })
+            .collect();
+
+        let mut tasters: Vec<TasterTriangleResult> = Vec::new();
+        for answer in &answers {
+            let key = answer.taster_name.to_lowercase();
+            match tasters.iter_mut().find(|t| t.taster_name.to_lowercase() == key) {
+                Some(taster) => {
+                    taster.answers += 1;
+                    taster.correct += answer.correct as i64;
+                }
+                None => tasters.push(TasterTriangleResult {
+                    taster_name: answer.taster_name.clone(),
+                    answers: 1,
+                    correct: answer.correct as i64,
+                }),
+            }
+        }
+        tasters.sort_by_key(|t| t.taster_name.to_lowercase());
+
+        let correct = answers.iter().filter(|a| a.correct).count() as i64;
+        Ok(TriangleResults {
+            session_id,
+            control_lot_id,
+            test_lot_id,
+            summary: triangle_summary(correct, answers.len() as i64, significance_level),
+            sets,
+            tasters,
+            answers,
         })
     }
 
@@ -363,7 +1541,9 @@ impl CuppingService {
     pub async fn list_sessions(&self, business_id: Uuid) -> AppResult<Vec<CuppingSession>> {
         let session_rows = sqlx::query_as::<_, CuppingSessionRow>(
             r#"
-            SELECT id, business_id, session_date, cupper_name, location, notes, notes_th, created_at, updated_at
+            SELECT id, business_id, session_date, cupper_name, location, notes, notes_th,
+                   session_type, is_blind, revealed_at, revealed_by,
+                   status, finalized_at, finalized_by, signed_by, created_at, updated_at
             FROM cupping_sessions
             WHERE business_id = $1
             ORDER BY session_date DESC, created_at DESC
@@ -377,11 +1557,12 @@ impl CuppingService {
         for row in session_rows {
             let sample_rows = sqlx::query_as::<_, CuppingSampleRow>(
                 r#"
-                SELECT id, session_id, lot_id, sample_number,
+                SELECT id, session_id, lot_id, sample_number, blind_code,
                        fragrance_aroma, flavor, aftertaste, acidity, body, balance,
                        uniformity, clean_cup, sweetness, overall,
-                       total_score, tasting_notes, tasting_notes_th,
-                       defects_taint, defects_fault, final_score,
+                       total_score, tasting_notes, tasting_notes_th, flavor_descriptors,
+                       defects_taint, defects_fault, final_score, normalized_score, roast_session_id,
+                       tds_percent, extraction_percent, water_activity, roast_date,
                        created_at, updated_at
                 FROM cupping_samples
                 WHERE session_id = $1
@@ -397,24 +1578,14 @@ impl CuppingService {
                 .map(|r| self.row_to_sample(r))
                 .collect();
 
-            sessions.push(CuppingSession {
-                id: row.id,
-                business_id: row.business_id,
-                session_date: row.session_date,
-                cupper_name: row.cupper_name,
-                location: row.location,
-                notes: row.notes,
-                notes_th: row.notes_th,
-                samples,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            });
+            sessions.push(Self::row_to_session(row, samples).conceal());
         }
 
         Ok(sessions)
     }
 
-    /// Get cupping history for a lot
+    /// Get cupping history for a lot; samples of blind sessions count once
+    /// the session is revealed
     pub async fn get_lot_cupping_history(
         &self,
         business_id: Uuid,
@@ -422,15 +1593,17 @@ impl CuppingService {
     ) -> AppResult<Vec<CuppingSample>> {
         let rows = sqlx::query_as::<_, CuppingSampleRow>(
             r#"
-            SELECT cs.id, cs.session_id, cs.lot_id, cs.sample_number,
+            SELECT cs.id, cs.session_id, cs.lot_id, cs.sample_number, cs.blind_code,
                    cs.fragrance_aroma, cs.flavor, cs.aftertaste, cs.acidity, cs.body, cs.balance,
                    cs.uniformity, cs.clean_cup, cs.sweetness, cs.overall,
-                   cs.total_score, cs.tasting_notes, cs.tasting_notes_th,
-                   cs.defects_taint, cs.defects_fault, cs.final_score,
+                   cs.total_score, cs.tasting_notes, cs.tasting_notes_th, cs.flavor_descriptors,
+                   cs.defects_taint, cs.defects_fault, cs.final_score, cs.normalized_score, cs.roast_session_id,
+                   cs.tds_percent, cs.extraction_percent, cs.water_activity, cs.roast_date,
                    cs.created_at, cs.updated_at
             FROM cupping_samples cs
             JOIN cupping_sessions s ON s.id = cs.session_id
             WHERE cs.lot_id = $1 AND s.business_id = $2
+              AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)
             ORDER BY s.session_date DESC, cs.created_at DESC
             "#,
         )
@@ -479,16 +1652,7 @@ impl CuppingService {
 
     /// Calculate total cupping score from individual scores
     pub fn calculate_total_score(scores: &CuppingScores) -> Decimal {
-        scores.fragrance_aroma
-            + scores.flavor
-            + scores.aftertaste
-            + scores.acidity
-            + scores.body
-            + scores.balance
-            + scores.uniformity
-            + scores.clean_cup
-            + scores.sweetness
-            + scores.overall
+        scores.total()
     }
 
     /// Classify coffee based on final cupping score
@@ -505,7 +1669,7 @@ impl CuppingService {
     }
 
     /// Validate cupping scores are within valid ranges
-    fn validate_scores(&self, scores: &CuppingScores) -> AppResult<()> {
+    pub(crate) fn validate_scores(scores: &CuppingScores) -> AppResult<()> {
         let min = Decimal::from(0);
         let max_standard = Decimal::from(10);
 
@@ -550,27 +1714,275 @@ impl CuppingService {
         Ok(())
     }
 
-    /// Validate session access
+    /// Samples of a session in sample order
+    async fn session_samples(&self, session_id: Uuid) -> AppResult<Vec<CuppingSample>> {
+        let sample_rows = sqlx::query_as::<_, CuppingSampleRow>(
+            r#"
+            SELECT id, session_id, lot_id, sample_number, blind_code,
+                   fragrance_aroma, flavor, aftertaste, acidity, body, balance,
+                   uniformity, clean_cup, sweetness, overall,
+                   total_score, tasting_notes, tasting_notes_th, flavor_descriptors,
+                   defects_taint, defects_fault, final_score, normalized_score, roast_session_id,
+                   tds_percent, extraction_percent, water_activity, roast_date,
+                   created_at, updated_at
+            FROM cupping_samples
+            WHERE session_id = $1
+            ORDER BY sample_number
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(sample_rows
+            .into_iter()
+            .map(|r| self.row_to_sample(r))
+            .collect())
+    }
+
+    /// Add an entry to a sample's edit history
+    async fn record_edit(
+        tx: &mut Transaction<'_, Postgres>,
+        sample: &CuppingSample,
+        action: SampleEditAction,
+        changes: &[SampleFieldChange],
+        reason: Option<String>,
+        user_id: Uuid,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cupping_sample_edits (session_id, sample_id, sample_number, action, changes, reason, edited_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(sample.session_id)
+        .bind(sample.id)
+        .bind(sample.sample_number)
+        .bind(action.as_str())
+        .bind(sqlx::types::Json(changes))
+        .bind(reason.and_then(clean_notes))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Duplicate sample checks configured for a business
+    async fn duplicate_settings(&self, business_id: Uuid) -> AppResult<DuplicateSampleSettings> {
+        let (policy, flag_identical_scores) = sqlx::query_as::<_, (String, bool)>(
+            "SELECT cupping_duplicate_lot_policy, cupping_flag_identical_scores FROM businesses WHERE id = $1",
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .unwrap_or_else(|| (DuplicateLotPolicy::default().as_str().to_string(), true));
+
+        Ok(DuplicateSampleSettings {
+            lot_policy: DuplicateLotPolicy::from_str(&policy).unwrap_or_default(),
+            flag_identical_scores,
+        })
+    }
+
+    /// Validate session access; returns whether the session is blind and
+    /// whether its lots are still hidden
     async fn validate_session_access(
         &self,
         business_id: Uuid,
         session_id: Uuid,
-    ) -> AppResult<()> {
-        let exists = sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(SELECT 1 FROM cupping_sessions WHERE id = $1 AND business_id = $2)",
+    ) -> AppResult<(bool, bool)> {
+        sqlx::query_as::<_, (bool, bool)>(
+            r#"
+            SELECT is_blind, is_blind AND revealed_at IS NULL
+            FROM cupping_sessions
+            WHERE id = $1 AND business_id = $2
+            "#,
         )
         .bind(session_id)
         .bind(business_id)
-        .fetch_one(&self.db)
-        .await?;
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))
+    }
 
-        if !exists {
-            return Err(AppError::NotFound("Cupping session".to_string()));
+    /// Date a session was cupped on
+    async fn session_date(&self, session_id: Uuid) -> AppResult<NaiveDate> {
+        sqlx::query_scalar::<_, NaiveDate>("SELECT session_date FROM cupping_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))
+    }
+
+    /// Lifecycle status of a session
+    async fn session_status(&self, session_id: Uuid) -> AppResult<CuppingSessionStatus> {
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM cupping_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))?;
+        Ok(CuppingSessionStatus::from_str(&status).unwrap_or_default())
+    }
+
+    /// Refuse changes to a finalized session
+    pub(crate) async fn ensure_open(&self, session_id: Uuid) -> AppResult<()> {
+        ensure_session_open(self.session_status(session_id).await?)
+    }
+
+    /// Move a draft session on once something is recorded in it
+    async fn mark_in_progress(&self, session_id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE cupping_sessions SET status = $2 WHERE id = $1 AND status = $3")
+            .bind(session_id)
+            .bind(CuppingSessionStatus::InProgress.as_str())
+            .bind(CuppingSessionStatus::Draft.as_str())
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Kind of a session of the business
+    async fn session_type(&self, business_id: Uuid, session_id: Uuid) -> AppResult<CuppingSessionType> {
+        let session_type = sqlx::query_scalar::<_, String>(
+            "SELECT session_type FROM cupping_sessions WHERE id = $1 AND business_id = $2",
+        )
+        .bind(session_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))?;
+        Ok(CuppingSessionType::from_str(&session_type).unwrap_or_default())
+    }
+
+    /// Validate a new triangle test; returns its significance level
+    async fn validate_triangle_test(&self, business_id: Uuid, input: &CreateTriangleTestInput) -> AppResult<Decimal> {
+        if input.control_lot_id == input.test_lot_id {
+            return Err(AppError::Validation {
+                field: "triangle.test_lot_id".to_string(),
+                message: "The test lot must differ from the control lot".to_string(),
+                message_th: "ล็อตทดสอบต้องไม่ใช่ล็อตเดียวกับล็อตควบคุม".to_string(),
+            });
+        }
+        self.validate_lot_access(business_id, input.control_lot_id).await?;
+        self.validate_lot_access(business_id, input.test_lot_id).await?;
+
+        if !(1..=MAX_TRIANGLE_SETS).contains(&input.sets) {
+            return Err(AppError::Validation {
+                field: "triangle.sets".to_string(),
+                message: format!("Sets must be between 1 and {}", MAX_TRIANGLE_SETS),
+                message_th: format!("จำนวนชุดต้องอยู่ระหว่าง 1 ถึง {}", MAX_TRIANGLE_SETS),
+            });
+        }
+
+        let significance_level = input.significance_level.unwrap_or(DEFAULT_SIGNIFICANCE_LEVEL);
+        if significance_level <= Decimal::ZERO || significance_level > Decimal::new(2, 1) {
+            return Err(AppError::Validation {
+                field: "triangle.significance_level".to_string(),
+                message: "Significance level must be above 0 and at most 0.2".to_string(),
+                message_th: "ระดับนัยสำคัญต้องมากกว่า 0 และไม่เกิน 0.2".to_string(),
+            });
         }
+        Ok(significance_level)
+    }
 
+    /// Store a triangle test and its sets, each cup with its own code
+    async fn create_triangle_sets(
+        tx: &mut Transaction<'_, Postgres>,
+        session_id: Uuid,
+        input: &CreateTriangleTestInput,
+        significance_level: Decimal,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cupping_triangle_tests (session_id, control_lot_id, test_lot_id, significance_level)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(session_id)
+        .bind(input.control_lot_id)
+        .bind(input.test_lot_id)
+        .bind(significance_level)
+        .execute(&mut **tx)
+        .await?;
+
+        let mut used = HashSet::new();
+        for set_number in 1..=input.sets {
+            let arrangement = triangle_arrangement(set_number);
+            let mut cup_codes = Vec::with_capacity(TRIANGLE_CUPS);
+            for _ in 0..TRIANGLE_CUPS {
+                // At most 180 cups, well within the 900 codes
+                let code = next_blind_code(&used, Uuid::new_v4().as_u128() as u64)
+                    .expect("triangle sessions use fewer cups than there are codes");
+                used.insert(code.clone());
+                cup_codes.push(code);
+            }
+            sqlx::query(
+                r#"
+                INSERT INTO cupping_triangle_sets (session_id, set_number, cup_codes, odd_position, odd_is_test)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(session_id)
+            .bind(set_number)
+            .bind(&cup_codes)
+            .bind(arrangement.odd_position)
+            .bind(arrangement.odd_is_test)
+            .execute(&mut **tx)
+            .await?;
+        }
         Ok(())
     }
 
+    /// Lots and significance level of a session's triangle test
+    async fn triangle_test(&self, business_id: Uuid, session_id: Uuid) -> AppResult<(Uuid, Uuid, Decimal)> {
+        if self.session_type(business_id, session_id).await? != CuppingSessionType::Triangle {
+            return Err(AppError::Validation {
+                field: "session_id".to_string(),
+                message: "Not a triangle session".to_string(),
+                message_th: "รอบการชิมนี้ไม่ใช่การทดสอบสามถ้วย".to_string(),
+            });
+        }
+        sqlx::query_as::<_, (Uuid, Uuid, Decimal)>(
+            "SELECT control_lot_id, test_lot_id, significance_level FROM cupping_triangle_tests WHERE session_id = $1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Triangle test".to_string()))
+    }
+
+    /// Sets of a triangle session in serving order
+    async fn triangle_sets(&self, session_id: Uuid) -> AppResult<Vec<TriangleSetRow>> {
+        let sets = sqlx::query_as::<_, TriangleSetRow>(
+            r#"
+            SELECT id, set_number, cup_codes, odd_position, odd_is_test
+            FROM cupping_triangle_sets
+            WHERE session_id = $1
+            ORDER BY set_number
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(sets)
+    }
+
+    /// Answers of a triangle session by set, then taster
+    async fn triangle_answers(&self, session_id: Uuid) -> AppResult<Vec<TriangleAnswer>> {
+        let answers = sqlx::query_as::<_, TriangleAnswer>(
+            r#"
+            SELECT a.id, s.set_number, a.taster_name, a.chosen_code, a.correct, a.comments,
+                   a.recorded_by, a.created_at
+            FROM cupping_triangle_answers a
+            JOIN cupping_triangle_sets s ON s.id = a.set_id
+            WHERE a.session_id = $1
+            ORDER BY s.set_number, LOWER(a.taster_name)
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(answers)
+    }
+
     /// Validate lot access
     async fn validate_lot_access(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<()> {
         let exists = sqlx::query_scalar::<_, bool>(
@@ -588,6 +2000,43 @@ impl CuppingService {
         Ok(())
     }
 
+    /// Validate that a roast session belongs to the business and roasted
+    /// the sample's lot
+    async fn validate_roast_session(&self, business_id: Uuid, roast_session_id: Option<Uuid>, lot_id: Uuid) -> AppResult<()> {
+        let roast = match roast_session_id {
+            Some(id) => sqlx::query_as::<_, SampleRoast>("SELECT business_id, lot_id FROM roast_sessions WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.db)
+                .await?,
+            None => None,
+        };
+        validate_sample_roast(roast_session_id, roast, business_id, lot_id)
+    }
+
+    /// Convert database row to CuppingSession
+    fn row_to_session(row: CuppingSessionRow, samples: Vec<CuppingSample>) -> CuppingSession {
+        CuppingSession {
+            id: row.id,
+            business_id: row.business_id,
+            session_date: row.session_date,
+            cupper_name: row.cupper_name,
+            location: row.location,
+            notes: row.notes,
+            notes_th: row.notes_th,
+            session_type: CuppingSessionType::from_str(&row.session_type).unwrap_or_default(),
+            blind: row.is_blind,
+            revealed_at: row.revealed_at,
+            revealed_by: row.revealed_by,
+            status: CuppingSessionStatus::from_str(&row.status).unwrap_or_default(),
+            finalized_at: row.finalized_at,
+            finalized_by: row.finalized_by,
+            signed_by: row.signed_by,
+            samples,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+
     /// Convert database row to CuppingSample
     fn row_to_sample(&self, row: CuppingSampleRow) -> CuppingSample {
         let scores = CuppingScores {
@@ -613,15 +2062,30 @@ impl CuppingService {
         CuppingSample {
             id: row.id,
             session_id: row.session_id,
-            lot_id: row.lot_id,
+            lot_id: Some(row.lot_id),
             sample_number: row.sample_number,
+            blind_code: row.blind_code,
             scores,
             total_score: row.total_score,
             tasting_notes: row.tasting_notes,
             tasting_notes_th: row.tasting_notes_th,
+            flavor_descriptors: row
+                .flavor_descriptors
+                .iter()
+                .filter_map(|code| flavor_descriptor(code))
+                .map(FlavorDescriptor::entry)
+                .collect(),
             defects,
             final_score: row.final_score,
+            normalized_score: row.normalized_score,
             classification,
+            roast_session_id: row.roast_session_id,
+            measurements: BrewMeasurements {
+                tds_percent: row.tds_percent,
+                extraction_percent: row.extraction_percent,
+                water_activity: row.water_activity,
+                roast_date: row.roast_date,
+            },
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
@@ -0,0 +1,533 @@
+//! Cupping analytics across sessions
+//!
+//! Cupper bias: for every lot cupped by more than one cupper, each cupper's
+//! mean score on the lot is compared with the mean of the other cuppers'
+//! means (leave-one-out, so a cupper never pulls the reference towards
+//! themselves). A cupper's bias is the average of those deviations over the
+//! lots they share with the panel. Normalized scores subtract the cupper's
+//! bias from the final score, making sessions by different cuppers
+//! comparable. Cuppers without enough shared lots get no bias and samples
+//! keep no normalized score. Panel samples count each cupper's own score
+//! sheet, and are normalized with the mean of their cuppers' normalized
+//! scores once every cupper on the panel has a bias.
+//!
+//! Attribute analytics: statistics of each SCA attribute over the samples of
+//! a period, grouped by any of lot, plot, variety and processing method. A
+//! lot harvested from several plots or varieties counts towards each of
+//! them; its process is the latest processing method. Samples of blind
+//! sessions count once the session is revealed.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::CuppingScores;
+use crate::services::cupping_panel::{attribute_scores, attribute_stats, AttributeStats};
+use crate::services::reporting_period::resolve_date_range;
+use crate::services::BusinessService;
+
+/// Shared lots needed before a cupper's bias is trusted
+pub const DEFAULT_MIN_SHARED_LOTS: usize = 3;
+
+/// Every scored sample of a business, a row per cupper on panel samples
+const SCORED_SAMPLES_SQL: &str = r#"
+    SELECT cs.id AS sample_id, COALESCE(cc.cupper_name, s.cupper_name) AS cupper_name,
+           cs.lot_id, COALESCE(cc.final_score, cs.final_score) AS final_score
+    FROM cupping_samples cs
+    JOIN cupping_sessions s ON s.id = cs.session_id
+    LEFT JOIN cupping_cupper_scores cc ON cc.sample_id = cs.id
+    WHERE s.business_id = $1
+"#;
+
+/// A scored sample with the cupper who scored it
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScoredSample {
+    pub sample_id: Uuid,
+    pub cupper_name: String,
+    pub lot_id: Uuid,
+    pub final_score: Decimal,
+}
+
+/// Scoring bias of one cupper relative to the panel
+#[derive(Debug, Clone, Serialize)]
+pub struct CupperBias {
+    pub cupper_name: String,
+    pub sample_count: usize,
+    pub mean_score: Decimal,
+    /// Lots also cupped by at least one other cupper
+    pub shared_lots: usize,
+    /// Average points above (+) or below (-) the other cuppers; `None` when
+    /// there are fewer shared lots than required
+    pub bias: Option<Decimal>,
+}
+
+/// Query parameters for the cupper bias report
+#[derive(Debug, Deserialize)]
+pub struct CupperBiasQuery {
+    pub min_shared_lots: Option<usize>,
+}
+
+/// Dimension cupping attribute analytics can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeGroupBy {
+    Lot,
+    Plot,
+    Variety,
+    Process,
+}
+
+impl AttributeGroupBy {
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "lot" => Some(AttributeGroupBy::Lot),
+            "plot" => Some(AttributeGroupBy::Plot),
+            "variety" => Some(AttributeGroupBy::Variety),
+            "process" => Some(AttributeGroupBy::Process),
+            _ => None,
+        }
+    }
+}
+
+/// Query parameters for cupping attribute analytics
+#[derive(Debug, Default, Deserialize)]
+pub struct CuppingAttributeQuery {
+    /// Comma-separated dimensions: lot, plot, variety, process (default lot)
+    pub group_by: Option<String>,
+    /// Crop season by the year it starts in; overrides `from` and `to`
+    pub season: Option<i32>,
+    /// Report period such as `season:2024/25` or `quarter:2024-Q2`;
+    /// overrides `season`, `from` and `to`
+    pub period: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only lots harvested from this variety
+    pub variety: Option<String>,
+    /// Only lots last processed with this method
+    pub process: Option<String>,
+}
+
+/// A sample with the lot attributes it is grouped by
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AttributeSample {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub lot_name: String,
+    pub plot_ids: Vec<Uuid>,
+    pub varieties: Vec<String>,
+    pub process: Option<String>,
+    pub fragrance_aroma: Decimal,
+    pub flavor: Decimal,
+    pub aftertaste: Decimal,
+    pub acidity: Decimal,
+    pub body: Decimal,
+    pub balance: Decimal,
+    pub uniformity: Decimal,
+    pub clean_cup: Decimal,
+    pub sweetness: Decimal,
+    pub overall: Decimal,
+    pub final_score: Decimal,
+}
+
+impl AttributeSample {
+    fn scores(&self) -> CuppingScores {
+        CuppingScores {
+            fragrance_aroma: self.fragrance_aroma,
+            flavor: self.flavor,
+            aftertaste: self.aftertaste,
+            acidity: self.acidity,
+            body: self.body,
+            balance: self.balance,
+            uniformity: self.uniformity,
+            clean_cup: self.clean_cup,
+            sweetness: self.sweetness,
+            overall: self.overall,
+        }
+    }
+}
+
+/// Attribute statistics of one group; only the grouped dimensions are set
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeGroup {
+    pub lot_id: Option<Uuid>,
+    pub traceability_code: Option<String>,
+    pub lot_name: Option<String>,
+    pub plot_id: Option<Uuid>,
+    pub plot_name: Option<String>,
+    pub variety: Option<String>,
+    pub process: Option<String>,
+    pub sample_count: usize,
+    pub lot_count: usize,
+    pub final_score: AttributeStats,
+    pub attributes: Vec<AttributeStats>,
+}
+
+/// Cupping attribute analytics of a period
+#[derive(Debug, Serialize)]
+pub struct CuppingAttributeAnalytics {
+    pub group_by: Vec<AttributeGroupBy>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub groups: Vec<AttributeGroup>,
+}
+
+/// Dimensions in `group_by`, deduplicated in lot, plot, variety, process
+/// order; lot when empty
+pub fn parse_group_by(group_by: Option<&str>) -> AppResult<Vec<AttributeGroupBy>> {
+    let mut dimensions = BTreeSet::new();
+    for part in group_by.unwrap_or_default().split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let dimension = AttributeGroupBy::from_str(&part.to_lowercase()).ok_or_else(|| AppError::Validation {
+            field: "group_by".to_string(),
+            message: format!("Unknown group_by '{}'; use lot, plot, variety or process", part),
+            message_th: format!("ไม่รู้จัก group_by '{}' ใช้ lot, plot, variety หรือ process", part),
+        })?;
+        dimensions.insert(dimension);
+    }
+    if dimensions.is_empty() {
+        dimensions.insert(AttributeGroupBy::Lot);
+    }
+    Ok(dimensions.into_iter().collect())
+}
+
+/// Key of a sample's group for each plot and variety combination it counts
+/// towards
+type AttributeGroupKey = (Option<Uuid>, Option<Uuid>, Option<String>, Option<String>);
+
+fn group_keys(sample: &AttributeSample, group_by: &[AttributeGroupBy]) -> Vec<AttributeGroupKey> {
+    let by = |dimension| group_by.contains(&dimension);
+    let lot = by(AttributeGroupBy::Lot).then_some(sample.lot_id);
+    let process = if by(AttributeGroupBy::Process) { sample.process.clone() } else { None };
+    let plots: Vec<Option<Uuid>> = if by(AttributeGroupBy::Plot) && !sample.plot_ids.is_empty() {
+        sample.plot_ids.iter().copied().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    let varieties: Vec<Option<String>> = if by(AttributeGroupBy::Variety) && !sample.varieties.is_empty() {
+        sample.varieties.iter().cloned().map(Some).collect()
+    } else {
+        vec![None]
+    };
+
+    plots
+        .iter()
+        .flat_map(|plot| {
+            varieties
+                .iter()
+                .map(|variety| (lot, *plot, variety.clone(), process.clone()))
+        })
+        .collect()
+}
+
+/// Attribute statistics per group, best mean final score first
+pub fn attribute_groups(
+    samples: &[AttributeSample],
+    group_by: &[AttributeGroupBy],
+    plot_names: &HashMap<Uuid, String>,
+) -> Vec<AttributeGroup> {
+    let mut groups: BTreeMap<AttributeGroupKey, Vec<&AttributeSample>> = BTreeMap::new();
+    for sample in samples {
+        for key in group_keys(sample, group_by) {
+            groups.entry(key).or_default().push(sample);
+        }
+    }
+
+    let mut groups: Vec<AttributeGroup> = groups
+        .into_iter()
+        .map(|((lot_id, plot_id, variety, process), samples)| {
+            let lot = lot_id.and_then(|_| samples.first());
+            let scores: Vec<[(&'static str, Decimal); 10]> =
+                samples.iter().map(|s| attribute_scores(&s.scores())).collect();
+            let attributes = (0..10)
+                .map(|i| {
+                    let values: Vec<Decimal> = scores.iter().map(|s| s[i].1).collect();
+                    attribute_stats(scores[0][i].0, &values)
+                })
+                .collect();
+            let finals: Vec<Decimal> = samples.iter().map(|s| s.final_score).collect();
+            let lots: BTreeSet<Uuid> = samples.iter().map(|s| s.lot_id).collect();
+
+            AttributeGroup {
+                lot_id,
+                traceability_code: lot.map(|s| s.traceability_code.clone()),
+                lot_name: lot.map(|s| s.lot_name.clone()),
+                plot_id,
+                plot_name: plot_id.and_then(|id| plot_names.get(&id).cloned()),
+                variety,
+                process,
+                sample_count: samples.len(),
+                lot_count: lots.len(),
+                final_score: attribute_stats("final_score", &finals),
+                attributes,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.final_score.mean));
+    groups
+}
+
+/// Cupper names are free text; group them case- and whitespace-insensitively
+pub(crate) fn cupper_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    values.iter().sum::<Decimal>() / Decimal::from(values.len())
+}
+
+/// Compute each cupper's bias; cuppers are returned by name
+pub fn cupper_biases(samples: &[ScoredSample], min_shared_lots: usize) -> Vec<CupperBias> {
+    // cupper -> lot -> scores
+    let mut scores: BTreeMap<String, BTreeMap<Uuid, Vec<Decimal>>> = BTreeMap::new();
+    let mut names: HashMap<String, &str> = HashMap::new();
+    for sample in samples {
+        let key = cupper_key(&sample.cupper_name);
+        names.entry(key.clone()).or_insert(sample.cupper_name.trim());
+        scores
+            .entry(key)
+            .or_default()
+            .entry(sample.lot_id)
+            .or_default()
+            .push(sample.final_score);
+    }
+
+    // lot -> [(cupper, mean score of that cupper on the lot)]
+    let mut lot_means: HashMap<Uuid, Vec<(&str, Decimal)>> = HashMap::new();
+    for (cupper, lots) in &scores {
+        for (lot_id, lot_scores) in lots {
+            lot_means.entry(*lot_id).or_default().push((cupper, mean(lot_scores)));
+        }
+    }
+
+    scores
+        .iter()
+        .map(|(cupper, lots)| {
+            let all_scores: Vec<Decimal> = lots.values().flatten().copied().collect();
+            let deviations: Vec<Decimal> = lots
+                .keys()
+                .filter_map(|lot_id| {
+                    let panel = &lot_means[lot_id];
+                    let own = panel.iter().find(|(c, _)| c == cupper)?.1;
+                    let others: Vec<Decimal> = panel
+                        .iter()
+                        .filter(|(c, _)| c != cupper)
+                        .map(|(_, m)| *m)
+                        .collect();
+                    (!others.is_empty()).then(|| own - mean(&others))
+                })
+                .collect();
+
+            CupperBias {
+                cupper_name: names[cupper].to_string(),
+                sample_count: all_scores.len(),
+                mean_score: mean(&all_scores).round_dp(2),
+                shared_lots: deviations.len(),
+                bias: (!deviations.is_empty() && deviations.len() >= min_shared_lots)
+                    .then(|| mean(&deviations).round_dp(2)),
+            }
+        })
+        .collect()
+}
+
+/// Final score with the cupper's bias removed, kept within 0-100
+pub fn normalized_score(final_score: Decimal, bias: Decimal) -> Decimal {
+    (final_score - bias)
+        .round_dp(2)
+        .clamp(Decimal::ZERO, Decimal::ONE_HUNDRED)
+}
+
+/// Normalized score of a sample from its cuppers' normalized scores: their
+/// mean, or `None` while any cupper on the sample has no bias
+pub fn sample_normalized_score(normalized: &[Option<Decimal>]) -> Option<Decimal> {
+    let scores: Option<Vec<Decimal>> = normalized.iter().copied().collect();
+    scores
+        .filter(|s| !s.is_empty())
+        .map(|s| mean(&s).round_dp(2))
+}
+
+/// Cupping analytics service
+#[derive(Clone)]
+pub struct CuppingAnalyticsService {
+    db: PgPool,
+}
+
+impl CuppingAnalyticsService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    async fn scored_samples(&self, business_id: Uuid) -> AppResult<Vec<ScoredSample>> {
+        let samples = sqlx::query_as::<_, ScoredSample>(SCORED_SAMPLES_SQL)
+            .bind(business_id)
+            .fetch_all(&self.db)
+            .await?;
+
+        Ok(samples)
+    }
+
+    /// Attribute statistics of the business's samples per group
+    pub async fn attribute_analytics(
+        &self,
+        business_id: Uuid,
+        query: &CuppingAttributeQuery,
+    ) -> AppResult<CuppingAttributeAnalytics> {
+        let group_by = parse_group_by(query.group_by.as_deref())?;
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let (from, to) = match (query.period.as_deref(), query.season) {
+            (None, Some(season)) => calendar.season_bounds(season).map(|(from, to)| (Some(from), Some(to)))?,
+            (period, _) => resolve_date_range(period, query.from, query.to, &calendar, Utc::now().date_naive())?,
+        };
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(AppError::Validation {
+                    field: "from".to_string(),
+                    message: "Start date must not be after end date".to_string(),
+                    message_th: "วันที่เริ่มต้องไม่อยู่หลังวันที่สิ้นสุด".to_string(),
+                });
+            }
+        }
+
+        let samples = sqlx::query_as::<_, AttributeSample>(
+            r#"
+            SELECT l.id AS lot_id, l.traceability_code, l.name AS lot_name,
+                   ARRAY(SELECT DISTINCT h.plot_id FROM harvests h WHERE h.lot_id = l.id) AS plot_ids,
+                   ARRAY(SELECT DISTINCT pv.variety::TEXT
+                         FROM harvests h JOIN plot_varieties pv ON pv.plot_id = h.plot_id
+                         WHERE h.lot_id = l.id) AS varieties,
+                   (SELECT pr.method FROM processing_records pr
+                    WHERE pr.lot_id = l.id
+                    ORDER BY pr.start_date DESC LIMIT 1) AS process,
+                   cs.fragrance_aroma, cs.flavor, cs.aftertaste, cs.acidity, cs.body, cs.balance,
+                   cs.uniformity, cs.clean_cup, cs.sweetness, cs.overall, cs.final_score
+            FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            JOIN lots l ON l.id = cs.lot_id
+            WHERE s.business_id = $1
+              AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)
+              AND ($2::DATE IS NULL OR s.session_date >= $2)
+              AND ($3::DATE IS NULL OR s.session_date <= $3)
+            "#,
+        )
+        .bind(business_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db)
+        .await?;
+
+        let variety = query.variety.as_deref().map(str::trim).filter(|v| !v.is_empty());
+        let process = query.process.as_deref().map(str::trim).filter(|p| !p.is_empty());
+        let samples: Vec<AttributeSample> = samples
+            .into_iter()
+            .filter(|s| variety.is_none_or(|v| s.varieties.iter().any(|sv| sv.eq_ignore_ascii_case(v))))
+            .filter(|s| process.is_none_or(|p| s.process.as_deref().is_some_and(|sp| sp.eq_ignore_ascii_case(p))))
+            .collect();
+
+        let plot_names: HashMap<Uuid, String> = if group_by.contains(&AttributeGroupBy::Plot) {
+            sqlx::query_as::<_, (Uuid, String)>("SELECT id, name FROM plots WHERE business_id = $1")
+                .bind(business_id)
+                .fetch_all(&self.db)
+                .await?
+                .into_iter()
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        Ok(CuppingAttributeAnalytics {
+            groups: attribute_groups(&samples, &group_by, &plot_names),
+            group_by,
+            from,
+            to,
+        })
+    }
+
+    /// Per-cupper bias relative to the panel
+    pub async fn get_cupper_biases(
+        &self,
+        business_id: Uuid,
+        min_shared_lots: usize,
+    ) -> AppResult<Vec<CupperBias>> {
+        let samples = self.scored_samples(business_id).await?;
+        Ok(cupper_biases(&samples, min_shared_lots))
+    }
+
+    /// Recompute `normalized_score` on the samples of the business's open
+    /// sessions; returns the number of samples with a normalized score
+    pub async fn refresh_normalized_scores(&self, business_id: Uuid) -> AppResult<u64> {
+        let mut tx = self.db.begin().await?;
+        let normalized = Self::renormalize(&mut tx, business_id).await?;
+        tx.commit().await?;
+        Ok(normalized)
+    }
+
+    /// Recompute `normalized_score` within a transaction. Biases come from
+    /// every sample of the business, but samples of finalized sessions keep
+    /// the score they were signed off with
+    pub async fn renormalize(tx: &mut Transaction<'_, Postgres>, business_id: Uuid) -> AppResult<u64> {
+        // One recompute per business at a time, so an older one can't
+        // overwrite a newer
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("normalized_scores:{}", business_id))
+            .execute(&mut **tx)
+            .await?;
+
+        let samples = sqlx::query_as::<_, ScoredSample>(SCORED_SAMPLES_SQL)
+            .bind(business_id)
+            .fetch_all(&mut **tx)
+            .await?;
+        let biases: HashMap<String, Decimal> = cupper_biases(&samples, DEFAULT_MIN_SHARED_LOTS)
+            .into_iter()
+            .filter_map(|b| Some((cupper_key(&b.cupper_name), b.bias?)))
+            .collect();
+
+        // Panel samples have a row per cupper
+        let mut per_sample: BTreeMap<Uuid, Vec<Option<Decimal>>> = BTreeMap::new();
+        for s in &samples {
+            let bias = biases.get(&cupper_key(&s.cupper_name));
+            per_sample
+                .entry(s.sample_id)
+                .or_default()
+                .push(bias.map(|b| normalized_score(s.final_score, *b)));
+        }
+        let (ids, normalized): (Vec<Uuid>, Vec<Option<Decimal>>) = per_sample
+            .into_iter()
+            .map(|(id, cuppers)| (id, sample_normalized_score(&cuppers)))
+            .unzip();
+
+        let updated = sqlx::query_scalar::<_, Option<Decimal>>(
+            r#"
+            WITH v AS (
+                SELECT * FROM UNNEST($1::UUID[], $2::DECIMAL[]) AS v(id, normalized_score)
+            ),
+            open_samples AS (
+                SELECT cs.id, cs.normalized_score AS current, v.normalized_score
+                FROM cupping_samples cs
+                JOIN cupping_sessions s ON s.id = cs.session_id
+                JOIN v ON v.id = cs.id
+                WHERE s.status <> 'finalized'
+            ),
+            changed AS (
+                UPDATE cupping_samples cs
+                SET normalized_score = o.normalized_score
+                FROM open_samples o
+                WHERE cs.id = o.id
+                  AND o.current IS DISTINCT FROM o.normalized_score
+            )
+            SELECT normalized_score FROM open_samples
+            "#,
+        )
+        .bind(&ids)
+        .bind(&normalized)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(updated.iter().filter(|n| n.is_some()).count() as u64)
+    }
+}
@@ -0,0 +1,360 @@
+//! Cupping flight randomization and table layout
+//!
+//! Places a session's samples at randomized table positions under 3-digit
+//! blind codes, with the SCA protocol's five bowls per sample by default,
+//! and prints one label per bowl. Layouts are not stored: the same seed
+//! always produces the same layout, so the lab can regenerate the key sheet
+//! or reprint labels later. Samples of a blind session keep the codes they
+//! were given when added.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shared::{DisplayFormat, Language};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::{Config, PdfConfig};
+use crate::error::{AppError, AppResult};
+use crate::services::pdf::{PdfFonts, PdfPage, TextStyle, A4_HEIGHT_MM, A4_WIDTH_MM};
+use crate::services::BusinessService;
+
+/// Bowls per sample in the SCA cupping protocol
+pub const SCA_BOWLS_PER_SAMPLE: u32 = 5;
+pub const MAX_BOWLS_PER_SAMPLE: u32 = 10;
+pub const DEFAULT_POSITIONS_PER_TABLE: u32 = 8;
+pub const MAX_POSITIONS_PER_TABLE: u32 = 30;
+/// Distinct 3-digit blind codes
+pub const MAX_FLIGHT_SAMPLES: usize = 900;
+
+/// Label sheet: 3 x 8 labels of 70 x 37 mm on A4
+const LABEL_COLUMNS: usize = 3;
+const LABEL_ROWS: usize = 8;
+const LABEL_WIDTH: f32 = A4_WIDTH_MM / LABEL_COLUMNS as f32;
+const LABEL_HEIGHT: f32 = 37.0;
+const LABEL_TOP: f32 = (A4_HEIGHT_MM - LABEL_ROWS as f32 * LABEL_HEIGHT) / 2.0;
+
+/// Query parameters for layout generation
+#[derive(Debug, Default, Deserialize)]
+pub struct FlightLayoutQuery {
+    /// Reuse a previous layout; a new seed is drawn when omitted
+    pub seed: Option<u32>,
+    pub bowls_per_sample: Option<u32>,
+    pub positions_per_table: Option<u32>,
+    pub language: Option<String>, // labels: "en" (default) or "th"
+}
+
+/// A session sample to place on the table
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FlightSample {
+    pub sample_id: Uuid,
+    pub sample_number: i32,
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    /// Code given when the sample was added to a blind session
+    pub blind_code: Option<String>,
+}
+
+/// Where a sample goes and what its bowls are labelled
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightPosition {
+    pub table: u32,
+    /// 1-based position along the table
+    pub position: u32,
+    pub blind_code: String,
+    pub sample_id: Uuid,
+    pub sample_number: i32,
+    /// None while the session is blind and not yet revealed
+    pub lot_id: Option<Uuid>,
+    pub traceability_code: Option<String>,
+    pub bowl_labels: Vec<String>,
+}
+
+impl FlightPosition {
+    /// Drop the lot while the session is blind
+    fn conceal_lot(&mut self) {
+        self.lot_id = None;
+        self.traceability_code = None;
+    }
+}
+
+/// Randomized table layout for a cupping session
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightLayout {
+    pub session_id: Uuid,
+    pub session_date: NaiveDate,
+    pub cupper_name: String,
+    pub seed: u32,
+    pub bowls_per_sample: u32,
+    pub positions_per_table: u32,
+    pub table_count: u32,
+    pub total_bowls: u32,
+    /// In table order
+    pub positions: Vec<FlightPosition>,
+}
+
+/// SplitMix64: small, fast and stable across releases, so a seed keeps
+/// producing the same layout
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`
+    fn below(&mut self, bound: u64) -> u64 {
+        // Rejection sampling avoids modulo bias
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Validate the layout options, applying defaults
+pub fn flight_options(query: &FlightLayoutQuery) -> AppResult<(u32, u32)> {
+    let bowls = query.bowls_per_sample.unwrap_or(SCA_BOWLS_PER_SAMPLE);
+    if bowls == 0 || bowls > MAX_BOWLS_PER_SAMPLE {
+        return Err(AppError::Validation {
+            field: "bowls_per_sample".to_string(),
+            message: format!("Bowls per sample must be between 1 and {}", MAX_BOWLS_PER_SAMPLE),
+            message_th: format!("จำนวนถ้วยต่อตัวอย่างต้องอยู่ระหว่าง 1 ถึง {}", MAX_BOWLS_PER_SAMPLE),
+        });
+    }
+
+    let positions = query.positions_per_table.unwrap_or(DEFAULT_POSITIONS_PER_TABLE);
+    if positions == 0 || positions > MAX_POSITIONS_PER_TABLE {
+        return Err(AppError::Validation {
+            field: "positions_per_table".to_string(),
+            message: format!("Positions per table must be between 1 and {}", MAX_POSITIONS_PER_TABLE),
+            message_th: format!("จำนวนตำแหน่งต่อโต๊ะต้องอยู่ระหว่าง 1 ถึง {}", MAX_POSITIONS_PER_TABLE),
+        });
+    }
+
+    Ok((bowls, positions))
+}
+
+/// Shuffle the samples onto tables and assign unique blind codes (100-999);
+/// at most `MAX_FLIGHT_SAMPLES` samples
+pub fn plan_flight(
+    samples: &[FlightSample],
+    bowls_per_sample: u32,
+    positions_per_table: u32,
+    seed: u32,
+) -> Vec<FlightPosition> {
+    let mut rng = SplitMix64(seed as u64);
+
+    // Fisher-Yates
+    let mut order: Vec<&FlightSample> = samples.iter().collect();
+    for i in (1..order.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        order.swap(i, j);
+    }
+
+    let mut used = HashSet::new();
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            let code = loop {
+                let code = 100 + rng.below(900) as u32;
+                if used.insert(code) {
+                    break code.to_string();
+                }
+            };
+            let index = index as u32;
+            FlightPosition {
+                table: index / positions_per_table + 1,
+                position: index % positions_per_table + 1,
+                bowl_labels: (1..=bowls_per_sample)
+                    .map(|bowl| format!("{}-{}", code, bowl))
+                    .collect(),
+                blind_code: code,
+                sample_id: sample.sample_id,
+                sample_number: sample.sample_number,
+                lot_id: Some(sample.lot_id),
+                traceability_code: Some(sample.traceability_code.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Drop the lots from a layout while its session is blind and not yet
+/// revealed, so the key sheet can't match bowls to lots
+pub fn conceal_flight(positions: &mut [FlightPosition], concealed: bool) {
+    if concealed {
+        for position in positions {
+            position.conceal_lot();
+        }
+    }
+}
+
+/// Cupping flight service
+#[derive(Clone)]
+pub struct CuppingFlightService {
+    db: PgPool,
+    pdf: PdfConfig,
+}
+
+impl CuppingFlightService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            pdf: config.pdf.clone(),
+        }
+    }
+
+    /// Generate the table layout for a session
+    pub async fn generate_layout(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        query: &FlightLayoutQuery,
+    ) -> AppResult<FlightLayout> {
+        let (bowls_per_sample, positions_per_table) = flight_options(query)?;
+
+        let (session_date, cupper_name, concealed) = sqlx::query_as::<_, (NaiveDate, String, bool)>(
+            r#"
+            SELECT session_date, cupper_name, is_blind AND revealed_at IS NULL
+            FROM cupping_sessions
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(session_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))?;
+
+        let samples = sqlx::query_as::<_, FlightSample>(
+            r#"
+            SELECT cs.id AS sample_id, cs.sample_number, cs.lot_id, l.traceability_code, cs.blind_code
+            FROM cupping_samples cs
+            JOIN lots l ON l.id = cs.lot_id
+            WHERE cs.session_id = $1
+            ORDER BY cs.sample_number
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        if samples.is_empty() {
+            return Err(AppError::Validation {
+                field: "session_id".to_string(),
+                message: "Add samples to the session before generating a layout".to_string(),
+                message_th: "กรุณาเพิ่มตัวอย่างในรอบการชิมก่อนจัดโต๊ะ".to_string(),
+            });
+        }
+        if samples.len() > MAX_FLIGHT_SAMPLES {
+            return Err(AppError::Validation {
+                field: "session_id".to_string(),
+                message: format!("A flight can hold at most {} samples", MAX_FLIGHT_SAMPLES),
+                message_th: format!("หนึ่งรอบการชิมจัดโต๊ะได้ไม่เกิน {} ตัวอย่าง", MAX_FLIGHT_SAMPLES),
+            });
+        }
+
+        // Random without pulling in an RNG crate
+        let seed = query.seed.unwrap_or_else(|| Uuid::new_v4().as_u128() as u32);
+        let mut positions = plan_flight(&samples, bowls_per_sample, positions_per_table, seed);
+        // Blind sessions keep the codes their cuppers score under
+        for position in &mut positions {
+            let stored = samples
+                .iter()
+                .find(|s| s.sample_id == position.sample_id)
+                .and_then(|s| s.blind_code.clone());
+            if let Some(code) = stored {
+                position.bowl_labels = (1..=bowls_per_sample).map(|bowl| format!("{}-{}", code, bowl)).collect();
+                position.blind_code = code;
+            }
+        }
+        conceal_flight(&mut positions, concealed);
+
+        Ok(FlightLayout {
+            session_id,
+            session_date,
+            cupper_name,
+            seed,
+            bowls_per_sample,
+            positions_per_table,
+            table_count: (samples.len() as u32).div_ceil(positions_per_table),
+            total_bowls: samples.len() as u32 * bowls_per_sample,
+            positions,
+        })
+    }
+
+    /// Bowl labels PDF for a layout. Thai labels are used only when a
+    /// Thai-capable font is configured
+    pub async fn generate_labels(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        query: &FlightLayoutQuery,
+    ) -> AppResult<(FlightLayout, Vec<u8>)> {
+        let layout = self.generate_layout(business_id, session_id, query).await?;
+        let fonts = PdfFonts::load(&self.pdf).await?;
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let thai = query.language.as_deref() == Some("th") && fonts.supports_thai();
+
+        let pdf = render_labels(&layout, &fonts, thai, display)?;
+        Ok((layout, pdf))
+    }
+}
+
+/// One label per bowl, in table order so they can be applied while walking
+/// along the table
+fn render_labels(
+    layout: &FlightLayout,
+    fonts: &PdfFonts,
+    thai: bool,
+    display: DisplayFormat,
+) -> AppResult<Vec<u8>> {
+    let fmt = display.for_language(if thai { &Language::Thai } else { &Language::English });
+    let (table_label, bowl_label) = if thai { ("โต๊ะ", "ถ้วย") } else { ("Table", "Bowl") };
+    let footer = format!("{}  {}  #{}", fmt.date(layout.session_date), layout.cupper_name, layout.seed);
+
+    let title = format!("Bowl labels - {}", fmt.date(layout.session_date));
+    let mut page = PdfPage::new(&title, A4_WIDTH_MM, A4_HEIGHT_MM, fonts)?;
+
+    let bowls = layout
+        .positions
+        .iter()
+        .flat_map(|position| (1..=layout.bowls_per_sample).map(move |bowl| (position, bowl)));
+    for (i, (position, bowl)) in bowls.enumerate() {
+        let slot = i % (LABEL_COLUMNS * LABEL_ROWS);
+        if i > 0 && slot == 0 {
+            page.add_page();
+        }
+        let x = (slot % LABEL_COLUMNS) as f32 * LABEL_WIDTH;
+        let y = LABEL_TOP + (slot / LABEL_COLUMNS) as f32 * LABEL_HEIGHT;
+
+        page.outline_rect(x + 1.0, y + 1.0, LABEL_WIDTH - 2.0, LABEL_HEIGHT - 2.0, 0.3, 0.8);
+        page.text(x + 6.0, y + 15.0, TextStyle::bold(26.0), &position.blind_code);
+        page.text(
+            x + 6.0,
+            y + 23.0,
+            TextStyle::regular(10.0),
+            &format!(
+                "{} {}/{}   {} {} - {}",
+                bowl_label,
+                fmt.integer(bowl as i64),
+                fmt.integer(layout.bowls_per_sample as i64),
+                table_label,
+                fmt.integer(position.table as i64),
+                fmt.integer(position.position as i64),
+            ),
+        );
+        page.text(x + 6.0, y + 30.0, TextStyle::regular(7.0), &footer);
+    }
+
+    page.finish()
+}
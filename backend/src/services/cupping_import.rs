@@ -0,0 +1,715 @@
+//! Import of historical cupping data from SCA score sheet exports
+//!
+//! Labs keep years of cupping history in spreadsheets or cupping apps such
+//! as Cropster and Tastify, one row per cup evaluated. Headers are matched
+//! against the column names used by common score sheet layouts and by those
+//! apps' CSV exports, rows are grouped into sessions by date, cupper,
+//! location and the app's session name, and lots are matched by
+//! traceability code or name. A dry run returns the same preview without
+//! writing anything; rows already in the database are skipped, so a
+//! corrected file can be imported again.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::BUDDHIST_ERA_OFFSET;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::{CuppingDefects, CuppingScores};
+
+/// Largest difference between a sheet's total and the recomputed total
+/// before the row is flagged
+const TOTAL_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// Cupping import service
+#[derive(Clone)]
+pub struct CuppingImportService {
+    db: PgPool,
+}
+
+/// Layout of a score sheet export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetFormat {
+    /// Spreadsheet following the SCA score sheet
+    Sca,
+    /// Cropster Cup evaluation export
+    Cropster,
+    /// Tastify cupping export
+    Tastify,
+}
+
+/// Field a score sheet column maps to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportField {
+    SessionDate,
+    Cupper,
+    Location,
+    /// Session name or number in the exporting app
+    Session,
+    Lot,
+    FragranceAroma,
+    Flavor,
+    Aftertaste,
+    Acidity,
+    Body,
+    Balance,
+    Uniformity,
+    CleanCup,
+    Sweetness,
+    /// Uniformity as a count of uniform cups (2 points each)
+    UniformityCups,
+    /// Clean cup as a count of clean cups (2 points each)
+    CleanCupCups,
+    /// Sweetness as a count of sweet cups (2 points each)
+    SweetnessCups,
+    Overall,
+    TaintCount,
+    FaultCount,
+    /// Defect points deducted, for sheets without taint and fault counts
+    DefectPoints,
+    TastingNotes,
+    /// Total or final score on the sheet, only used as a check
+    TotalScore,
+}
+
+/// Fields every sheet must provide (the three cup-based attributes may come
+/// as points or as cup counts)
+const REQUIRED_FIELDS: &[(ImportField, Option<ImportField>)] = &[
+    (ImportField::SessionDate, None),
+    (ImportField::Cupper, None),
+    (ImportField::Lot, None),
+    (ImportField::FragranceAroma, None),
+    (ImportField::Flavor, None),
+    (ImportField::Aftertaste, None),
+    (ImportField::Acidity, None),
+    (ImportField::Body, None),
+    (ImportField::Balance, None),
+    (ImportField::Uniformity, Some(ImportField::UniformityCups)),
+    (ImportField::CleanCup, Some(ImportField::CleanCupCups)),
+    (ImportField::Sweetness, Some(ImportField::SweetnessCups)),
+    (ImportField::Overall, None),
+];
+
+/// Header with case, spacing and punctuation dropped
+fn header_key(header: &str) -> String {
+    header
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Layout of an export from its headers: Cropster names the cupper
+/// "Evaluator" and Tastify names the session "Cupping Name"
+pub fn detect_format<'a>(headers: impl IntoIterator<Item = &'a str>) -> SheetFormat {
+    let keys: Vec<String> = headers.into_iter().map(header_key).collect();
+    if keys.iter().any(|key| key == "evaluator") {
+        SheetFormat::Cropster
+    } else if keys.iter().any(|key| key == "cupping_name") {
+        SheetFormat::Tastify
+    } else {
+        SheetFormat::Sca
+    }
+}
+
+/// Field for a score sheet header, matching the names used by common layouts
+/// (case, spacing and punctuation are ignored). Cropster and Tastify export
+/// the defect deduction as "Defects", and Cropster's "Sample ID" is its own
+/// sample number rather than the lot.
+pub fn header_field(format: SheetFormat, header: &str) -> Option<ImportField> {
+    let key = header_key(header);
+
+    let field = match (format, key.as_str()) {
+        (SheetFormat::Cropster, "sample_id") => return None,
+        (SheetFormat::Cropster | SheetFormat::Tastify, "defects") => ImportField::DefectPoints,
+        (_, "date" | "session_date" | "cupping_date" | "date_cupped" | "evaluation_date") => ImportField::SessionDate,
+        (_, "cupper" | "cupper_name" | "taster" | "q_grader" | "grader" | "evaluator") => ImportField::Cupper,
+        (_, "location" | "lab" | "cupping_lab") => ImportField::Location,
+        (_, "session" | "session_name" | "cupping_name") => ImportField::Session,
+        (
+            _,
+            "lot" | "lot_code" | "lot_id" | "lot_name" | "sample" | "sample_id" | "sample_code" | "sample_name"
+            | "traceability_code",
+        ) => ImportField::Lot,
+        (_, "fragrance_aroma" | "fragrance" | "aroma" | "dry_fragrance") => ImportField::FragranceAroma,
+        (_, "flavor" | "flavour") => ImportField::Flavor,
+        (_, "aftertaste" | "after_taste" | "finish") => ImportField::Aftertaste,
+        (_, "acidity") => ImportField::Acidity,
+        (_, "body" | "mouthfeel") => ImportField::Body,
+        (_, "balance") => ImportField::Balance,
+        (_, "uniformity" | "uniform_cup") => ImportField::Uniformity,
+        (_, "clean_cup" | "cleancup" | "clean") => ImportField::CleanCup,
+        (_, "sweetness" | "sweet") => ImportField::Sweetness,
+        (_, "uniformity_cups" | "uniform_cups") => ImportField::UniformityCups,
+        (_, "clean_cups" | "clean_cup_cups") => ImportField::CleanCupCups,
+        (_, "sweet_cups" | "sweetness_cups") => ImportField::SweetnessCups,
+        (_, "overall" | "cupper_points" | "cuppers_points" | "cupper_s_points" | "overall_impression") => {
+            ImportField::Overall
+        }
+        (_, "taints" | "taint" | "defects_taint" | "taint_cups") => ImportField::TaintCount,
+        (_, "faults" | "fault" | "defects_fault" | "fault_cups") => ImportField::FaultCount,
+        (_, "defect_points" | "defects_points" | "defect_deduction") => ImportField::DefectPoints,
+        (_, "notes" | "tasting_notes" | "comments" | "comment" | "descriptors" | "flavor_notes") => ImportField::TastingNotes,
+        (_, "total" | "total_score" | "final_score" | "score" | "final") => ImportField::TotalScore,
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Cupping date as written on score sheets: ISO, or day first with `/`, `-`
+/// or `.`, optionally followed by a time as cupping apps export it. Buddhist
+/// Era years are converted to Gregorian.
+pub fn parse_sheet_date(value: &str) -> Option<NaiveDate> {
+    let date = value.trim().split([' ', 'T']).next()?;
+    let parts: Vec<u32> = date
+        .split(['-', '/', '.'])
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, month, day) = match parts[..] {
+        [year, month, day] if year > 31 => (year as i32, month, day),
+        [day, month, year] if year > 31 => (year as i32, month, day),
+        _ => return None,
+    };
+    // Converted before building the date, as 29 February of a B.E. leap
+    // year is not a valid Gregorian date in the B.E. year number
+    let year = if year > 2400 { year - BUDDHIST_ERA_OFFSET } else { year };
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Score as written on score sheets, with either `.` or `,` as decimal mark
+pub fn parse_sheet_score(value: &str) -> Option<Decimal> {
+    value.trim().replace(',', ".").parse::<Decimal>().ok()
+}
+
+/// Taints (2 points each) and faults (4 points each) making up a defect
+/// deduction, as many faults as fit; None when the points are not whole
+/// taints and faults of at most five cups each
+pub fn defects_from_points(points: Decimal) -> Option<CuppingDefects> {
+    if !points.fract().is_zero() {
+        return None;
+    }
+    let points = points.to_i32()?;
+    if points < 0 || points % 2 != 0 {
+        return None;
+    }
+    let fault_count = (points / 4).min(5);
+    let taint_count = (points - fault_count * 4) / 2;
+    (taint_count <= 5).then_some(CuppingDefects {
+        taint_count,
+        fault_count,
+    })
+}
+
+/// Header mapped to a field
+#[derive(Debug, Clone, Serialize)]
+pub struct MappedColumn {
+    pub header: String,
+    pub field: ImportField,
+}
+
+/// A score sheet row read into a sample
+#[derive(Debug, Clone)]
+pub struct SheetRow {
+    pub session_date: NaiveDate,
+    pub cupper_name: String,
+    pub location: Option<String>,
+    /// Session name or number in the exporting app
+    pub session_name: Option<String>,
+    pub lot_reference: String,
+    pub scores: CuppingScores,
+    pub defects: CuppingDefects,
+    pub tasting_notes: Option<String>,
+    /// Total on the sheet, when given
+    pub sheet_total: Option<Decimal>,
+}
+
+impl SheetRow {
+    pub fn final_score(&self) -> Decimal {
+        self.scores.total() - self.defects.total_deduction()
+    }
+}
+
+/// Score sheet with its headers mapped and rows read
+#[derive(Debug)]
+pub struct ParsedSheet {
+    pub format: SheetFormat,
+    pub columns: Vec<MappedColumn>,
+    pub ignored_columns: Vec<String>,
+    /// Line number and the row, or why it could not be read
+    pub rows: Vec<(u64, Result<SheetRow, String>)>,
+}
+
+/// Read a score sheet export in the given layout, detected from the headers
+/// when not given. Fails when a required column is missing; problems in
+/// individual rows are reported per row.
+pub fn parse_score_sheet(csv_data: &str, format: Option<SheetFormat>) -> AppResult<ParsedSheet> {
+    let csv_data = csv_data.trim_start_matches('\u{feff}');
+    let header_line = csv_data.lines().next().unwrap_or_default();
+    // Spreadsheets in locales with a decimal comma export with semicolons
+    let delimiter = if header_line.matches(';').count() > header_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(csv_data.as_bytes());
+    let headers = reader.headers().map_err(invalid_csv)?.clone();
+    let format = format.unwrap_or_else(|| detect_format(headers.iter()));
+
+    let mut columns = Vec::new();
+    let mut ignored_columns = Vec::new();
+    let mut index: HashMap<ImportField, usize> = HashMap::new();
+    for (i, header) in headers.iter().enumerate() {
+        match header_field(format, header) {
+            Some(field) if !index.contains_key(&field) => {
+                index.insert(field, i);
+                columns.push(MappedColumn {
+                    header: header.to_string(),
+                    field,
+                });
+            }
+            _ => ignored_columns.push(header.to_string()),
+        }
+    }
+
+    let missing: Vec<String> = REQUIRED_FIELDS
+        .iter()
+        .filter(|(field, alternative)| {
+            !index.contains_key(field) && !alternative.is_some_and(|a| index.contains_key(&a))
+        })
+        .map(|(field, _)| field_name(*field))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::Validation {
+            field: "csv".to_string(),
+            message: format!("Score sheet is missing columns: {}", missing.join(", ")),
+            message_th: format!("ใบให้คะแนนไม่มีคอลัมน์: {}", missing.join(", ")),
+        });
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(invalid_csv)?;
+        if record.iter().all(|value| value.is_empty()) {
+            continue;
+        }
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        rows.push((line, read_row(&record, &index)));
+    }
+
+    Ok(ParsedSheet {
+        format,
+        columns,
+        ignored_columns,
+        rows,
+    })
+}
+
+fn read_row(record: &csv::StringRecord, index: &HashMap<ImportField, usize>) -> Result<SheetRow, String> {
+    let text = |field: ImportField| {
+        index
+            .get(&field)
+            .and_then(|i| record.get(*i))
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let required = |field: ImportField| text(field).ok_or_else(|| format!("{} is empty", field_name(field)));
+    let score = |field: ImportField| -> Result<Option<Decimal>, String> {
+        match text(field) {
+            None => Ok(None),
+            Some(value) => parse_sheet_score(&value)
+                .map(Some)
+                .ok_or_else(|| format!("{} is not a number: {}", field_name(field), value)),
+        }
+    };
+    let attribute = |field: ImportField, cups: Option<ImportField>| -> Result<Decimal, String> {
+        let points = match score(field)? {
+            Some(points) => points,
+            None => match cups {
+                Some(cups) => score(cups)?.map(|count| count * Decimal::TWO),
+                None => None,
+            }
+            .ok_or_else(|| format!("{} is empty", field_name(field)))?,
+        };
+        if points < Decimal::ZERO || points > Decimal::TEN {
+            return Err(format!("{} must be between 0 and 10", field_name(field)));
+        }
+        Ok(points)
+    };
+    let count = |field: ImportField| -> Result<i32, String> {
+        match text(field) {
+            None => Ok(0),
+            Some(value) => value
+                .parse::<i32>()
+                .ok()
+                .filter(|n| (0..=5).contains(n))
+                .ok_or_else(|| format!("{} must be a cup count between 0 and 5", field_name(field))),
+        }
+    };
+
+    // Taint and fault counts when given, else the deduction split into them
+    let counts = (text(ImportField::TaintCount), text(ImportField::FaultCount));
+    let defects = match (counts, score(ImportField::DefectPoints)?) {
+        ((None, None), Some(points)) => defects_from_points(points).ok_or_else(|| {
+            format!(
+                "{} must be whole taints (2 points) and faults (4 points) of at most 5 cups each",
+                field_name(ImportField::DefectPoints)
+            )
+        })?,
+        _ => CuppingDefects {
+            taint_count: count(ImportField::TaintCount)?,
+            fault_count: count(ImportField::FaultCount)?,
+        },
+    };
+
+    let date = required(ImportField::SessionDate)?;
+    let session_date = parse_sheet_date(&date).ok_or_else(|| format!("Unrecognized date: {}", date))?;
+
+    Ok(SheetRow {
+        session_date,
+        cupper_name: required(ImportField::Cupper)?,
+        location: text(ImportField::Location),
+        session_name: text(ImportField::Session),
+        lot_reference: required(ImportField::Lot)?,
+        scores: CuppingScores {
+            fragrance_aroma: attribute(ImportField::FragranceAroma, None)?,
+            flavor: attribute(ImportField::Flavor, None)?,
+            aftertaste: attribute(ImportField::Aftertaste, None)?,
+            acidity: attribute(ImportField::Acidity, None)?,
+            body: attribute(ImportField::Body, None)?,
+            balance: attribute(ImportField::Balance, None)?,
+            uniformity: attribute(ImportField::Uniformity, Some(ImportField::UniformityCups))?,
+            clean_cup: attribute(ImportField::CleanCup, Some(ImportField::CleanCupCups))?,
+            sweetness: attribute(ImportField::Sweetness, Some(ImportField::SweetnessCups))?,
+            overall: attribute(ImportField::Overall, None)?,
+        },
+        defects,
+        tasting_notes: text(ImportField::TastingNotes),
+        sheet_total: score(ImportField::TotalScore)?,
+    })
+}
+
+fn field_name(field: ImportField) -> String {
+    serde_json::to_value(field)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn invalid_csv(err: csv::Error) -> AppError {
+    AppError::Validation {
+        field: "csv".to_string(),
+        message: format!("Could not read the score sheet: {}", err),
+        message_th: format!("ไม่สามารถอ่านใบให้คะแนนได้: {}", err),
+    }
+}
+
+/// Date, cupper, location and app session name of a session
+type SessionKey = (NaiveDate, String, Option<String>, Option<String>);
+
+/// Key of the session a row belongs to
+fn session_key(row: &SheetRow) -> SessionKey {
+    (
+        row.session_date,
+        row.cupper_name.trim().to_lowercase(),
+        row.location.as_ref().map(|l| l.trim().to_lowercase()),
+        row.session_name.as_ref().map(|s| s.trim().to_lowercase()),
+    )
+}
+
+/// Import query
+#[derive(Debug, Deserialize)]
+pub struct CuppingImportQuery {
+    /// Preview the import without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Layout of the export; detected from the headers when omitted
+    pub format: Option<SheetFormat>,
+}
+
+/// What happens to a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    /// Imported, or would be on a dry run
+    Ready,
+    /// Already recorded; skipped
+    Duplicate,
+    /// Could not be read or matched; skipped
+    Error,
+}
+
+/// Outcome of one score sheet row
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRow {
+    pub line: u64,
+    pub status: ImportRowStatus,
+    pub session_date: Option<NaiveDate>,
+    pub cupper_name: Option<String>,
+    pub lot_reference: Option<String>,
+    pub lot_id: Option<Uuid>,
+    pub final_score: Option<Decimal>,
+    pub message: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Session the ready rows go into
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSession {
+    /// Set once imported
+    pub session_id: Option<Uuid>,
+    pub session_date: NaiveDate,
+    pub cupper_name: String,
+    pub location: Option<String>,
+    pub session_name: Option<String>,
+    pub samples: usize,
+}
+
+/// Preview or result of an import
+#[derive(Debug, Clone, Serialize)]
+pub struct CuppingImportResult {
+    pub dry_run: bool,
+    pub format: SheetFormat,
+    pub columns: Vec<MappedColumn>,
+    pub ignored_columns: Vec<String>,
+    pub ready: usize,
+    pub duplicates: usize,
+    pub errors: usize,
+    pub sessions: Vec<ImportSession>,
+    pub rows: Vec<ImportRow>,
+}
+
+impl CuppingImportService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Import a score sheet export, or preview it on a dry run
+    pub async fn import(
+        &self,
+        business_id: Uuid,
+        csv_data: &str,
+        query: &CuppingImportQuery,
+    ) -> AppResult<CuppingImportResult> {
+        let dry_run = query.dry_run;
+        let sheet = parse_score_sheet(csv_data, query.format)?;
+
+        let lots = sqlx::query_as::<_, (Uuid, String, String)>(
+            "SELECT id, traceability_code, name FROM lots WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        let mut lots_by_reference: HashMap<String, Vec<Uuid>> = HashMap::new();
+        for (id, code, name) in lots {
+            lots_by_reference.entry(code.trim().to_lowercase()).or_default().push(id);
+            let by_name = lots_by_reference.entry(name.trim().to_lowercase()).or_default();
+            if !by_name.contains(&id) {
+                by_name.push(id);
+            }
+        }
+
+        // Samples already recorded, to skip rows imported before
+        let existing = sqlx::query_as::<_, (NaiveDate, String, Uuid, Decimal)>(
+            r#"
+            SELECT s.session_date, LOWER(TRIM(s.cupper_name)), cs.lot_id, cs.final_score
+            FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            WHERE s.business_id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        let mut recorded: HashSet<(NaiveDate, String, Uuid, Decimal)> = existing
+            .into_iter()
+            .map(|(date, cupper, lot_id, score)| (date, cupper, lot_id, score.normalize()))
+            .collect();
+
+        let mut rows = Vec::new();
+        let mut ready: Vec<(SheetRow, Uuid)> = Vec::new();
+        for (line, parsed) in sheet.rows {
+            let sheet_row = match parsed {
+                Ok(sheet_row) => sheet_row,
+                Err(message) => {
+                    rows.push(ImportRow {
+                        line,
+                        status: ImportRowStatus::Error,
+                        session_date: None,
+                        cupper_name: None,
+                        lot_reference: None,
+                        lot_id: None,
+                        final_score: None,
+                        message: Some(message),
+                        warnings: vec![],
+                    });
+                    continue;
+                }
+            };
+
+            let final_score = sheet_row.final_score();
+            let mut row = ImportRow {
+                line,
+                status: ImportRowStatus::Ready,
+                session_date: Some(sheet_row.session_date),
+                cupper_name: Some(sheet_row.cupper_name.clone()),
+                lot_reference: Some(sheet_row.lot_reference.clone()),
+                lot_id: None,
+                final_score: Some(final_score),
+                message: None,
+                warnings: vec![],
+            };
+            if let Some(total) = sheet_row.sheet_total {
+                let matches_total = (total - sheet_row.scores.total()).abs() <= TOTAL_TOLERANCE
+                    || (total - final_score).abs() <= TOTAL_TOLERANCE;
+                if !matches_total {
+                    row.warnings.push(format!(
+                        "Sheet total {} differs from the recomputed score {}",
+                        total, final_score
+                    ));
+                }
+            }
+
+            match lots_by_reference
+                .get(&sheet_row.lot_reference.trim().to_lowercase())
+                .map(Vec::as_slice)
+            {
+                Some([lot_id]) => {
+                    row.lot_id = Some(*lot_id);
+                    let key = (
+                        sheet_row.session_date,
+                        sheet_row.cupper_name.trim().to_lowercase(),
+                        *lot_id,
+                        final_score.normalize(),
+                    );
+                    if recorded.insert(key) {
+                        ready.push((sheet_row, *lot_id));
+                    } else {
+                        row.status = ImportRowStatus::Duplicate;
+                        row.message = Some("Already recorded".to_string());
+                    }
+                }
+                Some([_, _, ..]) => {
+                    row.status = ImportRowStatus::Error;
+                    row.message = Some(format!(
+                        "Several lots are named {}; use the traceability code",
+                        sheet_row.lot_reference
+                    ));
+                }
+                _ => {
+                    row.status = ImportRowStatus::Error;
+                    row.message = Some(format!("No lot matches {}", sheet_row.lot_reference));
+                }
+            }
+            rows.push(row);
+        }
+
+        // Group ready rows into sessions in the order they first appear
+        let mut sessions: Vec<ImportSession> = Vec::new();
+        let mut session_rows: Vec<Vec<(SheetRow, Uuid)>> = Vec::new();
+        let mut session_index: HashMap<SessionKey, usize> = HashMap::new();
+        for (sheet_row, lot_id) in ready {
+            let i = *session_index.entry(session_key(&sheet_row)).or_insert_with(|| {
+                sessions.push(ImportSession {
+                    session_id: None,
+                    session_date: sheet_row.session_date,
+                    cupper_name: sheet_row.cupper_name.trim().to_string(),
+                    location: sheet_row.location.clone(),
+                    session_name: sheet_row.session_name.as_ref().map(|s| s.trim().to_string()),
+                    samples: 0,
+                });
+                session_rows.push(Vec::new());
+                sessions.len() - 1
+            });
+            sessions[i].samples += 1;
+            session_rows[i].push((sheet_row, lot_id));
+        }
+
+        if !dry_run && !sessions.is_empty() {
+            let mut tx = self.db.begin().await?;
+            for (session, samples) in sessions.iter_mut().zip(&session_rows) {
+                let session_id = sqlx::query_scalar::<_, Uuid>(
+                    r#"
+                    INSERT INTO cupping_sessions (business_id, session_date, cupper_name, location, notes, status)
+                    VALUES ($1, $2, $3, $4, $5, 'in_progress')
+                    RETURNING id
+                    "#,
+                )
+                .bind(business_id)
+                .bind(session.session_date)
+                .bind(&session.cupper_name)
+                .bind(&session.location)
+                .bind(match &session.session_name {
+                    Some(name) => format!("Imported from score sheet, session {}", name),
+                    None => "Imported from score sheet".to_string(),
+                })
+                .fetch_one(&mut *tx)
+                .await?;
+
+                for (number, (sample, lot_id)) in samples.iter().enumerate() {
+                    let scores = &sample.scores;
+                    sqlx::query(
+                        r#"
+                        INSERT INTO cupping_samples (
+                            session_id, lot_id, sample_number,
+                            fragrance_aroma, flavor, aftertaste, acidity, body, balance,
+                            uniformity, clean_cup, sweetness, overall,
+                            total_score, tasting_notes,
+                            defects_taint, defects_fault, final_score
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+                        "#,
+                    )
+                    .bind(session_id)
+                    .bind(lot_id)
+                    .bind(number as i32 + 1)
+                    .bind(scores.fragrance_aroma)
+                    .bind(scores.flavor)
+                    .bind(scores.aftertaste)
+                    .bind(scores.acidity)
+                    .bind(scores.body)
+                    .bind(scores.balance)
+                    .bind(scores.uniformity)
+                    .bind(scores.clean_cup)
+                    .bind(scores.sweetness)
+                    .bind(scores.overall)
+                    .bind(scores.total())
+                    .bind(&sample.tasting_notes)
+                    .bind(sample.defects.taint_count)
+                    .bind(sample.defects.fault_count)
+                    .bind(sample.final_score())
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                session.session_id = Some(session_id);
+            }
+            tx.commit().await?;
+        }
+
+        let count = |status: ImportRowStatus| rows.iter().filter(|r| r.status == status).count();
+        Ok(CuppingImportResult {
+            dry_run,
+            format: sheet.format,
+            columns: sheet.columns,
+            ignored_columns: sheet.ignored_columns,
+            ready: count(ImportRowStatus::Ready),
+            duplicates: count(ImportRowStatus::Duplicate),
+            errors: count(ImportRowStatus::Error),
+            sessions,
+            rows,
+        })
+    }
+}
@@ -0,0 +1,711 @@
+//! Panel cuppings: several cuppers scoring the same samples
+//!
+//! A QC lab cups each sample with a panel. Every cupper's score sheet is
+//! kept, and the sample's own scores become the panel consensus: the mean of
+//! each attribute, with the median defect counts. The panel report gives the
+//! mean, median and standard deviation of every attribute and flags scores
+//! far from the rest of the panel: a score is an outlier when it is more
+//! than [`ATTRIBUTE_OUTLIER_POINTS`] (for the final score
+//! [`FINAL_SCORE_OUTLIER_POINTS`]) away from the median of the other
+//! cuppers, in panels of at least [`MIN_OUTLIER_PANEL`].
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::{CuppingDefects, CuppingScores};
+use crate::services::cupping_analytics::cupper_key;
+use crate::services::CuppingService;
+
+/// Points from the other cuppers' median that flag an attribute score
+pub const ATTRIBUTE_OUTLIER_POINTS: Decimal = Decimal::ONE;
+
+/// Points from the other cuppers' median that flag a final score
+pub const FINAL_SCORE_OUTLIER_POINTS: Decimal = Decimal::from_parts(3, 0, 0, false, 0);
+
+/// Cuppers needed on a sample before scores are flagged
+pub const MIN_OUTLIER_PANEL: usize = 3;
+
+/// Cupping panel service
+#[derive(Clone)]
+pub struct CuppingPanelService {
+    db: PgPool,
+}
+
+/// The ten SCA attributes by name
+pub fn attribute_scores(scores: &CuppingScores) -> [(&'static str, Decimal); 10] {
+    [
+        ("fragrance_aroma", scores.fragrance_aroma),
+        ("flavor", scores.flavor),
+        ("aftertaste", scores.aftertaste),
+        ("acidity", scores.acidity),
+        ("body", scores.body),
+        ("balance", scores.balance),
+        ("uniformity", scores.uniformity),
+        ("clean_cup", scores.clean_cup),
+        ("sweetness", scores.sweetness),
+        ("overall", scores.overall),
+    ]
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    values.iter().sum::<Decimal>() / Decimal::from(values.len())
+}
+
+/// Median; the mean of the two middle values for an even count
+pub fn median(values: &[Decimal]) -> Decimal {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    match sorted.len() {
+        0 => Decimal::ZERO,
+        len if len % 2 == 1 => sorted[len / 2],
+        len => (sorted[len / 2 - 1] + sorted[len / 2]) / Decimal::TWO,
+    }
+}
+
+/// Sample standard deviation; `None` for fewer than two scores
+pub fn std_dev(values: &[Decimal]) -> Option<Decimal> {
+    if values.len() < 2 {
+        return None;
+    }
+    let average = mean(values);
+    let squares: Decimal = values.iter().map(|v| (v - average) * (v - average)).sum();
+    let variance = squares / Decimal::from(values.len() - 1);
+    variance.to_f64().map(f64::sqrt).and_then(Decimal::from_f64)
+}
+
+/// Signed distance of each score from the median of the other scores, for
+/// the scores further away than `threshold`; empty for small panels
+pub fn outlier_deviations(values: &[Decimal], threshold: Decimal) -> Vec<(usize, Decimal)> {
+    if values.len() < MIN_OUTLIER_PANEL {
+        return Vec::new();
+    }
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            let others: Vec<Decimal> = values
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, v)| *v)
+                .collect();
+            let deviation = value - median(&others);
+            (deviation.abs() > threshold).then_some((index, deviation))
+        })
+        .collect()
+}
+
+/// Panel consensus of score sheets: the mean of each attribute and the
+/// median defect counts (the lower middle count for an even panel)
+pub fn consensus(sheets: &[(CuppingScores, CuppingDefects)]) -> (CuppingScores, CuppingDefects) {
+    let attribute = |pick: fn(&CuppingScores) -> Decimal| {
+        let values: Vec<Decimal> = sheets.iter().map(|(s, _)| pick(s)).collect();
+        mean(&values).round_dp(2)
+    };
+    let count = |pick: fn(&CuppingDefects) -> i32| {
+        let mut values: Vec<i32> = sheets.iter().map(|(_, d)| pick(d)).collect();
+        values.sort_unstable();
+        values.get(values.len().saturating_sub(1) / 2).copied().unwrap_or(0)
+    };
+
+    (
+        CuppingScores {
+            fragrance_aroma: attribute(|s| s.fragrance_aroma),
+            flavor: attribute(|s| s.flavor),
+            aftertaste: attribute(|s| s.aftertaste),
+            acidity: attribute(|s| s.acidity),
+            body: attribute(|s| s.body),
+            balance: attribute(|s| s.balance),
+            uniformity: attribute(|s| s.uniformity),
+            clean_cup: attribute(|s| s.clean_cup),
+            sweetness: attribute(|s| s.sweetness),
+            overall: attribute(|s| s.overall),
+        },
+        CuppingDefects {
+            taint_count: count(|d| d.taint_count),
+            fault_count: count(|d| d.fault_count),
+        },
+    )
+}
+
+/// Panel statistics of one attribute (or the final score)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributeStats {
+    pub attribute: &'static str,
+    pub mean: Decimal,
+    pub median: Decimal,
+    /// `None` with a single cupper
+    pub std_dev: Option<Decimal>,
+    pub min: Decimal,
+    pub max: Decimal,
+}
+
+/// Statistics of the scores the panel gave one attribute
+pub fn attribute_stats(attribute: &'static str, values: &[Decimal]) -> AttributeStats {
+    AttributeStats {
+        attribute,
+        mean: mean(values).round_dp(2),
+        median: median(values).round_dp(2),
+        std_dev: std_dev(values).map(|s| s.round_dp(2)),
+        min: values.iter().copied().min().unwrap_or_default(),
+        max: values.iter().copied().max().unwrap_or_default(),
+    }
+}
+
+/// Score far from the rest of the panel
+#[derive(Debug, Clone, Serialize)]
+pub struct PanelOutlier {
+    pub cupper_name: String,
+    /// Attribute name, or `final_score`
+    pub attribute: &'static str,
+    pub score: Decimal,
+    /// Points above (+) or below (-) the median of the other cuppers
+    pub deviation: Decimal,
+}
+
+/// Database row for a cupper's score sheet
+#[derive(Debug, sqlx::FromRow)]
+struct ScoreSheetRow {
+    id: Uuid,
+    sample_id: Uuid,
+    cupper_name: String,
+    fragrance_aroma: Decimal,
+    flavor: Decimal,
+    aftertaste: Decimal,
+    acidity: Decimal,
+    body: Decimal,
+    balance: Decimal,
+    uniformity: Decimal,
+    clean_cup: Decimal,
+    sweetness: Decimal,
+    overall: Decimal,
+    total_score: Decimal,
+    defects_taint: i32,
+    defects_fault: i32,
+    final_score: Decimal,
+    tasting_notes: Option<String>,
+    tasting_notes_th: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// One cupper's scores for a sample
+#[derive(Debug, Clone, Serialize)]
+pub struct CupperScoreSheet {
+    /// `None` for the head cupper's scores on a sample no panel has scored
+    pub id: Option<Uuid>,
+    pub sample_id: Uuid,
+    pub cupper_name: String,
+    pub scores: CuppingScores,
+    pub total_score: Decimal,
+    pub defects: CuppingDefects,
+    pub final_score: Decimal,
+    pub tasting_notes: Option<String>,
+    pub tasting_notes_th: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for recording a cupper's scores; a cupper scoring the sample again
+/// replaces their earlier sheet
+#[derive(Debug, Deserialize)]
+pub struct RecordCupperScoresInput {
+    pub cupper_name: String,
+    pub scores: CuppingScores,
+    pub defects: Option<CuppingDefects>,
+    pub tasting_notes: Option<String>,
+    pub tasting_notes_th: Option<String>,
+}
+
+/// Panel results of one sample
+#[derive(Debug, Clone, Serialize)]
+pub struct SamplePanel {
+    pub sample_id: Uuid,
+    pub sample_number: i32,
+    /// None while the session is blind and not yet revealed
+    pub lot_id: Option<Uuid>,
+    pub blind_code: Option<String>,
+    pub cuppers: usize,
+    /// The sample's final score: the final score of the panel consensus
+    pub consensus_score: Decimal,
+    pub attributes: Vec<AttributeStats>,
+    pub final_score: AttributeStats,
+    pub outliers: Vec<PanelOutlier>,
+    pub score_sheets: Vec<CupperScoreSheet>,
+}
+
+/// Panel results of a session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionPanel {
+    pub session_id: Uuid,
+    /// Every cupper who scored a sample, head cupper first
+    pub cuppers: Vec<String>,
+    pub samples: Vec<SamplePanel>,
+}
+
+/// Statistics and outliers of a sample's score sheets
+pub fn sample_panel(
+    sample_id: Uuid,
+    sample_number: i32,
+    lot_id: Option<Uuid>,
+    blind_code: Option<String>,
+    consensus_score: Decimal,
+    score_sheets: Vec<CupperScoreSheet>,
+) -> SamplePanel {
+    let mut outliers = Vec::new();
+    let mut flag = |attribute: &'static str, values: &[Decimal], threshold: Decimal| {
+        for (index, deviation) in outlier_deviations(values, threshold) {
+            outliers.push(PanelOutlier {
+                cupper_name: score_sheets[index].cupper_name.clone(),
+                attribute,
+                score: values[index],
+                deviation,
+            });
+        }
+    };
+
+    let per_sheet: Vec<[(&'static str, Decimal); 10]> =
+        score_sheets.iter().map(|s| attribute_scores(&s.scores)).collect();
+    let attributes = (0..10)
+        .map(|i| {
+            let name = per_sheet.first().map(|a| a[i].0).unwrap_or_default();
+            let values: Vec<Decimal> = per_sheet.iter().map(|a| a[i].1).collect();
+            flag(name, &values, ATTRIBUTE_OUTLIER_POINTS);
+            attribute_stats(name, &values)
+        })
+        .collect();
+    let finals: Vec<Decimal> = score_sheets.iter().map(|s| s.final_score).collect();
+    flag("final_score", &finals, FINAL_SCORE_OUTLIER_POINTS);
+
+    SamplePanel {
+        sample_id,
+        sample_number,
+        lot_id,
+        blind_code,
+        cuppers: score_sheets.len(),
+        consensus_score,
+        attributes,
+        final_score: attribute_stats("final_score", &finals),
+        outliers,
+        score_sheets,
+    }
+}
+
+/// Sample of the session with its stored (consensus) scores
+#[derive(Debug, sqlx::FromRow)]
+struct PanelSampleRow {
+    id: Uuid,
+    sample_number: i32,
+    lot_id: Option<Uuid>,
+    blind_code: Option<String>,
+    head_cupper: String,
+    fragrance_aroma: Decimal,
+    flavor: Decimal,
+    aftertaste: Decimal,
+    acidity: Decimal,
+    body: Decimal,
+    balance: Decimal,
+    uniformity: Decimal,
+    clean_cup: Decimal,
+    sweetness: Decimal,
+    overall: Decimal,
+    total_score: Decimal,
+    defects_taint: i32,
+    defects_fault: i32,
+    final_score: Decimal,
+    tasting_notes: Option<String>,
+    tasting_notes_th: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl PanelSampleRow {
+    /// The sample's scores as the head cupper's sheet
+    fn head_sheet(&self) -> CupperScoreSheet {
+        CupperScoreSheet {
+            id: None,
+            sample_id: self.id,
+            cupper_name: self.head_cupper.clone(),
+            scores: CuppingScores {
+                fragrance_aroma: self.fragrance_aroma,
+                flavor: self.flavor,
+                aftertaste: self.aftertaste,
+                acidity: self.acidity,
+                body: self.body,
+                balance: self.balance,
+                uniformity: self.uniformity,
+                clean_cup: self.clean_cup,
+                sweetness: self.sweetness,
+                overall: self.overall,
+            },
+            total_score: self.total_score,
+            defects: CuppingDefects {
+                taint_count: self.defects_taint,
+                fault_count: self.defects_fault,
+            },
+            final_score: self.final_score,
+            tasting_notes: self.tasting_notes.clone(),
+            tasting_notes_th: self.tasting_notes_th.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+const PANEL_SAMPLE_SELECT: &str = r#"
+    SELECT cs.id, cs.sample_number,
+           CASE WHEN s.is_blind AND s.revealed_at IS NULL THEN NULL ELSE cs.lot_id END AS lot_id,
+           cs.blind_code, s.cupper_name AS head_cupper,
+           cs.fragrance_aroma, cs.flavor, cs.aftertaste, cs.acidity, cs.body, cs.balance,
+           cs.uniformity, cs.clean_cup, cs.sweetness, cs.overall, cs.total_score,
+           cs.defects_taint, cs.defects_fault, cs.final_score, cs.tasting_notes, cs.tasting_notes_th,
+           cs.created_at, cs.updated_at
+    FROM cupping_samples cs
+    JOIN cupping_sessions s ON s.id = cs.session_id
+"#;
+
+const SCORE_SHEET_COLUMNS: &str = r#"
+    id, sample_id, cupper_name, fragrance_aroma, flavor, aftertaste, acidity, body, balance,
+    uniformity, clean_cup, sweetness, overall, total_score, defects_taint, defects_fault,
+    final_score, tasting_notes, tasting_notes_th, created_at, updated_at
+"#;
+
+impl CuppingPanelService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a cupper's scores for a sample and update the sample to the
+    /// panel consensus. The first panel sheet on a sample keeps the head
+    /// cupper's original scores as their sheet
+    pub async fn record_scores(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        sample_id: Uuid,
+        input: RecordCupperScoresInput,
+    ) -> AppResult<SamplePanel> {
+        let cupper_name = input.cupper_name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if cupper_name.is_empty() {
+            return Err(AppError::Validation {
+                field: "cupper_name".to_string(),
+                message: "Cupper name is required".to_string(),
+                message_th: "ต้องระบุชื่อผู้ชิม".to_string(),
+            });
+        }
+        CuppingService::validate_scores(&input.scores)?;
+        let sample = self.get_sample(business_id, session_id, sample_id).await?;
+        CuppingService::new(self.db.clone()).ensure_open(session_id).await?;
+        let defects = input.defects.unwrap_or_default();
+        let total_score = input.scores.total();
+        let final_score = total_score - defects.total_deduction();
+
+        let mut tx = self.db.begin().await?;
+
+        let has_sheets = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM cupping_cupper_scores WHERE sample_id = $1)",
+        )
+        .bind(sample_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !has_sheets && cupper_key(&sample.head_cupper) != cupper_key(&cupper_name) {
+            Self::upsert_sheet(&mut tx, &sample.head_sheet()).await?;
+        }
+
+        let now = Utc::now();
+        Self::upsert_sheet(
+            &mut tx,
+            &CupperScoreSheet {
+                id: None,
+                sample_id,
+                cupper_name,
+                scores: input.scores,
+                total_score,
+                defects,
+                final_score,
+                tasting_notes: input.tasting_notes,
+                tasting_notes_th: input.tasting_notes_th,
+                created_at: now,
+                updated_at: now,
+            },
+        )
+        .await?;
+        Self::update_consensus(&mut tx, sample_id).await?;
+        tx.commit().await?;
+
+        self.sample_panel(business_id, session_id, sample_id).await
+    }
+
+    /// Remove a cupper's sheet and update the sample to the remaining
+    /// panel; the last sheet cannot be removed
+    pub async fn delete_scores(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        sample_id: Uuid,
+        sheet_id: Uuid,
+    ) -> AppResult<SamplePanel> {
+        self.get_sample(business_id, session_id, sample_id).await?;
+        CuppingService::new(self.db.clone()).ensure_open(session_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let sheets = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM cupping_cupper_scores WHERE sample_id = $1 FOR UPDATE",
+        )
+        .bind(sample_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if !sheets.contains(&sheet_id) {
+            return Err(AppError::NotFound("Cupper scores".to_string()));
+        }
+        if sheets.len() == 1 {
+            return Err(AppError::Validation {
+                field: "score_id".to_string(),
+                message: "The sample's only score sheet cannot be removed".to_string(),
+                message_th: "ไม่สามารถลบใบคะแนนเดียวของตัวอย่างได้".to_string(),
+            });
+        }
+
+        sqlx::query("DELETE FROM cupping_cupper_scores WHERE id = $1")
+            .bind(sheet_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::update_consensus(&mut tx, sample_id).await?;
+        tx.commit().await?;
+
+        self.sample_panel(business_id, session_id, sample_id).await
+    }
+
+    /// Panel statistics and outliers of every sample in a session
+    pub async fn session_panel(&self, business_id: Uuid, session_id: Uuid) -> AppResult<SessionPanel> {
+        let head_cupper = sqlx::query_scalar::<_, String>(
+            "SELECT cupper_name FROM cupping_sessions WHERE id = $1 AND business_id = $2",
+        )
+        .bind(session_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping session".to_string()))?;
+
+        let samples = sqlx::query_as::<_, PanelSampleRow>(&format!(
+            "{} WHERE cs.session_id = $1 ORDER BY cs.sample_number",
+            PANEL_SAMPLE_SELECT
+        ))
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+        let sample_ids: Vec<Uuid> = samples.iter().map(|s| s.id).collect();
+        let sheets = self.score_sheets(&sample_ids).await?;
+
+        let mut cuppers = vec![head_cupper];
+        let samples: Vec<SamplePanel> = samples
+            .iter()
+            .map(|sample| {
+                let sample_sheets = Self::sheets_or_head(sample, &sheets);
+                for sheet in &sample_sheets {
+                    if !cuppers.iter().any(|c| cupper_key(c) == cupper_key(&sheet.cupper_name)) {
+                        cuppers.push(sheet.cupper_name.clone());
+                    }
+                }
+                sample_panel(
+                    sample.id,
+                    sample.sample_number,
+                    sample.lot_id,
+                    sample.blind_code.clone(),
+                    sample.final_score,
+                    sample_sheets,
+                )
+            })
+            .collect();
+
+        Ok(SessionPanel {
+            session_id,
+            cuppers,
+            samples,
+        })
+    }
+
+    async fn sample_panel(&self, business_id: Uuid, session_id: Uuid, sample_id: Uuid) -> AppResult<SamplePanel> {
+        let sample = self.get_sample(business_id, session_id, sample_id).await?;
+        let sheets = self.score_sheets(&[sample_id]).await?;
+        let sample_sheets = Self::sheets_or_head(&sample, &sheets);
+        Ok(sample_panel(
+            sample.id,
+            sample.sample_number,
+            sample.lot_id,
+            sample.blind_code.clone(),
+            sample.final_score,
+            sample_sheets,
+        ))
+    }
+
+    /// The panel sheets of a sample, or the head cupper's scores alone
+    fn sheets_or_head(sample: &PanelSampleRow, sheets: &[CupperScoreSheet]) -> Vec<CupperScoreSheet> {
+        let own: Vec<CupperScoreSheet> = sheets.iter().filter(|s| s.sample_id == sample.id).cloned().collect();
+        if own.is_empty() {
+            vec![sample.head_sheet()]
+        } else {
+            own
+        }
+    }
+
+    async fn get_sample(&self, business_id: Uuid, session_id: Uuid, sample_id: Uuid) -> AppResult<PanelSampleRow> {
+        sqlx::query_as::<_, PanelSampleRow>(&format!(
+            "{} WHERE cs.id = $1 AND cs.session_id = $2 AND s.business_id = $3",
+            PANEL_SAMPLE_SELECT
+        ))
+        .bind(sample_id)
+        .bind(session_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping sample".to_string()))
+    }
+
+    /// Score sheets of samples, in the order they were first recorded
+    async fn score_sheets(&self, sample_ids: &[Uuid]) -> AppResult<Vec<CupperScoreSheet>> {
+        let rows = sqlx::query_as::<_, ScoreSheetRow>(&format!(
+            "SELECT {} FROM cupping_cupper_scores WHERE sample_id = ANY($1) ORDER BY created_at, cupper_name",
+            SCORE_SHEET_COLUMNS
+        ))
+        .bind(sample_ids)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_sheet).collect())
+    }
+
+    async fn upsert_sheet(tx: &mut Transaction<'_, Postgres>, sheet: &CupperScoreSheet) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cupping_cupper_scores (
+                sample_id, cupper_name, fragrance_aroma, flavor, aftertaste, acidity, body, balance,
+                uniformity, clean_cup, sweetness, overall, total_score, defects_taint, defects_fault,
+                final_score, tasting_notes, tasting_notes_th
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            ON CONFLICT (sample_id, (LOWER(cupper_name))) DO UPDATE
+            SET cupper_name = EXCLUDED.cupper_name,
+                fragrance_aroma = EXCLUDED.fragrance_aroma, flavor = EXCLUDED.flavor,
+                aftertaste = EXCLUDED.aftertaste, acidity = EXCLUDED.acidity, body = EXCLUDED.body,
+                balance = EXCLUDED.balance, uniformity = EXCLUDED.uniformity,
+                clean_cup = EXCLUDED.clean_cup, sweetness = EXCLUDED.sweetness,
+                overall = EXCLUDED.overall, total_score = EXCLUDED.total_score,
+                defects_taint = EXCLUDED.defects_taint, defects_fault = EXCLUDED.defects_fault,
+                final_score = EXCLUDED.final_score, tasting_notes = EXCLUDED.tasting_notes,
+                tasting_notes_th = EXCLUDED.tasting_notes_th
+            "#,
+        )
+        .bind(sheet.sample_id)
+        .bind(&sheet.cupper_name)
+        .bind(sheet.scores.fragrance_aroma)
+        .bind(sheet.scores.flavor)
+        .bind(sheet.scores.aftertaste)
+        .bind(sheet.scores.acidity)
+        .bind(sheet.scores.body)
+        .bind(sheet.scores.balance)
+        .bind(sheet.scores.uniformity)
+        .bind(sheet.scores.clean_cup)
+        .bind(sheet.scores.sweetness)
+        .bind(sheet.scores.overall)
+        .bind(sheet.total_score)
+        .bind(sheet.defects.taint_count)
+        .bind(sheet.defects.fault_count)
+        .bind(sheet.final_score)
+        .bind(&sheet.tasting_notes)
+        .bind(&sheet.tasting_notes_th)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set the sample's scores to the consensus of its panel sheets
+    async fn update_consensus(tx: &mut Transaction<'_, Postgres>, sample_id: Uuid) -> AppResult<()> {
+        let rows = sqlx::query_as::<_, ScoreSheetRow>(&format!(
+            "SELECT {} FROM cupping_cupper_scores WHERE sample_id = $1",
+            SCORE_SHEET_COLUMNS
+        ))
+        .bind(sample_id)
+        .fetch_all(&mut **tx)
+        .await?;
+        let sheets: Vec<(CuppingScores, CuppingDefects)> = rows
+            .into_iter()
+            .map(Self::row_to_sheet)
+            .map(|s| (s.scores, s.defects))
+            .collect();
+        if sheets.is_empty() {
+            return Ok(());
+        }
+
+        let (scores, defects) = consensus(&sheets);
+        let total_score = scores.total();
+        let final_score = total_score - defects.total_deduction();
+        sqlx::query(
+            r#"
+            UPDATE cupping_samples
+            SET fragrance_aroma = $2, flavor = $3, aftertaste = $4, acidity = $5, body = $6,
+                balance = $7, uniformity = $8, clean_cup = $9, sweetness = $10, overall = $11,
+                total_score = $12, defects_taint = $13, defects_fault = $14, final_score = $15
+            WHERE id = $1
+            "#,
+        )
+        .bind(sample_id)
+        .bind(scores.fragrance_aroma)
+        .bind(scores.flavor)
+        .bind(scores.aftertaste)
+        .bind(scores.acidity)
+        .bind(scores.body)
+        .bind(scores.balance)
+        .bind(scores.uniformity)
+        .bind(scores.clean_cup)
+        .bind(scores.sweetness)
+        .bind(scores.overall)
+        .bind(total_score)
+        .bind(defects.taint_count)
+        .bind(defects.fault_count)
+        .bind(final_score)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    fn row_to_sheet(row: ScoreSheetRow) -> CupperScoreSheet {
+        CupperScoreSheet {
+            id: Some(row.id),
+            sample_id: row.sample_id,
+            cupper_name: row.cupper_name,
+            scores: CuppingScores {
+                fragrance_aroma: row.fragrance_aroma,
+                flavor: row.flavor,
+                aftertaste: row.aftertaste,
+                acidity: row.acidity,
+                body: row.body,
+                balance: row.balance,
+                uniformity: row.uniformity,
+                clean_cup: row.clean_cup,
+                sweetness: row.sweetness,
+                overall: row.overall,
+            },
+            total_score: row.total_score,
+            defects: CuppingDefects {
+                taint_count: row.defects_taint,
+                fault_count: row.defects_fault,
+            },
+            final_score: row.final_score,
+            tasting_notes: row.tasting_notes,
+            tasting_notes_th: row.tasting_notes_th,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
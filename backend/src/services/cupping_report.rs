@@ -0,0 +1,314 @@
+//! Cupping session score sheet report
+//!
+//! A4 PDF of a cupping session: the session details, then one block per
+//! sample with its ten attribute scores, a radar chart of them, defects,
+//! final score and classification, flavor descriptors and tasting notes.
+//! Labels are in English or Thai. Samples of a blind session show their
+//! blind code instead of the lot until the session is revealed.
+
+use std::collections::HashMap;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use shared::{DisplayFormat, Language};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::{Config, PdfConfig};
+use crate::error::AppResult;
+use crate::services::cupping::{CuppingSample, CuppingSession};
+use crate::services::cupping_panel::attribute_scores;
+use crate::services::pdf::{line_height, PdfFonts, PdfPage, TextStyle, A4_HEIGHT_MM, A4_WIDTH_MM};
+use crate::services::xlsx_templates::classification_label;
+use crate::services::{BusinessService, CuppingService};
+
+const MARGIN: f32 = 15.0;
+const CONTENT_WIDTH: f32 = A4_WIDTH_MM - 2.0 * MARGIN;
+const BODY_SIZE: f32 = 8.5;
+const SAMPLE_BLOCK_HEIGHT: f32 = 62.0;
+const RADAR_RADIUS: f32 = 20.0;
+
+/// Lowest score on the radar chart (its centre); attributes are scored 6-10
+pub const RADAR_MIN_SCORE: Decimal = Decimal::from_parts(6, 0, 0, false, 0);
+pub const RADAR_MAX_SCORE: Decimal = Decimal::from_parts(10, 0, 0, false, 0);
+
+/// Attribute labels in SCA order: (Thai, English, short English)
+const ATTRIBUTE_LABELS: [(&str, &str, &str); 10] = [
+    ("กลิ่นหอม", "Fragrance/Aroma", "Frag"),
+    ("รสชาติ", "Flavor", "Flav"),
+    ("รสที่ค้าง", "Aftertaste", "Aftr"),
+    ("ความเปรี้ยว", "Acidity", "Acid"),
+    ("บอดี้", "Body", "Body"),
+    ("ความสมดุล", "Balance", "Bal"),
+    ("ความสม่ำเสมอ", "Uniformity", "Unif"),
+    ("ความสะอาด", "Clean Cup", "Clean"),
+    ("ความหวาน", "Sweetness", "Swt"),
+    ("ภาพรวม", "Overall", "Ovr"),
+];
+
+/// Query parameters for the report
+#[derive(Debug, Default, Deserialize)]
+pub struct CuppingReportQuery {
+    pub language: Option<String>, // "en" (default) or "th"
+}
+
+/// Cupping report service
+#[derive(Clone)]
+pub struct CuppingReportService {
+    db: PgPool,
+    pdf: PdfConfig,
+}
+
+/// Share of the radar chart radius a score reaches: 0 at 6 points, 1 at 10
+pub fn radar_value(score: Decimal) -> f32 {
+    let share = (score - RADAR_MIN_SCORE) / (RADAR_MAX_SCORE - RADAR_MIN_SCORE);
+    share.to_f32().unwrap_or(0.0).clamp(0.0, 1.0)
+}
+
+/// Radar values of a sample's attributes in SCA order
+pub fn radar_values(sample: &CuppingSample) -> [f32; 10] {
+    attribute_scores(&sample.scores).map(|(_, score)| radar_value(score))
+}
+
+/// Points of a radar chart centred on (`cx`, `cy`): the first axis points
+/// up and the others follow clockwise
+pub fn radar_points(values: &[f32], cx: f32, cy: f32, radius: f32) -> Vec<(f32, f32)> {
+    let axes = values.len().max(1) as f32;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let angle = std::f32::consts::TAU * i as f32 / axes - std::f32::consts::FRAC_PI_2;
+            (cx + radius * value * angle.cos(), cy + radius * value * angle.sin())
+        })
+        .collect()
+}
+
+impl CuppingReportService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            pdf: config.pdf.clone(),
+        }
+    }
+
+    /// Score sheet PDF of a session. Thai labels are used only when a
+    /// Thai-capable font is configured
+    pub async fn generate(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        query: &CuppingReportQuery,
+    ) -> AppResult<Vec<u8>> {
+        let session = CuppingService::new(self.db.clone())
+            .get_session(business_id, session_id)
+            .await?;
+
+        let lot_ids: Vec<Uuid> = session.samples.iter().filter_map(|s| s.lot_id).collect();
+        let lot_codes: HashMap<Uuid, String> = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, traceability_code FROM lots WHERE id = ANY($1) AND business_id = $2",
+        )
+        .bind(&lot_ids)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .collect();
+
+        let fonts = PdfFonts::load(&self.pdf).await?;
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let thai = query.language.as_deref() == Some("th") && fonts.supports_thai();
+
+        render_report(&session, &lot_codes, &fonts, thai, display)
+    }
+}
+
+fn tr(thai: bool, th: &'static str, en: &'static str) -> &'static str {
+    if thai {
+        th
+    } else {
+        en
+    }
+}
+
+fn render_report(
+    session: &CuppingSession,
+    lot_codes: &HashMap<Uuid, String>,
+    fonts: &PdfFonts,
+    thai: bool,
+    display: DisplayFormat,
+) -> AppResult<Vec<u8>> {
+    let fmt = display.for_language(if thai { &Language::Thai } else { &Language::English });
+    let title = tr(thai, "รายงานผลการคัพปิ้ง", "Cupping Session Report");
+    let mut page = PdfPage::new(title, A4_WIDTH_MM, A4_HEIGHT_MM, fonts)?;
+
+    let mut y = MARGIN + 6.0;
+    page.text(MARGIN, y, TextStyle::bold(16.0), title);
+    y += 7.0;
+
+    let mut details = vec![
+        fmt.date(session.session_date),
+        format!("{}: {}", tr(thai, "ผู้ชิม", "Cupper"), session.cupper_name),
+    ];
+    if let Some(location) = session.location.as_deref().filter(|l| !l.is_empty()) {
+        details.push(format!("{}: {}", tr(thai, "สถานที่", "Location"), location));
+    }
+    if session.blind {
+        details.push(
+            if session.revealed_at.is_some() {
+                tr(thai, "ชิมแบบปิดตา (เปิดเผยแล้ว)", "Blind (revealed)")
+            } else {
+                tr(thai, "ชิมแบบปิดตา", "Blind")
+            }
+            .to_string(),
+        );
+    }
+    page.text(MARGIN, y, TextStyle::regular(BODY_SIZE + 1.0), &details.join("   "));
+    y += line_height(BODY_SIZE + 1.0);
+
+    if !session.samples.is_empty() {
+        let finals: Decimal = session.samples.iter().map(|s| s.final_score).sum();
+        let average = finals / Decimal::from(session.samples.len());
+        page.text(
+            MARGIN,
+            y,
+            TextStyle::regular(BODY_SIZE + 1.0),
+            &format!(
+                "{}: {}   {}: {}",
+                tr(thai, "จำนวนตัวอย่าง", "Samples"),
+                fmt.integer(session.samples.len() as i64),
+                tr(thai, "คะแนนเฉลี่ย", "Average final score"),
+                fmt.decimal(average, 2),
+            ),
+        );
+        y += line_height(BODY_SIZE + 1.0);
+    }
+
+    let notes = if thai { session.notes_th.as_ref().or(session.notes.as_ref()) } else { session.notes.as_ref() };
+    if let Some(notes) = notes.filter(|n| !n.is_empty()) {
+        y += page.wrapped_text(MARGIN, y, CONTENT_WIDTH, TextStyle::regular(BODY_SIZE), notes, 3);
+    }
+    y += 2.0;
+    page.rule(MARGIN, MARGIN + CONTENT_WIDTH, y, 0.6, 0.3);
+    y += 4.0;
+
+    if session.samples.is_empty() {
+        page.text(MARGIN, y + 4.0, TextStyle::regular(BODY_SIZE), tr(thai, "ยังไม่มีตัวอย่าง", "No samples yet"));
+    }
+
+    for sample in &session.samples {
+        if y + SAMPLE_BLOCK_HEIGHT > A4_HEIGHT_MM - MARGIN {
+            page.add_page();
+            y = MARGIN;
+        }
+        render_sample(&page, sample, lot_codes, thai, &fmt, y);
+        y += SAMPLE_BLOCK_HEIGHT;
+        page.rule(MARGIN, MARGIN + CONTENT_WIDTH, y - 2.0, 0.3, 0.7);
+    }
+
+    page.finish()
+}
+
+fn render_sample(
+    page: &PdfPage,
+    sample: &CuppingSample,
+    lot_codes: &HashMap<Uuid, String>,
+    thai: bool,
+    fmt: &DisplayFormat,
+    top: f32,
+) {
+    let mut y = top + 5.0;
+    let identity = match sample.lot_id {
+        Some(lot_id) => lot_codes.get(&lot_id).cloned().unwrap_or_default(),
+        None => format!("{} {}", tr(thai, "รหัส", "Code"), sample.blind_code.clone().unwrap_or_default()),
+    };
+    page.text(
+        MARGIN,
+        y,
+        TextStyle::bold(11.0),
+        &format!("{} {}   {}", tr(thai, "ตัวอย่างที่", "Sample"), fmt.integer(sample.sample_number as i64), identity),
+    );
+
+    // Final score and classification, left of the radar chart
+    let score_x = MARGIN + 95.0;
+    page.text(score_x, y, TextStyle::bold(14.0), &fmt.decimal(sample.final_score, 2));
+    page.text(
+        score_x + 18.0,
+        y,
+        TextStyle::regular(BODY_SIZE),
+        &classification_label(&sample.classification, thai),
+    );
+    y += 6.0;
+
+    // Attribute scores in two columns of five
+    let row_height = line_height(BODY_SIZE) + 0.4;
+    for (i, (_, score)) in attribute_scores(&sample.scores).iter().enumerate() {
+        let (th, en, _) = ATTRIBUTE_LABELS[i];
+        let x = MARGIN + (i / 5) as f32 * 60.0;
+        let row_y = y + (i % 5) as f32 * row_height;
+        page.text(x, row_y, TextStyle::regular(BODY_SIZE), tr(thai, th, en));
+        page.text(x + 36.0, row_y, TextStyle::bold(BODY_SIZE), &fmt.decimal(*score, 2));
+    }
+    y += 5.0 * row_height + 1.0;
+
+    let mut totals = vec![format!("{} {}", tr(thai, "คะแนนรวม", "Total"), fmt.decimal(sample.total_score, 2))];
+    let deduction = sample.defects.total_deduction();
+    if !deduction.is_zero() {
+        totals.push(format!(
+            "{} {} / {} {} (-{})",
+            tr(thai, "ข้อบกพร่อง (taint)", "Taints"),
+            fmt.integer(sample.defects.taint_count as i64),
+            tr(thai, "ข้อบกพร่อง (fault)", "Faults"),
+            fmt.integer(sample.defects.fault_count as i64),
+            fmt.decimal(deduction, 0),
+        ));
+    }
+    if let Some(normalized) = sample.normalized_score {
+        totals.push(format!("{} {}", tr(thai, "คะแนนปรับมาตรฐาน", "Normalized"), fmt.decimal(normalized, 2)));
+    }
+    page.text(MARGIN, y, TextStyle::regular(BODY_SIZE), &totals.join("   "));
+    y += row_height;
+
+    let text_width = 125.0;
+    if !sample.flavor_descriptors.is_empty() {
+        let names: Vec<&str> = sample
+            .flavor_descriptors
+            .iter()
+            .map(|d| if thai { d.name_th.as_str() } else { d.name.as_str() })
+            .collect();
+        let line = format!("{}: {}", tr(thai, "กลิ่นรส", "Flavors"), names.join(", "));
+        y += page.wrapped_text(MARGIN, y, text_width, TextStyle::regular(BODY_SIZE), &line, 2);
+    }
+    let notes = if thai {
+        sample.tasting_notes_th.as_ref().or(sample.tasting_notes.as_ref())
+    } else {
+        sample.tasting_notes.as_ref()
+    };
+    if let Some(notes) = notes.filter(|n| !n.is_empty()) {
+        let line = format!("{}: {}", tr(thai, "บันทึกการชิม", "Notes"), notes);
+        page.wrapped_text(MARGIN, y, text_width, TextStyle::regular(BODY_SIZE), &line, 2);
+    }
+
+    render_radar(page, sample, MARGIN + CONTENT_WIDTH - RADAR_RADIUS - 8.0, top + 31.0);
+}
+
+/// Radar chart with rings at 7, 8, 9 and 10 points
+fn render_radar(page: &PdfPage, sample: &CuppingSample, cx: f32, cy: f32) {
+    for ring in [0.25, 0.5, 0.75, 1.0] {
+        page.polygon(&radar_points(&[ring; 10], cx, cy, RADAR_RADIUS), 0.2, 0.8);
+    }
+    for (x, y) in radar_points(&[1.0; 10], cx, cy, RADAR_RADIUS) {
+        page.polygon(&[(cx, cy), (x, y)], 0.2, 0.8);
+    }
+
+    let points = radar_points(&radar_values(sample), cx, cy, RADAR_RADIUS);
+    page.fill_polygon(&points, 0.8);
+    page.polygon(&points, 0.6, 0.1);
+
+    // Short English labels fit around the chart in either language
+    for (i, (x, y)) in radar_points(&[1.2; 10], cx, cy, RADAR_RADIUS).into_iter().enumerate() {
+        let label = ATTRIBUTE_LABELS[i].2;
+        page.text(x - label.len() as f32 * 0.6, y + 1.0, TextStyle::regular(5.5), label);
+    }
+}
@@ -0,0 +1,203 @@
+//! Triangle (odd-one-out) discrimination tests
+//!
+//! A triangle session checks whether tasters can tell a test lot from a
+//! control lot, e.g. to validate that a process change is detectable. Each
+//! set serves three coded cups, two of one lot and one of the other, in one
+//! of the six balanced serving orders of ISO 4120. Tasters must pick the odd
+//! cup, so a guess is right one time in three; the number of correct answers
+//! is tested against that chance with a one-sided binomial test.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Cups served in a triangle set
+pub const TRIANGLE_CUPS: usize = 3;
+
+/// Chance of picking the odd cup by guessing
+pub const GUESS_PROBABILITY: f64 = 1.0 / 3.0;
+
+/// Significance level used when none is given
+pub const DEFAULT_SIGNIFICANCE_LEVEL: Decimal = Decimal::from_parts(5, 0, 0, false, 2);
+
+/// Most sets a triangle session can serve
+pub const MAX_TRIANGLE_SETS: i32 = 60;
+
+/// Serving orders, as (odd cup position 1-3, odd cup is the test lot):
+/// AAB, ABA, BAA, BBA, BAB, ABB with A the control lot
+const ARRANGEMENTS: [(i32, bool); 6] = [(3, true), (2, true), (1, true), (3, false), (2, false), (1, false)];
+
+/// Input for the triangle test of a new triangle session
+#[derive(Debug, Deserialize)]
+pub struct CreateTriangleTestInput {
+    pub control_lot_id: Uuid,
+    pub test_lot_id: Uuid,
+    /// Sets to serve, 1-60; a multiple of 6 balances the serving orders
+    pub sets: i32,
+    /// Alpha to test at (default 0.05)
+    pub significance_level: Option<Decimal>,
+}
+
+/// Input for a taster's answer on one set
+#[derive(Debug, Deserialize)]
+pub struct RecordTriangleAnswerInput {
+    pub set_number: i32,
+    pub taster_name: String,
+    /// Code of the cup the taster picked as the odd one
+    pub chosen_code: String,
+    pub comments: Option<String>,
+}
+
+/// Serving order of a set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriangleArrangement {
+    /// Position (1-3) of the odd cup
+    pub odd_position: i32,
+    /// Whether the odd cup is the test lot
+    pub odd_is_test: bool,
+}
+
+/// Set as served to tasters, without the answer
+#[derive(Debug, Serialize)]
+pub struct TriangleSet {
+    pub set_number: i32,
+    /// Cup codes in serving order
+    pub cup_codes: Vec<String>,
+    pub answers: i64,
+}
+
+/// Triangle test of a session as served
+#[derive(Debug, Serialize)]
+pub struct TriangleTest {
+    pub session_id: Uuid,
+    pub control_lot_id: Uuid,
+    pub test_lot_id: Uuid,
+    pub significance_level: Decimal,
+    pub sets: Vec<TriangleSet>,
+}
+
+/// A taster's answer on one set
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TriangleAnswer {
+    pub id: Uuid,
+    pub set_number: i32,
+    pub taster_name: String,
+    pub chosen_code: String,
+    pub correct: bool,
+    pub comments: Option<String>,
+    pub recorded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of the binomial test
+#[derive(Debug, Clone, Serialize)]
+pub struct TriangleSummary {
+    pub answers: i64,
+    pub correct: i64,
+    /// Share of correct answers, 0-1
+    pub proportion_correct: f64,
+    /// Chance of at least this many correct answers by guessing alone
+    pub p_value: f64,
+    pub significance_level: Decimal,
+    /// Correct answers needed for significance; None if even all correct
+    /// would not be enough
+    pub min_correct_for_significance: Option<i64>,
+    /// Whether the lots are detectably different
+    pub significant: bool,
+    /// Estimated share of tasters who really tell the lots apart, 0-100
+    pub discriminators_percent: f64,
+}
+
+/// One set with its answer key and answers
+#[derive(Debug, Serialize)]
+pub struct TriangleSetResult {
+    pub set_number: i32,
+    pub cup_codes: Vec<String>,
+    pub odd_code: String,
+    pub odd_is_test: bool,
+    pub answers: i64,
+    pub correct: i64,
+}
+
+/// Answers of one taster
+#[derive(Debug, Serialize)]
+pub struct TasterTriangleResult {
+    pub taster_name: String,
+    pub answers: i64,
+    pub correct: i64,
+}
+
+/// Results of a triangle session
+#[derive(Debug, Serialize)]
+pub struct TriangleResults {
+    pub session_id: Uuid,
+    pub control_lot_id: Uuid,
+    pub test_lot_id: Uuid,
+    pub summary: TriangleSummary,
+    pub sets: Vec<TriangleSetResult>,
+    pub tasters: Vec<TasterTriangleResult>,
+    pub answers: Vec<TriangleAnswer>,
+}
+
+/// Serving order of a set, cycling through the six balanced orders
+pub fn triangle_arrangement(set_number: i32) -> TriangleArrangement {
+    let (odd_position, odd_is_test) = ARRANGEMENTS[(set_number - 1).rem_euclid(ARRANGEMENTS.len() as i32) as usize];
+    TriangleArrangement {
+        odd_position,
+        odd_is_test,
+    }
+}
+
+/// One-sided binomial p-value: chance of `correct` or more right answers
+/// out of `total` by guessing
+pub fn binomial_p_value(correct: i64, total: i64) -> f64 {
+    if total <= 0 || correct <= 0 {
+        return 1.0;
+    }
+    if correct > total {
+        return 0.0;
+    }
+    let (ln_p, ln_q) = (GUESS_PROBABILITY.ln(), (1.0 - GUESS_PROBABILITY).ln());
+    // ln C(total, k), advanced term by term
+    let mut ln_choose = 0.0;
+    let mut p_value = 0.0;
+    for k in 0..=total {
+        if k >= correct {
+            p_value += (ln_choose + k as f64 * ln_p + (total - k) as f64 * ln_q).exp();
+        }
+        ln_choose += ((total - k) as f64).ln() - ((k + 1) as f64).ln();
+    }
+    p_value.min(1.0)
+}
+
+/// Fewest correct answers out of `total` significant at `alpha`
+pub fn min_correct_for_significance(total: i64, alpha: f64) -> Option<i64> {
+    (1..=total).find(|&correct| binomial_p_value(correct, total) <= alpha)
+}
+
+/// Estimated share (0-1) of tasters who discriminate rather than guess:
+/// correct = discriminators + (1 - discriminators) / 3
+pub fn discriminator_proportion(correct: i64, total: i64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+    (1.5 * correct as f64 / total as f64 - 0.5).clamp(0.0, 1.0)
+}
+
+/// Binomial test of a session's answers
+pub fn triangle_summary(correct: i64, total: i64, significance_level: Decimal) -> TriangleSummary {
+    let alpha = significance_level.to_f64().unwrap_or(0.05);
+    let p_value = binomial_p_value(correct, total);
+    TriangleSummary {
+        answers: total,
+        correct,
+        proportion_correct: if total > 0 { correct as f64 / total as f64 } else { 0.0 },
+        p_value,
+        significance_level,
+        min_correct_for_significance: min_correct_for_significance(total, alpha),
+        significant: total > 0 && p_value <= alpha,
+        discriminators_percent: (discriminator_proportion(correct, total) * 1000.0).round() / 10.0,
+    }
+}
@@ -0,0 +1,319 @@
+//! Record completeness (data health) scoring for lots
+//!
+//! Buyers judge a lot by how complete its traceability record is. Each lot
+//! is scored on the records expected at its stage: harvest and weather,
+//! photos and certifications from the start, processing once it is
+//! parchment, grading and cupping once it is green. Blended lots count the
+//! records of their source lots. The dashboard lists the gaps that cost the
+//! most points across unsold lots.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::lot::LotStage;
+
+/// Lots listed on the dashboard when no limit is given
+pub const DEFAULT_DASHBOARD_LOTS: i64 = 20;
+
+/// Data quality service
+#[derive(Clone)]
+pub struct DataQualityService {
+    db: PgPool,
+}
+
+/// A record a complete lot has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletenessCheck {
+    Harvest,
+    /// Every harvest has a weather snapshot
+    WeatherLinked,
+    /// Photos of the plot, harvest, processing or grading
+    Photos,
+    Certification,
+    Processing,
+    Grading,
+    Cupping,
+}
+
+impl CompletenessCheck {
+    pub const ALL: [CompletenessCheck; 7] = [
+        CompletenessCheck::Harvest,
+        CompletenessCheck::WeatherLinked,
+        CompletenessCheck::Photos,
+        CompletenessCheck::Certification,
+        CompletenessCheck::Processing,
+        CompletenessCheck::Grading,
+        CompletenessCheck::Cupping,
+    ];
+
+    /// Points the check is worth out of 100 for a green lot
+    pub fn weight(&self) -> i32 {
+        match self {
+            CompletenessCheck::Harvest => 20,
+            CompletenessCheck::WeatherLinked => 10,
+            CompletenessCheck::Photos => 10,
+            CompletenessCheck::Certification => 10,
+            CompletenessCheck::Processing => 15,
+            CompletenessCheck::Grading => 15,
+            CompletenessCheck::Cupping => 20,
+        }
+    }
+
+    /// Whether the record is expected of a lot at `stage`
+    pub fn applies_to(&self, stage: LotStage) -> bool {
+        match self {
+            CompletenessCheck::Processing => stage >= LotStage::Parchment,
+            CompletenessCheck::Grading | CompletenessCheck::Cupping => stage >= LotStage::GreenBean,
+            _ => true,
+        }
+    }
+
+    pub fn message(&self) -> (&'static str, &'static str) {
+        match self {
+            CompletenessCheck::Harvest => ("Record the harvest", "บันทึกการเก็บเกี่ยว"),
+            CompletenessCheck::WeatherLinked => {
+                ("Link weather to every harvest", "เชื่อมข้อมูลสภาพอากาศกับทุกการเก็บเกี่ยว")
+            }
+            CompletenessCheck::Photos => ("Add photos", "เพิ่มรูปภาพ"),
+            CompletenessCheck::Certification => ("Add a valid certification", "เพิ่มใบรับรองที่ยังไม่หมดอายุ"),
+            CompletenessCheck::Processing => ("Record processing", "บันทึกการแปรรูป"),
+            CompletenessCheck::Grading => ("Grade the green coffee", "คัดเกรดสารกาแฟ"),
+            CompletenessCheck::Cupping => ("Cup the lot", "คัปปิ้งล็อตนี้"),
+        }
+    }
+}
+
+/// Score (0-100) and missing records for a lot at `stage` holding `present`
+pub fn score_lot(stage: LotStage, present: &[CompletenessCheck]) -> (i32, Vec<CompletenessCheck>) {
+    let applicable: Vec<CompletenessCheck> = CompletenessCheck::ALL
+        .into_iter()
+        .filter(|check| check.applies_to(stage))
+        .collect();
+    let possible: i32 = applicable.iter().map(|c| c.weight()).sum();
+    let earned: i32 = applicable.iter().filter(|c| present.contains(c)).map(|c| c.weight()).sum();
+    let missing = applicable.into_iter().filter(|c| !present.contains(c)).collect();
+    let score = if possible > 0 {
+        (earned * 100 + possible / 2) / possible
+    } else {
+        100
+    };
+    (score, missing)
+}
+
+/// A missing record with what to do about it
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletenessGap {
+    pub check: CompletenessCheck,
+    /// Points gained by completing it
+    pub points: i32,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Completeness of one lot
+#[derive(Debug, Clone, Serialize)]
+pub struct LotDataQuality {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub stage: LotStage,
+    pub score: i32,
+    pub complete: Vec<CompletenessCheck>,
+    /// Largest gains first
+    pub gaps: Vec<CompletenessGap>,
+}
+
+/// How often a record is missing across lots
+#[derive(Debug, Clone, Serialize)]
+pub struct GapSummary {
+    pub check: CompletenessCheck,
+    pub lots_missing: usize,
+    /// Points the business average would gain if every lot completed it
+    pub average_points: f64,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Data health dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct DataQualityDashboard {
+    /// Average score of unsold lots
+    pub average_score: i32,
+    pub lots_scored: usize,
+    pub complete_lots: usize,
+    /// Biggest gaps across the business first
+    pub gaps: Vec<GapSummary>,
+    /// Least complete lots first
+    pub lots: Vec<LotDataQuality>,
+}
+
+/// Dashboard query
+#[derive(Debug, Default, Deserialize)]
+pub struct DataQualityQuery {
+    /// Lots to list (default 20)
+    pub limit: Option<i64>,
+}
+
+/// Present records per lot: (id, code, name, stage, harvest, weather,
+/// photos, certification, processing, grading, cupping)
+type CompletenessRow = (Uuid, String, String, LotStage, bool, bool, bool, bool, bool, bool, bool);
+
+const COMPLETENESS_SQL: &str = r#"
+    WITH family AS (
+        SELECT l.id AS lot_id, l.id AS member_id FROM lots l WHERE l.business_id = $1
+        UNION
+        SELECT s.lot_id, s.source_lot_id FROM lot_sources s
+        JOIN lots l ON l.id = s.lot_id WHERE l.business_id = $1
+    ),
+    lot_harvests AS (
+        SELECT f.lot_id, h.id AS harvest_id, h.plot_id,
+               (h.weather_snapshot IS NOT NULL OR h.weather_snapshot_id IS NOT NULL) AS has_weather
+        FROM family f JOIN harvests h ON h.lot_id = f.member_id
+    )
+    SELECT l.id, l.traceability_code, l.name, l.stage,
+           EXISTS(SELECT 1 FROM lot_harvests lh WHERE lh.lot_id = l.id),
+           EXISTS(SELECT 1 FROM lot_harvests lh WHERE lh.lot_id = l.id)
+               AND NOT EXISTS(SELECT 1 FROM lot_harvests lh WHERE lh.lot_id = l.id AND NOT lh.has_weather),
+           EXISTS(
+               SELECT 1 FROM media m
+               WHERE m.business_id = $1 AND (
+                   (m.entity_type = 'harvest' AND m.entity_id IN
+                       (SELECT harvest_id FROM lot_harvests lh WHERE lh.lot_id = l.id))
+                   OR (m.entity_type = 'plot' AND m.entity_id IN
+                       (SELECT plot_id FROM lot_harvests lh WHERE lh.lot_id = l.id))
+                   OR (m.entity_type = 'processing' AND m.entity_id IN
+                       (SELECT pr.id FROM processing_records pr JOIN family f ON f.member_id = pr.lot_id
+                        WHERE f.lot_id = l.id))
+                   OR (m.entity_type = 'grading' AND m.entity_id IN
+                       (SELECT g.id FROM green_bean_grades g JOIN family f ON f.member_id = g.lot_id
+                        WHERE f.lot_id = l.id))
+               )
+           ),
+           EXISTS(
+               SELECT 1 FROM certifications c
+               WHERE c.business_id = $1 AND c.is_active AND c.expiration_date >= CURRENT_DATE
+                 AND (c.scope <> 'plot' OR c.plot_id IN
+                     (SELECT plot_id FROM lot_harvests lh WHERE lh.lot_id = l.id))
+           ),
+           EXISTS(SELECT 1 FROM processing_records pr JOIN family f ON f.member_id = pr.lot_id
+                  WHERE f.lot_id = l.id),
+           EXISTS(SELECT 1 FROM green_bean_grades g JOIN family f ON f.member_id = g.lot_id
+                  WHERE f.lot_id = l.id),
+           EXISTS(SELECT 1 FROM cupping_samples cs JOIN family f ON f.member_id = cs.lot_id
+                  WHERE f.lot_id = l.id)
+    FROM lots l
+    WHERE l.business_id = $1
+"#;
+
+fn row_to_quality(row: CompletenessRow) -> LotDataQuality {
+    let (lot_id, traceability_code, name, stage, harvest, weather, photos, certification, processing, grading, cupping) =
+        row;
+    let flags = [harvest, weather, photos, certification, processing, grading, cupping];
+    let present: Vec<CompletenessCheck> = CompletenessCheck::ALL
+        .into_iter()
+        .zip(flags)
+        .filter(|(_, present)| *present)
+        .map(|(check, _)| check)
+        .collect();
+    let (score, missing) = score_lot(stage, &present);
+
+    let possible: i32 = CompletenessCheck::ALL
+        .iter()
+        .filter(|c| c.applies_to(stage))
+        .map(|c| c.weight())
+        .sum();
+    let mut gaps: Vec<CompletenessGap> = missing
+        .into_iter()
+        .map(|check| {
+            let (message, message_th) = check.message();
+            CompletenessGap {
+                check,
+                points: check.weight() * 100 / possible.max(1),
+                message: message.to_string(),
+                message_th: message_th.to_string(),
+            }
+        })
+        .collect();
+    gaps.sort_by_key(|g| std::cmp::Reverse(g.points));
+
+    LotDataQuality {
+        lot_id,
+        traceability_code,
+        name,
+        stage,
+        score,
+        complete: present,
+        gaps,
+    }
+}
+
+impl DataQualityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Completeness of one lot
+    pub async fn get_lot(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<LotDataQuality> {
+        let row = sqlx::query_as::<_, CompletenessRow>(&format!("{} AND l.id = $2", COMPLETENESS_SQL))
+            .bind(business_id)
+            .bind(lot_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        Ok(row_to_quality(row))
+    }
+
+    /// Data health across unsold lots with the biggest gaps first
+    pub async fn get_dashboard(&self, business_id: Uuid, query: &DataQualityQuery) -> AppResult<DataQualityDashboard> {
+        let rows = sqlx::query_as::<_, CompletenessRow>(&format!("{} AND l.stage <> 'sold'", COMPLETENESS_SQL))
+            .bind(business_id)
+            .fetch_all(&self.db)
+            .await?;
+
+        let mut lots: Vec<LotDataQuality> = rows.into_iter().map(row_to_quality).collect();
+        let lots_scored = lots.len();
+        let average_score = if lots.is_empty() {
+            100
+        } else {
+            (lots.iter().map(|l| l.score as f64).sum::<f64>() / lots_scored as f64).round() as i32
+        };
+        let complete_lots = lots.iter().filter(|l| l.gaps.is_empty()).count();
+
+        let mut gaps: Vec<GapSummary> = CompletenessCheck::ALL
+            .into_iter()
+            .filter_map(|check| {
+                let missing: Vec<i32> = lots
+                    .iter()
+                    .filter_map(|l| l.gaps.iter().find(|g| g.check == check).map(|g| g.points))
+                    .collect();
+                if missing.is_empty() {
+                    return None;
+                }
+                let (message, message_th) = check.message();
+                Some(GapSummary {
+                    check,
+                    lots_missing: missing.len(),
+                    average_points: (missing.iter().sum::<i32>() as f64 / lots_scored as f64 * 10.0).round() / 10.0,
+                    message: message.to_string(),
+                    message_th: message_th.to_string(),
+                })
+            })
+            .collect();
+        gaps.sort_by(|a, b| b.average_points.total_cmp(&a.average_points));
+
+        lots.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.traceability_code.cmp(&b.traceability_code)));
+        lots.truncate(query.limit.unwrap_or(DEFAULT_DASHBOARD_LOTS).clamp(1, 500) as usize);
+
+        Ok(DataQualityDashboard {
+            average_score,
+            lots_scored,
+            complete_lots,
+            gaps,
+            lots,
+        })
+    }
+}
@@ -0,0 +1,487 @@
+//! Farm activity log
+//!
+//! Records field work per plot. Fertilizer and pesticide applications name
+//! the product used; while the business holds an active organic
+//! certification covering the plot on the activity date, the product is
+//! checked against the allowed-substances list and a product not on it is
+//! logged as a compliance issue on each such certification.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+
+/// Activity types that apply a product to the plot
+pub const INPUT_ACTIVITY_TYPES: [&str; 2] = ["fertilizer", "pesticide"];
+
+/// All recognised activity types
+pub const ACTIVITY_TYPES: [&str; 5] = ["fertilizer", "pesticide", "pruning", "weeding", "other"];
+
+/// Farm activity service
+#[derive(Clone)]
+pub struct FarmActivityService {
+    db: PgPool,
+}
+
+/// Farm activity record
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FarmActivity {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    pub activity_type: String,
+    pub activity_date: NaiveDate,
+    pub product_name: Option<String>,
+    pub quantity: Option<Decimal>,
+    pub unit: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Compliance issues this activity raised
+    pub compliance_issues: i64,
+}
+
+/// Farm activity with the organic violations found when it was recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedFarmActivity {
+    #[serde(flatten)]
+    pub activity: FarmActivity,
+    pub organic_violations: Vec<OrganicViolation>,
+}
+
+/// Product not on the allowed list, logged against a certification
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganicViolation {
+    pub issue_id: Uuid,
+    pub certification_id: Uuid,
+    pub certification_name: String,
+    pub requirement_code: Option<String>,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Input for recording a farm activity
+#[derive(Debug, Deserialize)]
+pub struct RecordFarmActivityInput {
+    pub plot_id: Uuid,
+    pub activity_type: String,
+    pub activity_date: NaiveDate,
+    pub product_name: Option<String>,
+    pub quantity: Option<Decimal>,
+    pub unit: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing farm activities
+#[derive(Debug, Default, Deserialize)]
+pub struct FarmActivityQuery {
+    pub plot_id: Option<Uuid>,
+    pub activity_type: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<i64>,
+}
+
+/// Substance allowed under organic certification
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OrganicAllowedInput {
+    pub id: Uuid,
+    /// None for the default list
+    pub business_id: Option<Uuid>,
+    pub input_type: String,
+    pub substance: String,
+    pub substance_th: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Input for adding a product the certifier approved
+#[derive(Debug, Deserialize)]
+pub struct AddOrganicAllowedInput {
+    pub input_type: String,
+    pub substance: String,
+    pub substance_th: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Organic certification covering a plot on a date
+#[derive(Debug, sqlx::FromRow)]
+struct HeldOrganicCertification {
+    id: Uuid,
+    certification_name: String,
+    requirement_id: Option<Uuid>,
+    requirement_code: Option<String>,
+}
+
+/// Lowercase with single spaces, for comparing product names
+pub fn normalize_substance(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Whether a product matches an allowed substance of the same input type,
+/// by English or Thai name
+pub fn is_allowed_input(input_type: &str, product_name: &str, allowed: &[OrganicAllowedInput]) -> bool {
+    let product = normalize_substance(product_name);
+    allowed.iter().filter(|a| a.input_type == input_type).any(|a| {
+        normalize_substance(&a.substance) == product
+            || a.substance_th.as_deref().is_some_and(|th| normalize_substance(th) == product)
+    })
+}
+
+/// Organic Thailand requirement a non-organic input breaks
+pub fn organic_requirement_code(input_type: &str) -> Option<&'static str> {
+    match input_type {
+        "pesticide" => Some("OT-01"),
+        "fertilizer" => Some("OT-02"),
+        _ => None,
+    }
+}
+
+fn validate_input_type(field: &str, value: &str, allowed: &[&str]) -> AppResult<()> {
+    if allowed.contains(&value) {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        field: field.to_string(),
+        message: format!("Must be one of: {}", allowed.join(", ")),
+        message_th: format!("ต้องเป็นหนึ่งใน: {}", allowed.join(", ")),
+    })
+}
+
+impl FarmActivityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a farm activity; fertilizer and pesticide products are checked
+    /// against the allowed list while an organic certification covers the plot
+    pub async fn record(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordFarmActivityInput,
+    ) -> AppResult<RecordedFarmActivity> {
+        validate_input_type("activity_type", &input.activity_type, &ACTIVITY_TYPES)?;
+        let applies_input = INPUT_ACTIVITY_TYPES.contains(&input.activity_type.as_str());
+        let product_name = input
+            .product_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty());
+        if applies_input && product_name.is_none() {
+            return Err(AppError::Validation {
+                field: "product_name".to_string(),
+                message: "Product name is required for fertilizer and pesticide applications".to_string(),
+                message_th: "ต้องระบุชื่อผลิตภัณฑ์สำหรับการใส่ปุ๋ยและการใช้สารกำจัดศัตรูพืช".to_string(),
+            });
+        }
+        if input.quantity.is_some_and(|q| q <= Decimal::ZERO) {
+            return Err(AppError::Validation {
+                field: "quantity".to_string(),
+                message: "Quantity must be greater than 0".to_string(),
+                message_th: "ปริมาณต้องมากกว่า 0".to_string(),
+            });
+        }
+
+        let plot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM plots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(input.plot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !plot_exists {
+            return Err(AppError::NotFound("Plot".to_string()));
+        }
+
+        let mut tx = self.db.begin().await?;
+        let activity_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO farm_activities (
+                business_id, plot_id, activity_type, activity_date,
+                product_name, quantity, unit, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.plot_id)
+        .bind(&input.activity_type)
+        .bind(input.activity_date)
+        .bind(product_name)
+        .bind(input.quantity)
+        .bind(&input.unit)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let organic_violations = match product_name {
+            Some(product) if applies_input => {
+                self.check_organic_input(&mut tx, business_id, activity_id, &input, product)
+                    .await?
+            }
+            _ => Vec::new(),
+        };
+        tx.commit().await?;
+
+        let activity = self.get(business_id, activity_id).await?;
+        Ok(RecordedFarmActivity {
+            activity,
+            organic_violations,
+        })
+    }
+
+    /// Log a compliance issue on every organic certification covering the
+    /// plot when the product is not on the allowed list
+    async fn check_organic_input(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        business_id: Uuid,
+        activity_id: Uuid,
+        input: &RecordFarmActivityInput,
+        product: &str,
+    ) -> AppResult<Vec<OrganicViolation>> {
+        let requirement_code = organic_requirement_code(&input.activity_type);
+        let certifications = sqlx::query_as::<_, HeldOrganicCertification>(
+            r#"
+            SELECT c.id, c.certification_name, r.id AS requirement_id, r.requirement_code
+            FROM certifications c
+            LEFT JOIN certification_requirements r
+                ON r.certification_type = c.certification_type AND r.requirement_code = $4
+            WHERE c.business_id = $1
+              AND c.is_active = true
+              AND c.certification_type IN ('organic_thailand', 'usda_organic')
+              AND $3 BETWEEN c.issue_date AND c.expiration_date
+              AND (c.scope <> 'plot' OR c.plot_id = $2)
+            ORDER BY c.certification_name
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.plot_id)
+        .bind(input.activity_date)
+        .bind(requirement_code)
+        .fetch_all(&mut **tx)
+        .await?;
+        if certifications.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let allowed = self.allowed_inputs(&mut **tx, business_id).await?;
+        if is_allowed_input(&input.activity_type, product, &allowed) {
+            return Ok(Vec::new());
+        }
+
+        let (kind, kind_th) = match input.activity_type.as_str() {
+            "pesticide" => ("Pesticide", "สารกำจัดศัตรูพืช"),
+            _ => ("Fertilizer", "ปุ๋ย"),
+        };
+        let message = format!(
+            "{} \"{}\" applied on {} is not on the organic allowed-substances list",
+            kind, product, input.activity_date
+        );
+        let message_th = format!(
+            "{} \"{}\" ที่ใช้เมื่อ {} ไม่อยู่ในรายการสารที่อนุญาตสำหรับเกษตรอินทรีย์",
+            kind_th, product, input.activity_date
+        );
+
+        let mut violations = Vec::with_capacity(certifications.len());
+        for certification in certifications {
+            let issue_id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO certification_compliance_issues (
+                    certification_id, requirement_id, farm_activity_id, description, description_th
+                )
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id
+                "#,
+            )
+            .bind(certification.id)
+            .bind(certification.requirement_id)
+            .bind(activity_id)
+            .bind(&message)
+            .bind(&message_th)
+            .fetch_one(&mut **tx)
+            .await?;
+
+            // The broken requirement is no longer met until reviewed
+            if let Some(requirement_id) = certification.requirement_id {
+                sqlx::query(
+                    r#"
+                    INSERT INTO certification_compliance (
+                        certification_id, requirement_id, is_compliant, compliance_notes
+                    )
+                    VALUES ($1, $2, false, $3)
+                    ON CONFLICT (certification_id, requirement_id) DO UPDATE SET
+                        is_compliant = false,
+                        compliance_notes = $3,
+                        updated_at = NOW()
+                    "#,
+                )
+                .bind(certification.id)
+                .bind(requirement_id)
+                .bind(&message)
+                .execute(&mut **tx)
+                .await?;
+            }
+
+            violations.push(OrganicViolation {
+                issue_id,
+                certification_id: certification.id,
+                certification_name: certification.certification_name,
+                requirement_code: certification.requirement_code,
+                message: message.clone(),
+                message_th: message_th.clone(),
+            });
+        }
+        Ok(violations)
+    }
+
+    /// Get a farm activity
+    pub async fn get(&self, business_id: Uuid, activity_id: Uuid) -> AppResult<FarmActivity> {
+        sqlx::query_as::<_, FarmActivity>(
+            r#"
+            SELECT a.id, a.business_id, a.plot_id, p.name AS plot_name, a.activity_type,
+                   a.activity_date, a.product_name, a.quantity, a.unit, a.notes,
+                   a.created_by, a.created_at,
+                   (SELECT COUNT(*) FROM certification_compliance_issues i
+                    WHERE i.farm_activity_id = a.id) AS compliance_issues
+            FROM farm_activities a
+            JOIN plots p ON p.id = a.plot_id
+            WHERE a.id = $1 AND a.business_id = $2
+            "#,
+        )
+        .bind(activity_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Farm activity".to_string()))
+    }
+
+    /// List farm activities, newest first
+    pub async fn list(&self, business_id: Uuid, query: &FarmActivityQuery) -> AppResult<Vec<FarmActivity>> {
+        let activities = sqlx::query_as::<_, FarmActivity>(
+            r#"
+            SELECT a.id, a.business_id, a.plot_id, p.name AS plot_name, a.activity_type,
+                   a.activity_date, a.product_name, a.quantity, a.unit, a.notes,
+                   a.created_by, a.created_at,
+                   (SELECT COUNT(*) FROM certification_compliance_issues i
+                    WHERE i.farm_activity_id = a.id) AS compliance_issues
+            FROM farm_activities a
+            JOIN plots p ON p.id = a.plot_id
+            WHERE a.business_id = $1
+              AND ($2::UUID IS NULL OR a.plot_id = $2)
+              AND ($3::TEXT IS NULL OR a.activity_type = $3)
+              AND ($4::DATE IS NULL OR a.activity_date >= $4)
+              AND ($5::DATE IS NULL OR a.activity_date <= $5)
+            ORDER BY a.activity_date DESC, a.created_at DESC
+            LIMIT $6
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.plot_id)
+        .bind(&query.activity_type)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(activities)
+    }
+
+    /// Delete a farm activity into the recycle bin; compliance issues it
+    /// raised are kept
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, activity_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::FarmActivity, activity_id)
+            .await
+    }
+
+    /// Default allowed substances plus the business's approved products
+    pub async fn list_allowed_inputs(&self, business_id: Uuid) -> AppResult<Vec<OrganicAllowedInput>> {
+        self.allowed_inputs(&self.db, business_id).await
+    }
+
+    async fn allowed_inputs<'e, E>(&self, executor: E, business_id: Uuid) -> AppResult<Vec<OrganicAllowedInput>>
+    where
+        E: sqlx::Executor<'e, Database = Postgres>,
+    {
+        let allowed = sqlx::query_as::<_, OrganicAllowedInput>(
+            r#"
+            SELECT id, business_id, input_type, substance, substance_th, notes
+            FROM organic_allowed_inputs
+            WHERE business_id IS NULL OR business_id = $1
+            ORDER BY input_type, business_id NULLS FIRST, substance
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(executor)
+        .await?;
+
+        Ok(allowed)
+    }
+
+    /// Add a product the business's certifier approved
+    pub async fn add_allowed_input(
+        &self,
+        business_id: Uuid,
+        input: AddOrganicAllowedInput,
+    ) -> AppResult<OrganicAllowedInput> {
+        validate_input_type("input_type", &input.input_type, &INPUT_ACTIVITY_TYPES)?;
+        let substance = input.substance.split_whitespace().collect::<Vec<_>>().join(" ");
+        if substance.is_empty() {
+            return Err(AppError::Validation {
+                field: "substance".to_string(),
+                message: "Substance name is required".to_string(),
+                message_th: "ต้องระบุชื่อสาร".to_string(),
+            });
+        }
+
+        let existing = self.list_allowed_inputs(business_id).await?;
+        if is_allowed_input(&input.input_type, &substance, &existing) {
+            return Err(AppError::Conflict {
+                resource: "organic_allowed_input".to_string(),
+                message: format!("{} is already on the allowed list", substance),
+                message_th: format!("{} อยู่ในรายการที่อนุญาตแล้ว", substance),
+            });
+        }
+
+        let allowed = sqlx::query_as::<_, OrganicAllowedInput>(
+            r#"
+            INSERT INTO organic_allowed_inputs (business_id, input_type, substance, substance_th, notes)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, business_id, input_type, substance, substance_th, notes
+            "#,
+        )
+        .bind(business_id)
+        .bind(&input.input_type)
+        .bind(&substance)
+        .bind(input.substance_th.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+        .bind(&input.notes)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(allowed)
+    }
+
+    /// Remove one of the business's approved products; the default list
+    /// cannot be changed
+    pub async fn delete_allowed_input(&self, business_id: Uuid, allowed_input_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM organic_allowed_inputs WHERE id = $1 AND business_id = $2")
+            .bind(allowed_input_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Allowed input".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,711 @@
+//! Thai GAP submission export
+//!
+//! Assembles what a Department of Agriculture GAP application asks for from
+//! the records already kept: the applicant, every production plot (location,
+//! area, varieties, planting and tree counts), the season's harvest and
+//! post-harvest records, water tests and the Thai GAP practice checklist.
+//! The same sections make up the XLSX workbook and the PDF, followed by a
+//! list of what is still missing so extension officers can follow up before
+//! filing.
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use shared::{DisplayFormat, Language};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::{Config, PdfConfig};
+use crate::error::{AppError, AppResult};
+use crate::services::pdf::{chars_per_line, line_height, wrap_text, PdfFonts, PdfPage, TextStyle};
+use crate::services::reporting_period::resolve_season;
+use crate::services::xlsx::{display_width, XlsxCell, XlsxSheet, XlsxWorkbook};
+use crate::services::BusinessService;
+
+/// A4 landscape, which fits the plot and harvest tables
+const PAGE_WIDTH: f32 = 297.0;
+const PAGE_HEIGHT: f32 = 210.0;
+const MARGIN: f32 = 14.0;
+const BODY_SIZE: f32 = 8.0;
+/// Widest a PDF column gets before its text wraps, in characters
+const MAX_PDF_COLUMN_CHARS: usize = 40;
+
+/// GAP export service
+#[derive(Clone)]
+pub struct GapExportService {
+    db: PgPool,
+    pdf: PdfConfig,
+}
+
+/// Query parameters for the submission export
+#[derive(Debug, Deserialize)]
+pub struct GapExportQuery {
+    /// Crop season by the year it starts in; the current season by default
+    pub season: Option<i32>,
+    /// Season as a report period such as `season:2024/25`; overrides `season`
+    pub period: Option<String>,
+    pub language: Option<String>, // "th" (default) or "en"
+}
+
+/// Applicant details
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct GapApplicant {
+    pub business_name: String,
+    pub owner_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub subdistrict: Option<String>,
+    pub district: Option<String>,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    /// Current Thai GAP certificate, when renewing
+    pub certificate_number: Option<String>,
+    pub certificate_expires: Option<NaiveDate>,
+}
+
+/// Production plot
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GapPlot {
+    pub name: String,
+    pub latitude: Option<Decimal>,
+    pub longitude: Option<Decimal>,
+    pub altitude_meters: Option<i32>,
+    pub area_rai: Option<Decimal>,
+    pub shade_coverage_percent: Option<i32>,
+    pub varieties: Option<String>,
+    pub first_planted: Option<NaiveDate>,
+    pub tree_count: Option<i64>,
+}
+
+/// Harvest record
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GapHarvest {
+    pub harvest_date: NaiveDate,
+    pub plot_name: String,
+    pub traceability_code: String,
+    pub cherry_weight_kg: Decimal,
+    pub ripe_percent: i32,
+    pub picker_name: Option<String>,
+}
+
+/// Post-harvest processing record
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GapProcessing {
+    pub traceability_code: String,
+    pub method: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub responsible_person: String,
+    pub final_moisture_percent: Option<Decimal>,
+}
+
+/// Water test
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GapWaterTest {
+    pub measured_on: NaiveDate,
+    pub source: String,
+    pub ph: Decimal,
+    pub tds_ppm: Decimal,
+}
+
+/// Thai GAP checklist item and how the business stands on it
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GapPractice {
+    pub requirement_code: String,
+    pub requirement_name: String,
+    pub requirement_name_th: Option<String>,
+    pub category: Option<String>,
+    pub is_critical: bool,
+    /// `None` while not assessed
+    pub is_compliant: Option<bool>,
+    pub compliance_notes: Option<String>,
+    pub evidence_url: Option<String>,
+}
+
+/// Everything that goes into a submission
+#[derive(Debug, Clone, Default)]
+pub struct GapSubmission {
+    /// Season label such as "2024/25"
+    pub season_label: String,
+    pub applicant: GapApplicant,
+    pub plots: Vec<GapPlot>,
+    pub harvests: Vec<GapHarvest>,
+    pub processing: Vec<GapProcessing>,
+    pub water_tests: Vec<GapWaterTest>,
+    pub practices: Vec<GapPractice>,
+}
+
+/// Something the application still lacks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingItem {
+    pub section: &'static str,
+    pub message: String,
+    pub message_th: String,
+}
+
+fn missing(section: &'static str, message: String, message_th: String) -> MissingItem {
+    MissingItem {
+        section,
+        message,
+        message_th,
+    }
+}
+
+/// What the submission lacks, in form order
+pub fn missing_information(submission: &GapSubmission) -> Vec<MissingItem> {
+    let mut items = Vec::new();
+    let applicant = &submission.applicant;
+
+    let address = [
+        (applicant.address.as_ref(), "address", "ที่อยู่"),
+        (applicant.subdistrict.as_ref(), "subdistrict", "ตำบล"),
+        (applicant.district.as_ref(), "district", "อำเภอ"),
+        (applicant.province.as_ref(), "province", "จังหวัด"),
+    ];
+    for (value, en, th) in address {
+        if value.is_none_or(|v| v.trim().is_empty()) {
+            items.push(missing("applicant", format!("Applicant {} is missing", en), format!("ไม่มี{}ผู้ยื่นคำขอ", th)));
+        }
+    }
+    if applicant.owner_name.is_none() {
+        items.push(missing("applicant", "No owner account to sign the application".to_string(), "ไม่มีบัญชีเจ้าของสำหรับลงนามในคำขอ".to_string()));
+    }
+    if applicant.phone.as_ref().is_none_or(|p| p.trim().is_empty()) {
+        items.push(missing("applicant", "Applicant phone number is missing".to_string(), "ไม่มีเบอร์โทรศัพท์ผู้ยื่นคำขอ".to_string()));
+    }
+
+    if submission.plots.is_empty() {
+        items.push(missing("plots", "No production plots recorded".to_string(), "ยังไม่มีแปลงปลูก".to_string()));
+    }
+    for plot in &submission.plots {
+        let checks = [
+            (plot.latitude.is_none() || plot.longitude.is_none(), "coordinates", "พิกัด"),
+            (plot.area_rai.is_none(), "area", "พื้นที่"),
+            (plot.varieties.is_none(), "varieties", "สายพันธุ์"),
+            (plot.first_planted.is_none(), "planting date", "วันที่ปลูก"),
+            (plot.tree_count.is_none(), "tree count", "จำนวนต้น"),
+        ];
+        for (_, en, th) in checks.into_iter().filter(|(absent, _, _)| *absent) {
+            items.push(missing(
+                "plots",
+                format!("Plot {}: {} missing", plot.name, en),
+                format!("แปลง {}: ไม่มี{}", plot.name, th),
+            ));
+        }
+    }
+
+    if submission.harvests.is_empty() {
+        items.push(missing(
+            "harvests",
+            format!("No harvest records in the {} season", submission.season_label),
+            format!("ไม่มีบันทึกการเก็บเกี่ยวในฤดู {}", submission.season_label),
+        ));
+    }
+    if submission.water_tests.is_empty() {
+        items.push(missing(
+            "water",
+            "No water tests in the season".to_string(),
+            "ไม่มีผลตรวจคุณภาพน้ำในฤดูนี้".to_string(),
+        ));
+    }
+
+    for practice in &submission.practices {
+        let name_th = practice.requirement_name_th.as_deref().unwrap_or(&practice.requirement_name);
+        match practice.is_compliant {
+            None => items.push(missing(
+                "practices",
+                format!("{} {}: not assessed", practice.requirement_code, practice.requirement_name),
+                format!("{} {}: ยังไม่ได้ประเมิน", practice.requirement_code, name_th),
+            )),
+            Some(false) if practice.is_critical => items.push(missing(
+                "practices",
+                format!("{} {}: critical requirement not met", practice.requirement_code, practice.requirement_name),
+                format!("{} {}: ยังไม่ผ่านข้อกำหนดหลัก", practice.requirement_code, name_th),
+            )),
+            Some(_) => {}
+        }
+    }
+    items
+}
+
+fn tr(thai: bool, th: &str, en: &str) -> String {
+    if thai { th } else { en }.to_string()
+}
+
+fn text(value: impl Into<String>) -> XlsxCell {
+    XlsxCell::Text(value.into())
+}
+
+fn optional_text(value: &Option<String>) -> XlsxCell {
+    value.as_ref().map(|v| text(v.clone())).unwrap_or(XlsxCell::Empty)
+}
+
+fn header(thai: bool, columns: &[(&str, &str)]) -> Vec<String> {
+    columns.iter().map(|(th, en)| tr(thai, th, en)).collect()
+}
+
+/// The submission's sections, each as a sheet
+pub fn submission_sheets(submission: &GapSubmission, thai: bool, fmt: DisplayFormat) -> Vec<XlsxSheet> {
+    let season = &submission.season_label;
+    let a = &submission.applicant;
+
+    let mut applicant = XlsxSheet::new(
+        tr(thai, "ผู้ยื่นคำขอ", "Applicant"),
+        header(thai, &[("รายการ", "Item"), ("ข้อมูล", "Details")]),
+    )
+    .with_title(tr(
+        thai,
+        &format!("คำขอรับรองแหล่งผลิต GAP พืช (กาแฟ) ฤดูการผลิต {}", season),
+        &format!("Thai GAP Certification Application (Coffee), {} Season", season),
+    ));
+    let fields = [
+        (("ชื่อแหล่งผลิต / ธุรกิจ", "Farm / Business"), Some(a.business_name.clone())),
+        (("ชื่อผู้ยื่นคำขอ", "Applicant"), a.owner_name.clone()),
+        (("โทรศัพท์", "Phone"), a.phone.clone()),
+        (("อีเมล", "Email"), a.email.clone()),
+        (("ที่อยู่", "Address"), a.address.clone()),
+        (("ตำบล", "Subdistrict"), a.subdistrict.clone()),
+        (("อำเภอ", "District"), a.district.clone()),
+        (("จังหวัด", "Province"), a.province.clone()),
+        (("รหัสไปรษณีย์", "Postal code"), a.postal_code.clone()),
+        (("พืชที่ขอรับรอง", "Crop"), Some(tr(thai, "กาแฟอาราบิก้า", "Arabica coffee"))),
+        (("จำนวนแปลง", "Plots"), Some(fmt.integer(submission.plots.len() as i64))),
+        (
+            ("พื้นที่รวม (ไร่)", "Total area (rai)"),
+            Some(fmt.decimal(submission.plots.iter().filter_map(|p| p.area_rai).sum(), 2)),
+        ),
+        (("เลขที่ใบรับรองเดิม", "Current certificate"), a.certificate_number.clone()),
+        (("วันหมดอายุใบรับรอง", "Certificate expires"), a.certificate_expires.map(|d| fmt.date(d))),
+    ];
+    for ((th, en), value) in fields {
+        applicant.push_row(vec![text(tr(thai, th, en)), optional_text(&value)]);
+    }
+
+    let mut plots = XlsxSheet::new(
+        tr(thai, "แปลงปลูก", "Plots"),
+        header(
+            thai,
+            &[
+                ("ลำดับ", "No."),
+                ("ชื่อแปลง", "Plot"),
+                ("ละติจูด", "Latitude"),
+                ("ลองจิจูด", "Longitude"),
+                ("ความสูง (ม.)", "Altitude (m)"),
+                ("พื้นที่ (ไร่)", "Area (rai)"),
+                ("สายพันธุ์", "Varieties"),
+                ("ปลูกเมื่อ", "Planted"),
+                ("จำนวนต้น", "Trees"),
+                ("ร่มเงา (%)", "Shade (%)"),
+            ],
+        ),
+    );
+    for (i, plot) in submission.plots.iter().enumerate() {
+        plots.push_row(vec![
+            XlsxCell::Integer(i as i64 + 1),
+            text(&plot.name),
+            plot.latitude.map(|v| text(v.round_dp(6).to_string())).unwrap_or(XlsxCell::Empty),
+            plot.longitude.map(|v| text(v.round_dp(6).to_string())).unwrap_or(XlsxCell::Empty),
+            plot.altitude_meters.map(|v| XlsxCell::Integer(v as i64)).unwrap_or(XlsxCell::Empty),
+            plot.area_rai.map(XlsxCell::Number).unwrap_or(XlsxCell::Empty),
+            optional_text(&plot.varieties),
+            plot.first_planted.map(XlsxCell::Date).unwrap_or(XlsxCell::Empty),
+            plot.tree_count.map(XlsxCell::Integer).unwrap_or(XlsxCell::Empty),
+            plot.shade_coverage_percent.map(|v| XlsxCell::Integer(v as i64)).unwrap_or(XlsxCell::Empty),
+        ]);
+    }
+    if !submission.plots.is_empty() {
+        let mut footer = vec![XlsxCell::Empty, text(tr(thai, "รวม", "Total"))];
+        footer.extend([XlsxCell::Empty, XlsxCell::Empty, XlsxCell::Empty]);
+        footer.push(XlsxCell::Number(submission.plots.iter().filter_map(|p| p.area_rai).sum()));
+        footer.extend([XlsxCell::Empty, XlsxCell::Empty]);
+        footer.push(XlsxCell::Integer(submission.plots.iter().filter_map(|p| p.tree_count).sum()));
+        plots.set_footer(footer);
+    }
+
+    let mut harvests = XlsxSheet::new(
+        tr(thai, "การเก็บเกี่ยว", "Harvests"),
+        header(
+            thai,
+            &[
+                ("วันที่", "Date"),
+                ("แปลง", "Plot"),
+                ("รหัสล็อต", "Lot"),
+                ("เชอร์รี่ (กก.)", "Cherry (kg)"),
+                ("สุก (%)", "Ripe (%)"),
+                ("ผู้เก็บ", "Picker"),
+            ],
+        ),
+    );
+    for harvest in &submission.harvests {
+        harvests.push_row(vec![
+            XlsxCell::Date(harvest.harvest_date),
+            text(&harvest.plot_name),
+            text(&harvest.traceability_code),
+            XlsxCell::Number(harvest.cherry_weight_kg),
+            XlsxCell::Percent(Decimal::from(harvest.ripe_percent)),
+            optional_text(&harvest.picker_name),
+        ]);
+    }
+    if !submission.harvests.is_empty() {
+        harvests.set_footer(vec![
+            text(tr(thai, "รวม", "Total")),
+            XlsxCell::Empty,
+            XlsxCell::Empty,
+            XlsxCell::Number(submission.harvests.iter().map(|h| h.cherry_weight_kg).sum()),
+        ]);
+    }
+
+    let mut processing = XlsxSheet::new(
+        tr(thai, "หลังการเก็บเกี่ยว", "Post-harvest"),
+        header(
+            thai,
+            &[
+                ("รหัสล็อต", "Lot"),
+                ("วิธีแปรรูป", "Method"),
+                ("เริ่ม", "Start"),
+                ("เสร็จ", "End"),
+                ("ผู้รับผิดชอบ", "Responsible"),
+                ("ความชื้นสุดท้าย (%)", "Final moisture (%)"),
+            ],
+        ),
+    );
+    for record in &submission.processing {
+        processing.push_row(vec![
+            text(&record.traceability_code),
+            text(&record.method),
+            XlsxCell::Date(record.start_date),
+            record.end_date.map(XlsxCell::Date).unwrap_or(XlsxCell::Empty),
+            text(&record.responsible_person),
+            record.final_moisture_percent.map(XlsxCell::Percent).unwrap_or(XlsxCell::Empty),
+        ]);
+    }
+
+    let mut water = XlsxSheet::new(
+        tr(thai, "คุณภาพน้ำ", "Water"),
+        header(thai, &[("วันที่", "Date"), ("แหล่งน้ำ", "Source"), ("pH", "pH"), ("TDS (ppm)", "TDS (ppm)")]),
+    );
+    for test in &submission.water_tests {
+        water.push_row(vec![
+            XlsxCell::Date(test.measured_on),
+            text(&test.source),
+            XlsxCell::Number(test.ph),
+            XlsxCell::Number(test.tds_ppm),
+        ]);
+    }
+
+    let mut practices = XlsxSheet::new(
+        tr(thai, "การปฏิบัติ", "Practices"),
+        header(
+            thai,
+            &[
+                ("รหัส", "Code"),
+                ("ข้อกำหนด", "Requirement"),
+                ("หมวด", "Category"),
+                ("ข้อกำหนดหลัก", "Critical"),
+                ("ผลการประเมิน", "Status"),
+                ("หมายเหตุ", "Notes"),
+                ("หลักฐาน", "Evidence"),
+            ],
+        ),
+    );
+    for practice in &submission.practices {
+        let name = if thai {
+            practice.requirement_name_th.clone().unwrap_or_else(|| practice.requirement_name.clone())
+        } else {
+            practice.requirement_name.clone()
+        };
+        let status = match practice.is_compliant {
+            Some(true) => tr(thai, "ผ่าน", "Compliant"),
+            Some(false) => tr(thai, "ไม่ผ่าน", "Not compliant"),
+            None => tr(thai, "ยังไม่ประเมิน", "Not assessed"),
+        };
+        practices.push_row(vec![
+            text(&practice.requirement_code),
+            text(name),
+            optional_text(&practice.category),
+            text(if practice.is_critical { tr(thai, "ใช่", "Yes") } else { String::new() }),
+            text(status),
+            optional_text(&practice.compliance_notes),
+            optional_text(&practice.evidence_url),
+        ]);
+    }
+
+    let mut gaps = XlsxSheet::new(
+        tr(thai, "ข้อมูลที่ยังขาด", "Missing"),
+        header(thai, &[("หมวด", "Section"), ("รายการ", "Item")]),
+    );
+    for item in missing_information(submission) {
+        let section = match item.section {
+            "applicant" => ("ผู้ยื่นคำขอ", "Applicant"),
+            "plots" => ("แปลงปลูก", "Plots"),
+            "harvests" => ("การเก็บเกี่ยว", "Harvests"),
+            "water" => ("คุณภาพน้ำ", "Water"),
+            _ => ("การปฏิบัติ", "Practices"),
+        };
+        gaps.push_row(vec![
+            text(tr(thai, section.0, section.1)),
+            text(if thai { item.message_th } else { item.message }),
+        ]);
+    }
+
+    vec![applicant, plots, harvests, processing, water, practices, gaps]
+}
+
+/// Cell as printed text
+fn cell_text(cell: &XlsxCell, fmt: DisplayFormat) -> String {
+    match cell {
+        XlsxCell::Empty => String::new(),
+        XlsxCell::Text(s) => s.clone(),
+        XlsxCell::Number(d) | XlsxCell::Money(d) => fmt.decimal(*d, 2),
+        XlsxCell::Integer(i) => fmt.integer(*i),
+        XlsxCell::Percent(d) => fmt.percent(*d, 1),
+        XlsxCell::Date(d) => fmt.date(*d),
+    }
+}
+
+/// Column widths in millimetres, proportional to the widest text in each
+/// column and filling `total`
+pub fn column_widths(rows: &[Vec<String>], total: f32) -> Vec<f32> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let chars: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|row| row.get(c))
+                .map(|t| display_width(t).clamp(3, MAX_PDF_COLUMN_CHARS))
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+    let sum: usize = chars.iter().sum();
+    chars.iter().map(|c| total * *c as f32 / sum.max(1) as f32).collect()
+}
+
+fn render_submission(sheets: &[XlsxSheet], fonts: &PdfFonts, thai: bool, fmt: DisplayFormat) -> AppResult<Vec<u8>> {
+    let title = sheets.first().and_then(|s| s.title.clone()).unwrap_or_default();
+    let mut page = PdfPage::new(&title, PAGE_WIDTH, PAGE_HEIGHT, fonts)?;
+    let width = PAGE_WIDTH - 2.0 * MARGIN;
+    let bottom = PAGE_HEIGHT - MARGIN;
+    let row_line = line_height(BODY_SIZE);
+
+    page.text(MARGIN, MARGIN + 4.0, TextStyle::bold(14.0), &title);
+    let issued = format!("{} {}", tr(thai, "จัดทำเมื่อ", "Prepared"), fmt.date(Utc::now().date_naive()));
+    page.text(MARGIN, MARGIN + 10.0, TextStyle::regular(BODY_SIZE), &issued);
+    let mut y = MARGIN + 18.0;
+
+    for sheet in sheets {
+        let mut rows: Vec<Vec<String>> = vec![sheet.header.clone()];
+        rows.extend(sheet.rows.iter().map(|row| row.iter().map(|c| cell_text(c, fmt)).collect()));
+        if let Some(footer) = &sheet.footer {
+            rows.push(footer.iter().map(|c| cell_text(c, fmt)).collect());
+        }
+        let widths = column_widths(&rows, width);
+
+        if y + 3.0 * row_line + 8.0 > bottom {
+            page.add_page();
+            y = MARGIN + 4.0;
+        }
+        y += 2.0;
+        page.text(MARGIN, y, TextStyle::bold(11.0), &sheet.name);
+        y += 1.6;
+        page.rule(MARGIN, MARGIN + width, y, 0.5, 0.6);
+        y += row_line + 1.0;
+
+        if sheet.rows.is_empty() {
+            page.text(MARGIN, y, TextStyle::regular(BODY_SIZE), &tr(thai, "ไม่มีข้อมูล", "No records"));
+            y += row_line + 4.0;
+            continue;
+        }
+
+        let last = rows.len() - 1;
+        for (i, row) in rows.iter().enumerate() {
+            let bold = i == 0 || (sheet.footer.is_some() && i == last);
+            let style = if bold { TextStyle::bold(BODY_SIZE) } else { TextStyle::regular(BODY_SIZE) };
+            let height = row
+                .iter()
+                .zip(&widths)
+                .map(|(t, w)| wrap_lines(t, *w).min(3) as f32 * row_line)
+                .fold(row_line, f32::max);
+            if y + height > bottom {
+                page.add_page();
+                y = MARGIN + 4.0;
+            }
+            if i == 0 {
+                page.fill_rect(MARGIN, y - row_line + 0.6, width, height + 1.0, 0.9);
+            }
+            let mut x = MARGIN;
+            for (t, w) in row.iter().zip(&widths) {
+                page.wrapped_text(x + 0.8, y, w - 1.6, style, t, 3);
+                x += w;
+            }
+            y += height + 1.0;
+        }
+        y += 4.0;
+    }
+
+    page.finish()
+}
+
+fn wrap_lines(text: &str, width: f32) -> usize {
+    wrap_text(text, chars_per_line(width - 1.6, BODY_SIZE)).len().max(1)
+}
+
+impl GapExportService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            pdf: config.pdf.clone(),
+        }
+    }
+
+    /// Gather the submission for a season
+    pub async fn build(&self, business_id: Uuid, query: &GapExportQuery) -> AppResult<GapSubmission> {
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let season = resolve_season(query.period.as_deref(), query.season, &calendar, Utc::now().date_naive())?;
+        let (start, end) = calendar.season_bounds(season)?;
+
+        let applicant = sqlx::query_as::<_, GapApplicant>(
+            r#"
+            SELECT b.name AS business_name, u.name AS owner_name,
+                   COALESCE(b.phone, u.phone) AS phone, COALESCE(b.email, u.email) AS email,
+                   b.address, b.subdistrict, b.district, b.province, b.postal_code,
+                   c.certificate_number, c.expiration_date AS certificate_expires
+            FROM businesses b
+            LEFT JOIN users u ON u.id = business_owner_id(b.id)
+            LEFT JOIN LATERAL (
+                SELECT certificate_number, expiration_date
+                FROM certifications
+                WHERE business_id = b.id AND certification_type = 'thai_gap' AND is_active
+                ORDER BY expiration_date DESC
+                LIMIT 1
+            ) c ON TRUE
+            WHERE b.id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business".to_string()))?;
+
+        let plots = sqlx::query_as::<_, GapPlot>(
+            r#"
+            SELECT p.name, p.latitude, p.longitude, p.altitude_meters, p.area_rai, p.shade_coverage_percent,
+                   STRING_AGG(pv.variety, ', ' ORDER BY pv.variety) AS varieties,
+                   MIN(pv.planting_date) AS first_planted,
+                   SUM(pv.tree_count)::BIGINT AS tree_count
+            FROM plots p
+            LEFT JOIN plot_varieties pv ON pv.plot_id = p.id
+            WHERE p.business_id = $1
+            GROUP BY p.id
+            ORDER BY p.name
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let harvests = sqlx::query_as::<_, GapHarvest>(
+            r#"
+            SELECT h.harvest_date, p.name AS plot_name, l.traceability_code, h.cherry_weight_kg,
+                   h.ripe_percent, h.picker_name
+            FROM harvests h
+            JOIN plots p ON p.id = h.plot_id
+            JOIN lots l ON l.id = h.lot_id
+            WHERE h.business_id = $1 AND h.harvest_date BETWEEN $2 AND $3
+            ORDER BY h.harvest_date, p.name
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let processing = sqlx::query_as::<_, GapProcessing>(
+            r#"
+            SELECT l.traceability_code, pr.method, pr.start_date, pr.end_date, pr.responsible_person,
+                   pr.final_moisture_percent
+            FROM processing_records pr
+            JOIN lots l ON l.id = pr.lot_id
+            WHERE l.business_id = $1 AND pr.start_date BETWEEN $2 AND $3
+            ORDER BY pr.start_date, l.traceability_code
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let water_tests = sqlx::query_as::<_, GapWaterTest>(
+            r#"
+            SELECT (measured_at AT TIME ZONE 'Asia/Bangkok')::DATE AS measured_on, source, ph, tds_ppm
+            FROM water_quality_measurements
+            WHERE business_id = $1
+              AND (measured_at AT TIME ZONE 'Asia/Bangkok')::DATE BETWEEN $2 AND $3
+            ORDER BY measured_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        // Checklist against the current Thai GAP certification, if any
+        let practices = sqlx::query_as::<_, GapPractice>(
+            r#"
+            SELECT r.requirement_code, r.requirement_name, r.requirement_name_th, r.category, r.is_critical,
+                   cc.is_compliant, cc.compliance_notes, cc.evidence_url
+            FROM certification_requirements r
+            LEFT JOIN LATERAL (
+                SELECT id FROM certifications
+                WHERE business_id = $1 AND certification_type = 'thai_gap'
+                ORDER BY is_active DESC, expiration_date DESC
+                LIMIT 1
+            ) c ON TRUE
+            LEFT JOIN certification_compliance cc ON cc.certification_id = c.id AND cc.requirement_id = r.id
+            WHERE r.certification_type = 'thai_gap'
+            ORDER BY r.display_order, r.requirement_code
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(GapSubmission {
+            season_label: calendar.season_label(season),
+            applicant,
+            plots,
+            harvests,
+            processing,
+            water_tests,
+            practices,
+        })
+    }
+
+    async fn display_format(&self, business_id: Uuid, thai: bool) -> AppResult<DisplayFormat> {
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        Ok(display.for_language(if thai { &Language::Thai } else { &Language::English }))
+    }
+
+    /// Submission workbook, one sheet per section
+    pub async fn xlsx(&self, business_id: Uuid, query: &GapExportQuery, thai: bool) -> AppResult<Vec<u8>> {
+        let submission = self.build(business_id, query).await?;
+        let fmt = self.display_format(business_id, thai).await?;
+        let mut workbook = XlsxWorkbook::new().with_calendar(fmt.calendar);
+        for sheet in submission_sheets(&submission, thai, fmt) {
+            workbook.add_sheet(sheet);
+        }
+        workbook.to_bytes()
+    }
+
+    /// Submission PDF; Thai only when a Thai-capable font is configured
+    pub async fn pdf(&self, business_id: Uuid, query: &GapExportQuery, thai: bool) -> AppResult<Vec<u8>> {
+        let submission = self.build(business_id, query).await?;
+        let fonts = PdfFonts::load(&self.pdf).await?;
+        let thai = thai && fonts.supports_thai();
+        let fmt = self.display_format(business_id, thai).await?;
+        render_submission(&submission_sheets(&submission, thai, fmt), &fonts, thai, fmt)
+    }
+}
@@ -1,18 +1,46 @@
-//! Green bean grading service following SCA standards
+//! Green bean grading service
+//!
+//! Besides defect counts, a grading carries the sample's physical analysis:
+//! moisture, water activity, bulk density and the percent retained on
+//! screens 19 down to 13. A screen analysis also fills the coarser screen
+//! size distribution quality specs check.
+//!
+//! The grade is classified under the grading standard picked for the
+//! grading (see `grading_standard`), the SCA rules unless the business
+//! chose otherwise.
+//!
+//! Correcting or deleting a grading records the values it had in the
+//! grading's edit history.
 
 use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::external::ai_defect_detection::{AiDefectDetectionClient, DetectDefectsRequest};
+use crate::services::grading_photo::decode_photo;
+use crate::services::job_queue::{Job, JobQueueService, DEFAULT_MAX_ATTEMPTS, JOB_TYPE_AI_GRADING};
 use crate::services::lot::LotStage;
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+use crate::services::GradingStandardService;
 use shared::{
-    classify_grade, AiDefectDetection, DefectBreakdown, DefectCount, GradeClassification,
-    ScreenSizeDistribution,
+    classify_grade_with, AiDefectDetection, DefectBreakdown, DefectCount, DefectType, GradeClassification, Language,
+    ScreenAnalysis, ScreenSizeDistribution,
 };
 
+/// Lowest green bean bulk density accepted, g/L
+pub const MIN_BULK_DENSITY_G_PER_L: Decimal = Decimal::from_parts(300, 0, 0, false, 0);
+
+/// Highest green bean bulk density accepted, g/L
+pub const MAX_BULK_DENSITY_G_PER_L: Decimal = Decimal::from_parts(1000, 0, 0, false, 0);
+
+/// Most lots compared side by side
+pub const MAX_COMPARED_LOTS: usize = 10;
+
 /// Grading service for managing green bean quality grades
 #[derive(Clone)]
 pub struct GradingService {
@@ -34,7 +62,11 @@ struct GradingRow {
     moisture_percent: Decimal,
     density: Option<Decimal>,
     screen_size_distribution: Option<serde_json::Value>,
+    screen_analysis: Option<serde_json::Value>,
+    water_activity: Option<Decimal>,
+    bulk_density_g_per_l: Option<Decimal>,
     grade: String,
+    grading_standard_id: Option<Uuid>,
     notes: Option<String>,
     notes_th: Option<String>,
     created_at: DateTime<Utc>,
@@ -54,6 +86,9 @@ impl From<GradingRow> for GradingRecord {
             .screen_size_distribution
             .and_then(|v| serde_json::from_value(v).ok());
 
+        let screen_analysis: Option<ScreenAnalysis> =
+            row.screen_analysis.and_then(|v| serde_json::from_value(v).ok());
+
         GradingRecord {
             id: row.id,
             lot_id: row.lot_id,
@@ -69,7 +104,11 @@ impl From<GradingRow> for GradingRecord {
             moisture_percent: row.moisture_percent,
             density: row.density,
             screen_size,
+            screen_analysis,
+            water_activity: row.water_activity,
+            bulk_density_g_per_l: row.bulk_density_g_per_l,
             grade: grade_from_str(&row.grade),
+            grading_standard_id: row.grading_standard_id,
             notes: row.notes,
             notes_th: row.notes_th,
             created_at: row.created_at,
@@ -91,7 +130,13 @@ pub struct GradingRecord {
     pub moisture_percent: Decimal,
     pub density: Option<Decimal>,
     pub screen_size: Option<ScreenSizeDistribution>,
+    /// Percent retained on screens 19 to 13
+    pub screen_analysis: Option<ScreenAnalysis>,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
     pub grade: GradeClassification,
+    /// Standard the grade was classified under; None for the SCA rules
+    pub grading_standard_id: Option<Uuid>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -105,14 +150,105 @@ pub struct RecordGradingInput {
     pub grading_date: NaiveDate,
     pub grader_name: String,
     pub sample_weight_grams: Decimal,
+    /// Full defects; taken from `defect_breakdown` when one is given
+    #[serde(default)]
     pub category1_count: i32,
+    #[serde(default)]
     pub category2_count: i32,
     pub defect_breakdown: Option<DefectBreakdown>,
     pub moisture_percent: Decimal,
     pub density: Option<Decimal>,
     pub screen_size: Option<ScreenSizeDistribution>,
+    /// Fills `screen_size` when given
+    pub screen_analysis: Option<ScreenAnalysis>,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
+    /// Standard to classify the grade under; the business default when left out
+    pub grading_standard_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+}
+
+/// Input for correcting a grading; fields left out stay as they are
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateGradingInput {
+    pub grading_date: Option<NaiveDate>,
+    pub grader_name: Option<String>,
+    pub sample_weight_grams: Option<Decimal>,
+    /// Replaces the breakdown and the category counts taken from it
+    pub defect_breakdown: Option<DefectBreakdown>,
+    /// Full defects; given without a breakdown they clear the breakdown
+    pub category1_count: Option<i32>,
+    pub category2_count: Option<i32>,
+    pub moisture_percent: Option<Decimal>,
+    pub density: Option<Decimal>,
+    /// Standard to classify the grade under again
+    pub grading_standard_id: Option<Uuid>,
+    /// An empty string clears the notes
     pub notes: Option<String>,
     pub notes_th: Option<String>,
+    /// Why the grading was corrected, kept in its edit history
+    pub reason: Option<String>,
+}
+
+/// Input for deleting a grading
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteGradingQuery {
+    pub reason: Option<String>,
+}
+
+/// What an entry in a grading's edit history did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradingEditAction {
+    Update,
+    Delete,
+}
+
+impl GradingEditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// A field of a grading changed by an edit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradingFieldChange {
+    pub field: String,
+    pub from: Value,
+    /// Null when the grading was deleted
+    pub to: Value,
+}
+
+/// An entry in a grading's edit history
+#[derive(Debug, Clone, Serialize)]
+pub struct GradingEdit {
+    pub id: Uuid,
+    pub grading_id: Uuid,
+    pub lot_id: Uuid,
+    pub action: GradingEditAction,
+    pub changes: Vec<GradingFieldChange>,
+    pub reason: Option<String>,
+    pub edited_by: Option<Uuid>,
+    pub edited_by_name: Option<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Database row for a grading edit
+#[derive(Debug, sqlx::FromRow)]
+struct GradingEditRow {
+    id: Uuid,
+    grading_id: Uuid,
+    lot_id: Uuid,
+    action: String,
+    changes: sqlx::types::Json<Vec<GradingFieldChange>>,
+    reason: Option<String>,
+    edited_by: Option<Uuid>,
+    edited_by_name: Option<String>,
+    edited_at: DateTime<Utc>,
 }
 
 /// Input for recording grading with AI detection
@@ -126,10 +262,163 @@ pub struct RecordGradingWithAiInput {
     pub moisture_percent: Decimal,
     pub density: Option<Decimal>,
     pub screen_size: Option<ScreenSizeDistribution>,
+    /// Fills `screen_size` when given
+    pub screen_analysis: Option<ScreenAnalysis>,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
+    /// Standard to classify the grade under; the business default when left out
+    pub grading_standard_id: Option<Uuid>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
 }
 
+/// Input for queueing AI grading of a tray photo; the detection fills the
+/// defect counts of the grading recorded once it finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueAiGradingInput {
+    pub image_base64: String,
+    pub lot_id: Uuid,
+    pub grading_date: NaiveDate,
+    pub grader_name: String,
+    pub sample_weight_grams: Decimal,
+    pub moisture_percent: Decimal,
+    pub density: Option<Decimal>,
+    pub screen_size: Option<ScreenSizeDistribution>,
+    /// Fills `screen_size` when given
+    pub screen_analysis: Option<ScreenAnalysis>,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
+    /// Standard to classify the grade under; the business default when left out
+    pub grading_standard_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+}
+
+/// Input for recording a grading's physical analysis; readings left out
+/// stay as they are
+#[derive(Debug, Default, Deserialize)]
+pub struct RecordPhysicalAnalysisInput {
+    pub screen_analysis: Option<ScreenAnalysis>,
+    pub moisture_percent: Option<Decimal>,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
+}
+
+/// Physical analysis of a graded sample
+#[derive(Debug, Serialize)]
+pub struct PhysicalAnalysis {
+    pub grading_id: Uuid,
+    pub lot_id: Uuid,
+    pub grading_date: NaiveDate,
+    pub moisture_percent: Decimal,
+    pub water_activity: Option<Decimal>,
+    pub bulk_density_g_per_l: Option<Decimal>,
+    pub screen_analysis: Option<ScreenAnalysis>,
+    /// Percent passing screen 13
+    pub pan_percent: Option<Decimal>,
+    pub screen_size: Option<ScreenSizeDistribution>,
+}
+
+impl From<GradingRecord> for PhysicalAnalysis {
+    fn from(grading: GradingRecord) -> Self {
+        PhysicalAnalysis {
+            grading_id: grading.id,
+            lot_id: grading.lot_id,
+            grading_date: grading.grading_date,
+            moisture_percent: grading.moisture_percent,
+            water_activity: grading.water_activity,
+            bulk_density_g_per_l: grading.bulk_density_g_per_l,
+            pan_percent: grading.screen_analysis.as_ref().map(ScreenAnalysis::pan_percent),
+            screen_analysis: grading.screen_analysis,
+            screen_size: grading.screen_size,
+        }
+    }
+}
+
+/// Check a physical analysis: each screen 0-100% and at most 100% retained
+/// in all, water activity 0-1 and a plausible bulk density
+pub fn validate_physical_analysis(
+    screen_analysis: Option<&ScreenAnalysis>,
+    water_activity: Option<Decimal>,
+    bulk_density_g_per_l: Option<Decimal>,
+) -> AppResult<()> {
+    if let Some(analysis) = screen_analysis {
+        for (screen, share) in analysis.screens() {
+            if share < Decimal::ZERO || share > Decimal::ONE_HUNDRED {
+                return Err(AppError::Validation {
+                    field: format!("screen_analysis.screen_{}", screen),
+                    message: format!("Percent retained on screen {} must be between 0 and 100", screen),
+                    message_th: format!("เปอร์เซ็นต์ที่ค้างบนตะแกรง {} ต้องอยู่ระหว่าง 0 ถึง 100", screen),
+                });
+            }
+        }
+        if analysis.retained_percent() > Decimal::ONE_HUNDRED {
+            return Err(AppError::Validation {
+                field: "screen_analysis".to_string(),
+                message: format!(
+                    "Screens retain {}% of the sample; they cannot retain more than 100%",
+                    analysis.retained_percent()
+                ),
+                message_th: format!(
+                    "ตะแกรงรวมกันค้างตัวอย่าง {}% ซึ่งต้องไม่เกิน 100%",
+                    analysis.retained_percent()
+                ),
+            });
+        }
+    }
+    if water_activity.is_some_and(|aw| aw < Decimal::ZERO || aw > Decimal::ONE) {
+        return Err(AppError::Validation {
+            field: "water_activity".to_string(),
+            message: "Water activity must be between 0 and 1".to_string(),
+            message_th: "ค่าวอเตอร์แอคทิวิตี้ต้องอยู่ระหว่าง 0 ถึง 1".to_string(),
+        });
+    }
+    if bulk_density_g_per_l
+        .is_some_and(|density| density < MIN_BULK_DENSITY_G_PER_L || density > MAX_BULK_DENSITY_G_PER_L)
+    {
+        return Err(AppError::Validation {
+            field: "bulk_density_g_per_l".to_string(),
+            message: format!(
+                "Bulk density must be between {} and {} g/L",
+                MIN_BULK_DENSITY_G_PER_L, MAX_BULK_DENSITY_G_PER_L
+            ),
+            message_th: format!(
+                "ความหนาแน่นรวมต้องอยู่ระหว่าง {} ถึง {} กรัม/ลิตร",
+                MIN_BULK_DENSITY_G_PER_L, MAX_BULK_DENSITY_G_PER_L
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Defect counts of a grading: the full defect equivalents of the breakdown
+/// when there is one, else the counts entered
+pub fn grading_defects(
+    category1_count: i32,
+    category2_count: i32,
+    defect_breakdown: Option<DefectBreakdown>,
+) -> AppResult<DefectCount> {
+    let Some(breakdown) = defect_breakdown else {
+        return Ok(DefectCount {
+            category1_count,
+            category2_count,
+            defect_breakdown: None,
+        });
+    };
+    if let Some(defect) = breakdown.negative_count() {
+        let code = serde_json::to_value(defect)
+            .ok()
+            .and_then(|code| code.as_str().map(str::to_string))
+            .unwrap_or_default();
+        return Err(AppError::Validation {
+            field: format!("defect_breakdown.{}", code),
+            message: "Defect counts cannot be negative".to_string(),
+            message_th: "จำนวนข้อบกพร่องต้องไม่ติดลบ".to_string(),
+        });
+    }
+    Ok(DefectCount::from_breakdown(breakdown))
+}
+
 /// Grading comparison result
 #[derive(Debug, Serialize)]
 pub struct GradingComparison {
@@ -155,6 +444,139 @@ pub struct DefectTrend {
     pub total_change: i32,
 }
 
+/// Lots side by side for choosing buyer samples
+#[derive(Debug, Serialize)]
+pub struct LotGradingComparison {
+    /// Defect types found in any of the lots' latest gradings, the order of
+    /// every lot's `defect_counts`
+    pub defect_types: Vec<DefectType>,
+    /// In the order asked for
+    pub lots: Vec<LotGradingColumn>,
+}
+
+/// A lot's latest grading and its cupping scores
+#[derive(Debug, Serialize)]
+pub struct LotGradingColumn {
+    pub lot_id: Uuid,
+    pub lot_name: String,
+    pub traceability_code: String,
+    pub stage: LotStage,
+    /// Latest grading; the grading fields are empty for a lot not graded yet
+    pub grading_id: Option<Uuid>,
+    pub grading_date: Option<NaiveDate>,
+    pub grade: Option<GradeClassification>,
+    pub category1_defects: Option<i32>,
+    pub category2_defects: Option<i32>,
+    pub total_defects: Option<i32>,
+    /// Occurrences of each of `defect_types`; empty without a breakdown
+    pub defect_counts: Option<Vec<i32>>,
+    pub moisture_percent: Option<Decimal>,
+    pub water_activity: Option<Decimal>,
+    /// Final scores of the lot's samples, leaving out blind sessions not
+    /// yet revealed
+    pub cupping_sample_count: i64,
+    pub average_cupping_score: Option<Decimal>,
+    pub latest_cupping_score: Option<Decimal>,
+    pub latest_cupping_date: Option<NaiveDate>,
+}
+
+/// Lot ids of a comparison, comma-separated, in order without repeats
+pub fn parse_compared_lot_ids(lot_ids: &str) -> AppResult<Vec<Uuid>> {
+    let mut ids: Vec<Uuid> = Vec::new();
+    for part in lot_ids.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let id = Uuid::parse_str(part).map_err(|_| AppError::Validation {
+            field: "lot_ids".to_string(),
+            message: format!("'{}' is not a lot id", part),
+            message_th: format!("'{}' ไม่ใช่รหัสล็อต", part),
+        })?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.len() < 2 || ids.len() > MAX_COMPARED_LOTS {
+        return Err(AppError::Validation {
+            field: "lot_ids".to_string(),
+            message: format!("Compare 2 to {} different lots", MAX_COMPARED_LOTS),
+            message_th: format!("เปรียบเทียบได้ 2 ถึง {} ล็อตที่ต่างกัน", MAX_COMPARED_LOTS),
+        });
+    }
+    Ok(ids)
+}
+
+/// Values of a grading kept in its edit history, by field
+pub fn grading_values(grading: &GradingRecord) -> Vec<(&'static str, Value)> {
+    vec![
+        ("grading_date", serde_json::json!(grading.grading_date)),
+        ("grader_name", serde_json::json!(grading.grader_name)),
+        ("sample_weight_grams", serde_json::json!(grading.sample_weight_grams)),
+        ("category1_count", serde_json::json!(grading.defects.category1_count)),
+        ("category2_count", serde_json::json!(grading.defects.category2_count)),
+        ("defect_breakdown", serde_json::json!(grading.defects.defect_breakdown)),
+        ("moisture_percent", serde_json::json!(grading.moisture_percent)),
+        ("density", serde_json::json!(grading.density)),
+        ("grade", serde_json::json!(grading.grade)),
+        ("grading_standard_id", serde_json::json!(grading.grading_standard_id)),
+        ("notes", serde_json::json!(grading.notes)),
+        ("notes_th", serde_json::json!(grading.notes_th)),
+    ]
+}
+
+/// Fields that differ between a grading's values before and after an edit;
+/// with no values after (a delete) every field is listed
+pub fn grading_changes(
+    before: &[(&'static str, Value)],
+    after: Option<&[(&'static str, Value)]>,
+) -> Vec<GradingFieldChange> {
+    before
+        .iter()
+        .filter_map(|(field, from)| {
+            let to = match after {
+                Some(after) => after.iter().find(|(f, _)| f == field).map(|(_, v)| v.clone()).unwrap_or(Value::Null),
+                None => Value::Null,
+            };
+            if after.is_some() && *from == to {
+                return None;
+            }
+            Some(GradingFieldChange { field: field.to_string(), from: from.clone(), to })
+        })
+        .collect()
+}
+
+/// Defect counts after a correction: a new breakdown gives the counts, new
+/// counts without one clear the old breakdown, and otherwise they stay
+pub fn corrected_defects(
+    existing: &DefectCount,
+    defect_breakdown: Option<DefectBreakdown>,
+    category1_count: Option<i32>,
+    category2_count: Option<i32>,
+) -> AppResult<DefectCount> {
+    if defect_breakdown.is_some() {
+        return grading_defects(0, 0, defect_breakdown);
+    }
+    if category1_count.is_none() && category2_count.is_none() {
+        return Ok(existing.clone());
+    }
+    grading_defects(
+        category1_count.unwrap_or(existing.category1_count),
+        category2_count.unwrap_or(existing.category2_count),
+        None,
+    )
+}
+
+/// Notes as stored: trimmed, with blank notes cleared
+fn clean_notes(notes: String) -> Option<String> {
+    let notes = notes.trim();
+    (!notes.is_empty()).then(|| notes.to_string())
+}
+
+/// Defect types counted in any of the breakdowns, in SCA order
+pub fn compared_defect_types(breakdowns: &[Option<&DefectBreakdown>]) -> Vec<DefectType> {
+    DefectType::ALL
+        .into_iter()
+        .filter(|defect| breakdowns.iter().flatten().any(|b| b.count(*defect) > 0))
+        .collect()
+}
+
 impl GradingService {
     /// Create a new GradingService instance
     pub fn new(db: PgPool) -> Self {
@@ -172,21 +594,29 @@ impl GradingService {
             .await?;
 
         // Validate input
+        let defects = grading_defects(
+            input.category1_count,
+            input.category2_count,
+            input.defect_breakdown.clone(),
+        )?;
         self.validate_grading_input(
             &input.grader_name,
             input.sample_weight_grams,
-            input.category1_count,
-            input.category2_count,
+            defects.category1_count,
+            defects.category2_count,
             input.moisture_percent,
         )?;
+        validate_physical_analysis(
+            input.screen_analysis.as_ref(),
+            input.water_activity,
+            input.bulk_density_g_per_l,
+        )?;
 
-        // Calculate grade classification
-        let defects = DefectCount {
-            category1_count: input.category1_count,
-            category2_count: input.category2_count,
-            defect_breakdown: input.defect_breakdown.clone(),
-        };
-        let grade = classify_grade(&defects);
+        // Classify under the chosen standard
+        let standard = GradingStandardService::new(self.db.clone())
+            .resolve(business_id, input.grading_standard_id)
+            .await?;
+        let grade = classify_grade_with(&defects, Some(input.moisture_percent), &standard.thresholds);
 
         // Serialize optional fields
         let defect_breakdown_json = input
@@ -197,9 +627,17 @@ impl GradingService {
             .map_err(|e| AppError::Internal(e.to_string()))?;
 
         let screen_size_json = input
-            .screen_size
+            .screen_analysis
             .as_ref()
-            .map(|s| serde_json::to_value(s))
+            .map(ScreenAnalysis::distribution)
+            .or(input.screen_size)
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let screen_analysis_json = input
+            .screen_analysis
+            .as_ref()
+            .map(serde_json::to_value)
             .transpose()
             .map_err(|e| AppError::Internal(e.to_string()))?;
 
@@ -210,21 +648,22 @@ impl GradingService {
                 lot_id, grading_date, grader_name, sample_weight_grams,
                 category1_count, category2_count, defect_breakdown,
                 moisture_percent, density, screen_size_distribution, grade,
-                notes, notes_th
+                notes, notes_th, screen_analysis, water_activity, bulk_density_g_per_l, grading_standard_id
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING id, lot_id, grading_date, grader_name, sample_weight_grams,
                       category1_count, category2_count, defect_breakdown, ai_detection,
                       moisture_percent, density, screen_size_distribution, grade,
-                      notes, notes_th, created_at, updated_at
+                      screen_analysis, water_activity, bulk_density_g_per_l,
+                      grading_standard_id, notes, notes_th, created_at, updated_at
             "#,
         )
         .bind(input.lot_id)
         .bind(input.grading_date)
         .bind(&input.grader_name)
         .bind(input.sample_weight_grams)
-        .bind(input.category1_count)
-        .bind(input.category2_count)
+        .bind(defects.category1_count)
+        .bind(defects.category2_count)
         .bind(&defect_breakdown_json)
         .bind(input.moisture_percent)
         .bind(input.density)
@@ -232,6 +671,10 @@ impl GradingService {
         .bind(grade_to_str(&grade))
         .bind(&input.notes)
         .bind(&input.notes_th)
+        .bind(&screen_analysis_json)
+        .bind(input.water_activity)
+        .bind(input.bulk_density_g_per_l)
+        .bind(standard.id)
         .fetch_one(&self.db)
         .await?;
 
@@ -248,22 +691,29 @@ impl GradingService {
         self.validate_lot_for_grading(business_id, input.lot_id)
             .await?;
 
-        // Validate input
+        // Validate input; the detected breakdown gives the defect counts
+        let defects = grading_defects(
+            input.ai_detection.category1_count,
+            input.ai_detection.category2_count,
+            Some(input.ai_detection.defect_breakdown.clone()),
+        )?;
         self.validate_grading_input(
             &input.grader_name,
             input.sample_weight_grams,
-            input.ai_detection.category1_count,
-            input.ai_detection.category2_count,
+            defects.category1_count,
+            defects.category2_count,
             input.moisture_percent,
         )?;
+        validate_physical_analysis(
+            input.screen_analysis.as_ref(),
+            input.water_activity,
+            input.bulk_density_g_per_l,
+        )?;
 
-        // Use AI detection counts for grade classification
-        let defects = DefectCount {
-            category1_count: input.ai_detection.category1_count,
-            category2_count: input.ai_detection.category2_count,
-            defect_breakdown: Some(input.ai_detection.defect_breakdown.clone()),
-        };
-        let grade = classify_grade(&defects);
+        let standard = GradingStandardService::new(self.db.clone())
+            .resolve(business_id, input.grading_standard_id)
+            .await?;
+        let grade = classify_grade_with(&defects, Some(input.moisture_percent), &standard.thresholds);
 
         // Serialize fields
         let ai_detection_json = serde_json::to_value(&input.ai_detection)
@@ -273,9 +723,17 @@ impl GradingService {
             .map_err(|e| AppError::Internal(e.to_string()))?;
 
         let screen_size_json = input
-            .screen_size
+            .screen_analysis
             .as_ref()
-            .map(|s| serde_json::to_value(s))
+            .map(ScreenAnalysis::distribution)
+            .or(input.screen_size)
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let screen_analysis_json = input
+            .screen_analysis
+            .as_ref()
+            .map(serde_json::to_value)
             .transpose()
             .map_err(|e| AppError::Internal(e.to_string()))?;
 
@@ -286,21 +744,22 @@ impl GradingService {
                 lot_id, grading_date, grader_name, sample_weight_grams,
                 category1_count, category2_count, defect_breakdown, ai_detection,
                 moisture_percent, density, screen_size_distribution, grade,
-                notes, notes_th
+                notes, notes_th, screen_analysis, water_activity, bulk_density_g_per_l, grading_standard_id
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             RETURNING id, lot_id, grading_date, grader_name, sample_weight_grams,
                       category1_count, category2_count, defect_breakdown, ai_detection,
                       moisture_percent, density, screen_size_distribution, grade,
-                      notes, notes_th, created_at, updated_at
+                      screen_analysis, water_activity, bulk_density_g_per_l,
+                      grading_standard_id, notes, notes_th, created_at, updated_at
             "#,
         )
         .bind(input.lot_id)
         .bind(input.grading_date)
         .bind(&input.grader_name)
         .bind(input.sample_weight_grams)
-        .bind(input.ai_detection.category1_count)
-        .bind(input.ai_detection.category2_count)
+        .bind(defects.category1_count)
+        .bind(defects.category2_count)
         .bind(&defect_breakdown_json)
         .bind(&ai_detection_json)
         .bind(input.moisture_percent)
@@ -309,12 +768,97 @@ impl GradingService {
         .bind(grade_to_str(&grade))
         .bind(&input.notes)
         .bind(&input.notes_th)
+        .bind(&screen_analysis_json)
+        .bind(input.water_activity)
+        .bind(input.bulk_density_g_per_l)
+        .bind(standard.id)
         .fetch_one(&self.db)
         .await?;
 
         Ok(row.into())
     }
 
+    /// Queue AI defect detection of a tray photo; the grading is recorded
+    /// by the background worker once the detection comes back
+    pub async fn queue_grading_with_ai(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: QueueAiGradingInput,
+    ) -> AppResult<Job> {
+        if AiDefectDetectionClient::from_env().is_none() {
+            return Err(AppError::Configuration("AI detection service not configured".to_string()));
+        }
+        decode_photo(&input.image_base64)?;
+        self.validate_lot_for_grading(business_id, input.lot_id)
+            .await?;
+        self.validate_grading_input(
+            &input.grader_name,
+            input.sample_weight_grams,
+            0,
+            0,
+            input.moisture_percent,
+        )?;
+        validate_physical_analysis(
+            input.screen_analysis.as_ref(),
+            input.water_activity,
+            input.bulk_density_g_per_l,
+        )?;
+
+        GradingStandardService::new(self.db.clone())
+            .resolve(business_id, input.grading_standard_id)
+            .await?;
+
+        let payload = serde_json::to_value(&input).map_err(|e| AppError::Internal(e.to_string()))?;
+        JobQueueService::new(self.db.clone())
+            .enqueue(business_id, Some(user_id), JOB_TYPE_AI_GRADING, payload, DEFAULT_MAX_ATTEMPTS)
+            .await
+    }
+
+    /// Status of a queued AI grading
+    pub async fn get_ai_grading_job(&self, business_id: Uuid, job_id: Uuid) -> AppResult<Job> {
+        JobQueueService::new(self.db.clone())
+            .get_job(business_id, JOB_TYPE_AI_GRADING, job_id)
+            .await
+    }
+
+    /// Run queued AI grading: detect the photo's defects and record the
+    /// grading with them
+    pub async fn run_queued_ai_grading(
+        &self,
+        ai_client: &AiDefectDetectionClient,
+        business_id: Uuid,
+        input: QueueAiGradingInput,
+    ) -> AppResult<GradingRecord> {
+        let response = ai_client
+            .detect_defects(DetectDefectsRequest {
+                image_base64: input.image_base64,
+                sample_weight_grams: input.sample_weight_grams.to_f64(),
+            })
+            .await?;
+
+        self.record_grading_with_ai(
+            business_id,
+            RecordGradingWithAiInput {
+                lot_id: input.lot_id,
+                grading_date: input.grading_date,
+                grader_name: input.grader_name,
+                sample_weight_grams: input.sample_weight_grams,
+                ai_detection: response.detection.into(),
+                moisture_percent: input.moisture_percent,
+                density: input.density,
+                screen_size: input.screen_size,
+                screen_analysis: input.screen_analysis,
+                water_activity: input.water_activity,
+                bulk_density_g_per_l: input.bulk_density_g_per_l,
+                grading_standard_id: input.grading_standard_id,
+                notes: input.notes,
+                notes_th: input.notes_th,
+            },
+        )
+        .await
+    }
+
     /// Get grading record by ID
     pub async fn get_grading(
         &self,
@@ -326,7 +870,8 @@ impl GradingService {
             SELECT g.id, g.lot_id, g.grading_date, g.grader_name, g.sample_weight_grams,
                    g.category1_count, g.category2_count, g.defect_breakdown, g.ai_detection,
                    g.moisture_percent, g.density, g.screen_size_distribution, g.grade,
-                   g.notes, g.notes_th, g.created_at, g.updated_at
+                   g.screen_analysis, g.water_activity, g.bulk_density_g_per_l,
+                   g.grading_standard_id, g.notes, g.notes_th, g.created_at, g.updated_at
             FROM green_bean_grades g
             JOIN lots l ON l.id = g.lot_id
             WHERE g.id = $1 AND l.business_id = $2
@@ -341,6 +886,272 @@ impl GradingService {
         Ok(row.into())
     }
 
+    /// Physical analysis of a graded sample
+    pub async fn get_physical_analysis(&self, business_id: Uuid, grading_id: Uuid) -> AppResult<PhysicalAnalysis> {
+        Ok(self.get_grading(business_id, grading_id).await?.into())
+    }
+
+    /// Record the physical analysis of a graded sample; a screen analysis
+    /// replaces the screen size distribution, and the grade is classified
+    /// again for the new moisture
+    pub async fn record_physical_analysis(
+        &self,
+        business_id: Uuid,
+        grading_id: Uuid,
+        input: RecordPhysicalAnalysisInput,
+    ) -> AppResult<PhysicalAnalysis> {
+        let grading = self.get_grading(business_id, grading_id).await?;
+        let screen_analysis = input.screen_analysis.or(grading.screen_analysis);
+        let moisture_percent = input.moisture_percent.unwrap_or(grading.moisture_percent);
+        let water_activity = input.water_activity.or(grading.water_activity);
+        let bulk_density_g_per_l = input.bulk_density_g_per_l.or(grading.bulk_density_g_per_l);
+
+        self.validate_grading_input(
+            &grading.grader_name,
+            grading.sample_weight_grams,
+            grading.defects.category1_count,
+            grading.defects.category2_count,
+            moisture_percent,
+        )?;
+        validate_physical_analysis(screen_analysis.as_ref(), water_activity, bulk_density_g_per_l)?;
+
+        // Moisture limits of the grading's standard may change its grade
+        let standard = GradingStandardService::new(self.db.clone())
+            .for_grading(grading.grading_standard_id)
+            .await?;
+        let grade = classify_grade_with(&grading.defects, Some(moisture_percent), &standard.thresholds);
+
+        let screen_size_json = screen_analysis
+            .as_ref()
+            .map(ScreenAnalysis::distribution)
+            .or(grading.screen_size)
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let screen_analysis_json = screen_analysis
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE green_bean_grades
+            SET screen_analysis = $2, screen_size_distribution = $3, moisture_percent = $4,
+                water_activity = $5, bulk_density_g_per_l = $6, grade = $7, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(grading_id)
+        .bind(&screen_analysis_json)
+        .bind(&screen_size_json)
+        .bind(moisture_percent)
+        .bind(water_activity)
+        .bind(bulk_density_g_per_l)
+        .bind(grade_to_str(&grade))
+        .execute(&self.db)
+        .await?;
+
+        self.get_physical_analysis(business_id, grading_id).await
+    }
+
+    /// Correct a grading; the grade is classified again and the changed
+    /// fields are kept in the grading's edit history
+    pub async fn update_grading(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        grading_id: Uuid,
+        input: UpdateGradingInput,
+    ) -> AppResult<GradingRecord> {
+        let before = self.get_grading(business_id, grading_id).await?;
+
+        let defects = corrected_defects(
+            &before.defects,
+            input.defect_breakdown,
+            input.category1_count,
+            input.category2_count,
+        )?;
+        let grader_name = input.grader_name.unwrap_or_else(|| before.grader_name.clone());
+        let sample_weight_grams = input.sample_weight_grams.unwrap_or(before.sample_weight_grams);
+        let moisture_percent = input.moisture_percent.unwrap_or(before.moisture_percent);
+        self.validate_grading_input(
+            &grader_name,
+            sample_weight_grams,
+            defects.category1_count,
+            defects.category2_count,
+            moisture_percent,
+        )?;
+
+        let standards = GradingStandardService::new(self.db.clone());
+        let (standard_id, thresholds) = match input.grading_standard_id {
+            Some(standard_id) => {
+                let standard = standards.resolve(business_id, Some(standard_id)).await?;
+                (standard.id, standard.thresholds)
+            }
+            None => {
+                let standard = standards.for_grading(before.grading_standard_id).await?;
+                (before.grading_standard_id, standard.thresholds)
+            }
+        };
+        let grade = classify_grade_with(&defects, Some(moisture_percent), &thresholds);
+
+        let defect_breakdown_json = defects
+            .defect_breakdown
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query_as::<_, GradingRow>(
+            r#"
+            UPDATE green_bean_grades
+            SET grading_date = $2, grader_name = $3, sample_weight_grams = $4,
+                category1_count = $5, category2_count = $6, defect_breakdown = $7,
+                moisture_percent = $8, density = $9, grade = $10, grading_standard_id = $11,
+                notes = $12, notes_th = $13, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, lot_id, grading_date, grader_name, sample_weight_grams,
+                      category1_count, category2_count, defect_breakdown, ai_detection,
+                      moisture_percent, density, screen_size_distribution, grade,
+                      screen_analysis, water_activity, bulk_density_g_per_l,
+                      grading_standard_id, notes, notes_th, created_at, updated_at
+            "#,
+        )
+        .bind(grading_id)
+        .bind(input.grading_date.unwrap_or(before.grading_date))
+        .bind(grader_name.trim())
+        .bind(sample_weight_grams)
+        .bind(defects.category1_count)
+        .bind(defects.category2_count)
+        .bind(&defect_breakdown_json)
+        .bind(moisture_percent)
+        .bind(input.density.or(before.density))
+        .bind(grade_to_str(&grade))
+        .bind(standard_id)
+        .bind(input.notes.map_or(before.notes.clone(), clean_notes))
+        .bind(input.notes_th.map_or(before.notes_th.clone(), clean_notes))
+        .fetch_one(&mut *tx)
+        .await?;
+        let after: GradingRecord = row.into();
+
+        let changes = grading_changes(&grading_values(&before), Some(&grading_values(&after)));
+        if !changes.is_empty() {
+            Self::record_edit(&mut tx, business_id, &before, GradingEditAction::Update, &changes, input.reason, user_id)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(after)
+    }
+
+    /// Delete a grading with its photos, keeping it in the recycle bin and
+    /// the values it had in its edit history. Gradings a quality evaluation
+    /// rests on are kept
+    pub async fn delete_grading(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        grading_id: Uuid,
+        reason: Option<String>,
+    ) -> AppResult<()> {
+        let grading = self.get_grading(business_id, grading_id).await?;
+
+        let evaluations = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM quality_evaluations WHERE grading_id = $1",
+        )
+        .bind(grading_id)
+        .fetch_one(&self.db)
+        .await?;
+        if evaluations > 0 {
+            return Err(AppError::Conflict {
+                resource: "grading_id".to_string(),
+                message: format!(
+                    "The grading is used by {} quality evaluation(s) and cannot be deleted",
+                    evaluations
+                ),
+                message_th: format!("การเกรดนี้ถูกใช้ในการประเมินคุณภาพ {} รายการ จึงลบไม่ได้", evaluations),
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+        let changes = grading_changes(&grading_values(&grading), None);
+        Self::record_edit(&mut tx, business_id, &grading, GradingEditAction::Delete, &changes, reason, user_id).await?;
+        RecycleBinService::capture(&mut tx, business_id, Some(user_id), DeletedEntity::Grading, grading_id).await?;
+        sqlx::query("DELETE FROM green_bean_grades WHERE id = $1")
+            .bind(grading_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Edit history of a grading, newest first; kept after the grading is
+    /// deleted
+    pub async fn grading_edit_history(&self, business_id: Uuid, grading_id: Uuid) -> AppResult<Vec<GradingEdit>> {
+        let rows = sqlx::query_as::<_, GradingEditRow>(
+            r#"
+            SELECT e.id, e.grading_id, e.lot_id, e.action, e.changes, e.reason,
+                   e.edited_by, u.name AS edited_by_name, e.edited_at
+            FROM grading_edits e
+            LEFT JOIN users u ON u.id = e.edited_by
+            WHERE e.business_id = $1 AND e.grading_id = $2
+            ORDER BY e.edited_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(grading_id)
+        .fetch_all(&self.db)
+        .await?;
+        if rows.is_empty() {
+            // Not edited yet, or not a grading of the business
+            self.get_grading(business_id, grading_id).await?;
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| GradingEdit {
+                id: row.id,
+                grading_id: row.grading_id,
+                lot_id: row.lot_id,
+                action: if row.action == "delete" { GradingEditAction::Delete } else { GradingEditAction::Update },
+                changes: row.changes.0,
+                reason: row.reason,
+                edited_by: row.edited_by,
+                edited_by_name: row.edited_by_name,
+                edited_at: row.edited_at,
+            })
+            .collect())
+    }
+
+    async fn record_edit(
+        tx: &mut Transaction<'_, Postgres>,
+        business_id: Uuid,
+        grading: &GradingRecord,
+        action: GradingEditAction,
+        changes: &[GradingFieldChange],
+        reason: Option<String>,
+        user_id: Uuid,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO grading_edits (business_id, lot_id, grading_id, action, changes, reason, edited_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(business_id)
+        .bind(grading.lot_id)
+        .bind(grading.id)
+        .bind(action.as_str())
+        .bind(sqlx::types::Json(changes))
+        .bind(reason.and_then(clean_notes))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
     /// Get grading history for a lot
     pub async fn get_grading_history(
         &self,
@@ -352,7 +1163,8 @@ impl GradingService {
             SELECT g.id, g.lot_id, g.grading_date, g.grader_name, g.sample_weight_grams,
                    g.category1_count, g.category2_count, g.defect_breakdown, g.ai_detection,
                    g.moisture_percent, g.density, g.screen_size_distribution, g.grade,
-                   g.notes, g.notes_th, g.created_at, g.updated_at
+                   g.screen_analysis, g.water_activity, g.bulk_density_g_per_l,
+                   g.grading_standard_id, g.notes, g.notes_th, g.created_at, g.updated_at
             FROM green_bean_grades g
             JOIN lots l ON l.id = g.lot_id
             WHERE g.lot_id = $1 AND l.business_id = $2
@@ -374,7 +1186,8 @@ impl GradingService {
             SELECT g.id, g.lot_id, g.grading_date, g.grader_name, g.sample_weight_grams,
                    g.category1_count, g.category2_count, g.defect_breakdown, g.ai_detection,
                    g.moisture_percent, g.density, g.screen_size_distribution, g.grade,
-                   g.notes, g.notes_th, g.created_at, g.updated_at
+                   g.screen_analysis, g.water_activity, g.bulk_density_g_per_l,
+                   g.grading_standard_id, g.notes, g.notes_th, g.created_at, g.updated_at
             FROM green_bean_grades g
             JOIN lots l ON l.id = g.lot_id
             WHERE l.business_id = $1
@@ -433,13 +1246,105 @@ impl GradingService {
         })
     }
 
+    /// Compare the latest gradings and cupping scores of several lots
+    pub async fn compare_lots(&self, business_id: Uuid, lot_ids: &[Uuid]) -> AppResult<LotGradingComparison> {
+        let lots = sqlx::query_as::<_, (Uuid, String, String, LotStage)>(
+            "SELECT id, name, traceability_code, stage FROM lots WHERE business_id = $1 AND id = ANY($2)",
+        )
+        .bind(business_id)
+        .bind(lot_ids)
+        .fetch_all(&self.db)
+        .await?;
+        if lots.len() != lot_ids.len() {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        let gradings: Vec<GradingRecord> = sqlx::query_as::<_, GradingRow>(
+            r#"
+            SELECT DISTINCT ON (g.lot_id)
+                   g.id, g.lot_id, g.grading_date, g.grader_name, g.sample_weight_grams,
+                   g.category1_count, g.category2_count, g.defect_breakdown, g.ai_detection,
+                   g.moisture_percent, g.density, g.screen_size_distribution, g.grade,
+                   g.screen_analysis, g.water_activity, g.bulk_density_g_per_l,
+                   g.grading_standard_id, g.notes, g.notes_th, g.created_at, g.updated_at
+            FROM green_bean_grades g
+            JOIN lots l ON l.id = g.lot_id
+            WHERE l.business_id = $1 AND g.lot_id = ANY($2)
+            ORDER BY g.lot_id, g.grading_date DESC, g.created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(lot_ids)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(GradingRecord::from)
+        .collect();
+
+        let cuppings = sqlx::query_as::<_, (Uuid, i64, Option<Decimal>, Option<Decimal>, Option<NaiveDate>)>(
+            r#"
+            SELECT cs.lot_id, COUNT(*), ROUND(AVG(cs.final_score), 2),
+                   (ARRAY_AGG(cs.final_score ORDER BY s.session_date DESC, cs.created_at DESC))[1],
+                   MAX(s.session_date)
+            FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            WHERE s.business_id = $1 AND cs.lot_id = ANY($2)
+              AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)
+            GROUP BY cs.lot_id
+            "#,
+        )
+        .bind(business_id)
+        .bind(lot_ids)
+        .fetch_all(&self.db)
+        .await?;
+
+        let breakdowns: Vec<Option<&DefectBreakdown>> =
+            gradings.iter().map(|g| g.defects.defect_breakdown.as_ref()).collect();
+        let defect_types = compared_defect_types(&breakdowns);
+
+        let columns = lot_ids
+            .iter()
+            .filter_map(|lot_id| lots.iter().find(|lot| lot.0 == *lot_id))
+            .map(|(lot_id, lot_name, traceability_code, stage)| {
+                let grading = gradings.iter().find(|g| g.lot_id == *lot_id);
+                let cupping = cuppings.iter().find(|c| c.0 == *lot_id);
+                LotGradingColumn {
+                    lot_id: *lot_id,
+                    lot_name: lot_name.clone(),
+                    traceability_code: traceability_code.clone(),
+                    stage: *stage,
+                    grading_id: grading.map(|g| g.id),
+                    grading_date: grading.map(|g| g.grading_date),
+                    grade: grading.map(|g| g.grade.clone()),
+                    category1_defects: grading.map(|g| g.defects.category1_defects()),
+                    category2_defects: grading.map(|g| g.defects.category2_defects()),
+                    total_defects: grading.map(|g| g.defects.total()),
+                    defect_counts: grading
+                        .and_then(|g| g.defects.defect_breakdown.as_ref())
+                        .map(|b| defect_types.iter().map(|defect| b.count(*defect)).collect()),
+                    moisture_percent: grading.map(|g| g.moisture_percent),
+                    water_activity: grading.and_then(|g| g.water_activity),
+                    cupping_sample_count: cupping.map(|c| c.1).unwrap_or(0),
+                    average_cupping_score: cupping.and_then(|c| c.2),
+                    latest_cupping_score: cupping.and_then(|c| c.3),
+                    latest_cupping_date: cupping.and_then(|c| c.4),
+                }
+            })
+            .collect();
+
+        Ok(LotGradingComparison {
+            defect_types,
+            lots: columns,
+        })
+    }
+
     /// Validate lot exists and is in appropriate stage for grading
     async fn validate_lot_for_grading(
         &self,
         business_id: Uuid,
         lot_id: Uuid,
     ) -> AppResult<()> {
-        let lot = sqlx::query_as::<_, (Uuid, String)>(
+        let lot = sqlx::query_as::<_, (Uuid, LotStage)>(
             "SELECT id, stage FROM lots WHERE id = $1 AND business_id = $2",
         )
         .bind(lot_id)
@@ -449,16 +1354,16 @@ impl GradingService {
         .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
 
         // Grading is typically done on green beans
-        if lot.1 != LotStage::GreenBean.as_str() {
+        if lot.1 != LotStage::GreenBean {
             return Err(AppError::Validation {
                 field: "lot_id".to_string(),
                 message: format!(
-                    "Lot should be in GreenBean stage for grading, current stage: {}",
+                    "Lot should be in Green Bean stage for grading, current stage: {}",
                     lot.1
                 ),
                 message_th: format!(
-                    "ล็อตควรอยู่ในสถานะกาแฟกะลาเพื่อการเกรด สถานะปัจจุบัน: {}",
-                    lot.1
+                    "ล็อตควรอยู่ในสถานะสารกาแฟเพื่อการเกรด สถานะปัจจุบัน: {}",
+                    lot.1.name_in(&Language::Thai)
                 ),
             });
         }
@@ -520,7 +1425,7 @@ impl GradingService {
 }
 
 /// Convert GradeClassification to database string
-fn grade_to_str(grade: &GradeClassification) -> &'static str {
+pub(crate) fn grade_to_str(grade: &GradeClassification) -> &'static str {
     match grade {
         GradeClassification::SpecialtyGrade => "specialty_grade",
         GradeClassification::PremiumGrade => "premium_grade",
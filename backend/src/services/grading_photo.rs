@@ -0,0 +1,447 @@
+//! Grading photo attachments and AI re-analysis
+//!
+//! Bean tray photos of a graded sample are stored in S3 under the grading
+//! and listed with it. Re-analyzing a grading sends its stored photos back
+//! through the AI defect detection service; each run adds a numbered version
+//! of every photo's analysis, with the breakdown's full defect equivalents
+//! and the grade they classify to, so results from newer detection models
+//! can be compared with earlier runs and with the grading as recorded. The
+//! grading itself is never changed by a re-analysis.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::{classify_grade_with, DefectBreakdown, DefectCount};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::external::ai_defect_detection::{AiDefectDetectionClient, DetectDefectsRequest};
+use crate::external::S3Client;
+use crate::services::grading::{grade_to_str, grading_defects};
+use crate::services::GradingStandardService;
+
+/// Largest tray photo accepted, in bytes
+pub const MAX_GRADING_PHOTO_BYTES: usize = 10 * 1024 * 1024;
+
+/// Photos one grading may have
+pub const MAX_PHOTOS_PER_GRADING: i64 = 20;
+
+/// Grading photo service
+#[derive(Clone)]
+pub struct GradingPhotoService {
+    db: PgPool,
+    s3: Option<S3Client>,
+    ai_client: Option<AiDefectDetectionClient>,
+}
+
+/// Tray photo attached to a grading
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GradingPhoto {
+    pub id: Uuid,
+    pub grading_id: Uuid,
+    pub s3_key: String,
+    pub content_type: String,
+    pub file_size_bytes: i64,
+    pub sha256: String,
+    pub caption: Option<String>,
+    pub uploaded_by: Option<Uuid>,
+    pub uploaded_at: DateTime<Utc>,
+    /// Latest analysis version, if the photo has been analyzed
+    pub latest_version: Option<i32>,
+}
+
+/// One version of the AI analysis of a photo
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GradingPhotoAnalysis {
+    pub id: Uuid,
+    pub photo_id: Uuid,
+    pub grading_id: Uuid,
+    pub version: i32,
+    pub ai_request_id: String,
+    pub detected_beans: i32,
+    pub defect_breakdown: serde_json::Value,
+    /// Full defect equivalents of the detected breakdown
+    pub category1_count: i32,
+    pub category2_count: i32,
+    /// Grade the detected defects classify to
+    pub grade: String,
+    /// Grade the AI service suggested
+    pub suggested_grade: String,
+    pub confidence_score: f32,
+    pub annotated_image_url: Option<String>,
+    pub analyzed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for attaching a photo to a grading
+#[derive(Debug, Deserialize)]
+pub struct UploadGradingPhotoInput {
+    pub image_base64: String,
+    pub caption: Option<String>,
+}
+
+/// Input for re-analyzing a grading's photos
+#[derive(Debug, Default, Deserialize)]
+pub struct ReanalyzeGradingInput {
+    /// Only these photos; every photo of the grading when omitted
+    pub photo_ids: Option<Vec<Uuid>>,
+}
+
+/// Result of a re-analysis
+#[derive(Debug, Serialize)]
+pub struct GradingReanalysis {
+    pub grading_id: Uuid,
+    /// Grade recorded on the grading
+    pub recorded_grade: String,
+    pub analyses: Vec<GradingPhotoAnalysis>,
+    /// Photos whose new analysis grades differently from the grading
+    pub grade_changes: usize,
+}
+
+/// Fields of a grading its photos are analyzed against
+#[derive(sqlx::FromRow)]
+struct GradingHeader {
+    grade: String,
+    sample_weight_grams: Decimal,
+    moisture_percent: Decimal,
+    grading_standard_id: Option<Uuid>,
+}
+
+/// Detection of one photo awaiting its analysis version
+struct PhotoDetection {
+    photo_id: Uuid,
+    request_id: String,
+    detected_beans: i32,
+    breakdown: DefectBreakdown,
+    defects: DefectCount,
+    suggested_grade: String,
+    confidence_score: f32,
+    annotated_image_url: Option<String>,
+}
+
+/// Image type of a photo from its leading bytes; only JPEG, PNG and WebP
+/// are accepted
+pub fn image_content_type(image: &[u8]) -> Option<&'static str> {
+    if image.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if image.len() >= 12 && &image[0..4] == b"RIFF" && &image[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// File extension of an accepted image type
+pub fn extension(content_type: &str) -> &'static str {
+    match content_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        _ => "jpg",
+    }
+}
+
+/// S3 key of a grading photo
+pub fn photo_key(business_id: Uuid, grading_id: Uuid, photo_id: Uuid, content_type: &str) -> String {
+    format!(
+        "gradings/{}/{}/{}.{}",
+        business_id,
+        grading_id,
+        photo_id,
+        extension(content_type)
+    )
+}
+
+/// Decode and check an uploaded photo, returning its bytes and type
+pub fn decode_photo(image_base64: &str) -> AppResult<(Vec<u8>, &'static str)> {
+    let image = BASE64.decode(image_base64.trim()).map_err(|_| AppError::Validation {
+        field: "image_base64".to_string(),
+        message: "Image must be base64 encoded".to_string(),
+        message_th: "รูปภาพต้องเข้ารหัสแบบ base64".to_string(),
+    })?;
+    if image.is_empty() || image.len() > MAX_GRADING_PHOTO_BYTES {
+        return Err(AppError::Validation {
+            field: "image_base64".to_string(),
+            message: format!("Image must be between 1 byte and {} MB", MAX_GRADING_PHOTO_BYTES / (1024 * 1024)),
+            message_th: format!("รูปภาพต้องมีขนาดไม่เกิน {} MB", MAX_GRADING_PHOTO_BYTES / (1024 * 1024)),
+        });
+    }
+    let content_type = image_content_type(&image).ok_or_else(|| AppError::Validation {
+        field: "image_base64".to_string(),
+        message: "Image must be a JPEG, PNG or WebP photo".to_string(),
+        message_th: "รูปภาพต้องเป็นไฟล์ JPEG, PNG หรือ WebP".to_string(),
+    })?;
+    Ok((image, content_type))
+}
+
+const PHOTO_SELECT: &str = r#"
+    SELECT p.id, p.grading_id, p.s3_key, p.content_type, p.file_size_bytes, p.sha256, p.caption,
+           p.uploaded_by, p.uploaded_at,
+           (SELECT MAX(a.version) FROM grading_photo_analyses a WHERE a.photo_id = p.id) AS latest_version
+    FROM grading_photos p
+"#;
+
+const ANALYSIS_COLUMNS: &str = "id, photo_id, grading_id, version, ai_request_id, detected_beans, defect_breakdown, \
+     category1_count, category2_count, grade, suggested_grade, confidence_score, annotated_image_url, \
+     analyzed_by, created_at";
+
+impl GradingPhotoService {
+    /// Create a service using the S3 bucket and AI service configured in the
+    /// environment
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db,
+            s3: S3Client::from_env(),
+            ai_client: AiDefectDetectionClient::from_env(),
+        }
+    }
+
+    fn s3(&self) -> AppResult<&S3Client> {
+        self.s3
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("S3 media storage not configured".to_string()))
+    }
+
+    /// Grade and sample weight of a grading of the business
+    async fn grading(&self, business_id: Uuid, grading_id: Uuid) -> AppResult<GradingHeader> {
+        sqlx::query_as::<_, GradingHeader>(
+            r#"
+            SELECT g.grade, g.sample_weight_grams, g.moisture_percent, g.grading_standard_id
+            FROM green_bean_grades g
+            JOIN lots l ON l.id = g.lot_id
+            WHERE g.id = $1 AND l.business_id = $2
+            "#,
+        )
+        .bind(grading_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Grading record".to_string()))
+    }
+
+    /// Attach a tray photo to a grading, storing it in S3
+    pub async fn upload_photo(
+        &self,
+        business_id: Uuid,
+        grading_id: Uuid,
+        user_id: Uuid,
+        input: UploadGradingPhotoInput,
+    ) -> AppResult<GradingPhoto> {
+        self.grading(business_id, grading_id).await?;
+        let (image, content_type) = decode_photo(&input.image_base64)?;
+        let sha256 = format!("{:x}", Sha256::digest(&image));
+
+        let (photos, duplicate) = sqlx::query_as::<_, (i64, bool)>(
+            "SELECT COUNT(*), COALESCE(BOOL_OR(sha256 = $2), FALSE) FROM grading_photos WHERE grading_id = $1",
+        )
+        .bind(grading_id)
+        .bind(&sha256)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(AppError::Conflict {
+                resource: "grading_photo".to_string(),
+                message: "This photo is already attached to the grading".to_string(),
+                message_th: "รูปภาพนี้แนบกับผลการคัดเกรดแล้ว".to_string(),
+            });
+        }
+        if photos >= MAX_PHOTOS_PER_GRADING {
+            return Err(AppError::Validation {
+                field: "image_base64".to_string(),
+                message: format!("A grading can have at most {} photos", MAX_PHOTOS_PER_GRADING),
+                message_th: format!("ผลการคัดเกรดมีรูปภาพได้ไม่เกิน {} รูป", MAX_PHOTOS_PER_GRADING),
+            });
+        }
+
+        let photo_id = Uuid::new_v4();
+        let s3_key = photo_key(business_id, grading_id, photo_id, content_type);
+        let file_size_bytes = image.len() as i64;
+        self.s3()?.put_object(&s3_key, image, content_type).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO grading_photos (id, grading_id, business_id, s3_key, content_type,
+                                        file_size_bytes, sha256, caption, uploaded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(photo_id)
+        .bind(grading_id)
+        .bind(business_id)
+        .bind(&s3_key)
+        .bind(content_type)
+        .bind(file_size_bytes)
+        .bind(&sha256)
+        .bind(&input.caption)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+
+        sqlx::query_as::<_, GradingPhoto>(&format!("{} WHERE p.id = $1", PHOTO_SELECT))
+            .bind(photo_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Photos of a grading, oldest first
+    pub async fn list_photos(&self, business_id: Uuid, grading_id: Uuid) -> AppResult<Vec<GradingPhoto>> {
+        self.grading(business_id, grading_id).await?;
+        let photos = sqlx::query_as::<_, GradingPhoto>(&format!(
+            "{} WHERE p.grading_id = $1 ORDER BY p.uploaded_at",
+            PHOTO_SELECT
+        ))
+        .bind(grading_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(photos)
+    }
+
+    /// Every analysis version of a grading's photos, newest first
+    pub async fn list_analyses(&self, business_id: Uuid, grading_id: Uuid) -> AppResult<Vec<GradingPhotoAnalysis>> {
+        self.grading(business_id, grading_id).await?;
+        let analyses = sqlx::query_as::<_, GradingPhotoAnalysis>(&format!(
+            "SELECT {} FROM grading_photo_analyses WHERE grading_id = $1 ORDER BY created_at DESC, version DESC",
+            ANALYSIS_COLUMNS
+        ))
+        .bind(grading_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(analyses)
+    }
+
+    /// Run the grading's stored photos through AI defect detection again,
+    /// saving the results as each photo's next analysis version
+    pub async fn reanalyze(
+        &self,
+        business_id: Uuid,
+        grading_id: Uuid,
+        user_id: Uuid,
+        input: ReanalyzeGradingInput,
+    ) -> AppResult<GradingReanalysis> {
+        let grading = self.grading(business_id, grading_id).await?;
+        let (recorded_grade, sample_weight_grams) = (grading.grade, grading.sample_weight_grams);
+        let standard = GradingStandardService::new(self.db.clone())
+            .for_grading(grading.grading_standard_id)
+            .await?;
+        if input.photo_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            return Err(AppError::Validation {
+                field: "photo_ids".to_string(),
+                message: "List at least one photo, or omit photo_ids to re-analyze every photo".to_string(),
+                message_th: "ระบุรูปภาพอย่างน้อยหนึ่งรูป หรือไม่ระบุ photo_ids เพื่อวิเคราะห์ทุกรูป".to_string(),
+            });
+        }
+
+        let photos: Vec<GradingPhoto> = self
+            .list_photos(business_id, grading_id)
+            .await?
+            .into_iter()
+            .filter(|photo| input.photo_ids.as_ref().is_none_or(|ids| ids.contains(&photo.id)))
+            .collect();
+        if let Some(missing) = input
+            .photo_ids
+            .iter()
+            .flatten()
+            .find(|id| !photos.iter().any(|photo| photo.id == **id))
+        {
+            return Err(AppError::Validation {
+                field: "photo_ids".to_string(),
+                message: format!("Photo {} is not attached to this grading", missing),
+                message_th: format!("รูปภาพ {} ไม่ได้แนบกับผลการคัดเกรดนี้", missing),
+            });
+        }
+        if photos.is_empty() {
+            return Err(AppError::Validation {
+                field: "photo_ids".to_string(),
+                message: "The grading has no photos to analyze".to_string(),
+                message_th: "ผลการคัดเกรดนี้ไม่มีรูปภาพให้วิเคราะห์".to_string(),
+            });
+        }
+
+        let ai_client = self
+            .ai_client
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("AI detection service not configured".to_string()))?;
+        let s3 = self.s3()?;
+
+        // Detect every photo before saving, so a failed run saves nothing
+        let mut detections = Vec::with_capacity(photos.len());
+        for photo in &photos {
+            let image = s3.get_object(&photo.s3_key).await?;
+            let response = ai_client
+                .detect_defects(DetectDefectsRequest {
+                    image_base64: BASE64.encode(&image),
+                    sample_weight_grams: sample_weight_grams.to_f64(),
+                })
+                .await?;
+            let detection = response.detection;
+            let breakdown: DefectBreakdown = detection.defect_breakdown.into();
+            let defects = grading_defects(
+                detection.category1_count,
+                detection.category2_count,
+                Some(breakdown.clone()),
+            )?;
+            detections.push(PhotoDetection {
+                photo_id: photo.id,
+                request_id: response.request_id,
+                detected_beans: detection.detected_beans,
+                breakdown,
+                defects,
+                suggested_grade: response.suggested_grade,
+                confidence_score: detection.confidence_score,
+                annotated_image_url: detection.annotated_image_url,
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+        let mut analyses = Vec::with_capacity(detections.len());
+        for detection in detections {
+            let breakdown_json =
+                serde_json::to_value(&detection.breakdown).map_err(|e| AppError::Internal(e.to_string()))?;
+            let analysis = sqlx::query_as::<_, GradingPhotoAnalysis>(&format!(
+                r#"
+                INSERT INTO grading_photo_analyses (photo_id, grading_id, version, ai_request_id, detected_beans,
+                                                    defect_breakdown, category1_count, category2_count, grade,
+                                                    suggested_grade, confidence_score, annotated_image_url, analyzed_by)
+                SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12
+                FROM grading_photo_analyses WHERE photo_id = $1
+                RETURNING {}
+                "#,
+                ANALYSIS_COLUMNS
+            ))
+            .bind(detection.photo_id)
+            .bind(grading_id)
+            .bind(&detection.request_id)
+            .bind(detection.detected_beans)
+            .bind(breakdown_json)
+            .bind(detection.defects.category1_count)
+            .bind(detection.defects.category2_count)
+            .bind(grade_to_str(&classify_grade_with(
+                &detection.defects,
+                Some(grading.moisture_percent),
+                &standard.thresholds,
+            )))
+            .bind(&detection.suggested_grade)
+            .bind(detection.confidence_score)
+            .bind(&detection.annotated_image_url)
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            analyses.push(analysis);
+        }
+        tx.commit().await?;
+
+        let grade_changes = analyses.iter().filter(|a| a.grade != recorded_grade).count();
+        Ok(GradingReanalysis {
+            grading_id,
+            recorded_grade,
+            analyses,
+            grade_changes,
+        })
+    }
+}
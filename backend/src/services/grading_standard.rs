@@ -0,0 +1,377 @@
+//! Grading standards per market
+//!
+//! Buyers grade green coffee against different standards: the SCA rules,
+//! Thai FDA limits or an EU importer's specification. A business keeps its
+//! own standards, each giving the most full defects (in all and of
+//! category 1) and the moisture range of every grade, and picks one when
+//! recording a grading. A grading without one uses the business default
+//! standard, or the SCA rules when the business has no default.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::{sca_thresholds, GradeClassification, GradeThreshold};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Grading standard service
+#[derive(Clone)]
+pub struct GradingStandardService {
+    db: PgPool,
+}
+
+/// Grading standard of a business
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GradingStandard {
+    pub id: Uuid,
+    pub name: String,
+    pub name_th: String,
+    pub market: Option<String>,
+    /// Limits of each grade, best grade first; a sample meeting none is off grade
+    pub thresholds: sqlx::types::Json<Vec<GradeThreshold>>,
+    pub is_default: bool,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Standard a grading is classified under
+#[derive(Debug, Clone)]
+pub struct ResolvedStandard {
+    /// None for the SCA rules
+    pub id: Option<Uuid>,
+    pub thresholds: Vec<GradeThreshold>,
+}
+
+/// Input for adding a grading standard
+#[derive(Debug, Deserialize)]
+pub struct CreateGradingStandardInput {
+    pub name: String,
+    pub name_th: String,
+    pub market: Option<String>,
+    pub thresholds: Vec<GradeThreshold>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Input for editing a grading standard; fields left out stay as they are.
+/// Gradings already recorded keep the grade they were given
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateGradingStandardInput {
+    pub name: Option<String>,
+    pub name_th: Option<String>,
+    pub market: Option<String>,
+    pub thresholds: Option<Vec<GradeThreshold>>,
+    pub is_default: Option<bool>,
+    pub active: Option<bool>,
+}
+
+const STANDARD_COLUMNS: &str =
+    "id, name, name_th, market, thresholds, is_default, active, created_at, updated_at";
+
+fn threshold_error(grade: &GradeClassification, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: format!("thresholds.{}", grade_code(grade)),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn grade_code(grade: &GradeClassification) -> String {
+    serde_json::to_value(grade)
+        .ok()
+        .and_then(|code| code.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Check the thresholds of a standard and put them best grade first. Off
+/// grade has no thresholds (it takes every sample no other grade does), each
+/// grade appears once, limits are non-negative with moisture within 0-100%,
+/// and a worse grade allows at least as many defects as a better one
+pub fn validate_thresholds(thresholds: &[GradeThreshold]) -> AppResult<Vec<GradeThreshold>> {
+    if thresholds.is_empty() {
+        return Err(AppError::Validation {
+            field: "thresholds".to_string(),
+            message: "Give the limits of at least one grade".to_string(),
+            message_th: "กรุณาระบุเกณฑ์ของเกรดอย่างน้อยหนึ่งเกรด".to_string(),
+        });
+    }
+
+    let mut ordered: Vec<GradeThreshold> = Vec::with_capacity(thresholds.len());
+    for grade in GradeClassification::ALL.iter() {
+        let mut matching = thresholds.iter().filter(|t| t.grade == *grade);
+        let Some(threshold) = matching.next() else {
+            continue;
+        };
+        if matching.next().is_some() {
+            return Err(threshold_error(grade, "Each grade can be given once", "ระบุแต่ละเกรดได้เพียงครั้งเดียว"));
+        }
+        if *grade == GradeClassification::OffGrade {
+            return Err(threshold_error(
+                grade,
+                "Off grade takes every sample that meets no other grade and has no limits",
+                "เกรดต่ำกว่ามาตรฐานรับตัวอย่างที่ไม่ผ่านเกรดอื่นทั้งหมดและไม่มีเกณฑ์",
+            ));
+        }
+        if threshold.max_total_defects < 0 || threshold.max_category1_defects.is_some_and(|max| max < 0) {
+            return Err(threshold_error(grade, "Defect limits cannot be negative", "เกณฑ์ข้อบกพร่องต้องไม่ติดลบ"));
+        }
+        let moisture = [threshold.min_moisture_percent, threshold.max_moisture_percent];
+        if moisture
+            .iter()
+            .flatten()
+            .any(|m| *m < Decimal::ZERO || *m > Decimal::ONE_HUNDRED)
+        {
+            return Err(threshold_error(
+                grade,
+                "Moisture limits must be between 0 and 100%",
+                "เกณฑ์ความชื้นต้องอยู่ระหว่าง 0 ถึง 100%",
+            ));
+        }
+        if let [Some(min), Some(max)] = moisture {
+            if min > max {
+                return Err(threshold_error(
+                    grade,
+                    "The lowest moisture cannot be above the highest",
+                    "ความชื้นต่ำสุดต้องไม่มากกว่าความชื้นสูงสุด",
+                ));
+            }
+        }
+        if ordered.last().is_some_and(|better| better.max_total_defects > threshold.max_total_defects) {
+            return Err(threshold_error(
+                grade,
+                "A grade must allow at least as many defects as the grades above it",
+                "เกรดที่ต่ำกว่าต้องยอมรับข้อบกพร่องได้ไม่น้อยกว่าเกรดที่สูงกว่า",
+            ));
+        }
+        ordered.push(threshold.clone());
+    }
+
+    Ok(ordered)
+}
+
+fn duplicate_name() -> AppError {
+    AppError::Conflict {
+        resource: "grading_standard".to_string(),
+        message: "A grading standard with this name already exists".to_string(),
+        message_th: "มีมาตรฐานการคัดเกรดชื่อนี้อยู่แล้ว".to_string(),
+    }
+}
+
+fn validate_names(name: &str, name_th: &str) -> AppResult<()> {
+    if name.trim().is_empty() || name_th.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: "Give the standard a name in English and Thai".to_string(),
+            message_th: "กรุณาตั้งชื่อมาตรฐานทั้งภาษาอังกฤษและภาษาไทย".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl GradingStandardService {
+    /// Create a new GradingStandardService instance
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Grading standards of the business, the default first
+    pub async fn list_standards(&self, business_id: Uuid, include_inactive: bool) -> AppResult<Vec<GradingStandard>> {
+        let standards = sqlx::query_as::<_, GradingStandard>(&format!(
+            r#"
+            SELECT {} FROM grading_standards
+            WHERE business_id = $1 AND (active OR $2)
+            ORDER BY is_default DESC, name
+            "#,
+            STANDARD_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(include_inactive)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(standards)
+    }
+
+    /// A grading standard of the business
+    pub async fn get_standard(&self, business_id: Uuid, standard_id: Uuid) -> AppResult<GradingStandard> {
+        sqlx::query_as::<_, GradingStandard>(&format!(
+            "SELECT {} FROM grading_standards WHERE id = $1 AND business_id = $2",
+            STANDARD_COLUMNS
+        ))
+        .bind(standard_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Grading standard".to_string()))
+    }
+
+    /// Add a grading standard; a new default replaces the old one
+    pub async fn create_standard(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateGradingStandardInput,
+    ) -> AppResult<GradingStandard> {
+        validate_names(&input.name, &input.name_th)?;
+        let thresholds = validate_thresholds(&input.thresholds)?;
+        self.check_name_free(business_id, &input.name, Uuid::nil()).await?;
+
+        let mut tx = self.db.begin().await?;
+        if input.is_default {
+            sqlx::query("UPDATE grading_standards SET is_default = FALSE WHERE business_id = $1 AND is_default")
+                .bind(business_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let standard_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO grading_standards (business_id, name, name_th, market, thresholds, is_default, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(input.name_th.trim())
+        .bind(input.market.as_deref().map(str::trim).filter(|m| !m.is_empty()))
+        .bind(sqlx::types::Json(&thresholds))
+        .bind(input.is_default)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_standard(business_id, standard_id).await
+    }
+
+    /// Edit a grading standard. A standard no longer in use cannot be the
+    /// default
+    pub async fn update_standard(
+        &self,
+        business_id: Uuid,
+        standard_id: Uuid,
+        input: UpdateGradingStandardInput,
+    ) -> AppResult<GradingStandard> {
+        let existing = self.get_standard(business_id, standard_id).await?;
+        let name = input.name.unwrap_or(existing.name);
+        let name_th = input.name_th.unwrap_or(existing.name_th);
+        validate_names(&name, &name_th)?;
+        let thresholds = match input.thresholds {
+            Some(thresholds) => validate_thresholds(&thresholds)?,
+            None => existing.thresholds.0,
+        };
+        self.check_name_free(business_id, &name, standard_id).await?;
+        let market = input.market.or(existing.market);
+        let active = input.active.unwrap_or(existing.active);
+        let is_default = input.is_default.unwrap_or(existing.is_default) && active;
+
+        let mut tx = self.db.begin().await?;
+        if is_default && !existing.is_default {
+            sqlx::query("UPDATE grading_standards SET is_default = FALSE WHERE business_id = $1 AND is_default")
+                .bind(business_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE grading_standards
+            SET name = $2, name_th = $3, market = $4, thresholds = $5, is_default = $6, active = $7,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(standard_id)
+        .bind(name.trim())
+        .bind(name_th.trim())
+        .bind(market.as_deref().map(str::trim).filter(|m| !m.is_empty()))
+        .bind(sqlx::types::Json(&thresholds))
+        .bind(is_default)
+        .bind(active)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_standard(business_id, standard_id).await
+    }
+
+    async fn check_name_free(&self, business_id: Uuid, name: &str, standard_id: Uuid) -> AppResult<()> {
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM grading_standards WHERE business_id = $1 AND name = $2 AND id <> $3)",
+        )
+        .bind(business_id)
+        .bind(name.trim())
+        .bind(standard_id)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(duplicate_name());
+        }
+        Ok(())
+    }
+
+    /// Standard to classify a grading under: the one picked, else the
+    /// business default, else the SCA rules
+    pub async fn resolve(&self, business_id: Uuid, standard_id: Option<Uuid>) -> AppResult<ResolvedStandard> {
+        let standard = match standard_id {
+            Some(standard_id) => {
+                let standard = self.get_standard(business_id, standard_id).await?;
+                if !standard.active {
+                    return Err(AppError::Validation {
+                        field: "grading_standard_id".to_string(),
+                        message: "The grading standard is no longer in use".to_string(),
+                        message_th: "มาตรฐานการคัดเกรดนี้ไม่ได้ใช้งานแล้ว".to_string(),
+                    });
+                }
+                Some(standard)
+            }
+            None => sqlx::query_as::<_, GradingStandard>(&format!(
+                "SELECT {} FROM grading_standards WHERE business_id = $1 AND is_default AND active",
+                STANDARD_COLUMNS
+            ))
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?,
+        };
+
+        Ok(match standard {
+            Some(standard) => ResolvedStandard {
+                id: Some(standard.id),
+                thresholds: standard.thresholds.0,
+            },
+            None => ResolvedStandard {
+                id: None,
+                thresholds: sca_thresholds(),
+            },
+        })
+    }
+
+    /// Standard a recorded grading was classified under; the SCA rules when
+    /// it had none or its standard has since been removed
+    pub async fn for_grading(&self, standard_id: Option<Uuid>) -> AppResult<ResolvedStandard> {
+        let thresholds = match standard_id {
+            Some(standard_id) => {
+                sqlx::query_scalar::<_, sqlx::types::Json<Vec<GradeThreshold>>>(
+                    "SELECT thresholds FROM grading_standards WHERE id = $1",
+                )
+                .bind(standard_id)
+                .fetch_optional(&self.db)
+                .await?
+            }
+            None => None,
+        };
+
+        Ok(match thresholds {
+            Some(thresholds) => ResolvedStandard {
+                id: standard_id,
+                thresholds: thresholds.0,
+            },
+            None => ResolvedStandard {
+                id: None,
+                thresholds: sca_thresholds(),
+            },
+        })
+    }
+}
@@ -7,6 +7,9 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+use crate::services::ripeness_estimate::{ripeness_overridden, RipenessEstimateService};
+use crate::services::weight_unit::{normalize_unit, WeightUnitService};
 use super::lot::{CreateLotInput, LotService};
 
 /// Harvest service for managing coffee harvests
@@ -24,10 +27,19 @@ pub struct Harvest {
     pub business_id: Uuid,
     pub harvest_date: NaiveDate,
     pub picker_name: Option<String>,
+    /// Picking crew the picker worked in
+    pub crew_name: Option<String>,
     pub cherry_weight_kg: Decimal,
+    /// Weight as entered, in `cherry_weight_unit`
+    pub cherry_weight_original: Decimal,
+    pub cherry_weight_unit: String,
     pub underripe_percent: i32,
     pub ripe_percent: i32,
     pub overripe_percent: i32,
+    /// Photo estimate the ripeness was pre-filled from
+    pub ripeness_estimate_id: Option<Uuid>,
+    /// Ripeness entered differs from the photo estimate
+    pub ripeness_overridden: bool,
     pub weather_snapshot: Option<serde_json::Value>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
@@ -44,10 +56,19 @@ struct HarvestWithLotRow {
     pub business_id: Uuid,
     pub harvest_date: NaiveDate,
     pub picker_name: Option<String>,
+    /// Picking crew the picker worked in
+    pub crew_name: Option<String>,
     pub cherry_weight_kg: Decimal,
+    /// Weight as entered, in `cherry_weight_unit`
+    pub cherry_weight_original: Decimal,
+    pub cherry_weight_unit: String,
     pub underripe_percent: i32,
     pub ripe_percent: i32,
     pub overripe_percent: i32,
+    /// Photo estimate the ripeness was pre-filled from
+    pub ripeness_estimate_id: Option<Uuid>,
+    /// Ripeness entered differs from the photo estimate
+    pub ripeness_overridden: bool,
     pub weather_snapshot: Option<serde_json::Value>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
@@ -67,10 +88,19 @@ pub struct HarvestWithLot {
     pub business_id: Uuid,
     pub harvest_date: NaiveDate,
     pub picker_name: Option<String>,
+    /// Picking crew the picker worked in
+    pub crew_name: Option<String>,
     pub cherry_weight_kg: Decimal,
+    /// Weight as entered, in `cherry_weight_unit`
+    pub cherry_weight_original: Decimal,
+    pub cherry_weight_unit: String,
     pub underripe_percent: i32,
     pub ripe_percent: i32,
     pub overripe_percent: i32,
+    /// Photo estimate the ripeness was pre-filled from
+    pub ripeness_estimate_id: Option<Uuid>,
+    /// Ripeness entered differs from the photo estimate
+    pub ripeness_overridden: bool,
     pub weather_snapshot: Option<serde_json::Value>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
@@ -90,10 +120,15 @@ impl From<HarvestWithLotRow> for HarvestWithLot {
             business_id: row.business_id,
             harvest_date: row.harvest_date,
             picker_name: row.picker_name,
+            crew_name: row.crew_name,
             cherry_weight_kg: row.cherry_weight_kg,
+            cherry_weight_original: row.cherry_weight_original,
+            cherry_weight_unit: row.cherry_weight_unit,
             underripe_percent: row.underripe_percent,
             ripe_percent: row.ripe_percent,
             overripe_percent: row.overripe_percent,
+            ripeness_estimate_id: row.ripeness_estimate_id,
+            ripeness_overridden: row.ripeness_overridden,
             weather_snapshot: row.weather_snapshot,
             notes: row.notes,
             notes_th: row.notes_th,
@@ -112,10 +147,19 @@ pub struct RecordHarvestInput {
     pub plot_id: Uuid,
     pub harvest_date: NaiveDate,
     pub picker_name: Option<String>,
-    pub cherry_weight_kg: Decimal,
+    /// Picking crew the picker worked in
+    pub crew_name: Option<String>,
+    /// Cherry weight in `cherry_weight_unit`
+    #[serde(alias = "cherry_weight_kg")]
+    pub cherry_weight: Decimal,
+    /// Unit the weight was entered in (`kg` when omitted)
+    pub cherry_weight_unit: Option<String>,
     pub underripe_percent: i32,
     pub ripe_percent: i32,
     pub overripe_percent: i32,
+    /// Photo estimate the ripeness was pre-filled from; the percentages
+    /// above are recorded either way
+    pub ripeness_estimate_id: Option<Uuid>,
     pub weather_snapshot: Option<serde_json::Value>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
@@ -123,6 +167,9 @@ pub struct RecordHarvestInput {
     pub lot_id: Option<Uuid>,
     /// Optional: name for new lot (if lot_id not provided)
     pub lot_name: Option<String>,
+    /// Record the harvest even though it looks like one already recorded
+    #[serde(default)]
+    pub confirm_duplicate: bool,
 }
 
 /// Input for updating a harvest
@@ -130,7 +177,13 @@ pub struct RecordHarvestInput {
 pub struct UpdateHarvestInput {
     pub harvest_date: Option<NaiveDate>,
     pub picker_name: Option<String>,
-    pub cherry_weight_kg: Option<Decimal>,
+    /// Picking crew the picker worked in
+    pub crew_name: Option<String>,
+    /// Cherry weight in `cherry_weight_unit`
+    #[serde(alias = "cherry_weight_kg")]
+    pub cherry_weight: Option<Decimal>,
+    /// Unit of the weight; changing it alone re-converts the weight as entered
+    pub cherry_weight_unit: Option<String>,
     pub underripe_percent: Option<i32>,
     pub ripe_percent: Option<i32>,
     pub overripe_percent: Option<i32>,
@@ -139,8 +192,34 @@ pub struct UpdateHarvestInput {
     pub notes_th: Option<String>,
 }
 
+/// Share of the larger weight two pickings of one plot on one day may
+/// differ by and still count as the same picking (2%)
+pub const DUPLICATE_WEIGHT_TOLERANCE: Decimal = Decimal::from_parts(2, 0, 0, false, 2);
+
+/// Harvest already recorded for the same plot and date
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SameDayHarvest {
+    pub id: Uuid,
+    pub picker_name: Option<String>,
+    pub cherry_weight_kg: Decimal,
+}
+
+/// Whether two cherry weights are within the duplicate tolerance
+pub fn weights_match(a: Decimal, b: Decimal) -> bool {
+    (a - b).abs() <= a.max(b) * DUPLICATE_WEIGHT_TOLERANCE
+}
+
+/// The same-day harvest a new one most likely repeats: the closest weight
+/// within the tolerance
+pub fn find_duplicate_harvest(cherry_weight_kg: Decimal, same_day: &[SameDayHarvest]) -> Option<&SameDayHarvest> {
+    same_day
+        .iter()
+        .filter(|h| weights_match(cherry_weight_kg, h.cherry_weight_kg))
+        .min_by_key(|h| (h.cherry_weight_kg - cherry_weight_kg).abs())
+}
+
 /// Ripeness assessment
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RipenessAssessment {
     pub underripe_percent: i32,
     pub ripe_percent: i32,
@@ -177,9 +256,10 @@ impl HarvestService {
     pub async fn get_harvests(&self, business_id: Uuid) -> AppResult<Vec<HarvestWithLot>> {
         let rows = sqlx::query_as::<_, HarvestWithLotRow>(
             r#"
-            SELECT h.id, h.lot_id, h.plot_id, h.business_id, h.harvest_date, h.picker_name,
-                   h.cherry_weight_kg, h.underripe_percent, h.ripe_percent, h.overripe_percent,
-                   h.weather_snapshot, h.notes, h.notes_th, h.created_at, h.updated_at,
+            SELECT h.id, h.lot_id, h.plot_id, h.business_id, h.harvest_date, h.picker_name, h.crew_name,
+                   h.cherry_weight_kg, h.cherry_weight_original, h.cherry_weight_unit,
+                   h.underripe_percent, h.ripe_percent, h.overripe_percent,
+                   h.ripeness_estimate_id, h.ripeness_overridden, h.weather_snapshot, h.notes, h.notes_th, h.created_at, h.updated_at,
                    l.traceability_code as lot_traceability_code, l.name as lot_name, p.name as plot_name
             FROM harvests h
             JOIN lots l ON l.id = h.lot_id
@@ -203,9 +283,10 @@ impl HarvestService {
     ) -> AppResult<Vec<Harvest>> {
         let harvests = sqlx::query_as::<_, Harvest>(
             r#"
-            SELECT id, lot_id, plot_id, business_id, harvest_date, picker_name,
-                   cherry_weight_kg, underripe_percent, ripe_percent, overripe_percent,
-                   weather_snapshot, notes, notes_th, created_at, updated_at
+            SELECT id, lot_id, plot_id, business_id, harvest_date, picker_name, crew_name,
+                   cherry_weight_kg, cherry_weight_original, cherry_weight_unit,
+                   underripe_percent, ripe_percent, overripe_percent,
+                   ripeness_estimate_id, ripeness_overridden, weather_snapshot, notes, notes_th, created_at, updated_at
             FROM harvests
             WHERE lot_id = $1 AND business_id = $2
             ORDER BY harvest_date DESC
@@ -227,9 +308,10 @@ impl HarvestService {
     ) -> AppResult<HarvestWithLot> {
         let row = sqlx::query_as::<_, HarvestWithLotRow>(
             r#"
-            SELECT h.id, h.lot_id, h.plot_id, h.business_id, h.harvest_date, h.picker_name,
-                   h.cherry_weight_kg, h.underripe_percent, h.ripe_percent, h.overripe_percent,
-                   h.weather_snapshot, h.notes, h.notes_th, h.created_at, h.updated_at,
+            SELECT h.id, h.lot_id, h.plot_id, h.business_id, h.harvest_date, h.picker_name, h.crew_name,
+                   h.cherry_weight_kg, h.cherry_weight_original, h.cherry_weight_unit,
+                   h.underripe_percent, h.ripe_percent, h.overripe_percent,
+                   h.ripeness_estimate_id, h.ripeness_overridden, h.weather_snapshot, h.notes, h.notes_th, h.created_at, h.updated_at,
                    l.traceability_code as lot_traceability_code, l.name as lot_name, p.name as plot_name
             FROM harvests h
             JOIN lots l ON l.id = h.lot_id
@@ -265,14 +347,42 @@ impl HarvestService {
             message_th: format!("เปอร์เซ็นต์ความสุกไม่ถูกต้อง: {}", msg),
         })?;
 
+        // Ripeness pre-filled from a photo estimate, possibly overridden
+        let overridden = match input.ripeness_estimate_id {
+            Some(estimate_id) => {
+                let estimate = RipenessEstimateService::new(self.db.clone())
+                    .get_estimate(business_id, estimate_id)
+                    .await?;
+                let used = sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (SELECT 1 FROM harvests WHERE ripeness_estimate_id = $1)",
+                )
+                .bind(estimate_id)
+                .fetch_one(&self.db)
+                .await?;
+                if used {
+                    return Err(AppError::Conflict {
+                        resource: "ripeness_estimate_id".to_string(),
+                        message: "This ripeness estimate is already used by another harvest".to_string(),
+                        message_th: "ผลประเมินความสุกนี้ถูกใช้กับการเก็บเกี่ยวอื่นแล้ว".to_string(),
+                    });
+                }
+                ripeness_overridden(&estimate.assessment(), &ripeness)
+            }
+            None => false,
+        };
+
         // Validate cherry weight
-        if input.cherry_weight_kg <= Decimal::ZERO {
+        if input.cherry_weight <= Decimal::ZERO {
             return Err(AppError::Validation {
-                field: "cherry_weight_kg".to_string(),
+                field: "cherry_weight".to_string(),
                 message: "Cherry weight must be greater than 0".to_string(),
                 message_th: "น้ำหนักเชอร์รี่ต้องมากกว่า 0".to_string(),
             });
         }
+        let unit = normalize_unit(input.cherry_weight_unit.as_deref());
+        let weight = WeightUnitService::new(self.db.clone())
+            .to_kg(business_id, input.cherry_weight, &unit, "cherry_weight_unit")
+            .await?;
 
         // Validate plot exists and belongs to business
         let plot_name = sqlx::query_scalar::<_, String>(
@@ -287,6 +397,34 @@ impl HarvestService {
         // Start transaction
         let mut tx = self.db.begin().await?;
 
+        // Two people often record the same picking. Entries for one plot
+        // and date take turns, so two of them sent at once can't both miss
+        // each other
+        if !input.confirm_duplicate {
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+                .bind(format!("harvest:{}:{}", input.plot_id, input.harvest_date))
+                .execute(&mut *tx)
+                .await?;
+
+            let same_day = sqlx::query_as::<_, SameDayHarvest>(
+                r#"
+                SELECT id, picker_name, cherry_weight_kg
+                FROM harvests
+                WHERE business_id = $1 AND plot_id = $2 AND harvest_date = $3
+                ORDER BY created_at
+                "#,
+            )
+            .bind(business_id)
+            .bind(input.plot_id)
+            .bind(input.harvest_date)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if let Some(duplicate) = find_duplicate_harvest(weight.kg, &same_day) {
+                return Err(AppError::DuplicateHarvest { duplicate: duplicate.clone() });
+            }
+        }
+
         // Get or create lot
         let lot_id = if let Some(existing_lot_id) = input.lot_id {
             // Validate lot exists and belongs to business
@@ -324,10 +462,12 @@ impl HarvestService {
         // Create harvest
         let harvest_id = sqlx::query_scalar::<_, Uuid>(
             r#"
-            INSERT INTO harvests (lot_id, plot_id, business_id, harvest_date, picker_name,
-                                  cherry_weight_kg, underripe_percent, ripe_percent, overripe_percent,
+            INSERT INTO harvests (lot_id, plot_id, business_id, harvest_date, picker_name, crew_name,
+                                  cherry_weight_kg, cherry_weight_original, cherry_weight_unit,
+                                  underripe_percent, ripe_percent, overripe_percent,
+                                  ripeness_estimate_id, ripeness_overridden,
                                   weather_snapshot, notes, notes_th)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING id
             "#,
         )
@@ -336,10 +476,15 @@ impl HarvestService {
         .bind(business_id)
         .bind(input.harvest_date)
         .bind(&input.picker_name)
-        .bind(input.cherry_weight_kg)
+        .bind(&input.crew_name)
+        .bind(weight.kg)
+        .bind(weight.original)
+        .bind(weight.unit)
         .bind(input.underripe_percent)
         .bind(input.ripe_percent)
         .bind(input.overripe_percent)
+        .bind(input.ripeness_estimate_id)
+        .bind(overridden)
         .bind(&input.weather_snapshot)
         .bind(&input.notes)
         .bind(&input.notes_th)
@@ -350,7 +495,7 @@ impl HarvestService {
         sqlx::query(
             "UPDATE lots SET current_weight_kg = current_weight_kg + $1 WHERE id = $2"
         )
-        .bind(input.cherry_weight_kg)
+        .bind(weight.kg)
         .bind(lot_id)
         .execute(&mut *tx)
         .await?;
@@ -371,9 +516,10 @@ impl HarvestService {
         // Get existing harvest
         let existing = sqlx::query_as::<_, Harvest>(
             r#"
-            SELECT id, lot_id, plot_id, business_id, harvest_date, picker_name,
-                   cherry_weight_kg, underripe_percent, ripe_percent, overripe_percent,
-                   weather_snapshot, notes, notes_th, created_at, updated_at
+            SELECT id, lot_id, plot_id, business_id, harvest_date, picker_name, crew_name,
+                   cherry_weight_kg, cherry_weight_original, cherry_weight_unit,
+                   underripe_percent, ripe_percent, overripe_percent,
+                   ripeness_estimate_id, ripeness_overridden, weather_snapshot, notes, notes_th, created_at, updated_at
             FROM harvests
             WHERE id = $1 AND business_id = $2
             "#,
@@ -387,7 +533,14 @@ impl HarvestService {
         // Prepare updated values
         let harvest_date = input.harvest_date.unwrap_or(existing.harvest_date);
         let picker_name = input.picker_name.or(existing.picker_name);
-        let cherry_weight_kg = input.cherry_weight_kg.unwrap_or(existing.cherry_weight_kg);
+        let crew_name = input.crew_name.or(existing.crew_name);
+        let cherry_weight_original = input.cherry_weight.unwrap_or(existing.cherry_weight_original);
+        let cherry_weight_unit = match input.cherry_weight_unit.as_deref() {
+            Some(unit) => normalize_unit(Some(unit)),
+            None => existing.cherry_weight_unit.clone(),
+        };
+        let weight_changed = cherry_weight_original != existing.cherry_weight_original
+            || cherry_weight_unit != existing.cherry_weight_unit;
         let underripe_percent = input.underripe_percent.unwrap_or(existing.underripe_percent);
         let ripe_percent = input.ripe_percent.unwrap_or(existing.ripe_percent);
         let overripe_percent = input.overripe_percent.unwrap_or(existing.overripe_percent);
@@ -409,11 +562,44 @@ impl HarvestService {
             })?;
         }
 
+        let ripeness_overridden = match existing.ripeness_estimate_id {
+            Some(estimate_id) => {
+                let estimate = RipenessEstimateService::new(self.db.clone())
+                    .get_estimate(business_id, estimate_id)
+                    .await?;
+                ripeness_overridden(
+                    &estimate.assessment(),
+                    &RipenessAssessment {
+                        underripe_percent,
+                        ripe_percent,
+                        overripe_percent,
+                    },
+                )
+            }
+            None => false,
+        };
+
+        let cherry_weight_kg = if weight_changed {
+            if cherry_weight_original <= Decimal::ZERO {
+                return Err(AppError::Validation {
+                    field: "cherry_weight".to_string(),
+                    message: "Cherry weight must be greater than 0".to_string(),
+                    message_th: "น้ำหนักเชอร์รี่ต้องมากกว่า 0".to_string(),
+                });
+            }
+            WeightUnitService::new(self.db.clone())
+                .to_kg(business_id, cherry_weight_original, &cherry_weight_unit, "cherry_weight_unit")
+                .await?
+                .kg
+        } else {
+            existing.cherry_weight_kg
+        };
+
         // Start transaction
         let mut tx = self.db.begin().await?;
 
         // Update lot weight if cherry weight changed
-        if input.cherry_weight_kg.is_some() {
+        if weight_changed {
             let weight_diff = cherry_weight_kg - existing.cherry_weight_kg;
             sqlx::query(
                 "UPDATE lots SET current_weight_kg = current_weight_kg + $1 WHERE id = $2"
@@ -429,20 +615,26 @@ impl HarvestService {
             r#"
             UPDATE harvests
             SET harvest_date = $1, picker_name = $2, cherry_weight_kg = $3,
-                underripe_percent = $4, ripe_percent = $5, overripe_percent = $6,
-                weather_snapshot = $7, notes = $8, notes_th = $9
-            WHERE id = $10
+                cherry_weight_original = $4, cherry_weight_unit = $5,
+                underripe_percent = $6, ripe_percent = $7, overripe_percent = $8,
+                weather_snapshot = $9, notes = $10, notes_th = $11, ripeness_overridden = $12,
+                crew_name = $13
+            WHERE id = $14
             "#,
         )
         .bind(harvest_date)
         .bind(&picker_name)
         .bind(cherry_weight_kg)
+        .bind(cherry_weight_original)
+        .bind(&cherry_weight_unit)
         .bind(underripe_percent)
         .bind(ripe_percent)
         .bind(overripe_percent)
         .bind(&weather_snapshot)
         .bind(&notes)
         .bind(&notes_th)
+        .bind(ripeness_overridden)
+        .bind(&crew_name)
         .bind(harvest_id)
         .execute(&mut *tx)
         .await?;
@@ -453,10 +645,11 @@ impl HarvestService {
         self.get_harvest(business_id, harvest_id).await
     }
 
-    /// Delete a harvest
+    /// Delete a harvest, keeping it in the recycle bin
     pub async fn delete_harvest(
         &self,
         business_id: Uuid,
+        user_id: Uuid,
         harvest_id: Uuid,
     ) -> AppResult<()> {
         // Get harvest to update lot weight
@@ -472,6 +665,8 @@ impl HarvestService {
         // Start transaction
         let mut tx = self.db.begin().await?;
 
+        RecycleBinService::capture(&mut tx, business_id, Some(user_id), DeletedEntity::Harvest, harvest_id).await?;
+
         // Update lot weight
         sqlx::query(
             "UPDATE lots SET current_weight_kg = current_weight_kg - $1 WHERE id = $2"
@@ -539,6 +734,26 @@ mod tests {
         assert!(ripeness.validate().is_err());
     }
 
+    #[tokio::test]
+    async fn test_duplicate_harvest_response_names_the_harvest() {
+        use axum::response::IntoResponse;
+
+        let duplicate = SameDayHarvest {
+            id: Uuid::nil(),
+            picker_name: Some("Somchai".to_string()),
+            cherry_weight_kg: Decimal::from(25),
+        };
+        let response = AppError::DuplicateHarvest { duplicate }.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "DUPLICATE_HARVEST");
+        assert_eq!(json["error"]["field"], "cherry_weight_kg");
+        assert_eq!(json["error"]["duplicate"]["id"], Uuid::nil().to_string());
+        assert_eq!(json["error"]["duplicate"]["picker_name"], "Somchai");
+    }
+
     #[test]
     fn test_yield_calculation() {
         let weight = Decimal::from(100);
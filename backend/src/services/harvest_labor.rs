@@ -0,0 +1,377 @@
+//! Harvest labor planning
+//!
+//! Turns the expected harvest per plot into picker-days for each of the
+//! coming weeks. Expected harvest is the cherry picked on the plot in the
+//! same week a year earlier (364 days, so weekdays line up). Days the
+//! weather forecast rates poor for harvesting are taken out of the week's
+//! working days, which raises the pickers needed on the days left.
+
+use chrono::{Duration, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::{DisplayFormat, Language};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::notification::{notification_action_url, CreateNotificationInput, NotificationType};
+use crate::services::weather::{HarvestSuitability, HarvestWindowRecommendation};
+use crate::services::{BusinessService, NotificationService, WeatherService};
+
+pub const DEFAULT_PLAN_WEEKS: u32 = 4;
+pub const MAX_PLAN_WEEKS: u32 = 12;
+/// Typical hand-picking rate for selective picking of ripe cherry
+pub const DEFAULT_KG_PER_PICKER_DAY: u32 = 50;
+pub const DEFAULT_WORKDAYS_PER_WEEK: u32 = 6;
+
+/// Days between a harvest and the same weekday a year later
+const YEAR_OFFSET_DAYS: i64 = 364;
+
+/// Harvest labor planning service
+#[derive(Clone)]
+pub struct HarvestLaborService {
+    db: PgPool,
+    weather: WeatherService,
+}
+
+/// Planning horizon and picking rate
+#[derive(Debug, Default, Deserialize)]
+pub struct LaborPlanQuery {
+    /// Defaults to today
+    pub start_date: Option<NaiveDate>,
+    pub weeks: Option<u32>,
+    /// Cherry one picker harvests in a day
+    pub kg_per_picker_day: Option<Decimal>,
+    pub workdays_per_week: Option<u32>,
+    /// Limit the plan to one plot
+    pub plot_id: Option<Uuid>,
+}
+
+/// Picker-days for one plot in one week
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotLaborWeek {
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    pub week_start: NaiveDate,
+    pub expected_cherry_kg: Decimal,
+    pub picker_days: u32,
+    /// Working days left after poor-weather days
+    pub workable_days: u32,
+    /// Pickers needed on each workable day
+    pub pickers_needed: u32,
+    /// Forecast days rated good or excellent for picking
+    pub recommended_dates: Vec<NaiveDate>,
+    /// Forecast days rated poor for picking
+    pub poor_weather_dates: Vec<NaiveDate>,
+}
+
+/// Picker-days across all plots in one week
+#[derive(Debug, Clone, Serialize)]
+pub struct LaborWeekTotal {
+    pub week_start: NaiveDate,
+    pub expected_cherry_kg: Decimal,
+    pub picker_days: u32,
+}
+
+/// Picker-days plan for the coming weeks
+#[derive(Debug, Clone, Serialize)]
+pub struct LaborPlan {
+    pub start_date: NaiveDate,
+    pub kg_per_picker_day: Decimal,
+    pub workdays_per_week: u32,
+    /// Whether a weather forecast was available for any plot
+    pub weather_applied: bool,
+    pub plots: Vec<PlotLaborWeek>,
+    pub weeks: Vec<LaborWeekTotal>,
+}
+
+/// Plot with coordinates for the weather forecast
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct PlanPlot {
+    id: Uuid,
+    name: String,
+    latitude: Option<Decimal>,
+    longitude: Option<Decimal>,
+}
+
+fn invalid(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Picker-days, workable days and the good and poor forecast dates for one
+/// plot-week. Every forecast day rated poor removes a working day, keeping
+/// at least one.
+pub fn plan_plot_week(
+    expected_kg: Decimal,
+    kg_per_picker_day: Decimal,
+    workdays_per_week: u32,
+    windows: &[&HarvestWindowRecommendation],
+) -> (u32, u32, Vec<NaiveDate>, Vec<NaiveDate>) {
+    let picker_days = if kg_per_picker_day > Decimal::ZERO {
+        (expected_kg / kg_per_picker_day).ceil().to_u32().unwrap_or(0)
+    } else {
+        0
+    };
+    let recommended: Vec<NaiveDate> = windows
+        .iter()
+        .filter(|w| matches!(w.suitability, HarvestSuitability::Excellent | HarvestSuitability::Good))
+        .map(|w| w.date)
+        .collect();
+    let poor: Vec<NaiveDate> = windows
+        .iter()
+        .filter(|w| w.suitability == HarvestSuitability::Poor)
+        .map(|w| w.date)
+        .collect();
+    let workable_days = workdays_per_week.saturating_sub(poor.len() as u32).max(1);
+    (picker_days, workable_days, recommended, poor)
+}
+
+/// Create a harvest reminder with a plot's picker-days for the coming weeks
+pub fn create_harvest_labor_notification(
+    plot_name: &str,
+    weeks: &[&PlotLaborWeek],
+    format: DisplayFormat,
+    plot_id: Uuid,
+) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
+    let lines: Vec<String> = weeks
+        .iter()
+        .map(|w| {
+            format!(
+                "Week of {}: {} picker-days, {} pickers over {} days ({} kg)",
+                en.date(w.week_start),
+                en.integer(w.picker_days as i64),
+                en.integer(w.pickers_needed as i64),
+                en.integer(w.workable_days as i64),
+                en.decimal(w.expected_cherry_kg, 0)
+            )
+        })
+        .collect();
+    let lines_th: Vec<String> = weeks
+        .iter()
+        .map(|w| {
+            format!(
+                "สัปดาห์ที่เริ่ม {}: {} แรงงาน-วัน คนเก็บ {} คน {} วัน ({} กก.)",
+                th.date(w.week_start),
+                th.integer(w.picker_days as i64),
+                th.integer(w.pickers_needed as i64),
+                th.integer(w.workable_days as i64),
+                th.decimal(w.expected_cherry_kg, 0)
+            )
+        })
+        .collect();
+    CreateNotificationInput {
+        notification_type: NotificationType::HarvestReminder,
+        title: format!("Picker Plan: {}", plot_name),
+        title_th: Some(format!("แผนแรงงานเก็บเกี่ยว: {}", plot_name)),
+        message: lines.join("\n"),
+        message_th: Some(lines_th.join("\n")),
+        entity_type: Some("plot".to_string()),
+        entity_id: Some(plot_id),
+        action_url: notification_action_url(&NotificationType::HarvestReminder, Some("plot"), Some(plot_id)),
+        priority: Some(1),
+        severity: None,
+    }
+}
+
+impl HarvestLaborService {
+    pub fn new(db: PgPool) -> Self {
+        let weather = WeatherService::new(db.clone());
+        Self { db, weather }
+    }
+
+    /// Use live forecasts from the weather API, not only cached ones
+    pub fn with_weather_client(db: PgPool, api_key: String) -> Self {
+        let weather = WeatherService::with_client(db.clone(), api_key);
+        Self { db, weather }
+    }
+
+    /// Plan picker-days per plot per week
+    pub async fn plan(&self, business_id: Uuid, query: &LaborPlanQuery) -> AppResult<LaborPlan> {
+        let weeks = query.weeks.unwrap_or(DEFAULT_PLAN_WEEKS);
+        if !(1..=MAX_PLAN_WEEKS).contains(&weeks) {
+            return Err(invalid(
+                "weeks",
+                &format!("Plan 1 to {} weeks ahead", MAX_PLAN_WEEKS),
+                &format!("วางแผนล่วงหน้าได้ 1 ถึง {} สัปดาห์", MAX_PLAN_WEEKS),
+            ));
+        }
+        let kg_per_picker_day = query
+            .kg_per_picker_day
+            .unwrap_or_else(|| Decimal::from(DEFAULT_KG_PER_PICKER_DAY));
+        if kg_per_picker_day <= Decimal::ZERO {
+            return Err(invalid(
+                "kg_per_picker_day",
+                "Picking rate must be greater than 0",
+                "อัตราการเก็บต้องมากกว่า 0",
+            ));
+        }
+        let workdays_per_week = query.workdays_per_week.unwrap_or(DEFAULT_WORKDAYS_PER_WEEK);
+        if !(1..=7).contains(&workdays_per_week) {
+            return Err(invalid(
+                "workdays_per_week",
+                "Working days must be 1 to 7 per week",
+                "วันทำงานต้องอยู่ระหว่าง 1 ถึง 7 วันต่อสัปดาห์",
+            ));
+        }
+        let start = query.start_date.unwrap_or_else(|| Utc::now().date_naive());
+        let horizon_days = 7 * weeks as i64;
+
+        let plots = sqlx::query_as::<_, PlanPlot>(
+            r#"
+            SELECT id, name, latitude, longitude
+            FROM plots
+            WHERE business_id = $1 AND ($2::UUID IS NULL OR id = $2)
+            ORDER BY name
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.plot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        // Last year's cherry per plot and week, moved forward a year
+        let last_year = start - Duration::days(YEAR_OFFSET_DAYS);
+        let expected = sqlx::query_as::<_, (Uuid, i32, Decimal)>(
+            r#"
+            SELECT plot_id, ((harvest_date - $2) / 7)::INT AS week, SUM(cherry_weight_kg)
+            FROM harvests
+            WHERE business_id = $1 AND harvest_date >= $2 AND harvest_date < $3
+            GROUP BY plot_id, week
+            "#,
+        )
+        .bind(business_id)
+        .bind(last_year)
+        .bind(last_year + Duration::days(horizon_days))
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut weather_applied = false;
+        let mut plan = Vec::new();
+        for plot in &plots {
+            let windows = match (plot.latitude, plot.longitude) {
+                (Some(latitude), Some(longitude)) => {
+                    match self.weather.get_forecast(business_id, latitude, longitude).await {
+                        Ok(forecast) => self.weather.get_harvest_window_recommendations(&forecast, None),
+                        Err(e) => {
+                            tracing::warn!("No forecast for plot {}: {}", plot.id, e);
+                            Vec::new()
+                        }
+                    }
+                }
+                _ => Vec::new(),
+            };
+            weather_applied |= !windows.is_empty();
+
+            for week in 0..weeks {
+                let week_start = start + Duration::days(7 * week as i64);
+                let week_end = week_start + Duration::days(7);
+                let expected_kg: Decimal = expected
+                    .iter()
+                    .filter(|(plot_id, w, _)| *plot_id == plot.id && *w == week as i32)
+                    .map(|(_, _, kg)| *kg)
+                    .sum();
+                if expected_kg <= Decimal::ZERO {
+                    continue;
+                }
+                let week_windows: Vec<&HarvestWindowRecommendation> = windows
+                    .iter()
+                    .filter(|w| week_start <= w.date && w.date < week_end)
+                    .collect();
+                let (picker_days, workable_days, recommended_dates, poor_weather_dates) =
+                    plan_plot_week(expected_kg, kg_per_picker_day, workdays_per_week, &week_windows);
+
+                plan.push(PlotLaborWeek {
+                    plot_id: plot.id,
+                    plot_name: plot.name.clone(),
+                    week_start,
+                    expected_cherry_kg: expected_kg,
+                    picker_days,
+                    workable_days,
+                    pickers_needed: picker_days.div_ceil(workable_days),
+                    recommended_dates,
+                    poor_weather_dates,
+                });
+            }
+        }
+
+        let totals = (0..weeks)
+            .map(|week| {
+                let week_start = start + Duration::days(7 * week as i64);
+                let rows = plan.iter().filter(|p| p.week_start == week_start);
+                LaborWeekTotal {
+                    week_start,
+                    expected_cherry_kg: rows.clone().map(|p| p.expected_cherry_kg).sum(),
+                    picker_days: rows.map(|p| p.picker_days).sum(),
+                }
+            })
+            .collect();
+
+        Ok(LaborPlan {
+            start_date: start,
+            kg_per_picker_day,
+            workdays_per_week,
+            weather_applied,
+            plots: plan,
+            weeks: totals,
+        })
+    }
+
+    /// Queue the plan as harvest reminders, one per plot, for the farm
+    /// managers, or the business owner when there are none.
+    /// Returns the number of notifications queued
+    pub async fn notify(&self, business_id: Uuid, query: &LaborPlanQuery) -> AppResult<i32> {
+        let plan = self.plan(business_id, query).await?;
+        if plan.plots.is_empty() {
+            return Ok(0);
+        }
+
+        let recipients = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT u.id
+            FROM users u
+            JOIN roles r ON r.id = u.role_id
+            WHERE u.business_id = $1 AND u.is_active AND r.template_key = 'farm_manager'
+            UNION
+            SELECT business_owner_id($1)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM users u
+                JOIN roles r ON r.id = u.role_id
+                WHERE u.business_id = $1 AND u.is_active AND r.template_key = 'farm_manager'
+            )
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let format = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let notifications = NotificationService::new(self.db.clone());
+        let mut plot_ids: Vec<Uuid> = plan.plots.iter().map(|p| p.plot_id).collect();
+        plot_ids.dedup();
+
+        let mut count = 0;
+        for plot_id in plot_ids {
+            let weeks: Vec<&PlotLaborWeek> = plan.plots.iter().filter(|p| p.plot_id == plot_id).collect();
+            for &user_id in &recipients {
+                let notification =
+                    create_harvest_labor_notification(&weeks[0].plot_name, &weeks, format, plot_id);
+                if notifications
+                    .queue_notification(user_id, business_id, notification)
+                    .await?
+                    .is_some()
+                {
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+}
@@ -7,6 +7,8 @@ use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::notification::AlertSeverity;
+use crate::services::weight_unit::{normalize_unit, WeightUnitService};
 
 /// Inventory service for managing stock transactions and alerts
 #[derive(Clone)]
@@ -75,6 +77,9 @@ pub struct InventoryTransaction {
     pub lot_id: Uuid,
     pub transaction_type: TransactionType,
     pub quantity_kg: Decimal,
+    /// Quantity as entered, in `quantity_unit`
+    pub quantity_original: Decimal,
+    pub quantity_unit: String,
     pub direction: String,
     pub stage: String,
     pub reference_type: Option<String>,
@@ -96,13 +101,18 @@ pub struct InventoryTransaction {
 pub struct RecordTransactionInput {
     pub lot_id: Uuid,
     pub transaction_type: TransactionType,
-    pub quantity_kg: Decimal,
+    /// Quantity in `quantity_unit`
+    #[serde(alias = "quantity_kg")]
+    pub quantity: Decimal,
+    /// Unit the quantity was entered in (`kg` when omitted)
+    pub quantity_unit: Option<String>,
     pub direction: TransactionDirection,
     pub stage: String,
     pub reference_type: Option<String>,
     pub reference_id: Option<Uuid>,
     pub counterparty_name: Option<String>,
     pub counterparty_contact: Option<String>,
+    /// Price per kg, whatever unit the quantity was entered in
     pub unit_price: Option<Decimal>,
     pub currency: Option<String>,
     pub notes: Option<String>,
@@ -145,6 +155,7 @@ pub struct InventoryAlert {
     pub last_triggered_at: Option<DateTime<Utc>>,
     pub notify_email: bool,
     pub notify_line: bool,
+    pub severity: AlertSeverity,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -157,6 +168,8 @@ pub struct CreateAlertInput {
     pub threshold_kg: Decimal,
     pub notify_email: Option<bool>,
     pub notify_line: Option<bool>,
+    /// Default: warning
+    pub severity: Option<AlertSeverity>,
 }
 
 /// Input for updating inventory alert
@@ -166,6 +179,7 @@ pub struct UpdateAlertInput {
     pub is_active: Option<bool>,
     pub notify_email: Option<bool>,
     pub notify_line: Option<bool>,
+    pub severity: Option<AlertSeverity>,
 }
 
 /// Inventory valuation for a lot
@@ -203,6 +217,7 @@ struct TriggeredAlertRow {
     last_triggered_at: Option<DateTime<Utc>>,
     notify_email: bool,
     notify_line: bool,
+    severity: AlertSeverity,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
     current_balance: Decimal,
@@ -222,9 +237,9 @@ impl InventoryService {
         input: RecordTransactionInput,
     ) -> AppResult<InventoryTransaction> {
         // Validate quantity
-        if input.quantity_kg <= Decimal::ZERO {
+        if input.quantity <= Decimal::ZERO {
             return Err(AppError::Validation {
-                field: "quantity_kg".to_string(),
+                field: "quantity".to_string(),
                 message: "Quantity must be positive".to_string(),
                 message_th: "ปริมาณต้องเป็นค่าบวก".to_string(),
             });
@@ -243,20 +258,26 @@ impl InventoryService {
             return Err(AppError::NotFound("Lot".to_string()));
         }
 
+        let unit = normalize_unit(input.quantity_unit.as_deref());
+        let quantity = WeightUnitService::new(self.db.clone())
+            .to_kg(business_id, input.quantity, &unit, "quantity_unit")
+            .await?;
+
         // Calculate total price if unit price provided
-        let total_price = input.unit_price.map(|up| up * input.quantity_kg);
+        let total_price = input.unit_price.map(|up| up * quantity.kg);
         let currency = input.currency.unwrap_or_else(|| "THB".to_string());
         let transaction_date = input.transaction_date.unwrap_or_else(|| Utc::now().date_naive());
 
         let transaction = sqlx::query_as::<_, InventoryTransaction>(
             r#"
             INSERT INTO inventory_transactions (
-                business_id, lot_id, transaction_type, quantity_kg, direction, stage,
-                reference_type, reference_id, counterparty_name, counterparty_contact,
+                business_id, lot_id, transaction_type, quantity_kg, quantity_original, quantity_unit,
+                direction, stage, reference_type, reference_id, counterparty_name, counterparty_contact,
                 unit_price, total_price, currency, notes, notes_th, transaction_date, created_by
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
-            RETURNING id, business_id, lot_id, transaction_type, quantity_kg, direction, stage,
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            RETURNING id, business_id, lot_id, transaction_type, quantity_kg, quantity_original,
+                      quantity_unit, direction, stage,
                       reference_type, reference_id, counterparty_name, counterparty_contact,
                       unit_price, total_price, currency, notes, notes_th, transaction_date,
                       created_at, created_by
@@ -265,7 +286,9 @@ impl InventoryService {
         .bind(business_id)
         .bind(input.lot_id)
         .bind(input.transaction_type)
-        .bind(input.quantity_kg)
+        .bind(quantity.kg)
+        .bind(quantity.original)
+        .bind(quantity.unit)
         .bind(input.direction.as_str())
         .bind(&input.stage)
         .bind(&input.reference_type)
@@ -336,7 +359,8 @@ impl InventoryService {
 
         let transactions = sqlx::query_as::<_, InventoryTransaction>(
             r#"
-            SELECT id, business_id, lot_id, transaction_type, quantity_kg, direction, stage,
+            SELECT id, business_id, lot_id, transaction_type, quantity_kg, quantity_original,
+                   quantity_unit, direction, stage,
                    reference_type, reference_id, counterparty_name, counterparty_contact,
                    unit_price, total_price, currency, notes, notes_th, transaction_date,
                    created_at, created_by
@@ -360,7 +384,8 @@ impl InventoryService {
     ) -> AppResult<Vec<InventoryTransaction>> {
         let transactions = sqlx::query_as::<_, InventoryTransaction>(
             r#"
-            SELECT id, business_id, lot_id, transaction_type, quantity_kg, direction, stage,
+            SELECT id, business_id, lot_id, transaction_type, quantity_kg, quantity_original,
+                   quantity_unit, direction, stage,
                    reference_type, reference_id, counterparty_name, counterparty_contact,
                    unit_price, total_price, currency, notes, notes_th, transaction_date,
                    created_at, created_by
@@ -421,10 +446,10 @@ impl InventoryService {
 
         let alert = sqlx::query_as::<_, InventoryAlert>(
             r#"
-            INSERT INTO inventory_alerts (business_id, lot_id, stage, threshold_kg, notify_email, notify_line)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO inventory_alerts (business_id, lot_id, stage, threshold_kg, notify_email, notify_line, severity)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING id, business_id, lot_id, stage, threshold_kg, is_active, last_triggered_at,
-                      notify_email, notify_line, created_at, updated_at
+                      notify_email, notify_line, severity, created_at, updated_at
             "#,
         )
         .bind(business_id)
@@ -433,6 +458,7 @@ impl InventoryService {
         .bind(input.threshold_kg)
         .bind(notify_email)
         .bind(notify_line)
+        .bind(input.severity.unwrap_or(AlertSeverity::Warning))
         .fetch_one(&self.db)
         .await?;
 
@@ -447,8 +473,8 @@ impl InventoryService {
         input: UpdateAlertInput,
     ) -> AppResult<InventoryAlert> {
         // Check if alert exists
-        let existing = sqlx::query_as::<_, (Decimal, bool, bool, bool)>(
-            "SELECT threshold_kg, is_active, notify_email, notify_line FROM inventory_alerts WHERE id = $1 AND business_id = $2"
+        let existing = sqlx::query_as::<_, (Decimal, bool, bool, bool, AlertSeverity)>(
+            "SELECT threshold_kg, is_active, notify_email, notify_line, severity FROM inventory_alerts WHERE id = $1 AND business_id = $2"
         )
         .bind(alert_id)
         .bind(business_id)
@@ -460,6 +486,7 @@ impl InventoryService {
         let is_active = input.is_active.unwrap_or(existing.1);
         let notify_email = input.notify_email.unwrap_or(existing.2);
         let notify_line = input.notify_line.unwrap_or(existing.3);
+        let severity = input.severity.unwrap_or(existing.4);
 
         // Validate threshold
         if threshold_kg <= Decimal::ZERO {
@@ -473,10 +500,10 @@ impl InventoryService {
         let alert = sqlx::query_as::<_, InventoryAlert>(
             r#"
             UPDATE inventory_alerts
-            SET threshold_kg = $1, is_active = $2, notify_email = $3, notify_line = $4
+            SET threshold_kg = $1, is_active = $2, notify_email = $3, notify_line = $4, severity = $6
             WHERE id = $5
             RETURNING id, business_id, lot_id, stage, threshold_kg, is_active, last_triggered_at,
-                      notify_email, notify_line, created_at, updated_at
+                      notify_email, notify_line, severity, created_at, updated_at
             "#,
         )
         .bind(threshold_kg)
@@ -484,6 +511,7 @@ impl InventoryService {
         .bind(notify_email)
         .bind(notify_line)
         .bind(alert_id)
+        .bind(severity)
         .fetch_one(&self.db)
         .await?;
 
@@ -512,7 +540,7 @@ impl InventoryService {
         let alerts = sqlx::query_as::<_, InventoryAlert>(
             r#"
             SELECT id, business_id, lot_id, stage, threshold_kg, is_active, last_triggered_at,
-                   notify_email, notify_line, created_at, updated_at
+                   notify_email, notify_line, severity, created_at, updated_at
             FROM inventory_alerts
             WHERE business_id = $1
             ORDER BY created_at DESC
@@ -530,7 +558,7 @@ impl InventoryService {
         let rows = sqlx::query_as::<_, TriggeredAlertRow>(
             r#"
             SELECT ia.id, ia.business_id, ia.lot_id, ia.stage, ia.threshold_kg, ia.is_active,
-                   ia.last_triggered_at, ia.notify_email, ia.notify_line, ia.created_at, ia.updated_at,
+                   ia.last_triggered_at, ia.notify_email, ia.notify_line, ia.severity, ia.created_at, ia.updated_at,
                    COALESCE(get_lot_inventory_balance(ia.lot_id), 0) as current_balance
             FROM inventory_alerts ia
             WHERE ia.business_id = $1 AND ia.is_active = true
@@ -553,6 +581,7 @@ impl InventoryService {
                 last_triggered_at: r.last_triggered_at,
                 notify_email: r.notify_email,
                 notify_line: r.notify_line,
+                severity: r.severity,
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             },
@@ -0,0 +1,253 @@
+//! Queue of background work
+//!
+//! Requests that would otherwise wait on slow external services queue a job
+//! instead and return at once; a background worker (see `jobs`) claims due
+//! jobs, runs them and records the result. A job that fails with a temporary
+//! error (the external service unreachable, a database hiccup) is queued
+//! again after a growing delay until it has used its attempts; any other
+//! error fails it straight away. Clients follow a job by its ID.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Job kind: AI defect detection of a tray photo, then recording the grading
+pub const JOB_TYPE_AI_GRADING: &str = "ai_grading";
+
+/// Attempts a job gets unless queued with another limit
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; doubled for each later one
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+
+/// Longest delay between retries
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+
+/// Minutes after which a job still running is taken to have been abandoned
+/// by a worker that stopped, and may be claimed again
+const STALE_JOB_MINUTES: i64 = 15;
+
+/// Job queue service
+#[derive(Clone)]
+pub struct JobQueueService {
+    db: PgPool,
+}
+
+/// Where a job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Queued job as shown to clients
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub job_type: String,
+    /// queued, running, succeeded or failed
+    pub status: String,
+    /// Set once the job succeeds
+    pub result: Option<serde_json::Value>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Earliest time the job runs (next retry while queued after a failure)
+    pub run_after: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Job claimed by a worker, with its input
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ClaimedJob {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+/// Delay before retrying a job that failed on attempt `attempt` (from 1)
+pub fn retry_delay(attempt: i32) -> Duration {
+    let doublings = attempt.clamp(1, 32) as u32 - 1;
+    let seconds = BASE_RETRY_DELAY_SECONDS
+        .saturating_mul(2i64.saturating_pow(doublings))
+        .min(MAX_RETRY_DELAY_SECONDS);
+    Duration::seconds(seconds)
+}
+
+/// Whether an error may clear up if the job is run again
+pub fn is_retryable(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::AiDetectionError(_)
+            | AppError::ExternalService(_)
+            | AppError::StorageError(_)
+            | AppError::DatabaseError(_)
+            | AppError::RateLimited { .. }
+    )
+}
+
+/// Status of a job after a failed attempt: queued again while it has
+/// attempts left and the error is temporary, failed otherwise
+pub fn status_after_failure(attempts: i32, max_attempts: i32, retryable: bool) -> JobStatus {
+    if retryable && attempts < max_attempts {
+        JobStatus::Queued
+    } else {
+        JobStatus::Failed
+    }
+}
+
+const JOB_COLUMNS: &str = "id, job_type, status, result, attempts, max_attempts, run_after, last_error, \
+                           created_at, started_at, finished_at";
+
+impl JobQueueService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Queue a job to run as soon as a worker is free
+    pub async fn enqueue(
+        &self,
+        business_id: Uuid,
+        created_by: Option<Uuid>,
+        job_type: &str,
+        payload: serde_json::Value,
+        max_attempts: i32,
+    ) -> AppResult<Job> {
+        let job = sqlx::query_as::<_, Job>(&format!(
+            r#"
+            INSERT INTO jobs (business_id, job_type, payload, max_attempts, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(job_type)
+        .bind(&payload)
+        .bind(max_attempts)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(job)
+    }
+
+    /// A job of the business of the given kind
+    pub async fn get_job(&self, business_id: Uuid, job_type: &str, job_id: Uuid) -> AppResult<Job> {
+        sqlx::query_as::<_, Job>(&format!(
+            "SELECT {} FROM jobs WHERE id = $1 AND business_id = $2 AND job_type = $3",
+            JOB_COLUMNS
+        ))
+        .bind(job_id)
+        .bind(business_id)
+        .bind(job_type)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Job".to_string()))
+    }
+
+    /// Claim up to `limit` due jobs of a kind, oldest first, marking them
+    /// running. Jobs abandoned while running are claimed again, or failed
+    /// when they have no attempts left
+    pub async fn claim_due(&self, job_type: &str, limit: i64) -> AppResult<Vec<ClaimedJob>> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'failed', finished_at = NOW(), updated_at = NOW(), payload = payload - 'image_base64',
+                last_error = COALESCE(last_error, 'Worker stopped while running the job')
+            WHERE job_type = $1 AND status = 'running' AND attempts >= max_attempts
+              AND started_at < NOW() - make_interval(mins => $2)
+            "#,
+        )
+        .bind(job_type)
+        .bind(STALE_JOB_MINUTES as i32)
+        .execute(&self.db)
+        .await?;
+
+        let jobs = sqlx::query_as::<_, ClaimedJob>(
+            r#"
+            UPDATE jobs
+            SET status = $4, attempts = attempts + 1, started_at = NOW(), updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM jobs
+                WHERE job_type = $1
+                  AND ((status = 'queued' AND run_after <= NOW())
+                       OR (status = 'running' AND started_at < NOW() - make_interval(mins => $2)))
+                ORDER BY run_after
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, business_id, payload, attempts, max_attempts
+            "#,
+        )
+        .bind(job_type)
+        .bind(STALE_JOB_MINUTES as i32)
+        .bind(limit)
+        .bind(JobStatus::Running.as_str())
+        .fetch_all(&self.db)
+        .await?;
+        Ok(jobs)
+    }
+
+    /// Record that a job succeeded, dropping large inputs from its payload
+    pub async fn complete(&self, job_id: Uuid, result: serde_json::Value) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = $3, result = $2, last_error = NULL, payload = payload - 'image_base64',
+                finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .bind(&result)
+        .bind(JobStatus::Succeeded.as_str())
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed attempt, queueing the job again after the retry
+    /// delay or failing it for good
+    pub async fn fail(&self, job: &ClaimedJob, error: &AppError) -> AppResult<JobStatus> {
+        let status = status_after_failure(job.attempts, job.max_attempts, is_retryable(error));
+        let run_after = Utc::now() + retry_delay(job.attempts);
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = $2, last_error = $3, updated_at = NOW(),
+                run_after = CASE WHEN $2 = 'queued' THEN $4 ELSE run_after END,
+                finished_at = CASE WHEN $2 = 'failed' THEN NOW() END,
+                payload = CASE WHEN $2 = 'failed' THEN payload - 'image_base64' ELSE payload END
+            WHERE id = $1
+            "#,
+        )
+        .bind(job.id)
+        .bind(status.as_str())
+        .bind(error.to_string())
+        .bind(run_after)
+        .execute(&self.db)
+        .await?;
+        Ok(status)
+    }
+}
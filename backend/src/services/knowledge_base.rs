@@ -0,0 +1,649 @@
+//! Knowledge base of SOPs and training items
+//!
+//! Serves bilingual standard operating procedures and short training items
+//! (processing best practices, cupping protocol and the like). Built-in items
+//! are shared by every business; a business's admins add, edit and retire
+//! their own. Each item names the role templates it is meant for, and a
+//! member's recommendations are the published items for their role they have
+//! not read. Reads are kept per member against the item's version: editing
+//! the text of an item bumps its version, so members who read the earlier
+//! text see it as updated and have it recommended again.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Kinds of knowledge base item
+pub const ARTICLE_KINDS: [&str; 2] = ["sop", "training"];
+
+/// Topics items are filed under
+pub const ARTICLE_CATEGORIES: [&str; 8] = [
+    "harvest",
+    "processing",
+    "grading",
+    "cupping",
+    "roasting",
+    "storage",
+    "safety",
+    "general",
+];
+
+/// Role templates an item can be meant for
+pub const AUDIENCE_ROLES: [&str; 6] = ["owner", "farm_manager", "processor", "roaster", "cupper", "viewer"];
+
+/// Most items recommended at once
+pub const MAX_RECOMMENDATIONS: usize = 10;
+
+/// Characters read per minute, for the reading time of an item
+const CHARACTERS_PER_MINUTE: usize = 1000;
+
+/// Knowledge base service
+#[derive(Clone)]
+pub struct KnowledgeBaseService {
+    db: PgPool,
+}
+
+/// Whether a member has read an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadStatus {
+    Unread,
+    Read,
+    /// Read, but the item has changed since
+    Updated,
+}
+
+/// Knowledge base item
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct KnowledgeArticle {
+    pub id: Uuid,
+    /// None for built-in items
+    pub business_id: Option<Uuid>,
+    pub slug: String,
+    pub kind: String,
+    pub category: String,
+    pub title: String,
+    pub title_th: String,
+    pub summary: String,
+    pub summary_th: String,
+    pub body: String,
+    pub body_th: String,
+    /// Role templates the item is meant for; empty for everyone
+    pub audience_roles: Vec<String>,
+    pub reading_minutes: i32,
+    pub published: bool,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Version the member last read
+    #[serde(skip)]
+    pub version_read: Option<i32>,
+}
+
+/// Item as listed, without its body
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArticleSummary {
+    pub id: Uuid,
+    pub built_in: bool,
+    pub slug: String,
+    pub kind: String,
+    pub category: String,
+    pub title: String,
+    pub title_th: String,
+    pub summary: String,
+    pub summary_th: String,
+    pub audience_roles: Vec<String>,
+    pub reading_minutes: i32,
+    pub published: bool,
+    pub version: i32,
+    pub updated_at: DateTime<Utc>,
+    pub read_status: ReadStatus,
+}
+
+impl From<&KnowledgeArticle> for ArticleSummary {
+    fn from(article: &KnowledgeArticle) -> Self {
+        Self {
+            id: article.id,
+            built_in: article.business_id.is_none(),
+            slug: article.slug.clone(),
+            kind: article.kind.clone(),
+            category: article.category.clone(),
+            title: article.title.clone(),
+            title_th: article.title_th.clone(),
+            summary: article.summary.clone(),
+            summary_th: article.summary_th.clone(),
+            audience_roles: article.audience_roles.clone(),
+            reading_minutes: article.reading_minutes,
+            published: article.published,
+            version: article.version,
+            updated_at: article.updated_at,
+            read_status: read_status(article.version, article.version_read),
+        }
+    }
+}
+
+/// Item with the member's read status
+#[derive(Debug, Serialize)]
+pub struct ArticleView {
+    #[serde(flatten)]
+    pub article: KnowledgeArticle,
+    pub built_in: bool,
+    pub read_status: ReadStatus,
+}
+
+/// Filters for listing items
+#[derive(Debug, Default, Deserialize)]
+pub struct ArticleQuery {
+    pub kind: Option<String>,
+    pub category: Option<String>,
+    /// Only items for this role template
+    pub role: Option<String>,
+    /// Only items not read, or changed since read
+    #[serde(default)]
+    pub unread: bool,
+    /// Include unpublished items (admins only)
+    #[serde(default)]
+    pub include_drafts: bool,
+}
+
+/// Items recommended to a member
+#[derive(Debug, Serialize)]
+pub struct ArticleRecommendations {
+    /// Role template of the member's role; None for a custom role
+    pub role: Option<String>,
+    pub articles: Vec<ArticleSummary>,
+}
+
+/// Input for adding an item
+#[derive(Debug, Deserialize)]
+pub struct CreateArticleInput {
+    pub slug: String,
+    pub kind: String,
+    pub category: String,
+    pub title: String,
+    pub title_th: String,
+    pub summary: Option<String>,
+    pub summary_th: Option<String>,
+    pub body: String,
+    pub body_th: String,
+    pub audience_roles: Option<Vec<String>>,
+    /// Published unless false
+    pub published: Option<bool>,
+}
+
+/// Input for editing an item; fields left out stay as they are
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateArticleInput {
+    pub kind: Option<String>,
+    pub category: Option<String>,
+    pub title: Option<String>,
+    pub title_th: Option<String>,
+    pub summary: Option<String>,
+    pub summary_th: Option<String>,
+    pub body: Option<String>,
+    pub body_th: Option<String>,
+    pub audience_roles: Option<Vec<String>>,
+    pub published: Option<bool>,
+}
+
+/// A member's read of an item
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ArticleRead {
+    pub article_id: Uuid,
+    pub version_read: i32,
+    pub first_read_at: DateTime<Utc>,
+    pub last_read_at: DateTime<Utc>,
+}
+
+/// Read status of one member for an item
+#[derive(Debug, Serialize)]
+pub struct ArticleReader {
+    pub user_id: Uuid,
+    pub name: String,
+    pub role_name: String,
+    pub read_status: ReadStatus,
+    pub version_read: Option<i32>,
+    pub last_read_at: Option<DateTime<Utc>>,
+}
+
+/// Who in the business has read an item
+#[derive(Debug, Serialize)]
+pub struct ArticleReadership {
+    pub article_id: Uuid,
+    pub version: i32,
+    /// Active members whose role the item is meant for
+    pub audience: usize,
+    /// Of them, those who read the current version
+    pub read: usize,
+    /// Of them, those who read an earlier version
+    pub updated: usize,
+    pub readers: Vec<ArticleReader>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MemberReadRow {
+    user_id: Uuid,
+    name: String,
+    role_name: String,
+    template_key: Option<String>,
+    version_read: Option<i32>,
+    last_read_at: Option<DateTime<Utc>>,
+}
+
+/// Read status of an item at `version` for a member who last read
+/// `version_read`
+pub fn read_status(version: i32, version_read: Option<i32>) -> ReadStatus {
+    match version_read {
+        None => ReadStatus::Unread,
+        Some(read) if read >= version => ReadStatus::Read,
+        Some(_) => ReadStatus::Updated,
+    }
+}
+
+/// Whether an item meant for `audience` is for a member with role template
+/// `role`; an item for no role in particular is for everyone
+pub fn is_for_role(audience: &[String], role: Option<&str>) -> bool {
+    audience.is_empty() || role.is_some_and(|role| audience.iter().any(|r| r == role))
+}
+
+/// Reading time of an item in whole minutes, by the longer of its two
+/// languages
+pub fn reading_minutes(body: &str, body_th: &str) -> i32 {
+    let characters = body.chars().count().max(body_th.chars().count());
+    characters.div_ceil(CHARACTERS_PER_MINUTE).max(1) as i32
+}
+
+/// Items to recommend to a member with role template `role`: published
+/// items for the role not read at their current version. Changed items come
+/// first, then items meant for the role before items for everyone, then
+/// SOPs before training, then the shortest
+pub fn recommend(articles: &[ArticleSummary], role: Option<&str>) -> Vec<ArticleSummary> {
+    let mut recommended: Vec<ArticleSummary> = articles
+        .iter()
+        .filter(|a| a.published && a.read_status != ReadStatus::Read && is_for_role(&a.audience_roles, role))
+        .cloned()
+        .collect();
+    recommended.sort_by_key(|a| {
+        (
+            a.read_status != ReadStatus::Updated,
+            a.audience_roles.is_empty(),
+            a.kind != "sop",
+            a.reading_minutes,
+        )
+    });
+    recommended.truncate(MAX_RECOMMENDATIONS);
+    recommended
+}
+
+fn validate_choice(field: &str, value: &str, allowed: &[&str]) -> AppResult<()> {
+    if allowed.contains(&value) {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        field: field.to_string(),
+        message: format!("Must be one of: {}", allowed.join(", ")),
+        message_th: format!("ต้องเป็นหนึ่งใน: {}", allowed.join(", ")),
+    })
+}
+
+/// Check a slug is lowercase words joined by hyphens
+pub fn validate_slug(slug: &str) -> AppResult<()> {
+    let valid = !slug.is_empty()
+        && slug.len() <= 100
+        && slug.split('-').all(|word| {
+            !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        });
+    if valid {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        field: "slug".to_string(),
+        message: "Slug must be lowercase letters and digits joined by hyphens".to_string(),
+        message_th: "slug ต้องเป็นตัวอักษรภาษาอังกฤษพิมพ์เล็กและตัวเลข คั่นด้วยขีดกลาง".to_string(),
+    })
+}
+
+fn validate_text(field: &str, value: &str) -> AppResult<()> {
+    if value.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: field.to_string(),
+            message: "Required in both English and Thai".to_string(),
+            message_th: "ต้องระบุทั้งภาษาอังกฤษและภาษาไทย".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_audience(roles: &[String]) -> AppResult<()> {
+    roles
+        .iter()
+        .try_for_each(|role| validate_choice("audience_roles", role, &AUDIENCE_ROLES))
+}
+
+const ARTICLE_SELECT: &str = r#"
+    SELECT a.id, a.business_id, a.slug, a.kind, a.category, a.title, a.title_th, a.summary, a.summary_th,
+           a.body, a.body_th, a.audience_roles, a.reading_minutes, a.published, a.version,
+           a.created_at, a.updated_at, r.version_read
+    FROM knowledge_articles a
+    LEFT JOIN knowledge_article_reads r ON r.article_id = a.id AND r.user_id = $2
+    WHERE (a.business_id IS NULL OR a.business_id = $1)
+"#;
+
+impl KnowledgeBaseService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Role template of a member's role
+    async fn member_role(&self, business_id: Uuid, role_id: Uuid) -> AppResult<Option<String>> {
+        let role = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT template_key FROM roles WHERE id = $1 AND business_id = $2",
+        )
+        .bind(role_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(role.flatten())
+    }
+
+    /// Items of the business and the built-in ones, with the member's read
+    /// status; drafts only when `include_drafts`
+    pub async fn list_articles(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        query: &ArticleQuery,
+    ) -> AppResult<Vec<ArticleSummary>> {
+        if let Some(kind) = query.kind.as_deref() {
+            validate_choice("kind", kind, &ARTICLE_KINDS)?;
+        }
+        if let Some(category) = query.category.as_deref() {
+            validate_choice("category", category, &ARTICLE_CATEGORIES)?;
+        }
+        if let Some(role) = query.role.as_deref() {
+            validate_choice("role", role, &AUDIENCE_ROLES)?;
+        }
+
+        let articles = sqlx::query_as::<_, KnowledgeArticle>(&format!(
+            r#"{}
+              AND ($3::VARCHAR IS NULL OR a.kind = $3)
+              AND ($4::VARCHAR IS NULL OR a.category = $4)
+              AND (a.published OR $5)
+            ORDER BY a.category, a.kind, a.title
+            "#,
+            ARTICLE_SELECT
+        ))
+        .bind(business_id)
+        .bind(user_id)
+        .bind(&query.kind)
+        .bind(&query.category)
+        .bind(query.include_drafts)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(articles
+            .iter()
+            .map(ArticleSummary::from)
+            .filter(|a| query.role.is_none() || is_for_role(&a.audience_roles, query.role.as_deref()))
+            .filter(|a| !query.unread || a.read_status != ReadStatus::Read)
+            .collect())
+    }
+
+    /// An item with the member's read status; drafts only when
+    /// `include_drafts`
+    pub async fn get_article(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        article_id: Uuid,
+        include_drafts: bool,
+    ) -> AppResult<ArticleView> {
+        let article = sqlx::query_as::<_, KnowledgeArticle>(&format!("{} AND a.id = $3", ARTICLE_SELECT))
+            .bind(business_id)
+            .bind(user_id)
+            .bind(article_id)
+            .fetch_optional(&self.db)
+            .await?
+            .filter(|article| article.published || include_drafts)
+            .ok_or_else(|| AppError::NotFound("Knowledge article".to_string()))?;
+
+        Ok(ArticleView {
+            built_in: article.business_id.is_none(),
+            read_status: read_status(article.version, article.version_read),
+            article,
+        })
+    }
+
+    /// Items recommended to a member for their role
+    pub async fn recommendations(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        role_id: Uuid,
+    ) -> AppResult<ArticleRecommendations> {
+        let role = self.member_role(business_id, role_id).await?;
+        let articles = self
+            .list_articles(business_id, user_id, &ArticleQuery::default())
+            .await?;
+        Ok(ArticleRecommendations {
+            articles: recommend(&articles, role.as_deref()),
+            role,
+        })
+    }
+
+    /// Record that a member read the current version of an item
+    pub async fn mark_read(&self, business_id: Uuid, user_id: Uuid, article_id: Uuid) -> AppResult<ArticleRead> {
+        let article = self.get_article(business_id, user_id, article_id, false).await?;
+        let read = sqlx::query_as::<_, ArticleRead>(
+            r#"
+            INSERT INTO knowledge_article_reads (article_id, user_id, business_id, version_read)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (article_id, user_id)
+            DO UPDATE SET version_read = EXCLUDED.version_read, last_read_at = NOW()
+            RETURNING article_id, version_read, first_read_at, last_read_at
+            "#,
+        )
+        .bind(article_id)
+        .bind(user_id)
+        .bind(business_id)
+        .bind(article.article.version)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(read)
+    }
+
+    /// Add an item for the business
+    pub async fn create_article(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateArticleInput,
+    ) -> AppResult<ArticleView> {
+        validate_slug(&input.slug)?;
+        validate_choice("kind", &input.kind, &ARTICLE_KINDS)?;
+        validate_choice("category", &input.category, &ARTICLE_CATEGORIES)?;
+        validate_text("title", &input.title)?;
+        validate_text("title_th", &input.title_th)?;
+        validate_text("body", &input.body)?;
+        validate_text("body_th", &input.body_th)?;
+        let audience_roles = input.audience_roles.unwrap_or_default();
+        validate_audience(&audience_roles)?;
+
+        let taken = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM knowledge_articles WHERE slug = $1 AND (business_id = $2 OR business_id IS NULL))",
+        )
+        .bind(&input.slug)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if taken {
+            return Err(AppError::Conflict {
+                resource: "knowledge_article".to_string(),
+                message: format!("An article with slug '{}' already exists", input.slug),
+                message_th: format!("มีบทความที่ใช้ slug '{}' แล้ว", input.slug),
+            });
+        }
+
+        let article_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO knowledge_articles (business_id, slug, kind, category, title, title_th, summary, summary_th,
+                                            body, body_th, audience_roles, reading_minutes, published,
+                                            created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $14)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(&input.slug)
+        .bind(&input.kind)
+        .bind(&input.category)
+        .bind(input.title.trim())
+        .bind(input.title_th.trim())
+        .bind(input.summary.unwrap_or_default())
+        .bind(input.summary_th.unwrap_or_default())
+        .bind(&input.body)
+        .bind(&input.body_th)
+        .bind(&audience_roles)
+        .bind(reading_minutes(&input.body, &input.body_th))
+        .bind(input.published.unwrap_or(true))
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get_article(business_id, user_id, article_id, true).await
+    }
+
+    /// The business's own item; built-in items cannot be changed
+    async fn own_article(&self, business_id: Uuid, user_id: Uuid, article_id: Uuid) -> AppResult<KnowledgeArticle> {
+        let view = self.get_article(business_id, user_id, article_id, true).await?;
+        if view.built_in {
+            return Err(AppError::Validation {
+                field: "id".to_string(),
+                message: "Cannot change built-in knowledge articles".to_string(),
+                message_th: "ไม่สามารถแก้ไขบทความในระบบได้".to_string(),
+            });
+        }
+        Ok(view.article)
+    }
+
+    /// Edit an item of the business. Changing its text starts a new version,
+    /// so members who read it are shown it as updated
+    pub async fn update_article(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        article_id: Uuid,
+        input: UpdateArticleInput,
+    ) -> AppResult<ArticleView> {
+        let existing = self.own_article(business_id, user_id, article_id).await?;
+        if let Some(kind) = input.kind.as_deref() {
+            validate_choice("kind", kind, &ARTICLE_KINDS)?;
+        }
+        if let Some(category) = input.category.as_deref() {
+            validate_choice("category", category, &ARTICLE_CATEGORIES)?;
+        }
+        for (field, value) in [
+            ("title", &input.title),
+            ("title_th", &input.title_th),
+            ("body", &input.body),
+            ("body_th", &input.body_th),
+        ] {
+            if let Some(value) = value {
+                validate_text(field, value)?;
+            }
+        }
+        if let Some(roles) = &input.audience_roles {
+            validate_audience(roles)?;
+        }
+
+        let body = input.body.unwrap_or(existing.body.clone());
+        let body_th = input.body_th.unwrap_or(existing.body_th.clone());
+        let text_changed = body != existing.body || body_th != existing.body_th;
+        let version = if text_changed { existing.version + 1 } else { existing.version };
+
+        sqlx::query(
+            r#"
+            UPDATE knowledge_articles
+            SET kind = $1, category = $2, title = $3, title_th = $4, summary = $5, summary_th = $6,
+                body = $7, body_th = $8, audience_roles = $9, reading_minutes = $10, published = $11,
+                version = $12, updated_by = $13, updated_at = NOW()
+            WHERE id = $14 AND business_id = $15
+            "#,
+        )
+        .bind(input.kind.unwrap_or(existing.kind))
+        .bind(input.category.unwrap_or(existing.category))
+        .bind(input.title.map(|t| t.trim().to_string()).unwrap_or(existing.title))
+        .bind(input.title_th.map(|t| t.trim().to_string()).unwrap_or(existing.title_th))
+        .bind(input.summary.unwrap_or(existing.summary))
+        .bind(input.summary_th.unwrap_or(existing.summary_th))
+        .bind(&body)
+        .bind(&body_th)
+        .bind(input.audience_roles.unwrap_or(existing.audience_roles))
+        .bind(reading_minutes(&body, &body_th))
+        .bind(input.published.unwrap_or(existing.published))
+        .bind(version)
+        .bind(user_id)
+        .bind(article_id)
+        .bind(business_id)
+        .execute(&self.db)
+        .await?;
+
+        self.get_article(business_id, user_id, article_id, true).await
+    }
+
+    /// Remove an item of the business with its reads
+    pub async fn delete_article(&self, business_id: Uuid, user_id: Uuid, article_id: Uuid) -> AppResult<()> {
+        self.own_article(business_id, user_id, article_id).await?;
+        sqlx::query("DELETE FROM knowledge_articles WHERE id = $1 AND business_id = $2")
+            .bind(article_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Which active members the item is meant for have read it
+    pub async fn readership(&self, business_id: Uuid, user_id: Uuid, article_id: Uuid) -> AppResult<ArticleReadership> {
+        let article = self.get_article(business_id, user_id, article_id, true).await?.article;
+        let rows = sqlx::query_as::<_, MemberReadRow>(
+            r#"
+            SELECT u.id AS user_id, u.name, ro.name AS role_name, ro.template_key,
+                   kr.version_read, kr.last_read_at
+            FROM users u
+            JOIN roles ro ON ro.id = u.role_id
+            LEFT JOIN knowledge_article_reads kr ON kr.user_id = u.id AND kr.article_id = $2
+            WHERE u.business_id = $1 AND u.is_active
+            ORDER BY u.name
+            "#,
+        )
+        .bind(business_id)
+        .bind(article_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let readers: Vec<ArticleReader> = rows
+            .into_iter()
+            .filter(|row| is_for_role(&article.audience_roles, row.template_key.as_deref()))
+            .map(|row| ArticleReader {
+                user_id: row.user_id,
+                name: row.name,
+                role_name: row.role_name,
+                read_status: read_status(article.version, row.version_read),
+                version_read: row.version_read,
+                last_read_at: row.last_read_at,
+            })
+            .collect();
+
+        Ok(ArticleReadership {
+            article_id,
+            version: article.version,
+            audience: readers.len(),
+            read: readers.iter().filter(|r| r.read_status == ReadStatus::Read).count(),
+            updated: readers.iter().filter(|r| r.read_status == ReadStatus::Updated).count(),
+            readers,
+        })
+    }
+}
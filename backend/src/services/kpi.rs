@@ -0,0 +1,532 @@
+//! Seasonal KPI targets and variance tracking
+//!
+//! Owners set targets per crop season: cherry harvested, average cupping
+//! score, share of specialty samples (scoring 80 or more) and sales
+//! revenue. Targets left empty are not tracked. The KPI report compares the
+//! season to date against them: cumulative KPIs (cherry, revenue) are also
+//! judged against the target prorated by how far the season has run, and
+//! every KPI is compared with the same point of the previous season for its
+//! trend. Revenue counts sales orders that were not cancelled, in the
+//! targets' currency only.
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::reporting_period::{resolve_season, validate_season, ReportingCalendar};
+use crate::services::BusinessService;
+
+/// Lowest cupping score counted as specialty
+pub const SPECIALTY_MIN_SCORE: Decimal = Decimal::from_parts(80, 0, 0, false, 0);
+
+/// Change from the previous season, in percent of it, still reported as flat
+pub const FLAT_TREND_PCT: Decimal = Decimal::from_parts(1, 0, 0, false, 0);
+
+/// KPI service
+#[derive(Clone)]
+pub struct KpiService {
+    db: PgPool,
+}
+
+/// A tracked KPI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KpiKind {
+    CherryKg,
+    AvgScore,
+    SpecialtyPct,
+    Revenue,
+}
+
+impl KpiKind {
+    pub const ALL: [KpiKind; 4] = [KpiKind::CherryKg, KpiKind::AvgScore, KpiKind::SpecialtyPct, KpiKind::Revenue];
+
+    /// Whether the KPI adds up over the season (rather than averaging)
+    pub fn is_cumulative(&self) -> bool {
+        matches!(self, KpiKind::CherryKg | KpiKind::Revenue)
+    }
+}
+
+/// Direction of a KPI against the same point of the previous season
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KpiTrend {
+    Up,
+    Down,
+    Flat,
+}
+
+/// Targets for one crop season
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct KpiTargets {
+    pub id: Uuid,
+    pub season: i32,
+    pub target_cherry_kg: Option<Decimal>,
+    pub target_avg_score: Option<Decimal>,
+    pub target_specialty_pct: Option<Decimal>,
+    pub target_revenue: Option<Decimal>,
+    pub currency: String,
+    pub summary_sent_for: Option<NaiveDate>,
+    pub updated_by: Option<Uuid>,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl KpiTargets {
+    pub fn target(&self, kpi: KpiKind) -> Option<Decimal> {
+        match kpi {
+            KpiKind::CherryKg => self.target_cherry_kg,
+            KpiKind::AvgScore => self.target_avg_score,
+            KpiKind::SpecialtyPct => self.target_specialty_pct,
+            KpiKind::Revenue => self.target_revenue,
+        }
+    }
+}
+
+/// Targets to set for a season
+#[derive(Debug, Deserialize)]
+pub struct KpiTargetsInput {
+    pub target_cherry_kg: Option<Decimal>,
+    pub target_avg_score: Option<Decimal>,
+    pub target_specialty_pct: Option<Decimal>,
+    pub target_revenue: Option<Decimal>,
+    /// THB by default
+    pub currency: Option<String>,
+}
+
+/// Query parameters for the KPI report
+#[derive(Debug, Default, Deserialize)]
+pub struct KpiQuery {
+    /// Crop season by the year it starts in; the current season by default
+    pub season: Option<i32>,
+    /// Season as a report period such as `season:2024/25`; overrides `season`
+    pub period: Option<String>,
+}
+
+/// Raw figures over a period
+#[derive(Debug, Clone, Default, PartialEq, FromRow)]
+pub struct KpiActuals {
+    pub cherry_kg: Decimal,
+    pub sample_count: i64,
+    pub specialty_count: i64,
+    pub score_total: Decimal,
+    pub revenue: Decimal,
+}
+
+impl KpiActuals {
+    /// Value of a KPI; averages are empty without cupping samples
+    pub fn value(&self, kpi: KpiKind) -> Option<Decimal> {
+        match kpi {
+            KpiKind::CherryKg => Some(self.cherry_kg),
+            KpiKind::Revenue => Some(self.revenue),
+            KpiKind::AvgScore => {
+                (self.sample_count > 0).then(|| (self.score_total / Decimal::from(self.sample_count)).round_dp(2))
+            }
+            KpiKind::SpecialtyPct => (self.sample_count > 0).then(|| {
+                (Decimal::from(self.specialty_count) * Decimal::ONE_HUNDRED / Decimal::from(self.sample_count))
+                    .round_dp(2)
+            }),
+        }
+    }
+
+    pub fn add(&mut self, other: &KpiActuals) {
+        self.cherry_kg += other.cherry_kg;
+        self.sample_count += other.sample_count;
+        self.specialty_count += other.specialty_count;
+        self.score_total += other.score_total;
+        self.revenue += other.revenue;
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct MonthActualsRow {
+    month: NaiveDate,
+    #[sqlx(flatten)]
+    actuals: KpiActuals,
+}
+
+/// One month of the season
+#[derive(Debug, Clone, Serialize)]
+pub struct KpiMonth {
+    /// First day of the month
+    pub month: NaiveDate,
+    pub cherry_kg: Decimal,
+    pub avg_score: Option<Decimal>,
+    pub specialty_pct: Option<Decimal>,
+    pub revenue: Decimal,
+}
+
+impl KpiMonth {
+    pub fn new(month: NaiveDate, actuals: &KpiActuals) -> Self {
+        Self {
+            month,
+            cherry_kg: actuals.cherry_kg,
+            avg_score: actuals.value(KpiKind::AvgScore),
+            specialty_pct: actuals.value(KpiKind::SpecialtyPct),
+            revenue: actuals.revenue,
+        }
+    }
+}
+
+/// A KPI's actual against its target
+#[derive(Debug, Clone, Serialize)]
+pub struct KpiMetric {
+    pub kpi: KpiKind,
+    pub target: Option<Decimal>,
+    /// Season to date
+    pub actual: Option<Decimal>,
+    /// Actual less target
+    pub variance: Option<Decimal>,
+    pub percent_of_target: Option<Decimal>,
+    /// Target prorated by the season elapsed for cumulative KPIs; the
+    /// target itself for averages
+    pub expected_to_date: Option<Decimal>,
+    pub on_track: Option<bool>,
+    /// Same point of the previous season
+    pub previous: Option<Decimal>,
+    pub trend: Option<KpiTrend>,
+}
+
+/// Actuals against targets for a season
+#[derive(Debug, Clone, Serialize)]
+pub struct KpiReport {
+    pub season: i32,
+    pub season_label: String,
+    /// Last day counted; the season's end once it is over
+    pub as_of: NaiveDate,
+    pub season_elapsed_pct: Decimal,
+    pub currency: String,
+    pub targets: Option<KpiTargets>,
+    pub metrics: Vec<KpiMetric>,
+    pub months: Vec<KpiMonth>,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Share of the season run by the end of `as_of`, from 0 to 1
+pub fn season_elapsed(start: NaiveDate, end: NaiveDate, as_of: NaiveDate) -> Decimal {
+    let total = (end - start).num_days() + 1;
+    let elapsed = ((as_of - start).num_days() + 1).clamp(0, total);
+    (Decimal::from(elapsed) / Decimal::from(total)).round_dp(4)
+}
+
+/// Direction of `actual` against `previous`; changes within
+/// `FLAT_TREND_PCT` of the previous value are flat
+pub fn kpi_trend(actual: Decimal, previous: Decimal) -> KpiTrend {
+    let tolerance = previous.abs() * FLAT_TREND_PCT / Decimal::ONE_HUNDRED;
+    let change = actual - previous;
+    if change > tolerance {
+        KpiTrend::Up
+    } else if change < -tolerance {
+        KpiTrend::Down
+    } else {
+        KpiTrend::Flat
+    }
+}
+
+/// Compare a KPI with its target, `elapsed` of the way through the season
+pub fn kpi_metric(
+    kpi: KpiKind,
+    target: Option<Decimal>,
+    actual: Option<Decimal>,
+    previous: Option<Decimal>,
+    elapsed: Decimal,
+) -> KpiMetric {
+    let expected_to_date = target.map(|t| if kpi.is_cumulative() { (t * elapsed).round_dp(2) } else { t });
+    let (variance, percent_of_target, on_track) = match (target, actual, expected_to_date) {
+        (Some(target), Some(actual), Some(expected)) => (
+            Some(actual - target),
+            (target > Decimal::ZERO).then(|| (actual * Decimal::ONE_HUNDRED / target).round_dp(1)),
+            Some(actual >= expected),
+        ),
+        _ => (None, None, None),
+    };
+    let trend = match (actual, previous) {
+        (Some(actual), Some(previous)) => Some(kpi_trend(actual, previous)),
+        _ => None,
+    };
+    KpiMetric {
+        kpi,
+        target,
+        actual,
+        variance,
+        percent_of_target,
+        expected_to_date,
+        on_track,
+        previous,
+        trend,
+    }
+}
+
+/// Every KPI of a season compared with its targets
+pub fn kpi_metrics(
+    targets: Option<&KpiTargets>,
+    actual: &KpiActuals,
+    previous: &KpiActuals,
+    elapsed: Decimal,
+) -> Vec<KpiMetric> {
+    KpiKind::ALL
+        .iter()
+        .map(|&kpi| {
+            kpi_metric(
+                kpi,
+                targets.and_then(|t| t.target(kpi)),
+                actual.value(kpi),
+                previous.value(kpi),
+                elapsed,
+            )
+        })
+        .collect()
+}
+
+/// Check targets and return the currency they are kept in
+pub fn validate_targets(input: &KpiTargetsInput) -> AppResult<String> {
+    let targets = [
+        ("target_cherry_kg", input.target_cherry_kg),
+        ("target_avg_score", input.target_avg_score),
+        ("target_specialty_pct", input.target_specialty_pct),
+        ("target_revenue", input.target_revenue),
+    ];
+    if targets.iter().all(|(_, target)| target.is_none()) {
+        return Err(validation("targets", "Set at least one target", "กรุณากำหนดเป้าหมายอย่างน้อยหนึ่งรายการ"));
+    }
+    for (field, target) in targets {
+        if target.is_some_and(|t| t <= Decimal::ZERO) {
+            return Err(validation(field, "Targets must be greater than zero", "เป้าหมายต้องมากกว่าศูนย์"));
+        }
+    }
+    for (field, target) in [
+        ("target_avg_score", input.target_avg_score),
+        ("target_specialty_pct", input.target_specialty_pct),
+    ] {
+        if target.is_some_and(|t| t > Decimal::ONE_HUNDRED) {
+            return Err(validation(field, "At most 100", "ต้องไม่เกิน 100"));
+        }
+    }
+    let currency = input.currency.as_deref().unwrap_or("THB").trim().to_uppercase();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(validation(
+            "currency",
+            "Currency must be a 3-letter code such as THB or USD",
+            "สกุลเงินต้องเป็นรหัส 3 ตัวอักษร เช่น THB หรือ USD",
+        ));
+    }
+    Ok(currency)
+}
+
+const TARGET_COLUMNS: &str = r#"
+    id, season, target_cherry_kg, target_avg_score, target_specialty_pct, target_revenue,
+    currency, summary_sent_for, updated_by, created_at, updated_at
+"#;
+
+impl KpiService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Targets of every season, latest first
+    pub async fn list_targets(&self, business_id: Uuid) -> AppResult<Vec<KpiTargets>> {
+        let targets = sqlx::query_as::<_, KpiTargets>(&format!(
+            "SELECT {} FROM kpi_targets WHERE business_id = $1 ORDER BY season DESC",
+            TARGET_COLUMNS
+        ))
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(targets)
+    }
+
+    pub async fn get_targets(&self, business_id: Uuid, season: i32) -> AppResult<Option<KpiTargets>> {
+        let targets = sqlx::query_as::<_, KpiTargets>(&format!(
+            "SELECT {} FROM kpi_targets WHERE business_id = $1 AND season = $2",
+            TARGET_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(season)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(targets)
+    }
+
+    /// Set the targets of a season, replacing any set before
+    pub async fn set_targets(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        season: i32,
+        input: KpiTargetsInput,
+    ) -> AppResult<KpiTargets> {
+        validate_season(season)?;
+        let currency = validate_targets(&input)?;
+
+        let targets = sqlx::query_as::<_, KpiTargets>(&format!(
+            r#"
+            INSERT INTO kpi_targets (
+                business_id, season, target_cherry_kg, target_avg_score, target_specialty_pct,
+                target_revenue, currency, updated_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (business_id, season) DO UPDATE SET
+                target_cherry_kg = EXCLUDED.target_cherry_kg,
+                target_avg_score = EXCLUDED.target_avg_score,
+                target_specialty_pct = EXCLUDED.target_specialty_pct,
+                target_revenue = EXCLUDED.target_revenue,
+                currency = EXCLUDED.currency,
+                updated_by = EXCLUDED.updated_by
+            RETURNING {}
+            "#,
+            TARGET_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(season)
+        .bind(input.target_cherry_kg)
+        .bind(input.target_avg_score)
+        .bind(input.target_specialty_pct)
+        .bind(input.target_revenue)
+        .bind(currency)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(targets)
+    }
+
+    pub async fn delete_targets(&self, business_id: Uuid, season: i32) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM kpi_targets WHERE business_id = $1 AND season = $2")
+            .bind(business_id)
+            .bind(season)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("KPI targets".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Figures for each month from `start` up to and including `until`
+    async fn monthly_actuals(
+        &self,
+        business_id: Uuid,
+        start: NaiveDate,
+        until: NaiveDate,
+        currency: &str,
+    ) -> AppResult<Vec<(NaiveDate, KpiActuals)>> {
+        if until < start {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query_as::<_, MonthActualsRow>(
+            r#"
+            SELECT m.month::date AS month,
+                   (SELECT COALESCE(SUM(h.cherry_weight_kg), 0)
+                    FROM harvests h
+                    WHERE h.business_id = $1
+                      AND h.harvest_date >= m.month AND h.harvest_date < m.month + INTERVAL '1 month'
+                      AND h.harvest_date <= $3) AS cherry_kg,
+                   c.sample_count, c.specialty_count, c.score_total,
+                   (SELECT COALESCE(SUM(so.total_amount), 0)
+                    FROM sales_orders so
+                    WHERE so.business_id = $1
+                      AND so.status <> 'cancelled'
+                      AND so.currency = $4
+                      AND so.created_at::date >= m.month AND so.created_at::date < m.month + INTERVAL '1 month'
+                      AND so.created_at::date <= $3) AS revenue
+            FROM generate_series($2::timestamp, $3::timestamp, INTERVAL '1 month') AS m(month)
+            CROSS JOIN LATERAL (
+                SELECT COUNT(csamp.id) AS sample_count,
+                       COUNT(csamp.id) FILTER (WHERE csamp.total_score >= $5) AS specialty_count,
+                       COALESCE(SUM(csamp.total_score), 0) AS score_total
+                FROM cupping_sessions cs
+                JOIN cupping_samples csamp ON csamp.session_id = cs.id
+                WHERE cs.business_id = $1
+                  AND cs.session_date >= m.month AND cs.session_date < m.month + INTERVAL '1 month'
+                  AND cs.session_date <= $3
+            ) c
+            ORDER BY m.month
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(until)
+        .bind(currency)
+        .bind(SPECIALTY_MIN_SCORE)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.month, row.actuals)).collect())
+    }
+
+    /// KPI report for a season as of today
+    pub async fn report(&self, business_id: Uuid, query: &KpiQuery) -> AppResult<KpiReport> {
+        let today = Utc::now().date_naive();
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let season = resolve_season(query.period.as_deref(), query.season, &calendar, today)?;
+        self.report_as_of(business_id, &calendar, season, today).await
+    }
+
+    /// KPI report for a season of the business's calendar counting up to
+    /// `as_of`
+    pub async fn report_as_of(
+        &self,
+        business_id: Uuid,
+        calendar: &ReportingCalendar,
+        season: i32,
+        as_of: NaiveDate,
+    ) -> AppResult<KpiReport> {
+        let (start, end) = calendar.season_bounds(season)?;
+        let as_of = as_of.min(end);
+        let targets = self.get_targets(business_id, season).await?;
+        let currency = targets.as_ref().map(|t| t.currency.clone()).unwrap_or_else(|| "THB".to_string());
+
+        let months = self.monthly_actuals(business_id, start, as_of, &currency).await?;
+        let mut actual = KpiActuals::default();
+        for (_, month) in &months {
+            actual.add(month);
+        }
+
+        // Same point of the previous season
+        let mut previous = KpiActuals::default();
+        if let (Some(previous_start), Some(previous_as_of)) = (
+            start.checked_sub_months(Months::new(12)),
+            as_of.checked_sub_months(Months::new(12)),
+        ) {
+            for (_, month) in self.monthly_actuals(business_id, previous_start, previous_as_of, &currency).await? {
+                previous.add(&month);
+            }
+        }
+
+        let elapsed = season_elapsed(start, end, as_of);
+        Ok(KpiReport {
+            season,
+            season_label: calendar.season_label(season),
+            as_of,
+            season_elapsed_pct: (elapsed * Decimal::ONE_HUNDRED).round_dp(1),
+            metrics: kpi_metrics(targets.as_ref(), &actual, &previous, elapsed),
+            months: months.iter().map(|(month, actuals)| KpiMonth::new(*month, actuals)).collect(),
+            currency,
+            targets,
+        })
+    }
+
+    /// Record that the monthly summary covering `month` was sent
+    pub async fn mark_summary_sent(&self, targets_id: Uuid, month: NaiveDate) -> AppResult<()> {
+        sqlx::query("UPDATE kpi_targets SET summary_sent_for = $2 WHERE id = $1")
+            .bind(targets_id)
+            .bind(month.with_day(1).unwrap_or(month))
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,405 @@
+//! Third-party lab results for lots
+//!
+//! Moisture, water activity, ochratoxin A and pesticide residue analyses
+//! from external labs, judged pass/fail against default limits or limits
+//! given with the result. The lab report is referenced by URL like
+//! certification documents, and results marked for buyers are printed on
+//! the lot spec sheet.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+
+/// Green coffee moisture range in percent (as for grading)
+pub const MOISTURE_MIN_PERCENT: Decimal = Decimal::from_parts(10, 0, 0, false, 0);
+pub const MOISTURE_MAX_PERCENT: Decimal = Decimal::from_parts(12, 0, 0, false, 0);
+/// SCA maximum water activity for green coffee
+pub const WATER_ACTIVITY_MAX: Decimal = Decimal::from_parts(70, 0, 0, false, 2);
+/// Ochratoxin A limit in µg/kg commonly required by buyers of green coffee
+pub const OCHRATOXIN_A_MAX_UG_KG: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+
+/// Lab result service
+#[derive(Clone)]
+pub struct LabResultService {
+    db: PgPool,
+}
+
+/// Kind of analysis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabAnalysis {
+    Moisture,
+    WaterActivity,
+    OchratoxinA,
+    PesticideResidue,
+}
+
+impl LabAnalysis {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LabAnalysis::Moisture => "moisture",
+            LabAnalysis::WaterActivity => "water_activity",
+            LabAnalysis::OchratoxinA => "ochratoxin_a",
+            LabAnalysis::PesticideResidue => "pesticide_residue",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "moisture" => Some(LabAnalysis::Moisture),
+            "water_activity" => Some(LabAnalysis::WaterActivity),
+            "ochratoxin_a" => Some(LabAnalysis::OchratoxinA),
+            "pesticide_residue" => Some(LabAnalysis::PesticideResidue),
+            _ => None,
+        }
+    }
+
+    /// Unit results are reported in
+    pub fn unit(&self) -> &'static str {
+        match self {
+            LabAnalysis::Moisture => "%",
+            LabAnalysis::WaterActivity => "aw",
+            LabAnalysis::OchratoxinA => "µg/kg",
+            LabAnalysis::PesticideResidue => "mg/kg",
+        }
+    }
+
+    /// Default (min, max) limits. Pesticide residue limits depend on the
+    /// compound and destination market, so they come with the result.
+    pub fn default_limits(&self) -> (Option<Decimal>, Option<Decimal>) {
+        match self {
+            LabAnalysis::Moisture => (Some(MOISTURE_MIN_PERCENT), Some(MOISTURE_MAX_PERCENT)),
+            LabAnalysis::WaterActivity => (None, Some(WATER_ACTIVITY_MAX)),
+            LabAnalysis::OchratoxinA => (None, Some(OCHRATOXIN_A_MAX_UG_KG)),
+            LabAnalysis::PesticideResidue => (None, None),
+        }
+    }
+
+    pub fn label(&self, thai: bool) -> &'static str {
+        match (self, thai) {
+            (LabAnalysis::Moisture, false) => "Moisture",
+            (LabAnalysis::Moisture, true) => "ความชื้น",
+            (LabAnalysis::WaterActivity, false) => "Water activity",
+            (LabAnalysis::WaterActivity, true) => "ค่าวอเตอร์แอคทิวิตี",
+            (LabAnalysis::OchratoxinA, false) => "Ochratoxin A",
+            (LabAnalysis::OchratoxinA, true) => "โอคราทอกซินเอ",
+            (LabAnalysis::PesticideResidue, false) => "Pesticide residue",
+            (LabAnalysis::PesticideResidue, true) => "สารเคมีตกค้าง",
+        }
+    }
+}
+
+/// Whether a value is within its limits (inclusive)
+pub fn within_limits(value: Decimal, min: Option<Decimal>, max: Option<Decimal>) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+/// Database row for a lab result
+#[derive(Debug, sqlx::FromRow)]
+struct LabResultRow {
+    id: Uuid,
+    business_id: Uuid,
+    lot_id: Uuid,
+    analysis: String,
+    analyte: Option<String>,
+    value: Decimal,
+    unit: String,
+    min_limit: Option<Decimal>,
+    max_limit: Option<Decimal>,
+    passed: bool,
+    lab_name: String,
+    report_number: Option<String>,
+    tested_on: NaiveDate,
+    document_name: Option<String>,
+    file_url: Option<String>,
+    file_size_bytes: Option<i64>,
+    mime_type: Option<String>,
+    include_in_buyer_pack: bool,
+    notes: Option<String>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+/// Lab report file
+#[derive(Debug, Clone, Serialize)]
+pub struct LabReportDocument {
+    pub document_name: String,
+    pub file_url: String,
+    pub file_size_bytes: Option<i64>,
+    pub mime_type: Option<String>,
+}
+
+/// Lab result with its pass/fail verdict
+#[derive(Debug, Clone, Serialize)]
+pub struct LabResult {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+    pub analysis: LabAnalysis,
+    pub analyte: Option<String>,
+    pub value: Decimal,
+    pub unit: String,
+    pub min_limit: Option<Decimal>,
+    pub max_limit: Option<Decimal>,
+    pub passed: bool,
+    pub lab_name: String,
+    pub report_number: Option<String>,
+    pub tested_on: NaiveDate,
+    pub document: Option<LabReportDocument>,
+    pub include_in_buyer_pack: bool,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a lab result
+#[derive(Debug, Deserialize)]
+pub struct RecordLabResultInput {
+    pub lot_id: Uuid,
+    pub analysis: LabAnalysis,
+    /// Compound tested for; required for pesticide residue
+    pub analyte: Option<String>,
+    /// In the analysis's unit (%, aw, µg/kg or mg/kg)
+    pub value: Decimal,
+    /// Override the default limits, e.g. a buyer's stricter limit; required
+    /// (`max_limit`) for pesticide residue
+    pub min_limit: Option<Decimal>,
+    pub max_limit: Option<Decimal>,
+    pub lab_name: String,
+    pub report_number: Option<String>,
+    pub tested_on: NaiveDate,
+    pub document_name: Option<String>,
+    pub file_url: Option<String>,
+    pub file_size_bytes: Option<i64>,
+    pub mime_type: Option<String>,
+    /// Print on the lot spec sheet (default true)
+    pub include_in_buyer_pack: Option<bool>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing lab results
+#[derive(Debug, Default, Deserialize)]
+pub struct LabResultQuery {
+    pub lot_id: Option<Uuid>,
+    pub analysis: Option<LabAnalysis>,
+    /// Only failed results
+    #[serde(default)]
+    pub failed: bool,
+}
+
+const LAB_RESULT_COLUMNS: &str = r#"
+    id, business_id, lot_id, analysis, analyte, value, unit, min_limit, max_limit, passed,
+    lab_name, report_number, tested_on, document_name, file_url, file_size_bytes, mime_type,
+    include_in_buyer_pack, notes, created_by, created_at
+"#;
+
+impl LabResultService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a lab result, judging it against the given or default limits
+    pub async fn record(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordLabResultInput,
+    ) -> AppResult<LabResult> {
+        let lab_name = input.lab_name.trim();
+        if lab_name.is_empty() {
+            return Err(AppError::Validation {
+                field: "lab_name".to_string(),
+                message: "Lab name is required".to_string(),
+                message_th: "ต้องระบุชื่อห้องปฏิบัติการ".to_string(),
+            });
+        }
+        if input.value < Decimal::ZERO {
+            return Err(AppError::Validation {
+                field: "value".to_string(),
+                message: "Result cannot be negative".to_string(),
+                message_th: "ผลการวิเคราะห์ต้องไม่ติดลบ".to_string(),
+            });
+        }
+        let analyte = input.analyte.as_deref().map(str::trim).filter(|a| !a.is_empty());
+        if input.analysis == LabAnalysis::PesticideResidue {
+            if analyte.is_none() {
+                return Err(AppError::Validation {
+                    field: "analyte".to_string(),
+                    message: "Name the compound tested for".to_string(),
+                    message_th: "ต้องระบุชื่อสารที่ตรวจ".to_string(),
+                });
+            }
+            if input.max_limit.is_none() {
+                return Err(AppError::Validation {
+                    field: "max_limit".to_string(),
+                    message: "Pesticide residue needs the maximum residue limit (MRL)".to_string(),
+                    message_th: "ต้องระบุค่าสารตกค้างสูงสุดที่ยอมรับได้ (MRL)".to_string(),
+                });
+            }
+        }
+
+        let (default_min, default_max) = input.analysis.default_limits();
+        let min_limit = input.min_limit.or(default_min);
+        let max_limit = input.max_limit.or(default_max);
+        if let (Some(min), Some(max)) = (min_limit, max_limit) {
+            if min > max {
+                return Err(AppError::Validation {
+                    field: "min_limit".to_string(),
+                    message: "Minimum limit is above the maximum limit".to_string(),
+                    message_th: "ค่าต่ำสุดต้องไม่เกินค่าสูงสุด".to_string(),
+                });
+            }
+        }
+        let passed = within_limits(input.value, min_limit, max_limit);
+
+        let file_url = input.file_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+        let document_name = file_url.map(|url| {
+            input
+                .document_name
+                .clone()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url).to_string())
+        });
+
+        let lot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !lot_exists {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        let row = sqlx::query_as::<_, LabResultRow>(&format!(
+            r#"
+            INSERT INTO lab_results (
+                business_id, lot_id, analysis, analyte, value, unit, min_limit, max_limit, passed,
+                lab_name, report_number, tested_on, document_name, file_url, file_size_bytes,
+                mime_type, include_in_buyer_pack, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            RETURNING {}
+            "#,
+            LAB_RESULT_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.analysis.as_str())
+        .bind(analyte)
+        .bind(input.value)
+        .bind(input.analysis.unit())
+        .bind(min_limit)
+        .bind(max_limit)
+        .bind(passed)
+        .bind(lab_name)
+        .bind(&input.report_number)
+        .bind(input.tested_on)
+        .bind(document_name)
+        .bind(file_url)
+        .bind(input.file_size_bytes)
+        .bind(&input.mime_type)
+        .bind(input.include_in_buyer_pack.unwrap_or(true))
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(Self::row_to_result(row))
+    }
+
+    /// List lab results, most recent test first
+    pub async fn list(&self, business_id: Uuid, query: &LabResultQuery) -> AppResult<Vec<LabResult>> {
+        let rows = sqlx::query_as::<_, LabResultRow>(&format!(
+            r#"
+            SELECT {}
+            FROM lab_results
+            WHERE business_id = $1
+              AND ($2::UUID IS NULL OR lot_id = $2)
+              AND ($3::TEXT IS NULL OR analysis = $3)
+              AND (NOT $4 OR NOT passed)
+            ORDER BY tested_on DESC, created_at DESC
+            "#,
+            LAB_RESULT_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.analysis.map(|a| a.as_str()))
+        .bind(query.failed)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_result).collect())
+    }
+
+    /// Results of a lot marked for buyers: the latest per analysis (and
+    /// compound, for pesticide residue)
+    pub async fn buyer_pack_results(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<Vec<LabResult>> {
+        let rows = sqlx::query_as::<_, LabResultRow>(&format!(
+            r#"
+            SELECT {}
+            FROM (
+                SELECT DISTINCT ON (analysis, LOWER(COALESCE(analyte, ''))) *
+                FROM lab_results
+                WHERE business_id = $1 AND lot_id = $2 AND include_in_buyer_pack
+                ORDER BY analysis, LOWER(COALESCE(analyte, '')), tested_on DESC, created_at DESC
+            ) latest
+            ORDER BY analysis, analyte
+            "#,
+            LAB_RESULT_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_result).collect())
+    }
+
+    /// Delete a lab result, keeping it in the recycle bin
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, result_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::LabResult, result_id)
+            .await
+    }
+
+    fn row_to_result(row: LabResultRow) -> LabResult {
+        let document = match (row.document_name, row.file_url) {
+            (Some(document_name), Some(file_url)) => Some(LabReportDocument {
+                document_name,
+                file_url,
+                file_size_bytes: row.file_size_bytes,
+                mime_type: row.mime_type,
+            }),
+            _ => None,
+        };
+
+        LabResult {
+            id: row.id,
+            business_id: row.business_id,
+            lot_id: row.lot_id,
+            // The column is constrained to the known analyses
+            analysis: LabAnalysis::from_str(&row.analysis).unwrap_or(LabAnalysis::Moisture),
+            analyte: row.analyte,
+            value: row.value,
+            unit: row.unit,
+            min_limit: row.min_limit,
+            max_limit: row.max_limit,
+            passed: row.passed,
+            lab_name: row.lab_name,
+            report_number: row.report_number,
+            tested_on: row.tested_on,
+            document,
+            include_in_buyer_pack: row.include_in_buyer_pack,
+            notes: row.notes,
+            created_by: row.created_by,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Bulk label printing for packages and samples
+//!
+//! Prints one label per physical package of shipment items and one per
+//! sample drawn from a lot (inventory transactions of type `sample`). Each
+//! label carries a QR code to the lot's public traceability page, the
+//! package or sample code, the lot and the weight. Labels go on A4 sheets
+//! of a grid of labels or on thermal roll labels, one label per page. On A4
+//! the first labels of a partly used sheet can be skipped.
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use shared::{DisplayFormat, Language};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::{Config, PdfConfig};
+use crate::error::{AppError, AppResult};
+use crate::services::pdf::{PdfFonts, PdfPage, TextStyle, A4_HEIGHT_MM, A4_WIDTH_MM};
+use crate::services::{BusinessService, TraceabilityService};
+
+/// Most labels printed in one request
+pub const MAX_LABELS: usize = 2000;
+
+/// Label stock the labels are printed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum LabelLayout {
+    /// A4 sheet of 3 x 8 labels of 70 x 37 mm
+    #[default]
+    #[serde(rename = "a4_3x8")]
+    A4ThreeByEight,
+    /// A4 sheet of 2 x 7 labels of 105 x 42 mm
+    #[serde(rename = "a4_2x7")]
+    A4TwoBySeven,
+    /// A4 sheet of 2 x 4 labels of 105 x 74 mm, for bags and boxes
+    #[serde(rename = "a4_2x4")]
+    A4TwoByFour,
+    /// Thermal roll of 100 x 50 mm labels
+    #[serde(rename = "thermal_100x50")]
+    Thermal100x50,
+    /// Thermal roll of 60 x 40 mm labels
+    #[serde(rename = "thermal_60x40")]
+    Thermal60x40,
+    /// Thermal roll of 100 x 150 mm shipping labels
+    #[serde(rename = "thermal_100x150")]
+    Thermal100x150,
+}
+
+/// Page and label sizes of a layout, in millimetres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelSheet {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f32,
+    pub label_height: f32,
+}
+
+impl LabelSheet {
+    const fn a4(columns: usize, rows: usize, label_height: f32) -> Self {
+        Self {
+            page_width: A4_WIDTH_MM,
+            page_height: A4_HEIGHT_MM,
+            columns,
+            rows,
+            label_width: A4_WIDTH_MM / columns as f32,
+            label_height,
+        }
+    }
+
+    const fn roll(label_width: f32, label_height: f32) -> Self {
+        Self {
+            page_width: label_width,
+            page_height: label_height,
+            columns: 1,
+            rows: 1,
+            label_width,
+            label_height,
+        }
+    }
+
+    pub fn labels_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Page (0-based) and top-left corner of the label in slot `index`;
+    /// labels are centred vertically on the page
+    pub fn position(&self, index: usize) -> (usize, f32, f32) {
+        let slot = index % self.labels_per_page();
+        let top = (self.page_height - self.rows as f32 * self.label_height) / 2.0;
+        (
+            index / self.labels_per_page(),
+            (slot % self.columns) as f32 * self.label_width,
+            top + (slot / self.columns) as f32 * self.label_height,
+        )
+    }
+}
+
+impl LabelLayout {
+    pub fn sheet(&self) -> LabelSheet {
+        match self {
+            Self::A4ThreeByEight => LabelSheet::a4(3, 8, 37.0),
+            Self::A4TwoBySeven => LabelSheet::a4(2, 7, 42.0),
+            Self::A4TwoByFour => LabelSheet::a4(2, 4, 74.0),
+            Self::Thermal100x50 => LabelSheet::roll(100.0, 50.0),
+            Self::Thermal60x40 => LabelSheet::roll(60.0, 40.0),
+            Self::Thermal100x150 => LabelSheet::roll(100.0, 150.0),
+        }
+    }
+}
+
+/// Labels to print
+#[derive(Debug, Deserialize)]
+pub struct PrintLabelsInput {
+    /// Shipment items; one label per package of each
+    #[serde(default)]
+    pub package_ids: Vec<Uuid>,
+    /// Sample inventory transactions
+    #[serde(default)]
+    pub sample_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub layout: LabelLayout,
+    /// Labels already used on the first A4 sheet
+    #[serde(default)]
+    pub skip_labels: usize,
+    pub language: Option<String>, // "en" (default) or "th"
+}
+
+/// What one label shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub code: String,
+    /// Package number and count, or what the sample is
+    pub caption: String,
+    pub lot_code: String,
+    pub lot_name: String,
+    pub weight_kg: Decimal,
+    pub trace_url: String,
+}
+
+/// Label sheet service
+#[derive(Clone)]
+pub struct LabelSheetService {
+    db: PgPool,
+    pdf: PdfConfig,
+    public_url: String,
+}
+
+/// Database row for a shipment item
+#[derive(Debug, sqlx::FromRow)]
+struct PackageRow {
+    id: Uuid,
+    shipment_number: String,
+    package_count: i32,
+    package_type: Option<String>,
+    quantity_kg: Decimal,
+    traceability_code: String,
+    lot_name: String,
+    qr_code_url: Option<String>,
+}
+
+/// Database row for a sample transaction
+#[derive(Debug, sqlx::FromRow)]
+struct SampleRow {
+    id: Uuid,
+    quantity_kg: Decimal,
+    stage: String,
+    traceability_code: String,
+    lot_name: String,
+    qr_code_url: Option<String>,
+}
+
+/// Split a shipment item's weight over its packages; the packages weigh
+/// the same to the gram and the last one takes the remainder
+pub fn package_weights(quantity_kg: Decimal, package_count: u32) -> Vec<Decimal> {
+    if package_count == 0 {
+        return Vec::new();
+    }
+    let each = (quantity_kg / Decimal::from(package_count)).round_dp(3);
+    let mut weights = vec![each; package_count as usize];
+    if let Some(last) = weights.last_mut() {
+        *last = quantity_kg - each * Decimal::from(package_count - 1);
+    }
+    weights
+}
+
+/// Code of a sample label, from its transaction id
+pub fn sample_code(id: Uuid) -> String {
+    format!("S-{}", &id.simple().to_string()[..8]).to_uppercase()
+}
+
+impl LabelSheetService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            pdf: config.pdf.clone(),
+            public_url: config.server.public_url.clone(),
+        }
+    }
+
+    /// Labels PDF for packages and samples, in the order they were given.
+    /// Thai captions are used only when a Thai-capable font is configured
+    pub async fn generate(&self, business_id: Uuid, input: &PrintLabelsInput) -> AppResult<Vec<u8>> {
+        let labels = self.labels(business_id, input).await?;
+        if labels.is_empty() {
+            return Err(AppError::Validation {
+                field: "package_ids".to_string(),
+                message: "Select at least one package or sample".to_string(),
+                message_th: "เลือกหีบห่อหรือตัวอย่างอย่างน้อยหนึ่งรายการ".to_string(),
+            });
+        }
+        if labels.len() > MAX_LABELS {
+            return Err(AppError::Validation {
+                field: "package_ids".to_string(),
+                message: format!("At most {} labels can be printed at once", MAX_LABELS),
+                message_th: format!("พิมพ์ฉลากได้ครั้งละไม่เกิน {} ดวง", MAX_LABELS),
+            });
+        }
+
+        let fonts = PdfFonts::load(&self.pdf).await?;
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let thai = input.language.as_deref() == Some("th") && fonts.supports_thai();
+        let sheet = input.layout.sheet();
+        // Skipping only applies to sheets; a roll has no used labels
+        let skip = input.skip_labels.min(sheet.labels_per_page().saturating_sub(1));
+
+        render_labels(&labels, sheet, skip, &fonts, thai, display)
+    }
+
+    async fn labels(&self, business_id: Uuid, input: &PrintLabelsInput) -> AppResult<Vec<Label>> {
+        let thai = input.language.as_deref() == Some("th");
+        let mut labels = Vec::new();
+
+        let packages = sqlx::query_as::<_, PackageRow>(
+            r#"
+            SELECT si.id, s.shipment_number, si.package_count, si.package_type, si.quantity_kg,
+                   l.traceability_code, l.name AS lot_name, l.qr_code_url
+            FROM shipment_items si
+            JOIN shipments s ON s.id = si.shipment_id
+            JOIN lots l ON l.id = si.lot_id
+            WHERE si.id = ANY($1) AND s.business_id = $2
+            "#,
+        )
+        .bind(&input.package_ids)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        for id in &input.package_ids {
+            let package = packages
+                .iter()
+                .find(|p| p.id == *id)
+                .ok_or_else(|| AppError::NotFound("Package".to_string()))?;
+            let count = package.package_count.max(0) as u32;
+            let trace_url = self.trace_url(&package.traceability_code, &package.qr_code_url);
+            for (i, weight_kg) in package_weights(package.quantity_kg, count).into_iter().enumerate() {
+                let number = if thai {
+                    format!("หีบห่อ {}/{}", i + 1, count)
+                } else {
+                    format!("Package {}/{}", i + 1, count)
+                };
+                labels.push(Label {
+                    code: format!("{}-{}", package.shipment_number, i + 1),
+                    caption: match &package.package_type {
+                        Some(package_type) => format!("{}  {}", number, package_type),
+                        None => number,
+                    },
+                    lot_code: package.traceability_code.clone(),
+                    lot_name: package.lot_name.clone(),
+                    weight_kg,
+                    trace_url: trace_url.clone(),
+                });
+            }
+        }
+
+        let samples = sqlx::query_as::<_, SampleRow>(
+            r#"
+            SELECT it.id, it.quantity_kg, it.stage,
+                   l.traceability_code, l.name AS lot_name, l.qr_code_url
+            FROM inventory_transactions it
+            JOIN lots l ON l.id = it.lot_id
+            WHERE it.id = ANY($1) AND it.business_id = $2 AND it.transaction_type = 'sample'
+            "#,
+        )
+        .bind(&input.sample_ids)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        for id in &input.sample_ids {
+            let sample = samples
+                .iter()
+                .find(|s| s.id == *id)
+                .ok_or_else(|| AppError::NotFound("Sample".to_string()))?;
+            let stage = sample.stage.replace('_', " ");
+            labels.push(Label {
+                code: sample_code(sample.id),
+                caption: if thai { format!("ตัวอย่าง {}", stage) } else { format!("Sample {}", stage) },
+                lot_code: sample.traceability_code.clone(),
+                lot_name: sample.lot_name.clone(),
+                weight_kg: sample.quantity_kg,
+                trace_url: self.trace_url(&sample.traceability_code, &sample.qr_code_url),
+            });
+        }
+
+        Ok(labels)
+    }
+
+    fn trace_url(&self, traceability_code: &str, qr_code_url: &Option<String>) -> String {
+        qr_code_url
+            .clone()
+            .unwrap_or_else(|| TraceabilityService::generate_qr_code_url(traceability_code, &self.public_url))
+    }
+}
+
+/// QR code on the left, code, caption, lot and weight to its right; sizes
+/// follow the label height
+fn render_labels(
+    labels: &[Label],
+    sheet: LabelSheet,
+    skip: usize,
+    fonts: &PdfFonts,
+    thai: bool,
+    display: DisplayFormat,
+) -> AppResult<Vec<u8>> {
+    let fmt = display.for_language(if thai { &Language::Thai } else { &Language::English });
+    let kg = if thai { "กก." } else { "kg" };
+    let scale = (sheet.label_height / 37.0).min(sheet.label_width / 70.0).clamp(0.8, 1.6);
+    let padding = 3.0 * scale;
+    let qr_size = (sheet.label_height - 2.0 * padding).min(sheet.label_width * 0.4);
+
+    let mut page = PdfPage::new("Labels", sheet.page_width, sheet.page_height, fonts)?;
+    let mut current_page = 0;
+
+    for (i, label) in labels.iter().enumerate() {
+        let (page_index, x, y) = sheet.position(skip + i);
+        while current_page < page_index {
+            page.add_page();
+            current_page += 1;
+        }
+
+        if sheet.labels_per_page() > 1 {
+            page.outline_rect(x + 1.0, y + 1.0, sheet.label_width - 2.0, sheet.label_height - 2.0, 0.3, 0.8);
+        }
+        page.qr_code(x + padding, y + (sheet.label_height - qr_size) / 2.0, qr_size, &label.trace_url)?;
+
+        let text_x = x + 2.0 * padding + qr_size;
+        let text_width = sheet.label_width - qr_size - 3.0 * padding;
+        let mut text_y = y + padding + 5.0 * scale;
+        page.text(text_x, text_y, TextStyle::bold(11.0 * scale), &label.code);
+        text_y += 4.5 * scale;
+        page.text(text_x, text_y, TextStyle::regular(7.5 * scale), &label.caption);
+        text_y += 4.5 * scale;
+        page.text(text_x, text_y, TextStyle::bold(8.0 * scale), &label.lot_code);
+        text_y += 0.5 * scale;
+        text_y += page.wrapped_text(
+            text_x,
+            text_y + 3.5 * scale,
+            text_width,
+            TextStyle::regular(7.0 * scale),
+            &label.lot_name,
+            2,
+        );
+        page.text(
+            text_x,
+            text_y + 8.0 * scale,
+            TextStyle::bold(12.0 * scale),
+            &format!("{} {}", fmt.decimal(label.weight_kg, 2), kg),
+        );
+    }
+
+    page.finish()
+}
@@ -3,22 +3,55 @@
 //! Supports quick logging of:
 //! - Harvest entries via text commands
 //! - Processing entries via text commands
+//! - Manual rain gauge readings via text commands
 //!
 //! Command formats:
 //! - Harvest: "harvest [plot_name] [weight_kg] [ripe%]" or "เก็บ [plot_name] [weight_kg] [ripe%]"
 //! - Processing: "process [lot_code] [method]" or "แปรรูป [lot_code] [method]"
+//! - Rain: "rain [plot_name] [mm]" or "ฝน [plot_name] [mm]"
+//! - SOP checklists: "sop" lists them, "sop [number] [lot_code]" starts one
+//!   (or "ขั้นตอน ..."), and "done [step] [step] ..." or "เสร็จ ..." ticks
+//!   steps of the member's open checklist
+//!
+//! Shared locations are matched to the nearest plot and answered with quick
+//! replies to attach the check-in to today's harvest or record the weather there.
+//!
+//! A photo of a cherry basket is sent for a ripeness estimate, which fills in
+//! the ripeness of the member's next harvest command without a ripe % (within
+//! an hour); giving a ripe % overrides it.
+//!
+//! Postback data uses query-string form and is routed to the same commands:
+//! - "action=harvest&plot=plot1&kg=50&ripe=85"
+//! - "action=process&lot=CQM-2024-DOI-001&method=washed"
+//! - "action=help"
+//! - "action=checkin_harvest&checkin=<id>" / "action=checkin_weather&checkin=<id>"
+//! - "action=ack&notification=<id>" (read receipt for a pushed notification)
+//!
+//! Webhook events are deserialized permissively: events that cannot be parsed
+//! are logged and skipped, and unsupported event types are logged and ignored,
+//! so a new LINE event type never fails the whole webhook delivery.
 
-use chrono::Local;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{Local, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
-use crate::services::harvest::{HarvestService, RecordHarvestInput};
+use crate::services::harvest::{HarvestService, RecordHarvestInput, RipenessAssessment, SameDayHarvest};
 use crate::services::processing::{ProcessingService, StartProcessingInput};
-use crate::services::notification::{LineMessage, LineMessagingClient};
+use crate::services::notification::{
+    LineMessage, LineMessagingClient, LineQuickReply, LineQuickReplyItem, NotificationService,
+};
+use crate::services::sop_checklist::{step_lines, CheckItemsInput, StartExecutionInput};
+use crate::services::SopChecklistService;
+use crate::services::ripeness_estimate::{
+    EstimateRipenessInput, RipenessEstimateService, RIPENESS_ESTIMATE_VALID_MINUTES,
+};
+use crate::services::weather::{RecordRainfallInput, WeatherService, MAX_RAINFALL_MM};
 use shared::ProcessingMethod;
 
 /// LINE Chatbot service
@@ -34,11 +67,40 @@ pub struct LineChatbotService {
 #[derive(Debug, Deserialize)]
 pub struct LineWebhookRequest {
     /// User ID of the LINE Official Account that received the webhook event
+    #[serde(default)]
     pub destination: String,
-    /// Array of webhook event objects
+    /// Array of webhook event objects (unparseable events are logged and dropped)
+    #[serde(default, deserialize_with = "deserialize_events")]
     pub events: Vec<LineWebhookEvent>,
 }
 
+/// Deserialize webhook events one by one, skipping (and logging) any event
+/// that does not match the expected shape instead of rejecting the request
+fn deserialize_events<'de, D>(deserializer: D) -> Result<Vec<LineWebhookEvent>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw: Vec<serde_json::Value> = Vec::deserialize(deserializer)?;
+    let events = raw
+        .into_iter()
+        .filter_map(|value| {
+            let event_type = value
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or("<missing>")
+                .to_string();
+            match serde_json::from_value::<LineWebhookEvent>(value) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    tracing::warn!("Skipping malformed LINE webhook event (type {}): {}", event_type, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(events)
+}
+
 /// LINE Webhook event
 /// See: https://developers.line.biz/en/reference/messaging-api/#common-properties
 #[derive(Debug, Deserialize)]
@@ -50,10 +112,14 @@ pub struct LineWebhookEvent {
     #[serde(rename = "replyToken")]
     pub reply_token: Option<String>,
     /// Source of the event
+    #[serde(default)]
     pub source: LineEventSource,
     /// Message object (only for message events)
     pub message: Option<LineEventMessage>,
+    /// Postback object (only for postback events)
+    pub postback: Option<LinePostback>,
     /// Time of the event in milliseconds
+    #[serde(default)]
     pub timestamp: i64,
     /// Channel state: "active" or "standby"
     #[serde(default = "default_mode")]
@@ -70,6 +136,30 @@ fn default_mode() -> String {
     "active".to_string()
 }
 
+/// Webhook event types handled by the chatbot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineEventKind {
+    Message,
+    Follow,
+    Unfollow,
+    Postback,
+    /// Any other event type (join, leave, beacon, ...), kept for logging
+    Other(String),
+}
+
+impl LineWebhookEvent {
+    /// Classify the event by its `type` field
+    pub fn kind(&self) -> LineEventKind {
+        match self.event_type.as_str() {
+            "message" => LineEventKind::Message,
+            "follow" => LineEventKind::Follow,
+            "unfollow" => LineEventKind::Unfollow,
+            "postback" => LineEventKind::Postback,
+            other => LineEventKind::Other(other.to_string()),
+        }
+    }
+}
+
 /// Delivery context for webhook events
 #[derive(Debug, Deserialize)]
 pub struct DeliveryContext {
@@ -79,9 +169,9 @@ pub struct DeliveryContext {
 }
 
 /// LINE event source
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct LineEventSource {
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default)]
     pub source_type: String,
     #[serde(rename = "userId")]
     pub user_id: Option<String>,
@@ -98,27 +188,92 @@ pub struct LineEventSource {
 pub struct LineEventMessage {
     #[serde(rename = "type")]
     pub message_type: String,
+    #[serde(default)]
     pub id: String,
     pub text: Option<String>,
     /// Quote token for quoting this message in a reply
     #[serde(rename = "quoteToken")]
     pub quote_token: Option<String>,
+    /// Sticker package ID (only for sticker messages)
+    #[serde(rename = "packageId")]
+    pub package_id: Option<String>,
+    /// Sticker ID (only for sticker messages)
+    #[serde(rename = "stickerId")]
+    pub sticker_id: Option<String>,
+    /// Location title (only for location messages)
+    pub title: Option<String>,
+    /// Location address (only for location messages)
+    pub address: Option<String>,
+    /// Latitude (only for location messages)
+    pub latitude: Option<f64>,
+    /// Longitude (only for location messages)
+    pub longitude: Option<f64>,
+}
+
+/// LINE postback payload
+/// See: https://developers.line.biz/en/reference/messaging-api/#postback-event
+#[derive(Debug, Deserialize)]
+pub struct LinePostback {
+    /// Postback data set on the action, in query-string form
+    pub data: String,
+    /// Values picked by datetime picker / rich menu switch actions
+    #[serde(default)]
+    pub params: Option<HashMap<String, String>>,
+}
+
+impl LinePostback {
+    /// Parse the postback data ("key=value&key=value") into a map
+    pub fn fields(&self) -> HashMap<String, String> {
+        parse_postback_data(&self.data)
+    }
+}
+
+/// Parse postback data in query-string form. Keys without a value map to ""
+pub fn parse_postback_data(data: &str) -> HashMap<String, String> {
+    data.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+            None => (pair.trim().to_string(), String::new()),
+        })
+        .collect()
 }
 
 /// Parsed command from user message
 #[derive(Debug, Clone)]
 pub enum ChatbotCommand {
-    /// Record a harvest: plot_name, weight_kg, ripe_percent
+    /// Record a harvest: plot_name, weight_kg, ripe_percent (None takes the
+    /// latest photo estimate, else 80%); confirm_duplicate records it even
+    /// when it repeats one of the plot's harvests that day
     Harvest {
         plot_name: String,
         weight_kg: Decimal,
-        ripe_percent: i32,
+        ripe_percent: Option<i32>,
+        confirm_duplicate: bool,
     },
     /// Start processing: lot_code, method
     Processing {
         lot_code: String,
         method: ProcessingMethod,
     },
+    /// Record a rain gauge reading: plot_name, rainfall_mm
+    Rain {
+        plot_name: String,
+        rainfall_mm: Decimal,
+    },
+    /// Attach a location check-in to today's harvest on the matched plot
+    AttachCheckIn { checkin_id: Uuid },
+    /// Record a weather snapshot at a location check-in
+    CheckInWeather { checkin_id: Uuid },
+    /// Acknowledge (mark read) a pushed LINE notification
+    Acknowledge { notification_id: Uuid },
+    /// List SOP checklists (no number) or start the numbered one, for a lot
+    Checklist {
+        number: Option<usize>,
+        lot_code: Option<String>,
+    },
+    /// Tick steps of the member's open SOP checklist
+    CheckSteps { positions: Vec<i32> },
     /// Help command
     Help,
     /// Unknown command
@@ -135,6 +290,114 @@ pub struct CommandResult {
     pub entity_id: Option<Uuid>,
 }
 
+/// Ripe % of a harvest command when neither given nor estimated from a photo
+pub const DEFAULT_RIPE_PERCENT: i32 = 80;
+
+/// Last word of a harvest command recording it despite a same-day duplicate
+pub const CONFIRM_WORDS: [&str; 2] = ["confirm", "ยืนยัน"];
+
+/// Reply to a harvest command repeating one of the plot's harvests that
+/// day, with the command to send to record it anyway
+pub fn duplicate_harvest_reply(
+    plot: &str,
+    typed_plot: &str,
+    weight_kg: Decimal,
+    ripe_percent: Option<i32>,
+    duplicate: &SameDayHarvest,
+) -> CommandResult {
+    let ripe = ripe_percent.map(|p| format!(" {}", p)).unwrap_or_default();
+    let (by, by_th) = match &duplicate.picker_name {
+        Some(picker) => (format!(" by {}", picker), format!(" โดย {}", picker)),
+        None => (String::new(), String::new()),
+    };
+    CommandResult {
+        success: false,
+        message: format!(
+            "⚠️ A harvest of {} kg{} was already recorded on {} today.\nIf this is another picking, send:\nharvest {} {}{} {}",
+            duplicate.cherry_weight_kg, by, plot, typed_plot, weight_kg, ripe, CONFIRM_WORDS[0]
+        ),
+        message_th: format!(
+            "⚠️ วันนี้บันทึกการเก็บเกี่ยว {} กก.{} ที่แปลง {} แล้ว\nหากเป็นการเก็บอีกรอบ ส่ง:\nเก็บ {} {}{} {}",
+            duplicate.cherry_weight_kg, by_th, plot, typed_plot, weight_kg, ripe, CONFIRM_WORDS[1]
+        ),
+        entity_id: Some(duplicate.id),
+    }
+}
+
+/// Ripeness of a harvest command: the photo estimate unless a ripe % is
+/// given, whose remainder is split between underripe and overripe
+pub fn harvest_ripeness(ripe_percent: Option<i32>, estimate: Option<&RipenessAssessment>) -> RipenessAssessment {
+    if let (None, Some(estimate)) = (ripe_percent, estimate) {
+        return estimate.clone();
+    }
+    let ripe_percent = ripe_percent.unwrap_or(DEFAULT_RIPE_PERCENT);
+    let remaining = 100 - ripe_percent;
+    let underripe = remaining / 2;
+    RipenessAssessment {
+        underripe_percent: underripe,
+        ripe_percent,
+        overripe_percent: remaining - underripe,
+    }
+}
+
+/// Maximum distance between a shared location and a plot for a check-in match
+pub const PLOT_CHECKIN_RADIUS_METERS: f64 = 500.0;
+
+/// Reply to a shared location: result text plus follow-up quick replies
+#[derive(Debug)]
+pub struct LocationCheckIn {
+    pub result: CommandResult,
+    pub quick_reply: Option<LineQuickReply>,
+}
+
+/// Plot with GPS coordinates, used for check-in matching
+#[derive(Debug, Clone, FromRow)]
+pub struct PlotLocation {
+    pub id: Uuid,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Stored check-in row
+#[derive(Debug, FromRow)]
+struct CheckInRow {
+    id: Uuid,
+    plot_id: Option<Uuid>,
+    plot_name: Option<String>,
+    latitude: Decimal,
+    longitude: Decimal,
+    harvest_id: Option<Uuid>,
+    weather_snapshot_id: Option<Uuid>,
+}
+
+/// Great-circle distance between two coordinates in meters
+pub fn haversine_distance_meters(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Find the nearest plot within `radius_meters`, returning it with its distance
+pub fn find_nearest_plot(
+    latitude: f64,
+    longitude: f64,
+    plots: &[PlotLocation],
+    radius_meters: f64,
+) -> Option<(&PlotLocation, f64)> {
+    plots
+        .iter()
+        .map(|plot| {
+            let distance = haversine_distance_meters(latitude, longitude, plot.latitude, plot.longitude);
+            (plot, distance)
+        })
+        .filter(|(_, distance)| *distance <= radius_meters)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
 /// LINE reply message request
 #[derive(Debug, Serialize)]
 struct LineReplyRequest {
@@ -181,28 +444,137 @@ impl LineChatbotService {
                 }
             }
             
-            if event.event_type == "message" {
-                if let Some(message) = &event.message {
-                    if message.message_type == "text" {
-                        if let (Some(text), Some(user_id)) = (&message.text, &event.source.user_id) {
-                            let result = self.handle_text_message(user_id, text).await;
-                            
-                            // Reply to user
-                            if let Some(reply_token) = &event.reply_token {
-                                let reply_text = match &result {
-                                    Ok(r) => format!("{}\n{}", r.message, r.message_th),
-                                    Err(e) => format!("Error: {}", e),
-                                };
-                                let _ = self.reply_message(reply_token, &reply_text).await;
-                            }
-                        }
+            match event.kind() {
+                LineEventKind::Message => self.handle_message_event(&event).await,
+                LineEventKind::Postback => {
+                    if let (Some(postback), Some(user_id)) = (&event.postback, &event.source.user_id) {
+                        let result = self.handle_postback(user_id, postback).await;
+                        self.reply_with_result(&event, &result).await;
+                    }
+                }
+                LineEventKind::Follow => {
+                    tracing::info!("LINE user followed: {:?}", event.source.user_id);
+                    if let Some(reply_token) = &event.reply_token {
+                        let welcome = format!("{}\n\n{}", self.get_help_message_th(), self.get_help_message_en());
+                        let _ = self.reply_message(reply_token, &welcome).await;
                     }
                 }
+                LineEventKind::Unfollow => {
+                    tracing::info!("LINE user unfollowed: {:?}", event.source.user_id);
+                }
+                LineEventKind::Other(event_type) => {
+                    tracing::warn!("Ignoring unsupported LINE event type: {}", event_type);
+                }
             }
         }
         Ok(())
     }
 
+    /// Handle a message event (text, sticker, location, media)
+    async fn handle_message_event(&self, event: &LineWebhookEvent) {
+        let Some(message) = &event.message else {
+            tracing::warn!("LINE message event without message object");
+            return;
+        };
+
+        match message.message_type.as_str() {
+            "text" => {
+                if let (Some(text), Some(user_id)) = (&message.text, &event.source.user_id) {
+                    let result = self.handle_text_message(user_id, text).await;
+                    self.reply_with_result(event, &result).await;
+                }
+            }
+            "image" => {
+                if let Some(user_id) = &event.source.user_id {
+                    let result = self.handle_image_message(user_id, &message.id).await;
+                    self.reply_with_result(event, &result).await;
+                }
+            }
+            "sticker" => {
+                tracing::debug!(
+                    "Ignoring LINE sticker {:?}/{:?}",
+                    message.package_id,
+                    message.sticker_id
+                );
+            }
+            "location" => {
+                let (Some(latitude), Some(longitude), Some(user_id)) =
+                    (message.latitude, message.longitude, &event.source.user_id)
+                else {
+                    tracing::warn!("LINE location message without coordinates or user");
+                    return;
+                };
+                let location_name = message.title.as_deref().or(message.address.as_deref());
+                match self.handle_location_message(user_id, latitude, longitude, location_name).await {
+                    Ok(checkin) => {
+                        if let Some(reply_token) = &event.reply_token {
+                            let reply = LineMessage::Text {
+                                text: format!("{}\n{}", checkin.result.message, checkin.result.message_th),
+                                quick_reply: checkin.quick_reply,
+                            };
+                            let _ = self.reply_messages(reply_token, vec![reply]).await;
+                        }
+                    }
+                    Err(e) => self.reply_with_result(event, &Err(e)).await,
+                }
+            }
+            other => {
+                tracing::warn!("Ignoring unsupported LINE message type: {}", other);
+            }
+        }
+    }
+
+    /// Reply to the event with a command result, if it can be replied to
+    async fn reply_with_result(&self, event: &LineWebhookEvent, result: &AppResult<CommandResult>) {
+        if let Some(reply_token) = &event.reply_token {
+            let reply_text = match result {
+                Ok(r) => format!("{}\n{}", r.message, r.message_th),
+                Err(e) => format!("Error: {}", e),
+            };
+            let _ = self.reply_message(reply_token, &reply_text).await;
+        }
+    }
+
+    /// Handle a postback from a LINE action (rich menu, quick reply, template)
+    pub async fn handle_postback(
+        &self,
+        line_user_id: &str,
+        postback: &LinePostback,
+    ) -> AppResult<CommandResult> {
+        tracing::debug!("LINE postback {} params {:?}", postback.data, postback.params);
+        let user_info = self.get_user_from_line_id(line_user_id).await?;
+        let command = self.parse_postback(postback);
+        self.execute_command(&user_info, command).await
+    }
+
+    /// Parse postback data into a command
+    pub fn parse_postback(&self, postback: &LinePostback) -> ChatbotCommand {
+        let fields = postback.fields();
+        let get = |key: &str| fields.get(key).map(String::as_str).unwrap_or("");
+
+        match get("action") {
+            "harvest" => {
+                let confirm = if get("confirm") == "1" { CONFIRM_WORDS[0] } else { "" };
+                let args: Vec<&str> = [get("plot"), get("kg"), get("ripe"), confirm]
+                    .into_iter()
+                    .filter(|arg| !arg.is_empty())
+                    .collect();
+                self.parse_harvest_command(&args)
+            }
+            "process" => self.parse_processing_command(&[get("lot"), get("method")]),
+            "checkin_harvest" | "checkin_weather" => match Uuid::parse_str(get("checkin")) {
+                Ok(checkin_id) if get("action") == "checkin_harvest" => ChatbotCommand::AttachCheckIn { checkin_id },
+                Ok(checkin_id) => ChatbotCommand::CheckInWeather { checkin_id },
+                Err(_) => ChatbotCommand::Unknown(postback.data.clone()),
+            },
+            "ack" => match Uuid::parse_str(get("notification")) {
+                Ok(notification_id) => ChatbotCommand::Acknowledge { notification_id },
+                Err(_) => ChatbotCommand::Unknown(postback.data.clone()),
+            },
+            "help" => ChatbotCommand::Help,
+            _ => ChatbotCommand::Unknown(postback.data.clone()),
+        }
+    }
 
     /// Handle a text message from LINE
     pub async fn handle_text_message(
@@ -216,16 +588,23 @@ impl LineChatbotService {
         // Parse the command
         let command = self.parse_command(text);
         
-        // Execute the command
+        self.execute_command(&user_info, command).await
+    }
+
+    /// Execute a parsed command on behalf of a LINE user
+    async fn execute_command(
+        &self,
+        user_info: &UserInfo,
+        command: ChatbotCommand,
+    ) -> AppResult<CommandResult> {
         match command {
-            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent } => {
+            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent, confirm_duplicate } => {
                 self.execute_harvest_command(
-                    user_info.user_id,
-                    user_info.business_id,
-                    &user_info.business_code,
+                    user_info,
                     &plot_name,
                     weight_kg,
                     ripe_percent,
+                    confirm_duplicate,
                 ).await
             }
             ChatbotCommand::Processing { lot_code, method } => {
@@ -236,6 +615,38 @@ impl LineChatbotService {
                     method,
                 ).await
             }
+            ChatbotCommand::Rain { plot_name, rainfall_mm } => {
+                self.execute_rain_command(
+                    user_info.user_id,
+                    user_info.business_id,
+                    &plot_name,
+                    rainfall_mm,
+                ).await
+            }
+            ChatbotCommand::AttachCheckIn { checkin_id } => {
+                self.attach_checkin_to_harvest(user_info.business_id, checkin_id).await
+            }
+            ChatbotCommand::CheckInWeather { checkin_id } => {
+                self.record_checkin_weather(user_info.business_id, checkin_id).await
+            }
+            ChatbotCommand::Acknowledge { notification_id } => {
+                let notification_service = NotificationService::new(self.db.clone());
+                let entry = notification_service
+                    .acknowledge_line_notification(user_info.user_id, notification_id)
+                    .await?;
+                Ok(CommandResult {
+                    success: true,
+                    message: format!("👍 Marked as read: {}", entry.title),
+                    message_th: format!("👍 รับทราบแล้ว: {}", entry.title_th.as_deref().unwrap_or(&entry.title)),
+                    entity_id: Some(entry.id),
+                })
+            }
+            ChatbotCommand::Checklist { number, lot_code } => {
+                self.execute_checklist_command(user_info, number, lot_code.as_deref()).await
+            }
+            ChatbotCommand::CheckSteps { positions } => {
+                self.execute_check_steps_command(user_info, positions).await
+            }
             ChatbotCommand::Help => {
                 Ok(CommandResult {
                     success: true,
@@ -269,10 +680,16 @@ impl LineChatbotService {
             // English commands
             "harvest" | "h" => self.parse_harvest_command(&parts[1..]),
             "process" | "p" => self.parse_processing_command(&parts[1..]),
+            "rain" | "r" => self.parse_rain_command(&parts[1..]),
+            "sop" | "checklist" => self.parse_checklist_command(&parts[1..]),
+            "done" => self.parse_done_command(&parts[1..]),
             "help" | "?" => ChatbotCommand::Help,
             // Thai commands
             "เก็บ" | "เก็บเกี่ยว" => self.parse_harvest_command(&parts[1..]),
             "แปรรูป" | "โปรเซส" => self.parse_processing_command(&parts[1..]),
+            "ฝน" => self.parse_rain_command(&parts[1..]),
+            "ขั้นตอน" => self.parse_checklist_command(&parts[1..]),
+            "เสร็จ" => self.parse_done_command(&parts[1..]),
             "ช่วยเหลือ" | "วิธีใช้" => ChatbotCommand::Help,
             _ => ChatbotCommand::Unknown(text),
         }
@@ -280,8 +697,12 @@ impl LineChatbotService {
 
     /// Parse harvest command arguments
     fn parse_harvest_command(&self, args: &[&str]) -> ChatbotCommand {
-        // Format: harvest [plot_name] [weight_kg] [ripe%]
+        // Format: harvest [plot_name] [weight_kg] [ripe%] [confirm]
         // Example: harvest plot1 50 85
+        let (args, confirm_duplicate) = match args.split_last() {
+            Some((last, rest)) if CONFIRM_WORDS.contains(last) => (rest, true),
+            _ => (args, false),
+        };
         if args.len() < 2 {
             return ChatbotCommand::Unknown(
                 "harvest command requires: plot_name weight_kg [ripe%]".to_string()
@@ -297,22 +718,23 @@ impl LineChatbotService {
             ),
         };
         
-        // Default ripe percent to 80 if not provided
+        // Without a ripe percent the latest photo estimate or the default is used
         let ripe_percent = if args.len() > 2 {
             match args[2].parse::<i32>() {
-                Ok(p) if (0..=100).contains(&p) => p,
+                Ok(p) if (0..=100).contains(&p) => Some(p),
                 _ => return ChatbotCommand::Unknown(
                     format!("Invalid ripe percent: {}", args[2])
                 ),
             }
         } else {
-            80 // Default
+            None
         };
         
         ChatbotCommand::Harvest {
             plot_name,
             weight_kg,
             ripe_percent,
+            confirm_duplicate,
         }
     }
 
@@ -329,25 +751,88 @@ impl LineChatbotService {
         
         let lot_code = args[0].to_uppercase();
         
-        let method = match args[1].to_lowercase().as_str() {
-            "natural" | "ธรรมชาติ" => ProcessingMethod::Natural,
-            "washed" | "ล้าง" => ProcessingMethod::Washed,
-            "honey" | "ฮันนี่" => ProcessingMethod::Honey { mucilage_percent: 50 },
-            "wet-hulled" | "wethulled" | "กะลาเปียก" => ProcessingMethod::WetHulled,
-            "anaerobic" | "ไร้อากาศ" => ProcessingMethod::Anaerobic { hours: 72 },
-            _ => return ChatbotCommand::Unknown(
-                format!("Unknown processing method: {}. Use: natural, washed, honey, wet-hulled, anaerobic", args[1])
+        // The method may carry its parameter, e.g. "honey 30%"
+        let method_text = args[1..].join(" ");
+        let method = match ProcessingMethod::parse(&method_text) {
+            Some(method) => method,
+            None => return ChatbotCommand::Unknown(
+                format!("Unknown processing method: {}. Use: natural, washed, honey [mucilage %], wet-hulled, anaerobic [hours]", method_text)
             ),
         };
         
         ChatbotCommand::Processing { lot_code, method }
     }
 
+    /// Parse rain gauge command arguments
+    fn parse_rain_command(&self, args: &[&str]) -> ChatbotCommand {
+        // Format: rain [plot_name] [mm]
+        // Example: rain plot1 12.5
+        if args.len() < 2 {
+            return ChatbotCommand::Unknown(
+                "rain command requires: plot_name mm".to_string()
+            );
+        }
+
+        let rainfall_mm = match Decimal::from_str(args[1]) {
+            Ok(mm) if mm >= Decimal::ZERO && mm <= Decimal::from(MAX_RAINFALL_MM) => mm,
+            _ => return ChatbotCommand::Unknown(
+                format!("Invalid rainfall: {}", args[1])
+            ),
+        };
+
+        ChatbotCommand::Rain {
+            plot_name: args[0].to_string(),
+            rainfall_mm,
+        }
+    }
+
+    /// Parse SOP checklist command arguments
+    fn parse_checklist_command(&self, args: &[&str]) -> ChatbotCommand {
+        // Format: sop [number] [lot_code]
+        // Example: sop 2 CQM-2024-DOI-001
+        let number = match args.first() {
+            None => None,
+            Some(arg) => match arg.parse::<usize>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => return ChatbotCommand::Unknown(
+                    format!("Invalid checklist number: {}", arg)
+                ),
+            },
+        };
+
+        ChatbotCommand::Checklist {
+            number,
+            lot_code: args.get(1).map(|code| code.to_uppercase()),
+        }
+    }
+
+    /// Parse the step numbers of a done command
+    fn parse_done_command(&self, args: &[&str]) -> ChatbotCommand {
+        // Format: done [step] [step] ...
+        // Example: done 1 2 3 (or done 1,2,3)
+        let mut positions = Vec::new();
+        for arg in args.iter().flat_map(|arg| arg.split(',')).filter(|arg| !arg.is_empty()) {
+            match arg.parse::<i32>() {
+                Ok(p) if p > 0 => positions.push(p),
+                _ => return ChatbotCommand::Unknown(
+                    format!("Invalid step number: {}", arg)
+                ),
+            }
+        }
+        if positions.is_empty() {
+            return ChatbotCommand::Unknown(
+                "done command requires: step numbers".to_string()
+            );
+        }
+
+        ChatbotCommand::CheckSteps { positions }
+    }
+
     /// Get user info from LINE user ID
     async fn get_user_from_line_id(&self, line_user_id: &str) -> AppResult<UserInfo> {
         let row = sqlx::query_as::<_, (Uuid, Uuid, String)>(
             r#"
-            SELECT lc.user_id, u.business_id, b.code
+            SELECT lc.user_id, u.business_id, b.business_code
             FROM line_connections lc
             JOIN users u ON u.id = lc.user_id
             JOIN businesses b ON b.id = u.business_id
@@ -358,8 +843,8 @@ impl LineChatbotService {
         .fetch_optional(&self.db)
         .await?
         .ok_or_else(|| AppError::Unauthorized {
-            message: "LINE account not linked to any user".to_string(),
-            message_th: "บัญชี LINE ไม่ได้เชื่อมต่อกับผู้ใช้ใดๆ".to_string(),
+            message: "LINE account not linked to any user; sign up or link it with LINE Login".to_string(),
+            message_th: "บัญชี LINE ไม่ได้เชื่อมต่อกับผู้ใช้ใดๆ สมัครหรือเชื่อมต่อได้ด้วย LINE Login".to_string(),
         })?;
         
         Ok(UserInfo {
@@ -373,13 +858,14 @@ impl LineChatbotService {
     /// Execute harvest command
     async fn execute_harvest_command(
         &self,
-        user_id: Uuid,
-        business_id: Uuid,
-        business_code: &str,
+        user_info: &UserInfo,
         plot_name: &str,
         weight_kg: Decimal,
-        ripe_percent: i32,
+        ripe_percent: Option<i32>,
+        confirm_duplicate: bool,
     ) -> AppResult<CommandResult> {
+        let UserInfo { user_id, business_id, ref business_code } = *user_info;
+
         // Find plot by name
         let plot = sqlx::query_as::<_, (Uuid, String)>(
             "SELECT id, name FROM plots WHERE business_id = $1 AND LOWER(name) LIKE $2 LIMIT 1"
@@ -390,40 +876,56 @@ impl LineChatbotService {
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Plot '{}'", plot_name)))?;
         
-        // Calculate ripeness (assume remaining is split between underripe and overripe)
-        let remaining = 100 - ripe_percent;
-        let underripe = remaining / 2;
-        let overripe = remaining - underripe;
+        // A recent basket photo fills in the ripeness unless a ripe % is given
+        let estimate = RipenessEstimateService::new(self.db.clone())
+            .latest_unused(business_id, user_id, Utc::now())
+            .await?;
+        let ripeness = harvest_ripeness(ripe_percent, estimate.as_ref().map(|e| e.assessment()).as_ref());
+        let (source, source_th) = match (&estimate, ripe_percent) {
+            (Some(_), None) => (" (from photo)", " (จากรูปถ่าย)"),
+            (Some(_), Some(_)) => (" (photo estimate overridden)", " (แก้ไขจากผลประเมินรูปถ่าย)"),
+            (None, _) => ("", ""),
+        };
         
         // Create harvest input
         let input = RecordHarvestInput {
             plot_id: plot.0,
             harvest_date: Local::now().date_naive(),
             picker_name: Some("LINE Quick Entry".to_string()),
-            cherry_weight_kg: weight_kg,
-            underripe_percent: underripe,
-            ripe_percent,
-            overripe_percent: overripe,
+            crew_name: None,
+            cherry_weight: weight_kg,
+            cherry_weight_unit: None,
+            underripe_percent: ripeness.underripe_percent,
+            ripe_percent: ripeness.ripe_percent,
+            overripe_percent: ripeness.overripe_percent,
+            ripeness_estimate_id: estimate.as_ref().map(|e| e.id),
             weather_snapshot: None,
             notes: Some("Recorded via LINE chatbot".to_string()),
             notes_th: Some("บันทึกผ่าน LINE chatbot".to_string()),
             lot_id: None,
             lot_name: None,
+            confirm_duplicate,
         };
         
-        // Record harvest
+        // Record harvest; a resent picking is answered with how to confirm it
         let harvest_service = HarvestService::new(self.db.clone());
-        let harvest = harvest_service.record_harvest(business_id, business_code, input).await?;
+        let harvest = match harvest_service.record_harvest(business_id, business_code, input).await {
+            Ok(harvest) => harvest,
+            Err(AppError::DuplicateHarvest { duplicate }) => {
+                return Ok(duplicate_harvest_reply(&plot.1, plot_name, weight_kg, ripe_percent, &duplicate));
+            }
+            Err(e) => return Err(e),
+        };
         
         Ok(CommandResult {
             success: true,
             message: format!(
-                "✅ Harvest recorded!\nPlot: {}\nWeight: {} kg\nRipeness: {}% ripe\nLot: {}",
-                plot.1, weight_kg, ripe_percent, harvest.lot_traceability_code
+                "✅ Harvest recorded!\nPlot: {}\nWeight: {} kg\nRipeness: {}% ripe{}\nLot: {}",
+                plot.1, weight_kg, ripeness.ripe_percent, source, harvest.lot_traceability_code
             ),
             message_th: format!(
-                "✅ บันทึกการเก็บเกี่ยวแล้ว!\nแปลง: {}\nน้ำหนัก: {} กก.\nความสุก: {}%\nล็อต: {}",
-                plot.1, weight_kg, ripe_percent, harvest.lot_traceability_code
+                "✅ บันทึกการเก็บเกี่ยวแล้ว!\nแปลง: {}\nน้ำหนัก: {} กก.\nความสุก: {}%{}\nล็อต: {}",
+                plot.1, weight_kg, ripeness.ripe_percent, source_th, harvest.lot_traceability_code
             ),
             entity_id: Some(harvest.id),
         })
@@ -486,14 +988,460 @@ impl LineChatbotService {
     }
 
 
+    /// Execute rain gauge command
+    async fn execute_rain_command(
+        &self,
+        user_id: Uuid,
+        business_id: Uuid,
+        plot_name: &str,
+        rainfall_mm: Decimal,
+    ) -> AppResult<CommandResult> {
+        // Find plot by name
+        let plot = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, name FROM plots WHERE business_id = $1 AND LOWER(name) LIKE $2 LIMIT 1"
+        )
+        .bind(business_id)
+        .bind(format!("%{}%", plot_name.to_lowercase()))
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Plot '{}'", plot_name)))?;
+
+        let input = RecordRainfallInput {
+            plot_id: plot.0,
+            observed_on: Some(Local::now().date_naive()),
+            rainfall_mm,
+            notes: Some("Recorded via LINE chatbot".to_string()),
+        };
+
+        let weather_service = WeatherService::new(self.db.clone());
+        let observation = weather_service
+            .record_rainfall(business_id, Some(user_id), "line", input)
+            .await?;
+
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "🌧️ Rainfall recorded!\nPlot: {}\nRain: {} mm\nDate: {}",
+                plot.1, observation.rainfall_mm, observation.observed_on
+            ),
+            message_th: format!(
+                "🌧️ บันทึกปริมาณฝนแล้ว!\nแปลง: {}\nฝน: {} มม.\nวันที่: {}",
+                plot.1, observation.rainfall_mm, observation.observed_on
+            ),
+            entity_id: Some(observation.id),
+        })
+    }
+
+    /// List the business's SOP checklists, or start (or carry on with) the
+    /// numbered one, for a lot when a lot code is given
+    async fn execute_checklist_command(
+        &self,
+        user_info: &UserInfo,
+        number: Option<usize>,
+        lot_code: Option<&str>,
+    ) -> AppResult<CommandResult> {
+        let checklist_service = SopChecklistService::new(self.db.clone());
+        let checklists = checklist_service
+            .list_checklists(user_info.business_id, false)
+            .await?;
+
+        let Some(number) = number else {
+            if checklists.is_empty() {
+                return Ok(CommandResult {
+                    success: true,
+                    message: "No SOP checklists yet.".to_string(),
+                    message_th: "ยังไม่มีรายการตรวจสอบ SOP".to_string(),
+                    entity_id: None,
+                });
+            }
+            let names = |thai: bool| {
+                checklists
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{}. {}", i + 1, if thai { &c.name_th } else { &c.name }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            return Ok(CommandResult {
+                success: true,
+                message: format!("📋 SOP checklists:\n{}\n\nStart one with: sop [number] [lot_code]", names(false)),
+                message_th: format!("📋 รายการตรวจสอบ SOP:\n{}\n\nเริ่มด้วย: ขั้นตอน [หมายเลข] [รหัสล็อต]", names(true)),
+                entity_id: None,
+            });
+        };
+
+        let checklist = checklists
+            .get(number - 1)
+            .ok_or_else(|| AppError::NotFound(format!("Checklist {}", number)))?;
+        let lot_id = match lot_code {
+            Some(code) => Some(
+                sqlx::query_scalar::<_, Uuid>(
+                    "SELECT id FROM lots WHERE business_id = $1 AND UPPER(traceability_code) = $2"
+                )
+                .bind(user_info.business_id)
+                .bind(code.to_uppercase())
+                .fetch_optional(&self.db)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Lot '{}'", code)))?,
+            ),
+            None => None,
+        };
+
+        let execution = checklist_service
+            .start_execution(
+                user_info.business_id,
+                user_info.user_id,
+                checklist.id,
+                StartExecutionInput { lot_id, ..Default::default() },
+                "line",
+            )
+            .await?;
+
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "📋 {}\n{}\n\nReply 'done [step numbers]' as you finish steps",
+                checklist.name,
+                step_lines(&execution.steps, false)
+            ),
+            message_th: format!(
+                "📋 {}\n{}\n\nตอบ 'เสร็จ [หมายเลขขั้นตอน]' เมื่อทำแต่ละขั้นตอนเสร็จ",
+                checklist.name_th,
+                step_lines(&execution.steps, true)
+            ),
+            entity_id: Some(execution.summary.id),
+        })
+    }
+
+    /// Tick steps of the member's open SOP checklist
+    async fn execute_check_steps_command(
+        &self,
+        user_info: &UserInfo,
+        positions: Vec<i32>,
+    ) -> AppResult<CommandResult> {
+        let checklist_service = SopChecklistService::new(self.db.clone());
+        let Some(execution_id) = checklist_service
+            .latest_open_execution(user_info.business_id, user_info.user_id)
+            .await?
+        else {
+            return Ok(CommandResult {
+                success: false,
+                message: "No open checklist. Start one with: sop [number]".to_string(),
+                message_th: "ไม่มีรายการตรวจสอบที่เปิดอยู่ เริ่มด้วย: ขั้นตอน [หมายเลข]".to_string(),
+                entity_id: None,
+            });
+        };
+
+        let execution = checklist_service
+            .check_items(
+                user_info.business_id,
+                user_info.user_id,
+                execution_id,
+                CheckItemsInput { positions, ..Default::default() },
+                "line",
+            )
+            .await?;
+
+        let (status, status_th) = if execution.summary.completed_at.is_some() {
+            ("✅ Checklist complete!".to_string(), "✅ ทำครบทุกขั้นตอนแล้ว!".to_string())
+        } else {
+            (
+                format!("{} required step(s) to go", execution.required_remaining),
+                format!("เหลืออีก {} ขั้นตอนที่ต้องทำ", execution.required_remaining),
+            )
+        };
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "📋 {}\n{}\n\n{}",
+                execution.summary.checklist_name,
+                step_lines(&execution.steps, false),
+                status
+            ),
+            message_th: format!(
+                "📋 {}\n{}\n\n{}",
+                execution.summary.checklist_name_th,
+                step_lines(&execution.steps, true),
+                status_th
+            ),
+            entity_id: Some(execution.summary.id),
+        })
+    }
+
+    /// Estimate the ripeness of a cherry basket photo for the next harvest
+    pub async fn handle_image_message(&self, line_user_id: &str, message_id: &str) -> AppResult<CommandResult> {
+        let user_info = self.get_user_from_line_id(line_user_id).await?;
+        let line_client = self
+            .line_client
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("LINE_CHANNEL_ACCESS_TOKEN not set".to_string()))?;
+        let image = line_client
+            .get_message_content(message_id)
+            .await
+            .map_err(AppError::ExternalService)?;
+
+        let estimate = RipenessEstimateService::new(self.db.clone())
+            .estimate(
+                user_info.business_id,
+                Some(user_info.user_id),
+                "line",
+                EstimateRipenessInput {
+                    image_base64: BASE64.encode(image),
+                },
+            )
+            .await?;
+
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "🍒 Ripeness from photo: {}% ripe, {}% underripe, {}% overripe\nSend 'harvest [plot] [kg]' within {} minutes to record it, or add a ripe % to override.",
+                estimate.ripe_percent, estimate.underripe_percent, estimate.overripe_percent, RIPENESS_ESTIMATE_VALID_MINUTES
+            ),
+            message_th: format!(
+                "🍒 ความสุกจากรูปถ่าย: สุก {}% ยังไม่สุก {}% สุกเกิน {}%\nพิมพ์ 'เก็บ [แปลง] [กก.]' ภายใน {} นาทีเพื่อบันทึก หรือใส่ %สุก เพื่อแก้ไข",
+                estimate.ripe_percent, estimate.underripe_percent, estimate.overripe_percent, RIPENESS_ESTIMATE_VALID_MINUTES
+            ),
+            entity_id: Some(estimate.id),
+        })
+    }
+
+    /// Match a shared location to the nearest plot and record a check-in
+    pub async fn handle_location_message(
+        &self,
+        line_user_id: &str,
+        latitude: f64,
+        longitude: f64,
+        location_name: Option<&str>,
+    ) -> AppResult<LocationCheckIn> {
+        let user_info = self.get_user_from_line_id(line_user_id).await?;
+
+        let plots = sqlx::query_as::<_, PlotLocation>(
+            r#"
+            SELECT id, name, latitude::float8 AS latitude, longitude::float8 AS longitude
+            FROM plots
+            WHERE business_id = $1 AND latitude IS NOT NULL AND longitude IS NOT NULL
+            "#,
+        )
+        .bind(user_info.business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let nearest = find_nearest_plot(latitude, longitude, &plots, PLOT_CHECKIN_RADIUS_METERS);
+        let plot_id = nearest.map(|(plot, _)| plot.id);
+        let distance_meters = nearest.map(|(_, distance)| distance.round() as i32);
+
+        let checkin_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO plot_checkins (business_id, user_id, plot_id, latitude, longitude,
+                                       location_name, distance_meters)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(user_info.business_id)
+        .bind(user_info.user_id)
+        .bind(plot_id)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(location_name)
+        .bind(distance_meters)
+        .fetch_one(&self.db)
+        .await?;
+
+        let mut items = Vec::new();
+        let result = match nearest {
+            Some((plot, distance)) => {
+                if self.find_todays_harvest(user_info.business_id, plot.id).await?.is_some() {
+                    items.push(LineQuickReplyItem::postback(
+                        "แนบกับการเก็บเกี่ยว",
+                        format!("action=checkin_harvest&checkin={}", checkin_id),
+                        "Attach to today's harvest / แนบกับการเก็บเกี่ยววันนี้",
+                    ));
+                }
+                CommandResult {
+                    success: true,
+                    message: format!("📍 Checked in at {} ({:.0} m away)", plot.name, distance),
+                    message_th: format!("📍 เช็คอินที่แปลง {} (ห่าง {:.0} ม.)", plot.name, distance),
+                    entity_id: Some(checkin_id),
+                }
+            }
+            None => CommandResult {
+                success: true,
+                message: format!(
+                    "📍 Location saved. No plot within {:.0} m.",
+                    PLOT_CHECKIN_RADIUS_METERS
+                ),
+                message_th: format!(
+                    "📍 บันทึกตำแหน่งแล้ว ไม่พบแปลงในระยะ {:.0} ม.",
+                    PLOT_CHECKIN_RADIUS_METERS
+                ),
+                entity_id: Some(checkin_id),
+            },
+        };
+
+        items.push(LineQuickReplyItem::postback(
+            "บันทึกสภาพอากาศ",
+            format!("action=checkin_weather&checkin={}", checkin_id),
+            "Record weather here / บันทึกสภาพอากาศที่นี่",
+        ));
+
+        Ok(LocationCheckIn {
+            result,
+            quick_reply: Some(LineQuickReply { items }),
+        })
+    }
+
+    /// Latest harvest recorded today on a plot: (harvest_id, cherry_weight_kg)
+    async fn find_todays_harvest(
+        &self,
+        business_id: Uuid,
+        plot_id: Uuid,
+    ) -> AppResult<Option<(Uuid, Decimal)>> {
+        let harvest = sqlx::query_as::<_, (Uuid, Decimal)>(
+            r#"
+            SELECT id, cherry_weight_kg
+            FROM harvests
+            WHERE business_id = $1 AND plot_id = $2 AND harvest_date = $3
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(business_id)
+        .bind(plot_id)
+        .bind(Local::now().date_naive())
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(harvest)
+    }
+
+    /// Load a check-in belonging to the business
+    async fn get_checkin(&self, business_id: Uuid, checkin_id: Uuid) -> AppResult<CheckInRow> {
+        sqlx::query_as::<_, CheckInRow>(
+            r#"
+            SELECT c.id, c.plot_id, p.name AS plot_name, c.latitude, c.longitude,
+                   c.harvest_id, c.weather_snapshot_id
+            FROM plot_checkins c
+            LEFT JOIN plots p ON p.id = c.plot_id
+            WHERE c.id = $1 AND c.business_id = $2
+            "#,
+        )
+        .bind(checkin_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Check-in".to_string()))
+    }
+
+    /// Attach a check-in to today's harvest on its matched plot
+    async fn attach_checkin_to_harvest(
+        &self,
+        business_id: Uuid,
+        checkin_id: Uuid,
+    ) -> AppResult<CommandResult> {
+        let checkin = self.get_checkin(business_id, checkin_id).await?;
+
+        let plot_id = checkin.plot_id.ok_or_else(|| AppError::Validation {
+            field: "plot_id".to_string(),
+            message: "Check-in is not near any plot".to_string(),
+            message_th: "ตำแหน่งนี้ไม่อยู่ใกล้แปลงใด".to_string(),
+        })?;
+        let plot_name = checkin.plot_name.unwrap_or_default();
+
+        let (harvest_id, cherry_weight_kg) = self
+            .find_todays_harvest(business_id, plot_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Today's harvest on plot '{}'", plot_name)))?;
+
+        sqlx::query("UPDATE plot_checkins SET harvest_id = $1, updated_at = NOW() WHERE id = $2")
+            .bind(harvest_id)
+            .bind(checkin.id)
+            .execute(&self.db)
+            .await?;
+
+        if let Some(snapshot_id) = checkin.weather_snapshot_id {
+            WeatherService::new(self.db.clone())
+                .link_to_harvest(business_id, harvest_id, snapshot_id)
+                .await?;
+        }
+
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "✅ Location attached to today's harvest\nPlot: {}\nWeight: {} kg",
+                plot_name, cherry_weight_kg
+            ),
+            message_th: format!(
+                "✅ แนบตำแหน่งกับการเก็บเกี่ยววันนี้แล้ว\nแปลง: {}\nน้ำหนัก: {} กก.",
+                plot_name, cherry_weight_kg
+            ),
+            entity_id: Some(harvest_id),
+        })
+    }
+
+    /// Fetch current weather at a check-in location and store a snapshot
+    async fn record_checkin_weather(
+        &self,
+        business_id: Uuid,
+        checkin_id: Uuid,
+    ) -> AppResult<CommandResult> {
+        let checkin = self.get_checkin(business_id, checkin_id).await?;
+
+        let api_key = std::env::var("CQM_WEATHER_API_KEY").unwrap_or_default();
+        if api_key.is_empty() {
+            return Err(AppError::Configuration("CQM_WEATHER_API_KEY not set".to_string()));
+        }
+
+        let weather_service = WeatherService::with_client(self.db.clone(), api_key);
+        let snapshot = weather_service
+            .fetch_and_store_current(business_id, checkin.latitude, checkin.longitude)
+            .await?;
+
+        sqlx::query("UPDATE plot_checkins SET weather_snapshot_id = $1, updated_at = NOW() WHERE id = $2")
+            .bind(snapshot.id)
+            .bind(checkin.id)
+            .execute(&self.db)
+            .await?;
+
+        if let Some(harvest_id) = checkin.harvest_id {
+            weather_service
+                .link_to_harvest(business_id, harvest_id, snapshot.id)
+                .await?;
+        }
+
+        let description = snapshot.weather_description.clone().unwrap_or_default();
+        let humidity = snapshot
+            .humidity_percent
+            .map(|h| format!("{}%", h))
+            .unwrap_or_else(|| "-".to_string());
+
+        Ok(CommandResult {
+            success: true,
+            message: format!(
+                "🌤️ Weather recorded\n{}°C {}\nHumidity: {}",
+                snapshot.temperature_celsius, description, humidity
+            ),
+            message_th: format!(
+                "🌤️ บันทึกสภาพอากาศแล้ว\n{}°C {}\nความชื้น: {}",
+                snapshot.temperature_celsius, description, humidity
+            ),
+            entity_id: Some(snapshot.id),
+        })
+    }
+
     /// Reply to a LINE message
     async fn reply_message(&self, reply_token: &str, text: &str) -> AppResult<()> {
+        self.reply_messages(reply_token, vec![LineMessage::text(text)]).await
+    }
+
+    /// Reply to a LINE message with one or more message objects
+    async fn reply_messages(&self, reply_token: &str, messages: Vec<LineMessage>) -> AppResult<()> {
         let channel_access_token = std::env::var("LINE_CHANNEL_ACCESS_TOKEN")
             .map_err(|_| AppError::Configuration("LINE_CHANNEL_ACCESS_TOKEN not set".to_string()))?;
         
         let request = LineReplyRequest {
             reply_token: reply_token.to_string(),
-            messages: vec![LineMessage::Text { text: text.to_string() }],
+            messages,
         };
         
         let http_client = reqwest::Client::new();
@@ -521,12 +1469,23 @@ impl LineChatbotService {
 🌿 HARVEST
   harvest [plot] [kg] [ripe%]
   Example: harvest plot1 50 85
+  Send a basket photo first to fill in the ripeness
+  Add "confirm" to record a second picking of the same weight today
 
 ⚙️ PROCESSING
   process [lot_code] [method]
   Methods: natural, washed, honey, wet-hulled, anaerobic
   Example: process CQM-2024-DOI-001 washed
 
+🌧️ RAIN GAUGE
+  rain [plot] [mm]
+  Example: rain plot1 12.5
+
+📋 SOP CHECKLISTS
+  sop (list), sop [number] [lot_code]
+  done [step numbers]
+  Example: sop 1, then done 1 2
+
 ❓ HELP
   help or ?"#.to_string()
     }
@@ -538,12 +1497,23 @@ impl LineChatbotService {
 🌿 เก็บเกี่ยว
   เก็บ [แปลง] [กก.] [%สุก]
   ตัวอย่าง: เก็บ แปลง1 50 85
+  ส่งรูปตะกร้าเชอร์รี่ก่อนเพื่อประเมินความสุก
+  เติม "ยืนยัน" เพื่อบันทึกการเก็บรอบที่สองน้ำหนักเท่าเดิมในวันเดียวกัน
 
 ⚙️ แปรรูป
   แปรรูป [รหัสล็อต] [วิธี]
   วิธี: ธรรมชาติ, ล้าง, ฮันนี่, กะลาเปียก, ไร้อากาศ
   ตัวอย่าง: แปรรูป CQM-2024-DOI-001 ล้าง
 
+🌧️ ปริมาณฝน
+  ฝน [แปลง] [มม.]
+  ตัวอย่าง: ฝน แปลง1 12.5
+
+📋 รายการตรวจสอบ SOP
+  ขั้นตอน (ดูรายการ), ขั้นตอน [หมายเลข] [รหัสล็อต]
+  เสร็จ [หมายเลขขั้นตอน]
+  ตัวอย่าง: ขั้นตอน 1 แล้ว เสร็จ 1 2
+
 ❓ ช่วยเหลือ
   ช่วยเหลือ หรือ วิธีใช้"#.to_string()
     }
@@ -577,16 +1547,26 @@ mod tests {
                 // English commands
                 "harvest" | "h" => self.parse_harvest_command(&parts[1..]),
                 "process" | "p" => self.parse_processing_command(&parts[1..]),
+                "rain" | "r" => self.parse_rain_command(&parts[1..]),
+                "sop" | "checklist" => self.parse_checklist_command(&parts[1..]),
+                "done" => self.parse_done_command(&parts[1..]),
                 "help" | "?" => ChatbotCommand::Help,
                 // Thai commands
                 "เก็บ" | "เก็บเกี่ยว" => self.parse_harvest_command(&parts[1..]),
                 "แปรรูป" | "โปรเซส" => self.parse_processing_command(&parts[1..]),
+                "ฝน" => self.parse_rain_command(&parts[1..]),
+                "ขั้นตอน" => self.parse_checklist_command(&parts[1..]),
+                "เสร็จ" => self.parse_done_command(&parts[1..]),
                 "ช่วยเหลือ" | "วิธีใช้" => ChatbotCommand::Help,
                 _ => ChatbotCommand::Unknown(text),
             }
         }
 
         fn parse_harvest_command(&self, args: &[&str]) -> ChatbotCommand {
+            let (args, confirm_duplicate) = match args.split_last() {
+                Some((last, rest)) if CONFIRM_WORDS.contains(last) => (rest, true),
+                _ => (args, false),
+            };
             if args.len() < 2 {
                 return ChatbotCommand::Unknown(
                     "harvest command requires: plot_name weight_kg [ripe%]".to_string()
@@ -604,19 +1584,20 @@ mod tests {
             
             let ripe_percent = if args.len() > 2 {
                 match args[2].parse::<i32>() {
-                    Ok(p) if (0..=100).contains(&p) => p,
+                    Ok(p) if (0..=100).contains(&p) => Some(p),
                     _ => return ChatbotCommand::Unknown(
                         format!("Invalid ripe percent: {}", args[2])
                     ),
                 }
             } else {
-                80
+                None
             };
             
             ChatbotCommand::Harvest {
                 plot_name,
                 weight_kg,
                 ripe_percent,
+                confirm_duplicate,
             }
         }
 
@@ -629,19 +1610,72 @@ mod tests {
             
             let lot_code = args[0].to_uppercase();
             
-            let method = match args[1].to_lowercase().as_str() {
-                "natural" | "ธรรมชาติ" => ProcessingMethod::Natural,
-                "washed" | "ล้าง" => ProcessingMethod::Washed,
-                "honey" | "ฮันนี่" => ProcessingMethod::Honey { mucilage_percent: 50 },
-                "wet-hulled" | "wethulled" | "กะลาเปียก" => ProcessingMethod::WetHulled,
-                "anaerobic" | "ไร้อากาศ" => ProcessingMethod::Anaerobic { hours: 72 },
-                _ => return ChatbotCommand::Unknown(
-                    format!("Unknown processing method: {}", args[1])
+            let method_text = args[1..].join(" ");
+            let method = match ProcessingMethod::parse(&method_text) {
+                Some(method) => method,
+                None => return ChatbotCommand::Unknown(
+                    format!("Unknown processing method: {}", method_text)
                 ),
             };
             
             ChatbotCommand::Processing { lot_code, method }
         }
+
+        fn parse_rain_command(&self, args: &[&str]) -> ChatbotCommand {
+            if args.len() < 2 {
+                return ChatbotCommand::Unknown(
+                    "rain command requires: plot_name mm".to_string()
+                );
+            }
+
+            let rainfall_mm = match Decimal::from_str(args[1]) {
+                Ok(mm) if mm >= Decimal::ZERO && mm <= Decimal::from(MAX_RAINFALL_MM) => mm,
+                _ => return ChatbotCommand::Unknown(
+                    format!("Invalid rainfall: {}", args[1])
+                ),
+            };
+
+            ChatbotCommand::Rain {
+                plot_name: args[0].to_string(),
+                rainfall_mm,
+            }
+        }
+
+        fn parse_checklist_command(&self, args: &[&str]) -> ChatbotCommand {
+            let number = match args.first() {
+                None => None,
+                Some(arg) => match arg.parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return ChatbotCommand::Unknown(
+                        format!("Invalid checklist number: {}", arg)
+                    ),
+                },
+            };
+
+            ChatbotCommand::Checklist {
+                number,
+                lot_code: args.get(1).map(|code| code.to_uppercase()),
+            }
+        }
+
+        fn parse_done_command(&self, args: &[&str]) -> ChatbotCommand {
+            let mut positions = Vec::new();
+            for arg in args.iter().flat_map(|arg| arg.split(',')).filter(|arg| !arg.is_empty()) {
+                match arg.parse::<i32>() {
+                    Ok(p) if p > 0 => positions.push(p),
+                    _ => return ChatbotCommand::Unknown(
+                        format!("Invalid step number: {}", arg)
+                    ),
+                }
+            }
+            if positions.is_empty() {
+                return ChatbotCommand::Unknown(
+                    "done command requires: step numbers".to_string()
+                );
+            }
+
+            ChatbotCommand::CheckSteps { positions }
+        }
     }
 
     #[test]
@@ -650,10 +1684,10 @@ mod tests {
         
         let cmd = parser.parse_command("harvest plot1 50 85");
         match cmd {
-            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent } => {
+            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent, .. } => {
                 assert_eq!(plot_name, "plot1");
                 assert_eq!(weight_kg, Decimal::from(50));
-                assert_eq!(ripe_percent, 85);
+                assert_eq!(ripe_percent, Some(85));
             }
             _ => panic!("Expected Harvest command"),
         }
@@ -665,10 +1699,10 @@ mod tests {
         
         let cmd = parser.parse_command("เก็บ แปลง1 30 90");
         match cmd {
-            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent } => {
+            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent, .. } => {
                 assert_eq!(plot_name, "แปลง1");
                 assert_eq!(weight_kg, Decimal::from(30));
-                assert_eq!(ripe_percent, 90);
+                assert_eq!(ripe_percent, Some(90));
             }
             _ => panic!("Expected Harvest command"),
         }
@@ -680,15 +1714,91 @@ mod tests {
         
         let cmd = parser.parse_command("harvest myplot 25");
         match cmd {
-            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent } => {
+            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent, .. } => {
                 assert_eq!(plot_name, "myplot");
                 assert_eq!(weight_kg, Decimal::from(25));
-                assert_eq!(ripe_percent, 80); // Default
+                assert_eq!(ripe_percent, None); // Photo estimate or default
             }
             _ => panic!("Expected Harvest command"),
         }
     }
 
+    #[test]
+    fn test_parse_harvest_command_confirm() {
+        let parser = CommandParser;
+
+        match parser.parse_command("harvest myplot 25 85 confirm") {
+            ChatbotCommand::Harvest { ripe_percent, confirm_duplicate, .. } => {
+                assert_eq!(ripe_percent, Some(85));
+                assert!(confirm_duplicate);
+            }
+            _ => panic!("Expected Harvest command"),
+        }
+        match parser.parse_command("เก็บ แปลง1 30 ยืนยัน") {
+            ChatbotCommand::Harvest { weight_kg, ripe_percent, confirm_duplicate, .. } => {
+                assert_eq!(weight_kg, Decimal::from(30));
+                assert_eq!(ripe_percent, None);
+                assert!(confirm_duplicate);
+            }
+            _ => panic!("Expected Harvest command"),
+        }
+        match parser.parse_command("harvest myplot 25") {
+            ChatbotCommand::Harvest { confirm_duplicate, .. } => assert!(!confirm_duplicate),
+            _ => panic!("Expected Harvest command"),
+        }
+        assert!(matches!(parser.parse_command("harvest myplot confirm"), ChatbotCommand::Unknown(_)));
+    }
+
+    #[test]
+    fn test_duplicate_harvest_reply_offers_confirm() {
+        let duplicate = SameDayHarvest {
+            id: Uuid::from_u128(7),
+            picker_name: Some("Somchai".to_string()),
+            cherry_weight_kg: Decimal::from(25),
+        };
+        let reply = duplicate_harvest_reply("Doi Chang A", "doi", Decimal::from(25), Some(85), &duplicate);
+        assert!(!reply.success);
+        assert_eq!(reply.entity_id, Some(duplicate.id));
+        assert!(reply.message.contains("25 kg by Somchai was already recorded on Doi Chang A"));
+        assert!(reply.message.ends_with("harvest doi 25 85 confirm"));
+        assert!(reply.message_th.contains("โดย Somchai"));
+        assert!(reply.message_th.ends_with("เก็บ doi 25 85 ยืนยัน"));
+
+        // The resent command parses back into a confirmed harvest
+        let resend = reply.message.lines().last().unwrap();
+        match CommandParser.parse_command(resend) {
+            ChatbotCommand::Harvest { plot_name, confirm_duplicate, .. } => {
+                assert_eq!(plot_name, "doi");
+                assert!(confirm_duplicate);
+            }
+            _ => panic!("Expected Harvest command"),
+        }
+
+        let unnamed = SameDayHarvest { picker_name: None, ..duplicate };
+        let reply = duplicate_harvest_reply("Doi Chang A", "doi", Decimal::from(25), None, &unnamed);
+        assert!(reply.message.contains("25 kg was already recorded"));
+        assert!(reply.message.ends_with("harvest doi 25 confirm"));
+    }
+
+
+    #[test]
+    fn test_harvest_ripeness_from_photo_estimate() {
+        let estimate = RipenessAssessment {
+            underripe_percent: 12,
+            ripe_percent: 81,
+            overripe_percent: 7,
+        };
+        assert_eq!(harvest_ripeness(None, Some(&estimate)), estimate);
+
+        // A ripe % given overrides the estimate
+        let overridden = harvest_ripeness(Some(90), Some(&estimate));
+        assert_eq!(overridden.ripe_percent, 90);
+        assert_eq!(overridden.underripe_percent + overridden.overripe_percent, 10);
+
+        let default = harvest_ripeness(None, None);
+        assert_eq!(default.ripe_percent, DEFAULT_RIPE_PERCENT);
+        assert!(default.validate().is_ok());
+    }
 
     #[test]
     fn test_parse_processing_command_english() {
@@ -704,6 +1814,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_processing_command_with_parameter() {
+        let parser = CommandParser;
+
+        let cmd = parser.parse_command("process CQM-2024-DOI-001 honey 30%");
+        match cmd {
+            ChatbotCommand::Processing { method, .. } => {
+                assert_eq!(method, ProcessingMethod::Honey { mucilage_percent: 30 });
+            }
+            _ => panic!("Expected Processing command"),
+        }
+    }
+
     #[test]
     fn test_parse_processing_command_thai() {
         let parser = CommandParser;
@@ -874,7 +1997,7 @@ mod tests {
         let cmd = parser.parse_command("harvest plot1 50 0");
         match cmd {
             ChatbotCommand::Harvest { ripe_percent, .. } => {
-                assert_eq!(ripe_percent, 0);
+                assert_eq!(ripe_percent, Some(0));
             }
             _ => panic!("Expected Harvest command with 0% ripe"),
         }
@@ -883,7 +2006,7 @@ mod tests {
         let cmd = parser.parse_command("harvest plot1 50 100");
         match cmd {
             ChatbotCommand::Harvest { ripe_percent, .. } => {
-                assert_eq!(ripe_percent, 100);
+                assert_eq!(ripe_percent, Some(100));
             }
             _ => panic!("Expected Harvest command with 100% ripe"),
         }
@@ -922,10 +2045,10 @@ mod tests {
         // Extra whitespace between arguments should be handled
         let cmd = parser.parse_command("harvest   plot1   50   85");
         match cmd {
-            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent } => {
+            ChatbotCommand::Harvest { plot_name, weight_kg, ripe_percent, .. } => {
                 assert_eq!(plot_name, "plot1");
                 assert_eq!(weight_kg, Decimal::from(50));
-                assert_eq!(ripe_percent, 85);
+                assert_eq!(ripe_percent, Some(85));
             }
             _ => panic!("Expected Harvest command"),
         }
@@ -1022,6 +2145,74 @@ mod tests {
         assert!(matches!(cmd, ChatbotCommand::Processing { .. }));
     }
 
+    #[test]
+    fn test_parse_rain_command() {
+        let parser = CommandParser;
+
+        match parser.parse_command("rain plot1 12.5") {
+            ChatbotCommand::Rain { plot_name, rainfall_mm } => {
+                assert_eq!(plot_name, "plot1");
+                assert_eq!(rainfall_mm, Decimal::from_str("12.5").unwrap());
+            }
+            other => panic!("Expected Rain command, got {:?}", other),
+        }
+
+        match parser.parse_command("ฝน แปลง1 30") {
+            ChatbotCommand::Rain { plot_name, rainfall_mm } => {
+                assert_eq!(plot_name, "แปลง1");
+                assert_eq!(rainfall_mm, Decimal::from(30));
+            }
+            other => panic!("Expected Rain command, got {:?}", other),
+        }
+
+        // A dry day is a valid reading
+        assert!(matches!(parser.parse_command("r plot1 0"), ChatbotCommand::Rain { .. }));
+    }
+
+    #[test]
+    fn test_parse_rain_command_invalid() {
+        let parser = CommandParser;
+
+        assert!(matches!(parser.parse_command("rain plot1"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("rain plot1 -5"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("rain plot1 abc"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("ฝน แปลง1 501"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("rain plot1 500"), ChatbotCommand::Rain { .. }));
+    }
+
+    #[test]
+    fn test_parse_checklist_command() {
+        let parser = CommandParser;
+
+        assert!(matches!(
+            parser.parse_command("sop"),
+            ChatbotCommand::Checklist { number: None, lot_code: None }
+        ));
+        match parser.parse_command("ขั้นตอน 2 cqm-2024-doi-001") {
+            ChatbotCommand::Checklist { number, lot_code } => {
+                assert_eq!(number, Some(2));
+                assert_eq!(lot_code.as_deref(), Some("CQM-2024-DOI-001"));
+            }
+            _ => panic!("Expected Checklist command"),
+        }
+        assert!(matches!(parser.parse_command("sop 0"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("checklist x"), ChatbotCommand::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_done_command() {
+        let parser = CommandParser;
+
+        match parser.parse_command("done 1 2,3") {
+            ChatbotCommand::CheckSteps { positions } => assert_eq!(positions, vec![1, 2, 3]),
+            _ => panic!("Expected CheckSteps command"),
+        }
+        assert!(matches!(parser.parse_command("เสร็จ 4"), ChatbotCommand::CheckSteps { .. }));
+        assert!(matches!(parser.parse_command("done"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("done 1 two"), ChatbotCommand::Unknown(_)));
+        assert!(matches!(parser.parse_command("done 0"), ChatbotCommand::Unknown(_)));
+    }
+
     #[test]
     fn test_webhook_request_deserialization() {
         let json = r#"{
@@ -1152,4 +2343,258 @@ mod tests {
         
         assert!(event.delivery_context.as_ref().unwrap().is_redelivery);
     }
+
+    // ========================================================================
+    // Webhook payload corpus
+    // ========================================================================
+
+    /// Expected outcome for a corpus payload
+    struct CorpusCase {
+        name: &'static str,
+        payload: &'static str,
+        /// Event kinds that survive permissive deserialization, in order
+        kinds: &'static [&'static str],
+    }
+
+    /// Sample payloads based on the LINE Messaging API reference
+    const WEBHOOK_CORPUS: &[CorpusCase] = &[
+        CorpusCase {
+            name: "text message",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"message","replyToken":"r1","source":{"type":"user","userId":"U1"},"message":{"type":"text","id":"1","text":"help","quoteToken":"q1"},"timestamp":1700000000000,"mode":"active","webhookEventId":"e1","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["message"],
+        },
+        CorpusCase {
+            name: "sticker message",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"message","replyToken":"r2","source":{"type":"user","userId":"U1"},"message":{"type":"sticker","id":"2","packageId":"446","stickerId":"1988","stickerResourceType":"ANIMATION","keywords":["happy"],"quoteToken":"q2"},"timestamp":1700000000000,"mode":"active","webhookEventId":"e2","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["message"],
+        },
+        CorpusCase {
+            name: "location message",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"message","replyToken":"r3","source":{"type":"user","userId":"U1"},"message":{"type":"location","id":"3","title":"Doi Chang","address":"Mae Suai, Chiang Rai","latitude":19.8163,"longitude":99.5524},"timestamp":1700000000000,"mode":"active","webhookEventId":"e3","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["message"],
+        },
+        CorpusCase {
+            name: "image message",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"message","replyToken":"r4","source":{"type":"user","userId":"U1"},"message":{"type":"image","id":"4","contentProvider":{"type":"line"},"quoteToken":"q4"},"timestamp":1700000000000,"mode":"active","webhookEventId":"e4","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["message"],
+        },
+        CorpusCase {
+            name: "follow",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"follow","replyToken":"r5","source":{"type":"user","userId":"U1"},"follow":{"isUnblocked":false},"timestamp":1700000000000,"mode":"active","webhookEventId":"e5","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["follow"],
+        },
+        CorpusCase {
+            name: "unfollow",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"unfollow","source":{"type":"user","userId":"U1"},"timestamp":1700000000000,"mode":"active","webhookEventId":"e6","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["unfollow"],
+        },
+        CorpusCase {
+            name: "postback with datetime params",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"postback","replyToken":"r7","source":{"type":"user","userId":"U1"},"postback":{"data":"action=harvest&plot=plot1&kg=50&ripe=85","params":{"date":"2024-12-01"}},"timestamp":1700000000000,"mode":"active","webhookEventId":"e7","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["postback"],
+        },
+        CorpusCase {
+            name: "join group",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"join","replyToken":"r8","source":{"type":"group","groupId":"G1"},"timestamp":1700000000000,"mode":"active","webhookEventId":"e8","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["join"],
+        },
+        CorpusCase {
+            name: "unknown future event type",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"membership","source":{"type":"user","userId":"U1"},"membership":{"type":"joined","membershipId":7},"timestamp":1700000000000,"mode":"active","webhookEventId":"e9","deliveryContext":{"isRedelivery":false}}]}"#,
+            kinds: &["membership"],
+        },
+        CorpusCase {
+            name: "malformed event is skipped, valid events kept",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"message","source":{"type":"user","userId":"U1"},"message":{"type":"text","text":42},"timestamp":1700000000000},{"type":"follow","source":{"type":"user","userId":"U2"},"timestamp":1700000000001}]}"#,
+            kinds: &["follow"],
+        },
+        CorpusCase {
+            name: "event without source or timestamp",
+            payload: r#"{"destination":"Uxxx","events":[{"type":"postback","postback":{"data":"action=help"}}]}"#,
+            kinds: &["postback"],
+        },
+        CorpusCase {
+            name: "webhook verification (empty events)",
+            payload: r#"{"destination":"Uxxx","events":[]}"#,
+            kinds: &[],
+        },
+    ];
+
+    #[test]
+    fn test_webhook_corpus_deserializes_permissively() {
+        for case in WEBHOOK_CORPUS {
+            let request: LineWebhookRequest = serde_json::from_str(case.payload)
+                .unwrap_or_else(|e| panic!("corpus case '{}' failed: {}", case.name, e));
+            let kinds: Vec<&str> = request.events.iter().map(|e| e.event_type.as_str()).collect();
+            assert_eq!(kinds, case.kinds, "corpus case '{}'", case.name);
+        }
+    }
+
+    #[test]
+    fn test_webhook_event_kinds() {
+        let kinds: Vec<LineEventKind> = WEBHOOK_CORPUS
+            .iter()
+            .flat_map(|case| serde_json::from_str::<LineWebhookRequest>(case.payload).unwrap().events)
+            .map(|event| event.kind())
+            .collect();
+
+        assert!(kinds.contains(&LineEventKind::Message));
+        assert!(kinds.contains(&LineEventKind::Follow));
+        assert!(kinds.contains(&LineEventKind::Unfollow));
+        assert!(kinds.contains(&LineEventKind::Postback));
+        assert!(kinds.contains(&LineEventKind::Other("join".to_string())));
+        assert!(kinds.contains(&LineEventKind::Other("membership".to_string())));
+    }
+
+    #[test]
+    fn test_webhook_sticker_and_location_fields() {
+        let sticker: LineWebhookRequest = serde_json::from_str(WEBHOOK_CORPUS[1].payload).unwrap();
+        let message = sticker.events[0].message.as_ref().unwrap();
+        assert_eq!(message.message_type, "sticker");
+        assert_eq!(message.package_id.as_deref(), Some("446"));
+        assert_eq!(message.sticker_id.as_deref(), Some("1988"));
+        assert!(message.text.is_none());
+
+        let location: LineWebhookRequest = serde_json::from_str(WEBHOOK_CORPUS[2].payload).unwrap();
+        let message = location.events[0].message.as_ref().unwrap();
+        assert_eq!(message.message_type, "location");
+        assert_eq!(message.title.as_deref(), Some("Doi Chang"));
+        assert_eq!(message.latitude, Some(19.8163));
+        assert_eq!(message.longitude, Some(99.5524));
+    }
+
+    #[test]
+    fn test_webhook_postback_payload() {
+        let request: LineWebhookRequest = serde_json::from_str(WEBHOOK_CORPUS[6].payload).unwrap();
+        let postback = request.events[0].postback.as_ref().unwrap();
+        let fields = postback.fields();
+
+        assert_eq!(fields.get("action").map(String::as_str), Some("harvest"));
+        assert_eq!(fields.get("plot").map(String::as_str), Some("plot1"));
+        assert_eq!(fields.get("kg").map(String::as_str), Some("50"));
+        assert_eq!(fields.get("ripe").map(String::as_str), Some("85"));
+        assert_eq!(
+            postback.params.as_ref().unwrap().get("date").map(String::as_str),
+            Some("2024-12-01")
+        );
+    }
+
+    #[test]
+    fn test_parse_postback_data_edge_cases() {
+        let fields = parse_postback_data("action=help&&flag&lot= CQM-001 ");
+        assert_eq!(fields.get("action").map(String::as_str), Some("help"));
+        assert_eq!(fields.get("flag").map(String::as_str), Some(""));
+        assert_eq!(fields.get("lot").map(String::as_str), Some("CQM-001"));
+        assert_eq!(fields.len(), 3);
+
+        assert!(parse_postback_data("").is_empty());
+    }
+
+    #[test]
+    fn test_webhook_rejects_non_object_body() {
+        // Only a structurally invalid body is rejected; individual events never are
+        assert!(serde_json::from_str::<LineWebhookRequest>(r#"{"events":"nope"}"#).is_err());
+        assert!(serde_json::from_str::<LineWebhookRequest>("42").is_err());
+    }
+
+    // ========================================================================
+    // Location check-ins
+    // ========================================================================
+
+    fn plot(name: &str, latitude: f64, longitude: f64) -> PlotLocation {
+        PlotLocation {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn test_haversine_distance() {
+        // Same point
+        assert_eq!(haversine_distance_meters(19.8163, 99.5524, 19.8163, 99.5524), 0.0);
+
+        // 0.001 degree of latitude is ~111 m
+        let d = haversine_distance_meters(19.8163, 99.5524, 19.8173, 99.5524);
+        assert!((d - 111.2).abs() < 1.0, "got {}", d);
+
+        // Chiang Mai to Chiang Rai is roughly 160 km in a straight line
+        let d = haversine_distance_meters(18.7883, 98.9853, 19.9105, 99.8406);
+        assert!((150_000.0..170_000.0).contains(&d), "got {}", d);
+
+        // Symmetric
+        let there = haversine_distance_meters(18.7883, 98.9853, 19.9105, 99.8406);
+        let back = haversine_distance_meters(19.9105, 99.8406, 18.7883, 98.9853);
+        assert!((there - back).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_find_nearest_plot_within_radius() {
+        let plots = vec![
+            plot("Upper", 19.8190, 99.5524), // ~300 m north
+            plot("Lower", 19.8173, 99.5524), // ~111 m north
+            plot("Far", 19.8500, 99.5524),   // ~3.7 km north
+        ];
+
+        let (nearest, distance) =
+            find_nearest_plot(19.8163, 99.5524, &plots, PLOT_CHECKIN_RADIUS_METERS).unwrap();
+        assert_eq!(nearest.name, "Lower");
+        assert!(distance < 120.0);
+    }
+
+    #[test]
+    fn test_find_nearest_plot_outside_radius() {
+        let plots = vec![plot("Far", 19.8500, 99.5524)];
+        assert!(find_nearest_plot(19.8163, 99.5524, &plots, PLOT_CHECKIN_RADIUS_METERS).is_none());
+        assert!(find_nearest_plot(19.8163, 99.5524, &[], PLOT_CHECKIN_RADIUS_METERS).is_none());
+    }
+
+    #[test]
+    fn test_quick_reply_serialization() {
+        let message = LineMessage::Text {
+            text: "📍 Checked in".to_string(),
+            quick_reply: Some(LineQuickReply {
+                items: vec![LineQuickReplyItem::postback(
+                    "บันทึกสภาพอากาศ",
+                    "action=checkin_weather&checkin=abc",
+                    "Record weather here",
+                )],
+            }),
+        };
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "text");
+        assert_eq!(json["quickReply"]["items"][0]["type"], "action");
+        assert_eq!(json["quickReply"]["items"][0]["action"]["type"], "postback");
+        assert_eq!(json["quickReply"]["items"][0]["action"]["data"], "action=checkin_weather&checkin=abc");
+        assert_eq!(json["quickReply"]["items"][0]["action"]["displayText"], "Record weather here");
+
+        // Plain text messages omit quickReply entirely
+        let json = serde_json::to_value(LineMessage::text("hello")).unwrap();
+        assert!(json.get("quickReply").is_none());
+    }
+
+    #[test]
+    fn test_checkin_postback_data() {
+        let checkin_id = Uuid::new_v4();
+        let fields = parse_postback_data(&format!("action=checkin_harvest&checkin={}", checkin_id));
+        assert_eq!(fields.get("action").map(String::as_str), Some("checkin_harvest"));
+        assert_eq!(Uuid::parse_str(&fields["checkin"]).unwrap(), checkin_id);
+        // LINE limits postback data to 300 characters
+        assert!(format!("action=checkin_weather&checkin={}", checkin_id).len() <= 300);
+    }
+
+    #[test]
+    fn test_acknowledge_quick_reply() {
+        let log_id = Uuid::new_v4();
+        let json = serde_json::to_value(crate::services::notification::acknowledge_quick_reply(log_id)).unwrap();
+        let data = json["items"][0]["action"]["data"].as_str().unwrap();
+
+        let fields = parse_postback_data(data);
+        assert_eq!(fields.get("action").map(String::as_str), Some("ack"));
+        assert_eq!(Uuid::parse_str(&fields["notification"]).unwrap(), log_id);
+        assert!(data.len() <= 300);
+        assert!(json["items"][0]["action"]["label"].as_str().unwrap().chars().count() <= 20);
+    }
 }
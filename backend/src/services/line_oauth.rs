@@ -92,6 +92,13 @@ pub struct LineOAuthResult {
     pub picture_url: Option<String>,
 }
 
+/// LINE account signing in, with the tokens from its authorization code
+#[derive(Debug)]
+pub struct LineSignIn {
+    pub profile: LineUserProfile,
+    pub tokens: LineTokenResponse,
+}
+
 /// Input for linking LINE to existing user
 #[derive(Debug, Deserialize)]
 pub struct LinkLineInput {
@@ -274,6 +281,14 @@ impl LineOAuthService {
     // Database Operations
     // ========================================================================
 
+    /// Exchange an authorization code for the LINE account behind it, to
+    /// sign in or register with
+    pub async fn authenticate(&self, code: &str) -> AppResult<LineSignIn> {
+        let tokens = self.exchange_code(code).await?;
+        let profile = self.get_user_profile(&tokens.access_token).await?;
+        Ok(LineSignIn { profile, tokens })
+    }
+
     /// Handle LINE OAuth callback - link to existing user or return profile for new user
     pub async fn handle_callback(
         &self,
@@ -452,6 +467,21 @@ impl LineOAuthService {
     pub async fn disconnect(&self, user_id: Uuid) -> AppResult<bool> {
         // Get connection to revoke token
         if let Some(connection) = self.get_connection(user_id).await? {
+            // LINE is the only way into accounts registered with it
+            let has_password = sqlx::query_scalar::<_, bool>(
+                "SELECT password_hash IS NOT NULL FROM users WHERE id = $1",
+            )
+            .bind(user_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !has_password {
+                return Err(AppError::Validation {
+                    field: "line".to_string(),
+                    message: "This account signs in with LINE only and cannot disconnect it".to_string(),
+                    message_th: "บัญชีนี้เข้าสู่ระบบด้วย LINE เท่านั้น จึงยกเลิกการเชื่อมต่อไม่ได้".to_string(),
+                });
+            }
+
             // Revoke token if available
             if let Some(token) = &connection.access_token {
                 let _ = self.revoke_token(token).await; // Ignore errors
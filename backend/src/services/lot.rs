@@ -3,10 +3,16 @@
 use chrono::{DateTime, Datelike, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use shared::Language;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::lot_certification::{mixed_claims, validate_claim_types};
+use crate::services::sequence::SequenceScope;
+use crate::services::{LotCertificationService, SequenceService};
+
+pub use shared::LotStage;
 
 /// Lot service for managing coffee lots and traceability
 #[derive(Clone)]
@@ -14,41 +20,6 @@ pub struct LotService {
     db: PgPool,
 }
 
-/// Lot stage in the supply chain
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
-#[serde(rename_all = "snake_case")]
-pub enum LotStage {
-    Cherry,
-    Parchment,
-    GreenBean,
-    RoastedBean,
-    Sold,
-}
-
-impl LotStage {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            LotStage::Cherry => "cherry",
-            LotStage::Parchment => "parchment",
-            LotStage::GreenBean => "green_bean",
-            LotStage::RoastedBean => "roasted_bean",
-            LotStage::Sold => "sold",
-        }
-    }
-
-    pub fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "cherry" => Some(LotStage::Cherry),
-            "parchment" => Some(LotStage::Parchment),
-            "green_bean" => Some(LotStage::GreenBean),
-            "roasted_bean" => Some(LotStage::RoastedBean),
-            "sold" => Some(LotStage::Sold),
-            _ => None,
-        }
-    }
-}
-
 /// Lot information
 #[derive(Debug, Clone, Serialize)]
 pub struct Lot {
@@ -56,7 +27,7 @@ pub struct Lot {
     pub business_id: Uuid,
     pub traceability_code: String,
     pub name: String,
-    pub stage: String,
+    pub stage: LotStage,
     pub current_weight_kg: Decimal,
     pub qr_code_url: Option<String>,
     pub notes: Option<String>,
@@ -81,6 +52,9 @@ pub struct LotWithSources {
     #[serde(flatten)]
     pub lot: Lot,
     pub sources: Vec<LotSourceInfo>,
+    /// Certification claims dropped when certified and non-certified lots
+    /// were blended
+    pub downgraded_claims: Vec<String>,
 }
 
 /// Source lot info for display
@@ -107,6 +81,10 @@ pub struct BlendLotsInput {
     pub sources: Vec<BlendSourceInput>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
+    /// Certification claims to drop from the blend; required for every
+    /// claim some but not all source lots carry
+    #[serde(default)]
+    pub downgrade_claims: Vec<String>,
 }
 
 /// Source lot for blending
@@ -120,7 +98,7 @@ pub struct BlendSourceInput {
 #[derive(Debug, Deserialize)]
 pub struct UpdateLotInput {
     pub name: Option<String>,
-    pub stage: Option<String>,
+    pub stage: Option<LotStage>,
     pub current_weight_kg: Option<Decimal>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
@@ -139,22 +117,18 @@ impl LotService {
         business_code: &str,
     ) -> AppResult<String> {
         let year = Utc::now().year();
-        
-        // Get next sequence number
-        let sequence: i32 = sqlx::query_scalar(
-            "SELECT get_next_lot_sequence($1, $2)"
-        )
-        .bind(business_id)
-        .bind(year)
-        .fetch_one(&self.db)
-        .await?;
+
+        // Allocate the next number for this year atomically
+        let sequence = SequenceService::new(self.db.clone())
+            .next(business_id, SequenceScope::Lot, &year.to_string())
+            .await?;
 
         Ok(format!("CQM-{}-{}-{:04}", year, business_code, sequence))
     }
 
     /// Get all lots for a business
     pub async fn get_lots(&self, business_id: Uuid) -> AppResult<Vec<Lot>> {
-        let rows = sqlx::query_as::<_, (Uuid, Uuid, String, String, String, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
+        let rows = sqlx::query_as::<_, (Uuid, Uuid, String, String, LotStage, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
             r#"
             SELECT id, business_id, traceability_code, name, stage, current_weight_kg,
                    qr_code_url, notes, notes_th, created_at, updated_at
@@ -182,6 +156,28 @@ impl LotService {
         }).collect())
     }
 
+    /// Fingerprint of the lot list: lot count, latest update and highest
+    /// sync version. Any insert, update or delete changes it.
+    pub async fn get_lots_fingerprint(&self, business_id: Uuid) -> AppResult<String> {
+        let (count, updated_at, version) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>, Option<i64>)>(
+            r#"
+            SELECT COUNT(*), MAX(updated_at), MAX(entity_version)
+            FROM lots
+            WHERE business_id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(format!(
+            "{}:{}:{}",
+            count,
+            updated_at.map(|t| t.timestamp_micros()).unwrap_or(0),
+            version.unwrap_or(0)
+        ))
+    }
+
     /// Get a lot by ID with its sources
     pub async fn get_lot_with_sources(
         &self,
@@ -189,7 +185,7 @@ impl LotService {
         lot_id: Uuid,
     ) -> AppResult<LotWithSources> {
         // Get lot
-        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, String, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
+        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, LotStage, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
             r#"
             SELECT id, business_id, traceability_code, name, stage, current_weight_kg,
                    qr_code_url, notes, notes_th, created_at, updated_at
@@ -239,7 +235,18 @@ impl LotService {
         })
         .collect();
 
-        Ok(LotWithSources { lot, sources })
+        let downgraded_claims = sqlx::query_scalar::<_, Vec<String>>(
+            "SELECT downgraded_claims FROM lots WHERE id = $1",
+        )
+        .bind(lot_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(LotWithSources {
+            lot,
+            sources,
+            downgraded_claims,
+        })
     }
 
     /// Create a new lot (internal use - typically created via harvest)
@@ -265,7 +272,7 @@ impl LotService {
         let qr_code_url = format!("https://trace.coffeeqm.com/{}", traceability_code);
 
         // Create lot
-        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, String, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
+        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, LotStage, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
             r#"
             INSERT INTO lots (business_id, traceability_code, name, stage, qr_code_url, notes, notes_th)
             VALUES ($1, $2, $3, 'cherry', $4, $5, $6)
@@ -297,10 +304,13 @@ impl LotService {
         })
     }
 
-    /// Blend multiple lots into a new lot
+    /// Blend multiple lots into a new lot. Certified lots are only blended
+    /// with lots lacking the certification when the blend drops the claim;
+    /// the downgrade is recorded in the audit log
     pub async fn blend_lots(
         &self,
         business_id: Uuid,
+        user_id: Uuid,
         business_code: &str,
         input: BlendLotsInput,
     ) -> AppResult<LotWithSources> {
@@ -350,6 +360,41 @@ impl LotService {
             total_weight += source_lot.0 * source.proportion_percent / Decimal::from(100);
         }
 
+        // Keep certified coffee segregated unless the blend drops the claim
+        validate_claim_types("downgrade_claims", &input.downgrade_claims)?;
+        let source_ids: Vec<Uuid> = input.sources.iter().map(|s| s.source_lot_id).collect();
+        let claims = LotCertificationService::new(self.db.clone())
+            .claims_for_lots(business_id, &source_ids)
+            .await?;
+        let source_claims: Vec<_> = source_ids.iter().map(|id| claims[id].clone()).collect();
+        let mixed = mixed_claims(&source_claims);
+        let unconfirmed: Vec<&str> = mixed
+            .iter()
+            .filter(|c| !input.downgrade_claims.contains(c))
+            .map(String::as_str)
+            .collect();
+        if !unconfirmed.is_empty() {
+            return Err(AppError::Conflict {
+                resource: "certification_claims".to_string(),
+                message: format!(
+                    "Blending would mix lots certified {} with lots that are not; add them to downgrade_claims to blend without these claims",
+                    unconfirmed.join(", ")
+                ),
+                message_th: format!(
+                    "การผสมจะรวมล็อตที่ได้รับรอง {} กับล็อตที่ไม่ได้รับรอง กรุณาระบุใน downgrade_claims เพื่อผสมโดยไม่อ้างการรับรองนี้",
+                    unconfirmed.join(", ")
+                ),
+            });
+        }
+        let downgraded_claims: Vec<String> = source_claims
+            .iter()
+            .flatten()
+            .filter(|c| input.downgrade_claims.contains(c))
+            .cloned()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
         // Start transaction
         let mut tx = self.db.begin().await?;
 
@@ -360,8 +405,8 @@ impl LotService {
         // Create new blended lot
         let lot_id = sqlx::query_scalar::<_, Uuid>(
             r#"
-            INSERT INTO lots (business_id, traceability_code, name, stage, current_weight_kg, qr_code_url, notes, notes_th)
-            VALUES ($1, $2, $3, 'cherry', $4, $5, $6, $7)
+            INSERT INTO lots (business_id, traceability_code, name, stage, current_weight_kg, qr_code_url, notes, notes_th, downgraded_claims)
+            VALUES ($1, $2, $3, 'cherry', $4, $5, $6, $7, $8)
             RETURNING id
             "#,
         )
@@ -372,9 +417,31 @@ impl LotService {
         .bind(&qr_code_url)
         .bind(&input.notes)
         .bind(&input.notes_th)
+        .bind(&downgraded_claims)
         .fetch_one(&mut *tx)
         .await?;
 
+        if !downgraded_claims.is_empty() {
+            let sources: Vec<serde_json::Value> = source_ids
+                .iter()
+                .zip(&source_claims)
+                .map(|(id, claims)| serde_json::json!({ "lot_id": id, "claims": claims }))
+                .collect();
+            sqlx::query(
+                r#"
+                INSERT INTO audit_log (business_id, user_id, action, resource_type, resource_id, old_values, new_values)
+                VALUES ($1, $2, 'downgrade_certification_claims', 'lot', $3, $4, $5)
+                "#,
+            )
+            .bind(business_id)
+            .bind(user_id)
+            .bind(lot_id)
+            .bind(serde_json::json!({ "sources": sources }))
+            .bind(serde_json::json!({ "downgraded_claims": downgraded_claims }))
+            .execute(&mut *tx)
+            .await?;
+        }
+
         // Add source references
         for source in &input.sources {
             sqlx::query(
@@ -404,7 +471,7 @@ impl LotService {
         input: UpdateLotInput,
     ) -> AppResult<Lot> {
         // Check if lot exists
-        let existing = sqlx::query_as::<_, (String, String, Decimal, Option<String>, Option<String>)>(
+        let existing = sqlx::query_as::<_, (String, LotStage, Decimal, Option<String>, Option<String>)>(
             "SELECT name, stage, current_weight_kg, notes, notes_th FROM lots WHERE id = $1 AND business_id = $2"
         )
         .bind(lot_id)
@@ -413,13 +480,17 @@ impl LotService {
         .await?
         .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
 
-        // Validate stage if provided
-        if let Some(ref stage) = input.stage {
-            if LotStage::from_str(stage).is_none() {
+        // Lots only move forward through the supply chain
+        if let Some(stage) = input.stage {
+            if !existing.1.can_transition_to(stage) {
                 return Err(AppError::Validation {
                     field: "stage".to_string(),
-                    message: "Invalid lot stage".to_string(),
-                    message_th: "สถานะล็อตไม่ถูกต้อง".to_string(),
+                    message: format!("A lot cannot move from {} to {}", existing.1, stage),
+                    message_th: format!(
+                        "ไม่สามารถเปลี่ยนสถานะล็อตจาก{}เป็น{}",
+                        existing.1.name_in(&Language::Thai),
+                        stage.name_in(&Language::Thai)
+                    ),
                 });
             }
         }
@@ -431,7 +502,7 @@ impl LotService {
         let notes = input.notes.or(existing.3);
         let notes_th = input.notes_th.or(existing.4);
 
-        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, String, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
+        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, LotStage, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
             r#"
             UPDATE lots
             SET name = $1, stage = $2, current_weight_kg = $3, notes = $4, notes_th = $5
@@ -441,7 +512,7 @@ impl LotService {
             "#,
         )
         .bind(&name)
-        .bind(&stage)
+        .bind(stage)
         .bind(current_weight_kg)
         .bind(&notes)
         .bind(&notes_th)
@@ -466,7 +537,7 @@ impl LotService {
 
     /// Get lot by traceability code (public access for QR code)
     pub async fn get_lot_by_code(&self, traceability_code: &str) -> AppResult<Lot> {
-        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, String, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
+        let row = sqlx::query_as::<_, (Uuid, Uuid, String, String, LotStage, Decimal, Option<String>, Option<String>, Option<String>, DateTime<Utc>, DateTime<Utc>)>(
             r#"
             SELECT id, business_id, traceability_code, name, stage, current_weight_kg,
                    qr_code_url, notes, notes_th, created_at, updated_at
@@ -0,0 +1,385 @@
+//! Certification claims of lots
+//!
+//! A lot may carry a certification claim when every harvest in it comes from
+//! a plot covered by an active certification of that type on the harvest
+//! date, and every lot blended into it carries the claim too. Blending lots
+//! that differ in a claim would mix certified with non-certified coffee, so
+//! it is refused unless the user downgrades the blend's claim; downgraded
+//! claims stay on the lot for good. The traceability view shows each claim
+//! with the harvests, certificates and source lots it was derived from.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Values of the `certification_type` enum a lot can claim
+pub const CLAIM_TYPES: [&str; 7] = [
+    "thai_gap",
+    "organic_thailand",
+    "usda_organic",
+    "fair_trade",
+    "rainforest_alliance",
+    "utz",
+    "other",
+];
+
+/// Lot certification service
+#[derive(Clone)]
+pub struct LotCertificationService {
+    db: PgPool,
+}
+
+/// Active certification as used for claims
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ClaimCertification {
+    pub id: Uuid,
+    pub certification_type: String,
+    pub certification_name: String,
+    pub certification_body: String,
+    pub certificate_number: String,
+    pub scope: String,
+    pub plot_id: Option<Uuid>,
+    pub issue_date: NaiveDate,
+    pub expiration_date: NaiveDate,
+}
+
+/// Harvests, blend sources and downgrades of a business's lots
+#[derive(Debug, Clone, Default)]
+pub struct ClaimData {
+    pub certifications: Vec<ClaimCertification>,
+    /// Lot to (plot, harvest date)
+    pub harvests: HashMap<Uuid, Vec<(Uuid, NaiveDate)>>,
+    /// Lot to the lots blended into it
+    pub sources: HashMap<Uuid, Vec<Uuid>>,
+    pub downgraded: HashMap<Uuid, BTreeSet<String>>,
+    pub plot_names: HashMap<Uuid, String>,
+    pub lot_codes: HashMap<Uuid, String>,
+}
+
+/// How a lot's claim to one certification was decided
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimDerivation {
+    pub certification_type: String,
+    pub claimed: bool,
+    /// The lot's own harvests and the certificate covering each
+    pub harvests: Vec<HarvestCoverage>,
+    /// Lots blended into this one and whether they carry the claim
+    pub sources: Vec<SourceClaim>,
+    /// Dropped when certified and non-certified lots were blended
+    pub downgraded: bool,
+    pub explanation: String,
+    pub explanation_th: String,
+}
+
+/// Harvest and the certificate covering its plot on the harvest date
+#[derive(Debug, Clone, Serialize)]
+pub struct HarvestCoverage {
+    pub plot_name: String,
+    pub harvest_date: NaiveDate,
+    /// None when no certificate of the type covers the harvest
+    pub certificate_number: Option<String>,
+}
+
+/// Blended source lot and whether it carries the claim
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceClaim {
+    pub traceability_code: String,
+    pub claimed: bool,
+}
+
+/// Whether a certification covers a plot on a date
+pub fn covers(certification: &ClaimCertification, plot_id: Uuid, date: NaiveDate) -> bool {
+    let in_scope = certification.scope != "plot" || certification.plot_id == Some(plot_id);
+    in_scope && certification.issue_date <= date && date <= certification.expiration_date
+}
+
+/// Claims every harvest supports: certification types covering each
+/// harvest's plot on its date
+pub fn harvest_claims(certifications: &[ClaimCertification], harvests: &[(Uuid, NaiveDate)]) -> BTreeSet<String> {
+    let mut claims: BTreeSet<String> = certifications.iter().map(|c| c.certification_type.clone()).collect();
+    for (plot_id, date) in harvests {
+        claims.retain(|claim| {
+            certifications
+                .iter()
+                .any(|c| &c.certification_type == claim && covers(c, *plot_id, *date))
+        });
+    }
+    claims
+}
+
+/// Claims a lot may carry; a lot without harvests or sources carries none
+pub fn lot_claims(lot_id: Uuid, data: &ClaimData) -> BTreeSet<String> {
+    fn walk(
+        lot_id: Uuid,
+        data: &ClaimData,
+        memo: &mut HashMap<Uuid, BTreeSet<String>>,
+        visiting: &mut HashSet<Uuid>,
+    ) -> BTreeSet<String> {
+        if let Some(claims) = memo.get(&lot_id) {
+            return claims.clone();
+        }
+        // A cycle in the blend graph cannot be traced back to a harvest
+        if !visiting.insert(lot_id) {
+            return BTreeSet::new();
+        }
+
+        let harvests = data.harvests.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let sources = data.sources.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let mut claims = if harvests.is_empty() && sources.is_empty() {
+            BTreeSet::new()
+        } else if harvests.is_empty() {
+            CLAIM_TYPES.iter().map(|c| c.to_string()).collect()
+        } else {
+            harvest_claims(&data.certifications, harvests)
+        };
+        for source in sources {
+            let source_claims = walk(*source, data, memo, visiting);
+            claims.retain(|c| source_claims.contains(c));
+        }
+        if let Some(downgraded) = data.downgraded.get(&lot_id) {
+            claims.retain(|c| !downgraded.contains(c));
+        }
+
+        visiting.remove(&lot_id);
+        memo.insert(lot_id, claims.clone());
+        claims
+    }
+
+    walk(lot_id, data, &mut HashMap::new(), &mut HashSet::new())
+}
+
+/// Claims some but not all of the lots carry; blending them mixes
+/// certified with non-certified coffee
+pub fn mixed_claims(claims: &[BTreeSet<String>]) -> BTreeSet<String> {
+    let union: BTreeSet<String> = claims.iter().flatten().cloned().collect();
+    union
+        .into_iter()
+        .filter(|claim| !claims.iter().all(|c| c.contains(claim)))
+        .collect()
+}
+
+/// Certification types the business holds, in claim order
+pub fn held_claim_types(data: &ClaimData) -> Vec<String> {
+    CLAIM_TYPES
+        .iter()
+        .filter(|t| data.certifications.iter().any(|c| c.certification_type == **t))
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Explain whether a lot may carry a claim; the first rule that fails is
+/// the explanation
+pub fn derive_claim(lot_id: Uuid, claim: &str, data: &ClaimData) -> ClaimDerivation {
+    let harvests: Vec<HarvestCoverage> = data
+        .harvests
+        .get(&lot_id)
+        .into_iter()
+        .flatten()
+        .map(|(plot_id, date)| HarvestCoverage {
+            plot_name: data.plot_names.get(plot_id).cloned().unwrap_or_default(),
+            harvest_date: *date,
+            certificate_number: data
+                .certifications
+                .iter()
+                .find(|c| c.certification_type == claim && covers(c, *plot_id, *date))
+                .map(|c| c.certificate_number.clone()),
+        })
+        .collect();
+    let sources: Vec<SourceClaim> = data
+        .sources
+        .get(&lot_id)
+        .into_iter()
+        .flatten()
+        .map(|source| SourceClaim {
+            traceability_code: data.lot_codes.get(source).cloned().unwrap_or_default(),
+            claimed: lot_claims(*source, data).contains(claim),
+        })
+        .collect();
+    let downgraded = data.downgraded.get(&lot_id).is_some_and(|d| d.contains(claim));
+    let claimed = lot_claims(lot_id, data).contains(claim);
+
+    let uncovered = harvests.iter().filter(|h| h.certificate_number.is_none()).count();
+    let missing: Vec<&str> = sources
+        .iter()
+        .filter(|s| !s.claimed)
+        .map(|s| s.traceability_code.as_str())
+        .collect();
+    let (explanation, explanation_th) = if downgraded {
+        (
+            "Claim dropped when certified and non-certified lots were blended".to_string(),
+            "ยกเลิกการอ้างการรับรองเมื่อผสมล็อตที่ได้รับรองกับล็อตที่ไม่ได้รับรอง".to_string(),
+        )
+    } else if harvests.is_empty() && sources.is_empty() {
+        (
+            "No harvests recorded for this lot".to_string(),
+            "ยังไม่มีการบันทึกการเก็บเกี่ยวของล็อตนี้".to_string(),
+        )
+    } else if uncovered > 0 {
+        (
+            format!(
+                "{} of {} harvests come from plots without a valid certificate on the harvest date",
+                uncovered,
+                harvests.len()
+            ),
+            format!(
+                "{} จาก {} การเก็บเกี่ยวมาจากแปลงที่ไม่มีใบรับรองที่มีผลในวันเก็บเกี่ยว",
+                uncovered,
+                harvests.len()
+            ),
+        )
+    } else if !missing.is_empty() {
+        (
+            format!("Blended with lots that do not carry this claim: {}", missing.join(", ")),
+            format!("ผสมกับล็อตที่ไม่ได้รับรองนี้: {}", missing.join(", ")),
+        )
+    } else if claimed {
+        (
+            "All harvests come from certified plots within the certificate's validity and all blended lots carry this claim"
+                .to_string(),
+            "การเก็บเกี่ยวทั้งหมดมาจากแปลงที่ได้รับรองในช่วงที่ใบรับรองมีผล และล็อตที่ผสมทั้งหมดได้รับรองนี้".to_string(),
+        )
+    } else {
+        (
+            "Lots further back in the blend do not carry this claim".to_string(),
+            "ล็อตต้นทางก่อนหน้าในการผสมไม่ได้รับรองนี้".to_string(),
+        )
+    };
+
+    ClaimDerivation {
+        certification_type: claim.to_string(),
+        claimed,
+        harvests,
+        sources,
+        downgraded,
+        explanation,
+        explanation_th,
+    }
+}
+
+/// Certificates of a type covering any harvest in the lot or the lots
+/// blended into it
+pub fn supporting_certifications<'a>(lot_id: Uuid, claim: &str, data: &'a ClaimData) -> Vec<&'a ClaimCertification> {
+    let mut lots = vec![lot_id];
+    let mut seen = HashSet::new();
+    let mut supporting: Vec<&ClaimCertification> = Vec::new();
+    while let Some(lot) = lots.pop() {
+        if !seen.insert(lot) {
+            continue;
+        }
+        for (plot_id, date) in data.harvests.get(&lot).into_iter().flatten() {
+            for certification in &data.certifications {
+                if certification.certification_type == claim
+                    && covers(certification, *plot_id, *date)
+                    && !supporting.iter().any(|c| c.id == certification.id)
+                {
+                    supporting.push(certification);
+                }
+            }
+        }
+        lots.extend(data.sources.get(&lot).into_iter().flatten());
+    }
+    supporting
+}
+
+/// Check requested downgrades name known certification types
+pub fn validate_claim_types(field: &str, claims: &[String]) -> AppResult<()> {
+    match claims.iter().find(|c| !CLAIM_TYPES.contains(&c.as_str())) {
+        Some(unknown) => Err(AppError::Validation {
+            field: field.to_string(),
+            message: format!("Unknown certification type: {}", unknown),
+            message_th: format!("ประเภทใบรับรองไม่ถูกต้อง: {}", unknown),
+        }),
+        None => Ok(()),
+    }
+}
+
+impl LotCertificationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Load the certifications, harvests, blend sources and downgrades of a
+    /// business's lots
+    pub async fn load(&self, business_id: Uuid) -> AppResult<ClaimData> {
+        let certifications = sqlx::query_as::<_, ClaimCertification>(
+            r#"
+            SELECT id, certification_type::TEXT AS certification_type, certification_name,
+                   certification_body, certificate_number, scope::TEXT AS scope,
+                   plot_id, issue_date, expiration_date
+            FROM certifications
+            WHERE business_id = $1 AND is_active = true
+            ORDER BY expiration_date DESC
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut data = ClaimData {
+            certifications,
+            ..Default::default()
+        };
+
+        let harvests = sqlx::query_as::<_, (Uuid, Uuid, NaiveDate)>(
+            "SELECT lot_id, plot_id, harvest_date FROM harvests WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        for (lot_id, plot_id, harvest_date) in harvests {
+            data.harvests.entry(lot_id).or_default().push((plot_id, harvest_date));
+        }
+
+        let sources = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT ls.lot_id, ls.source_lot_id
+            FROM lot_sources ls
+            JOIN lots l ON l.id = ls.lot_id
+            WHERE l.business_id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        for (lot_id, source_lot_id) in sources {
+            data.sources.entry(lot_id).or_default().push(source_lot_id);
+        }
+
+        let lots = sqlx::query_as::<_, (Uuid, String, Vec<String>)>(
+            "SELECT id, traceability_code, downgraded_claims FROM lots WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        for (lot_id, traceability_code, claims) in lots {
+            if !claims.is_empty() {
+                data.downgraded.insert(lot_id, claims.into_iter().collect());
+            }
+            data.lot_codes.insert(lot_id, traceability_code);
+        }
+
+        data.plot_names = sqlx::query_as::<_, (Uuid, String)>("SELECT id, name FROM plots WHERE business_id = $1")
+            .bind(business_id)
+            .fetch_all(&self.db)
+            .await?
+            .into_iter()
+            .collect();
+
+        Ok(data)
+    }
+
+    /// Claims each of the given lots may carry
+    pub async fn claims_for_lots(
+        &self,
+        business_id: Uuid,
+        lot_ids: &[Uuid],
+    ) -> AppResult<HashMap<Uuid, BTreeSet<String>>> {
+        let data = self.load(business_id).await?;
+        Ok(lot_ids.iter().map(|id| (*id, lot_claims(*id, &data))).collect())
+    }
+}
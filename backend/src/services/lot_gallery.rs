@@ -0,0 +1,388 @@
+//! Lot photo gallery
+//!
+//! Photos attached to a lot are kept in the `media` table in the order the
+//! business sets. Each photo is public or internal (the default), and one
+//! public photo may be the lot's cover. The public traceability page shows
+//! the public photos only, cover first, so defect and working photos stay
+//! in the app.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::external::s3::S3Client;
+use crate::services::grading_photo::{decode_photo, extension};
+
+/// Most photos in a lot's gallery
+pub const MAX_PHOTOS_PER_LOT: i64 = 30;
+
+/// Lot photo gallery service
+#[derive(Clone)]
+pub struct LotGalleryService {
+    db: PgPool,
+    s3: Option<S3Client>,
+}
+
+/// Who sees a photo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PhotoVisibility {
+    /// Shown on the public traceability page
+    Public,
+    /// Only shown in the app
+    #[default]
+    Internal,
+}
+
+/// Photo in a lot's gallery
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LotPhoto {
+    pub id: Uuid,
+    pub lot_id: Uuid,
+    pub s3_key: String,
+    pub file_type: String,
+    pub original_filename: Option<String>,
+    pub file_size_bytes: Option<i64>,
+    pub description: Option<String>,
+    pub description_th: Option<String>,
+    pub visibility: PhotoVisibility,
+    /// Position in the gallery, from 1
+    pub sort_order: i32,
+    pub is_cover: bool,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+const PHOTO_SELECT: &str = r#"
+    SELECT id, entity_id AS lot_id, s3_key, file_type, original_filename, file_size_bytes,
+           description, description_th, visibility, sort_order, is_cover, uploaded_by, created_at
+    FROM media
+"#;
+
+/// Input for adding a photo to a lot's gallery
+#[derive(Debug, Deserialize)]
+pub struct UploadLotPhotoInput {
+    /// JPEG, PNG or WebP
+    pub image_base64: String,
+    pub original_filename: Option<String>,
+    pub description: Option<String>,
+    pub description_th: Option<String>,
+    /// Internal unless given
+    #[serde(default)]
+    pub visibility: PhotoVisibility,
+}
+
+/// Input for editing a gallery photo; fields left out stay as they are
+#[derive(Debug, Deserialize)]
+pub struct UpdateLotPhotoInput {
+    /// An empty string clears the description
+    pub description: Option<String>,
+    pub description_th: Option<String>,
+    /// Making the cover internal leaves the lot without a cover
+    pub visibility: Option<PhotoVisibility>,
+}
+
+/// Input for reordering a lot's gallery
+#[derive(Debug, Deserialize)]
+pub struct ReorderLotPhotosInput {
+    /// Every photo of the lot, in the new order
+    pub photo_ids: Vec<Uuid>,
+}
+
+/// Public photo shown on the traceability page
+#[derive(Debug, Clone, Serialize)]
+pub struct TracePhoto {
+    pub url: String,
+    pub description: Option<String>,
+    pub description_th: Option<String>,
+    pub is_cover: bool,
+}
+
+/// Check a new gallery order names each of the lot's photos once
+pub fn validate_photo_order(photo_ids: &[Uuid], order: &[Uuid]) -> AppResult<()> {
+    let mut seen: Vec<Uuid> = Vec::with_capacity(order.len());
+    for id in order {
+        if !photo_ids.contains(id) {
+            return Err(AppError::NotFound("Lot photo".to_string()));
+        }
+        if seen.contains(id) {
+            return Err(AppError::Validation {
+                field: "photo_ids".to_string(),
+                message: "Each photo can appear once in the order".to_string(),
+                message_th: "แต่ละรูปภาพระบุได้เพียงครั้งเดียวในลำดับ".to_string(),
+            });
+        }
+        seen.push(*id);
+    }
+    if seen.len() != photo_ids.len() {
+        return Err(AppError::Validation {
+            field: "photo_ids".to_string(),
+            message: format!("List all {} photos of the lot in the new order", photo_ids.len()),
+            message_th: format!("กรุณาระบุรูปภาพทั้ง {} รูปของล็อตตามลำดับใหม่", photo_ids.len()),
+        });
+    }
+    Ok(())
+}
+
+/// Photos shown on the traceability page: the public ones in gallery
+/// order, with the cover moved to the front
+pub fn public_gallery(photos: &[LotPhoto]) -> Vec<&LotPhoto> {
+    let mut public: Vec<&LotPhoto> = photos
+        .iter()
+        .filter(|p| p.visibility == PhotoVisibility::Public)
+        .collect();
+    public.sort_by_key(|p| (!p.is_cover, p.sort_order, p.created_at));
+    public
+}
+
+/// S3 key of a lot photo
+pub fn lot_photo_key(business_id: Uuid, lot_id: Uuid, photo_id: Uuid, content_type: &str) -> String {
+    format!("lots/{}/{}/{}.{}", business_id, lot_id, photo_id, extension(content_type))
+}
+
+/// Descriptions as stored: trimmed, with blank ones cleared
+fn clean_description(description: String) -> Option<String> {
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+impl LotGalleryService {
+    /// Create a service using the S3 bucket configured in the environment
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db,
+            s3: S3Client::from_env(),
+        }
+    }
+
+    fn s3(&self) -> AppResult<&S3Client> {
+        self.s3
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("S3 media storage not configured".to_string()))
+    }
+
+    async fn check_lot(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<()> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn photos(&self, lot_id: Uuid) -> AppResult<Vec<LotPhoto>> {
+        let photos = sqlx::query_as::<_, LotPhoto>(&format!(
+            "{} WHERE entity_type = 'lot' AND entity_id = $1 ORDER BY sort_order, created_at",
+            PHOTO_SELECT
+        ))
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(photos)
+    }
+
+    async fn photo(&self, business_id: Uuid, lot_id: Uuid, photo_id: Uuid) -> AppResult<LotPhoto> {
+        sqlx::query_as::<_, LotPhoto>(&format!(
+            "{} WHERE id = $1 AND entity_type = 'lot' AND entity_id = $2 AND business_id = $3",
+            PHOTO_SELECT
+        ))
+        .bind(photo_id)
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot photo".to_string()))
+    }
+
+    /// A lot's gallery in order, public and internal photos alike
+    pub async fn list_photos(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<Vec<LotPhoto>> {
+        self.check_lot(business_id, lot_id).await?;
+        self.photos(lot_id).await
+    }
+
+    /// Add a photo to the end of a lot's gallery, storing it in S3
+    pub async fn upload_photo(
+        &self,
+        business_id: Uuid,
+        lot_id: Uuid,
+        user_id: Uuid,
+        input: UploadLotPhotoInput,
+    ) -> AppResult<LotPhoto> {
+        self.check_lot(business_id, lot_id).await?;
+        let (image, content_type) = decode_photo(&input.image_base64)?;
+        let sha256 = format!("{:x}", Sha256::digest(&image));
+
+        let (photos, duplicate, last) = sqlx::query_as::<_, (i64, bool, i32)>(
+            r#"
+            SELECT COUNT(*), COALESCE(BOOL_OR(sha256 = $2), FALSE), COALESCE(MAX(sort_order), 0)
+            FROM media
+            WHERE entity_type = 'lot' AND entity_id = $1
+            "#,
+        )
+        .bind(lot_id)
+        .bind(&sha256)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(AppError::Conflict {
+                resource: "lot_photo".to_string(),
+                message: "This photo is already in the lot's gallery".to_string(),
+                message_th: "รูปภาพนี้อยู่ในแกลเลอรีของล็อตแล้ว".to_string(),
+            });
+        }
+        if photos >= MAX_PHOTOS_PER_LOT {
+            return Err(AppError::Validation {
+                field: "image_base64".to_string(),
+                message: format!("A lot can have at most {} photos", MAX_PHOTOS_PER_LOT),
+                message_th: format!("ล็อตมีรูปภาพได้ไม่เกิน {} รูป", MAX_PHOTOS_PER_LOT),
+            });
+        }
+
+        let photo_id = Uuid::new_v4();
+        let s3_key = lot_photo_key(business_id, lot_id, photo_id, content_type);
+        let file_size_bytes = image.len() as i64;
+        self.s3()?.put_object(&s3_key, image, content_type).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO media (id, business_id, entity_type, entity_id, file_type, s3_key, original_filename,
+                               file_size_bytes, description, description_th, visibility, sort_order, sha256, uploaded_by)
+            VALUES ($1, $2, 'lot', $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(photo_id)
+        .bind(business_id)
+        .bind(lot_id)
+        .bind(content_type)
+        .bind(&s3_key)
+        .bind(input.original_filename.and_then(clean_description))
+        .bind(file_size_bytes)
+        .bind(input.description.and_then(clean_description))
+        .bind(input.description_th.and_then(clean_description))
+        .bind(input.visibility)
+        .bind(last + 1)
+        .bind(&sha256)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+
+        self.photo(business_id, lot_id, photo_id).await
+    }
+
+    /// Edit a photo's descriptions or visibility
+    pub async fn update_photo(
+        &self,
+        business_id: Uuid,
+        lot_id: Uuid,
+        photo_id: Uuid,
+        input: UpdateLotPhotoInput,
+    ) -> AppResult<LotPhoto> {
+        let photo = self.photo(business_id, lot_id, photo_id).await?;
+        let visibility = input.visibility.unwrap_or(photo.visibility);
+
+        sqlx::query(
+            r#"
+            UPDATE media
+            SET description = $2, description_th = $3, visibility = $4,
+                is_cover = is_cover AND $4 = 'public'
+            WHERE id = $1
+            "#,
+        )
+        .bind(photo_id)
+        .bind(input.description.map_or(photo.description, clean_description))
+        .bind(input.description_th.map_or(photo.description_th, clean_description))
+        .bind(visibility)
+        .execute(&self.db)
+        .await?;
+
+        self.photo(business_id, lot_id, photo_id).await
+    }
+
+    /// Make a public photo the lot's cover, in place of the current one
+    pub async fn set_cover(&self, business_id: Uuid, lot_id: Uuid, photo_id: Uuid) -> AppResult<LotPhoto> {
+        let photo = self.photo(business_id, lot_id, photo_id).await?;
+        if photo.visibility != PhotoVisibility::Public {
+            return Err(AppError::Validation {
+                field: "visibility".to_string(),
+                message: "Only a public photo can be the cover".to_string(),
+                message_th: "รูปปกต้องเป็นรูปภาพสาธารณะ".to_string(),
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query("UPDATE media SET is_cover = FALSE WHERE entity_type = 'lot' AND entity_id = $1 AND is_cover")
+            .bind(lot_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE media SET is_cover = TRUE WHERE id = $1")
+            .bind(photo_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.photo(business_id, lot_id, photo_id).await
+    }
+
+    /// Put a lot's photos in a new order
+    pub async fn reorder_photos(
+        &self,
+        business_id: Uuid,
+        lot_id: Uuid,
+        input: ReorderLotPhotosInput,
+    ) -> AppResult<Vec<LotPhoto>> {
+        let photos = self.list_photos(business_id, lot_id).await?;
+        let photo_ids: Vec<Uuid> = photos.iter().map(|p| p.id).collect();
+        validate_photo_order(&photo_ids, &input.photo_ids)?;
+
+        let mut tx = self.db.begin().await?;
+        for (position, photo_id) in input.photo_ids.iter().enumerate() {
+            sqlx::query("UPDATE media SET sort_order = $2 WHERE id = $1")
+                .bind(photo_id)
+                .bind(position as i32 + 1)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        self.photos(lot_id).await
+    }
+
+    /// Remove a photo from the gallery and from S3; with its file gone it
+    /// is not kept in the recycle bin
+    pub async fn delete_photo(&self, business_id: Uuid, lot_id: Uuid, photo_id: Uuid) -> AppResult<()> {
+        let photo = self.photo(business_id, lot_id, photo_id).await?;
+        self.s3()?.delete_object(&photo.s3_key).await?;
+        sqlx::query("DELETE FROM media WHERE id = $1")
+            .bind(photo_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Public photos of a lot for its traceability page; none when media
+    /// storage is not configured
+    pub async fn trace_photos(&self, lot_id: Uuid) -> AppResult<Vec<TracePhoto>> {
+        let Some(s3) = self.s3.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let photos = self.photos(lot_id).await?;
+        Ok(public_gallery(&photos)
+            .into_iter()
+            .map(|p| TracePhoto {
+                url: s3.object_url(&p.s3_key),
+                description: p.description.clone(),
+                description_th: p.description_th.clone(),
+                is_cover: p.is_cover,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,441 @@
+//! Insurance policies for lots
+//!
+//! Exporters insure high-value microlots while stored and in transit. A
+//! policy covers one lot, optionally for a single shipment (sales order), and
+//! records the insurer, coverage and validity. Policies close to lapsing are
+//! reminded to the business owner, and valid policies marked for buyers are
+//! printed on the lot spec sheet.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+
+/// Days before `valid_until` a policy counts as expiring and is reminded
+pub const EXPIRY_ALERT_DAYS: i64 = 30;
+
+/// Lot insurance service
+#[derive(Clone)]
+pub struct LotInsuranceService {
+    db: PgPool,
+}
+
+/// What the policy covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageType {
+    /// Warehouse storage (fire, theft, water damage)
+    Storage,
+    /// Cargo in transit to the buyer
+    Transit,
+    /// Storage and transit
+    AllRisk,
+}
+
+impl CoverageType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoverageType::Storage => "storage",
+            CoverageType::Transit => "transit",
+            CoverageType::AllRisk => "all_risk",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "storage" => Some(CoverageType::Storage),
+            "transit" => Some(CoverageType::Transit),
+            "all_risk" => Some(CoverageType::AllRisk),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self, thai: bool) -> &'static str {
+        match (self, thai) {
+            (CoverageType::Storage, false) => "Storage",
+            (CoverageType::Storage, true) => "ระหว่างจัดเก็บ",
+            (CoverageType::Transit, false) => "Transit",
+            (CoverageType::Transit, true) => "ระหว่างขนส่ง",
+            (CoverageType::AllRisk, false) => "All risks",
+            (CoverageType::AllRisk, true) => "คุ้มครองทุกความเสี่ยง",
+        }
+    }
+}
+
+/// Where a policy is in its validity period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsuranceStatus {
+    /// Cover has not started yet
+    Upcoming,
+    Active,
+    /// Lapses within [`EXPIRY_ALERT_DAYS`]
+    Expiring,
+    Expired,
+}
+
+/// Status of a policy on a given day; the policy is valid on both end dates
+pub fn policy_status(valid_from: NaiveDate, valid_until: NaiveDate, today: NaiveDate) -> InsuranceStatus {
+    if today < valid_from {
+        InsuranceStatus::Upcoming
+    } else if today > valid_until {
+        InsuranceStatus::Expired
+    } else if (valid_until - today).num_days() <= EXPIRY_ALERT_DAYS {
+        InsuranceStatus::Expiring
+    } else {
+        InsuranceStatus::Active
+    }
+}
+
+/// Database row for a policy
+#[derive(Debug, sqlx::FromRow)]
+struct PolicyRow {
+    id: Uuid,
+    business_id: Uuid,
+    lot_id: Uuid,
+    lot_code: String,
+    sales_order_id: Option<Uuid>,
+    order_number: Option<String>,
+    policy_number: String,
+    insurer: String,
+    coverage_type: String,
+    insured_amount: Decimal,
+    currency: String,
+    deductible_amount: Option<Decimal>,
+    valid_from: NaiveDate,
+    valid_until: NaiveDate,
+    document_name: Option<String>,
+    file_url: Option<String>,
+    include_in_buyer_pack: bool,
+    notes: Option<String>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+/// Insurance policy with its current status
+#[derive(Debug, Clone, Serialize)]
+pub struct InsurancePolicy {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+    pub lot_code: String,
+    pub sales_order_id: Option<Uuid>,
+    pub order_number: Option<String>,
+    pub policy_number: String,
+    pub insurer: String,
+    pub coverage_type: CoverageType,
+    pub insured_amount: Decimal,
+    pub currency: String,
+    pub deductible_amount: Option<Decimal>,
+    pub valid_from: NaiveDate,
+    pub valid_until: NaiveDate,
+    pub status: InsuranceStatus,
+    /// Negative once expired
+    pub days_until_expiry: i64,
+    pub document_name: Option<String>,
+    pub file_url: Option<String>,
+    pub include_in_buyer_pack: bool,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a policy
+#[derive(Debug, Deserialize)]
+pub struct RecordInsurancePolicyInput {
+    pub lot_id: Uuid,
+    /// The shipment covered, for transit cover of one order
+    pub sales_order_id: Option<Uuid>,
+    pub policy_number: String,
+    pub insurer: String,
+    pub coverage_type: CoverageType,
+    pub insured_amount: Decimal,
+    /// ISO 4217 code (default THB)
+    pub currency: Option<String>,
+    pub deductible_amount: Option<Decimal>,
+    pub valid_from: NaiveDate,
+    pub valid_until: NaiveDate,
+    pub document_name: Option<String>,
+    pub file_url: Option<String>,
+    /// Print on the lot spec sheet while valid (default true)
+    pub include_in_buyer_pack: Option<bool>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing policies
+#[derive(Debug, Default, Deserialize)]
+pub struct InsurancePolicyQuery {
+    pub lot_id: Option<Uuid>,
+    pub sales_order_id: Option<Uuid>,
+    pub status: Option<InsuranceStatus>,
+}
+
+const POLICY_SELECT: &str = r#"
+    SELECT p.id, p.business_id, p.lot_id, l.traceability_code AS lot_code,
+           p.sales_order_id, so.order_number, p.policy_number, p.insurer, p.coverage_type,
+           p.insured_amount, p.currency, p.deductible_amount, p.valid_from, p.valid_until,
+           p.document_name, p.file_url, p.include_in_buyer_pack, p.notes, p.created_by, p.created_at
+    FROM lot_insurance_policies p
+    JOIN lots l ON l.id = p.lot_id
+    LEFT JOIN sales_orders so ON so.id = p.sales_order_id
+"#;
+
+impl LotInsuranceService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a policy for a lot or one of its shipments
+    pub async fn record(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordInsurancePolicyInput,
+    ) -> AppResult<InsurancePolicy> {
+        let policy_number = input.policy_number.trim();
+        if policy_number.is_empty() {
+            return Err(AppError::Validation {
+                field: "policy_number".to_string(),
+                message: "Policy number is required".to_string(),
+                message_th: "ต้องระบุเลขที่กรมธรรม์".to_string(),
+            });
+        }
+        let insurer = input.insurer.trim();
+        if insurer.is_empty() {
+            return Err(AppError::Validation {
+                field: "insurer".to_string(),
+                message: "Insurer is required".to_string(),
+                message_th: "ต้องระบุบริษัทประกันภัย".to_string(),
+            });
+        }
+        if input.insured_amount <= Decimal::ZERO {
+            return Err(AppError::Validation {
+                field: "insured_amount".to_string(),
+                message: "Insured amount must be greater than zero".to_string(),
+                message_th: "ทุนประกันต้องมากกว่าศูนย์".to_string(),
+            });
+        }
+        if input.deductible_amount.is_some_and(|d| d < Decimal::ZERO || d > input.insured_amount) {
+            return Err(AppError::Validation {
+                field: "deductible_amount".to_string(),
+                message: "Deductible must be between zero and the insured amount".to_string(),
+                message_th: "ค่าเสียหายส่วนแรกต้องไม่ติดลบและไม่เกินทุนประกัน".to_string(),
+            });
+        }
+        if input.valid_until < input.valid_from {
+            return Err(AppError::Validation {
+                field: "valid_until".to_string(),
+                message: "Policy cannot end before it starts".to_string(),
+                message_th: "วันสิ้นสุดความคุ้มครองต้องไม่ก่อนวันเริ่มต้น".to_string(),
+            });
+        }
+        let currency = input
+            .currency
+            .as_deref()
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| "THB".to_string());
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(AppError::Validation {
+                field: "currency".to_string(),
+                message: "Currency must be a 3-letter code such as THB or USD".to_string(),
+                message_th: "สกุลเงินต้องเป็นรหัส 3 ตัวอักษร เช่น THB หรือ USD".to_string(),
+            });
+        }
+
+        let lot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !lot_exists {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        if let Some(order_id) = input.sales_order_id {
+            let order_lot = sqlx::query_scalar::<_, Option<Uuid>>(
+                "SELECT lot_id FROM sales_orders WHERE id = $1 AND business_id = $2",
+            )
+            .bind(order_id)
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Sales order".to_string()))?;
+            if order_lot.is_some_and(|lot_id| lot_id != input.lot_id) {
+                return Err(AppError::Validation {
+                    field: "sales_order_id".to_string(),
+                    message: "The sales order ships a different lot".to_string(),
+                    message_th: "คำสั่งขายนี้เป็นของล็อตอื่น".to_string(),
+                });
+            }
+        }
+
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM lot_insurance_policies
+                WHERE business_id = $1 AND lot_id = $2 AND policy_number = $3
+            )
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(policy_number)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(AppError::Conflict {
+                resource: "lot_insurance_policy".to_string(),
+                message: format!("Policy {} is already recorded for this lot", policy_number),
+                message_th: format!("กรมธรรม์ {} ถูกบันทึกสำหรับล็อตนี้แล้ว", policy_number),
+            });
+        }
+
+        let file_url = input.file_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+        let document_name = file_url.map(|url| {
+            input
+                .document_name
+                .clone()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url).to_string())
+        });
+
+        let policy_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO lot_insurance_policies (
+                business_id, lot_id, sales_order_id, policy_number, insurer, coverage_type,
+                insured_amount, currency, deductible_amount, valid_from, valid_until,
+                document_name, file_url, include_in_buyer_pack, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.sales_order_id)
+        .bind(policy_number)
+        .bind(insurer)
+        .bind(input.coverage_type.as_str())
+        .bind(input.insured_amount)
+        .bind(&currency)
+        .bind(input.deductible_amount)
+        .bind(input.valid_from)
+        .bind(input.valid_until)
+        .bind(document_name)
+        .bind(file_url)
+        .bind(input.include_in_buyer_pack.unwrap_or(true))
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get(business_id, policy_id).await
+    }
+
+    /// Get a policy
+    pub async fn get(&self, business_id: Uuid, policy_id: Uuid) -> AppResult<InsurancePolicy> {
+        let row = sqlx::query_as::<_, PolicyRow>(&format!(
+            "{} WHERE p.id = $1 AND p.business_id = $2",
+            POLICY_SELECT
+        ))
+        .bind(policy_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Insurance policy".to_string()))?;
+
+        Ok(Self::row_to_policy(row, Utc::now().date_naive()))
+    }
+
+    /// List policies, soonest expiry first
+    pub async fn list(
+        &self,
+        business_id: Uuid,
+        query: &InsurancePolicyQuery,
+    ) -> AppResult<Vec<InsurancePolicy>> {
+        let rows = sqlx::query_as::<_, PolicyRow>(&format!(
+            r#"
+            {}
+            WHERE p.business_id = $1
+              AND ($2::UUID IS NULL OR p.lot_id = $2)
+              AND ($3::UUID IS NULL OR p.sales_order_id = $3)
+            ORDER BY p.valid_until, p.created_at
+            "#,
+            POLICY_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.sales_order_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let today = Utc::now().date_naive();
+        Ok(rows
+            .into_iter()
+            .map(|row| Self::row_to_policy(row, today))
+            .filter(|p| query.status.is_none_or(|status| p.status == status))
+            .collect())
+    }
+
+    /// Policies of a lot marked for buyers that cover today
+    pub async fn buyer_pack_policies(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<Vec<InsurancePolicy>> {
+        let rows = sqlx::query_as::<_, PolicyRow>(&format!(
+            r#"
+            {}
+            WHERE p.business_id = $1 AND p.lot_id = $2 AND p.include_in_buyer_pack
+              AND CURRENT_DATE BETWEEN p.valid_from AND p.valid_until
+            ORDER BY p.valid_until DESC
+            "#,
+            POLICY_SELECT
+        ))
+        .bind(business_id)
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let today = Utc::now().date_naive();
+        Ok(rows.into_iter().map(|row| Self::row_to_policy(row, today)).collect())
+    }
+
+    /// Delete a policy, keeping it in the recycle bin
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, policy_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::InsurancePolicy, policy_id)
+            .await
+    }
+
+    fn row_to_policy(row: PolicyRow, today: NaiveDate) -> InsurancePolicy {
+        InsurancePolicy {
+            id: row.id,
+            business_id: row.business_id,
+            lot_id: row.lot_id,
+            lot_code: row.lot_code,
+            sales_order_id: row.sales_order_id,
+            order_number: row.order_number,
+            policy_number: row.policy_number,
+            insurer: row.insurer,
+            // The column is constrained to the known coverage types
+            coverage_type: CoverageType::from_str(&row.coverage_type).unwrap_or(CoverageType::AllRisk),
+            insured_amount: row.insured_amount,
+            currency: row.currency,
+            deductible_amount: row.deductible_amount,
+            valid_from: row.valid_from,
+            valid_until: row.valid_until,
+            status: policy_status(row.valid_from, row.valid_until, today),
+            days_until_expiry: (row.valid_until - today).num_days(),
+            document_name: row.document_name,
+            file_url: row.file_url,
+            include_in_buyer_pack: row.include_in_buyer_pack,
+            notes: row.notes,
+            created_by: row.created_by,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,515 @@
+//! Lot recommendations for incoming orders
+//!
+//! Given an order spec (quantity, minimum cupping score, process and
+//! certifications), ranks the lots that can fill the order on their own and
+//! proposes blends of lots that reach the score together. Quantities held
+//! by active reservations are not available, and lots past crop are ranked
+//! after current-crop lots with the same result.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::LotStage;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::certification::CertificationType;
+
+/// Green coffee older than this is considered past crop
+pub const PAST_CROP_DAYS: i32 = 365;
+
+/// Components in a proposed blend when the order does not say
+pub const DEFAULT_BLEND_COMPONENTS: usize = 3;
+pub const MAX_BLEND_COMPONENTS: usize = 4;
+
+/// Highest-scoring lots considered as blend components
+const MAX_BLEND_CANDIDATES: usize = 12;
+
+const DEFAULT_RECOMMENDATIONS: usize = 10;
+
+/// Lot recommendation service
+#[derive(Clone)]
+pub struct LotRecommendationService {
+    db: PgPool,
+}
+
+/// Order requirements to match lots against
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderSpec {
+    pub quantity_kg: Decimal,
+    pub min_cupping_score: Option<Decimal>,
+    /// Processing method of the lot's latest processing record, e.g. washed
+    pub process: Option<String>,
+    /// Every listed certification must cover the lot
+    #[serde(default)]
+    pub certifications: Vec<CertificationType>,
+    /// Defaults to green_bean
+    pub stage: Option<LotStage>,
+    pub max_age_days: Option<i32>,
+    /// 1 disables blends
+    pub max_blend_components: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A lot's availability and quality as seen by the recommender
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LotCandidate {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    /// Current weight minus active reservations
+    pub available_kg: Decimal,
+    /// Final score of the latest cupping sample
+    pub cupping_score: Option<Decimal>,
+    pub process: Option<String>,
+    /// Certification types covering the lot
+    pub certifications: Vec<String>,
+    /// Days since processing finished, or since the last harvest
+    pub age_days: i32,
+}
+
+/// A lot that fills the whole order on its own
+#[derive(Debug, Clone, Serialize)]
+pub struct LotMatch {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub available_kg: Decimal,
+    pub cupping_score: Option<Decimal>,
+    pub process: Option<String>,
+    pub certifications: Vec<String>,
+    pub age_days: i32,
+    pub past_crop: bool,
+}
+
+/// A lot's share of a proposed blend
+#[derive(Debug, Clone, Serialize)]
+pub struct BlendComponent {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub cupping_score: Decimal,
+    pub age_days: i32,
+    pub quantity_kg: Decimal,
+    pub proportion_percent: Decimal,
+}
+
+/// Lots that fill the order together, highest scores first
+#[derive(Debug, Clone, Serialize)]
+pub struct BlendMatch {
+    /// Weighted average of the component scores
+    pub blended_score: Decimal,
+    pub past_crop: bool,
+    pub components: Vec<BlendComponent>,
+}
+
+/// Ranked single lots and blends for an order
+#[derive(Debug, Clone, Serialize)]
+pub struct LotRecommendations {
+    pub quantity_kg: Decimal,
+    pub lots: Vec<LotMatch>,
+    pub blends: Vec<BlendMatch>,
+}
+
+/// Quantity of a lot held for a buyer
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LotReservation {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub quantity_kg: Decimal,
+    pub reserved_for: String,
+    pub reserved_until: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for reserving part of a lot
+#[derive(Debug, Deserialize)]
+pub struct CreateReservationInput {
+    pub lot_id: Uuid,
+    pub quantity_kg: Decimal,
+    pub reserved_for: String,
+    pub reserved_until: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing reservations
+#[derive(Debug, Default, Deserialize)]
+pub struct ReservationQuery {
+    pub lot_id: Option<Uuid>,
+    /// Include reservations that ended before today
+    #[serde(default)]
+    pub include_expired: bool,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Whether a lot meets the order's process, certification and age
+/// requirements; quantity and score are checked separately
+pub fn meets_requirements(lot: &LotCandidate, spec: &OrderSpec) -> bool {
+    let process_ok = match &spec.process {
+        Some(process) => lot
+            .process
+            .as_deref()
+            .is_some_and(|p| p.eq_ignore_ascii_case(process.trim())),
+        None => true,
+    };
+    let certified = spec
+        .certifications
+        .iter()
+        .all(|c| lot.certifications.iter().any(|held| held == c.as_str()));
+    let fresh = spec.max_age_days.iter().all(|max| lot.age_days <= *max);
+    process_ok && certified && fresh
+}
+
+/// Lots that fill the order alone: current crop first, then by score
+/// (unscored last) and age
+pub fn rank_lots(candidates: &[LotCandidate], spec: &OrderSpec) -> Vec<LotMatch> {
+    let mut lots: Vec<&LotCandidate> = candidates
+        .iter()
+        .filter(|lot| meets_requirements(lot, spec))
+        .filter(|lot| lot.available_kg >= spec.quantity_kg)
+        .filter(|lot| match spec.min_cupping_score {
+            Some(min) => lot.cupping_score.is_some_and(|s| s >= min),
+            None => true,
+        })
+        .collect();
+    lots.sort_by(|a, b| {
+        (a.age_days > PAST_CROP_DAYS)
+            .cmp(&(b.age_days > PAST_CROP_DAYS))
+            .then(b.cupping_score.cmp(&a.cupping_score))
+            .then(a.age_days.cmp(&b.age_days))
+    });
+
+    lots.into_iter()
+        .map(|lot| LotMatch {
+            lot_id: lot.lot_id,
+            traceability_code: lot.traceability_code.clone(),
+            name: lot.name.clone(),
+            available_kg: lot.available_kg,
+            cupping_score: lot.cupping_score,
+            process: lot.process.clone(),
+            certifications: lot.certifications.clone(),
+            age_days: lot.age_days,
+            past_crop: lot.age_days > PAST_CROP_DAYS,
+        })
+        .collect()
+}
+
+/// All index combinations of `k` out of `n`, in lexicographic order
+pub(crate) fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 || k > n {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let mut indices: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(indices.clone());
+        // Advance the rightmost index that still has room
+        let Some(i) = (0..k).rev().find(|&i| indices[i] < n - k + i) else {
+            return result;
+        };
+        indices[i] += 1;
+        for j in i + 1..k {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+/// Blends of 2 to `max_components` scored lots that fill the order and reach
+/// the minimum score. Each blend takes as much as it can from its best lots,
+/// and blends that would leave a component unused are skipped.
+pub fn propose_blends(
+    candidates: &[LotCandidate],
+    spec: &OrderSpec,
+    max_components: usize,
+) -> Vec<BlendMatch> {
+    let mut scored: Vec<(&LotCandidate, Decimal)> = candidates
+        .iter()
+        .filter(|lot| meets_requirements(lot, spec) && lot.available_kg > Decimal::ZERO)
+        .filter_map(|lot| lot.cupping_score.map(|score| (lot, score)))
+        .collect();
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.age_days.cmp(&b.0.age_days)));
+    scored.truncate(MAX_BLEND_CANDIDATES);
+
+    let mut blends = Vec::new();
+    for size in 2..=max_components.min(MAX_BLEND_COMPONENTS) {
+        for combo in combinations(scored.len(), size) {
+            let mut remaining = spec.quantity_kg;
+            let mut components = Vec::with_capacity(size);
+            let mut weighted = Decimal::ZERO;
+            for &i in &combo {
+                let (lot, score) = scored[i];
+                let quantity = lot.available_kg.min(remaining);
+                if quantity <= Decimal::ZERO {
+                    break;
+                }
+                remaining -= quantity;
+                weighted += quantity * score;
+                components.push(BlendComponent {
+                    lot_id: lot.lot_id,
+                    traceability_code: lot.traceability_code.clone(),
+                    name: lot.name.clone(),
+                    cupping_score: score,
+                    age_days: lot.age_days,
+                    quantity_kg: quantity,
+                    proportion_percent: (quantity / spec.quantity_kg * Decimal::from(100)).round_dp(2),
+                });
+            }
+            if remaining > Decimal::ZERO || components.len() < size {
+                continue;
+            }
+
+            let blended_score = (weighted / spec.quantity_kg).round_dp(2);
+            if spec.min_cupping_score.iter().all(|min| blended_score >= *min) {
+                blends.push(BlendMatch {
+                    blended_score,
+                    past_crop: components.iter().any(|c| c.age_days > PAST_CROP_DAYS),
+                    components,
+                });
+            }
+        }
+    }
+
+    blends.sort_by(|a, b| {
+        a.past_crop
+            .cmp(&b.past_crop)
+            .then(b.blended_score.cmp(&a.blended_score))
+            .then(a.components.len().cmp(&b.components.len()))
+    });
+    blends
+}
+
+impl LotRecommendationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Rank lots and blends that can fill an order
+    pub async fn recommend(&self, business_id: Uuid, spec: &OrderSpec) -> AppResult<LotRecommendations> {
+        if spec.quantity_kg <= Decimal::ZERO {
+            return Err(validation(
+                "quantity_kg",
+                "Order quantity must be greater than 0",
+                "ปริมาณคำสั่งซื้อต้องมากกว่า 0",
+            ));
+        }
+        let stage = spec.stage.unwrap_or(LotStage::GreenBean);
+        if stage == LotStage::Sold {
+            return Err(validation(
+                "stage",
+                "Stage must be cherry, parchment, green_bean or roasted_bean",
+                "ขั้นตอนต้องเป็น cherry, parchment, green_bean หรือ roasted_bean",
+            ));
+        }
+        let max_components = spec.max_blend_components.unwrap_or(DEFAULT_BLEND_COMPONENTS);
+        if !(1..=MAX_BLEND_COMPONENTS).contains(&max_components) {
+            return Err(validation(
+                "max_blend_components",
+                &format!("Blends can have 1 to {} components", MAX_BLEND_COMPONENTS),
+                &format!("เบลนด์มีได้ 1 ถึง {} ล็อต", MAX_BLEND_COMPONENTS),
+            ));
+        }
+        let limit = spec.limit.unwrap_or(DEFAULT_RECOMMENDATIONS).clamp(1, 100);
+
+        let candidates = self.candidates(business_id, stage).await?;
+        let mut lots = rank_lots(&candidates, spec);
+        lots.truncate(limit);
+        let mut blends = propose_blends(&candidates, spec, max_components);
+        blends.truncate(limit);
+
+        Ok(LotRecommendations {
+            quantity_kg: spec.quantity_kg,
+            lots,
+            blends,
+        })
+    }
+
+    /// Lots at a stage with unreserved weight left
+    async fn candidates(&self, business_id: Uuid, stage: LotStage) -> AppResult<Vec<LotCandidate>> {
+        // Plot-scoped certifications cover a lot only when every harvest in
+        // it comes from the certified plot
+        let candidates = sqlx::query_as::<_, LotCandidate>(
+            r#"
+            SELECT l.id AS lot_id, l.traceability_code, l.name,
+                   l.current_weight_kg - COALESCE(r.reserved_kg, 0) AS available_kg,
+                   c.final_score AS cupping_score,
+                   p.method AS process,
+                   ARRAY(
+                       SELECT DISTINCT cert.certification_type::TEXT
+                       FROM certifications cert
+                       WHERE cert.business_id = l.business_id
+                         AND cert.is_active AND cert.expiration_date >= CURRENT_DATE
+                         AND (cert.plot_id IS NULL OR (
+                             h.harvest_count > 0 AND NOT EXISTS (
+                                 SELECT 1 FROM harvests hp
+                                 WHERE hp.lot_id = l.id AND hp.plot_id <> cert.plot_id
+                             )
+                         ))
+                   ) AS certifications,
+                   (CURRENT_DATE - COALESCE(p.end_date, h.last_harvest, l.created_at::DATE))::INT AS age_days
+            FROM lots l
+            LEFT JOIN LATERAL (
+                SELECT SUM(quantity_kg) AS reserved_kg
+                FROM lot_reservations
+                WHERE lot_id = l.id AND (reserved_until IS NULL OR reserved_until >= CURRENT_DATE)
+            ) r ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT cs.final_score
+                FROM cupping_samples cs
+                JOIN cupping_sessions s ON s.id = cs.session_id
+                WHERE cs.lot_id = l.id
+                ORDER BY s.session_date DESC, cs.created_at DESC
+                LIMIT 1
+            ) c ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT method, end_date
+                FROM processing_records
+                WHERE lot_id = l.id
+                ORDER BY start_date DESC, created_at DESC
+                LIMIT 1
+            ) p ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT MAX(harvest_date) AS last_harvest, COUNT(*) AS harvest_count
+                FROM harvests
+                WHERE lot_id = l.id
+            ) h ON TRUE
+            WHERE l.business_id = $1 AND l.stage = $2
+              AND l.current_weight_kg - COALESCE(r.reserved_kg, 0) > 0
+            ORDER BY l.traceability_code
+            "#,
+        )
+        .bind(business_id)
+        .bind(stage)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(candidates)
+    }
+
+    /// Reserve part of a lot for a buyer
+    pub async fn create_reservation(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateReservationInput,
+    ) -> AppResult<LotReservation> {
+        let reserved_for = input.reserved_for.trim();
+        if reserved_for.is_empty() {
+            return Err(validation(
+                "reserved_for",
+                "Buyer or order reference is required",
+                "ต้องระบุผู้ซื้อหรือเลขที่คำสั่งซื้อ",
+            ));
+        }
+        if input.quantity_kg <= Decimal::ZERO {
+            return Err(validation(
+                "quantity_kg",
+                "Reserved quantity must be greater than 0",
+                "ปริมาณที่จองต้องมากกว่า 0",
+            ));
+        }
+
+        let available = sqlx::query_scalar::<_, Decimal>(
+            r#"
+            SELECT l.current_weight_kg - COALESCE((
+                SELECT SUM(quantity_kg) FROM lot_reservations
+                WHERE lot_id = l.id AND (reserved_until IS NULL OR reserved_until >= CURRENT_DATE)
+            ), 0)
+            FROM lots l
+            WHERE l.id = $1 AND l.business_id = $2
+            "#,
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        if input.quantity_kg > available {
+            return Err(validation(
+                "quantity_kg",
+                &format!("Only {} kg of this lot is unreserved", available.max(Decimal::ZERO)),
+                &format!("ล็อตนี้เหลือที่ยังไม่ถูกจองเพียง {} กก.", available.max(Decimal::ZERO)),
+            ));
+        }
+
+        let reservation = sqlx::query_as::<_, LotReservation>(
+            r#"
+            WITH inserted AS (
+                INSERT INTO lot_reservations (
+                    business_id, lot_id, quantity_kg, reserved_for, reserved_until, notes, created_by
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING *
+            )
+            SELECT r.id, r.business_id, r.lot_id, l.traceability_code, r.quantity_kg,
+                   r.reserved_for, r.reserved_until, r.notes, r.created_by, r.created_at
+            FROM inserted r
+            JOIN lots l ON l.id = r.lot_id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.quantity_kg)
+        .bind(reserved_for)
+        .bind(input.reserved_until)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(reservation)
+    }
+
+    /// List reservations, newest first
+    pub async fn list_reservations(
+        &self,
+        business_id: Uuid,
+        query: &ReservationQuery,
+    ) -> AppResult<Vec<LotReservation>> {
+        let reservations = sqlx::query_as::<_, LotReservation>(
+            r#"
+            SELECT r.id, r.business_id, r.lot_id, l.traceability_code, r.quantity_kg,
+                   r.reserved_for, r.reserved_until, r.notes, r.created_by, r.created_at
+            FROM lot_reservations r
+            JOIN lots l ON l.id = r.lot_id
+            WHERE r.business_id = $1
+              AND ($2::UUID IS NULL OR r.lot_id = $2)
+              AND ($3 OR r.reserved_until IS NULL OR r.reserved_until >= CURRENT_DATE)
+            ORDER BY r.created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.include_expired)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(reservations)
+    }
+
+    /// Release a reservation
+    pub async fn delete_reservation(&self, business_id: Uuid, reservation_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM lot_reservations WHERE id = $1 AND business_id = $2")
+            .bind(reservation_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Lot reservation".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+//! Lot stage reconciliation
+//!
+//! A lot's stage is moved on by the services that record its progress:
+//! completing processing makes it green bean and completing a roast makes it
+//! roasted bean. A stage left behind by a failed or interrupted update hides
+//! the lot from the views of its real stage. The stage each lot should have
+//! reached is re-derived from its records; lots whose stage lags behind are
+//! reported and can be moved on. A stage ahead of the records is left alone,
+//! as lots bought in as green or roasted coffee have no records to show.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::lot::LotStage;
+
+/// Hours between reconciliations of a business by the background job
+const RECONCILE_INTERVAL_HOURS: i64 = 6;
+
+/// Businesses reconciled per job run
+const RECONCILE_BATCH_SIZE: i64 = 20;
+
+/// Lot stage service
+#[derive(Clone)]
+pub struct LotStageService {
+    db: PgPool,
+}
+
+/// Record showing how far a lot has come
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageEvidence {
+    /// Processing completed with a green bean weight
+    ProcessingCompleted,
+    /// Green bean grading
+    Graded,
+    /// Completed roast session
+    RoastCompleted,
+}
+
+impl StageEvidence {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "processing_completed" => Some(StageEvidence::ProcessingCompleted),
+            "graded" => Some(StageEvidence::Graded),
+            "roast_completed" => Some(StageEvidence::RoastCompleted),
+            _ => None,
+        }
+    }
+
+    /// Stage a lot has at least reached once the record exists
+    pub fn implied_stage(&self) -> LotStage {
+        match self {
+            StageEvidence::ProcessingCompleted | StageEvidence::Graded => LotStage::GreenBean,
+            StageEvidence::RoastCompleted => LotStage::RoastedBean,
+        }
+    }
+}
+
+/// Record of a lot implying a stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageRecord {
+    pub evidence: StageEvidence,
+    pub record_id: Uuid,
+    pub date: NaiveDate,
+}
+
+/// Lot whose stage lags behind its records
+#[derive(Debug, Clone, Serialize)]
+pub struct StageMismatch {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub stage: LotStage,
+    pub expected_stage: LotStage,
+    /// Latest record implying the expected stage
+    pub evidence: StageEvidence,
+    pub record_id: Uuid,
+    pub record_date: NaiveDate,
+}
+
+/// Lots of a business whose stage lags behind their records
+#[derive(Debug, Serialize)]
+pub struct StageCheck {
+    pub lots_checked: usize,
+    pub mismatches: Vec<StageMismatch>,
+}
+
+/// Lots to move on to their expected stage
+#[derive(Debug, Default, Deserialize)]
+pub struct RepairStagesInput {
+    /// Only these lots; every mismatched lot when omitted
+    pub lot_ids: Option<Vec<Uuid>>,
+}
+
+/// Lots moved on to their expected stage
+#[derive(Debug, Serialize)]
+pub struct StageRepair {
+    pub repaired: Vec<StageMismatch>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LotStageRow {
+    id: Uuid,
+    traceability_code: String,
+    name: String,
+    stage: LotStage,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StageRecordRow {
+    lot_id: Uuid,
+    evidence: String,
+    record_id: Uuid,
+    record_date: NaiveDate,
+}
+
+/// The record implying the furthest stage, the latest of them on a tie
+pub fn furthest_record(records: &[StageRecord]) -> Option<&StageRecord> {
+    records
+        .iter()
+        .max_by_key(|record| (record.evidence.implied_stage(), record.date))
+}
+
+/// Stage a lot should be moved on to, with the record showing it; None when
+/// its stage is at or past what its records show
+pub fn expected_stage(stage: LotStage, records: &[StageRecord]) -> Option<(LotStage, &StageRecord)> {
+    let record = furthest_record(records)?;
+    let expected = record.evidence.implied_stage();
+    (stage < expected).then_some((expected, record))
+}
+
+impl LotStageService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Lots of the business whose stage lags behind their records
+    pub async fn check(&self, business_id: Uuid) -> AppResult<StageCheck> {
+        let lots = sqlx::query_as::<_, LotStageRow>(
+            "SELECT id, traceability_code, name, stage FROM lots WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let rows = sqlx::query_as::<_, StageRecordRow>(
+            r#"
+            SELECT p.lot_id, 'processing_completed' AS evidence, p.id AS record_id, p.end_date AS record_date
+            FROM processing_records p
+            JOIN lots l ON l.id = p.lot_id
+            WHERE l.business_id = $1 AND p.end_date IS NOT NULL AND p.green_bean_weight_kg IS NOT NULL
+            UNION ALL
+            SELECT g.lot_id, 'graded', g.id, g.grading_date
+            FROM green_bean_grades g
+            JOIN lots l ON l.id = g.lot_id
+            WHERE l.business_id = $1
+            UNION ALL
+            SELECT r.lot_id, 'roast_completed', r.id, r.session_date
+            FROM roast_sessions r
+            WHERE r.business_id = $1 AND r.status = 'completed'
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut records: HashMap<Uuid, Vec<StageRecord>> = HashMap::new();
+        for row in rows {
+            if let Some(evidence) = StageEvidence::from_str(&row.evidence) {
+                records.entry(row.lot_id).or_default().push(StageRecord {
+                    evidence,
+                    record_id: row.record_id,
+                    date: row.record_date,
+                });
+            }
+        }
+
+        let lots_checked = lots.len();
+        let mismatches = lots
+            .into_iter()
+            .filter_map(|lot| {
+                let lot_records = records.get(&lot.id)?;
+                let (expected_stage, record) = expected_stage(lot.stage, lot_records)?;
+                Some(StageMismatch {
+                    lot_id: lot.id,
+                    traceability_code: lot.traceability_code,
+                    name: lot.name,
+                    stage: lot.stage,
+                    expected_stage,
+                    evidence: record.evidence,
+                    record_id: record.record_id,
+                    record_date: record.date,
+                })
+            })
+            .collect();
+
+        Ok(StageCheck {
+            lots_checked,
+            mismatches,
+        })
+    }
+
+    /// Move lagging lots on to their expected stage; `user_id` is None when
+    /// the background job repairs them. Each move is audited.
+    pub async fn repair(
+        &self,
+        business_id: Uuid,
+        user_id: Option<Uuid>,
+        input: &RepairStagesInput,
+    ) -> AppResult<StageRepair> {
+        if input.lot_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            return Err(AppError::Validation {
+                field: "lot_ids".to_string(),
+                message: "List at least one lot, or omit lot_ids to repair every lot".to_string(),
+                message_th: "ระบุล็อตอย่างน้อยหนึ่งล็อต หรือไม่ระบุ lot_ids เพื่อแก้ไขทุกล็อต".to_string(),
+            });
+        }
+
+        let check = self.check(business_id).await?;
+        let mut repaired = Vec::new();
+        let mut tx = self.db.begin().await?;
+        for mismatch in check.mismatches {
+            if input.lot_ids.as_ref().is_some_and(|ids| !ids.contains(&mismatch.lot_id)) {
+                continue;
+            }
+
+            // Only if the stage has not moved since the check
+            let updated = sqlx::query("UPDATE lots SET stage = $1 WHERE id = $2 AND business_id = $3 AND stage = $4")
+                .bind(mismatch.expected_stage)
+                .bind(mismatch.lot_id)
+                .bind(business_id)
+                .bind(mismatch.stage)
+                .execute(&mut *tx)
+                .await?;
+            if updated.rows_affected() == 0 {
+                continue;
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO audit_log (business_id, user_id, action, resource_type, resource_id, old_values, new_values)
+                VALUES ($1, $2, 'reconcile_lot_stage', 'lot', $3, $4, $5)
+                "#,
+            )
+            .bind(business_id)
+            .bind(user_id)
+            .bind(mismatch.lot_id)
+            .bind(serde_json::json!({ "stage": mismatch.stage }))
+            .bind(serde_json::json!({
+                "stage": mismatch.expected_stage,
+                "evidence": mismatch.evidence,
+                "record_id": mismatch.record_id,
+            }))
+            .execute(&mut *tx)
+            .await?;
+
+            repaired.push(mismatch);
+        }
+        tx.commit().await?;
+
+        Ok(StageRepair { repaired })
+    }
+
+    /// Reconcile businesses not reconciled for a while. Returns the number
+    /// of lots moved on
+    pub async fn reconcile_due(&self, now: DateTime<Utc>) -> AppResult<usize> {
+        let due = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM businesses
+            WHERE lot_stages_checked_at IS NULL OR lot_stages_checked_at < $1
+            ORDER BY lot_stages_checked_at NULLS FIRST
+            LIMIT $2
+            "#,
+        )
+        .bind(now - Duration::hours(RECONCILE_INTERVAL_HOURS))
+        .bind(RECONCILE_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut repaired = 0;
+        for business_id in due {
+            sqlx::query("UPDATE businesses SET lot_stages_checked_at = $2 WHERE id = $1")
+                .bind(business_id)
+                .bind(now)
+                .execute(&self.db)
+                .await?;
+            match self.repair(business_id, None, &RepairStagesInput::default()).await {
+                Ok(repair) => {
+                    for mismatch in &repair.repaired {
+                        tracing::warn!(
+                            "Lot {} stage moved from {} to {} after {:?} {}",
+                            mismatch.lot_id,
+                            mismatch.stage.as_str(),
+                            mismatch.expected_stage.as_str(),
+                            mismatch.evidence,
+                            mismatch.record_id
+                        );
+                    }
+                    repaired += repair.repaired.len();
+                }
+                Err(e) => tracing::error!("Lot stage reconciliation of business {} failed: {}", business_id, e),
+            }
+        }
+        Ok(repaired)
+    }
+}
@@ -0,0 +1,656 @@
+//! Lot transfers between businesses on the platform
+//!
+//! When a farm sells a lot to a roaster that also uses the platform, the
+//! seller offers it to the buyer's business (found by its business code),
+//! for the whole lot or part of it. The buyer accepts or declines; the
+//! seller may cancel while the offer is pending. On acceptance the coffee
+//! becomes a new lot of the buyer, with the buyer's traceability code and a
+//! link to the seller's lot, whose traceability record the buyer can read
+//! but not change. The sale is recorded in the seller's inventory and the
+//! purchase in the buyer's, and the seller's lot is marked sold once all of
+//! it has gone.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::notification::create_lot_transfer_notification;
+use crate::services::traceability::TraceabilityView;
+use crate::services::{LotService, NotificationService, TraceabilityService};
+
+/// Lot transfer service
+#[derive(Clone)]
+pub struct LotTransferService {
+    db: PgPool,
+}
+
+/// Where a transfer is in the handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Cancelled,
+}
+
+impl TransferStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferStatus::Pending => "pending",
+            TransferStatus::Accepted => "accepted",
+            TransferStatus::Declined => "declined",
+            TransferStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Step a business takes on a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferAction {
+    /// Buyer takes the lot
+    Accept,
+    /// Buyer turns the lot down
+    Decline,
+    /// Seller withdraws the offer
+    Cancel,
+}
+
+/// Side of a transfer a business is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferParty {
+    Seller,
+    Buyer,
+}
+
+/// Lot transfer as seen by either business
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LotTransfer {
+    pub id: Uuid,
+    pub seller_business_id: Uuid,
+    pub seller_business_name: String,
+    pub buyer_business_id: Uuid,
+    pub buyer_business_name: String,
+    /// Seller's lot
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub lot_name: String,
+    pub quantity_kg: Decimal,
+    pub stage: String,
+    pub unit_price: Option<Decimal>,
+    pub total_price: Option<Decimal>,
+    pub currency: String,
+    /// pending, accepted, declined or cancelled
+    pub status: String,
+    pub notes: Option<String>,
+    pub response_notes: Option<String>,
+    /// Lot created in the buyer's business once accepted
+    pub buyer_lot_id: Option<Uuid>,
+    pub buyer_traceability_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+}
+
+const TRANSFER_SELECT: &str = r#"
+    SELECT t.id, t.seller_business_id, sb.name AS seller_business_name,
+           t.buyer_business_id, bb.name AS buyer_business_name,
+           t.lot_id, l.traceability_code, l.name AS lot_name,
+           t.quantity_kg, t.stage, t.unit_price, t.unit_price * t.quantity_kg AS total_price,
+           t.currency, t.status, t.notes, t.response_notes,
+           t.buyer_lot_id, bl.traceability_code AS buyer_traceability_code,
+           t.created_at, t.responded_at
+    FROM lot_transfers t
+    JOIN businesses sb ON sb.id = t.seller_business_id
+    JOIN businesses bb ON bb.id = t.buyer_business_id
+    JOIN lots l ON l.id = t.lot_id
+    LEFT JOIN lots bl ON bl.id = t.buyer_lot_id
+"#;
+
+/// Input for offering a lot to another business
+#[derive(Debug, Deserialize)]
+pub struct InitiateTransferInput {
+    pub lot_id: Uuid,
+    /// Business code of the buyer, as in its traceability codes
+    pub buyer_business_code: String,
+    /// The whole lot when left out
+    pub quantity_kg: Option<Decimal>,
+    /// Price per kg
+    pub unit_price: Option<Decimal>,
+    pub currency: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Input for accepting, declining or cancelling a transfer
+#[derive(Debug, Default, Deserialize)]
+pub struct RespondTransferInput {
+    /// Name of the buyer's lot on acceptance; the seller's lot name if left out
+    pub lot_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing transfers
+#[derive(Debug, Default, Deserialize)]
+pub struct TransferQuery {
+    /// incoming (bought) or outgoing (sold); both when left out
+    pub direction: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TransferHeader {
+    seller_business_id: Uuid,
+    buyer_business_id: Uuid,
+    lot_id: Uuid,
+    quantity_kg: Decimal,
+    stage: String,
+    unit_price: Option<Decimal>,
+    currency: String,
+    status: String,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Kilograms to transfer: the requested quantity, or all that is not
+/// already offered in other pending transfers
+pub fn transfer_quantity(requested: Option<Decimal>, available: Decimal) -> AppResult<Decimal> {
+    let quantity = requested.unwrap_or(available);
+    if quantity <= Decimal::ZERO {
+        return Err(validation(
+            "quantity_kg",
+            "No coffee left in the lot to transfer",
+            "ไม่มีกาแฟเหลือในล็อตให้โอน",
+        ));
+    }
+    if quantity > available {
+        return Err(AppError::Validation {
+            field: "quantity_kg".to_string(),
+            message: format!("Only {} kg of the lot is available to transfer", available),
+            message_th: format!("ล็อตนี้มีกาแฟให้โอนได้เพียง {} กก.", available),
+        });
+    }
+    Ok(quantity)
+}
+
+/// Status a transfer moves to when a party takes an action, or None when
+/// the action is not theirs to take or the transfer is no longer pending
+pub fn next_status(status: &str, party: TransferParty, action: TransferAction) -> Option<TransferStatus> {
+    if status != TransferStatus::Pending.as_str() {
+        return None;
+    }
+    match (party, action) {
+        (TransferParty::Buyer, TransferAction::Accept) => Some(TransferStatus::Accepted),
+        (TransferParty::Buyer, TransferAction::Decline) => Some(TransferStatus::Declined),
+        (TransferParty::Seller, TransferAction::Cancel) => Some(TransferStatus::Cancelled),
+        _ => None,
+    }
+}
+
+/// Stage of the seller's lot once a quantity has left it; sold when empty
+pub fn stage_after_transfer(stage: &str, remaining_kg: Decimal) -> &str {
+    if remaining_kg <= Decimal::ZERO {
+        "sold"
+    } else {
+        stage
+    }
+}
+
+fn validate_currency(currency: Option<&str>) -> AppResult<String> {
+    let currency = currency.unwrap_or("THB").trim().to_uppercase();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(validation(
+            "currency",
+            "Currency must be a 3-letter code such as THB or USD",
+            "สกุลเงินต้องเป็นรหัส 3 ตัวอักษร เช่น THB หรือ USD",
+        ));
+    }
+    Ok(currency)
+}
+
+fn not_pending() -> AppError {
+    AppError::Conflict {
+        resource: "lot_transfer".to_string(),
+        message: "The transfer has already been accepted, declined or cancelled".to_string(),
+        message_th: "การโอนนี้ได้รับการตอบรับ ปฏิเสธ หรือยกเลิกไปแล้ว".to_string(),
+    }
+}
+
+impl LotTransferService {
+    /// Create a new LotTransferService instance
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Transfers the business sold or bought, newest first
+    pub async fn list_transfers(&self, business_id: Uuid, query: TransferQuery) -> AppResult<Vec<LotTransfer>> {
+        let parties = match query.direction.as_deref() {
+            None => "(t.seller_business_id = $1 OR t.buyer_business_id = $1)",
+            Some("incoming") => "t.buyer_business_id = $1",
+            Some("outgoing") => "t.seller_business_id = $1",
+            Some(_) => {
+                return Err(validation(
+                    "direction",
+                    "Direction must be incoming or outgoing",
+                    "ทิศทางต้องเป็น incoming หรือ outgoing",
+                ))
+            }
+        };
+
+        let transfers = sqlx::query_as::<_, LotTransfer>(&format!(
+            "{} WHERE {} AND ($2::text IS NULL OR t.status = $2) ORDER BY t.created_at DESC",
+            TRANSFER_SELECT, parties
+        ))
+        .bind(business_id)
+        .bind(&query.status)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(transfers)
+    }
+
+    /// A transfer the business is the seller or buyer of
+    pub async fn get_transfer(&self, business_id: Uuid, transfer_id: Uuid) -> AppResult<LotTransfer> {
+        sqlx::query_as::<_, LotTransfer>(&format!(
+            "{} WHERE t.id = $1 AND (t.seller_business_id = $2 OR t.buyer_business_id = $2)",
+            TRANSFER_SELECT
+        ))
+        .bind(transfer_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot transfer".to_string()))
+    }
+
+    /// Offer a lot, or part of it, to another business on the platform
+    pub async fn initiate_transfer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: InitiateTransferInput,
+    ) -> AppResult<LotTransfer> {
+        let currency = validate_currency(input.currency.as_deref())?;
+        if input.unit_price.is_some_and(|p| p < Decimal::ZERO) {
+            return Err(validation("unit_price", "Price cannot be negative", "ราคาต้องไม่ติดลบ"));
+        }
+
+        let buyer = sqlx::query_as::<_, (Uuid, String)>("SELECT id, name FROM businesses WHERE business_code = $1")
+            .bind(input.buyer_business_code.trim().to_uppercase())
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Business".to_string()))?;
+        if buyer.0 == business_id {
+            return Err(validation(
+                "buyer_business_code",
+                "A lot cannot be transferred to its own business",
+                "ไม่สามารถโอนล็อตให้ธุรกิจของตนเองได้",
+            ));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let lot = sqlx::query_as::<_, (String, Decimal)>(
+            "SELECT stage, current_weight_kg FROM lots WHERE id = $1 AND business_id = $2 FOR UPDATE",
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+        if lot.0 == "sold" {
+            return Err(validation("lot_id", "The lot has already been sold", "ล็อตนี้ขายไปแล้ว"));
+        }
+
+        let offered = sqlx::query_scalar::<_, Decimal>(
+            "SELECT COALESCE(SUM(quantity_kg), 0) FROM lot_transfers WHERE lot_id = $1 AND status = 'pending'",
+        )
+        .bind(input.lot_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let quantity = transfer_quantity(input.quantity_kg, lot.1 - offered)?;
+
+        let transfer_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO lot_transfers (
+                seller_business_id, buyer_business_id, lot_id, quantity_kg, stage,
+                unit_price, currency, notes, initiated_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(buyer.0)
+        .bind(input.lot_id)
+        .bind(quantity)
+        .bind(&lot.0)
+        .bind(input.unit_price)
+        .bind(&currency)
+        .bind(input.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let transfer = self.get_transfer(business_id, transfer_id).await?;
+        self.notify(
+            transfer.buyer_business_id,
+            &transfer.seller_business_name,
+            &transfer,
+            "offered",
+            "เสนอโอน",
+        )
+        .await?;
+        Ok(transfer)
+    }
+
+    /// Accept a transfer offered to the business: the coffee becomes a new
+    /// lot of the buyer and moves between the two inventories
+    pub async fn accept_transfer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        transfer_id: Uuid,
+        input: RespondTransferInput,
+    ) -> AppResult<LotTransfer> {
+        let business_code = sqlx::query_scalar::<_, String>("SELECT business_code FROM businesses WHERE id = $1")
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        let mut tx = self.db.begin().await?;
+
+        let transfer = self.lock_transfer(&mut tx, business_id, transfer_id).await?;
+        if transfer.buyer_business_id != business_id {
+            return Err(validation(
+                "transfer_id",
+                "Only the buyer can accept a transfer",
+                "เฉพาะผู้ซื้อเท่านั้นที่ตอบรับการโอนได้",
+            ));
+        }
+        next_status(&transfer.status, TransferParty::Buyer, TransferAction::Accept).ok_or_else(not_pending)?;
+
+        let seller_lot = sqlx::query_as::<_, (String, Decimal, String)>(
+            "SELECT name, current_weight_kg, stage FROM lots WHERE id = $1 FOR UPDATE",
+        )
+        .bind(transfer.lot_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if seller_lot.1 < transfer.quantity_kg {
+            return Err(AppError::Conflict {
+                resource: "lot_transfer".to_string(),
+                message: format!(
+                    "The seller's lot has only {} kg left of the {} kg offered",
+                    seller_lot.1, transfer.quantity_kg
+                ),
+                message_th: format!(
+                    "ล็อตของผู้ขายเหลือเพียง {} กก. จาก {} กก. ที่เสนอ",
+                    seller_lot.1, transfer.quantity_kg
+                ),
+            });
+        }
+        let names = sqlx::query_as::<_, (String, String)>(
+            "SELECT (SELECT name FROM businesses WHERE id = $1), (SELECT name FROM businesses WHERE id = $2)",
+        )
+        .bind(transfer.seller_business_id)
+        .bind(transfer.buyer_business_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let lot_name = input
+            .lot_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(&seller_lot.0)
+            .to_string();
+        let traceability_code = LotService::new(self.db.clone())
+            .generate_traceability_code(business_id, &business_code)
+            .await?;
+        let qr_code_url = format!("https://trace.coffeeqm.com/{}", traceability_code);
+
+        let buyer_lot_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO lots (business_id, traceability_code, name, stage, current_weight_kg, qr_code_url, transferred_from_lot_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(&traceability_code)
+        .bind(&lot_name)
+        .bind(&transfer.stage)
+        .bind(transfer.quantity_kg)
+        .bind(&qr_code_url)
+        .bind(transfer.lot_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let remaining = seller_lot.1 - transfer.quantity_kg;
+        sqlx::query("UPDATE lots SET current_weight_kg = $2, stage = $3, updated_at = NOW() WHERE id = $1")
+            .bind(transfer.lot_id)
+            .bind(remaining)
+            .bind(stage_after_transfer(&seller_lot.2, remaining))
+            .execute(&mut *tx)
+            .await?;
+
+        // Sale out of the seller's lot, purchase into the buyer's
+        let total_price = transfer.unit_price.map(|p| p * transfer.quantity_kg);
+        for (owner, lot_id, transaction_type, direction, counterparty, created_by) in [
+            (transfer.seller_business_id, transfer.lot_id, "sale", "out", &names.1, None),
+            (transfer.buyer_business_id, buyer_lot_id, "purchase", "in", &names.0, Some(user_id)),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO inventory_transactions (
+                    business_id, lot_id, transaction_type, quantity_kg, quantity_original, quantity_unit,
+                    direction, stage, reference_type, reference_id, counterparty_name,
+                    unit_price, total_price, currency, created_by
+                )
+                VALUES ($1, $2, $3::inventory_transaction_type, $4, $4, 'kg', $5, $6, 'lot_transfer', $7, $8, $9, $10, $11, $12)
+                "#,
+            )
+            .bind(owner)
+            .bind(lot_id)
+            .bind(transaction_type)
+            .bind(transfer.quantity_kg)
+            .bind(direction)
+            .bind(&transfer.stage)
+            .bind(transfer_id)
+            .bind(counterparty)
+            .bind(transfer.unit_price)
+            .bind(total_price)
+            .bind(&transfer.currency)
+            .bind(created_by)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE lot_transfers
+            SET status = $2, buyer_lot_id = $3, response_notes = $4, responded_by = $5, responded_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(transfer_id)
+        .bind(TransferStatus::Accepted.as_str())
+        .bind(buyer_lot_id)
+        .bind(input.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let transfer = self.get_transfer(business_id, transfer_id).await?;
+        self.notify(
+            transfer.seller_business_id,
+            &transfer.buyer_business_name,
+            &transfer,
+            "accepted",
+            "ตอบรับ",
+        )
+        .await?;
+        Ok(transfer)
+    }
+
+    /// Turn down a transfer offered to the business
+    pub async fn decline_transfer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        transfer_id: Uuid,
+        input: RespondTransferInput,
+    ) -> AppResult<LotTransfer> {
+        self.close_transfer(business_id, user_id, transfer_id, TransferParty::Buyer, TransferAction::Decline, input)
+            .await
+    }
+
+    /// Withdraw a pending transfer the business offered
+    pub async fn cancel_transfer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        transfer_id: Uuid,
+        input: RespondTransferInput,
+    ) -> AppResult<LotTransfer> {
+        self.close_transfer(business_id, user_id, transfer_id, TransferParty::Seller, TransferAction::Cancel, input)
+            .await
+    }
+
+    /// Traceability record of the seller's lot a transfer came from,
+    /// read-only for the buyer once the transfer is accepted
+    pub async fn origin_traceability(&self, business_id: Uuid, transfer_id: Uuid) -> AppResult<TraceabilityView> {
+        let transfer = self.get_transfer(business_id, transfer_id).await?;
+        if transfer.status != TransferStatus::Accepted.as_str() {
+            return Err(validation(
+                "transfer_id",
+                "The origin record is shared once the transfer is accepted",
+                "ข้อมูลต้นทางจะแสดงเมื่อการโอนได้รับการตอบรับแล้ว",
+            ));
+        }
+        TraceabilityService::new(self.db.clone())
+            .get_traceability_view(&transfer.traceability_code, None)
+            .await
+    }
+
+    async fn close_transfer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        transfer_id: Uuid,
+        party: TransferParty,
+        action: TransferAction,
+        input: RespondTransferInput,
+    ) -> AppResult<LotTransfer> {
+        let mut tx = self.db.begin().await?;
+
+        let transfer = self.lock_transfer(&mut tx, business_id, transfer_id).await?;
+        let party_business_id = match party {
+            TransferParty::Seller => transfer.seller_business_id,
+            TransferParty::Buyer => transfer.buyer_business_id,
+        };
+        if party_business_id != business_id {
+            return Err(match party {
+                TransferParty::Seller => validation(
+                    "transfer_id",
+                    "Only the seller can cancel a transfer",
+                    "เฉพาะผู้ขายเท่านั้นที่ยกเลิกการโอนได้",
+                ),
+                TransferParty::Buyer => validation(
+                    "transfer_id",
+                    "Only the buyer can decline a transfer",
+                    "เฉพาะผู้ซื้อเท่านั้นที่ปฏิเสธการโอนได้",
+                ),
+            });
+        }
+        let status = next_status(&transfer.status, party, action).ok_or_else(not_pending)?;
+
+        sqlx::query(
+            r#"
+            UPDATE lot_transfers
+            SET status = $2, response_notes = $3, responded_by = $4, responded_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(transfer_id)
+        .bind(status.as_str())
+        .bind(input.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let transfer = self.get_transfer(business_id, transfer_id).await?;
+        match party {
+            TransferParty::Seller => {
+                self.notify(
+                    transfer.buyer_business_id,
+                    &transfer.seller_business_name,
+                    &transfer,
+                    "cancelled",
+                    "ยกเลิก",
+                )
+                .await?
+            }
+            TransferParty::Buyer => {
+                self.notify(
+                    transfer.seller_business_id,
+                    &transfer.buyer_business_name,
+                    &transfer,
+                    "declined",
+                    "ปฏิเสธ",
+                )
+                .await?
+            }
+        }
+        Ok(transfer)
+    }
+
+    async fn lock_transfer(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        business_id: Uuid,
+        transfer_id: Uuid,
+    ) -> AppResult<TransferHeader> {
+        sqlx::query_as::<_, TransferHeader>(
+            r#"
+            SELECT seller_business_id, buyer_business_id, lot_id, quantity_kg, stage, unit_price, currency, status
+            FROM lot_transfers
+            WHERE id = $1 AND (seller_business_id = $2 OR buyer_business_id = $2)
+            FOR UPDATE
+            "#,
+        )
+        .bind(transfer_id)
+        .bind(business_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot transfer".to_string()))
+    }
+
+    async fn notify(
+        &self,
+        recipient_business_id: Uuid,
+        actor_name: &str,
+        transfer: &LotTransfer,
+        event: &str,
+        event_th: &str,
+    ) -> AppResult<()> {
+        let notification =
+            create_lot_transfer_notification(actor_name, &transfer.traceability_code, event, event_th, transfer.id);
+        NotificationService::new(self.db.clone())
+            .notify_business_owner(recipient_business_id, notification)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+//! Public producer directory and lot marketplace
+//!
+//! Businesses that opt in are listed in a public directory and can offer
+//! selected lots with an indicative price. Buyers search listings without
+//! an account; cupping results are shown as a score band rather than the
+//! exact score, and the quantity never exceeds what is left unreserved.
+//! Inquiries on a listing become sales leads of the producer, and the
+//! buyer gets a private link to negotiate on them.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::LotStage;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::sales::{CreateLeadInput, SalesService};
+
+/// Width of a public cupping score band
+pub const SCORE_BAND_WIDTH: Decimal = Decimal::TWO;
+
+/// Listings returned per page when no limit is given
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Marketplace service
+#[derive(Clone)]
+pub struct MarketplaceService {
+    db: PgPool,
+}
+
+/// Cupping score band, `min` inclusive and `max` exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ScoreBand {
+    pub min: Decimal,
+    pub max: Decimal,
+}
+
+/// Band an average cupping score falls in
+pub fn score_band(score: Decimal) -> ScoreBand {
+    let min = (score / SCORE_BAND_WIDTH).floor() * SCORE_BAND_WIDTH;
+    ScoreBand {
+        min,
+        max: min + SCORE_BAND_WIDTH,
+    }
+}
+
+/// A lot offered by the business
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MarketplaceListing {
+    pub id: Uuid,
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub lot_name: String,
+    pub quantity_kg: Decimal,
+    pub indicative_price_per_kg: Option<Decimal>,
+    pub currency: String,
+    pub is_active: bool,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for listing a lot
+#[derive(Debug, Deserialize)]
+pub struct CreateListingInput {
+    pub lot_id: Uuid,
+    pub quantity_kg: Decimal,
+    pub indicative_price_per_kg: Option<Decimal>,
+    pub currency: Option<String>,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+}
+
+/// Input for changing a listing
+#[derive(Debug, Deserialize)]
+pub struct UpdateListingInput {
+    pub quantity_kg: Option<Decimal>,
+    pub indicative_price_per_kg: Option<Decimal>,
+    pub is_active: Option<bool>,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+}
+
+/// Producer shown with a public listing
+#[derive(Debug, Clone, Serialize)]
+pub struct ListingProducer {
+    pub id: Uuid,
+    pub name: String,
+    pub province: Option<String>,
+    pub district: Option<String>,
+}
+
+/// Listing as buyers see it
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicListing {
+    pub id: Uuid,
+    pub traceability_code: String,
+    pub lot_name: String,
+    pub stage: String,
+    pub process: Option<String>,
+    pub varieties: Vec<String>,
+    /// Band of the average final cupping score of finalized, revealed
+    /// sessions; absent until the lot is cupped
+    pub score_band: Option<ScoreBand>,
+    pub quantity_kg: Decimal,
+    pub indicative_price_per_kg: Option<Decimal>,
+    pub currency: String,
+    pub notes: Option<String>,
+    pub notes_th: Option<String>,
+    pub producer: ListingProducer,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Search filters for public listings
+#[derive(Debug, Default, Deserialize)]
+pub struct ListingSearchQuery {
+    pub process: Option<String>,
+    pub variety: Option<String>,
+    pub province: Option<String>,
+    /// Lowest score band to include, e.g. 84 for 84-86 and up
+    pub min_score: Option<Decimal>,
+    /// Matches lot and producer names
+    pub q: Option<String>,
+    pub producer_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Search filters for the producer directory
+#[derive(Debug, Default, Deserialize)]
+pub struct ProducerSearchQuery {
+    pub province: Option<String>,
+    pub q: Option<String>,
+}
+
+/// Producer in the public directory
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PublicProducer {
+    pub id: Uuid,
+    pub name: String,
+    pub business_type: String,
+    pub province: Option<String>,
+    pub district: Option<String>,
+    pub description: Option<String>,
+    pub description_th: Option<String>,
+    pub active_listings: i64,
+}
+
+/// Buyer inquiry on a listing
+#[derive(Debug, Deserialize)]
+pub struct InquiryInput {
+    pub buyer_name: String,
+    pub buyer_company: Option<String>,
+    pub buyer_email: Option<String>,
+    pub buyer_phone: Option<String>,
+    pub buyer_country: Option<String>,
+    pub requested_quantity_kg: Option<Decimal>,
+    pub message: Option<String>,
+}
+
+/// Acknowledgement returned to the buyer
+#[derive(Debug, Clone, Serialize)]
+pub struct InquiryReceipt {
+    pub inquiry_id: Uuid,
+    /// Keep private: follows and answers the negotiation at
+    /// `/api/marketplace/inquiries/:access_token`
+    pub access_token: String,
+    pub received_at: DateTime<Utc>,
+    pub message: String,
+    pub message_th: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PublicListingRow {
+    id: Uuid,
+    lot_id: Uuid,
+    traceability_code: String,
+    lot_name: String,
+    stage: String,
+    process: Option<String>,
+    varieties: Vec<String>,
+    score: Option<Decimal>,
+    available_kg: Decimal,
+    indicative_price_per_kg: Option<Decimal>,
+    currency: String,
+    notes: Option<String>,
+    notes_th: Option<String>,
+    business_id: Uuid,
+    business_name: String,
+    province: Option<String>,
+    district: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<PublicListingRow> for PublicListing {
+    fn from(row: PublicListingRow) -> Self {
+        PublicListing {
+            id: row.id,
+            traceability_code: row.traceability_code,
+            lot_name: row.lot_name,
+            stage: row.stage,
+            process: row.process,
+            varieties: row.varieties,
+            score_band: row.score.map(score_band),
+            quantity_kg: row.available_kg,
+            indicative_price_per_kg: row.indicative_price_per_kg,
+            currency: row.currency,
+            notes: row.notes,
+            notes_th: row.notes_th,
+            producer: ListingProducer {
+                id: row.business_id,
+                name: row.business_name,
+                province: row.province,
+                district: row.district,
+            },
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Visible listings: active, of an opted-in producer, with unsold and
+/// unreserved weight left
+const PUBLIC_LISTINGS_SQL: &str = r#"
+    WITH visible AS (
+        SELECT ml.id, ml.lot_id, l.traceability_code, l.name AS lot_name, l.stage,
+               (SELECT pr.method FROM processing_records pr WHERE pr.lot_id = l.id
+                ORDER BY pr.start_date DESC LIMIT 1) AS process,
+               ARRAY(SELECT DISTINCT pv.variety FROM harvests h
+                     JOIN plot_varieties pv ON pv.plot_id = h.plot_id
+                     WHERE h.lot_id = l.id ORDER BY pv.variety) AS varieties,
+               (SELECT AVG(cs.final_score)
+                FROM cupping_samples cs
+                JOIN cupping_sessions s ON s.id = cs.session_id
+                WHERE cs.lot_id = l.id AND s.status = 'finalized'
+                  AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)) AS score,
+               LEAST(ml.quantity_kg, l.current_weight_kg - COALESCE((
+                   SELECT SUM(r.quantity_kg) FROM lot_reservations r
+                   WHERE r.lot_id = l.id AND (r.reserved_until IS NULL OR r.reserved_until >= CURRENT_DATE)
+               ), 0)) AS available_kg,
+               ml.indicative_price_per_kg, ml.currency, ml.notes, ml.notes_th,
+               b.id AS business_id, b.name AS business_name, b.province, b.district, ml.updated_at
+        FROM marketplace_listings ml
+        JOIN lots l ON l.id = ml.lot_id
+        JOIN businesses b ON b.id = ml.business_id
+        WHERE ml.is_active AND b.marketplace_opt_in AND l.stage <> 'sold'
+    )
+    SELECT id, lot_id, traceability_code, lot_name, stage, process, varieties, score, available_kg,
+           indicative_price_per_kg, currency, notes, notes_th, business_id, business_name,
+           province, district, updated_at
+    FROM visible
+    WHERE available_kg > 0
+"#;
+
+const LISTING_COLUMNS: &str = r#"
+    ml.id, ml.lot_id, l.traceability_code, l.name AS lot_name, ml.quantity_kg,
+    ml.indicative_price_per_kg, ml.currency, ml.is_active, ml.notes, ml.notes_th,
+    ml.created_by, ml.created_at, ml.updated_at
+"#;
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Pattern matching `value` anywhere, with LIKE wildcards escaped
+fn contains_pattern(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| format!("%{}%", v.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")))
+}
+
+fn validate_listing_values(quantity_kg: Option<Decimal>, price: Option<Decimal>) -> AppResult<()> {
+    if quantity_kg.is_some_and(|q| q <= Decimal::ZERO) {
+        return Err(validation(
+            "quantity_kg",
+            "Listed quantity must be greater than 0",
+            "ปริมาณที่ลงประกาศต้องมากกว่า 0",
+        ));
+    }
+    if price.is_some_and(|p| p <= Decimal::ZERO) {
+        return Err(validation(
+            "indicative_price_per_kg",
+            "Indicative price must be greater than 0",
+            "ราคาโดยประมาณต้องมากกว่า 0",
+        ));
+    }
+    Ok(())
+}
+
+impl MarketplaceService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// List a lot on the marketplace
+    pub async fn create_listing(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateListingInput,
+    ) -> AppResult<MarketplaceListing> {
+        validate_listing_values(Some(input.quantity_kg), input.indicative_price_per_kg)?;
+        let currency = input.currency.as_deref().unwrap_or("THB").trim().to_uppercase();
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(validation(
+                "currency",
+                "Currency must be a 3-letter code such as THB or USD",
+                "สกุลเงินต้องเป็นรหัส 3 ตัวอักษร เช่น THB หรือ USD",
+            ));
+        }
+
+        let stage = sqlx::query_scalar::<_, LotStage>("SELECT stage FROM lots WHERE id = $1 AND business_id = $2")
+            .bind(input.lot_id)
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+        if stage == LotStage::Sold {
+            return Err(validation("lot_id", "Sold lots cannot be listed", "ไม่สามารถลงประกาศล็อตที่ขายแล้ว"));
+        }
+
+        let listed = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM marketplace_listings WHERE lot_id = $1)")
+            .bind(input.lot_id)
+            .fetch_one(&self.db)
+            .await?;
+        if listed {
+            return Err(AppError::Conflict {
+                resource: "marketplace_listing".to_string(),
+                message: "This lot is already listed".to_string(),
+                message_th: "ล็อตนี้ลงประกาศแล้ว".to_string(),
+            });
+        }
+
+        let listing = sqlx::query_as::<_, MarketplaceListing>(&format!(
+            r#"
+            WITH ml AS (
+                INSERT INTO marketplace_listings (
+                    business_id, lot_id, quantity_kg, indicative_price_per_kg, currency, notes, notes_th, created_by
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING *
+            )
+            SELECT {}
+            FROM ml
+            JOIN lots l ON l.id = ml.lot_id
+            "#,
+            LISTING_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.quantity_kg)
+        .bind(input.indicative_price_per_kg)
+        .bind(&currency)
+        .bind(&input.notes)
+        .bind(&input.notes_th)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(listing)
+    }
+
+    /// Listings of the business, active first
+    pub async fn list_listings(&self, business_id: Uuid) -> AppResult<Vec<MarketplaceListing>> {
+        let listings = sqlx::query_as::<_, MarketplaceListing>(&format!(
+            r#"
+            SELECT {}
+            FROM marketplace_listings ml
+            JOIN lots l ON l.id = ml.lot_id
+            WHERE ml.business_id = $1
+            ORDER BY ml.is_active DESC, ml.updated_at DESC
+            "#,
+            LISTING_COLUMNS
+        ))
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(listings)
+    }
+
+    /// Change quantity, price, notes or visibility of a listing
+    pub async fn update_listing(
+        &self,
+        business_id: Uuid,
+        listing_id: Uuid,
+        input: UpdateListingInput,
+    ) -> AppResult<MarketplaceListing> {
+        validate_listing_values(input.quantity_kg, input.indicative_price_per_kg)?;
+
+        sqlx::query_as::<_, MarketplaceListing>(&format!(
+            r#"
+            WITH ml AS (
+                UPDATE marketplace_listings
+                SET quantity_kg = COALESCE($3, quantity_kg),
+                    indicative_price_per_kg = COALESCE($4, indicative_price_per_kg),
+                    is_active = COALESCE($5, is_active),
+                    notes = COALESCE($6, notes),
+                    notes_th = COALESCE($7, notes_th)
+                WHERE id = $2 AND business_id = $1
+                RETURNING *
+            )
+            SELECT {}
+            FROM ml
+            JOIN lots l ON l.id = ml.lot_id
+            "#,
+            LISTING_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(listing_id)
+        .bind(input.quantity_kg)
+        .bind(input.indicative_price_per_kg)
+        .bind(input.is_active)
+        .bind(&input.notes)
+        .bind(&input.notes_th)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Marketplace listing".to_string()))
+    }
+
+    /// Remove a listing; leads from it are kept
+    pub async fn delete_listing(&self, business_id: Uuid, listing_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM marketplace_listings WHERE id = $1 AND business_id = $2")
+            .bind(listing_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Marketplace listing".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Search visible listings, best score band first
+    pub async fn search_listings(&self, query: &ListingSearchQuery) -> AppResult<Vec<PublicListing>> {
+        let rows = sqlx::query_as::<_, PublicListingRow>(&format!(
+            r#"
+            {}
+              AND ($1::text IS NULL OR process = $1)
+              AND ($2::text IS NULL OR $2 = ANY(varieties))
+              AND ($3::text IS NULL OR province = $3)
+              AND ($4::numeric IS NULL OR FLOOR(score / $5) * $5 >= $4)
+              AND ($6::text IS NULL OR lot_name ILIKE $6 OR business_name ILIKE $6)
+              AND ($7::uuid IS NULL OR business_id = $7)
+            ORDER BY score DESC NULLS LAST, updated_at DESC
+            LIMIT $8 OFFSET $9
+            "#,
+            PUBLIC_LISTINGS_SQL
+        ))
+        .bind(&query.process)
+        .bind(&query.variety)
+        .bind(&query.province)
+        .bind(query.min_score)
+        .bind(SCORE_BAND_WIDTH)
+        .bind(contains_pattern(&query.q))
+        .bind(query.producer_id)
+        .bind(query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, 200))
+        .bind(query.offset.unwrap_or(0).max(0))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(PublicListing::from).collect())
+    }
+
+    /// One visible listing
+    pub async fn get_public_listing(&self, listing_id: Uuid) -> AppResult<PublicListing> {
+        sqlx::query_as::<_, PublicListingRow>(&format!("{} AND id = $1", PUBLIC_LISTINGS_SQL))
+            .bind(listing_id)
+            .fetch_optional(&self.db)
+            .await?
+            .map(PublicListing::from)
+            .ok_or_else(|| AppError::NotFound("Marketplace listing".to_string()))
+    }
+
+    /// Opted-in producers with their number of visible listings
+    pub async fn list_producers(&self, query: &ProducerSearchQuery) -> AppResult<Vec<PublicProducer>> {
+        let producers = sqlx::query_as::<_, PublicProducer>(&format!(
+            r#"
+            WITH listings AS ({})
+            SELECT b.id, b.name, b.business_type, b.province, b.district,
+                   b.marketplace_description AS description,
+                   b.marketplace_description_th AS description_th,
+                   (SELECT COUNT(*) FROM listings WHERE listings.business_id = b.id) AS active_listings
+            FROM businesses b
+            WHERE b.marketplace_opt_in
+              AND ($1::text IS NULL OR b.province = $1)
+              AND ($2::text IS NULL OR b.name ILIKE $2)
+            ORDER BY active_listings DESC, b.name
+            "#,
+            PUBLIC_LISTINGS_SQL
+        ))
+        .bind(&query.province)
+        .bind(contains_pattern(&query.q))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(producers)
+    }
+
+    /// Send an inquiry on a listing to its producer as a sales lead
+    pub async fn submit_inquiry(&self, listing_id: Uuid, input: InquiryInput) -> AppResult<InquiryReceipt> {
+        let listing = sqlx::query_as::<_, PublicListingRow>(&format!("{} AND id = $1", PUBLIC_LISTINGS_SQL))
+            .bind(listing_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Marketplace listing".to_string()))?;
+
+        if input.requested_quantity_kg.is_some_and(|q| q > listing.available_kg) {
+            return Err(AppError::Validation {
+                field: "requested_quantity_kg".to_string(),
+                message: format!("Only {} kg is offered", listing.available_kg.normalize()),
+                message_th: format!("มีเสนอขายเพียง {} กก.", listing.available_kg.normalize()),
+            });
+        }
+
+        let lead = SalesService::new(self.db.clone())
+            .create_lead(
+                listing.business_id,
+                CreateLeadInput {
+                    source: "marketplace",
+                    listing_id: Some(listing_id),
+                    lot_id: Some(listing.lot_id),
+                    buyer_name: input.buyer_name,
+                    buyer_company: input.buyer_company,
+                    buyer_email: input.buyer_email,
+                    buyer_phone: input.buyer_phone,
+                    buyer_country: input.buyer_country,
+                    requested_quantity_kg: input.requested_quantity_kg,
+                    message: input.message,
+                },
+            )
+            .await?;
+
+        Ok(InquiryReceipt {
+            inquiry_id: lead.id,
+            access_token: lead.buyer_access_token.unwrap_or_default(),
+            received_at: lead.created_at,
+            message: "Your inquiry was sent to the producer".to_string(),
+            message_th: "ส่งคำถามถึงผู้ผลิตแล้ว".to_string(),
+        })
+    }
+}
@@ -0,0 +1,610 @@
+//! Team members and invitations
+//!
+//! Owners invite coworkers into their business by email, or by a link the
+//! owner shares over LINE since pickers are rarely reachable by email. The
+//! invitee accepts with the link's token, which creates their user with the
+//! role chosen on the invitation. Members can later change role or be
+//! deactivated.
+
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::external::SmtpMailer;
+use crate::services::auth::{hash_action_token, validate_new_password};
+
+/// How long an invitation link stays valid
+pub const INVITATION_TTL_DAYS: i64 = 7;
+
+/// Member service
+#[derive(Clone)]
+pub struct MemberService {
+    db: PgPool,
+    mailer: Option<SmtpMailer>,
+    public_url: String,
+}
+
+/// Member of a business
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Member {
+    pub id: Uuid,
+    pub name: String,
+    /// Null for members who sign in with LINE only
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub role_id: Uuid,
+    pub role_name: String,
+    pub preferred_language: String,
+    pub is_active: bool,
+    pub line_connected: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    /// Set while repeated failed sign-ins keep the account locked
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pending invitation
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MemberInvitation {
+    pub id: Uuid,
+    pub channel: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub role_id: Uuid,
+    pub role_name: String,
+    pub preferred_language: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Invitation just created, with the link to pass on
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedInvitation {
+    #[serde(flatten)]
+    pub invitation: MemberInvitation,
+    pub invite_url: String,
+    /// Opens LINE with the invitation message ready to send
+    pub line_share_url: Option<String>,
+    pub email_sent: bool,
+}
+
+/// What an invitee sees before accepting
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct InvitationPreview {
+    pub business_name: String,
+    pub role_name: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub preferred_language: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Input for inviting a member
+#[derive(Debug, Deserialize)]
+pub struct InviteMemberInput {
+    /// email or line
+    pub channel: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub role_id: Uuid,
+    pub preferred_language: Option<String>,
+}
+
+/// Input for accepting an invitation
+#[derive(Debug, Deserialize)]
+pub struct AcceptInvitationInput {
+    pub token: String,
+    pub name: String,
+    /// Required for LINE invitations; email invitations use the invited address
+    pub email: Option<String>,
+    pub password: String,
+    pub phone: Option<String>,
+    pub preferred_language: Option<String>,
+}
+
+/// Input for changing a member's role or status
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberInput {
+    pub role_id: Option<Uuid>,
+    pub is_active: Option<bool>,
+}
+
+/// User created by accepting an invitation
+#[derive(Debug, Clone)]
+pub struct AcceptedInvitation {
+    pub user_id: Uuid,
+    pub business_id: Uuid,
+    pub email: String,
+}
+
+/// Pending invitation row locked while accepting
+#[derive(Debug, sqlx::FromRow)]
+struct InvitationRow {
+    id: Uuid,
+    business_id: Uuid,
+    role_id: Uuid,
+    channel: String,
+    email: Option<String>,
+    preferred_language: String,
+}
+
+/// Trim and lowercase an email; None unless it looks like an address
+pub fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let valid = !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.chars().any(char::is_whitespace)
+        && email.len() <= 255;
+    valid.then_some(email)
+}
+
+/// Percent-encode text for a URL query, keeping unreserved characters
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Invitation message in the invitee's language
+pub fn invitation_message(business_name: &str, role_name: &str, link: &str, thai: bool) -> (String, String) {
+    if thai {
+        (
+            format!("คำเชิญเข้าร่วม {}", business_name),
+            format!(
+                "คุณได้รับเชิญให้เข้าร่วม {} ในตำแหน่ง {} เปิดลิงก์นี้ภายใน {} วันเพื่อสร้างบัญชี:\n{}",
+                business_name, role_name, INVITATION_TTL_DAYS, link
+            ),
+        )
+    } else {
+        (
+            format!("Invitation to join {}", business_name),
+            format!(
+                "You have been invited to join {} as {}. Open this link within {} days to create your account:\n{}",
+                business_name, role_name, INVITATION_TTL_DAYS, link
+            ),
+        )
+    }
+}
+
+fn language(value: Option<&str>) -> &'static str {
+    match value {
+        Some("en") => "en",
+        _ => "th",
+    }
+}
+
+fn invalid_email(field: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: "A valid email address is required".to_string(),
+        message_th: "กรุณาระบุอีเมลที่ถูกต้อง".to_string(),
+    }
+}
+
+fn invalid_invitation() -> AppError {
+    AppError::Validation {
+        field: "token".to_string(),
+        message: "This invitation is invalid or has expired; ask for a new one".to_string(),
+        message_th: "คำเชิญไม่ถูกต้องหรือหมดอายุแล้ว กรุณาขอคำเชิญใหม่".to_string(),
+    }
+}
+
+fn email_taken() -> AppError {
+    AppError::Conflict {
+        resource: "email".to_string(),
+        message: "An account already uses this email".to_string(),
+        message_th: "อีเมลนี้มีบัญชีอยู่แล้ว".to_string(),
+    }
+}
+
+impl MemberService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            mailer: SmtpMailer::from_config(&config.email),
+            public_url: config.server.public_url.clone(),
+        }
+    }
+
+    /// List the members of a business
+    pub async fn list_members(&self, business_id: Uuid) -> AppResult<Vec<Member>> {
+        let members = sqlx::query_as::<_, Member>(
+            r#"
+            SELECT u.id, u.name, u.email, u.phone, u.role_id, r.name AS role_name,
+                   u.preferred_language, u.is_active,
+                   EXISTS(SELECT 1 FROM line_connections lc WHERE lc.user_id = u.id) AS line_connected,
+                   u.last_login_at,
+                   CASE WHEN u.locked_until > NOW() THEN u.locked_until END AS locked_until,
+                   u.created_at
+            FROM users u
+            JOIN roles r ON r.id = u.role_id
+            WHERE u.business_id = $1
+            ORDER BY u.is_active DESC, u.name
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(members)
+    }
+
+    /// Invite a coworker; email invitations are mailed, LINE invitations
+    /// return a link for the owner to share
+    pub async fn invite(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: InviteMemberInput,
+    ) -> AppResult<CreatedInvitation> {
+        let email = match input.email.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+            Some(email) => Some(normalize_email(email).ok_or_else(|| invalid_email("email"))?),
+            None => None,
+        };
+        match input.channel.as_str() {
+            "email" if email.is_none() => return Err(invalid_email("email")),
+            "email" if self.mailer.is_none() => {
+                return Err(AppError::Configuration("Email delivery is not configured".to_string()));
+            }
+            "email" | "line" => {}
+            _ => {
+                return Err(AppError::Validation {
+                    field: "channel".to_string(),
+                    message: "Channel must be email or line".to_string(),
+                    message_th: "ช่องทางต้องเป็น email หรือ line".to_string(),
+                });
+            }
+        }
+
+        let (business_name, role_name) = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT b.name, r.name
+            FROM roles r
+            JOIN businesses b ON b.id = r.business_id
+            WHERE r.id = $1 AND r.business_id = $2
+            "#,
+        )
+        .bind(input.role_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Role".to_string()))?;
+
+        if let Some(email) = &email {
+            if self.email_in_use(email).await? {
+                return Err(email_taken());
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+        // A new invitation replaces any pending one for the same address
+        if let Some(email) = &email {
+            sqlx::query(
+                r#"
+                UPDATE member_invitations SET revoked_at = NOW()
+                WHERE business_id = $1 AND email = $2 AND accepted_at IS NULL AND revoked_at IS NULL
+                "#,
+            )
+            .bind(business_id)
+            .bind(email)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let preferred_language = language(input.preferred_language.as_deref());
+        let invitation_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO member_invitations (
+                business_id, role_id, channel, email, name, preferred_language,
+                token_hash, invited_by, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.role_id)
+        .bind(&input.channel)
+        .bind(&email)
+        .bind(input.name.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+        .bind(preferred_language)
+        .bind(hash_action_token(&token))
+        .bind(user_id)
+        .bind(Utc::now() + Duration::days(INVITATION_TTL_DAYS))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let invite_url = format!("{}/join?token={}", self.public_url.trim_end_matches('/'), token);
+        let (subject, body) =
+            invitation_message(&business_name, &role_name, &invite_url, preferred_language == "th");
+
+        // Roll back when the email cannot be sent, so no dead invitation stays
+        let email_sent = match (&self.mailer, &email, input.channel.as_str()) {
+            (Some(mailer), Some(email), "email") => {
+                mailer.send(email, &subject, &body, None).await?;
+                true
+            }
+            _ => false,
+        };
+        tx.commit().await?;
+
+        let invitation = self.get_invitation(business_id, invitation_id).await?;
+        Ok(CreatedInvitation {
+            invitation,
+            line_share_url: (input.channel == "line")
+                .then(|| format!("https://line.me/R/msg/text/?{}", percent_encode(&body))),
+            invite_url,
+            email_sent,
+        })
+    }
+
+    async fn get_invitation(&self, business_id: Uuid, invitation_id: Uuid) -> AppResult<MemberInvitation> {
+        sqlx::query_as::<_, MemberInvitation>(
+            r#"
+            SELECT i.id, i.channel, i.email, i.name, i.role_id, r.name AS role_name,
+                   i.preferred_language, i.invited_by, i.expires_at, i.created_at
+            FROM member_invitations i
+            JOIN roles r ON r.id = i.role_id
+            WHERE i.id = $1 AND i.business_id = $2
+            "#,
+        )
+        .bind(invitation_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation".to_string()))
+    }
+
+    /// List invitations not yet accepted, revoked or expired
+    pub async fn list_pending(&self, business_id: Uuid) -> AppResult<Vec<MemberInvitation>> {
+        let invitations = sqlx::query_as::<_, MemberInvitation>(
+            r#"
+            SELECT i.id, i.channel, i.email, i.name, i.role_id, r.name AS role_name,
+                   i.preferred_language, i.invited_by, i.expires_at, i.created_at
+            FROM member_invitations i
+            JOIN roles r ON r.id = i.role_id
+            WHERE i.business_id = $1
+              AND i.accepted_at IS NULL AND i.revoked_at IS NULL AND i.expires_at > NOW()
+            ORDER BY i.created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(invitations)
+    }
+
+    /// Revoke a pending invitation
+    pub async fn revoke(&self, business_id: Uuid, invitation_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE member_invitations SET revoked_at = NOW()
+            WHERE id = $1 AND business_id = $2 AND accepted_at IS NULL AND revoked_at IS NULL
+            "#,
+        )
+        .bind(invitation_id)
+        .bind(business_id)
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Invitation".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Show an invitation to the invitee before they accept
+    pub async fn preview(&self, token: &str) -> AppResult<InvitationPreview> {
+        sqlx::query_as::<_, InvitationPreview>(
+            r#"
+            SELECT b.name AS business_name, r.name AS role_name, i.email, i.name,
+                   i.preferred_language, i.expires_at
+            FROM member_invitations i
+            JOIN businesses b ON b.id = i.business_id
+            JOIN roles r ON r.id = i.role_id
+            WHERE i.token_hash = $1
+              AND i.accepted_at IS NULL AND i.revoked_at IS NULL AND i.expires_at > NOW()
+            "#,
+        )
+        .bind(hash_action_token(token))
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(invalid_invitation)
+    }
+
+    /// Accept an invitation, creating the invitee's user with the invited role
+    pub async fn accept(&self, input: AcceptInvitationInput) -> AppResult<AcceptedInvitation> {
+        let name = input.name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation {
+                field: "name".to_string(),
+                message: "Name is required".to_string(),
+                message_th: "ต้องระบุชื่อ".to_string(),
+            });
+        }
+        validate_new_password(&input.password).map_err(|_| AppError::Validation {
+            field: "password".to_string(),
+            message: "Password must be 8 to 72 bytes long".to_string(),
+            message_th: "รหัสผ่านต้องยาว 8 ถึง 72 ไบต์".to_string(),
+        })?;
+        let password_hash = hash(&input.password, DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))?;
+
+        let mut tx = self.db.begin().await?;
+        let invitation = sqlx::query_as::<_, InvitationRow>(
+            r#"
+            SELECT id, business_id, role_id, channel, email, preferred_language
+            FROM member_invitations
+            WHERE token_hash = $1
+              AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > NOW()
+            FOR UPDATE
+            "#,
+        )
+        .bind(hash_action_token(&input.token))
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(invalid_invitation)?;
+
+        // Email invitations are tied to the invited address
+        let email = match &invitation.email {
+            Some(email) => email.clone(),
+            None => input
+                .email
+                .as_deref()
+                .and_then(normalize_email)
+                .ok_or_else(|| invalid_email("email"))?,
+        };
+        // Sign-in is by email alone, so an address can belong to one account
+        let in_use = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(email) = $1)")
+            .bind(&email)
+            .fetch_one(&mut *tx)
+            .await?;
+        if in_use {
+            return Err(email_taken());
+        }
+
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO users (
+                business_id, role_id, email, password_hash, name, phone,
+                preferred_language, email_verified
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+            "#,
+        )
+        .bind(invitation.business_id)
+        .bind(invitation.role_id)
+        .bind(&email)
+        .bind(&password_hash)
+        .bind(name)
+        .bind(input.phone.as_deref().map(str::trim).filter(|p| !p.is_empty()))
+        .bind(language(Some(input.preferred_language.as_deref().unwrap_or(&invitation.preferred_language))))
+        .bind(invitation.channel == "email")
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE member_invitations SET accepted_at = NOW(), accepted_user_id = $2 WHERE id = $1")
+            .bind(invitation.id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(AcceptedInvitation {
+            user_id,
+            business_id: invitation.business_id,
+            email,
+        })
+    }
+
+    /// Change a member's role or deactivate them; members cannot change
+    /// their own membership
+    pub async fn update_member(
+        &self,
+        business_id: Uuid,
+        current_user_id: Uuid,
+        member_id: Uuid,
+        input: UpdateMemberInput,
+    ) -> AppResult<Member> {
+        if member_id == current_user_id {
+            return Err(AppError::Validation {
+                field: "member_id".to_string(),
+                message: "You cannot change your own role or status".to_string(),
+                message_th: "ไม่สามารถเปลี่ยนบทบาทหรือสถานะของตนเองได้".to_string(),
+            });
+        }
+        if let Some(role_id) = input.role_id {
+            let role_exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM roles WHERE id = $1 AND business_id = $2)",
+            )
+            .bind(role_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !role_exists {
+                return Err(AppError::NotFound("Role".to_string()));
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+        let updated = sqlx::query(
+            r#"
+            UPDATE users
+            SET role_id = COALESCE($3, role_id),
+                is_active = COALESCE($4, is_active),
+                updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(member_id)
+        .bind(business_id)
+        .bind(input.role_id)
+        .bind(input.is_active)
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Member".to_string()));
+        }
+
+        // Sessions carry the old permissions; sign the member out
+        if input.role_id.is_some() || input.is_active == Some(false) {
+            sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+                .bind(member_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        self.list_members(business_id)
+            .await?
+            .into_iter()
+            .find(|m| m.id == member_id)
+            .ok_or_else(|| AppError::NotFound("Member".to_string()))
+    }
+
+    /// Lift a member's sign-in lockout and forget their failed attempts
+    pub async fn unlock_member(&self, business_id: Uuid, member_id: Uuid) -> AppResult<Member> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE users
+            SET failed_login_count = 0, lockout_count = 0, locked_until = NULL, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(member_id)
+        .bind(business_id)
+        .execute(&self.db)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Member".to_string()));
+        }
+
+        self.list_members(business_id)
+            .await?
+            .into_iter()
+            .find(|m| m.id == member_id)
+            .ok_or_else(|| AppError::NotFound("Member".to_string()))
+    }
+
+    async fn email_in_use(&self, email: &str) -> AppResult<bool> {
+        let in_use = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(email) = $1)")
+            .bind(email)
+            .fetch_one(&self.db)
+            .await?;
+        Ok(in_use)
+    }
+}
@@ -1,39 +1,159 @@
 //! Business logic services for the Coffee Quality Management Platform
 
+pub mod activity_feed;
 pub mod auth;
+pub mod benchmarking;
+pub mod blend_optimizer;
+pub mod business;
 pub mod certification;
+pub mod cupper_calibration;
 pub mod cupping;
+pub mod cupping_analytics;
+pub mod cupping_flight;
+pub mod cupping_import;
+pub mod cupping_panel;
+pub mod cupping_report;
+pub mod cupping_triangle;
+pub mod data_quality;
+pub mod farm_activity;
+pub mod gap_export;
 pub mod grading;
+pub mod grading_photo;
+pub mod grading_standard;
 pub mod harvest;
+pub mod harvest_labor;
+pub mod lab_result;
+pub mod label_sheet;
 pub mod inventory;
+pub mod job_queue;
+pub mod knowledge_base;
+pub mod kpi;
 pub mod line_chatbot;
 pub mod line_oauth;
 pub mod lot;
+pub mod lot_certification;
+pub mod lot_gallery;
+pub mod lot_insurance;
+pub mod lot_recommendation;
+pub mod lot_stage;
+pub mod lot_transfer;
+pub mod marketplace;
+pub mod member;
+pub mod moisture;
 pub mod notification;
+pub mod pdf;
+pub mod picker_quality;
 pub mod plot;
+pub mod plot_import;
+pub mod plot_profitability;
+pub mod plot_validation;
+pub mod pricing;
 pub mod processing;
+pub mod processing_capacity;
+pub mod quality;
+pub mod recycle_bin;
+pub mod reference_data;
+pub mod report_builder;
+pub mod report_schedule;
 pub mod reporting;
+pub mod reporting_period;
+pub mod research;
+pub mod ripeness_estimate;
 pub mod roasting;
 pub mod role;
+pub mod sales;
+pub mod sales_negotiation;
+pub mod sample;
+pub mod sequence;
+pub mod shipment;
+pub mod shipment_conditions;
+pub mod sop_checklist;
+pub mod spec_sheet;
+pub mod storage;
 pub mod sync;
+pub mod totp;
 pub mod traceability;
+pub mod traceability_check;
+pub mod water_quality;
 pub mod weather;
+pub mod weather_station;
+pub mod weight_unit;
+pub mod weekly_digest;
+pub mod xlsx;
+pub mod xlsx_templates;
 
+pub use activity_feed::ActivityFeedService;
 pub use auth::AuthService;
+pub use benchmarking::BenchmarkingService;
+pub use blend_optimizer::BlendOptimizerService;
+pub use business::BusinessService;
 pub use certification::CertificationService;
+pub use cupper_calibration::CupperCalibrationService;
 pub use cupping::CuppingService;
+pub use cupping_analytics::CuppingAnalyticsService;
+pub use cupping_flight::CuppingFlightService;
+pub use cupping_import::CuppingImportService;
+pub use cupping_panel::CuppingPanelService;
+pub use cupping_report::CuppingReportService;
+pub use data_quality::DataQualityService;
+pub use farm_activity::FarmActivityService;
+pub use gap_export::GapExportService;
 pub use grading::GradingService;
+pub use grading_photo::GradingPhotoService;
+pub use grading_standard::GradingStandardService;
 pub use harvest::HarvestService;
+pub use harvest_labor::HarvestLaborService;
+pub use lab_result::LabResultService;
+pub use label_sheet::LabelSheetService;
 pub use inventory::InventoryService;
+pub use job_queue::JobQueueService;
+pub use knowledge_base::KnowledgeBaseService;
+pub use kpi::KpiService;
 pub use line_chatbot::LineChatbotService;
 pub use line_oauth::LineOAuthService;
 pub use lot::LotService;
+pub use lot_certification::LotCertificationService;
+pub use lot_gallery::LotGalleryService;
+pub use lot_insurance::LotInsuranceService;
+pub use lot_recommendation::LotRecommendationService;
+pub use lot_stage::LotStageService;
+pub use lot_transfer::LotTransferService;
+pub use marketplace::MarketplaceService;
+pub use member::MemberService;
+pub use moisture::MoistureService;
 pub use notification::NotificationService;
+pub use picker_quality::PickerQualityService;
 pub use plot::PlotService;
+pub use plot_import::PlotImportService;
+pub use plot_profitability::PlotProfitabilityService;
+pub use plot_validation::PlotValidationService;
+pub use pricing::PricingService;
 pub use processing::ProcessingService;
+pub use processing_capacity::ProcessingCapacityService;
+pub use quality::QualityService;
+pub use recycle_bin::RecycleBinService;
+pub use reference_data::ReferenceDataService;
+pub use report_builder::ReportBuilderService;
+pub use report_schedule::ReportScheduleService;
 pub use reporting::ReportingService;
+pub use research::ResearchService;
 pub use roasting::RoastingService;
 pub use role::RoleService;
+pub use sales::SalesService;
+pub use sales_negotiation::SalesNegotiationService;
+pub use sample::SampleService;
+pub use sequence::SequenceService;
+pub use shipment::ShipmentService;
+pub use shipment_conditions::ShipmentConditionsService;
+pub use sop_checklist::SopChecklistService;
+pub use spec_sheet::SpecSheetService;
+pub use storage::StorageService;
 pub use sync::SyncService;
 pub use traceability::TraceabilityService;
+pub use traceability_check::TraceabilityCheckService;
+pub use water_quality::WaterQualityService;
 pub use weather::WeatherService;
+pub use weather_station::WeatherStationService;
+pub use weight_unit::WeightUnitService;
+pub use weekly_digest::WeeklyDigestService;
+pub use xlsx_templates::XlsxTemplateService;
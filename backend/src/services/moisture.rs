@@ -0,0 +1,376 @@
+//! Moisture reading log for lots
+//!
+//! Moisture meter readings taken on a lot over time, each with the stage
+//! the coffee was in and the device used. A lot's readings are charted per
+//! stage to follow drying and storage, and green beans above 12.5% come
+//! back with a warning.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::lot::LotStage;
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+
+/// Highest moisture of green beans before they risk mold in storage
+pub const GREEN_BEAN_MAX_MOISTURE_PERCENT: Decimal = Decimal::from_parts(125, 0, 0, false, 1);
+
+/// Moisture service
+#[derive(Clone)]
+pub struct MoistureService {
+    db: PgPool,
+}
+
+/// Database row for a reading
+#[derive(Debug, sqlx::FromRow)]
+struct MoistureReadingRow {
+    id: Uuid,
+    business_id: Uuid,
+    lot_id: Uuid,
+    measured_at: DateTime<Utc>,
+    stage: LotStage,
+    moisture_percent: Decimal,
+    device: Option<String>,
+    notes: Option<String>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+const READING_COLUMNS: &str = r#"
+    id, business_id, lot_id, measured_at, stage, moisture_percent, device, notes,
+    created_by, created_at
+"#;
+
+/// Moisture reading with a warning when it is too wet for its stage
+#[derive(Debug, Clone, Serialize)]
+pub struct MoistureReading {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+    pub measured_at: DateTime<Utc>,
+    pub stage: LotStage,
+    pub moisture_percent: Decimal,
+    pub device: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub warning: Option<MoistureWarning>,
+}
+
+/// Green beans above the moisture limit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoistureWarning {
+    pub value: Decimal,
+    pub max: Decimal,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Input for recording a reading
+#[derive(Debug, Deserialize)]
+pub struct RecordMoistureInput {
+    pub lot_id: Uuid,
+    pub moisture_percent: Decimal,
+    /// Defaults to the lot's current stage
+    pub stage: Option<LotStage>,
+    /// Defaults to now
+    pub measured_at: Option<DateTime<Utc>>,
+    pub device: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing readings
+#[derive(Debug, Default, Deserialize)]
+pub struct MoistureQuery {
+    pub lot_id: Option<Uuid>,
+    pub stage: Option<LotStage>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// One point of a lot's moisture chart
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoisturePoint {
+    pub measured_at: DateTime<Utc>,
+    pub stage: LotStage,
+    pub moisture_percent: Decimal,
+}
+
+/// Readings of a lot in one stage, in time order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoistureStageSeries {
+    pub stage: LotStage,
+    pub first_percent: Decimal,
+    pub latest_percent: Decimal,
+    pub min_percent: Decimal,
+    pub max_percent: Decimal,
+    pub points: Vec<MoisturePoint>,
+}
+
+/// A lot's moisture over time, for charting
+#[derive(Debug, Clone, Serialize)]
+pub struct MoistureTimeline {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub current_stage: LotStage,
+    pub green_bean_max_percent: Decimal,
+    pub series: Vec<MoistureStageSeries>,
+    pub latest: Option<MoistureReading>,
+    /// Readings carrying a warning
+    pub warning_count: usize,
+}
+
+/// Lot whose latest green bean reading is above the limit
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MoistureAlert {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub reading_id: Uuid,
+    pub measured_at: DateTime<Utc>,
+    pub moisture_percent: Decimal,
+    pub device: Option<String>,
+}
+
+/// Warn when green beans are above the moisture limit
+pub fn moisture_warning(stage: LotStage, moisture_percent: Decimal) -> Option<MoistureWarning> {
+    if stage != LotStage::GreenBean || moisture_percent <= GREEN_BEAN_MAX_MOISTURE_PERCENT {
+        return None;
+    }
+    Some(MoistureWarning {
+        value: moisture_percent,
+        max: GREEN_BEAN_MAX_MOISTURE_PERCENT,
+        message: format!(
+            "Green bean moisture of {}% is above {}%",
+            moisture_percent, GREEN_BEAN_MAX_MOISTURE_PERCENT
+        ),
+        message_th: format!(
+            "ความชื้นสารกาแฟ {}% สูงกว่า {}%",
+            moisture_percent, GREEN_BEAN_MAX_MOISTURE_PERCENT
+        ),
+    })
+}
+
+/// Stage a reading is recorded under: the one given, or the lot's own.
+/// Sold lots have no stage to take, so the reading must name one.
+pub fn reading_stage(requested: Option<LotStage>, lot_stage: LotStage) -> AppResult<LotStage> {
+    match requested.unwrap_or(lot_stage) {
+        LotStage::Sold => Err(AppError::Validation {
+            field: "stage".to_string(),
+            message: "Give the stage the coffee was in when measured".to_string(),
+            message_th: "กรุณาระบุขั้นตอนของกาแฟขณะวัดความชื้น".to_string(),
+        }),
+        stage => Ok(stage),
+    }
+}
+
+/// Group chart points by stage, stages in supply chain order and points in
+/// time order
+pub fn stage_series(points: &[MoisturePoint]) -> Vec<MoistureStageSeries> {
+    let mut ordered = points.to_vec();
+    ordered.sort_by(|a, b| a.stage.cmp(&b.stage).then(a.measured_at.cmp(&b.measured_at)));
+
+    let mut series: Vec<MoistureStageSeries> = Vec::new();
+    for point in ordered {
+        match series.last_mut() {
+            Some(current) if current.stage == point.stage => {
+                current.latest_percent = point.moisture_percent;
+                current.min_percent = current.min_percent.min(point.moisture_percent);
+                current.max_percent = current.max_percent.max(point.moisture_percent);
+                current.points.push(point);
+            }
+            _ => series.push(MoistureStageSeries {
+                stage: point.stage,
+                first_percent: point.moisture_percent,
+                latest_percent: point.moisture_percent,
+                min_percent: point.moisture_percent,
+                max_percent: point.moisture_percent,
+                points: vec![point],
+            }),
+        }
+    }
+    series
+}
+
+impl MoistureService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a reading
+    pub async fn record(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordMoistureInput,
+    ) -> AppResult<MoistureReading> {
+        if input.moisture_percent < Decimal::ZERO || input.moisture_percent > Decimal::ONE_HUNDRED {
+            return Err(AppError::Validation {
+                field: "moisture_percent".to_string(),
+                message: "Moisture must be between 0 and 100%".to_string(),
+                message_th: "ความชื้นต้องอยู่ระหว่าง 0 ถึง 100%".to_string(),
+            });
+        }
+
+        let lot_stage = sqlx::query_scalar::<_, LotStage>(
+            "SELECT stage FROM lots WHERE id = $1 AND business_id = $2",
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+        let stage = reading_stage(input.stage, lot_stage)?;
+
+        let device = input.device.as_deref().map(str::trim).filter(|d| !d.is_empty());
+
+        let row = sqlx::query_as::<_, MoistureReadingRow>(&format!(
+            r#"
+            INSERT INTO moisture_readings (
+                business_id, lot_id, measured_at, stage, moisture_percent, device, notes, created_by
+            )
+            VALUES ($1, $2, COALESCE($3, NOW()), $4, $5, $6, $7, $8)
+            RETURNING {}
+            "#,
+            READING_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.measured_at)
+        .bind(stage)
+        .bind(input.moisture_percent)
+        .bind(device)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(Self::row_to_reading(row))
+    }
+
+    /// List readings, newest first
+    pub async fn list(&self, business_id: Uuid, query: &MoistureQuery) -> AppResult<Vec<MoistureReading>> {
+        let rows = sqlx::query_as::<_, MoistureReadingRow>(&format!(
+            r#"
+            SELECT {}
+            FROM moisture_readings
+            WHERE business_id = $1
+              AND ($2::UUID IS NULL OR lot_id = $2)
+              AND ($3::VARCHAR IS NULL OR stage = $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR measured_at >= $4)
+              AND ($5::TIMESTAMPTZ IS NULL OR measured_at <= $5)
+            ORDER BY measured_at DESC
+            LIMIT $6
+            "#,
+            READING_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.stage)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_reading).collect())
+    }
+
+    /// A lot's readings grouped by stage for charting
+    pub async fn timeline(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<MoistureTimeline> {
+        let (traceability_code, current_stage) = sqlx::query_as::<_, (String, LotStage)>(
+            "SELECT traceability_code, stage FROM lots WHERE id = $1 AND business_id = $2",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        let rows = sqlx::query_as::<_, MoistureReadingRow>(&format!(
+            r#"
+            SELECT {}
+            FROM moisture_readings
+            WHERE business_id = $1 AND lot_id = $2
+            ORDER BY measured_at, created_at
+            "#,
+            READING_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let readings: Vec<MoistureReading> = rows.into_iter().map(Self::row_to_reading).collect();
+        let points: Vec<MoisturePoint> = readings
+            .iter()
+            .map(|r| MoisturePoint {
+                measured_at: r.measured_at,
+                stage: r.stage,
+                moisture_percent: r.moisture_percent,
+            })
+            .collect();
+
+        Ok(MoistureTimeline {
+            lot_id,
+            traceability_code,
+            current_stage,
+            green_bean_max_percent: GREEN_BEAN_MAX_MOISTURE_PERCENT,
+            series: stage_series(&points),
+            warning_count: readings.iter().filter(|r| r.warning.is_some()).count(),
+            latest: readings.last().cloned(),
+        })
+    }
+
+    /// Lots not yet sold whose latest green bean reading is above the limit
+    pub async fn alerts(&self, business_id: Uuid) -> AppResult<Vec<MoistureAlert>> {
+        let alerts = sqlx::query_as::<_, MoistureAlert>(
+            r#"
+            SELECT lot_id, traceability_code, reading_id, measured_at, moisture_percent, device
+            FROM (
+                SELECT DISTINCT ON (m.lot_id)
+                    m.lot_id, l.traceability_code, m.id AS reading_id, m.measured_at,
+                    m.moisture_percent, m.device
+                FROM moisture_readings m
+                JOIN lots l ON l.id = m.lot_id
+                WHERE m.business_id = $1 AND m.stage = 'green_bean' AND l.stage <> 'sold'
+                ORDER BY m.lot_id, m.measured_at DESC, m.created_at DESC
+            ) latest
+            WHERE moisture_percent > $2
+            ORDER BY moisture_percent DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(GREEN_BEAN_MAX_MOISTURE_PERCENT)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(alerts)
+    }
+
+    /// Delete a reading, keeping it in the recycle bin
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, reading_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::MoistureReading, reading_id)
+            .await
+    }
+
+    fn row_to_reading(row: MoistureReadingRow) -> MoistureReading {
+        MoistureReading {
+            warning: moisture_warning(row.stage, row.moisture_percent),
+            id: row.id,
+            business_id: row.business_id,
+            lot_id: row.lot_id,
+            measured_at: row.measured_at,
+            stage: row.stage,
+            moisture_percent: row.moisture_percent,
+            device: row.device,
+            notes: row.notes,
+            created_by: row.created_by,
+            created_at: row.created_at,
+        }
+    }
+}
@@ -5,19 +5,28 @@
 //! - LINE messaging integration
 //! - In-app notification management
 //! - Notification triggers for various events
+//! - Severity levels routed to channels per user
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use shared::{DisplayFormat, Language};
 use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::external::SmtpMailer;
+use crate::services::kpi::{KpiKind, KpiReport, KpiService};
+use crate::services::lot_insurance::EXPIRY_ALERT_DAYS;
+use crate::services::weekly_digest::WeeklyDigest;
+use crate::services::BusinessService;
 
 /// Notification service for managing notifications
 #[derive(Clone)]
 pub struct NotificationService {
     db: PgPool,
     line_client: Option<LineMessagingClient>,
+    mailer: Option<SmtpMailer>,
 }
 
 /// LINE Messaging API client
@@ -33,10 +42,12 @@ pub struct LineMessagingClient {
 pub enum NotificationType {
     LowInventory,
     CertificationExpiring,
+    InsuranceExpiring,
     ProcessingMilestone,
     WeatherAlert,
     HarvestReminder,
     QualityAlert,
+    WeeklyDigest,
     System,
 }
 
@@ -49,6 +60,42 @@ pub enum NotificationChannel {
     Email,
 }
 
+impl sqlx::postgres::PgHasArrayType for NotificationChannel {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_notification_channel")
+    }
+}
+
+/// Alert severity, routed to channels per user
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "alert_severity", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    /// Severity of a notification given only a priority
+    pub fn from_priority(priority: i32) -> Self {
+        match priority {
+            p if p >= 2 => AlertSeverity::Critical,
+            1 => AlertSeverity::Warning,
+            _ => AlertSeverity::Info,
+        }
+    }
+
+    /// Queue priority of an alert of this severity
+    pub fn priority(&self) -> i32 {
+        match self {
+            AlertSeverity::Info => 0,
+            AlertSeverity::Warning => 1,
+            AlertSeverity::Critical => 2,
+        }
+    }
+}
+
 /// Notification status enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "notification_status", rename_all = "snake_case")]
@@ -71,6 +118,55 @@ pub struct NotificationPreferences {
     pub weather_alert_enabled: bool,
     pub harvest_reminder_enabled: bool,
     pub quality_alert_enabled: bool,
+    pub weekly_digest_enabled: bool,
+    /// Channels for each severity; the in-app inbox always gets a copy
+    pub info_channels: Vec<NotificationChannel>,
+    pub warning_channels: Vec<NotificationChannel>,
+    pub critical_channels: Vec<NotificationChannel>,
+    /// Channels for the weekly digest, whatever its severity
+    pub digest_channels: Vec<NotificationChannel>,
+}
+
+impl NotificationPreferences {
+    /// Channels configured for a severity
+    pub fn channels_for(&self, severity: AlertSeverity) -> &[NotificationChannel] {
+        match severity {
+            AlertSeverity::Info => &self.info_channels,
+            AlertSeverity::Warning => &self.warning_channels,
+            AlertSeverity::Critical => &self.critical_channels,
+        }
+    }
+
+    /// Channels configured for a notification: the digest has its own,
+    /// everything else follows its severity
+    pub fn channels_for_notification(
+        &self,
+        notification_type: &NotificationType,
+        severity: AlertSeverity,
+    ) -> &[NotificationChannel] {
+        match notification_type {
+            NotificationType::WeeklyDigest => &self.digest_channels,
+            _ => self.channels_for(severity),
+        }
+    }
+}
+
+/// Push channels a notification of a severity goes to: the ones routed for
+/// it that are switched on, LINE before email. In-app is not listed as every
+/// notification is kept in the inbox.
+pub fn route_channels(
+    routed: &[NotificationChannel],
+    line_enabled: bool,
+    email_enabled: bool,
+) -> Vec<NotificationChannel> {
+    [
+        (NotificationChannel::Line, line_enabled),
+        (NotificationChannel::Email, email_enabled),
+    ]
+    .into_iter()
+    .filter(|(channel, enabled)| *enabled && routed.contains(channel))
+    .map(|(channel, _)| channel)
+    .collect()
 }
 
 /// Input for updating notification preferences
@@ -84,6 +180,11 @@ pub struct UpdatePreferencesInput {
     pub weather_alert_enabled: Option<bool>,
     pub harvest_reminder_enabled: Option<bool>,
     pub quality_alert_enabled: Option<bool>,
+    pub weekly_digest_enabled: Option<bool>,
+    pub info_channels: Option<Vec<NotificationChannel>>,
+    pub warning_channels: Option<Vec<NotificationChannel>>,
+    pub critical_channels: Option<Vec<NotificationChannel>>,
+    pub digest_channels: Option<Vec<NotificationChannel>>,
 }
 
 /// Queued notification
@@ -99,8 +200,11 @@ pub struct QueuedNotification {
     pub message_th: Option<String>,
     pub entity_type: Option<String>,
     pub entity_id: Option<Uuid>,
+    /// Client path the notification opens
+    pub action_url: Option<String>,
     pub scheduled_at: DateTime<Utc>,
     pub priority: i32,
+    pub severity: AlertSeverity,
     pub status: NotificationStatus,
     pub created_at: DateTime<Utc>,
 }
@@ -122,8 +226,14 @@ pub struct NotificationLogEntry {
     pub status: NotificationStatus,
     pub error_message: Option<String>,
     pub line_message_id: Option<String>,
+    pub priority: i32,
+    pub severity: AlertSeverity,
     pub sent_at: DateTime<Utc>,
     pub read_at: Option<DateTime<Utc>>,
+    /// When this notification was escalated for not being acknowledged
+    pub escalated_at: Option<DateTime<Utc>>,
+    /// Original notification, if this entry is an escalation
+    pub escalation_of: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -157,7 +267,14 @@ pub struct CreateNotificationInput {
     pub message_th: Option<String>,
     pub entity_type: Option<String>,
     pub entity_id: Option<Uuid>,
+    /// Client path to open; defaults from the entity (see
+    /// `notification_action_url`)
+    #[serde(default)]
+    pub action_url: Option<String>,
     pub priority: Option<i32>,
+    /// Defaults from the priority
+    #[serde(default)]
+    pub severity: Option<AlertSeverity>,
 }
 
 /// LINE message types
@@ -165,7 +282,64 @@ pub struct CreateNotificationInput {
 #[serde(tag = "type")]
 pub enum LineMessage {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(rename = "quickReply", skip_serializing_if = "Option::is_none")]
+        quick_reply: Option<LineQuickReply>,
+    },
+}
+
+impl LineMessage {
+    /// Plain text message without quick replies
+    pub fn text(text: impl Into<String>) -> Self {
+        LineMessage::Text { text: text.into(), quick_reply: None }
+    }
+}
+
+/// Quick reply buttons shown under a LINE message
+/// See: https://developers.line.biz/en/reference/messaging-api/#quick-reply
+#[derive(Debug, Clone, Serialize)]
+pub struct LineQuickReply {
+    pub items: Vec<LineQuickReplyItem>,
+}
+
+/// Quick reply button
+#[derive(Debug, Clone, Serialize)]
+pub struct LineQuickReplyItem {
+    /// Always "action"
+    #[serde(rename = "type")]
+    pub item_type: &'static str,
+    pub action: LineAction,
+}
+
+impl LineQuickReplyItem {
+    /// Quick reply button that sends a postback with `data`
+    pub fn postback(label: impl Into<String>, data: impl Into<String>, display_text: impl Into<String>) -> Self {
+        Self {
+            item_type: "action",
+            action: LineAction::Postback {
+                label: label.into(),
+                data: data.into(),
+                display_text: display_text.into(),
+            },
+        }
+    }
+}
+
+/// LINE action objects
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum LineAction {
+    #[serde(rename = "postback")]
+    Postback {
+        /// Button label (max 20 characters)
+        label: String,
+        /// Postback data (max 300 characters)
+        data: String,
+        /// Text shown in the chat as the user's message when tapped
+        #[serde(rename = "displayText")]
+        display_text: String,
+    },
 }
 
 /// LINE push message request
@@ -182,6 +356,35 @@ struct LineApiResponse {
     message: Option<String>,
 }
 
+/// LINE push message response
+#[derive(Debug, Deserialize)]
+struct LinePushResponse {
+    #[serde(rename = "sentMessages", default)]
+    sent_messages: Vec<LineSentMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LineSentMessage {
+    id: String,
+}
+
+/// Postback data for the "acknowledge" quick reply on a LINE notification
+pub fn acknowledge_postback_data(log_id: Uuid) -> String {
+    format!("action=ack&notification={}", log_id)
+}
+
+/// Quick reply attached to LINE notifications so the user can confirm they saw it.
+/// LINE does not send read receipts for bot messages, so this is the only read signal.
+pub fn acknowledge_quick_reply(log_id: Uuid) -> LineQuickReply {
+    LineQuickReply {
+        items: vec![LineQuickReplyItem::postback(
+            "รับทราบ ✓",
+            acknowledge_postback_data(log_id),
+            "Acknowledged / รับทราบ",
+        )],
+    }
+}
+
 impl LineMessagingClient {
     /// Create a new LINE messaging client
     pub fn new(channel_access_token: String) -> Self {
@@ -197,12 +400,12 @@ impl LineMessagingClient {
         Some(Self::new(token))
     }
 
-    /// Send a push message to a user
+    /// Send a push message to a user, returning the LINE message ID when reported
     pub async fn send_push_message(
         &self,
         line_user_id: &str,
         message: LineMessage,
-    ) -> Result<(), String> {
+    ) -> Result<Option<String>, String> {
         let request = LinePushRequest {
             to: line_user_id.to_string(),
             messages: vec![message],
@@ -219,7 +422,13 @@ impl LineMessagingClient {
             .map_err(|e| format!("Failed to send LINE message: {}", e))?;
 
         if response.status().is_success() {
-            Ok(())
+            let sent = response
+                .json::<LinePushResponse>()
+                .await
+                .ok()
+                .and_then(|r| r.sent_messages.into_iter().next())
+                .map(|m| m.id);
+            Ok(sent)
         } else {
             let error: LineApiResponse = response
                 .json()
@@ -228,6 +437,26 @@ impl LineMessagingClient {
             Err(error.message.unwrap_or_else(|| "Unknown error".to_string()))
         }
     }
+
+    /// Download the content (image, video, audio or file) a user sent
+    pub async fn get_message_content(&self, message_id: &str) -> Result<Vec<u8>, String> {
+        let response = self
+            .http_client
+            .get(format!("https://api-data.line.me/v2/bot/message/{}/content", message_id))
+            .header("Authorization", format!("Bearer {}", self.channel_access_token))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch LINE message content: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("LINE message content returned {}", response.status()));
+        }
+        response
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| format!("Failed to read LINE message content: {}", e))
+    }
 }
 
 impl NotificationService {
@@ -236,6 +465,7 @@ impl NotificationService {
         Self {
             db,
             line_client: LineMessagingClient::from_env(),
+            mailer: None,
         }
     }
 
@@ -244,6 +474,17 @@ impl NotificationService {
         Self {
             db,
             line_client: Some(line_client),
+            mailer: None,
+        }
+    }
+
+    /// Create with an SMTP mailer so notifications routed to email are
+    /// delivered; without one they stay in-app
+    pub fn with_mailer(db: PgPool, mailer: Option<SmtpMailer>) -> Self {
+        Self {
+            db,
+            line_client: LineMessagingClient::from_env(),
+            mailer,
         }
     }
 
@@ -258,7 +499,8 @@ impl NotificationService {
             SELECT user_id, line_enabled, email_enabled,
                    low_inventory_enabled, certification_expiring_enabled,
                    processing_milestone_enabled, weather_alert_enabled,
-                   harvest_reminder_enabled, quality_alert_enabled
+                   harvest_reminder_enabled, quality_alert_enabled, weekly_digest_enabled,
+                   info_channels, warning_channels, critical_channels, digest_channels
             FROM notification_preferences
             WHERE user_id = $1
             "#,
@@ -287,12 +529,18 @@ impl NotificationService {
                 processing_milestone_enabled = COALESCE($6, processing_milestone_enabled),
                 weather_alert_enabled = COALESCE($7, weather_alert_enabled),
                 harvest_reminder_enabled = COALESCE($8, harvest_reminder_enabled),
-                quality_alert_enabled = COALESCE($9, quality_alert_enabled)
+                quality_alert_enabled = COALESCE($9, quality_alert_enabled),
+                weekly_digest_enabled = COALESCE($10, weekly_digest_enabled),
+                info_channels = COALESCE($11, info_channels),
+                warning_channels = COALESCE($12, warning_channels),
+                critical_channels = COALESCE($13, critical_channels),
+                digest_channels = COALESCE($14, digest_channels)
             WHERE user_id = $1
             RETURNING user_id, line_enabled, email_enabled,
                       low_inventory_enabled, certification_expiring_enabled,
                       processing_milestone_enabled, weather_alert_enabled,
-                      harvest_reminder_enabled, quality_alert_enabled
+                      harvest_reminder_enabled, quality_alert_enabled, weekly_digest_enabled,
+                      info_channels, warning_channels, critical_channels, digest_channels
             "#,
         )
         .bind(user_id)
@@ -304,6 +552,11 @@ impl NotificationService {
         .bind(input.weather_alert_enabled)
         .bind(input.harvest_reminder_enabled)
         .bind(input.quality_alert_enabled)
+        .bind(input.weekly_digest_enabled)
+        .bind(&input.info_channels)
+        .bind(&input.warning_channels)
+        .bind(&input.critical_channels)
+        .bind(&input.digest_channels)
         .fetch_one(&self.db)
         .await?;
 
@@ -320,11 +573,15 @@ impl NotificationService {
 
         let enabled = match notification_type {
             NotificationType::LowInventory => prefs.low_inventory_enabled,
-            NotificationType::CertificationExpiring => prefs.certification_expiring_enabled,
+            // Policy expiry follows the certification (document expiry) preference
+            NotificationType::CertificationExpiring | NotificationType::InsuranceExpiring => {
+                prefs.certification_expiring_enabled
+            }
             NotificationType::ProcessingMilestone => prefs.processing_milestone_enabled,
             NotificationType::WeatherAlert => prefs.weather_alert_enabled,
             NotificationType::HarvestReminder => prefs.harvest_reminder_enabled,
             NotificationType::QualityAlert => prefs.quality_alert_enabled,
+            NotificationType::WeeklyDigest => prefs.weekly_digest_enabled,
             NotificationType::System => true, // System notifications always enabled
         };
 
@@ -347,18 +604,34 @@ impl NotificationService {
             return Ok(None);
         }
 
+        let notification = self.insert_queue_entry(user_id, business_id, &input).await?;
+
+        Ok(Some(notification))
+    }
+
+    /// Insert a notification into the queue without checking preferences
+    async fn insert_queue_entry(
+        &self,
+        user_id: Uuid,
+        business_id: Uuid,
+        input: &CreateNotificationInput,
+    ) -> AppResult<QueuedNotification> {
+        let priority = input.priority.unwrap_or(0);
+        let action_url = input.action_url.clone().or_else(|| {
+            notification_action_url(&input.notification_type, input.entity_type.as_deref(), input.entity_id)
+        });
         let notification = sqlx::query_as::<_, QueuedNotification>(
             r#"
             INSERT INTO notification_queue (
                 user_id, business_id, notification_type,
                 title, title_th, message, message_th,
-                entity_type, entity_id, priority
+                entity_type, entity_id, action_url, priority, severity
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING id, user_id, business_id, notification_type,
                       title, title_th, message, message_th,
-                      entity_type, entity_id, scheduled_at, priority,
-                      status, created_at
+                      entity_type, entity_id, action_url, scheduled_at, priority,
+                      severity, status, created_at
             "#,
         )
         .bind(user_id)
@@ -370,11 +643,13 @@ impl NotificationService {
         .bind(&input.message_th)
         .bind(&input.entity_type)
         .bind(input.entity_id)
-        .bind(input.priority.unwrap_or(0))
+        .bind(action_url)
+        .bind(priority)
+        .bind(input.severity.unwrap_or_else(|| AlertSeverity::from_priority(priority)))
         .fetch_one(&self.db)
         .await?;
 
-        Ok(Some(notification))
+        Ok(notification)
     }
 
     /// Get pending notifications from queue
@@ -383,8 +658,8 @@ impl NotificationService {
             r#"
             SELECT id, user_id, business_id, notification_type,
                    title, title_th, message, message_th,
-                   entity_type, entity_id, scheduled_at, priority,
-                   status, created_at
+                   entity_type, entity_id, action_url, scheduled_at, priority,
+                   severity, status, created_at
             FROM notification_queue
             WHERE status = 'pending'
               AND scheduled_at <= NOW()
@@ -403,128 +678,176 @@ impl NotificationService {
     // Send Notifications
     // ========================================================================
 
-    /// Send a notification (determines channel and sends)
+    /// Send a notification to the channels its type or severity is routed
+    /// to. The in-app inbox always gets a copy; returns the log entry of the
+    /// first push channel, or of the in-app copy when nothing was pushed.
     pub async fn send_notification(
         &self,
         notification: &QueuedNotification,
     ) -> AppResult<NotificationLogEntry> {
-        // Determine the channel to use
-        let channel = self.get_notification_channel(notification.user_id).await?;
-
-        match channel {
-            NotificationChannel::Line => {
-                self.send_line_notification(notification).await
-            }
-            NotificationChannel::InApp => {
-                self.send_in_app_notification(notification).await
-            }
-            NotificationChannel::Email => {
-                // Email not implemented yet, fall back to in-app
-                self.send_in_app_notification(notification).await
-            }
-        }
+        let channels = self
+            .get_notification_channels(notification.user_id, &notification.notification_type, notification.severity)
+            .await?;
+        self.send_via(notification, &channels).await
     }
 
-    /// Get the preferred notification channel for a user
-    pub async fn get_notification_channel(&self, user_id: Uuid) -> AppResult<NotificationChannel> {
-        // Check if LINE is connected and enabled
-        let line_info = sqlx::query_as::<_, (bool, Option<String>)>(
-            r#"
-            SELECT np.line_enabled, lc.line_user_id
-            FROM notification_preferences np
-            LEFT JOIN line_connections lc ON lc.user_id = np.user_id
-            WHERE np.user_id = $1
-            "#,
-        )
-        .bind(user_id)
-        .fetch_optional(&self.db)
-        .await?;
+    /// Push channels for a user's notifications of a type and severity
+    pub async fn get_notification_channels(
+        &self,
+        user_id: Uuid,
+        notification_type: &NotificationType,
+        severity: AlertSeverity,
+    ) -> AppResult<Vec<NotificationChannel>> {
+        let prefs = match self.get_preferences(user_id).await {
+            Ok(prefs) => prefs,
+            // Users without preferences only get the in-app copy
+            Err(AppError::NotFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        Ok(route_channels(
+            prefs.channels_for_notification(notification_type, severity),
+            prefs.line_enabled,
+            prefs.email_enabled,
+        ))
+    }
 
-        match line_info {
-            Some((line_enabled, Some(_line_user_id))) if line_enabled && self.line_client.is_some() => {
-                Ok(NotificationChannel::Line)
+    /// Create the in-app copy, push to each channel and log the deliveries
+    async fn send_via(
+        &self,
+        notification: &QueuedNotification,
+        channels: &[NotificationChannel],
+    ) -> AppResult<NotificationLogEntry> {
+        let in_app = self.create_in_app_notification(notification).await?;
+
+        let mut first_entry = None;
+        for channel in channels {
+            let entry = match channel {
+                NotificationChannel::Line => self.push_line(notification, in_app.id).await?,
+                NotificationChannel::Email => self.push_email(notification, in_app.id).await?,
+                NotificationChannel::InApp => None,
+            };
+            if first_entry.is_none() {
+                first_entry = entry;
             }
-            _ => Ok(NotificationChannel::InApp),
         }
+
+        let log_entry = match first_entry {
+            Some(entry) => entry,
+            // Nothing pushed (not routed, not connected or not configured)
+            None => {
+                self.log_notification(
+                    Uuid::new_v4(),
+                    notification,
+                    NotificationChannel::InApp,
+                    NotificationStatus::Sent,
+                    None,
+                    None,
+                    Some(in_app.id),
+                )
+                .await?
+            }
+        };
+
+        // Update queue status
+        self.update_queue_status(notification.id, NotificationStatus::Sent).await?;
+
+        Ok(log_entry)
     }
 
-    /// Send notification via LINE
-    async fn send_line_notification(
+    /// Push a notification to LINE; `None` when the user has not connected
+    /// LINE or no LINE client is configured
+    async fn push_line(
         &self,
         notification: &QueuedNotification,
-    ) -> AppResult<NotificationLogEntry> {
-        // Get LINE user ID
+        in_app_notification_id: Uuid,
+    ) -> AppResult<Option<NotificationLogEntry>> {
+        let Some(client) = &self.line_client else {
+            return Ok(None);
+        };
         let line_user_id = sqlx::query_scalar::<_, String>(
             "SELECT line_user_id FROM line_connections WHERE user_id = $1",
         )
         .bind(notification.user_id)
         .fetch_optional(&self.db)
         .await?;
-
-        let line_user_id = match line_user_id {
-            Some(id) => id,
-            None => {
-                // Fall back to in-app if LINE not connected
-                return self.send_in_app_notification(notification).await;
-            }
+        let Some(line_user_id) = line_user_id else {
+            return Ok(None);
         };
 
-        // Send via LINE
-        let message_text = format!("{}\n\n{}", notification.title, notification.message);
-        let message = LineMessage::Text { text: message_text };
-
-        let (status, error_message, line_message_id) = match &self.line_client {
-            Some(client) => {
-                match client.send_push_message(&line_user_id, message).await {
-                    Ok(()) => (NotificationStatus::Sent, None, None),
-                    Err(e) => (NotificationStatus::Failed, Some(e), None),
-                }
-            }
-            None => {
-                // No LINE client, fall back to in-app
-                return self.send_in_app_notification(notification).await;
-            }
+        // Send with an acknowledge quick reply pointing at the log entry
+        let log_id = Uuid::new_v4();
+        let message = LineMessage::Text {
+            text: format!("{}\n\n{}", notification.title, notification.message),
+            quick_reply: Some(acknowledge_quick_reply(log_id)),
         };
+        let (status, error_message, line_message_id) =
+            match client.send_push_message(&line_user_id, message).await {
+                Ok(message_id) => (NotificationStatus::Sent, None, message_id),
+                Err(e) => (NotificationStatus::Failed, Some(e), None),
+            };
+
+        let log_entry = self
+            .log_notification(
+                log_id,
+                notification,
+                NotificationChannel::Line,
+                status,
+                error_message,
+                line_message_id,
+                Some(in_app_notification_id),
+            )
+            .await?;
 
-        // Log the notification
-        let log_entry = self.log_notification(
-            notification,
-            NotificationChannel::Line,
-            status,
-            error_message,
-            line_message_id,
-        ).await?;
-
-        // Update queue status
-        self.update_queue_status(notification.id, NotificationStatus::Sent).await?;
-
-        // Also create in-app notification
-        self.create_in_app_notification(notification).await?;
-
-        Ok(log_entry)
+        Ok(Some(log_entry))
     }
 
-    /// Send notification via in-app
-    async fn send_in_app_notification(
+    /// Email a notification in the user's language; `None` when no SMTP
+    /// relay is configured
+    async fn push_email(
         &self,
         notification: &QueuedNotification,
-    ) -> AppResult<NotificationLogEntry> {
-        // Create in-app notification
-        self.create_in_app_notification(notification).await?;
-
-        // Log the notification
-        let log_entry = self.log_notification(
-            notification,
-            NotificationChannel::InApp,
-            NotificationStatus::Sent,
-            None,
-            None,
-        ).await?;
+        in_app_notification_id: Uuid,
+    ) -> AppResult<Option<NotificationLogEntry>> {
+        let Some(mailer) = &self.mailer else {
+            return Ok(None);
+        };
+        let recipient = sqlx::query_as::<_, (String, String)>(
+            "SELECT email, preferred_language FROM users WHERE id = $1 AND is_active = true AND email IS NOT NULL",
+        )
+        .bind(notification.user_id)
+        .fetch_optional(&self.db)
+        .await?;
+        let Some((email, language)) = recipient else {
+            return Ok(None);
+        };
 
-        // Update queue status
-        self.update_queue_status(notification.id, NotificationStatus::Sent).await?;
+        let (subject, body) = if language == "th" {
+            (
+                notification.title_th.as_deref().unwrap_or(&notification.title),
+                notification.message_th.as_deref().unwrap_or(&notification.message),
+            )
+        } else {
+            (notification.title.as_str(), notification.message.as_str())
+        };
+        let (status, error_message) = match mailer.send(&email, subject, body, None).await {
+            Ok(()) => (NotificationStatus::Sent, None),
+            Err(e) => (NotificationStatus::Failed, Some(e.to_string())),
+        };
 
-        Ok(log_entry)
+        let log_entry = self
+            .log_notification(
+                Uuid::new_v4(),
+                notification,
+                NotificationChannel::Email,
+                status,
+                error_message,
+                None,
+                Some(in_app_notification_id),
+            )
+            .await?;
+
+        Ok(Some(log_entry))
     }
 
     /// Create an in-app notification
@@ -537,9 +860,9 @@ impl NotificationService {
             INSERT INTO in_app_notifications (
                 user_id, business_id, notification_type,
                 title, title_th, message, message_th,
-                entity_type, entity_id
+                entity_type, entity_id, action_url
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING id, user_id, business_id, notification_type,
                       title, title_th, message, message_th,
                       entity_type, entity_id, action_url,
@@ -555,6 +878,7 @@ impl NotificationService {
         .bind(&notification.message_th)
         .bind(&notification.entity_type)
         .bind(notification.entity_id)
+        .bind(&notification.action_url)
         .fetch_one(&self.db)
         .await?;
 
@@ -562,26 +886,31 @@ impl NotificationService {
     }
 
     /// Log a sent notification
+    #[allow(clippy::too_many_arguments)]
     async fn log_notification(
         &self,
+        log_id: Uuid,
         notification: &QueuedNotification,
         channel: NotificationChannel,
         status: NotificationStatus,
         error_message: Option<String>,
         line_message_id: Option<String>,
+        in_app_notification_id: Option<Uuid>,
     ) -> AppResult<NotificationLogEntry> {
         let log_entry = sqlx::query_as::<_, NotificationLogEntry>(
             r#"
             INSERT INTO notification_log (
-                user_id, business_id, notification_type, channel,
+                id, user_id, business_id, notification_type, channel,
                 title, title_th, message, message_th,
-                entity_type, entity_id, status, error_message, line_message_id
+                entity_type, entity_id, status, error_message, line_message_id,
+                priority, in_app_notification_id, severity
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            VALUES ($14, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $15, $16, $17)
             RETURNING id, user_id, business_id, notification_type, channel,
                       title, title_th, message, message_th,
                       entity_type, entity_id, status, error_message,
-                      line_message_id, sent_at, read_at, created_at
+                      line_message_id, priority, severity, sent_at, read_at,
+                      escalated_at, escalation_of, created_at
             "#,
         )
         .bind(notification.user_id)
@@ -597,12 +926,56 @@ impl NotificationService {
         .bind(&status)
         .bind(&error_message)
         .bind(&line_message_id)
+        .bind(log_id)
+        .bind(notification.priority)
+        .bind(in_app_notification_id)
+        .bind(notification.severity)
         .fetch_one(&self.db)
         .await?;
 
         Ok(log_entry)
     }
 
+    /// Record that the user acknowledged a LINE notification from the chat.
+    /// Repeated taps keep the first read time. The in-app copy is marked read
+    /// too, so copies sent on other channels are not escalated.
+    pub async fn acknowledge_line_notification(
+        &self,
+        user_id: Uuid,
+        log_id: Uuid,
+    ) -> AppResult<NotificationLogEntry> {
+        let log_entry = sqlx::query_as::<_, NotificationLogEntry>(
+            r#"
+            UPDATE notification_log
+            SET read_at = COALESCE(read_at, NOW()), status = 'read'
+            WHERE id = $1 AND user_id = $2 AND channel = 'line'
+            RETURNING id, user_id, business_id, notification_type, channel,
+                      title, title_th, message, message_th,
+                      entity_type, entity_id, status, error_message,
+                      line_message_id, priority, severity, sent_at, read_at,
+                      escalated_at, escalation_of, created_at
+            "#,
+        )
+        .bind(log_id)
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Notification".to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE in_app_notifications
+            SET is_read = true, read_at = COALESCE(read_at, NOW())
+            WHERE id = (SELECT in_app_notification_id FROM notification_log WHERE id = $1)
+            "#,
+        )
+        .bind(log_id)
+        .execute(&self.db)
+        .await?;
+
+        Ok(log_entry)
+    }
+
     /// Update queue status
     async fn update_queue_status(
         &self,
@@ -757,7 +1130,8 @@ impl NotificationService {
             SELECT id, user_id, business_id, notification_type, channel,
                    title, title_th, message, message_th,
                    entity_type, entity_id, status, error_message,
-                   line_message_id, sent_at, read_at, created_at
+                   line_message_id, priority, severity, sent_at, read_at,
+                      escalated_at, escalation_of, created_at
             FROM notification_log
             WHERE user_id = $1
             ORDER BY sent_at DESC
@@ -777,28 +1151,68 @@ impl NotificationService {
 // Notification Trigger Helpers
 // ============================================================================
 
+/// Client path a notification opens: the page of the entity it is about,
+/// or the report a summary comes from
+pub fn notification_action_url(
+    notification_type: &NotificationType,
+    entity_type: Option<&str>,
+    entity_id: Option<Uuid>,
+) -> Option<String> {
+    if *notification_type == NotificationType::WeeklyDigest {
+        return Some("/reports/weekly-digest".to_string());
+    }
+    let path = match entity_type? {
+        "kpi_targets" => return Some("/reports/kpi".to_string()),
+        "lot" => "/lots",
+        "plot" => "/plots",
+        "certification" => "/certifications",
+        "lot_insurance_policy" => "/insurance-policies",
+        "storage_location" => "/storage-locations",
+        "sales_lead" => "/sales/leads",
+        "roast_session" => "/roasting/sessions",
+        "cupping_session" => "/cupping/sessions",
+        "shipment" => "/shipments",
+        "lot_transfer" => "/lot-transfers",
+        _ => return None,
+    };
+    Some(format!("{}/{}", path, entity_id?))
+}
+
 /// Create a low inventory notification
 pub fn create_low_inventory_notification(
     lot_name: &str,
-    current_quantity: f64,
-    threshold: f64,
+    current_quantity: Decimal,
+    threshold: Decimal,
     stage: &str,
+    format: DisplayFormat,
+    severity: AlertSeverity,
+    lot_id: Uuid,
 ) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
     CreateNotificationInput {
         notification_type: NotificationType::LowInventory,
         title: format!("Low Inventory Alert: {}", lot_name),
         title_th: Some(format!("แจ้งเตือนสินค้าคงคลังต่ำ: {}", lot_name)),
         message: format!(
-            "Lot '{}' has fallen below the threshold. Current: {:.2} kg, Threshold: {:.2} kg, Stage: {}",
-            lot_name, current_quantity, threshold, stage
+            "Lot '{}' has fallen below the threshold. Current: {} kg, Threshold: {} kg, Stage: {}",
+            lot_name,
+            en.decimal(current_quantity, 2),
+            en.decimal(threshold, 2),
+            stage
         ),
         message_th: Some(format!(
-            "ล็อต '{}' มีปริมาณต่ำกว่าเกณฑ์ ปัจจุบัน: {:.2} กก., เกณฑ์: {:.2} กก., ขั้นตอน: {}",
-            lot_name, current_quantity, threshold, stage
+            "ล็อต '{}' มีปริมาณต่ำกว่าเกณฑ์ ปัจจุบัน: {} กก., เกณฑ์: {} กก., ขั้นตอน: {}",
+            lot_name,
+            th.decimal(current_quantity, 2),
+            th.decimal(threshold, 2),
+            stage
         )),
         entity_type: Some("lot".to_string()),
-        entity_id: None,
-        priority: Some(1),
+        entity_id: Some(lot_id),
+        action_url: notification_action_url(&NotificationType::LowInventory, Some("lot"), Some(lot_id)),
+        priority: Some(severity.priority()),
+        severity: Some(severity),
     }
 }
 
@@ -806,23 +1220,78 @@ pub fn create_low_inventory_notification(
 pub fn create_certification_expiring_notification(
     cert_name: &str,
     days_until: i32,
+    expiration_date: NaiveDate,
+    format: DisplayFormat,
     cert_id: Uuid,
 ) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
     CreateNotificationInput {
         notification_type: NotificationType::CertificationExpiring,
         title: format!("Certification Expiring: {}", cert_name),
         title_th: Some(format!("ใบรับรองใกล้หมดอายุ: {}", cert_name)),
         message: format!(
-            "Your certification '{}' will expire in {} days. Please renew to maintain compliance.",
-            cert_name, days_until
+            "Your certification '{}' will expire in {} days (on {}). Please renew to maintain compliance.",
+            cert_name,
+            en.integer(days_until as i64),
+            en.date_long(expiration_date, &Language::English)
         ),
         message_th: Some(format!(
-            "ใบรับรอง '{}' จะหมดอายุใน {} วัน กรุณาต่ออายุเพื่อรักษาการปฏิบัติตามมาตรฐาน",
-            cert_name, days_until
+            "ใบรับรอง '{}' จะหมดอายุใน {} วัน (วันที่ {}) กรุณาต่ออายุเพื่อรักษาการปฏิบัติตามมาตรฐาน",
+            cert_name,
+            th.integer(days_until as i64),
+            th.date_long(expiration_date, &Language::Thai)
         )),
         entity_type: Some("certification".to_string()),
         entity_id: Some(cert_id),
+        action_url: notification_action_url(
+            &NotificationType::CertificationExpiring,
+            Some("certification"),
+            Some(cert_id),
+        ),
         priority: Some(if days_until <= 30 { 2 } else { 1 }),
+        severity: None,
+    }
+}
+
+/// Create a lot insurance expiring notification
+pub fn create_insurance_expiring_notification(
+    policy_number: &str,
+    lot_code: &str,
+    days_until: i32,
+    expiration_date: NaiveDate,
+    format: DisplayFormat,
+    policy_id: Uuid,
+) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
+    CreateNotificationInput {
+        notification_type: NotificationType::InsuranceExpiring,
+        title: format!("Insurance Expiring: {}", lot_code),
+        title_th: Some(format!("ประกันภัยใกล้หมดอายุ: {}", lot_code)),
+        message: format!(
+            "Insurance policy {} for lot {} will expire in {} days (on {}). Renew it before the lot is stored or shipped uninsured.",
+            policy_number,
+            lot_code,
+            en.integer(days_until as i64),
+            en.date_long(expiration_date, &Language::English)
+        ),
+        message_th: Some(format!(
+            "กรมธรรม์ {} ของล็อต {} จะหมดอายุใน {} วัน (วันที่ {}) กรุณาต่ออายุก่อนจัดเก็บหรือขนส่งโดยไม่มีประกัน",
+            policy_number,
+            lot_code,
+            th.integer(days_until as i64),
+            th.date_long(expiration_date, &Language::Thai)
+        )),
+        entity_type: Some("lot_insurance_policy".to_string()),
+        entity_id: Some(policy_id),
+        action_url: notification_action_url(
+            &NotificationType::InsuranceExpiring,
+            Some("lot_insurance_policy"),
+            Some(policy_id),
+        ),
+        priority: Some(if days_until <= 7 { 2 } else { 1 }),
+        severity: None,
     }
 }
 
@@ -831,6 +1300,7 @@ pub fn create_weather_alert_notification(
     plot_name: &str,
     alert_message: &str,
     plot_id: Uuid,
+    severity: AlertSeverity,
 ) -> CreateNotificationInput {
     CreateNotificationInput {
         notification_type: NotificationType::WeatherAlert,
@@ -840,7 +1310,9 @@ pub fn create_weather_alert_notification(
         message_th: None,
         entity_type: Some("plot".to_string()),
         entity_id: Some(plot_id),
-        priority: Some(2),
+        action_url: notification_action_url(&NotificationType::WeatherAlert, Some("plot"), Some(plot_id)),
+        priority: Some(severity.priority()),
+        severity: Some(severity),
     }
 }
 
@@ -858,7 +1330,240 @@ pub fn create_processing_milestone_notification(
         message_th: Some(format!("ล็อต '{}' ถึงขั้นตอน: {}", lot_name, milestone)),
         entity_type: Some("lot".to_string()),
         entity_id: Some(lot_id),
+        action_url: notification_action_url(
+            &NotificationType::ProcessingMilestone,
+            Some("lot"),
+            Some(lot_id),
+        ),
         priority: Some(0),
+        severity: None,
+    }
+}
+
+/// Create a notification about a buyer's activity on a sales lead
+pub fn create_sales_lead_notification(
+    buyer_name: &str,
+    traceability_code: Option<&str>,
+    event: &str,
+    event_th: &str,
+    lead_id: Uuid,
+) -> CreateNotificationInput {
+    let lot = traceability_code.map(|code| format!(" ({})", code)).unwrap_or_default();
+    CreateNotificationInput {
+        notification_type: NotificationType::System,
+        title: format!("Buyer {}: {}", event, buyer_name),
+        title_th: Some(format!("ผู้ซื้อ{}: {}", event_th, buyer_name)),
+        message: format!("{} {}{}", buyer_name, event, lot),
+        message_th: Some(format!("{} {}{}", buyer_name, event_th, lot)),
+        entity_type: Some("sales_lead".to_string()),
+        entity_id: Some(lead_id),
+        action_url: notification_action_url(&NotificationType::System, Some("sales_lead"), Some(lead_id)),
+        priority: Some(1),
+        severity: None,
+    }
+}
+
+/// Create a notification of a lot transfer between businesses
+pub fn create_lot_transfer_notification(
+    business_name: &str,
+    traceability_code: &str,
+    event: &str,
+    event_th: &str,
+    transfer_id: Uuid,
+) -> CreateNotificationInput {
+    CreateNotificationInput {
+        notification_type: NotificationType::System,
+        title: format!("Lot transfer {}: {}", event, traceability_code),
+        title_th: Some(format!("การโอนล็อต{}: {}", event_th, traceability_code)),
+        message: format!("{} {} lot {}", business_name, event, traceability_code),
+        message_th: Some(format!("{} {}ล็อต {}", business_name, event_th, traceability_code)),
+        entity_type: Some("lot_transfer".to_string()),
+        entity_id: Some(transfer_id),
+        action_url: notification_action_url(&NotificationType::System, Some("lot_transfer"), Some(transfer_id)),
+        priority: Some(1),
+        severity: None,
+    }
+}
+
+/// Create the weekly digest for a business owner
+pub fn create_weekly_digest_notification(digest: &WeeklyDigest, format: DisplayFormat) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
+    let volumes = &digest.volumes;
+
+    let mut message = vec![format!(
+        "Harvested {} kg cherry ({} harvests), processed {} kg green ({} batches), roasted {} kg ({} sessions)",
+        en.decimal(volumes.harvested_cherry_kg, 1),
+        en.integer(volumes.harvest_count),
+        en.decimal(volumes.processed_green_kg, 1),
+        en.integer(volumes.batches_finished),
+        en.decimal(volumes.roasted_kg, 1),
+        en.integer(volumes.roast_sessions)
+    )];
+    let mut message_th = vec![format!(
+        "เก็บเกี่ยวเชอร์รี่ {} กก. ({} ครั้ง) แปรรูปได้สารกาแฟ {} กก. ({} ล็อต) คั่ว {} กก. ({} ครั้ง)",
+        th.decimal(volumes.harvested_cherry_kg, 1),
+        th.integer(volumes.harvest_count),
+        th.decimal(volumes.processed_green_kg, 1),
+        th.integer(volumes.batches_finished),
+        th.decimal(volumes.roasted_kg, 1),
+        th.integer(volumes.roast_sessions)
+    )];
+
+    if digest.samples_cupped > 0 {
+        let top = |f: &DisplayFormat| {
+            digest
+                .top_cuppings
+                .iter()
+                .map(|c| format!("{} {}", c.lot_code, f.decimal(c.total_score, 2)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        message.push(format!(
+            "Cupped {} samples, {} below specialty; best: {}",
+            en.integer(digest.samples_cupped),
+            en.integer(digest.samples_below_specialty),
+            top(&en)
+        ));
+        message_th.push(format!(
+            "คัปปิ้ง {} ตัวอย่าง ต่ำกว่าเกรดพิเศษ {} ตัวอย่าง คะแนนสูงสุด: {}",
+            th.integer(digest.samples_cupped),
+            th.integer(digest.samples_below_specialty),
+            top(&th)
+        ));
+    }
+
+    if digest.warning_alerts + digest.critical_alerts > 0 {
+        message.push(format!(
+            "Alerts: {} critical, {} warnings",
+            en.integer(digest.critical_alerts),
+            en.integer(digest.warning_alerts)
+        ));
+        message_th.push(format!(
+            "การแจ้งเตือน: วิกฤต {} รายการ เตือน {} รายการ",
+            th.integer(digest.critical_alerts),
+            th.integer(digest.warning_alerts)
+        ));
+    }
+
+    if !digest.expirations.is_empty() {
+        let list = |f: &DisplayFormat, language: &Language| {
+            digest
+                .expirations
+                .iter()
+                .map(|e| format!("{} ({})", e.name, f.date_long(e.expires_on, language)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        message.push(format!("Expiring soon: {}", list(&en, &Language::English)));
+        message_th.push(format!("ใกล้หมดอายุ: {}", list(&th, &Language::Thai)));
+    }
+
+    if digest.tasks.orders_to_ship + digest.tasks.batches_in_progress > 0 {
+        message.push(format!(
+            "Open: {} orders to ship, {} batches in processing",
+            en.integer(digest.tasks.orders_to_ship),
+            en.integer(digest.tasks.batches_in_progress)
+        ));
+        message_th.push(format!(
+            "งานค้าง: คำสั่งซื้อรอจัดส่ง {} รายการ ล็อตกำลังแปรรูป {} ล็อต",
+            th.integer(digest.tasks.orders_to_ship),
+            th.integer(digest.tasks.batches_in_progress)
+        ));
+    }
+
+    CreateNotificationInput {
+        notification_type: NotificationType::WeeklyDigest,
+        title: format!(
+            "Weekly summary: {} - {}",
+            en.date_long(digest.week_start, &Language::English),
+            en.date_long(digest.week_end, &Language::English)
+        ),
+        title_th: Some(format!(
+            "สรุปประจำสัปดาห์: {} - {}",
+            th.date_long(digest.week_start, &Language::Thai),
+            th.date_long(digest.week_end, &Language::Thai)
+        )),
+        message: message.join("\n"),
+        message_th: Some(message_th.join("\n")),
+        entity_type: Some("business".to_string()),
+        entity_id: Some(digest.business_id),
+        action_url: notification_action_url(
+            &NotificationType::WeeklyDigest,
+            Some("business"),
+            Some(digest.business_id),
+        ),
+        priority: Some(if digest.critical_alerts > 0 { 1 } else { 0 }),
+        severity: Some(AlertSeverity::Info),
+    }
+}
+
+/// Create the monthly summary of a season's KPIs against their targets
+pub fn create_kpi_summary_notification(
+    report: &KpiReport,
+    month: NaiveDate,
+    format: DisplayFormat,
+    targets_id: Uuid,
+) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
+    let tracked: Vec<_> = report.metrics.iter().filter(|m| m.target.is_some()).collect();
+    let behind = tracked.iter().filter(|m| m.on_track == Some(false)).count();
+
+    let value = |f: &DisplayFormat, kpi: KpiKind, value: Decimal, thai: bool| match (kpi, thai) {
+        (KpiKind::CherryKg, false) => format!("{} kg", f.decimal(value, 0)),
+        (KpiKind::CherryKg, true) => format!("{} กก.", f.decimal(value, 0)),
+        (KpiKind::AvgScore, _) => f.decimal(value, 2),
+        (KpiKind::SpecialtyPct, _) => f.percent(value, 1),
+        (KpiKind::Revenue, _) => format!("{} {}", f.decimal(value, 0), report.currency),
+    };
+    let summary = |f: &DisplayFormat, thai: bool| {
+        tracked
+            .iter()
+            .map(|m| {
+                let (name, of) = match (m.kpi, thai) {
+                    (KpiKind::CherryKg, false) => ("Cherry", "of"),
+                    (KpiKind::AvgScore, false) => ("Average score", "vs"),
+                    (KpiKind::SpecialtyPct, false) => ("Specialty", "vs"),
+                    (KpiKind::Revenue, false) => ("Revenue", "of"),
+                    (KpiKind::CherryKg, true) => ("เชอร์รี่", "จากเป้า"),
+                    (KpiKind::AvgScore, true) => ("คะแนนเฉลี่ย", "เป้า"),
+                    (KpiKind::SpecialtyPct, true) => ("สัดส่วนเกรดพิเศษ", "เป้า"),
+                    (KpiKind::Revenue, true) => ("รายได้", "จากเป้า"),
+                };
+                let status = match (m.on_track == Some(true), thai) {
+                    (true, false) => "on track",
+                    (false, false) => "behind",
+                    (true, true) => "ตามแผน",
+                    (false, true) => "ต่ำกว่าแผน",
+                };
+                let actual = m.actual.map(|v| value(f, m.kpi, v, thai)).unwrap_or_else(|| "-".to_string());
+                let target = m.target.map(|v| value(f, m.kpi, v, thai)).unwrap_or_default();
+                format!("{} {} {} {} ({})", name, actual, of, target, status)
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    CreateNotificationInput {
+        notification_type: NotificationType::System,
+        title: format!("KPI summary for {}: season {}", en.month(month), report.season_label),
+        title_th: Some(format!("สรุป KPI ประจำเดือน {}: ฤดูการผลิต {}", th.month(month), report.season_label)),
+        message: format!(
+            "Season to date ({}% of the season elapsed): {}",
+            en.decimal(report.season_elapsed_pct, 0),
+            summary(&en, false)
+        ),
+        message_th: Some(format!(
+            "ตั้งแต่ต้นฤดูถึงปัจจุบัน (ผ่านไป {}% ของฤดู): {}",
+            th.decimal(report.season_elapsed_pct, 0),
+            summary(&th, true)
+        )),
+        entity_type: Some("kpi_targets".to_string()),
+        entity_id: Some(targets_id),
+        action_url: notification_action_url(&NotificationType::System, Some("kpi_targets"), Some(targets_id)),
+        priority: Some(if behind > 0 { 1 } else { 0 }),
+        severity: Some(AlertSeverity::Info),
     }
 }
 
@@ -909,15 +1614,16 @@ impl NotificationService {
     /// Returns the number of notifications queued
     pub async fn trigger_low_inventory_alerts(&self, business_id: Uuid) -> AppResult<i32> {
         // Get triggered inventory alerts
-        let alerts = sqlx::query_as::<_, (Uuid, Uuid, String, String, f64, f64, Uuid)>(
+        let format = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let alerts = sqlx::query_as::<_, (Uuid, Uuid, String, String, Decimal, Decimal, AlertSeverity, Uuid)>(
             r#"
             SELECT ia.id, ia.lot_id, l.name, ia.stage::text, 
-                   COALESCE(get_lot_inventory_balance(ia.lot_id, ia.stage::text), 0)::float8 as current_qty,
-                   ia.threshold_kg::float8,
-                   b.owner_id
+                   COALESCE(get_lot_inventory_balance(ia.lot_id, ia.stage::text), 0)::numeric as current_qty,
+                   ia.threshold_kg, ia.severity,
+                   owner.id AS owner_id
             FROM inventory_alerts ia
             JOIN lots l ON l.id = ia.lot_id
-            JOIN businesses b ON b.id = ia.business_id
+            JOIN users owner ON owner.id = business_owner_id(ia.business_id)
             WHERE ia.business_id = $1
               AND ia.is_active = true
               AND COALESCE(get_lot_inventory_balance(ia.lot_id, ia.stage::text), 0) <= ia.threshold_kg
@@ -929,12 +1635,15 @@ impl NotificationService {
         .await?;
 
         let mut count = 0;
-        for (alert_id, lot_id, lot_name, stage, current_qty, threshold, user_id) in alerts {
+        for (alert_id, lot_id, lot_name, stage, current_qty, threshold, severity, user_id) in alerts {
             let notification = create_low_inventory_notification(
                 &lot_name,
                 current_qty,
                 threshold,
                 &stage,
+                format,
+                severity,
+                lot_id,
             );
 
             // Queue the notification
@@ -955,13 +1664,15 @@ impl NotificationService {
     /// Returns the number of notifications queued
     pub async fn trigger_certification_expiry_alerts(&self, business_id: Uuid) -> AppResult<i32> {
         // Get certifications expiring within 90 days that haven't been notified recently
-        let certs = sqlx::query_as::<_, (Uuid, String, i32, Uuid)>(
+        let format = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let certs = sqlx::query_as::<_, (Uuid, String, i32, NaiveDate, Uuid)>(
             r#"
             SELECT c.id, c.certification_name, 
                    (c.expiration_date - CURRENT_DATE)::int as days_until,
-                   b.owner_id
+                   c.expiration_date,
+                   owner.id AS owner_id
             FROM certifications c
-            JOIN businesses b ON b.id = c.business_id
+            JOIN users owner ON owner.id = business_owner_id(c.business_id)
             LEFT JOIN certification_alerts ca ON ca.certification_id = c.id
             WHERE c.business_id = $1
               AND c.is_active = true
@@ -982,10 +1693,12 @@ impl NotificationService {
         .await?;
 
         let mut count = 0;
-        for (cert_id, cert_name, days_until, user_id) in certs {
+        for (cert_id, cert_name, days_until, expiration_date, user_id) in certs {
             let notification = create_certification_expiring_notification(
                 &cert_name,
                 days_until,
+                expiration_date,
+                format,
                 cert_id,
             );
 
@@ -1019,22 +1732,73 @@ impl NotificationService {
         Ok(count)
     }
 
+    /// Trigger notifications for lot insurance policies about to lapse
+    /// Returns the number of notifications queued
+    pub async fn trigger_insurance_expiry_alerts(&self, business_id: Uuid) -> AppResult<i32> {
+        // Each policy is reminded once, inside the expiry window
+        let format = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let policies = sqlx::query_as::<_, (Uuid, String, String, i32, NaiveDate, Uuid)>(
+            r#"
+            SELECT p.id, p.policy_number, l.traceability_code,
+                   (p.valid_until - CURRENT_DATE)::int AS days_until,
+                   p.valid_until,
+                   owner.id AS owner_id
+            FROM lot_insurance_policies p
+            JOIN lots l ON l.id = p.lot_id
+            JOIN users owner ON owner.id = business_owner_id(p.business_id)
+            WHERE p.business_id = $1
+              AND p.expiry_alert_sent_at IS NULL
+              AND p.valid_until >= CURRENT_DATE
+              AND p.valid_until <= CURRENT_DATE + $2::int
+            "#,
+        )
+        .bind(business_id)
+        .bind(EXPIRY_ALERT_DAYS as i32)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut count = 0;
+        for (policy_id, policy_number, lot_code, days_until, valid_until, user_id) in policies {
+            let notification = create_insurance_expiring_notification(
+                &policy_number,
+                &lot_code,
+                days_until,
+                valid_until,
+                format,
+                policy_id,
+            );
+
+            if self.queue_notification(user_id, business_id, notification).await?.is_some() {
+                sqlx::query("UPDATE lot_insurance_policies SET expiry_alert_sent_at = NOW() WHERE id = $1")
+                    .bind(policy_id)
+                    .execute(&self.db)
+                    .await?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
     /// Trigger notifications for weather alerts
     /// Returns the number of notifications queued
     pub async fn trigger_weather_alerts(&self, business_id: Uuid) -> AppResult<i32> {
-        // Get active weather alerts that have been triggered
-        let alerts = sqlx::query_as::<_, (Uuid, Uuid, String, String, Uuid)>(
+        // Get active weather alerts that have been triggered; snoozed alerts
+        // are skipped until the snooze expires
+        let alerts = sqlx::query_as::<_, (Uuid, Uuid, String, String, AlertSeverity, Uuid)>(
             r#"
             SELECT wa.id, wa.plot_id, p.name, 
                    COALESCE(wa.alert_type, 'rain') || ' alert for ' || p.name as alert_message,
-                   b.owner_id
+                   wa.severity,
+                   owner.id AS owner_id
             FROM weather_alerts wa
             JOIN plots p ON p.id = wa.plot_id
-            JOIN businesses b ON b.id = wa.business_id
+            JOIN users owner ON owner.id = business_owner_id(wa.business_id)
             WHERE wa.business_id = $1
               AND wa.is_active = true
               AND wa.notify_line = true
               AND (wa.last_triggered_at IS NULL OR wa.last_triggered_at < NOW() - INTERVAL '6 hours')
+              AND (wa.muted_until IS NULL OR wa.muted_until <= NOW())
             "#,
         )
         .bind(business_id)
@@ -1042,11 +1806,12 @@ impl NotificationService {
         .await?;
 
         let mut count = 0;
-        for (alert_id, plot_id, plot_name, alert_message, user_id) in alerts {
+        for (alert_id, plot_id, plot_name, alert_message, severity, user_id) in alerts {
             let notification = create_weather_alert_notification(
                 &plot_name,
                 &alert_message,
                 plot_id,
+                severity,
             );
 
             // Queue the notification
@@ -1076,6 +1841,53 @@ impl NotificationService {
         self.queue_notification(user_id, business_id, notification).await
     }
 
+    /// Send the owner the KPI summary of the month just ended, once, when
+    /// the season it falls in has targets
+    /// Returns the number of notifications queued
+    pub async fn trigger_kpi_summary(&self, business_id: Uuid) -> AppResult<i32> {
+        let Some(month_end) = Utc::now().date_naive().with_day(1).and_then(|d| d.pred_opt()) else {
+            return Ok(0);
+        };
+        let month = month_end.with_day(1).unwrap_or(month_end);
+
+        let kpi = KpiService::new(self.db.clone());
+        let business = BusinessService::new(self.db.clone());
+        let calendar = business.get_reporting_calendar(business_id).await?;
+        let Some(targets) = kpi.get_targets(business_id, calendar.season_of(month_end)).await? else {
+            return Ok(0);
+        };
+        if targets.summary_sent_for.is_some_and(|sent| sent >= month) {
+            return Ok(0);
+        }
+
+        let report = kpi.report_as_of(business_id, &calendar, targets.season, month_end).await?;
+        let format = business.get_display_format(business_id).await?;
+        let notification = create_kpi_summary_notification(&report, month, format, targets.id);
+
+        if self.notify_business_owner(business_id, notification).await?.is_some() {
+            kpi.mark_summary_sent(targets.id, month).await?;
+            return Ok(1);
+        }
+        Ok(0)
+    }
+
+    /// Queue a notification for the owner of a business
+    pub async fn notify_business_owner(
+        &self,
+        business_id: Uuid,
+        input: CreateNotificationInput,
+    ) -> AppResult<Option<QueuedNotification>> {
+        let owner_id = sqlx::query_scalar::<_, Option<Uuid>>("SELECT business_owner_id($1)")
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        match owner_id {
+            Some(user_id) => self.queue_notification(user_id, business_id, input).await,
+            None => Ok(None),
+        }
+    }
+
     /// Process all pending notifications in the queue
     /// Returns the number of notifications sent
     pub async fn process_notification_queue(&self, batch_size: i32) -> AppResult<i32> {
@@ -1108,9 +1920,305 @@ impl NotificationService {
         // Trigger certification expiry alerts
         total += self.trigger_certification_expiry_alerts(business_id).await?;
 
+        // Trigger lot insurance expiry alerts
+        total += self.trigger_insurance_expiry_alerts(business_id).await?;
+
         // Trigger weather alerts
         total += self.trigger_weather_alerts(business_id).await?;
 
+        // Monthly KPI summary
+        total += self.trigger_kpi_summary(business_id).await?;
+
+        // Escalate unacknowledged high-priority notifications
+        total += self.run_escalations(business_id).await?;
+
         Ok(total)
     }
 }
+
+// ============================================================================
+// Escalation Policies
+// ============================================================================
+
+/// Escalation policy for unacknowledged notifications
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EscalationPolicy {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub notification_type: Option<NotificationType>,
+    pub min_priority: i32,
+    pub escalate_after_hours: i32,
+    pub escalate_to_user_id: Option<Uuid>,
+    pub channel: Option<NotificationChannel>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for creating an escalation policy
+#[derive(Debug, Deserialize)]
+pub struct CreateEscalationPolicyInput {
+    /// Restrict to one notification type (default: any)
+    pub notification_type: Option<NotificationType>,
+    /// Minimum priority to escalate (default: 2)
+    pub min_priority: Option<i32>,
+    /// Hours to wait for a read/acknowledgement (default: 4)
+    pub escalate_after_hours: Option<i32>,
+    /// Backup contact (default: business owner)
+    pub escalate_to_user_id: Option<Uuid>,
+    /// Escalation channel (default: alternate of the original channel)
+    pub channel: Option<NotificationChannel>,
+}
+
+/// Unacknowledged notification matched to an escalation policy
+#[derive(Debug, FromRow)]
+struct EscalationCandidate {
+    log_id: Uuid,
+    in_app_notification_id: Option<Uuid>,
+    recipient_name: String,
+    notification_type: NotificationType,
+    channel: NotificationChannel,
+    title: String,
+    title_th: Option<String>,
+    message: String,
+    message_th: Option<String>,
+    entity_type: Option<String>,
+    entity_id: Option<Uuid>,
+    priority: i32,
+    escalate_after_hours: i32,
+    escalate_to_user_id: Option<Uuid>,
+    policy_channel: Option<NotificationChannel>,
+    owner_id: Uuid,
+}
+
+/// Channel to use for an escalation: the policy's channel, or a different
+/// channel than the one that went unanswered
+pub fn escalation_channel(
+    original: &NotificationChannel,
+    policy_channel: Option<&NotificationChannel>,
+) -> NotificationChannel {
+    match policy_channel {
+        Some(channel) => channel.clone(),
+        None => match original {
+            NotificationChannel::Line => NotificationChannel::InApp,
+            NotificationChannel::InApp | NotificationChannel::Email => NotificationChannel::Line,
+        },
+    }
+}
+
+/// Build the escalated notification sent to the backup contact
+pub fn create_escalation_notification(
+    recipient_name: &str,
+    hours: i32,
+    title: &str,
+    title_th: Option<&str>,
+    message: &str,
+    message_th: Option<&str>,
+) -> (String, String, String, String) {
+    (
+        format!("[Escalated] {}", title),
+        format!("[แจ้งเตือนซ้ำ] {}", title_th.unwrap_or(title)),
+        format!(
+            "Not acknowledged by {} within {} hours.\n\n{}",
+            recipient_name, hours, message
+        ),
+        format!(
+            "{} ยังไม่รับทราบภายใน {} ชั่วโมง\n\n{}",
+            recipient_name,
+            hours,
+            message_th.unwrap_or(message)
+        ),
+    )
+}
+
+impl NotificationService {
+    // ========================================================================
+    // Escalation Policies
+    // ========================================================================
+
+    /// Create an escalation policy
+    pub async fn create_escalation_policy(
+        &self,
+        business_id: Uuid,
+        input: CreateEscalationPolicyInput,
+    ) -> AppResult<EscalationPolicy> {
+        let escalate_after_hours = input.escalate_after_hours.unwrap_or(4);
+        if escalate_after_hours <= 0 {
+            return Err(AppError::Validation {
+                field: "escalate_after_hours".to_string(),
+                message: "Escalation delay must be at least 1 hour".to_string(),
+                message_th: "ระยะเวลาก่อนแจ้งเตือนซ้ำต้องอย่างน้อย 1 ชั่วโมง".to_string(),
+            });
+        }
+
+        if let Some(user_id) = input.escalate_to_user_id {
+            let user_exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND business_id = $2 AND is_active = true)",
+            )
+            .bind(user_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+
+            if !user_exists {
+                return Err(AppError::NotFound("User".to_string()));
+            }
+        }
+
+        let policy = sqlx::query_as::<_, EscalationPolicy>(
+            r#"
+            INSERT INTO escalation_policies (
+                business_id, notification_type, min_priority, escalate_after_hours,
+                escalate_to_user_id, channel
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, business_id, notification_type, min_priority, escalate_after_hours,
+                      escalate_to_user_id, channel, is_active, created_at, updated_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(&input.notification_type)
+        .bind(input.min_priority.unwrap_or(2))
+        .bind(escalate_after_hours)
+        .bind(input.escalate_to_user_id)
+        .bind(&input.channel)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(policy)
+    }
+
+    /// List escalation policies for a business
+    pub async fn list_escalation_policies(&self, business_id: Uuid) -> AppResult<Vec<EscalationPolicy>> {
+        let policies = sqlx::query_as::<_, EscalationPolicy>(
+            r#"
+            SELECT id, business_id, notification_type, min_priority, escalate_after_hours,
+                   escalate_to_user_id, channel, is_active, created_at, updated_at
+            FROM escalation_policies
+            WHERE business_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(policies)
+    }
+
+    /// Delete an escalation policy
+    pub async fn delete_escalation_policy(&self, business_id: Uuid, policy_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM escalation_policies WHERE id = $1 AND business_id = $2")
+            .bind(policy_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Escalation policy".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Escalate sent notifications that were not read or acknowledged within
+    /// the policy window. Each notification is escalated at most once.
+    /// Returns the number of escalations sent
+    pub async fn run_escalations(&self, business_id: Uuid) -> AppResult<i32> {
+        // Pick the most specific matching policy (typed before "any"), then the shortest delay.
+        // A notification pushed to several channels is escalated once.
+        let candidates = sqlx::query_as::<_, EscalationCandidate>(
+            r#"
+            SELECT DISTINCT ON (COALESCE(nl.in_app_notification_id, nl.id))
+                   nl.id AS log_id, nl.in_app_notification_id, u.name AS recipient_name,
+                   nl.notification_type, nl.channel,
+                   nl.title, nl.title_th, nl.message, nl.message_th,
+                   nl.entity_type, nl.entity_id, nl.priority,
+                   ep.escalate_after_hours, ep.escalate_to_user_id,
+                   ep.channel AS policy_channel, owner.id AS owner_id
+            FROM notification_log nl
+            JOIN users u ON u.id = nl.user_id
+            JOIN users owner ON owner.id = business_owner_id(nl.business_id)
+            LEFT JOIN in_app_notifications ian ON ian.id = nl.in_app_notification_id
+            JOIN LATERAL (
+                SELECT p.escalate_after_hours, p.escalate_to_user_id, p.channel
+                FROM escalation_policies p
+                WHERE p.business_id = nl.business_id
+                  AND p.is_active = true
+                  AND nl.priority >= p.min_priority
+                  AND (p.notification_type IS NULL OR p.notification_type = nl.notification_type)
+                  AND nl.sent_at <= NOW() - make_interval(hours => p.escalate_after_hours)
+                ORDER BY p.notification_type NULLS LAST, p.escalate_after_hours
+                LIMIT 1
+            ) ep ON true
+            WHERE nl.business_id = $1
+              AND nl.status = 'sent'
+              AND nl.read_at IS NULL
+              AND COALESCE(ian.is_read, false) = false
+              AND nl.escalated_at IS NULL
+              AND nl.escalation_of IS NULL
+            ORDER BY COALESCE(nl.in_app_notification_id, nl.id), nl.sent_at
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut count = 0;
+        for candidate in candidates {
+            let target_user_id = candidate.escalate_to_user_id.unwrap_or(candidate.owner_id);
+            let channel = escalation_channel(&candidate.channel, candidate.policy_channel.as_ref());
+
+            let (title, title_th, message, message_th) = create_escalation_notification(
+                &candidate.recipient_name,
+                candidate.escalate_after_hours,
+                &candidate.title,
+                candidate.title_th.as_deref(),
+                &candidate.message,
+                candidate.message_th.as_deref(),
+            );
+            let input = CreateNotificationInput {
+                notification_type: candidate.notification_type,
+                title,
+                title_th: Some(title_th),
+                message,
+                message_th: Some(message_th),
+                entity_type: candidate.entity_type,
+                entity_id: candidate.entity_id,
+                action_url: None,
+                priority: Some(candidate.priority + 1),
+                severity: None,
+            };
+
+            // Escalations bypass preferences: the business opted in via the policy
+            let queued = self.insert_queue_entry(target_user_id, business_id, &input).await?;
+            let sent = self.send_via(&queued, &[channel]).await;
+
+            let entry = match sent {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::error!("Failed to escalate notification {}: {}", candidate.log_id, e);
+                    self.update_queue_status(queued.id, NotificationStatus::Failed).await?;
+                    continue;
+                }
+            };
+
+            sqlx::query("UPDATE notification_log SET escalation_of = $1 WHERE id = $2")
+                .bind(candidate.log_id)
+                .bind(entry.id)
+                .execute(&self.db)
+                .await?;
+            sqlx::query(
+                "UPDATE notification_log SET escalated_at = NOW() WHERE id = $1 OR in_app_notification_id = $2",
+            )
+            .bind(candidate.log_id)
+            .bind(candidate.in_app_notification_id)
+            .execute(&self.db)
+            .await?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,318 @@
+//! PDF document generation
+//!
+//! Small drawing layer over `printpdf` for fixed-layout documents such as
+//! spec sheets and label sheets: coordinates in millimetres from the
+//! top-left corner of the current page, word wrapped text, shaded and
+//! outlined boxes, rules, polygons for charts and vector QR codes. Text
+//! uses the TrueType font from the `[pdf]` configuration when one is set
+//! (needed for Thai) and built-in Helvetica otherwise.
+
+use printpdf::path::{PaintMode, WindingOrder};
+use printpdf::{
+    BuiltinFont, Color, Greyscale, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference,
+    PdfLayerReference, Point, Polygon, Rect,
+};
+use qrcode::QrCode;
+
+use crate::config::PdfConfig;
+use crate::error::{AppError, AppResult};
+use crate::services::xlsx::display_width;
+
+/// A4 portrait page size in millimetres
+pub const A4_WIDTH_MM: f32 = 210.0;
+pub const A4_HEIGHT_MM: f32 = 297.0;
+
+/// Average glyph advance as a fraction of the font size, used for wrapping
+const AVERAGE_CHAR_WIDTH_EM: f32 = 0.5;
+
+/// Millimetres per typographic point
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+/// Font size and weight of a run of text
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    pub size: f32,
+    pub bold: bool,
+}
+
+impl TextStyle {
+    pub const fn regular(size: f32) -> Self {
+        Self { size, bold: false }
+    }
+
+    pub const fn bold(size: f32) -> Self {
+        Self { size, bold: true }
+    }
+}
+
+/// Font files loaded from configuration; empty when using built-in fonts
+#[derive(Debug, Clone, Default)]
+pub struct PdfFonts {
+    regular: Option<Vec<u8>>,
+    bold: Option<Vec<u8>>,
+}
+
+impl PdfFonts {
+    /// Read the configured font files
+    pub async fn load(config: &PdfConfig) -> AppResult<Self> {
+        if config.font_path.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let read = |path: String| async move {
+            tokio::fs::read(&path)
+                .await
+                .map_err(|e| AppError::Configuration(format!("Cannot read PDF font '{}': {}", path, e)))
+        };
+        let regular = read(config.font_path.clone()).await?;
+        let bold = if config.bold_font_path.is_empty() {
+            regular.clone()
+        } else {
+            read(config.bold_font_path.clone()).await?
+        };
+
+        Ok(Self {
+            regular: Some(regular),
+            bold: Some(bold),
+        })
+    }
+
+    /// Whether text outside Latin-1 (e.g. Thai) can be printed
+    pub fn supports_thai(&self) -> bool {
+        self.regular.is_some()
+    }
+}
+
+/// PDF under construction; drawing goes to the most recently added page
+pub struct PdfPage {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    width: f32,
+    height: f32,
+}
+
+impl PdfPage {
+    /// Start a document with one page of the given size
+    pub fn new(title: &str, width_mm: f32, height_mm: f32, fonts: &PdfFonts) -> AppResult<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(width_mm), Mm(height_mm), "Layer 1");
+        let layer = doc.get_page(page).get_layer(layer);
+
+        let (regular, bold) = match (&fonts.regular, &fonts.bold) {
+            (Some(regular), Some(bold)) => (
+                doc.add_external_font(regular.as_slice()).map_err(pdf_error)?,
+                doc.add_external_font(bold.as_slice()).map_err(pdf_error)?,
+            ),
+            _ => (
+                doc.add_builtin_font(BuiltinFont::Helvetica).map_err(pdf_error)?,
+                doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(pdf_error)?,
+            ),
+        };
+
+        Ok(Self {
+            doc,
+            layer,
+            regular,
+            bold,
+            width: width_mm,
+            height: height_mm,
+        })
+    }
+
+    /// Continue on a new page of the same size
+    pub fn add_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(self.width), Mm(self.height), "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+    }
+
+    /// Write a single line of text with its baseline at `y`
+    pub fn text(&self, x: f32, y: f32, style: TextStyle, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.layer.set_fill_color(grey(0.0));
+        let font = if style.bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, style.size, Mm(x), Mm(self.height - y), font);
+    }
+
+    /// Write word-wrapped text starting at `y`, at most `max_lines` lines;
+    /// returns the height used
+    pub fn wrapped_text(
+        &self,
+        x: f32,
+        y: f32,
+        width: f32,
+        style: TextStyle,
+        text: &str,
+        max_lines: usize,
+    ) -> f32 {
+        let line_height = line_height(style.size);
+        let lines = wrap_text(text, chars_per_line(width, style.size));
+        let shown = lines.len().min(max_lines);
+        for (i, line) in lines.iter().take(shown).enumerate() {
+            let line = if i + 1 == shown && lines.len() > shown {
+                format!("{}...", line.trim_end_matches(['.', ',', ' ']))
+            } else {
+                line.clone()
+            };
+            self.text(x, y + i as f32 * line_height, style, &line);
+        }
+        shown as f32 * line_height
+    }
+
+    /// Fill a rectangle whose top-left corner is at (`x`, `y`); `level` is
+    /// 0.0 (black) to 1.0 (white)
+    pub fn fill_rect(&self, x: f32, y: f32, width: f32, height: f32, level: f32) {
+        self.layer.set_fill_color(grey(level));
+        self.layer.add_rect(Rect::new(
+            Mm(x),
+            Mm(self.height - y - height),
+            Mm(x + width),
+            Mm(self.height - y),
+        ));
+    }
+
+    /// Outline a rectangle whose top-left corner is at (`x`, `y`)
+    pub fn outline_rect(&self, x: f32, y: f32, width: f32, height: f32, thickness: f32, level: f32) {
+        self.layer.set_outline_color(grey(level));
+        self.layer.set_outline_thickness(thickness);
+        let (top, bottom) = (self.height - y, self.height - y - height);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(x), Mm(top)), false),
+                (Point::new(Mm(x + width), Mm(top)), false),
+                (Point::new(Mm(x + width), Mm(bottom)), false),
+                (Point::new(Mm(x), Mm(bottom)), false),
+            ],
+            is_closed: true,
+        });
+    }
+
+    /// Draw a horizontal rule
+    pub fn rule(&self, x1: f32, x2: f32, y: f32, thickness: f32, level: f32) {
+        self.layer.set_outline_color(grey(level));
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(x1), Mm(self.height - y)), false),
+                (Point::new(Mm(x2), Mm(self.height - y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    /// Outline a closed polygon through `points`
+    pub fn polygon(&self, points: &[(f32, f32)], thickness: f32, level: f32) {
+        self.layer.set_outline_color(grey(level));
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: self.points(points),
+            is_closed: true,
+        });
+    }
+
+    /// Fill a closed polygon through `points`
+    pub fn fill_polygon(&self, points: &[(f32, f32)], level: f32) {
+        self.layer.set_fill_color(grey(level));
+        self.layer.add_polygon(Polygon {
+            rings: vec![self.points(points)],
+            mode: PaintMode::Fill,
+            winding_order: WindingOrder::NonZero,
+        });
+    }
+
+    fn points(&self, points: &[(f32, f32)]) -> Vec<(Point, bool)> {
+        points
+            .iter()
+            .map(|(x, y)| (Point::new(Mm(*x), Mm(self.height - y)), false))
+            .collect()
+    }
+
+    /// Draw a QR code as vector modules in a `size` x `size` square
+    pub fn qr_code(&self, x: f32, y: f32, size: f32, data: &str) -> AppResult<()> {
+        let code = QrCode::new(data.as_bytes())
+            .map_err(|e| AppError::Internal(format!("QR code generation error: {}", e)))?;
+        let modules = code.width();
+        let module = size / modules as f32;
+
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            if color == qrcode::Color::Dark {
+                let (row, col) = (i / modules, i % modules);
+                // Slight overlap avoids hairline gaps between modules in viewers
+                self.fill_rect(
+                    x + col as f32 * module,
+                    y + row as f32 * module,
+                    module + 0.05,
+                    module + 0.05,
+                    0.0,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the document into PDF bytes
+    pub fn finish(self) -> AppResult<Vec<u8>> {
+        self.doc.save_to_bytes().map_err(pdf_error)
+    }
+}
+
+fn pdf_error(e: printpdf::Error) -> AppError {
+    AppError::Internal(format!("PDF generation error: {}", e))
+}
+
+fn grey(level: f32) -> Color {
+    Color::Greyscale(Greyscale::new(level, None))
+}
+
+/// Baseline-to-baseline distance for a font size, in millimetres
+pub fn line_height(size: f32) -> f32 {
+    size * 1.35 * MM_PER_PT
+}
+
+/// Approximate number of characters that fit in `width` millimetres
+pub fn chars_per_line(width: f32, size: f32) -> usize {
+    ((width / (size * AVERAGE_CHAR_WIDTH_EM * MM_PER_PT)) as usize).max(1)
+}
+
+/// Greedy word wrap by display width; words longer than a line (or Thai
+/// text without spaces) are broken mid-word
+pub fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = if line.is_empty() {
+                display_width(word)
+            } else {
+                display_width(&line) + 1 + display_width(word)
+            };
+            if needed <= max_width {
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                if display_width(&line) >= max_width && display_width(&c.to_string()) > 0 {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
@@ -0,0 +1,338 @@
+//! Picker quality scoring
+//!
+//! Scores each picker, or each crew, over a crop season on the quality of
+//! the cherry they pick. Ripeness comes from their own harvests, averaged by
+//! cherry weight. Defects come from the latest grading of each lot they
+//! picked into, in full defects per 350 g sample, and are shared among the
+//! lot's pickers by their part of its cherry. The two make a score out of
+//! 100 that the report ranks pickers by and pays a per-kg bonus on above a
+//! threshold. Pickers are matched by name, ignoring case and extra spaces.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::reporting_period::resolve_season;
+use crate::services::BusinessService;
+
+/// Score a picker must reach for a bonus unless the report sets another
+pub const DEFAULT_BONUS_MIN_SCORE: Decimal = Decimal::from_parts(80, 0, 0, false, 0);
+
+/// Share of the score from ripeness (60%); defects make up the rest
+pub const RIPENESS_WEIGHT: Decimal = Decimal::from_parts(6, 0, 0, false, 1);
+
+/// Points of defect score lost per full defect in a 350 g sample
+pub const POINTS_PER_DEFECT: Decimal = Decimal::from_parts(4, 0, 0, false, 0);
+
+/// Sample size defect counts are compared at, in grams
+pub const DEFECT_SAMPLE_GRAMS: Decimal = Decimal::from_parts(350, 0, 0, false, 0);
+
+/// Picker quality service
+#[derive(Clone)]
+pub struct PickerQualityService {
+    db: PgPool,
+}
+
+/// Who a report scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickerGrouping {
+    #[default]
+    Picker,
+    Crew,
+}
+
+/// Query for the picker quality report
+#[derive(Debug, Default, Deserialize)]
+pub struct PickerQualityQuery {
+    /// Crop season by the year it starts in; the current season by default
+    pub season: Option<i32>,
+    /// Season as a report period such as `season:2024/25`; overrides `season`
+    pub period: Option<String>,
+    /// Score pickers (default) or crews
+    pub group_by: Option<PickerGrouping>,
+    /// Score needed for a bonus (default 80)
+    pub min_score: Option<Decimal>,
+    /// Bonus in THB per kg of cherry picked, paid above `min_score`
+    pub bonus_per_kg: Option<Decimal>,
+}
+
+/// Harvest of a picker or crew in the season
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickedHarvest {
+    pub picker: String,
+    pub lot_id: Uuid,
+    pub cherry_kg: Decimal,
+    pub underripe_percent: i32,
+    pub ripe_percent: i32,
+    pub overripe_percent: i32,
+}
+
+/// Quality of one picker or crew over a season
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PickerQuality {
+    pub rank: usize,
+    /// Name as first entered
+    pub picker_name: String,
+    pub harvests: usize,
+    pub cherry_kg: Decimal,
+    /// Averages weighted by cherry weight
+    pub underripe_percent: Decimal,
+    pub ripe_percent: Decimal,
+    pub overripe_percent: Decimal,
+    /// Cherry picked into lots that have been graded
+    pub graded_cherry_kg: Decimal,
+    /// Full defects per 350 g in the graded lots picked into
+    pub defects_per_350g: Option<Decimal>,
+    pub defect_score: Option<Decimal>,
+    /// Ripeness and defect scores combined; ripeness alone until a lot
+    /// picked into is graded
+    pub quality_score: Decimal,
+    pub bonus_eligible: bool,
+    pub bonus_thb: Option<Decimal>,
+}
+
+/// Picker quality over a crop season
+#[derive(Debug, Clone, Serialize)]
+pub struct PickerQualityReport {
+    pub season: i32,
+    pub season_label: String,
+    pub group_by: PickerGrouping,
+    pub min_score: Decimal,
+    pub bonus_per_kg: Option<Decimal>,
+    /// Highest score first
+    pub pickers: Vec<PickerQuality>,
+    /// Cherry recorded with no picker (or crew) name
+    pub unattributed_cherry_kg: Decimal,
+    pub total_bonus_thb: Option<Decimal>,
+}
+
+/// Key pickers are matched by: trimmed, lower case, single spaces
+pub fn picker_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Full defects of a grading scaled to a 350 g sample; None for an empty
+/// sample
+pub fn defects_per_350g(full_defects: i32, sample_weight_grams: Decimal) -> Option<Decimal> {
+    (sample_weight_grams > Decimal::ZERO).then(|| Decimal::from(full_defects) * DEFECT_SAMPLE_GRAMS / sample_weight_grams)
+}
+
+/// Defect score out of 100: four points lost per full defect in 350 g
+pub fn defect_score(defects_per_350g: Decimal) -> Decimal {
+    (Decimal::ONE_HUNDRED - defects_per_350g * POINTS_PER_DEFECT).clamp(Decimal::ZERO, Decimal::ONE_HUNDRED)
+}
+
+/// Quality score out of 100 from the ripe percentage and the defect score
+pub fn quality_score(ripe_percent: Decimal, defect_score: Option<Decimal>) -> Decimal {
+    let ripeness = ripe_percent.clamp(Decimal::ZERO, Decimal::ONE_HUNDRED);
+    match defect_score {
+        Some(defects) => ripeness * RIPENESS_WEIGHT + defects * (Decimal::ONE - RIPENESS_WEIGHT),
+        None => ripeness,
+    }
+}
+
+#[derive(Default)]
+struct PickerTotals {
+    name: String,
+    harvests: usize,
+    cherry_kg: Decimal,
+    underripe_kg: Decimal,
+    ripe_kg: Decimal,
+    overripe_kg: Decimal,
+    graded_kg: Decimal,
+    defect_kg: Decimal,
+}
+
+/// Score and rank pickers from their harvests and the defects per 350 g of
+/// each graded lot. Ties keep the order pickers were first seen in
+pub fn score_pickers(harvests: &[PickedHarvest], lot_defects: &HashMap<Uuid, Decimal>) -> Vec<PickerQuality> {
+    let mut order: Vec<String> = Vec::new();
+    let mut totals: HashMap<String, PickerTotals> = HashMap::new();
+    for harvest in harvests {
+        if harvest.cherry_kg <= Decimal::ZERO {
+            continue;
+        }
+        let key = picker_key(&harvest.picker);
+        if key.is_empty() {
+            continue;
+        }
+        let entry = totals.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            PickerTotals {
+                name: harvest.picker.trim().to_string(),
+                ..Default::default()
+            }
+        });
+        let kg = harvest.cherry_kg;
+        entry.harvests += 1;
+        entry.cherry_kg += kg;
+        entry.underripe_kg += kg * Decimal::from(harvest.underripe_percent);
+        entry.ripe_kg += kg * Decimal::from(harvest.ripe_percent);
+        entry.overripe_kg += kg * Decimal::from(harvest.overripe_percent);
+        if let Some(defects) = lot_defects.get(&harvest.lot_id) {
+            entry.graded_kg += kg;
+            entry.defect_kg += kg * defects;
+        }
+    }
+
+    let mut pickers: Vec<PickerQuality> = order
+        .into_iter()
+        .filter_map(|key| totals.remove(&key))
+        .map(|t| {
+            let ripe_percent = t.ripe_kg / t.cherry_kg;
+            let defects = (t.graded_kg > Decimal::ZERO).then(|| t.defect_kg / t.graded_kg);
+            let defect_score = defects.map(defect_score);
+            PickerQuality {
+                rank: 0,
+                picker_name: t.name,
+                harvests: t.harvests,
+                cherry_kg: t.cherry_kg,
+                underripe_percent: (t.underripe_kg / t.cherry_kg).round_dp(1),
+                ripe_percent: ripe_percent.round_dp(1),
+                overripe_percent: (t.overripe_kg / t.cherry_kg).round_dp(1),
+                graded_cherry_kg: t.graded_kg,
+                defects_per_350g: defects.map(|d| d.round_dp(1)),
+                defect_score: defect_score.map(|s| s.round_dp(1)),
+                quality_score: quality_score(ripe_percent, defect_score).round_dp(1),
+                bonus_eligible: false,
+                bonus_thb: None,
+            }
+        })
+        .collect();
+
+    pickers.sort_by_key(|picker| std::cmp::Reverse(picker.quality_score));
+    for (index, picker) in pickers.iter_mut().enumerate() {
+        picker.rank = index + 1;
+    }
+    pickers
+}
+
+/// Mark pickers at or above `min_score` eligible and work out their bonus
+/// at `bonus_per_kg` of cherry picked. Returns the total bonus
+pub fn apply_bonuses(pickers: &mut [PickerQuality], min_score: Decimal, bonus_per_kg: Option<Decimal>) -> Option<Decimal> {
+    for picker in pickers.iter_mut() {
+        picker.bonus_eligible = picker.quality_score >= min_score;
+        picker.bonus_thb = bonus_per_kg.map(|rate| {
+            if picker.bonus_eligible {
+                (picker.cherry_kg * rate).round_dp(2)
+            } else {
+                Decimal::ZERO
+            }
+        });
+    }
+    bonus_per_kg.map(|_| pickers.iter().filter_map(|p| p.bonus_thb).sum())
+}
+
+fn validate_query(query: &PickerQualityQuery) -> AppResult<()> {
+    if query
+        .min_score
+        .is_some_and(|score| score < Decimal::ZERO || score > Decimal::ONE_HUNDRED)
+    {
+        return Err(AppError::Validation {
+            field: "min_score".to_string(),
+            message: "Minimum score must be between 0 and 100".to_string(),
+            message_th: "คะแนนขั้นต่ำต้องอยู่ระหว่าง 0 ถึง 100".to_string(),
+        });
+    }
+    if query.bonus_per_kg.is_some_and(|rate| rate < Decimal::ZERO) {
+        return Err(AppError::Validation {
+            field: "bonus_per_kg".to_string(),
+            message: "Bonus per kg cannot be negative".to_string(),
+            message_th: "โบนัสต่อกิโลกรัมต้องไม่ติดลบ".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl PickerQualityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Quality score of each picker or crew over a crop season
+    pub async fn report(&self, business_id: Uuid, query: &PickerQualityQuery) -> AppResult<PickerQualityReport> {
+        validate_query(query)?;
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let season = resolve_season(query.period.as_deref(), query.season, &calendar, Utc::now().date_naive())?;
+        let (start, end) = calendar.season_bounds(season)?;
+        let group_by = query.group_by.unwrap_or_default();
+
+        let rows = sqlx::query_as::<_, (Uuid, Option<String>, Option<String>, Decimal, i32, i32, i32)>(
+            r#"
+            SELECT lot_id, picker_name, crew_name, cherry_weight_kg,
+                   underripe_percent, ripe_percent, overripe_percent
+            FROM harvests
+            WHERE business_id = $1 AND harvest_date BETWEEN $2 AND $3
+            ORDER BY harvest_date, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut unattributed = Decimal::ZERO;
+        let mut harvests = Vec::with_capacity(rows.len());
+        for (lot_id, picker_name, crew_name, cherry_kg, underripe_percent, ripe_percent, overripe_percent) in rows {
+            let name = match group_by {
+                PickerGrouping::Picker => picker_name,
+                PickerGrouping::Crew => crew_name,
+            };
+            match name.filter(|name| !picker_key(name).is_empty()) {
+                Some(picker) => harvests.push(PickedHarvest {
+                    picker,
+                    lot_id,
+                    cherry_kg,
+                    underripe_percent,
+                    ripe_percent,
+                    overripe_percent,
+                }),
+                None => unattributed += cherry_kg,
+            }
+        }
+
+        // Latest grading of each lot picked into in the season
+        let gradings = sqlx::query_as::<_, (Uuid, i32, Decimal)>(
+            r#"
+            SELECT DISTINCT ON (g.lot_id) g.lot_id, g.category1_count + g.category2_count, g.sample_weight_grams
+            FROM green_bean_grades g
+            JOIN lots l ON l.id = g.lot_id
+            WHERE l.business_id = $1
+              AND g.lot_id IN (SELECT lot_id FROM harvests WHERE business_id = $1 AND harvest_date BETWEEN $2 AND $3)
+            ORDER BY g.lot_id, g.grading_date DESC, g.created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+        let lot_defects: HashMap<Uuid, Decimal> = gradings
+            .into_iter()
+            .filter_map(|(lot_id, full_defects, sample)| Some((lot_id, defects_per_350g(full_defects, sample)?)))
+            .collect();
+
+        let min_score = query.min_score.unwrap_or(DEFAULT_BONUS_MIN_SCORE);
+        let mut pickers = score_pickers(&harvests, &lot_defects);
+        let total_bonus_thb = apply_bonuses(&mut pickers, min_score, query.bonus_per_kg);
+
+        Ok(PickerQualityReport {
+            season,
+            season_label: calendar.season_label(season),
+            group_by,
+            min_score,
+            bonus_per_kg: query.bonus_per_kg,
+            pickers,
+            unattributed_cherry_kg: unattributed,
+            total_bonus_thb,
+        })
+    }
+}
+
@@ -7,6 +7,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
 
 /// Plot service for managing farm plots
 #[derive(Clone)]
@@ -27,6 +28,8 @@ pub struct Plot {
     pub shade_coverage_percent: Option<i32>,
     pub notes: Option<String>,
     pub notes_th: Option<String>,
+    /// GeoJSON Polygon or MultiPolygon in WGS84 longitude/latitude
+    pub boundary: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -120,7 +123,7 @@ impl PlotService {
             r#"
             SELECT id, business_id, name, latitude, longitude, area_rai, 
                    altitude_meters, shade_coverage_percent, notes, notes_th,
-                   created_at, updated_at
+                   boundary, created_at, updated_at
             FROM plots
             WHERE business_id = $1
             ORDER BY name ASC
@@ -144,7 +147,7 @@ impl PlotService {
             r#"
             SELECT id, business_id, name, latitude, longitude, area_rai,
                    altitude_meters, shade_coverage_percent, notes, notes_th,
-                   created_at, updated_at
+                   boundary, created_at, updated_at
             FROM plots
             WHERE id = $1 AND business_id = $2
             "#,
@@ -284,7 +287,7 @@ impl PlotService {
     ) -> AppResult<PlotWithVarieties> {
         // Check if plot exists
         let existing = sqlx::query_as::<_, Plot>(
-            "SELECT id, business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent, notes, notes_th, created_at, updated_at FROM plots WHERE id = $1 AND business_id = $2",
+            "SELECT id, business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent, notes, notes_th, boundary, created_at, updated_at FROM plots WHERE id = $1 AND business_id = $2",
         )
         .bind(plot_id)
         .bind(business_id)
@@ -366,8 +369,8 @@ impl PlotService {
         self.get_plot_with_varieties(business_id, plot_id).await
     }
 
-    /// Delete a plot
-    pub async fn delete_plot(&self, business_id: Uuid, plot_id: Uuid) -> AppResult<()> {
+    /// Delete a plot, keeping it in the recycle bin
+    pub async fn delete_plot(&self, business_id: Uuid, user_id: Uuid, plot_id: Uuid) -> AppResult<()> {
         // Check if plot exists
         let exists = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM plots WHERE id = $1 AND business_id = $2",
@@ -398,12 +401,9 @@ impl PlotService {
         }
 
         // Delete plot (cascade will delete varieties)
-        sqlx::query("DELETE FROM plots WHERE id = $1")
-            .bind(plot_id)
-            .execute(&self.db)
-            .await?;
-
-        Ok(())
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::Plot, plot_id)
+            .await
     }
 
     /// Add a variety to a plot
@@ -505,7 +505,7 @@ impl PlotService {
     ) -> AppResult<PlotStatistics> {
         // Check if plot exists
         let plot = sqlx::query_as::<_, Plot>(
-            "SELECT id, business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent, notes, notes_th, created_at, updated_at FROM plots WHERE id = $1 AND business_id = $2",
+            "SELECT id, business_id, name, latitude, longitude, area_rai, altitude_meters, shade_coverage_percent, notes, notes_th, boundary, created_at, updated_at FROM plots WHERE id = $1 AND business_id = $2",
         )
         .bind(plot_id)
         .bind(business_id)
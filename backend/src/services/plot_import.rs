@@ -0,0 +1,708 @@
+//! Plot import from GeoJSON
+//!
+//! Cooperatives keep plot outlines in QGIS or other GIS tools. A GeoJSON
+//! FeatureCollection of Polygon/MultiPolygon features (WGS84) becomes one
+//! plot per feature: the name, altitude, shade, area, varieties and notes
+//! come from feature properties through an attribute mapping, the area and
+//! centroid are computed from the outline when not given. Features with
+//! invalid geometry, duplicate names or outlines overlapping another plot
+//! are reported and skipped; a dry run reports without writing.
+
+use std::collections::HashSet;
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Features accepted in one import
+pub const MAX_IMPORT_FEATURES: usize = 2000;
+
+/// Vertices accepted per feature
+pub const MAX_FEATURE_VERTICES: usize = 5000;
+
+/// Square metres in one rai
+const SQUARE_METRES_PER_RAI: f64 = 1600.0;
+
+/// Mean earth radius in metres
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Distance in degrees (about 1 cm) within which points count as on an edge
+const EPSILON: f64 = 1e-7;
+
+/// Plot import service
+#[derive(Clone)]
+pub struct PlotImportService {
+    db: PgPool,
+}
+
+/// Longitude, latitude
+pub type Position = (f64, f64);
+
+/// Outer ring followed by holes; each ring is closed
+pub type Polygon = Vec<Vec<Position>>;
+
+/// Read the polygons of a GeoJSON Polygon or MultiPolygon geometry
+pub fn parse_geometry(geometry: &Value) -> Result<Vec<Polygon>, String> {
+    let kind = geometry.get("type").and_then(Value::as_str).unwrap_or_default();
+    let coordinates = geometry
+        .get("coordinates")
+        .ok_or_else(|| "Geometry has no coordinates".to_string())?;
+
+    let parse_polygon = |value: &Value| -> Result<Polygon, String> {
+        value
+            .as_array()
+            .ok_or_else(|| "Polygon coordinates must be an array of rings".to_string())?
+            .iter()
+            .map(|ring| {
+                ring.as_array()
+                    .ok_or_else(|| "Ring must be an array of positions".to_string())?
+                    .iter()
+                    .map(|position| match position.as_array().map(Vec::as_slice) {
+                        Some([lon, lat, ..]) => match (lon.as_f64(), lat.as_f64()) {
+                            (Some(lon), Some(lat)) => Ok((lon, lat)),
+                            _ => Err("Positions must be numbers".to_string()),
+                        },
+                        _ => Err("Positions must have longitude and latitude".to_string()),
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+
+    match kind {
+        "Polygon" => Ok(vec![parse_polygon(coordinates)?]),
+        "MultiPolygon" => coordinates
+            .as_array()
+            .ok_or_else(|| "MultiPolygon coordinates must be an array of polygons".to_string())?
+            .iter()
+            .map(parse_polygon)
+            .collect(),
+        "" => Err("Geometry has no type".to_string()),
+        other => Err(format!("{} geometry is not a plot outline; use Polygon or MultiPolygon", other)),
+    }
+}
+
+/// Twice the signed area of a ring in its own units
+fn signed_area2(ring: &[Position]) -> f64 {
+    ring.windows(2).map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1).sum()
+}
+
+/// Orientation of `c` relative to the line `a`-`b`: 1 left, -1 right, 0 on it
+fn orientation(a: Position, b: Position, c: Position) -> i8 {
+    let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    let scale = ((b.0 - a.0).abs() + (b.1 - a.1).abs()).max(EPSILON);
+    if cross.abs() <= EPSILON * scale {
+        0
+    } else if cross > 0.0 {
+        1
+    } else {
+        -1
+    }
+}
+
+fn on_segment(a: Position, b: Position, p: Position) -> bool {
+    orientation(a, b, p) == 0
+        && p.0 >= a.0.min(b.0) - EPSILON
+        && p.0 <= a.0.max(b.0) + EPSILON
+        && p.1 >= a.1.min(b.1) - EPSILON
+        && p.1 <= a.1.max(b.1) + EPSILON
+}
+
+/// Whether segments `a`-`b` and `c`-`d` cross through each other's interior
+fn segments_cross(a: Position, b: Position, c: Position, d: Position) -> bool {
+    let (o1, o2, o3, o4) = (orientation(a, b, c), orientation(a, b, d), orientation(c, d, a), orientation(c, d, b));
+    o1 != 0 && o2 != 0 && o3 != 0 && o4 != 0 && o1 != o2 && o3 != o4
+}
+
+/// Whether segments `a`-`b` and `c`-`d` share any point
+fn segments_touch(a: Position, b: Position, c: Position, d: Position) -> bool {
+    segments_cross(a, b, c, d)
+        || on_segment(a, b, c)
+        || on_segment(a, b, d)
+        || on_segment(c, d, a)
+        || on_segment(c, d, b)
+}
+
+/// Whether `p` lies strictly inside a closed ring (not on its edge)
+fn inside_ring(ring: &[Position], p: Position) -> bool {
+    if ring.windows(2).any(|w| on_segment(w[0], w[1], p)) {
+        return false;
+    }
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether `p` lies strictly inside a polygon and outside its holes
+fn inside_polygon(polygon: &Polygon, p: Position) -> bool {
+    inside_ring(&polygon[0], p) && !polygon[1..].iter().any(|hole| inside_ring(hole, p) || hole.windows(2).any(|w| on_segment(w[0], w[1], p)))
+}
+
+/// Whether a ring crosses or touches itself
+fn self_intersects(ring: &[Position]) -> bool {
+    let segments = ring.len() - 1;
+    for i in 0..segments {
+        for j in (i + 1)..segments {
+            let adjacent = j == i + 1 || (i == 0 && j == segments - 1);
+            if adjacent {
+                continue;
+            }
+            if segments_touch(ring[i], ring[i + 1], ring[j], ring[j + 1]) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Problems that make the polygons unusable as a plot outline
+pub fn validate_polygons(polygons: &[Polygon]) -> Vec<String> {
+    let mut issues = Vec::new();
+    if polygons.is_empty() {
+        issues.push("Geometry has no polygons".to_string());
+    }
+    let vertices: usize = polygons.iter().flatten().map(Vec::len).sum();
+    if vertices > MAX_FEATURE_VERTICES {
+        issues.push(format!("Outline has {} vertices; at most {} are accepted", vertices, MAX_FEATURE_VERTICES));
+        return issues;
+    }
+
+    for (p, polygon) in polygons.iter().enumerate() {
+        let label = if polygons.len() > 1 { format!("Polygon {}: ", p + 1) } else { String::new() };
+        if polygon.is_empty() {
+            issues.push(format!("{}Polygon has no rings", label));
+            continue;
+        }
+        for (r, ring) in polygon.iter().enumerate() {
+            let ring_label = if r == 0 { format!("{}Outer ring", label) } else { format!("{}Hole {}", label, r) };
+            if ring.iter().any(|(lon, lat)| !lon.is_finite() || !lat.is_finite() || lon.abs() > 180.0 || lat.abs() > 90.0) {
+                issues.push(format!(
+                    "{} has coordinates outside longitude/latitude range; reproject the layer to WGS84 (EPSG:4326)",
+                    ring_label
+                ));
+                continue;
+            }
+            if ring.len() < 4 {
+                issues.push(format!("{} needs at least 4 positions", ring_label));
+                continue;
+            }
+            if ring.first() != ring.last() {
+                issues.push(format!("{} is not closed", ring_label));
+                continue;
+            }
+            if signed_area2(ring).abs() <= EPSILON * EPSILON {
+                issues.push(format!("{} has no area", ring_label));
+                continue;
+            }
+            if self_intersects(ring) {
+                issues.push(format!("{} crosses itself", ring_label));
+                continue;
+            }
+            if r > 0 && !ring[..ring.len() - 1].iter().any(|v| inside_ring(&polygon[0], *v)) {
+                issues.push(format!("{} lies outside the outer ring", ring_label));
+            }
+        }
+    }
+    issues
+}
+
+/// Area of the polygons in rai, using a local equirectangular projection
+pub fn area_rai(polygons: &[Polygon]) -> f64 {
+    let points: Vec<Position> = polygons.iter().filter_map(|p| p.first()).flatten().copied().collect();
+    if points.is_empty() {
+        return 0.0;
+    }
+    let mean_lat = points.iter().map(|p| p.1).sum::<f64>() / points.len() as f64;
+    let x_scale = EARTH_RADIUS_M * mean_lat.to_radians().cos() * std::f64::consts::PI / 180.0;
+    let y_scale = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+    let project = |ring: &Vec<Position>| -> f64 {
+        let projected: Vec<Position> = ring.iter().map(|(lon, lat)| (lon * x_scale, lat * y_scale)).collect();
+        signed_area2(&projected).abs() / 2.0
+    };
+
+    let square_metres: f64 = polygons
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| project(&p[0]) - p[1..].iter().map(project).sum::<f64>())
+        .sum();
+    square_metres / SQUARE_METRES_PER_RAI
+}
+
+/// Area-weighted centroid of the outer rings
+pub fn centroid(polygons: &[Polygon]) -> Option<Position> {
+    let (mut area, mut x, mut y) = (0.0, 0.0, 0.0);
+    for ring in polygons.iter().filter_map(|p| p.first()) {
+        for w in ring.windows(2) {
+            let cross = w[0].0 * w[1].1 - w[1].0 * w[0].1;
+            area += cross;
+            x += (w[0].0 + w[1].0) * cross;
+            y += (w[0].1 + w[1].1) * cross;
+        }
+    }
+    if area.abs() <= f64::EPSILON {
+        return None;
+    }
+    Some((x / (3.0 * area), y / (3.0 * area)))
+}
+
+fn bounding_box(polygons: &[Polygon]) -> (f64, f64, f64, f64) {
+    polygons.iter().filter_map(|p| p.first()).flatten().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(min_x, min_y, max_x, max_y), (x, y)| (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y)),
+    )
+}
+
+fn polygons_overlap(a: &Polygon, b: &Polygon) -> bool {
+    let (a_outer, b_outer) = (&a[0], &b[0]);
+    for wa in a_outer.windows(2) {
+        for wb in b_outer.windows(2) {
+            if segments_cross(wa[0], wa[1], wb[0], wb[1]) {
+                return true;
+            }
+        }
+    }
+    let interior_point = |polygon: &Polygon| centroid(std::slice::from_ref(polygon)).filter(|c| inside_polygon(polygon, *c));
+    a_outer.iter().any(|v| inside_polygon(b, *v))
+        || b_outer.iter().any(|v| inside_polygon(a, *v))
+        || interior_point(a).is_some_and(|c| inside_polygon(b, c))
+        || interior_point(b).is_some_and(|c| inside_polygon(a, c))
+}
+
+/// Whether two outlines share interior area; outlines that only share an
+/// edge or a corner do not overlap
+pub fn outlines_overlap(a: &[Polygon], b: &[Polygon]) -> bool {
+    let (a_min_x, a_min_y, a_max_x, a_max_y) = bounding_box(a);
+    let (b_min_x, b_min_y, b_max_x, b_max_y) = bounding_box(b);
+    if a_max_x < b_min_x || b_max_x < a_min_x || a_max_y < b_min_y || b_max_y < a_min_y {
+        return false;
+    }
+    a.iter()
+        .filter(|p| !p.is_empty())
+        .any(|pa| b.iter().filter(|p| !p.is_empty()).any(|pb| polygons_overlap(pa, pb)))
+}
+
+/// Feature property names to read plot fields from; unmapped fields are
+/// looked up under their usual names, ignoring case
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlotAttributeMapping {
+    pub name: Option<String>,
+    pub altitude_meters: Option<String>,
+    pub shade_coverage_percent: Option<String>,
+    pub area_rai: Option<String>,
+    pub varieties: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Import query
+#[derive(Debug, Deserialize)]
+pub struct PlotImportQuery {
+    /// Preview the import without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Import overlapping outlines with a warning instead of skipping them
+    #[serde(default)]
+    pub allow_overlaps: bool,
+}
+
+/// What happens to a feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotImportStatus {
+    /// Imported, or would be on a dry run
+    Ready,
+    /// Invalid, duplicate or overlapping; skipped
+    Error,
+}
+
+/// Outcome of one feature
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotImportRow {
+    /// Position of the feature in the collection, from 1
+    pub feature: usize,
+    pub status: PlotImportStatus,
+    pub name: Option<String>,
+    /// Set once imported
+    pub plot_id: Option<Uuid>,
+    pub area_rai: Option<Decimal>,
+    /// Area computed from the outline
+    pub outline_area_rai: Option<Decimal>,
+    pub latitude: Option<Decimal>,
+    pub longitude: Option<Decimal>,
+    pub altitude_meters: Option<i32>,
+    pub shade_coverage_percent: Option<i32>,
+    pub varieties: Vec<String>,
+    /// Names of plots (existing or in this file) the outline overlaps
+    pub overlaps: Vec<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Preview or result of an import
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotImportResult {
+    pub dry_run: bool,
+    pub features: usize,
+    pub ready: usize,
+    pub errors: usize,
+    pub plots: Vec<PlotImportRow>,
+}
+
+/// Property value by mapped name, or else the first default name present
+fn property<'a>(properties: &'a Map<String, Value>, mapped: Option<&str>, defaults: &[&str]) -> Option<&'a Value> {
+    let find = |key: &str| {
+        properties
+            .iter()
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case(key.trim()))
+            .map(|(_, v)| v)
+            .filter(|v| !v.is_null())
+    };
+    match mapped {
+        Some(key) => find(key),
+        None => defaults.iter().find_map(|key| find(key)),
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    let text = match value? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn number(value: Option<&Value>) -> Result<Option<f64>, ()> {
+    match value {
+        None => Ok(None),
+        Some(Value::Number(n)) => Ok(n.as_f64()),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => s.trim().replace(',', ".").parse::<f64>().map(Some).map_err(|_| ()),
+        Some(_) => Err(()),
+    }
+}
+
+/// Variety names in a property such as "Typica, Catimor"
+pub fn split_varieties(value: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    value
+        .split([',', ';', '/', '|'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && seen.insert(v.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn decimal(value: f64, dp: u32) -> Option<Decimal> {
+    Decimal::from_f64(value).map(|d| d.round_dp(dp))
+}
+
+/// A feature read from the collection, before checks against other plots
+struct ParsedFeature {
+    row: PlotImportRow,
+    polygons: Vec<Polygon>,
+    boundary: Option<Value>,
+    notes: Option<String>,
+}
+
+fn parse_feature(index: usize, feature: &Value, mapping: &PlotAttributeMapping) -> ParsedFeature {
+    let empty = Map::new();
+    let properties = feature.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let name = text(property(properties, mapping.name.as_deref(), &["name", "plot_name", "plot", "ชื่อแปลง"]));
+    if name.is_none() {
+        errors.push(match &mapping.name {
+            Some(key) => format!("Property '{}' with the plot name is missing", key),
+            None => "No plot name; map the name property".to_string(),
+        });
+    }
+    if name.as_ref().is_some_and(|n| n.chars().count() > 255) {
+        errors.push("Plot name is longer than 255 characters".to_string());
+    }
+
+    let altitude = match number(property(
+        properties,
+        mapping.altitude_meters.as_deref(),
+        &["altitude_meters", "altitude", "elevation", "elev"],
+    )) {
+        Ok(Some(a)) if (0.0..=3000.0).contains(&a) => Some(a.round() as i32),
+        Ok(Some(_)) => {
+            errors.push("Altitude must be between 0 and 3000 meters".to_string());
+            None
+        }
+        Ok(None) => None,
+        Err(()) => {
+            errors.push("Altitude is not a number".to_string());
+            None
+        }
+    };
+
+    let shade = match number(property(
+        properties,
+        mapping.shade_coverage_percent.as_deref(),
+        &["shade_coverage_percent", "shade_percent", "shade"],
+    )) {
+        Ok(Some(s)) if (0.0..=100.0).contains(&s) => Some(s.round() as i32),
+        Ok(Some(_)) => {
+            errors.push("Shade coverage must be between 0 and 100".to_string());
+            None
+        }
+        Ok(None) => None,
+        Err(()) => {
+            errors.push("Shade coverage is not a number".to_string());
+            None
+        }
+    };
+
+    let given_area = match number(property(properties, mapping.area_rai.as_deref(), &["area_rai", "rai"])) {
+        Ok(Some(a)) if a > 0.0 => Some(a),
+        Ok(Some(_)) => {
+            errors.push("Area must be greater than 0".to_string());
+            None
+        }
+        Ok(None) => None,
+        Err(()) => {
+            errors.push("Area is not a number".to_string());
+            None
+        }
+    };
+
+    let varieties = text(property(properties, mapping.varieties.as_deref(), &["varieties", "variety", "พันธุ์"]))
+        .map(|v| split_varieties(&v))
+        .unwrap_or_default();
+    if varieties.iter().any(|v| v.chars().count() > 100) {
+        errors.push("Variety names are limited to 100 characters".to_string());
+    }
+    let notes = text(property(properties, mapping.notes.as_deref(), &["notes", "note", "remark", "remarks"]));
+
+    let geometry = feature.get("geometry").filter(|g| !g.is_null());
+    let polygons = match geometry.map(parse_geometry) {
+        Some(Ok(polygons)) => {
+            let issues = validate_polygons(&polygons);
+            if issues.is_empty() {
+                polygons
+            } else {
+                errors.extend(issues);
+                Vec::new()
+            }
+        }
+        Some(Err(message)) => {
+            errors.push(message);
+            Vec::new()
+        }
+        None => {
+            errors.push("Feature has no geometry".to_string());
+            Vec::new()
+        }
+    };
+
+    let (outline_area, center) = if polygons.is_empty() {
+        (None, None)
+    } else {
+        (Some(area_rai(&polygons)), centroid(&polygons))
+    };
+    if let (Some(given), Some(outline)) = (given_area, outline_area) {
+        if (given - outline).abs() > outline * 0.2 {
+            warnings.push(format!(
+                "Area {:.2} rai differs from the outline's {:.2} rai by more than 20%",
+                given, outline
+            ));
+        }
+    }
+
+    ParsedFeature {
+        row: PlotImportRow {
+            feature: index + 1,
+            status: if errors.is_empty() { PlotImportStatus::Ready } else { PlotImportStatus::Error },
+            name,
+            plot_id: None,
+            area_rai: given_area.or(outline_area).and_then(|a| decimal(a, 2)),
+            outline_area_rai: outline_area.and_then(|a| decimal(a, 2)),
+            latitude: center.and_then(|c| decimal(c.1, 8)),
+            longitude: center.and_then(|c| decimal(c.0, 8)),
+            altitude_meters: altitude,
+            shade_coverage_percent: shade,
+            varieties,
+            overlaps: Vec::new(),
+            errors,
+            warnings,
+        },
+        boundary: geometry.cloned(),
+        polygons,
+        notes,
+    }
+}
+
+/// Features of a FeatureCollection, with the attribute mapping; the body is
+/// either the collection itself or `{ "feature_collection": ..., "mapping": ... }`
+pub fn read_import_body(body: &Value) -> AppResult<(Vec<Value>, PlotAttributeMapping)> {
+    let (collection, mapping) = if body.get("type").and_then(Value::as_str) == Some("FeatureCollection") {
+        (body, PlotAttributeMapping::default())
+    } else {
+        let mapping = match body.get("mapping") {
+            Some(value) if !value.is_null() => serde_json::from_value(value.clone()).map_err(|e| AppError::Validation {
+                field: "mapping".to_string(),
+                message: format!("Attribute mapping is not valid: {}", e),
+                message_th: "การจับคู่คุณสมบัติไม่ถูกต้อง".to_string(),
+            })?,
+            _ => PlotAttributeMapping::default(),
+        };
+        (body.get("feature_collection").unwrap_or(&Value::Null), mapping)
+    };
+
+    if collection.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+        return Err(AppError::Validation {
+            field: "feature_collection".to_string(),
+            message: "Body must be a GeoJSON FeatureCollection".to_string(),
+            message_th: "ข้อมูลต้องเป็น GeoJSON FeatureCollection".to_string(),
+        });
+    }
+    let features = collection.get("features").and_then(Value::as_array).cloned().unwrap_or_default();
+    if features.is_empty() {
+        return Err(AppError::Validation {
+            field: "features".to_string(),
+            message: "The collection has no features".to_string(),
+            message_th: "ไม่มีข้อมูลแปลงในไฟล์".to_string(),
+        });
+    }
+    if features.len() > MAX_IMPORT_FEATURES {
+        return Err(AppError::Validation {
+            field: "features".to_string(),
+            message: format!("At most {} features can be imported at once", MAX_IMPORT_FEATURES),
+            message_th: format!("นำเข้าได้ครั้งละไม่เกิน {} แปลง", MAX_IMPORT_FEATURES),
+        });
+    }
+    Ok((features, mapping))
+}
+
+impl PlotImportService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Import plots from a GeoJSON FeatureCollection, or preview on a dry run
+    pub async fn import(&self, business_id: Uuid, body: &Value, query: &PlotImportQuery) -> AppResult<PlotImportResult> {
+        let (features, mapping) = read_import_body(body)?;
+        let mut parsed: Vec<ParsedFeature> = features
+            .iter()
+            .enumerate()
+            .map(|(i, feature)| parse_feature(i, feature, &mapping))
+            .collect();
+
+        let existing = sqlx::query_as::<_, (String, Option<Value>)>(
+            "SELECT name, boundary FROM plots WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        let existing_names: HashSet<String> = existing.iter().map(|(name, _)| name.trim().to_lowercase()).collect();
+        let existing_outlines: Vec<(String, Vec<Polygon>)> = existing
+            .into_iter()
+            .filter_map(|(name, boundary)| Some((name, parse_geometry(&boundary?).ok()?)))
+            .collect();
+
+        // Names must be new and unique within the file
+        let mut file_names = HashSet::new();
+        for feature in parsed.iter_mut() {
+            let Some(name) = feature.row.name.as_ref().map(|n| n.to_lowercase()) else {
+                continue;
+            };
+            if existing_names.contains(&name) {
+                feature.row.errors.push("A plot with this name already exists".to_string());
+            } else if !file_names.insert(name) {
+                feature.row.errors.push("Another feature in the file has this name".to_string());
+            }
+        }
+
+        // Overlaps with existing plots and with earlier features in the file
+        for i in 0..parsed.len() {
+            if parsed[i].polygons.is_empty() {
+                continue;
+            }
+            let mut overlaps: Vec<String> = existing_outlines
+                .iter()
+                .filter(|(_, outline)| outlines_overlap(&parsed[i].polygons, outline))
+                .map(|(name, _)| name.clone())
+                .collect();
+            for j in 0..parsed.len() {
+                if i != j && !parsed[j].polygons.is_empty() && outlines_overlap(&parsed[i].polygons, &parsed[j].polygons) {
+                    overlaps.push(parsed[j].row.name.clone().unwrap_or_else(|| format!("feature {}", j + 1)));
+                }
+            }
+            if !overlaps.is_empty() {
+                let message = format!("Outline overlaps {}", overlaps.join(", "));
+                if query.allow_overlaps {
+                    parsed[i].row.warnings.push(message);
+                } else {
+                    parsed[i].row.errors.push(message);
+                }
+                parsed[i].row.overlaps = overlaps;
+            }
+        }
+
+        for feature in parsed.iter_mut() {
+            feature.row.status = if feature.row.errors.is_empty() {
+                PlotImportStatus::Ready
+            } else {
+                PlotImportStatus::Error
+            };
+        }
+
+        if !query.dry_run {
+            let mut tx = self.db.begin().await?;
+            for feature in parsed.iter_mut().filter(|f| f.row.status == PlotImportStatus::Ready) {
+                let row = &mut feature.row;
+                let plot_id = sqlx::query_scalar::<_, Uuid>(
+                    r#"
+                    INSERT INTO plots (business_id, name, latitude, longitude, area_rai,
+                                      altitude_meters, shade_coverage_percent, notes, boundary)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    RETURNING id
+                    "#,
+                )
+                .bind(business_id)
+                .bind(&row.name)
+                .bind(row.latitude)
+                .bind(row.longitude)
+                .bind(row.area_rai)
+                .bind(row.altitude_meters)
+                .bind(row.shade_coverage_percent)
+                .bind(&feature.notes)
+                .bind(&feature.boundary)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                for variety in &row.varieties {
+                    sqlx::query("INSERT INTO plot_varieties (plot_id, variety) VALUES ($1, $2)")
+                        .bind(plot_id)
+                        .bind(variety)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                row.plot_id = Some(plot_id);
+            }
+            tx.commit().await?;
+        }
+
+        let plots: Vec<PlotImportRow> = parsed.into_iter().map(|f| f.row).collect();
+        let ready = plots.iter().filter(|r| r.status == PlotImportStatus::Ready).count();
+        Ok(PlotImportResult {
+            dry_run: query.dry_run,
+            features: plots.len(),
+            ready,
+            errors: plots.len() - ready,
+            plots,
+        })
+    }
+}
@@ -0,0 +1,551 @@
+//! Plot profitability
+//!
+//! Traces the coffee each lot sold back to the plots it was picked on and
+//! sets it against what the plots cost in the same crop season. A lot's
+//! realized revenue is split over its plots by their share of its cherry;
+//! blended lots pass their revenue on to their source lots by blend
+//! proportion. Costs are entered per plot and season, or once for the whole
+//! farm and spread over the plots by area or by the cherry they picked.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::reporting_period::{resolve_season, validate_season};
+use crate::services::BusinessService;
+
+/// Plot profitability service
+#[derive(Clone)]
+pub struct PlotProfitabilityService {
+    db: PgPool,
+}
+
+/// What a cost was spent on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostCategory {
+    Labor,
+    /// Fertilizer, pesticide, seedlings
+    Inputs,
+    Equipment,
+    /// Rent and land tax
+    Land,
+    Transport,
+    Processing,
+    Other,
+}
+
+impl CostCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostCategory::Labor => "labor",
+            CostCategory::Inputs => "inputs",
+            CostCategory::Equipment => "equipment",
+            CostCategory::Land => "land",
+            CostCategory::Transport => "transport",
+            CostCategory::Processing => "processing",
+            CostCategory::Other => "other",
+        }
+    }
+}
+
+/// How a cost shared by all plots is spread over them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostAllocation {
+    /// By plot area in rai
+    #[default]
+    Area,
+    /// By cherry picked on each plot in the season
+    Cherry,
+}
+
+impl CostAllocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostAllocation::Area => "area",
+            CostAllocation::Cherry => "cherry",
+        }
+    }
+}
+
+/// Plot and crop season coffee was picked in
+pub type Origin = (Uuid, i32);
+
+/// Harvests and blend sources of the business's lots
+#[derive(Debug, Clone, Default)]
+pub struct LotOrigins {
+    /// Plot, season and cherry kg of each harvest, per lot
+    pub harvests: HashMap<Uuid, Vec<(Uuid, i32, Decimal)>>,
+    /// Source lot and blend percent, per blended lot
+    pub sources: HashMap<Uuid, Vec<(Uuid, Decimal)>>,
+}
+
+/// Share of a lot's coffee from each plot and season, summing to one for a
+/// lot traced back to its harvests. A lot with harvests is split by their
+/// cherry; a blend by its source proportions. A lot with neither, or a
+/// cycle in the blend graph, traces to nothing
+pub fn lot_shares(lot_id: Uuid, origins: &LotOrigins) -> BTreeMap<Origin, Decimal> {
+    fn walk(
+        lot_id: Uuid,
+        origins: &LotOrigins,
+        memo: &mut HashMap<Uuid, BTreeMap<Origin, Decimal>>,
+        visiting: &mut HashSet<Uuid>,
+    ) -> BTreeMap<Origin, Decimal> {
+        if let Some(shares) = memo.get(&lot_id) {
+            return shares.clone();
+        }
+        if !visiting.insert(lot_id) {
+            return BTreeMap::new();
+        }
+
+        let mut shares: BTreeMap<Origin, Decimal> = BTreeMap::new();
+        let harvests = origins.harvests.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let cherry: Decimal = harvests.iter().map(|(_, _, kg)| *kg).sum();
+        if cherry > Decimal::ZERO {
+            for (plot_id, season, kg) in harvests {
+                *shares.entry((*plot_id, *season)).or_default() += kg / cherry;
+            }
+        } else {
+            for (source_lot_id, percent) in origins.sources.get(&lot_id).map(Vec::as_slice).unwrap_or_default() {
+                let proportion = percent / Decimal::ONE_HUNDRED;
+                for (origin, share) in walk(*source_lot_id, origins, memo, visiting) {
+                    *shares.entry(origin).or_default() += share * proportion;
+                }
+            }
+        }
+
+        visiting.remove(&lot_id);
+        memo.insert(lot_id, shares.clone());
+        shares
+    }
+
+    walk(lot_id, origins, &mut HashMap::new(), &mut HashSet::new())
+}
+
+/// Split an amount over plots in proportion to their weights; empty when
+/// no plot has a weight
+pub fn allocate(amount: Decimal, weights: &[(Uuid, Decimal)]) -> Vec<(Uuid, Decimal)> {
+    let total: Decimal = weights.iter().map(|(_, w)| *w).filter(|w| *w > Decimal::ZERO).sum();
+    if total <= Decimal::ZERO {
+        return Vec::new();
+    }
+    weights
+        .iter()
+        .filter(|(_, w)| *w > Decimal::ZERO)
+        .map(|(plot_id, w)| (*plot_id, amount * w / total))
+        .collect()
+}
+
+/// Raw figures of a plot, or of the farm, over a season
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SeasonFigures {
+    pub cherry_kg: Decimal,
+    pub revenue: Decimal,
+    pub direct_cost: Decimal,
+    pub shared_cost: Decimal,
+}
+
+impl SeasonFigures {
+    pub fn add(&mut self, other: &SeasonFigures) {
+        self.cherry_kg += other.cherry_kg;
+        self.revenue += other.revenue;
+        self.direct_cost += other.direct_cost;
+        self.shared_cost += other.shared_cost;
+    }
+}
+
+/// Revenue against costs, in THB
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profit {
+    pub cherry_kg: Decimal,
+    pub revenue_thb: Decimal,
+    /// Costs entered for the plot
+    pub direct_cost_thb: Decimal,
+    /// Its part of the costs shared by all plots
+    pub shared_cost_thb: Decimal,
+    pub total_cost_thb: Decimal,
+    pub profit_thb: Decimal,
+    /// Profit as a percent of revenue
+    pub margin_percent: Option<Decimal>,
+    pub cost_per_kg_cherry: Option<Decimal>,
+}
+
+/// Profit from a season's figures, rounded to satang
+pub fn profit(figures: &SeasonFigures) -> Profit {
+    let total_cost = figures.direct_cost + figures.shared_cost;
+    let profit = figures.revenue - total_cost;
+    Profit {
+        cherry_kg: figures.cherry_kg,
+        revenue_thb: figures.revenue.round_dp(2),
+        direct_cost_thb: figures.direct_cost.round_dp(2),
+        shared_cost_thb: figures.shared_cost.round_dp(2),
+        total_cost_thb: total_cost.round_dp(2),
+        profit_thb: profit.round_dp(2),
+        margin_percent: (figures.revenue > Decimal::ZERO)
+            .then(|| (profit / figures.revenue * Decimal::ONE_HUNDRED).round_dp(1)),
+        cost_per_kg_cherry: (figures.cherry_kg > Decimal::ZERO).then(|| (total_cost / figures.cherry_kg).round_dp(2)),
+    }
+}
+
+/// Profitability of one plot over a season
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotProfitability {
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    pub area_rai: Option<Decimal>,
+    #[serde(flatten)]
+    pub profit: Profit,
+    pub cherry_kg_per_rai: Option<Decimal>,
+    pub profit_per_rai: Option<Decimal>,
+    /// Lots holding the plot's cherry with no sale price recorded yet, so
+    /// revenue is still to come
+    pub unsold_lots: usize,
+}
+
+/// Plot profitability from its season figures
+pub fn plot_profitability(
+    plot_id: Uuid,
+    plot_name: String,
+    area_rai: Option<Decimal>,
+    figures: &SeasonFigures,
+    unsold_lots: usize,
+) -> PlotProfitability {
+    let profit = profit(figures);
+    let area = area_rai.filter(|a| *a > Decimal::ZERO);
+    PlotProfitability {
+        plot_id,
+        plot_name,
+        area_rai,
+        cherry_kg_per_rai: area.map(|a| (figures.cherry_kg / a).round_dp(1)),
+        profit_per_rai: area.map(|a| (profit.profit_thb / a).round_dp(2)),
+        profit,
+        unsold_lots,
+    }
+}
+
+/// Per-plot profitability over a crop season
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotProfitabilityReport {
+    pub season: i32,
+    pub season_label: String,
+    /// Highest profit per rai first; plots without an area last
+    pub plots: Vec<PlotProfitability>,
+    pub total: Profit,
+    /// Shared costs with no plot to spread them over (no plot areas, or no
+    /// cherry picked); counted in the total only
+    pub unallocated_cost_thb: Decimal,
+}
+
+/// Production cost of a plot, or shared by all plots
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PlotCost {
+    pub id: Uuid,
+    pub plot_id: Option<Uuid>,
+    pub plot_name: Option<String>,
+    pub season: i32,
+    pub category: String,
+    pub amount: Decimal,
+    pub cost_date: Option<NaiveDate>,
+    pub description: Option<String>,
+    pub allocation: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a production cost
+#[derive(Debug, Deserialize)]
+pub struct RecordPlotCostInput {
+    /// Leave empty for a cost shared by all plots
+    pub plot_id: Option<Uuid>,
+    /// Defaults to the crop season of `cost_date`, or the current season
+    pub season: Option<i32>,
+    pub category: CostCategory,
+    /// Amount in THB
+    pub amount: Decimal,
+    pub cost_date: Option<NaiveDate>,
+    pub description: Option<String>,
+    /// How a shared cost is spread (default by area)
+    pub allocation: Option<CostAllocation>,
+}
+
+/// Filters for listing production costs
+#[derive(Debug, Default, Deserialize)]
+pub struct PlotCostQuery {
+    pub season: Option<i32>,
+    pub plot_id: Option<Uuid>,
+}
+
+/// Query for the plot profitability report
+#[derive(Debug, Default, Deserialize)]
+pub struct PlotProfitabilityQuery {
+    /// Crop season by the year it starts in; the current season by default
+    pub season: Option<i32>,
+    /// Season as a report period such as `season:2024/25`; overrides `season`
+    pub period: Option<String>,
+}
+
+const PLOT_COST_SELECT: &str = r#"
+    SELECT c.id, c.plot_id, p.name AS plot_name, c.season, c.category, c.amount, c.cost_date,
+           c.description, c.allocation, c.created_by, c.created_at
+    FROM plot_costs c
+    LEFT JOIN plots p ON p.id = c.plot_id
+"#;
+
+impl PlotProfitabilityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a production cost for a season
+    pub async fn record_cost(&self, business_id: Uuid, user_id: Uuid, input: RecordPlotCostInput) -> AppResult<PlotCost> {
+        if input.amount <= Decimal::ZERO {
+            return Err(AppError::Validation {
+                field: "amount".to_string(),
+                message: "Amount must be greater than zero".to_string(),
+                message_th: "จำนวนเงินต้องมากกว่าศูนย์".to_string(),
+            });
+        }
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let season = input
+            .season
+            .unwrap_or_else(|| calendar.season_of(input.cost_date.unwrap_or_else(|| Utc::now().date_naive())));
+        validate_season(season)?;
+
+        if let Some(plot_id) = input.plot_id {
+            let plot_exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM plots WHERE id = $1 AND business_id = $2)",
+            )
+            .bind(plot_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !plot_exists {
+                return Err(AppError::NotFound("Plot".to_string()));
+            }
+        }
+        let description = input.description.as_deref().map(str::trim).filter(|d| !d.is_empty());
+
+        let cost_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO plot_costs (
+                business_id, plot_id, season, category, amount, cost_date, description, allocation, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.plot_id)
+        .bind(season)
+        .bind(input.category.as_str())
+        .bind(input.amount)
+        .bind(input.cost_date)
+        .bind(description)
+        .bind(input.allocation.unwrap_or_default().as_str())
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        sqlx::query_as::<_, PlotCost>(&format!("{} WHERE c.id = $1", PLOT_COST_SELECT))
+            .bind(cost_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// List production costs, latest season first
+    pub async fn list_costs(&self, business_id: Uuid, query: &PlotCostQuery) -> AppResult<Vec<PlotCost>> {
+        let costs = sqlx::query_as::<_, PlotCost>(&format!(
+            r#"
+            {}
+            WHERE c.business_id = $1
+              AND ($2::INTEGER IS NULL OR c.season = $2)
+              AND ($3::UUID IS NULL OR c.plot_id = $3)
+            ORDER BY c.season DESC, c.cost_date DESC NULLS LAST, c.created_at DESC
+            "#,
+            PLOT_COST_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.season)
+        .bind(query.plot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(costs)
+    }
+
+    /// Delete a production cost
+    pub async fn delete_cost(&self, business_id: Uuid, cost_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM plot_costs WHERE id = $1 AND business_id = $2")
+            .bind(cost_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Plot cost".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Revenue, costs and profit of each plot over a crop season
+    pub async fn report(&self, business_id: Uuid, query: &PlotProfitabilityQuery) -> AppResult<PlotProfitabilityReport> {
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let season = resolve_season(query.period.as_deref(), query.season, &calendar, Utc::now().date_naive())?;
+
+        let plots = sqlx::query_as::<_, (Uuid, String, Option<Decimal>)>(
+            "SELECT id, name, area_rai FROM plots WHERE business_id = $1 ORDER BY name",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut origins = LotOrigins::default();
+        let harvests = sqlx::query_as::<_, (Uuid, Uuid, NaiveDate, Decimal)>(
+            "SELECT lot_id, plot_id, harvest_date, cherry_weight_kg FROM harvests WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        for (lot_id, plot_id, harvest_date, cherry_kg) in harvests {
+            origins
+                .harvests
+                .entry(lot_id)
+                .or_default()
+                .push((plot_id, calendar.season_of(harvest_date), cherry_kg));
+        }
+
+        let sources = sqlx::query_as::<_, (Uuid, Uuid, Decimal)>(
+            r#"
+            SELECT ls.lot_id, ls.source_lot_id, ls.proportion_percent
+            FROM lot_sources ls
+            JOIN lots l ON l.id = ls.lot_id
+            WHERE l.business_id = $1
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        let mut blended: HashSet<Uuid> = HashSet::new();
+        for (lot_id, source_lot_id, percent) in sources {
+            origins.sources.entry(lot_id).or_default().push((source_lot_id, percent));
+            blended.insert(source_lot_id);
+        }
+
+        let revenues: HashMap<Uuid, Decimal> = sqlx::query_as::<_, (Uuid, Decimal)>(
+            r#"
+            SELECT lot_id, SUM(quantity_kg * price_thb_per_kg)
+            FROM lot_sale_prices
+            WHERE business_id = $1
+            GROUP BY lot_id
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .collect();
+
+        let lot_ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM lots WHERE business_id = $1")
+            .bind(business_id)
+            .fetch_all(&self.db)
+            .await?;
+
+        let costs = sqlx::query_as::<_, (Option<Uuid>, Decimal, String)>(
+            "SELECT plot_id, amount, allocation FROM plot_costs WHERE business_id = $1 AND season = $2",
+        )
+        .bind(business_id)
+        .bind(season)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut figures: HashMap<Uuid, SeasonFigures> = HashMap::new();
+        let mut unsold: HashMap<Uuid, usize> = HashMap::new();
+        for harvest in origins.harvests.values().flatten() {
+            if harvest.1 == season {
+                figures.entry(harvest.0).or_default().cherry_kg += harvest.2;
+            }
+        }
+        for lot_id in lot_ids {
+            let revenue = revenues.get(&lot_id).copied();
+            // Lots blended away are sold through the blend
+            if revenue.is_none() && blended.contains(&lot_id) {
+                continue;
+            }
+            for ((plot_id, origin_season), share) in lot_shares(lot_id, &origins) {
+                if origin_season != season {
+                    continue;
+                }
+                match revenue {
+                    Some(revenue) => figures.entry(plot_id).or_default().revenue += revenue * share,
+                    None => *unsold.entry(plot_id).or_default() += 1,
+                }
+            }
+        }
+
+        let area_weights: Vec<(Uuid, Decimal)> = plots
+            .iter()
+            .filter_map(|(plot_id, _, area)| area.map(|a| (*plot_id, a)))
+            .collect();
+        let cherry_weights: Vec<(Uuid, Decimal)> = plots
+            .iter()
+            .map(|(plot_id, _, _)| (*plot_id, figures.get(plot_id).map(|f| f.cherry_kg).unwrap_or_default()))
+            .collect();
+        let mut unallocated = Decimal::ZERO;
+        for (plot_id, amount, allocation) in costs {
+            if let Some(plot_id) = plot_id {
+                figures.entry(plot_id).or_default().direct_cost += amount;
+                continue;
+            }
+            let weights = if allocation == CostAllocation::Cherry.as_str() {
+                &cherry_weights
+            } else {
+                &area_weights
+            };
+            let shares = allocate(amount, weights);
+            if shares.is_empty() {
+                unallocated += amount;
+            }
+            for (plot_id, share) in shares {
+                figures.entry(plot_id).or_default().shared_cost += share;
+            }
+        }
+
+        let mut total = SeasonFigures::default();
+        let mut rows: Vec<PlotProfitability> = Vec::new();
+        for (plot_id, name, area_rai) in plots {
+            let unsold_lots = unsold.get(&plot_id).copied().unwrap_or_default();
+            let Some(plot_figures) = figures.get(&plot_id) else {
+                continue;
+            };
+            total.add(plot_figures);
+            rows.push(plot_profitability(plot_id, name, area_rai, plot_figures, unsold_lots));
+        }
+        total.shared_cost += unallocated;
+
+        rows.sort_by(|a, b| {
+            match (a.profit_per_rai, b.profit_per_rai) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+            .then_with(|| b.profit.profit_thb.cmp(&a.profit.profit_thb))
+        });
+
+        Ok(PlotProfitabilityReport {
+            season,
+            season_label: calendar.season_label(season),
+            plots: rows,
+            total: profit(&total),
+            unallocated_cost_thb: unallocated.round_dp(2),
+        })
+    }
+}
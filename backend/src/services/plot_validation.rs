@@ -0,0 +1,272 @@
+//! Plot validation
+//!
+//! Catches land counted twice: plot outlines overlapping other plots of the
+//! business (or, with a cooperative code, of any member business), and
+//! plots whose harvests in one crop season add up to more cherry than the
+//! plot's area can plausibly bear.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::without_tenant;
+use crate::services::plot_import::{outlines_overlap, parse_geometry, validate_polygons, Polygon};
+use crate::services::reporting_period::ReportingCalendar;
+use crate::services::BusinessService;
+
+/// Cherry per rai in one season above which harvests are flagged; well
+/// above the best Thai arabica yields (about 1,500 kg cherry per rai)
+pub const DEFAULT_MAX_CHERRY_KG_PER_RAI: Decimal = Decimal::from_parts(2500, 0, 0, false, 0);
+
+/// Plot validation service
+#[derive(Clone)]
+pub struct PlotValidationService {
+    db: PgPool,
+}
+
+/// Query parameters for plot validation
+#[derive(Debug, Deserialize)]
+pub struct PlotValidationQuery {
+    /// Also compare outlines with plots of the other businesses in the
+    /// cooperative
+    #[serde(default)]
+    pub include_cooperative: bool,
+    /// Cherry kg per rai per season above which harvests are flagged
+    pub max_cherry_kg_per_rai: Option<Decimal>,
+}
+
+/// Two plots whose outlines overlap
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotOverlap {
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    /// Set when the other plot belongs to the business
+    pub other_plot_id: Option<Uuid>,
+    pub other_plot_name: String,
+    /// Set when the other plot belongs to another cooperative member
+    pub other_business_name: Option<String>,
+}
+
+/// Plot area used for yield checks
+#[derive(Debug, Clone)]
+pub struct PlotArea {
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    pub area_rai: Option<Decimal>,
+}
+
+/// Cherry picked on a plot on one day
+#[derive(Debug, Clone)]
+pub struct HarvestWeight {
+    pub plot_id: Uuid,
+    pub harvest_date: NaiveDate,
+    pub cherry_weight_kg: Decimal,
+}
+
+/// Plot whose harvests in a season exceed the plausible yield
+#[derive(Debug, Clone, Serialize)]
+pub struct YieldFlag {
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    /// Year the season starts in
+    pub season: i32,
+    /// Season label such as "2024/25"
+    pub season_label: String,
+    pub area_rai: Decimal,
+    pub cherry_kg: Decimal,
+    pub harvests: usize,
+    pub cherry_kg_per_rai: Decimal,
+    pub max_cherry_kg_per_rai: Decimal,
+}
+
+/// Overlaps and implausible yields of a business's plots
+#[derive(Debug, Clone, Serialize)]
+pub struct PlotValidationReport {
+    pub plots: usize,
+    /// Plots without an outline, which overlap checks cannot cover
+    pub plots_without_boundary: usize,
+    /// Plots without an area, which yield checks cannot cover
+    pub plots_without_area: usize,
+    pub cooperative_code: Option<String>,
+    pub max_cherry_kg_per_rai: Decimal,
+    pub overlaps: Vec<PlotOverlap>,
+    pub yield_flags: Vec<YieldFlag>,
+}
+
+/// Plots and seasons whose summed cherry exceeds `max_kg_per_rai`, highest
+/// yield first; plots without an area are skipped
+pub fn yield_flags(
+    plots: &[PlotArea],
+    harvests: &[HarvestWeight],
+    max_kg_per_rai: Decimal,
+    calendar: &ReportingCalendar,
+) -> Vec<YieldFlag> {
+    let mut totals: BTreeMap<(Uuid, i32), (Decimal, usize)> = BTreeMap::new();
+    for harvest in harvests {
+        let total = totals.entry((harvest.plot_id, calendar.season_of(harvest.harvest_date))).or_default();
+        total.0 += harvest.cherry_weight_kg;
+        total.1 += 1;
+    }
+
+    let mut flags: Vec<YieldFlag> = totals
+        .into_iter()
+        .filter_map(|((plot_id, season), (cherry_kg, count))| {
+            let plot = plots.iter().find(|p| p.plot_id == plot_id)?;
+            let area_rai = plot.area_rai.filter(|a| *a > Decimal::ZERO)?;
+            let per_rai = cherry_kg / area_rai;
+            (per_rai > max_kg_per_rai).then(|| YieldFlag {
+                plot_id,
+                plot_name: plot.plot_name.clone(),
+                season,
+                season_label: calendar.season_label(season),
+                area_rai,
+                cherry_kg,
+                harvests: count,
+                cherry_kg_per_rai: per_rai.round_dp(1),
+                max_cherry_kg_per_rai: max_kg_per_rai,
+            })
+        })
+        .collect();
+    flags.sort_by(|a, b| b.cherry_kg_per_rai.cmp(&a.cherry_kg_per_rai).then(a.season.cmp(&b.season)));
+    flags
+}
+
+/// Outline of a stored plot, if it has a usable one
+fn outline(boundary: Option<&Value>) -> Option<Vec<Polygon>> {
+    let polygons = parse_geometry(boundary?).ok()?;
+    validate_polygons(&polygons).is_empty().then_some(polygons)
+}
+
+impl PlotValidationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Check the plots of a business for overlaps and implausible yields
+    pub async fn validate(&self, business_id: Uuid, query: &PlotValidationQuery) -> AppResult<PlotValidationReport> {
+        let max_kg_per_rai = query.max_cherry_kg_per_rai.unwrap_or(DEFAULT_MAX_CHERRY_KG_PER_RAI);
+        if max_kg_per_rai <= Decimal::ZERO {
+            return Err(AppError::Validation {
+                field: "max_cherry_kg_per_rai".to_string(),
+                message: "Maximum yield must be greater than 0".to_string(),
+                message_th: "ผลผลิตสูงสุดต้องมากกว่า 0".to_string(),
+            });
+        }
+
+        let cooperative_code = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT cooperative_code FROM businesses WHERE id = $1",
+        )
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business".to_string()))?;
+        if query.include_cooperative && cooperative_code.is_none() {
+            return Err(AppError::Validation {
+                field: "include_cooperative".to_string(),
+                message: "Set a cooperative code in business settings to check plots across the cooperative"
+                    .to_string(),
+                message_th: "กรุณาระบุรหัสสหกรณ์ในการตั้งค่าธุรกิจเพื่อตรวจสอบแปลงทั้งสหกรณ์".to_string(),
+            });
+        }
+
+        let plots = sqlx::query_as::<_, (Uuid, String, Option<Decimal>, Option<Value>)>(
+            "SELECT id, name, area_rai, boundary FROM plots WHERE business_id = $1 ORDER BY name",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let outlines: Vec<(Uuid, &str, Vec<Polygon>)> = plots
+            .iter()
+            .filter_map(|(id, name, _, boundary)| Some((*id, name.as_str(), outline(boundary.as_ref())?)))
+            .collect();
+
+        let mut overlaps = Vec::new();
+        for (i, (plot_id, plot_name, polygons)) in outlines.iter().enumerate() {
+            for (other_id, other_name, other_polygons) in &outlines[i + 1..] {
+                if outlines_overlap(polygons, other_polygons) {
+                    overlaps.push(PlotOverlap {
+                        plot_id: *plot_id,
+                        plot_name: plot_name.to_string(),
+                        other_plot_id: Some(*other_id),
+                        other_plot_name: other_name.to_string(),
+                        other_business_name: None,
+                    });
+                }
+            }
+        }
+
+        if query.include_cooperative {
+            // Other members' plots are outside the request's tenant
+            let members = without_tenant(sqlx::query_as::<_, (String, String, Value)>(
+                r#"
+                SELECT b.name, p.name, p.boundary
+                FROM plots p
+                JOIN businesses b ON b.id = p.business_id
+                WHERE b.cooperative_code = $2 AND b.id <> $1 AND p.boundary IS NOT NULL
+                "#,
+            )
+            .bind(business_id)
+            .bind(&cooperative_code)
+            .fetch_all(&self.db))
+            .await?;
+
+            for (business_name, other_name, boundary) in &members {
+                let Some(other_polygons) = outline(Some(boundary)) else {
+                    continue;
+                };
+                for (plot_id, plot_name, polygons) in &outlines {
+                    if outlines_overlap(polygons, &other_polygons) {
+                        overlaps.push(PlotOverlap {
+                            plot_id: *plot_id,
+                            plot_name: plot_name.to_string(),
+                            other_plot_id: None,
+                            other_plot_name: other_name.clone(),
+                            other_business_name: Some(business_name.clone()),
+                        });
+                    }
+                }
+            }
+        }
+
+        let harvests = sqlx::query_as::<_, (Uuid, NaiveDate, Decimal)>(
+            "SELECT plot_id, harvest_date, cherry_weight_kg FROM harvests WHERE business_id = $1",
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|(plot_id, harvest_date, cherry_weight_kg)| HarvestWeight {
+            plot_id,
+            harvest_date,
+            cherry_weight_kg,
+        })
+        .collect::<Vec<_>>();
+
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let areas: Vec<PlotArea> = plots
+            .iter()
+            .map(|(plot_id, plot_name, area_rai, _)| PlotArea {
+                plot_id: *plot_id,
+                plot_name: plot_name.clone(),
+                area_rai: *area_rai,
+            })
+            .collect();
+
+        Ok(PlotValidationReport {
+            plots: plots.len(),
+            plots_without_boundary: plots.len() - outlines.len(),
+            plots_without_area: areas.iter().filter(|p| p.area_rai.is_none_or(|a| a <= Decimal::ZERO)).count(),
+            cooperative_code,
+            max_cherry_kg_per_rai: max_kg_per_rai,
+            overlaps,
+            yield_flags: yield_flags(&areas, &harvests, max_kg_per_rai, &calendar),
+        })
+    }
+}
@@ -0,0 +1,756 @@
+//! Realized sale prices and market reference prices
+//!
+//! Records the price each lot actually sold for, by grade, and the market
+//! prices the business follows (the ICE "C" price, Thai FOB quotes), entered
+//! by hand. Both are normalized to THB per kg so the pricing report can put
+//! every sale next to the latest reference on its sale date and show the
+//! premium over the market per lot.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::grading::grade_to_str;
+use crate::services::reporting_period::resolve_date_range;
+use crate::services::BusinessService;
+use shared::GradeClassification;
+
+/// Pounds in a kilogram, for the C price quoted in US cents per lb
+pub const LB_PER_KG: Decimal = Decimal::from_parts(220_462_262, 0, 0, false, 8);
+
+/// Oldest reference, in days before the sale, still compared with it
+pub const MAX_REFERENCE_AGE_DAYS: i64 = 31;
+
+/// Period of the pricing report when no start is given
+pub const DEFAULT_REPORT_DAYS: i64 = 365;
+
+/// Pricing service
+#[derive(Clone)]
+pub struct PricingService {
+    db: PgPool,
+}
+
+/// Market price followed as a reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketBenchmark {
+    /// ICE Arabica "C" futures
+    #[default]
+    CPrice,
+    /// Free-on-board quotes for Thai Arabica
+    ThaiFob,
+    Other,
+}
+
+impl MarketBenchmark {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketBenchmark::CPrice => "c_price",
+            MarketBenchmark::ThaiFob => "thai_fob",
+            MarketBenchmark::Other => "other",
+        }
+    }
+}
+
+/// How a price is quoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceUnit {
+    /// US cents per pound, as the C price is quoted
+    UscPerLb,
+    UsdPerKg,
+    ThbPerKg,
+}
+
+impl PriceUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceUnit::UscPerLb => "usc_per_lb",
+            PriceUnit::UsdPerKg => "usd_per_kg",
+            PriceUnit::ThbPerKg => "thb_per_kg",
+        }
+    }
+
+    /// Whether converting to baht needs an exchange rate
+    pub fn is_usd(&self) -> bool {
+        !matches!(self, PriceUnit::ThbPerKg)
+    }
+}
+
+/// Price in THB per kg, rounded to satang; `None` for a US dollar price
+/// without an exchange rate
+pub fn to_thb_per_kg(price: Decimal, unit: PriceUnit, thb_per_usd: Option<Decimal>) -> Option<Decimal> {
+    let thb = match unit {
+        PriceUnit::ThbPerKg => price,
+        PriceUnit::UsdPerKg => price * thb_per_usd?,
+        PriceUnit::UscPerLb => price / Decimal::ONE_HUNDRED * LB_PER_KG * thb_per_usd?,
+    };
+    Some(thb.round_dp(2))
+}
+
+/// Latest reference on or before `date` and at most
+/// [`MAX_REFERENCE_AGE_DAYS`] older; `references` are sorted by date
+pub fn reference_on(references: &[(NaiveDate, Decimal)], date: NaiveDate) -> Option<Decimal> {
+    let earliest = date - Duration::days(MAX_REFERENCE_AGE_DAYS);
+    let index = references.partition_point(|(day, _)| *day <= date);
+    references[..index]
+        .last()
+        .filter(|(day, _)| *day >= earliest)
+        .map(|(_, price)| *price)
+}
+
+/// Sale as the pricing report compares it
+#[derive(Debug, Clone, Copy)]
+pub struct PricedSale {
+    pub quantity_kg: Decimal,
+    pub price_thb_per_kg: Decimal,
+    /// Market reference on the sale date, if one was recorded
+    pub reference_thb_per_kg: Option<Decimal>,
+}
+
+/// Quantity-weighted prices of a set of sales
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceSummary {
+    pub sales: usize,
+    pub sold_kg: Decimal,
+    pub revenue_thb: Decimal,
+    pub average_price_thb_per_kg: Option<Decimal>,
+    /// Quantity sold while a market reference was recorded
+    pub referenced_kg: Decimal,
+    pub average_reference_thb_per_kg: Option<Decimal>,
+    /// Average price over the reference, for the referenced sales only
+    pub premium_thb_per_kg: Option<Decimal>,
+    pub premium_percent: Option<Decimal>,
+}
+
+/// Summarize sales; premiums compare only the sales with a reference
+pub fn summarize(sales: &[PricedSale]) -> PriceSummary {
+    let sold_kg: Decimal = sales.iter().map(|s| s.quantity_kg).sum();
+    let revenue: Decimal = sales.iter().map(|s| s.quantity_kg * s.price_thb_per_kg).sum();
+
+    let referenced: Vec<(Decimal, Decimal, Decimal)> = sales
+        .iter()
+        .filter_map(|s| s.reference_thb_per_kg.map(|r| (s.quantity_kg, s.price_thb_per_kg, r)))
+        .collect();
+    let referenced_kg: Decimal = referenced.iter().map(|(kg, _, _)| *kg).sum();
+    let (reference, premium) = if referenced_kg > Decimal::ZERO {
+        let reference = referenced.iter().map(|(kg, _, r)| kg * r).sum::<Decimal>() / referenced_kg;
+        let price = referenced.iter().map(|(kg, p, _)| kg * p).sum::<Decimal>() / referenced_kg;
+        (Some(reference), Some(price - reference))
+    } else {
+        (None, None)
+    };
+
+    PriceSummary {
+        sales: sales.len(),
+        sold_kg,
+        revenue_thb: revenue.round_dp(2),
+        average_price_thb_per_kg: (sold_kg > Decimal::ZERO).then(|| (revenue / sold_kg).round_dp(2)),
+        referenced_kg,
+        average_reference_thb_per_kg: reference.map(|r| r.round_dp(2)),
+        premium_thb_per_kg: premium.map(|p| p.round_dp(2)),
+        premium_percent: reference
+            .zip(premium)
+            .filter(|(r, _)| *r > Decimal::ZERO)
+            .map(|(r, p)| (p / r * Decimal::ONE_HUNDRED).round_dp(1)),
+    }
+}
+
+/// Realized sale price of a lot
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SalePrice {
+    pub id: Uuid,
+    pub lot_id: Uuid,
+    pub lot_code: String,
+    pub sales_order_id: Option<Uuid>,
+    pub order_number: Option<String>,
+    pub grade: Option<String>,
+    pub sale_date: NaiveDate,
+    pub buyer_name: Option<String>,
+    pub quantity_kg: Decimal,
+    pub price_per_kg: Decimal,
+    pub currency: String,
+    pub thb_per_usd: Option<Decimal>,
+    pub price_thb_per_kg: Decimal,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a realized sale price. With a sales order the lot,
+/// quantity, price, currency and buyer default to the order's
+#[derive(Debug, Deserialize)]
+pub struct RecordSalePriceInput {
+    pub sales_order_id: Option<Uuid>,
+    pub lot_id: Option<Uuid>,
+    /// Defaults to the lot's latest grading
+    pub grade: Option<GradeClassification>,
+    /// Defaults to the order date, or today
+    pub sale_date: Option<NaiveDate>,
+    pub buyer_name: Option<String>,
+    pub quantity_kg: Option<Decimal>,
+    pub price_per_kg: Option<Decimal>,
+    /// THB (default) or USD
+    pub currency: Option<String>,
+    /// Required for USD prices
+    pub thb_per_usd: Option<Decimal>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing sale prices
+#[derive(Debug, Default, Deserialize)]
+pub struct SalePriceQuery {
+    pub lot_id: Option<Uuid>,
+    pub grade: Option<GradeClassification>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Market reference price
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MarketReferencePrice {
+    pub id: Uuid,
+    pub benchmark: String,
+    pub reference_date: NaiveDate,
+    pub price: Decimal,
+    pub unit: String,
+    pub thb_per_usd: Option<Decimal>,
+    pub price_thb_per_kg: Decimal,
+    pub source: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a market reference price; a second price for the
+/// same benchmark and day replaces the first
+#[derive(Debug, Deserialize)]
+pub struct RecordMarketPriceInput {
+    pub benchmark: MarketBenchmark,
+    pub reference_date: NaiveDate,
+    pub price: Decimal,
+    pub unit: PriceUnit,
+    /// Required for US dollar prices
+    pub thb_per_usd: Option<Decimal>,
+    /// Where the price was read, e.g. a newspaper or exporter bulletin
+    pub source: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing market reference prices
+#[derive(Debug, Default, Deserialize)]
+pub struct MarketPriceQuery {
+    pub benchmark: Option<MarketBenchmark>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Query for the pricing report
+#[derive(Debug, Default, Deserialize)]
+pub struct PricingReportQuery {
+    /// Defaults to [`DEFAULT_REPORT_DAYS`] before `to`
+    pub from: Option<NaiveDate>,
+    /// Defaults to today
+    pub to: Option<NaiveDate>,
+    /// Report period such as `season:2024/25` or `fy:current`; overrides
+    /// `from` and `to`
+    pub period: Option<String>,
+    /// Market price the sales are compared with (default C price)
+    pub benchmark: Option<MarketBenchmark>,
+    pub lot_id: Option<Uuid>,
+}
+
+/// Prices of one lot and grade
+#[derive(Debug, Clone, Serialize)]
+pub struct LotPricing {
+    pub lot_id: Uuid,
+    pub lot_code: String,
+    pub lot_name: String,
+    pub grade: Option<String>,
+    pub first_sale: NaiveDate,
+    pub last_sale: NaiveDate,
+    #[serde(flatten)]
+    pub summary: PriceSummary,
+}
+
+/// Realized prices against the market, per lot and grade
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub benchmark: MarketBenchmark,
+    /// Highest premium first; lots without a reference last
+    pub lots: Vec<LotPricing>,
+    pub total: PriceSummary,
+}
+
+/// Sale with its lot, as read for the report
+#[derive(Debug, sqlx::FromRow)]
+struct ReportSaleRow {
+    lot_id: Uuid,
+    lot_code: String,
+    lot_name: String,
+    grade: Option<String>,
+    sale_date: NaiveDate,
+    quantity_kg: Decimal,
+    price_thb_per_kg: Decimal,
+}
+
+/// Sales order a realized price is recorded from
+#[derive(Debug, sqlx::FromRow)]
+struct OrderRow {
+    lot_id: Option<Uuid>,
+    buyer_name: String,
+    quantity_kg: Decimal,
+    price_per_kg: Decimal,
+    currency: String,
+    created_at: DateTime<Utc>,
+}
+
+const SALE_PRICE_SELECT: &str = r#"
+    SELECT sp.id, sp.lot_id, l.traceability_code AS lot_code, sp.sales_order_id, so.order_number,
+           sp.grade, sp.sale_date, sp.buyer_name, sp.quantity_kg, sp.price_per_kg, sp.currency,
+           sp.thb_per_usd, sp.price_thb_per_kg, sp.notes, sp.created_by, sp.created_at
+    FROM lot_sale_prices sp
+    JOIN lots l ON l.id = sp.lot_id
+    LEFT JOIN sales_orders so ON so.id = sp.sales_order_id
+"#;
+
+const MARKET_PRICE_SELECT: &str = r#"
+    SELECT id, benchmark, reference_date, price, unit, thb_per_usd, price_thb_per_kg,
+           source, notes, created_by, created_at
+    FROM market_reference_prices
+"#;
+
+impl PricingService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record the price a lot sold for
+    pub async fn record_sale_price(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordSalePriceInput,
+    ) -> AppResult<SalePrice> {
+        let order = match input.sales_order_id {
+            Some(order_id) => Some(self.get_order(business_id, order_id).await?),
+            None => None,
+        };
+
+        let lot_id = match (input.lot_id, order.as_ref().and_then(|o| o.lot_id)) {
+            (Some(lot_id), Some(order_lot)) if lot_id != order_lot => {
+                return Err(AppError::Validation {
+                    field: "lot_id".to_string(),
+                    message: "The sales order sold a different lot".to_string(),
+                    message_th: "คำสั่งขายนี้เป็นของล็อตอื่น".to_string(),
+                });
+            }
+            (Some(lot_id), _) | (None, Some(lot_id)) => lot_id,
+            (None, None) => {
+                return Err(AppError::Validation {
+                    field: "lot_id".to_string(),
+                    message: "Lot is required".to_string(),
+                    message_th: "ต้องระบุล็อต".to_string(),
+                });
+            }
+        };
+        let quantity_kg = input.quantity_kg.or(order.as_ref().map(|o| o.quantity_kg));
+        let quantity_kg = quantity_kg.filter(|q| *q > Decimal::ZERO).ok_or_else(|| AppError::Validation {
+            field: "quantity_kg".to_string(),
+            message: "Quantity sold must be greater than zero".to_string(),
+            message_th: "ปริมาณที่ขายต้องมากกว่าศูนย์".to_string(),
+        })?;
+        let price_per_kg = input.price_per_kg.or(order.as_ref().map(|o| o.price_per_kg));
+        let price_per_kg = price_per_kg.filter(|p| *p > Decimal::ZERO).ok_or_else(|| AppError::Validation {
+            field: "price_per_kg".to_string(),
+            message: "Price per kg must be greater than zero".to_string(),
+            message_th: "ราคาต่อกิโลกรัมต้องมากกว่าศูนย์".to_string(),
+        })?;
+        let currency = input
+            .currency
+            .as_deref()
+            .or(order.as_ref().map(|o| o.currency.as_str()))
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| "THB".to_string());
+        let unit = match currency.as_str() {
+            "THB" => PriceUnit::ThbPerKg,
+            "USD" => PriceUnit::UsdPerKg,
+            _ => {
+                return Err(AppError::Validation {
+                    field: "currency".to_string(),
+                    message: "Sale prices are recorded in THB or USD".to_string(),
+                    message_th: "บันทึกราคาขายเป็น THB หรือ USD เท่านั้น".to_string(),
+                });
+            }
+        };
+        let price_thb_per_kg = Self::normalize(price_per_kg, unit, input.thb_per_usd)?;
+        let sale_date = input
+            .sale_date
+            .or(order.as_ref().map(|o| o.created_at.date_naive()))
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let buyer_name = input
+            .buyer_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .or(order.map(|o| o.buyer_name));
+
+        let lot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !lot_exists {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        if let Some(order_id) = input.sales_order_id {
+            let recorded = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM lot_sale_prices WHERE sales_order_id = $1)",
+            )
+            .bind(order_id)
+            .fetch_one(&self.db)
+            .await?;
+            if recorded {
+                return Err(AppError::Conflict {
+                    resource: "lot_sale_price".to_string(),
+                    message: "The price of this sales order is already recorded".to_string(),
+                    message_th: "บันทึกราคาของคำสั่งขายนี้แล้ว".to_string(),
+                });
+            }
+        }
+
+        let grade = match input.grade {
+            Some(grade) => Some(grade_to_str(&grade).to_string()),
+            None => {
+                sqlx::query_scalar::<_, String>(
+                    r#"
+                    SELECT grade FROM green_bean_grades
+                    WHERE lot_id = $1
+                    ORDER BY grading_date DESC, created_at DESC
+                    LIMIT 1
+                    "#,
+                )
+                .bind(lot_id)
+                .fetch_optional(&self.db)
+                .await?
+            }
+        };
+
+        let price_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO lot_sale_prices (
+                business_id, lot_id, sales_order_id, grade, sale_date, buyer_name, quantity_kg,
+                price_per_kg, currency, thb_per_usd, price_thb_per_kg, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(lot_id)
+        .bind(input.sales_order_id)
+        .bind(grade)
+        .bind(sale_date)
+        .bind(buyer_name)
+        .bind(quantity_kg)
+        .bind(price_per_kg)
+        .bind(&currency)
+        .bind(input.thb_per_usd.filter(|_| unit.is_usd()))
+        .bind(price_thb_per_kg)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get_sale_price(business_id, price_id).await
+    }
+
+    /// Get a realized sale price
+    pub async fn get_sale_price(&self, business_id: Uuid, price_id: Uuid) -> AppResult<SalePrice> {
+        sqlx::query_as::<_, SalePrice>(&format!(
+            "{} WHERE sp.id = $1 AND sp.business_id = $2",
+            SALE_PRICE_SELECT
+        ))
+        .bind(price_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sale price".to_string()))
+    }
+
+    /// List realized sale prices, latest sale first
+    pub async fn list_sale_prices(&self, business_id: Uuid, query: &SalePriceQuery) -> AppResult<Vec<SalePrice>> {
+        let prices = sqlx::query_as::<_, SalePrice>(&format!(
+            r#"
+            {}
+            WHERE sp.business_id = $1
+              AND ($2::UUID IS NULL OR sp.lot_id = $2)
+              AND ($3::TEXT IS NULL OR sp.grade = $3)
+              AND ($4::DATE IS NULL OR sp.sale_date >= $4)
+              AND ($5::DATE IS NULL OR sp.sale_date <= $5)
+            ORDER BY sp.sale_date DESC, sp.created_at DESC
+            "#,
+            SALE_PRICE_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.grade.as_ref().map(grade_to_str))
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(prices)
+    }
+
+    /// Delete a realized sale price
+    pub async fn delete_sale_price(&self, business_id: Uuid, price_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM lot_sale_prices WHERE id = $1 AND business_id = $2")
+            .bind(price_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Sale price".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Record a market reference price, replacing one already recorded for
+    /// the benchmark on that day
+    pub async fn record_market_price(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordMarketPriceInput,
+    ) -> AppResult<MarketReferencePrice> {
+        if input.price <= Decimal::ZERO {
+            return Err(AppError::Validation {
+                field: "price".to_string(),
+                message: "Price must be greater than zero".to_string(),
+                message_th: "ราคาต้องมากกว่าศูนย์".to_string(),
+            });
+        }
+        let price_thb_per_kg = Self::normalize(input.price, input.unit, input.thb_per_usd)?;
+        let source = input.source.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+        let reference = sqlx::query_as::<_, MarketReferencePrice>(
+            r#"
+            INSERT INTO market_reference_prices (
+                business_id, benchmark, reference_date, price, unit, thb_per_usd,
+                price_thb_per_kg, source, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (business_id, benchmark, reference_date) DO UPDATE
+            SET price = EXCLUDED.price, unit = EXCLUDED.unit, thb_per_usd = EXCLUDED.thb_per_usd,
+                price_thb_per_kg = EXCLUDED.price_thb_per_kg, source = EXCLUDED.source,
+                notes = EXCLUDED.notes, created_by = EXCLUDED.created_by, created_at = NOW()
+            RETURNING id, benchmark, reference_date, price, unit, thb_per_usd, price_thb_per_kg,
+                      source, notes, created_by, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.benchmark.as_str())
+        .bind(input.reference_date)
+        .bind(input.price)
+        .bind(input.unit.as_str())
+        .bind(input.thb_per_usd.filter(|_| input.unit.is_usd()))
+        .bind(price_thb_per_kg)
+        .bind(source)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(reference)
+    }
+
+    /// List market reference prices, latest first
+    pub async fn list_market_prices(
+        &self,
+        business_id: Uuid,
+        query: &MarketPriceQuery,
+    ) -> AppResult<Vec<MarketReferencePrice>> {
+        let prices = sqlx::query_as::<_, MarketReferencePrice>(&format!(
+            r#"
+            {}
+            WHERE business_id = $1
+              AND ($2::TEXT IS NULL OR benchmark = $2)
+              AND ($3::DATE IS NULL OR reference_date >= $3)
+              AND ($4::DATE IS NULL OR reference_date <= $4)
+            ORDER BY reference_date DESC, benchmark
+            "#,
+            MARKET_PRICE_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.benchmark.map(|b| b.as_str()))
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(prices)
+    }
+
+    /// Delete a market reference price
+    pub async fn delete_market_price(&self, business_id: Uuid, reference_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM market_reference_prices WHERE id = $1 AND business_id = $2")
+            .bind(reference_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Market reference price".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Realized prices per lot and grade against the market on each sale date
+    pub async fn report(&self, business_id: Uuid, query: &PricingReportQuery) -> AppResult<PricingReport> {
+        let today = Utc::now().date_naive();
+        let calendar = BusinessService::new(self.db.clone()).get_reporting_calendar(business_id).await?;
+        let (from, to) = resolve_date_range(query.period.as_deref(), query.from, query.to, &calendar, today)?;
+        let to = to.unwrap_or(today);
+        let from = from.unwrap_or(to - Duration::days(DEFAULT_REPORT_DAYS));
+        if from > to {
+            return Err(AppError::Validation {
+                field: "from".to_string(),
+                message: "Report cannot start after it ends".to_string(),
+                message_th: "วันเริ่มต้นของรายงานต้องไม่หลังวันสิ้นสุด".to_string(),
+            });
+        }
+        let benchmark = query.benchmark.unwrap_or_default();
+
+        let sales = sqlx::query_as::<_, ReportSaleRow>(
+            r#"
+            SELECT sp.lot_id, l.traceability_code AS lot_code, l.name AS lot_name, sp.grade,
+                   sp.sale_date, sp.quantity_kg, sp.price_thb_per_kg
+            FROM lot_sale_prices sp
+            JOIN lots l ON l.id = sp.lot_id
+            WHERE sp.business_id = $1 AND sp.sale_date BETWEEN $2 AND $3
+              AND ($4::UUID IS NULL OR sp.lot_id = $4)
+            ORDER BY sp.sale_date
+            "#,
+        )
+        .bind(business_id)
+        .bind(from)
+        .bind(to)
+        .bind(query.lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let references = sqlx::query_as::<_, (NaiveDate, Decimal)>(
+            r#"
+            SELECT reference_date, price_thb_per_kg
+            FROM market_reference_prices
+            WHERE business_id = $1 AND benchmark = $2 AND reference_date BETWEEN $3 AND $4
+            ORDER BY reference_date
+            "#,
+        )
+        .bind(business_id)
+        .bind(benchmark.as_str())
+        .bind(from - Duration::days(MAX_REFERENCE_AGE_DAYS))
+        .bind(to)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut groups: Vec<(LotPricing, Vec<PricedSale>)> = Vec::new();
+        let mut all = Vec::with_capacity(sales.len());
+        for row in sales {
+            let sale = PricedSale {
+                quantity_kg: row.quantity_kg,
+                price_thb_per_kg: row.price_thb_per_kg,
+                reference_thb_per_kg: reference_on(&references, row.sale_date),
+            };
+            all.push(sale);
+            match groups
+                .iter_mut()
+                .find(|(lot, _)| lot.lot_id == row.lot_id && lot.grade == row.grade)
+            {
+                Some((lot, lot_sales)) => {
+                    lot.last_sale = row.sale_date;
+                    lot_sales.push(sale);
+                }
+                None => groups.push((
+                    LotPricing {
+                        lot_id: row.lot_id,
+                        lot_code: row.lot_code,
+                        lot_name: row.lot_name,
+                        grade: row.grade,
+                        first_sale: row.sale_date,
+                        last_sale: row.sale_date,
+                        summary: summarize(&[]),
+                    },
+                    vec![sale],
+                )),
+            }
+        }
+
+        let mut lots: Vec<LotPricing> = groups
+            .into_iter()
+            .map(|(mut lot, lot_sales)| {
+                lot.summary = summarize(&lot_sales);
+                lot
+            })
+            .collect();
+        lots.sort_by(|a, b| {
+            match (a.summary.premium_thb_per_kg, b.summary.premium_thb_per_kg) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+            .then_with(|| a.lot_code.cmp(&b.lot_code))
+        });
+
+        Ok(PricingReport {
+            from,
+            to,
+            benchmark,
+            lots,
+            total: summarize(&all),
+        })
+    }
+
+    async fn get_order(&self, business_id: Uuid, order_id: Uuid) -> AppResult<OrderRow> {
+        sqlx::query_as::<_, OrderRow>(
+            r#"
+            SELECT lot_id, buyer_name, quantity_kg, price_per_kg, currency, created_at
+            FROM sales_orders
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(order_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sales order".to_string()))
+    }
+
+    /// Price in THB per kg, or the validation error for a missing or
+    /// invalid exchange rate
+    fn normalize(price: Decimal, unit: PriceUnit, thb_per_usd: Option<Decimal>) -> AppResult<Decimal> {
+        if unit.is_usd() && thb_per_usd.is_none_or(|rate| rate <= Decimal::ZERO) {
+            return Err(AppError::Validation {
+                field: "thb_per_usd".to_string(),
+                message: "An exchange rate (THB per USD) is required for US dollar prices".to_string(),
+                message_th: "ต้องระบุอัตราแลกเปลี่ยน (บาทต่อดอลลาร์สหรัฐ) สำหรับราคาเป็นดอลลาร์".to_string(),
+            });
+        }
+        to_thb_per_kg(price, unit, thb_per_usd).ok_or_else(|| AppError::Internal("Price conversion failed".to_string()))
+    }
+}
@@ -8,7 +8,10 @@ use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::services::lot::LotStage;
-use shared::{DryingLog, FermentationLog, ProcessingMethod};
+use shared::{
+    calculate_processing_yield, validate_processing_method, DryingLog, FermentationLog, Language, ProcessingMethod,
+    MAX_ANAEROBIC_HOURS, MAX_CUSTOM_METHOD_NAME_LENGTH,
+};
 
 /// Processing service for managing coffee processing records
 #[derive(Clone)]
@@ -130,7 +133,7 @@ impl ProcessingService {
         input: StartProcessingInput,
     ) -> AppResult<ProcessingRecord> {
         // Validate lot exists and belongs to business
-        let lot = sqlx::query_as::<_, (Uuid, String, Decimal)>(
+        let lot = sqlx::query_as::<_, (Uuid, LotStage, Decimal)>(
             "SELECT id, stage, current_weight_kg FROM lots WHERE id = $1 AND business_id = $2",
         )
         .bind(input.lot_id)
@@ -140,7 +143,7 @@ impl ProcessingService {
         .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
 
         // Validate lot is in Cherry stage
-        if lot.1 != LotStage::Cherry.as_str() {
+        if lot.1 != LotStage::Cherry {
             return Err(AppError::Validation {
                 field: "lot_id".to_string(),
                 message: format!(
@@ -149,11 +152,20 @@ impl ProcessingService {
                 ),
                 message_th: format!(
                     "ล็อตต้องอยู่ในสถานะเชอร์รี่เพื่อเริ่มการแปรรูป สถานะปัจจุบัน: {}",
-                    lot.1
+                    lot.1.name_in(&Language::Thai)
                 ),
             });
         }
 
+        validate_processing_method(&input.method).map_err(|message| AppError::Validation {
+            field: "method".to_string(),
+            message: message.to_string(),
+            message_th: format!(
+                "พารามิเตอร์ของวิธีการแปรรูปไม่ถูกต้อง: เมือก 0-100%, หมักไร้อากาศ 1-{} ชั่วโมง, ชื่อวิธีการไม่เกิน {} ตัวอักษร",
+                MAX_ANAEROBIC_HOURS, MAX_CUSTOM_METHOD_NAME_LENGTH
+            ),
+        })?;
+
         // Check if processing already exists for this lot
         let existing =
             sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM processing_records WHERE lot_id = $1")
@@ -322,15 +334,9 @@ impl ProcessingService {
         }
 
         // Calculate processing yield
-        let processing_yield = if let Some(cherry) = cherry_weight {
-            if cherry > Decimal::ZERO {
-                Some((input.green_bean_weight_kg / cherry) * Decimal::from(100))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let processing_yield = cherry_weight
+            .filter(|cherry| *cherry > Decimal::ZERO)
+            .map(|cherry| calculate_processing_yield(cherry, input.green_bean_weight_kg));
 
         // Start transaction
         let mut tx = self.db.begin().await?;
@@ -365,7 +371,7 @@ impl ProcessingService {
             WHERE id = $3
             "#,
         )
-        .bind(LotStage::GreenBean.as_str())
+        .bind(LotStage::GreenBean)
         .bind(input.green_bean_weight_kg)
         .bind(lot_id)
         .execute(&mut *tx)
@@ -469,32 +475,14 @@ impl ProcessingService {
     }
 }
 
-/// Convert ProcessingMethod to database representation
+/// Convert ProcessingMethod to database representation: its code and,
+/// for methods with parameters, the parameters
 fn method_to_db(method: &ProcessingMethod) -> (String, Option<serde_json::Value>) {
-    match method {
-        ProcessingMethod::Natural => ("natural".to_string(), None),
-        ProcessingMethod::Washed => ("washed".to_string(), None),
-        ProcessingMethod::Honey { mucilage_percent } => (
-            "honey".to_string(),
-            Some(serde_json::json!({ "mucilage_percent": mucilage_percent })),
-        ),
-        ProcessingMethod::WetHulled => ("wet_hulled".to_string(), None),
-        ProcessingMethod::Anaerobic { hours } => (
-            "anaerobic".to_string(),
-            Some(serde_json::json!({ "hours": hours })),
-        ),
-        ProcessingMethod::Custom(name) => (
-            "custom".to_string(),
-            Some(serde_json::json!({ "name": name })),
-        ),
-    }
-}
-
-/// Calculate processing yield percentage
-pub fn calculate_processing_yield(cherry_weight: Decimal, green_bean_weight: Decimal) -> Decimal {
-    if cherry_weight.is_zero() {
-        Decimal::ZERO
-    } else {
-        (green_bean_weight / cherry_weight) * Decimal::from(100)
-    }
+    let details = match method {
+        ProcessingMethod::Natural | ProcessingMethod::Washed | ProcessingMethod::WetHulled => None,
+        ProcessingMethod::Honey { mucilage_percent } => Some(serde_json::json!({ "mucilage_percent": mucilage_percent })),
+        ProcessingMethod::Anaerobic { hours } => Some(serde_json::json!({ "hours": hours })),
+        ProcessingMethod::Custom(name) => Some(serde_json::json!({ "name": name.trim() })),
+    };
+    (method.code().to_string(), details)
 }
@@ -0,0 +1,460 @@
+//! Processing capacity planning
+//!
+//! Fermentation tanks and drying beds hold a limited weight of cherry. The
+//! plan simulates, day by day over the coming weeks, the load from
+//! processing still in progress and from the harvest expected in each week,
+//! and warns for every week whose peak load exceeds the active capacity.
+//! Expected harvest is the cherry harvested in the same week a year earlier
+//! (364 days, so weekdays line up), assumed to be fermented.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+pub const DEFAULT_PLAN_WEEKS: u32 = 4;
+pub const MAX_PLAN_WEEKS: u32 = 12;
+/// Typical wet fermentation in tanks
+pub const DEFAULT_FERMENTATION_DAYS: u32 = 2;
+/// Typical drying on raised beds down to 10-12% moisture
+pub const DEFAULT_DRYING_DAYS: u32 = 14;
+
+/// Days between a harvest and the same weekday a year later
+const YEAR_OFFSET_DAYS: i64 = 364;
+
+/// Processing capacity service
+#[derive(Clone)]
+pub struct ProcessingCapacityService {
+    db: PgPool,
+}
+
+/// Kind of processing resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    FermentationTank,
+    DryingBed,
+}
+
+impl ResourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceType::FermentationTank => "fermentation_tank",
+            ResourceType::DryingBed => "drying_bed",
+        }
+    }
+}
+
+/// Fermentation tank or drying bed
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProcessingResource {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub name: String,
+    pub resource_type: String,
+    /// Cherry weight the resource holds at once
+    pub capacity_kg: Decimal,
+    pub is_active: bool,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for creating or replacing a resource
+#[derive(Debug, Deserialize)]
+pub struct ProcessingResourceInput {
+    pub name: String,
+    pub resource_type: ResourceType,
+    pub capacity_kg: Decimal,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+    pub notes: Option<String>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Planning horizon and processing durations
+#[derive(Debug, Default, Deserialize)]
+pub struct CapacityPlanQuery {
+    /// Defaults to today
+    pub start_date: Option<NaiveDate>,
+    pub weeks: Option<u32>,
+    pub fermentation_days: Option<u32>,
+    pub drying_days: Option<u32>,
+}
+
+/// Cherry entering processing on a day
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PlannedBatch {
+    pub start_date: NaiveDate,
+    pub cherry_kg: Decimal,
+    /// Naturals and honeys go straight to the beds
+    pub fermented: bool,
+}
+
+/// Load and capacity for one week of the plan
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityWeek {
+    pub week_start: NaiveDate,
+    /// Cherry entering processing this week
+    pub cherry_in_kg: Decimal,
+    pub peak_tank_load_kg: Decimal,
+    pub peak_bed_load_kg: Decimal,
+    pub tank_utilization_percent: Option<Decimal>,
+    pub bed_utilization_percent: Option<Decimal>,
+}
+
+/// A week in which a resource type is over capacity
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityWarning {
+    pub week_start: NaiveDate,
+    pub resource_type: ResourceType,
+    /// First day of the week over capacity
+    pub first_date: NaiveDate,
+    pub peak_load_kg: Decimal,
+    pub capacity_kg: Decimal,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Capacity plan for the coming weeks
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityPlan {
+    pub start_date: NaiveDate,
+    pub fermentation_days: u32,
+    pub drying_days: u32,
+    pub tank_capacity_kg: Decimal,
+    pub bed_capacity_kg: Decimal,
+    pub weeks: Vec<CapacityWeek>,
+    pub warnings: Vec<CapacityWarning>,
+}
+
+fn invalid(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn duplicate_resource_name() -> AppError {
+    AppError::Conflict {
+        resource: "processing_resource".to_string(),
+        message: "A tank or bed with this name already exists".to_string(),
+        message_th: "มีถังหมักหรือแคร่ตากชื่อนี้อยู่แล้ว".to_string(),
+    }
+}
+
+fn validate_resource(input: &ProcessingResourceInput) -> AppResult<()> {
+    if input.name.trim().is_empty() {
+        return Err(invalid("name", "Resource name is required", "กรุณาระบุชื่อถังหมักหรือแคร่ตาก"));
+    }
+    if input.capacity_kg <= Decimal::ZERO {
+        return Err(invalid("capacity_kg", "Capacity must be greater than 0", "ความจุต้องมากกว่า 0"));
+    }
+    Ok(())
+}
+
+fn utilization(load: Decimal, capacity: Decimal) -> Option<Decimal> {
+    (capacity > Decimal::ZERO).then(|| (load / capacity * Decimal::ONE_HUNDRED).round_dp(1))
+}
+
+/// Simulate daily tank and bed load for `weeks` weeks from `start`.
+/// A batch ferments for `fermentation_days` from its start date (when
+/// fermented), then dries for `drying_days`.
+pub fn plan_weeks(
+    start: NaiveDate,
+    weeks: u32,
+    batches: &[PlannedBatch],
+    tank_capacity: Decimal,
+    bed_capacity: Decimal,
+    fermentation_days: u32,
+    drying_days: u32,
+) -> (Vec<CapacityWeek>, Vec<CapacityWarning>) {
+    let fermentation = Duration::days(fermentation_days as i64);
+    let drying = Duration::days(drying_days as i64);
+    let mut plan = Vec::with_capacity(weeks as usize);
+    let mut warnings = Vec::new();
+
+    for week in 0..weeks {
+        let week_start = start + Duration::days(7 * week as i64);
+        let week_end = week_start + Duration::days(7);
+        let mut peak_tank = Decimal::ZERO;
+        let mut peak_bed = Decimal::ZERO;
+        let mut tank_over: Option<NaiveDate> = None;
+        let mut bed_over: Option<NaiveDate> = None;
+
+        for offset in 0..7 {
+            let day = week_start + Duration::days(offset);
+            let mut tank_load = Decimal::ZERO;
+            let mut bed_load = Decimal::ZERO;
+            for batch in batches {
+                let drying_start = if batch.fermented {
+                    if batch.start_date <= day && day < batch.start_date + fermentation {
+                        tank_load += batch.cherry_kg;
+                    }
+                    batch.start_date + fermentation
+                } else {
+                    batch.start_date
+                };
+                if drying_start <= day && day < drying_start + drying {
+                    bed_load += batch.cherry_kg;
+                }
+            }
+            peak_tank = peak_tank.max(tank_load);
+            peak_bed = peak_bed.max(bed_load);
+            if tank_load > tank_capacity && tank_over.is_none() {
+                tank_over = Some(day);
+            }
+            if bed_load > bed_capacity && bed_over.is_none() {
+                bed_over = Some(day);
+            }
+        }
+
+        for (resource_type, first_date, peak, capacity) in [
+            (ResourceType::FermentationTank, tank_over, peak_tank, tank_capacity),
+            (ResourceType::DryingBed, bed_over, peak_bed, bed_capacity),
+        ] {
+            let Some(first_date) = first_date else { continue };
+            let (label, label_th) = match resource_type {
+                ResourceType::FermentationTank => ("Fermentation tanks", "ถังหมัก"),
+                ResourceType::DryingBed => ("Drying beds", "แคร่ตาก"),
+            };
+            warnings.push(CapacityWarning {
+                week_start,
+                resource_type,
+                first_date,
+                peak_load_kg: peak,
+                capacity_kg: capacity,
+                message: format!(
+                    "{} need {} kg from {} but hold {} kg",
+                    label, peak, first_date, capacity
+                ),
+                message_th: format!(
+                    "{}ต้องรองรับ {} กก. ตั้งแต่ {} แต่รับได้ {} กก.",
+                    label_th, peak, first_date, capacity
+                ),
+            });
+        }
+
+        plan.push(CapacityWeek {
+            week_start,
+            cherry_in_kg: batches
+                .iter()
+                .filter(|b| week_start <= b.start_date && b.start_date < week_end)
+                .map(|b| b.cherry_kg)
+                .sum(),
+            peak_tank_load_kg: peak_tank,
+            peak_bed_load_kg: peak_bed,
+            tank_utilization_percent: utilization(peak_tank, tank_capacity),
+            bed_utilization_percent: utilization(peak_bed, bed_capacity),
+        });
+    }
+
+    (plan, warnings)
+}
+
+impl ProcessingCapacityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Create a tank or bed
+    pub async fn create_resource(
+        &self,
+        business_id: Uuid,
+        input: ProcessingResourceInput,
+    ) -> AppResult<ProcessingResource> {
+        validate_resource(&input)?;
+
+        sqlx::query_as::<_, ProcessingResource>(
+            r#"
+            INSERT INTO processing_resources (business_id, name, resource_type, capacity_kg, is_active, notes)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (business_id, name) DO NOTHING
+            RETURNING id, business_id, name, resource_type, capacity_kg, is_active, notes, created_at, updated_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(input.resource_type.as_str())
+        .bind(input.capacity_kg)
+        .bind(input.is_active)
+        .bind(&input.notes)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(duplicate_resource_name)
+    }
+
+    /// List tanks and beds by type and name
+    pub async fn list_resources(&self, business_id: Uuid) -> AppResult<Vec<ProcessingResource>> {
+        let resources = sqlx::query_as::<_, ProcessingResource>(
+            r#"
+            SELECT id, business_id, name, resource_type, capacity_kg, is_active, notes, created_at, updated_at
+            FROM processing_resources
+            WHERE business_id = $1
+            ORDER BY resource_type, name
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(resources)
+    }
+
+    /// Replace a tank or bed
+    pub async fn update_resource(
+        &self,
+        business_id: Uuid,
+        resource_id: Uuid,
+        input: ProcessingResourceInput,
+    ) -> AppResult<ProcessingResource> {
+        validate_resource(&input)?;
+
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM processing_resources WHERE business_id = $1 AND name = $2 AND id <> $3)",
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(resource_id)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(duplicate_resource_name());
+        }
+
+        sqlx::query_as::<_, ProcessingResource>(
+            r#"
+            UPDATE processing_resources
+            SET name = $3, resource_type = $4, capacity_kg = $5, is_active = $6, notes = $7
+            WHERE id = $1 AND business_id = $2
+            RETURNING id, business_id, name, resource_type, capacity_kg, is_active, notes, created_at, updated_at
+            "#,
+        )
+        .bind(resource_id)
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(input.resource_type.as_str())
+        .bind(input.capacity_kg)
+        .bind(input.is_active)
+        .bind(&input.notes)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Processing resource".to_string()))
+    }
+
+    /// Delete a tank or bed
+    pub async fn delete_resource(&self, business_id: Uuid, resource_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM processing_resources WHERE id = $1 AND business_id = $2")
+            .bind(resource_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Processing resource".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Plan tank and bed load for the coming weeks
+    pub async fn plan(&self, business_id: Uuid, query: &CapacityPlanQuery) -> AppResult<CapacityPlan> {
+        let weeks = query.weeks.unwrap_or(DEFAULT_PLAN_WEEKS);
+        if !(1..=MAX_PLAN_WEEKS).contains(&weeks) {
+            return Err(invalid(
+                "weeks",
+                &format!("Plan 1 to {} weeks ahead", MAX_PLAN_WEEKS),
+                &format!("วางแผนล่วงหน้าได้ 1 ถึง {} สัปดาห์", MAX_PLAN_WEEKS),
+            ));
+        }
+        let fermentation_days = query.fermentation_days.unwrap_or(DEFAULT_FERMENTATION_DAYS);
+        let drying_days = query.drying_days.unwrap_or(DEFAULT_DRYING_DAYS);
+        if !(1..=10).contains(&fermentation_days) {
+            return Err(invalid(
+                "fermentation_days",
+                "Fermentation must take 1 to 10 days",
+                "ระยะเวลาหมักต้องอยู่ระหว่าง 1 ถึง 10 วัน",
+            ));
+        }
+        if !(1..=60).contains(&drying_days) {
+            return Err(invalid(
+                "drying_days",
+                "Drying must take 1 to 60 days",
+                "ระยะเวลาตากต้องอยู่ระหว่าง 1 ถึง 60 วัน",
+            ));
+        }
+        let start = query.start_date.unwrap_or_else(|| Utc::now().date_naive());
+        let horizon_days = 7 * weeks as i64;
+
+        let (tank_capacity, bed_capacity) = sqlx::query_as::<_, (Decimal, Decimal)>(
+            r#"
+            SELECT COALESCE(SUM(capacity_kg) FILTER (WHERE resource_type = 'fermentation_tank'), 0),
+                   COALESCE(SUM(capacity_kg) FILTER (WHERE resource_type = 'drying_bed'), 0)
+            FROM processing_resources
+            WHERE business_id = $1 AND is_active
+            "#,
+        )
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        // Batches still in progress, then last year's harvest moved forward
+        let mut batches = sqlx::query_as::<_, PlannedBatch>(
+            r#"
+            SELECT pr.start_date, pr.cherry_weight_kg AS cherry_kg,
+                   pr.method NOT IN ('natural', 'honey') AS fermented
+            FROM processing_records pr
+            JOIN lots l ON l.id = pr.lot_id
+            WHERE l.business_id = $1 AND pr.end_date IS NULL AND pr.cherry_weight_kg > 0
+              AND pr.start_date < $2
+            "#,
+        )
+        .bind(business_id)
+        .bind(start + Duration::days(horizon_days))
+        .fetch_all(&self.db)
+        .await?;
+
+        let expected = sqlx::query_as::<_, PlannedBatch>(
+            r#"
+            SELECT (harvest_date + $4::INT) AS start_date, SUM(cherry_weight_kg) AS cherry_kg,
+                   TRUE AS fermented
+            FROM harvests
+            WHERE business_id = $1 AND harvest_date >= $2 AND harvest_date < $3
+            GROUP BY harvest_date
+            "#,
+        )
+        .bind(business_id)
+        .bind(start - Duration::days(YEAR_OFFSET_DAYS))
+        .bind(start - Duration::days(YEAR_OFFSET_DAYS) + Duration::days(horizon_days))
+        .bind(YEAR_OFFSET_DAYS as i32)
+        .fetch_all(&self.db)
+        .await?;
+        batches.extend(expected);
+
+        let (weeks, warnings) = plan_weeks(
+            start,
+            weeks,
+            &batches,
+            tank_capacity,
+            bed_capacity,
+            fermentation_days,
+            drying_days,
+        );
+
+        Ok(CapacityPlan {
+            start_date: start,
+            fermentation_days,
+            drying_days,
+            tank_capacity_kg: tank_capacity,
+            bed_capacity_kg: bed_capacity,
+            weeks,
+            warnings,
+        })
+    }
+}
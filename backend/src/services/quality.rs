@@ -0,0 +1,709 @@
+//! Quality specs and evaluations
+//!
+//! A business defines quality specs (minimum cupping score, maximum
+//! category 1 and 2 defects, moisture range, screen size), optionally
+//! assigned to buyers or markets. A quality evaluation bundles a green
+//! grading and a cupping sample of the same lot and records a combined
+//! pass/fail decision against one spec. Every check is stored with the limits
+//! used, so a decision stays explainable after the spec is edited. The
+//! conformity check runs all specs against the latest grading and cupping of
+//! every lot still in stock.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::ScreenSizeDistribution;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Quality service for specs and evaluations
+#[derive(Clone)]
+pub struct QualityService {
+    db: PgPool,
+}
+
+/// Business-defined quality spec; unset limits are not checked
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct QualitySpec {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub name: String,
+    pub min_cupping_score: Option<Decimal>,
+    pub max_category1_defects: Option<i32>,
+    pub max_category2_defects: Option<i32>,
+    pub min_moisture_percent: Option<Decimal>,
+    pub max_moisture_percent: Option<Decimal>,
+    /// Screen that at least `min_screen_percent` of the sample must pass
+    pub min_screen_size: Option<i16>,
+    pub min_screen_percent: Option<Decimal>,
+    pub buyers: Vec<String>,
+    pub markets: Vec<String>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for creating or replacing a spec
+#[derive(Debug, Deserialize)]
+pub struct QualitySpecInput {
+    pub name: String,
+    pub min_cupping_score: Option<Decimal>,
+    pub max_category1_defects: Option<i32>,
+    pub max_category2_defects: Option<i32>,
+    pub min_moisture_percent: Option<Decimal>,
+    pub max_moisture_percent: Option<Decimal>,
+    pub min_screen_size: Option<i16>,
+    pub min_screen_percent: Option<Decimal>,
+    #[serde(default)]
+    pub buyers: Vec<String>,
+    #[serde(default)]
+    pub markets: Vec<String>,
+    pub notes: Option<String>,
+}
+
+/// Measured quality of a lot from a grading and a cupping sample; values
+/// are missing when the lot has not been graded or cupped
+#[derive(Debug, Clone, Default)]
+pub struct MeasuredQuality {
+    pub cupping_score: Option<Decimal>,
+    pub category1_defects: Option<i32>,
+    pub category2_defects: Option<i32>,
+    pub moisture_percent: Option<Decimal>,
+    pub screen_size: Option<ScreenSizeDistribution>,
+}
+
+/// Outcome of one spec criterion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpecCheck {
+    /// cupping_score, category1_defects, category2_defects, moisture_percent
+    /// or screen_size (percent of the sample on the required screen)
+    pub criterion: String,
+    /// Missing measurements fail the check
+    pub actual: Option<Decimal>,
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+    pub passed: bool,
+}
+
+/// Quality evaluation with its decision
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct QualityEvaluation {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub lot_id: Uuid,
+    pub grading_id: Uuid,
+    pub cupping_sample_id: Uuid,
+    pub spec_id: Option<Uuid>,
+    pub spec_name: String,
+    pub passed: bool,
+    pub checks: sqlx::types::Json<Vec<SpecCheck>>,
+    pub notes: Option<String>,
+    pub evaluated_by: Option<Uuid>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+/// Input for evaluating a lot against a spec
+#[derive(Debug, Deserialize)]
+pub struct CreateQualityEvaluationInput {
+    pub lot_id: Uuid,
+    pub grading_id: Uuid,
+    pub cupping_sample_id: Uuid,
+    pub spec_id: Uuid,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing evaluations
+#[derive(Debug, Default, Deserialize)]
+pub struct QualityEvaluationQuery {
+    pub lot_id: Option<Uuid>,
+}
+
+/// Filters for the conformity check
+#[derive(Debug, Default, Deserialize)]
+pub struct ConformityQuery {
+    pub spec_id: Option<Uuid>,
+    /// Only specs assigned to this buyer (case-insensitive)
+    pub buyer: Option<String>,
+    /// Only specs assigned to this market (case-insensitive)
+    pub market: Option<String>,
+    /// Leave out lots meeting none of the specs
+    #[serde(default)]
+    pub conforming_only: bool,
+}
+
+/// Result of one spec for one lot
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecConformity {
+    pub spec_id: Uuid,
+    pub spec_name: String,
+    pub conforms: bool,
+    pub checks: Vec<SpecCheck>,
+}
+
+/// Which specs a lot in stock currently meets
+#[derive(Debug, Clone, Serialize)]
+pub struct LotConformity {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub stage: String,
+    pub current_weight_kg: Decimal,
+    /// Latest grading and cupping sample the checks are based on
+    pub grading_id: Option<Uuid>,
+    pub cupping_sample_id: Option<Uuid>,
+    pub conforming_specs: Vec<String>,
+    pub specs: Vec<SpecConformity>,
+}
+
+/// Database row for a lot's latest quality measurements
+#[derive(Debug, sqlx::FromRow)]
+struct LotQualityRow {
+    lot_id: Uuid,
+    traceability_code: String,
+    name: String,
+    stage: String,
+    current_weight_kg: Decimal,
+    grading_id: Option<Uuid>,
+    category1_count: Option<i32>,
+    category2_count: Option<i32>,
+    moisture_percent: Option<Decimal>,
+    screen_size_distribution: Option<sqlx::types::Json<ScreenSizeDistribution>>,
+    cupping_sample_id: Option<Uuid>,
+    final_score: Option<Decimal>,
+}
+
+fn invalid(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn duplicate_spec_name() -> AppError {
+    AppError::Conflict {
+        resource: "quality_spec".to_string(),
+        message: "A quality spec with this name already exists".to_string(),
+        message_th: "มีเกณฑ์คุณภาพชื่อนี้อยู่แล้ว".to_string(),
+    }
+}
+
+fn trimmed(names: &[String]) -> Vec<String> {
+    names.iter().map(|n| n.trim().to_string()).collect()
+}
+
+/// Validate spec limits
+pub fn validate_spec(input: &QualitySpecInput) -> AppResult<()> {
+    let hundred = Decimal::ONE_HUNDRED;
+
+    if input.name.trim().is_empty() {
+        return Err(invalid("name", "Spec name is required", "กรุณาระบุชื่อเกณฑ์คุณภาพ"));
+    }
+    if input.min_cupping_score.is_some_and(|s| s < Decimal::ZERO || s > hundred) {
+        return Err(invalid(
+            "min_cupping_score",
+            "Minimum cupping score must be between 0 and 100",
+            "คะแนนคัปปิ้งขั้นต่ำต้องอยู่ระหว่าง 0 ถึง 100",
+        ));
+    }
+    for (field, limit) in [
+        ("max_category1_defects", input.max_category1_defects),
+        ("max_category2_defects", input.max_category2_defects),
+    ] {
+        if limit.is_some_and(|l| l < 0) {
+            return Err(invalid(field, "Defect limits cannot be negative", "จำนวนข้อบกพร่องต้องไม่ติดลบ"));
+        }
+    }
+    for (field, limit) in [
+        ("min_moisture_percent", input.min_moisture_percent),
+        ("max_moisture_percent", input.max_moisture_percent),
+    ] {
+        if limit.is_some_and(|l| l < Decimal::ZERO || l > hundred) {
+            return Err(invalid(field, "Moisture must be between 0 and 100%", "ความชื้นต้องอยู่ระหว่าง 0 ถึง 100%"));
+        }
+    }
+    if let (Some(min), Some(max)) = (input.min_moisture_percent, input.max_moisture_percent) {
+        if min > max {
+            return Err(invalid(
+                "min_moisture_percent",
+                "Minimum moisture cannot exceed maximum moisture",
+                "ความชื้นขั้นต่ำต้องไม่เกินความชื้นสูงสุด",
+            ));
+        }
+    }
+    match (input.min_screen_size, input.min_screen_percent) {
+        (None, None) => {}
+        (Some(size), Some(percent)) => {
+            if !(14..=18).contains(&size) {
+                return Err(invalid("min_screen_size", "Screen size must be between 14 and 18", "ขนาดตะแกรงต้องอยู่ระหว่าง 14 ถึง 18"));
+            }
+            if percent < Decimal::ZERO || percent > hundred {
+                return Err(invalid(
+                    "min_screen_percent",
+                    "Screen percentage must be between 0 and 100",
+                    "เปอร์เซ็นต์บนตะแกรงต้องอยู่ระหว่าง 0 ถึง 100",
+                ));
+            }
+        }
+        _ => {
+            return Err(invalid(
+                "min_screen_size",
+                "Screen size and screen percentage must be set together",
+                "ต้องระบุขนาดตะแกรงและเปอร์เซ็นต์บนตะแกรงคู่กัน",
+            ));
+        }
+    }
+    for (field, names) in [("buyers", &input.buyers), ("markets", &input.markets)] {
+        if names.iter().any(|n| n.trim().is_empty()) {
+            return Err(invalid(field, "Buyer and market names cannot be blank", "ชื่อผู้ซื้อและตลาดต้องไม่ว่าง"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check measured quality against every limit set on the spec
+pub fn check_spec(spec: &QualitySpec, measured: &MeasuredQuality) -> Vec<SpecCheck> {
+    let check = |criterion: &str, actual: Option<Decimal>, min: Option<Decimal>, max: Option<Decimal>| SpecCheck {
+        criterion: criterion.to_string(),
+        actual,
+        min,
+        max,
+        passed: actual.is_some_and(|a| min.iter().all(|m| a >= *m) && max.iter().all(|m| a <= *m)),
+    };
+
+    let mut checks = Vec::new();
+    if spec.min_cupping_score.is_some() {
+        checks.push(check("cupping_score", measured.cupping_score, spec.min_cupping_score, None));
+    }
+    if let Some(max) = spec.max_category1_defects {
+        checks.push(check(
+            "category1_defects",
+            measured.category1_defects.map(Decimal::from),
+            None,
+            Some(Decimal::from(max)),
+        ));
+    }
+    if let Some(max) = spec.max_category2_defects {
+        checks.push(check(
+            "category2_defects",
+            measured.category2_defects.map(Decimal::from),
+            None,
+            Some(Decimal::from(max)),
+        ));
+    }
+    if spec.min_moisture_percent.is_some() || spec.max_moisture_percent.is_some() {
+        checks.push(check(
+            "moisture_percent",
+            measured.moisture_percent,
+            spec.min_moisture_percent,
+            spec.max_moisture_percent,
+        ));
+    }
+    if let (Some(size), Some(percent)) = (spec.min_screen_size, spec.min_screen_percent) {
+        let share = measured
+            .screen_size
+            .as_ref()
+            .map(|screen| screen.share_at_or_above(size.clamp(14, 18) as u8));
+        checks.push(check("screen_size", share, Some(percent), None));
+    }
+    checks
+}
+
+/// Whether a spec applies to the buyer and market filters
+pub fn spec_applies(spec: &QualitySpec, buyer: Option<&str>, market: Option<&str>) -> bool {
+    let listed = |names: &[String], wanted: Option<&str>| match wanted {
+        Some(wanted) => names.iter().any(|n| n.trim().eq_ignore_ascii_case(wanted.trim())),
+        None => true,
+    };
+    listed(&spec.buyers, buyer) && listed(&spec.markets, market)
+}
+
+impl QualityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // ========================================================================
+    // Specs
+    // ========================================================================
+
+    /// Create a quality spec
+    pub async fn create_spec(&self, business_id: Uuid, input: QualitySpecInput) -> AppResult<QualitySpec> {
+        validate_spec(&input)?;
+
+        sqlx::query_as::<_, QualitySpec>(
+            r#"
+            INSERT INTO quality_specs (
+                business_id, name, min_cupping_score, max_category1_defects, max_category2_defects,
+                min_moisture_percent, max_moisture_percent, min_screen_size, min_screen_percent,
+                buyers, markets, notes
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (business_id, name) DO NOTHING
+            RETURNING id, business_id, name, min_cupping_score, max_category1_defects, max_category2_defects,
+                      min_moisture_percent, max_moisture_percent, min_screen_size, min_screen_percent,
+                      buyers, markets, notes, created_at, updated_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(input.min_cupping_score)
+        .bind(input.max_category1_defects)
+        .bind(input.max_category2_defects)
+        .bind(input.min_moisture_percent)
+        .bind(input.max_moisture_percent)
+        .bind(input.min_screen_size)
+        .bind(input.min_screen_percent)
+        .bind(trimmed(&input.buyers))
+        .bind(trimmed(&input.markets))
+        .bind(&input.notes)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(duplicate_spec_name)
+    }
+
+    /// List quality specs by name
+    pub async fn list_specs(&self, business_id: Uuid) -> AppResult<Vec<QualitySpec>> {
+        let specs = sqlx::query_as::<_, QualitySpec>(
+            r#"
+            SELECT id, business_id, name, min_cupping_score, max_category1_defects, max_category2_defects,
+                   min_moisture_percent, max_moisture_percent, min_screen_size, min_screen_percent,
+                   buyers, markets, notes, created_at, updated_at
+            FROM quality_specs
+            WHERE business_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(specs)
+    }
+
+    /// Get a quality spec
+    pub async fn get_spec(&self, business_id: Uuid, spec_id: Uuid) -> AppResult<QualitySpec> {
+        sqlx::query_as::<_, QualitySpec>(
+            r#"
+            SELECT id, business_id, name, min_cupping_score, max_category1_defects, max_category2_defects,
+                   min_moisture_percent, max_moisture_percent, min_screen_size, min_screen_percent,
+                   buyers, markets, notes, created_at, updated_at
+            FROM quality_specs
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(spec_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Quality spec".to_string()))
+    }
+
+    /// Replace a quality spec; past evaluations keep their recorded limits
+    pub async fn update_spec(
+        &self,
+        business_id: Uuid,
+        spec_id: Uuid,
+        input: QualitySpecInput,
+    ) -> AppResult<QualitySpec> {
+        validate_spec(&input)?;
+
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM quality_specs WHERE business_id = $1 AND name = $2 AND id <> $3)",
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(spec_id)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(duplicate_spec_name());
+        }
+
+        sqlx::query_as::<_, QualitySpec>(
+            r#"
+            UPDATE quality_specs
+            SET name = $3, min_cupping_score = $4, max_category1_defects = $5,
+                max_category2_defects = $6, min_moisture_percent = $7,
+                max_moisture_percent = $8, min_screen_size = $9, min_screen_percent = $10,
+                buyers = $11, markets = $12, notes = $13
+            WHERE id = $1 AND business_id = $2
+            RETURNING id, business_id, name, min_cupping_score, max_category1_defects, max_category2_defects,
+                      min_moisture_percent, max_moisture_percent, min_screen_size, min_screen_percent,
+                      buyers, markets, notes, created_at, updated_at
+            "#,
+        )
+        .bind(spec_id)
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(input.min_cupping_score)
+        .bind(input.max_category1_defects)
+        .bind(input.max_category2_defects)
+        .bind(input.min_moisture_percent)
+        .bind(input.max_moisture_percent)
+        .bind(input.min_screen_size)
+        .bind(input.min_screen_percent)
+        .bind(trimmed(&input.buyers))
+        .bind(trimmed(&input.markets))
+        .bind(&input.notes)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Quality spec".to_string()))
+    }
+
+    /// Delete a quality spec; evaluations against it are kept
+    pub async fn delete_spec(&self, business_id: Uuid, spec_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM quality_specs WHERE id = $1 AND business_id = $2")
+            .bind(spec_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Quality spec".to_string()));
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Evaluations
+    // ========================================================================
+
+    /// Evaluate a lot's grading and cupping sample against a spec
+    pub async fn evaluate(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateQualityEvaluationInput,
+    ) -> AppResult<QualityEvaluation> {
+        let spec = self.get_spec(business_id, input.spec_id).await?;
+
+        let (grading_lot, category1_defects, category2_defects, moisture_percent, screen_size) =
+            sqlx::query_as::<_, (Uuid, i32, i32, Decimal, Option<sqlx::types::Json<ScreenSizeDistribution>>)>(
+                r#"
+                SELECT g.lot_id, g.category1_count, g.category2_count, g.moisture_percent,
+                       g.screen_size_distribution
+                FROM green_bean_grades g
+                JOIN lots l ON l.id = g.lot_id
+                WHERE g.id = $1 AND l.business_id = $2
+                "#,
+            )
+            .bind(input.grading_id)
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Grading record".to_string()))?;
+
+        let (sample_lot, cupping_score) = sqlx::query_as::<_, (Uuid, Decimal)>(
+            r#"
+            SELECT cs.lot_id, cs.final_score
+            FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            WHERE cs.id = $1 AND s.business_id = $2
+            "#,
+        )
+        .bind(input.cupping_sample_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Cupping sample".to_string()))?;
+
+        if grading_lot != input.lot_id {
+            return Err(invalid(
+                "grading_id",
+                "The grading record belongs to a different lot",
+                "ผลการคัดเกรดเป็นของล็อตอื่น",
+            ));
+        }
+        if sample_lot != input.lot_id {
+            return Err(invalid(
+                "cupping_sample_id",
+                "The cupping sample belongs to a different lot",
+                "ตัวอย่างคัปปิ้งเป็นของล็อตอื่น",
+            ));
+        }
+
+        let checks = check_spec(
+            &spec,
+            &MeasuredQuality {
+                cupping_score: Some(cupping_score),
+                category1_defects: Some(category1_defects),
+                category2_defects: Some(category2_defects),
+                moisture_percent: Some(moisture_percent),
+                screen_size: screen_size.map(|s| s.0),
+            },
+        );
+        let passed = checks.iter().all(|c| c.passed);
+
+        let evaluation = sqlx::query_as::<_, QualityEvaluation>(
+            r#"
+            INSERT INTO quality_evaluations (
+                business_id, lot_id, grading_id, cupping_sample_id, spec_id, spec_name,
+                passed, checks, notes, evaluated_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, business_id, lot_id, grading_id, cupping_sample_id, spec_id, spec_name,
+                      passed, checks, notes, evaluated_by, evaluated_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.grading_id)
+        .bind(input.cupping_sample_id)
+        .bind(spec.id)
+        .bind(&spec.name)
+        .bind(passed)
+        .bind(sqlx::types::Json(&checks))
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(evaluation)
+    }
+
+    /// List evaluations, newest first
+    pub async fn list_evaluations(
+        &self,
+        business_id: Uuid,
+        query: &QualityEvaluationQuery,
+    ) -> AppResult<Vec<QualityEvaluation>> {
+        let evaluations = sqlx::query_as::<_, QualityEvaluation>(
+            r#"
+            SELECT id, business_id, lot_id, grading_id, cupping_sample_id, spec_id, spec_name,
+                   passed, checks, notes, evaluated_by, evaluated_at
+            FROM quality_evaluations
+            WHERE business_id = $1 AND ($2::UUID IS NULL OR lot_id = $2)
+            ORDER BY evaluated_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(evaluations)
+    }
+
+    /// Get an evaluation
+    pub async fn get_evaluation(&self, business_id: Uuid, evaluation_id: Uuid) -> AppResult<QualityEvaluation> {
+        sqlx::query_as::<_, QualityEvaluation>(
+            r#"
+            SELECT id, business_id, lot_id, grading_id, cupping_sample_id, spec_id, spec_name,
+                   passed, checks, notes, evaluated_by, evaluated_at
+            FROM quality_evaluations
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(evaluation_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Quality evaluation".to_string()))
+    }
+
+    // ========================================================================
+    // Conformity
+    // ========================================================================
+
+    /// Check every lot in stock against the selected specs, using each lot's
+    /// latest grading and cupping sample
+    pub async fn check_conformity(
+        &self,
+        business_id: Uuid,
+        query: &ConformityQuery,
+    ) -> AppResult<Vec<LotConformity>> {
+        let specs: Vec<QualitySpec> = self
+            .list_specs(business_id)
+            .await?
+            .into_iter()
+            .filter(|spec| query.spec_id.is_none() || query.spec_id == Some(spec.id))
+            .filter(|spec| spec_applies(spec, query.buyer.as_deref(), query.market.as_deref()))
+            .collect();
+        if let Some(spec_id) = query.spec_id.filter(|_| specs.is_empty()) {
+            // Distinguish an unknown spec from one filtered out by buyer/market
+            self.get_spec(business_id, spec_id).await?;
+        }
+        if specs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query_as::<_, LotQualityRow>(
+            r#"
+            SELECT l.id AS lot_id, l.traceability_code, l.name, l.stage, l.current_weight_kg,
+                   g.id AS grading_id, g.category1_count, g.category2_count, g.moisture_percent,
+                   g.screen_size_distribution,
+                   c.id AS cupping_sample_id, c.final_score
+            FROM lots l
+            LEFT JOIN LATERAL (
+                SELECT id, category1_count, category2_count, moisture_percent, screen_size_distribution
+                FROM green_bean_grades
+                WHERE lot_id = l.id
+                ORDER BY grading_date DESC, created_at DESC
+                LIMIT 1
+            ) g ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT cs.id, cs.final_score
+                FROM cupping_samples cs
+                JOIN cupping_sessions s ON s.id = cs.session_id
+                WHERE cs.lot_id = l.id
+                ORDER BY s.session_date DESC, cs.created_at DESC
+                LIMIT 1
+            ) c ON TRUE
+            WHERE l.business_id = $1 AND l.stage <> 'sold' AND l.current_weight_kg > 0
+            ORDER BY l.traceability_code
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let lots = rows
+            .into_iter()
+            .map(|row| {
+                let measured = MeasuredQuality {
+                    cupping_score: row.final_score,
+                    category1_defects: row.category1_count,
+                    category2_defects: row.category2_count,
+                    moisture_percent: row.moisture_percent,
+                    screen_size: row.screen_size_distribution.map(|s| s.0),
+                };
+                let results: Vec<SpecConformity> = specs
+                    .iter()
+                    .map(|spec| {
+                        let checks = check_spec(spec, &measured);
+                        SpecConformity {
+                            spec_id: spec.id,
+                            spec_name: spec.name.clone(),
+                            conforms: checks.iter().all(|c| c.passed),
+                            checks,
+                        }
+                    })
+                    .collect();
+
+                LotConformity {
+                    lot_id: row.lot_id,
+                    traceability_code: row.traceability_code,
+                    name: row.name,
+                    stage: row.stage,
+                    current_weight_kg: row.current_weight_kg,
+                    grading_id: row.grading_id,
+                    cupping_sample_id: row.cupping_sample_id,
+                    conforming_specs: results
+                        .iter()
+                        .filter(|r| r.conforms)
+                        .map(|r| r.spec_name.clone())
+                        .collect(),
+                    specs: results,
+                }
+            })
+            .filter(|lot| !query.conforming_only || !lot.conforming_specs.is_empty())
+            .collect();
+
+        Ok(lots)
+    }
+}
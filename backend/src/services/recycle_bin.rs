@@ -0,0 +1,557 @@
+//! Recycle bin for deleted records
+//!
+//! Deleting a plot, harvest, farm activity, certification, lab result,
+//! shipment, insurance policy, water quality measurement, moisture reading,
+//! cupping sample or grading keeps a snapshot of it in the same
+//! transaction: the row itself, every row its delete cascades to (found by
+//! following the database's foreign keys), and the rows whose reference to
+//! it is cleared. Restoring inserts the rows again, parents first, and puts
+//! the cleared references back. Snapshots older than the business's
+//! retention period are purged by a background job.
+//!
+//! Lot photos are not kept: deleting one removes its file from storage, so
+//! there would be nothing to restore the row to.
+
+use std::collections::{HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::{ensure_session_open, CuppingSessionStatus};
+
+/// Longest retention a business can choose
+pub const MAX_RETENTION_DAYS: i32 = 365;
+/// Rows one snapshot may hold, so a runaway cascade cannot bloat the bin
+pub const MAX_SNAPSHOT_ROWS: usize = 10_000;
+
+/// Recycle bin service
+#[derive(Clone)]
+pub struct RecycleBinService {
+    db: PgPool,
+}
+
+/// Kind of record kept in the recycle bin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletedEntity {
+    Plot,
+    Harvest,
+    FarmActivity,
+    Certification,
+    LabResult,
+    Shipment,
+    InsurancePolicy,
+    WaterQuality,
+    MoistureReading,
+    CuppingSample,
+    Grading,
+}
+
+impl DeletedEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plot => "plot",
+            Self::Harvest => "harvest",
+            Self::FarmActivity => "farm_activity",
+            Self::Certification => "certification",
+            Self::LabResult => "lab_result",
+            Self::Shipment => "shipment",
+            Self::InsurancePolicy => "insurance_policy",
+            Self::WaterQuality => "water_quality",
+            Self::MoistureReading => "moisture_reading",
+            Self::CuppingSample => "cupping_sample",
+            Self::Grading => "grading",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "plot" => Some(Self::Plot),
+            "harvest" => Some(Self::Harvest),
+            "farm_activity" => Some(Self::FarmActivity),
+            "certification" => Some(Self::Certification),
+            "lab_result" => Some(Self::LabResult),
+            "shipment" => Some(Self::Shipment),
+            "insurance_policy" => Some(Self::InsurancePolicy),
+            "water_quality" => Some(Self::WaterQuality),
+            "moisture_reading" => Some(Self::MoistureReading),
+            "cupping_sample" => Some(Self::CuppingSample),
+            "grading" => Some(Self::Grading),
+            _ => None,
+        }
+    }
+
+    /// Table the record lives in
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Plot => "plots",
+            Self::Harvest => "harvests",
+            Self::FarmActivity => "farm_activities",
+            Self::Certification => "certifications",
+            Self::LabResult => "lab_results",
+            Self::Shipment => "shipments",
+            Self::InsurancePolicy => "lot_insurance_policies",
+            Self::WaterQuality => "water_quality_measurements",
+            Self::MoistureReading => "moisture_readings",
+            Self::CuppingSample => "cupping_samples",
+            Self::Grading => "green_bean_grades",
+        }
+    }
+
+    /// SQL limiting the row aliased `t` to the business bound as `$2`;
+    /// samples and gradings belong to it through their session and lot
+    fn scope_sql(&self) -> &'static str {
+        match self {
+            Self::CuppingSample => "t.session_id IN (SELECT s.id FROM cupping_sessions s WHERE s.business_id = $2)",
+            Self::Grading => "t.lot_id IN (SELECT l.id FROM lots l WHERE l.business_id = $2)",
+            _ => "t.business_id = $2",
+        }
+    }
+
+    /// SQL naming the record in the bin, over the row aliased `t`
+    fn label_sql(&self) -> &'static str {
+        match self {
+            Self::Plot => "t.name",
+            Self::Harvest => {
+                "(SELECT p.name FROM plots p WHERE p.id = t.plot_id) || ' ' || t.harvest_date::text"
+            }
+            Self::FarmActivity => "t.activity_type || ' ' || t.activity_date::text",
+            Self::Certification => "t.certification_name || ' ' || t.certificate_number",
+            Self::LabResult => "t.analysis || ' ' || (SELECT l.traceability_code FROM lots l WHERE l.id = t.lot_id)",
+            Self::Shipment => "t.shipment_number",
+            Self::InsurancePolicy => "t.policy_number",
+            Self::WaterQuality => "t.source || ' ' || t.measured_at::date::text",
+            Self::MoistureReading => {
+                "(SELECT l.traceability_code FROM lots l WHERE l.id = t.lot_id) || ' ' || t.measured_at::date::text"
+            }
+            // The session rather than the lot, which a blind session hides
+            Self::CuppingSample => {
+                "(SELECT s.cupper_name || ' ' || s.session_date::text FROM cupping_sessions s WHERE s.id = t.session_id) \
+                 || ' #' || t.sample_number::text"
+            }
+            Self::Grading => {
+                "(SELECT l.traceability_code FROM lots l WHERE l.id = t.lot_id) || ' ' || t.grading_date::text"
+            }
+        }
+    }
+
+    /// Resource name in not-found errors
+    pub fn resource(&self) -> &'static str {
+        match self {
+            Self::Plot => "Plot",
+            Self::Harvest => "Harvest",
+            Self::FarmActivity => "Farm activity",
+            Self::Certification => "Certification",
+            Self::LabResult => "Lab result",
+            Self::Shipment => "Shipment",
+            Self::InsurancePolicy => "Insurance policy",
+            Self::WaterQuality => "Water quality measurement",
+            Self::MoistureReading => "Moisture reading",
+            Self::CuppingSample => "Cupping sample",
+            Self::Grading => "Grading",
+        }
+    }
+}
+
+/// A row removed along with the record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotRow {
+    pub table: String,
+    pub row: Value,
+}
+
+/// Rows whose reference to a removed row was cleared on delete
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotLink {
+    pub table: String,
+    pub column: String,
+    pub target: Uuid,
+    pub ids: Vec<Uuid>,
+}
+
+/// Everything a delete removed or cleared; rows are in parent-first order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordSnapshot {
+    pub rows: Vec<SnapshotRow>,
+    pub links: Vec<SnapshotLink>,
+}
+
+/// Id of a captured row, if the table has one
+pub fn row_id(row: &Value) -> Option<Uuid> {
+    row.get("id")?.as_str()?.parse().ok()
+}
+
+/// Check a retention period chosen by a business
+pub fn validate_retention_days(days: i32) -> AppResult<()> {
+    if !(1..=MAX_RETENTION_DAYS).contains(&days) {
+        return Err(AppError::Validation {
+            field: "recycle_bin_retention_days".to_string(),
+            message: format!("Retention must be between 1 and {} days", MAX_RETENTION_DAYS),
+            message_th: format!("ระยะเวลาเก็บรักษาต้องอยู่ระหว่าง 1 ถึง {} วัน", MAX_RETENTION_DAYS),
+        });
+    }
+    Ok(())
+}
+
+/// A record in the recycle bin
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DeletedRecord {
+    pub id: Uuid,
+    pub entity: String,
+    pub record_id: Uuid,
+    pub label: String,
+    /// Rows that come back on restore, the record included
+    pub row_count: i32,
+    pub deleted_by: Option<Uuid>,
+    pub deleted_by_name: Option<String>,
+    pub deleted_at: DateTime<Utc>,
+    pub purge_at: DateTime<Utc>,
+}
+
+/// Query parameters for the recycle bin
+#[derive(Debug, Deserialize)]
+pub struct RecycleBinQuery {
+    pub entity: Option<DeletedEntity>,
+}
+
+/// Result of restoring a record
+#[derive(Debug, Serialize)]
+pub struct RestoredRecord {
+    pub entity: DeletedEntity,
+    pub record_id: Uuid,
+    pub label: String,
+    pub rows_restored: u64,
+}
+
+/// Foreign key referencing a table's id
+#[derive(Debug, sqlx::FromRow)]
+struct ReferencingKey {
+    table_name: String,
+    column_name: String,
+    /// pg_constraint.confdeltype: 'c' cascade, 'n' set null
+    on_delete: String,
+    has_id: bool,
+}
+
+impl RecycleBinService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Keep a snapshot of a record and everything its delete touches; call
+    /// inside the transaction that deletes it
+    pub async fn capture(
+        tx: &mut Transaction<'_, Postgres>,
+        business_id: Uuid,
+        user_id: Option<Uuid>,
+        entity: DeletedEntity,
+        record_id: Uuid,
+    ) -> AppResult<()> {
+        let (root, label) = sqlx::query_as::<_, (Value, Option<String>)>(&format!(
+            "SELECT to_jsonb(t), {} FROM {} t WHERE t.id = $1 AND {} FOR UPDATE OF t",
+            entity.label_sql(),
+            entity.table(),
+            entity.scope_sql()
+        ))
+        .bind(record_id)
+        .bind(business_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(entity.resource().to_string()))?;
+
+        let mut snapshot = RecordSnapshot::default();
+        snapshot.rows.push(SnapshotRow { table: entity.table().to_string(), row: root });
+
+        let mut seen: HashSet<Uuid> = HashSet::from([record_id]);
+        let mut queue: VecDeque<(String, Vec<Uuid>)> = VecDeque::from([(entity.table().to_string(), vec![record_id])]);
+
+        while let Some((table, ids)) = queue.pop_front() {
+            for key in Self::referencing_keys(tx, &table).await? {
+                if key.on_delete == "c" {
+                    let rows = sqlx::query_scalar::<_, Value>(&format!(
+                        r#"SELECT to_jsonb(t) FROM "{}" t WHERE t."{}" = ANY($1)"#,
+                        key.table_name, key.column_name
+                    ))
+                    .bind(&ids)
+                    .fetch_all(&mut **tx)
+                    .await?;
+
+                    let mut child_ids = Vec::new();
+                    for row in rows {
+                        if let Some(id) = row_id(&row) {
+                            if !seen.insert(id) {
+                                continue;
+                            }
+                            child_ids.push(id);
+                        }
+                        snapshot.rows.push(SnapshotRow { table: key.table_name.clone(), row });
+                    }
+                    if snapshot.rows.len() > MAX_SNAPSHOT_ROWS {
+                        return Err(AppError::Validation {
+                            field: "id".to_string(),
+                            message: format!(
+                                "Too many related records to keep in the recycle bin (more than {})",
+                                MAX_SNAPSHOT_ROWS
+                            ),
+                            message_th: format!(
+                                "มีข้อมูลที่เกี่ยวข้องมากเกินกว่าจะเก็บในถังขยะ (เกิน {} รายการ)",
+                                MAX_SNAPSHOT_ROWS
+                            ),
+                        });
+                    }
+                    if !child_ids.is_empty() {
+                        queue.push_back((key.table_name, child_ids));
+                    }
+                } else if key.has_id {
+                    let linked = sqlx::query_as::<_, (Uuid, Uuid)>(&format!(
+                        r#"SELECT t."{col}", t.id FROM "{table}" t WHERE t."{col}" = ANY($1) ORDER BY t.id"#,
+                        col = key.column_name,
+                        table = key.table_name
+                    ))
+                    .bind(&ids)
+                    .fetch_all(&mut **tx)
+                    .await?;
+
+                    for target in &ids {
+                        let linked_ids: Vec<Uuid> =
+                            linked.iter().filter(|(t, _)| t == target).map(|(_, id)| *id).collect();
+                        if !linked_ids.is_empty() {
+                            snapshot.links.push(SnapshotLink {
+                                table: key.table_name.clone(),
+                                column: key.column_name.clone(),
+                                target: *target,
+                                ids: linked_ids,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO deleted_records (business_id, entity, record_id, label, snapshot, deleted_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(business_id)
+        .bind(entity.as_str())
+        .bind(record_id)
+        .bind(label.unwrap_or_default())
+        .bind(sqlx::types::Json(&snapshot))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Move a record to the recycle bin and delete it
+    pub async fn delete(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        entity: DeletedEntity,
+        record_id: Uuid,
+    ) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        Self::capture(&mut tx, business_id, Some(user_id), entity, record_id).await?;
+
+        sqlx::query(&format!("DELETE FROM {} t WHERE t.id = $1 AND {}", entity.table(), entity.scope_sql()))
+            .bind(record_id)
+            .bind(business_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Records in the recycle bin, most recently deleted first
+    pub async fn list(&self, business_id: Uuid, query: &RecycleBinQuery) -> AppResult<Vec<DeletedRecord>> {
+        let records = sqlx::query_as::<_, DeletedRecord>(
+            r#"
+            SELECT d.id, d.entity, d.record_id, d.label,
+                   jsonb_array_length(d.snapshot->'rows') AS row_count,
+                   d.deleted_by, u.name AS deleted_by_name, d.deleted_at,
+                   d.deleted_at + b.recycle_bin_retention_days * INTERVAL '1 day' AS purge_at
+            FROM deleted_records d
+            JOIN businesses b ON b.id = d.business_id
+            LEFT JOIN users u ON u.id = d.deleted_by
+            WHERE d.business_id = $1 AND ($2::text IS NULL OR d.entity = $2)
+            ORDER BY d.deleted_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.entity.map(|e| e.as_str()))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Put a deleted record back, with everything its delete removed
+    pub async fn restore(&self, business_id: Uuid, deleted_id: Uuid) -> AppResult<RestoredRecord> {
+        let mut tx = self.db.begin().await?;
+
+        let (entity, record_id, label, snapshot) =
+            sqlx::query_as::<_, (String, Uuid, String, sqlx::types::Json<RecordSnapshot>)>(
+                r#"
+                SELECT entity, record_id, label, snapshot
+                FROM deleted_records
+                WHERE id = $1 AND business_id = $2
+                FOR UPDATE
+                "#,
+            )
+            .bind(deleted_id)
+            .bind(business_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Deleted record".to_string()))?;
+        let entity = DeletedEntity::from_str(&entity)
+            .ok_or_else(|| AppError::Internal(format!("Unknown recycle bin entity '{}'", entity)))?;
+
+        // A sample can only come back to a session still taking scores
+        if entity == DeletedEntity::CuppingSample {
+            let status = sqlx::query_scalar::<_, String>(
+                "SELECT status FROM cupping_sessions WHERE id = ($1::jsonb->>'session_id')::uuid FOR UPDATE",
+            )
+            .bind(snapshot.rows.first().map(|r| &r.row))
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(status) = status {
+                ensure_session_open(CuppingSessionStatus::from_str(&status).unwrap_or_default())?;
+            }
+        }
+
+        let mut rows_restored = 0;
+        for (index, row) in snapshot.rows.iter().enumerate() {
+            // Rows recreated by triggers when the record comes back (such as
+            // certification alerts) are already there
+            let sql = format!(
+                r#"INSERT INTO "{table}" SELECT * FROM jsonb_populate_record(NULL::"{table}", $1){conflict}"#,
+                table = row.table,
+                conflict = if index == 0 { "" } else { " ON CONFLICT DO NOTHING" }
+            );
+            match sqlx::query(&sql).bind(&row.row).execute(&mut *tx).await {
+                Ok(result) => rows_restored += result.rows_affected(),
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                    return Err(AppError::Conflict {
+                        resource: entity.resource().to_string(),
+                        message: format!("Cannot restore '{}': a record with the same details exists", label),
+                        message_th: format!("ไม่สามารถกู้คืน '{}': มีข้อมูลเดียวกันอยู่แล้ว", label),
+                    });
+                }
+                Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                    return Err(AppError::Validation {
+                        field: "id".to_string(),
+                        message: format!("Cannot restore '{}': a record it belongs to has been deleted", label),
+                        message_th: format!("ไม่สามารถกู้คืน '{}': ข้อมูลที่เชื่อมโยงอยู่ถูกลบไปแล้ว", label),
+                    });
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        for link in &snapshot.links {
+            sqlx::query(&format!(
+                r#"UPDATE "{table}" SET "{col}" = $1 WHERE id = ANY($2) AND "{col}" IS NULL"#,
+                table = link.table,
+                col = link.column
+            ))
+            .bind(link.target)
+            .bind(&link.ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // A harvest's cherry was taken off its lot when it was deleted
+        if entity == DeletedEntity::Harvest {
+            sqlx::query(
+                r#"
+                UPDATE lots SET current_weight_kg = lots.current_weight_kg + h.cherry_weight_kg
+                FROM harvests h
+                WHERE h.id = $1 AND lots.id = h.lot_id
+                "#,
+            )
+            .bind(record_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("DELETE FROM deleted_records WHERE id = $1")
+            .bind(deleted_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(RestoredRecord { entity, record_id, label, rows_restored })
+    }
+
+    /// Remove a record from the recycle bin for good
+    pub async fn purge(&self, business_id: Uuid, deleted_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM deleted_records WHERE id = $1 AND business_id = $2")
+            .bind(deleted_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Deleted record".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Purge every record past its business's retention period, returning
+    /// how many were purged
+    pub async fn purge_expired(&self) -> AppResult<usize> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM deleted_records d
+            USING businesses b
+            WHERE b.id = d.business_id
+              AND d.deleted_at + b.recycle_bin_retention_days * INTERVAL '1 day' <= NOW()
+            "#,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Single-column foreign keys onto a table's id that cascade or clear
+    /// on delete
+    async fn referencing_keys(tx: &mut Transaction<'_, Postgres>, table: &str) -> AppResult<Vec<ReferencingKey>> {
+        let keys = sqlx::query_as::<_, ReferencingKey>(
+            r#"
+            SELECT cl.relname::text AS table_name,
+                   a.attname::text AS column_name,
+                   c.confdeltype::text AS on_delete,
+                   EXISTS (
+                       SELECT 1 FROM pg_attribute ia
+                       WHERE ia.attrelid = c.conrelid AND ia.attname = 'id' AND NOT ia.attisdropped
+                   ) AS has_id
+            FROM pg_constraint c
+            JOIN pg_class cl ON cl.oid = c.conrelid
+            JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+            JOIN pg_attribute pa ON pa.attrelid = c.confrelid AND pa.attnum = c.confkey[1]
+            WHERE c.contype = 'f'
+              AND c.confrelid = $1::text::regclass
+              AND cardinality(c.conkey) = 1
+              AND pa.attname = 'id'
+              AND c.confdeltype IN ('c', 'n')
+            ORDER BY cl.relname, a.attname
+            "#,
+        )
+        .bind(table)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(keys)
+    }
+}
@@ -0,0 +1,317 @@
+//! Reference data bundles for offline clients
+//!
+//! The PWA caches the reference catalogs (varieties, regions, processing
+//! method templates, cupping descriptors and role permissions) locally.
+//! Each catalog is versioned by a hash of its content and the bundle
+//! version joins the catalog versions in a fixed order, so a client sending
+//! back the bundle version it holds only receives the catalogs that changed.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shared::{THAI_COFFEE_PROVINCES, THAI_COFFEE_PROVINCES_EN};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+
+/// Separator between catalog versions in the bundle version
+const VERSION_SEPARATOR: char = '.';
+
+/// Varieties commonly grown in Thailand: (key, name, Thai name)
+const VARIETIES: &[(&str, &str, &str)] = &[
+    ("typica", "Typica", "ทิปปิก้า"),
+    ("catimor", "Catimor", "คาติมอร์"),
+    ("catuai", "Catuai", "คาทูไอ"),
+    ("geisha", "Geisha", "เกอิชา"),
+    ("bourbon", "Bourbon", "เบอร์บอน"),
+    ("sl28", "SL28", "เอสแอล 28"),
+    ("sl34", "SL34", "เอสแอล 34"),
+    ("caturra", "Caturra", "คาทูร่า"),
+];
+
+/// Processing methods with default parameters: (method, name, Thai name,
+/// fermented in tanks, default parameters as JSON)
+const PROCESSING_METHODS: &[(&str, &str, &str, bool, &str)] = &[
+    ("natural", "Natural", "แบบแห้ง (เนเชอรัล)", false, "{}"),
+    ("washed", "Washed", "แบบเปียก (วอช)", true, "{}"),
+    ("honey", "Honey", "ฮันนี่", false, r#"{"mucilage_percent": 50}"#),
+    ("wet_hulled", "Wet Hulled", "เว็ทฮัลล์", true, "{}"),
+    ("anaerobic", "Anaerobic", "หมักแบบไร้อากาศ", true, r#"{"hours": 72}"#),
+];
+
+/// Cupping descriptors after the SCA flavor wheel: (key, category, name, Thai name)
+const DESCRIPTORS: &[(&str, &str, &str, &str)] = &[
+    ("floral", "floral", "Floral", "กลิ่นดอกไม้"),
+    ("jasmine", "floral", "Jasmine", "มะลิ"),
+    ("black_tea", "floral", "Black tea", "ชาดำ"),
+    ("berry", "fruity", "Berry", "เบอร์รี่"),
+    ("citrus", "fruity", "Citrus", "ส้ม"),
+    ("lemon", "fruity", "Lemon", "มะนาว"),
+    ("stone_fruit", "fruity", "Stone fruit", "ผลไม้เมล็ดแข็ง"),
+    ("tropical_fruit", "fruity", "Tropical fruit", "ผลไม้เขตร้อน"),
+    ("dried_fruit", "fruity", "Dried fruit", "ผลไม้แห้ง"),
+    ("winey", "sour_fermented", "Winey", "ไวน์"),
+    ("fermented", "sour_fermented", "Fermented", "หมัก"),
+    ("herbal", "green_vegetative", "Herbal", "สมุนไพร"),
+    ("grassy", "green_vegetative", "Grassy", "หญ้า"),
+    ("nutty", "nutty_cocoa", "Nutty", "ถั่ว"),
+    ("almond", "nutty_cocoa", "Almond", "อัลมอนด์"),
+    ("milk_chocolate", "nutty_cocoa", "Milk chocolate", "ช็อกโกแลตนม"),
+    ("dark_chocolate", "nutty_cocoa", "Dark chocolate", "ดาร์กช็อกโกแลต"),
+    ("brown_sugar", "sweet", "Brown sugar", "น้ำตาลทรายแดง"),
+    ("caramel", "sweet", "Caramel", "คาราเมล"),
+    ("honey", "sweet", "Honey", "น้ำผึ้ง"),
+    ("vanilla", "sweet", "Vanilla", "วานิลลา"),
+    ("cinnamon", "spices", "Cinnamon", "อบเชย"),
+    ("clove", "spices", "Clove", "กานพลู"),
+    ("smoky", "roasted", "Smoky", "ควัน"),
+    ("tobacco", "roasted", "Tobacco", "ยาสูบ"),
+    ("earthy", "other", "Earthy", "ดิน"),
+];
+
+/// Reference data service
+#[derive(Clone)]
+pub struct ReferenceDataService {
+    db: PgPool,
+}
+
+/// Coffee variety
+#[derive(Debug, Clone, Serialize)]
+pub struct VarietyEntry {
+    pub key: String,
+    pub name: String,
+    pub name_th: Option<String>,
+    /// Planted in the business's plots but not a built-in variety
+    pub custom: bool,
+}
+
+/// Coffee-growing province
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionEntry {
+    pub name: String,
+    pub name_th: String,
+}
+
+/// Processing method template
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingMethodEntry {
+    pub method: String,
+    pub name: String,
+    pub name_th: String,
+    /// Whether the cherry goes through fermentation tanks
+    pub fermented: bool,
+    pub default_parameters: serde_json::Value,
+}
+
+/// Cupping descriptor
+#[derive(Debug, Clone, Serialize)]
+pub struct DescriptorEntry {
+    pub key: String,
+    pub category: String,
+    pub name: String,
+    pub name_th: String,
+}
+
+/// Role with its permissions as `resource:action`
+#[derive(Debug, Clone, Serialize)]
+pub struct RolePermissionsEntry {
+    pub role_id: Uuid,
+    pub name: String,
+    pub name_th: Option<String>,
+    pub template_key: Option<String>,
+    pub permissions: Vec<String>,
+}
+
+/// One catalog of the bundle; items are left out when unchanged
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferenceCatalog<T> {
+    pub version: String,
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<T>>,
+}
+
+/// All reference catalogs
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferenceDataBundle {
+    /// Send back as `since` to receive only changed catalogs
+    pub version: String,
+    /// Whether any catalog changed since the client's version
+    pub changed: bool,
+    pub varieties: ReferenceCatalog<VarietyEntry>,
+    pub regions: ReferenceCatalog<RegionEntry>,
+    pub processing_methods: ReferenceCatalog<ProcessingMethodEntry>,
+    pub descriptors: ReferenceCatalog<DescriptorEntry>,
+    pub role_permissions: ReferenceCatalog<RolePermissionsEntry>,
+}
+
+/// Version of a catalog: the first 16 hex digits of the SHA-256 of its JSON
+pub fn catalog_version<T: Serialize>(items: &[T]) -> String {
+    let json = serde_json::to_vec(items).unwrap_or_default();
+    Sha256::digest(&json)
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Build a catalog, leaving out the items when the client holds `known`
+pub fn build_catalog<T: Serialize>(items: Vec<T>, known: Option<&str>) -> ReferenceCatalog<T> {
+    let version = catalog_version(&items);
+    let changed = known != Some(version.as_str());
+    ReferenceCatalog {
+        items: changed.then_some(items),
+        version,
+        changed,
+    }
+}
+
+/// Catalog versions from a bundle version. A version with the wrong number
+/// of parts (e.g. from before a catalog was added) matches nothing.
+pub fn split_bundle_version(since: Option<&str>, catalogs: usize) -> Vec<Option<&str>> {
+    let parts: Vec<&str> = since.map(|s| s.split(VERSION_SEPARATOR).collect()).unwrap_or_default();
+    if parts.len() != catalogs {
+        return vec![None; catalogs];
+    }
+    parts.into_iter().map(Some).collect()
+}
+
+fn builtin_varieties() -> Vec<VarietyEntry> {
+    VARIETIES
+        .iter()
+        .map(|(key, name, name_th)| VarietyEntry {
+            key: key.to_string(),
+            name: name.to_string(),
+            name_th: Some(name_th.to_string()),
+            custom: false,
+        })
+        .collect()
+}
+
+fn regions() -> Vec<RegionEntry> {
+    THAI_COFFEE_PROVINCES_EN
+        .iter()
+        .zip(THAI_COFFEE_PROVINCES)
+        .map(|(name, name_th)| RegionEntry {
+            name: name.to_string(),
+            name_th: name_th.to_string(),
+        })
+        .collect()
+}
+
+fn processing_methods() -> Vec<ProcessingMethodEntry> {
+    PROCESSING_METHODS
+        .iter()
+        .map(|(method, name, name_th, fermented, parameters)| ProcessingMethodEntry {
+            method: method.to_string(),
+            name: name.to_string(),
+            name_th: name_th.to_string(),
+            fermented: *fermented,
+            default_parameters: serde_json::from_str(parameters).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn descriptors() -> Vec<DescriptorEntry> {
+    DESCRIPTORS
+        .iter()
+        .map(|(key, category, name, name_th)| DescriptorEntry {
+            key: key.to_string(),
+            category: category.to_string(),
+            name: name.to_string(),
+            name_th: name_th.to_string(),
+        })
+        .collect()
+}
+
+impl ReferenceDataService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Reference catalogs, with items only for catalogs changed since the
+    /// bundle version `since`
+    pub async fn get_bundle(&self, business_id: Uuid, since: Option<&str>) -> AppResult<ReferenceDataBundle> {
+        let known = split_bundle_version(since, 5);
+
+        let mut varieties = builtin_varieties();
+        let custom = sqlx::query_as::<_, (String, Option<String>)>(
+            r#"
+            SELECT DISTINCT ON (LOWER(pv.variety)) pv.variety, pv.variety_th
+            FROM plot_varieties pv
+            JOIN plots p ON p.id = pv.plot_id
+            WHERE p.business_id = $1
+            ORDER BY LOWER(pv.variety), pv.variety_th NULLS LAST
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        for (name, name_th) in custom {
+            if varieties.iter().any(|v| v.name.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            varieties.push(VarietyEntry {
+                key: name.to_lowercase(),
+                name,
+                name_th,
+                custom: true,
+            });
+        }
+
+        let rows = sqlx::query_as::<_, (Uuid, String, Option<String>, Option<String>, Option<String>)>(
+            r#"
+            SELECT r.id, r.name, r.name_th, r.template_key, p.resource || ':' || p.action
+            FROM roles r
+            LEFT JOIN role_permissions rp ON rp.role_id = r.id
+            LEFT JOIN permissions p ON p.id = rp.permission_id
+            WHERE r.business_id = $1
+            ORDER BY r.is_system_role DESC, r.name, p.resource, p.action
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        let mut role_permissions: Vec<RolePermissionsEntry> = Vec::new();
+        for (role_id, name, name_th, template_key, permission) in rows {
+            if role_permissions.last().map(|r| r.role_id) != Some(role_id) {
+                role_permissions.push(RolePermissionsEntry {
+                    role_id,
+                    name,
+                    name_th,
+                    template_key,
+                    permissions: Vec::new(),
+                });
+            }
+            if let (Some(role), Some(permission)) = (role_permissions.last_mut(), permission) {
+                role.permissions.push(permission);
+            }
+        }
+
+        let varieties = build_catalog(varieties, known[0]);
+        let regions = build_catalog(regions(), known[1]);
+        let processing_methods = build_catalog(processing_methods(), known[2]);
+        let descriptors = build_catalog(descriptors(), known[3]);
+        let role_permissions = build_catalog(role_permissions, known[4]);
+
+        let version = [
+            &varieties.version,
+            &regions.version,
+            &processing_methods.version,
+            &descriptors.version,
+            &role_permissions.version,
+        ]
+        .map(String::as_str)
+        .join(&VERSION_SEPARATOR.to_string());
+
+        Ok(ReferenceDataBundle {
+            changed: since != Some(version.as_str()),
+            version,
+            varieties,
+            regions,
+            processing_methods,
+            descriptors,
+            role_permissions,
+        })
+    }
+}
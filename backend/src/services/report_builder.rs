@@ -0,0 +1,979 @@
+//! Configurable report builder
+//!
+//! Users pick an entity, columns, filters, grouping and sorting; the service
+//! validates every identifier against a fixed catalogue and binds every value
+//! as a query parameter, so no user input is ever spliced into SQL. Results
+//! can be returned as JSON, CSV or XLSX and configurations saved for reuse.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::{format_date, CalendarSystem};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::xlsx::{XlsxCell, XlsxSheet, XlsxWorkbook};
+use crate::services::RoleService;
+
+/// Default number of rows returned by a report
+pub const DEFAULT_REPORT_ROWS: i64 = 10_000;
+
+/// Hard cap on rows returned by a report
+pub const MAX_REPORT_ROWS: i64 = 100_000;
+
+/// Data type of a report column, used for filter validation and XLSX cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Text,
+    Number,
+    Integer,
+    Date,
+    Timestamp,
+    Uuid,
+}
+
+impl ColumnType {
+    /// Postgres type a bound filter value is cast to
+    fn sql_cast(&self) -> &'static str {
+        match self {
+            ColumnType::Text => "text",
+            ColumnType::Number => "numeric",
+            ColumnType::Integer => "bigint",
+            ColumnType::Date => "date",
+            ColumnType::Timestamp => "timestamptz",
+            ColumnType::Uuid => "uuid",
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, ColumnType::Number | ColumnType::Integer)
+    }
+}
+
+/// Column available in the report builder
+#[derive(Debug, Serialize)]
+pub struct ReportColumn {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub label_th: &'static str,
+    pub column_type: ColumnType,
+    #[serde(skip)]
+    expr: &'static str,
+}
+
+/// Entity (data source) available in the report builder
+#[derive(Debug, Serialize)]
+pub struct ReportEntity {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub label_th: &'static str,
+    pub columns: &'static [ReportColumn],
+    #[serde(skip)]
+    from: &'static str,
+    #[serde(skip)]
+    business_column: &'static str,
+}
+
+const fn col(
+    key: &'static str,
+    label: &'static str,
+    label_th: &'static str,
+    column_type: ColumnType,
+    expr: &'static str,
+) -> ReportColumn {
+    ReportColumn { key, label, label_th, column_type, expr }
+}
+
+use ColumnType::{Date, Integer, Number, Text, Timestamp, Uuid as UuidType};
+
+/// Entities and columns users may report on
+pub static REPORT_ENTITIES: &[ReportEntity] = &[
+    ReportEntity {
+        key: "harvests",
+        label: "Harvests",
+        label_th: "การเก็บเกี่ยว",
+        from: "harvests h JOIN plots p ON p.id = h.plot_id JOIN lots l ON l.id = h.lot_id",
+        business_column: "h.business_id",
+        columns: &[
+            col("harvest_date", "Harvest Date", "วันที่เก็บเกี่ยว", Date, "h.harvest_date"),
+            col("plot_name", "Plot", "แปลง", Text, "p.name"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("picker_name", "Picker", "ผู้เก็บ", Text, "h.picker_name"),
+            col("cherry_weight_kg", "Cherry Weight (kg)", "น้ำหนักเชอร์รี่ (กก.)", Number, "h.cherry_weight_kg"),
+            col("ripe_percent", "Ripe %", "สุก %", Integer, "h.ripe_percent"),
+            col("underripe_percent", "Underripe %", "ดิบ %", Integer, "h.underripe_percent"),
+            col("overripe_percent", "Overripe %", "สุกเกิน %", Integer, "h.overripe_percent"),
+            col("notes", "Notes", "หมายเหตุ", Text, "h.notes"),
+        ],
+    },
+    ReportEntity {
+        key: "lots",
+        label: "Lots",
+        label_th: "ล็อต",
+        from: "lots l",
+        business_column: "l.business_id",
+        columns: &[
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("stage", "Stage", "ขั้นตอน", Text, "l.stage"),
+            col("current_weight_kg", "Current Weight (kg)", "น้ำหนักปัจจุบัน (กก.)", Number, "l.current_weight_kg"),
+            col("created_at", "Created", "สร้างเมื่อ", Timestamp, "l.created_at"),
+        ],
+    },
+    ReportEntity {
+        key: "processing",
+        label: "Processing",
+        label_th: "การแปรรูป",
+        from: "processing_records pr JOIN lots l ON l.id = pr.lot_id",
+        business_column: "l.business_id",
+        columns: &[
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("method", "Method", "วิธีการแปรรูป", Text, "pr.method"),
+            col("start_date", "Start Date", "วันที่เริ่ม", Date, "pr.start_date"),
+            col("end_date", "End Date", "วันที่สิ้นสุด", Date, "pr.end_date"),
+            col("responsible_person", "Responsible", "ผู้รับผิดชอบ", Text, "pr.responsible_person"),
+            col("cherry_weight_kg", "Cherry Weight (kg)", "น้ำหนักเชอร์รี่ (กก.)", Number, "pr.cherry_weight_kg"),
+            col("green_bean_weight_kg", "Green Bean Weight (kg)", "น้ำหนักสารกาแฟ (กก.)", Number, "pr.green_bean_weight_kg"),
+            col("processing_yield_percent", "Yield %", "ผลผลิต %", Number, "pr.processing_yield_percent"),
+            col("final_moisture_percent", "Final Moisture %", "ความชื้นสุดท้าย %", Number, "pr.final_moisture_percent"),
+        ],
+    },
+    ReportEntity {
+        key: "gradings",
+        label: "Green Bean Gradings",
+        label_th: "การคัดเกรดสารกาแฟ",
+        from: "green_bean_grades g JOIN lots l ON l.id = g.lot_id",
+        business_column: "l.business_id",
+        columns: &[
+            col("grading_date", "Grading Date", "วันที่คัดเกรด", Date, "g.grading_date"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("grader_name", "Grader", "ผู้คัดเกรด", Text, "g.grader_name"),
+            col("grade", "Grade", "เกรด", Text, "g.grade"),
+            col("category1_count", "Category 1 Defects", "ข้อบกพร่องประเภท 1", Integer, "g.category1_count"),
+            col("category2_count", "Category 2 Defects", "ข้อบกพร่องประเภท 2", Integer, "g.category2_count"),
+            col("moisture_percent", "Moisture %", "ความชื้น %", Number, "g.moisture_percent"),
+            col("density", "Density", "ความหนาแน่น", Number, "g.density"),
+        ],
+    },
+    ReportEntity {
+        key: "cupping",
+        label: "Cupping Results",
+        label_th: "ผลการคัปปิ้ง",
+        from: "cupping_samples cs JOIN cupping_sessions s ON s.id = cs.session_id JOIN lots l ON l.id = cs.lot_id",
+        business_column: "s.business_id",
+        columns: &[
+            col("session_date", "Session Date", "วันที่คัปปิ้ง", Date, "s.session_date"),
+            col("cupper_name", "Cupper", "ผู้ชิม", Text, "s.cupper_name"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("sample_number", "Sample #", "ตัวอย่างที่", Integer, "cs.sample_number"),
+            col("fragrance_aroma", "Fragrance/Aroma", "กลิ่นหอม", Number, "cs.fragrance_aroma"),
+            col("flavor", "Flavor", "รสชาติ", Number, "cs.flavor"),
+            col("acidity", "Acidity", "ความเปรี้ยว", Number, "cs.acidity"),
+            col("body", "Body", "บอดี้", Number, "cs.body"),
+            col("total_score", "Total Score", "คะแนนรวม", Number, "cs.total_score"),
+            col("final_score", "Final Score", "คะแนนสุดท้าย", Number, "cs.final_score"),
+            col("tasting_notes", "Tasting Notes", "บันทึกการชิม", Text, "cs.tasting_notes"),
+        ],
+    },
+    ReportEntity {
+        key: "inventory",
+        label: "Inventory Transactions",
+        label_th: "รายการสินค้าคงคลัง",
+        from: "inventory_transactions t JOIN lots l ON l.id = t.lot_id",
+        business_column: "t.business_id",
+        columns: &[
+            col("transaction_date", "Date", "วันที่", Date, "t.transaction_date"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("traceability_code", "Traceability Code", "รหัสตรวจสอบย้อนกลับ", Text, "l.traceability_code"),
+            col("transaction_type", "Type", "ประเภท", Text, "t.transaction_type::TEXT"),
+            col("direction", "Direction", "ทิศทาง", Text, "t.direction"),
+            col("stage", "Stage", "ขั้นตอน", Text, "t.stage"),
+            col("quantity_kg", "Quantity (kg)", "ปริมาณ (กก.)", Number, "t.quantity_kg"),
+            col("unit_price", "Unit Price", "ราคาต่อหน่วย", Number, "t.unit_price"),
+            col("total_price", "Total Price", "ราคารวม", Number, "t.total_price"),
+            col("currency", "Currency", "สกุลเงิน", Text, "t.currency"),
+            col("counterparty_name", "Counterparty", "คู่ค้า", Text, "t.counterparty_name"),
+            col("counterparty_contact", "Counterparty Contact", "ติดต่อคู่ค้า", Text, "t.counterparty_contact"),
+        ],
+    },
+    ReportEntity {
+        key: "roasts",
+        label: "Roast Sessions",
+        label_th: "การคั่ว",
+        from: "roast_sessions r JOIN lots l ON l.id = r.lot_id",
+        business_column: "r.business_id",
+        columns: &[
+            col("session_date", "Roast Date", "วันที่คั่ว", Date, "r.session_date"),
+            col("lot_name", "Lot", "ล็อต", Text, "l.name"),
+            col("lot_id", "Lot ID", "รหัสล็อต", UuidType, "l.id"),
+            col("roaster_name", "Roaster", "ผู้คั่ว", Text, "r.roaster_name"),
+            col("equipment", "Equipment", "เครื่องคั่ว", Text, "r.equipment"),
+            col("green_bean_weight_kg", "Green Weight (kg)", "น้ำหนักสารกาแฟ (กก.)", Number, "r.green_bean_weight_kg"),
+            col("roasted_weight_kg", "Roasted Weight (kg)", "น้ำหนักหลังคั่ว (กก.)", Number, "r.roasted_weight_kg"),
+            col("weight_loss_percent", "Weight Loss %", "น้ำหนักที่หายไป %", Number, "r.weight_loss_percent"),
+            col("development_time_ratio", "DTR %", "DTR %", Number, "r.development_time_ratio"),
+            col("roast_level", "Roast Level", "ระดับการคั่ว", Text, "r.roast_level"),
+            col("status", "Status", "สถานะ", Text, "r.status"),
+        ],
+    },
+];
+
+/// Report builder configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    pub entity: String,
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub filters: Vec<ReportFilterClause>,
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    pub aggregates: Vec<ReportAggregate>,
+    #[serde(default)]
+    pub sort: Vec<ReportSort>,
+    pub limit: Option<i64>,
+}
+
+/// Filter condition on a column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportFilterClause {
+    pub column: String,
+    pub op: FilterOp,
+    #[serde(default)]
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains,
+    In,
+    IsNull,
+    NotNull,
+}
+
+/// Aggregated output column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportAggregate {
+    pub function: AggregateFunction,
+    pub column: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+        }
+    }
+}
+
+/// Sort on an output column key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSort {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+    Xlsx,
+}
+
+/// Column of a report result
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportOutputColumn {
+    pub key: String,
+    pub label: String,
+    pub label_th: String,
+    pub column_type: ColumnType,
+}
+
+/// Executed report: columns and rows of text values
+#[derive(Debug, Serialize)]
+pub struct ReportResult {
+    pub entity: String,
+    pub columns: Vec<ReportOutputColumn>,
+    pub rows: Vec<Vec<Option<String>>>,
+    /// True when the row limit cut the result short
+    pub truncated: bool,
+}
+
+/// Saved report configuration
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SavedReport {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: sqlx::types::Json<ReportDefinition>,
+    pub default_format: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for saving a report configuration
+#[derive(Debug, Deserialize)]
+pub struct SaveReportInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: ReportDefinition,
+    pub default_format: Option<ReportFormat>,
+}
+
+/// Validated output column with its SQL expression
+#[derive(Debug)]
+struct PlannedColumn {
+    output: ReportOutputColumn,
+    select_expr: String,
+}
+
+/// Validated report ready to execute
+#[derive(Debug)]
+pub struct ReportPlan {
+    entity: &'static ReportEntity,
+    columns: Vec<PlannedColumn>,
+    group_exprs: Vec<&'static str>,
+    filters: Vec<(&'static ReportColumn, FilterOp, Vec<String>)>,
+    sort: Vec<(usize, bool)>,
+    limit: i64,
+}
+
+fn invalid(field: &str, message: String, message_th: String) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message,
+        message_th,
+    }
+}
+
+/// Look up a report entity by key
+pub fn find_entity(key: &str) -> Option<&'static ReportEntity> {
+    REPORT_ENTITIES.iter().find(|e| e.key == key)
+}
+
+fn find_column(
+    entity: &'static ReportEntity,
+    key: &str,
+    hidden: &HashSet<String>,
+) -> Option<&'static ReportColumn> {
+    if hidden.contains(key) {
+        return None;
+    }
+    entity.columns.iter().find(|c| c.key == key)
+}
+
+fn unknown_column(field: &str, key: &str) -> AppError {
+    invalid(
+        field,
+        format!("Unknown column '{}'", key),
+        format!("ไม่พบคอลัมน์ '{}'", key),
+    )
+}
+
+/// Validate a filter value against the column type, returning its text form
+pub fn normalize_filter_value(column_type: ColumnType, value: &serde_json::Value) -> Option<String> {
+    let text = match value {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+
+    let valid = match column_type {
+        ColumnType::Text => true,
+        ColumnType::Number => Decimal::from_str(&text).is_ok(),
+        ColumnType::Integer => text.parse::<i64>().is_ok(),
+        ColumnType::Date => NaiveDate::parse_from_str(&text, "%Y-%m-%d").is_ok(),
+        ColumnType::Timestamp => {
+            DateTime::parse_from_rfc3339(&text).is_ok()
+                || NaiveDate::parse_from_str(&text, "%Y-%m-%d").is_ok()
+        }
+        ColumnType::Uuid => Uuid::parse_str(&text).is_ok(),
+    };
+
+    valid.then_some(text)
+}
+
+/// Escape LIKE wildcards so `contains` matches the value literally
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Validate a report definition against the catalogue
+///
+/// Columns in `hidden` (field visibility policies of the caller's role) are
+/// treated as unknown, so hidden data can't be exported or filtered on.
+pub fn plan_report(definition: &ReportDefinition, hidden: &HashSet<String>) -> AppResult<ReportPlan> {
+    let entity = find_entity(&definition.entity).ok_or_else(|| {
+        invalid(
+            "entity",
+            format!("Unknown report entity '{}'", definition.entity),
+            format!("ไม่พบประเภทรายงาน '{}'", definition.entity),
+        )
+    })?;
+
+    let grouped = !definition.group_by.is_empty() || !definition.aggregates.is_empty();
+    let mut columns = Vec::new();
+    let mut group_exprs = Vec::new();
+
+    if grouped {
+        if let Some(extra) = definition.columns.iter().find(|c| !definition.group_by.contains(c)) {
+            return Err(invalid(
+                "columns",
+                format!("Column '{}' must be in group_by when grouping", extra),
+                format!("คอลัมน์ '{}' ต้องอยู่ใน group_by เมื่อจัดกลุ่ม", extra),
+            ));
+        }
+
+        for key in &definition.group_by {
+            let column = find_column(entity, key, hidden).ok_or_else(|| unknown_column("group_by", key))?;
+            group_exprs.push(column.expr);
+            columns.push(PlannedColumn {
+                output: output_column(column.key.to_string(), column.label, column.label_th, column.column_type),
+                select_expr: column.expr.to_string(),
+            });
+        }
+
+        for aggregate in &definition.aggregates {
+            let function = aggregate.function;
+            let (expr, column_type, label, label_th) = if aggregate.column == "*" {
+                if function != AggregateFunction::Count {
+                    return Err(invalid(
+                        "aggregates",
+                        "Only count can be applied to '*'".to_string(),
+                        "ใช้ '*' ได้กับ count เท่านั้น".to_string(),
+                    ));
+                }
+                ("*", ColumnType::Integer, "Rows", "จำนวนรายการ")
+            } else {
+                let column = find_column(entity, &aggregate.column, hidden)
+                    .ok_or_else(|| unknown_column("aggregates", &aggregate.column))?;
+                if matches!(function, AggregateFunction::Sum | AggregateFunction::Avg)
+                    && !column.column_type.is_numeric()
+                {
+                    return Err(invalid(
+                        "aggregates",
+                        format!("Cannot {} non-numeric column '{}'", function.as_str(), column.key),
+                        format!("ไม่สามารถใช้ {} กับคอลัมน์ที่ไม่ใช่ตัวเลข '{}'", function.as_str(), column.key),
+                    ));
+                }
+                let column_type = match function {
+                    AggregateFunction::Count => ColumnType::Integer,
+                    AggregateFunction::Avg => ColumnType::Number,
+                    _ => column.column_type,
+                };
+                (column.expr, column_type, column.label, column.label_th)
+            };
+
+            let key = format!("{}_{}", function.as_str(), aggregate.column.replace('*', "rows"));
+            let select_expr = match function {
+                AggregateFunction::Avg => format!("ROUND(AVG({}), 2)", expr),
+                _ => format!("{}({})", function.as_str().to_uppercase(), expr),
+            };
+            columns.push(PlannedColumn {
+                output: output_column(
+                    key,
+                    &format!("{} ({})", label, function.as_str()),
+                    &format!("{} ({})", label_th, function.as_str()),
+                    column_type,
+                ),
+                select_expr,
+            });
+        }
+    } else {
+        if definition.columns.is_empty() {
+            return Err(invalid(
+                "columns",
+                "Select at least one column".to_string(),
+                "กรุณาเลือกอย่างน้อยหนึ่งคอลัมน์".to_string(),
+            ));
+        }
+        for key in &definition.columns {
+            let column = find_column(entity, key, hidden).ok_or_else(|| unknown_column("columns", key))?;
+            columns.push(PlannedColumn {
+                output: output_column(column.key.to_string(), column.label, column.label_th, column.column_type),
+                select_expr: column.expr.to_string(),
+            });
+        }
+    }
+
+    let mut filters = Vec::new();
+    for filter in &definition.filters {
+        let column = find_column(entity, &filter.column, hidden)
+            .ok_or_else(|| unknown_column("filters", &filter.column))?;
+        let values = match filter.op {
+            FilterOp::IsNull | FilterOp::NotNull => Vec::new(),
+            FilterOp::In => {
+                let items = filter.value.as_array().filter(|items| !items.is_empty());
+                items
+                    .map(|items| {
+                        items
+                            .iter()
+                            .map(|v| normalize_filter_value(column.column_type, v))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .unwrap_or(None)
+                    .ok_or_else(|| invalid_filter_value(column))?
+            }
+            FilterOp::Contains if column.column_type != ColumnType::Text => {
+                return Err(invalid(
+                    "filters",
+                    format!("'contains' only applies to text columns, not '{}'", column.key),
+                    format!("'contains' ใช้ได้กับคอลัมน์ข้อความเท่านั้น ไม่ใช่ '{}'", column.key),
+                ));
+            }
+            _ => vec![normalize_filter_value(column.column_type, &filter.value)
+                .ok_or_else(|| invalid_filter_value(column))?],
+        };
+        filters.push((column, filter.op, values));
+    }
+
+    let mut sort = Vec::new();
+    for item in &definition.sort {
+        let index = columns
+            .iter()
+            .position(|c| c.output.key == item.column)
+            .ok_or_else(|| {
+                invalid(
+                    "sort",
+                    format!("Sort column '{}' is not part of the report output", item.column),
+                    format!("คอลัมน์เรียงลำดับ '{}' ไม่อยู่ในผลลัพธ์รายงาน", item.column),
+                )
+            })?;
+        sort.push((index, item.descending));
+    }
+
+    let limit = definition.limit.unwrap_or(DEFAULT_REPORT_ROWS);
+    if !(1..=MAX_REPORT_ROWS).contains(&limit) {
+        return Err(invalid(
+            "limit",
+            format!("Limit must be between 1 and {}", MAX_REPORT_ROWS),
+            format!("จำนวนแถวต้องอยู่ระหว่าง 1 ถึง {}", MAX_REPORT_ROWS),
+        ));
+    }
+
+    Ok(ReportPlan {
+        entity,
+        columns,
+        group_exprs,
+        filters,
+        sort,
+        limit,
+    })
+}
+
+fn invalid_filter_value(column: &ReportColumn) -> AppError {
+    invalid(
+        "filters",
+        format!("Invalid filter value for column '{}'", column.key),
+        format!("ค่าตัวกรองของคอลัมน์ '{}' ไม่ถูกต้อง", column.key),
+    )
+}
+
+fn output_column(key: String, label: &str, label_th: &str, column_type: ColumnType) -> ReportOutputColumn {
+    ReportOutputColumn {
+        key,
+        label: label.to_string(),
+        label_th: label_th.to_string(),
+        column_type,
+    }
+}
+
+impl ReportPlan {
+    /// Build the parameterized query; identifiers come only from the catalogue
+    fn query(&self, business_id: Uuid) -> QueryBuilder<'_, Postgres> {
+        let mut qb = QueryBuilder::new("SELECT ");
+        for (index, column) in self.columns.iter().enumerate() {
+            if index > 0 {
+                qb.push(", ");
+            }
+            qb.push(format!("({})::TEXT AS c{}", column.select_expr, index));
+        }
+        qb.push(format!(" FROM {} WHERE {} = ", self.entity.from, self.entity.business_column));
+        qb.push_bind(business_id);
+
+        for (column, op, values) in &self.filters {
+            let cast = column.column_type.sql_cast();
+            qb.push(format!(" AND {} ", column.expr));
+            match op {
+                FilterOp::IsNull => {
+                    qb.push("IS NULL");
+                }
+                FilterOp::NotNull => {
+                    qb.push("IS NOT NULL");
+                }
+                FilterOp::Contains => {
+                    qb.push("ILIKE '%' || ");
+                    qb.push_bind(escape_like(&values[0]));
+                    qb.push(" || '%'");
+                }
+                FilterOp::In => {
+                    qb.push("= ANY(");
+                    qb.push_bind(values.clone());
+                    qb.push(format!("::{}[])", cast));
+                }
+                _ => {
+                    let operator = match op {
+                        FilterOp::Eq => "=",
+                        FilterOp::Ne => "<>",
+                        FilterOp::Gt => ">",
+                        FilterOp::Gte => ">=",
+                        FilterOp::Lt => "<",
+                        _ => "<=",
+                    };
+                    qb.push(format!("{} ", operator));
+                    qb.push_bind(values[0].clone());
+                    qb.push(format!("::{}", cast));
+                }
+            }
+        }
+
+        if !self.group_exprs.is_empty() {
+            qb.push(format!(" GROUP BY {}", self.group_exprs.join(", ")));
+        }
+
+        // Sort on the typed expression, not its text form
+        let order: Vec<String> = if self.sort.is_empty() {
+            (0..self.group_exprs.len().min(self.columns.len()))
+                .map(|i| self.columns[i].select_expr.clone())
+                .collect()
+        } else {
+            self.sort
+                .iter()
+                .map(|(index, descending)| {
+                    format!(
+                        "{} {} NULLS LAST",
+                        self.columns[*index].select_expr,
+                        if *descending { "DESC" } else { "ASC" }
+                    )
+                })
+                .collect()
+        };
+        if !order.is_empty() {
+            qb.push(format!(" ORDER BY {}", order.join(", ")));
+        }
+
+        // Fetch one extra row to detect truncation
+        qb.push(" LIMIT ");
+        qb.push_bind(self.limit + 1);
+        qb
+    }
+}
+
+/// Convert a text value to a typed spreadsheet cell
+pub fn to_xlsx_cell(column_type: ColumnType, value: Option<&str>) -> XlsxCell {
+    let Some(value) = value else {
+        return XlsxCell::Empty;
+    };
+    match column_type {
+        ColumnType::Number => Decimal::from_str(value)
+            .map(XlsxCell::Number)
+            .unwrap_or_else(|_| XlsxCell::Text(value.to_string())),
+        ColumnType::Integer => value
+            .parse::<i64>()
+            .map(XlsxCell::Integer)
+            .unwrap_or_else(|_| XlsxCell::Text(value.to_string())),
+        ColumnType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(XlsxCell::Date)
+            .unwrap_or_else(|_| XlsxCell::Text(value.to_string())),
+        _ => XlsxCell::Text(value.to_string()),
+    }
+}
+
+/// Text value for CSV output; dates are shown in the business calendar
+pub fn to_csv_value(column_type: ColumnType, value: Option<&str>, calendar: CalendarSystem) -> String {
+    let Some(value) = value else {
+        return String::new();
+    };
+    match column_type {
+        ColumnType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(|date| format_date(date, calendar))
+            .unwrap_or_else(|_| value.to_string()),
+        _ => value.to_string(),
+    }
+}
+
+impl ReportResult {
+    fn header(&self, thai: bool) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|c| if thai { c.label_th.clone() } else { c.label.clone() })
+            .collect()
+    }
+
+    /// Render as CSV with a UTF-8 BOM so spreadsheet apps detect Thai text
+    pub fn to_csv(&self, thai: bool, calendar: CalendarSystem) -> AppResult<Vec<u8>> {
+        let mut wtr = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        wtr.write_record(self.header(thai))
+            .map_err(|e| AppError::Internal(format!("CSV serialization error: {}", e)))?;
+        for row in &self.rows {
+            let record = row
+                .iter()
+                .zip(&self.columns)
+                .map(|(value, column)| to_csv_value(column.column_type, value.as_deref(), calendar));
+            wtr.write_record(record)
+                .map_err(|e| AppError::Internal(format!("CSV serialization error: {}", e)))?;
+        }
+        wtr.into_inner()
+            .map_err(|e| AppError::Internal(format!("CSV writer error: {}", e)))
+    }
+
+    /// Render as a single-sheet XLSX workbook with typed cells
+    pub fn to_xlsx(&self, sheet_name: &str, thai: bool, calendar: CalendarSystem) -> AppResult<Vec<u8>> {
+        let mut sheet = XlsxSheet::new(sheet_name, self.header(thai));
+        for row in &self.rows {
+            sheet.push_row(
+                row.iter()
+                    .zip(&self.columns)
+                    .map(|(value, column)| to_xlsx_cell(column.column_type, value.as_deref()))
+                    .collect(),
+            );
+        }
+        let mut workbook = XlsxWorkbook::new().with_calendar(calendar);
+        workbook.add_sheet(sheet);
+        workbook.to_bytes()
+    }
+}
+
+/// Report builder service
+#[derive(Clone)]
+pub struct ReportBuilderService {
+    db: PgPool,
+}
+
+impl ReportBuilderService {
+    /// Create a new ReportBuilderService instance
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Execute a report definition for a business
+    ///
+    /// Fields hidden from the role on the `reports` resource are excluded.
+    pub async fn run_report(
+        &self,
+        business_id: Uuid,
+        role_id: Uuid,
+        definition: &ReportDefinition,
+    ) -> AppResult<ReportResult> {
+        let hidden: HashSet<String> = RoleService::new(self.db.clone())
+            .get_hidden_fields(role_id, "reports")
+            .await?
+            .into_iter()
+            .collect();
+        let plan = plan_report(definition, &hidden)?;
+
+        let rows = plan.query(business_id).build().fetch_all(&self.db).await?;
+
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows.iter().take(plan.limit as usize) {
+            let mut record = Vec::with_capacity(plan.columns.len());
+            for index in 0..plan.columns.len() {
+                record.push(row.try_get::<Option<String>, _>(index)?);
+            }
+            values.push(record);
+        }
+
+        Ok(ReportResult {
+            entity: plan.entity.key.to_string(),
+            columns: plan.columns.into_iter().map(|c| c.output).collect(),
+            truncated: rows.len() as i64 > plan.limit,
+            rows: values,
+        })
+    }
+
+    /// List saved report configurations
+    pub async fn list_saved_reports(&self, business_id: Uuid) -> AppResult<Vec<SavedReport>> {
+        let reports = sqlx::query_as::<_, SavedReport>(
+            r#"
+            SELECT id, business_id, name, description, definition, default_format,
+                   created_by, created_at, updated_at
+            FROM saved_reports
+            WHERE business_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(reports)
+    }
+
+    /// Get a saved report configuration
+    pub async fn get_saved_report(&self, business_id: Uuid, report_id: Uuid) -> AppResult<SavedReport> {
+        sqlx::query_as::<_, SavedReport>(
+            r#"
+            SELECT id, business_id, name, description, definition, default_format,
+                   created_by, created_at, updated_at
+            FROM saved_reports
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(report_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Saved report".to_string()))
+    }
+
+    /// Save a report configuration after validating it
+    pub async fn save_report(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: SaveReportInput,
+    ) -> AppResult<SavedReport> {
+        self.validate_saved_report(&input)?;
+
+        sqlx::query_as::<_, SavedReport>(
+            r#"
+            INSERT INTO saved_reports (business_id, name, description, definition, default_format, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (business_id, name) DO NOTHING
+            RETURNING id, business_id, name, description, definition, default_format,
+                      created_by, created_at, updated_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(&input.description)
+        .bind(sqlx::types::Json(&input.definition))
+        .bind(format_name(input.default_format.unwrap_or_default()))
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(duplicate_report_name)
+    }
+
+    /// Replace a saved report configuration
+    pub async fn update_saved_report(
+        &self,
+        business_id: Uuid,
+        report_id: Uuid,
+        input: SaveReportInput,
+    ) -> AppResult<SavedReport> {
+        self.validate_saved_report(&input)?;
+
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM saved_reports WHERE business_id = $1 AND name = $2 AND id <> $3)",
+        )
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(report_id)
+        .fetch_one(&self.db)
+        .await?;
+        if duplicate {
+            return Err(duplicate_report_name());
+        }
+
+        sqlx::query_as::<_, SavedReport>(
+            r#"
+            UPDATE saved_reports
+            SET name = $3, description = $4, definition = $5, default_format = $6, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            RETURNING id, business_id, name, description, definition, default_format,
+                      created_by, created_at, updated_at
+            "#,
+        )
+        .bind(report_id)
+        .bind(business_id)
+        .bind(input.name.trim())
+        .bind(&input.description)
+        .bind(sqlx::types::Json(&input.definition))
+        .bind(format_name(input.default_format.unwrap_or_default()))
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Saved report".to_string()))
+    }
+
+    /// Delete a saved report configuration
+    pub async fn delete_saved_report(&self, business_id: Uuid, report_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM saved_reports WHERE id = $1 AND business_id = $2")
+            .bind(report_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Saved report".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn validate_saved_report(&self, input: &SaveReportInput) -> AppResult<()> {
+        if input.name.trim().is_empty() {
+            return Err(invalid(
+                "name",
+                "Report name is required".to_string(),
+                "กรุณาระบุชื่อรายงาน".to_string(),
+            ));
+        }
+        // Validate against the full catalogue; hidden fields are enforced at run time
+        plan_report(&input.definition, &HashSet::new()).map(|_| ())
+    }
+}
+
+fn duplicate_report_name() -> AppError {
+    AppError::Conflict {
+        resource: "saved_report".to_string(),
+        message: "A saved report with this name already exists".to_string(),
+        message_th: "มีรายงานที่บันทึกไว้ชื่อนี้อยู่แล้ว".to_string(),
+    }
+}
+
+/// Stored name of a report format
+pub fn format_name(format: ReportFormat) -> &'static str {
+    match format {
+        ReportFormat::Json => "json",
+        ReportFormat::Csv => "csv",
+        ReportFormat::Xlsx => "xlsx",
+    }
+}
@@ -0,0 +1,835 @@
+//! Scheduled report delivery
+//!
+//! Saved reports can be delivered on a daily, weekly or monthly schedule as
+//! an email attachment or as a LINE message linking to a time-limited
+//! download. Due schedules are picked up by the background job runner.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::Language;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::external::email::EmailAttachment;
+use crate::external::SmtpMailer;
+use crate::services::notification::{LineMessage, LineMessagingClient};
+use crate::services::report_builder::{format_name, ReportFormat};
+use crate::services::{BusinessService, ReportBuilderService};
+
+/// Days a LINE download link stays valid
+pub const DOWNLOAD_LINK_DAYS: i64 = 7;
+
+/// Maximum schedules delivered per job run
+const SCHEDULE_BATCH_SIZE: i64 = 20;
+
+/// How often a schedule runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl ScheduleFrequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Daily => "daily",
+            ScheduleFrequency::Weekly => "weekly",
+            ScheduleFrequency::Monthly => "monthly",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(ScheduleFrequency::Daily),
+            "weekly" => Some(ScheduleFrequency::Weekly),
+            "monthly" => Some(ScheduleFrequency::Monthly),
+            _ => None,
+        }
+    }
+}
+
+/// How a scheduled report is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryChannel {
+    Email,
+    Line,
+}
+
+impl DeliveryChannel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryChannel::Email => "email",
+            DeliveryChannel::Line => "line",
+        }
+    }
+}
+
+/// Report delivery schedule
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReportSchedule {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub saved_report_id: Uuid,
+    pub report_name: String,
+    pub frequency: String,
+    pub day_of_week: Option<i16>,
+    pub day_of_month: Option<i16>,
+    pub time_of_day: NaiveTime,
+    pub utc_offset_minutes: i32,
+    pub format: String,
+    pub channel: String,
+    pub recipient_user_ids: Vec<Uuid>,
+    pub recipient_emails: Vec<String>,
+    pub language: String,
+    pub is_active: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_status: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for creating or replacing a schedule
+#[derive(Debug, Deserialize)]
+pub struct ReportScheduleInput {
+    pub saved_report_id: Uuid,
+    pub frequency: ScheduleFrequency,
+    /// ISO weekday, 1 = Monday (weekly only)
+    pub day_of_week: Option<i16>,
+    /// 1-28 (monthly only)
+    pub day_of_month: Option<i16>,
+    pub time_of_day: NaiveTime,
+    /// Defaults to Thailand (UTC+7)
+    pub utc_offset_minutes: Option<i32>,
+    pub format: Option<ReportFormat>,
+    pub channel: DeliveryChannel,
+    #[serde(default)]
+    pub recipient_user_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub recipient_emails: Vec<String>,
+    pub language: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// Record of one scheduled delivery
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReportDelivery {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub status: String,
+    pub row_count: Option<i32>,
+    pub recipients_sent: i32,
+    pub recipients_failed: i32,
+    pub error_message: Option<String>,
+    pub file_name: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Generated report file served from a LINE download link
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReportDownload {
+    pub file_name: String,
+    pub content_type: String,
+    pub file_data: Vec<u8>,
+}
+
+/// Rendered report ready to send
+struct ReportFile {
+    row_count: i32,
+    /// Report date and row count formatted for the schedule's language
+    report_date: String,
+    row_count_text: String,
+    attachment: EmailAttachment,
+}
+
+/// Default offset for schedules: Thailand (UTC+7, no daylight saving)
+pub const DEFAULT_UTC_OFFSET_MINUTES: i32 = 7 * 60;
+
+/// Next time a schedule is due strictly after `after`
+///
+/// `time_of_day`, `day_of_week` and `day_of_month` are interpreted in local
+/// time at `utc_offset_minutes`.
+pub fn next_run_after(
+    frequency: ScheduleFrequency,
+    day_of_week: Option<i16>,
+    day_of_month: Option<i16>,
+    time_of_day: NaiveTime,
+    utc_offset_minutes: i32,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let offset = Duration::minutes(utc_offset_minutes as i64);
+    let local_today = (after + offset).date_naive();
+
+    (0..=62)
+        .map(|days| local_today + Duration::days(days))
+        .filter(|date| match frequency {
+            ScheduleFrequency::Daily => true,
+            ScheduleFrequency::Weekly => {
+                day_of_week == Some(date.weekday().number_from_monday() as i16)
+            }
+            ScheduleFrequency::Monthly => day_of_month == Some(date.day() as i16),
+        })
+        .map(|date| (date.and_time(time_of_day) - offset).and_utc())
+        .find(|candidate| *candidate > after)
+}
+
+/// Validation problem with a schedule input, if any
+fn schedule_input_error(input: &ReportScheduleInput) -> Option<AppError> {
+    let invalid = |field: &str, message: &str, message_th: &str| {
+        Some(AppError::Validation {
+            field: field.to_string(),
+            message: message.to_string(),
+            message_th: message_th.to_string(),
+        })
+    };
+
+    match input.frequency {
+        ScheduleFrequency::Weekly if !matches!(input.day_of_week, Some(1..=7)) => {
+            return invalid(
+                "day_of_week",
+                "Weekly schedules need day_of_week between 1 (Monday) and 7 (Sunday)",
+                "กำหนดการรายสัปดาห์ต้องระบุวัน 1 (จันทร์) ถึง 7 (อาทิตย์)",
+            );
+        }
+        ScheduleFrequency::Monthly if !matches!(input.day_of_month, Some(1..=28)) => {
+            return invalid(
+                "day_of_month",
+                "Monthly schedules need day_of_month between 1 and 28",
+                "กำหนดการรายเดือนต้องระบุวันที่ 1 ถึง 28",
+            );
+        }
+        _ => {}
+    }
+
+    if !(-12 * 60..=14 * 60).contains(&input.utc_offset_minutes.unwrap_or(DEFAULT_UTC_OFFSET_MINUTES)) {
+        return invalid(
+            "utc_offset_minutes",
+            "UTC offset must be between -720 and 840 minutes",
+            "ค่าเขตเวลาต้องอยู่ระหว่าง -720 ถึง 840 นาที",
+        );
+    }
+
+    if input.format == Some(ReportFormat::Json) {
+        return invalid(
+            "format",
+            "Scheduled reports are delivered as csv or xlsx",
+            "รายงานตามกำหนดการส่งได้เฉพาะ csv หรือ xlsx",
+        );
+    }
+
+    if !matches!(input.language.as_deref(), None | Some("th") | Some("en")) {
+        return invalid("language", "Language must be th or en", "ภาษาต้องเป็น th หรือ en");
+    }
+
+    match input.channel {
+        DeliveryChannel::Line if !input.recipient_emails.is_empty() => {
+            return invalid(
+                "recipient_emails",
+                "Email recipients can only be used with the email channel",
+                "ผู้รับทางอีเมลใช้ได้กับช่องทางอีเมลเท่านั้น",
+            );
+        }
+        _ if input.recipient_user_ids.is_empty() && input.recipient_emails.is_empty() => {
+            return invalid(
+                "recipient_user_ids",
+                "At least one recipient is required",
+                "กรุณาระบุผู้รับอย่างน้อยหนึ่งคน",
+            );
+        }
+        _ => {}
+    }
+
+    if input
+        .recipient_emails
+        .iter()
+        .any(|email| email.parse::<lettre::Address>().is_err())
+    {
+        return invalid(
+            "recipient_emails",
+            "Invalid email address",
+            "รูปแบบอีเมลไม่ถูกต้อง",
+        );
+    }
+
+    None
+}
+
+/// Report schedule service
+#[derive(Clone)]
+pub struct ReportScheduleService {
+    db: PgPool,
+    mailer: Option<SmtpMailer>,
+    line_client: Option<LineMessagingClient>,
+    public_url: String,
+}
+
+impl ReportScheduleService {
+    /// Create a new ReportScheduleService instance
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            mailer: SmtpMailer::from_config(&config.email),
+            line_client: LineMessagingClient::from_env(),
+            public_url: config.server.public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// List schedules for a business
+    pub async fn list_schedules(&self, business_id: Uuid) -> AppResult<Vec<ReportSchedule>> {
+        let schedules = sqlx::query_as::<_, ReportSchedule>(
+            r#"
+            SELECT rs.id, rs.business_id, rs.saved_report_id, sr.name AS report_name,
+                   rs.frequency, rs.day_of_week, rs.day_of_month, rs.time_of_day,
+                   rs.utc_offset_minutes, rs.format, rs.channel, rs.recipient_user_ids,
+                   rs.recipient_emails, rs.language, rs.is_active, rs.next_run_at,
+                   rs.last_run_at, rs.last_status, rs.created_by, rs.created_at, rs.updated_at
+            FROM report_schedules rs
+            JOIN saved_reports sr ON sr.id = rs.saved_report_id
+            WHERE rs.business_id = $1
+            ORDER BY sr.name, rs.next_run_at
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    /// Get a schedule
+    pub async fn get_schedule(&self, business_id: Uuid, schedule_id: Uuid) -> AppResult<ReportSchedule> {
+        sqlx::query_as::<_, ReportSchedule>(
+            r#"
+            SELECT rs.id, rs.business_id, rs.saved_report_id, sr.name AS report_name,
+                   rs.frequency, rs.day_of_week, rs.day_of_month, rs.time_of_day,
+                   rs.utc_offset_minutes, rs.format, rs.channel, rs.recipient_user_ids,
+                   rs.recipient_emails, rs.language, rs.is_active, rs.next_run_at,
+                   rs.last_run_at, rs.last_status, rs.created_by, rs.created_at, rs.updated_at
+            FROM report_schedules rs
+            JOIN saved_reports sr ON sr.id = rs.saved_report_id
+            WHERE rs.id = $1 AND rs.business_id = $2
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Report schedule".to_string()))
+    }
+
+    /// Create a schedule for a saved report
+    pub async fn create_schedule(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: ReportScheduleInput,
+    ) -> AppResult<ReportSchedule> {
+        let next_run_at = self.validate_input(business_id, &input).await?;
+
+        let schedule_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO report_schedules (
+                business_id, saved_report_id, frequency, day_of_week, day_of_month,
+                time_of_day, utc_offset_minutes, format, channel, recipient_user_ids,
+                recipient_emails, language, is_active, next_run_at, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.saved_report_id)
+        .bind(input.frequency.as_str())
+        .bind(schedule_day_of_week(&input))
+        .bind(schedule_day_of_month(&input))
+        .bind(input.time_of_day)
+        .bind(input.utc_offset_minutes.unwrap_or(DEFAULT_UTC_OFFSET_MINUTES))
+        .bind(format_name(input.format.unwrap_or(ReportFormat::Xlsx)))
+        .bind(input.channel.as_str())
+        .bind(&input.recipient_user_ids)
+        .bind(&input.recipient_emails)
+        .bind(input.language.as_deref().unwrap_or("th"))
+        .bind(input.is_active.unwrap_or(true))
+        .bind(next_run_at)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get_schedule(business_id, schedule_id).await
+    }
+
+    /// Replace a schedule; the next run is recomputed from now
+    pub async fn update_schedule(
+        &self,
+        business_id: Uuid,
+        schedule_id: Uuid,
+        input: ReportScheduleInput,
+    ) -> AppResult<ReportSchedule> {
+        let next_run_at = self.validate_input(business_id, &input).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE report_schedules
+            SET saved_report_id = $3, frequency = $4, day_of_week = $5, day_of_month = $6,
+                time_of_day = $7, utc_offset_minutes = $8, format = $9, channel = $10,
+                recipient_user_ids = $11, recipient_emails = $12, language = $13,
+                is_active = $14, next_run_at = $15, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(business_id)
+        .bind(input.saved_report_id)
+        .bind(input.frequency.as_str())
+        .bind(schedule_day_of_week(&input))
+        .bind(schedule_day_of_month(&input))
+        .bind(input.time_of_day)
+        .bind(input.utc_offset_minutes.unwrap_or(DEFAULT_UTC_OFFSET_MINUTES))
+        .bind(format_name(input.format.unwrap_or(ReportFormat::Xlsx)))
+        .bind(input.channel.as_str())
+        .bind(&input.recipient_user_ids)
+        .bind(&input.recipient_emails)
+        .bind(input.language.as_deref().unwrap_or("th"))
+        .bind(input.is_active.unwrap_or(true))
+        .bind(next_run_at)
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Report schedule".to_string()));
+        }
+
+        self.get_schedule(business_id, schedule_id).await
+    }
+
+    /// Delete a schedule and its delivery history
+    pub async fn delete_schedule(&self, business_id: Uuid, schedule_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM report_schedules WHERE id = $1 AND business_id = $2")
+            .bind(schedule_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Report schedule".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Delivery history for a schedule, newest first
+    pub async fn list_deliveries(
+        &self,
+        business_id: Uuid,
+        schedule_id: Uuid,
+    ) -> AppResult<Vec<ReportDelivery>> {
+        let deliveries = sqlx::query_as::<_, ReportDelivery>(
+            r#"
+            SELECT id, schedule_id, status, row_count, recipients_sent, recipients_failed,
+                   error_message, file_name, expires_at, created_at
+            FROM report_deliveries
+            WHERE schedule_id = $1 AND business_id = $2
+            ORDER BY created_at DESC
+            LIMIT 50
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Fetch a generated report by its download token
+    pub async fn get_download(&self, token: &str) -> AppResult<ReportDownload> {
+        sqlx::query_as::<_, ReportDownload>(
+            r#"
+            SELECT file_name, content_type, file_data
+            FROM report_deliveries
+            WHERE download_token = $1 AND expires_at > NOW() AND file_data IS NOT NULL
+            "#,
+        )
+        .bind(token)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Report download".to_string()))
+    }
+
+    /// Deliver every due schedule and advance its next run
+    ///
+    /// Called by the background job runner. Returns the number of schedules run.
+    pub async fn run_due_schedules(&self) -> AppResult<usize> {
+        // Expired download files are no longer reachable; free the storage
+        sqlx::query(
+            "UPDATE report_deliveries SET file_data = NULL WHERE file_data IS NOT NULL AND expires_at <= NOW()",
+        )
+        .execute(&self.db)
+        .await?;
+
+        let due = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT id, business_id FROM report_schedules
+            WHERE is_active = TRUE AND next_run_at <= NOW()
+            ORDER BY next_run_at
+            LIMIT $1
+            "#,
+        )
+        .bind(SCHEDULE_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await?;
+
+        for (schedule_id, business_id) in &due {
+            let schedule = self.get_schedule(*business_id, *schedule_id).await?;
+
+            // Advance first so a failing delivery is not retried every poll
+            let frequency = ScheduleFrequency::parse(&schedule.frequency).unwrap_or(ScheduleFrequency::Daily);
+            let next_run_at = next_run_after(
+                frequency,
+                schedule.day_of_week,
+                schedule.day_of_month,
+                schedule.time_of_day,
+                schedule.utc_offset_minutes,
+                Utc::now(),
+            );
+            sqlx::query("UPDATE report_schedules SET next_run_at = COALESCE($2, next_run_at + INTERVAL '1 day') WHERE id = $1")
+                .bind(schedule.id)
+                .bind(next_run_at)
+                .execute(&self.db)
+                .await?;
+
+            if let Err(e) = self.deliver(&schedule).await {
+                tracing::error!("Scheduled report {} failed: {}", schedule.id, e);
+            }
+        }
+
+        Ok(due.len())
+    }
+
+    /// Generate and send a schedule's report now, recording the delivery
+    pub async fn deliver(&self, schedule: &ReportSchedule) -> AppResult<ReportDelivery> {
+        let delivery = match self.render(schedule).await {
+            Ok(file) => self.send(schedule, file).await?,
+            Err(e) => self.record_failure(schedule, &e.to_string()).await?,
+        };
+
+        sqlx::query(
+            "UPDATE report_schedules SET last_run_at = NOW(), last_status = $2 WHERE id = $1",
+        )
+        .bind(schedule.id)
+        .bind(&delivery.status)
+        .execute(&self.db)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Run the saved report as the schedule's owner and render the file
+    async fn render(&self, schedule: &ReportSchedule) -> AppResult<ReportFile> {
+        // Run with the owner's role so their field visibility policies apply
+        let role_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT role_id FROM users WHERE id = $1 AND business_id = $2 AND is_active = TRUE",
+        )
+        .bind(schedule.created_by)
+        .bind(schedule.business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| {
+            AppError::ExternalService("Schedule owner is no longer an active user".to_string())
+        })?;
+
+        let builder = ReportBuilderService::new(self.db.clone());
+        let saved = builder
+            .get_saved_report(schedule.business_id, schedule.saved_report_id)
+            .await?;
+        let result = builder
+            .run_report(schedule.business_id, role_id, &saved.definition)
+            .await?;
+
+        let thai = schedule.language == "th";
+        let display = BusinessService::new(self.db.clone())
+            .get_display_format(schedule.business_id)
+            .await?;
+        let calendar = display.calendar;
+        let text_format = display.for_language(if thai { &Language::Thai } else { &Language::English });
+        let local_date = (Utc::now() + Duration::minutes(schedule.utc_offset_minutes as i64)).date_naive();
+        let base_name: String = saved
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+
+        let (data, extension, content_type) = if schedule.format == "csv" {
+            (result.to_csv(thai, calendar)?, "csv", "text/csv; charset=utf-8")
+        } else {
+            (
+                result.to_xlsx(&saved.name, thai, calendar)?,
+                "xlsx",
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            )
+        };
+
+        Ok(ReportFile {
+            row_count: result.rows.len() as i32,
+            report_date: text_format.date(local_date),
+            row_count_text: text_format.integer(result.rows.len() as i64),
+            attachment: EmailAttachment {
+                filename: format!("{}_{}.{}", base_name, local_date, extension),
+                content_type: content_type.to_string(),
+                data,
+            },
+        })
+    }
+
+    /// Send a rendered report to every recipient and record the outcome
+    async fn send(&self, schedule: &ReportSchedule, file: ReportFile) -> AppResult<ReportDelivery> {
+        let is_line = schedule.channel == "line";
+        let token = is_line.then(|| format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()));
+
+        // Record the delivery first so LINE download links work once sent
+        let delivery_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO report_deliveries (
+                schedule_id, business_id, status, row_count, file_name, content_type,
+                file_data, download_token, expires_at
+            )
+            VALUES ($1, $2, 'failed', $3, $4, $5, $6, $7,
+                    CASE WHEN $7::VARCHAR IS NOT NULL THEN NOW() + make_interval(days => $8) END)
+            RETURNING id
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(schedule.business_id)
+        .bind(file.row_count)
+        .bind(&file.attachment.filename)
+        .bind(&file.attachment.content_type)
+        .bind(is_line.then_some(&file.attachment.data))
+        .bind(&token)
+        .bind(DOWNLOAD_LINK_DAYS as i32)
+        .fetch_one(&self.db)
+        .await?;
+
+        let (sent, failed, errors) = match &token {
+            Some(token) => self.send_line(schedule, &file, token).await?,
+            None => self.send_email(schedule, &file).await?,
+        };
+
+        let status = match (sent, failed) {
+            (s, 0) if s > 0 => "sent",
+            (s, _) if s > 0 => "partial",
+            _ => "failed",
+        };
+        let error_message = (!errors.is_empty()).then(|| errors.join("; "));
+
+        let delivery = sqlx::query_as::<_, ReportDelivery>(
+            r#"
+            UPDATE report_deliveries
+            SET status = $2, recipients_sent = $3, recipients_failed = $4, error_message = $5
+            WHERE id = $1
+            RETURNING id, schedule_id, status, row_count, recipients_sent, recipients_failed,
+                      error_message, file_name, expires_at, created_at
+            "#,
+        )
+        .bind(delivery_id)
+        .bind(status)
+        .bind(sent)
+        .bind(failed)
+        .bind(error_message)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Email the report as an attachment to each recipient
+    async fn send_email(
+        &self,
+        schedule: &ReportSchedule,
+        file: &ReportFile,
+    ) -> AppResult<(i32, i32, Vec<String>)> {
+        let Some(mailer) = &self.mailer else {
+            return Ok((0, 1, vec!["Email delivery is not configured".to_string()]));
+        };
+
+        let mut recipients: BTreeSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT email FROM users WHERE id = ANY($1) AND business_id = $2 AND is_active = TRUE AND email IS NOT NULL",
+        )
+        .bind(&schedule.recipient_user_ids)
+        .bind(schedule.business_id)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .collect();
+        recipients.extend(schedule.recipient_emails.iter().cloned());
+
+        let (subject, body) = if schedule.language == "th" {
+            (
+                format!("รายงาน {} ({})", schedule.report_name, file.report_date),
+                format!(
+                    "รายงาน \"{}\" ตามกำหนดการ ณ วันที่ {} ({} รายการ) แนบมากับอีเมลนี้",
+                    schedule.report_name, file.report_date, file.row_count_text
+                ),
+            )
+        } else {
+            (
+                format!("Report: {} ({})", schedule.report_name, file.report_date),
+                format!(
+                    "Your scheduled report \"{}\" for {} ({} rows) is attached.",
+                    schedule.report_name, file.report_date, file.row_count_text
+                ),
+            )
+        };
+
+        let mut sent = 0;
+        let mut errors = Vec::new();
+        for recipient in &recipients {
+            match mailer.send(recipient, &subject, &body, Some(&file.attachment)).await {
+                Ok(()) => sent += 1,
+                Err(e) => errors.push(format!("{}: {}", recipient, e)),
+            }
+        }
+
+        Ok((sent, errors.len() as i32, errors))
+    }
+
+    /// Push a LINE message linking to the stored report to each recipient
+    async fn send_line(
+        &self,
+        schedule: &ReportSchedule,
+        file: &ReportFile,
+        token: &str,
+    ) -> AppResult<(i32, i32, Vec<String>)> {
+        let Some(client) = &self.line_client else {
+            return Ok((0, 1, vec!["LINE messaging is not configured".to_string()]));
+        };
+
+        let line_user_ids = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT lc.line_user_id
+            FROM line_connections lc
+            JOIN users u ON u.id = lc.user_id
+            WHERE lc.user_id = ANY($1) AND u.business_id = $2 AND u.is_active = TRUE
+            "#,
+        )
+        .bind(&schedule.recipient_user_ids)
+        .bind(schedule.business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut errors = Vec::new();
+        let unreachable = schedule.recipient_user_ids.len().saturating_sub(line_user_ids.len());
+        if unreachable > 0 {
+            errors.push(format!("{} recipient(s) have no linked LINE account", unreachable));
+        }
+
+        let url = format!("{}/api/v1/report-downloads/{}", self.public_url, token);
+        let text = if schedule.language == "th" {
+            format!(
+                "📊 รายงาน {} ({})\n{} รายการ\nดาวน์โหลด (ใช้ได้ {} วัน): {}",
+                schedule.report_name, file.report_date, file.row_count_text, DOWNLOAD_LINK_DAYS, url
+            )
+        } else {
+            format!(
+                "📊 Report: {} ({})\n{} rows\nDownload (valid {} days): {}",
+                schedule.report_name, file.report_date, file.row_count_text, DOWNLOAD_LINK_DAYS, url
+            )
+        };
+
+        let mut sent = 0;
+        for line_user_id in &line_user_ids {
+            let message = LineMessage::Text {
+                text: text.clone(),
+                quick_reply: None,
+            };
+            match client.send_push_message(line_user_id, message).await {
+                Ok(_) => sent += 1,
+                Err(e) => errors.push(e),
+            }
+        }
+
+        Ok((sent, (line_user_ids.len() - sent as usize + unreachable) as i32, errors))
+    }
+
+    async fn record_failure(&self, schedule: &ReportSchedule, error: &str) -> AppResult<ReportDelivery> {
+        let delivery = sqlx::query_as::<_, ReportDelivery>(
+            r#"
+            INSERT INTO report_deliveries (schedule_id, business_id, status, error_message)
+            VALUES ($1, $2, 'failed', $3)
+            RETURNING id, schedule_id, status, row_count, recipients_sent, recipients_failed,
+                      error_message, file_name, expires_at, created_at
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(schedule.business_id)
+        .bind(error)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Validate a schedule input and compute its first run
+    async fn validate_input(
+        &self,
+        business_id: Uuid,
+        input: &ReportScheduleInput,
+    ) -> AppResult<DateTime<Utc>> {
+        if let Some(error) = schedule_input_error(input) {
+            return Err(error);
+        }
+
+        // The saved report must belong to the business
+        ReportBuilderService::new(self.db.clone())
+            .get_saved_report(business_id, input.saved_report_id)
+            .await?;
+
+        let known = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM users WHERE id = ANY($1) AND business_id = $2",
+        )
+        .bind(&input.recipient_user_ids)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        let requested: BTreeSet<&Uuid> = input.recipient_user_ids.iter().collect();
+        if known as usize != requested.len() {
+            return Err(AppError::Validation {
+                field: "recipient_user_ids".to_string(),
+                message: "Recipients must be users of this business".to_string(),
+                message_th: "ผู้รับต้องเป็นผู้ใช้ของธุรกิจนี้".to_string(),
+            });
+        }
+
+        next_run_after(
+            input.frequency,
+            schedule_day_of_week(input),
+            schedule_day_of_month(input),
+            input.time_of_day,
+            input.utc_offset_minutes.unwrap_or(DEFAULT_UTC_OFFSET_MINUTES),
+            Utc::now(),
+        )
+        .ok_or_else(|| AppError::Internal("Failed to compute next schedule run".to_string()))
+    }
+}
+
+/// Day of week stored for a schedule (weekly only)
+fn schedule_day_of_week(input: &ReportScheduleInput) -> Option<i16> {
+    (input.frequency == ScheduleFrequency::Weekly)
+        .then_some(input.day_of_week)
+        .flatten()
+}
+
+/// Day of month stored for a schedule (monthly only)
+fn schedule_day_of_month(input: &ReportScheduleInput) -> Option<i16> {
+    (input.frequency == ScheduleFrequency::Monthly)
+        .then_some(input.day_of_month)
+        .flatten()
+}
@@ -8,6 +8,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppResult;
+use crate::services::kpi::{KpiQuery, KpiReport, KpiService};
 
 /// Reporting service
 #[derive(Clone)]
@@ -57,6 +58,8 @@ pub struct DashboardMetrics {
     pub pending_alerts: i64,
     pub recent_harvests: i64,
     pub expiring_certifications: i64,
+    /// Current season's KPIs against their targets
+    pub kpi: KpiReport,
 }
 
 /// Report filter parameters
@@ -281,7 +284,7 @@ impl ReportingService {
             r#"
             SELECT COUNT(*) FROM certifications
             WHERE business_id = $1
-              AND status = 'active'
+              AND is_active = true
               AND expiration_date <= CURRENT_DATE + INTERVAL '90 days'
             "#,
         )
@@ -289,6 +292,8 @@ impl ReportingService {
         .fetch_one(&self.db)
         .await?;
 
+        let kpi = KpiService::new(self.db.clone()).report(business_id, &KpiQuery::default()).await?;
+
         Ok(DashboardMetrics {
             total_lots: lot_counts.0,
             active_lots: lot_counts.1,
@@ -297,6 +302,7 @@ impl ReportingService {
             pending_alerts,
             recent_harvests,
             expiring_certifications: expiring_certs,
+            kpi,
         })
     }
 
@@ -0,0 +1,265 @@
+//! Reporting calendar and report periods
+//!
+//! Crop seasons and fiscal years run twelve months from the month a business
+//! sets them to start in (seasons from October and fiscal years from January
+//! by default) and are named after the year they start in: the 2024/25
+//! season covers October 2024 to September 2025. Period-based reports take a
+//! `period` parameter resolved here against the business's calendar:
+//! - `season:2024/25` or `season:2024`, `season:current`, `season:previous`
+//! - `fy:2024/25` or `fy:2024`, `fy:current`, `fy:previous`
+//! - `year:2024`, `quarter:2024-Q2`, `month:2024-06`
+
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::error::{AppError, AppResult};
+
+/// Month crop seasons start in unless the business sets another
+pub const DEFAULT_SEASON_START_MONTH: u32 = 10;
+
+/// Month fiscal years start in unless the business sets another
+pub const DEFAULT_FISCAL_YEAR_START_MONTH: u32 = 1;
+
+const PERIOD_FORMAT: &str = "season:2024/25, fy:2024, year:2024, quarter:2024-Q2 or month:2024-06";
+
+/// Season and fiscal year boundaries of a business
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportingCalendar {
+    pub season_start_month: u32,
+    pub fiscal_year_start_month: u32,
+}
+
+impl Default for ReportingCalendar {
+    fn default() -> Self {
+        Self {
+            season_start_month: DEFAULT_SEASON_START_MONTH,
+            fiscal_year_start_month: DEFAULT_FISCAL_YEAR_START_MONTH,
+        }
+    }
+}
+
+/// Period a report covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    /// Crop season by the year it starts in
+    Season(i32),
+    /// Fiscal year by the year it starts in
+    FiscalYear(i32),
+    Year(i32),
+    /// Calendar quarter 1-4
+    Quarter(i32, u32),
+    Month(i32, u32),
+}
+
+fn period_error(message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: "period".to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn invalid_period() -> AppError {
+    period_error(
+        &format!("Period must look like {}", PERIOD_FORMAT),
+        &format!("ช่วงเวลาต้องอยู่ในรูปแบบ {}", PERIOD_FORMAT),
+    )
+}
+
+/// Whether `month` is a month number
+pub fn validate_start_month(field: &str, month: i32) -> AppResult<()> {
+    if (1..=12).contains(&month) {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        field: field.to_string(),
+        message: "Month must be between 1 and 12".to_string(),
+        message_th: "เดือนต้องอยู่ระหว่าง 1 ถึง 12".to_string(),
+    })
+}
+
+/// Label of a twelve-month year by the year it starts in, such as
+/// "2024/25"; a year starting in January is just "2024"
+pub fn year_label(year: i32, start_month: u32) -> String {
+    if start_month == 1 {
+        year.to_string()
+    } else {
+        format!("{}/{:02}", year, (year + 1).rem_euclid(100))
+    }
+}
+
+/// Year a twelve-month year starting in `start_month` and containing
+/// `date` starts in
+fn year_starting(date: NaiveDate, start_month: u32) -> i32 {
+    if date.month() >= start_month {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
+/// First and last day of a twelve-month year starting in `start_month`
+fn year_bounds(field: &str, year: i32, start_month: u32) -> AppResult<(NaiveDate, NaiveDate)> {
+    NaiveDate::from_ymd_opt(year, start_month, 1)
+        .and_then(|start| {
+            let end = start.checked_add_months(Months::new(12))?.pred_opt()?;
+            Some((start, end))
+        })
+        .ok_or_else(|| AppError::Validation {
+            field: field.to_string(),
+            message: "Not a valid year".to_string(),
+            message_th: "ปีไม่ถูกต้อง".to_string(),
+        })
+}
+
+/// Check a season year can be turned into dates
+pub fn validate_season(season: i32) -> AppResult<()> {
+    year_bounds("season", season, DEFAULT_SEASON_START_MONTH).map(|_| ())
+}
+
+impl ReportingCalendar {
+    /// Calendar from stored start months; months out of range fall back
+    /// to the defaults
+    pub fn new(season_start_month: i32, fiscal_year_start_month: i32) -> Self {
+        let month = |value: i32, default: u32| u32::try_from(value).ok().filter(|m| (1..=12).contains(m)).unwrap_or(default);
+        Self {
+            season_start_month: month(season_start_month, DEFAULT_SEASON_START_MONTH),
+            fiscal_year_start_month: month(fiscal_year_start_month, DEFAULT_FISCAL_YEAR_START_MONTH),
+        }
+    }
+
+    /// Year the crop season containing `date` starts in
+    pub fn season_of(&self, date: NaiveDate) -> i32 {
+        year_starting(date, self.season_start_month)
+    }
+
+    /// Season label such as "2024/25"
+    pub fn season_label(&self, season: i32) -> String {
+        year_label(season, self.season_start_month)
+    }
+
+    /// First and last day of a crop season
+    pub fn season_bounds(&self, season: i32) -> AppResult<(NaiveDate, NaiveDate)> {
+        year_bounds("season", season, self.season_start_month)
+    }
+
+    /// Year the fiscal year containing `date` starts in
+    pub fn fiscal_year_of(&self, date: NaiveDate) -> i32 {
+        year_starting(date, self.fiscal_year_start_month)
+    }
+
+    /// First and last day of a fiscal year
+    pub fn fiscal_year_bounds(&self, fiscal_year: i32) -> AppResult<(NaiveDate, NaiveDate)> {
+        year_bounds("period", fiscal_year, self.fiscal_year_start_month)
+    }
+}
+
+/// Start year of "2024", "2024/25" or "2024/2025"; the second year must
+/// follow the first
+fn parse_year_label(value: &str) -> Option<i32> {
+    let (start, end) = match value.split_once('/') {
+        Some((start, end)) => (start, Some(end)),
+        None => (value, None),
+    };
+    let year: i32 = start.trim().parse().ok()?;
+    match end.map(str::trim) {
+        None => Some(year),
+        Some(end) if end.len() == 2 => (end.parse::<i32>().ok()? == (year + 1).rem_euclid(100)).then_some(year),
+        Some(end) => (end.parse::<i32>().ok()? == year + 1).then_some(year),
+    }
+}
+
+/// Parse a `period` parameter; `current` and `previous` seasons and fiscal
+/// years are taken as of `today`
+pub fn parse_period(value: &str, calendar: &ReportingCalendar, today: NaiveDate) -> AppResult<ReportPeriod> {
+    let (kind, rest) = value.trim().split_once(':').ok_or_else(invalid_period)?;
+    let rest = rest.trim();
+    let relative = |current: i32| match rest {
+        "current" => Some(current),
+        "previous" => Some(current - 1),
+        _ => parse_year_label(rest),
+    };
+    let period = match kind.trim().to_ascii_lowercase().as_str() {
+        "season" => relative(calendar.season_of(today)).map(ReportPeriod::Season),
+        "fy" | "fiscal_year" => relative(calendar.fiscal_year_of(today)).map(ReportPeriod::FiscalYear),
+        "year" => rest.parse().ok().map(ReportPeriod::Year),
+        "quarter" => rest.split_once(['-', ' ']).and_then(|(year, quarter)| {
+            let quarter = quarter.trim().trim_start_matches(['Q', 'q']).parse().ok()?;
+            (1..=4).contains(&quarter).then_some(ReportPeriod::Quarter(year.parse().ok()?, quarter))
+        }),
+        "month" => rest.split_once('-').and_then(|(year, month)| {
+            let month = month.parse().ok()?;
+            (1..=12).contains(&month).then_some(ReportPeriod::Month(year.parse().ok()?, month))
+        }),
+        _ => None,
+    };
+    period.ok_or_else(invalid_period)
+}
+
+impl ReportPeriod {
+    /// First and last day of the period
+    pub fn bounds(&self, calendar: &ReportingCalendar) -> AppResult<(NaiveDate, NaiveDate)> {
+        match *self {
+            ReportPeriod::Season(season) => year_bounds("period", season, calendar.season_start_month),
+            ReportPeriod::FiscalYear(year) => calendar.fiscal_year_bounds(year),
+            ReportPeriod::Year(year) => year_bounds("period", year, 1),
+            ReportPeriod::Quarter(year, quarter) => month_span(year, (quarter - 1) * 3 + 1, 3),
+            ReportPeriod::Month(year, month) => month_span(year, month, 1),
+        }
+    }
+}
+
+fn month_span(year: i32, month: u32, months: u32) -> AppResult<(NaiveDate, NaiveDate)> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|start| Some((start, start.checked_add_months(Months::new(months))?.pred_opt()?)))
+        .ok_or_else(invalid_period)
+}
+
+/// First and last day of a `period` parameter; None when not given
+pub fn resolve_period(
+    period: Option<&str>,
+    calendar: &ReportingCalendar,
+    today: NaiveDate,
+) -> AppResult<Option<(NaiveDate, NaiveDate)>> {
+    period
+        .map(|period| parse_period(period, calendar, today)?.bounds(calendar))
+        .transpose()
+}
+
+/// Season of a season-based report: the season a `period` parameter names,
+/// else `season`, else the current season
+pub fn resolve_season(
+    period: Option<&str>,
+    season: Option<i32>,
+    calendar: &ReportingCalendar,
+    today: NaiveDate,
+) -> AppResult<i32> {
+    let season = match period {
+        Some(period) => match parse_period(period, calendar, today)? {
+            ReportPeriod::Season(season) => season,
+            _ => {
+                return Err(period_error(
+                    "This report covers a crop season; use a period such as season:2024/25",
+                    "รายงานนี้แสดงตามฤดูการผลิต ให้ระบุช่วงเวลาเช่น season:2024/25",
+                ))
+            }
+        },
+        None => season.unwrap_or_else(|| calendar.season_of(today)),
+    };
+    calendar.season_bounds(season)?;
+    Ok(season)
+}
+
+/// Dates a report covers: those of a `period` parameter when given, else
+/// `from` and `to` as given
+pub fn resolve_date_range(
+    period: Option<&str>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    calendar: &ReportingCalendar,
+    today: NaiveDate,
+) -> AppResult<(Option<NaiveDate>, Option<NaiveDate>)> {
+    Ok(match resolve_period(period, calendar, today)? {
+        Some((start, end)) => (Some(start), Some(end)),
+        None => (from, to),
+    })
+}
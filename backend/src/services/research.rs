@@ -0,0 +1,706 @@
+//! Read-only research API
+//!
+//! Research partners (universities studying Thai coffee quality) read
+//! de-identified datasets of cupping scores, processing batches and
+//! weather with an API key. Only businesses that opt in contribute, and
+//! every field is anonymized before it leaves this service:
+//!
+//! - businesses, plots, people and free-text notes are never included
+//! - lots appear as pseudonyms keyed by a per-partner salt, so one partner
+//!   can join its quality and processing records but two partners cannot
+//!   link theirs
+//! - location is the province and an altitude band, never coordinates
+//! - dates are reduced to the quarter, batch weights to bands
+//! - record-level rows are only released for combinations of province,
+//!   altitude band, variety, process and quarter that at least
+//!   `MIN_GROUP_BUSINESSES` businesses contribute to, and weather is only
+//!   released as province averages over as many businesses
+//!
+//! Each partner has an hourly request limit enforced from the request log.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::auth::hash_action_token;
+use crate::services::benchmarking::MIN_COHORT_BUSINESSES;
+
+/// Businesses a released group of records must span
+pub const MIN_GROUP_BUSINESSES: usize = MIN_COHORT_BUSINESSES;
+
+/// Width of an altitude band in meters
+pub const ALTITUDE_BAND_METERS: i64 = 200;
+
+/// Width of a batch weight band in kg
+pub const WEIGHT_BAND_KG: i64 = 100;
+
+/// Hourly request limit of a partner created without one
+pub const DEFAULT_REQUESTS_PER_HOUR: i32 = 100;
+
+/// Months of data returned when none are given
+pub const DEFAULT_DATASET_MONTHS: i32 = 24;
+
+/// Prefix of every research API key
+pub const API_KEY_PREFIX: &str = "cqr_";
+
+/// Characters of a key kept in the clear to tell keys apart
+const KEY_PREFIX_LENGTH: usize = 12;
+
+/// Characters of a lot pseudonym
+const PSEUDONYM_LENGTH: usize = 16;
+
+/// Lot attributes shared by the record-level datasets. Variety is the
+/// single variety planted on the lot's harvested plots, or "mixed";
+/// altitude is the average of those plots
+const LOT_CONTEXT: &str = r#"
+    SELECT l.id AS lot_id, l.business_id, b.province,
+           (SELECT ROUND(AVG(p.altitude_meters))::INTEGER
+            FROM harvests h JOIN plots p ON p.id = h.plot_id
+            WHERE h.lot_id = l.id) AS altitude_meters,
+           COALESCE(
+               (SELECT CASE WHEN COUNT(DISTINCT pv.variety) = 1 THEN MIN(pv.variety) ELSE 'mixed' END
+                FROM harvests h JOIN plot_varieties pv ON pv.plot_id = h.plot_id
+                WHERE h.lot_id = l.id
+                HAVING COUNT(pv.variety) > 0),
+               'unknown') AS variety
+    FROM lots l
+    JOIN businesses b ON b.id = l.business_id
+    WHERE b.research_opt_in AND b.province IS NOT NULL
+      AND ($2::text IS NULL OR b.province = $2)
+"#;
+
+/// Research service
+#[derive(Clone)]
+pub struct ResearchService {
+    db: PgPool,
+}
+
+/// A research partner and its API key, without the key itself
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ResearchPartner {
+    pub id: Uuid,
+    pub name: String,
+    pub institution: String,
+    pub contact_email: Option<String>,
+    pub key_prefix: String,
+    pub requests_per_hour: i32,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for registering a research partner
+#[derive(Debug, Deserialize)]
+pub struct CreateResearchPartnerInput {
+    pub name: String,
+    pub institution: String,
+    pub contact_email: Option<String>,
+    pub requests_per_hour: Option<i32>,
+}
+
+/// A newly registered partner; the API key is only ever shown here
+#[derive(Debug, Serialize)]
+pub struct IssuedResearchKey {
+    pub partner: ResearchPartner,
+    pub api_key: String,
+}
+
+/// Query parameters for a dataset
+#[derive(Debug, Deserialize)]
+pub struct ResearchDatasetQuery {
+    /// Months back from today, 1-60
+    pub months: Option<i32>,
+    pub province: Option<String>,
+}
+
+/// Value band, `min` inclusive and `max` exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct ValueBand {
+    pub min: i64,
+    pub max: i64,
+}
+
+/// Records of a dataset with the guarantees they were released under
+#[derive(Debug, Serialize)]
+pub struct ResearchDataset<T> {
+    pub dataset: &'static str,
+    pub generated_at: DateTime<Utc>,
+    pub months: i32,
+    pub min_group_businesses: usize,
+    pub records: Vec<T>,
+}
+
+/// One cupping sample
+#[derive(Debug, Serialize)]
+pub struct QualityRecord {
+    pub lot: String,
+    pub quarter: String,
+    pub province: String,
+    pub altitude_band_meters: Option<ValueBand>,
+    pub variety: String,
+    pub process: String,
+    pub fragrance_aroma: Decimal,
+    pub flavor: Decimal,
+    pub aftertaste: Decimal,
+    pub acidity: Decimal,
+    pub body: Decimal,
+    pub balance: Decimal,
+    pub uniformity: Decimal,
+    pub clean_cup: Decimal,
+    pub sweetness: Decimal,
+    pub overall: Decimal,
+    pub defects_taint: i32,
+    pub defects_fault: i32,
+    pub final_score: Decimal,
+    /// Latest green bean grade of the lot
+    pub green_grade: Option<String>,
+}
+
+/// One processing batch
+#[derive(Debug, Serialize)]
+pub struct ProcessingRecord {
+    pub lot: String,
+    pub quarter: String,
+    pub province: String,
+    pub altitude_band_meters: Option<ValueBand>,
+    pub variety: String,
+    pub process: String,
+    pub duration_days: Option<i32>,
+    pub cherry_weight_band_kg: Option<ValueBand>,
+    pub yield_percent: Option<Decimal>,
+    pub final_moisture_percent: Option<Decimal>,
+}
+
+/// Monthly weather of a province, averaged over its contributing
+/// businesses
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WeatherRecord {
+    pub province: String,
+    pub month: String,
+    pub avg_temperature_celsius: Option<Decimal>,
+    pub min_temperature_celsius: Option<Decimal>,
+    pub max_temperature_celsius: Option<Decimal>,
+    pub avg_humidity_percent: Option<Decimal>,
+    /// Gauge rainfall of the month per plot
+    pub avg_plot_rainfall_mm: Option<Decimal>,
+}
+
+/// Key lookup result used to serve a request
+#[derive(Debug, sqlx::FromRow)]
+struct PartnerAccess {
+    id: Uuid,
+    pseudonym_salt: String,
+    requests_per_hour: i32,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct QualityRow {
+    business_id: Uuid,
+    lot_id: Uuid,
+    province: String,
+    altitude_meters: Option<i32>,
+    variety: String,
+    process: String,
+    session_date: NaiveDate,
+    fragrance_aroma: Decimal,
+    flavor: Decimal,
+    aftertaste: Decimal,
+    acidity: Decimal,
+    body: Decimal,
+    balance: Decimal,
+    uniformity: Decimal,
+    clean_cup: Decimal,
+    sweetness: Decimal,
+    overall: Decimal,
+    defects_taint: i32,
+    defects_fault: i32,
+    final_score: Decimal,
+    green_grade: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProcessingRow {
+    business_id: Uuid,
+    lot_id: Uuid,
+    province: String,
+    altitude_meters: Option<i32>,
+    variety: String,
+    process: String,
+    start_date: NaiveDate,
+    duration_days: Option<i32>,
+    cherry_weight_kg: Option<Decimal>,
+    yield_percent: Option<Decimal>,
+    final_moisture_percent: Option<Decimal>,
+}
+
+/// Quasi-identifiers of a record-level row; rows are only released for
+/// keys spanning enough businesses
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GroupKey {
+    pub province: String,
+    pub altitude_band: Option<ValueBand>,
+    pub variety: String,
+    pub process: String,
+    pub quarter: String,
+}
+
+/// New random API key
+pub fn generate_api_key() -> String {
+    format!("{}{}{}", API_KEY_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Leading characters of a key stored in the clear
+pub fn key_prefix(api_key: &str) -> String {
+    api_key.chars().take(KEY_PREFIX_LENGTH).collect()
+}
+
+/// Stable pseudonym of an id for the partner owning `salt`
+pub fn pseudonym(salt: &str, id: Uuid) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(id.as_bytes());
+    let digest = format!("{:x}", mac.finalize().into_bytes());
+    digest[..PSEUDONYM_LENGTH].to_string()
+}
+
+/// Calendar quarter of a date, e.g. "2024-Q3"
+pub fn quarter(date: NaiveDate) -> String {
+    format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1)
+}
+
+/// Band `value` falls in for bands `width` wide
+pub fn value_band(value: i64, width: i64) -> ValueBand {
+    let min = value.div_euclid(width) * width;
+    ValueBand { min, max: min + width }
+}
+
+/// Altitude band of a plot altitude
+pub fn altitude_band(meters: i32) -> ValueBand {
+    value_band(meters as i64, ALTITUDE_BAND_METERS)
+}
+
+/// Weight band of a batch weight
+pub fn weight_band(kg: Decimal) -> ValueBand {
+    value_band(kg.floor().to_i64().unwrap_or(0), WEIGHT_BAND_KG)
+}
+
+/// Rows whose group spans at least `min_businesses` businesses, in their
+/// original order
+pub fn suppress_small_groups<K: Eq + Hash, T>(rows: Vec<(Uuid, K, T)>, min_businesses: usize) -> Vec<T> {
+    let mut businesses: HashMap<&K, HashSet<Uuid>> = HashMap::new();
+    for (business_id, key, _) in &rows {
+        businesses.entry(key).or_default().insert(*business_id);
+    }
+    let released: HashSet<usize> = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, key, _))| businesses[key].len() >= min_businesses)
+        .map(|(index, _)| index)
+        .collect();
+    rows.into_iter()
+        .enumerate()
+        .filter(|(index, _)| released.contains(index))
+        .map(|(_, (_, _, record))| record)
+        .collect()
+}
+
+/// Seconds until a partner may send another request, or None while under
+/// its hourly limit. `oldest_request` is the oldest request of the past hour
+pub fn rate_limit_retry_after(
+    requests: i64,
+    limit: i32,
+    oldest_request: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    if requests < limit as i64 {
+        return None;
+    }
+    let frees_at = oldest_request.unwrap_or(now) + Duration::hours(1);
+    Some((frees_at - now).num_seconds().max(1))
+}
+
+/// Whether `presented` is the configured admin token; never when none is
+/// configured. Digests are compared so timing does not reveal the token
+pub fn admin_token_matches(configured: &str, presented: &str) -> bool {
+    !configured.is_empty() && Sha256::digest(configured.as_bytes()) == Sha256::digest(presented.trim().as_bytes())
+}
+
+/// Months of data requested, 1-60
+pub fn dataset_months(months: Option<i32>) -> AppResult<i32> {
+    let months = months.unwrap_or(DEFAULT_DATASET_MONTHS);
+    if !(1..=60).contains(&months) {
+        return Err(AppError::Validation {
+            field: "months".to_string(),
+            message: "Months must be between 1 and 60".to_string(),
+            message_th: "จำนวนเดือนต้องอยู่ระหว่าง 1 ถึง 60".to_string(),
+        });
+    }
+    Ok(months)
+}
+
+fn round_1(value: Option<Decimal>) -> Option<Decimal> {
+    value.map(|v| v.round_dp(1))
+}
+
+fn invalid_api_key() -> AppError {
+    AppError::Unauthorized {
+        message: "Invalid or revoked research API key".to_string(),
+        message_th: "คีย์ API สำหรับงานวิจัยไม่ถูกต้องหรือถูกยกเลิกแล้ว".to_string(),
+    }
+}
+
+impl ResearchService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Register a partner and issue its API key
+    pub async fn create_partner(&self, input: CreateResearchPartnerInput) -> AppResult<IssuedResearchKey> {
+        for (field, value, message_th) in [
+            ("name", &input.name, "กรุณาระบุชื่อ"),
+            ("institution", &input.institution, "กรุณาระบุสถาบัน"),
+        ] {
+            if value.trim().is_empty() {
+                return Err(AppError::Validation {
+                    field: field.to_string(),
+                    message: format!("{} is required", field),
+                    message_th: message_th.to_string(),
+                });
+            }
+        }
+        let requests_per_hour = input.requests_per_hour.unwrap_or(DEFAULT_REQUESTS_PER_HOUR);
+        if !(1..=10_000).contains(&requests_per_hour) {
+            return Err(AppError::Validation {
+                field: "requests_per_hour".to_string(),
+                message: "Requests per hour must be between 1 and 10000".to_string(),
+                message_th: "จำนวนคำขอต่อชั่วโมงต้องอยู่ระหว่าง 1 ถึง 10000".to_string(),
+            });
+        }
+
+        let api_key = generate_api_key();
+        let partner = sqlx::query_as::<_, ResearchPartner>(
+            r#"
+            INSERT INTO research_partners
+                (name, institution, contact_email, key_prefix, key_hash, pseudonym_salt, requests_per_hour)
+            VALUES ($1, $2, NULLIF(TRIM($3), ''), $4, $5, $6, $7)
+            RETURNING id, name, institution, contact_email, key_prefix, requests_per_hour,
+                      last_used_at, revoked_at, created_at
+            "#,
+        )
+        .bind(input.name.trim())
+        .bind(input.institution.trim())
+        .bind(&input.contact_email)
+        .bind(key_prefix(&api_key))
+        .bind(hash_action_token(&api_key))
+        .bind(format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()))
+        .bind(requests_per_hour)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(IssuedResearchKey { partner, api_key })
+    }
+
+    /// All partners, newest first
+    pub async fn list_partners(&self) -> AppResult<Vec<ResearchPartner>> {
+        let partners = sqlx::query_as::<_, ResearchPartner>(
+            r#"
+            SELECT id, name, institution, contact_email, key_prefix, requests_per_hour,
+                   last_used_at, revoked_at, created_at
+            FROM research_partners
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(partners)
+    }
+
+    /// Revoke a partner's API key; its request log is kept
+    pub async fn revoke_partner(&self, partner_id: Uuid) -> AppResult<()> {
+        let revoked = sqlx::query("UPDATE research_partners SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1")
+            .bind(partner_id)
+            .execute(&self.db)
+            .await?
+            .rows_affected();
+        if revoked == 0 {
+            return Err(AppError::NotFound("Research partner".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Cupping samples of opted-in businesses
+    pub async fn quality_dataset(
+        &self,
+        api_key: &str,
+        query: &ResearchDatasetQuery,
+    ) -> AppResult<ResearchDataset<QualityRecord>> {
+        let months = dataset_months(query.months)?;
+        let partner = self.authorize(api_key).await?;
+
+        let rows = sqlx::query_as::<_, QualityRow>(&format!(
+            r#"
+            WITH lot_context AS ({LOT_CONTEXT})
+            SELECT lc.business_id, lc.lot_id, lc.province, lc.altitude_meters, lc.variety,
+                   COALESCE((SELECT pr.method FROM processing_records pr
+                             WHERE pr.lot_id = lc.lot_id
+                             ORDER BY pr.start_date DESC LIMIT 1), 'unknown') AS process,
+                   s.session_date, cs.fragrance_aroma, cs.flavor, cs.aftertaste, cs.acidity, cs.body,
+                   cs.balance, cs.uniformity, cs.clean_cup, cs.sweetness, cs.overall,
+                   cs.defects_taint, cs.defects_fault, cs.final_score,
+                   (SELECT g.grade FROM green_bean_grades g
+                    WHERE g.lot_id = lc.lot_id
+                    ORDER BY g.grading_date DESC LIMIT 1) AS green_grade
+            FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
+            JOIN lot_context lc ON lc.lot_id = cs.lot_id
+            WHERE s.session_date >= (CURRENT_DATE - make_interval(months => $1))::DATE
+            ORDER BY s.session_date, cs.id
+            "#
+        ))
+        .bind(months)
+        .bind(&query.province)
+        .fetch_all(&self.db)
+        .await?;
+
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let key = GroupKey {
+                    province: row.province.clone(),
+                    altitude_band: row.altitude_meters.map(altitude_band),
+                    variety: row.variety.clone(),
+                    process: row.process.clone(),
+                    quarter: quarter(row.session_date),
+                };
+                let record = QualityRecord {
+                    lot: pseudonym(&partner.pseudonym_salt, row.lot_id),
+                    quarter: key.quarter.clone(),
+                    province: row.province,
+                    altitude_band_meters: key.altitude_band,
+                    variety: row.variety,
+                    process: row.process,
+                    fragrance_aroma: row.fragrance_aroma,
+                    flavor: row.flavor,
+                    aftertaste: row.aftertaste,
+                    acidity: row.acidity,
+                    body: row.body,
+                    balance: row.balance,
+                    uniformity: row.uniformity,
+                    clean_cup: row.clean_cup,
+                    sweetness: row.sweetness,
+                    overall: row.overall,
+                    defects_taint: row.defects_taint,
+                    defects_fault: row.defects_fault,
+                    final_score: row.final_score,
+                    green_grade: row.green_grade,
+                };
+                (row.business_id, key, record)
+            })
+            .collect();
+
+        let records = suppress_small_groups(rows, MIN_GROUP_BUSINESSES);
+        self.record_request(partner.id, "quality", records.len()).await?;
+        Ok(dataset("quality", months, records))
+    }
+
+    /// Processing batches of opted-in businesses
+    pub async fn processing_dataset(
+        &self,
+        api_key: &str,
+        query: &ResearchDatasetQuery,
+    ) -> AppResult<ResearchDataset<ProcessingRecord>> {
+        let months = dataset_months(query.months)?;
+        let partner = self.authorize(api_key).await?;
+
+        let rows = sqlx::query_as::<_, ProcessingRow>(&format!(
+            r#"
+            WITH lot_context AS ({LOT_CONTEXT})
+            SELECT lc.business_id, lc.lot_id, lc.province, lc.altitude_meters, lc.variety,
+                   pr.method AS process, pr.start_date,
+                   (pr.end_date - pr.start_date) AS duration_days,
+                   pr.cherry_weight_kg, pr.processing_yield_percent AS yield_percent,
+                   pr.final_moisture_percent
+            FROM processing_records pr
+            JOIN lot_context lc ON lc.lot_id = pr.lot_id
+            WHERE pr.start_date >= (CURRENT_DATE - make_interval(months => $1))::DATE
+            ORDER BY pr.start_date, pr.id
+            "#
+        ))
+        .bind(months)
+        .bind(&query.province)
+        .fetch_all(&self.db)
+        .await?;
+
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let key = GroupKey {
+                    province: row.province.clone(),
+                    altitude_band: row.altitude_meters.map(altitude_band),
+                    variety: row.variety.clone(),
+                    process: row.process.clone(),
+                    quarter: quarter(row.start_date),
+                };
+                let record = ProcessingRecord {
+                    lot: pseudonym(&partner.pseudonym_salt, row.lot_id),
+                    quarter: key.quarter.clone(),
+                    province: row.province,
+                    altitude_band_meters: key.altitude_band,
+                    variety: row.variety,
+                    process: row.process,
+                    duration_days: row.duration_days,
+                    cherry_weight_band_kg: row.cherry_weight_kg.map(weight_band),
+                    yield_percent: round_1(row.yield_percent),
+                    final_moisture_percent: round_1(row.final_moisture_percent),
+                };
+                (row.business_id, key, record)
+            })
+            .collect();
+
+        let records = suppress_small_groups(rows, MIN_GROUP_BUSINESSES);
+        self.record_request(partner.id, "processing", records.len()).await?;
+        Ok(dataset("processing", months, records))
+    }
+
+    /// Monthly province weather of opted-in businesses; each measure is
+    /// only given when enough businesses contribute to it
+    pub async fn weather_dataset(
+        &self,
+        api_key: &str,
+        query: &ResearchDatasetQuery,
+    ) -> AppResult<ResearchDataset<WeatherRecord>> {
+        let months = dataset_months(query.months)?;
+        let partner = self.authorize(api_key).await?;
+
+        let records = sqlx::query_as::<_, WeatherRecord>(
+            r#"
+            WITH weather AS (
+                SELECT b.province, to_char(w.recorded_at AT TIME ZONE 'Asia/Bangkok', 'YYYY-MM') AS month,
+                       COUNT(DISTINCT w.business_id) AS businesses,
+                       AVG(w.temperature_celsius) AS avg_temperature_celsius,
+                       MIN(w.temperature_celsius) AS min_temperature_celsius,
+                       MAX(w.temperature_celsius) AS max_temperature_celsius,
+                       AVG(w.humidity_percent) AS avg_humidity_percent
+                FROM weather_snapshots w
+                JOIN businesses b ON b.id = w.business_id
+                WHERE b.research_opt_in AND b.province IS NOT NULL
+                  AND ($2::text IS NULL OR b.province = $2)
+                  AND w.recorded_at >= NOW() - make_interval(months => $1)
+                GROUP BY 1, 2
+            ),
+            plot_rainfall AS (
+                SELECT b.province, to_char(r.observed_on, 'YYYY-MM') AS month, r.business_id,
+                       SUM(r.rainfall_mm) AS rainfall_mm
+                FROM rainfall_observations r
+                JOIN businesses b ON b.id = r.business_id
+                WHERE b.research_opt_in AND b.province IS NOT NULL
+                  AND ($2::text IS NULL OR b.province = $2)
+                  AND r.observed_on >= (CURRENT_DATE - make_interval(months => $1))::DATE
+                GROUP BY 1, 2, 3, r.plot_id
+            ),
+            rainfall AS (
+                SELECT province, month, COUNT(DISTINCT business_id) AS businesses,
+                       AVG(rainfall_mm) AS avg_plot_rainfall_mm
+                FROM plot_rainfall
+                GROUP BY 1, 2
+            )
+            SELECT COALESCE(w.province, r.province) AS province,
+                   COALESCE(w.month, r.month) AS month,
+                   CASE WHEN w.businesses >= $3 THEN w.avg_temperature_celsius END AS avg_temperature_celsius,
+                   CASE WHEN w.businesses >= $3 THEN w.min_temperature_celsius END AS min_temperature_celsius,
+                   CASE WHEN w.businesses >= $3 THEN w.max_temperature_celsius END AS max_temperature_celsius,
+                   CASE WHEN w.businesses >= $3 THEN w.avg_humidity_percent END AS avg_humidity_percent,
+                   CASE WHEN r.businesses >= $3 THEN r.avg_plot_rainfall_mm END AS avg_plot_rainfall_mm
+            FROM weather w
+            FULL OUTER JOIN rainfall r ON r.province = w.province AND r.month = w.month
+            WHERE w.businesses >= $3 OR r.businesses >= $3
+            ORDER BY 1, 2
+            "#,
+        )
+        .bind(months)
+        .bind(&query.province)
+        .bind(MIN_GROUP_BUSINESSES as i64)
+        .fetch_all(&self.db)
+        .await?;
+
+        let records: Vec<WeatherRecord> = records
+            .into_iter()
+            .map(|record| WeatherRecord {
+                avg_temperature_celsius: round_1(record.avg_temperature_celsius),
+                min_temperature_celsius: round_1(record.min_temperature_celsius),
+                max_temperature_celsius: round_1(record.max_temperature_celsius),
+                avg_humidity_percent: round_1(record.avg_humidity_percent),
+                avg_plot_rainfall_mm: round_1(record.avg_plot_rainfall_mm),
+                ..record
+            })
+            .collect();
+
+        self.record_request(partner.id, "weather", records.len()).await?;
+        Ok(dataset("weather", months, records))
+    }
+
+    /// Partner owning an active key, if under its hourly request limit
+    async fn authorize(&self, api_key: &str) -> AppResult<PartnerAccess> {
+        if !api_key.starts_with(API_KEY_PREFIX) {
+            return Err(invalid_api_key());
+        }
+        let partner = sqlx::query_as::<_, PartnerAccess>(
+            r#"
+            SELECT id, pseudonym_salt, requests_per_hour
+            FROM research_partners
+            WHERE key_hash = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(hash_action_token(api_key))
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(invalid_api_key)?;
+
+        let (requests, oldest) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT COUNT(*), MIN(requested_at)
+            FROM research_api_requests
+            WHERE partner_id = $1 AND requested_at > NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(partner.id)
+        .fetch_one(&self.db)
+        .await?;
+
+        match rate_limit_retry_after(requests, partner.requests_per_hour, oldest, Utc::now()) {
+            Some(retry_after_secs) => Err(AppError::RateLimited { retry_after_secs }),
+            None => Ok(partner),
+        }
+    }
+
+    async fn record_request(&self, partner_id: Uuid, dataset: &str, row_count: usize) -> AppResult<()> {
+        sqlx::query("INSERT INTO research_api_requests (partner_id, dataset, row_count) VALUES ($1, $2, $3)")
+            .bind(partner_id)
+            .bind(dataset)
+            .bind(row_count as i32)
+            .execute(&self.db)
+            .await?;
+        sqlx::query("UPDATE research_partners SET last_used_at = NOW() WHERE id = $1")
+            .bind(partner_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+fn dataset<T>(name: &'static str, months: i32, records: Vec<T>) -> ResearchDataset<T> {
+    ResearchDataset {
+        dataset: name,
+        generated_at: Utc::now(),
+        months,
+        min_group_businesses: MIN_GROUP_BUSINESSES,
+        records,
+    }
+}
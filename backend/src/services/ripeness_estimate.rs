@@ -0,0 +1,235 @@
+//! Cherry ripeness estimation from harvest basket photos
+//!
+//! A photo of a cherry basket is sent to the AI service, which returns the
+//! underripe, ripe and overripe shares of the cherries it counted. The shares
+//! are rounded to whole percentages summing to 100 and stored, so the
+//! estimate can pre-fill the ripeness of the harvest entered next: the web
+//! form passes its `ripeness_estimate_id`, and the LINE chatbot uses the
+//! member's latest unused photo for a `harvest` command without a ripe
+//! percentage. The percentages entered always win; a harvest whose ripeness
+//! differs from its estimate is marked as overridden.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::external::ai_defect_detection::{AiDefectDetectionClient, EstimateRipenessRequest};
+use crate::services::harvest::RipenessAssessment;
+
+/// Minutes a chatbot photo estimate pre-fills the member's next harvest
+pub const RIPENESS_ESTIMATE_VALID_MINUTES: i64 = 60;
+
+/// Largest basket photo accepted, in bytes
+pub const MAX_RIPENESS_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Ripeness estimate service
+#[derive(Clone)]
+pub struct RipenessEstimateService {
+    db: PgPool,
+    ai_client: Option<AiDefectDetectionClient>,
+}
+
+/// Stored ripeness estimate of a basket photo
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RipenessEstimate {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub user_id: Option<Uuid>,
+    /// `web` or `line`
+    pub source: String,
+    pub ai_request_id: String,
+    pub image_url: Option<String>,
+    pub cherries_counted: Option<i32>,
+    pub underripe_percent: i32,
+    pub ripe_percent: i32,
+    pub overripe_percent: i32,
+    pub confidence_score: f32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RipenessEstimate {
+    /// The estimate as a ripeness assessment
+    pub fn assessment(&self) -> RipenessAssessment {
+        RipenessAssessment {
+            underripe_percent: self.underripe_percent,
+            ripe_percent: self.ripe_percent,
+            overripe_percent: self.overripe_percent,
+        }
+    }
+}
+
+/// Input for estimating ripeness from a photo
+#[derive(Debug, Deserialize)]
+pub struct EstimateRipenessInput {
+    pub image_base64: String,
+}
+
+/// Round ripeness shares to whole percentages summing to 100, giving the
+/// points left over to the largest remainders. None when the shares are
+/// negative or all zero
+pub fn whole_percentages(underripe: f64, ripe: f64, overripe: f64) -> Option<RipenessAssessment> {
+    let shares = [underripe, ripe, overripe];
+    if shares.iter().any(|share| !share.is_finite() || *share < 0.0) {
+        return None;
+    }
+    let total: f64 = shares.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let scaled = shares.map(|share| share * 100.0 / total);
+    let mut percents = scaled.map(|share| share.floor() as i32);
+    let mut by_remainder = [0, 1, 2];
+    by_remainder.sort_by(|a, b| (scaled[*b] - scaled[*b].floor()).total_cmp(&(scaled[*a] - scaled[*a].floor())));
+    let left_over = 100 - percents.iter().sum::<i32>();
+    for index in by_remainder.iter().cycle().take(left_over.max(0) as usize) {
+        percents[*index] += 1;
+    }
+
+    Some(RipenessAssessment {
+        underripe_percent: percents[0],
+        ripe_percent: percents[1],
+        overripe_percent: percents[2],
+    })
+}
+
+/// Whether the ripeness entered differs from the estimate it was pre-filled
+/// from
+pub fn ripeness_overridden(estimate: &RipenessAssessment, entered: &RipenessAssessment) -> bool {
+    estimate != entered
+}
+
+/// Whether a chatbot photo estimate taken at `estimated_at` still pre-fills
+/// a harvest recorded at `now`
+pub fn estimate_still_valid(estimated_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - estimated_at <= Duration::minutes(RIPENESS_ESTIMATE_VALID_MINUTES)
+}
+
+impl RipenessEstimateService {
+    /// Create a service using the AI service configured in the environment
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db,
+            ai_client: AiDefectDetectionClient::from_env(),
+        }
+    }
+
+    /// Create with an explicit AI client
+    pub fn with_client(db: PgPool, ai_client: AiDefectDetectionClient) -> Self {
+        Self {
+            db,
+            ai_client: Some(ai_client),
+        }
+    }
+
+    /// Estimate the ripeness of a basket photo and store the estimate
+    pub async fn estimate(
+        &self,
+        business_id: Uuid,
+        user_id: Option<Uuid>,
+        source: &str,
+        input: EstimateRipenessInput,
+    ) -> AppResult<RipenessEstimate> {
+        let image = BASE64.decode(input.image_base64.trim()).map_err(|_| AppError::Validation {
+            field: "image_base64".to_string(),
+            message: "Image must be base64 encoded".to_string(),
+            message_th: "รูปภาพต้องเข้ารหัสแบบ base64".to_string(),
+        })?;
+        if image.is_empty() || image.len() > MAX_RIPENESS_IMAGE_BYTES {
+            return Err(AppError::Validation {
+                field: "image_base64".to_string(),
+                message: format!("Image must be between 1 byte and {} MB", MAX_RIPENESS_IMAGE_BYTES / (1024 * 1024)),
+                message_th: format!("รูปภาพต้องมีขนาดไม่เกิน {} MB", MAX_RIPENESS_IMAGE_BYTES / (1024 * 1024)),
+            });
+        }
+
+        let ai_client = self
+            .ai_client
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("AI detection service not configured".to_string()))?;
+        let response = ai_client
+            .estimate_ripeness(EstimateRipenessRequest {
+                image_base64: BASE64.encode(&image),
+            })
+            .await?;
+
+        let ripeness = whole_percentages(response.underripe_percent, response.ripe_percent, response.overripe_percent)
+            .ok_or_else(|| {
+                AppError::AiDetectionError(format!(
+                    "No cherries recognised in the photo (request {})",
+                    response.request_id
+                ))
+            })?;
+
+        let estimate = sqlx::query_as::<_, RipenessEstimate>(
+            r#"
+            INSERT INTO ripeness_estimates (business_id, user_id, source, ai_request_id, image_url,
+                                            cherries_counted, underripe_percent, ripe_percent,
+                                            overripe_percent, confidence_score)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, business_id, user_id, source, ai_request_id, image_url, cherries_counted,
+                      underripe_percent, ripe_percent, overripe_percent, confidence_score, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(user_id)
+        .bind(source)
+        .bind(&response.request_id)
+        .bind(&response.image_url)
+        .bind(response.cherries_counted)
+        .bind(ripeness.underripe_percent)
+        .bind(ripeness.ripe_percent)
+        .bind(ripeness.overripe_percent)
+        .bind(response.confidence_score)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(estimate)
+    }
+
+    /// Get an estimate of the business
+    pub async fn get_estimate(&self, business_id: Uuid, estimate_id: Uuid) -> AppResult<RipenessEstimate> {
+        sqlx::query_as::<_, RipenessEstimate>(
+            r#"
+            SELECT id, business_id, user_id, source, ai_request_id, image_url, cherries_counted,
+                   underripe_percent, ripe_percent, overripe_percent, confidence_score, created_at
+            FROM ripeness_estimates
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(estimate_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Ripeness estimate".to_string()))
+    }
+
+    /// The member's latest estimate not yet used by a harvest, if still valid
+    pub async fn latest_unused(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> AppResult<Option<RipenessEstimate>> {
+        let estimate = sqlx::query_as::<_, RipenessEstimate>(
+            r#"
+            SELECT e.id, e.business_id, e.user_id, e.source, e.ai_request_id, e.image_url, e.cherries_counted,
+                   e.underripe_percent, e.ripe_percent, e.overripe_percent, e.confidence_score, e.created_at
+            FROM ripeness_estimates e
+            WHERE e.business_id = $1 AND e.user_id = $2
+              AND NOT EXISTS (SELECT 1 FROM harvests h WHERE h.ripeness_estimate_id = e.id)
+            ORDER BY e.created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(business_id)
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(estimate.filter(|estimate| estimate_still_valid(estimate.created_at, now)))
+    }
+}
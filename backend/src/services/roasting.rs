@@ -1,5 +1,7 @@
 //! Roast profile management service for coffee roasting operations
 
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -8,6 +10,7 @@ use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::services::lot::LotStage;
+use shared::{calculate_dtr, calculate_weight_loss, Language};
 
 /// Roasting service for managing roast sessions and profile templates
 #[derive(Clone)]
@@ -149,7 +152,6 @@ pub struct RoastSession {
     pub equipment: Option<String>,
     pub green_bean_weight_kg: Decimal,
     pub initial_moisture_percent: Option<Decimal>,
-    pub temperature_log: Option<serde_json::Value>,
     pub charge_temp_celsius: Option<Decimal>,
     pub turning_point_time_seconds: Option<i32>,
     pub turning_point_temp_celsius: Option<Decimal>,
@@ -196,6 +198,61 @@ pub struct LogTemperatureInput {
     pub checkpoints: Vec<TemperatureCheckpoint>,
 }
 
+/// Roast curve as stored or downsampled
+#[derive(Debug, Clone, Serialize)]
+pub struct TemperatureCurve {
+    pub session_id: Uuid,
+    /// Bucket width the curve was averaged over; `None` for every reading
+    pub resolution_seconds: Option<i32>,
+    /// Readings stored for the session
+    pub total_readings: usize,
+    pub readings: Vec<TemperatureCheckpoint>,
+}
+
+/// Coarsest downsampling resolution
+pub const MAX_RESOLUTION_SECONDS: i32 = 600;
+
+/// Parse a resolution such as `10s`, `2m` or `30` (seconds)
+pub fn parse_resolution(resolution: &str) -> Option<i32> {
+    let resolution = resolution.trim().to_ascii_lowercase();
+    let (number, unit) = match resolution.strip_suffix('m') {
+        Some(minutes) => (minutes, 60),
+        None => (resolution.strip_suffix('s').unwrap_or(&resolution), 1),
+    };
+    let seconds = number.trim().parse::<i32>().ok()?.checked_mul(unit)?;
+    (1..=MAX_RESOLUTION_SECONDS).contains(&seconds).then_some(seconds)
+}
+
+/// Average readings into buckets of `resolution_seconds`, each placed at its
+/// first reading's time. Notes in a bucket (e.g. "First crack") are kept.
+/// Readings must be sorted by time.
+pub fn downsample(readings: &[TemperatureCheckpoint], resolution_seconds: i32) -> Vec<TemperatureCheckpoint> {
+    let mut buckets: Vec<(i32, TemperatureCheckpoint, Decimal)> = Vec::new();
+    for reading in readings {
+        let bucket = reading.time_seconds / resolution_seconds;
+        match buckets.last_mut() {
+            Some((b, point, count)) if *b == bucket => {
+                point.temp_celsius += reading.temp_celsius;
+                *count += Decimal::ONE;
+                if let Some(notes) = &reading.notes {
+                    point.notes = Some(match point.notes.take() {
+                        Some(existing) => format!("{}; {}", existing, notes),
+                        None => notes.clone(),
+                    });
+                }
+            }
+            _ => buckets.push((bucket, reading.clone(), Decimal::ONE)),
+        }
+    }
+    buckets
+        .into_iter()
+        .map(|(_, mut point, count)| {
+            point.temp_celsius = (point.temp_celsius / count).round_dp(1);
+            point
+        })
+        .collect()
+}
+
 /// Input for logging roast milestones
 #[derive(Debug, Deserialize)]
 pub struct LogMilestonesInput {
@@ -477,7 +534,7 @@ impl RoastingService {
         input: StartRoastSessionInput,
     ) -> AppResult<RoastSession> {
         // Validate lot exists and belongs to business
-        let lot = sqlx::query_as::<_, (Uuid, String, Decimal)>(
+        let lot = sqlx::query_as::<_, (Uuid, LotStage, Decimal)>(
             "SELECT id, stage, current_weight_kg FROM lots WHERE id = $1 AND business_id = $2",
         )
         .bind(input.lot_id)
@@ -487,16 +544,16 @@ impl RoastingService {
         .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
 
         // Validate lot is in GreenBean stage
-        if lot.1 != LotStage::GreenBean.as_str() {
+        if lot.1 != LotStage::GreenBean {
             return Err(AppError::Validation {
                 field: "lot_id".to_string(),
                 message: format!(
-                    "Lot must be in GreenBean stage to start roasting, current stage: {}",
+                    "Lot must be in Green Bean stage to start roasting, current stage: {}",
                     lot.1
                 ),
                 message_th: format!(
-                    "ล็อตต้องอยู่ในสถานะกาแฟกะลาเพื่อเริ่มการคั่ว สถานะปัจจุบัน: {}",
-                    lot.1
+                    "ล็อตต้องอยู่ในสถานะสารกาแฟเพื่อเริ่มการคั่ว สถานะปัจจุบัน: {}",
+                    lot.1.name_in(&Language::Thai)
                 ),
             });
         }
@@ -544,7 +601,7 @@ impl RoastingService {
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING id, business_id, lot_id, template_id, session_date, roaster_name,
                       equipment, green_bean_weight_kg, initial_moisture_percent,
-                      temperature_log, charge_temp_celsius,
+                      charge_temp_celsius,
                       turning_point_time_seconds, turning_point_temp_celsius,
                       first_crack_time_seconds, first_crack_temp_celsius,
                       second_crack_time_seconds, second_crack_temp_celsius,
@@ -583,7 +640,7 @@ impl RoastingService {
             r#"
             SELECT id, business_id, lot_id, template_id, session_date, roaster_name,
                    equipment, green_bean_weight_kg, initial_moisture_percent,
-                   temperature_log, charge_temp_celsius,
+                   charge_temp_celsius,
                    turning_point_time_seconds, turning_point_temp_celsius,
                    first_crack_time_seconds, first_crack_temp_celsius,
                    second_crack_time_seconds, second_crack_temp_celsius,
@@ -611,7 +668,7 @@ impl RoastingService {
             r#"
             SELECT id, business_id, lot_id, template_id, session_date, roaster_name,
                    equipment, green_bean_weight_kg, initial_moisture_percent,
-                   temperature_log, charge_temp_celsius,
+                   charge_temp_celsius,
                    turning_point_time_seconds, turning_point_temp_celsius,
                    first_crack_time_seconds, first_crack_temp_celsius,
                    second_crack_time_seconds, second_crack_temp_celsius,
@@ -655,7 +712,7 @@ impl RoastingService {
             r#"
             SELECT id, business_id, lot_id, template_id, session_date, roaster_name,
                    equipment, green_bean_weight_kg, initial_moisture_percent,
-                   temperature_log, charge_temp_celsius,
+                   charge_temp_celsius,
                    turning_point_time_seconds, turning_point_temp_celsius,
                    first_crack_time_seconds, first_crack_temp_celsius,
                    second_crack_time_seconds, second_crack_temp_celsius,
@@ -695,44 +752,98 @@ impl RoastingService {
             });
         }
 
-        // Merge with existing temperature log
-        let mut existing_log: Vec<TemperatureCheckpoint> = session
-            .temperature_log
-            .and_then(|v| serde_json::from_value(v).ok())
-            .unwrap_or_default();
+        if let Some(checkpoint) = input.checkpoints.iter().find(|c| c.time_seconds < 0) {
+            return Err(AppError::Validation {
+                field: "checkpoints".to_string(),
+                message: format!("Reading time cannot be negative: {}", checkpoint.time_seconds),
+                message_th: format!("เวลาของค่าอุณหภูมิต้องไม่ติดลบ: {}", checkpoint.time_seconds),
+            });
+        }
 
-        existing_log.extend(input.checkpoints);
+        // A second logged again replaces the earlier reading
+        let readings: BTreeMap<i32, (Decimal, Option<String>)> = input
+            .checkpoints
+            .into_iter()
+            .map(|c| (c.time_seconds, (c.temp_celsius, c.notes)))
+            .collect();
+        let times: Vec<i32> = readings.keys().copied().collect();
+        let (temps, notes): (Vec<Decimal>, Vec<Option<String>>) = readings.into_values().unzip();
 
-        // Sort by time
-        existing_log.sort_by_key(|c| c.time_seconds);
+        sqlx::query(
+            r#"
+            INSERT INTO roast_temperature_readings (session_id, time_seconds, temp_celsius, notes)
+            SELECT $1, t.time_seconds, t.temp_celsius, t.notes
+            FROM UNNEST($2::INT[], $3::DECIMAL[], $4::TEXT[]) AS t(time_seconds, temp_celsius, notes)
+            ON CONFLICT (session_id, time_seconds)
+            DO UPDATE SET temp_celsius = EXCLUDED.temp_celsius, notes = EXCLUDED.notes
+            "#,
+        )
+        .bind(session_id)
+        .bind(&times)
+        .bind(&temps)
+        .bind(&notes)
+        .execute(&self.db)
+        .await?;
 
-        let temp_log_json = serde_json::to_value(&existing_log)
-            .map_err(|e| AppError::Internal(e.to_string()))?;
+        Ok(session)
+    }
 
-        let updated = sqlx::query_as::<_, RoastSession>(
+    /// Roast curve, averaged over `resolution` (e.g. `10s`, `1m`) when given
+    pub async fn get_temperature_log(
+        &self,
+        business_id: Uuid,
+        session_id: Uuid,
+        resolution: Option<&str>,
+    ) -> AppResult<TemperatureCurve> {
+        let resolution_seconds = match resolution {
+            Some(resolution) => Some(parse_resolution(resolution).ok_or_else(|| AppError::Validation {
+                field: "resolution".to_string(),
+                message: format!(
+                    "Resolution must be 1s to {}s, e.g. 10s or 1m",
+                    MAX_RESOLUTION_SECONDS
+                ),
+                message_th: format!(
+                    "ความละเอียดต้องอยู่ระหว่าง 1 ถึง {} วินาที เช่น 10s หรือ 1m",
+                    MAX_RESOLUTION_SECONDS
+                ),
+            })?),
+            None => None,
+        };
+
+        // Validate session exists
+        let _ = self.get_session(business_id, session_id).await?;
+
+        let readings = sqlx::query_as::<_, (i32, Decimal, Option<String>)>(
             r#"
-            UPDATE roast_sessions
-            SET temperature_log = $1
-            WHERE id = $2
-            RETURNING id, business_id, lot_id, template_id, session_date, roaster_name,
-                      equipment, green_bean_weight_kg, initial_moisture_percent,
-                      temperature_log, charge_temp_celsius,
-                      turning_point_time_seconds, turning_point_temp_celsius,
-                      first_crack_time_seconds, first_crack_temp_celsius,
-                      second_crack_time_seconds, second_crack_temp_celsius,
-                      drop_time_seconds, drop_temp_celsius,
-                      roasted_weight_kg, weight_loss_percent, final_moisture_percent,
-                      development_time_seconds, development_time_ratio,
-                      roast_level, color_value, status, notes, notes_th,
-                      created_at, updated_at, completed_at, created_by
+            SELECT time_seconds, temp_celsius, notes
+            FROM roast_temperature_readings
+            WHERE session_id = $1
+            ORDER BY time_seconds
             "#,
         )
-        .bind(&temp_log_json)
         .bind(session_id)
-        .fetch_one(&self.db)
-        .await?;
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|(time_seconds, temp_celsius, notes)| TemperatureCheckpoint {
+            time_seconds,
+            temp_celsius,
+            notes,
+        })
+        .collect::<Vec<_>>();
+
+        let total_readings = readings.len();
+        let readings = match resolution_seconds {
+            Some(seconds) => downsample(&readings, seconds),
+            None => readings,
+        };
 
-        Ok(updated)
+        Ok(TemperatureCurve {
+            session_id,
+            resolution_seconds,
+            total_readings,
+            readings,
+        })
     }
 
     /// Log roast milestones (turning point, first crack, second crack)
@@ -765,7 +876,7 @@ impl RoastingService {
             WHERE id = $7
             RETURNING id, business_id, lot_id, template_id, session_date, roaster_name,
                       equipment, green_bean_weight_kg, initial_moisture_percent,
-                      temperature_log, charge_temp_celsius,
+                      charge_temp_celsius,
                       turning_point_time_seconds, turning_point_temp_celsius,
                       first_crack_time_seconds, first_crack_temp_celsius,
                       second_crack_time_seconds, second_crack_temp_celsius,
@@ -859,7 +970,7 @@ impl RoastingService {
             WHERE id = $13
             RETURNING id, business_id, lot_id, template_id, session_date, roaster_name,
                       equipment, green_bean_weight_kg, initial_moisture_percent,
-                      temperature_log, charge_temp_celsius,
+                      charge_temp_celsius,
                       turning_point_time_seconds, turning_point_temp_celsius,
                       first_crack_time_seconds, first_crack_temp_celsius,
                       second_crack_time_seconds, second_crack_temp_celsius,
@@ -894,7 +1005,7 @@ impl RoastingService {
             WHERE id = $3
             "#,
         )
-        .bind(LotStage::RoastedBean.as_str())
+        .bind(LotStage::RoastedBean)
         .bind(input.roasted_weight_kg)
         .bind(session.lot_id)
         .execute(&mut *tx)
@@ -932,7 +1043,7 @@ impl RoastingService {
             WHERE id = $4
             RETURNING id, business_id, lot_id, template_id, session_date, roaster_name,
                       equipment, green_bean_weight_kg, initial_moisture_percent,
-                      temperature_log, charge_temp_celsius,
+                      charge_temp_celsius,
                       turning_point_time_seconds, turning_point_temp_celsius,
                       first_crack_time_seconds, first_crack_temp_celsius,
                       second_crack_time_seconds, second_crack_temp_celsius,
@@ -953,7 +1064,8 @@ impl RoastingService {
         Ok(updated)
     }
 
-    /// Get cupping samples linked to a roast session
+    /// Get cupping samples linked to a roast session; samples of a blind
+    /// session are left out until it is revealed
     pub async fn get_session_cuppings(
         &self,
         business_id: Uuid,
@@ -964,15 +1076,19 @@ impl RoastingService {
 
         let samples = sqlx::query_as::<_, CuppingSampleSummary>(
             r#"
-            SELECT cs.id, cs.session_id, cs.lot_id, cs.total_score, cs.notes, cs.notes_th,
+            SELECT cs.id, cs.session_id, cs.lot_id, cs.total_score,
+                   cs.tasting_notes AS notes, cs.tasting_notes_th AS notes_th,
                    cs.created_at, l.name as lot_name, l.traceability_code
             FROM cupping_samples cs
+            JOIN cupping_sessions s ON s.id = cs.session_id
             JOIN lots l ON l.id = cs.lot_id
-            WHERE cs.roast_session_id = $1
+            WHERE cs.roast_session_id = $1 AND s.business_id = $2
+              AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)
             ORDER BY cs.created_at DESC
             "#,
         )
         .bind(session_id)
+        .bind(business_id)
         .fetch_all(&self.db)
         .await?;
 
@@ -993,21 +1109,3 @@ pub struct CuppingSampleSummary {
     pub lot_name: String,
     pub traceability_code: String,
 }
-
-/// Calculate weight loss percentage
-/// Formula: ((green_weight - roasted_weight) / green_weight) × 100
-pub fn calculate_weight_loss(green_weight: Decimal, roasted_weight: Decimal) -> Decimal {
-    if green_weight.is_zero() {
-        return Decimal::ZERO;
-    }
-    ((green_weight - roasted_weight) / green_weight) * Decimal::from(100)
-}
-
-/// Calculate development time ratio (DTR)
-/// Formula: (development_time / total_time) × 100
-pub fn calculate_dtr(development_time: i32, total_time: i32) -> Decimal {
-    if total_time <= 0 {
-        return Decimal::ZERO;
-    }
-    (Decimal::from(development_time) / Decimal::from(total_time)) * Decimal::from(100)
-}
@@ -1,7 +1,9 @@
 //! Role management service for custom roles and permissions
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
@@ -22,6 +24,8 @@ pub struct Role {
     pub description: Option<String>,
     pub description_th: Option<String>,
     pub is_system_role: bool,
+    /// Template the role was created from, if any
+    pub template_key: Option<String>,
 }
 
 /// Permission information
@@ -54,6 +58,72 @@ pub struct UpdateRoleInput {
     pub permission_ids: Option<Vec<Uuid>>,
 }
 
+/// Built-in role template with its permission rules
+#[derive(Debug, Serialize)]
+pub struct RoleTemplate {
+    pub key: String,
+    pub name_th: String,
+    pub description: String,
+    pub description_th: String,
+    pub rules: Vec<RoleTemplateRule>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RoleTemplateRow {
+    key: String,
+    name_th: String,
+    description: String,
+    description_th: String,
+}
+
+/// Permission rule of a role template; `*` matches every resource or action
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RoleTemplateRule {
+    #[serde(skip)]
+    pub template_key: String,
+    pub resource: String,
+    pub action: String,
+}
+
+/// Outcome of applying one template to a business
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppliedRoleTemplate {
+    pub template_key: String,
+    pub role_id: Option<Uuid>,
+    /// created, updated, unchanged or skipped (name taken by a custom role)
+    pub status: String,
+    pub permissions_added: i32,
+}
+
+/// Field hidden from a role's API responses
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FieldVisibilityPolicy {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub role_id: Uuid,
+    pub resource: String,
+    pub field: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for hiding a field from a role
+#[derive(Debug, Deserialize)]
+pub struct CreateFieldVisibilityInput {
+    /// API resource (e.g. "inventory"); defaults to every resource
+    pub resource: Option<String>,
+    pub field: String,
+}
+
+/// Whether a resource or field name is a plain snake_case identifier
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 100
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
 /// Role with its permissions
 #[derive(Debug, Serialize)]
 pub struct RoleWithPermissions {
@@ -72,7 +142,7 @@ impl RoleService {
     pub async fn get_roles(&self, business_id: Uuid) -> AppResult<Vec<Role>> {
         let roles = sqlx::query_as::<_, Role>(
             r#"
-            SELECT id, business_id, name, name_th, description, description_th, is_system_role
+            SELECT id, business_id, name, name_th, description, description_th, is_system_role, template_key
             FROM roles
             WHERE business_id = $1
             ORDER BY is_system_role DESC, name ASC
@@ -94,7 +164,7 @@ impl RoleService {
         // Get role
         let role = sqlx::query_as::<_, Role>(
             r#"
-            SELECT id, business_id, name, name_th, description, description_th, is_system_role
+            SELECT id, business_id, name, name_th, description, description_th, is_system_role, template_key
             FROM roles
             WHERE id = $1 AND business_id = $2
             "#,
@@ -144,8 +214,7 @@ impl RoleService {
         input: CreateRoleInput,
     ) -> AppResult<RoleWithPermissions> {
         // Validate role name doesn't conflict with system roles
-        let system_names = ["owner", "manager", "worker"];
-        if system_names.contains(&input.name.to_lowercase().as_str()) {
+        if self.is_reserved_role_name(&input.name).await? {
             return Err(AppError::Validation {
                 field: "name".to_string(),
                 message: "Cannot use reserved role name".to_string(),
@@ -233,7 +302,7 @@ impl RoleService {
     ) -> AppResult<RoleWithPermissions> {
         // Get existing role
         let existing = sqlx::query_as::<_, Role>(
-            "SELECT id, business_id, name, name_th, description, description_th, is_system_role FROM roles WHERE id = $1 AND business_id = $2",
+            "SELECT id, business_id, name, name_th, description, description_th, is_system_role, template_key FROM roles WHERE id = $1 AND business_id = $2",
         )
         .bind(role_id)
         .bind(business_id)
@@ -252,8 +321,7 @@ impl RoleService {
 
         // Validate new name if provided
         if let Some(ref name) = input.name {
-            let system_names = ["owner", "manager", "worker"];
-            if system_names.contains(&name.to_lowercase().as_str()) {
+            if self.is_reserved_role_name(name).await? {
                 return Err(AppError::Validation {
                     field: "name".to_string(),
                     message: "Cannot use reserved role name".to_string(),
@@ -356,7 +424,7 @@ impl RoleService {
     pub async fn delete_role(&self, business_id: Uuid, role_id: Uuid) -> AppResult<()> {
         // Check if role exists and is not a system role
         let role = sqlx::query_as::<_, Role>(
-            "SELECT id, business_id, name, name_th, description, description_th, is_system_role FROM roles WHERE id = $1 AND business_id = $2",
+            "SELECT id, business_id, name, name_th, description, description_th, is_system_role, template_key FROM roles WHERE id = $1 AND business_id = $2",
         )
         .bind(role_id)
         .bind(business_id)
@@ -396,4 +464,194 @@ impl RoleService {
 
         Ok(())
     }
+
+    /// Whether a role name is reserved for system roles and role templates
+    async fn is_reserved_role_name(&self, name: &str) -> AppResult<bool> {
+        let reserved = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT LOWER($1) IN ('manager', 'worker')
+                OR EXISTS(SELECT 1 FROM role_templates WHERE key = LOWER($1))
+            "#,
+        )
+        .bind(name)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(reserved)
+    }
+
+    /// Get the built-in role templates with their permission rules
+    pub async fn get_role_templates(&self) -> AppResult<Vec<RoleTemplate>> {
+        let templates = sqlx::query_as::<_, RoleTemplateRow>(
+            r#"
+            SELECT key, name_th, description, description_th
+            FROM role_templates
+            ORDER BY sort_order, key
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let rules = sqlx::query_as::<_, RoleTemplateRule>(
+            r#"
+            SELECT template_key, resource, action
+            FROM role_template_permissions
+            ORDER BY template_key, resource, action
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut rules_by_template: HashMap<String, Vec<RoleTemplateRule>> = HashMap::new();
+        for rule in rules {
+            rules_by_template
+                .entry(rule.template_key.clone())
+                .or_default()
+                .push(rule);
+        }
+
+        Ok(templates
+            .into_iter()
+            .map(|t| RoleTemplate {
+                rules: rules_by_template.remove(&t.key).unwrap_or_default(),
+                key: t.key,
+                name_th: t.name_th,
+                description: t.description,
+                description_th: t.description_th,
+            })
+            .collect())
+    }
+
+    /// Create missing template roles and grant template permissions that were
+    /// introduced since the roles were created. Existing grants are never removed.
+    pub async fn apply_role_templates(&self, business_id: Uuid) -> AppResult<Vec<AppliedRoleTemplate>> {
+        let applied = sqlx::query_as::<_, AppliedRoleTemplate>(
+            r#"
+            SELECT applied_template AS template_key, applied_role_id AS role_id,
+                   status, permissions_added
+            FROM apply_role_templates($1)
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(applied)
+    }
+
+    /// List field visibility policies for a business
+    pub async fn get_field_visibility_policies(
+        &self,
+        business_id: Uuid,
+    ) -> AppResult<Vec<FieldVisibilityPolicy>> {
+        let policies = sqlx::query_as::<_, FieldVisibilityPolicy>(
+            r#"
+            SELECT id, business_id, role_id, resource, field, created_by, created_at
+            FROM field_visibility_policies
+            WHERE business_id = $1
+            ORDER BY role_id, resource, field
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(policies)
+    }
+
+    /// Hide a response field from a role
+    pub async fn create_field_visibility_policy(
+        &self,
+        business_id: Uuid,
+        created_by: Uuid,
+        role_id: Uuid,
+        input: CreateFieldVisibilityInput,
+    ) -> AppResult<FieldVisibilityPolicy> {
+        let resource = input.resource.unwrap_or_else(|| "*".to_string());
+        if resource != "*" && !is_identifier(&resource) {
+            return Err(AppError::Validation {
+                field: "resource".to_string(),
+                message: "Resource must be '*' or an API resource name such as 'inventory'".to_string(),
+                message_th: "ทรัพยากรต้องเป็น '*' หรือชื่อทรัพยากรของ API เช่น 'inventory'".to_string(),
+            });
+        }
+        if !is_identifier(&input.field) {
+            return Err(AppError::Validation {
+                field: "field".to_string(),
+                message: "Field must be a snake_case field name such as 'unit_price'".to_string(),
+                message_th: "ชื่อฟิลด์ต้องเป็นรูปแบบ snake_case เช่น 'unit_price'".to_string(),
+            });
+        }
+
+        let role_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM roles WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(role_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        if !role_exists {
+            return Err(AppError::NotFound("Role".to_string()));
+        }
+
+        sqlx::query_as::<_, FieldVisibilityPolicy>(
+            r#"
+            INSERT INTO field_visibility_policies (business_id, role_id, resource, field, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (role_id, resource, field) DO NOTHING
+            RETURNING id, business_id, role_id, resource, field, created_by, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(role_id)
+        .bind(&resource)
+        .bind(&input.field)
+        .bind(created_by)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::Conflict {
+            resource: "field_visibility_policy".to_string(),
+            message: "Field is already hidden for this role".to_string(),
+            message_th: "ฟิลด์นี้ถูกซ่อนสำหรับบทบาทนี้อยู่แล้ว".to_string(),
+        })
+    }
+
+    /// Remove a field visibility policy
+    pub async fn delete_field_visibility_policy(
+        &self,
+        business_id: Uuid,
+        policy_id: Uuid,
+    ) -> AppResult<()> {
+        let result = sqlx::query(
+            "DELETE FROM field_visibility_policies WHERE id = $1 AND business_id = $2",
+        )
+        .bind(policy_id)
+        .bind(business_id)
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Field visibility policy".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Fields hidden from a role on a resource (including '*' policies)
+    pub async fn get_hidden_fields(&self, role_id: Uuid, resource: &str) -> AppResult<Vec<String>> {
+        let fields = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT field
+            FROM field_visibility_policies
+            WHERE role_id = $1 AND (resource = '*' OR resource = $2)
+            "#,
+        )
+        .bind(role_id)
+        .bind(resource)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(fields)
+    }
 }
@@ -0,0 +1,262 @@
+//! Sales leads
+//!
+//! Buyer contacts a business has not yet closed a sale with. Leads come in
+//! from marketplace inquiries and move from new through contacted,
+//! qualified and negotiating to won or lost. The business owner is notified
+//! of every new lead.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::notification::{create_sales_lead_notification, NotificationService};
+
+/// Longest message a buyer can leave with a lead
+pub const MAX_MESSAGE_CHARS: usize = 4000;
+
+/// Sales service
+#[derive(Clone)]
+pub struct SalesService {
+    db: PgPool,
+}
+
+/// Where a lead stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadStatus {
+    New,
+    Contacted,
+    Qualified,
+    Negotiating,
+    Won,
+    Lost,
+}
+
+impl LeadStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeadStatus::New => "new",
+            LeadStatus::Contacted => "contacted",
+            LeadStatus::Qualified => "qualified",
+            LeadStatus::Negotiating => "negotiating",
+            LeadStatus::Won => "won",
+            LeadStatus::Lost => "lost",
+        }
+    }
+}
+
+/// Buyer lead
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SalesLead {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub source: String,
+    pub listing_id: Option<Uuid>,
+    pub lot_id: Option<Uuid>,
+    pub traceability_code: Option<String>,
+    pub buyer_name: String,
+    pub buyer_company: Option<String>,
+    pub buyer_email: Option<String>,
+    pub buyer_phone: Option<String>,
+    pub buyer_country: Option<String>,
+    pub requested_quantity_kg: Option<Decimal>,
+    pub message: Option<String>,
+    pub status: String,
+    /// Secret in the buyer's link to the negotiation thread
+    pub buyer_access_token: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// New lead; the buyer must leave an email or a phone number
+#[derive(Debug, Clone)]
+pub struct CreateLeadInput {
+    pub source: &'static str,
+    pub listing_id: Option<Uuid>,
+    pub lot_id: Option<Uuid>,
+    pub buyer_name: String,
+    pub buyer_company: Option<String>,
+    pub buyer_email: Option<String>,
+    pub buyer_phone: Option<String>,
+    pub buyer_country: Option<String>,
+    pub requested_quantity_kg: Option<Decimal>,
+    pub message: Option<String>,
+}
+
+/// Query parameters for listing leads
+#[derive(Debug, Deserialize)]
+pub struct LeadQuery {
+    pub status: Option<LeadStatus>,
+}
+
+/// Input for moving a lead along
+#[derive(Debug, Deserialize)]
+pub struct UpdateLeadStatusInput {
+    pub status: LeadStatus,
+}
+
+const LEAD_COLUMNS: &str = r#"
+    sl.id, sl.business_id, sl.source, sl.listing_id, sl.lot_id, l.traceability_code,
+    sl.buyer_name, sl.buyer_company, sl.buyer_email, sl.buyer_phone, sl.buyer_country,
+    sl.requested_quantity_kg, sl.message, sl.status, sl.buyer_access_token, sl.created_at, sl.updated_at
+"#;
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Trimmed text, `None` when blank
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl SalesService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a lead for a business
+    pub async fn create_lead(&self, business_id: Uuid, input: CreateLeadInput) -> AppResult<SalesLead> {
+        let buyer_name = input.buyer_name.trim();
+        if buyer_name.is_empty() {
+            return Err(validation("buyer_name", "Your name is required", "ต้องระบุชื่อ"));
+        }
+        let buyer_email = non_blank(input.buyer_email);
+        let buyer_phone = non_blank(input.buyer_phone);
+        if buyer_email.is_none() && buyer_phone.is_none() {
+            return Err(validation(
+                "buyer_email",
+                "Leave an email or phone number so the producer can reply",
+                "กรุณาระบุอีเมลหรือเบอร์โทรศัพท์เพื่อให้ผู้ผลิตติดต่อกลับ",
+            ));
+        }
+        if let Some(email) = &buyer_email {
+            if !email.contains('@') {
+                return Err(validation("buyer_email", "Email address is not valid", "อีเมลไม่ถูกต้อง"));
+            }
+        }
+        let limits = [
+            ("buyer_name", Some(buyer_name), 255),
+            ("buyer_company", input.buyer_company.as_deref(), 255),
+            ("buyer_email", buyer_email.as_deref(), 255),
+            ("buyer_phone", buyer_phone.as_deref(), 50),
+            ("buyer_country", input.buyer_country.as_deref(), 100),
+            ("message", input.message.as_deref(), MAX_MESSAGE_CHARS),
+        ];
+        for (field, value, max) in limits {
+            if value.is_some_and(|v| v.trim().chars().count() > max) {
+                return Err(AppError::Validation {
+                    field: field.to_string(),
+                    message: format!("At most {} characters", max),
+                    message_th: format!("ไม่เกิน {} ตัวอักษร", max),
+                });
+            }
+        }
+        if input.requested_quantity_kg.is_some_and(|q| q <= Decimal::ZERO) {
+            return Err(validation(
+                "requested_quantity_kg",
+                "Requested quantity must be greater than 0",
+                "ปริมาณที่ต้องการต้องมากกว่า 0",
+            ));
+        }
+
+        let lead = sqlx::query_as::<_, SalesLead>(&format!(
+            r#"
+            WITH sl AS (
+                INSERT INTO sales_leads (
+                    business_id, source, listing_id, lot_id, buyer_name, buyer_company, buyer_email,
+                    buyer_phone, buyer_country, requested_quantity_kg, message, buyer_access_token
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                RETURNING *
+            )
+            SELECT {}
+            FROM sl
+            LEFT JOIN lots l ON l.id = sl.lot_id
+            "#,
+            LEAD_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(input.source)
+        .bind(input.listing_id)
+        .bind(input.lot_id)
+        .bind(buyer_name)
+        .bind(non_blank(input.buyer_company))
+        .bind(buyer_email)
+        .bind(buyer_phone)
+        .bind(non_blank(input.buyer_country))
+        .bind(input.requested_quantity_kg)
+        .bind(non_blank(input.message))
+        .bind(format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()))
+        .fetch_one(&self.db)
+        .await?;
+
+        let notification = create_sales_lead_notification(
+            &lead.buyer_name,
+            lead.traceability_code.as_deref(),
+            "sent an inquiry",
+            "ส่งคำถาม",
+            lead.id,
+        );
+        NotificationService::new(self.db.clone())
+            .notify_business_owner(business_id, notification)
+            .await?;
+
+        Ok(lead)
+    }
+
+    /// Leads of a business, newest first
+    pub async fn list_leads(&self, business_id: Uuid, query: &LeadQuery) -> AppResult<Vec<SalesLead>> {
+        let leads = sqlx::query_as::<_, SalesLead>(&format!(
+            r#"
+            SELECT {}
+            FROM sales_leads sl
+            LEFT JOIN lots l ON l.id = sl.lot_id
+            WHERE sl.business_id = $1 AND ($2::text IS NULL OR sl.status = $2)
+            ORDER BY sl.created_at DESC
+            "#,
+            LEAD_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(query.status.map(|s| s.as_str()))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(leads)
+    }
+
+    /// Move a lead to another status
+    pub async fn update_lead_status(
+        &self,
+        business_id: Uuid,
+        lead_id: Uuid,
+        input: UpdateLeadStatusInput,
+    ) -> AppResult<SalesLead> {
+        sqlx::query_as::<_, SalesLead>(&format!(
+            r#"
+            WITH sl AS (
+                UPDATE sales_leads SET status = $3
+                WHERE id = $2 AND business_id = $1
+                RETURNING *
+            )
+            SELECT {}
+            FROM sl
+            LEFT JOIN lots l ON l.id = sl.lot_id
+            "#,
+            LEAD_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(lead_id)
+        .bind(input.status.as_str())
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sales lead".to_string()))
+    }
+}
@@ -0,0 +1,561 @@
+//! Offer negotiation on sales leads
+//!
+//! Buyer and producer take turns making offers (quantity and price per kg)
+//! on a lead. At most one offer is open at a time: a new offer from the
+//! other side counters it, a new offer from the same side withdraws it.
+//! Only the other side can accept or reject an open offer. Accepting closes
+//! the lead as won, creates a sales order and reserves the quantity on the
+//! lot. Buyers take part through the access token in their inquiry link;
+//! the producer's owner is notified of every buyer move.
+
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::notification::{create_sales_lead_notification, NotificationService};
+use crate::services::sales::MAX_MESSAGE_CHARS;
+use crate::services::sequence::{SequenceScope, SequenceService};
+
+/// Sales negotiation service
+#[derive(Clone)]
+pub struct SalesNegotiationService {
+    db: PgPool,
+}
+
+/// Side of a negotiation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Party {
+    Buyer,
+    Producer,
+}
+
+impl Party {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Party::Buyer => "buyer",
+            Party::Producer => "producer",
+        }
+    }
+}
+
+/// Answer to an open offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferResponse {
+    Accept,
+    Reject,
+}
+
+/// Status an open offer takes when `actor` makes a new offer
+pub fn superseded_status(open_party: Party, actor: Party) -> &'static str {
+    if open_party == actor {
+        "withdrawn"
+    } else {
+        "countered"
+    }
+}
+
+/// Whether offers can still be made on a lead in `status`
+pub fn lead_is_open(status: &str) -> bool {
+    !matches!(status, "won" | "lost")
+}
+
+/// Total value of an order, rounded to satang/cents
+pub fn order_total(quantity_kg: Decimal, price_per_kg: Decimal) -> Decimal {
+    (quantity_kg * price_per_kg).round_dp(2)
+}
+
+/// Sales order number, e.g. SO-2024-0007
+pub fn order_number(year: i32, sequence: i64) -> String {
+    format!("SO-{}-{:04}", year, sequence)
+}
+
+/// Offer on a lead
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SalesOffer {
+    pub id: Uuid,
+    pub lead_id: Uuid,
+    pub party: String,
+    pub quantity_kg: Decimal,
+    pub price_per_kg: Decimal,
+    pub currency: String,
+    pub message: Option<String>,
+    /// "open", "countered", "accepted", "rejected" or "withdrawn"
+    pub status: String,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for making or countering an offer
+#[derive(Debug, Deserialize)]
+pub struct OfferInput {
+    pub quantity_kg: Decimal,
+    pub price_per_kg: Decimal,
+    pub currency: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Input for answering an offer
+#[derive(Debug, Deserialize)]
+pub struct RespondOfferInput {
+    pub response: OfferResponse,
+}
+
+/// Order created from an accepted offer
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SalesOrder {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub order_number: String,
+    pub lead_id: Option<Uuid>,
+    pub offer_id: Option<Uuid>,
+    pub lot_id: Option<Uuid>,
+    pub buyer_name: String,
+    pub buyer_company: Option<String>,
+    pub buyer_email: Option<String>,
+    pub buyer_phone: Option<String>,
+    pub quantity_kg: Decimal,
+    pub price_per_kg: Decimal,
+    pub currency: String,
+    pub total_amount: Decimal,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Negotiation on a lead as either side sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct NegotiationThread {
+    pub lead_id: Uuid,
+    pub buyer_name: String,
+    pub producer_name: String,
+    pub lot_name: Option<String>,
+    pub traceability_code: Option<String>,
+    pub requested_quantity_kg: Option<Decimal>,
+    pub lead_status: String,
+    pub offers: Vec<SalesOffer>,
+    pub order: Option<SalesOrder>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LeadHeader {
+    id: Uuid,
+    business_id: Uuid,
+    lot_id: Option<Uuid>,
+    buyer_name: String,
+    buyer_company: Option<String>,
+    buyer_email: Option<String>,
+    buyer_phone: Option<String>,
+    producer_name: String,
+    lot_name: Option<String>,
+    traceability_code: Option<String>,
+    requested_quantity_kg: Option<Decimal>,
+    status: String,
+}
+
+const LEAD_HEADER_SQL: &str = r#"
+    SELECT sl.id, sl.business_id, sl.lot_id, sl.buyer_name, sl.buyer_company, sl.buyer_email,
+           sl.buyer_phone, b.name AS producer_name, l.name AS lot_name, l.traceability_code,
+           sl.requested_quantity_kg, sl.status
+    FROM sales_leads sl
+    JOIN businesses b ON b.id = sl.business_id
+    LEFT JOIN lots l ON l.id = sl.lot_id
+"#;
+
+const OFFER_COLUMNS: &str = r#"
+    id, lead_id, party, quantity_kg, price_per_kg, currency, message, status, responded_at, created_at
+"#;
+
+const ORDER_COLUMNS: &str = r#"
+    id, business_id, order_number, lead_id, offer_id, lot_id, buyer_name, buyer_company, buyer_email,
+    buyer_phone, quantity_kg, price_per_kg, currency, total_amount, status, created_at, updated_at
+"#;
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn lead_closed() -> AppError {
+    AppError::Conflict {
+        resource: "sales_lead".to_string(),
+        message: "This negotiation is closed".to_string(),
+        message_th: "การเจรจานี้ปิดแล้ว".to_string(),
+    }
+}
+
+fn validate_offer(input: &OfferInput) -> AppResult<String> {
+    if input.quantity_kg <= Decimal::ZERO {
+        return Err(validation("quantity_kg", "Quantity must be greater than 0", "ปริมาณต้องมากกว่า 0"));
+    }
+    if input.price_per_kg <= Decimal::ZERO {
+        return Err(validation("price_per_kg", "Price must be greater than 0", "ราคาต้องมากกว่า 0"));
+    }
+    if input.message.as_deref().is_some_and(|m| m.chars().count() > MAX_MESSAGE_CHARS) {
+        return Err(AppError::Validation {
+            field: "message".to_string(),
+            message: format!("At most {} characters", MAX_MESSAGE_CHARS),
+            message_th: format!("ไม่เกิน {} ตัวอักษร", MAX_MESSAGE_CHARS),
+        });
+    }
+    let currency = input.currency.as_deref().unwrap_or("THB").trim().to_uppercase();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(validation(
+            "currency",
+            "Currency must be a 3-letter code such as THB or USD",
+            "สกุลเงินต้องเป็นรหัส 3 ตัวอักษร เช่น THB หรือ USD",
+        ));
+    }
+    Ok(currency)
+}
+
+impl SalesNegotiationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    async fn lead_for_producer(&self, business_id: Uuid, lead_id: Uuid) -> AppResult<LeadHeader> {
+        sqlx::query_as::<_, LeadHeader>(&format!("{} WHERE sl.id = $1 AND sl.business_id = $2", LEAD_HEADER_SQL))
+            .bind(lead_id)
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Sales lead".to_string()))
+    }
+
+    async fn lead_for_buyer(&self, token: &str) -> AppResult<LeadHeader> {
+        sqlx::query_as::<_, LeadHeader>(&format!("{} WHERE sl.buyer_access_token = $1", LEAD_HEADER_SQL))
+            .bind(token)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Inquiry".to_string()))
+    }
+
+    async fn thread(&self, lead: LeadHeader) -> AppResult<NegotiationThread> {
+        let offers = sqlx::query_as::<_, SalesOffer>(&format!(
+            "SELECT {} FROM sales_offers WHERE lead_id = $1 ORDER BY created_at",
+            OFFER_COLUMNS
+        ))
+        .bind(lead.id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let order = sqlx::query_as::<_, SalesOrder>(&format!(
+            "SELECT {} FROM sales_orders WHERE lead_id = $1",
+            ORDER_COLUMNS
+        ))
+        .bind(lead.id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(NegotiationThread {
+            lead_id: lead.id,
+            buyer_name: lead.buyer_name,
+            producer_name: lead.producer_name,
+            lot_name: lead.lot_name,
+            traceability_code: lead.traceability_code,
+            requested_quantity_kg: lead.requested_quantity_kg,
+            lead_status: lead.status,
+            offers,
+            order,
+        })
+    }
+
+    /// Negotiation on one of the business's leads
+    pub async fn get_thread(&self, business_id: Uuid, lead_id: Uuid) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_producer(business_id, lead_id).await?;
+        self.thread(lead).await
+    }
+
+    /// Negotiation behind a buyer's inquiry link
+    pub async fn get_buyer_thread(&self, token: &str) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_buyer(token).await?;
+        self.thread(lead).await
+    }
+
+    /// Producer makes or counters an offer
+    pub async fn producer_offer(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        lead_id: Uuid,
+        input: OfferInput,
+    ) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_producer(business_id, lead_id).await?;
+        self.make_offer(&lead, Party::Producer, Some(user_id), input).await?;
+        self.thread(lead).await
+    }
+
+    /// Buyer makes or counters an offer
+    pub async fn buyer_offer(&self, token: &str, input: OfferInput) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_buyer(token).await?;
+        self.make_offer(&lead, Party::Buyer, None, input).await?;
+        self.notify_producer(&lead, "made an offer", "เสนอราคา").await?;
+        self.thread(lead).await
+    }
+
+    /// Producer accepts or rejects the buyer's open offer
+    pub async fn producer_respond(
+        &self,
+        business_id: Uuid,
+        lead_id: Uuid,
+        offer_id: Uuid,
+        input: RespondOfferInput,
+    ) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_producer(business_id, lead_id).await?;
+        self.respond(&lead, Party::Producer, offer_id, input.response).await?;
+        self.thread(lead).await
+    }
+
+    /// Buyer accepts or rejects the producer's open offer
+    pub async fn buyer_respond(
+        &self,
+        token: &str,
+        offer_id: Uuid,
+        input: RespondOfferInput,
+    ) -> AppResult<NegotiationThread> {
+        let lead = self.lead_for_buyer(token).await?;
+        self.respond(&lead, Party::Buyer, offer_id, input.response).await?;
+        let (event, event_th) = match input.response {
+            OfferResponse::Accept => ("accepted your offer", "ยอมรับข้อเสนอของคุณ"),
+            OfferResponse::Reject => ("rejected your offer", "ปฏิเสธข้อเสนอของคุณ"),
+        };
+        self.notify_producer(&lead, event, event_th).await?;
+        self.thread(lead).await
+    }
+
+    /// Sales orders of the business, newest first
+    pub async fn list_orders(&self, business_id: Uuid) -> AppResult<Vec<SalesOrder>> {
+        let orders = sqlx::query_as::<_, SalesOrder>(&format!(
+            "SELECT {} FROM sales_orders WHERE business_id = $1 ORDER BY created_at DESC",
+            ORDER_COLUMNS
+        ))
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(orders)
+    }
+
+    async fn notify_producer(&self, lead: &LeadHeader, event: &str, event_th: &str) -> AppResult<()> {
+        let notification = create_sales_lead_notification(
+            &lead.buyer_name,
+            lead.traceability_code.as_deref(),
+            event,
+            event_th,
+            lead.id,
+        );
+        NotificationService::new(self.db.clone())
+            .notify_business_owner(lead.business_id, notification)
+            .await?;
+        Ok(())
+    }
+
+    async fn make_offer(
+        &self,
+        lead: &LeadHeader,
+        actor: Party,
+        user_id: Option<Uuid>,
+        input: OfferInput,
+    ) -> AppResult<()> {
+        let currency = validate_offer(&input)?;
+
+        let mut tx = self.db.begin().await?;
+
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM sales_leads WHERE id = $1 FOR UPDATE")
+            .bind(lead.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !lead_is_open(&status) {
+            return Err(lead_closed());
+        }
+
+        let open = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, party FROM sales_offers WHERE lead_id = $1 AND status = 'open'",
+        )
+        .bind(lead.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some((open_id, open_party)) = open {
+            let open_party = if open_party == "buyer" { Party::Buyer } else { Party::Producer };
+            sqlx::query("UPDATE sales_offers SET status = $2, responded_at = NOW() WHERE id = $1")
+                .bind(open_id)
+                .bind(superseded_status(open_party, actor))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO sales_offers (
+                lead_id, business_id, party, quantity_kg, price_per_kg, currency, message, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(lead.id)
+        .bind(lead.business_id)
+        .bind(actor.as_str())
+        .bind(input.quantity_kg)
+        .bind(input.price_per_kg)
+        .bind(&currency)
+        .bind(input.message.as_deref().map(str::trim).filter(|m| !m.is_empty()))
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE sales_leads SET status = 'negotiating' WHERE id = $1")
+            .bind(lead.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn respond(
+        &self,
+        lead: &LeadHeader,
+        actor: Party,
+        offer_id: Uuid,
+        response: OfferResponse,
+    ) -> AppResult<()> {
+        // Allocated up front; an unused number only leaves a gap
+        let order_number = match response {
+            OfferResponse::Accept => {
+                let year = Utc::now().year();
+                let sequence = SequenceService::new(self.db.clone())
+                    .next(lead.business_id, SequenceScope::SalesOrder, &year.to_string())
+                    .await?;
+                Some(order_number(year, sequence))
+            }
+            OfferResponse::Reject => None,
+        };
+
+        let mut tx = self.db.begin().await?;
+
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM sales_leads WHERE id = $1 FOR UPDATE")
+            .bind(lead.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !lead_is_open(&status) {
+            return Err(lead_closed());
+        }
+
+        let offer = sqlx::query_as::<_, SalesOffer>(&format!(
+            "SELECT {} FROM sales_offers WHERE id = $1 AND lead_id = $2",
+            OFFER_COLUMNS
+        ))
+        .bind(offer_id)
+        .bind(lead.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Offer".to_string()))?;
+
+        if offer.status != "open" {
+            return Err(AppError::Conflict {
+                resource: "sales_offer".to_string(),
+                message: "This offer is no longer open".to_string(),
+                message_th: "ข้อเสนอนี้ไม่เปิดรับแล้ว".to_string(),
+            });
+        }
+        if offer.party == actor.as_str() {
+            return Err(validation(
+                "offer_id",
+                "You cannot answer your own offer",
+                "ไม่สามารถตอบข้อเสนอของตนเองได้",
+            ));
+        }
+
+        let Some(order_number) = order_number else {
+            sqlx::query("UPDATE sales_offers SET status = 'rejected', responded_at = NOW() WHERE id = $1")
+                .bind(offer.id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        if let Some(lot_id) = lead.lot_id {
+            let available = sqlx::query_scalar::<_, Decimal>(
+                r#"
+                SELECT l.current_weight_kg - COALESCE((
+                    SELECT SUM(quantity_kg) FROM lot_reservations
+                    WHERE lot_id = l.id AND (reserved_until IS NULL OR reserved_until >= CURRENT_DATE)
+                ), 0)
+                FROM lots l
+                WHERE l.id = $1
+                FOR UPDATE
+                "#,
+            )
+            .bind(lot_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if offer.quantity_kg > available {
+                return Err(AppError::Validation {
+                    field: "quantity_kg".to_string(),
+                    message: format!("Only {} kg of this lot is unreserved", available.max(Decimal::ZERO)),
+                    message_th: format!("ล็อตนี้เหลือที่ยังไม่ถูกจองเพียง {} กก.", available.max(Decimal::ZERO)),
+                });
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO lot_reservations (business_id, lot_id, quantity_kg, reserved_for, notes)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(lead.business_id)
+            .bind(lot_id)
+            .bind(offer.quantity_kg)
+            .bind(&order_number)
+            .bind(format!("Sales order for {}", lead.buyer_name))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE sales_offers SET status = 'accepted', responded_at = NOW() WHERE id = $1")
+            .bind(offer.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE sales_leads SET status = 'won' WHERE id = $1")
+            .bind(lead.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sales_orders (
+                business_id, order_number, lead_id, offer_id, lot_id, buyer_name, buyer_company,
+                buyer_email, buyer_phone, quantity_kg, price_per_kg, currency, total_amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(lead.business_id)
+        .bind(&order_number)
+        .bind(lead.id)
+        .bind(offer.id)
+        .bind(lead.lot_id)
+        .bind(&lead.buyer_name)
+        .bind(&lead.buyer_company)
+        .bind(&lead.buyer_email)
+        .bind(&lead.buyer_phone)
+        .bind(offer.quantity_kg)
+        .bind(offer.price_per_kg)
+        .bind(&offer.currency)
+        .bind(order_total(offer.quantity_kg, offer.price_per_kg))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
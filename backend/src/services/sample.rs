@@ -0,0 +1,345 @@
+//! Lab sample tracking
+//!
+//! A sample is a physical amount of coffee drawn from a lot for grading,
+//! cupping or a buyer. Drawing one takes its weight off the lot and records
+//! a `sample` inventory transaction in the same database transaction, so
+//! the ledger accounts for every gram that leaves for the lab. A sample
+//! then moves forward through pending grading, graded, cupped and sent to
+//! buyer, and may skip steps (a buyer's sample can go out ungraded) but
+//! never goes back.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Sample service
+#[derive(Clone)]
+pub struct SampleService {
+    db: PgPool,
+}
+
+/// Why a sample was drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SamplePurpose {
+    Grading,
+    Cupping,
+    Buyer,
+    Other,
+}
+
+/// Where a sample is in the lab workflow, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SampleStatus {
+    PendingGrading,
+    Graded,
+    Cupped,
+    SentToBuyer,
+}
+
+impl SampleStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SampleStatus::PendingGrading => "pending_grading",
+            SampleStatus::Graded => "graded",
+            SampleStatus::Cupped => "cupped",
+            SampleStatus::SentToBuyer => "sent_to_buyer",
+        }
+    }
+}
+
+/// Sample drawn from a lot
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LotSample {
+    pub id: Uuid,
+    pub lot_id: Uuid,
+    pub lot_name: String,
+    pub traceability_code: String,
+    pub weight_grams: i32,
+    pub purpose: SamplePurpose,
+    pub recipient: Option<String>,
+    pub status: SampleStatus,
+    /// The `sample` inventory transaction that took the weight off the lot
+    pub inventory_transaction_id: Option<Uuid>,
+    pub grading_id: Option<Uuid>,
+    pub cupping_sample_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub drawn_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const SAMPLE_SELECT: &str = r#"
+    SELECT s.id, s.lot_id, l.name AS lot_name, l.traceability_code, s.weight_grams, s.purpose,
+           s.recipient, s.status, s.inventory_transaction_id, s.grading_id, s.cupping_sample_id,
+           s.notes, s.drawn_at, s.sent_at, s.created_by, s.created_at, s.updated_at
+    FROM lot_samples s
+    JOIN lots l ON l.id = s.lot_id
+"#;
+
+/// Input for drawing a sample from a lot
+#[derive(Debug, Deserialize)]
+pub struct DrawSampleInput {
+    pub lot_id: Uuid,
+    pub weight_grams: i32,
+    pub purpose: SamplePurpose,
+    /// Lab, cupper or buyer the sample is for
+    pub recipient: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Input for moving a sample on in the workflow
+#[derive(Debug, Deserialize)]
+pub struct UpdateSampleStatusInput {
+    pub status: SampleStatus,
+    /// Grading made from the sample, when it is graded
+    pub grading_id: Option<Uuid>,
+    /// Cupping sample it was cupped as, when it is cupped
+    pub cupping_sample_id: Option<Uuid>,
+    /// Replaces the recipient, e.g. the buyer it is sent to
+    pub recipient: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing samples
+#[derive(Debug, Deserialize)]
+pub struct SampleQuery {
+    pub lot_id: Option<Uuid>,
+    pub status: Option<SampleStatus>,
+}
+
+/// Kilograms of a sample's weight, as inventory quantities are kept
+pub fn sample_quantity_kg(weight_grams: i32) -> Decimal {
+    Decimal::new(i64::from(weight_grams), 3)
+}
+
+/// Check a sample can be drawn from a lot holding `lot_weight_kg`, giving
+/// the kilograms to take off
+pub fn validate_sample_weight(weight_grams: i32, lot_weight_kg: Decimal) -> AppResult<Decimal> {
+    if weight_grams <= 0 {
+        return Err(AppError::Validation {
+            field: "weight_grams".to_string(),
+            message: "Sample weight must be positive".to_string(),
+            message_th: "น้ำหนักตัวอย่างต้องเป็นค่าบวก".to_string(),
+        });
+    }
+    let kg = sample_quantity_kg(weight_grams);
+    if kg > lot_weight_kg {
+        return Err(AppError::Validation {
+            field: "weight_grams".to_string(),
+            message: format!("The lot only holds {} kg", lot_weight_kg.normalize()),
+            message_th: format!("ล็อตมีน้ำหนักเหลือเพียง {} กก.", lot_weight_kg.normalize()),
+        });
+    }
+    Ok(kg)
+}
+
+/// Check a sample may move from `from` to `to`: forward only, skipping
+/// steps allowed
+pub fn validate_status_change(from: SampleStatus, to: SampleStatus) -> AppResult<()> {
+    if to <= from {
+        return Err(AppError::Validation {
+            field: "status".to_string(),
+            message: format!("A {} sample cannot move to {}", from.as_str(), to.as_str()),
+            message_th: format!(
+                "ตัวอย่างที่มีสถานะ {} ไม่สามารถเปลี่ยนเป็น {} ได้",
+                from.as_str(),
+                to.as_str()
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Trimmed text, None when blank
+fn clean_text(text: Option<&str>) -> Option<String> {
+    text.map(str::trim).filter(|t| !t.is_empty()).map(str::to_string)
+}
+
+impl SampleService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// List samples, newest first
+    pub async fn list_samples(&self, business_id: Uuid, query: SampleQuery) -> AppResult<Vec<LotSample>> {
+        let samples = sqlx::query_as::<_, LotSample>(&format!(
+            r#"{}
+            WHERE s.business_id = $1
+              AND ($2::uuid IS NULL OR s.lot_id = $2)
+              AND ($3::varchar IS NULL OR s.status = $3)
+            ORDER BY s.drawn_at DESC"#,
+            SAMPLE_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.lot_id)
+        .bind(query.status.map(|s| s.as_str()))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(samples)
+    }
+
+    /// Get one sample
+    pub async fn get_sample(&self, business_id: Uuid, sample_id: Uuid) -> AppResult<LotSample> {
+        sqlx::query_as::<_, LotSample>(&format!(
+            "{} WHERE s.id = $1 AND s.business_id = $2",
+            SAMPLE_SELECT
+        ))
+        .bind(sample_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sample".to_string()))
+    }
+
+    /// Draw a sample from a lot, taking its weight off the lot with a
+    /// `sample` inventory transaction
+    pub async fn draw_sample(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: DrawSampleInput,
+    ) -> AppResult<LotSample> {
+        let mut tx = self.db.begin().await?;
+
+        let (lot_weight_kg, stage) = sqlx::query_as::<_, (Decimal, String)>(
+            "SELECT current_weight_kg, stage FROM lots WHERE id = $1 AND business_id = $2 FOR UPDATE",
+        )
+        .bind(input.lot_id)
+        .bind(business_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        let quantity_kg = validate_sample_weight(input.weight_grams, lot_weight_kg)?;
+        let recipient = clean_text(input.recipient.as_deref());
+        let notes = clean_text(input.notes.as_deref());
+
+        let sample_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO lot_samples (business_id, lot_id, weight_grams, purpose, recipient, notes, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(input.weight_grams)
+        .bind(input.purpose)
+        .bind(&recipient)
+        .bind(&notes)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let transaction_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO inventory_transactions (
+                business_id, lot_id, transaction_type, quantity_kg, quantity_original, quantity_unit,
+                direction, stage, reference_type, reference_id, counterparty_name,
+                notes, transaction_date, created_by
+            )
+            VALUES ($1, $2, 'sample', $3, $3, 'kg', 'out', $4, 'lot_sample', $5, $6, $7, CURRENT_DATE, $8)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(quantity_kg)
+        .bind(&stage)
+        .bind(sample_id)
+        .bind(&recipient)
+        .bind(&notes)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE lot_samples SET inventory_transaction_id = $2 WHERE id = $1")
+            .bind(sample_id)
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE lots SET current_weight_kg = current_weight_kg - $2, updated_at = NOW() WHERE id = $1")
+            .bind(input.lot_id)
+            .bind(quantity_kg)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.get_sample(business_id, sample_id).await
+    }
+
+    /// Move a sample on in the workflow, linking the grading or cupping
+    /// made from it; either must be of the sample's lot
+    pub async fn update_status(
+        &self,
+        business_id: Uuid,
+        sample_id: Uuid,
+        input: UpdateSampleStatusInput,
+    ) -> AppResult<LotSample> {
+        let sample = self.get_sample(business_id, sample_id).await?;
+        validate_status_change(sample.status, input.status)?;
+
+        if let Some(grading_id) = input.grading_id {
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM green_bean_grades WHERE id = $1 AND lot_id = $2)",
+            )
+            .bind(grading_id)
+            .bind(sample.lot_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !exists {
+                return Err(AppError::NotFound("Grading".to_string()));
+            }
+        }
+
+        if let Some(cupping_sample_id) = input.cupping_sample_id {
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM cupping_samples WHERE id = $1 AND lot_id = $2)",
+            )
+            .bind(cupping_sample_id)
+            .bind(sample.lot_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !exists {
+                return Err(AppError::NotFound("Cupping sample".to_string()));
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE lot_samples
+            SET status = $2::varchar,
+                grading_id = COALESCE($3, grading_id),
+                cupping_sample_id = COALESCE($4, cupping_sample_id),
+                recipient = COALESCE($5, recipient),
+                notes = COALESCE($6, notes),
+                sent_at = CASE WHEN $2::varchar = 'sent_to_buyer' THEN NOW() ELSE sent_at END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(sample_id)
+        .bind(input.status)
+        .bind(input.grading_id)
+        .bind(input.cupping_sample_id)
+        .bind(clean_text(input.recipient.as_deref()))
+        .bind(clean_text(input.notes.as_deref()))
+        .execute(&self.db)
+        .await?;
+
+        self.get_sample(business_id, sample_id).await
+    }
+}
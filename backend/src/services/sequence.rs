@@ -0,0 +1,74 @@
+//! Code sequence allocation
+//!
+//! Numbers embedded in generated codes (lot traceability codes, cupping
+//! sample numbers, sales order and shipment numbers) come from per-business
+//! counters in `code_sequences`. Each value is allocated with a single
+//! atomic upsert, so concurrent requests never compute the same next
+//! number. Values are not reused when the insert that consumed them fails,
+//! so codes may have gaps.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+
+/// What a counter numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceScope {
+    /// Lot traceability codes, one counter per year
+    Lot,
+    /// Sample numbers, one counter per cupping session
+    CuppingSample,
+    /// Sales order numbers, one counter per year
+    SalesOrder,
+    /// Shipment numbers, one counter per year
+    Shipment,
+}
+
+impl SequenceScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SequenceScope::Lot => "lot",
+            SequenceScope::CuppingSample => "cupping_sample",
+            SequenceScope::SalesOrder => "sales_order",
+            SequenceScope::Shipment => "shipment",
+        }
+    }
+}
+
+/// Sequence allocation service
+#[derive(Clone)]
+pub struct SequenceService {
+    db: PgPool,
+}
+
+impl SequenceService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Allocate the next value of a counter
+    pub async fn next(&self, business_id: Uuid, scope: SequenceScope, key: &str) -> AppResult<i64> {
+        self.next_above(business_id, scope, key, 0).await
+    }
+
+    /// Allocate the next value of a counter, greater than `floor` (the
+    /// highest value already in use by rows written outside the counter)
+    pub async fn next_above(
+        &self,
+        business_id: Uuid,
+        scope: SequenceScope,
+        key: &str,
+        floor: i64,
+    ) -> AppResult<i64> {
+        let value = sqlx::query_scalar::<_, i64>("SELECT next_code_sequence($1, $2, $3, $4)")
+            .bind(business_id)
+            .bind(scope.as_str())
+            .bind(key)
+            .bind(floor)
+            .fetch_one(&self.db)
+            .await?;
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,959 @@
+//! Shipment and logistics tracking
+//!
+//! A shipment groups packages of one or more lots, optionally for a sales
+//! order, moving with one carrier from a departure to an arrival port. Its
+//! progress is recorded as milestones (booked, loaded, departed, arrived,
+//! cleared), entered by hand or posted by the carrier to a webhook holding
+//! the shipment's webhook token. Milestones may arrive out of order; the
+//! shipment status is the furthest one reached, and their times must follow
+//! the milestone order. Departure marks the shipment's sales orders shipped.
+//! Buyers follow the shipment through a link holding its share token.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+use crate::services::sequence::{SequenceScope, SequenceService};
+
+/// Shipment service
+#[derive(Clone)]
+pub struct ShipmentService {
+    db: PgPool,
+}
+
+/// How the cargo travels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportMode {
+    Sea,
+    Air,
+    Road,
+    Courier,
+}
+
+impl TransportMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransportMode::Sea => "sea",
+            TransportMode::Air => "air",
+            TransportMode::Road => "road",
+            TransportMode::Courier => "courier",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "sea" => Some(TransportMode::Sea),
+            "air" => Some(TransportMode::Air),
+            "road" => Some(TransportMode::Road),
+            "courier" => Some(TransportMode::Courier),
+            _ => None,
+        }
+    }
+}
+
+/// Step in a shipment's journey, in the order they happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Milestone {
+    /// Space confirmed with the carrier
+    Booked,
+    /// Loaded into the container, aircraft or truck
+    Loaded,
+    Departed,
+    Arrived,
+    /// Released by customs at destination
+    Cleared,
+}
+
+impl Milestone {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Milestone::Booked => "booked",
+            Milestone::Loaded => "loaded",
+            Milestone::Departed => "departed",
+            Milestone::Arrived => "arrived",
+            Milestone::Cleared => "cleared",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "booked" => Some(Milestone::Booked),
+            "loaded" => Some(Milestone::Loaded),
+            "departed" => Some(Milestone::Departed),
+            "arrived" => Some(Milestone::Arrived),
+            "cleared" => Some(Milestone::Cleared),
+            _ => None,
+        }
+    }
+}
+
+/// Milestone named by a carrier event code. Carriers use their own
+/// vocabulary, so common codes (e.g. `ATD`, `VESSEL_DEPARTED`, `Customs
+/// Released`) are accepted besides the milestone names.
+pub fn milestone_from_event(event: &str) -> Option<Milestone> {
+    let code = event.trim().to_lowercase().replace([' ', '-'], "_");
+    match code.as_str() {
+        "booked" | "booking_confirmed" | "bkd" => Some(Milestone::Booked),
+        "loaded" | "loaded_on_vessel" | "container_loaded" | "stuffed" | "lod" => Some(Milestone::Loaded),
+        "departed" | "departure" | "vessel_departed" | "flight_departed" | "dep" | "atd" => {
+            Some(Milestone::Departed)
+        }
+        "arrived" | "arrival" | "vessel_arrived" | "flight_arrived" | "arr" | "ata" => Some(Milestone::Arrived),
+        "cleared" | "customs_cleared" | "customs_released" | "released" | "clr" => Some(Milestone::Cleared),
+        _ => None,
+    }
+}
+
+/// Shipment status: the furthest milestone reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShipmentStatus {
+    /// No milestone recorded yet
+    Planned,
+    Booked,
+    Loaded,
+    Departed,
+    Arrived,
+    Cleared,
+}
+
+impl ShipmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShipmentStatus::Planned => "planned",
+            ShipmentStatus::Booked => "booked",
+            ShipmentStatus::Loaded => "loaded",
+            ShipmentStatus::Departed => "departed",
+            ShipmentStatus::Arrived => "arrived",
+            ShipmentStatus::Cleared => "cleared",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "planned" => Some(ShipmentStatus::Planned),
+            other => Milestone::from_str(other).map(ShipmentStatus::from),
+        }
+    }
+}
+
+impl From<Milestone> for ShipmentStatus {
+    fn from(milestone: Milestone) -> Self {
+        match milestone {
+            Milestone::Booked => ShipmentStatus::Booked,
+            Milestone::Loaded => ShipmentStatus::Loaded,
+            Milestone::Departed => ShipmentStatus::Departed,
+            Milestone::Arrived => ShipmentStatus::Arrived,
+            Milestone::Cleared => ShipmentStatus::Cleared,
+        }
+    }
+}
+
+/// Status of a shipment with these milestones recorded
+pub fn shipment_status(milestones: &[Milestone]) -> ShipmentStatus {
+    milestones
+        .iter()
+        .max()
+        .map(|m| ShipmentStatus::from(*m))
+        .unwrap_or(ShipmentStatus::Planned)
+}
+
+/// The recorded milestone that recording `milestone` at `occurred_at` would
+/// put out of order: an earlier step recorded later, or a later step
+/// recorded earlier. Re-recording a milestone replaces it, so it is ignored.
+pub fn out_of_sequence(
+    recorded: &[(Milestone, DateTime<Utc>)],
+    milestone: Milestone,
+    occurred_at: DateTime<Utc>,
+) -> Option<Milestone> {
+    recorded
+        .iter()
+        .find(|(m, at)| (*m < milestone && *at > occurred_at) || (*m > milestone && *at < occurred_at))
+        .map(|(m, _)| *m)
+}
+
+/// Shipment number, e.g. SH-2024-0007
+pub fn shipment_number(year: i32, sequence: i64) -> String {
+    format!("SH-{}-{:04}", year, sequence)
+}
+
+/// Database row for a shipment
+#[derive(Debug, sqlx::FromRow)]
+struct ShipmentRow {
+    id: Uuid,
+    business_id: Uuid,
+    shipment_number: String,
+    carrier: String,
+    mode: String,
+    tracking_number: Option<String>,
+    vessel: Option<String>,
+    departure_port: String,
+    arrival_port: String,
+    etd: Option<NaiveDate>,
+    eta: Option<NaiveDate>,
+    status: String,
+    share_token: String,
+    webhook_token: String,
+    notes: Option<String>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Packages of one lot on a shipment
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ShipmentItem {
+    pub id: Uuid,
+    pub lot_id: Uuid,
+    pub lot_code: String,
+    pub sales_order_id: Option<Uuid>,
+    pub order_number: Option<String>,
+    pub package_count: i32,
+    pub package_type: Option<String>,
+    pub quantity_kg: Decimal,
+}
+
+/// Database row for a milestone
+#[derive(Debug, sqlx::FromRow)]
+struct MilestoneRow {
+    id: Uuid,
+    milestone: String,
+    occurred_at: DateTime<Utc>,
+    location: Option<String>,
+    note: Option<String>,
+    source: String,
+    recorded_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+/// Recorded milestone
+#[derive(Debug, Clone, Serialize)]
+pub struct ShipmentMilestone {
+    pub id: Uuid,
+    pub milestone: Milestone,
+    pub occurred_at: DateTime<Utc>,
+    pub location: Option<String>,
+    pub note: Option<String>,
+    /// `manual` or `webhook`
+    pub source: String,
+    pub recorded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Shipment with its packages and milestones
+#[derive(Debug, Clone, Serialize)]
+pub struct Shipment {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub shipment_number: String,
+    pub carrier: String,
+    pub mode: TransportMode,
+    pub tracking_number: Option<String>,
+    pub vessel: Option<String>,
+    pub departure_port: String,
+    pub arrival_port: String,
+    pub etd: Option<NaiveDate>,
+    pub eta: Option<NaiveDate>,
+    pub status: ShipmentStatus,
+    pub total_packages: i64,
+    pub total_kg: Decimal,
+    /// Secret for the buyer's tracking link
+    pub share_token: String,
+    /// Secret for the carrier's status webhook
+    pub webhook_token: String,
+    pub notes: Option<String>,
+    pub items: Vec<ShipmentItem>,
+    /// In milestone order
+    pub milestones: Vec<ShipmentMilestone>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Packages of one lot to ship
+#[derive(Debug, Deserialize)]
+pub struct ShipmentItemInput {
+    pub lot_id: Uuid,
+    pub sales_order_id: Option<Uuid>,
+    pub package_count: i32,
+    pub package_type: Option<String>,
+    pub quantity_kg: Decimal,
+}
+
+/// Input for creating a shipment
+#[derive(Debug, Deserialize)]
+pub struct CreateShipmentInput {
+    pub carrier: String,
+    pub mode: TransportMode,
+    pub tracking_number: Option<String>,
+    pub vessel: Option<String>,
+    pub departure_port: String,
+    pub arrival_port: String,
+    pub etd: Option<NaiveDate>,
+    pub eta: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub items: Vec<ShipmentItemInput>,
+}
+
+/// Changes to a shipment's carrier details; omitted fields are kept
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateShipmentInput {
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub vessel: Option<String>,
+    pub etd: Option<NaiveDate>,
+    pub eta: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+/// Milestone entered by a user
+#[derive(Debug, Deserialize)]
+pub struct RecordMilestoneInput {
+    pub milestone: Milestone,
+    /// Defaults to now
+    pub occurred_at: Option<DateTime<Utc>>,
+    pub location: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Status update posted by a carrier
+#[derive(Debug, Deserialize)]
+pub struct CarrierStatusUpdate {
+    /// Milestone name or carrier event code, see [`milestone_from_event`]
+    pub event: String,
+    /// Defaults to the time the update is received
+    pub occurred_at: Option<DateTime<Utc>>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// Revised arrival date
+    pub eta: Option<NaiveDate>,
+}
+
+/// Shipment status returned to the carrier
+#[derive(Debug, Serialize)]
+pub struct CarrierStatusAck {
+    pub shipment_number: String,
+    pub milestone: Milestone,
+    pub status: ShipmentStatus,
+}
+
+/// Filters for listing shipments
+#[derive(Debug, Default, Deserialize)]
+pub struct ShipmentQuery {
+    pub status: Option<ShipmentStatus>,
+    pub lot_id: Option<Uuid>,
+    pub sales_order_id: Option<Uuid>,
+}
+
+/// Packages of a lot as shown to the buyer
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BuyerShipmentItem {
+    pub lot_code: String,
+    pub package_count: i32,
+    pub package_type: Option<String>,
+    pub quantity_kg: Decimal,
+}
+
+/// Milestone as shown to the buyer
+#[derive(Debug, Clone, Serialize)]
+pub struct BuyerMilestone {
+    pub milestone: Milestone,
+    pub occurred_at: DateTime<Utc>,
+    pub location: Option<String>,
+}
+
+/// Shipment as shown through the buyer's link, without internal notes or
+/// order details
+#[derive(Debug, Clone, Serialize)]
+pub struct BuyerShipmentView {
+    pub shipment_number: String,
+    pub shipper: String,
+    pub carrier: String,
+    pub mode: TransportMode,
+    pub tracking_number: Option<String>,
+    pub vessel: Option<String>,
+    pub departure_port: String,
+    pub arrival_port: String,
+    pub etd: Option<NaiveDate>,
+    pub eta: Option<NaiveDate>,
+    pub status: ShipmentStatus,
+    pub items: Vec<BuyerShipmentItem>,
+    pub milestones: Vec<BuyerMilestone>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const SHIPMENT_COLUMNS: &str = r#"
+    id, business_id, shipment_number, carrier, mode, tracking_number, vessel, departure_port,
+    arrival_port, etd, eta, status, share_token, webhook_token, notes, created_by, created_at, updated_at
+"#;
+
+const MILESTONE_ORDER_SQL: &str = r#"
+    ARRAY_POSITION(ARRAY['booked', 'loaded', 'departed', 'arrived', 'cleared']::VARCHAR[], milestone)
+"#;
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Where a milestone update came from
+enum MilestoneSource {
+    Manual(Uuid),
+    Webhook,
+}
+
+impl ShipmentService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Create a shipment with its packages
+    pub async fn create(&self, business_id: Uuid, user_id: Uuid, input: CreateShipmentInput) -> AppResult<Shipment> {
+        let carrier = input.carrier.trim();
+        if carrier.is_empty() {
+            return Err(validation("carrier", "Carrier is required", "ต้องระบุผู้ขนส่ง"));
+        }
+        let departure_port = input.departure_port.trim();
+        if departure_port.is_empty() {
+            return Err(validation("departure_port", "Departure port is required", "ต้องระบุท่าต้นทาง"));
+        }
+        let arrival_port = input.arrival_port.trim();
+        if arrival_port.is_empty() {
+            return Err(validation("arrival_port", "Arrival port is required", "ต้องระบุท่าปลายทาง"));
+        }
+        if let (Some(etd), Some(eta)) = (input.etd, input.eta) {
+            if eta < etd {
+                return Err(validation(
+                    "eta",
+                    "Arrival cannot be before departure",
+                    "วันถึงปลายทางต้องไม่ก่อนวันออกเดินทาง",
+                ));
+            }
+        }
+        if input.items.is_empty() {
+            return Err(validation("items", "A shipment needs at least one lot", "การขนส่งต้องมีอย่างน้อยหนึ่งล็อต"));
+        }
+        for item in &input.items {
+            if item.package_count <= 0 {
+                return Err(validation(
+                    "package_count",
+                    "Package count must be greater than 0",
+                    "จำนวนหีบห่อต้องมากกว่า 0",
+                ));
+            }
+            if item.quantity_kg <= Decimal::ZERO {
+                return Err(validation("quantity_kg", "Quantity must be greater than 0", "ปริมาณต้องมากกว่า 0"));
+            }
+            self.check_item(business_id, item).await?;
+        }
+
+        let year = Utc::now().year();
+        let sequence = SequenceService::new(self.db.clone())
+            .next(business_id, SequenceScope::Shipment, &year.to_string())
+            .await?;
+
+        let mut tx = self.db.begin().await?;
+
+        let shipment_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO shipments (
+                business_id, shipment_number, carrier, mode, tracking_number, vessel,
+                departure_port, arrival_port, etd, eta, share_token, webhook_token, notes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(shipment_number(year, sequence))
+        .bind(carrier)
+        .bind(input.mode.as_str())
+        .bind(non_blank(input.tracking_number))
+        .bind(non_blank(input.vessel))
+        .bind(departure_port)
+        .bind(arrival_port)
+        .bind(input.etd)
+        .bind(input.eta)
+        .bind(new_token())
+        .bind(new_token())
+        .bind(non_blank(input.notes))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for item in input.items {
+            sqlx::query(
+                r#"
+                INSERT INTO shipment_items (
+                    shipment_id, lot_id, sales_order_id, package_count, package_type, quantity_kg
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(shipment_id)
+            .bind(item.lot_id)
+            .bind(item.sales_order_id)
+            .bind(item.package_count)
+            .bind(non_blank(item.package_type))
+            .bind(item.quantity_kg)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        self.get(business_id, shipment_id).await
+    }
+
+    /// The lot and sales order of an item belong to the business, and the
+    /// order ships that lot
+    async fn check_item(&self, business_id: Uuid, item: &ShipmentItemInput) -> AppResult<()> {
+        let lot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(item.lot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !lot_exists {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        if let Some(order_id) = item.sales_order_id {
+            let order_lot = sqlx::query_scalar::<_, Option<Uuid>>(
+                "SELECT lot_id FROM sales_orders WHERE id = $1 AND business_id = $2",
+            )
+            .bind(order_id)
+            .bind(business_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Sales order".to_string()))?;
+            if order_lot.is_some_and(|lot_id| lot_id != item.lot_id) {
+                return Err(validation(
+                    "sales_order_id",
+                    "The sales order ships a different lot",
+                    "คำสั่งขายนี้เป็นของล็อตอื่น",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a shipment with its packages and milestones
+    pub async fn get(&self, business_id: Uuid, shipment_id: Uuid) -> AppResult<Shipment> {
+        let row = sqlx::query_as::<_, ShipmentRow>(&format!(
+            "SELECT {} FROM shipments WHERE id = $1 AND business_id = $2",
+            SHIPMENT_COLUMNS
+        ))
+        .bind(shipment_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shipment".to_string()))?;
+
+        self.assemble(row).await
+    }
+
+    /// List shipments, newest first
+    pub async fn list(&self, business_id: Uuid, query: &ShipmentQuery) -> AppResult<Vec<Shipment>> {
+        let rows = sqlx::query_as::<_, ShipmentRow>(&format!(
+            r#"
+            SELECT {} FROM shipments s
+            WHERE s.business_id = $1
+              AND ($2::VARCHAR IS NULL OR s.status = $2)
+              AND ($3::UUID IS NULL OR EXISTS(
+                  SELECT 1 FROM shipment_items si WHERE si.shipment_id = s.id AND si.lot_id = $3))
+              AND ($4::UUID IS NULL OR EXISTS(
+                  SELECT 1 FROM shipment_items si WHERE si.shipment_id = s.id AND si.sales_order_id = $4))
+            ORDER BY s.created_at DESC
+            "#,
+            SHIPMENT_COLUMNS
+        ))
+        .bind(business_id)
+        .bind(query.status.map(|s| s.as_str()))
+        .bind(query.lot_id)
+        .bind(query.sales_order_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut shipments = Vec::with_capacity(rows.len());
+        for row in rows {
+            shipments.push(self.assemble(row).await?);
+        }
+        Ok(shipments)
+    }
+
+    /// Update the carrier details of a shipment
+    pub async fn update(
+        &self,
+        business_id: Uuid,
+        shipment_id: Uuid,
+        input: UpdateShipmentInput,
+    ) -> AppResult<Shipment> {
+        if input.carrier.as_deref().is_some_and(|c| c.trim().is_empty()) {
+            return Err(validation("carrier", "Carrier is required", "ต้องระบุผู้ขนส่ง"));
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE shipments SET
+                carrier = COALESCE($3, carrier),
+                tracking_number = COALESCE($4, tracking_number),
+                vessel = COALESCE($5, vessel),
+                etd = COALESCE($6, etd),
+                eta = COALESCE($7, eta),
+                notes = COALESCE($8, notes)
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(shipment_id)
+        .bind(business_id)
+        .bind(input.carrier.as_deref().map(str::trim))
+        .bind(non_blank(input.tracking_number))
+        .bind(non_blank(input.vessel))
+        .bind(input.etd)
+        .bind(input.eta)
+        .bind(non_blank(input.notes))
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Shipment".to_string()));
+        }
+
+        self.get(business_id, shipment_id).await
+    }
+
+    /// Record a milestone entered by a user
+    pub async fn record_milestone(
+        &self,
+        business_id: Uuid,
+        shipment_id: Uuid,
+        user_id: Uuid,
+        input: RecordMilestoneInput,
+    ) -> AppResult<Shipment> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM shipments WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(shipment_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Shipment".to_string()));
+        }
+
+        self.apply_milestone(
+            shipment_id,
+            input.milestone,
+            input.occurred_at.unwrap_or_else(Utc::now),
+            non_blank(input.location),
+            non_blank(input.note),
+            MilestoneSource::Manual(user_id),
+        )
+        .await?;
+
+        self.get(business_id, shipment_id).await
+    }
+
+    /// Apply a status update posted by the carrier
+    pub async fn carrier_update(&self, webhook_token: &str, update: CarrierStatusUpdate) -> AppResult<CarrierStatusAck> {
+        let (shipment_id, shipment_number) = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, shipment_number FROM shipments WHERE webhook_token = $1",
+        )
+        .bind(webhook_token)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shipment".to_string()))?;
+
+        let milestone = milestone_from_event(&update.event).ok_or_else(|| {
+            validation(
+                "event",
+                &format!("Unknown shipment event: {}", update.event),
+                &format!("ไม่รู้จักสถานะการขนส่ง: {}", update.event),
+            )
+        })?;
+
+        if let Some(eta) = update.eta {
+            sqlx::query("UPDATE shipments SET eta = $2 WHERE id = $1")
+                .bind(shipment_id)
+                .bind(eta)
+                .execute(&self.db)
+                .await?;
+        }
+
+        let status = self
+            .apply_milestone(
+                shipment_id,
+                milestone,
+                update.occurred_at.unwrap_or_else(Utc::now),
+                non_blank(update.location),
+                non_blank(update.description),
+                MilestoneSource::Webhook,
+            )
+            .await?;
+
+        Ok(CarrierStatusAck {
+            shipment_number,
+            milestone,
+            status,
+        })
+    }
+
+    /// Record or correct a milestone and move the shipment status along
+    async fn apply_milestone(
+        &self,
+        shipment_id: Uuid,
+        milestone: Milestone,
+        occurred_at: DateTime<Utc>,
+        location: Option<String>,
+        note: Option<String>,
+        source: MilestoneSource,
+    ) -> AppResult<ShipmentStatus> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("SELECT id FROM shipments WHERE id = $1 FOR UPDATE")
+            .bind(shipment_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let recorded: Vec<(Milestone, DateTime<Utc>)> = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+            "SELECT milestone, occurred_at FROM shipment_milestones WHERE shipment_id = $1",
+        )
+        .bind(shipment_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .filter_map(|(m, at)| Milestone::from_str(&m).map(|m| (m, at)))
+        .filter(|(m, _)| *m != milestone)
+        .collect();
+
+        if let Some(conflict) = out_of_sequence(&recorded, milestone, occurred_at) {
+            return Err(validation(
+                "occurred_at",
+                &format!(
+                    "The {} time is out of order with the {} time already recorded",
+                    milestone.as_str(),
+                    conflict.as_str()
+                ),
+                &format!(
+                    "เวลา {} ไม่เรียงลำดับกับเวลา {} ที่บันทึกไว้",
+                    milestone.as_str(),
+                    conflict.as_str()
+                ),
+            ));
+        }
+
+        let (source_name, recorded_by) = match source {
+            MilestoneSource::Manual(user_id) => ("manual", Some(user_id)),
+            MilestoneSource::Webhook => ("webhook", None),
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO shipment_milestones (shipment_id, milestone, occurred_at, location, note, source, recorded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (shipment_id, milestone) DO UPDATE SET
+                occurred_at = EXCLUDED.occurred_at,
+                location = COALESCE(EXCLUDED.location, shipment_milestones.location),
+                note = COALESCE(EXCLUDED.note, shipment_milestones.note),
+                source = EXCLUDED.source,
+                recorded_by = EXCLUDED.recorded_by,
+                created_at = NOW()
+            "#,
+        )
+        .bind(shipment_id)
+        .bind(milestone.as_str())
+        .bind(occurred_at)
+        .bind(location)
+        .bind(note)
+        .bind(source_name)
+        .bind(recorded_by)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut reached: Vec<Milestone> = recorded.iter().map(|(m, _)| *m).collect();
+        reached.push(milestone);
+        let status = shipment_status(&reached);
+        sqlx::query("UPDATE shipments SET status = $2 WHERE id = $1")
+            .bind(shipment_id)
+            .bind(status.as_str())
+            .execute(&mut *tx)
+            .await?;
+
+        // Orders leave with the shipment
+        if milestone >= Milestone::Departed {
+            sqlx::query(
+                r#"
+                UPDATE sales_orders SET status = 'shipped'
+                WHERE status = 'confirmed'
+                  AND id IN (SELECT sales_order_id FROM shipment_items WHERE shipment_id = $1)
+                "#,
+            )
+            .bind(shipment_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(status)
+    }
+
+    /// Shipment as seen through the buyer's link
+    pub async fn buyer_view(&self, share_token: &str) -> AppResult<BuyerShipmentView> {
+        let row = sqlx::query_as::<_, ShipmentRow>(&format!(
+            "SELECT {} FROM shipments WHERE share_token = $1",
+            SHIPMENT_COLUMNS
+        ))
+        .bind(share_token)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shipment".to_string()))?;
+        let shipper = sqlx::query_scalar::<_, String>("SELECT name FROM businesses WHERE id = $1")
+            .bind(row.business_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        let items = sqlx::query_as::<_, BuyerShipmentItem>(
+            r#"
+            SELECT l.traceability_code AS lot_code, si.package_count, si.package_type, si.quantity_kg
+            FROM shipment_items si
+            JOIN lots l ON l.id = si.lot_id
+            WHERE si.shipment_id = $1
+            ORDER BY l.traceability_code
+            "#,
+        )
+        .bind(row.id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let milestones = self
+            .milestones(row.id)
+            .await?
+            .into_iter()
+            .map(|m| BuyerMilestone {
+                milestone: m.milestone,
+                occurred_at: m.occurred_at,
+                location: m.location,
+            })
+            .collect();
+
+        Ok(BuyerShipmentView {
+            shipment_number: row.shipment_number,
+            shipper,
+            carrier: row.carrier,
+            // The column is constrained to the known modes
+            mode: TransportMode::from_str(&row.mode).unwrap_or(TransportMode::Sea),
+            tracking_number: row.tracking_number,
+            vessel: row.vessel,
+            departure_port: row.departure_port,
+            arrival_port: row.arrival_port,
+            etd: row.etd,
+            eta: row.eta,
+            status: ShipmentStatus::from_str(&row.status).unwrap_or(ShipmentStatus::Planned),
+            items,
+            milestones,
+            updated_at: row.updated_at,
+        })
+    }
+
+    /// Delete a shipment, keeping it in the recycle bin
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, shipment_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::Shipment, shipment_id)
+            .await
+    }
+
+    async fn milestones(&self, shipment_id: Uuid) -> AppResult<Vec<ShipmentMilestone>> {
+        let rows = sqlx::query_as::<_, MilestoneRow>(&format!(
+            r#"
+            SELECT id, milestone, occurred_at, location, note, source, recorded_by, created_at
+            FROM shipment_milestones
+            WHERE shipment_id = $1
+            ORDER BY {}
+            "#,
+            MILESTONE_ORDER_SQL
+        ))
+        .bind(shipment_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(ShipmentMilestone {
+                    id: row.id,
+                    milestone: Milestone::from_str(&row.milestone)?,
+                    occurred_at: row.occurred_at,
+                    location: row.location,
+                    note: row.note,
+                    source: row.source,
+                    recorded_by: row.recorded_by,
+                    created_at: row.created_at,
+                })
+            })
+            .collect())
+    }
+
+    async fn assemble(&self, row: ShipmentRow) -> AppResult<Shipment> {
+        let items = sqlx::query_as::<_, ShipmentItem>(
+            r#"
+            SELECT si.id, si.lot_id, l.traceability_code AS lot_code, si.sales_order_id, so.order_number,
+                   si.package_count, si.package_type, si.quantity_kg
+            FROM shipment_items si
+            JOIN lots l ON l.id = si.lot_id
+            LEFT JOIN sales_orders so ON so.id = si.sales_order_id
+            WHERE si.shipment_id = $1
+            ORDER BY l.traceability_code
+            "#,
+        )
+        .bind(row.id)
+        .fetch_all(&self.db)
+        .await?;
+        let milestones = self.milestones(row.id).await?;
+
+        Ok(Shipment {
+            id: row.id,
+            business_id: row.business_id,
+            shipment_number: row.shipment_number,
+            carrier: row.carrier,
+            // The columns are constrained to the known values
+            mode: TransportMode::from_str(&row.mode).unwrap_or(TransportMode::Sea),
+            tracking_number: row.tracking_number,
+            vessel: row.vessel,
+            departure_port: row.departure_port,
+            arrival_port: row.arrival_port,
+            etd: row.etd,
+            eta: row.eta,
+            status: ShipmentStatus::from_str(&row.status).unwrap_or(ShipmentStatus::Planned),
+            total_packages: items.iter().map(|i| i64::from(i.package_count)).sum(),
+            total_kg: items.iter().map(|i| i.quantity_kg).sum(),
+            share_token: row.share_token,
+            webhook_token: row.webhook_token,
+            notes: row.notes,
+            items,
+            milestones,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
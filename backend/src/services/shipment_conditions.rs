@@ -0,0 +1,855 @@
+//! Transit conditions of shipments from data logger exports
+//!
+//! Containers carry temperature/humidity data loggers. Their CSV exports
+//! are imported against a shipment, one import per logger serial; importing
+//! the same logger again replaces its readings. Exports often start with a
+//! preamble (device, serial, trip settings) before the header row, which is
+//! found by its time and temperature columns. Times without an offset are
+//! read in the logger's zone (`utc_offset_hours`, Thailand by default).
+//!
+//! Readings are judged against limits given at import: green coffee keeps
+//! best below 30 °C and 70% relative humidity, and temperatures near
+//! freezing cause condensation when the container is opened. Consecutive
+//! readings outside a limit form an excursion, which lasts until the first
+//! reading back inside. The transit-conditions report lists each logger's
+//! statistics, mean kinetic temperature and excursions, marking those
+//! between the departed and arrived milestones.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping_import::{parse_sheet_date, parse_sheet_score};
+
+/// Default lowest temperature, °C
+pub const DEFAULT_TEMP_MIN_C: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+
+/// Default highest temperature, °C
+pub const DEFAULT_TEMP_MAX_C: Decimal = Decimal::from_parts(30, 0, 0, false, 0);
+
+/// Default highest relative humidity, %
+pub const DEFAULT_HUMIDITY_MAX_PCT: Decimal = Decimal::from_parts(70, 0, 0, false, 0);
+
+/// Offset of logger clocks without a zone (Indochina Time)
+pub const DEFAULT_UTC_OFFSET_HOURS: i32 = 7;
+
+/// Readings accepted in one export; three months at 5-minute intervals
+/// within the request body limit
+pub const MAX_LOGGER_READINGS: usize = 30_000;
+
+/// Unreadable rows listed in an import result; the rest are only counted
+const MAX_LISTED_ROW_ERRORS: usize = 50;
+
+/// Activation energy over the gas constant (83.144 kJ/mol / R), in kelvin,
+/// as used for mean kinetic temperature
+const MKT_ACTIVATION_KELVIN: f64 = 10_000.0;
+
+const KELVIN_OFFSET: f64 = 273.15;
+
+/// Shipment conditions service
+#[derive(Clone)]
+pub struct ShipmentConditionsService {
+    db: PgPool,
+}
+
+/// Field a logger export column maps to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoggerField {
+    /// Date and time in one column
+    Timestamp,
+    Date,
+    Time,
+    TemperatureC,
+    TemperatureF,
+    Humidity,
+}
+
+/// Field for a logger export header (case, spacing, units in brackets and
+/// punctuation are ignored)
+pub fn header_field(header: &str) -> Option<LoggerField> {
+    let key: String = header
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    let field = match key.as_str() {
+        "timestamp" | "date_time" | "datetime" | "recorded_at" | "time_stamp" | "date_and_time" | "local_time"
+        | "utc_time" => LoggerField::Timestamp,
+        "date" => LoggerField::Date,
+        "time" => LoggerField::Time,
+        "temperature" | "temperature_c" | "temp" | "temp_c" | "celsius" | "t_c" => LoggerField::TemperatureC,
+        "temperature_f" | "temp_f" | "fahrenheit" | "t_f" => LoggerField::TemperatureF,
+        "humidity" | "humidity_rh" | "rh" | "relative_humidity" | "humidity_percent" | "rh_percent" => {
+            LoggerField::Humidity
+        }
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Clock time, 24-hour or with AM/PM
+fn parse_clock(value: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value.trim(), format).ok())
+}
+
+/// Reading time: RFC 3339, or a date (ISO or day first, B.E. years
+/// converted) and clock time read at `utc_offset_hours`
+pub fn parse_reading_time(value: &str, utc_offset_hours: i32) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let (date, clock) = value.split_once(['T', ' '])?;
+    let local = parse_sheet_date(date)?.and_time(parse_clock(clock)?);
+    FixedOffset::east_opt(utc_offset_hours * 3600)?
+        .from_local_datetime(&local)
+        .single()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Logger serial written in an export's preamble, e.g. `Serial Number:,A1B2`
+pub fn preamble_serial(line: &str) -> Option<String> {
+    if !line.to_lowercase().contains("serial") {
+        return None;
+    }
+    let (_, value) = line.split_once([':', ',', ';', '\t'])?;
+    let value = value.trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '"' | '\t'));
+    (!value.is_empty()).then(|| value.chars().take(100).collect())
+}
+
+/// Column separator of a line: whichever of `,`, `;` and tab is most used
+fn delimiter_of(line: &str) -> u8 {
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| line.matches(*d as char).count())
+        .unwrap_or(b',')
+}
+
+/// Index of each field in a header row, when it has a time and temperature
+fn header_index(line: &str) -> Option<(u8, HashMap<LoggerField, usize>)> {
+    let delimiter = delimiter_of(line);
+    let mut index = HashMap::new();
+    for (i, header) in line.split(delimiter as char).enumerate() {
+        if let Some(field) = header_field(header.trim().trim_matches('"')) {
+            index.entry(field).or_insert(i);
+        }
+    }
+    let has_time = index.contains_key(&LoggerField::Timestamp)
+        || (index.contains_key(&LoggerField::Date) && index.contains_key(&LoggerField::Time));
+    let has_temperature =
+        index.contains_key(&LoggerField::TemperatureC) || index.contains_key(&LoggerField::TemperatureF);
+    (has_time && has_temperature).then_some((delimiter, index))
+}
+
+/// One logged reading
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggerReading {
+    pub recorded_at: DateTime<Utc>,
+    pub temperature_c: Decimal,
+    pub relative_humidity: Option<Decimal>,
+}
+
+/// Row that could not be read
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggerRowError {
+    pub line: u64,
+    pub message: String,
+}
+
+/// Logger export with its readings in time order
+#[derive(Debug)]
+pub struct ParsedLoggerLog {
+    pub serial: Option<String>,
+    pub columns: Vec<LoggerField>,
+    pub readings: Vec<LoggerReading>,
+    pub errors: Vec<LoggerRowError>,
+}
+
+/// Read a logger export. Fails when no header row with a time and
+/// temperature column is found; unreadable rows are reported per line.
+pub fn parse_logger_csv(csv_data: &str, utc_offset_hours: i32) -> AppResult<ParsedLoggerLog> {
+    let csv_data = csv_data.trim_start_matches('\u{feff}');
+    let mut serial = None;
+    let mut header = None;
+    let mut offset = 0;
+    for (number, raw_line) in csv_data.split_inclusive('\n').enumerate() {
+        let line = raw_line.trim_end_matches(['\r', '\n']);
+        if let Some(found) = header_index(line) {
+            header = Some((number as u64, found));
+            break;
+        }
+        serial = serial.or_else(|| preamble_serial(line));
+        offset += raw_line.len();
+    }
+    let Some((preamble_lines, (delimiter, index))) = header else {
+        return Err(AppError::Validation {
+            field: "csv".to_string(),
+            message: "No header row with time and temperature columns was found".to_string(),
+            message_th: "ไม่พบแถวหัวตารางที่มีคอลัมน์เวลาและอุณหภูมิ".to_string(),
+        });
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(&csv_data.as_bytes()[offset..]);
+    reader.headers().map_err(invalid_csv)?;
+
+    let mut by_time = BTreeMap::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(invalid_csv)?;
+        if record.iter().all(|value| value.is_empty()) {
+            continue;
+        }
+        let line = record.position().map(|p| p.line()).unwrap_or_default() + preamble_lines;
+        match read_reading(&record, &index, utc_offset_hours) {
+            Ok(reading) => {
+                // A repeated time keeps the later row
+                by_time.insert(reading.recorded_at, reading);
+            }
+            Err(message) => errors.push(LoggerRowError { line, message }),
+        }
+        if by_time.len() > MAX_LOGGER_READINGS {
+            return Err(AppError::Validation {
+                field: "csv".to_string(),
+                message: format!("A logger export can have at most {} readings", MAX_LOGGER_READINGS),
+                message_th: format!("ไฟล์ข้อมูลจากเครื่องบันทึกมีได้ไม่เกิน {} รายการ", MAX_LOGGER_READINGS),
+            });
+        }
+    }
+
+    let mut columns: Vec<LoggerField> = index.keys().copied().collect();
+    columns.sort_by_key(|field| index[field]);
+
+    Ok(ParsedLoggerLog {
+        serial,
+        columns,
+        readings: by_time.into_values().collect(),
+        errors,
+    })
+}
+
+fn read_reading(
+    record: &csv::StringRecord,
+    index: &HashMap<LoggerField, usize>,
+    utc_offset_hours: i32,
+) -> Result<LoggerReading, String> {
+    let text = |field: LoggerField| {
+        index
+            .get(&field)
+            .and_then(|i| record.get(*i))
+            .filter(|value| !value.is_empty())
+    };
+    let number = |field: LoggerField| -> Result<Option<Decimal>, String> {
+        text(field)
+            .map(|value| parse_sheet_score(value).ok_or_else(|| format!("Not a number: {}", value)))
+            .transpose()
+    };
+
+    let time_text = match (text(LoggerField::Timestamp), text(LoggerField::Date), text(LoggerField::Time)) {
+        (Some(timestamp), _, _) => timestamp.to_string(),
+        (None, Some(date), Some(time)) => format!("{} {}", date, time),
+        _ => return Err("Time is empty".to_string()),
+    };
+    let recorded_at =
+        parse_reading_time(&time_text, utc_offset_hours).ok_or_else(|| format!("Unrecognized time: {}", time_text))?;
+
+    let temperature_c = match (number(LoggerField::TemperatureC)?, number(LoggerField::TemperatureF)?) {
+        (Some(celsius), _) => celsius,
+        (None, Some(fahrenheit)) => ((fahrenheit - Decimal::from(32)) * Decimal::from(5) / Decimal::from(9)).round_dp(2),
+        (None, None) => return Err("Temperature is empty".to_string()),
+    };
+    if temperature_c < Decimal::from(-60) || temperature_c > Decimal::from(90) {
+        return Err(format!("Temperature out of the logger range: {}", temperature_c));
+    }
+
+    let relative_humidity = number(LoggerField::Humidity)?;
+    if relative_humidity.is_some_and(|rh| rh < Decimal::ZERO || rh > Decimal::ONE_HUNDRED) {
+        return Err("Humidity must be between 0 and 100%".to_string());
+    }
+
+    Ok(LoggerReading {
+        recorded_at,
+        temperature_c: temperature_c.round_dp(2),
+        relative_humidity: relative_humidity.map(|rh| rh.round_dp(2)),
+    })
+}
+
+fn invalid_csv(err: csv::Error) -> AppError {
+    AppError::Validation {
+        field: "csv".to_string(),
+        message: format!("Could not read the logger export: {}", err),
+        message_th: format!("ไม่สามารถอ่านไฟล์จากเครื่องบันทึกได้: {}", err),
+    }
+}
+
+/// Limits readings are judged against
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ConditionLimits {
+    pub temp_min_c: Decimal,
+    pub temp_max_c: Decimal,
+    pub humidity_max_pct: Decimal,
+}
+
+impl Default for ConditionLimits {
+    fn default() -> Self {
+        Self {
+            temp_min_c: DEFAULT_TEMP_MIN_C,
+            temp_max_c: DEFAULT_TEMP_MAX_C,
+            humidity_max_pct: DEFAULT_HUMIDITY_MAX_PCT,
+        }
+    }
+}
+
+/// Which limit an excursion broke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExcursionKind {
+    TemperatureHigh,
+    TemperatureLow,
+    HumidityHigh,
+}
+
+impl ExcursionKind {
+    /// The reading's value when it breaks this limit
+    fn breach(&self, reading: &LoggerReading, limits: &ConditionLimits) -> Option<Decimal> {
+        match self {
+            ExcursionKind::TemperatureHigh => Some(reading.temperature_c).filter(|t| *t > limits.temp_max_c),
+            ExcursionKind::TemperatureLow => Some(reading.temperature_c).filter(|t| *t < limits.temp_min_c),
+            ExcursionKind::HumidityHigh => reading.relative_humidity.filter(|rh| *rh > limits.humidity_max_pct),
+        }
+    }
+
+    /// Whether `value` is further past the limit than `peak`
+    fn worse(&self, value: Decimal, peak: Decimal) -> bool {
+        match self {
+            ExcursionKind::TemperatureLow => value < peak,
+            ExcursionKind::TemperatureHigh | ExcursionKind::HumidityHigh => value > peak,
+        }
+    }
+}
+
+/// Run of readings outside a limit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Excursion {
+    pub kind: ExcursionKind,
+    pub started_at: DateTime<Utc>,
+    /// First reading back inside the limit, or the last reading
+    pub ended_at: DateTime<Utc>,
+    pub duration_minutes: i64,
+    /// Most extreme value reached
+    pub peak: Decimal,
+    pub readings: usize,
+    /// Overlaps the time between the departed and arrived milestones (or
+    /// the whole log when the shipment has not departed)
+    pub during_transit: bool,
+}
+
+/// Excursions of readings in time order, earliest first
+pub fn find_excursions(readings: &[LoggerReading], limits: &ConditionLimits) -> Vec<Excursion> {
+    let mut excursions = Vec::new();
+    for kind in [
+        ExcursionKind::TemperatureHigh,
+        ExcursionKind::TemperatureLow,
+        ExcursionKind::HumidityHigh,
+    ] {
+        let mut current: Option<Excursion> = None;
+        for reading in readings {
+            let Some(value) = kind.breach(reading, limits) else {
+                if let Some(mut excursion) = current.take() {
+                    excursion.ended_at = reading.recorded_at;
+                    excursions.push(excursion);
+                }
+                continue;
+            };
+            match current.as_mut() {
+                Some(excursion) => {
+                    excursion.ended_at = reading.recorded_at;
+                    excursion.readings += 1;
+                    if kind.worse(value, excursion.peak) {
+                        excursion.peak = value;
+                    }
+                }
+                None => {
+                    current = Some(Excursion {
+                        kind,
+                        started_at: reading.recorded_at,
+                        ended_at: reading.recorded_at,
+                        duration_minutes: 0,
+                        peak: value,
+                        readings: 1,
+                        during_transit: true,
+                    });
+                }
+            }
+        }
+        excursions.extend(current);
+    }
+
+    for excursion in &mut excursions {
+        excursion.duration_minutes = (excursion.ended_at - excursion.started_at).num_minutes();
+    }
+    excursions.sort_by_key(|e| e.started_at);
+    excursions
+}
+
+/// Whether an excursion overlaps the transit between departure and arrival
+pub fn overlaps_transit(
+    excursion: &Excursion,
+    departed_at: Option<DateTime<Utc>>,
+    arrived_at: Option<DateTime<Utc>>,
+) -> bool {
+    departed_at.is_none_or(|departed| excursion.ended_at >= departed)
+        && arrived_at.is_none_or(|arrived| excursion.started_at <= arrived)
+}
+
+/// Mean kinetic temperature (°C): the constant temperature with the same
+/// cumulative thermal effect as the logged ones
+pub fn mean_kinetic_temperature(temperatures_c: &[Decimal]) -> Option<Decimal> {
+    if temperatures_c.is_empty() {
+        return None;
+    }
+    let sum: f64 = temperatures_c
+        .iter()
+        .filter_map(|t| t.to_f64())
+        .map(|t| (-MKT_ACTIVATION_KELVIN / (t + KELVIN_OFFSET)).exp())
+        .sum();
+    let kelvin = MKT_ACTIVATION_KELVIN / -(sum / temperatures_c.len() as f64).ln();
+    Decimal::from_f64(kelvin - KELVIN_OFFSET).map(|t| t.round_dp(1))
+}
+
+/// Lowest, highest and mean of a measure
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricStats {
+    pub min: Decimal,
+    pub max: Decimal,
+    pub mean: Decimal,
+}
+
+/// Statistics of values; `None` when there are none
+pub fn metric_stats(values: &[Decimal]) -> Option<MetricStats> {
+    let min = values.iter().min()?;
+    let max = values.iter().max()?;
+    let mean = values.iter().sum::<Decimal>() / Decimal::from(values.len());
+    Some(MetricStats {
+        min: *min,
+        max: *max,
+        mean: mean.round_dp(1),
+    })
+}
+
+/// Conditions recorded by one logger
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionSummary {
+    pub limits: ConditionLimits,
+    pub reading_count: usize,
+    pub first_reading_at: Option<DateTime<Utc>>,
+    pub last_reading_at: Option<DateTime<Utc>>,
+    pub temperature: Option<MetricStats>,
+    pub mean_kinetic_temperature: Option<Decimal>,
+    /// Absent for temperature-only loggers
+    pub humidity: Option<MetricStats>,
+    pub excursions: Vec<Excursion>,
+    /// Total length of the excursions during transit
+    pub transit_minutes_out_of_range: i64,
+    /// Any excursion during transit
+    pub breached: bool,
+}
+
+/// Summarize readings in time order against limits
+pub fn summarize(
+    readings: &[LoggerReading],
+    limits: ConditionLimits,
+    departed_at: Option<DateTime<Utc>>,
+    arrived_at: Option<DateTime<Utc>>,
+) -> ConditionSummary {
+    let temperatures: Vec<Decimal> = readings.iter().map(|r| r.temperature_c).collect();
+    let humidities: Vec<Decimal> = readings.iter().filter_map(|r| r.relative_humidity).collect();
+    let mut excursions = find_excursions(readings, &limits);
+    for excursion in &mut excursions {
+        excursion.during_transit = overlaps_transit(excursion, departed_at, arrived_at);
+    }
+    let in_transit = excursions.iter().filter(|e| e.during_transit);
+
+    ConditionSummary {
+        limits,
+        reading_count: readings.len(),
+        first_reading_at: readings.first().map(|r| r.recorded_at),
+        last_reading_at: readings.last().map(|r| r.recorded_at),
+        temperature: metric_stats(&temperatures),
+        mean_kinetic_temperature: mean_kinetic_temperature(&temperatures),
+        humidity: metric_stats(&humidities),
+        transit_minutes_out_of_range: in_transit.clone().map(|e| e.duration_minutes).sum(),
+        breached: in_transit.count() > 0,
+        excursions,
+    }
+}
+
+/// Import query
+#[derive(Debug, Default, Deserialize)]
+pub struct LoggerImportQuery {
+    /// Preview the import without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Defaults to the serial in the export's preamble
+    pub logger_serial: Option<String>,
+    pub file_name: Option<String>,
+    pub temp_min_c: Option<Decimal>,
+    pub temp_max_c: Option<Decimal>,
+    pub humidity_max_pct: Option<Decimal>,
+    /// Offset of times written without a zone (default +7)
+    pub utc_offset_hours: Option<i32>,
+}
+
+/// Preview or result of a logger import
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggerImportResult {
+    pub dry_run: bool,
+    /// Set once imported
+    pub import_id: Option<Uuid>,
+    pub logger_serial: String,
+    pub columns: Vec<LoggerField>,
+    /// Rows skipped because they could not be read
+    pub skipped_rows: usize,
+    /// The first unreadable rows
+    pub row_errors: Vec<LoggerRowError>,
+    /// Replaced an earlier import of the same logger
+    pub replaced: bool,
+    pub conditions: ConditionSummary,
+}
+
+/// Conditions of one logger on the report
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggerConditions {
+    pub import_id: Uuid,
+    pub logger_serial: String,
+    pub file_name: Option<String>,
+    pub imported_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub conditions: ConditionSummary,
+}
+
+/// Transit-conditions report of a shipment
+#[derive(Debug, Clone, Serialize)]
+pub struct TransitConditionsReport {
+    pub shipment_id: Uuid,
+    pub shipment_number: String,
+    pub departure_port: String,
+    pub arrival_port: String,
+    pub departed_at: Option<DateTime<Utc>>,
+    pub arrived_at: Option<DateTime<Utc>>,
+    /// Any logger out of range during transit
+    pub breached: bool,
+    pub loggers: Vec<LoggerConditions>,
+}
+
+/// Shipment a log belongs to
+#[derive(Debug, sqlx::FromRow)]
+struct ShipmentHeader {
+    id: Uuid,
+    shipment_number: String,
+    departure_port: String,
+    arrival_port: String,
+    departed_at: Option<DateTime<Utc>>,
+    arrived_at: Option<DateTime<Utc>>,
+}
+
+/// Database row for an import
+#[derive(Debug, sqlx::FromRow)]
+struct ImportRow {
+    id: Uuid,
+    logger_serial: String,
+    file_name: Option<String>,
+    temp_min_c: Decimal,
+    temp_max_c: Decimal,
+    humidity_max_pct: Decimal,
+    created_at: DateTime<Utc>,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+impl ShipmentConditionsService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    async fn shipment(&self, business_id: Uuid, shipment_id: Uuid) -> AppResult<ShipmentHeader> {
+        sqlx::query_as::<_, ShipmentHeader>(
+            r#"
+            SELECT s.id, s.shipment_number, s.departure_port, s.arrival_port,
+                   (SELECT occurred_at FROM shipment_milestones
+                    WHERE shipment_id = s.id AND milestone = 'departed') AS departed_at,
+                   (SELECT occurred_at FROM shipment_milestones
+                    WHERE shipment_id = s.id AND milestone = 'arrived') AS arrived_at
+            FROM shipments s
+            WHERE s.id = $1 AND s.business_id = $2
+            "#,
+        )
+        .bind(shipment_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shipment".to_string()))
+    }
+
+    /// Import a logger export for a shipment, or preview it on a dry run
+    pub async fn import(
+        &self,
+        business_id: Uuid,
+        shipment_id: Uuid,
+        user_id: Uuid,
+        csv_data: &str,
+        query: &LoggerImportQuery,
+    ) -> AppResult<LoggerImportResult> {
+        let shipment = self.shipment(business_id, shipment_id).await?;
+
+        let limits = ConditionLimits {
+            temp_min_c: query.temp_min_c.unwrap_or(DEFAULT_TEMP_MIN_C),
+            temp_max_c: query.temp_max_c.unwrap_or(DEFAULT_TEMP_MAX_C),
+            humidity_max_pct: query.humidity_max_pct.unwrap_or(DEFAULT_HUMIDITY_MAX_PCT),
+        };
+        if limits.temp_max_c <= limits.temp_min_c {
+            return Err(validation(
+                "temp_max_c",
+                "The highest temperature must be above the lowest",
+                "อุณหภูมิสูงสุดต้องมากกว่าอุณหภูมิต่ำสุด",
+            ));
+        }
+        if limits.humidity_max_pct <= Decimal::ZERO || limits.humidity_max_pct > Decimal::ONE_HUNDRED {
+            return Err(validation(
+                "humidity_max_pct",
+                "Humidity limit must be between 0 and 100%",
+                "ค่าความชื้นสูงสุดต้องอยู่ระหว่าง 0 ถึง 100%",
+            ));
+        }
+        let utc_offset_hours = query.utc_offset_hours.unwrap_or(DEFAULT_UTC_OFFSET_HOURS);
+        if !(-12..=14).contains(&utc_offset_hours) {
+            return Err(validation(
+                "utc_offset_hours",
+                "UTC offset must be between -12 and +14 hours",
+                "เขตเวลาต้องอยู่ระหว่าง -12 ถึง +14 ชั่วโมง",
+            ));
+        }
+
+        let log = parse_logger_csv(csv_data, utc_offset_hours)?;
+        let logger_serial = query
+            .logger_serial
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .or(log.serial)
+            .ok_or_else(|| {
+                validation(
+                    "logger_serial",
+                    "The export has no logger serial; give logger_serial",
+                    "ไฟล์ไม่มีหมายเลขเครื่องบันทึก กรุณาระบุ logger_serial",
+                )
+            })?;
+        if log.readings.is_empty() {
+            return Err(validation(
+                "csv",
+                "The export has no readable readings",
+                "ไฟล์ไม่มีข้อมูลการวัดที่อ่านได้",
+            ));
+        }
+
+        let conditions = summarize(&log.readings, limits, shipment.departed_at, shipment.arrived_at);
+        let replaced = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM shipment_logger_imports WHERE shipment_id = $1 AND logger_serial = $2)",
+        )
+        .bind(shipment.id)
+        .bind(&logger_serial)
+        .fetch_one(&self.db)
+        .await?;
+
+        let import_id = if query.dry_run {
+            None
+        } else {
+            Some(
+                self.store(shipment.id, user_id, &logger_serial, query.file_name.as_deref(), &limits, &log.readings)
+                    .await?,
+            )
+        };
+
+        Ok(LoggerImportResult {
+            dry_run: query.dry_run,
+            import_id,
+            logger_serial,
+            columns: log.columns,
+            skipped_rows: log.errors.len(),
+            row_errors: log.errors.into_iter().take(MAX_LISTED_ROW_ERRORS).collect(),
+            replaced,
+            conditions,
+        })
+    }
+
+    async fn store(
+        &self,
+        shipment_id: Uuid,
+        user_id: Uuid,
+        logger_serial: &str,
+        file_name: Option<&str>,
+        limits: &ConditionLimits,
+        readings: &[LoggerReading],
+    ) -> AppResult<Uuid> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM shipment_logger_imports WHERE shipment_id = $1 AND logger_serial = $2")
+            .bind(shipment_id)
+            .bind(logger_serial)
+            .execute(&mut *tx)
+            .await?;
+
+        let import_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO shipment_logger_imports (
+                shipment_id, logger_serial, file_name, temp_min_c, temp_max_c, humidity_max_pct,
+                reading_count, first_reading_at, last_reading_at, imported_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id
+            "#,
+        )
+        .bind(shipment_id)
+        .bind(logger_serial)
+        .bind(file_name.map(str::trim).filter(|n| !n.is_empty()))
+        .bind(limits.temp_min_c)
+        .bind(limits.temp_max_c)
+        .bind(limits.humidity_max_pct)
+        .bind(readings.len() as i32)
+        .bind(readings.first().map(|r| r.recorded_at))
+        .bind(readings.last().map(|r| r.recorded_at))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let times: Vec<DateTime<Utc>> = readings.iter().map(|r| r.recorded_at).collect();
+        let temperatures: Vec<Decimal> = readings.iter().map(|r| r.temperature_c).collect();
+        let humidities: Vec<Option<Decimal>> = readings.iter().map(|r| r.relative_humidity).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO shipment_logger_readings (import_id, recorded_at, temperature_c, relative_humidity)
+            SELECT $1, t.recorded_at, t.temperature_c, t.relative_humidity
+            FROM UNNEST($2::TIMESTAMPTZ[], $3::DECIMAL[], $4::DECIMAL[])
+                AS t(recorded_at, temperature_c, relative_humidity)
+            "#,
+        )
+        .bind(import_id)
+        .bind(&times)
+        .bind(&temperatures)
+        .bind(&humidities)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(import_id)
+    }
+
+    /// Transit-conditions report: each logger's readings judged against its
+    /// limits, with excursions during transit marked
+    pub async fn report(&self, business_id: Uuid, shipment_id: Uuid) -> AppResult<TransitConditionsReport> {
+        let shipment = self.shipment(business_id, shipment_id).await?;
+
+        let imports = sqlx::query_as::<_, ImportRow>(
+            r#"
+            SELECT id, logger_serial, file_name, temp_min_c, temp_max_c, humidity_max_pct, created_at
+            FROM shipment_logger_imports
+            WHERE shipment_id = $1
+            ORDER BY logger_serial
+            "#,
+        )
+        .bind(shipment.id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut loggers = Vec::with_capacity(imports.len());
+        for import in imports {
+            let readings: Vec<LoggerReading> = sqlx::query_as::<_, (DateTime<Utc>, Decimal, Option<Decimal>)>(
+                r#"
+                SELECT recorded_at, temperature_c, relative_humidity
+                FROM shipment_logger_readings
+                WHERE import_id = $1
+                ORDER BY recorded_at
+                "#,
+            )
+            .bind(import.id)
+            .fetch_all(&self.db)
+            .await?
+            .into_iter()
+            .map(|(recorded_at, temperature_c, relative_humidity)| LoggerReading {
+                recorded_at,
+                temperature_c,
+                relative_humidity,
+            })
+            .collect();
+
+            let limits = ConditionLimits {
+                temp_min_c: import.temp_min_c,
+                temp_max_c: import.temp_max_c,
+                humidity_max_pct: import.humidity_max_pct,
+            };
+            loggers.push(LoggerConditions {
+                import_id: import.id,
+                logger_serial: import.logger_serial,
+                file_name: import.file_name,
+                imported_at: import.created_at,
+                conditions: summarize(&readings, limits, shipment.departed_at, shipment.arrived_at),
+            });
+        }
+
+        Ok(TransitConditionsReport {
+            shipment_id: shipment.id,
+            shipment_number: shipment.shipment_number,
+            departure_port: shipment.departure_port,
+            arrival_port: shipment.arrival_port,
+            departed_at: shipment.departed_at,
+            arrived_at: shipment.arrived_at,
+            breached: loggers.iter().any(|l| l.conditions.breached),
+            loggers,
+        })
+    }
+
+    /// Delete a logger import with its readings
+    pub async fn delete_import(&self, business_id: Uuid, shipment_id: Uuid, import_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM shipment_logger_imports i
+            USING shipments s
+            WHERE i.id = $1 AND i.shipment_id = $2 AND s.id = i.shipment_id AND s.business_id = $3
+            "#,
+        )
+        .bind(import_id)
+        .bind(shipment_id)
+        .bind(business_id)
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Logger import".to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,851 @@
+//! SOP checklists and their execution records
+//!
+//! A checklist is an SOP broken into steps workers tick off, such as the
+//! daily drying bed turning routine. Admins write checklists or make them
+//! from the numbered steps of an SOP in the knowledge base. Each run of a
+//! checklist is an execution, for a day and optionally a lot; ticks are kept
+//! with who made them, when and whether from the app or the LINE chatbot, and
+//! an execution is complete once every required step is ticked. Together
+//! they are the compliance record of the SOP, listed per checklist or lot.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::KnowledgeBaseService;
+
+/// How often a checklist is meant to be run
+pub const CHECKLIST_FREQUENCIES: [&str; 3] = ["daily", "per_lot", "as_needed"];
+
+/// Most steps in a checklist
+pub const MAX_CHECKLIST_ITEMS: usize = 50;
+
+/// Longest span of a compliance report
+const MAX_COMPLIANCE_DAYS: i64 = 366;
+
+/// SOP checklist service
+#[derive(Clone)]
+pub struct SopChecklistService {
+    db: PgPool,
+}
+
+/// Step of a checklist
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ChecklistItem {
+    pub id: Uuid,
+    pub position: i32,
+    pub text: String,
+    pub text_th: String,
+    pub required: bool,
+}
+
+/// Checklist with its steps in order
+#[derive(Debug, Serialize)]
+pub struct SopChecklist {
+    pub id: Uuid,
+    /// SOP the checklist was made from
+    pub article_id: Option<Uuid>,
+    pub name: String,
+    pub name_th: String,
+    pub frequency: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub items: Vec<ChecklistItem>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ChecklistRow {
+    id: Uuid,
+    article_id: Option<Uuid>,
+    name: String,
+    name_th: String,
+    frequency: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Step of a checklist being written
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChecklistItemInput {
+    pub text: String,
+    pub text_th: String,
+    /// Required unless false
+    pub required: Option<bool>,
+}
+
+/// Input for writing a checklist
+#[derive(Debug, Deserialize)]
+pub struct CreateChecklistInput {
+    pub name: String,
+    pub name_th: String,
+    /// daily, per_lot or as_needed (the default)
+    pub frequency: Option<String>,
+    pub items: Vec<ChecklistItemInput>,
+}
+
+/// Input for making a checklist from the numbered steps of an SOP
+#[derive(Debug, Deserialize)]
+pub struct ChecklistFromSopInput {
+    pub article_id: Uuid,
+    pub frequency: Option<String>,
+}
+
+/// Input for editing a checklist; fields left out stay as they are. Steps
+/// can only be replaced before the checklist is first run
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateChecklistInput {
+    pub name: Option<String>,
+    pub name_th: Option<String>,
+    pub frequency: Option<String>,
+    pub active: Option<bool>,
+    pub items: Option<Vec<ChecklistItemInput>>,
+}
+
+/// Input for starting a run of a checklist
+#[derive(Debug, Default, Deserialize)]
+pub struct StartExecutionInput {
+    /// Required for per-lot checklists
+    pub lot_id: Option<Uuid>,
+    /// Today unless given
+    pub execution_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+/// Steps to tick, by ID or by their number in the checklist
+#[derive(Debug, Default, Deserialize)]
+pub struct CheckItemsInput {
+    #[serde(default)]
+    pub item_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub positions: Vec<i32>,
+    pub note: Option<String>,
+}
+
+/// Step of an execution and its tick, if any
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExecutionStep {
+    pub item_id: Uuid,
+    pub position: i32,
+    pub text: String,
+    pub text_th: String,
+    pub required: bool,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub checked_by: Option<Uuid>,
+    pub checked_by_name: Option<String>,
+    pub check_source: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Execution of a checklist as listed
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExecutionSummary {
+    pub id: Uuid,
+    pub checklist_id: Uuid,
+    pub checklist_name: String,
+    pub checklist_name_th: String,
+    pub lot_id: Option<Uuid>,
+    pub lot_code: Option<String>,
+    pub execution_date: NaiveDate,
+    pub started_by: Option<Uuid>,
+    pub started_by_name: Option<String>,
+    pub source: String,
+    pub notes: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub checked_items: i64,
+    pub total_items: i64,
+}
+
+/// Execution with every step
+#[derive(Debug, Serialize)]
+pub struct SopExecution {
+    #[serde(flatten)]
+    pub summary: ExecutionSummary,
+    /// Required steps not yet ticked
+    pub required_remaining: usize,
+    pub steps: Vec<ExecutionStep>,
+}
+
+/// Filters for listing executions
+#[derive(Debug, Default, Deserialize)]
+pub struct ExecutionQuery {
+    pub checklist_id: Option<Uuid>,
+    pub lot_id: Option<Uuid>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only executions not yet complete (true) or only complete ones (false)
+    pub open: Option<bool>,
+}
+
+/// Period of a compliance report
+#[derive(Debug, Deserialize)]
+pub struct ComplianceQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// How well a checklist was kept over a period
+#[derive(Debug, Serialize)]
+pub struct ChecklistCompliance {
+    pub checklist_id: Uuid,
+    pub frequency: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub executions: usize,
+    pub completed: usize,
+    /// Days in the period, for daily checklists
+    pub days_expected: Option<usize>,
+    /// Days with a completed execution, for daily checklists
+    pub days_completed: Option<usize>,
+    /// Days without a completed execution, for daily checklists
+    pub missed_dates: Vec<NaiveDate>,
+}
+
+/// Text of a numbered ("1." or "1)") or bulleted ("-", "*", "•") step line,
+/// None for other lines
+pub fn strip_step_marker(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        rest
+    } else {
+        let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        line[digits..].strip_prefix(['.', ')'])?
+    };
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim()).filter(|text| !text.is_empty())
+}
+
+/// Steps of an SOP, pairing its English and Thai step lines in order
+pub fn checklist_steps(body: &str, body_th: &str) -> AppResult<Vec<ChecklistItemInput>> {
+    let steps: Vec<&str> = body.lines().filter_map(strip_step_marker).collect();
+    let steps_th: Vec<&str> = body_th.lines().filter_map(strip_step_marker).collect();
+    if steps.is_empty() || steps.len() != steps_th.len() {
+        return Err(AppError::Validation {
+            field: "article_id".to_string(),
+            message: "The SOP needs the same numbered steps in English and Thai to become a checklist".to_string(),
+            message_th: "SOP ต้องมีขั้นตอนแบบมีหมายเลขเท่ากันทั้งภาษาอังกฤษและภาษาไทยจึงจะสร้างรายการตรวจสอบได้"
+                .to_string(),
+        });
+    }
+    Ok(steps
+        .into_iter()
+        .zip(steps_th)
+        .map(|(text, text_th)| ChecklistItemInput {
+            text: text.to_string(),
+            text_th: text_th.to_string(),
+            required: None,
+        })
+        .collect())
+}
+
+/// Required steps of an execution not yet ticked
+pub fn required_remaining(steps: &[ExecutionStep]) -> usize {
+    steps.iter().filter(|s| s.required && s.checked_at.is_none()).count()
+}
+
+/// Steps of an execution as chat lines, ticked ones marked done
+pub fn step_lines(steps: &[ExecutionStep], thai: bool) -> String {
+    steps
+        .iter()
+        .map(|step| {
+            format!(
+                "{} {}. {}",
+                if step.checked_at.is_some() { "✅" } else { "⬜" },
+                step.position,
+                if thai { &step.text_th } else { &step.text }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Days from `from` to `to` without a completed execution
+pub fn missed_dates(from: NaiveDate, to: NaiveDate, completed: &BTreeSet<NaiveDate>) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .filter(|day| !completed.contains(day))
+        .collect()
+}
+
+/// Steps picked by ID or number, checked against the execution's steps
+pub fn select_steps(steps: &[ExecutionStep], item_ids: &[Uuid], positions: &[i32]) -> AppResult<Vec<Uuid>> {
+    let mut selected = BTreeSet::new();
+    for id in item_ids {
+        if !steps.iter().any(|s| s.item_id == *id) {
+            return Err(AppError::NotFound("Checklist item".to_string()));
+        }
+        selected.insert(*id);
+    }
+    for position in positions {
+        let step = steps.iter().find(|s| s.position == *position).ok_or_else(|| AppError::Validation {
+            field: "positions".to_string(),
+            message: format!("The checklist has no step {}", position),
+            message_th: format!("รายการตรวจสอบไม่มีขั้นตอนที่ {}", position),
+        })?;
+        selected.insert(step.item_id);
+    }
+    if selected.is_empty() {
+        return Err(AppError::Validation {
+            field: "item_ids".to_string(),
+            message: "Choose at least one step to tick".to_string(),
+            message_th: "เลือกอย่างน้อยหนึ่งขั้นตอน".to_string(),
+        });
+    }
+    Ok(selected.into_iter().collect())
+}
+
+fn validate_frequency(frequency: &str) -> AppResult<()> {
+    if CHECKLIST_FREQUENCIES.contains(&frequency) {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        field: "frequency".to_string(),
+        message: format!("Must be one of: {}", CHECKLIST_FREQUENCIES.join(", ")),
+        message_th: format!("ต้องเป็นหนึ่งใน: {}", CHECKLIST_FREQUENCIES.join(", ")),
+    })
+}
+
+fn validate_name(field: &str, name: &str) -> AppResult<()> {
+    if name.trim().is_empty() || name.len() > 200 {
+        return Err(AppError::Validation {
+            field: field.to_string(),
+            message: "Name is required (max 200 characters)".to_string(),
+            message_th: "ต้องระบุชื่อ (ไม่เกิน 200 ตัวอักษร)".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_items(items: &[ChecklistItemInput]) -> AppResult<()> {
+    if items.is_empty() || items.len() > MAX_CHECKLIST_ITEMS {
+        return Err(AppError::Validation {
+            field: "items".to_string(),
+            message: format!("A checklist has 1 to {} steps", MAX_CHECKLIST_ITEMS),
+            message_th: format!("รายการตรวจสอบต้องมี 1 ถึง {} ขั้นตอน", MAX_CHECKLIST_ITEMS),
+        });
+    }
+    if items.iter().any(|i| i.text.trim().is_empty() || i.text_th.trim().is_empty()) {
+        return Err(AppError::Validation {
+            field: "items".to_string(),
+            message: "Every step needs English and Thai text".to_string(),
+            message_th: "ทุกขั้นตอนต้องมีข้อความภาษาอังกฤษและภาษาไทย".to_string(),
+        });
+    }
+    Ok(())
+}
+
+const EXECUTION_SELECT: &str = r#"
+    SELECT e.id, e.checklist_id, c.name AS checklist_name, c.name_th AS checklist_name_th,
+           e.lot_id, l.traceability_code AS lot_code, e.execution_date, e.started_by,
+           u.name AS started_by_name, e.source, e.notes, e.started_at, e.completed_at,
+           (SELECT COUNT(*) FROM sop_execution_checks ec WHERE ec.execution_id = e.id) AS checked_items,
+           (SELECT COUNT(*) FROM sop_checklist_items i WHERE i.checklist_id = e.checklist_id) AS total_items
+    FROM sop_executions e
+    JOIN sop_checklists c ON c.id = e.checklist_id
+    LEFT JOIN lots l ON l.id = e.lot_id
+    LEFT JOIN users u ON u.id = e.started_by
+"#;
+
+impl SopChecklistService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    async fn items(&self, checklist_id: Uuid) -> AppResult<Vec<ChecklistItem>> {
+        let items = sqlx::query_as::<_, ChecklistItem>(
+            "SELECT id, position, text, text_th, required FROM sop_checklist_items WHERE checklist_id = $1 ORDER BY position",
+        )
+        .bind(checklist_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(items)
+    }
+
+    async fn replace_items(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        checklist_id: Uuid,
+        items: &[ChecklistItemInput],
+    ) -> AppResult<()> {
+        sqlx::query("DELETE FROM sop_checklist_items WHERE checklist_id = $1")
+            .bind(checklist_id)
+            .execute(&mut **tx)
+            .await?;
+        for (index, item) in items.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO sop_checklist_items (checklist_id, position, text, text_th, required) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(checklist_id)
+            .bind(index as i32 + 1)
+            .bind(item.text.trim())
+            .bind(item.text_th.trim())
+            .bind(item.required.unwrap_or(true))
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Checklists of the business, by name; only active ones unless
+    /// `include_inactive`
+    pub async fn list_checklists(&self, business_id: Uuid, include_inactive: bool) -> AppResult<Vec<SopChecklist>> {
+        let rows = sqlx::query_as::<_, ChecklistRow>(
+            r#"
+            SELECT id, article_id, name, name_th, frequency, active, created_at, updated_at
+            FROM sop_checklists
+            WHERE business_id = $1 AND (active OR $2)
+            ORDER BY name
+            "#,
+        )
+        .bind(business_id)
+        .bind(include_inactive)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut checklists = Vec::with_capacity(rows.len());
+        for row in rows {
+            let items = self.items(row.id).await?;
+            checklists.push(SopChecklist::from_row(row, items));
+        }
+        Ok(checklists)
+    }
+
+    /// A checklist of the business with its steps
+    pub async fn get_checklist(&self, business_id: Uuid, checklist_id: Uuid) -> AppResult<SopChecklist> {
+        let row = sqlx::query_as::<_, ChecklistRow>(
+            r#"
+            SELECT id, article_id, name, name_th, frequency, active, created_at, updated_at
+            FROM sop_checklists
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(checklist_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("SOP checklist".to_string()))?;
+        let items = self.items(row.id).await?;
+        Ok(SopChecklist::from_row(row, items))
+    }
+
+    async fn insert_checklist(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        article_id: Option<Uuid>,
+        input: CreateChecklistInput,
+    ) -> AppResult<SopChecklist> {
+        validate_name("name", &input.name)?;
+        validate_name("name_th", &input.name_th)?;
+        let frequency = input.frequency.unwrap_or_else(|| "as_needed".to_string());
+        validate_frequency(&frequency)?;
+        validate_items(&input.items)?;
+
+        let mut tx = self.db.begin().await?;
+        let checklist_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO sop_checklists (business_id, article_id, name, name_th, frequency, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(article_id)
+        .bind(input.name.trim())
+        .bind(input.name_th.trim())
+        .bind(&frequency)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        Self::replace_items(&mut tx, checklist_id, &input.items).await?;
+        tx.commit().await?;
+
+        self.get_checklist(business_id, checklist_id).await
+    }
+
+    /// Write a checklist
+    pub async fn create_checklist(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateChecklistInput,
+    ) -> AppResult<SopChecklist> {
+        self.insert_checklist(business_id, user_id, None, input).await
+    }
+
+    /// Make a checklist from the numbered steps of an SOP in the knowledge
+    /// base, named after it
+    pub async fn create_from_sop(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: ChecklistFromSopInput,
+    ) -> AppResult<SopChecklist> {
+        let article = KnowledgeBaseService::new(self.db.clone())
+            .get_article(business_id, user_id, input.article_id, true)
+            .await?
+            .article;
+        if article.kind != "sop" {
+            return Err(AppError::Validation {
+                field: "article_id".to_string(),
+                message: "Only SOPs can become checklists".to_string(),
+                message_th: "สร้างรายการตรวจสอบได้จาก SOP เท่านั้น".to_string(),
+            });
+        }
+        let items = checklist_steps(&article.body, &article.body_th)?;
+        self.insert_checklist(
+            business_id,
+            user_id,
+            Some(article.id),
+            CreateChecklistInput {
+                name: article.title,
+                name_th: article.title_th,
+                frequency: input.frequency,
+                items,
+            },
+        )
+        .await
+    }
+
+    /// Edit a checklist
+    pub async fn update_checklist(
+        &self,
+        business_id: Uuid,
+        checklist_id: Uuid,
+        input: UpdateChecklistInput,
+    ) -> AppResult<SopChecklist> {
+        let existing = self.get_checklist(business_id, checklist_id).await?;
+        let name = input.name.unwrap_or(existing.name);
+        let name_th = input.name_th.unwrap_or(existing.name_th);
+        let frequency = input.frequency.unwrap_or(existing.frequency);
+        validate_name("name", &name)?;
+        validate_name("name_th", &name_th)?;
+        validate_frequency(&frequency)?;
+
+        let mut tx = self.db.begin().await?;
+        if let Some(items) = &input.items {
+            validate_items(items)?;
+            let run = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM sop_executions WHERE checklist_id = $1)",
+            )
+            .bind(checklist_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if run {
+                return Err(AppError::Conflict {
+                    resource: "sop_checklist".to_string(),
+                    message: "The steps of a checklist that has been run cannot change; deactivate it and write a new one"
+                        .to_string(),
+                    message_th: "ไม่สามารถแก้ไขขั้นตอนของรายการตรวจสอบที่ใช้งานแล้ว ให้ปิดใช้งานและสร้างรายการใหม่"
+                        .to_string(),
+                });
+            }
+            Self::replace_items(&mut tx, checklist_id, items).await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE sop_checklists
+            SET name = $1, name_th = $2, frequency = $3, active = $4, updated_at = NOW()
+            WHERE id = $5 AND business_id = $6
+            "#,
+        )
+        .bind(name.trim())
+        .bind(name_th.trim())
+        .bind(&frequency)
+        .bind(input.active.unwrap_or(existing.active))
+        .bind(checklist_id)
+        .bind(business_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_checklist(business_id, checklist_id).await
+    }
+
+    /// Start a run of a checklist, or carry on with the open run of the same
+    /// checklist, day and lot
+    pub async fn start_execution(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        checklist_id: Uuid,
+        input: StartExecutionInput,
+        source: &str,
+    ) -> AppResult<SopExecution> {
+        let checklist = self.get_checklist(business_id, checklist_id).await?;
+        if !checklist.active {
+            return Err(AppError::Validation {
+                field: "checklist_id".to_string(),
+                message: "The checklist is no longer in use".to_string(),
+                message_th: "รายการตรวจสอบนี้ไม่ได้ใช้งานแล้ว".to_string(),
+            });
+        }
+        if checklist.frequency == "per_lot" && input.lot_id.is_none() {
+            return Err(AppError::Validation {
+                field: "lot_id".to_string(),
+                message: "This checklist is run for a lot".to_string(),
+                message_th: "รายการตรวจสอบนี้ต้องระบุล็อต".to_string(),
+            });
+        }
+        if let Some(lot_id) = input.lot_id {
+            let lot_exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM lots WHERE id = $1 AND business_id = $2)",
+            )
+            .bind(lot_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !lot_exists {
+                return Err(AppError::NotFound("Lot".to_string()));
+            }
+        }
+        let execution_date = input.execution_date.unwrap_or_else(|| Local::now().date_naive());
+
+        let open = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM sop_executions
+            WHERE checklist_id = $1 AND execution_date = $2 AND lot_id IS NOT DISTINCT FROM $3
+              AND completed_at IS NULL
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(checklist_id)
+        .bind(execution_date)
+        .bind(input.lot_id)
+        .fetch_optional(&self.db)
+        .await?;
+        if let Some(execution_id) = open {
+            return self.get_execution(business_id, execution_id).await;
+        }
+
+        let execution_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO sop_executions (checklist_id, business_id, lot_id, execution_date, started_by, source, notes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(checklist_id)
+        .bind(business_id)
+        .bind(input.lot_id)
+        .bind(execution_date)
+        .bind(user_id)
+        .bind(source)
+        .bind(&input.notes)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get_execution(business_id, execution_id).await
+    }
+
+    /// An execution with every step and its tick
+    pub async fn get_execution(&self, business_id: Uuid, execution_id: Uuid) -> AppResult<SopExecution> {
+        let summary = sqlx::query_as::<_, ExecutionSummary>(&format!(
+            "{} WHERE e.id = $1 AND e.business_id = $2",
+            EXECUTION_SELECT
+        ))
+        .bind(execution_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("SOP execution".to_string()))?;
+
+        let steps = sqlx::query_as::<_, ExecutionStep>(
+            r#"
+            SELECT i.id AS item_id, i.position, i.text, i.text_th, i.required,
+                   ec.checked_at, ec.checked_by, u.name AS checked_by_name, ec.source AS check_source, ec.note
+            FROM sop_checklist_items i
+            LEFT JOIN sop_execution_checks ec ON ec.item_id = i.id AND ec.execution_id = $1
+            LEFT JOIN users u ON u.id = ec.checked_by
+            WHERE i.checklist_id = $2
+            ORDER BY i.position
+            "#,
+        )
+        .bind(execution_id)
+        .bind(summary.checklist_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(SopExecution {
+            required_remaining: required_remaining(&steps),
+            summary,
+            steps,
+        })
+    }
+
+    /// Tick steps of an execution; ticking a step again keeps the first
+    /// tick. The execution is complete once every required step is ticked
+    pub async fn check_items(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        execution_id: Uuid,
+        input: CheckItemsInput,
+        source: &str,
+    ) -> AppResult<SopExecution> {
+        let execution = self.get_execution(business_id, execution_id).await?;
+        let item_ids = select_steps(&execution.steps, &input.item_ids, &input.positions)?;
+
+        let mut tx = self.db.begin().await?;
+        for item_id in &item_ids {
+            sqlx::query(
+                r#"
+                INSERT INTO sop_execution_checks (execution_id, item_id, checked_by, source, note)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (execution_id, item_id) DO NOTHING
+                "#,
+            )
+            .bind(execution_id)
+            .bind(item_id)
+            .bind(user_id)
+            .bind(source)
+            .bind(&input.note)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE sop_executions e
+            SET completed_at = NOW()
+            WHERE e.id = $1 AND e.completed_at IS NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM sop_checklist_items i
+                  WHERE i.checklist_id = e.checklist_id AND i.required
+                    AND NOT EXISTS (
+                        SELECT 1 FROM sop_execution_checks ec WHERE ec.execution_id = e.id AND ec.item_id = i.id
+                    )
+              )
+            "#,
+        )
+        .bind(execution_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_execution(business_id, execution_id).await
+    }
+
+    /// Latest open execution a member started, for ticking steps from chat
+    pub async fn latest_open_execution(&self, business_id: Uuid, user_id: Uuid) -> AppResult<Option<Uuid>> {
+        let execution_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM sop_executions
+            WHERE business_id = $1 AND started_by = $2 AND completed_at IS NULL
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(business_id)
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(execution_id)
+    }
+
+    /// Executions of the business, newest first
+    pub async fn list_executions(&self, business_id: Uuid, query: &ExecutionQuery) -> AppResult<Vec<ExecutionSummary>> {
+        let executions = sqlx::query_as::<_, ExecutionSummary>(&format!(
+            r#"{}
+            WHERE e.business_id = $1
+              AND ($2::UUID IS NULL OR e.checklist_id = $2)
+              AND ($3::UUID IS NULL OR e.lot_id = $3)
+              AND ($4::DATE IS NULL OR e.execution_date >= $4)
+              AND ($5::DATE IS NULL OR e.execution_date <= $5)
+              AND ($6::BOOLEAN IS NULL OR (e.completed_at IS NULL) = $6)
+            ORDER BY e.execution_date DESC, e.started_at DESC
+            LIMIT 500
+            "#,
+            EXECUTION_SELECT
+        ))
+        .bind(business_id)
+        .bind(query.checklist_id)
+        .bind(query.lot_id)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.open)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(executions)
+    }
+
+    /// How well a checklist was kept over a period; for a daily checklist,
+    /// the days it was not completed
+    pub async fn compliance(
+        &self,
+        business_id: Uuid,
+        checklist_id: Uuid,
+        query: &ComplianceQuery,
+    ) -> AppResult<ChecklistCompliance> {
+        if query.to < query.from || (query.to - query.from).num_days() >= MAX_COMPLIANCE_DAYS {
+            return Err(AppError::Validation {
+                field: "to".to_string(),
+                message: format!("The period must run forwards and span at most {} days", MAX_COMPLIANCE_DAYS),
+                message_th: format!("ช่วงเวลาต้องเรียงจากเริ่มถึงสิ้นสุด และไม่เกิน {} วัน", MAX_COMPLIANCE_DAYS),
+            });
+        }
+        let checklist = self.get_checklist(business_id, checklist_id).await?;
+        let executions = sqlx::query_as::<_, (NaiveDate, bool)>(
+            r#"
+            SELECT execution_date, completed_at IS NOT NULL
+            FROM sop_executions
+            WHERE checklist_id = $1 AND execution_date BETWEEN $2 AND $3
+            "#,
+        )
+        .bind(checklist_id)
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_all(&self.db)
+        .await?;
+
+        let completed_dates: BTreeSet<NaiveDate> = executions
+            .iter()
+            .filter(|(_, completed)| *completed)
+            .map(|(date, _)| *date)
+            .collect();
+        let daily = checklist.frequency == "daily";
+        let missed = if daily {
+            missed_dates(query.from, query.to, &completed_dates)
+        } else {
+            Vec::new()
+        };
+
+        Ok(ChecklistCompliance {
+            checklist_id,
+            frequency: checklist.frequency,
+            from: query.from,
+            to: query.to,
+            executions: executions.len(),
+            completed: executions.iter().filter(|(_, completed)| *completed).count(),
+            days_expected: daily.then(|| (query.to - query.from).num_days() as usize + 1),
+            days_completed: daily.then_some(completed_dates.len()),
+            missed_dates: missed,
+        })
+    }
+}
+
+impl SopChecklist {
+    fn from_row(row: ChecklistRow, items: Vec<ChecklistItem>) -> Self {
+        Self {
+            id: row.id,
+            article_id: row.article_id,
+            name: row.name,
+            name_th: row.name_th,
+            frequency: row.frequency,
+            active: row.active,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            items,
+        }
+    }
+}
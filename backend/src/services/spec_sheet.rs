@@ -0,0 +1,395 @@
+//! Lot spec sheet (lot passport) generation
+//!
+//! One-page, buyer-facing PDF summarising a lot: origin, variety, process,
+//! green grading (screen size and moisture), cupping score with descriptors,
+//! third-party lab results and valid insurance marked for buyers,
+//! certifications and a QR code to the public traceability page. Built from
+//! the same aggregate as the public traceability view.
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use shared::{localize_digits, DisplayFormat, Language, ScreenSizeDistribution};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::{Config, PdfConfig};
+use crate::error::{AppError, AppResult};
+use crate::services::pdf::{
+    line_height, PdfFonts, PdfPage, TextStyle, A4_HEIGHT_MM, A4_WIDTH_MM,
+};
+use crate::services::lab_result::LabResult;
+use crate::services::lot_insurance::InsurancePolicy;
+use crate::services::traceability::TraceabilityView;
+use crate::services::{BusinessService, LabResultService, LotInsuranceService, TraceabilityService};
+
+const MARGIN: f32 = 18.0;
+const CONTENT_WIDTH: f32 = A4_WIDTH_MM - 2.0 * MARGIN;
+const LABEL_WIDTH: f32 = 42.0;
+const QR_SIZE: f32 = 36.0;
+const BODY_SIZE: f32 = 9.5;
+
+/// Spec sheet service
+#[derive(Clone)]
+pub struct SpecSheetService {
+    db: PgPool,
+    pdf: PdfConfig,
+    public_url: String,
+}
+
+impl SpecSheetService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            pdf: config.pdf.clone(),
+            public_url: config.server.public_url.clone(),
+        }
+    }
+
+    /// Generate the spec sheet PDF for a lot. Thai labels are used only when
+    /// a Thai-capable font is configured
+    pub async fn generate(&self, business_id: Uuid, lot_id: Uuid, thai: bool) -> AppResult<Vec<u8>> {
+        let code = sqlx::query_scalar::<_, String>(
+            "SELECT traceability_code FROM lots WHERE id = $1 AND business_id = $2",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        let view = TraceabilityService::new(self.db.clone())
+            .get_traceability_view(&code, None)
+            .await?;
+        let fonts = PdfFonts::load(&self.pdf).await?;
+        let trace_url = view
+            .lot
+            .qr_code_url
+            .clone()
+            .unwrap_or_else(|| TraceabilityService::generate_qr_code_url(&code, &self.public_url));
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let lab_results = LabResultService::new(self.db.clone())
+            .buyer_pack_results(business_id, lot_id)
+            .await?;
+        let insurance = LotInsuranceService::new(self.db.clone())
+            .buyer_pack_policies(business_id, lot_id)
+            .await?;
+
+        render_spec_sheet(
+            &view,
+            &lab_results,
+            &insurance,
+            &trace_url,
+            &fonts,
+            thai && fonts.supports_thai(),
+            display,
+        )
+    }
+}
+
+/// Lays out the sheet top to bottom, tracking the current y position
+struct Layout<'a> {
+    page: &'a PdfPage,
+    thai: bool,
+    y: f32,
+}
+
+impl Layout<'_> {
+    fn label(&self, th: &'static str, en: &'static str) -> &'static str {
+        if self.thai {
+            th
+        } else {
+            en
+        }
+    }
+
+    fn section(&mut self, th: &'static str, en: &'static str) {
+        self.y += 4.0;
+        self.page.text(MARGIN, self.y, TextStyle::bold(11.0), self.label(th, en));
+        self.y += 1.6;
+        self.page.rule(MARGIN, MARGIN + CONTENT_WIDTH, self.y, 0.5, 0.6);
+        self.y += line_height(BODY_SIZE) + 0.8;
+    }
+
+    /// Label/value row; skipped when there is no value
+    fn field(&mut self, th: &'static str, en: &'static str, value: Option<String>) {
+        let Some(value) = value.filter(|v| !v.is_empty()) else {
+            return;
+        };
+        self.page.text(MARGIN, self.y, TextStyle::bold(BODY_SIZE), self.label(th, en));
+        let height = self.page.wrapped_text(
+            MARGIN + LABEL_WIDTH,
+            self.y,
+            CONTENT_WIDTH - LABEL_WIDTH,
+            TextStyle::regular(BODY_SIZE),
+            &value,
+            3,
+        );
+        self.y += height.max(line_height(BODY_SIZE)) + 0.6;
+    }
+}
+
+fn render_spec_sheet(
+    view: &TraceabilityView,
+    lab_results: &[LabResult],
+    insurance: &[InsurancePolicy],
+    trace_url: &str,
+    fonts: &PdfFonts,
+    thai: bool,
+    display: DisplayFormat,
+) -> AppResult<Vec<u8>> {
+    // Thai digits only make sense (and only render) in Thai output
+    let fmt = display.for_language(if thai { &Language::Thai } else { &Language::English });
+    let date = |d| fmt.date(d);
+    let title = format!("{} - {}", view.lot.traceability_code, view.lot.name);
+    let page = PdfPage::new(&title, A4_WIDTH_MM, A4_HEIGHT_MM, fonts)?;
+    let mut layout = Layout { page: &page, thai, y: 0.0 };
+
+    // Header band with the QR code on the right
+    let band_height = QR_SIZE + 12.0;
+    page.fill_rect(0.0, 0.0, A4_WIDTH_MM, band_height, 0.93);
+    let qr_x = A4_WIDTH_MM - MARGIN - QR_SIZE;
+    page.fill_rect(qr_x - 2.0, 4.0, QR_SIZE + 4.0, QR_SIZE + 4.0, 1.0);
+    page.qr_code(qr_x, 6.0, QR_SIZE, trace_url)?;
+
+    let header_width = qr_x - MARGIN - 6.0;
+    let heading = layout.label("ข้อมูลจำเพาะล็อต", "LOT SPECIFICATION SHEET");
+    page.text(MARGIN, 14.0, TextStyle::regular(9.0), heading);
+    let name_height =
+        page.wrapped_text(MARGIN, 23.0, header_width, TextStyle::bold(18.0), &view.lot.name, 2);
+    let mut y = 23.0 + name_height;
+    page.text(MARGIN, y, TextStyle::regular(11.0), &view.lot.traceability_code);
+    y += line_height(11.0);
+    page.text(MARGIN, y, TextStyle::regular(9.0), &view.business.name);
+    let caption = layout.label("สแกนเพื่อดูที่มา", "Scan to trace this lot");
+    page.text(qr_x, band_height - 1.5, TextStyle::regular(7.0), caption);
+    layout.y = band_height + 2.0;
+
+    layout.section("ล็อต", "Lot");
+    layout.field("ขั้นตอน", "Stage", Some(humanize(&view.lot.stage)));
+    layout.field(
+        "น้ำหนักคงเหลือ",
+        "Available",
+        Some(format!("{} kg", fmt.decimal(view.lot.current_weight_kg, 1))),
+    );
+
+    layout.section("แหล่งที่มา", "Origin");
+    if let Some(origin) = &view.origin {
+        layout.field("แปลงปลูก", "Farm / Plot", Some(origin.plot_name.clone()));
+        let region: Vec<&str> = [origin.district.as_deref(), origin.province.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        layout.field("พื้นที่", "Region", Some(region.join(", ")));
+        layout.field("ความสูง", "Altitude", origin.altitude_meters.map(|m| format!("{} m.a.s.l.", fmt.integer(m as i64))));
+        layout.field("สายพันธุ์", "Variety", Some(origin.varieties.join(", ")));
+    } else {
+        layout.field("จังหวัด", "Province", view.business.province.clone());
+    }
+    if let (Some(first), Some(last)) = (view.harvests.first(), view.harvests.last()) {
+        let period = if first.harvest_date == last.harvest_date {
+            date(first.harvest_date)
+        } else {
+            format!("{} - {}", date(first.harvest_date), date(last.harvest_date))
+        };
+        layout.field("ช่วงเก็บเกี่ยว", "Harvest", Some(period));
+        let ripe = view.harvests.iter().map(|h| h.ripeness_ripe_percent as i64).sum::<i64>()
+            / view.harvests.len() as i64;
+        layout.field("เชอร์รี่สุก", "Ripe cherries", Some(format!("{}%", fmt.integer(ripe))));
+    }
+    if !view.sources.is_empty() {
+        let sources: Vec<String> = view
+            .sources
+            .iter()
+            .map(|s| format!("{} {}", s.traceability_code, fmt.percent(s.proportion_percent, 1)))
+            .collect();
+        layout.field("ส่วนผสม", "Blend of", Some(sources.join(", ")));
+    }
+
+    if let Some(processing) = &view.processing {
+        layout.section("การแปรรูป", "Process");
+        layout.field("วิธีแปรรูป", "Method", Some(humanize(&processing.method)));
+        layout.field("การหมัก", "Fermentation", processing.fermentation_hours.map(|h| format!("{} h", fmt.integer(h as i64))));
+        let drying_days = processing.drying_days.map(|days| fmt.integer(days as i64));
+        let drying = match (&processing.drying_method, drying_days) {
+            (Some(method), Some(days)) => Some(format!("{}, {} days", humanize(method), days)),
+            (Some(method), None) => Some(humanize(method)),
+            (None, Some(days)) => Some(format!("{} days", days)),
+            (None, None) => None,
+        };
+        layout.field("การตาก", "Drying", drying);
+    }
+
+    let moisture = view
+        .grading
+        .as_ref()
+        .and_then(|g| g.moisture_percent)
+        .or_else(|| view.processing.as_ref().and_then(|p| p.final_moisture_percent));
+    if view.grading.is_some() || moisture.is_some() {
+        layout.section("การคัดเกรดสารกาแฟ", "Green Coffee");
+        if let Some(grading) = &view.grading {
+            layout.field("เกรด", "Grade", Some(humanize(&grading.grade)));
+            layout.field(
+                "ข้อบกพร่อง",
+                "Defects",
+                Some(format!("{} per 350 g", fmt.integer(grading.total_defects as i64))),
+            );
+            layout.field(
+                "ขนาดเมล็ด",
+                "Screen size",
+                grading
+                    .screen_size_distribution
+                    .clone()
+                    .and_then(|v| serde_json::from_value::<ScreenSizeDistribution>(v).ok())
+                    .map(|s| localize_digits(&screen_size_summary(&s), fmt.digits)),
+            );
+        }
+        layout.field("ความชื้น", "Moisture", moisture.map(|m| fmt.percent(m, 1)));
+    }
+
+    if let Some(cupping) = &view.cupping {
+        layout.section("ผลการคัปปิ้ง", "Cupping");
+        let box_y = layout.y - line_height(BODY_SIZE);
+        page.fill_rect(MARGIN, box_y, 34.0, 20.0, 0.15);
+        page.fill_rect(MARGIN + 0.6, box_y + 0.6, 32.8, 18.8, 1.0);
+        let score = fmt.decimal(cupping.final_score, 2);
+        page.text(MARGIN + 4.0, box_y + 11.0, TextStyle::bold(20.0), &score);
+        page.text(MARGIN + 4.0, box_y + 17.0, TextStyle::regular(7.5), layout.label("คะแนน SCA", "SCA score"));
+
+        let x = MARGIN + 40.0;
+        let width = CONTENT_WIDTH - 40.0;
+        page.text(x, layout.y, TextStyle::bold(12.0), &cupping.classification);
+        let mut y = layout.y + line_height(12.0);
+        let notes = if thai {
+            cupping.tasting_notes_th.clone().or(cupping.tasting_notes.clone())
+        } else {
+            cupping.tasting_notes.clone().or(cupping.tasting_notes_th.clone())
+        };
+        if let Some(notes) = notes.filter(|n| !n.is_empty()) {
+            y += page.wrapped_text(x, y, width, TextStyle::regular(BODY_SIZE), &notes, 3);
+        }
+        page.text(
+            x,
+            y,
+            TextStyle::regular(8.0),
+            &format!("{} · {}", date(cupping.session_date), cupping.cupper_name),
+        );
+        layout.y = (y + line_height(8.0)).max(box_y + 20.0 + line_height(BODY_SIZE));
+    }
+
+    if !lab_results.is_empty() {
+        layout.section("ผลตรวจจากห้องปฏิบัติการ", "Lab Results");
+        for result in lab_results {
+            let name = match &result.analyte {
+                Some(analyte) => format!("{} ({})", result.analysis.label(thai), analyte),
+                None => result.analysis.label(thai).to_string(),
+            };
+            let limit = match (result.min_limit, result.max_limit) {
+                (Some(min), Some(max)) => format!(", {}-{}", fmt.decimal(min, 2), fmt.decimal(max, 2)),
+                (None, Some(max)) => format!(", max {}", fmt.decimal(max, 2)),
+                (Some(min), None) => format!(", min {}", fmt.decimal(min, 2)),
+                (None, None) => String::new(),
+            };
+            let verdict = if result.passed {
+                layout.label("ผ่าน", "Pass")
+            } else {
+                layout.label("ไม่ผ่าน", "Fail")
+            };
+            let report = result
+                .report_number
+                .as_ref()
+                .map(|number| format!(", No. {}", number))
+                .unwrap_or_default();
+            let line = format!(
+                "{}: {} {}{} - {} ({}, {}{})",
+                name,
+                fmt.decimal(result.value, 2),
+                result.unit,
+                limit,
+                verdict,
+                result.lab_name,
+                date(result.tested_on),
+                report
+            );
+            let body = TextStyle::regular(BODY_SIZE);
+            layout.y += page.wrapped_text(MARGIN, layout.y, CONTENT_WIDTH, body, &line, 2) + 0.6;
+        }
+    }
+
+    if !view.certifications.is_empty() {
+        layout.section("การรับรอง", "Certifications");
+        for cert in view.certifications.iter().take(6) {
+            let line = format!(
+                "{} - {} (No. {}, valid until {})",
+                cert.certification_name,
+                cert.certifying_body,
+                cert.certificate_number,
+                date(cert.valid_until)
+            );
+            let body = TextStyle::regular(BODY_SIZE);
+            layout.y += page.wrapped_text(MARGIN, layout.y, CONTENT_WIDTH, body, &line, 2) + 0.6;
+        }
+    }
+
+    if !insurance.is_empty() {
+        layout.section("การประกันภัย", "Insurance");
+        for policy in insurance.iter().take(4) {
+            let shipment = policy
+                .order_number
+                .as_ref()
+                .map(|number| format!(", {} {}", layout.label("คำสั่งขาย", "order"), number))
+                .unwrap_or_default();
+            let line = format!(
+                "{} - {} {} {} (No. {}{}, {} - {})",
+                policy.insurer,
+                policy.coverage_type.label(thai),
+                fmt.decimal(policy.insured_amount, 2),
+                policy.currency,
+                policy.policy_number,
+                shipment,
+                date(policy.valid_from),
+                date(policy.valid_until)
+            );
+            let body = TextStyle::regular(BODY_SIZE);
+            layout.y += page.wrapped_text(MARGIN, layout.y, CONTENT_WIDTH, body, &line, 2) + 0.6;
+        }
+    }
+
+    // Footer
+    let footer_y = A4_HEIGHT_MM - 12.0;
+    page.rule(MARGIN, MARGIN + CONTENT_WIDTH, footer_y - 5.0, 0.3, 0.7);
+    page.text(MARGIN, footer_y, TextStyle::regular(7.5), trace_url);
+    page.text(
+        A4_WIDTH_MM - MARGIN - 45.0,
+        footer_y,
+        TextStyle::regular(7.5),
+        &format!("{} {}", layout.label("ออกเมื่อ", "Issued"), date(Utc::now().date_naive())),
+    );
+
+    page.finish()
+}
+
+/// "green_bean" -> "Green bean"
+fn humanize(value: &str) -> String {
+    let text = value.replace(['_', '-'], " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+/// Screen size shares, largest screens first, skipping empty bands
+pub fn screen_size_summary(screen: &ScreenSizeDistribution) -> String {
+    [
+        ("18+", screen.screen_18_plus),
+        ("17", screen.screen_17),
+        ("16", screen.screen_16),
+        ("15", screen.screen_15),
+        ("14-", screen.screen_14_below),
+    ]
+    .into_iter()
+    .filter(|(_, share)| *share > Decimal::ZERO)
+    .map(|(band, share)| format!("S{} {}%", band, share.round_dp(1).normalize()))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
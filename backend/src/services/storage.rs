@@ -0,0 +1,606 @@
+//! Storage locations and hot-season advisories
+//!
+//! Lots are kept in warehouses recorded with their coordinates. Green coffee
+//! (parchment and green bean) stored without climate control loses moisture
+//! and fades in long hot spells, so the weather forecast for each such
+//! location is checked for at least three days in a row peaking above 32°C.
+//! The business owner is advised once per hot spell with the green lots
+//! stored there, so they can be moved or ventilated in time.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use shared::{DisplayFormat, Language, LotStage};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::external::weather::WeatherForecast;
+use crate::services::notification::{
+    notification_action_url, AlertSeverity, CreateNotificationInput, NotificationType,
+};
+use crate::services::{BusinessService, NotificationService, WeatherService};
+
+/// Daily high above which stored green coffee is at risk (°C)
+pub const HOT_STORAGE_CELSIUS: i64 = 32;
+
+/// Consecutive hot days that make a hot spell
+pub const HOT_SPELL_MIN_DAYS: usize = 3;
+
+/// Lot stages holding green coffee
+pub const GREEN_COFFEE_STAGES: [&str; 2] = [LotStage::Parchment.as_str(), LotStage::GreenBean.as_str()];
+
+/// Hours between forecast checks of a location by the background job
+const HEAT_CHECK_INTERVAL_HOURS: i64 = 3;
+
+/// Locations checked per job run
+const HEAT_CHECK_BATCH_SIZE: i64 = 20;
+
+/// Storage location service
+#[derive(Clone)]
+pub struct StorageService {
+    db: PgPool,
+    weather: WeatherService,
+}
+
+/// Warehouse where lots are stored
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StorageLocation {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub name: String,
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+    pub climate_controlled: bool,
+    pub notes: Option<String>,
+    /// Last day of the hot spell the owner was advised of
+    pub heat_advised_through: Option<NaiveDate>,
+    pub lot_count: i64,
+    /// Parchment and green bean stored here
+    pub green_coffee_kg: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Input for creating or updating a storage location
+#[derive(Debug, Deserialize)]
+pub struct StorageLocationInput {
+    pub name: String,
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+    pub climate_controlled: Option<bool>,
+    pub notes: Option<String>,
+}
+
+/// Lots moved into a storage location
+#[derive(Debug, Deserialize)]
+pub struct StoreLotsInput {
+    pub lot_ids: Vec<Uuid>,
+}
+
+/// Lot kept at a storage location
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StoredLot {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub name: String,
+    pub stage: String,
+    pub current_weight_kg: Decimal,
+}
+
+/// Forecast high of one local day
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DailyHigh {
+    pub date: NaiveDate,
+    pub max_celsius: Decimal,
+}
+
+/// Run of consecutive days above the storage threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HotSpell {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub days: usize,
+    pub peak_celsius: Decimal,
+}
+
+/// Hot spell forecast at a location without climate control
+#[derive(Debug, Clone, Serialize)]
+pub struct HeatAdvisory {
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub hot_spell: HotSpell,
+    /// Green lots stored at the location
+    pub lots: Vec<StoredLot>,
+    /// Whether the owner was already advised of this spell
+    pub already_advised: bool,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Check a location's name and coordinates
+pub fn validate_location(input: &StorageLocationInput) -> AppResult<()> {
+    if input.name.trim().is_empty() {
+        return Err(validation("name", "Name is required", "ต้องระบุชื่อสถานที่จัดเก็บ"));
+    }
+    if input.latitude.abs() > Decimal::from(90) {
+        return Err(validation(
+            "latitude",
+            "Latitude must be between -90 and 90",
+            "ละติจูดต้องอยู่ระหว่าง -90 ถึง 90",
+        ));
+    }
+    if input.longitude.abs() > Decimal::from(180) {
+        return Err(validation(
+            "longitude",
+            "Longitude must be between -180 and 180",
+            "ลองจิจูดต้องอยู่ระหว่าง -180 ถึง 180",
+        ));
+    }
+    Ok(())
+}
+
+/// Highest forecast temperature of each local day, in date order
+pub fn daily_highs(forecast: &WeatherForecast) -> Vec<DailyHigh> {
+    let offset = Duration::seconds(forecast.timezone_offset_seconds as i64);
+    let mut highs: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+    for item in &forecast.forecasts {
+        let date = (item.timestamp + offset).date_naive();
+        let high = highs.entry(date).or_insert(item.temp_max_celsius);
+        *high = (*high).max(item.temp_max_celsius);
+    }
+    highs
+        .into_iter()
+        .map(|(date, max_celsius)| DailyHigh { date, max_celsius })
+        .collect()
+}
+
+/// First run of at least `min_days` consecutive days peaking above
+/// `threshold`
+pub fn find_hot_spell(highs: &[DailyHigh], threshold: Decimal, min_days: usize) -> Option<HotSpell> {
+    let mut run: Vec<&DailyHigh> = Vec::new();
+    for high in highs {
+        let hot = high.max_celsius > threshold;
+        let continues = run
+            .last()
+            .is_some_and(|last| last.date + Duration::days(1) == high.date);
+        if run.len() >= min_days && !(hot && continues) {
+            break;
+        }
+        if !hot || !continues {
+            run.clear();
+        }
+        if hot {
+            run.push(high);
+        }
+    }
+    if run.len() < min_days.max(1) {
+        return None;
+    }
+    Some(HotSpell {
+        start: run[0].date,
+        end: run[run.len() - 1].date,
+        days: run.len(),
+        peak_celsius: run.iter().map(|h| h.max_celsius).max().unwrap_or_default(),
+    })
+}
+
+/// Create the advisory for a hot spell at a storage location
+pub fn create_heat_advisory_notification(advisory: &HeatAdvisory, format: DisplayFormat) -> CreateNotificationInput {
+    let en = format.for_language(&Language::English);
+    let th = format.for_language(&Language::Thai);
+    let spell = &advisory.hot_spell;
+    let lots = advisory
+        .lots
+        .iter()
+        .map(|l| format!("{} ({} kg)", l.traceability_code, en.decimal(l.current_weight_kg, 0)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let lots_th = advisory
+        .lots
+        .iter()
+        .map(|l| format!("{} ({} กก.)", l.traceability_code, th.decimal(l.current_weight_kg, 0)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    CreateNotificationInput {
+        notification_type: NotificationType::WeatherAlert,
+        title: format!("Heat Advisory: {}", advisory.location_name),
+        title_th: Some(format!("เตือนอากาศร้อนที่คลังสินค้า: {}", advisory.location_name)),
+        message: format!(
+            "{} days above {}°C forecast from {} to {} (up to {}°C). Green coffee stored here may lose quality; move or ventilate: {}",
+            en.integer(spell.days as i64),
+            HOT_STORAGE_CELSIUS,
+            en.date(spell.start),
+            en.date(spell.end),
+            en.decimal(spell.peak_celsius, 1),
+            lots
+        ),
+        message_th: Some(format!(
+            "พยากรณ์อากาศร้อนเกิน {}°C ติดต่อกัน {} วัน ตั้งแต่ {} ถึง {} (สูงสุด {}°C) สารกาแฟที่จัดเก็บอาจเสื่อมคุณภาพ ควรย้ายหรือระบายอากาศ: {}",
+            HOT_STORAGE_CELSIUS,
+            th.integer(spell.days as i64),
+            th.date(spell.start),
+            th.date(spell.end),
+            th.decimal(spell.peak_celsius, 1),
+            lots_th
+        )),
+        entity_type: Some("storage_location".to_string()),
+        entity_id: Some(advisory.location_id),
+        action_url: notification_action_url(
+            &NotificationType::WeatherAlert,
+            Some("storage_location"),
+            Some(advisory.location_id),
+        ),
+        priority: Some(AlertSeverity::Warning.priority()),
+        severity: Some(AlertSeverity::Warning),
+    }
+}
+
+const LOCATION_SELECT: &str = r#"
+    SELECT s.id, s.business_id, s.name, s.latitude, s.longitude, s.climate_controlled, s.notes,
+           s.heat_advised_through,
+           COUNT(l.id) AS lot_count,
+           COALESCE(SUM(l.current_weight_kg) FILTER (WHERE l.stage IN ('parchment', 'green_bean')), 0)
+               AS green_coffee_kg,
+           s.created_at, s.updated_at
+    FROM storage_locations s
+    LEFT JOIN lots l ON l.storage_location_id = s.id AND l.current_weight_kg > 0
+"#;
+
+impl StorageService {
+    pub fn new(db: PgPool) -> Self {
+        let weather = WeatherService::new(db.clone());
+        Self { db, weather }
+    }
+
+    /// Use live forecasts from the weather API, not only cached ones
+    pub fn with_weather_client(db: PgPool, api_key: String) -> Self {
+        let weather = WeatherService::with_client(db.clone(), api_key);
+        Self { db, weather }
+    }
+
+    /// List the business's storage locations
+    pub async fn list(&self, business_id: Uuid) -> AppResult<Vec<StorageLocation>> {
+        let locations = sqlx::query_as::<_, StorageLocation>(&format!(
+            "{} WHERE s.business_id = $1 GROUP BY s.id ORDER BY s.name",
+            LOCATION_SELECT
+        ))
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(locations)
+    }
+
+    /// Get a storage location
+    pub async fn get(&self, business_id: Uuid, location_id: Uuid) -> AppResult<StorageLocation> {
+        sqlx::query_as::<_, StorageLocation>(&format!(
+            "{} WHERE s.id = $1 AND s.business_id = $2 GROUP BY s.id",
+            LOCATION_SELECT
+        ))
+        .bind(location_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Storage location".to_string()))
+    }
+
+    /// Record a storage location
+    pub async fn create(&self, business_id: Uuid, input: StorageLocationInput) -> AppResult<StorageLocation> {
+        validate_location(&input)?;
+        let name = input.name.trim();
+        self.ensure_unique_name(business_id, name, None).await?;
+
+        let location_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO storage_locations (business_id, name, latitude, longitude, climate_controlled, notes)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(name)
+        .bind(input.latitude)
+        .bind(input.longitude)
+        .bind(input.climate_controlled.unwrap_or(false))
+        .bind(&input.notes)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get(business_id, location_id).await
+    }
+
+    /// Update a storage location; moving it clears the advised hot spell
+    pub async fn update(
+        &self,
+        business_id: Uuid,
+        location_id: Uuid,
+        input: StorageLocationInput,
+    ) -> AppResult<StorageLocation> {
+        validate_location(&input)?;
+        let name = input.name.trim();
+        self.ensure_unique_name(business_id, name, Some(location_id)).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE storage_locations
+            SET name = $3, climate_controlled = $6, notes = $7,
+                heat_advised_through = CASE
+                    WHEN latitude = $4 AND longitude = $5 THEN heat_advised_through
+                END,
+                latitude = $4, longitude = $5
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(location_id)
+        .bind(business_id)
+        .bind(name)
+        .bind(input.latitude)
+        .bind(input.longitude)
+        .bind(input.climate_controlled.unwrap_or(false))
+        .bind(&input.notes)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Storage location".to_string()));
+        }
+
+        self.get(business_id, location_id).await
+    }
+
+    /// Delete a storage location; its lots are left without a location
+    pub async fn delete(&self, business_id: Uuid, location_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM storage_locations WHERE id = $1 AND business_id = $2")
+            .bind(location_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Storage location".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn ensure_unique_name(&self, business_id: Uuid, name: &str, except: Option<Uuid>) -> AppResult<()> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM storage_locations
+                WHERE business_id = $1 AND LOWER(name) = LOWER($2) AND ($3::uuid IS NULL OR id <> $3)
+            )
+            "#,
+        )
+        .bind(business_id)
+        .bind(name)
+        .bind(except)
+        .fetch_one(&self.db)
+        .await?;
+        if taken {
+            return Err(AppError::Conflict {
+                resource: "storage_location".to_string(),
+                message: format!("A storage location named {} already exists", name),
+                message_th: format!("มีสถานที่จัดเก็บชื่อ {} อยู่แล้ว", name),
+            });
+        }
+        Ok(())
+    }
+
+    /// Lots currently stored at a location
+    pub async fn list_lots(&self, business_id: Uuid, location_id: Uuid) -> AppResult<Vec<StoredLot>> {
+        self.get(business_id, location_id).await?;
+        self.stored_lots(location_id, false).await
+    }
+
+    /// Move lots into a location
+    pub async fn store_lots(
+        &self,
+        business_id: Uuid,
+        location_id: Uuid,
+        input: StoreLotsInput,
+    ) -> AppResult<Vec<StoredLot>> {
+        self.get(business_id, location_id).await?;
+        if input.lot_ids.is_empty() {
+            return Err(validation("lot_ids", "Choose at least one lot", "กรุณาเลือกล็อตอย่างน้อยหนึ่งล็อต"));
+        }
+
+        let found = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM lots WHERE id = ANY($1) AND business_id = $2",
+        )
+        .bind(&input.lot_ids)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+        let mut distinct = input.lot_ids.clone();
+        distinct.sort();
+        distinct.dedup();
+        if found != distinct.len() as i64 {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+
+        sqlx::query("UPDATE lots SET storage_location_id = $1 WHERE id = ANY($2) AND business_id = $3")
+            .bind(location_id)
+            .bind(&distinct)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+
+        self.stored_lots(location_id, false).await
+    }
+
+    /// Take a lot out of a location
+    pub async fn remove_lot(&self, business_id: Uuid, location_id: Uuid, lot_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query(
+            "UPDATE lots SET storage_location_id = NULL WHERE id = $1 AND business_id = $2 AND storage_location_id = $3",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .bind(location_id)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Lot".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn stored_lots(&self, location_id: Uuid, green_only: bool) -> AppResult<Vec<StoredLot>> {
+        let lots = sqlx::query_as::<_, StoredLot>(
+            r#"
+            SELECT id AS lot_id, traceability_code, name, stage, current_weight_kg
+            FROM lots
+            WHERE storage_location_id = $1
+              AND current_weight_kg > 0
+              AND (NOT $2 OR stage = ANY($3))
+            ORDER BY current_weight_kg DESC, traceability_code
+            "#,
+        )
+        .bind(location_id)
+        .bind(green_only)
+        .bind(&GREEN_COFFEE_STAGES[..])
+        .fetch_all(&self.db)
+        .await?;
+        Ok(lots)
+    }
+
+    // ========================================================================
+    // Heat Advisories
+    // ========================================================================
+
+    /// Hot spell forecast at a location, with the green lots stored there;
+    /// `None` when the location is climate controlled, holds no green
+    /// coffee or no spell is forecast
+    async fn advisory_for(&self, location: &StorageLocation) -> AppResult<Option<HeatAdvisory>> {
+        if location.climate_controlled {
+            return Ok(None);
+        }
+        let lots = self.stored_lots(location.id, true).await?;
+        if lots.is_empty() {
+            return Ok(None);
+        }
+
+        let forecast = match self
+            .weather
+            .get_forecast(location.business_id, location.latitude, location.longitude)
+            .await
+        {
+            Ok(forecast) => forecast,
+            Err(e) => {
+                tracing::warn!("No forecast for storage location {}: {}", location.id, e);
+                return Ok(None);
+            }
+        };
+        let Some(hot_spell) = find_hot_spell(
+            &daily_highs(&forecast),
+            Decimal::from(HOT_STORAGE_CELSIUS),
+            HOT_SPELL_MIN_DAYS,
+        ) else {
+            return Ok(None);
+        };
+
+        Ok(Some(HeatAdvisory {
+            location_id: location.id,
+            location_name: location.name.clone(),
+            already_advised: location
+                .heat_advised_through
+                .is_some_and(|through| hot_spell.start <= through),
+            hot_spell,
+            lots,
+        }))
+    }
+
+    /// Hot spells forecast at the business's locations without climate control
+    pub async fn heat_advisories(&self, business_id: Uuid) -> AppResult<Vec<HeatAdvisory>> {
+        let mut advisories = Vec::new();
+        for location in self.list(business_id).await? {
+            if let Some(advisory) = self.advisory_for(&location).await? {
+                advisories.push(advisory);
+            }
+        }
+        Ok(advisories)
+    }
+
+    /// Advise the owner of a hot spell at a location unless already done
+    async fn advise(&self, location: &StorageLocation) -> AppResult<bool> {
+        let Some(advisory) = self.advisory_for(location).await? else {
+            return Ok(false);
+        };
+        if advisory.already_advised {
+            return Ok(false);
+        }
+
+        let format = BusinessService::new(self.db.clone())
+            .get_display_format(location.business_id)
+            .await?;
+        let notification = create_heat_advisory_notification(&advisory, format);
+        let queued = NotificationService::new(self.db.clone())
+            .notify_business_owner(location.business_id, notification)
+            .await?;
+
+        sqlx::query("UPDATE storage_locations SET heat_advised_through = $2 WHERE id = $1")
+            .bind(location.id)
+            .bind(advisory.hot_spell.end)
+            .execute(&self.db)
+            .await?;
+        Ok(queued.is_some())
+    }
+
+    /// Advise the owner of every new hot spell at the business's locations
+    /// Returns the number of notifications queued
+    pub async fn notify_heat_advisories(&self, business_id: Uuid) -> AppResult<i32> {
+        let mut count = 0;
+        for location in self.list(business_id).await? {
+            if self.advise(&location).await? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Check the forecast of locations not checked for a while, across all
+    /// businesses. Returns the number of notifications queued
+    pub async fn check_due_locations(&self, now: DateTime<Utc>) -> AppResult<usize> {
+        let due = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT s.id, s.business_id
+            FROM storage_locations s
+            WHERE NOT s.climate_controlled
+              AND (s.heat_checked_at IS NULL OR s.heat_checked_at < $1)
+              AND EXISTS (
+                  SELECT 1 FROM lots l
+                  WHERE l.storage_location_id = s.id AND l.current_weight_kg > 0 AND l.stage = ANY($2)
+              )
+            ORDER BY s.heat_checked_at NULLS FIRST
+            LIMIT $3
+            "#,
+        )
+        .bind(now - Duration::hours(HEAT_CHECK_INTERVAL_HOURS))
+        .bind(&GREEN_COFFEE_STAGES[..])
+        .bind(HEAT_CHECK_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut queued = 0;
+        for (location_id, business_id) in due {
+            sqlx::query("UPDATE storage_locations SET heat_checked_at = $2 WHERE id = $1")
+                .bind(location_id)
+                .bind(now)
+                .execute(&self.db)
+                .await?;
+            let location = self.get(business_id, location_id).await?;
+            match self.advise(&location).await {
+                Ok(true) => queued += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Heat advisory for storage location {} failed: {}", location_id, e),
+            }
+        }
+        Ok(queued)
+    }
+}
@@ -258,6 +258,7 @@ impl SyncService {
             _ => Err(AppError::Validation {
                 field: "operation".to_string(),
                 message: format!("Invalid operation: {}", change.operation),
+                message_th: format!("การดำเนินการไม่ถูกต้อง: {}", change.operation),
             }),
         }
     }
@@ -269,6 +270,7 @@ impl SyncService {
             .ok_or_else(|| AppError::Validation {
                 field: "data".to_string(),
                 message: "Data must be an object".to_string(),
+                message_th: "ข้อมูลต้องเป็นออบเจกต์".to_string(),
             })?
             .keys()
             .cloned()
@@ -297,6 +299,7 @@ impl SyncService {
         let obj = change.data.as_object().ok_or_else(|| AppError::Validation {
             field: "data".to_string(),
             message: "Data must be an object".to_string(),
+            message_th: "ข้อมูลต้องเป็นออบเจกต์".to_string(),
         })?;
 
         let updates: Vec<String> = obj.keys()
@@ -335,6 +338,29 @@ impl SyncService {
         Ok(())
     }
 
+    /// Role permission resource guarding a synced entity type
+    pub fn entity_resource(entity_type: &str) -> Option<&'static str> {
+        match entity_type {
+            "plots" => Some("plot"),
+            "harvests" => Some("harvest"),
+            "processing_records" => Some("processing"),
+            "green_bean_grades" => Some("grading"),
+            "cupping_sessions" | "cupping_samples" => Some("cupping"),
+            "lots" | "inventory_transactions" => Some("inventory"),
+            "roast_sessions" => Some("roast_profile"),
+            _ => None,
+        }
+    }
+
+    /// Permission action needed to apply a sync operation
+    pub fn operation_action(operation: &str) -> &'static str {
+        match operation {
+            "create" => "create",
+            "delete" => "delete",
+            _ => "edit",
+        }
+    }
+
     fn validate_table_name(entity_type: &str) -> AppResult<&str> {
         match entity_type {
             "plots" | "lots" | "harvests" | "processing_records" | "green_bean_grades"
@@ -344,6 +370,7 @@ impl SyncService {
             _ => Err(AppError::Validation {
                 field: "entity_type".to_string(),
                 message: format!("Invalid entity type: {}", entity_type),
+                message_th: format!("ประเภทข้อมูลไม่ถูกต้อง: {}", entity_type),
             }),
         }
     }
@@ -380,10 +407,7 @@ impl SyncService {
         .bind(user_id)
         .fetch_optional(&self.db)
         .await?
-        .ok_or(AppError::NotFound {
-            resource: "conflict".to_string(),
-            id: conflict_id.to_string(),
-        })?;
+        .ok_or_else(|| AppError::NotFound(format!("Sync conflict {}", conflict_id)))?;
 
         let (status, resolved_data) = match resolution {
             ConflictResolution::KeepLocal => {
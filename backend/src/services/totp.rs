@@ -0,0 +1,161 @@
+//! Time-based one-time passwords (RFC 6238) for two-factor sign-in
+//!
+//! Codes are 6 digits from HMAC-SHA1 over 30-second steps, the parameters
+//! every authenticator app supports. A code from the step before or after
+//! the current one is accepted to allow for clock drift, and each step is
+//! accepted once. Backup codes are one-time codes for a lost phone; only
+//! their hashes are stored.
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::services::member::percent_encode;
+
+/// Seconds each code is valid for
+pub const TOTP_PERIOD_SECONDS: u64 = 30;
+
+/// Digits in a code
+pub const TOTP_DIGITS: u32 = 6;
+
+/// Steps either side of the current one still accepted
+pub const TOTP_ALLOWED_DRIFT_STEPS: u64 = 1;
+
+/// Secret length; 160 bits as RFC 4226 recommends
+pub const TOTP_SECRET_BYTES: usize = 20;
+
+/// Backup codes issued at a time
+pub const BACKUP_CODE_COUNT: usize = 10;
+
+/// Name shown for the account in authenticator apps
+pub const TOTP_ISSUER: &str = "Coffee QM";
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Backup codes avoid characters easily misread on paper (0/o, 1/l/i)
+const BACKUP_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// RFC 4648 base32 without padding, as used in otpauth URIs
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Decode base32, ignoring case, spaces and padding; `None` on other
+/// characters
+pub fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// `count` random bytes
+fn random_bytes(count: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(count);
+    while bytes.len() < count {
+        let seed = [*Uuid::new_v4().as_bytes(), *Uuid::new_v4().as_bytes()].concat();
+        bytes.extend_from_slice(&Sha256::digest(&seed));
+    }
+    bytes.truncate(count);
+    bytes
+}
+
+/// New base32 secret for an authenticator app
+pub fn generate_secret() -> String {
+    base32_encode(&random_bytes(TOTP_SECRET_BYTES))
+}
+
+/// Step number of a Unix time
+pub fn time_step(unix_seconds: u64) -> u64 {
+    unix_seconds / TOTP_PERIOD_SECONDS
+}
+
+/// Code for a step (RFC 4226 HOTP with the step as counter)
+pub fn hotp(secret: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]])
+        & 0x7fff_ffff;
+    format!("{:0width$}", binary % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize)
+}
+
+/// Step a code was generated for, when it matches the current step or one
+/// within the allowed drift and is later than `last_used_step`
+pub fn verify_totp(secret_base32: &str, code: &str, unix_seconds: u64, last_used_step: Option<u64>) -> Option<u64> {
+    if !looks_like_totp(code) {
+        return None;
+    }
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let secret = base32_decode(secret_base32)?;
+    let current = time_step(unix_seconds);
+    (current.saturating_sub(TOTP_ALLOWED_DRIFT_STEPS)..=current + TOTP_ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&secret, *step) == code)
+}
+
+/// Whether an entered second factor is shaped like a TOTP code rather than
+/// a backup code
+pub fn looks_like_totp(code: &str) -> bool {
+    let digits: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    digits.len() == TOTP_DIGITS as usize && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// otpauth URI an authenticator app scans from a QR code
+pub fn otpauth_uri(secret_base32: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(TOTP_ISSUER),
+        percent_encode(account),
+        secret_base32,
+        percent_encode(TOTP_ISSUER),
+        TOTP_DIGITS,
+        TOTP_PERIOD_SECONDS
+    )
+}
+
+/// New backup codes, formatted `xxxxx-xxxxx`
+pub fn generate_backup_codes() -> Vec<String> {
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+            let chars: String = random_bytes(10)
+                .iter()
+                .map(|b| BACKUP_CODE_ALPHABET[*b as usize % BACKUP_CODE_ALPHABET.len()] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Backup code as hashed: lowercase without separators or spaces
+pub fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -5,10 +5,14 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::Serialize;
-use sqlx::{FromRow, PgPool};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::middleware::without_tenant;
+use crate::services::lot_certification::{derive_claim, held_claim_types, supporting_certifications, ClaimDerivation};
+use crate::services::lot_gallery::TracePhoto;
+use crate::services::{LotCertificationService, LotGalleryService};
 
 /// Traceability service for public lot information
 #[derive(Clone)]
@@ -20,6 +24,8 @@ pub struct TraceabilityService {
 #[derive(Debug, Serialize)]
 pub struct TraceabilityView {
     pub lot: LotInfo,
+    /// The lot's public photos, cover first
+    pub photos: Vec<TracePhoto>,
     pub business: BusinessInfo,
     pub origin: Option<OriginInfo>,
     pub harvests: Vec<HarvestInfo>,
@@ -27,7 +33,14 @@ pub struct TraceabilityView {
     pub grading: Option<GradingInfo>,
     pub cupping: Option<CuppingInfo>,
     pub sources: Vec<SourceLotInfo>,
+    /// Lot of another business this lot was bought from, whose own
+    /// traceability page holds its earlier history
+    pub transferred_from: Option<TransferOriginInfo>,
+    /// Certificates backing the claims the lot carries
     pub certifications: Vec<CertificationInfo>,
+    /// Each certification type the business holds, with whether and why
+    /// the lot may claim it
+    pub certification_claims: Vec<ClaimDerivation>,
 }
 
 /// Basic lot information
@@ -111,8 +124,16 @@ pub struct SourceLotInfo {
     pub proportion_percent: Decimal,
 }
 
+/// Seller's lot of a lot bought through a transfer
+#[derive(Debug, Serialize)]
+pub struct TransferOriginInfo {
+    pub traceability_code: String,
+    pub name: String,
+    pub business_name: String,
+}
+
 /// Certification info for traceability view
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Serialize)]
 pub struct CertificationInfo {
     pub certification_type: String,
     pub certification_name: String,
@@ -159,6 +180,9 @@ impl TraceabilityService {
             created_at: lot_row.7,
         };
 
+        // Get the curated gallery
+        let photos = LotGalleryService::new(self.db.clone()).trace_photos(lot_id).await?;
+
         // Get business info
         let business = self.get_business_info(business_id).await?;
 
@@ -180,12 +204,32 @@ impl TraceabilityService {
         // Get source lots (for blended lots)
         let sources = self.get_source_lots(lot_id).await?;
 
-        // Get certifications for the lot (based on business and plot)
-        let plot_id = self.get_plot_id_from_lot(lot_id).await?;
-        let certifications = self.get_certifications(business_id, plot_id).await?;
+        // Get the seller's lot (for lots bought from another business)
+        let transferred_from = self.get_transfer_origin(lot_id).await?;
+
+        // Derive the certification claims the lot may carry
+        let claim_data = LotCertificationService::new(self.db.clone()).load(business_id).await?;
+        let certification_claims: Vec<ClaimDerivation> = held_claim_types(&claim_data)
+            .iter()
+            .map(|claim| derive_claim(lot_id, claim, &claim_data))
+            .collect();
+        let certifications = certification_claims
+            .iter()
+            .filter(|c| c.claimed)
+            .flat_map(|c| supporting_certifications(lot_id, &c.certification_type, &claim_data))
+            .map(|c| CertificationInfo {
+                certification_type: c.certification_type.clone(),
+                certification_name: c.certification_name.clone(),
+                certifying_body: c.certification_body.clone(),
+                certificate_number: c.certificate_number.clone(),
+                scope: c.scope.clone(),
+                valid_until: c.expiration_date,
+            })
+            .collect();
 
         Ok(TraceabilityView {
             lot,
+            photos,
             business,
             origin,
             harvests,
@@ -193,7 +237,9 @@ impl TraceabilityService {
             grading,
             cupping,
             sources,
+            transferred_from,
             certifications,
+            certification_claims,
         })
     }
 
@@ -216,9 +262,10 @@ impl TraceabilityService {
         // Get plot info from first harvest
         let plot_row = sqlx::query_as::<_, (String, Option<i32>, Option<String>, Option<String>)>(
             r#"
-            SELECT p.name, p.altitude_meters, p.province, p.district
+            SELECT p.name, p.altitude_meters, b.province, b.district
             FROM harvests h
             JOIN plots p ON p.id = h.plot_id
+            JOIN businesses b ON b.id = p.business_id
             WHERE h.lot_id = $1
             LIMIT 1
             "#,
@@ -231,7 +278,7 @@ impl TraceabilityService {
             // Get varieties for the plot
             let varieties = sqlx::query_scalar::<_, String>(
                 r#"
-                SELECT pv.variety_name
+                SELECT DISTINCT pv.variety
                 FROM harvests h
                 JOIN plot_varieties pv ON pv.plot_id = h.plot_id
                 WHERE h.lot_id = $1
@@ -257,7 +304,7 @@ impl TraceabilityService {
     async fn get_harvests(&self, lot_id: Uuid) -> AppResult<Vec<HarvestInfo>> {
         let rows = sqlx::query_as::<_, (NaiveDate, Decimal, i32, Option<String>)>(
             r#"
-            SELECT harvest_date, cherry_weight_kg, ripeness_ripe_percent, picker_name
+            SELECT harvest_date, cherry_weight_kg, ripe_percent, picker_name
             FROM harvests
             WHERE lot_id = $1
             ORDER BY harvest_date
@@ -282,7 +329,7 @@ impl TraceabilityService {
         let row = sqlx::query_as::<_, (String, NaiveDate, Option<NaiveDate>, Option<serde_json::Value>, Option<serde_json::Value>, Option<Decimal>, Option<Decimal>, Option<Decimal>)>(
             r#"
             SELECT method, start_date, end_date, fermentation_log, drying_log,
-                   final_moisture_percent, green_bean_weight_kg, yield_percent
+                   final_moisture_percent, green_bean_weight_kg, processing_yield_percent
             FROM processing_records
             WHERE lot_id = $1
             ORDER BY start_date DESC
@@ -328,11 +375,7 @@ impl TraceabilityService {
         let row = sqlx::query_as::<_, (NaiveDate, String, i32, Option<Decimal>, Option<serde_json::Value>)>(
             r#"
             SELECT grading_date, grade, 
-                   (category1_full_black + category1_full_sour + category1_dried_cherry + 
-                    category1_fungus + category1_foreign_matter + category1_severe_insect +
-                    category2_partial_black + category2_partial_sour + category2_parchment +
-                    category2_floater + category2_immature + category2_withered +
-                    category2_shell + category2_broken + category2_hull + category2_husk) as total_defects,
+                   (category1_count + category2_count) as total_defects,
                    moisture_percent, screen_size_distribution
             FROM green_bean_grades
             WHERE lot_id = $1
@@ -359,7 +402,8 @@ impl TraceabilityService {
             SELECT s.session_date, s.cupper_name, cs.final_score, cs.tasting_notes, cs.tasting_notes_th
             FROM cupping_samples cs
             JOIN cupping_sessions s ON s.id = cs.session_id
-            WHERE cs.lot_id = $1
+            WHERE cs.lot_id = $1 AND s.status = 'finalized'
+              AND (NOT s.is_blind OR s.revealed_at IS NOT NULL)
             ORDER BY s.session_date DESC
             LIMIT 1
             "#,
@@ -414,59 +458,30 @@ impl TraceabilityService {
             .collect())
     }
 
-    /// Generate QR code URL for a lot
-    pub fn generate_qr_code_url(traceability_code: &str, base_url: &str) -> String {
-        format!("{}/trace/{}", base_url, traceability_code)
-    }
-
-    /// Get plot ID from lot's first harvest
-    async fn get_plot_id_from_lot(&self, lot_id: Uuid) -> AppResult<Option<Uuid>> {
-        let plot_id = sqlx::query_scalar::<_, Uuid>(
-            "SELECT plot_id FROM harvests WHERE lot_id = $1 LIMIT 1",
+    async fn get_transfer_origin(&self, lot_id: Uuid) -> AppResult<Option<TransferOriginInfo>> {
+        // The origin lot belongs to the seller, outside the request's tenant
+        let row = without_tenant(sqlx::query_as::<_, (String, String, String)>(
+            r#"
+            SELECT o.traceability_code, o.name, b.name
+            FROM lots l
+            JOIN lots o ON o.id = l.transferred_from_lot_id
+            JOIN businesses b ON b.id = o.business_id
+            WHERE l.id = $1
+            "#,
         )
         .bind(lot_id)
-        .fetch_optional(&self.db)
+        .fetch_optional(&self.db))
         .await?;
 
-        Ok(plot_id)
+        Ok(row.map(|r| TransferOriginInfo {
+            traceability_code: r.0,
+            name: r.1,
+            business_name: r.2,
+        }))
     }
 
-    /// Get active certifications for traceability view
-    async fn get_certifications(
-        &self,
-        business_id: Uuid,
-        plot_id: Option<Uuid>,
-    ) -> AppResult<Vec<CertificationInfo>> {
-        let today = Utc::now().date_naive();
-
-        let certifications = sqlx::query_as::<_, CertificationInfo>(
-            r#"
-            SELECT 
-                certification_type::TEXT as certification_type,
-                certification_name,
-                certification_body as certifying_body,
-                certificate_number,
-                scope::TEXT as scope,
-                expiration_date as valid_until
-            FROM certifications
-            WHERE business_id = $1
-              AND is_active = true
-              AND expiration_date >= $2
-              AND (
-                  scope = 'business'
-                  OR scope = 'farm'
-                  OR (scope = 'plot' AND plot_id = $3)
-                  OR scope = 'facility'
-              )
-            ORDER BY certification_type ASC
-            "#,
-        )
-        .bind(business_id)
-        .bind(today)
-        .bind(plot_id)
-        .fetch_all(&self.db)
-        .await?;
-
-        Ok(certifications)
+    /// Generate QR code URL for a lot
+    pub fn generate_qr_code_url(traceability_code: &str, base_url: &str) -> String {
+        format!("{}/trace/{}", base_url, traceability_code)
     }
 }
@@ -0,0 +1,414 @@
+//! Consistency check of a lot's traceability timeline
+//!
+//! The public traceability page shows harvest, processing, grading, cupping
+//! and roasting dates as entered. Data-entry slips (a wrong year, swapped
+//! start and end dates) produce sequences that cannot have happened, such as
+//! a roast before the coffee finished drying. This check lists them so they
+//! can be corrected before a buyer scans the lot.
+
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Traceability check service
+#[derive(Clone)]
+pub struct TraceabilityCheckService {
+    db: PgPool,
+}
+
+/// Dated record on a lot's timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatedRecord {
+    pub id: Uuid,
+    pub date: NaiveDate,
+}
+
+/// Processing record dates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessingSpan {
+    pub id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub drying_start: Option<NaiveDate>,
+    pub drying_end: Option<NaiveDate>,
+    /// Day of the first fermentation reading
+    pub fermentation_start: Option<NaiveDate>,
+}
+
+/// Roast session date and milestones in seconds from charge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoastTimeline {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub turning_point_seconds: Option<i32>,
+    pub first_crack_seconds: Option<i32>,
+    pub second_crack_seconds: Option<i32>,
+    pub drop_seconds: Option<i32>,
+}
+
+/// Everything dated on a lot
+#[derive(Debug, Clone, Default)]
+pub struct LotTimeline {
+    pub harvests: Vec<DatedRecord>,
+    pub processing: Vec<ProcessingSpan>,
+    pub gradings: Vec<DatedRecord>,
+    /// Cupping sessions the lot was cupped in
+    pub cuppings: Vec<DatedRecord>,
+    pub roasts: Vec<RoastTimeline>,
+}
+
+/// Kind of inconsistency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineIssueKind {
+    /// Dated after today
+    FutureDate,
+    /// Ends before it starts
+    NegativeDuration,
+    /// Harvested after processing of the lot began
+    HarvestAfterProcessingStart,
+    /// Drying or fermentation recorded before processing started
+    BeforeProcessingStart,
+    /// Happened before the lot's first harvest
+    BeforeHarvest,
+    /// Graded, cupped or roasted before processing was complete
+    BeforeProcessingComplete,
+    /// Roast milestones out of order (turning point, first crack, second
+    /// crack, drop)
+    RoastMilestonesOutOfOrder,
+}
+
+/// An impossible sequence on the lot's timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineIssue {
+    pub kind: TimelineIssueKind,
+    /// harvest, processing, grading, cupping_session or roast_session
+    pub record_type: &'static str,
+    pub record_id: Uuid,
+    pub date: NaiveDate,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Result of checking a lot
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceabilityCheck {
+    pub lot_id: Uuid,
+    pub traceability_code: String,
+    pub consistent: bool,
+    pub issues: Vec<TimelineIssue>,
+}
+
+fn issue(
+    kind: TimelineIssueKind,
+    record_type: &'static str,
+    record_id: Uuid,
+    date: NaiveDate,
+    message: String,
+    message_th: String,
+) -> TimelineIssue {
+    TimelineIssue {
+        kind,
+        record_type,
+        record_id,
+        date,
+        message,
+        message_th,
+    }
+}
+
+/// Find impossible sequences on a lot's timeline
+pub fn check_timeline(timeline: &LotTimeline, today: NaiveDate) -> Vec<TimelineIssue> {
+    use TimelineIssueKind::*;
+    let mut issues = Vec::new();
+
+    let first_harvest = timeline.harvests.iter().map(|h| h.date).min();
+    let processing_start = timeline.processing.iter().map(|p| p.start_date).min();
+    // Processing is complete when every record has ended
+    let processing_end = if timeline.processing.iter().all(|p| p.end_date.is_some()) {
+        timeline.processing.iter().filter_map(|p| p.end_date).max()
+    } else {
+        None
+    };
+
+    let dated = timeline
+        .harvests
+        .iter()
+        .map(|h| ("harvest", "Harvest", "การเก็บเกี่ยว", h))
+        .chain(timeline.gradings.iter().map(|g| ("grading", "Grading", "การคัดเกรด", g)))
+        .chain(timeline.cuppings.iter().map(|c| ("cupping_session", "Cupping", "การคัปปิ้ง", c)));
+    for (record_type, name, name_th, record) in dated {
+        if record.date > today {
+            issues.push(issue(
+                FutureDate,
+                record_type,
+                record.id,
+                record.date,
+                format!("{} is dated in the future ({})", name, record.date),
+                format!("{}ลงวันที่ในอนาคต ({})", name_th, record.date),
+            ));
+        }
+    }
+
+    for processing in &timeline.processing {
+        let id = processing.id;
+        let latest = [Some(processing.start_date), processing.end_date, processing.drying_start, processing.drying_end]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(processing.start_date);
+        if latest > today {
+            issues.push(issue(
+                FutureDate,
+                "processing",
+                id,
+                latest,
+                format!("Processing is dated in the future ({})", latest),
+                format!("การแปรรูปลงวันที่ในอนาคต ({})", latest),
+            ));
+        }
+        if let Some(end) = processing.end_date.filter(|end| *end < processing.start_date) {
+            issues.push(issue(
+                NegativeDuration,
+                "processing",
+                id,
+                end,
+                format!("Processing ends ({}) before it starts ({})", end, processing.start_date),
+                format!("การแปรรูปสิ้นสุด ({}) ก่อนเริ่ม ({})", end, processing.start_date),
+            ));
+        }
+        if let (Some(start), Some(end)) = (processing.drying_start, processing.drying_end) {
+            if end < start {
+                issues.push(issue(
+                    NegativeDuration,
+                    "processing",
+                    id,
+                    end,
+                    format!("Drying ends ({}) before it starts ({})", end, start),
+                    format!("การตากสิ้นสุด ({}) ก่อนเริ่ม ({})", end, start),
+                ));
+            }
+        }
+        for (stage, stage_th, date) in [
+            ("Drying", "การตาก", processing.drying_start),
+            ("Fermentation", "การหมัก", processing.fermentation_start),
+        ] {
+            if let Some(date) = date.filter(|d| *d < processing.start_date) {
+                issues.push(issue(
+                    BeforeProcessingStart,
+                    "processing",
+                    id,
+                    date,
+                    format!("{} starts ({}) before processing starts ({})", stage, date, processing.start_date),
+                    format!("{}เริ่ม ({}) ก่อนเริ่มแปรรูป ({})", stage_th, date, processing.start_date),
+                ));
+            }
+        }
+    }
+
+    if let Some(start) = processing_start {
+        for harvest in timeline.harvests.iter().filter(|h| h.date > start) {
+            issues.push(issue(
+                HarvestAfterProcessingStart,
+                "harvest",
+                harvest.id,
+                harvest.date,
+                format!("Harvested ({}) after processing started ({})", harvest.date, start),
+                format!("เก็บเกี่ยว ({}) หลังเริ่มแปรรูป ({})", harvest.date, start),
+            ));
+        }
+    }
+
+    let downstream = timeline
+        .gradings
+        .iter()
+        .map(|g| ("grading", "Graded", "คัดเกรด", *g))
+        .chain(timeline.cuppings.iter().map(|c| ("cupping_session", "Cupped", "คัปปิ้ง", *c)))
+        .chain(timeline.roasts.iter().map(|r| {
+            ("roast_session", "Roasted", "คั่ว", DatedRecord { id: r.id, date: r.date })
+        }));
+    for (record_type, verb, verb_th, record) in downstream {
+        if let Some(first_harvest) = first_harvest.filter(|h| record.date < *h) {
+            issues.push(issue(
+                BeforeHarvest,
+                record_type,
+                record.id,
+                record.date,
+                format!("{} ({}) before the first harvest ({})", verb, record.date, first_harvest),
+                format!("{} ({}) ก่อนการเก็บเกี่ยวครั้งแรก ({})", verb_th, record.date, first_harvest),
+            ));
+        }
+        // Without an end date processing is still running, so anything
+        // after its start is judged once it ends
+        let complete = processing_end.or(processing_start);
+        if let Some(complete) = complete.filter(|c| record.date < *c) {
+            issues.push(issue(
+                BeforeProcessingComplete,
+                record_type,
+                record.id,
+                record.date,
+                format!("{} ({}) before processing was complete ({})", verb, record.date, complete),
+                format!("{} ({}) ก่อนการแปรรูปเสร็จ ({})", verb_th, record.date, complete),
+            ));
+        }
+    }
+
+    for roast in &timeline.roasts {
+        if roast.date > today {
+            issues.push(issue(
+                FutureDate,
+                "roast_session",
+                roast.id,
+                roast.date,
+                format!("Roast is dated in the future ({})", roast.date),
+                format!("การคั่วลงวันที่ในอนาคต ({})", roast.date),
+            ));
+        }
+        let milestones: Vec<i32> = [
+            roast.turning_point_seconds,
+            roast.first_crack_seconds,
+            roast.second_crack_seconds,
+            roast.drop_seconds,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if milestones.iter().any(|s| *s < 0) || milestones.windows(2).any(|w| w[1] <= w[0]) {
+            issues.push(issue(
+                RoastMilestonesOutOfOrder,
+                "roast_session",
+                roast.id,
+                roast.date,
+                "Roast milestones are out of order (turning point, first crack, second crack, drop)"
+                    .to_string(),
+                "ลำดับเวลาการคั่วไม่ถูกต้อง (จุดกลับตัว แคร็กแรก แคร็กสอง และเทออก)".to_string(),
+            ));
+        }
+    }
+
+    issues.sort_by_key(|i| i.date);
+    issues
+}
+
+impl TraceabilityCheckService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Check a lot's timeline for impossible sequences
+    pub async fn check_lot(&self, business_id: Uuid, lot_id: Uuid) -> AppResult<TraceabilityCheck> {
+        let traceability_code = sqlx::query_scalar::<_, String>(
+            "SELECT traceability_code FROM lots WHERE id = $1 AND business_id = $2",
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lot".to_string()))?;
+
+        let dated = |rows: Vec<(Uuid, NaiveDate)>| -> Vec<DatedRecord> {
+            rows.into_iter().map(|(id, date)| DatedRecord { id, date }).collect()
+        };
+
+        let harvests = sqlx::query_as::<_, (Uuid, NaiveDate)>(
+            "SELECT id, harvest_date FROM harvests WHERE lot_id = $1",
+        )
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let processing = sqlx::query_as::<
+            _,
+            (Uuid, NaiveDate, Option<NaiveDate>, Option<NaiveDate>, Option<NaiveDate>, Option<NaiveDate>),
+        >(
+            r#"
+            SELECT id, start_date, end_date,
+                   (drying_log->>'start_date')::DATE,
+                   (drying_log->>'end_date')::DATE,
+                   (
+                       SELECT MIN((reading->>'timestamp')::TIMESTAMPTZ)::DATE
+                       FROM jsonb_array_elements(
+                           COALESCE(fermentation_log->'temperature_readings', '[]'::jsonb)
+                       ) reading
+                   )
+            FROM processing_records
+            WHERE lot_id = $1
+            "#,
+        )
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let gradings = sqlx::query_as::<_, (Uuid, NaiveDate)>(
+            "SELECT id, grading_date FROM green_bean_grades WHERE lot_id = $1",
+        )
+        .bind(lot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let cuppings = sqlx::query_as::<_, (Uuid, NaiveDate)>(
+            r#"
+            SELECT DISTINCT s.id, s.session_date
+            FROM cupping_sessions s
+            JOIN cupping_samples cs ON cs.session_id = s.id
+            WHERE cs.lot_id = $1 AND s.business_id = $2
+            "#,
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let roasts = sqlx::query_as::<_, (Uuid, NaiveDate, Option<i32>, Option<i32>, Option<i32>, Option<i32>)>(
+            r#"
+            SELECT id, session_date, turning_point_time_seconds, first_crack_time_seconds,
+                   second_crack_time_seconds, drop_time_seconds
+            FROM roast_sessions
+            WHERE lot_id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(lot_id)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let timeline = LotTimeline {
+            harvests: dated(harvests),
+            processing: processing
+                .into_iter()
+                .map(|(id, start_date, end_date, drying_start, drying_end, fermentation_start)| ProcessingSpan {
+                    id,
+                    start_date,
+                    end_date,
+                    drying_start,
+                    drying_end,
+                    fermentation_start,
+                })
+                .collect(),
+            gradings: dated(gradings),
+            cuppings: dated(cuppings),
+            roasts: roasts
+                .into_iter()
+                .map(|(id, date, turning, first, second, drop)| RoastTimeline {
+                    id,
+                    date,
+                    turning_point_seconds: turning,
+                    first_crack_seconds: first,
+                    second_crack_seconds: second,
+                    drop_seconds: drop,
+                })
+                .collect(),
+        };
+
+        let issues = check_timeline(&timeline, Utc::now().date_naive());
+        Ok(TraceabilityCheck {
+            lot_id,
+            traceability_code,
+            consistent: issues.is_empty(),
+            issues,
+        })
+    }
+}
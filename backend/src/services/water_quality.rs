@@ -0,0 +1,281 @@
+//! Water quality log for the cupping lab and processing
+//!
+//! Records TDS and pH of the water used for cupping and processing and
+//! warns when a measurement falls outside the SCA water standard.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::recycle_bin::{DeletedEntity, RecycleBinService};
+
+/// SCA water standard: acceptable TDS range in ppm (target 150)
+pub const SCA_TDS_MIN_PPM: Decimal = Decimal::from_parts(75, 0, 0, false, 0);
+pub const SCA_TDS_MAX_PPM: Decimal = Decimal::from_parts(250, 0, 0, false, 0);
+/// SCA water standard: acceptable pH range (target 7.0)
+pub const SCA_PH_MIN: Decimal = Decimal::from_parts(65, 0, 0, false, 1);
+pub const SCA_PH_MAX: Decimal = Decimal::from_parts(75, 0, 0, false, 1);
+
+/// Highest TDS accepted as a plausible reading
+const MAX_TDS_PPM: Decimal = Decimal::from_parts(5000, 0, 0, false, 0);
+
+/// Water quality service
+#[derive(Clone)]
+pub struct WaterQualityService {
+    db: PgPool,
+}
+
+/// Database row for a measurement
+#[derive(Debug, sqlx::FromRow)]
+struct WaterQualityRow {
+    id: Uuid,
+    business_id: Uuid,
+    measured_at: DateTime<Utc>,
+    source: String,
+    tds_ppm: Decimal,
+    ph: Decimal,
+    cupping_session_id: Option<Uuid>,
+    processing_record_id: Option<Uuid>,
+    notes: Option<String>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+/// Water quality measurement with any out-of-range warnings
+#[derive(Debug, Clone, Serialize)]
+pub struct WaterQualityMeasurement {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub measured_at: DateTime<Utc>,
+    pub source: String,
+    pub tds_ppm: Decimal,
+    pub ph: Decimal,
+    pub cupping_session_id: Option<Uuid>,
+    pub processing_record_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub within_sca_standard: bool,
+    pub warnings: Vec<WaterQualityWarning>,
+}
+
+/// A parameter outside the SCA recommended range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WaterQualityWarning {
+    pub parameter: &'static str,
+    pub value: Decimal,
+    pub min: Decimal,
+    pub max: Decimal,
+    pub message: String,
+    pub message_th: String,
+}
+
+/// Input for recording a measurement
+#[derive(Debug, Deserialize)]
+pub struct RecordWaterQualityInput {
+    pub source: String,
+    pub tds_ppm: Decimal,
+    pub ph: Decimal,
+    /// Defaults to now
+    pub measured_at: Option<DateTime<Utc>>,
+    pub cupping_session_id: Option<Uuid>,
+    pub processing_record_id: Option<Uuid>,
+    pub notes: Option<String>,
+}
+
+/// Filters for listing measurements
+#[derive(Debug, Default, Deserialize)]
+pub struct WaterQualityQuery {
+    pub cupping_session_id: Option<Uuid>,
+    pub processing_record_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// Compare a measurement with the SCA water standard
+pub fn water_quality_warnings(tds_ppm: Decimal, ph: Decimal) -> Vec<WaterQualityWarning> {
+    let mut warnings = Vec::new();
+
+    if tds_ppm < SCA_TDS_MIN_PPM || tds_ppm > SCA_TDS_MAX_PPM {
+        let (message, message_th) = if tds_ppm < SCA_TDS_MIN_PPM {
+            ("TDS is below the SCA range", "ค่า TDS ต่ำกว่าเกณฑ์ SCA")
+        } else {
+            ("TDS is above the SCA range", "ค่า TDS สูงกว่าเกณฑ์ SCA")
+        };
+        warnings.push(WaterQualityWarning {
+            parameter: "tds_ppm",
+            value: tds_ppm,
+            min: SCA_TDS_MIN_PPM,
+            max: SCA_TDS_MAX_PPM,
+            message: format!("{} of {}-{} ppm", message, SCA_TDS_MIN_PPM, SCA_TDS_MAX_PPM),
+            message_th: format!("{} ({}-{} ppm)", message_th, SCA_TDS_MIN_PPM, SCA_TDS_MAX_PPM),
+        });
+    }
+
+    if ph < SCA_PH_MIN || ph > SCA_PH_MAX {
+        let (message, message_th) = if ph < SCA_PH_MIN {
+            ("pH is below the SCA range", "ค่า pH ต่ำกว่าเกณฑ์ SCA")
+        } else {
+            ("pH is above the SCA range", "ค่า pH สูงกว่าเกณฑ์ SCA")
+        };
+        warnings.push(WaterQualityWarning {
+            parameter: "ph",
+            value: ph,
+            min: SCA_PH_MIN,
+            max: SCA_PH_MAX,
+            message: format!("{} of {}-{}", message, SCA_PH_MIN, SCA_PH_MAX),
+            message_th: format!("{} ({}-{})", message_th, SCA_PH_MIN, SCA_PH_MAX),
+        });
+    }
+
+    warnings
+}
+
+impl WaterQualityService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Record a measurement
+    pub async fn record(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: RecordWaterQualityInput,
+    ) -> AppResult<WaterQualityMeasurement> {
+        let source = input.source.trim();
+        if source.is_empty() {
+            return Err(AppError::Validation {
+                field: "source".to_string(),
+                message: "Water source is required".to_string(),
+                message_th: "ต้องระบุแหล่งน้ำ".to_string(),
+            });
+        }
+        if input.tds_ppm < Decimal::ZERO || input.tds_ppm > MAX_TDS_PPM {
+            return Err(AppError::Validation {
+                field: "tds_ppm".to_string(),
+                message: format!("TDS must be between 0 and {} ppm", MAX_TDS_PPM),
+                message_th: format!("ค่า TDS ต้องอยู่ระหว่าง 0 ถึง {} ppm", MAX_TDS_PPM),
+            });
+        }
+        if input.ph < Decimal::ZERO || input.ph > Decimal::from(14) {
+            return Err(AppError::Validation {
+                field: "ph".to_string(),
+                message: "pH must be between 0 and 14".to_string(),
+                message_th: "ค่า pH ต้องอยู่ระหว่าง 0 ถึง 14".to_string(),
+            });
+        }
+
+        if let Some(session_id) = input.cupping_session_id {
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM cupping_sessions WHERE id = $1 AND business_id = $2)",
+            )
+            .bind(session_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !exists {
+                return Err(AppError::NotFound("Cupping session".to_string()));
+            }
+        }
+        if let Some(processing_id) = input.processing_record_id {
+            let exists = sqlx::query_scalar::<_, bool>(
+                r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM processing_records pr
+                    JOIN lots l ON l.id = pr.lot_id
+                    WHERE pr.id = $1 AND l.business_id = $2
+                )
+                "#,
+            )
+            .bind(processing_id)
+            .bind(business_id)
+            .fetch_one(&self.db)
+            .await?;
+            if !exists {
+                return Err(AppError::NotFound("Processing record".to_string()));
+            }
+        }
+
+        let row = sqlx::query_as::<_, WaterQualityRow>(
+            r#"
+            INSERT INTO water_quality_measurements (
+                business_id, measured_at, source, tds_ppm, ph,
+                cupping_session_id, processing_record_id, notes, created_by
+            )
+            VALUES ($1, COALESCE($2, NOW()), $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, business_id, measured_at, source, tds_ppm, ph,
+                      cupping_session_id, processing_record_id, notes, created_by, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.measured_at)
+        .bind(source)
+        .bind(input.tds_ppm)
+        .bind(input.ph)
+        .bind(input.cupping_session_id)
+        .bind(input.processing_record_id)
+        .bind(&input.notes)
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(Self::row_to_measurement(row))
+    }
+
+    /// List measurements, newest first
+    pub async fn list(
+        &self,
+        business_id: Uuid,
+        query: &WaterQualityQuery,
+    ) -> AppResult<Vec<WaterQualityMeasurement>> {
+        let rows = sqlx::query_as::<_, WaterQualityRow>(
+            r#"
+            SELECT id, business_id, measured_at, source, tds_ppm, ph,
+                   cupping_session_id, processing_record_id, notes, created_by, created_at
+            FROM water_quality_measurements
+            WHERE business_id = $1
+              AND ($2::UUID IS NULL OR cupping_session_id = $2)
+              AND ($3::UUID IS NULL OR processing_record_id = $3)
+            ORDER BY measured_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(business_id)
+        .bind(query.cupping_session_id)
+        .bind(query.processing_record_id)
+        .bind(query.limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_measurement).collect())
+    }
+
+    /// Delete a measurement, keeping it in the recycle bin
+    pub async fn delete(&self, business_id: Uuid, user_id: Uuid, measurement_id: Uuid) -> AppResult<()> {
+        RecycleBinService::new(self.db.clone())
+            .delete(business_id, user_id, DeletedEntity::WaterQuality, measurement_id)
+            .await
+    }
+
+    fn row_to_measurement(row: WaterQualityRow) -> WaterQualityMeasurement {
+        let warnings = water_quality_warnings(row.tds_ppm, row.ph);
+        WaterQualityMeasurement {
+            id: row.id,
+            business_id: row.business_id,
+            measured_at: row.measured_at,
+            source: row.source,
+            tds_ppm: row.tds_ppm,
+            ph: row.ph,
+            cupping_session_id: row.cupping_session_id,
+            processing_record_id: row.processing_record_id,
+            notes: row.notes,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            within_sca_standard: warnings.is_empty(),
+            warnings,
+        }
+    }
+}
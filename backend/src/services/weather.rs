@@ -8,6 +8,7 @@ use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::services::notification::AlertSeverity;
 use crate::external::weather::{CurrentWeather, WeatherClient, WeatherForecast};
 
 /// Weather service for managing weather data
@@ -42,6 +43,9 @@ pub struct WeatherSnapshot {
     pub sunrise: Option<DateTime<Utc>>,
     pub sunset: Option<DateTime<Utc>>,
     pub source: String,
+    /// Plot of the weather station the reading came from
+    pub plot_id: Option<Uuid>,
+    pub weather_station_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -83,8 +87,12 @@ pub struct WeatherAlert {
     pub last_triggered_at: Option<DateTime<Utc>>,
     pub notify_email: bool,
     pub notify_line: bool,
+    pub severity: AlertSeverity,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// End of the current snooze; None once the snooze has expired
+    pub muted_until: Option<DateTime<Utc>>,
+    pub is_muted: bool,
 }
 
 /// Input for creating weather alert
@@ -96,6 +104,17 @@ pub struct CreateWeatherAlertInput {
     pub threshold_unit: Option<String>,
     pub notify_email: Option<bool>,
     pub notify_line: Option<bool>,
+    /// Default: warning
+    pub severity: Option<AlertSeverity>,
+}
+
+/// Input for snoozing weather alerts: either a number of days or an end time
+#[derive(Debug, Deserialize)]
+pub struct SnoozeWeatherAlertInput {
+    pub days: Option<i64>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only snooze alerts of this type when snoozing a whole plot
+    pub alert_type: Option<String>,
 }
 
 /// Cached weather forecast
@@ -113,6 +132,124 @@ pub struct CachedForecast {
     pub created_at: DateTime<Utc>,
 }
 
+/// Maximum manual rain gauge reading accepted for a single observation (mm)
+pub const MAX_RAINFALL_MM: i64 = 500;
+
+/// Radius around a plot for API snapshots in plot-level daily aggregates (km)
+pub const PLOT_WEATHER_RADIUS_KM: i64 = 5;
+
+/// Longest a weather alert can be snoozed (roughly one monsoon season)
+pub const MAX_SNOOZE_DAYS: i64 = 120;
+
+/// Manual rain gauge observation for a plot
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RainfallObservation {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub plot_id: Uuid,
+    pub observed_on: NaiveDate,
+    pub rainfall_mm: Decimal,
+    pub source: String,
+    pub recorded_by: Option<Uuid>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for recording a rain gauge reading
+#[derive(Debug, Deserialize)]
+pub struct RecordRainfallInput {
+    pub plot_id: Uuid,
+    /// Defaults to today
+    pub observed_on: Option<NaiveDate>,
+    pub rainfall_mm: Decimal,
+    pub notes: Option<String>,
+}
+
+/// Daily weather aggregate combining API snapshots and manual rain gauges
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DailyWeatherSummary {
+    pub date: NaiveDate,
+    pub temperature_min_celsius: Option<Decimal>,
+    pub temperature_max_celsius: Option<Decimal>,
+    pub temperature_avg_celsius: Option<Decimal>,
+    pub humidity_avg_percent: Option<Decimal>,
+    /// Sum of hourly rain from API snapshots
+    pub api_rainfall_mm: Option<Decimal>,
+    /// Gauge total per plot, averaged across plots with readings
+    pub manual_rainfall_mm: Option<Decimal>,
+    /// Best available rainfall: manual gauges take precedence over API data
+    pub rainfall_mm: Option<Decimal>,
+    /// "manual" or "api"
+    pub rainfall_source: Option<String>,
+    pub snapshot_count: i64,
+    pub manual_observation_count: i64,
+}
+
+/// API snapshot aggregate for one day
+#[derive(Debug, FromRow)]
+struct DailySnapshotRow {
+    day: NaiveDate,
+    temperature_min: Decimal,
+    temperature_max: Decimal,
+    temperature_avg: Decimal,
+    humidity_avg: Option<Decimal>,
+    rain_mm: Option<Decimal>,
+    snapshot_count: i64,
+}
+
+/// Manual rainfall aggregate for one day
+#[derive(Debug, FromRow)]
+struct DailyRainfallRow {
+    day: NaiveDate,
+    rainfall_mm: Decimal,
+    observation_count: i64,
+}
+
+/// Pick the rainfall figure for a day: gauge readings measured on the plot
+/// win over interpolated API data
+pub fn merge_rainfall(
+    api_rainfall_mm: Option<Decimal>,
+    manual_rainfall_mm: Option<Decimal>,
+) -> (Option<Decimal>, Option<&'static str>) {
+    match (manual_rainfall_mm, api_rainfall_mm) {
+        (Some(manual), _) => (Some(manual), Some("manual")),
+        (None, Some(api)) => (Some(api), Some("api")),
+        (None, None) => (None, None),
+    }
+}
+
+/// Resolve a snooze request (either a number of days or an end time) into the
+/// time the alert unmutes; None when the request is missing or out of range
+pub fn resolve_snooze_until(
+    now: DateTime<Utc>,
+    days: Option<i64>,
+    until: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    match (days, until) {
+        (Some(days), None) if (1..=MAX_SNOOZE_DAYS).contains(&days) => {
+            Some(now + Duration::days(days))
+        }
+        (None, Some(until)) if until > now && until <= now + Duration::days(MAX_SNOOZE_DAYS) => {
+            Some(until)
+        }
+        _ => None,
+    }
+}
+
+fn invalid_snooze() -> AppError {
+    AppError::Validation {
+        field: "days".to_string(),
+        message: format!(
+            "Provide either days or until, snoozing for 1 to {} days",
+            MAX_SNOOZE_DAYS
+        ),
+        message_th: format!(
+            "กรุณาระบุจำนวนวันหรือวันสิ้นสุด โดยปิดการแจ้งเตือนได้ 1 ถึง {} วัน",
+            MAX_SNOOZE_DAYS
+        ),
+    }
+}
+
 impl WeatherService {
     /// Create a new WeatherService instance
     pub fn new(db: PgPool) -> Self {
@@ -153,7 +290,7 @@ impl WeatherService {
                       temperature_celsius, feels_like_celsius, humidity_percent, pressure_hpa,
                       wind_speed_mps, wind_direction_deg, cloud_coverage_percent, visibility_meters,
                       weather_condition, weather_description, weather_icon,
-                      rain_1h_mm, rain_3h_mm, sunrise, sunset, source, created_at
+                      rain_1h_mm, rain_3h_mm, sunrise, sunset, source, plot_id, weather_station_id, created_at
             "#,
         )
         .bind(business_id)
@@ -229,7 +366,7 @@ impl WeatherService {
                    temperature_celsius, feels_like_celsius, humidity_percent, pressure_hpa,
                    wind_speed_mps, wind_direction_deg, cloud_coverage_percent, visibility_meters,
                    weather_condition, weather_description, weather_icon,
-                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, created_at
+                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, plot_id, weather_station_id, created_at
             FROM weather_snapshots
             WHERE id = $1 AND business_id = $2
             "#,
@@ -256,7 +393,7 @@ impl WeatherService {
                    temperature_celsius, feels_like_celsius, humidity_percent, pressure_hpa,
                    wind_speed_mps, wind_direction_deg, cloud_coverage_percent, visibility_meters,
                    weather_condition, weather_description, weather_icon,
-                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, created_at
+                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, plot_id, weather_station_id, created_at
             FROM weather_snapshots
             WHERE business_id = $1
               AND recorded_at >= $2::date
@@ -290,7 +427,7 @@ impl WeatherService {
                    temperature_celsius, feels_like_celsius, humidity_percent, pressure_hpa,
                    wind_speed_mps, wind_direction_deg, cloud_coverage_percent, visibility_meters,
                    weather_condition, weather_description, weather_icon,
-                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, created_at
+                   rain_1h_mm, rain_3h_mm, sunrise, sunset, source, plot_id, weather_station_id, created_at
             FROM weather_snapshots
             WHERE business_id = $1
               AND recorded_at > $2
@@ -375,7 +512,8 @@ impl WeatherService {
                    ws.temperature_celsius, ws.feels_like_celsius, ws.humidity_percent, ws.pressure_hpa,
                    ws.wind_speed_mps, ws.wind_direction_deg, ws.cloud_coverage_percent, ws.visibility_meters,
                    ws.weather_condition, ws.weather_description, ws.weather_icon,
-                   ws.rain_1h_mm, ws.rain_3h_mm, ws.sunrise, ws.sunset, ws.source, ws.created_at
+                   ws.rain_1h_mm, ws.rain_3h_mm, ws.sunrise, ws.sunset, ws.source, ws.plot_id, ws.weather_station_id,
+                   ws.created_at
             FROM weather_snapshots ws
             JOIN harvests h ON h.weather_snapshot_id = ws.id
             JOIN lots l ON l.id = h.lot_id
@@ -507,6 +645,200 @@ impl WeatherService {
         Ok(forecast)
     }
 
+    // ========================================================================
+    // Manual Rainfall and Daily Aggregates
+    // ========================================================================
+
+    /// Record a manual rain gauge reading for a plot
+    pub async fn record_rainfall(
+        &self,
+        business_id: Uuid,
+        recorded_by: Option<Uuid>,
+        source: &str,
+        input: RecordRainfallInput,
+    ) -> AppResult<RainfallObservation> {
+        if input.rainfall_mm < Decimal::ZERO || input.rainfall_mm > Decimal::from(MAX_RAINFALL_MM) {
+            return Err(AppError::Validation {
+                field: "rainfall_mm".to_string(),
+                message: format!("Rainfall must be between 0 and {} mm", MAX_RAINFALL_MM),
+                message_th: format!("ปริมาณฝนต้องอยู่ระหว่าง 0 ถึง {} มม.", MAX_RAINFALL_MM),
+            });
+        }
+
+        let plot_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM plots WHERE id = $1 AND business_id = $2)",
+        )
+        .bind(input.plot_id)
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        if !plot_exists {
+            return Err(AppError::NotFound("Plot".to_string()));
+        }
+
+        let observed_on = input
+            .observed_on
+            .unwrap_or_else(|| chrono::Local::now().date_naive());
+
+        let observation = sqlx::query_as::<_, RainfallObservation>(
+            r#"
+            INSERT INTO rainfall_observations (
+                business_id, plot_id, observed_on, rainfall_mm, source, recorded_by, notes
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, business_id, plot_id, observed_on, rainfall_mm, source,
+                      recorded_by, notes, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.plot_id)
+        .bind(observed_on)
+        .bind(input.rainfall_mm.round_dp(2))
+        .bind(source)
+        .bind(recorded_by)
+        .bind(&input.notes)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(observation)
+    }
+
+    /// List manual rain gauge readings for a date range
+    pub async fn list_rainfall(
+        &self,
+        business_id: Uuid,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        plot_id: Option<Uuid>,
+    ) -> AppResult<Vec<RainfallObservation>> {
+        let observations = sqlx::query_as::<_, RainfallObservation>(
+            r#"
+            SELECT id, business_id, plot_id, observed_on, rainfall_mm, source,
+                   recorded_by, notes, created_at
+            FROM rainfall_observations
+            WHERE business_id = $1
+              AND observed_on BETWEEN $2 AND $3
+              AND ($4::uuid IS NULL OR plot_id = $4)
+            ORDER BY observed_on DESC, created_at DESC
+            "#,
+        )
+        .bind(business_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(plot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(observations)
+    }
+
+    /// Daily weather aggregates merging API snapshots with manual rain gauges.
+    /// With a plot, only snapshots near the plot and that plot's gauge are used.
+    pub async fn get_daily_weather(
+        &self,
+        business_id: Uuid,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        plot_id: Option<Uuid>,
+    ) -> AppResult<Vec<DailyWeatherSummary>> {
+        let snapshots = sqlx::query_as::<_, DailySnapshotRow>(
+            r#"
+            SELECT ws.recorded_at::date AS day,
+                   MIN(ws.temperature_celsius) AS temperature_min,
+                   MAX(ws.temperature_celsius) AS temperature_max,
+                   ROUND(AVG(ws.temperature_celsius), 2) AS temperature_avg,
+                   ROUND(AVG(ws.humidity_percent), 1) AS humidity_avg,
+                   SUM(ws.rain_1h_mm) AS rain_mm,
+                   COUNT(*) AS snapshot_count
+            FROM weather_snapshots ws
+            LEFT JOIN plots p ON p.id = $4 AND p.business_id = ws.business_id
+            WHERE ws.business_id = $1
+              AND ws.recorded_at >= $2::date
+              AND ws.recorded_at < ($3::date + INTERVAL '1 day')
+              AND (
+                  $4::uuid IS NULL OR SQRT(
+                      POWER((ws.latitude - p.latitude) * 111, 2) +
+                      POWER((ws.longitude - p.longitude) * 102, 2)
+                  ) <= $5
+              )
+            GROUP BY day
+            "#,
+        )
+        .bind(business_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(plot_id)
+        .bind(PLOT_WEATHER_RADIUS_KM)
+        .fetch_all(&self.db)
+        .await?;
+
+        let rainfall = sqlx::query_as::<_, DailyRainfallRow>(
+            r#"
+            SELECT observed_on AS day,
+                   ROUND(AVG(plot_total), 2) AS rainfall_mm,
+                   SUM(readings)::bigint AS observation_count
+            FROM (
+                SELECT observed_on, plot_id, SUM(rainfall_mm) AS plot_total, COUNT(*) AS readings
+                FROM rainfall_observations
+                WHERE business_id = $1
+                  AND observed_on BETWEEN $2 AND $3
+                  AND ($4::uuid IS NULL OR plot_id = $4)
+                GROUP BY observed_on, plot_id
+            ) per_plot
+            GROUP BY observed_on
+            "#,
+        )
+        .bind(business_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(plot_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut days: std::collections::BTreeMap<NaiveDate, DailyWeatherSummary> =
+            std::collections::BTreeMap::new();
+        let empty_day = |date| DailyWeatherSummary {
+            date,
+            temperature_min_celsius: None,
+            temperature_max_celsius: None,
+            temperature_avg_celsius: None,
+            humidity_avg_percent: None,
+            api_rainfall_mm: None,
+            manual_rainfall_mm: None,
+            rainfall_mm: None,
+            rainfall_source: None,
+            snapshot_count: 0,
+            manual_observation_count: 0,
+        };
+
+        for row in snapshots {
+            let day = days.entry(row.day).or_insert_with(|| empty_day(row.day));
+            day.temperature_min_celsius = Some(row.temperature_min);
+            day.temperature_max_celsius = Some(row.temperature_max);
+            day.temperature_avg_celsius = Some(row.temperature_avg);
+            day.humidity_avg_percent = row.humidity_avg;
+            day.api_rainfall_mm = row.rain_mm;
+            day.snapshot_count = row.snapshot_count;
+        }
+
+        for row in rainfall {
+            let day = days.entry(row.day).or_insert_with(|| empty_day(row.day));
+            day.manual_rainfall_mm = Some(row.rainfall_mm);
+            day.manual_observation_count = row.observation_count;
+        }
+
+        Ok(days
+            .into_values()
+            .map(|mut day| {
+                let (rainfall_mm, source) = merge_rainfall(day.api_rainfall_mm, day.manual_rainfall_mm);
+                day.rainfall_mm = rainfall_mm;
+                day.rainfall_source = source.map(str::to_string);
+                day
+            })
+            .collect())
+    }
+
     // ========================================================================
     // Weather Alerts
     // ========================================================================
@@ -549,11 +881,12 @@ impl WeatherService {
             r#"
             INSERT INTO weather_alerts (
                 business_id, plot_id, alert_type, threshold_value, threshold_unit,
-                notify_email, notify_line
+                notify_email, notify_line, severity
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
-                      is_active, last_triggered_at, notify_email, notify_line, created_at, updated_at
+                      is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                      muted_until, false AS is_muted
             "#,
         )
         .bind(business_id)
@@ -563,6 +896,7 @@ impl WeatherService {
         .bind(&input.threshold_unit)
         .bind(notify_email)
         .bind(notify_line)
+        .bind(input.severity.unwrap_or(AlertSeverity::Warning))
         .fetch_one(&self.db)
         .await?;
 
@@ -574,7 +908,9 @@ impl WeatherService {
         let alerts = sqlx::query_as::<_, WeatherAlert>(
             r#"
             SELECT id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
-                   is_active, last_triggered_at, notify_email, notify_line, created_at, updated_at
+                   is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                   CASE WHEN muted_until > NOW() THEN muted_until END AS muted_until,
+                   COALESCE(muted_until > NOW(), false) AS is_muted
             FROM weather_alerts
             WHERE business_id = $1
             ORDER BY created_at DESC
@@ -602,6 +938,92 @@ impl WeatherService {
         Ok(())
     }
 
+    /// Snooze a weather alert; it unmutes automatically once the snooze expires
+    pub async fn snooze_alert(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        alert_id: Uuid,
+        input: SnoozeWeatherAlertInput,
+    ) -> AppResult<WeatherAlert> {
+        let muted_until = resolve_snooze_until(Utc::now(), input.days, input.until)
+            .ok_or_else(invalid_snooze)?;
+
+        sqlx::query_as::<_, WeatherAlert>(
+            r#"
+            UPDATE weather_alerts
+            SET muted_until = $3, muted_by = $4, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            RETURNING id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
+                      is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                      muted_until, true AS is_muted
+            "#,
+        )
+        .bind(alert_id)
+        .bind(business_id)
+        .bind(muted_until)
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Weather alert".to_string()))
+    }
+
+    /// Snooze every alert configured for a plot, optionally of a single type
+    pub async fn snooze_plot_alerts(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        plot_id: Uuid,
+        input: SnoozeWeatherAlertInput,
+    ) -> AppResult<Vec<WeatherAlert>> {
+        let muted_until = resolve_snooze_until(Utc::now(), input.days, input.until)
+            .ok_or_else(invalid_snooze)?;
+
+        let alerts = sqlx::query_as::<_, WeatherAlert>(
+            r#"
+            UPDATE weather_alerts
+            SET muted_until = $4, muted_by = $5, updated_at = NOW()
+            WHERE business_id = $1 AND plot_id = $2
+              AND ($3::VARCHAR IS NULL OR alert_type = $3)
+            RETURNING id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
+                      is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                      muted_until, true AS is_muted
+            "#,
+        )
+        .bind(business_id)
+        .bind(plot_id)
+        .bind(&input.alert_type)
+        .bind(muted_until)
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        if alerts.is_empty() {
+            return Err(AppError::NotFound("Weather alert".to_string()));
+        }
+
+        Ok(alerts)
+    }
+
+    /// Unmute a snoozed weather alert before its snooze expires
+    pub async fn unmute_alert(&self, business_id: Uuid, alert_id: Uuid) -> AppResult<WeatherAlert> {
+        sqlx::query_as::<_, WeatherAlert>(
+            r#"
+            UPDATE weather_alerts
+            SET muted_until = NULL, muted_by = NULL, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            RETURNING id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
+                      is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                      muted_until, false AS is_muted
+            "#,
+        )
+        .bind(alert_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Weather alert".to_string()))
+    }
+
     /// Check for rain alerts based on forecast
     pub async fn check_rain_alerts(
         &self,
@@ -611,7 +1033,9 @@ impl WeatherService {
         let alerts = sqlx::query_as::<_, WeatherAlert>(
             r#"
             SELECT id, business_id, plot_id, alert_type, threshold_value, threshold_unit,
-                   is_active, last_triggered_at, notify_email, notify_line, created_at, updated_at
+                   is_active, last_triggered_at, notify_email, notify_line, severity, created_at, updated_at,
+                   CASE WHEN muted_until > NOW() THEN muted_until END AS muted_until,
+                   COALESCE(muted_until > NOW(), false) AS is_muted
             FROM weather_alerts
             WHERE business_id = $1 AND alert_type = 'rain_forecast' AND is_active = true
             "#,
@@ -0,0 +1,645 @@
+//! Weather stations pushing readings by webhook
+//!
+//! A farm's own station (Davis, Ecowitt or another make) posts its readings
+//! to `/webhook/weather-stations/:token`. The station's field mapping says
+//! where each reading sits in the payload and in which unit, starting from
+//! a template for the make. Readings are converted to metric units and
+//! stored as weather snapshots tied to the station's plot.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::weather::WeatherSnapshot;
+
+/// Source recorded on snapshots received from a station
+pub const STATION_SOURCE: &str = "weather_station";
+
+/// Weather station service
+#[derive(Clone)]
+pub struct WeatherStationService {
+    db: PgPool,
+}
+
+/// Make of station a field mapping template exists for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StationVendor {
+    Davis,
+    Ecowitt,
+    Custom,
+}
+
+impl StationVendor {
+    pub const ALL: [StationVendor; 3] = [StationVendor::Davis, StationVendor::Ecowitt, StationVendor::Custom];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StationVendor::Davis => "davis",
+            StationVendor::Ecowitt => "ecowitt",
+            StationVendor::Custom => "custom",
+        }
+    }
+}
+
+/// Snapshot field a station reading can fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StationField {
+    RecordedAt,
+    TemperatureCelsius,
+    HumidityPercent,
+    PressureHpa,
+    WindSpeedMps,
+    WindDirectionDeg,
+    #[serde(rename = "rain_1h_mm")]
+    Rain1hMm,
+}
+
+impl StationField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StationField::RecordedAt => "recorded_at",
+            StationField::TemperatureCelsius => "temperature_celsius",
+            StationField::HumidityPercent => "humidity_percent",
+            StationField::PressureHpa => "pressure_hpa",
+            StationField::WindSpeedMps => "wind_speed_mps",
+            StationField::WindDirectionDeg => "wind_direction_deg",
+            StationField::Rain1hMm => "rain_1h_mm",
+        }
+    }
+
+    /// Units the payload may give the field in; the first is the default
+    pub fn units(&self) -> &'static [&'static str] {
+        match self {
+            StationField::RecordedAt => &["unix", "unix_ms", "datetime"],
+            StationField::TemperatureCelsius => &["c", "f"],
+            StationField::HumidityPercent => &["percent"],
+            StationField::PressureHpa => &["hpa", "inhg", "mmhg"],
+            StationField::WindSpeedMps => &["mps", "kmh", "mph", "knots"],
+            StationField::WindDirectionDeg => &["deg"],
+            StationField::Rain1hMm => &["mm", "in"],
+        }
+    }
+}
+
+/// Where a field sits in the station's payload and its unit there
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldMapping {
+    pub field: StationField,
+    /// Dot-separated path, with array positions as numbers
+    /// (`data.conditions.0.temp`)
+    pub path: String,
+    /// Defaults to the field's first unit
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+/// Field mapping template of a station make
+#[derive(Debug, Clone, Serialize)]
+pub struct StationTemplate {
+    pub vendor: StationVendor,
+    pub field_mapping: Vec<FieldMapping>,
+}
+
+/// Weather station of a plot
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WeatherStation {
+    pub id: Uuid,
+    pub business_id: Uuid,
+    pub plot_id: Uuid,
+    pub plot_name: String,
+    pub name: String,
+    pub vendor: String,
+    pub field_mapping: sqlx::types::Json<Vec<FieldMapping>>,
+    /// Goes in the station's upload URL
+    pub webhook_token: String,
+    pub active: bool,
+    pub last_received_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const STATION_SELECT: &str = r#"
+    SELECT s.id, s.business_id, s.plot_id, p.name AS plot_name, s.name, s.vendor, s.field_mapping,
+           s.webhook_token, s.active, s.last_received_at, s.created_at, s.updated_at
+    FROM weather_stations s
+    JOIN plots p ON p.id = s.plot_id
+"#;
+
+/// Input for adding a station
+#[derive(Debug, Deserialize)]
+pub struct CreateWeatherStationInput {
+    pub plot_id: Uuid,
+    pub name: String,
+    pub vendor: StationVendor,
+    /// Defaults to the vendor's template; required for custom stations
+    pub field_mapping: Option<Vec<FieldMapping>>,
+}
+
+/// Input for editing a station; fields left out stay as they are
+#[derive(Debug, Deserialize)]
+pub struct UpdateWeatherStationInput {
+    pub plot_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub field_mapping: Option<Vec<FieldMapping>>,
+    pub active: Option<bool>,
+}
+
+/// Readings taken from a payload, in metric units
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StationReading {
+    pub recorded_at: Option<DateTime<Utc>>,
+    pub temperature_celsius: Decimal,
+    pub humidity_percent: Option<i32>,
+    pub pressure_hpa: Option<i32>,
+    pub wind_speed_mps: Option<Decimal>,
+    pub wind_direction_deg: Option<i32>,
+    pub rain_1h_mm: Option<Decimal>,
+}
+
+/// Reply to a station's upload
+#[derive(Debug, Clone, Serialize)]
+pub struct StationUploadAck {
+    pub station_id: Uuid,
+    pub snapshot_id: Uuid,
+    pub recorded_at: DateTime<Utc>,
+    /// False when the station resent a reading already stored
+    pub created: bool,
+}
+
+fn validation(field: &str, message: &str, message_th: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+        message_th: message_th.to_string(),
+    }
+}
+
+/// Field mapping a station make starts from. Davis maps the current
+/// conditions of a WeatherLink Live; Ecowitt the fields of its custom
+/// server upload.
+pub fn station_template(vendor: StationVendor) -> Vec<FieldMapping> {
+    let mapping = |field: StationField, path: &str, unit: &str| FieldMapping {
+        field,
+        path: path.to_string(),
+        unit: Some(unit.to_string()),
+    };
+    match vendor {
+        StationVendor::Davis => vec![
+            mapping(StationField::RecordedAt, "data.ts", "unix"),
+            mapping(StationField::TemperatureCelsius, "data.conditions.0.temp", "f"),
+            mapping(StationField::HumidityPercent, "data.conditions.0.hum", "percent"),
+            mapping(StationField::WindSpeedMps, "data.conditions.0.wind_speed_last", "mph"),
+            mapping(StationField::WindDirectionDeg, "data.conditions.0.wind_dir_last", "deg"),
+            mapping(StationField::PressureHpa, "data.conditions.2.bar_sea_level", "inhg"),
+        ],
+        StationVendor::Ecowitt => vec![
+            mapping(StationField::RecordedAt, "dateutc", "datetime"),
+            mapping(StationField::TemperatureCelsius, "tempf", "f"),
+            mapping(StationField::HumidityPercent, "humidity", "percent"),
+            mapping(StationField::PressureHpa, "baromrelin", "inhg"),
+            mapping(StationField::WindSpeedMps, "windspeedmph", "mph"),
+            mapping(StationField::WindDirectionDeg, "winddir", "deg"),
+            mapping(StationField::Rain1hMm, "hourlyrainin", "in"),
+        ],
+        StationVendor::Custom => Vec::new(),
+    }
+}
+
+/// Check a field mapping and fill in default units. Temperature must be
+/// mapped since every snapshot has one, and each field at most once.
+pub fn validate_mapping(mapping: &[FieldMapping]) -> AppResult<Vec<FieldMapping>> {
+    if !mapping.iter().any(|m| m.field == StationField::TemperatureCelsius) {
+        return Err(validation(
+            "field_mapping",
+            "Map the station's temperature",
+            "กรุณาระบุฟิลด์อุณหภูมิของสถานี",
+        ));
+    }
+
+    let mut checked: Vec<FieldMapping> = Vec::with_capacity(mapping.len());
+    for entry in mapping {
+        let field = format!("field_mapping.{}", entry.field.as_str());
+        if checked.iter().any(|c| c.field == entry.field) {
+            return Err(validation(&field, "Each field can be mapped once", "แต่ละฟิลด์ระบุได้เพียงครั้งเดียว"));
+        }
+        let path = entry.path.trim();
+        if path.is_empty() || path.split('.').any(str::is_empty) {
+            return Err(validation(&field, "Path is required", "ต้องระบุตำแหน่งของข้อมูล"));
+        }
+        let units = entry.field.units();
+        let unit = entry.unit.as_deref().map(str::trim).unwrap_or(units[0]).to_lowercase();
+        if !units.contains(&unit.as_str()) {
+            return Err(validation(
+                &field,
+                &format!("Unit must be one of: {}", units.join(", ")),
+                &format!("หน่วยต้องเป็นหนึ่งใน: {}", units.join(", ")),
+            ));
+        }
+        checked.push(FieldMapping {
+            field: entry.field,
+            path: path.to_string(),
+            unit: Some(unit),
+        });
+    }
+    Ok(checked)
+}
+
+/// Value at a dot-separated path of the payload
+pub fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(payload, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// Number given as a JSON number or a string, as form uploads send them
+fn number(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => n.to_string().parse::<Decimal>().ok().or_else(|| n.as_f64().and_then(Decimal::from_f64_retain)),
+        Value::String(s) => s.trim().parse::<Decimal>().ok(),
+        _ => None,
+    }
+}
+
+/// Convert a reading to the metric unit of its field
+pub fn to_metric(value: Decimal, unit: &str) -> Decimal {
+    match unit {
+        "f" => (value - Decimal::from(32)) * Decimal::from(5) / Decimal::from(9),
+        "inhg" => value * Decimal::new(338639, 4),
+        "mmhg" => value * Decimal::new(133322, 5),
+        "kmh" => value / Decimal::new(36, 1),
+        "mph" => value * Decimal::new(44704, 5),
+        "knots" => value * Decimal::new(514444, 6),
+        "in" => value * Decimal::new(254, 1),
+        _ => value,
+    }
+}
+
+/// Time of a reading; datetimes without an offset are UTC
+fn timestamp(value: &Value, unit: &str) -> Option<DateTime<Utc>> {
+    match unit {
+        "unix" => number(value)?.to_i64().and_then(|s| Utc.timestamp_opt(s, 0).single()),
+        "unix_ms" => number(value)?.to_i64().and_then(|ms| Utc.timestamp_millis_opt(ms).single()),
+        _ => {
+            let text = value.as_str()?.trim();
+            DateTime::parse_from_rfc3339(text)
+                .map(|t| t.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+                        .ok()
+                        .map(|t| t.and_utc())
+                })
+        }
+    }
+}
+
+fn out_of_range(field: StationField, value: Decimal, min: i32, max: i32) -> AppError {
+    validation(
+        field.as_str(),
+        &format!("{} of {} is outside {} to {}; check the station's field mapping", field.as_str(), value, min, max),
+        &format!("{} มีค่า {} อยู่นอกช่วง {} ถึง {} กรุณาตรวจสอบการจับคู่ฟิลด์ของสถานี", field.as_str(), value, min, max),
+    )
+}
+
+/// Take the readings out of a station payload. Fields missing from the
+/// payload are left empty, except temperature which every snapshot needs;
+/// readings outside plausible ranges point to a wrong mapping and are
+/// rejected.
+pub fn read_payload(payload: &Value, mapping: &[FieldMapping]) -> AppResult<StationReading> {
+    let mut reading = StationReading::default();
+    let mut temperature = None;
+
+    for entry in mapping {
+        let unit = entry.unit.as_deref().unwrap_or(entry.field.units()[0]);
+        let Some(value) = lookup(payload, &entry.path) else {
+            continue;
+        };
+        if entry.field == StationField::RecordedAt {
+            reading.recorded_at = timestamp(value, unit);
+            continue;
+        }
+        let Some(raw) = number(value) else {
+            continue;
+        };
+        let metric = to_metric(raw, unit);
+        let within = |min: i32, max: i32| {
+            if metric < Decimal::from(min) || metric > Decimal::from(max) {
+                Err(out_of_range(entry.field, metric.round_dp(2), min, max))
+            } else {
+                Ok(metric)
+            }
+        };
+        match entry.field {
+            StationField::RecordedAt => {}
+            StationField::TemperatureCelsius => temperature = Some(within(-60, 70)?.round_dp(2)),
+            StationField::HumidityPercent => reading.humidity_percent = within(0, 100)?.round().to_i32(),
+            StationField::PressureHpa => reading.pressure_hpa = within(500, 1100)?.round().to_i32(),
+            StationField::WindSpeedMps => reading.wind_speed_mps = Some(within(0, 120)?.round_dp(2)),
+            StationField::WindDirectionDeg => reading.wind_direction_deg = within(0, 360)?.round().to_i32(),
+            StationField::Rain1hMm => reading.rain_1h_mm = Some(within(0, 500)?.round_dp(2)),
+        }
+    }
+
+    reading.temperature_celsius = temperature.ok_or_else(|| {
+        validation(
+            StationField::TemperatureCelsius.as_str(),
+            "The payload has no temperature at the mapped path",
+            "ไม่พบอุณหภูมิในข้อมูลตามตำแหน่งที่ระบุ",
+        )
+    })?;
+    Ok(reading)
+}
+
+fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+impl WeatherStationService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Stations of the business
+    pub async fn list_stations(&self, business_id: Uuid) -> AppResult<Vec<WeatherStation>> {
+        let stations = sqlx::query_as::<_, WeatherStation>(&format!(
+            "{} WHERE s.business_id = $1 ORDER BY p.name, s.name",
+            STATION_SELECT
+        ))
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(stations)
+    }
+
+    /// A station of the business
+    pub async fn get_station(&self, business_id: Uuid, station_id: Uuid) -> AppResult<WeatherStation> {
+        sqlx::query_as::<_, WeatherStation>(&format!(
+            "{} WHERE s.id = $1 AND s.business_id = $2",
+            STATION_SELECT
+        ))
+        .bind(station_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Weather station".to_string()))
+    }
+
+    /// Add a station to a plot; the response holds its webhook token
+    pub async fn create_station(
+        &self,
+        business_id: Uuid,
+        user_id: Uuid,
+        input: CreateWeatherStationInput,
+    ) -> AppResult<WeatherStation> {
+        let name = self.check_name(business_id, &input.name, None).await?;
+        self.check_plot(business_id, input.plot_id).await?;
+        let mapping = validate_mapping(
+            &input
+                .field_mapping
+                .unwrap_or_else(|| station_template(input.vendor)),
+        )?;
+
+        let station_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO weather_stations (business_id, plot_id, name, vendor, field_mapping, webhook_token, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(business_id)
+        .bind(input.plot_id)
+        .bind(&name)
+        .bind(input.vendor.as_str())
+        .bind(sqlx::types::Json(&mapping))
+        .bind(new_token())
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.get_station(business_id, station_id).await
+    }
+
+    /// Edit a station
+    pub async fn update_station(
+        &self,
+        business_id: Uuid,
+        station_id: Uuid,
+        input: UpdateWeatherStationInput,
+    ) -> AppResult<WeatherStation> {
+        let existing = self.get_station(business_id, station_id).await?;
+        let name = match &input.name {
+            Some(name) => self.check_name(business_id, name, Some(station_id)).await?,
+            None => existing.name,
+        };
+        let plot_id = input.plot_id.unwrap_or(existing.plot_id);
+        if plot_id != existing.plot_id {
+            self.check_plot(business_id, plot_id).await?;
+        }
+        let mapping = match &input.field_mapping {
+            Some(mapping) => validate_mapping(mapping)?,
+            None => existing.field_mapping.0,
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE weather_stations
+            SET name = $3, plot_id = $4, field_mapping = $5, active = $6, updated_at = NOW()
+            WHERE id = $1 AND business_id = $2
+            "#,
+        )
+        .bind(station_id)
+        .bind(business_id)
+        .bind(&name)
+        .bind(plot_id)
+        .bind(sqlx::types::Json(&mapping))
+        .bind(input.active.unwrap_or(existing.active))
+        .execute(&self.db)
+        .await?;
+
+        self.get_station(business_id, station_id).await
+    }
+
+    /// Issue a new webhook token, so the old upload URL stops working
+    pub async fn rotate_token(&self, business_id: Uuid, station_id: Uuid) -> AppResult<WeatherStation> {
+        let result = sqlx::query(
+            "UPDATE weather_stations SET webhook_token = $3, updated_at = NOW() WHERE id = $1 AND business_id = $2",
+        )
+        .bind(station_id)
+        .bind(business_id)
+        .bind(new_token())
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Weather station".to_string()));
+        }
+        self.get_station(business_id, station_id).await
+    }
+
+    /// Remove a station; snapshots it sent stay with the plot
+    pub async fn delete_station(&self, business_id: Uuid, station_id: Uuid) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM weather_stations WHERE id = $1 AND business_id = $2")
+            .bind(station_id)
+            .bind(business_id)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Weather station".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Store a payload posted by a station as a snapshot of its plot. A
+    /// reading resent with the same time returns the stored snapshot.
+    pub async fn receive_upload(&self, webhook_token: &str, payload: &Value) -> AppResult<StationUploadAck> {
+        let station = sqlx::query_as::<_, (Uuid, Uuid, Uuid, String, bool, sqlx::types::Json<Vec<FieldMapping>>, Option<Decimal>, Option<Decimal>)>(
+            r#"
+            SELECT s.id, s.business_id, s.plot_id, p.name, s.active, s.field_mapping, p.latitude, p.longitude
+            FROM weather_stations s
+            JOIN plots p ON p.id = s.plot_id
+            WHERE s.webhook_token = $1
+            "#,
+        )
+        .bind(webhook_token)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Weather station".to_string()))?;
+        let (station_id, business_id, plot_id, plot_name, active, mapping, latitude, longitude) = station;
+
+        if !active {
+            return Err(AppError::Conflict {
+                resource: "weather_station".to_string(),
+                message: "The weather station is turned off".to_string(),
+                message_th: "สถานีตรวจอากาศถูกปิดใช้งาน".to_string(),
+            });
+        }
+        let (Some(latitude), Some(longitude)) = (latitude, longitude) else {
+            return Err(validation(
+                "plot_id",
+                "The station's plot has no coordinates",
+                "แปลงของสถานียังไม่มีพิกัด",
+            ));
+        };
+
+        let reading = read_payload(payload, &mapping.0)?;
+        let recorded_at = reading.recorded_at.unwrap_or_else(Utc::now);
+
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM weather_snapshots WHERE weather_station_id = $1 AND recorded_at = $2",
+        )
+        .bind(station_id)
+        .bind(recorded_at)
+        .fetch_optional(&self.db)
+        .await?;
+        if let Some(snapshot_id) = existing {
+            return Ok(StationUploadAck {
+                station_id,
+                snapshot_id,
+                recorded_at,
+                created: false,
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+        let snapshot = sqlx::query_as::<_, WeatherSnapshot>(
+            r#"
+            INSERT INTO weather_snapshots (
+                business_id, latitude, longitude, location_name, recorded_at,
+                temperature_celsius, humidity_percent, pressure_hpa, wind_speed_mps, wind_direction_deg,
+                rain_1h_mm, source, plot_id, weather_station_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING id, business_id, latitude, longitude, location_name, recorded_at,
+                      temperature_celsius, feels_like_celsius, humidity_percent, pressure_hpa,
+                      wind_speed_mps, wind_direction_deg, cloud_coverage_percent, visibility_meters,
+                      weather_condition, weather_description, weather_icon,
+                      rain_1h_mm, rain_3h_mm, sunrise, sunset, source, plot_id, weather_station_id, created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(&plot_name)
+        .bind(recorded_at)
+        .bind(reading.temperature_celsius)
+        .bind(reading.humidity_percent)
+        .bind(reading.pressure_hpa)
+        .bind(reading.wind_speed_mps)
+        .bind(reading.wind_direction_deg)
+        .bind(reading.rain_1h_mm)
+        .bind(STATION_SOURCE)
+        .bind(plot_id)
+        .bind(station_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE weather_stations SET last_received_at = NOW() WHERE id = $1")
+            .bind(station_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(StationUploadAck {
+            station_id,
+            snapshot_id: snapshot.id,
+            recorded_at: snapshot.recorded_at,
+            created: true,
+        })
+    }
+
+    async fn check_name(&self, business_id: Uuid, name: &str, station_id: Option<Uuid>) -> AppResult<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(validation("name", "Station name is required", "ต้องระบุชื่อสถานี"));
+        }
+        let taken = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM weather_stations
+                WHERE business_id = $1 AND name = $2 AND ($3::UUID IS NULL OR id <> $3)
+            )
+            "#,
+        )
+        .bind(business_id)
+        .bind(name)
+        .bind(station_id)
+        .fetch_one(&self.db)
+        .await?;
+        if taken {
+            return Err(AppError::Conflict {
+                resource: "weather_station".to_string(),
+                message: format!("A weather station named {} already exists", name),
+                message_th: format!("มีสถานีตรวจอากาศชื่อ {} อยู่แล้ว", name),
+            });
+        }
+        Ok(name.to_string())
+    }
+
+    /// The plot must be the business's and have coordinates for snapshots
+    async fn check_plot(&self, business_id: Uuid, plot_id: Uuid) -> AppResult<()> {
+        let located = sqlx::query_scalar::<_, bool>(
+            "SELECT latitude IS NOT NULL AND longitude IS NOT NULL FROM plots WHERE id = $1 AND business_id = $2",
+        )
+        .bind(plot_id)
+        .bind(business_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Plot".to_string()))?;
+        if !located {
+            return Err(validation(
+                "plot_id",
+                "Set the plot's coordinates before adding a station",
+                "กรุณาระบุพิกัดของแปลงก่อนเพิ่มสถานี",
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+//! Weekly owner digest
+//!
+//! Every Monday morning (Thailand time) the owner of each business gets a
+//! digest of the week just ended, Monday to Sunday: volumes harvested,
+//! processed and roasted, the best cupping scores, alerts raised, what
+//! expires in the next 30 days and open work. The digest is queued as a
+//! notification and pushed right away on the channels the owner picked for
+//! it. Each week is generated once per business; weeks with nothing to
+//! report are recorded but not sent.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::external::SmtpMailer;
+use crate::services::notification::{create_weekly_digest_notification, NotificationService};
+use crate::services::report_schedule::DEFAULT_UTC_OFFSET_MINUTES;
+use crate::services::BusinessService;
+
+/// Local hour on Monday from which last week's digest is due
+pub const DIGEST_HOUR: i64 = 7;
+
+/// Days ahead that expirations are listed
+pub const EXPIRY_LOOKAHEAD_DAYS: i64 = 30;
+
+/// Cupping results listed
+const TOP_CUPPINGS: i64 = 3;
+
+/// Expirations listed
+const MAX_EXPIRATIONS: i64 = 10;
+
+/// Businesses handled per job run
+const DIGEST_BATCH_SIZE: i64 = 20;
+
+/// Weekly digest service
+#[derive(Clone)]
+pub struct WeeklyDigestService {
+    db: PgPool,
+    mailer: Option<SmtpMailer>,
+}
+
+/// Volumes moved during the week
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct DigestVolumes {
+    pub harvested_cherry_kg: Decimal,
+    pub harvest_count: i64,
+    pub processed_green_kg: Decimal,
+    pub batches_finished: i64,
+    pub roasted_kg: Decimal,
+    pub roast_sessions: i64,
+}
+
+/// A cupping result of the week
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DigestCupping {
+    pub lot_code: String,
+    pub total_score: Decimal,
+    pub session_date: NaiveDate,
+}
+
+/// Something expiring soon
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DigestExpiration {
+    /// certification or insurance
+    pub kind: String,
+    pub name: String,
+    pub expires_on: NaiveDate,
+}
+
+/// Open work at the time of the digest
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct DigestTasks {
+    pub orders_to_ship: i64,
+    pub batches_in_progress: i64,
+}
+
+/// Digest of one week
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklyDigest {
+    pub business_id: Uuid,
+    pub week_start: NaiveDate,
+    pub week_end: NaiveDate,
+    pub volumes: DigestVolumes,
+    pub samples_cupped: i64,
+    pub samples_below_specialty: i64,
+    pub top_cuppings: Vec<DigestCupping>,
+    pub warning_alerts: i64,
+    pub critical_alerts: i64,
+    pub expirations: Vec<DigestExpiration>,
+    pub tasks: DigestTasks,
+}
+
+impl WeeklyDigest {
+    /// Nothing happened and nothing is pending
+    pub fn is_empty(&self) -> bool {
+        self.volumes.harvest_count == 0
+            && self.volumes.batches_finished == 0
+            && self.volumes.roast_sessions == 0
+            && self.samples_cupped == 0
+            && self.warning_alerts == 0
+            && self.critical_alerts == 0
+            && self.expirations.is_empty()
+            && self.tasks.orders_to_ship == 0
+            && self.tasks.batches_in_progress == 0
+    }
+}
+
+/// Query parameters for the digest preview
+#[derive(Debug, Deserialize)]
+pub struct WeeklyDigestQuery {
+    /// Any day of the week; last week by default
+    pub week: Option<NaiveDate>,
+}
+
+/// Monday of the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Monday of the latest week whose digest is due at `now`: the week before
+/// the current one once `DIGEST_HOUR` on Monday has passed locally
+pub fn digest_week(now: DateTime<Utc>, utc_offset_minutes: i32) -> NaiveDate {
+    let local = now + Duration::minutes(utc_offset_minutes as i64) - Duration::hours(DIGEST_HOUR);
+    week_start(local.date_naive()) - Duration::days(7)
+}
+
+impl WeeklyDigestService {
+    pub fn new(db: PgPool, config: &Config) -> Self {
+        Self {
+            db,
+            mailer: SmtpMailer::from_config(&config.email),
+        }
+    }
+
+    /// Gather the digest of the week starting on `week_start`
+    pub async fn build(&self, business_id: Uuid, week_start: NaiveDate) -> AppResult<WeeklyDigest> {
+        let week_end = week_start + Duration::days(6);
+
+        let volumes = sqlx::query_as::<_, DigestVolumes>(
+            r#"
+            SELECT
+                (SELECT COALESCE(SUM(cherry_weight_kg), 0) FROM harvests
+                 WHERE business_id = $1 AND harvest_date BETWEEN $2 AND $3) AS harvested_cherry_kg,
+                (SELECT COUNT(*) FROM harvests
+                 WHERE business_id = $1 AND harvest_date BETWEEN $2 AND $3) AS harvest_count,
+                (SELECT COALESCE(SUM(pr.green_bean_weight_kg), 0) FROM processing_records pr
+                 JOIN lots l ON l.id = pr.lot_id
+                 WHERE l.business_id = $1 AND pr.end_date BETWEEN $2 AND $3) AS processed_green_kg,
+                (SELECT COUNT(*) FROM processing_records pr
+                 JOIN lots l ON l.id = pr.lot_id
+                 WHERE l.business_id = $1 AND pr.end_date BETWEEN $2 AND $3) AS batches_finished,
+                (SELECT COALESCE(SUM(roasted_weight_kg), 0) FROM roast_sessions
+                 WHERE business_id = $1 AND session_date BETWEEN $2 AND $3) AS roasted_kg,
+                (SELECT COUNT(*) FROM roast_sessions
+                 WHERE business_id = $1 AND session_date BETWEEN $2 AND $3) AS roast_sessions
+            "#,
+        )
+        .bind(business_id)
+        .bind(week_start)
+        .bind(week_end)
+        .fetch_one(&self.db)
+        .await?;
+
+        let (samples_cupped, samples_below_specialty) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(*), COUNT(*) FILTER (WHERE csamp.total_score < 80)
+            FROM cupping_samples csamp
+            JOIN cupping_sessions cs ON cs.id = csamp.session_id
+            WHERE cs.business_id = $1 AND cs.session_date BETWEEN $2 AND $3
+            "#,
+        )
+        .bind(business_id)
+        .bind(week_start)
+        .bind(week_end)
+        .fetch_one(&self.db)
+        .await?;
+
+        let top_cuppings = sqlx::query_as::<_, DigestCupping>(
+            r#"
+            SELECT l.traceability_code AS lot_code, csamp.total_score, cs.session_date
+            FROM cupping_samples csamp
+            JOIN cupping_sessions cs ON cs.id = csamp.session_id
+            JOIN lots l ON l.id = csamp.lot_id
+            WHERE cs.business_id = $1 AND cs.session_date BETWEEN $2 AND $3
+            ORDER BY csamp.total_score DESC, cs.session_date
+            LIMIT $4
+            "#,
+        )
+        .bind(business_id)
+        .bind(week_start)
+        .bind(week_end)
+        .bind(TOP_CUPPINGS)
+        .fetch_all(&self.db)
+        .await?;
+
+        // Alerts raised for the business in the week, local time
+        let (warning_alerts, critical_alerts) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE severity = 'warning'),
+                   COUNT(*) FILTER (WHERE severity = 'critical')
+            FROM notification_queue
+            WHERE business_id = $1
+              AND notification_type::text <> 'weekly_digest'
+              AND (created_at AT TIME ZONE 'UTC' + make_interval(mins => $4))::date BETWEEN $2 AND $3
+            "#,
+        )
+        .bind(business_id)
+        .bind(week_start)
+        .bind(week_end)
+        .bind(DEFAULT_UTC_OFFSET_MINUTES)
+        .fetch_one(&self.db)
+        .await?;
+
+        let expirations = sqlx::query_as::<_, DigestExpiration>(
+            r#"
+            SELECT 'certification' AS kind, certification_name AS name, expiration_date AS expires_on
+            FROM certifications
+            WHERE business_id = $1 AND is_active = true AND expiration_date BETWEEN $2 AND $3
+            UNION ALL
+            SELECT 'insurance', p.policy_number || ' (' || l.traceability_code || ')', p.valid_until
+            FROM lot_insurance_policies p
+            JOIN lots l ON l.id = p.lot_id
+            WHERE p.business_id = $1 AND p.valid_until BETWEEN $2 AND $3
+            ORDER BY expires_on
+            LIMIT $4
+            "#,
+        )
+        .bind(business_id)
+        .bind(week_end + Duration::days(1))
+        .bind(week_end + Duration::days(EXPIRY_LOOKAHEAD_DAYS))
+        .bind(MAX_EXPIRATIONS)
+        .fetch_all(&self.db)
+        .await?;
+
+        let tasks = sqlx::query_as::<_, DigestTasks>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM sales_orders
+                 WHERE business_id = $1 AND status = 'confirmed') AS orders_to_ship,
+                (SELECT COUNT(*) FROM processing_records pr
+                 JOIN lots l ON l.id = pr.lot_id
+                 WHERE l.business_id = $1 AND pr.end_date IS NULL) AS batches_in_progress
+            "#,
+        )
+        .bind(business_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(WeeklyDigest {
+            business_id,
+            week_start,
+            week_end,
+            volumes,
+            samples_cupped,
+            samples_below_specialty,
+            top_cuppings,
+            warning_alerts,
+            critical_alerts,
+            expirations,
+            tasks,
+        })
+    }
+
+    /// Generate and deliver a business's digest for a week unless it was
+    /// already generated. Returns whether a digest was sent
+    pub async fn send(&self, business_id: Uuid, week_start: NaiveDate) -> AppResult<bool> {
+        let digest = self.build(business_id, week_start).await?;
+
+        let claimed = sqlx::query(
+            "INSERT INTO weekly_digests (business_id, week_start) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(business_id)
+        .bind(week_start)
+        .execute(&self.db)
+        .await?
+        .rows_affected()
+            > 0;
+        if !claimed || digest.is_empty() {
+            return Ok(false);
+        }
+
+        let format = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        let notifications = NotificationService::with_mailer(self.db.clone(), self.mailer.clone());
+        let Some(queued) = notifications
+            .notify_business_owner(business_id, create_weekly_digest_notification(&digest, format))
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        sqlx::query("UPDATE weekly_digests SET notification_id = $3 WHERE business_id = $1 AND week_start = $2")
+            .bind(business_id)
+            .bind(week_start)
+            .bind(queued.id)
+            .execute(&self.db)
+            .await?;
+
+        // Deliver now; a failed push stays queued for the queue processor
+        if let Err(e) = notifications.send_notification(&queued).await {
+            tracing::error!("Failed to deliver weekly digest {}: {}", queued.id, e);
+        }
+        Ok(true)
+    }
+
+    /// Send last week's digest to every business still waiting for it
+    pub async fn send_due_digests(&self, now: DateTime<Utc>) -> AppResult<usize> {
+        let week_start = digest_week(now, DEFAULT_UTC_OFFSET_MINUTES);
+        let week_end = week_start + Duration::days(7);
+
+        let businesses = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT b.id
+            FROM businesses b
+            WHERE b.created_at < $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM weekly_digests d WHERE d.business_id = b.id AND d.week_start = $1
+              )
+            ORDER BY b.created_at
+            LIMIT $3
+            "#,
+        )
+        .bind(week_start)
+        .bind(week_end)
+        .bind(DIGEST_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut sent = 0;
+        for business_id in businesses {
+            match self.send(business_id, week_start).await {
+                Ok(true) => sent += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Weekly digest for business {} failed: {}", business_id, e),
+            }
+        }
+        Ok(sent)
+    }
+}
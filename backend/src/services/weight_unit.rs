@@ -0,0 +1,203 @@
+//! Weight units for entering weights in local measures
+//!
+//! Cherry is often weighed in tang (ถัง) or kasop (กระสอบ) rather than
+//! kilograms. Harvests and inventory transactions accept the unit a weight
+//! was entered in and are stored with both the entered value and its
+//! kilograms. The units and their kilograms are kept in the `weight_units`
+//! table; a business may set its own kilograms for the local units, which
+//! vary between villages, while kilograms and pounds are fixed.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Unit weights are taken to be in when none is given
+pub const DEFAULT_WEIGHT_UNIT: &str = "kg";
+
+/// Most kilograms a business may set for one unit
+pub const MAX_KG_PER_UNIT: Decimal = Decimal::from_parts(1000, 0, 0, false, 0);
+
+/// Decimal places weights are stored with
+const WEIGHT_DP: u32 = 3;
+
+/// Weight unit service
+#[derive(Clone)]
+pub struct WeightUnitService {
+    db: PgPool,
+}
+
+/// A weight unit with the kilograms the business uses for it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WeightUnit {
+    pub code: String,
+    pub name: String,
+    pub name_th: String,
+    pub kg_per_unit: Decimal,
+    pub default_kg_per_unit: Decimal,
+    /// Exact unit the business cannot change
+    pub fixed: bool,
+    /// Whether the business set its own kilograms
+    pub customized: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A weight as entered and in kilograms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConvertedWeight<'a> {
+    pub original: Decimal,
+    pub unit: &'a str,
+    pub kg: Decimal,
+}
+
+/// Input for setting a business's kilograms for a unit
+#[derive(Debug, Deserialize)]
+pub struct SetWeightUnitInput {
+    pub kg_per_unit: Decimal,
+}
+
+/// Unit code as stored: trimmed and lowercase, kilograms when blank
+pub fn normalize_unit(unit: Option<&str>) -> String {
+    match unit.map(str::trim) {
+        Some(unit) if !unit.is_empty() => unit.to_lowercase(),
+        _ => DEFAULT_WEIGHT_UNIT.to_string(),
+    }
+}
+
+/// Kilograms of a weight, rounded as weights are stored
+pub fn convert_to_kg(value: Decimal, kg_per_unit: Decimal) -> Decimal {
+    (value * kg_per_unit).round_dp(WEIGHT_DP)
+}
+
+/// Check kilograms a business sets for a unit
+pub fn validate_kg_per_unit(kg_per_unit: Decimal) -> AppResult<()> {
+    if kg_per_unit <= Decimal::ZERO || kg_per_unit > MAX_KG_PER_UNIT {
+        return Err(AppError::Validation {
+            field: "kg_per_unit".to_string(),
+            message: format!("Kilograms per unit must be greater than 0 and at most {}", MAX_KG_PER_UNIT),
+            message_th: format!("กิโลกรัมต่อหน่วยต้องมากกว่า 0 และไม่เกิน {}", MAX_KG_PER_UNIT),
+        });
+    }
+    Ok(())
+}
+
+impl WeightUnitService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Units with the kilograms the business uses for them
+    pub async fn list(&self, business_id: Uuid) -> AppResult<Vec<WeightUnit>> {
+        let units = sqlx::query_as::<_, WeightUnit>(
+            r#"
+            SELECT w.code, w.name, w.name_th,
+                   COALESCE(b.kg_per_unit, w.kg_per_unit) AS kg_per_unit,
+                   w.kg_per_unit AS default_kg_per_unit, w.fixed,
+                   b.kg_per_unit IS NOT NULL AS customized,
+                   COALESCE(b.updated_at, w.updated_at) AS updated_at
+            FROM weight_units w
+            LEFT JOIN business_weight_units b ON b.unit_code = w.code AND b.business_id = $1
+            ORDER BY w.sort_order, w.code
+            "#,
+        )
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(units)
+    }
+
+    /// A unit with the kilograms the business uses for it
+    pub async fn get(&self, business_id: Uuid, code: &str) -> AppResult<WeightUnit> {
+        let code = normalize_unit(Some(code));
+        self.list(business_id)
+            .await?
+            .into_iter()
+            .find(|u| u.code == code)
+            .ok_or_else(|| AppError::NotFound("Weight unit".to_string()))
+    }
+
+    /// Convert a weight entered in a unit to kilograms; `field` names the
+    /// unit input in the error for an unknown unit
+    pub async fn to_kg<'a>(
+        &self,
+        business_id: Uuid,
+        value: Decimal,
+        unit: &'a str,
+        field: &str,
+    ) -> AppResult<ConvertedWeight<'a>> {
+        if unit == DEFAULT_WEIGHT_UNIT {
+            return Ok(ConvertedWeight { original: value, unit, kg: value });
+        }
+
+        let kg_per_unit = sqlx::query_scalar::<_, Decimal>(
+            r#"
+            SELECT COALESCE(b.kg_per_unit, w.kg_per_unit)
+            FROM weight_units w
+            LEFT JOIN business_weight_units b ON b.unit_code = w.code AND b.business_id = $1
+            WHERE w.code = $2
+            "#,
+        )
+        .bind(business_id)
+        .bind(unit)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::Validation {
+            field: field.to_string(),
+            message: format!("Unknown weight unit '{}'", unit),
+            message_th: format!("ไม่รู้จักหน่วยน้ำหนัก '{}'", unit),
+        })?;
+
+        Ok(ConvertedWeight { original: value, unit, kg: convert_to_kg(value, kg_per_unit) })
+    }
+
+    /// Set the kilograms the business uses for a local unit
+    pub async fn set_kg_per_unit(
+        &self,
+        business_id: Uuid,
+        code: &str,
+        input: SetWeightUnitInput,
+    ) -> AppResult<WeightUnit> {
+        let unit = self.get(business_id, code).await?;
+        if unit.fixed {
+            return Err(AppError::Validation {
+                field: "code".to_string(),
+                message: format!("{} is an exact unit and cannot be changed", unit.name),
+                message_th: format!("{} เป็นหน่วยมาตรฐานที่แก้ไขไม่ได้", unit.name_th),
+            });
+        }
+        validate_kg_per_unit(input.kg_per_unit)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO business_weight_units (business_id, unit_code, kg_per_unit)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (business_id, unit_code)
+            DO UPDATE SET kg_per_unit = EXCLUDED.kg_per_unit, updated_at = NOW()
+            "#,
+        )
+        .bind(business_id)
+        .bind(&unit.code)
+        .bind(input.kg_per_unit)
+        .execute(&self.db)
+        .await?;
+
+        self.get(business_id, &unit.code).await
+    }
+
+    /// Go back to the default kilograms for a unit
+    pub async fn reset_kg_per_unit(&self, business_id: Uuid, code: &str) -> AppResult<WeightUnit> {
+        let unit = self.get(business_id, code).await?;
+
+        sqlx::query("DELETE FROM business_weight_units WHERE business_id = $1 AND unit_code = $2")
+            .bind(business_id)
+            .bind(&unit.code)
+            .execute(&self.db)
+            .await?;
+
+        self.get(business_id, &unit.code).await
+    }
+}
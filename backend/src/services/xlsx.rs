@@ -0,0 +1,349 @@
+//! XLSX workbook generation
+//!
+//! Thin layer over `rust_xlsxwriter` that gives every export the same house
+//! style: Tahoma (renders Thai cleanly), an optional title row, a bold shaded
+//! header with filters and frozen panes, native number/money/percent/date
+//! cells and a bold footer row for totals or averages. Date cells follow the
+//! workbook's calendar system. Sheets are built as plain data so services
+//! don't depend on the writer API.
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{
+    Color, ExcelDateTime, Format, FormatAlign, FormatBorder, Workbook, Worksheet, XlsxError,
+};
+use shared::{format_date, CalendarSystem};
+
+use crate::error::{AppError, AppResult};
+
+/// Font used for every cell; covers Thai script on Windows, macOS and LibreOffice
+const FONT_NAME: &str = "Tahoma";
+
+/// Widest auto-sized column, in characters
+const MAX_COLUMN_WIDTH: usize = 50;
+
+/// Date format showing Buddhist Era years; the `[$-1070000]` locale prefix
+/// switches Excel and LibreOffice to the Thai solar calendar while the cell
+/// keeps its native date value
+const BUDDHIST_DATE_FORMAT: &str = "[$-1070000]dd/mm/yyyy";
+
+/// Spreadsheet cell value
+#[derive(Debug, Clone, PartialEq)]
+pub enum XlsxCell {
+    Empty,
+    Text(String),
+    /// Decimal quantity, shown with thousands separators and 2 places
+    Number(Decimal),
+    Integer(i64),
+    /// Monetary amount; negatives shown in red
+    Money(Decimal),
+    /// Percentage in percent units (82.5 = 82.5%)
+    Percent(Decimal),
+    Date(NaiveDate),
+}
+
+/// Worksheet: optional title, header row, data rows and optional footer
+#[derive(Debug, Clone, Default)]
+pub struct XlsxSheet {
+    pub name: String,
+    pub title: Option<String>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<XlsxCell>>,
+    pub footer: Option<Vec<XlsxCell>>,
+}
+
+/// Workbook made of one or more sheets
+#[derive(Debug, Clone, Default)]
+pub struct XlsxWorkbook {
+    pub sheets: Vec<XlsxSheet>,
+    pub calendar: CalendarSystem,
+}
+
+impl XlsxSheet {
+    /// Create a sheet with a header row
+    pub fn new(name: impl Into<String>, header: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            header,
+            ..Default::default()
+        }
+    }
+
+    /// Add a bold title row above the header
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Append a data row
+    pub fn push_row(&mut self, row: Vec<XlsxCell>) {
+        self.rows.push(row);
+    }
+
+    /// Set the bold footer row (totals, averages) below the data
+    pub fn set_footer(&mut self, footer: Vec<XlsxCell>) {
+        self.footer = Some(footer);
+    }
+}
+
+impl XlsxWorkbook {
+    /// Create an empty workbook
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show dates in the given calendar system
+    pub fn with_calendar(mut self, calendar: CalendarSystem) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Add a sheet to the workbook
+    pub fn add_sheet(&mut self, sheet: XlsxSheet) {
+        self.sheets.push(sheet);
+    }
+
+    /// Serialize the workbook into XLSX bytes
+    pub fn to_bytes(&self) -> AppResult<Vec<u8>> {
+        let styles = Styles::new(self.calendar);
+        let mut workbook = Workbook::new();
+        let mut names: Vec<String> = Vec::new();
+
+        for sheet in &self.sheets {
+            let name = unique_sheet_name(&sheet.name, &names);
+            write_sheet(workbook.add_worksheet(), sheet, &name, &styles).map_err(xlsx_error)?;
+            names.push(name);
+        }
+        if names.is_empty() {
+            workbook.add_worksheet();
+        }
+
+        workbook.save_to_buffer().map_err(xlsx_error)
+    }
+}
+
+fn xlsx_error(e: XlsxError) -> AppError {
+    AppError::Internal(format!("XLSX generation error: {}", e))
+}
+
+/// Cell formats shared by every sheet of a workbook
+struct Styles {
+    title: Format,
+    header: Format,
+    body: [Format; 6],
+    footer: [Format; 6],
+    calendar: CalendarSystem,
+}
+
+impl Styles {
+    fn new(calendar: CalendarSystem) -> Self {
+        let base = Format::new().set_font_name(FONT_NAME).set_font_size(10);
+        // Indexed by `style_index`
+        let number_formats = [
+            "General",
+            "#,##0.00",
+            "#,##0",
+            "#,##0.00;[Red]-#,##0.00",
+            "0.0%",
+            match calendar {
+                CalendarSystem::Gregorian => "yyyy-mm-dd",
+                CalendarSystem::Buddhist => BUDDHIST_DATE_FORMAT,
+            },
+        ];
+
+        let body = number_formats.map(|num_format| base.clone().set_num_format(num_format));
+        let footer = number_formats.map(|num_format| {
+            base.clone()
+                .set_num_format(num_format)
+                .set_bold()
+                .set_border_top(FormatBorder::Thin)
+                .set_background_color(Color::RGB(0xF2F2F2))
+        });
+
+        Self {
+            title: base.clone().set_bold().set_font_size(14),
+            header: base
+                .set_bold()
+                .set_text_wrap()
+                .set_align(FormatAlign::Center)
+                .set_align(FormatAlign::VerticalCenter)
+                .set_background_color(Color::RGB(0xD9E1F2))
+                .set_border_bottom(FormatBorder::Thin),
+            body,
+            footer,
+            calendar,
+        }
+    }
+}
+
+/// Index of a cell's format in `Styles::body` / `Styles::footer`
+fn style_index(cell: &XlsxCell) -> usize {
+    match cell {
+        XlsxCell::Empty | XlsxCell::Text(_) => 0,
+        XlsxCell::Number(_) => 1,
+        XlsxCell::Integer(_) => 2,
+        XlsxCell::Money(_) => 3,
+        XlsxCell::Percent(_) => 4,
+        XlsxCell::Date(_) => 5,
+    }
+}
+
+fn write_sheet(
+    worksheet: &mut Worksheet,
+    sheet: &XlsxSheet,
+    name: &str,
+    styles: &Styles,
+) -> Result<(), XlsxError> {
+    worksheet.set_name(name)?;
+
+    let columns = sheet
+        .header
+        .len()
+        .max(sheet.rows.iter().map(Vec::len).max().unwrap_or(0))
+        .max(1);
+    let mut row: u32 = 0;
+
+    if let Some(title) = &sheet.title {
+        if columns > 1 {
+            worksheet.merge_range(0, 0, 0, (columns - 1) as u16, title, &styles.title)?;
+        } else {
+            worksheet.write_string_with_format(0, 0, title, &styles.title)?;
+        }
+        worksheet.set_row_height(0, 22)?;
+        row = 2;
+    }
+
+    let header_row = row;
+    if !sheet.header.is_empty() {
+        for (col, text) in sheet.header.iter().enumerate() {
+            worksheet.write_string_with_format(row, col as u16, text, &styles.header)?;
+        }
+        worksheet.set_freeze_panes(row + 1, 0)?;
+        row += 1;
+    }
+
+    for cells in &sheet.rows {
+        for (col, cell) in cells.iter().enumerate() {
+            write_cell(worksheet, row, col as u16, cell, &styles.body[style_index(cell)], styles.calendar)?;
+        }
+        row += 1;
+    }
+
+    if !sheet.header.is_empty() && !sheet.rows.is_empty() {
+        worksheet.autofilter(header_row, 0, row - 1, (sheet.header.len() - 1) as u16)?;
+    }
+
+    if let Some(footer) = &sheet.footer {
+        for col in 0..columns {
+            let cell = footer.get(col).unwrap_or(&XlsxCell::Empty);
+            write_cell(worksheet, row, col as u16, cell, &styles.footer[style_index(cell)], styles.calendar)?;
+        }
+    }
+
+    for col in 0..columns {
+        let widest = sheet
+            .rows
+            .iter()
+            .chain(&sheet.footer)
+            .filter_map(|cells| cells.get(col))
+            .map(cell_width)
+            .chain(sheet.header.get(col).map(|h| display_width(h)))
+            .max()
+            .unwrap_or(0);
+        worksheet.set_column_width(col as u16, (widest + 2).clamp(8, MAX_COLUMN_WIDTH) as f64)?;
+    }
+
+    Ok(())
+}
+
+fn write_cell(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    cell: &XlsxCell,
+    format: &Format,
+    calendar: CalendarSystem,
+) -> Result<(), XlsxError> {
+    match cell {
+        XlsxCell::Empty => {
+            worksheet.write_blank(row, col, format)?;
+        }
+        XlsxCell::Text(text) => {
+            worksheet.write_string_with_format(row, col, text, format)?;
+        }
+        XlsxCell::Number(value) | XlsxCell::Money(value) => {
+            worksheet.write_number_with_format(row, col, value.to_f64().unwrap_or_default(), format)?;
+        }
+        XlsxCell::Percent(value) => {
+            let fraction = value.to_f64().unwrap_or_default() / 100.0;
+            worksheet.write_number_with_format(row, col, fraction, format)?;
+        }
+        XlsxCell::Integer(value) => {
+            worksheet.write_number_with_format(row, col, *value as f64, format)?;
+        }
+        XlsxCell::Date(date) => {
+            // Excel dates start in 1900; anything else is written as text
+            let datetime = u16::try_from(date.year())
+                .ok()
+                .filter(|year| (1900..=9999).contains(year))
+                .and_then(|year| ExcelDateTime::from_ymd(year, date.month() as u8, date.day() as u8).ok());
+            match datetime {
+                Some(datetime) => {
+                    worksheet.write_datetime_with_format(row, col, datetime, format)?;
+                }
+                None => {
+                    worksheet.write_string_with_format(row, col, format_date(*date, calendar), format)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Approximate rendered width of a cell in characters
+fn cell_width(cell: &XlsxCell) -> usize {
+    match cell {
+        XlsxCell::Empty => 0,
+        XlsxCell::Text(text) => display_width(text),
+        XlsxCell::Number(value) | XlsxCell::Money(value) => value.round_dp(2).to_string().len() + 4,
+        XlsxCell::Percent(value) => value.round_dp(1).to_string().len() + 2,
+        XlsxCell::Integer(value) => value.to_string().len() + 2,
+        XlsxCell::Date(_) => 10,
+    }
+}
+
+/// Display width of text in characters; Thai vowel and tone marks stack on
+/// the base consonant and take no horizontal space
+pub fn display_width(text: &str) -> usize {
+    text.lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !matches!(*c as u32, 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Sheet names are limited to 31 characters, may not contain []:*?/\ and
+/// must be unique within the workbook (case-insensitively)
+pub fn unique_sheet_name(name: &str, taken: &[String]) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .take(31)
+        .collect();
+    let base = if cleaned.trim().is_empty() { "Sheet".to_string() } else { cleaned };
+
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.to_lowercase() == candidate.to_lowercase());
+    let mut candidate = base.clone();
+    let mut suffix = 2;
+    while is_taken(&candidate) {
+        let tag = format!(" ({})", suffix);
+        let stem: String = base.chars().take(31 - tag.chars().count()).collect();
+        candidate = format!("{}{}", stem, tag);
+        suffix += 1;
+    }
+    candidate
+}
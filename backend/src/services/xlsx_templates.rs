@@ -0,0 +1,777 @@
+//! Styled XLSX export templates
+//!
+//! Fixed multi-sheet workbooks for the inventory summary, cupping session
+//! results and financials. Headers are Thai by default or English on request,
+//! and columns hidden from the caller's role by field visibility policies on
+//! `reports` are left out of every sheet.
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use shared::{format_month, DisplayFormat, Language, LotStage};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cupping::CoffeeClassification;
+use crate::services::xlsx::{XlsxCell, XlsxSheet, XlsxWorkbook};
+use crate::services::{BusinessService, CuppingService, RoleService};
+
+/// Default look-back for movement and financial sheets
+const DEFAULT_RANGE_DAYS: i64 = 30;
+
+/// Template column: visibility key plus Thai and English headers
+struct TemplateColumn {
+    key: &'static str,
+    th: &'static str,
+    en: &'static str,
+}
+
+const fn col(key: &'static str, th: &'static str, en: &'static str) -> TemplateColumn {
+    TemplateColumn { key, th, en }
+}
+
+/// Sheet under construction; rows and footer are projected onto the visible
+/// columns when the sheet is finished
+struct TemplateSheet<'a> {
+    name: (&'a str, &'a str),
+    title: Option<String>,
+    columns: &'a [TemplateColumn],
+    rows: Vec<Vec<XlsxCell>>,
+    footer: Option<Vec<XlsxCell>>,
+}
+
+impl<'a> TemplateSheet<'a> {
+    fn new(name: (&'a str, &'a str), columns: &'a [TemplateColumn]) -> Self {
+        Self {
+            name,
+            title: None,
+            columns,
+            rows: Vec::new(),
+            footer: None,
+        }
+    }
+
+    fn finish(self, hidden: &HashSet<String>, thai: bool) -> XlsxSheet {
+        let visible: Vec<usize> = (0..self.columns.len())
+            .filter(|i| !hidden.contains(self.columns[*i].key))
+            .collect();
+        let project = |cells: Vec<XlsxCell>| -> Vec<XlsxCell> {
+            visible
+                .iter()
+                .map(|i| cells.get(*i).cloned().unwrap_or(XlsxCell::Empty))
+                .collect()
+        };
+
+        let header = visible
+            .iter()
+            .map(|i| {
+                let column = &self.columns[*i];
+                if thai { column.th } else { column.en }.to_string()
+            })
+            .collect();
+        let mut sheet = XlsxSheet::new(if thai { self.name.0 } else { self.name.1 }, header);
+        if let Some(title) = self.title {
+            sheet = sheet.with_title(title);
+        }
+        for row in self.rows {
+            sheet.push_row(project(row));
+        }
+        if let Some(footer) = self.footer {
+            sheet.set_footer(project(footer));
+        }
+        sheet
+    }
+}
+
+fn text(value: impl Into<String>) -> XlsxCell {
+    XlsxCell::Text(value.into())
+}
+
+fn opt_text(value: Option<String>) -> XlsxCell {
+    value.map(XlsxCell::Text).unwrap_or(XlsxCell::Empty)
+}
+
+fn opt_money(value: Option<Decimal>) -> XlsxCell {
+    value.map(XlsxCell::Money).unwrap_or(XlsxCell::Empty)
+}
+
+fn tr(thai: bool, th: &str, en: &str) -> String {
+    if thai { th } else { en }.to_string()
+}
+
+/// Localized lot stage name
+fn stage_label(stage: &str, thai: bool) -> String {
+    let language = if thai { Language::Thai } else { Language::English };
+    LotStage::from_name(stage)
+        .map_or(stage, |stage| stage.name_in(&language))
+        .to_string()
+}
+
+/// Localized inventory transaction type
+fn transaction_type_label(transaction_type: &str, thai: bool) -> String {
+    let (th, en) = match transaction_type {
+        "harvest_in" => ("รับจากการเก็บเกี่ยว", "Harvest in"),
+        "processing_out" => ("ส่งแปรรูป", "Processing out"),
+        "processing_in" => ("รับจากการแปรรูป", "Processing in"),
+        "roasting_out" => ("ส่งคั่ว", "Roasting out"),
+        "roasting_in" => ("รับจากการคั่ว", "Roasting in"),
+        "sale" => ("ขาย", "Sale"),
+        "purchase" => ("ซื้อ", "Purchase"),
+        "adjustment" => ("ปรับปรุงยอด", "Adjustment"),
+        "transfer" => ("โอนย้าย", "Transfer"),
+        "sample" => ("ตัวอย่าง", "Sample"),
+        "return" => ("รับคืน", "Return"),
+        other => (other, other),
+    };
+    tr(thai, th, en)
+}
+
+pub(crate) fn classification_label(classification: &CoffeeClassification, thai: bool) -> String {
+    if !thai {
+        return classification.to_string();
+    }
+    match classification {
+        CoffeeClassification::Outstanding => "ยอดเยี่ยม",
+        CoffeeClassification::Excellent => "ดีเยี่ยม",
+        CoffeeClassification::VeryGood => "ดีมาก",
+        CoffeeClassification::BelowSpecialty => "ต่ำกว่าเกรดพิเศษ",
+    }
+    .to_string()
+}
+
+/// Share of a total in percent units, zero when the total is zero
+pub fn percent_of(part: Decimal, total: Decimal) -> Decimal {
+    if total.is_zero() {
+        Decimal::ZERO
+    } else {
+        (part * Decimal::from(100) / total).round_dp(2)
+    }
+}
+
+/// Resolve an optional date range, defaulting to the last 30 days; `None`
+/// when the start is after the end
+fn resolve_range(
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let end = end_date.unwrap_or_else(|| Utc::now().date_naive());
+    let start = start_date.unwrap_or(end - Duration::days(DEFAULT_RANGE_DAYS));
+    (start <= end).then_some((start, end))
+}
+
+fn invalid_range() -> AppError {
+    AppError::Validation {
+        field: "start_date".to_string(),
+        message: "Start date must not be after end date".to_string(),
+        message_th: "วันที่เริ่มต้นต้องไม่อยู่หลังวันที่สิ้นสุด".to_string(),
+    }
+}
+
+// ============================================================================
+// Inventory summary
+// ============================================================================
+
+const LOT_BALANCE_COLUMNS: &[TemplateColumn] = &[
+    col("traceability_code", "รหัสติดตาม", "Traceability Code"),
+    col("lot_name", "ชื่อล็อต", "Lot"),
+    col("stage", "ขั้นตอน", "Stage"),
+    col("quantity_in", "รับเข้า (กก.)", "In (kg)"),
+    col("quantity_out", "จ่ายออก (กก.)", "Out (kg)"),
+    col("balance_kg", "คงเหลือ (กก.)", "Balance (kg)"),
+    col("last_movement", "เคลื่อนไหวล่าสุด", "Last Movement"),
+];
+
+const STAGE_BALANCE_COLUMNS: &[TemplateColumn] = &[
+    col("stage", "ขั้นตอน", "Stage"),
+    col("lot_count", "จำนวนล็อต", "Lots"),
+    col("balance_kg", "คงเหลือ (กก.)", "Balance (kg)"),
+    col("share", "สัดส่วน", "Share"),
+];
+
+const MOVEMENT_COLUMNS: &[TemplateColumn] = &[
+    col("transaction_date", "วันที่", "Date"),
+    col("traceability_code", "รหัสติดตาม", "Traceability Code"),
+    col("lot_name", "ชื่อล็อต", "Lot"),
+    col("transaction_type", "ประเภท", "Type"),
+    col("direction", "ทิศทาง", "Direction"),
+    col("stage", "ขั้นตอน", "Stage"),
+    col("quantity_kg", "ปริมาณ (กก.)", "Quantity (kg)"),
+    col("counterparty_name", "คู่ค้า", "Counterparty"),
+    col("notes", "หมายเหตุ", "Notes"),
+];
+
+#[derive(Debug, sqlx::FromRow)]
+struct LotBalanceRow {
+    traceability_code: String,
+    lot_name: String,
+    stage: String,
+    quantity_in: Decimal,
+    quantity_out: Decimal,
+    last_movement: NaiveDate,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MovementRow {
+    transaction_date: NaiveDate,
+    traceability_code: String,
+    lot_name: String,
+    transaction_type: String,
+    direction: String,
+    stage: String,
+    quantity_kg: Decimal,
+    counterparty_name: Option<String>,
+    notes: Option<String>,
+    notes_th: Option<String>,
+}
+
+// ============================================================================
+// Cupping session
+// ============================================================================
+
+const CUPPING_RESULT_COLUMNS: &[TemplateColumn] = &[
+    col("sample_number", "ตัวอย่างที่", "Sample"),
+    col("traceability_code", "รหัสติดตาม", "Traceability Code"),
+    col("lot_name", "ชื่อล็อต", "Lot"),
+    col("fragrance_aroma", "กลิ่นหอม", "Fragrance/Aroma"),
+    col("flavor", "รสชาติ", "Flavor"),
+    col("aftertaste", "รสที่ค้าง", "Aftertaste"),
+    col("acidity", "ความเปรี้ยว", "Acidity"),
+    col("body", "บอดี้", "Body"),
+    col("balance", "ความสมดุล", "Balance"),
+    col("uniformity", "ความสม่ำเสมอ", "Uniformity"),
+    col("clean_cup", "ความสะอาด", "Clean Cup"),
+    col("sweetness", "ความหวาน", "Sweetness"),
+    col("overall", "ภาพรวม", "Overall"),
+    col("total_score", "คะแนนรวม", "Total"),
+    col("defects_taint", "ข้อบกพร่อง (taint)", "Taints"),
+    col("defects_fault", "ข้อบกพร่อง (fault)", "Faults"),
+    col("final_score", "คะแนนสุดท้าย", "Final Score"),
+    col("classification", "ระดับคุณภาพ", "Classification"),
+];
+
+const TASTING_NOTE_COLUMNS: &[TemplateColumn] = &[
+    col("sample_number", "ตัวอย่างที่", "Sample"),
+    col("lot_name", "ชื่อล็อต", "Lot"),
+    col("tasting_notes", "บันทึกการชิม", "Tasting Notes"),
+    col("tasting_notes_th", "บันทึกการชิม (ไทย)", "Tasting Notes (Thai)"),
+];
+
+const SESSION_INFO_COLUMNS: &[TemplateColumn] = &[
+    col("field", "รายการ", "Field"),
+    col("value", "ค่า", "Value"),
+];
+
+/// Columns averaged in the cupping results footer
+const CUPPING_SCORE_RANGE: std::ops::Range<usize> = 3..17;
+
+#[derive(Debug, sqlx::FromRow)]
+struct LotLabelRow {
+    id: Uuid,
+    name: String,
+    traceability_code: String,
+}
+
+// ============================================================================
+// Financials
+// ============================================================================
+
+const MONTHLY_COLUMNS: &[TemplateColumn] = &[
+    col("month", "เดือน", "Month"),
+    col("currency", "สกุลเงิน", "Currency"),
+    col("quantity_kg", "ขาย (กก.)", "Sold (kg)"),
+    col("total_price", "ยอดขาย", "Sales"),
+    col("quantity_kg", "ซื้อ (กก.)", "Purchased (kg)"),
+    col("total_price", "ยอดซื้อ", "Purchases"),
+    col("total_price", "สุทธิ", "Net"),
+    col("unit_price", "ราคาขายเฉลี่ย/กก.", "Avg Sale Price/kg"),
+];
+
+const TRANSACTION_COLUMNS: &[TemplateColumn] = &[
+    col("transaction_date", "วันที่", "Date"),
+    col("traceability_code", "รหัสติดตาม", "Traceability Code"),
+    col("lot_name", "ชื่อล็อต", "Lot"),
+    col("stage", "ขั้นตอน", "Stage"),
+    col("counterparty_name", "คู่ค้า", "Counterparty"),
+    col("counterparty_contact", "ติดต่อ", "Contact"),
+    col("quantity_kg", "ปริมาณ (กก.)", "Quantity (kg)"),
+    col("unit_price", "ราคาต่อกก.", "Unit Price"),
+    col("total_price", "ยอดรวม", "Total"),
+    col("currency", "สกุลเงิน", "Currency"),
+    col("notes", "หมายเหตุ", "Notes"),
+];
+
+#[derive(Debug, sqlx::FromRow)]
+struct FinancialRow {
+    transaction_date: NaiveDate,
+    traceability_code: String,
+    lot_name: String,
+    transaction_type: String,
+    stage: String,
+    counterparty_name: Option<String>,
+    counterparty_contact: Option<String>,
+    quantity_kg: Decimal,
+    unit_price: Option<Decimal>,
+    amount: Option<Decimal>,
+    currency: String,
+    notes: Option<String>,
+    notes_th: Option<String>,
+}
+
+/// Monthly sales and purchase totals for one currency
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MonthlyTotals {
+    pub sold_kg: Decimal,
+    pub sales: Decimal,
+    pub purchased_kg: Decimal,
+    pub purchases: Decimal,
+}
+
+impl MonthlyTotals {
+    pub fn net(&self) -> Decimal {
+        self.sales - self.purchases
+    }
+
+    /// Average sale price per kg, if anything was sold
+    pub fn average_sale_price(&self) -> Option<Decimal> {
+        (!self.sold_kg.is_zero()).then(|| (self.sales / self.sold_kg).round_dp(2))
+    }
+}
+
+/// Group sale/purchase amounts by (first day of month, currency)
+pub fn monthly_totals<'a>(
+    transactions: impl IntoIterator<Item = (NaiveDate, &'a str, bool, Decimal, Decimal)>,
+) -> BTreeMap<(NaiveDate, String), MonthlyTotals> {
+    let mut months: BTreeMap<(NaiveDate, String), MonthlyTotals> = BTreeMap::new();
+    for (date, currency, is_sale, quantity_kg, amount) in transactions {
+        let month = date.with_day(1).unwrap_or(date);
+        let totals = months.entry((month, currency.to_string())).or_default();
+        if is_sale {
+            totals.sold_kg += quantity_kg;
+            totals.sales += amount;
+        } else {
+            totals.purchased_kg += quantity_kg;
+            totals.purchases += amount;
+        }
+    }
+    months
+}
+
+/// XLSX template service
+#[derive(Clone)]
+pub struct XlsxTemplateService {
+    db: PgPool,
+}
+
+impl XlsxTemplateService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Business display format for titles in the workbook's language
+    async fn display_format(&self, business_id: Uuid, thai: bool) -> AppResult<DisplayFormat> {
+        let display = BusinessService::new(self.db.clone()).get_display_format(business_id).await?;
+        Ok(display.for_language(if thai { &Language::Thai } else { &Language::English }))
+    }
+
+    async fn hidden_fields(&self, role_id: Uuid) -> AppResult<HashSet<String>> {
+        Ok(RoleService::new(self.db.clone())
+            .get_hidden_fields(role_id, "reports")
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Inventory balances by lot and by stage as of `end_date`, plus the
+    /// movements within the range
+    pub async fn inventory_summary(
+        &self,
+        business_id: Uuid,
+        role_id: Uuid,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        thai: bool,
+    ) -> AppResult<Vec<u8>> {
+        let (start, end) = resolve_range(start_date, end_date).ok_or_else(invalid_range)?;
+        let hidden = self.hidden_fields(role_id).await?;
+        let display = self.display_format(business_id, thai).await?;
+
+        let balances = sqlx::query_as::<_, LotBalanceRow>(
+            r#"
+            SELECT l.traceability_code, l.name AS lot_name, t.stage,
+                   COALESCE(SUM(t.quantity_kg) FILTER (WHERE t.direction = 'in'), 0) AS quantity_in,
+                   COALESCE(SUM(t.quantity_kg) FILTER (WHERE t.direction = 'out'), 0) AS quantity_out,
+                   MAX(t.transaction_date) AS last_movement
+            FROM inventory_transactions t
+            JOIN lots l ON l.id = t.lot_id
+            WHERE t.business_id = $1 AND t.transaction_date <= $2
+            GROUP BY l.id, l.traceability_code, l.name, t.stage
+            ORDER BY l.traceability_code, t.stage
+            "#,
+        )
+        .bind(business_id)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let movements = sqlx::query_as::<_, MovementRow>(
+            r#"
+            SELECT t.transaction_date, l.traceability_code, l.name AS lot_name,
+                   t.transaction_type::TEXT AS transaction_type, t.direction, t.stage,
+                   t.quantity_kg, t.counterparty_name, t.notes, t.notes_th
+            FROM inventory_transactions t
+            JOIN lots l ON l.id = t.lot_id
+            WHERE t.business_id = $1 AND t.transaction_date BETWEEN $2 AND $3
+            ORDER BY t.transaction_date, t.created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut workbook = XlsxWorkbook::new().with_calendar(display.calendar);
+
+        // Balance by lot
+        let mut by_lot = TemplateSheet::new(("คงคลังตามล็อต", "Balance by Lot"), LOT_BALANCE_COLUMNS);
+        by_lot.title = Some(if thai {
+            format!("สรุปสินค้าคงคลัง ณ วันที่ {}", display.date(end))
+        } else {
+            format!("Inventory Summary as of {}", display.date(end))
+        });
+        let mut stages: BTreeMap<String, (i64, Decimal)> = BTreeMap::new();
+        let (mut total_in, mut total_out) = (Decimal::ZERO, Decimal::ZERO);
+        for row in &balances {
+            let balance = row.quantity_in - row.quantity_out;
+            total_in += row.quantity_in;
+            total_out += row.quantity_out;
+            let stage = stages.entry(row.stage.clone()).or_default();
+            stage.1 += balance;
+            if balance > Decimal::ZERO {
+                stage.0 += 1;
+            }
+            by_lot.rows.push(vec![
+                text(&row.traceability_code),
+                text(&row.lot_name),
+                text(stage_label(&row.stage, thai)),
+                XlsxCell::Number(row.quantity_in),
+                XlsxCell::Number(row.quantity_out),
+                XlsxCell::Number(balance),
+                XlsxCell::Date(row.last_movement),
+            ]);
+        }
+        by_lot.footer = Some(vec![
+            text(tr(thai, "รวม", "Total")),
+            XlsxCell::Empty,
+            XlsxCell::Empty,
+            XlsxCell::Number(total_in),
+            XlsxCell::Number(total_out),
+            XlsxCell::Number(total_in - total_out),
+        ]);
+        workbook.add_sheet(by_lot.finish(&hidden, thai));
+
+        // Balance by stage
+        let total_balance: Decimal = stages.values().map(|(_, balance)| *balance).sum();
+        let mut by_stage =
+            TemplateSheet::new(("คงคลังตามขั้นตอน", "Balance by Stage"), STAGE_BALANCE_COLUMNS);
+        for (stage, (lots, balance)) in &stages {
+            by_stage.rows.push(vec![
+                text(stage_label(stage, thai)),
+                XlsxCell::Integer(*lots),
+                XlsxCell::Number(*balance),
+                XlsxCell::Percent(percent_of(*balance, total_balance)),
+            ]);
+        }
+        by_stage.footer = Some(vec![
+            text(tr(thai, "รวม", "Total")),
+            XlsxCell::Integer(stages.values().map(|(lots, _)| *lots).sum()),
+            XlsxCell::Number(total_balance),
+            XlsxCell::Percent(if total_balance.is_zero() { Decimal::ZERO } else { Decimal::from(100) }),
+        ]);
+        workbook.add_sheet(by_stage.finish(&hidden, thai));
+
+        // Movements within the range
+        let mut movement_sheet =
+            TemplateSheet::new(("รายการเคลื่อนไหว", "Movements"), MOVEMENT_COLUMNS);
+        movement_sheet.title = Some(if thai {
+            format!("รายการเคลื่อนไหว {} ถึง {}", display.date(start), display.date(end))
+        } else {
+            format!("Movements {} to {}", display.date(start), display.date(end))
+        });
+        for row in movements {
+            movement_sheet.rows.push(vec![
+                XlsxCell::Date(row.transaction_date),
+                text(row.traceability_code),
+                text(row.lot_name),
+                text(transaction_type_label(&row.transaction_type, thai)),
+                text(match row.direction.as_str() {
+                    "in" => tr(thai, "เข้า", "In"),
+                    _ => tr(thai, "ออก", "Out"),
+                }),
+                text(stage_label(&row.stage, thai)),
+                XlsxCell::Number(row.quantity_kg),
+                opt_text(row.counterparty_name),
+                opt_text(if thai { row.notes_th.or(row.notes) } else { row.notes.or(row.notes_th) }),
+            ]);
+        }
+        workbook.add_sheet(movement_sheet.finish(&hidden, thai));
+
+        workbook.to_bytes()
+    }
+
+    /// Scores, tasting notes and session details of one cupping session
+    pub async fn cupping_session(
+        &self,
+        business_id: Uuid,
+        role_id: Uuid,
+        session_id: Uuid,
+        thai: bool,
+    ) -> AppResult<Vec<u8>> {
+        let session = CuppingService::new(self.db.clone())
+            .get_session(business_id, session_id)
+            .await?;
+        let hidden = self.hidden_fields(role_id).await?;
+
+        let lot_ids: Vec<Uuid> = session.samples.iter().filter_map(|s| s.lot_id).collect();
+        let lots: std::collections::HashMap<Uuid, LotLabelRow> = sqlx::query_as::<_, LotLabelRow>(
+            r#"
+            SELECT id, name, traceability_code
+            FROM lots
+            WHERE id = ANY($1) AND business_id = $2
+            "#,
+        )
+        .bind(&lot_ids)
+        .bind(business_id)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|lot| (lot.id, lot))
+        .collect();
+
+        let display = self.display_format(business_id, thai).await?;
+        let session_date = display.date(session.session_date);
+        let title = if thai {
+            format!("ผลการคัปปิ้ง {} — {}", session_date, session.cupper_name)
+        } else {
+            format!("Cupping Results {} — {}", session_date, session.cupper_name)
+        };
+        let mut workbook = XlsxWorkbook::new().with_calendar(display.calendar);
+
+        let mut results = TemplateSheet::new(("ผลคัปปิ้ง", "Results"), CUPPING_RESULT_COLUMNS);
+        results.title = Some(title);
+        let mut notes = TemplateSheet::new(("บันทึกการชิม", "Tasting Notes"), TASTING_NOTE_COLUMNS);
+        for sample in &session.samples {
+            // Blind sessions show the blind code until revealed
+            let lot = sample.lot_id.and_then(|lot_id| lots.get(&lot_id));
+            let lot_name = lot.map(|l| l.name.clone()).unwrap_or_default();
+            let s = &sample.scores;
+            results.rows.push(vec![
+                XlsxCell::Integer(sample.sample_number as i64),
+                text(
+                    lot.map(|l| l.traceability_code.clone())
+                        .or_else(|| sample.blind_code.clone())
+                        .unwrap_or_default(),
+                ),
+                text(&lot_name),
+                XlsxCell::Number(s.fragrance_aroma),
+                XlsxCell::Number(s.flavor),
+                XlsxCell::Number(s.aftertaste),
+                XlsxCell::Number(s.acidity),
+                XlsxCell::Number(s.body),
+                XlsxCell::Number(s.balance),
+                XlsxCell::Number(s.uniformity),
+                XlsxCell::Number(s.clean_cup),
+                XlsxCell::Number(s.sweetness),
+                XlsxCell::Number(s.overall),
+                XlsxCell::Number(sample.total_score),
+                XlsxCell::Integer(sample.defects.taint_count as i64),
+                XlsxCell::Integer(sample.defects.fault_count as i64),
+                XlsxCell::Number(sample.final_score),
+                text(classification_label(&sample.classification, thai)),
+            ]);
+            notes.rows.push(vec![
+                XlsxCell::Integer(sample.sample_number as i64),
+                text(lot_name),
+                opt_text(sample.tasting_notes.clone()),
+                opt_text(sample.tasting_notes_th.clone()),
+            ]);
+        }
+        if !results.rows.is_empty() {
+            let count = Decimal::from(results.rows.len());
+            let mut footer = vec![text(tr(thai, "เฉลี่ย", "Average")), XlsxCell::Empty, XlsxCell::Empty];
+            for index in CUPPING_SCORE_RANGE {
+                let sum: Decimal = results
+                    .rows
+                    .iter()
+                    .map(|row| match &row[index] {
+                        XlsxCell::Number(value) => *value,
+                        XlsxCell::Integer(value) => Decimal::from(*value),
+                        _ => Decimal::ZERO,
+                    })
+                    .sum();
+                footer.push(XlsxCell::Number((sum / count).round_dp(2)));
+            }
+            results.footer = Some(footer);
+        }
+        workbook.add_sheet(results.finish(&hidden, thai));
+        workbook.add_sheet(notes.finish(&hidden, thai));
+
+        let finals: Vec<Decimal> = session.samples.iter().map(|s| s.final_score).collect();
+        let mut info = TemplateSheet::new(("ข้อมูลเซสชัน", "Session"), SESSION_INFO_COLUMNS);
+        let mut field = |th: &str, en: &str, value: XlsxCell| {
+            info.rows.push(vec![text(tr(thai, th, en)), value]);
+        };
+        field("วันที่", "Date", XlsxCell::Date(session.session_date));
+        field("ผู้ชิม", "Cupper", text(&session.cupper_name));
+        field("สถานที่", "Location", opt_text(session.location.clone()));
+        field("จำนวนตัวอย่าง", "Samples", XlsxCell::Integer(finals.len() as i64));
+        if !finals.is_empty() {
+            let average = finals.iter().sum::<Decimal>() / Decimal::from(finals.len());
+            field("คะแนนเฉลี่ย", "Average Score", XlsxCell::Number(average.round_dp(2)));
+            field("คะแนนสูงสุด", "Highest Score", XlsxCell::Number(finals.iter().copied().max().unwrap_or_default()));
+            field("คะแนนต่ำสุด", "Lowest Score", XlsxCell::Number(finals.iter().copied().min().unwrap_or_default()));
+        }
+        let session_notes = if thai {
+            session.notes_th.clone().or(session.notes.clone())
+        } else {
+            session.notes.clone().or(session.notes_th.clone())
+        };
+        field("หมายเหตุ", "Notes", opt_text(session_notes));
+        workbook.add_sheet(info.finish(&hidden, thai));
+
+        workbook.to_bytes()
+    }
+
+    /// Monthly summary plus sale and purchase listings for a date range
+    pub async fn financials(
+        &self,
+        business_id: Uuid,
+        role_id: Uuid,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        thai: bool,
+    ) -> AppResult<Vec<u8>> {
+        let (start, end) = resolve_range(start_date, end_date).ok_or_else(invalid_range)?;
+        let hidden = self.hidden_fields(role_id).await?;
+
+        let transactions = sqlx::query_as::<_, FinancialRow>(
+            r#"
+            SELECT t.transaction_date, l.traceability_code, l.name AS lot_name,
+                   t.transaction_type::TEXT AS transaction_type, t.stage,
+                   t.counterparty_name, t.counterparty_contact, t.quantity_kg, t.unit_price,
+                   COALESCE(t.total_price, t.quantity_kg * t.unit_price) AS amount,
+                   COALESCE(t.currency, 'THB') AS currency, t.notes, t.notes_th
+            FROM inventory_transactions t
+            JOIN lots l ON l.id = t.lot_id
+            WHERE t.business_id = $1
+              AND t.transaction_type IN ('sale', 'purchase')
+              AND t.transaction_date BETWEEN $2 AND $3
+            ORDER BY t.transaction_date, t.created_at
+            "#,
+        )
+        .bind(business_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.db)
+        .await?;
+
+        let single_currency = transactions
+            .first()
+            .map(|first| transactions.iter().all(|t| t.currency == first.currency))
+            .unwrap_or(true);
+        let display = self.display_format(business_id, thai).await?;
+        let mut workbook = XlsxWorkbook::new().with_calendar(display.calendar);
+
+        // Monthly summary per currency
+        let months = monthly_totals(transactions.iter().map(|t| {
+            (
+                t.transaction_date,
+                t.currency.as_str(),
+                t.transaction_type == "sale",
+                t.quantity_kg,
+                t.amount.unwrap_or_default(),
+            )
+        }));
+        let mut summary = TemplateSheet::new(("สรุปรายเดือน", "Monthly Summary"), MONTHLY_COLUMNS);
+        summary.title = Some(if thai {
+            format!("รายงานการเงิน {} ถึง {}", display.date(start), display.date(end))
+        } else {
+            format!("Financial Report {} to {}", display.date(start), display.date(end))
+        });
+        let mut overall = MonthlyTotals::default();
+        for ((month, currency), totals) in &months {
+            overall.sold_kg += totals.sold_kg;
+            overall.sales += totals.sales;
+            overall.purchased_kg += totals.purchased_kg;
+            overall.purchases += totals.purchases;
+            summary.rows.push(vec![
+                text(format_month(*month, display.calendar)),
+                text(currency),
+                XlsxCell::Number(totals.sold_kg),
+                XlsxCell::Money(totals.sales),
+                XlsxCell::Number(totals.purchased_kg),
+                XlsxCell::Money(totals.purchases),
+                XlsxCell::Money(totals.net()),
+                opt_money(totals.average_sale_price()),
+            ]);
+        }
+        // Amounts in different currencies can't be added up
+        if single_currency && !months.is_empty() {
+            summary.footer = Some(vec![
+                text(tr(thai, "รวม", "Total")),
+                XlsxCell::Empty,
+                XlsxCell::Number(overall.sold_kg),
+                XlsxCell::Money(overall.sales),
+                XlsxCell::Number(overall.purchased_kg),
+                XlsxCell::Money(overall.purchases),
+                XlsxCell::Money(overall.net()),
+                opt_money(overall.average_sale_price()),
+            ]);
+        }
+        workbook.add_sheet(summary.finish(&hidden, thai));
+
+        // Sale and purchase listings
+        for (transaction_type, name) in [("sale", ("ยอดขาย", "Sales")), ("purchase", ("ยอดซื้อ", "Purchases"))] {
+            let mut sheet = TemplateSheet::new(name, TRANSACTION_COLUMNS);
+            let (mut quantity, mut amount) = (Decimal::ZERO, Decimal::ZERO);
+            for row in transactions.iter().filter(|t| t.transaction_type == transaction_type) {
+                quantity += row.quantity_kg;
+                amount += row.amount.unwrap_or_default();
+                sheet.rows.push(vec![
+                    XlsxCell::Date(row.transaction_date),
+                    text(&row.traceability_code),
+                    text(&row.lot_name),
+                    text(stage_label(&row.stage, thai)),
+                    opt_text(row.counterparty_name.clone()),
+                    opt_text(row.counterparty_contact.clone()),
+                    XlsxCell::Number(row.quantity_kg),
+                    opt_money(row.unit_price),
+                    opt_money(row.amount),
+                    text(&row.currency),
+                    opt_text(if thai {
+                        row.notes_th.clone().or(row.notes.clone())
+                    } else {
+                        row.notes.clone().or(row.notes_th.clone())
+                    }),
+                ]);
+            }
+            if !sheet.rows.is_empty() {
+                sheet.footer = Some(vec![
+                    text(tr(thai, "รวม", "Total")),
+                    XlsxCell::Empty,
+                    XlsxCell::Empty,
+                    XlsxCell::Empty,
+                    XlsxCell::Empty,
+                    XlsxCell::Empty,
+                    XlsxCell::Number(quantity),
+                    XlsxCell::Empty,
+                    if single_currency { XlsxCell::Money(amount) } else { XlsxCell::Empty },
+                ]);
+            }
+            workbook.add_sheet(sheet.finish(&hidden, thai));
+        }
+
+        workbook.to_bytes()
+    }
+}
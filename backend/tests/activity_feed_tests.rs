@@ -0,0 +1,152 @@
+//! Activity feed tests
+//!
+//! Tests for the business activity feed:
+//! - Pages hold at most the page size and carry a cursor only when more follow
+//! - Paging with the cursor visits every entry once, newest first
+//! - Cupping scores link to their session, everything else to its lot
+//! - Entries after the member's last visit are unseen; all are before a first visit
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::prelude::*;
+use serde_json::json;
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Mirrors `page_size`
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Mirrors `paginate`
+fn paginate<T>(mut rows: Vec<T>, page_size: i64, id: impl Fn(&T) -> i64) -> (Vec<T>, Option<i64>) {
+    let page_size = page_size.max(0) as usize;
+    if rows.len() <= page_size {
+        return (rows, None);
+    }
+    rows.truncate(page_size);
+    let next_before = rows.last().map(id);
+    (rows, next_before)
+}
+
+/// Mirrors `activity_action_url`
+fn activity_action_url(entity_type: &str, lot_id: Option<Uuid>, details: &serde_json::Value) -> Option<String> {
+    if entity_type == "cupping_sample" {
+        let session_id = details.get("session_id")?.as_str()?;
+        return Some(format!("/cupping/sessions/{}", session_id));
+    }
+    lot_id.map(|lot_id| format!("/lots/{}", lot_id))
+}
+
+/// Mirrors `is_unseen`
+fn is_unseen(occurred_at: DateTime<Utc>, seen_at: Option<DateTime<Utc>>) -> bool {
+    seen_at.is_none_or(|seen_at| occurred_at > seen_at)
+}
+
+/// Mirrors the feed query: ids below the cursor, newest first, one past the
+/// page size
+fn fetch(ids: &[i64], before: Option<i64>, page_size: i64) -> Vec<i64> {
+    let mut rows: Vec<i64> = ids.iter().copied().filter(|id| before.is_none_or(|b| *id < b)).collect();
+    rows.sort_unstable_by(|a, b| b.cmp(a));
+    rows.truncate(page_size as usize + 1);
+    rows
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_page_size_defaults_and_bounds() {
+        assert_eq!(page_size(None), 50);
+        assert_eq!(page_size(Some(0)), 1);
+        assert_eq!(page_size(Some(20)), 20);
+        assert_eq!(page_size(Some(10_000)), 200);
+    }
+
+    #[test]
+    fn test_last_page_has_no_cursor() {
+        let (page, next) = paginate(vec![5, 4, 3], 3, |id| *id);
+        assert_eq!(page, vec![5, 4, 3]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn test_cursor_is_last_entry_of_full_page() {
+        let (page, next) = paginate(vec![9, 8, 7, 6], 3, |id| *id);
+        assert_eq!(page, vec![9, 8, 7]);
+        assert_eq!(next, Some(7));
+    }
+
+    #[test]
+    fn test_cupping_score_links_to_session() {
+        let session = Uuid::new_v4();
+        let details = json!({"session_id": session, "sample_number": 2, "final_score": 84.5});
+        assert_eq!(
+            activity_action_url("cupping_sample", Some(Uuid::new_v4()), &details),
+            Some(format!("/cupping/sessions/{}", session))
+        );
+    }
+
+    #[test]
+    fn test_blind_score_without_lot_still_links_to_session() {
+        let session = Uuid::new_v4();
+        let details = json!({"session_id": session});
+        assert!(activity_action_url("cupping_sample", None, &details).is_some());
+    }
+
+    #[test]
+    fn test_other_activities_link_to_lot() {
+        let lot = Uuid::new_v4();
+        let details = json!({"from_stage": "cherry", "to_stage": "parchment"});
+        assert_eq!(activity_action_url("lot", Some(lot), &details), Some(format!("/lots/{}", lot)));
+        assert_eq!(activity_action_url("harvest", None, &json!({})), None);
+    }
+
+    #[test]
+    fn test_unseen_after_last_visit() {
+        let seen = Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 0).unwrap();
+        assert!(is_unseen(seen + Duration::minutes(1), Some(seen)));
+        assert!(!is_unseen(seen, Some(seen)));
+        assert!(!is_unseen(seen - Duration::days(1), Some(seen)));
+    }
+
+    #[test]
+    fn test_everything_unseen_before_first_visit() {
+        let occurred = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        assert!(is_unseen(occurred, None));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_paging_visits_every_entry_once(
+        ids in prop::collection::btree_set(1i64..10_000, 0..300),
+        limit in 1i64..60,
+    ) {
+        let ids: Vec<i64> = ids.into_iter().collect();
+        let mut visited = Vec::new();
+        let mut before = None;
+        loop {
+            let (page, next) = paginate(fetch(&ids, before, limit), limit, |id| *id);
+            prop_assert!(page.len() as i64 <= limit);
+            visited.extend(page);
+            match next {
+                Some(cursor) => before = Some(cursor),
+                None => break,
+            }
+        }
+        let mut expected = ids.clone();
+        expected.reverse();
+        prop_assert_eq!(visited, expected);
+    }
+}
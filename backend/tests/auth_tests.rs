@@ -4,6 +4,11 @@
 //! - Property 1: Role Permission Enforcement
 //! - Property 2: Custom Role Permission Persistence
 //! - Thailand compliance validations
+//! - Password reset and email verification tokens
+//! - Refresh token rotation and session device labels
+//! - TOTP two-factor codes (RFC 6238 vectors) and backup codes
+//! - Account lockout backoff and per-address login throttling
+//! - LINE-only registration defaults and generated business codes
 
 use proptest::prelude::*;
 
@@ -96,7 +101,8 @@ fn permission_strategy() -> impl Strategy<Value = String> {
 
 proptest! {
     /// Property 2: Custom Role Permission Persistence
-    /// When a business is created, default roles (owner, manager, worker) must be created
+    /// When a business is created, the template roles (owner, farm_manager, processor,
+    /// roaster, cupper, viewer) must be created
     /// with the correct permissions.
     #[test]
     #[ignore] // Requires database connection
@@ -231,7 +237,7 @@ mod business_code_tests {
 #[cfg(test)]
 mod role_permission_tests {
     /// Expected permissions for owner role (all permissions)
-    const OWNER_PERMISSIONS: &[&str] = &[
+    pub(super) const OWNER_PERMISSIONS: &[&str] = &[
         "plot:view", "plot:create", "plot:edit", "plot:delete",
         "harvest:view", "harvest:create", "harvest:edit", "harvest:delete",
         "processing:view", "processing:create", "processing:edit", "processing:delete",
@@ -346,6 +352,224 @@ mod role_permission_tests {
     }
 }
 
+// ============================================================================
+// Unit Tests: Role Templates
+// ============================================================================
+
+#[cfg(test)]
+mod role_template_tests {
+    use super::role_permission_tests::OWNER_PERMISSIONS;
+    use std::collections::BTreeSet;
+
+    /// Template permission rules as seeded by the role templates migration
+    const TEMPLATE_RULES: &[(&str, &str, &str)] = &[
+        ("owner", "*", "*"),
+        ("farm_manager", "plot", "*"),
+        ("farm_manager", "harvest", "*"),
+        ("farm_manager", "processing", "*"),
+        ("farm_manager", "inventory", "*"),
+        ("farm_manager", "certification", "*"),
+        ("farm_manager", "report", "*"),
+        ("farm_manager", "grading", "view"),
+        ("farm_manager", "cupping", "view"),
+        ("farm_manager", "roast_profile", "view"),
+        ("farm_manager", "user", "view"),
+        ("farm_manager", "role", "view"),
+        ("farm_manager", "business", "view"),
+        ("processor", "processing", "*"),
+        ("processor", "grading", "*"),
+        ("processor", "inventory", "*"),
+        ("processor", "harvest", "view"),
+        ("processor", "harvest", "create"),
+        ("processor", "plot", "view"),
+        ("processor", "report", "view"),
+        ("roaster", "roast_profile", "*"),
+        ("roaster", "inventory", "view"),
+        ("roaster", "inventory", "create"),
+        ("roaster", "inventory", "edit"),
+        ("roaster", "cupping", "view"),
+        ("roaster", "cupping", "create"),
+        ("roaster", "grading", "view"),
+        ("roaster", "report", "view"),
+        ("cupper", "cupping", "*"),
+        ("cupper", "grading", "view"),
+        ("cupper", "grading", "create"),
+        ("cupper", "roast_profile", "view"),
+        ("cupper", "inventory", "view"),
+        ("cupper", "report", "view"),
+        ("viewer", "*", "view"),
+    ];
+
+    const TEMPLATES: &[&str] = &["owner", "farm_manager", "processor", "roaster", "cupper", "viewer"];
+
+    /// Expand a template's wildcard rules against the permission catalogue
+    fn expand(template: &str, catalogue: &[&str]) -> BTreeSet<String> {
+        catalogue
+            .iter()
+            .filter(|perm| {
+                let (resource, action) = perm.split_once(':').unwrap();
+                TEMPLATE_RULES.iter().any(|(key, r, a)| {
+                    *key == template
+                        && (*r == "*" || *r == resource)
+                        && (*a == "*" || *a == action)
+                })
+            })
+            .map(|perm| perm.to_string())
+            .collect()
+    }
+
+    /// Re-apply a template: only grants missing permissions, never removes
+    fn apply(granted: &mut BTreeSet<String>, template: &str, catalogue: &[&str]) -> usize {
+        let before = granted.len();
+        granted.extend(expand(template, catalogue));
+        granted.len() - before
+    }
+
+    #[test]
+    fn test_every_template_has_rules() {
+        for template in TEMPLATES {
+            assert!(
+                !expand(template, OWNER_PERMISSIONS).is_empty(),
+                "Template {} grants nothing",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn test_owner_template_grants_everything() {
+        assert_eq!(expand("owner", OWNER_PERMISSIONS).len(), OWNER_PERMISSIONS.len());
+    }
+
+    #[test]
+    fn test_viewer_template_is_read_only() {
+        let viewer = expand("viewer", OWNER_PERMISSIONS);
+        assert!(viewer.iter().all(|perm| perm.ends_with(":view")));
+        assert_eq!(
+            viewer.len(),
+            OWNER_PERMISSIONS.iter().filter(|p| p.ends_with(":view")).count()
+        );
+    }
+
+    #[test]
+    fn test_only_owner_manages_roles_and_business() {
+        for template in TEMPLATES.iter().filter(|t| **t != "owner") {
+            for perm in expand(template, OWNER_PERMISSIONS) {
+                let managing = (perm.starts_with("role:") || perm.starts_with("business:")
+                    || perm.starts_with("user:"))
+                    && !perm.ends_with(":view");
+                assert!(!managing, "{} should not have {}", template, perm);
+            }
+        }
+    }
+
+    #[test]
+    fn test_specialist_templates_cover_their_domain() {
+        assert!(expand("processor", OWNER_PERMISSIONS).contains("processing:delete"));
+        assert!(expand("roaster", OWNER_PERMISSIONS).contains("roast_profile:create"));
+        assert!(expand("cupper", OWNER_PERMISSIONS).contains("cupping:edit"));
+        assert!(!expand("cupper", OWNER_PERMISSIONS).contains("roast_profile:edit"));
+        assert!(expand("farm_manager", OWNER_PERMISSIONS).contains("harvest:delete"));
+    }
+
+    #[test]
+    fn test_reapply_grants_new_permissions_only() {
+        let mut granted = expand("farm_manager", OWNER_PERMISSIONS);
+        granted.insert("cupping:create".to_string()); // business customization
+
+        // Nothing new: re-applying is a no-op and keeps the customization
+        assert_eq!(apply(&mut granted, "farm_manager", OWNER_PERMISSIONS), 0);
+        assert!(granted.contains("cupping:create"));
+
+        // A permission introduced later is picked up by wildcard rules
+        let mut catalogue = OWNER_PERMISSIONS.to_vec();
+        catalogue.extend(["harvest:export", "cupping:export"]);
+        assert_eq!(apply(&mut granted, "farm_manager", &catalogue), 1);
+        assert!(granted.contains("harvest:export"));
+        assert!(!granted.contains("cupping:export"));
+    }
+}
+
+// ============================================================================
+// Unit Tests: Field Visibility
+// ============================================================================
+
+#[cfg(test)]
+mod field_visibility_tests {
+    use serde_json::{json, Value};
+    use std::collections::HashSet;
+
+    fn resource_from_path(path: &str) -> &str {
+        let path = path.strip_prefix("/api/v1").unwrap_or(path);
+        path.trim_start_matches('/').split('/').next().unwrap_or("")
+    }
+
+    fn redact_fields(value: &mut Value, hidden: &HashSet<String>) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|key, _| !hidden.contains(key));
+                for nested in map.values_mut() {
+                    redact_fields(nested, hidden);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    redact_fields(item, hidden);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn hidden(fields: &[&str]) -> HashSet<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resource_from_path() {
+        assert_eq!(resource_from_path("/api/v1/inventory/transactions"), "inventory");
+        assert_eq!(resource_from_path("/api/v1/lots"), "lots");
+        assert_eq!(resource_from_path("/inventory/balances/abc"), "inventory");
+        assert_eq!(resource_from_path("/api/v1"), "");
+    }
+
+    #[test]
+    fn test_redact_list_response() {
+        let mut body = json!([
+            {"id": 1, "quantity_kg": "12.5", "unit_price": "180", "counterparty_contact": "081"},
+            {"id": 2, "quantity_kg": "3.0", "unit_price": null}
+        ]);
+        redact_fields(&mut body, &hidden(&["unit_price", "counterparty_contact"]));
+
+        assert_eq!(
+            body,
+            json!([{"id": 1, "quantity_kg": "12.5"}, {"id": 2, "quantity_kg": "3.0"}])
+        );
+    }
+
+    #[test]
+    fn test_redact_nested_detail_response() {
+        let mut body = json!({
+            "lot": {"id": 1, "name": "Lot A"},
+            "transactions": [{"id": 7, "total_price": "900", "notes": "sale"}],
+            "summary": {"total_price": "900"}
+        });
+        redact_fields(&mut body, &hidden(&["total_price"]));
+
+        assert_eq!(body["transactions"][0], json!({"id": 7, "notes": "sale"}));
+        assert_eq!(body["summary"], json!({}));
+        assert_eq!(body["lot"]["name"], "Lot A");
+    }
+
+    #[test]
+    fn test_redact_without_policies_is_noop() {
+        let original = json!({"unit_price": "180", "items": [{"unit_cost": "90"}]});
+        let mut body = original.clone();
+        redact_fields(&mut body, &HashSet::new());
+        assert_eq!(body, original);
+    }
+}
+
 // ============================================================================
 // Unit Tests: Thailand Compliance
 // ============================================================================
@@ -515,6 +739,11 @@ mod error_message_tests {
             ("owner", "เจ้าของ"),
             ("manager", "ผู้จัดการ"),
             ("worker", "พนักงาน"),
+            ("farm_manager", "ผู้จัดการฟาร์ม"),
+            ("processor", "ผู้แปรรูป"),
+            ("roaster", "ผู้คั่ว"),
+            ("cupper", "นักชิมกาแฟ"),
+            ("viewer", "ผู้ดูข้อมูล"),
         ];
         
         for (en, th) in role_translations {
@@ -523,3 +752,582 @@ mod error_message_tests {
         }
     }
 }
+
+// ============================================================================
+// Unit Tests: Account Recovery
+// ============================================================================
+
+#[cfg(test)]
+mod account_recovery_tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use sha2::{Digest, Sha256};
+
+    const MIN_PASSWORD_LENGTH: usize = 8;
+    const MAX_PASSWORD_BYTES: usize = 72;
+
+    /// Mirrors `hash_action_token`
+    fn hash_action_token(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+    }
+
+    /// Mirrors `validate_new_password`
+    fn password_acceptable(password: &str) -> bool {
+        password.chars().count() >= MIN_PASSWORD_LENGTH
+            && !password.trim().is_empty()
+            && password.len() <= MAX_PASSWORD_BYTES
+    }
+
+    /// Mirrors the `consume_token` condition
+    fn token_usable(expires_at: chrono::DateTime<Utc>, used: bool, now: chrono::DateTime<Utc>) -> bool {
+        !used && expires_at > now
+    }
+
+    #[test]
+    fn test_token_hash_is_sha256_hex() {
+        let hash = hash_action_token("abc");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hash_action_token(" abc\n"), hash, "Pasted tokens are trimmed");
+    }
+
+    #[test]
+    fn test_password_length_limits() {
+        assert!(!password_acceptable("short7!"));
+        assert!(!password_acceptable("        "));
+        assert!(password_acceptable("eightchr"));
+        // Thai characters are 3 bytes each; 25 of them exceed bcrypt's 72
+        assert!(password_acceptable(&"ก".repeat(24)));
+        assert!(!password_acceptable(&"ก".repeat(25)));
+    }
+
+    #[test]
+    fn test_token_expiry_and_single_use() {
+        let issued = Utc.with_ymd_and_hms(2024, 12, 25, 8, 0, 0).unwrap();
+        let expires = issued + Duration::minutes(60);
+        assert!(token_usable(expires, false, issued + Duration::minutes(59)));
+        assert!(!token_usable(expires, false, expires));
+        assert!(!token_usable(expires, true, issued));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_distinct_tokens_have_distinct_hashes(a in "[0-9a-f]{64}", b in "[0-9a-f]{64}") {
+            prop_assume!(a != b);
+            prop_assert_ne!(hash_action_token(&a), hash_action_token(&b));
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests: Refresh Token Sessions
+// ============================================================================
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    const MAX_DEVICE_NAME_CHARS: usize = 100;
+
+    /// Mirrors `describe_user_agent`
+    fn describe_user_agent(user_agent: &str) -> Option<String> {
+        let browser = [
+            (" Line/", "LINE"),
+            ("Edg/", "Edge"),
+            ("Firefox/", "Firefox"),
+            ("Chrome/", "Chrome"),
+            ("CriOS/", "Chrome"),
+            ("Safari/", "Safari"),
+        ]
+        .into_iter()
+        .find(|(marker, _)| user_agent.contains(marker))
+        .map(|(_, name)| name);
+        let platform = [
+            ("Android", "Android"),
+            ("iPhone", "iOS"),
+            ("iPad", "iOS"),
+            ("Windows", "Windows"),
+            ("Mac OS X", "macOS"),
+            ("Linux", "Linux"),
+        ]
+        .into_iter()
+        .find(|(marker, _)| user_agent.contains(marker))
+        .map(|(_, name)| name);
+
+        match (browser, platform) {
+            (Some(browser), Some(platform)) => Some(format!("{} on {}", browser, platform)),
+            (Some(name), None) | (None, Some(name)) => Some(name.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Mirrors `device_label`
+    fn device_label(device_name: Option<&str>, user_agent: Option<&str>) -> Option<String> {
+        let named = device_name
+            .map(|n| n.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|n| !n.is_empty())
+            .map(|n| n.chars().take(MAX_DEVICE_NAME_CHARS).collect());
+        named.or_else(|| user_agent.and_then(describe_user_agent))
+    }
+
+    /// Outcome of presenting a refresh token, as decided by `refresh_token`
+    #[derive(Debug, PartialEq)]
+    enum Refresh {
+        Rotated,
+        Rejected,
+        /// Rejected, and the token's session is revoked
+        SessionRevoked,
+    }
+
+    /// Tokens of one session; only the last one is live
+    struct Session {
+        tokens: Vec<bool>, // revoked flags
+        revoked: bool,
+    }
+
+    impl Session {
+        fn new() -> Self {
+            Session { tokens: vec![false], revoked: false }
+        }
+
+        /// Mirrors the rotation and reuse checks of `refresh_token`
+        fn refresh(&mut self, token: usize) -> Refresh {
+            if self.tokens[token] {
+                if !self.revoked {
+                    self.revoked = true;
+                    self.tokens.iter_mut().for_each(|t| *t = true);
+                    return Refresh::SessionRevoked;
+                }
+                return Refresh::Rejected;
+            }
+            self.tokens[token] = true;
+            self.tokens.push(false);
+            Refresh::Rotated
+        }
+
+        fn live_token(&self) -> Option<usize> {
+            self.tokens.iter().position(|revoked| !revoked)
+        }
+    }
+
+    const CHROME_ANDROID: &str = "Mozilla/5.0 (Linux; Android 14; SM-A546E) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36";
+    const LINE_IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 Safari Line/14.5.0";
+    const SAFARI_MAC: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15";
+    const EDGE_WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0";
+
+    #[test]
+    fn test_user_agents_described() {
+        assert_eq!(describe_user_agent(CHROME_ANDROID).as_deref(), Some("Chrome on Android"));
+        assert_eq!(describe_user_agent(LINE_IPHONE).as_deref(), Some("LINE on iOS"));
+        assert_eq!(describe_user_agent(SAFARI_MAC).as_deref(), Some("Safari on macOS"));
+        assert_eq!(describe_user_agent(EDGE_WINDOWS).as_deref(), Some("Edge on Windows"));
+        assert_eq!(describe_user_agent("curl/8.5.0"), None);
+    }
+
+    #[test]
+    fn test_device_name_preferred_over_user_agent() {
+        assert_eq!(
+            device_label(Some("  Packing   room tablet "), Some(CHROME_ANDROID)).as_deref(),
+            Some("Packing room tablet")
+        );
+        assert_eq!(device_label(Some("   "), Some(CHROME_ANDROID)).as_deref(), Some("Chrome on Android"));
+        assert_eq!(device_label(None, None), None);
+    }
+
+    #[test]
+    fn test_rotation_issues_a_new_token() {
+        let mut session = Session::new();
+        assert_eq!(session.refresh(0), Refresh::Rotated);
+        assert_eq!(session.live_token(), Some(1));
+        assert_eq!(session.refresh(1), Refresh::Rotated);
+        assert_eq!(session.live_token(), Some(2));
+    }
+
+    #[test]
+    fn test_reused_token_revokes_session() {
+        let mut session = Session::new();
+        assert_eq!(session.refresh(0), Refresh::Rotated);
+        // A copy of the first token is replayed after the owner rotated it
+        assert_eq!(session.refresh(0), Refresh::SessionRevoked);
+        assert_eq!(session.live_token(), None);
+        assert_eq!(session.refresh(1), Refresh::Rejected, "The owner's token stops working too");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_device_label_bounded(name in "\\PC{0,300}") {
+            if let Some(label) = device_label(Some(&name), None) {
+                prop_assert!(label.chars().count() <= MAX_DEVICE_NAME_CHARS);
+                prop_assert!(!label.is_empty());
+            }
+        }
+
+        #[test]
+        fn prop_session_has_at_most_one_live_token(steps in prop::collection::vec(0usize..6, 0..20)) {
+            let mut session = Session::new();
+            for step in steps {
+                let token = step.min(session.tokens.len() - 1);
+                session.refresh(token);
+                prop_assert!(session.tokens.iter().filter(|revoked| !**revoked).count() <= 1);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests: TOTP Two-Factor Authentication
+// ============================================================================
+
+#[cfg(test)]
+mod two_factor_tests {
+    use super::*;
+    use hmac::{Hmac, Mac};
+    use sha1::Sha1;
+
+    const TOTP_PERIOD_SECONDS: u64 = 30;
+    const TOTP_DIGITS: u32 = 6;
+    const TOTP_ALLOWED_DRIFT_STEPS: u64 = 1;
+    const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    /// RFC 6238 test secret
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    /// Mirrors `base32_encode`
+    fn base32_encode(bytes: &[u8]) -> String {
+        let mut out = String::new();
+        let (mut buffer, mut bits) = (0u32, 0u32);
+        for &byte in bytes {
+            buffer = (buffer << 8) | u32::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+        }
+        out
+    }
+
+    /// Mirrors `base32_decode`
+    fn base32_decode(text: &str) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        let (mut buffer, mut bits) = (0u32, 0u32);
+        for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+            buffer = (buffer << 5) | value;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((buffer >> bits) as u8);
+            }
+        }
+        Some(out)
+    }
+
+    /// Mirrors `hotp`
+    fn hotp(secret: &[u8], counter: u64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(secret).unwrap();
+        mac.update(&counter.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]])
+            & 0x7fff_ffff;
+        format!("{:0width$}", binary % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize)
+    }
+
+    /// Mirrors `looks_like_totp`
+    fn looks_like_totp(code: &str) -> bool {
+        let digits: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        digits.len() == TOTP_DIGITS as usize && digits.chars().all(|c| c.is_ascii_digit())
+    }
+
+    /// Mirrors `verify_totp`
+    fn verify_totp(secret_base32: &str, code: &str, unix_seconds: u64, last_used_step: Option<u64>) -> Option<u64> {
+        if !looks_like_totp(code) {
+            return None;
+        }
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        let secret = base32_decode(secret_base32)?;
+        let current = unix_seconds / TOTP_PERIOD_SECONDS;
+        (current.saturating_sub(TOTP_ALLOWED_DRIFT_STEPS)..=current + TOTP_ALLOWED_DRIFT_STEPS)
+            .filter(|step| last_used_step.is_none_or(|last| *step > last))
+            .find(|step| hotp(&secret, *step) == code)
+    }
+
+    /// Mirrors `normalize_backup_code`
+    fn normalize_backup_code(code: &str) -> String {
+        code.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+
+    #[test]
+    fn test_rfc6238_sha1_vectors() {
+        // RFC 6238 appendix B, truncated to the last 6 of the 8 digits
+        for (time, code) in [
+            (59u64, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            assert_eq!(hotp(RFC_SECRET, time / TOTP_PERIOD_SECONDS), code, "time {}", time);
+        }
+    }
+
+    #[test]
+    fn test_base32_of_rfc_secret() {
+        let encoded = base32_encode(RFC_SECRET);
+        assert_eq!(encoded, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(base32_decode(&encoded.to_lowercase()), Some(RFC_SECRET.to_vec()));
+        assert_eq!(base32_decode("GEZD 1"), None);
+    }
+
+    #[test]
+    fn test_code_accepted_within_one_step_of_drift() {
+        let secret = base32_encode(RFC_SECRET);
+        let now = 1_111_111_111;
+        let step = now / TOTP_PERIOD_SECONDS;
+        assert_eq!(verify_totp(&secret, "050471", now, None), Some(step));
+        assert_eq!(verify_totp(&secret, "050471", now + 30, None), Some(step));
+        assert_eq!(verify_totp(&secret, "050471", now - 30, None), Some(step));
+        assert_eq!(verify_totp(&secret, "050471", now + 60, None), None);
+        assert_eq!(verify_totp(&secret, "050 471", now, None), Some(step));
+    }
+
+    #[test]
+    fn test_code_accepted_once() {
+        let secret = base32_encode(RFC_SECRET);
+        let now = 1_111_111_111;
+        let step = verify_totp(&secret, "050471", now, None).unwrap();
+        assert_eq!(verify_totp(&secret, "050471", now, Some(step)), None);
+    }
+
+    #[test]
+    fn test_backup_codes_are_not_mistaken_for_totp() {
+        assert!(looks_like_totp("123456"));
+        assert!(looks_like_totp(" 123 456 "));
+        assert!(!looks_like_totp("abcde-fghjk"));
+        assert!(!looks_like_totp("12345"));
+        assert!(!looks_like_totp("1234567"));
+    }
+
+    #[test]
+    fn test_backup_code_normalization() {
+        assert_eq!(normalize_backup_code("ABCDE-FGHJK"), "abcdefghjk");
+        assert_eq!(normalize_backup_code(" abcde fghjk "), "abcdefghjk");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_base32_round_trip(bytes in prop::collection::vec(any::<u8>(), 0..40)) {
+            prop_assert_eq!(base32_decode(&base32_encode(&bytes)), Some(bytes));
+        }
+
+        #[test]
+        fn prop_current_code_verifies(secret in prop::collection::vec(any::<u8>(), 20), time in 60u64..4_000_000_000) {
+            let code = hotp(&secret, time / TOTP_PERIOD_SECONDS);
+            prop_assert!(looks_like_totp(&code));
+            prop_assert!(verify_totp(&base32_encode(&secret), &code, time, None).is_some());
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests: Account Lockout and Login Throttling
+// ============================================================================
+
+#[cfg(test)]
+mod login_throttling_tests {
+    use super::*;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    const MAX_FAILED_LOGINS: i32 = 5;
+    const LOCKOUT_BASE_MINUTES: i64 = 5;
+    const MAX_LOCKOUT_MINUTES: i64 = 24 * 60;
+    const MAX_FAILED_LOGINS_PER_IP: i64 = 20;
+    const IP_THROTTLE_WINDOW_MINUTES: i64 = 15;
+
+    /// Mirrors `lockout_minutes`
+    fn lockout_minutes(lockout_count: i32) -> i64 {
+        let doublings = lockout_count.clamp(0, 16) as u32;
+        (LOCKOUT_BASE_MINUTES << doublings).min(MAX_LOCKOUT_MINUTES)
+    }
+
+    /// Mirrors `ip_retry_after`
+    fn ip_retry_after(failures: i64, oldest_failure: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<i64> {
+        if failures < MAX_FAILED_LOGINS_PER_IP {
+            return None;
+        }
+        let frees_at = oldest_failure? + Duration::minutes(IP_THROTTLE_WINDOW_MINUTES);
+        Some((frees_at - now).num_seconds().max(1))
+    }
+
+    /// Mirrors the counters kept by `record_failed_login` and cleared by a
+    /// sign-in
+    #[derive(Debug, Default)]
+    struct Account {
+        failed_login_count: i32,
+        lockout_count: i32,
+        locked_minutes: Option<i64>,
+    }
+
+    impl Account {
+        fn fail(&mut self) {
+            self.failed_login_count += 1;
+            if self.failed_login_count >= MAX_FAILED_LOGINS {
+                self.locked_minutes = Some(lockout_minutes(self.lockout_count));
+                self.failed_login_count = 0;
+                self.lockout_count += 1;
+            } else {
+                self.locked_minutes = None;
+            }
+        }
+
+        fn sign_in(&mut self) {
+            *self = Account::default();
+        }
+    }
+
+    #[test]
+    fn test_lockout_doubles_up_to_a_day() {
+        assert_eq!(lockout_minutes(0), 5);
+        assert_eq!(lockout_minutes(1), 10);
+        assert_eq!(lockout_minutes(3), 40);
+        assert_eq!(lockout_minutes(8), 1280);
+        assert_eq!(lockout_minutes(9), MAX_LOCKOUT_MINUTES);
+        assert_eq!(lockout_minutes(1000), MAX_LOCKOUT_MINUTES);
+        assert_eq!(lockout_minutes(-1), 5);
+    }
+
+    #[test]
+    fn test_fifth_failure_locks() {
+        let mut account = Account::default();
+        for _ in 0..4 {
+            account.fail();
+            assert_eq!(account.locked_minutes, None);
+        }
+        account.fail();
+        assert_eq!(account.locked_minutes, Some(5));
+
+        // The next run of failures locks for twice as long
+        for _ in 0..5 {
+            account.fail();
+        }
+        assert_eq!(account.locked_minutes, Some(10));
+
+        account.sign_in();
+        for _ in 0..5 {
+            account.fail();
+        }
+        assert_eq!(account.locked_minutes, Some(5));
+    }
+
+    #[test]
+    fn test_ip_throttle() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let oldest = now - Duration::minutes(10);
+        assert_eq!(ip_retry_after(19, Some(oldest), now), None);
+        assert_eq!(ip_retry_after(20, Some(oldest), now), Some(5 * 60));
+        // A failure about to leave the window still asks for a second
+        assert_eq!(ip_retry_after(25, Some(now - Duration::minutes(15)), now), Some(1));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_lockout_is_monotonic(count in 0i32..40) {
+            prop_assert!(lockout_minutes(count + 1) >= lockout_minutes(count));
+            prop_assert!(lockout_minutes(count) <= MAX_LOCKOUT_MINUTES);
+        }
+
+        #[test]
+        fn prop_locks_every_fifth_failure(failures in 1usize..60) {
+            let mut account = Account::default();
+            let mut locks = 0;
+            for _ in 0..failures {
+                account.fail();
+                if account.locked_minutes.is_some() {
+                    locks += 1;
+                }
+            }
+            prop_assert_eq!(locks, failures / MAX_FAILED_LOGINS as usize);
+            prop_assert_eq!(account.lockout_count as usize, locks);
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests: LINE Registration
+// ============================================================================
+
+#[cfg(test)]
+mod line_registration_tests {
+    use proptest::prelude::*;
+    use uuid::Uuid;
+
+    /// Mirrors `generated_business_code`
+    fn generated_business_code(seed: Uuid) -> String {
+        format!("L{}", &seed.simple().to_string()[..6]).to_uppercase()
+    }
+
+    /// Mirrors the name defaulting in `register_with_line`: the owner name
+    /// falls back to the LINE display name, the business name to the owner
+    fn default_names(
+        display_name: &str,
+        owner_name: Option<&str>,
+        business_name: Option<&str>,
+    ) -> (String, String) {
+        let owner = owner_name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(display_name.trim());
+        let business = business_name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(owner);
+        (owner.to_string(), business.to_string())
+    }
+
+    fn is_valid_business_code(code: &str) -> bool {
+        code.len() >= 3
+            && code.len() <= 10
+            && code.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    }
+
+    #[test]
+    fn test_generated_code_format() {
+        let seed = Uuid::parse_str("3fa85f64-5717-4562-b3fc-2c963f66afa6").unwrap();
+        assert_eq!(generated_business_code(seed), "L3FA85F");
+    }
+
+    #[test]
+    fn test_names_default_to_display_name() {
+        assert_eq!(
+            default_names(" Somchai ", None, None),
+            ("Somchai".to_string(), "Somchai".to_string())
+        );
+        assert_eq!(
+            default_names("Somchai", Some("Somchai K."), Some("  ")),
+            ("Somchai K.".to_string(), "Somchai K.".to_string())
+        );
+        assert_eq!(
+            default_names("Somchai", None, Some("Doi Farm")),
+            ("Somchai".to_string(), "Doi Farm".to_string())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_generated_code_is_valid(bytes in any::<u128>()) {
+            let code = generated_business_code(Uuid::from_u128(bytes));
+            prop_assert_eq!(code.len(), 7);
+            prop_assert!(code.starts_with('L'));
+            prop_assert!(is_valid_business_code(&code));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Benchmarking tests
+//!
+//! Tests for anonymous regional benchmarks:
+//! - Percentile ranks count ties as half and stay within 0-100
+//! - Quartiles interpolate between contributed values
+//! - Prices are compared as 50 THB/kg bands
+//! - Metrics are withheld until enough businesses report them
+
+use proptest::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const MIN_COHORT_BUSINESSES: usize = 5;
+const PRICE_BAND_WIDTH: Decimal = Decimal::from_parts(50, 0, 0, false, 0);
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Mirrors `price_band`, returning (min, max)
+fn price_band(price_per_kg: Decimal) -> (Decimal, Decimal) {
+    let min = (price_per_kg / PRICE_BAND_WIDTH).floor() * PRICE_BAND_WIDTH;
+    (min, min + PRICE_BAND_WIDTH)
+}
+
+/// Mirrors `percentile_rank`
+fn percentile_rank(value: Decimal, values: &[Decimal]) -> i32 {
+    if values.is_empty() {
+        return 0;
+    }
+    let below = values.iter().filter(|v| **v < value).count();
+    let equal = values.iter().filter(|v| **v == value).count();
+    ((below * 2 + equal) * 100 + values.len()) as i32 / (values.len() * 2) as i32
+}
+
+/// Mirrors `quantile`
+fn quantile(sorted: &[Decimal], q: Decimal) -> Decimal {
+    match sorted.len() {
+        0 => Decimal::ZERO,
+        1 => sorted[0],
+        len => {
+            let position = q * Decimal::from(len - 1);
+            let lower = position.floor();
+            let index = lower.to_usize().unwrap_or(0).min(len - 1);
+            let next = sorted[(index + 1).min(len - 1)];
+            sorted[index] + (next - sorted[index]) * (position - lower)
+        }
+    }
+}
+
+/// Mirrors the cohort size rule in `metric_benchmark`
+fn reported(values: &[Decimal]) -> bool {
+    values.len() >= MIN_COHORT_BUSINESSES
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    fn scores() -> Vec<Decimal> {
+        ["82", "83.5", "84", "85", "86.25"].iter().map(|s| dec(s)).collect()
+    }
+
+    #[test]
+    fn test_percentile_of_top_business() {
+        assert_eq!(percentile_rank(dec("86.25"), &scores()), 90);
+    }
+
+    #[test]
+    fn test_percentile_of_median_business() {
+        assert_eq!(percentile_rank(dec("84"), &scores()), 50);
+    }
+
+    #[test]
+    fn test_percentile_ties_count_half() {
+        let values = vec![dec("80"); 4];
+        assert_eq!(percentile_rank(dec("80"), &values), 50);
+    }
+
+    #[test]
+    fn test_quartiles_interpolate() {
+        let sorted = scores();
+        assert_eq!(quantile(&sorted, dec("0.25")), dec("83.5"));
+        assert_eq!(quantile(&sorted, dec("0.5")), dec("84"));
+        assert_eq!(quantile(&sorted, dec("0.75")), dec("85"));
+        assert_eq!(quantile(&[dec("10"), dec("20")], dec("0.5")), dec("15"));
+    }
+
+    #[test]
+    fn test_price_bands() {
+        assert_eq!(price_band(dec("349.99")), (dec("300"), dec("350")));
+        assert_eq!(price_band(dec("350")), (dec("350"), dec("400")));
+        assert_eq!(price_band(dec("12")), (dec("0"), dec("50")));
+    }
+
+    #[test]
+    fn test_small_cohorts_withheld() {
+        assert!(!reported(&scores()[..4]));
+        assert!(reported(&scores()));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_percentile_within_bounds(values in prop::collection::vec(0u32..10000, 1..40), pick in 0usize..40) {
+        let values: Vec<Decimal> = values.into_iter().map(|v| Decimal::new(v as i64, 2)).collect();
+        let value = values[pick % values.len()];
+        let rank = percentile_rank(value, &values);
+        prop_assert!((0..=100).contains(&rank));
+    }
+
+    #[test]
+    fn prop_quantiles_ordered(mut values in prop::collection::vec(0u32..10000, 1..40)) {
+        values.sort();
+        let sorted: Vec<Decimal> = values.into_iter().map(|v| Decimal::new(v as i64, 2)).collect();
+        let p25 = quantile(&sorted, dec("0.25"));
+        let median = quantile(&sorted, dec("0.5"));
+        let p75 = quantile(&sorted, dec("0.75"));
+
+        prop_assert!(sorted[0] <= p25 && p25 <= median && median <= p75 && p75 <= sorted[sorted.len() - 1]);
+    }
+
+    #[test]
+    fn prop_price_within_its_band(cents in 0u32..200000) {
+        let price = Decimal::new(cents as i64, 2);
+        let (min, max) = price_band(price);
+        prop_assert!(min <= price && price < max);
+    }
+}
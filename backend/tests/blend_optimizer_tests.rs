@@ -0,0 +1,206 @@
+//! Blend optimizer tests
+//!
+//! Tests for proposing blend ratios:
+//! - Ratios move in 5% steps and always add up to 100%
+//! - The blended score lands inside the target band, bounds included
+//! - The cheapest blend per lot combination is proposed, cheapest first
+//! - Components need enough stock for the requested quantity
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+const RATIO_STEP_PERCENT: u32 = 5;
+
+/// Mirrors `BlendLot`: (code, score, cost per kg, available kg)
+type Lot = (&'static str, Decimal, Decimal, Decimal);
+
+/// Mirrors `BlendProposal`: (cost per kg, score, [(code, percent)])
+type Proposal = (Decimal, Decimal, Vec<(&'static str, u32)>);
+
+fn lot(code: &'static str, score: &str, cost: &str, available_kg: &str) -> Lot {
+    (code, dec(score), dec(cost), dec(available_kg))
+}
+
+/// Mirrors `compositions`
+fn compositions(units: u32, parts: usize) -> Vec<Vec<u32>> {
+    if parts == 1 {
+        return if units > 0 { vec![vec![units]] } else { Vec::new() };
+    }
+    let mut result = Vec::new();
+    for first in 1..units {
+        for mut rest in compositions(units - first, parts - 1) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// All index combinations of `k` out of `n`
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    (0..n)
+        .flat_map(|first| {
+            combinations(n - first - 1, k - 1).into_iter().map(move |rest| {
+                std::iter::once(first).chain(rest.into_iter().map(|i| i + first + 1)).collect()
+            })
+        })
+        .collect()
+}
+
+/// Mirrors `optimize_blend`
+fn optimize_blend(
+    lots: &[Lot],
+    min_score: Decimal,
+    max_score: Decimal,
+    quantity_kg: Option<Decimal>,
+    max_components: usize,
+) -> Vec<Proposal> {
+    let hundred = Decimal::from(100);
+    let mut proposals = Vec::new();
+    for size in 1..=max_components.min(lots.len()) {
+        let splits = compositions(100 / RATIO_STEP_PERCENT, size);
+        for combo in combinations(lots.len(), size) {
+            let mut best: Option<Proposal> = None;
+            for split in &splits {
+                let (mut score, mut cost, mut in_stock) = (Decimal::ZERO, Decimal::ZERO, true);
+                for (&i, &share) in combo.iter().zip(split) {
+                    let percent = Decimal::from(share * RATIO_STEP_PERCENT);
+                    score += lots[i].1 * percent / hundred;
+                    cost += lots[i].2 * percent / hundred;
+                    if let Some(quantity) = quantity_kg {
+                        in_stock &= quantity * percent / hundred <= lots[i].3;
+                    }
+                }
+                if !in_stock || score < min_score || score > max_score {
+                    continue;
+                }
+                let better = match &best {
+                    Some((c, s, _)) => cost < *c || (cost == *c && score > *s),
+                    None => true,
+                };
+                if better {
+                    let ratios = combo
+                        .iter()
+                        .zip(split)
+                        .map(|(&i, &share)| (lots[i].0, share * RATIO_STEP_PERCENT))
+                        .collect();
+                    best = Some((cost, score, ratios));
+                }
+            }
+            proposals.extend(best);
+        }
+    }
+    proposals.sort_by(|a, b| a.0.cmp(&b.0).then(a.2.len().cmp(&b.2.len())).then(b.1.cmp(&a.1)));
+    proposals
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_cheap_lot_lifted_into_band() {
+        // 84-point lot at 300/kg lifted to 85 with an 88-point lot at 500/kg
+        let lots = vec![lot("CHEAP", "84", "300", "1000"), lot("PREMIUM", "88", "500", "1000")];
+        let proposals = optimize_blend(&lots, dec("85"), dec("86"), None, 2);
+
+        let (cost, score, ratios) = &proposals[0];
+        assert_eq!(*ratios, vec![("CHEAP", 75), ("PREMIUM", 25)]);
+        assert_eq!(*score, dec("85"));
+        assert_eq!(*cost, dec("350"));
+    }
+
+    #[test]
+    fn test_single_lot_in_band_needs_no_blend() {
+        let lots = vec![lot("A", "85.5", "400", "1000"), lot("B", "88", "200", "1000")];
+        let proposals = optimize_blend(&lots, dec("85"), dec("86"), None, 2);
+        // B alone is above the band; A alone fits, A+B mixed is cheaper
+        assert!(proposals.iter().any(|(_, _, r)| *r == vec![("A", 100)]));
+        assert_eq!(proposals[0].2.len(), 2);
+        assert!(proposals[0].0 < dec("400"));
+    }
+
+    #[test]
+    fn test_band_bounds_inclusive() {
+        let lots = vec![lot("A", "86", "400", "1000")];
+        assert_eq!(optimize_blend(&lots, dec("86"), dec("86"), None, 1).len(), 1);
+        assert!(optimize_blend(&lots, dec("86.01"), dec("87"), None, 1).is_empty());
+    }
+
+    #[test]
+    fn test_stock_limits_ratios() {
+        // Only 100 kg of the premium lot: at most 20% of a 500 kg blend
+        let lots = vec![lot("CHEAP", "83", "300", "1000"), lot("PREMIUM", "88", "500", "100")];
+        let proposals = optimize_blend(&lots, dec("85"), dec("86"), Some(dec("500")), 2);
+        assert!(proposals.is_empty());
+
+        let proposals = optimize_blend(&lots, dec("84"), dec("86"), Some(dec("500")), 2);
+        assert_eq!(proposals[0].2, vec![("CHEAP", 80), ("PREMIUM", 20)]);
+    }
+
+    #[test]
+    fn test_max_components_respected() {
+        let lots = vec![
+            lot("A", "80", "200", "1000"),
+            lot("B", "84", "300", "1000"),
+            lot("C", "90", "600", "1000"),
+        ];
+        for max in 1..=3 {
+            let proposals = optimize_blend(&lots, dec("84"), dec("85"), None, max);
+            assert!(proposals.iter().all(|(_, _, r)| r.len() <= max));
+        }
+    }
+
+    #[test]
+    fn test_composition_counts() {
+        assert_eq!(compositions(20, 1).len(), 1);
+        assert_eq!(compositions(20, 2).len(), 19);
+        assert_eq!(compositions(20, 3).len(), 171);
+        assert!(compositions(20, 3).iter().all(|c| c.iter().sum::<u32>() == 20));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+
+    proptest! {
+        /// Proposals are inside the band, sum to 100% and come cheapest first
+        #[test]
+        fn prop_proposals_valid(
+            lots in proptest::collection::vec((7800i64..=9200, 100i64..=900), 1..=4),
+            low in 8000i64..=8800,
+            width in 0i64..=200,
+        ) {
+            let lots: Vec<Lot> = lots
+                .into_iter()
+                .map(|(score, cost)| ("L", Decimal::new(score, 2), Decimal::from(cost), Decimal::from(1000)))
+                .collect();
+            let (min, max) = (Decimal::new(low, 2), Decimal::new(low + width, 2));
+            let proposals = optimize_blend(&lots, min, max, None, 3);
+
+            for (_, score, ratios) in &proposals {
+                prop_assert!(*score >= min && *score <= max);
+                prop_assert_eq!(ratios.iter().map(|(_, p)| p).sum::<u32>(), 100);
+            }
+            prop_assert!(proposals.windows(2).all(|w| w[0].0 <= w[1].0));
+        }
+    }
+}
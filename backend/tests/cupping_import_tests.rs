@@ -0,0 +1,280 @@
+//! Cupping import tests
+//!
+//! Tests for reading legacy SCA score sheet exports:
+//! - Headers from common layouts map to the same fields
+//! - Cropster and Tastify exports are detected from their headers
+//! - Day-first and Buddhist Era dates are read as Gregorian, times dropped
+//! - Scores accept a decimal comma; cup counts become points
+//! - A defect deduction splits into faults and taints
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const BUDDHIST_ERA_OFFSET: i32 = 543;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SheetFormat {
+    Sca,
+    Cropster,
+    Tastify,
+}
+
+/// Mirrors `header_key`
+fn header_key(header: &str) -> String {
+    header
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Mirrors `detect_format`
+fn detect_format<'a>(headers: impl IntoIterator<Item = &'a str>) -> SheetFormat {
+    let keys: Vec<String> = headers.into_iter().map(header_key).collect();
+    if keys.iter().any(|key| key == "evaluator") {
+        SheetFormat::Cropster
+    } else if keys.iter().any(|key| key == "cupping_name") {
+        SheetFormat::Tastify
+    } else {
+        SheetFormat::Sca
+    }
+}
+
+/// Mirrors `header_field`, returning the field's serialized name
+fn header_field(format: SheetFormat, header: &str) -> Option<&'static str> {
+    let key = header_key(header);
+
+    let field = match (format, key.as_str()) {
+        (SheetFormat::Cropster, "sample_id") => return None,
+        (SheetFormat::Cropster | SheetFormat::Tastify, "defects") => "defect_points",
+        (_, "date" | "session_date" | "cupping_date" | "date_cupped" | "evaluation_date") => "session_date",
+        (_, "cupper" | "cupper_name" | "taster" | "q_grader" | "grader" | "evaluator") => "cupper",
+        (_, "session" | "session_name" | "cupping_name") => "session",
+        (
+            _,
+            "lot" | "lot_code" | "lot_id" | "lot_name" | "sample" | "sample_id" | "sample_code" | "sample_name"
+            | "traceability_code",
+        ) => "lot",
+        (_, "fragrance_aroma" | "fragrance" | "aroma" | "dry_fragrance") => "fragrance_aroma",
+        (_, "flavor" | "flavour") => "flavor",
+        (_, "aftertaste" | "after_taste" | "finish") => "aftertaste",
+        (_, "clean_cup" | "cleancup" | "clean") => "clean_cup",
+        (_, "clean_cups" | "clean_cup_cups") => "clean_cup_cups",
+        (_, "overall" | "cupper_points" | "cuppers_points" | "cupper_s_points" | "overall_impression") => "overall",
+        (_, "defect_points" | "defects_points" | "defect_deduction") => "defect_points",
+        (_, "total" | "total_score" | "final_score" | "score" | "final") => "total_score",
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Mirrors `defects_from_points`, returning taint and fault counts
+fn defects_from_points(points: Decimal) -> Option<(i32, i32)> {
+    if !points.fract().is_zero() {
+        return None;
+    }
+    let points = points.to_i32()?;
+    if points < 0 || points % 2 != 0 {
+        return None;
+    }
+    let fault_count = (points / 4).min(5);
+    let taint_count = (points - fault_count * 4) / 2;
+    (taint_count <= 5).then_some((taint_count, fault_count))
+}
+
+/// Mirrors `parse_sheet_date`
+fn parse_sheet_date(value: &str) -> Option<NaiveDate> {
+    let date = value.trim().split([' ', 'T']).next()?;
+    let parts: Vec<u32> = date
+        .split(['-', '/', '.'])
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, month, day) = match parts[..] {
+        [year, month, day] if year > 31 => (year as i32, month, day),
+        [day, month, year] if year > 31 => (year as i32, month, day),
+        _ => return None,
+    };
+    // Converted before building the date, as 29 February of a B.E. leap
+    // year is not a valid Gregorian date in the B.E. year number
+    let year = if year > 2400 { year - BUDDHIST_ERA_OFFSET } else { year };
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Mirrors `parse_sheet_score`
+fn parse_sheet_score(value: &str) -> Option<Decimal> {
+    value.trim().replace(',', ".").parse::<Decimal>().ok()
+}
+
+/// Mirrors the delimiter detection in `parse_score_sheet`
+fn delimiter(header_line: &str) -> u8 {
+    if header_line.matches(';').count() > header_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_headers_map_to_fields() {
+        assert_eq!(header_field(SheetFormat::Sca, "Fragrance/Aroma"), Some("fragrance_aroma"));
+        assert_eq!(header_field(SheetFormat::Sca, "Flavour"), Some("flavor"));
+        assert_eq!(header_field(SheetFormat::Sca, "Cupper's Points"), Some("overall"));
+        assert_eq!(header_field(SheetFormat::Sca, "Q-Grader"), Some("cupper"));
+        assert_eq!(header_field(SheetFormat::Sca, "Sample ID"), Some("lot"));
+        assert_eq!(header_field(SheetFormat::Sca, "\u{feff}Date"), Some("session_date"));
+        assert_eq!(header_field(SheetFormat::Sca, "Clean Cups"), Some("clean_cup_cups"));
+        assert_eq!(header_field(SheetFormat::Sca, "Final Score"), Some("total_score"));
+    }
+
+    #[test]
+    fn test_cropster_export_headers() {
+        let headers = ["Session", "Session date", "Evaluator", "Sample ID", "Sample name", "Defects", "Final score"];
+        let format = detect_format(headers);
+        assert_eq!(format, SheetFormat::Cropster);
+        assert_eq!(header_field(format, "Evaluator"), Some("cupper"));
+        assert_eq!(header_field(format, "Session"), Some("session"));
+        // Cropster's own sample number is not the lot
+        assert_eq!(header_field(format, "Sample ID"), None);
+        assert_eq!(header_field(format, "Sample name"), Some("lot"));
+        assert_eq!(header_field(format, "Defects"), Some("defect_points"));
+    }
+
+    #[test]
+    fn test_tastify_export_headers() {
+        let headers = ["Cupping Name", "Cupping Date", "Cupper", "Sample Name", "Defects", "Total Score"];
+        let format = detect_format(headers);
+        assert_eq!(format, SheetFormat::Tastify);
+        assert_eq!(header_field(format, "Cupping Name"), Some("session"));
+        assert_eq!(header_field(format, "Sample Name"), Some("lot"));
+        assert_eq!(header_field(format, "Defects"), Some("defect_points"));
+    }
+
+    #[test]
+    fn test_plain_sheets_stay_sca() {
+        assert_eq!(detect_format(["Date", "Cupper", "Sample ID", "Flavor"]), SheetFormat::Sca);
+        assert_eq!(header_field(SheetFormat::Sca, "Defects"), None);
+    }
+
+    #[test]
+    fn test_unknown_headers_are_ignored() {
+        assert_eq!(header_field(SheetFormat::Sca, "Roast Color"), None);
+        assert_eq!(header_field(SheetFormat::Sca, ""), None);
+    }
+
+    #[test]
+    fn test_dates_in_sheet_formats() {
+        assert_eq!(parse_sheet_date("2019-03-14"), Some(date("2019-03-14")));
+        assert_eq!(parse_sheet_date("14/03/2019"), Some(date("2019-03-14")));
+        assert_eq!(parse_sheet_date("14.03.2019"), Some(date("2019-03-14")));
+    }
+
+    #[test]
+    fn test_dates_with_times() {
+        assert_eq!(parse_sheet_date("2019-03-14 09:30"), Some(date("2019-03-14")));
+        assert_eq!(parse_sheet_date("2019-03-14T09:30:00Z"), Some(date("2019-03-14")));
+        assert_eq!(parse_sheet_date("14/03/2019 9:30 AM"), Some(date("2019-03-14")));
+    }
+
+    #[test]
+    fn test_buddhist_era_dates() {
+        assert_eq!(parse_sheet_date("14/03/2562"), Some(date("2019-03-14")));
+        assert_eq!(parse_sheet_date("2562-03-14"), Some(date("2019-03-14")));
+    }
+
+    #[test]
+    fn test_unreadable_dates() {
+        assert_eq!(parse_sheet_date("March 14"), None);
+        assert_eq!(parse_sheet_date("31/02/2019"), None);
+    }
+
+    #[test]
+    fn test_scores_with_decimal_comma() {
+        assert_eq!(parse_sheet_score("7,75"), Some(dec("7.75")));
+        assert_eq!(parse_sheet_score(" 8.25 "), Some(dec("8.25")));
+        assert_eq!(parse_sheet_score("n/a"), None);
+    }
+
+    #[test]
+    fn test_cup_counts_become_points() {
+        let cups = parse_sheet_score("4").unwrap();
+        assert_eq!(cups * Decimal::TWO, dec("8"));
+    }
+
+    #[test]
+    fn test_defect_points_split_into_faults_and_taints() {
+        assert_eq!(defects_from_points(dec("0")), Some((0, 0)));
+        assert_eq!(defects_from_points(dec("2")), Some((1, 0)));
+        assert_eq!(defects_from_points(dec("4")), Some((0, 1)));
+        assert_eq!(defects_from_points(dec("6.0")), Some((1, 1)));
+        assert_eq!(defects_from_points(dec("30")), Some((5, 5)));
+    }
+
+    #[test]
+    fn test_unsplittable_defect_points() {
+        assert_eq!(defects_from_points(dec("3")), None);
+        assert_eq!(defects_from_points(dec("2.5")), None);
+        assert_eq!(defects_from_points(dec("-2")), None);
+        assert_eq!(defects_from_points(dec("32")), None);
+    }
+
+    #[test]
+    fn test_semicolon_exports() {
+        assert_eq!(delimiter("Date;Cupper;Lot;Flavor"), b';');
+        assert_eq!(delimiter("Date,Cupper,Lot,Flavor"), b',');
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_buddhist_and_gregorian_dates_agree(days in 0i64..20000) {
+        let gregorian = date("1990-01-01") + chrono::Duration::days(days);
+        let be = gregorian.format("%d/%m/").to_string()
+            + &(chrono::Datelike::year(&gregorian) + BUDDHIST_ERA_OFFSET).to_string();
+
+        prop_assert_eq!(parse_sheet_date(&be), Some(gregorian));
+        prop_assert_eq!(parse_sheet_date(&gregorian.to_string()), Some(gregorian));
+    }
+
+    #[test]
+    fn prop_decimal_comma_matches_point(whole in 6u32..=10, quarter in 0u32..4) {
+        let point = format!("{}.{:02}", whole, quarter * 25);
+        prop_assert_eq!(parse_sheet_score(&point.replace('.', ",")), parse_sheet_score(&point));
+    }
+
+    #[test]
+    fn prop_defect_points_keep_deduction(points in 0i32..=30) {
+        let split = defects_from_points(Decimal::from(points));
+        if let Some((taints, faults)) = split {
+            prop_assert_eq!(taints * 2 + faults * 4, points);
+            prop_assert!(taints <= 5 && faults <= 5);
+        } else {
+            prop_assert!(points % 2 != 0);
+        }
+    }
+}
@@ -3,6 +3,20 @@
 //! Tests for SCA cupping protocol implementation including:
 //! - Property 10: Cupping Score Calculation
 //! - Property 11: Cupping Score Range Validity
+//! - Duplicate sample detection within a session
+//! - Cupper bias and normalized scores
+//! - Flight randomization and table layout
+//! - Panel consensus, attribute statistics and outlier scores
+//! - Blind sample codes and hiding lots until a session is revealed
+//! - Flavor wheel descriptors on samples
+//! - Cupper calibration against the rest of the panel
+//! - Radar chart points on the session score sheet report
+//! - Edit history of corrected and deleted samples
+//! - Triangle test serving orders and binomial significance
+//! - Attribute analytics grouped by lot, plot, variety and process
+//! - Brew measurement ranges (TDS, extraction, water activity, roast date)
+//! - Session sign-off: finalized sessions are locked and signed by a panel cupper
+//! - Roast sessions named by samples: same business and lot, hidden while blind
 
 use proptest::prelude::*;
 use rust_decimal::Decimal;
@@ -17,20 +31,8 @@ fn dec(s: &str) -> Decimal {
 // Note: In actual integration tests, these would be imported from the crate
 // For unit tests, we replicate the core logic here
 
-/// SCA Cupping Protocol Scores
-#[derive(Debug, Clone)]
-struct CuppingScores {
-    fragrance_aroma: Decimal,
-    flavor: Decimal,
-    aftertaste: Decimal,
-    acidity: Decimal,
-    body: Decimal,
-    balance: Decimal,
-    uniformity: Decimal,
-    clean_cup: Decimal,
-    sweetness: Decimal,
-    overall: Decimal,
-}
+/// SCA Cupping Protocol Scores (shared with the cupping service and WASM client)
+use shared::CuppingScores;
 
 /// Cupping defects
 #[derive(Debug, Clone, Default)]
@@ -56,16 +58,7 @@ enum CoffeeClassification {
 
 /// Calculate total cupping score from individual scores
 fn calculate_total_score(scores: &CuppingScores) -> Decimal {
-    scores.fragrance_aroma
-        + scores.flavor
-        + scores.aftertaste
-        + scores.acidity
-        + scores.body
-        + scores.balance
-        + scores.uniformity
-        + scores.clean_cup
-        + scores.sweetness
-        + scores.overall
+    scores.total()
 }
 
 /// Classify coffee based on final cupping score
@@ -108,6 +101,366 @@ fn validate_scores(scores: &CuppingScores) -> Result<(), String> {
     Ok(())
 }
 
+/// Mirrors `DuplicateLotPolicy` in the cupping service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuplicateLotPolicy {
+    Allow,
+    Warn,
+    Block,
+}
+
+/// Mirrors `DuplicateSample` in the cupping service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuplicateSample {
+    LotBlocked(i32),
+    LotRepeated(i32),
+    IdenticalScores(i32),
+}
+
+/// Mirrors `check_duplicate_sample`; existing samples are
+/// (sample number, lot, scores)
+fn check_duplicate_sample(
+    lot_policy: DuplicateLotPolicy,
+    flag_identical_scores: bool,
+    lot: u32,
+    scores: &CuppingScores,
+    existing: &[(i32, u32, CuppingScores)],
+    confirmed: bool,
+) -> Option<DuplicateSample> {
+    if let Some((number, _, _)) = existing.iter().find(|(_, l, _)| *l == lot) {
+        match lot_policy {
+            DuplicateLotPolicy::Block => return Some(DuplicateSample::LotBlocked(*number)),
+            DuplicateLotPolicy::Warn if !confirmed => return Some(DuplicateSample::LotRepeated(*number)),
+            _ => {}
+        }
+    }
+
+    if flag_identical_scores && !confirmed {
+        if let Some((number, _, _)) = existing.iter().find(|(_, _, s)| s == scores) {
+            return Some(DuplicateSample::IdenticalScores(*number));
+        }
+    }
+
+    None
+}
+
+/// Mirrors `cupper_biases` in the cupping analytics service; samples are
+/// (cupper, lot, final score) and the result is (cupper, shared lots, bias)
+fn cupper_biases(
+    samples: &[(&str, u32, Decimal)],
+    min_shared_lots: usize,
+) -> Vec<(String, usize, Option<Decimal>)> {
+    use std::collections::BTreeMap;
+
+    fn mean(values: &[Decimal]) -> Decimal {
+        if values.is_empty() {
+            return Decimal::ZERO;
+        }
+        values.iter().sum::<Decimal>() / Decimal::from(values.len())
+    }
+
+    let mut scores: BTreeMap<String, BTreeMap<u32, Vec<Decimal>>> = BTreeMap::new();
+    for (cupper, lot, score) in samples {
+        let key = cupper.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        scores.entry(key).or_default().entry(*lot).or_default().push(*score);
+    }
+
+    let mut lot_means: BTreeMap<u32, Vec<(&str, Decimal)>> = BTreeMap::new();
+    for (cupper, lots) in &scores {
+        for (lot, lot_scores) in lots {
+            lot_means.entry(*lot).or_default().push((cupper, mean(lot_scores)));
+        }
+    }
+
+    scores
+        .iter()
+        .map(|(cupper, lots)| {
+            let deviations: Vec<Decimal> = lots
+                .keys()
+                .filter_map(|lot| {
+                    let panel = &lot_means[lot];
+                    let own = panel.iter().find(|(c, _)| c == cupper)?.1;
+                    let others: Vec<Decimal> = panel
+                        .iter()
+                        .filter(|(c, _)| c != cupper)
+                        .map(|(_, m)| *m)
+                        .collect();
+                    (!others.is_empty()).then(|| own - mean(&others))
+                })
+                .collect();
+            let bias = (!deviations.is_empty() && deviations.len() >= min_shared_lots)
+                .then(|| mean(&deviations).round_dp(2));
+            (cupper.clone(), deviations.len(), bias)
+        })
+        .collect()
+}
+
+/// Mirrors `normalized_score`
+fn normalized_score(final_score: Decimal, bias: Decimal) -> Decimal {
+    (final_score - bias).round_dp(2).clamp(Decimal::ZERO, Decimal::ONE_HUNDRED)
+}
+
+/// Mirrors `sample_normalized_score`
+fn sample_normalized_score(normalized: &[Option<Decimal>]) -> Option<Decimal> {
+    let scores: Option<Vec<Decimal>> = normalized.iter().copied().collect();
+    scores
+        .filter(|s| !s.is_empty())
+        .map(|s| (s.iter().sum::<Decimal>() / Decimal::from(s.len())).round_dp(2))
+}
+
+/// Mirrors `ATTRIBUTE_OUTLIER_POINTS`
+const ATTRIBUTE_OUTLIER_POINTS: Decimal = Decimal::ONE;
+
+/// Mirrors `MIN_OUTLIER_PANEL`
+const MIN_OUTLIER_PANEL: usize = 3;
+
+/// Mirrors `median` in the cupping panel service
+fn median(values: &[Decimal]) -> Decimal {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    match sorted.len() {
+        0 => Decimal::ZERO,
+        len if len % 2 == 1 => sorted[len / 2],
+        len => (sorted[len / 2 - 1] + sorted[len / 2]) / Decimal::TWO,
+    }
+}
+
+/// Mirrors `std_dev`
+fn std_dev(values: &[Decimal]) -> Option<Decimal> {
+    use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+    if values.len() < 2 {
+        return None;
+    }
+    let average = values.iter().sum::<Decimal>() / Decimal::from(values.len());
+    let squares: Decimal = values.iter().map(|v| (v - average) * (v - average)).sum();
+    let variance = squares / Decimal::from(values.len() - 1);
+    variance.to_f64().map(f64::sqrt).and_then(Decimal::from_f64)
+}
+
+/// Mirrors `outlier_deviations`
+fn outlier_deviations(values: &[Decimal], threshold: Decimal) -> Vec<(usize, Decimal)> {
+    if values.len() < MIN_OUTLIER_PANEL {
+        return Vec::new();
+    }
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            let others: Vec<Decimal> = values
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, v)| *v)
+                .collect();
+            let deviation = value - median(&others);
+            (deviation.abs() > threshold).then_some((index, deviation))
+        })
+        .collect()
+}
+
+/// Mirrors `consensus` for one attribute and one defect count: the mean
+/// score and the lower median count
+fn consensus_attribute(scores: &[Decimal], counts: &[i32]) -> (Decimal, i32) {
+    let mean = scores.iter().sum::<Decimal>() / Decimal::from(scores.len());
+    let mut counts = counts.to_vec();
+    counts.sort_unstable();
+    (mean.round_dp(2), counts[(counts.len() - 1) / 2])
+}
+
+/// Mirrors the SplitMix64 generator in the cupping flight service
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Mirrors `plan_flight`; samples are sample numbers and the result is
+/// (table, position, blind code, sample number, bowl labels) in table order
+fn plan_flight(
+    samples: &[i32],
+    bowls_per_sample: u32,
+    positions_per_table: u32,
+    seed: u32,
+) -> Vec<(u32, u32, String, i32, Vec<String>)> {
+    let mut rng = SplitMix64(seed as u64);
+
+    let mut order: Vec<i32> = samples.to_vec();
+    for i in (1..order.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        order.swap(i, j);
+    }
+
+    let mut used = std::collections::HashSet::new();
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            let code = loop {
+                let code = 100 + rng.below(900) as u32;
+                if used.insert(code) {
+                    break code.to_string();
+                }
+            };
+            let index = index as u32;
+            let labels = (1..=bowls_per_sample).map(|bowl| format!("{}-{}", code, bowl)).collect();
+            (index / positions_per_table + 1, index % positions_per_table + 1, code, sample, labels)
+        })
+        .collect()
+}
+
+/// Mirrors `conceal_flight`; positions are (blind code, lot id) and the
+/// result keeps the code with the lot, or None while concealed
+fn conceal_flight(positions: &[(String, u32)], concealed: bool) -> Vec<(String, Option<u32>)> {
+    positions
+        .iter()
+        .map(|(code, lot)| (code.clone(), (!concealed).then_some(*lot)))
+        .collect()
+}
+
+/// Mirrors `MAX_BLIND_CODES`
+const MAX_BLIND_CODES: usize = 900;
+
+/// Mirrors `next_blind_code`
+fn next_blind_code(used: &std::collections::HashSet<String>, entropy: u64) -> Option<String> {
+    let start = entropy % MAX_BLIND_CODES as u64;
+    (0..MAX_BLIND_CODES as u64)
+        .map(|offset| (100 + (start + offset) % MAX_BLIND_CODES as u64).to_string())
+        .find(|code| !used.contains(code))
+}
+
+/// Mirrors `CuppingSession::conceal` on (blind, revealed, sample lot ids)
+fn conceal(blind: bool, revealed: bool, lots: &[u32]) -> Vec<Option<u32>> {
+    let concealed = blind && !revealed;
+    lots.iter().map(|lot| (!concealed).then_some(*lot)).collect()
+}
+
+/// Mirrors `MAX_FLAVOR_DESCRIPTORS`
+const MAX_FLAVOR_DESCRIPTORS: usize = 12;
+
+/// Mirrors `validate_flavor_descriptors`, with the error reduced to the field
+fn validate_flavor_descriptors(codes: &[String]) -> Result<Vec<String>, String> {
+    let mut descriptors: Vec<String> = Vec::new();
+    for code in codes {
+        let code = code.trim();
+        if shared::flavor_descriptor(code).is_none() {
+            return Err(code.to_string());
+        }
+        if !descriptors.iter().any(|d| d == code) {
+            descriptors.push(code.to_string());
+        }
+    }
+    if descriptors.len() > MAX_FLAVOR_DESCRIPTORS {
+        return Err("flavor_descriptors".to_string());
+    }
+    Ok(descriptors)
+}
+
+/// Mirrors `sample_deviations` for a single attribute; sheets are
+/// (sample, cupper, score) and the result is the cupper's deviation from
+/// the other cuppers' mean on each sample they share
+fn sample_deviations(cupper: &str, sheets: &[(u32, &str, Decimal)]) -> Vec<(u32, Decimal)> {
+    use std::collections::BTreeMap;
+
+    let key = |name: &str| name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let mut by_sample: BTreeMap<u32, Vec<(String, Decimal)>> = BTreeMap::new();
+    for (sample, name, score) in sheets {
+        by_sample.entry(*sample).or_default().push((key(name), *score));
+    }
+
+    by_sample
+        .into_iter()
+        .filter_map(|(sample, panel)| {
+            let own = panel.iter().find(|(c, _)| *c == key(cupper))?.1;
+            let others: Vec<Decimal> = panel.iter().filter(|(c, _)| *c != key(cupper)).map(|(_, s)| *s).collect();
+            if others.is_empty() {
+                return None;
+            }
+            let mean = others.iter().sum::<Decimal>() / Decimal::from(others.len());
+            Some((sample, own - mean))
+        })
+        .collect()
+}
+
+/// Mirrors `attribute_calibration`; the result is (bias, mean absolute
+/// deviation, outliers)
+fn attribute_calibration(deviations: &[Decimal], threshold: Decimal) -> (Decimal, Decimal, usize) {
+    let mean = |values: &[Decimal]| {
+        if values.is_empty() {
+            Decimal::ZERO
+        } else {
+            values.iter().sum::<Decimal>() / Decimal::from(values.len())
+        }
+    };
+    let absolute: Vec<Decimal> = deviations.iter().map(|d| d.abs()).collect();
+    (
+        mean(deviations).round_dp(2),
+        mean(&absolute).round_dp(2),
+        absolute.iter().filter(|d| **d > threshold).count(),
+    )
+}
+
+/// Mirrors `radar_value`: share of the radar radius a score reaches
+fn radar_value(score: Decimal) -> f32 {
+    use rust_decimal::prelude::ToPrimitive;
+
+    let share = (score - Decimal::from(6)) / Decimal::from(4);
+    share.to_f32().unwrap_or(0.0).clamp(0.0, 1.0)
+}
+
+/// Mirrors `radar_points`
+fn radar_points(values: &[f32], cx: f32, cy: f32, radius: f32) -> Vec<(f32, f32)> {
+    let axes = values.len().max(1) as f32;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let angle = std::f32::consts::TAU * i as f32 / axes - std::f32::consts::FRAC_PI_2;
+            (cx + radius * value * angle.cos(), cy + radius * value * angle.sin())
+        })
+        .collect()
+}
+
+/// Mirrors `sample_changes`: (field, from, to) of the fields an edit
+/// changed; every field when the sample was deleted
+fn sample_changes(
+    before: &[(&'static str, serde_json::Value)],
+    after: Option<&[(&'static str, serde_json::Value)]>,
+) -> Vec<(String, serde_json::Value, serde_json::Value)> {
+    before
+        .iter()
+        .filter_map(|(field, from)| {
+            let to = match after {
+                Some(after) => after
+                    .iter()
+                    .find(|(f, _)| f == field)
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or(serde_json::Value::Null),
+                None => serde_json::Value::Null,
+            };
+            if after.is_some() && *from == to {
+                return None;
+            }
+            Some((field.to_string(), from.clone(), to))
+        })
+        .collect()
+}
+
 // ============================================================================
 // Unit Tests
 // ============================================================================
@@ -573,3 +926,1182 @@ mod property_tests {
         }
     }
 }
+
+// ============================================================================
+// Duplicate Sample Tests
+// ============================================================================
+
+#[cfg(test)]
+mod duplicate_tests {
+    use super::*;
+
+    fn scores(base: &str) -> CuppingScores {
+        CuppingScores {
+            fragrance_aroma: dec(base),
+            flavor: dec(base),
+            aftertaste: dec(base),
+            acidity: dec(base),
+            body: dec(base),
+            balance: dec(base),
+            uniformity: dec("10"),
+            clean_cup: dec("10"),
+            sweetness: dec("10"),
+            overall: dec(base),
+        }
+    }
+
+    #[test]
+    fn test_repeated_lot_by_policy() {
+        let existing = vec![(1, 7, scores("8.0"))];
+        let new = scores("7.5");
+
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Allow, true, 7, &new, &existing, false),
+            None
+        );
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Warn, true, 7, &new, &existing, false),
+            Some(DuplicateSample::LotRepeated(1))
+        );
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Warn, true, 7, &new, &existing, true),
+            None
+        );
+        // Confirmation does not override a block
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Block, true, 7, &new, &existing, true),
+            Some(DuplicateSample::LotBlocked(1))
+        );
+    }
+
+    #[test]
+    fn test_identical_scores_flagged() {
+        let existing = vec![(1, 7, scores("8.0")), (2, 8, scores("7.75"))];
+
+        // Trailing zeros don't hide a copy-paste
+        let copied = scores("7.750");
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Allow, true, 9, &copied, &existing, false),
+            Some(DuplicateSample::IdenticalScores(2))
+        );
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Allow, true, 9, &copied, &existing, true),
+            None
+        );
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Allow, false, 9, &copied, &existing, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_one_attribute_difference_is_not_identical() {
+        let existing = vec![(1, 7, scores("8.0"))];
+        let mut new = scores("8.0");
+        new.flavor = dec("8.25");
+
+        assert_eq!(
+            check_duplicate_sample(DuplicateLotPolicy::Warn, true, 9, &new, &existing, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_empty_session_never_duplicate() {
+        for policy in [DuplicateLotPolicy::Allow, DuplicateLotPolicy::Warn, DuplicateLotPolicy::Block] {
+            assert_eq!(check_duplicate_sample(policy, true, 1, &scores("8.0"), &[], false), None);
+        }
+    }
+}
+
+// ============================================================================
+// Cupper Bias Tests
+// ============================================================================
+
+#[cfg(test)]
+mod bias_tests {
+    use super::*;
+
+    #[test]
+    fn test_two_cuppers_have_opposite_bias() {
+        let samples = vec![
+            ("Somchai", 1, dec("86.0")),
+            ("Anna", 1, dec("84.0")),
+            ("Somchai", 2, dec("83.5")),
+            ("Anna", 2, dec("81.5")),
+            ("Somchai", 3, dec("88.0")),
+            ("Anna", 3, dec("86.0")),
+        ];
+
+        let biases = cupper_biases(&samples, 3);
+        assert_eq!(biases, vec![
+            ("anna".to_string(), 3, Some(dec("-2.00"))),
+            ("somchai".to_string(), 3, Some(dec("2.00"))),
+        ]);
+    }
+
+    #[test]
+    fn test_names_grouped_case_insensitively() {
+        let samples = vec![
+            ("Anna  Lee", 1, dec("84")),
+            ("anna lee", 2, dec("82")),
+            ("Ben", 1, dec("85")),
+            ("Ben", 2, dec("83")),
+        ];
+
+        let biases = cupper_biases(&samples, 2);
+        assert_eq!(biases[0], ("anna lee".to_string(), 2, Some(dec("-1"))));
+    }
+
+    #[test]
+    fn test_no_bias_below_minimum_shared_lots() {
+        let samples = vec![
+            ("Somchai", 1, dec("86")),
+            ("Anna", 1, dec("84")),
+            ("Somchai", 2, dec("83")),
+            // Only Anna cupped lot 3, so it tells nothing about her bias
+            ("Anna", 3, dec("80")),
+        ];
+
+        for (_, shared, bias) in cupper_biases(&samples, 3) {
+            assert_eq!(shared, 1);
+            assert_eq!(bias, None);
+        }
+    }
+
+    #[test]
+    fn test_normalized_score_clamped() {
+        assert_eq!(normalized_score(dec("86.25"), dec("1.5")), dec("84.75"));
+        assert_eq!(normalized_score(dec("99.5"), dec("-1.25")), dec("100"));
+        assert_eq!(normalized_score(dec("0.5"), dec("2")), dec("0"));
+    }
+
+    proptest! {
+        /// A cupper scoring every lot a fixed amount above an unbiased panel
+        /// gets exactly that offset as bias
+        #[test]
+        fn prop_constant_offset_recovered(
+            base in proptest::collection::vec(6000i64..9000, 3..8),
+            offset in -400i64..400,
+        ) {
+            let mut samples = Vec::new();
+            for (lot, score) in base.iter().enumerate() {
+                let score = Decimal::new(*score, 2);
+                samples.push(("Anna", lot as u32, score));
+                samples.push(("Ben", lot as u32, score));
+                samples.push(("Chai", lot as u32, score + Decimal::new(offset, 2)));
+            }
+
+            let biases = cupper_biases(&samples, 3);
+            let chai = biases.iter().find(|(c, _, _)| c == "chai").unwrap();
+            prop_assert_eq!(chai.2, Some(Decimal::new(offset, 2)));
+        }
+    }
+}
+
+// ============================================================================
+// Flight Layout Tests
+// ============================================================================
+
+#[cfg(test)]
+mod flight_tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_layout() {
+        let samples: Vec<i32> = (1..=12).collect();
+        assert_eq!(plan_flight(&samples, 5, 8, 42), plan_flight(&samples, 5, 8, 42));
+        assert_ne!(plan_flight(&samples, 5, 8, 42), plan_flight(&samples, 5, 8, 43));
+    }
+
+    #[test]
+    fn test_tables_fill_in_order() {
+        let samples: Vec<i32> = (1..=10).collect();
+        let layout = plan_flight(&samples, 5, 4, 7);
+
+        let places: Vec<(u32, u32)> = layout.iter().map(|p| (p.0, p.1)).collect();
+        assert_eq!(
+            places,
+            vec![(1, 1), (1, 2), (1, 3), (1, 4), (2, 1), (2, 2), (2, 3), (2, 4), (3, 1), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn test_bowl_labels_per_sca_protocol() {
+        let layout = plan_flight(&[1], 5, 8, 1);
+        let (_, _, code, _, labels) = &layout[0];
+
+        assert_eq!(labels.len(), 5);
+        assert_eq!(labels[0], format!("{}-1", code));
+        assert_eq!(labels[4], format!("{}-5", code));
+    }
+
+    #[test]
+    fn test_unrevealed_blind_layout_carries_no_lot() {
+        let layout = plan_flight(&[1, 2, 3], 5, 8, 9);
+        let positions: Vec<(String, u32)> = layout.iter().map(|p| (p.2.clone(), 100 + p.3 as u32)).collect();
+
+        let blind = conceal_flight(&positions, true);
+        assert!(blind.iter().all(|(_, lot)| lot.is_none()));
+        // Codes stay so the bowls can still be labelled
+        assert_eq!(
+            blind.iter().map(|(code, _)| code).collect::<Vec<_>>(),
+            positions.iter().map(|(code, _)| code).collect::<Vec<_>>()
+        );
+
+        let revealed = conceal_flight(&positions, false);
+        assert!(revealed.iter().zip(&positions).all(|((_, lot), (_, expected))| *lot == Some(*expected)));
+    }
+
+    proptest! {
+        /// Every sample is placed exactly once under a unique 3-digit code
+        #[test]
+        fn prop_layout_is_a_permutation(count in 1i32..60, seed in any::<u32>()) {
+            let samples: Vec<i32> = (1..=count).collect();
+            let layout = plan_flight(&samples, 5, 8, seed);
+
+            let mut placed: Vec<i32> = layout.iter().map(|p| p.3).collect();
+            placed.sort();
+            prop_assert_eq!(placed, samples);
+
+            let codes: std::collections::HashSet<&String> = layout.iter().map(|p| &p.2).collect();
+            prop_assert_eq!(codes.len(), layout.len());
+            for code in codes {
+                prop_assert!((100..=999).contains(&code.parse::<u32>().unwrap()));
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Panel Cupping Tests
+// ============================================================================
+
+#[cfg(test)]
+mod panel_tests {
+    use super::*;
+
+    #[test]
+    fn test_median_of_odd_and_even_panels() {
+        assert_eq!(median(&[dec("8.0"), dec("7.5"), dec("8.25")]), dec("8.0"));
+        assert_eq!(median(&[dec("8.0"), dec("7.5"), dec("8.25"), dec("7.75")]), dec("7.875"));
+        assert_eq!(median(&[dec("7.5")]), dec("7.5"));
+    }
+
+    #[test]
+    fn test_std_dev_needs_two_cuppers() {
+        assert_eq!(std_dev(&[dec("8.0")]), None);
+        assert_eq!(std_dev(&[dec("7.0"), dec("9.0")]).map(|s| s.round_dp(2)), Some(dec("1.41")));
+        assert_eq!(std_dev(&[dec("8.0"), dec("8.0"), dec("8.0")]), Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_score_far_from_other_cuppers_is_outlier() {
+        let acidity = [dec("7.75"), dec("8.0"), dec("6.5"), dec("7.75")];
+        assert_eq!(outlier_deviations(&acidity, ATTRIBUTE_OUTLIER_POINTS), vec![(2, dec("-1.25"))]);
+    }
+
+    #[test]
+    fn test_deviation_of_exactly_the_threshold_is_not_flagged() {
+        let flavor = [dec("8.0"), dec("8.0"), dec("9.0")];
+        assert!(outlier_deviations(&flavor, ATTRIBUTE_OUTLIER_POINTS).is_empty());
+    }
+
+    #[test]
+    fn test_no_outliers_in_panels_of_two() {
+        let body = [dec("6.0"), dec("9.0")];
+        assert!(outlier_deviations(&body, ATTRIBUTE_OUTLIER_POINTS).is_empty());
+    }
+
+    #[test]
+    fn test_consensus_mean_and_median_defects() {
+        let (score, taints) = consensus_attribute(&[dec("7.75"), dec("8.0"), dec("8.25")], &[0, 2, 1]);
+        assert_eq!(score, dec("8.00"));
+        assert_eq!(taints, 1);
+        // Even panels take the lower middle count; means round half to even
+        let (score, faults) = consensus_attribute(&[dec("7.5"), dec("7.75")], &[1, 0]);
+        assert_eq!(score, dec("7.62"));
+        assert_eq!(faults, 0);
+    }
+
+    #[test]
+    fn test_panel_normalized_score_needs_every_cupper_bias() {
+        assert_eq!(sample_normalized_score(&[Some(dec("84.5")), Some(dec("85.0"))]), Some(dec("84.75")));
+        assert_eq!(sample_normalized_score(&[Some(dec("84.5")), None]), None);
+        assert_eq!(sample_normalized_score(&[Some(dec("86.0"))]), Some(dec("86.0")));
+        assert_eq!(sample_normalized_score(&[]), None);
+    }
+
+    proptest! {
+        /// A panel scoring alike has no outliers, whatever its size
+        #[test]
+        fn prop_agreeing_panel_has_no_outliers(score in 600i64..1000, size in 1usize..10) {
+            let values = vec![Decimal::new(score, 2); size];
+            prop_assert!(outlier_deviations(&values, ATTRIBUTE_OUTLIER_POINTS).is_empty());
+            prop_assert_eq!(median(&values), Decimal::new(score, 2));
+        }
+
+        /// One cupper far off an agreeing panel is the only one flagged
+        #[test]
+        fn prop_single_dissenter_is_flagged(
+            score in 700i64..900,
+            offset in 101i64..200,
+            size in 3usize..8,
+            below in any::<bool>(),
+        ) {
+            let mut values = vec![Decimal::new(score, 2); size];
+            let offset = Decimal::new(if below { -offset } else { offset }, 2);
+            values[0] += offset;
+            prop_assert_eq!(outlier_deviations(&values, ATTRIBUTE_OUTLIER_POINTS), vec![(0, offset)]);
+        }
+
+        /// The median lies within the panel's scores
+        #[test]
+        fn prop_median_within_range(scores in proptest::collection::vec(600i64..1000, 1..12)) {
+            let values: Vec<Decimal> = scores.iter().map(|s| Decimal::new(*s, 2)).collect();
+            let m = median(&values);
+            prop_assert!(m >= *values.iter().min().unwrap() && m <= *values.iter().max().unwrap());
+        }
+    }
+}
+
+// ============================================================================
+// Blind Cupping Tests
+// ============================================================================
+
+#[cfg(test)]
+mod blind_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_blind_code_is_three_digits() {
+        let used = HashSet::new();
+        assert_eq!(next_blind_code(&used, 0), Some("100".to_string()));
+        assert_eq!(next_blind_code(&used, 899), Some("999".to_string()));
+        assert_eq!(next_blind_code(&used, 900), Some("100".to_string()));
+    }
+
+    #[test]
+    fn test_taken_code_moves_to_the_next_free_one() {
+        let used: HashSet<String> = ["999", "100"].iter().map(|c| c.to_string()).collect();
+        // Wraps from 999 back to the start of the range
+        assert_eq!(next_blind_code(&used, 899), Some("101".to_string()));
+    }
+
+    #[test]
+    fn test_no_code_left_in_a_full_session() {
+        let used: HashSet<String> = (100..1000).map(|c| c.to_string()).collect();
+        assert_eq!(next_blind_code(&used, 42), None);
+    }
+
+    #[test]
+    fn test_lots_hidden_until_revealed() {
+        assert_eq!(conceal(true, false, &[1, 2]), vec![None, None]);
+        assert_eq!(conceal(true, true, &[1, 2]), vec![Some(1), Some(2)]);
+        assert_eq!(conceal(false, false, &[1]), vec![Some(1)]);
+    }
+
+    proptest! {
+        /// Codes handed out one by one never repeat within a session
+        #[test]
+        fn prop_blind_codes_are_unique(entropies in proptest::collection::vec(any::<u64>(), 1..200)) {
+            let mut used = HashSet::new();
+            for entropy in entropies {
+                let code = next_blind_code(&used, entropy).unwrap();
+                let number: u32 = code.parse().unwrap();
+                prop_assert!((100..1000).contains(&number));
+                prop_assert!(used.insert(code));
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Flavor Descriptor Tests
+// ============================================================================
+
+#[cfg(test)]
+mod descriptor_tests {
+    use super::*;
+
+    fn codes(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_descriptors_keep_order_without_repeats() {
+        let result = validate_flavor_descriptors(&codes(&[
+            "fruity.berry.blueberry",
+            " sweet.brown_sugar.honey",
+            "fruity.berry.blueberry",
+        ]));
+        assert_eq!(result, Ok(codes(&["fruity.berry.blueberry", "sweet.brown_sugar.honey"])));
+    }
+
+    #[test]
+    fn test_categories_and_groups_are_accepted() {
+        assert!(validate_flavor_descriptors(&codes(&["fruity", "nutty_cocoa.cocoa"])).is_ok());
+    }
+
+    #[test]
+    fn test_free_text_is_rejected() {
+        assert_eq!(
+            validate_flavor_descriptors(&codes(&["fruity.berry.blueberry", "blueberry jam"])),
+            Err("blueberry jam".to_string())
+        );
+    }
+
+    #[test]
+    fn test_too_many_descriptors() {
+        let many: Vec<String> = shared::FLAVOR_WHEEL
+            .iter()
+            .take(MAX_FLAVOR_DESCRIPTORS + 1)
+            .map(|d| d.code.to_string())
+            .collect();
+        assert!(validate_flavor_descriptors(&many).is_err());
+        assert!(validate_flavor_descriptors(&many[..MAX_FLAVOR_DESCRIPTORS]).is_ok());
+    }
+}
+
+// ============================================================================
+// Cupper Calibration Tests
+// ============================================================================
+
+#[cfg(test)]
+mod calibration_tests {
+    use super::*;
+
+    #[test]
+    fn test_deviation_leaves_the_cupper_out() {
+        let sheets = [
+            (1, "Somchai", dec("8.5")),
+            (1, "Malee", dec("7.5")),
+            (1, "Anan", dec("8.0")),
+        ];
+        assert_eq!(sample_deviations("Somchai", &sheets), vec![(1, dec("0.75"))]);
+        assert_eq!(sample_deviations("Malee", &sheets), vec![(1, dec("-0.75"))]);
+    }
+
+    #[test]
+    fn test_samples_scored_alone_are_skipped() {
+        let sheets = [(1, "Somchai", dec("8.0")), (2, "Somchai", dec("7.5")), (2, "Malee", dec("7.0"))];
+        assert_eq!(sample_deviations("Somchai", &sheets), vec![(2, dec("0.5"))]);
+        assert!(sample_deviations("Anan", &sheets).is_empty());
+    }
+
+    #[test]
+    fn test_cupper_name_matches_like_the_bias_report() {
+        let sheets = [(1, "Somchai  Jaidee", dec("8.0")), (1, "Malee", dec("7.0"))];
+        assert_eq!(sample_deviations("somchai jaidee", &sheets), vec![(1, dec("1.0"))]);
+    }
+
+    #[test]
+    fn test_bias_keeps_sign_and_absolute_deviation_does_not() {
+        // Half a point high then half a point low cancels out as bias but
+        // is still half a point off on average
+        let (bias, mad, outliers) = attribute_calibration(&[dec("0.5"), dec("-0.5")], ATTRIBUTE_OUTLIER_POINTS);
+        assert_eq!(bias, Decimal::ZERO);
+        assert_eq!(mad, dec("0.5"));
+        assert_eq!(outliers, 0);
+    }
+
+    #[test]
+    fn test_outliers_beyond_the_panel_threshold() {
+        let deviations = [dec("1.25"), dec("1.0"), dec("-1.5"), dec("0.25")];
+        let (bias, mad, outliers) = attribute_calibration(&deviations, ATTRIBUTE_OUTLIER_POINTS);
+        assert_eq!(bias, dec("0.25"));
+        assert_eq!(mad, dec("1.0"));
+        assert_eq!(outliers, 2);
+    }
+
+    #[test]
+    fn test_no_shared_samples() {
+        assert_eq!(attribute_calibration(&[], ATTRIBUTE_OUTLIER_POINTS), (Decimal::ZERO, Decimal::ZERO, 0));
+    }
+
+    proptest! {
+        /// Scoring a constant offset above an agreeing panel shows up as
+        /// exactly that bias
+        #[test]
+        fn prop_constant_offset_is_the_bias(
+            score in 600i64..900,
+            offset in -100i64..100,
+            samples in 1u32..10,
+        ) {
+            let base = Decimal::new(score, 2);
+            let offset = Decimal::new(offset, 2);
+            let sheets: Vec<(u32, &str, Decimal)> = (0..samples)
+                .flat_map(|s| [(s, "Somchai", base + offset), (s, "Malee", base), (s, "Anan", base)])
+                .collect();
+            let deviations: Vec<Decimal> = sample_deviations("Somchai", &sheets).into_iter().map(|(_, d)| d).collect();
+            prop_assert_eq!(deviations.len(), samples as usize);
+            let (bias, mad, _) = attribute_calibration(&deviations, ATTRIBUTE_OUTLIER_POINTS);
+            prop_assert_eq!(bias, offset);
+            prop_assert_eq!(mad, offset.abs());
+        }
+
+        /// Bias is never further from zero than the mean absolute deviation
+        #[test]
+        fn prop_bias_within_absolute_deviation(values in proptest::collection::vec(-300i64..300, 1..20)) {
+            let deviations: Vec<Decimal> = values.iter().map(|v| Decimal::new(*v, 2)).collect();
+            let (bias, mad, outliers) = attribute_calibration(&deviations, ATTRIBUTE_OUTLIER_POINTS);
+            prop_assert!(bias.abs() <= mad);
+            prop_assert!(outliers <= deviations.len());
+        }
+    }
+}
+
+// ============================================================================
+// Session Report Tests
+// ============================================================================
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_radar_value_spans_six_to_ten() {
+        assert_eq!(radar_value(dec("6.00")), 0.0);
+        assert_eq!(radar_value(dec("8.00")), 0.5);
+        assert_eq!(radar_value(dec("10.00")), 1.0);
+        assert_eq!(radar_value(dec("5.50")), 0.0);
+    }
+
+    #[test]
+    fn test_first_axis_points_up_and_turns_clockwise() {
+        let points = radar_points(&[1.0, 1.0, 1.0, 1.0], 50.0, 50.0, 10.0);
+        assert!(close(points[0], (50.0, 40.0)));
+        assert!(close(points[1], (60.0, 50.0)));
+        assert!(close(points[2], (50.0, 60.0)));
+        assert!(close(points[3], (40.0, 50.0)));
+    }
+
+    #[test]
+    fn test_lowest_scores_meet_at_the_centre() {
+        let points = radar_points(&[radar_value(dec("6.00")); 10], 30.0, 70.0, 20.0);
+        assert!(points.iter().all(|p| close(*p, (30.0, 70.0))));
+    }
+
+    proptest! {
+        /// Every point stays inside the chart's circle
+        #[test]
+        fn prop_points_within_radius(scores in proptest::collection::vec(500i64..1100, 10)) {
+            let values: Vec<f32> = scores.iter().map(|s| radar_value(Decimal::new(*s, 2))).collect();
+            for (x, y) in radar_points(&values, 100.0, 100.0, 20.0) {
+                prop_assert!(((x - 100.0).powi(2) + (y - 100.0).powi(2)).sqrt() <= 20.0 + 1e-3);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Sample Edit History Tests
+// ============================================================================
+
+#[cfg(test)]
+mod sample_edit_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(flavor: &str, notes: Option<&str>) -> Vec<(&'static str, serde_json::Value)> {
+        vec![
+            ("fragrance_aroma", json!(dec("8.00"))),
+            ("flavor", json!(dec(flavor))),
+            ("tasting_notes", json!(notes)),
+        ]
+    }
+
+    #[test]
+    fn test_only_changed_fields_are_recorded() {
+        let changes = sample_changes(&values("7.50", None), Some(&values("8.25", None)));
+        assert_eq!(changes, vec![("flavor".to_string(), json!(dec("7.50")), json!(dec("8.25")))]);
+    }
+
+    #[test]
+    fn test_cleared_notes_are_a_change() {
+        let changes = sample_changes(&values("7.50", Some("jasmine")), Some(&values("7.50", None)));
+        assert_eq!(changes, vec![("tasting_notes".to_string(), json!("jasmine"), serde_json::Value::Null)]);
+    }
+
+    #[test]
+    fn test_delete_records_every_field() {
+        let changes = sample_changes(&values("7.50", None), None);
+        assert_eq!(changes.len(), 3);
+        assert!(changes.iter().all(|(_, _, to)| to.is_null()));
+    }
+
+    #[test]
+    fn test_corrected_scores_recalculate_the_final_score() {
+        let mut scores = CuppingScores {
+            fragrance_aroma: dec("8.0"),
+            flavor: dec("7.25"),
+            aftertaste: dec("7.75"),
+            acidity: dec("8.0"),
+            body: dec("7.5"),
+            balance: dec("8.0"),
+            uniformity: dec("10.0"),
+            clean_cup: dec("10.0"),
+            sweetness: dec("10.0"),
+            overall: dec("8.0"),
+        };
+        // One taint (2 points) either way
+        assert_eq!(calculate_total_score(&scores) - dec("2"), dec("82.5"));
+        scores.flavor = dec("8.25");
+        assert_eq!(calculate_total_score(&scores) - dec("2"), dec("83.5"));
+    }
+
+    proptest! {
+        /// An edit that changes nothing leaves no history
+        #[test]
+        fn prop_unchanged_sample_has_no_changes(flavor in 600i64..1000, notes in proptest::option::of("[a-z]{1,12}")) {
+            let flavor = Decimal::new(flavor, 2).to_string();
+            let sample = values(&flavor, notes.as_deref());
+            prop_assert!(sample_changes(&sample, Some(&sample)).is_empty());
+        }
+    }
+}
+
+// ============================================================================
+// Triangle Test Tests
+// ============================================================================
+
+/// Mirrors `ARRANGEMENTS` in `cupping_triangle`
+const TRIANGLE_ARRANGEMENTS: [(i32, bool); 6] = [(3, true), (2, true), (1, true), (3, false), (2, false), (1, false)];
+
+/// Mirrors `triangle_arrangement`, returning (odd position, odd is test)
+fn triangle_arrangement(set_number: i32) -> (i32, bool) {
+    TRIANGLE_ARRANGEMENTS[(set_number - 1).rem_euclid(TRIANGLE_ARRANGEMENTS.len() as i32) as usize]
+}
+
+/// Mirrors `binomial_p_value`
+fn binomial_p_value(correct: i64, total: i64) -> f64 {
+    if total <= 0 || correct <= 0 {
+        return 1.0;
+    }
+    if correct > total {
+        return 0.0;
+    }
+    let guess: f64 = 1.0 / 3.0;
+    let (ln_p, ln_q) = (guess.ln(), (1.0 - guess).ln());
+    let mut ln_choose = 0.0;
+    let mut p_value = 0.0;
+    for k in 0..=total {
+        if k >= correct {
+            p_value += (ln_choose + k as f64 * ln_p + (total - k) as f64 * ln_q).exp();
+        }
+        ln_choose += ((total - k) as f64).ln() - ((k + 1) as f64).ln();
+    }
+    p_value.min(1.0)
+}
+
+/// Mirrors `min_correct_for_significance`
+fn min_correct_for_significance(total: i64, alpha: f64) -> Option<i64> {
+    (1..=total).find(|&correct| binomial_p_value(correct, total) <= alpha)
+}
+
+/// Mirrors `discriminator_proportion`
+fn discriminator_proportion(correct: i64, total: i64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+    (1.5 * correct as f64 / total as f64 - 0.5).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod triangle_tests {
+    use super::*;
+
+    #[test]
+    fn test_six_sets_balance_the_serving_orders() {
+        let orders: Vec<(i32, bool)> = (1..=6).map(triangle_arrangement).collect();
+        for position in 1..=3 {
+            assert_eq!(orders.iter().filter(|(p, _)| *p == position).count(), 2);
+        }
+        assert_eq!(orders.iter().filter(|(_, test)| *test).count(), 3);
+        assert_eq!(triangle_arrangement(7), triangle_arrangement(1));
+    }
+
+    #[test]
+    fn test_p_value_of_all_answers_right() {
+        // (1/3)^3
+        assert!((binomial_p_value(3, 3) - 1.0 / 27.0).abs() < 1e-12);
+        assert_eq!(binomial_p_value(0, 10), 1.0);
+    }
+
+    #[test]
+    fn test_minimum_correct_matches_iso_4120() {
+        // ISO 4120 table, alpha 0.05
+        assert_eq!(min_correct_for_significance(6, 0.05), Some(5));
+        assert_eq!(min_correct_for_significance(12, 0.05), Some(8));
+        assert_eq!(min_correct_for_significance(24, 0.05), Some(13));
+        assert_eq!(min_correct_for_significance(36, 0.05), Some(18));
+        // alpha 0.01
+        assert_eq!(min_correct_for_significance(12, 0.01), Some(9));
+    }
+
+    #[test]
+    fn test_too_few_answers_cannot_be_significant() {
+        assert_eq!(min_correct_for_significance(2, 0.05), None);
+    }
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(discriminator_proportion(4, 12), 0.0);
+        assert_eq!(discriminator_proportion(12, 12), 1.0);
+        assert!((discriminator_proportion(8, 12) - 0.5).abs() < 1e-12);
+        assert_eq!(discriminator_proportion(1, 12), 0.0);
+    }
+
+    proptest! {
+        /// More correct answers are never less significant
+        #[test]
+        fn prop_p_value_falls_with_correct_answers(total in 1i64..80, correct in 0i64..80) {
+            let correct = correct.min(total);
+            let p = binomial_p_value(correct, total);
+            prop_assert!((0.0..=1.0).contains(&p));
+            if correct < total {
+                prop_assert!(binomial_p_value(correct + 1, total) <= p);
+            }
+        }
+
+        /// The significance threshold is the first count at or below alpha
+        #[test]
+        fn prop_threshold_is_first_significant_count(total in 3i64..80) {
+            if let Some(min) = min_correct_for_significance(total, 0.05) {
+                prop_assert!(binomial_p_value(min, total) <= 0.05);
+                prop_assert!(binomial_p_value(min - 1, total) > 0.05);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Attribute Analytics Tests
+// ============================================================================
+
+/// Mirrors `AttributeGroupBy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum AttributeGroupBy {
+    Lot,
+    Plot,
+    Variety,
+    Process,
+}
+
+/// Mirrors `parse_group_by`, returning None for an unknown dimension
+fn parse_group_by(group_by: Option<&str>) -> Option<Vec<AttributeGroupBy>> {
+    let mut dimensions = std::collections::BTreeSet::new();
+    for part in group_by.unwrap_or_default().split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        dimensions.insert(match part.to_lowercase().as_str() {
+            "lot" => AttributeGroupBy::Lot,
+            "plot" => AttributeGroupBy::Plot,
+            "variety" => AttributeGroupBy::Variety,
+            "process" => AttributeGroupBy::Process,
+            _ => return None,
+        });
+    }
+    if dimensions.is_empty() {
+        dimensions.insert(AttributeGroupBy::Lot);
+    }
+    Some(dimensions.into_iter().collect())
+}
+
+/// Sample reduced to what grouping needs: lot, plots, varieties, process
+struct AnalyticsSample {
+    lot: u32,
+    plots: Vec<u32>,
+    varieties: Vec<&'static str>,
+    process: Option<&'static str>,
+    acidity: Decimal,
+}
+
+type AnalyticsKey = (Option<u32>, Option<u32>, Option<&'static str>, Option<&'static str>);
+
+/// Mirrors `group_keys`
+fn group_keys(sample: &AnalyticsSample, group_by: &[AttributeGroupBy]) -> Vec<AnalyticsKey> {
+    let by = |dimension| group_by.contains(&dimension);
+    let lot = by(AttributeGroupBy::Lot).then_some(sample.lot);
+    let process = if by(AttributeGroupBy::Process) { sample.process } else { None };
+    let plots: Vec<Option<u32>> = if by(AttributeGroupBy::Plot) && !sample.plots.is_empty() {
+        sample.plots.iter().copied().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    let varieties: Vec<Option<&'static str>> = if by(AttributeGroupBy::Variety) && !sample.varieties.is_empty() {
+        sample.varieties.iter().copied().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    plots
+        .iter()
+        .flat_map(|plot| varieties.iter().map(move |variety| (lot, *plot, *variety, process)))
+        .collect()
+}
+
+/// Mean acidity per group, as `attribute_groups` computes each attribute
+fn mean_acidity(samples: &[AnalyticsSample], group_by: &[AttributeGroupBy]) -> std::collections::BTreeMap<AnalyticsKey, Decimal> {
+    let mut groups: std::collections::BTreeMap<AnalyticsKey, Vec<Decimal>> = std::collections::BTreeMap::new();
+    for sample in samples {
+        for key in group_keys(sample, group_by) {
+            groups.entry(key).or_default().push(sample.acidity);
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(key, values)| (key, (values.iter().sum::<Decimal>() / Decimal::from(values.len())).round_dp(2)))
+        .collect()
+}
+
+#[cfg(test)]
+mod attribute_analytics_tests {
+    use super::*;
+
+    fn samples() -> Vec<AnalyticsSample> {
+        vec![
+            AnalyticsSample { lot: 1, plots: vec![10], varieties: vec!["Typica"], process: Some("honey"), acidity: dec("8.0") },
+            AnalyticsSample { lot: 1, plots: vec![10], varieties: vec!["Typica"], process: Some("honey"), acidity: dec("7.5") },
+            AnalyticsSample { lot: 2, plots: vec![11], varieties: vec!["Typica"], process: Some("washed"), acidity: dec("7.0") },
+            AnalyticsSample { lot: 3, plots: vec![10, 11], varieties: vec!["Typica", "Catimor"], process: Some("honey"), acidity: dec("8.5") },
+        ]
+    }
+
+    #[test]
+    fn test_group_by_defaults_to_lot_and_is_ordered() {
+        assert_eq!(parse_group_by(None), Some(vec![AttributeGroupBy::Lot]));
+        assert_eq!(
+            parse_group_by(Some("process, Variety,process")),
+            Some(vec![AttributeGroupBy::Variety, AttributeGroupBy::Process])
+        );
+        assert_eq!(parse_group_by(Some("cupper")), None);
+    }
+
+    #[test]
+    fn test_average_acidity_of_honey_typica() {
+        let group_by = parse_group_by(Some("variety,process")).unwrap();
+        let means = mean_acidity(&samples(), &group_by);
+        assert_eq!(means[&(None, None, Some("Typica"), Some("honey"))], dec("8.0"));
+        assert_eq!(means[&(None, None, Some("Typica"), Some("washed"))], dec("7.0"));
+        assert_eq!(means[&(None, None, Some("Catimor"), Some("honey"))], dec("8.5"));
+    }
+
+    #[test]
+    fn test_blended_lot_counts_towards_each_plot() {
+        let means = mean_acidity(&samples(), &[AttributeGroupBy::Plot]);
+        // Plot 10: 8.0, 7.5 and the blend's 8.5
+        assert_eq!(means[&(None, Some(10), None, None)], dec("8.0"));
+        // Plot 11: 7.0 and the blend's 8.5
+        assert_eq!(means[&(None, Some(11), None, None)], dec("7.75"));
+    }
+
+    #[test]
+    fn test_lot_without_harvests_keeps_one_group() {
+        let sample = AnalyticsSample { lot: 4, plots: vec![], varieties: vec![], process: None, acidity: dec("7.0") };
+        assert_eq!(group_keys(&sample, &[AttributeGroupBy::Plot, AttributeGroupBy::Variety]), vec![(None, None, None, None)]);
+    }
+
+    proptest! {
+        /// A sample lands in one group per plot and variety combination
+        #[test]
+        fn prop_group_count_is_plots_times_varieties(plots in 0usize..4, varieties in 0usize..4) {
+            let sample = AnalyticsSample {
+                lot: 1,
+                plots: (0..plots as u32).collect(),
+                varieties: ["Typica", "Catimor", "Geisha", "SL28"][..varieties].to_vec(),
+                process: None,
+                acidity: dec("7.0"),
+            };
+            let keys = group_keys(&sample, &[AttributeGroupBy::Plot, AttributeGroupBy::Variety]);
+            prop_assert_eq!(keys.len(), plots.max(1) * varieties.max(1));
+        }
+    }
+}
+
+// ============================================================================
+// Brew Measurement Tests
+// ============================================================================
+
+/// Mirrors `BrewMeasurements`
+#[derive(Debug, Clone, Default)]
+struct BrewMeasurements {
+    tds_percent: Option<Decimal>,
+    extraction_percent: Option<Decimal>,
+    water_activity: Option<Decimal>,
+    roast_date: Option<chrono::NaiveDate>,
+}
+
+/// Mirrors `MAX_TDS_PERCENT` and `MAX_EXTRACTION_PERCENT`
+const MAX_TDS_PERCENT: i64 = 25;
+const MAX_EXTRACTION_PERCENT: i64 = 30;
+
+/// Mirrors `validate_measurements`, with the error reduced to the field
+fn validate_measurements(measurements: &BrewMeasurements, session_date: chrono::NaiveDate) -> Result<(), &'static str> {
+    let percentages = [
+        ("tds_percent", measurements.tds_percent, Decimal::from(MAX_TDS_PERCENT)),
+        ("extraction_percent", measurements.extraction_percent, Decimal::from(MAX_EXTRACTION_PERCENT)),
+    ];
+    for (field, value, max) in percentages {
+        if value.is_some_and(|value| value <= Decimal::ZERO || value > max) {
+            return Err(field);
+        }
+    }
+    if measurements.water_activity.is_some_and(|aw| aw < Decimal::ZERO || aw > Decimal::ONE) {
+        return Err("water_activity");
+    }
+    if measurements.roast_date.is_some_and(|date| date > session_date) {
+        return Err("roast_date");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod brew_measurement_tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn session_date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+    }
+
+    #[test]
+    fn test_no_measurements_is_valid() {
+        assert_eq!(validate_measurements(&BrewMeasurements::default(), session_date()), Ok(()));
+    }
+
+    #[test]
+    fn test_cupping_and_espresso_readings_accepted() {
+        let cupping = BrewMeasurements {
+            tds_percent: Some(dec("1.35")),
+            extraction_percent: Some(dec("20.5")),
+            water_activity: Some(dec("0.55")),
+            roast_date: Some(session_date().pred_opt().unwrap()),
+        };
+        assert_eq!(validate_measurements(&cupping, session_date()), Ok(()));
+
+        let espresso = BrewMeasurements {
+            tds_percent: Some(dec("9.8")),
+            ..Default::default()
+        };
+        assert_eq!(validate_measurements(&espresso, session_date()), Ok(()));
+    }
+
+    #[test]
+    fn test_out_of_range_readings_rejected() {
+        let cases = [
+            (BrewMeasurements { tds_percent: Some(Decimal::ZERO), ..Default::default() }, "tds_percent"),
+            (BrewMeasurements { tds_percent: Some(dec("25.1")), ..Default::default() }, "tds_percent"),
+            (BrewMeasurements { extraction_percent: Some(dec("35")), ..Default::default() }, "extraction_percent"),
+            (BrewMeasurements { water_activity: Some(dec("1.2")), ..Default::default() }, "water_activity"),
+            (BrewMeasurements { water_activity: Some(dec("-0.1")), ..Default::default() }, "water_activity"),
+        ];
+        for (measurements, field) in cases {
+            assert_eq!(validate_measurements(&measurements, session_date()), Err(field));
+        }
+    }
+
+    #[test]
+    fn test_roast_date_not_after_session() {
+        let same_day = BrewMeasurements { roast_date: Some(session_date()), ..Default::default() };
+        assert_eq!(validate_measurements(&same_day, session_date()), Ok(()));
+        let later = BrewMeasurements { roast_date: session_date().succ_opt(), ..Default::default() };
+        assert_eq!(validate_measurements(&later, session_date()), Err("roast_date"));
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_water_activity_valid_within_unit_range(thousandths in 0i64..=1000) {
+        let measurements = BrewMeasurements {
+            water_activity: Some(Decimal::new(thousandths, 3)),
+            ..Default::default()
+        };
+        let session_date = chrono::NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        prop_assert_eq!(validate_measurements(&measurements, session_date), Ok(()));
+    }
+}
+
+// ============================================================================
+// Session Sign-off Tests
+// ============================================================================
+
+/// Mirrors `CuppingSessionStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CuppingSessionStatus {
+    Draft,
+    InProgress,
+    Finalized,
+}
+
+/// Mirrors `ensure_session_open`
+fn ensure_session_open(status: CuppingSessionStatus) -> Result<(), &'static str> {
+    if status == CuppingSessionStatus::Finalized {
+        return Err("conflict");
+    }
+    Ok(())
+}
+
+/// Mirrors `check_finalize`
+fn check_finalize(status: CuppingSessionStatus) -> Result<(), &'static str> {
+    ensure_session_open(status)?;
+    if status == CuppingSessionStatus::Draft {
+        return Err("session_id");
+    }
+    Ok(())
+}
+
+/// Mirrors `cupper_key` in the cupping analytics service
+fn cupper_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Mirrors `signing_cupper`
+fn signing_cupper(head_cupper: &str, panel_cuppers: &[String], requested: Option<&str>) -> Option<String> {
+    let Some(requested) = requested.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Some(head_cupper.to_string());
+    };
+    let key = cupper_key(requested);
+    std::iter::once(head_cupper)
+        .chain(panel_cuppers.iter().map(String::as_str))
+        .find(|name| cupper_key(name) == key)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod sign_off_tests {
+    use super::*;
+
+    #[test]
+    fn test_only_sessions_in_progress_can_be_finalized() {
+        assert_eq!(check_finalize(CuppingSessionStatus::InProgress), Ok(()));
+        assert_eq!(check_finalize(CuppingSessionStatus::Draft), Err("session_id"));
+        assert_eq!(check_finalize(CuppingSessionStatus::Finalized), Err("conflict"));
+    }
+
+    #[test]
+    fn test_finalized_session_is_locked() {
+        assert!(ensure_session_open(CuppingSessionStatus::Draft).is_ok());
+        assert!(ensure_session_open(CuppingSessionStatus::InProgress).is_ok());
+        assert!(ensure_session_open(CuppingSessionStatus::Finalized).is_err());
+    }
+
+    #[test]
+    fn test_head_cupper_signs_by_default() {
+        assert_eq!(signing_cupper("Somchai", &[], None), Some("Somchai".to_string()));
+        assert_eq!(signing_cupper("Somchai", &[], Some("  ")), Some("Somchai".to_string()));
+    }
+
+    #[test]
+    fn test_panel_cupper_signs_under_recorded_name() {
+        let panel = vec!["Malee Srisuk".to_string()];
+        assert_eq!(
+            signing_cupper("Somchai", &panel, Some("malee  srisuk")),
+            Some("Malee Srisuk".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cupper_off_the_panel_cannot_sign() {
+        let panel = vec!["Malee".to_string()];
+        assert_eq!(signing_cupper("Somchai", &panel, Some("Niran")), None);
+    }
+}
+
+// ============================================================================
+// Roast Session Link Tests
+// ============================================================================
+
+/// Mirrors `SampleRoast`, with business and lot ids as numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SampleRoast {
+    business_id: u32,
+    lot_id: u32,
+}
+
+/// Mirrors `validate_sample_roast`, with the error as the field it names
+fn validate_sample_roast(
+    roast_session_id: Option<u32>,
+    roast: Option<SampleRoast>,
+    business_id: u32,
+    lot_id: u32,
+) -> Result<(), &'static str> {
+    if roast_session_id.is_none() {
+        return Ok(());
+    }
+    let roast = roast.filter(|r| r.business_id == business_id).ok_or("not_found")?;
+    if roast.lot_id != lot_id {
+        return Err("roast_session_id");
+    }
+    Ok(())
+}
+
+/// Mirrors `CuppingSession::conceal` on (blind, revealed, sample lot and
+/// roast session ids)
+fn conceal_roasts(blind: bool, revealed: bool, samples: &[(u32, Option<u32>)]) -> Vec<(Option<u32>, Option<u32>)> {
+    let concealed = blind && !revealed;
+    samples
+        .iter()
+        .map(|(lot, roast)| if concealed { (None, None) } else { (Some(*lot), *roast) })
+        .collect()
+}
+
+/// Mirrors the filter of `RoastingService::get_session_cuppings` on
+/// (sample id, blind, revealed) of the samples linked to a roast
+fn roast_cuppings(samples: &[(u32, bool, bool)]) -> Vec<u32> {
+    samples
+        .iter()
+        .filter(|(_, blind, revealed)| !blind || *revealed)
+        .map(|(id, _, _)| *id)
+        .collect()
+}
+
+#[cfg(test)]
+mod roast_link_tests {
+    use super::*;
+
+    const BUSINESS: u32 = 1;
+    const LOT: u32 = 10;
+
+    #[test]
+    fn test_roast_of_the_sample_lot_accepted() {
+        let roast = SampleRoast { business_id: BUSINESS, lot_id: LOT };
+        assert_eq!(validate_sample_roast(Some(7), Some(roast), BUSINESS, LOT), Ok(()));
+    }
+
+    #[test]
+    fn test_roast_of_another_lot_rejected() {
+        let roast = SampleRoast { business_id: BUSINESS, lot_id: 11 };
+        assert_eq!(validate_sample_roast(Some(7), Some(roast), BUSINESS, LOT), Err("roast_session_id"));
+    }
+
+    #[test]
+    fn test_roast_of_another_business_not_found() {
+        // Even a roast of the same lot id is hidden from another business
+        let roast = SampleRoast { business_id: 2, lot_id: LOT };
+        assert_eq!(validate_sample_roast(Some(7), Some(roast), BUSINESS, LOT), Err("not_found"));
+        assert_eq!(validate_sample_roast(Some(7), None, BUSINESS, LOT), Err("not_found"));
+    }
+
+    #[test]
+    fn test_sample_without_roast_accepted() {
+        assert_eq!(validate_sample_roast(None, None, BUSINESS, LOT), Ok(()));
+    }
+
+    #[test]
+    fn test_roast_hidden_while_blind() {
+        let samples = [(1, Some(7)), (2, None)];
+        assert_eq!(conceal_roasts(true, false, &samples), vec![(None, None), (None, None)]);
+        assert_eq!(conceal_roasts(true, true, &samples), vec![(Some(1), Some(7)), (Some(2), None)]);
+        assert_eq!(conceal_roasts(false, false, &samples), vec![(Some(1), Some(7)), (Some(2), None)]);
+    }
+
+    #[test]
+    fn test_roast_cuppings_leave_out_unrevealed_blind_samples() {
+        // Open, revealed blind and unrevealed blind sessions cupping one roast
+        let samples = [(1, false, false), (2, true, true), (3, true, false)];
+        assert_eq!(roast_cuppings(&samples), vec![1, 2]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Data quality tests
+//!
+//! Tests for lot record completeness scoring:
+//! - A fully recorded lot scores 100
+//! - Records not yet expected at the lot's stage are not counted as gaps
+//! - Missing records lower the score by their weight
+
+use proptest::prelude::*;
+use shared::LotStage;
+
+/// Mirrors `CompletenessCheck`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    Harvest,
+    WeatherLinked,
+    Photos,
+    Certification,
+    Processing,
+    Grading,
+    Cupping,
+}
+
+const ALL: [Check; 7] = [
+    Check::Harvest,
+    Check::WeatherLinked,
+    Check::Photos,
+    Check::Certification,
+    Check::Processing,
+    Check::Grading,
+    Check::Cupping,
+];
+
+fn weight(check: Check) -> i32 {
+    match check {
+        Check::Harvest => 20,
+        Check::WeatherLinked => 10,
+        Check::Photos => 10,
+        Check::Certification => 10,
+        Check::Processing => 15,
+        Check::Grading => 15,
+        Check::Cupping => 20,
+    }
+}
+
+/// Mirrors `CompletenessCheck::applies_to`
+fn applies_to(check: Check, stage: LotStage) -> bool {
+    match check {
+        Check::Processing => stage >= LotStage::Parchment,
+        Check::Grading | Check::Cupping => stage >= LotStage::GreenBean,
+        _ => true,
+    }
+}
+
+/// Mirrors `score_lot`
+fn score_lot(stage: LotStage, present: &[Check]) -> (i32, Vec<Check>) {
+    let applicable: Vec<Check> = ALL.into_iter().filter(|c| applies_to(*c, stage)).collect();
+    let possible: i32 = applicable.iter().map(|c| weight(*c)).sum();
+    let earned: i32 = applicable.iter().filter(|c| present.contains(c)).map(|c| weight(*c)).sum();
+    let missing = applicable.into_iter().filter(|c| !present.contains(c)).collect();
+    let score = if possible > 0 {
+        (earned * 100 + possible / 2) / possible
+    } else {
+        100
+    };
+    (score, missing)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_total_100() {
+        assert_eq!(ALL.iter().map(|c| weight(*c)).sum::<i32>(), 100);
+    }
+
+    #[test]
+    fn test_complete_lot_scores_100() {
+        let (score, missing) = score_lot(LotStage::GreenBean, &ALL);
+        assert_eq!(score, 100);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_empty_lot_scores_zero() {
+        let (score, missing) = score_lot(LotStage::RoastedBean, &[]);
+        assert_eq!(score, 0);
+        assert_eq!(missing.len(), ALL.len());
+    }
+
+    #[test]
+    fn test_cherry_lot_not_penalised_for_later_stages() {
+        let present = [Check::Harvest, Check::WeatherLinked, Check::Photos, Check::Certification];
+        let (score, missing) = score_lot(LotStage::Cherry, &present);
+        assert_eq!(score, 100);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_parchment_lot_expects_processing() {
+        let (score, missing) = score_lot(LotStage::Parchment, &[Check::Harvest]);
+        assert_eq!(missing, vec![Check::WeatherLinked, Check::Photos, Check::Certification, Check::Processing]);
+        // 20 of 65 possible points
+        assert_eq!(score, 31);
+    }
+
+    #[test]
+    fn test_missing_cupping_costs_its_weight() {
+        let present: Vec<Check> = ALL.into_iter().filter(|c| *c != Check::Cupping).collect();
+        let (score, missing) = score_lot(LotStage::GreenBean, &present);
+        assert_eq!(score, 80);
+        assert_eq!(missing, vec![Check::Cupping]);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_score_within_bounds(mask in 0u8..128, stage in prop::sample::select(LotStage::ALL.to_vec())) {
+        let present: Vec<Check> = ALL.into_iter().enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, c)| c)
+            .collect();
+        let (score, missing) = score_lot(stage, &present);
+
+        prop_assert!((0..=100).contains(&score));
+        prop_assert_eq!(score == 100, missing.is_empty());
+    }
+
+    #[test]
+    fn prop_adding_a_record_never_lowers_score(mask in 0u8..128, extra in 0usize..7) {
+        let present: Vec<Check> = ALL.into_iter().enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, c)| c)
+            .collect();
+        let mut more = present.clone();
+        more.push(ALL[extra]);
+
+        prop_assert!(score_lot(LotStage::GreenBean, &more).0 >= score_lot(LotStage::GreenBean, &present).0);
+    }
+}
@@ -0,0 +1,98 @@
+//! Conditional GET tests
+//!
+//! Tests for ETag handling on heavy read endpoints:
+//! - Tags are quoted, strong and depend only on the content
+//! - If-None-Match matches the tag in lists, weak form and `*`
+//! - Different tags or missing headers never match
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use proptest::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// Mirrors `etag_for`
+fn etag_for(content: &[u8]) -> String {
+    let hex: String = Sha256::digest(content)
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("\"{}\"", hex)
+}
+
+/// Mirrors `if_none_match`
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+fn headers(if_none_match: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(if_none_match).unwrap());
+    headers
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_is_quoted_hex() {
+        let etag = etag_for(b"lots:1:2:3");
+        assert_eq!(etag.len(), 34);
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert!(etag[1..33].chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_fingerprint_change_changes_etag() {
+        assert_ne!(etag_for(b"lots:role:4:100:7"), etag_for(b"lots:role:4:100:8"));
+        assert_eq!(etag_for(b"lots:role:4:100:7"), etag_for(b"lots:role:4:100:7"));
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let etag = etag_for(b"payload");
+        assert!(if_none_match(&headers(&etag), &etag));
+    }
+
+    #[test]
+    fn test_match_in_list_and_weak_form() {
+        let etag = etag_for(b"payload");
+        assert!(if_none_match(&headers(&format!("\"other\", {}", etag)), &etag));
+        assert!(if_none_match(&headers(&format!("W/{}", etag)), &etag));
+    }
+
+    #[test]
+    fn test_wildcard_matches() {
+        assert!(if_none_match(&headers("*"), &etag_for(b"payload")));
+    }
+
+    #[test]
+    fn test_stale_or_missing_tag_does_not_match() {
+        let etag = etag_for(b"payload");
+        assert!(!if_none_match(&headers(&etag_for(b"older payload")), &etag));
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_only_current_tag_matches(a in prop::collection::vec(any::<u8>(), 0..64), b in prop::collection::vec(any::<u8>(), 0..64)) {
+        let current = etag_for(&a);
+        let held = etag_for(&b);
+        prop_assert_eq!(if_none_match(&headers(&held), &current), a == b);
+    }
+}
@@ -0,0 +1,109 @@
+//! Farm activity tests
+//!
+//! Tests for checking inputs against the organic allowed-substances list:
+//! - Product names match regardless of case and spacing
+//! - Thai substance names match as well as English ones
+//! - A substance only counts for its own input type
+//! - Pesticides break OT-01 and fertilizers OT-02
+
+use proptest::prelude::*;
+
+/// Mirrors `OrganicAllowedInput` fields used for matching
+struct Allowed {
+    input_type: &'static str,
+    substance: &'static str,
+    substance_th: Option<&'static str>,
+}
+
+/// Mirrors `normalize_substance`
+fn normalize_substance(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Mirrors `is_allowed_input`
+fn is_allowed_input(input_type: &str, product_name: &str, allowed: &[Allowed]) -> bool {
+    let product = normalize_substance(product_name);
+    allowed.iter().filter(|a| a.input_type == input_type).any(|a| {
+        normalize_substance(a.substance) == product
+            || a.substance_th.is_some_and(|th| normalize_substance(th) == product)
+    })
+}
+
+/// Mirrors `organic_requirement_code`
+fn organic_requirement_code(input_type: &str) -> Option<&'static str> {
+    match input_type {
+        "pesticide" => Some("OT-01"),
+        "fertilizer" => Some("OT-02"),
+        _ => None,
+    }
+}
+
+fn default_list() -> Vec<Allowed> {
+    vec![
+        Allowed { input_type: "fertilizer", substance: "Compost", substance_th: Some("ปุ๋ยหมัก") },
+        Allowed { input_type: "fertilizer", substance: "Rock phosphate", substance_th: Some("หินฟอสเฟต") },
+        Allowed { input_type: "pesticide", substance: "Neem extract", substance_th: Some("สารสกัดสะเดา") },
+    ]
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_product_matches_ignoring_case_and_spacing() {
+        assert!(is_allowed_input("fertilizer", "  rock   PHOSPHATE ", &default_list()));
+    }
+
+    #[test]
+    fn test_thai_name_matches() {
+        assert!(is_allowed_input("pesticide", "สารสกัดสะเดา", &default_list()));
+    }
+
+    #[test]
+    fn test_synthetic_products_not_allowed() {
+        assert!(!is_allowed_input("fertilizer", "Urea 46-0-0", &default_list()));
+        assert!(!is_allowed_input("pesticide", "Glyphosate", &default_list()));
+        // Partial names do not match
+        assert!(!is_allowed_input("fertilizer", "Compost with NPK 15-15-15", &default_list()));
+    }
+
+    #[test]
+    fn test_substance_only_allowed_for_its_type() {
+        assert!(!is_allowed_input("pesticide", "Compost", &default_list()));
+    }
+
+    #[test]
+    fn test_requirement_codes() {
+        assert_eq!(organic_requirement_code("pesticide"), Some("OT-01"));
+        assert_eq!(organic_requirement_code("fertilizer"), Some("OT-02"));
+        assert_eq!(organic_requirement_code("pruning"), None);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_normalize_is_idempotent(name in "[ a-zA-Zก-ฮ0-9-]{0,40}") {
+        let once = normalize_substance(&name);
+        prop_assert_eq!(normalize_substance(&once), once.clone());
+        prop_assert!(!once.starts_with(' ') && !once.ends_with(' ') && !once.contains("  "));
+    }
+
+    #[test]
+    fn prop_listed_substance_allowed_in_any_case(upper in proptest::collection::vec(any::<bool>(), 14)) {
+        let product: String = "Rock phosphate"
+            .chars()
+            .zip(upper)
+            .map(|(c, u)| if u { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() })
+            .collect();
+        prop_assert!(is_allowed_input("fertilizer", &product, &default_list()));
+    }
+}
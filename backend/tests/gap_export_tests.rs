@@ -0,0 +1,199 @@
+//! Thai GAP export tests
+//!
+//! Tests for the submission's missing-information list and PDF layout:
+//! - Complete plots and applicant details leave nothing to follow up
+//! - Each absent plot field is listed once per plot
+//! - Unassessed checklist items and failed critical items are listed
+//! - PDF columns fill the page width in proportion to their text, with
+//!   Thai vowel and tone marks taking no width
+
+use proptest::prelude::*;
+
+/// Mirrors the plot fields checked by `missing_information`
+#[derive(Debug, Clone, Default)]
+struct Plot {
+    name: String,
+    has_coordinates: bool,
+    has_area: bool,
+    has_varieties: bool,
+    has_planting_date: bool,
+    has_tree_count: bool,
+}
+
+/// Mirrors `GapPractice` fields used for follow-up
+#[derive(Debug, Clone)]
+struct Practice {
+    code: &'static str,
+    is_critical: bool,
+    is_compliant: Option<bool>,
+}
+
+/// Mirrors `missing_information` for plots and practices
+fn missing_items(plots: &[Plot], practices: &[Practice]) -> Vec<String> {
+    let mut items = Vec::new();
+    if plots.is_empty() {
+        items.push("No production plots recorded".to_string());
+    }
+    for plot in plots {
+        let checks = [
+            (!plot.has_coordinates, "coordinates"),
+            (!plot.has_area, "area"),
+            (!plot.has_varieties, "varieties"),
+            (!plot.has_planting_date, "planting date"),
+            (!plot.has_tree_count, "tree count"),
+        ];
+        for (_, field) in checks.into_iter().filter(|(absent, _)| *absent) {
+            items.push(format!("Plot {}: {} missing", plot.name, field));
+        }
+    }
+    for practice in practices {
+        match practice.is_compliant {
+            None => items.push(format!("{}: not assessed", practice.code)),
+            Some(false) if practice.is_critical => items.push(format!("{}: critical requirement not met", practice.code)),
+            Some(_) => {}
+        }
+    }
+    items
+}
+
+/// Mirrors `MAX_PDF_COLUMN_CHARS`
+const MAX_PDF_COLUMN_CHARS: usize = 40;
+
+/// Mirrors `xlsx::display_width`
+fn display_width(text: &str) -> usize {
+    text.lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !matches!(*c as u32, 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Mirrors `column_widths`
+fn column_widths(rows: &[Vec<&str>], total: f32) -> Vec<f32> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let chars: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|row| row.get(c))
+                .map(|t| display_width(t).clamp(3, MAX_PDF_COLUMN_CHARS))
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+    let sum: usize = chars.iter().sum();
+    chars.iter().map(|c| total * *c as f32 / sum.max(1) as f32).collect()
+}
+
+fn complete_plot(name: &str) -> Plot {
+    Plot {
+        name: name.to_string(),
+        has_coordinates: true,
+        has_area: true,
+        has_varieties: true,
+        has_planting_date: true,
+        has_tree_count: true,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_records_need_no_follow_up() {
+        let practices = [Practice { code: "TG-01", is_critical: true, is_compliant: Some(true) }];
+        assert!(missing_items(&[complete_plot("A")], &practices).is_empty());
+    }
+
+    #[test]
+    fn test_no_plots_listed() {
+        assert_eq!(missing_items(&[], &[]), vec!["No production plots recorded"]);
+    }
+
+    #[test]
+    fn test_absent_plot_fields_listed() {
+        let plot = Plot {
+            has_area: false,
+            has_tree_count: false,
+            ..complete_plot("Doi 1")
+        };
+        assert_eq!(
+            missing_items(&[plot], &[]),
+            vec!["Plot Doi 1: area missing", "Plot Doi 1: tree count missing"]
+        );
+    }
+
+    #[test]
+    fn test_practices_follow_up() {
+        let practices = [
+            Practice { code: "TG-01", is_critical: true, is_compliant: Some(false) },
+            Practice { code: "TG-03", is_critical: false, is_compliant: Some(false) },
+            Practice { code: "TG-05", is_critical: true, is_compliant: None },
+        ];
+        assert_eq!(
+            missing_items(&[complete_plot("A")], &practices),
+            vec!["TG-01: critical requirement not met", "TG-05: not assessed"]
+        );
+    }
+
+    #[test]
+    fn test_column_widths_follow_text() {
+        let rows = vec![vec!["No.", "Plot name here"], vec!["1", "Doi"]];
+        let widths = column_widths(&rows, 170.0);
+        assert!((widths[0] - 30.0).abs() < 0.01);
+        assert!((widths[1] - 140.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_thai_marks_take_no_column_width() {
+        // ผู้ใหญ่บ้าน is 11 characters, 4 of them vowel and tone marks
+        assert_eq!(display_width("ผู้ใหญ่บ้าน"), 7);
+        assert_eq!(display_width("สวัสดี"), 4);
+        let widths = column_widths(&[vec!["ผู้ใหญ่บ้าน", "abcdefg"]], 140.0);
+        assert!((widths[0] - widths[1]).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_long_text_capped_at_column_limit() {
+        let long = "ก".repeat(60);
+        let widths = column_widths(&[vec![long.as_str(), "ab"]], 43.0);
+        assert!((widths[0] - 40.0).abs() < 0.01);
+        assert!((widths[1] - 3.0).abs() < 0.01);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_one_item_per_absent_plot_field(mask in 0u8..32, plots in 1usize..5) {
+        let plot = |i: usize| Plot {
+            name: format!("P{}", i),
+            has_coordinates: mask & 1 == 0,
+            has_area: mask & 2 == 0,
+            has_varieties: mask & 4 == 0,
+            has_planting_date: mask & 8 == 0,
+            has_tree_count: mask & 16 == 0,
+        };
+        let plots: Vec<Plot> = (0..plots).map(plot).collect();
+        prop_assert_eq!(missing_items(&plots, &[]).len(), plots.len() * mask.count_ones() as usize);
+    }
+
+    #[test]
+    fn prop_column_widths_fill_page(texts in prop::collection::vec("[a-z ก-ฮั่้ิีู]{0,60}", 1..8)) {
+        let rows = vec![texts.iter().map(String::as_str).collect::<Vec<_>>()];
+        let widths = column_widths(&rows, 269.0);
+        prop_assert_eq!(widths.len(), texts.len());
+        prop_assert!((widths.iter().sum::<f32>() - 269.0).abs() < 0.01);
+        prop_assert!(widths.iter().all(|w| *w > 0.0));
+    }
+}
@@ -0,0 +1,181 @@
+//! Grading photo tests
+//!
+//! Tests for bean tray photos attached to gradings:
+//! - Photos accepted as JPEG, PNG or WebP by their leading bytes
+//! - S3 keys grouped by business and grading
+//! - Signature Version 4 signing of S3 requests
+
+use hmac::{Hmac, Mac};
+use proptest::prelude::*;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Mirrors `image_content_type`
+fn image_content_type(image: &[u8]) -> Option<&'static str> {
+    if image.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if image.len() >= 12 && &image[0..4] == b"RIFF" && &image[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Mirrors `extension`
+fn extension(content_type: &str) -> &'static str {
+    match content_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        _ => "jpg",
+    }
+}
+
+/// Mirrors `photo_key`
+fn photo_key(business_id: Uuid, grading_id: Uuid, photo_id: Uuid, content_type: &str) -> String {
+    format!(
+        "gradings/{}/{}/{}.{}",
+        business_id,
+        grading_id,
+        photo_id,
+        extension(content_type)
+    )
+}
+
+/// Mirrors `hmac`
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Mirrors `payload_hash`
+fn payload_hash(payload: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(payload))
+}
+
+/// Mirrors `signing_key`
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    let region_key = hmac(&date_key, region);
+    let service_key = hmac(&region_key, service);
+    hmac(&service_key, "aws4_request")
+}
+
+/// Mirrors `encode_key`
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Mirrors `canonical_request`
+fn canonical_request(method: &str, encoded_key: &str, host: &str, content_hash: &str, amz_date: &str) -> String {
+    format!(
+        "{}\n/{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method, encoded_key, host, content_hash, amz_date, content_hash
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_photo_types_sniffed() {
+        assert_eq!(image_content_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]), Some("image/jpeg"));
+        assert_eq!(image_content_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(image_content_type(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+    }
+
+    #[test]
+    fn test_other_files_rejected() {
+        assert_eq!(image_content_type(b"%PDF-1.7"), None);
+        assert_eq!(image_content_type(b"GIF89a"), None);
+        assert_eq!(image_content_type(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(image_content_type(b"RIFF"), None);
+        assert_eq!(image_content_type(&[]), None);
+    }
+
+    #[test]
+    fn test_photo_key_layout() {
+        let business = Uuid::nil();
+        let grading = Uuid::from_u128(1);
+        let photo = Uuid::from_u128(2);
+        assert_eq!(
+            photo_key(business, grading, photo, "image/png"),
+            format!("gradings/{}/{}/{}.png", business, grading, photo)
+        );
+        assert!(photo_key(business, grading, photo, "image/jpeg").ends_with(".jpg"));
+    }
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn test_empty_payload_hash() {
+        assert_eq!(
+            payload_hash(&[]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_keys_encoded_by_segment() {
+        assert_eq!(encode_key("gradings/a b/tray+1.jpg"), "gradings/a%20b/tray%2B1.jpg");
+        assert_eq!(encode_key("ถาด.jpg"), "%E0%B8%96%E0%B8%B2%E0%B8%94.jpg");
+    }
+
+    #[test]
+    fn test_canonical_request_layout() {
+        let hash = payload_hash(&[]);
+        let request = canonical_request("GET", "gradings/x.jpg", "bucket.s3.ap-southeast-1.amazonaws.com", &hash, "20250101T000000Z");
+        let lines: Vec<&str> = request.lines().collect();
+        assert_eq!(lines[0], "GET");
+        assert_eq!(lines[1], "/gradings/x.jpg");
+        assert_eq!(lines[2], "");
+        assert_eq!(lines[3], "host:bucket.s3.ap-southeast-1.amazonaws.com");
+        assert_eq!(lines[7], "host;x-amz-content-sha256;x-amz-date");
+        assert_eq!(lines[8], hash);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_encoded_keys_are_url_safe(key in "\\PC{0,40}") {
+        let encoded = encode_key(&key);
+        prop_assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.~/%".contains(&b)));
+        prop_assert_eq!(encoded.matches('/').count(), key.matches('/').count());
+    }
+
+    #[test]
+    fn prop_unrecognised_bytes_rejected(first in 0u8..0x80, rest in prop::collection::vec(any::<u8>(), 0..16)) {
+        // No accepted format starts with a byte below 0x80 other than 'R'
+        prop_assume!(first != b'R');
+        let mut image = vec![first];
+        image.extend(rest);
+        prop_assert_eq!(image_content_type(&image), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Grading standard tests
+//!
+//! Tests for classifying gradings under per-market standards:
+//! - Thresholds checked and put best grade first
+//! - The SCA thresholds matching the SCA rules
+//! - Custom defect and moisture limits deciding the grade
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use shared::{classify_grade, classify_grade_with, sca_thresholds, DefectCount, GradeClassification, GradeThreshold};
+
+/// Mirrors `validate_thresholds`, with the error as None
+fn validate_thresholds(thresholds: &[GradeThreshold]) -> Option<Vec<GradeThreshold>> {
+    if thresholds.is_empty() {
+        return None;
+    }
+    let mut ordered: Vec<GradeThreshold> = Vec::with_capacity(thresholds.len());
+    for grade in GradeClassification::ALL.iter() {
+        let mut matching = thresholds.iter().filter(|t| t.grade == *grade);
+        let Some(threshold) = matching.next() else {
+            continue;
+        };
+        if matching.next().is_some() || *grade == GradeClassification::OffGrade {
+            return None;
+        }
+        if threshold.max_total_defects < 0 || threshold.max_category1_defects.is_some_and(|max| max < 0) {
+            return None;
+        }
+        let moisture = [threshold.min_moisture_percent, threshold.max_moisture_percent];
+        if moisture
+            .iter()
+            .flatten()
+            .any(|m| *m < Decimal::ZERO || *m > Decimal::ONE_HUNDRED)
+        {
+            return None;
+        }
+        if let [Some(min), Some(max)] = moisture {
+            if min > max {
+                return None;
+            }
+        }
+        if ordered.last().is_some_and(|better| better.max_total_defects > threshold.max_total_defects) {
+            return None;
+        }
+        ordered.push(threshold.clone());
+    }
+    Some(ordered)
+}
+
+fn threshold(grade: GradeClassification, max_total_defects: i32) -> GradeThreshold {
+    GradeThreshold {
+        grade,
+        max_total_defects,
+        max_category1_defects: None,
+        min_moisture_percent: None,
+        max_moisture_percent: None,
+    }
+}
+
+fn defects(category1: i32, category2: i32) -> DefectCount {
+    DefectCount {
+        category1_count: category1,
+        category2_count: category2,
+        defect_breakdown: None,
+    }
+}
+
+fn pct(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds_ordered_best_first() {
+        let ordered = validate_thresholds(&[
+            threshold(GradeClassification::ExchangeGrade, 30),
+            threshold(GradeClassification::SpecialtyGrade, 3),
+        ])
+        .unwrap();
+        let grades: Vec<_> = ordered.iter().map(|t| t.grade.clone()).collect();
+        assert_eq!(grades, vec![GradeClassification::SpecialtyGrade, GradeClassification::ExchangeGrade]);
+    }
+
+    #[test]
+    fn test_invalid_thresholds() {
+        assert!(validate_thresholds(&[]).is_none());
+        assert!(validate_thresholds(&[threshold(GradeClassification::OffGrade, 100)]).is_none());
+        assert!(validate_thresholds(&[
+            threshold(GradeClassification::PremiumGrade, 8),
+            threshold(GradeClassification::PremiumGrade, 10),
+        ])
+        .is_none());
+        assert!(validate_thresholds(&[threshold(GradeClassification::PremiumGrade, -1)]).is_none());
+        // A worse grade stricter than a better one
+        assert!(validate_thresholds(&[
+            threshold(GradeClassification::PremiumGrade, 10),
+            threshold(GradeClassification::ExchangeGrade, 8),
+        ])
+        .is_none());
+        let mut inverted = threshold(GradeClassification::PremiumGrade, 8);
+        inverted.min_moisture_percent = Some(pct("12.5"));
+        inverted.max_moisture_percent = Some(pct("9"));
+        assert!(validate_thresholds(&[inverted]).is_none());
+    }
+
+    #[test]
+    fn test_sca_thresholds_valid() {
+        assert_eq!(validate_thresholds(&sca_thresholds()), Some(sca_thresholds()));
+    }
+
+    #[test]
+    fn test_custom_standard_grades() {
+        // A buyer taking premium up to 12 defects and exchange up to 30
+        let standard = vec![
+            threshold(GradeClassification::PremiumGrade, 12),
+            threshold(GradeClassification::ExchangeGrade, 30),
+        ];
+        assert_eq!(classify_grade_with(&defects(2, 8), None, &standard), GradeClassification::PremiumGrade);
+        assert_eq!(classify_grade(&defects(2, 8)), GradeClassification::ExchangeGrade);
+        assert_eq!(classify_grade_with(&defects(10, 20), None, &standard), GradeClassification::ExchangeGrade);
+        assert_eq!(classify_grade_with(&defects(10, 21), None, &standard), GradeClassification::OffGrade);
+    }
+
+    #[test]
+    fn test_moisture_range_drops_grade() {
+        let mut specialty = threshold(GradeClassification::SpecialtyGrade, 5);
+        specialty.min_moisture_percent = Some(pct("9"));
+        specialty.max_moisture_percent = Some(pct("12"));
+        let standard = vec![specialty, threshold(GradeClassification::PremiumGrade, 8)];
+        assert_eq!(
+            classify_grade_with(&defects(0, 2), Some(pct("11.2")), &standard),
+            GradeClassification::SpecialtyGrade
+        );
+        assert_eq!(
+            classify_grade_with(&defects(0, 2), Some(pct("13.1")), &standard),
+            GradeClassification::PremiumGrade
+        );
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_sca_standard_matches_sca_rules(category1 in 0i32..=120, category2 in 0i32..=120) {
+        let sample = defects(category1, category2);
+        prop_assert_eq!(classify_grade_with(&sample, None, &sca_thresholds()), classify_grade(&sample));
+    }
+
+    #[test]
+    fn prop_grade_reached_meets_its_limits(
+        limits in prop::collection::vec(0i32..=60, 1..=4),
+        category1 in 0i32..=40,
+        category2 in 0i32..=40,
+    ) {
+        let mut limits = limits;
+        limits.sort();
+        let standard: Vec<GradeThreshold> = GradeClassification::ALL
+            .iter()
+            .zip(&limits)
+            .map(|(grade, max)| threshold(grade.clone(), *max))
+            .collect();
+        prop_assert!(validate_thresholds(&standard).is_some());
+        let sample = defects(category1, category2);
+        let grade = classify_grade_with(&sample, None, &standard);
+        match standard.iter().find(|t| t.grade == grade) {
+            Some(t) => prop_assert!(sample.total() <= t.max_total_defects),
+            None => {
+                prop_assert_eq!(grade, GradeClassification::OffGrade);
+                prop_assert!(standard.iter().all(|t| sample.total() > t.max_total_defects));
+            }
+        }
+    }
+}
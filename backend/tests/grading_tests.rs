@@ -1,5 +1,9 @@
 //! Tests for green bean grading service
 //! Verifies Property 9: Grade Classification Consistency
+//! and the physical analysis: screens 19-13, water activity and bulk density.
+//! A defect breakdown gives the defect counts as SCA full defect equivalents.
+//! Lots compared side by side share one list of defect types, and
+//! corrections keep the changed fields in the grading's edit history
 
 use rust_decimal::Decimal;
 use shared::{classify_grade, DefectBreakdown, DefectCount, GradeClassification};
@@ -385,6 +389,199 @@ mod screen_size {
     }
 }
 
+// =============================================================================
+// Defect Breakdown Tests
+// =============================================================================
+
+mod defect_breakdown {
+    use super::*;
+    use shared::DefectType;
+
+    /// Mirrors `grading_defects`, returning the rejected defect type
+    fn grading_defects(
+        category1_count: i32,
+        category2_count: i32,
+        defect_breakdown: Option<DefectBreakdown>,
+    ) -> Result<DefectCount, DefectType> {
+        let Some(breakdown) = defect_breakdown else {
+            return Ok(DefectCount {
+                category1_count,
+                category2_count,
+                defect_breakdown: None,
+            });
+        };
+        if let Some(defect) = breakdown.negative_count() {
+            return Err(defect);
+        }
+        Ok(DefectCount::from_breakdown(breakdown))
+    }
+
+    #[test]
+    fn counts_entered_without_breakdown() {
+        let defects = grading_defects(1, 4, None).unwrap();
+        assert_eq!((defects.category1_count, defects.category2_count), (1, 4));
+    }
+
+    #[test]
+    fn breakdown_gives_stored_counts() {
+        let breakdown = DefectBreakdown {
+            full_sour: 2,
+            floater: 10,
+            partial_sour: 3,
+            ..Default::default()
+        };
+        // Counts entered alongside a breakdown are replaced
+        let defects = grading_defects(0, 0, Some(breakdown)).unwrap();
+        assert_eq!((defects.category1_count, defects.category2_count), (2, 3));
+        assert_eq!(classify_grade(&defects), GradeClassification::PremiumGrade);
+    }
+
+    #[test]
+    fn negative_breakdown_rejected() {
+        let breakdown = DefectBreakdown {
+            floater: -2,
+            ..Default::default()
+        };
+        assert_eq!(grading_defects(0, 0, Some(breakdown)).unwrap_err(), DefectType::Floater);
+    }
+}
+
+// =============================================================================
+// Physical Analysis Tests
+// =============================================================================
+
+mod physical_analysis {
+    use super::*;
+    use proptest::prelude::*;
+    use shared::ScreenAnalysis;
+
+    const MIN_BULK_DENSITY_G_PER_L: i64 = 300;
+    const MAX_BULK_DENSITY_G_PER_L: i64 = 1000;
+
+    /// Mirrors `validate_physical_analysis`, returning the rejected field
+    fn validate_physical_analysis(
+        screen_analysis: Option<&ScreenAnalysis>,
+        water_activity: Option<Decimal>,
+        bulk_density_g_per_l: Option<Decimal>,
+    ) -> Result<(), String> {
+        if let Some(analysis) = screen_analysis {
+            for (screen, share) in analysis.screens() {
+                if share < Decimal::ZERO || share > Decimal::ONE_HUNDRED {
+                    return Err(format!("screen_analysis.screen_{}", screen));
+                }
+            }
+            if analysis.retained_percent() > Decimal::ONE_HUNDRED {
+                return Err("screen_analysis".to_string());
+            }
+        }
+        if water_activity.is_some_and(|aw| aw < Decimal::ZERO || aw > Decimal::ONE) {
+            return Err("water_activity".to_string());
+        }
+        if bulk_density_g_per_l.is_some_and(|density| {
+            density < Decimal::from(MIN_BULK_DENSITY_G_PER_L) || density > Decimal::from(MAX_BULK_DENSITY_G_PER_L)
+        }) {
+            return Err("bulk_density_g_per_l".to_string());
+        }
+        Ok(())
+    }
+
+    fn typical_analysis() -> ScreenAnalysis {
+        ScreenAnalysis {
+            screen_19: dec("5.0"),
+            screen_18: dec("20.0"),
+            screen_17: dec("30.0"),
+            screen_16: dec("25.0"),
+            screen_15: dec("12.0"),
+            screen_14: dec("5.0"),
+            screen_13: dec("2.0"),
+        }
+    }
+
+    #[test]
+    fn pan_takes_what_passes_screen_13() {
+        let analysis = typical_analysis();
+        assert_eq!(analysis.retained_percent(), dec("99.0"));
+        assert_eq!(analysis.pan_percent(), dec("1.0"));
+    }
+
+    #[test]
+    fn distribution_merges_outer_screens() {
+        let distribution = typical_analysis().distribution();
+        assert_eq!(distribution.screen_18_plus, dec("25.0"));
+        assert_eq!(distribution.screen_17, dec("30.0"));
+        assert_eq!(distribution.screen_14_below, dec("8.0"));
+    }
+
+    #[test]
+    fn missing_screens_default_to_zero() {
+        let analysis: ScreenAnalysis = serde_json::from_str(r#"{"screen_17": 60, "screen_16": 40}"#).unwrap();
+        assert_eq!(analysis.screen_19, Decimal::ZERO);
+        assert_eq!(analysis.pan_percent(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn typical_analysis_is_valid() {
+        assert_eq!(
+            validate_physical_analysis(Some(&typical_analysis()), Some(dec("0.55")), Some(dec("690"))),
+            Ok(())
+        );
+        assert_eq!(validate_physical_analysis(None, None, None), Ok(()));
+    }
+
+    #[test]
+    fn screens_cannot_retain_more_than_the_sample() {
+        let analysis = ScreenAnalysis {
+            screen_13: dec("10.0"),
+            ..typical_analysis()
+        };
+        assert_eq!(
+            validate_physical_analysis(Some(&analysis), None, None),
+            Err("screen_analysis".to_string())
+        );
+    }
+
+    #[test]
+    fn negative_screen_rejected() {
+        let analysis = ScreenAnalysis {
+            screen_15: dec("-1.0"),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_physical_analysis(Some(&analysis), None, None),
+            Err("screen_analysis.screen_15".to_string())
+        );
+    }
+
+    #[test]
+    fn water_activity_and_density_ranges() {
+        assert!(validate_physical_analysis(None, Some(dec("1.2")), None).is_err());
+        assert!(validate_physical_analysis(None, Some(Decimal::ONE), None).is_ok());
+        assert!(validate_physical_analysis(None, None, Some(dec("250"))).is_err());
+        assert!(validate_physical_analysis(None, None, Some(dec("1200"))).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_distribution_accounts_for_whole_sample(shares in prop::collection::vec(0u32..=140, 7)) {
+            let [s19, s18, s17, s16, s15, s14, s13] = [0, 1, 2, 3, 4, 5, 6].map(|i| Decimal::new(shares[i] as i64, 1));
+            let analysis = ScreenAnalysis {
+                screen_19: s19,
+                screen_18: s18,
+                screen_17: s17,
+                screen_16: s16,
+                screen_15: s15,
+                screen_14: s14,
+                screen_13: s13,
+            };
+            prop_assert!(analysis.retained_percent() <= Decimal::ONE_HUNDRED);
+            let d = analysis.distribution();
+            let total = d.screen_18_plus + d.screen_17 + d.screen_16 + d.screen_15 + d.screen_14_below;
+            prop_assert_eq!(total, Decimal::ONE_HUNDRED);
+            prop_assert!(validate_physical_analysis(Some(&analysis), None, None).is_ok());
+        }
+    }
+}
+
 // =============================================================================
 // AI Detection Integration Tests (structure validation)
 // =============================================================================
@@ -424,7 +621,7 @@ mod ai_detection {
             category2_count: ai_result.category2_count,
             defect_breakdown: Some(ai_result.defect_breakdown),
         };
-        // Total = 6, has cat1, so Premium
+        // Breakdown: 1 full black, partial blacks and broken below a full defect; cat1, so Premium
         assert_eq!(classify_grade(&defects), GradeClassification::PremiumGrade);
     }
 
@@ -452,7 +649,223 @@ mod ai_detection {
             defect_breakdown: Some(ai_result.defect_breakdown),
         };
 
-        // 0 cat1, 3 total = Specialty Grade
+        // Breakdown: no full defects = Specialty Grade
         assert_eq!(classify_grade(&defects), GradeClassification::SpecialtyGrade);
     }
 }
+
+// =============================================================================
+// Lot Comparison Tests
+// =============================================================================
+
+mod lot_comparison {
+    use super::*;
+    use proptest::prelude::*;
+    use shared::DefectType;
+    use uuid::Uuid;
+
+    /// Mirrors `MAX_COMPARED_LOTS`
+    const MAX_COMPARED_LOTS: usize = 10;
+
+    /// Mirrors `parse_compared_lot_ids`, with the error as None
+    fn parse_compared_lot_ids(lot_ids: &str) -> Option<Vec<Uuid>> {
+        let mut ids: Vec<Uuid> = Vec::new();
+        for part in lot_ids.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let id = Uuid::parse_str(part).ok()?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if ids.len() < 2 || ids.len() > MAX_COMPARED_LOTS {
+            return None;
+        }
+        Some(ids)
+    }
+
+    /// Mirrors `compared_defect_types`
+    fn compared_defect_types(breakdowns: &[Option<&DefectBreakdown>]) -> Vec<DefectType> {
+        DefectType::ALL
+            .into_iter()
+            .filter(|defect| breakdowns.iter().flatten().any(|b| b.count(*defect) > 0))
+            .collect()
+    }
+
+    fn ids(n: usize) -> Vec<Uuid> {
+        (0..n).map(|_| Uuid::new_v4()).collect()
+    }
+
+    fn joined(ids: &[Uuid]) -> String {
+        ids.iter().map(Uuid::to_string).collect::<Vec<_>>().join(",")
+    }
+
+    #[test]
+    fn lot_ids_keep_order_without_repeats() {
+        let lots = ids(3);
+        let query = format!("{}, {},{},,{}", lots[2], lots[0], lots[2], lots[1]);
+        assert_eq!(parse_compared_lot_ids(&query), Some(vec![lots[2], lots[0], lots[1]]));
+    }
+
+    #[test]
+    fn comparison_needs_two_to_ten_lots() {
+        let lots = ids(11);
+        assert_eq!(parse_compared_lot_ids(""), None);
+        assert_eq!(parse_compared_lot_ids(&joined(&lots[..1])), None);
+        assert_eq!(parse_compared_lot_ids(&format!("{},{}", lots[0], lots[0])), None);
+        assert!(parse_compared_lot_ids(&joined(&lots[..2])).is_some());
+        assert!(parse_compared_lot_ids(&joined(&lots[..10])).is_some());
+        assert_eq!(parse_compared_lot_ids(&joined(&lots)), None);
+    }
+
+    #[test]
+    fn unknown_lot_id_rejected() {
+        let lots = ids(2);
+        assert_eq!(parse_compared_lot_ids(&format!("{},{},LOT-7", lots[0], lots[1])), None);
+    }
+
+    #[test]
+    fn defect_types_found_in_any_lot_in_sca_order() {
+        let a = DefectBreakdown { broken: 4, full_black: 1, ..Default::default() };
+        let b = DefectBreakdown { insect_damage: 2, ..Default::default() };
+        let types = compared_defect_types(&[Some(&a), None, Some(&b)]);
+        assert_eq!(types, vec![DefectType::FullBlack, DefectType::Broken, DefectType::InsectDamage]);
+        let aligned: Vec<i32> = types.iter().map(|t| b.count(*t)).collect();
+        assert_eq!(aligned, vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn no_breakdowns_no_defect_types() {
+        assert!(compared_defect_types(&[None, None]).is_empty());
+        assert!(compared_defect_types(&[Some(&DefectBreakdown::default())]).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_aligned_counts_keep_every_defect(counts in prop::collection::vec((0usize..22, 1i32..20), 0..12)) {
+            let mut a = DefectBreakdown::default();
+            let mut b = DefectBreakdown::default();
+            for (i, (defect, count)) in counts.iter().enumerate() {
+                let target = if i % 2 == 0 { &mut a } else { &mut b };
+                let mut value = serde_json::to_value(&*target).unwrap();
+                let key = serde_json::to_value(DefectType::ALL[*defect]).unwrap();
+                value[key.as_str().unwrap()] = serde_json::json!(*count);
+                *target = serde_json::from_value(value).unwrap();
+            }
+            let types = compared_defect_types(&[Some(&a), Some(&b)]);
+            for breakdown in [&a, &b] {
+                let aligned: i32 = types.iter().map(|t| breakdown.count(*t)).sum();
+                let all: i32 = DefectType::ALL.iter().map(|t| breakdown.count(*t)).sum();
+                prop_assert_eq!(aligned, all);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Grading Correction Tests
+// =============================================================================
+
+mod grading_correction {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// Mirrors `corrected_defects`, with the error as None
+    fn corrected_defects(
+        existing: &DefectCount,
+        defect_breakdown: Option<DefectBreakdown>,
+        category1_count: Option<i32>,
+        category2_count: Option<i32>,
+    ) -> Option<DefectCount> {
+        if let Some(breakdown) = defect_breakdown {
+            return breakdown.negative_count().is_none().then(|| DefectCount::from_breakdown(breakdown));
+        }
+        if category1_count.is_none() && category2_count.is_none() {
+            return Some(existing.clone());
+        }
+        Some(DefectCount {
+            category1_count: category1_count.unwrap_or(existing.category1_count),
+            category2_count: category2_count.unwrap_or(existing.category2_count),
+            defect_breakdown: None,
+        })
+    }
+
+    /// Mirrors `grading_changes`, with changes as (field, from, to)
+    fn grading_changes(
+        before: &[(&'static str, Value)],
+        after: Option<&[(&'static str, Value)]>,
+    ) -> Vec<(String, Value, Value)> {
+        before
+            .iter()
+            .filter_map(|(field, from)| {
+                let to = match after {
+                    Some(after) => after.iter().find(|(f, _)| f == field).map(|(_, v)| v.clone()).unwrap_or(Value::Null),
+                    None => Value::Null,
+                };
+                if after.is_some() && *from == to {
+                    return None;
+                }
+                Some((field.to_string(), from.clone(), to))
+            })
+            .collect()
+    }
+
+    fn counted(category1_count: i32, category2_count: i32) -> DefectCount {
+        DefectCount { category1_count, category2_count, defect_breakdown: None }
+    }
+
+    #[test]
+    fn untouched_defects_stay() {
+        let existing = DefectCount::from_breakdown(DefectBreakdown { broken: 10, ..Default::default() });
+        let corrected = corrected_defects(&existing, None, None, None).unwrap();
+        assert_eq!(corrected.category2_count, 2);
+        assert!(corrected.defect_breakdown.is_some());
+    }
+
+    #[test]
+    fn new_breakdown_gives_counts() {
+        let breakdown = DefectBreakdown { full_black: 2, partial_black: 6, ..Default::default() };
+        let corrected = corrected_defects(&counted(9, 9), Some(breakdown), Some(1), None).unwrap();
+        assert_eq!((corrected.category1_count, corrected.category2_count), (2, 2));
+    }
+
+    #[test]
+    fn new_counts_clear_breakdown() {
+        let existing = DefectCount::from_breakdown(DefectBreakdown { full_sour: 3, ..Default::default() });
+        let corrected = corrected_defects(&existing, None, None, Some(4)).unwrap();
+        assert_eq!((corrected.category1_count, corrected.category2_count), (3, 4));
+        assert!(corrected.defect_breakdown.is_none());
+    }
+
+    #[test]
+    fn negative_breakdown_rejected() {
+        let breakdown = DefectBreakdown { shell: -1, ..Default::default() };
+        assert!(corrected_defects(&counted(0, 0), Some(breakdown), None, None).is_none());
+    }
+
+    #[test]
+    fn corrected_grade_follows_counts() {
+        let corrected = corrected_defects(&counted(0, 3), None, Some(1), Some(6)).unwrap();
+        assert_eq!(classify_grade(&corrected), GradeClassification::PremiumGrade);
+    }
+
+    #[test]
+    fn update_lists_only_changed_fields() {
+        let before = [("category1_count", json!(0)), ("category2_count", json!(12)), ("grade", json!("exchange_grade"))];
+        let after = [("category1_count", json!(0)), ("category2_count", json!(2)), ("grade", json!("specialty_grade"))];
+        assert_eq!(
+            grading_changes(&before, Some(&after)),
+            vec![
+                ("category2_count".to_string(), json!(12), json!(2)),
+                ("grade".to_string(), json!("exchange_grade"), json!("specialty_grade")),
+            ]
+        );
+        assert!(grading_changes(&before, Some(&before)).is_empty());
+    }
+
+    #[test]
+    fn delete_lists_every_field() {
+        let before = [("grader_name", json!("Somchai")), ("notes", Value::Null)];
+        let changes = grading_changes(&before, None);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|(_, _, to)| to.is_null()));
+    }
+}
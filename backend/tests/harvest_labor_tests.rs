@@ -0,0 +1,130 @@
+//! Harvest labor planning tests
+//!
+//! Tests for turning expected cherry into picker-days:
+//! - Picker-days round up to whole days of picking
+//! - Poor-weather forecast days reduce the working days, down to one
+//! - Pickers needed cover the picker-days over the workable days
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// Mirrors `HarvestSuitability`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Suitability {
+    Excellent,
+    Good,
+    Fair,
+    Poor,
+}
+
+/// Mirrors `plan_plot_week`
+fn plan_plot_week(
+    expected_kg: Decimal,
+    kg_per_picker_day: Decimal,
+    workdays_per_week: u32,
+    windows: &[(NaiveDate, Suitability)],
+) -> (u32, u32, Vec<NaiveDate>, Vec<NaiveDate>) {
+    let picker_days = if kg_per_picker_day > Decimal::ZERO {
+        (expected_kg / kg_per_picker_day).ceil().to_u32().unwrap_or(0)
+    } else {
+        0
+    };
+    let recommended = windows
+        .iter()
+        .filter(|w| matches!(w.1, Suitability::Excellent | Suitability::Good))
+        .map(|w| w.0)
+        .collect();
+    let poor: Vec<NaiveDate> = windows.iter().filter(|w| w.1 == Suitability::Poor).map(|w| w.0).collect();
+    let workable_days = workdays_per_week.saturating_sub(poor.len() as u32).max(1);
+    (picker_days, workable_days, recommended, poor)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_picker_days_round_up() {
+        let (picker_days, workable_days, _, _) = plan_plot_week(dec("1020"), dec("50"), 6, &[]);
+
+        assert_eq!(picker_days, 21);
+        assert_eq!(workable_days, 6);
+        assert_eq!(picker_days.div_ceil(workable_days), 4);
+    }
+
+    #[test]
+    fn test_poor_weather_removes_working_days() {
+        let windows = vec![
+            (date("2024-11-04"), Suitability::Excellent),
+            (date("2024-11-05"), Suitability::Poor),
+            (date("2024-11-06"), Suitability::Poor),
+            (date("2024-11-07"), Suitability::Fair),
+            (date("2024-11-08"), Suitability::Good),
+        ];
+        let (picker_days, workable_days, recommended, poor) =
+            plan_plot_week(dec("600"), dec("50"), 6, &windows);
+
+        assert_eq!(picker_days, 12);
+        assert_eq!(workable_days, 4);
+        assert_eq!(picker_days.div_ceil(workable_days), 3);
+        assert_eq!(recommended, vec![date("2024-11-04"), date("2024-11-08")]);
+        assert_eq!(poor, vec![date("2024-11-05"), date("2024-11-06")]);
+    }
+
+    #[test]
+    fn test_all_days_poor_keeps_one_day() {
+        let windows: Vec<_> = (4..=10)
+            .map(|d| (date(&format!("2024-11-{:02}", d)), Suitability::Poor))
+            .collect();
+        let (_, workable_days, _, _) = plan_plot_week(dec("100"), dec("50"), 5, &windows);
+
+        assert_eq!(workable_days, 1);
+    }
+
+    #[test]
+    fn test_custom_picking_rate() {
+        let (picker_days, _, _, _) = plan_plot_week(dec("300"), dec("75"), 6, &[]);
+        assert_eq!(picker_days, 4);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_picker_days_cover_expected_cherry(
+        expected in 1u32..20000,
+        rate in 10u32..120,
+        workdays in 1u32..=7,
+        poor_days in 0usize..7,
+    ) {
+        let windows: Vec<_> = (0..poor_days)
+            .map(|d| (date("2024-11-04") + chrono::Duration::days(d as i64), Suitability::Poor))
+            .collect();
+        let (picker_days, workable_days, _, _) =
+            plan_plot_week(Decimal::from(expected), Decimal::from(rate), workdays, &windows);
+
+        prop_assert!(Decimal::from(picker_days * rate) >= Decimal::from(expected));
+        prop_assert!(Decimal::from((picker_days - 1) * rate) < Decimal::from(expected));
+        prop_assert!(workable_days >= 1 && workable_days <= workdays);
+        prop_assert!(picker_days.div_ceil(workable_days) * workable_days >= picker_days);
+    }
+}
@@ -5,10 +5,12 @@
 //! - Property 5: Ripeness Assessment Validity
 //! - Property 6: Harvest Yield Calculation
 //! - Property 7: Lot Blending Traceability
+//! - Duplicate harvest entries of one picking
 
 use proptest::prelude::*;
 use rust_decimal::Decimal;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
 // ============================================================================
 // Property Test Strategies
@@ -201,6 +203,17 @@ proptest! {
 // Helper Functions (mirroring service implementations)
 // ============================================================================
 
+/// Mirrors the `next_code_sequence` upsert: allocations on one counter are
+/// serialized by the row lock, and a value never falls at or below `floor`
+fn next_code_sequence(counters: &mut HashMap<String, i64>, key: &str, floor: i64) -> i64 {
+    let next = match counters.get(key) {
+        Some(last) => (last + 1).max(floor.max(0) + 1),
+        None => floor.max(0) + 1,
+    };
+    counters.insert(key.to_string(), next);
+    next
+}
+
 /// Calculate yield per rai
 fn calculate_yield_per_rai(
     total_cherry_weight_kg: Decimal,
@@ -248,6 +261,29 @@ fn validate_blend_proportions(proportions: &[Decimal]) -> Result<(), String> {
     Ok(())
 }
 
+/// Mirrors `DUPLICATE_WEIGHT_TOLERANCE`
+const DUPLICATE_WEIGHT_TOLERANCE: Decimal = Decimal::from_parts(2, 0, 0, false, 2);
+
+/// Mirrors `SameDayHarvest`
+#[derive(Debug, Clone, PartialEq)]
+struct SameDayHarvest {
+    id: Uuid,
+    cherry_weight_kg: Decimal,
+}
+
+/// Mirrors `weights_match`
+fn weights_match(a: Decimal, b: Decimal) -> bool {
+    (a - b).abs() <= a.max(b) * DUPLICATE_WEIGHT_TOLERANCE
+}
+
+/// Mirrors `find_duplicate_harvest`
+fn find_duplicate_harvest(cherry_weight_kg: Decimal, same_day: &[SameDayHarvest]) -> Option<&SameDayHarvest> {
+    same_day
+        .iter()
+        .filter(|h| weights_match(cherry_weight_kg, h.cherry_weight_kg))
+        .min_by_key(|h| (h.cherry_weight_kg - cherry_weight_kg).abs())
+}
+
 // ============================================================================
 // Unit Tests: Ripeness Validation
 // ============================================================================
@@ -373,6 +409,52 @@ mod traceability_tests {
     }
 }
 
+// ============================================================================
+// Unit Tests: Code Sequences
+// ============================================================================
+
+#[cfg(test)]
+mod sequence_tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_starts_at_one_per_key() {
+        let mut counters = HashMap::new();
+        assert_eq!(next_code_sequence(&mut counters, "2024", 0), 1);
+        assert_eq!(next_code_sequence(&mut counters, "2024", 0), 2);
+        assert_eq!(next_code_sequence(&mut counters, "2025", 0), 1);
+    }
+
+    #[test]
+    fn test_sequence_skips_values_in_use() {
+        // Session already has samples 1-3 written before the counter existed
+        let mut counters = HashMap::new();
+        assert_eq!(next_code_sequence(&mut counters, "session", 3), 4);
+        assert_eq!(next_code_sequence(&mut counters, "session", 3), 5);
+        // A stale floor never moves the counter backwards
+        assert_eq!(next_code_sequence(&mut counters, "session", 1), 6);
+    }
+
+    proptest! {
+        /// Any interleaving of allocations yields distinct values above the
+        /// floor each caller saw
+        #[test]
+        fn prop_sequence_values_unique(
+            calls in prop::collection::vec((0usize..3, 0i64..20), 1..200)
+        ) {
+            let keys = ["2024", "2025", "session"];
+            let mut counters = HashMap::new();
+            let mut seen: HashSet<(usize, i64)> = HashSet::new();
+
+            for (key, floor) in calls {
+                let value = next_code_sequence(&mut counters, keys[key], floor);
+                prop_assert!(value > floor);
+                prop_assert!(seen.insert((key, value)), "duplicate value {} for {}", value, keys[key]);
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Unit Tests: Blend Proportions
 // ============================================================================
@@ -494,3 +576,62 @@ mod thai_harvest_tests {
         assert!(ripe >= 90, "Specialty coffee needs >90% ripe cherries");
     }
 }
+
+// ============================================================================
+// Unit Tests: Duplicate Harvest Entries
+// ============================================================================
+
+#[cfg(test)]
+mod duplicate_harvest_tests {
+    use super::*;
+
+    fn same_day(weights: &[i64]) -> Vec<SameDayHarvest> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, kg)| SameDayHarvest {
+                id: Uuid::from_u128(i as u128 + 1),
+                cherry_weight_kg: Decimal::from(*kg),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weight_within_two_percent_matches() {
+        assert!(weights_match(Decimal::from(100), Decimal::from(98)));
+        assert!(weights_match(Decimal::from(98), Decimal::from(100)));
+        assert!(weights_match(Decimal::new(505, 1), Decimal::from(50)));
+    }
+
+    #[test]
+    fn test_weight_beyond_two_percent_does_not_match() {
+        assert!(!weights_match(Decimal::from(100), Decimal::from(97)));
+        assert!(!weights_match(Decimal::from(20), Decimal::from(21)));
+    }
+
+    #[test]
+    fn test_closest_same_day_harvest_is_reported() {
+        let harvests = same_day(&[45, 101, 99]);
+        let duplicate = find_duplicate_harvest(Decimal::new(1005, 1), &harvests).unwrap();
+        assert_eq!(duplicate.id, Uuid::from_u128(2));
+    }
+
+    #[test]
+    fn test_no_duplicate_without_a_close_weight() {
+        assert!(find_duplicate_harvest(Decimal::from(60), &same_day(&[45, 100])).is_none());
+        assert!(find_duplicate_harvest(Decimal::from(60), &[]).is_none());
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_weights_match_is_symmetric(a in cherry_weight_strategy(), b in cherry_weight_strategy()) {
+        prop_assert_eq!(weights_match(a, b), weights_match(b, a));
+    }
+
+    #[test]
+    fn prop_identical_weight_is_a_duplicate(weight in cherry_weight_strategy()) {
+        let harvests = vec![SameDayHarvest { id: Uuid::nil(), cherry_weight_kg: weight }];
+        prop_assert!(find_duplicate_harvest(weight, &harvests).is_some());
+    }
+}
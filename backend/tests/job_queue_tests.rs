@@ -0,0 +1,123 @@
+//! Job queue tests
+//!
+//! Tests for retrying queued background work:
+//! - Retry delays doubling from 30 seconds up to an hour
+//! - Jobs queued again only for temporary errors with attempts left
+//! - Job status names
+
+use proptest::prelude::*;
+
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+
+/// Mirrors `JobStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    /// Mirrors `JobStatus::as_str`
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Mirrors `retry_delay`, in seconds
+fn retry_delay_seconds(attempt: i32) -> i64 {
+    let doublings = attempt.clamp(1, 32) as u32 - 1;
+    BASE_RETRY_DELAY_SECONDS
+        .saturating_mul(2i64.saturating_pow(doublings))
+        .min(MAX_RETRY_DELAY_SECONDS)
+}
+
+/// Mirrors `status_after_failure`
+fn status_after_failure(attempts: i32, max_attempts: i32, retryable: bool) -> JobStatus {
+    if retryable && attempts < max_attempts {
+        JobStatus::Queued
+    } else {
+        JobStatus::Failed
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delays_double() {
+        assert_eq!(retry_delay_seconds(1), 30);
+        assert_eq!(retry_delay_seconds(2), 60);
+        assert_eq!(retry_delay_seconds(3), 120);
+        assert_eq!(retry_delay_seconds(4), 240);
+    }
+
+    #[test]
+    fn test_retry_delay_capped_at_an_hour() {
+        assert_eq!(retry_delay_seconds(8), 3600);
+        assert_eq!(retry_delay_seconds(100), 3600);
+        assert_eq!(retry_delay_seconds(0), 30);
+    }
+
+    #[test]
+    fn test_temporary_errors_retried_while_attempts_left() {
+        assert_eq!(status_after_failure(1, 5, true), JobStatus::Queued);
+        assert_eq!(status_after_failure(4, 5, true), JobStatus::Queued);
+        assert_eq!(status_after_failure(5, 5, true), JobStatus::Failed);
+    }
+
+    #[test]
+    fn test_other_errors_fail_at_once() {
+        assert_eq!(status_after_failure(1, 5, false), JobStatus::Failed);
+    }
+
+    #[test]
+    fn test_status_names() {
+        let names: Vec<_> = [JobStatus::Queued, JobStatus::Running, JobStatus::Succeeded, JobStatus::Failed]
+            .iter()
+            .map(JobStatus::as_str)
+            .collect();
+        assert_eq!(names, vec!["queued", "running", "succeeded", "failed"]);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_retry_delays_grow_within_bounds(attempt in -5i32..200) {
+        let delay = retry_delay_seconds(attempt);
+        prop_assert!((BASE_RETRY_DELAY_SECONDS..=MAX_RETRY_DELAY_SECONDS).contains(&delay));
+        prop_assert!(retry_delay_seconds(attempt + 1) >= delay);
+    }
+
+    #[test]
+    fn prop_job_runs_at_most_max_attempts(max_attempts in 1i32..20, failures in 1i32..40) {
+        // Every attempt fails with a temporary error
+        let mut attempts = 0;
+        let mut status = JobStatus::Queued;
+        while status == JobStatus::Queued && attempts < failures {
+            attempts += 1;
+            status = status_after_failure(attempts, max_attempts, true);
+        }
+        prop_assert!(attempts <= max_attempts);
+        if failures >= max_attempts {
+            prop_assert_eq!(status, JobStatus::Failed);
+            prop_assert_eq!(attempts, max_attempts);
+        }
+    }
+}
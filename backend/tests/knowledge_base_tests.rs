@@ -0,0 +1,225 @@
+//! Knowledge base tests
+//!
+//! Tests for SOP and training content delivery:
+//! - Read status against the item's current version
+//! - Items for everyone or for particular role templates
+//! - Reading time from the longer language
+//! - Recommendation order and limit
+//! - Slug validation
+
+use proptest::prelude::*;
+
+const MAX_RECOMMENDATIONS: usize = 10;
+const CHARACTERS_PER_MINUTE: usize = 1000;
+
+/// Mirrors `ReadStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadStatus {
+    Unread,
+    Read,
+    Updated,
+}
+
+/// Mirrors the fields of `ArticleSummary` used for recommendations
+#[derive(Debug, Clone, PartialEq)]
+struct ArticleSummary {
+    slug: String,
+    kind: String,
+    audience_roles: Vec<String>,
+    reading_minutes: i32,
+    published: bool,
+    read_status: ReadStatus,
+}
+
+/// Mirrors `read_status`
+fn read_status(version: i32, version_read: Option<i32>) -> ReadStatus {
+    match version_read {
+        None => ReadStatus::Unread,
+        Some(read) if read >= version => ReadStatus::Read,
+        Some(_) => ReadStatus::Updated,
+    }
+}
+
+/// Mirrors `is_for_role`
+fn is_for_role(audience: &[String], role: Option<&str>) -> bool {
+    audience.is_empty() || role.is_some_and(|role| audience.iter().any(|r| r == role))
+}
+
+/// Mirrors `reading_minutes`
+fn reading_minutes(body: &str, body_th: &str) -> i32 {
+    let characters = body.chars().count().max(body_th.chars().count());
+    characters.div_ceil(CHARACTERS_PER_MINUTE).max(1) as i32
+}
+
+/// Mirrors `recommend`
+fn recommend(articles: &[ArticleSummary], role: Option<&str>) -> Vec<ArticleSummary> {
+    let mut recommended: Vec<ArticleSummary> = articles
+        .iter()
+        .filter(|a| a.published && a.read_status != ReadStatus::Read && is_for_role(&a.audience_roles, role))
+        .cloned()
+        .collect();
+    recommended.sort_by_key(|a| {
+        (
+            a.read_status != ReadStatus::Updated,
+            a.audience_roles.is_empty(),
+            a.kind != "sop",
+            a.reading_minutes,
+        )
+    });
+    recommended.truncate(MAX_RECOMMENDATIONS);
+    recommended
+}
+
+/// Mirrors the check in `validate_slug`
+fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= 100
+        && slug.split('-').all(|word| {
+            !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+}
+
+fn article(slug: &str, kind: &str, audience: &[&str], minutes: i32, status: ReadStatus) -> ArticleSummary {
+    ArticleSummary {
+        slug: slug.to_string(),
+        kind: kind.to_string(),
+        audience_roles: audience.iter().map(|r| r.to_string()).collect(),
+        reading_minutes: minutes,
+        published: true,
+        read_status: status,
+    }
+}
+
+fn slugs(articles: &[ArticleSummary]) -> Vec<&str> {
+    articles.iter().map(|a| a.slug.as_str()).collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_read_status_follows_version() {
+        assert_eq!(read_status(1, None), ReadStatus::Unread);
+        assert_eq!(read_status(2, Some(2)), ReadStatus::Read);
+        assert_eq!(read_status(3, Some(2)), ReadStatus::Updated);
+    }
+
+    #[test]
+    fn test_audience() {
+        let cuppers = vec!["cupper".to_string(), "roaster".to_string()];
+        assert!(is_for_role(&[], Some("viewer")));
+        assert!(is_for_role(&[], None));
+        assert!(is_for_role(&cuppers, Some("roaster")));
+        assert!(!is_for_role(&cuppers, Some("farm_manager")));
+        // Custom roles only see items for everyone
+        assert!(!is_for_role(&cuppers, None));
+    }
+
+    #[test]
+    fn test_reading_minutes_from_longer_language() {
+        assert_eq!(reading_minutes("", ""), 1);
+        assert_eq!(reading_minutes(&"a".repeat(1000), "ก"), 1);
+        assert_eq!(reading_minutes("a", &"ก".repeat(1001)), 2);
+    }
+
+    #[test]
+    fn test_recommendations_skip_read_drafts_and_other_roles() {
+        let mut draft = article("draft", "sop", &[], 1, ReadStatus::Unread);
+        draft.published = false;
+        let articles = vec![
+            article("read", "sop", &[], 1, ReadStatus::Read),
+            article("roasting", "sop", &["roaster"], 1, ReadStatus::Unread),
+            article("storage", "training", &[], 1, ReadStatus::Unread),
+            draft,
+        ];
+        assert_eq!(slugs(&recommend(&articles, Some("cupper"))), vec!["storage"]);
+    }
+
+    #[test]
+    fn test_recommendation_order() {
+        let articles = vec![
+            article("general-training", "training", &[], 2, ReadStatus::Unread),
+            article("general-sop", "sop", &[], 5, ReadStatus::Unread),
+            article("cupping-long", "training", &["cupper"], 8, ReadStatus::Unread),
+            article("cupping-short", "training", &["cupper"], 3, ReadStatus::Unread),
+            article("changed", "training", &[], 4, ReadStatus::Updated),
+        ];
+        assert_eq!(
+            slugs(&recommend(&articles, Some("cupper"))),
+            vec!["changed", "cupping-short", "cupping-long", "general-sop", "general-training"]
+        );
+    }
+
+    #[test]
+    fn test_recommendations_limited() {
+        let articles: Vec<_> = (0..15)
+            .map(|i| article(&format!("item-{}", i), "sop", &[], i, ReadStatus::Unread))
+            .collect();
+        let recommended = recommend(&articles, Some("owner"));
+        assert_eq!(recommended.len(), MAX_RECOMMENDATIONS);
+        assert_eq!(recommended[0].slug, "item-0");
+    }
+
+    #[test]
+    fn test_slugs() {
+        assert!(is_valid_slug("washed-process-sop"));
+        assert!(is_valid_slug("sca-2024"));
+        assert!(!is_valid_slug(""));
+        assert!(!is_valid_slug("Washed-Process"));
+        assert!(!is_valid_slug("washed--process"));
+        assert!(!is_valid_slug("-washed"));
+        assert!(!is_valid_slug("washed process"));
+        assert!(!is_valid_slug(&"a".repeat(101)));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_read_current_version_is_read(version in 1i32..100, behind in 0i32..100) {
+        prop_assert_eq!(read_status(version, Some(version)), ReadStatus::Read);
+        let status = read_status(version, Some(version - behind));
+        prop_assert_eq!(status == ReadStatus::Read, behind == 0);
+    }
+
+    #[test]
+    fn prop_recommendations_unread_and_for_role(
+        entries in prop::collection::vec((0usize..3, 0usize..3, 1i32..30, any::<bool>()), 0..30),
+        role in 0usize..3,
+    ) {
+        let roles = ["cupper", "roaster", "processor"];
+        let statuses = [ReadStatus::Unread, ReadStatus::Read, ReadStatus::Updated];
+        let articles: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (audience, status, minutes, general))| {
+                let audience: &[&str] = if *general { &[] } else { &roles[*audience..=*audience] };
+                article(&format!("item-{}", i), "sop", audience, *minutes, statuses[*status])
+            })
+            .collect();
+        let recommended = recommend(&articles, Some(roles[role]));
+        prop_assert!(recommended.len() <= MAX_RECOMMENDATIONS);
+        for item in &recommended {
+            prop_assert!(item.read_status != ReadStatus::Read);
+            prop_assert!(is_for_role(&item.audience_roles, Some(roles[role])));
+        }
+        for pair in recommended.windows(2) {
+            prop_assert!(pair[0].read_status == ReadStatus::Updated || pair[1].read_status != ReadStatus::Updated);
+        }
+    }
+
+    #[test]
+    fn prop_reading_minutes_at_least_one(body in ".{0,3000}", body_th in ".{0,3000}") {
+        let minutes = reading_minutes(&body, &body_th);
+        prop_assert!(minutes >= 1);
+        prop_assert!((minutes as usize - 1) * CHARACTERS_PER_MINUTE < body.chars().count().max(body_th.chars().count()).max(1));
+    }
+}
@@ -0,0 +1,365 @@
+//! KPI target tests
+//!
+//! Tests for seasonal KPI targets and the actuals-vs-target report:
+//! - Crop seasons run from October to the end of September
+//! - Season elapsed is clamped to the season
+//! - Averages come from cupping sample totals and are empty without samples
+//! - Cumulative KPIs are judged against the target prorated by the season
+//!   elapsed; averages against the target itself
+//! - Trends against the previous season with a flat band
+//! - Target validation
+
+use chrono::{Datelike, NaiveDate};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+const SEASON_START_MONTH: u32 = 10;
+const FLAT_TREND_PCT: Decimal = Decimal::from_parts(1, 0, 0, false, 0);
+
+/// Mirrors `KpiKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KpiKind {
+    CherryKg,
+    AvgScore,
+    SpecialtyPct,
+    Revenue,
+}
+
+impl KpiKind {
+    fn is_cumulative(&self) -> bool {
+        matches!(self, KpiKind::CherryKg | KpiKind::Revenue)
+    }
+}
+
+/// Mirrors `KpiTrend`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KpiTrend {
+    Up,
+    Down,
+    Flat,
+}
+
+/// Mirrors `KpiActuals`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct KpiActuals {
+    cherry_kg: Decimal,
+    sample_count: i64,
+    specialty_count: i64,
+    score_total: Decimal,
+    revenue: Decimal,
+}
+
+impl KpiActuals {
+    fn value(&self, kpi: KpiKind) -> Option<Decimal> {
+        match kpi {
+            KpiKind::CherryKg => Some(self.cherry_kg),
+            KpiKind::Revenue => Some(self.revenue),
+            KpiKind::AvgScore => {
+                (self.sample_count > 0).then(|| (self.score_total / Decimal::from(self.sample_count)).round_dp(2))
+            }
+            KpiKind::SpecialtyPct => (self.sample_count > 0).then(|| {
+                (Decimal::from(self.specialty_count) * Decimal::ONE_HUNDRED / Decimal::from(self.sample_count))
+                    .round_dp(2)
+            }),
+        }
+    }
+
+    fn add(&mut self, other: &KpiActuals) {
+        self.cherry_kg += other.cherry_kg;
+        self.sample_count += other.sample_count;
+        self.specialty_count += other.specialty_count;
+        self.score_total += other.score_total;
+        self.revenue += other.revenue;
+    }
+}
+
+/// Mirrors `KpiMetric`
+#[derive(Debug, Clone)]
+struct KpiMetric {
+    variance: Option<Decimal>,
+    percent_of_target: Option<Decimal>,
+    expected_to_date: Option<Decimal>,
+    on_track: Option<bool>,
+    trend: Option<KpiTrend>,
+}
+
+/// Mirrors `KpiTargetsInput`
+#[derive(Debug, Default)]
+struct KpiTargetsInput {
+    target_cherry_kg: Option<Decimal>,
+    target_avg_score: Option<Decimal>,
+    target_specialty_pct: Option<Decimal>,
+    target_revenue: Option<Decimal>,
+    currency: Option<String>,
+}
+
+/// Mirrors `crop_season`
+fn crop_season(date: NaiveDate) -> i32 {
+    if date.month() >= SEASON_START_MONTH {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
+/// Mirrors `season_bounds`
+fn season_bounds(season: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(season, SEASON_START_MONTH, 1)?;
+    let end = NaiveDate::from_ymd_opt(season + 1, SEASON_START_MONTH, 1)?.pred_opt()?;
+    Some((start, end))
+}
+
+/// Mirrors `season_elapsed`
+fn season_elapsed(start: NaiveDate, end: NaiveDate, as_of: NaiveDate) -> Decimal {
+    let total = (end - start).num_days() + 1;
+    let elapsed = ((as_of - start).num_days() + 1).clamp(0, total);
+    (Decimal::from(elapsed) / Decimal::from(total)).round_dp(4)
+}
+
+/// Mirrors `kpi_trend`
+fn kpi_trend(actual: Decimal, previous: Decimal) -> KpiTrend {
+    let tolerance = previous.abs() * FLAT_TREND_PCT / Decimal::ONE_HUNDRED;
+    let change = actual - previous;
+    if change > tolerance {
+        KpiTrend::Up
+    } else if change < -tolerance {
+        KpiTrend::Down
+    } else {
+        KpiTrend::Flat
+    }
+}
+
+/// Mirrors `kpi_metric`
+fn kpi_metric(
+    kpi: KpiKind,
+    target: Option<Decimal>,
+    actual: Option<Decimal>,
+    previous: Option<Decimal>,
+    elapsed: Decimal,
+) -> KpiMetric {
+    let expected_to_date = target.map(|t| if kpi.is_cumulative() { (t * elapsed).round_dp(2) } else { t });
+    let (variance, percent_of_target, on_track) = match (target, actual, expected_to_date) {
+        (Some(target), Some(actual), Some(expected)) => (
+            Some(actual - target),
+            (target > Decimal::ZERO).then(|| (actual * Decimal::ONE_HUNDRED / target).round_dp(1)),
+            Some(actual >= expected),
+        ),
+        _ => (None, None, None),
+    };
+    let trend = match (actual, previous) {
+        (Some(actual), Some(previous)) => Some(kpi_trend(actual, previous)),
+        _ => None,
+    };
+    KpiMetric {
+        variance,
+        percent_of_target,
+        expected_to_date,
+        on_track,
+        trend,
+    }
+}
+
+/// Mirrors `validate_targets`; returns the failing field
+fn validate_targets(input: &KpiTargetsInput) -> Result<String, &'static str> {
+    let targets = [
+        ("target_cherry_kg", input.target_cherry_kg),
+        ("target_avg_score", input.target_avg_score),
+        ("target_specialty_pct", input.target_specialty_pct),
+        ("target_revenue", input.target_revenue),
+    ];
+    if targets.iter().all(|(_, target)| target.is_none()) {
+        return Err("targets");
+    }
+    for (field, target) in targets {
+        if target.is_some_and(|t| t <= Decimal::ZERO) {
+            return Err(field);
+        }
+    }
+    for (field, target) in [
+        ("target_avg_score", input.target_avg_score),
+        ("target_specialty_pct", input.target_specialty_pct),
+    ] {
+        if target.is_some_and(|t| t > Decimal::ONE_HUNDRED) {
+            return Err(field);
+        }
+    }
+    let currency = input.currency.as_deref().unwrap_or("THB").trim().to_uppercase();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("currency");
+    }
+    Ok(currency)
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_season_bounds() {
+        assert_eq!(season_bounds(2024), Some((date(2024, 10, 1), date(2025, 9, 30))));
+        assert_eq!(crop_season(date(2025, 9, 30)), 2024);
+        assert_eq!(crop_season(date(2025, 10, 1)), 2025);
+    }
+
+    #[test]
+    fn test_season_elapsed_clamped() {
+        let (start, end) = season_bounds(2024).unwrap();
+        assert_eq!(season_elapsed(start, end, date(2024, 9, 15)), Decimal::ZERO);
+        assert_eq!(season_elapsed(start, end, end), Decimal::ONE);
+        assert_eq!(season_elapsed(start, end, date(2026, 1, 1)), Decimal::ONE);
+        // 2024/25 has 365 days; the first day counts as run
+        assert_eq!(season_elapsed(start, end, start), Decimal::new(27, 4));
+    }
+
+    #[test]
+    fn test_averages_from_samples() {
+        let actuals = KpiActuals {
+            cherry_kg: Decimal::from(1200),
+            sample_count: 4,
+            specialty_count: 3,
+            score_total: Decimal::new(33100, 2),
+            revenue: Decimal::from(50000),
+        };
+        assert_eq!(actuals.value(KpiKind::AvgScore), Some(Decimal::new(8275, 2)));
+        assert_eq!(actuals.value(KpiKind::SpecialtyPct), Some(Decimal::from(75)));
+        assert_eq!(actuals.value(KpiKind::CherryKg), Some(Decimal::from(1200)));
+
+        let empty = KpiActuals::default();
+        assert_eq!(empty.value(KpiKind::AvgScore), None);
+        assert_eq!(empty.value(KpiKind::SpecialtyPct), None);
+        assert_eq!(empty.value(KpiKind::Revenue), Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_average_over_months_weights_samples() {
+        let mut season = KpiActuals::default();
+        season.add(&KpiActuals { sample_count: 1, score_total: Decimal::from(90), ..Default::default() });
+        season.add(&KpiActuals { sample_count: 3, score_total: Decimal::from(240), ..Default::default() });
+        assert_eq!(season.value(KpiKind::AvgScore), Some(Decimal::new(8250, 2)));
+    }
+
+    #[test]
+    fn test_cumulative_kpi_prorated() {
+        // Halfway through the season with 45% of the cherry target in
+        let metric = kpi_metric(
+            KpiKind::CherryKg,
+            Some(Decimal::from(40000)),
+            Some(Decimal::from(18000)),
+            None,
+            Decimal::new(5, 1),
+        );
+        assert_eq!(metric.expected_to_date, Some(Decimal::from(20000)));
+        assert_eq!(metric.variance, Some(Decimal::from(-22000)));
+        assert_eq!(metric.percent_of_target, Some(Decimal::from(45)));
+        assert_eq!(metric.on_track, Some(false));
+        assert_eq!(metric.trend, None);
+    }
+
+    #[test]
+    fn test_average_kpi_against_target() {
+        let metric = kpi_metric(
+            KpiKind::AvgScore,
+            Some(Decimal::from(84)),
+            Some(Decimal::new(8450, 2)),
+            Some(Decimal::from(83)),
+            Decimal::new(1, 1),
+        );
+        assert_eq!(metric.expected_to_date, Some(Decimal::from(84)));
+        assert_eq!(metric.on_track, Some(true));
+        assert_eq!(metric.trend, Some(KpiTrend::Up));
+    }
+
+    #[test]
+    fn test_untracked_kpi() {
+        let metric = kpi_metric(KpiKind::Revenue, None, Some(Decimal::from(1000)), Some(Decimal::from(900)), Decimal::ONE);
+        assert_eq!(metric.variance, None);
+        assert_eq!(metric.on_track, None);
+        assert_eq!(metric.trend, Some(KpiTrend::Up));
+
+        let no_samples = kpi_metric(KpiKind::SpecialtyPct, Some(Decimal::from(60)), None, None, Decimal::ONE);
+        assert_eq!(no_samples.on_track, None);
+        assert_eq!(no_samples.percent_of_target, None);
+    }
+
+    #[test]
+    fn test_trend_flat_band() {
+        assert_eq!(kpi_trend(Decimal::new(8450, 2), Decimal::new(8400, 2)), KpiTrend::Flat);
+        assert_eq!(kpi_trend(Decimal::from(86), Decimal::from(84)), KpiTrend::Up);
+        assert_eq!(kpi_trend(Decimal::from(82), Decimal::from(84)), KpiTrend::Down);
+        assert_eq!(kpi_trend(Decimal::from(10), Decimal::ZERO), KpiTrend::Up);
+        assert_eq!(kpi_trend(Decimal::ZERO, Decimal::ZERO), KpiTrend::Flat);
+    }
+
+    #[test]
+    fn test_validate_targets() {
+        assert_eq!(validate_targets(&KpiTargetsInput::default()), Err("targets"));
+        assert_eq!(
+            validate_targets(&KpiTargetsInput { target_cherry_kg: Some(Decimal::ZERO), ..Default::default() }),
+            Err("target_cherry_kg")
+        );
+        assert_eq!(
+            validate_targets(&KpiTargetsInput { target_specialty_pct: Some(Decimal::from(120)), ..Default::default() }),
+            Err("target_specialty_pct")
+        );
+        assert_eq!(
+            validate_targets(&KpiTargetsInput {
+                target_revenue: Some(Decimal::from(500000)),
+                currency: Some("baht".to_string()),
+                ..Default::default()
+            }),
+            Err("currency")
+        );
+        assert_eq!(
+            validate_targets(&KpiTargetsInput {
+                target_avg_score: Some(Decimal::from(84)),
+                currency: Some(" usd ".to_string()),
+                ..Default::default()
+            }),
+            Ok("USD".to_string())
+        );
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_season_contains_its_dates(year in 2000i32..2100, ordinal in 1u32..=365) {
+        let day = NaiveDate::from_yo_opt(year, ordinal).unwrap();
+        let (start, end) = season_bounds(crop_season(day)).unwrap();
+        prop_assert!(start <= day && day <= end);
+        let elapsed = season_elapsed(start, end, day);
+        prop_assert!(elapsed > Decimal::ZERO && elapsed <= Decimal::ONE);
+    }
+
+    #[test]
+    fn prop_cumulative_on_track_matches_prorated_target(
+        target in 1i64..1_000_000,
+        actual in 0i64..1_000_000,
+        elapsed_bp in 0i64..=10_000,
+    ) {
+        let elapsed = Decimal::new(elapsed_bp, 4);
+        let metric = kpi_metric(KpiKind::Revenue, Some(Decimal::from(target)), Some(Decimal::from(actual)), None, elapsed);
+        let expected = (Decimal::from(target) * elapsed).round_dp(2);
+        prop_assert_eq!(metric.on_track, Some(Decimal::from(actual) >= expected));
+        prop_assert_eq!(metric.variance, Some(Decimal::from(actual - target)));
+    }
+
+    #[test]
+    fn prop_trend_is_antisymmetric_outside_band(a in 0i64..10_000, b in 0i64..10_000) {
+        let (a, b) = (Decimal::from(a), Decimal::from(b));
+        if kpi_trend(a, b) == KpiTrend::Up {
+            prop_assert_ne!(kpi_trend(b, a), KpiTrend::Up);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Lab result tests
+//!
+//! Tests for judging third-party analyses:
+//! - Default limits per analysis (moisture range, water activity, OTA)
+//! - Limits given with a result replace the defaults
+//! - Values on a limit pass
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Mirrors `LabAnalysis`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LabAnalysis {
+    Moisture,
+    WaterActivity,
+    OchratoxinA,
+    PesticideResidue,
+}
+
+/// Mirrors `LabAnalysis::default_limits`
+fn default_limits(analysis: LabAnalysis) -> (Option<Decimal>, Option<Decimal>) {
+    match analysis {
+        LabAnalysis::Moisture => (Some(dec("10")), Some(dec("12"))),
+        LabAnalysis::WaterActivity => (None, Some(dec("0.70"))),
+        LabAnalysis::OchratoxinA => (None, Some(dec("5"))),
+        LabAnalysis::PesticideResidue => (None, None),
+    }
+}
+
+/// Mirrors `within_limits`
+fn within_limits(value: Decimal, min: Option<Decimal>, max: Option<Decimal>) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+/// Mirrors the verdict in `LabResultService::record`
+fn judge(analysis: LabAnalysis, value: Decimal, min_limit: Option<Decimal>, max_limit: Option<Decimal>) -> bool {
+    let (default_min, default_max) = default_limits(analysis);
+    within_limits(value, min_limit.or(default_min), max_limit.or(default_max))
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_moisture_range() {
+        assert!(judge(LabAnalysis::Moisture, dec("11.2"), None, None));
+        assert!(!judge(LabAnalysis::Moisture, dec("9.4"), None, None));
+        assert!(!judge(LabAnalysis::Moisture, dec("12.8"), None, None));
+    }
+
+    #[test]
+    fn test_water_activity_limit() {
+        assert!(judge(LabAnalysis::WaterActivity, dec("0.58"), None, None));
+        assert!(!judge(LabAnalysis::WaterActivity, dec("0.72"), None, None));
+    }
+
+    #[test]
+    fn test_ochratoxin_limit() {
+        assert!(judge(LabAnalysis::OchratoxinA, dec("1.8"), None, None));
+        assert!(!judge(LabAnalysis::OchratoxinA, dec("6.1"), None, None));
+    }
+
+    #[test]
+    fn test_value_on_the_limit_passes() {
+        assert!(judge(LabAnalysis::Moisture, dec("12"), None, None));
+        assert!(judge(LabAnalysis::OchratoxinA, dec("5"), None, None));
+    }
+
+    #[test]
+    fn test_buyer_limit_replaces_default() {
+        // A buyer asking for OTA below 3 µg/kg
+        assert!(!judge(LabAnalysis::OchratoxinA, dec("4"), None, Some(dec("3"))));
+    }
+
+    #[test]
+    fn test_pesticide_residue_against_mrl() {
+        assert!(judge(LabAnalysis::PesticideResidue, dec("0.05"), None, Some(dec("0.1"))));
+        assert!(!judge(LabAnalysis::PesticideResidue, dec("0.2"), None, Some(dec("0.1"))));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_verdict_matches_range(value in 0u32..2000, min in 0u32..1000, span in 0u32..1000) {
+        let value = Decimal::new(value as i64, 2);
+        let min = Decimal::new(min as i64, 2);
+        let max = min + Decimal::new(span as i64, 2);
+
+        prop_assert_eq!(within_limits(value, Some(min), Some(max)), value >= min && value <= max);
+        prop_assert!(within_limits(value, None, None));
+    }
+}
@@ -0,0 +1,167 @@
+//! Label sheet tests
+//!
+//! Tests for the layout behind bulk label printing:
+//! - Label positions on A4 sheets and thermal rolls
+//! - Splitting a shipment item's weight over its packages
+//! - Sample label codes
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+const A4_WIDTH_MM: f32 = 210.0;
+const A4_HEIGHT_MM: f32 = 297.0;
+
+/// Mirrors `LabelSheet`
+#[derive(Debug, Clone, Copy)]
+struct LabelSheet {
+    page_height: f32,
+    columns: usize,
+    rows: usize,
+    label_width: f32,
+    label_height: f32,
+}
+
+impl LabelSheet {
+    fn a4(columns: usize, rows: usize, label_height: f32) -> Self {
+        Self {
+            page_height: A4_HEIGHT_MM,
+            columns,
+            rows,
+            label_width: A4_WIDTH_MM / columns as f32,
+            label_height,
+        }
+    }
+
+    fn roll(label_width: f32, label_height: f32) -> Self {
+        Self { page_height: label_height, columns: 1, rows: 1, label_width, label_height }
+    }
+
+    fn labels_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Mirrors `LabelSheet::position`
+    fn position(&self, index: usize) -> (usize, f32, f32) {
+        let slot = index % self.labels_per_page();
+        let top = (self.page_height - self.rows as f32 * self.label_height) / 2.0;
+        (
+            index / self.labels_per_page(),
+            (slot % self.columns) as f32 * self.label_width,
+            top + (slot / self.columns) as f32 * self.label_height,
+        )
+    }
+}
+
+/// Mirrors `LabelLayout::sheet` for the A4 layouts
+fn a4_layouts() -> [LabelSheet; 3] {
+    [LabelSheet::a4(3, 8, 37.0), LabelSheet::a4(2, 7, 42.0), LabelSheet::a4(2, 4, 74.0)]
+}
+
+/// Mirrors `package_weights`
+fn package_weights(quantity_kg: Decimal, package_count: u32) -> Vec<Decimal> {
+    if package_count == 0 {
+        return Vec::new();
+    }
+    let each = (quantity_kg / Decimal::from(package_count)).round_dp(3);
+    let mut weights = vec![each; package_count as usize];
+    if let Some(last) = weights.last_mut() {
+        *last = quantity_kg - each * Decimal::from(package_count - 1);
+    }
+    weights
+}
+
+/// Mirrors `sample_code`
+fn sample_code(id: Uuid) -> String {
+    format!("S-{}", &id.simple().to_string()[..8]).to_uppercase()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_a4_sheets_fit_the_page() {
+        for sheet in a4_layouts() {
+            assert!(sheet.rows as f32 * sheet.label_height <= A4_HEIGHT_MM);
+            assert!((sheet.columns as f32 * sheet.label_width - A4_WIDTH_MM).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_labels_fill_rows_left_to_right() {
+        let sheet = LabelSheet::a4(3, 8, 37.0);
+        assert_eq!(sheet.position(0), (0, 0.0, 0.5));
+        assert_eq!(sheet.position(1), (0, 70.0, 0.5));
+        assert_eq!(sheet.position(3), (0, 0.0, 37.5));
+        assert_eq!(sheet.position(23), (0, 140.0, 259.5));
+    }
+
+    #[test]
+    fn test_next_sheet_after_a_full_one() {
+        let sheet = LabelSheet::a4(3, 8, 37.0);
+        assert_eq!(sheet.position(24), (1, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_roll_prints_one_label_per_page() {
+        let roll = LabelSheet::roll(100.0, 50.0);
+        assert_eq!(roll.position(0), (0, 0.0, 0.0));
+        assert_eq!(roll.position(7), (7, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_package_weights_split_evenly() {
+        assert_eq!(package_weights(dec("1200"), 20), vec![dec("60"); 20]);
+    }
+
+    #[test]
+    fn test_last_package_takes_the_remainder() {
+        assert_eq!(package_weights(dec("100"), 3), vec![dec("33.333"), dec("33.333"), dec("33.334")]);
+    }
+
+    #[test]
+    fn test_no_packages() {
+        assert!(package_weights(dec("60"), 0).is_empty());
+    }
+
+    #[test]
+    fn test_sample_code() {
+        let id = Uuid::parse_str("9f3a2b1c-0000-4000-8000-000000000000").unwrap();
+        assert_eq!(sample_code(id), "S-9F3A2B1C");
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    /// Package weights add up to the shipment item's weight
+    #[test]
+    fn prop_package_weights_sum_to_quantity(grams in 1i64..100_000_000, count in 1u32..500) {
+        let quantity = Decimal::new(grams, 3);
+        let weights = package_weights(quantity, count);
+        prop_assert_eq!(weights.len(), count as usize);
+        prop_assert_eq!(weights.iter().sum::<Decimal>(), quantity);
+    }
+
+    /// Every label lies within its page
+    #[test]
+    fn prop_labels_stay_on_the_page(index in 0usize..1000, layout in 0usize..3) {
+        let sheet = a4_layouts()[layout];
+        let (page, x, y) = sheet.position(index);
+        prop_assert_eq!(page, index / sheet.labels_per_page());
+        prop_assert!(x >= 0.0 && x + sheet.label_width <= A4_WIDTH_MM + 0.01);
+        prop_assert!(y >= 0.0 && y + sheet.label_height <= A4_HEIGHT_MM + 0.01);
+    }
+}
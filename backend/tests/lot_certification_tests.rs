@@ -0,0 +1,204 @@
+//! Lot certification claim tests
+//!
+//! Tests for segregating certified lots:
+//! - A harvest supports a claim when a certification covers its plot on the date
+//! - Blends carry only the claims of every source lot, minus downgrades
+//! - Claims some but not all source lots carry are mixed
+//! - The derivation explains the first rule a claim fails
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+const CLAIM_TYPES: [&str; 7] = [
+    "thai_gap",
+    "organic_thailand",
+    "usda_organic",
+    "fair_trade",
+    "rainforest_alliance",
+    "utz",
+    "other",
+];
+
+/// Mirrors `ClaimCertification`
+#[derive(Clone)]
+struct Cert {
+    certification_type: &'static str,
+    plot_id: Option<u32>,
+    issue_date: NaiveDate,
+    expiration_date: NaiveDate,
+}
+
+/// Mirrors `ClaimData`
+#[derive(Default)]
+struct Data {
+    certifications: Vec<Cert>,
+    harvests: HashMap<u32, Vec<(u32, NaiveDate)>>,
+    sources: HashMap<u32, Vec<u32>>,
+    downgraded: HashMap<u32, BTreeSet<String>>,
+}
+
+/// Mirrors `covers`; `plot_id` set means plot scope
+fn covers(cert: &Cert, plot_id: u32, date: NaiveDate) -> bool {
+    let in_scope = cert.plot_id.is_none_or(|p| p == plot_id);
+    in_scope && cert.issue_date <= date && date <= cert.expiration_date
+}
+
+/// Mirrors `harvest_claims`
+fn harvest_claims(certs: &[Cert], harvests: &[(u32, NaiveDate)]) -> BTreeSet<String> {
+    let mut claims: BTreeSet<String> = certs.iter().map(|c| c.certification_type.to_string()).collect();
+    for (plot_id, date) in harvests {
+        claims.retain(|claim| certs.iter().any(|c| c.certification_type == claim && covers(c, *plot_id, *date)));
+    }
+    claims
+}
+
+/// Mirrors `lot_claims`
+fn lot_claims(lot_id: u32, data: &Data) -> BTreeSet<String> {
+    fn walk(lot_id: u32, data: &Data, visiting: &mut HashSet<u32>) -> BTreeSet<String> {
+        if !visiting.insert(lot_id) {
+            return BTreeSet::new();
+        }
+        let harvests = data.harvests.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let sources = data.sources.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let mut claims = if harvests.is_empty() && sources.is_empty() {
+            BTreeSet::new()
+        } else if harvests.is_empty() {
+            CLAIM_TYPES.iter().map(|c| c.to_string()).collect()
+        } else {
+            harvest_claims(&data.certifications, harvests)
+        };
+        for source in sources {
+            let source_claims = walk(*source, data, visiting);
+            claims.retain(|c| source_claims.contains(c));
+        }
+        if let Some(downgraded) = data.downgraded.get(&lot_id) {
+            claims.retain(|c| !downgraded.contains(c));
+        }
+        visiting.remove(&lot_id);
+        claims
+    }
+    walk(lot_id, data, &mut HashSet::new())
+}
+
+/// Mirrors `mixed_claims`
+fn mixed_claims(claims: &[BTreeSet<String>]) -> BTreeSet<String> {
+    let union: BTreeSet<String> = claims.iter().flatten().cloned().collect();
+    union.into_iter().filter(|claim| !claims.iter().all(|c| c.contains(claim))).collect()
+}
+
+/// Mirrors the rule order of `derive_claim`'s explanation
+fn explanation(downgraded: bool, harvests: usize, uncovered: usize, sources: usize, missing: usize, claimed: bool) -> &'static str {
+    if downgraded {
+        "downgraded"
+    } else if harvests == 0 && sources == 0 {
+        "no harvests"
+    } else if uncovered > 0 {
+        "uncovered harvests"
+    } else if missing > 0 {
+        "sources without claim"
+    } else if claimed {
+        "claimed"
+    } else {
+        "earlier sources without claim"
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn set(claims: &[&str]) -> BTreeSet<String> {
+    claims.iter().map(|c| c.to_string()).collect()
+}
+
+/// Business-wide Thai GAP and organic on plot 1 only, both for 2024
+fn certifications() -> Vec<Cert> {
+    vec![
+        Cert { certification_type: "thai_gap", plot_id: None, issue_date: date(2024, 1, 1), expiration_date: date(2024, 12, 31) },
+        Cert { certification_type: "organic_thailand", plot_id: Some(1), issue_date: date(2024, 1, 1), expiration_date: date(2024, 12, 31) },
+    ]
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_harvest_claims_follow_plot_scope_and_dates() {
+        let certs = certifications();
+        assert_eq!(harvest_claims(&certs, &[(1, date(2024, 11, 1))]), set(&["organic_thailand", "thai_gap"]));
+        assert_eq!(harvest_claims(&certs, &[(2, date(2024, 11, 1))]), set(&["thai_gap"]));
+        assert!(harvest_claims(&certs, &[(1, date(2025, 1, 5))]).is_empty());
+    }
+
+    #[test]
+    fn test_one_uncertified_harvest_loses_the_claim() {
+        let certs = certifications();
+        let harvests = [(1, date(2024, 11, 1)), (2, date(2024, 11, 2))];
+        assert_eq!(harvest_claims(&certs, &harvests), set(&["thai_gap"]));
+    }
+
+    #[test]
+    fn test_blend_keeps_common_claims_minus_downgrades() {
+        let mut data = Data { certifications: certifications(), ..Default::default() };
+        data.harvests.insert(10, vec![(1, date(2024, 11, 1))]);
+        data.harvests.insert(11, vec![(2, date(2024, 11, 1))]);
+        data.sources.insert(20, vec![10, 11]);
+        assert_eq!(lot_claims(20, &data), set(&["thai_gap"]));
+
+        data.downgraded.insert(20, set(&["thai_gap"]));
+        assert!(lot_claims(20, &data).is_empty());
+    }
+
+    #[test]
+    fn test_mixed_claims() {
+        let organic = set(&["organic_thailand", "thai_gap"]);
+        let gap = set(&["thai_gap"]);
+        assert_eq!(mixed_claims(&[organic.clone(), gap]), set(&["organic_thailand"]));
+        assert!(mixed_claims(&[organic.clone(), organic]).is_empty());
+    }
+
+    #[test]
+    fn test_explanation_names_first_failing_rule() {
+        assert_eq!(explanation(true, 2, 1, 0, 0, false), "downgraded");
+        assert_eq!(explanation(false, 0, 0, 0, 0, false), "no harvests");
+        assert_eq!(explanation(false, 3, 1, 2, 1, false), "uncovered harvests");
+        assert_eq!(explanation(false, 0, 0, 2, 1, false), "sources without claim");
+        assert_eq!(explanation(false, 2, 0, 0, 0, true), "claimed");
+    }
+
+    #[test]
+    fn test_lot_without_harvests_or_sources_claims_nothing() {
+        let data = Data { certifications: certifications(), ..Default::default() };
+        assert!(lot_claims(99, &data).is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_blend_claims_within_every_source(plots in prop::collection::vec(1u32..4, 1..6)) {
+        let mut data = Data { certifications: certifications(), ..Default::default() };
+        for (i, plot) in plots.iter().enumerate() {
+            data.harvests.insert(i as u32, vec![(*plot, date(2024, 12, 1))]);
+        }
+        data.sources.insert(100, (0..plots.len() as u32).collect());
+        let blend = lot_claims(100, &data);
+        let sources: Vec<BTreeSet<String>> = (0..plots.len() as u32).map(|i| lot_claims(i, &data)).collect();
+        for source in &sources {
+            prop_assert!(blend.is_subset(source));
+        }
+        // Nothing mixed means the blend keeps every source claim
+        if mixed_claims(&sources).is_empty() {
+            prop_assert_eq!(&blend, &sources[0]);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Lot photo gallery tests
+//!
+//! Tests for the photos of a lot:
+//! - A new order names every photo of the lot once
+//! - The traceability page shows public photos only, cover first
+//! - Photos are stored under the lot in S3
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+/// Mirrors `PhotoVisibility`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PhotoVisibility {
+    Public,
+    Internal,
+}
+
+/// Mirrors `LotPhoto`, with the fields the gallery order uses
+#[derive(Debug, Clone, PartialEq)]
+struct LotPhoto {
+    id: Uuid,
+    visibility: PhotoVisibility,
+    sort_order: i32,
+    is_cover: bool,
+    created_at: DateTime<Utc>,
+}
+
+/// Mirrors `validate_photo_order`, with the error as None
+fn validate_photo_order(photo_ids: &[Uuid], order: &[Uuid]) -> Option<()> {
+    let mut seen: Vec<Uuid> = Vec::with_capacity(order.len());
+    for id in order {
+        if !photo_ids.contains(id) || seen.contains(id) {
+            return None;
+        }
+        seen.push(*id);
+    }
+    (seen.len() == photo_ids.len()).then_some(())
+}
+
+/// Mirrors `public_gallery`
+fn public_gallery(photos: &[LotPhoto]) -> Vec<&LotPhoto> {
+    let mut public: Vec<&LotPhoto> = photos
+        .iter()
+        .filter(|p| p.visibility == PhotoVisibility::Public)
+        .collect();
+    public.sort_by_key(|p| (!p.is_cover, p.sort_order, p.created_at));
+    public
+}
+
+/// Mirrors `lot_photo_key`
+fn lot_photo_key(business_id: Uuid, lot_id: Uuid, photo_id: Uuid, content_type: &str) -> String {
+    let extension = match content_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        _ => "jpg",
+    };
+    format!("lots/{}/{}/{}.{}", business_id, lot_id, photo_id, extension)
+}
+
+fn photo(sort_order: i32, visibility: PhotoVisibility, is_cover: bool) -> LotPhoto {
+    LotPhoto {
+        id: Uuid::new_v4(),
+        visibility,
+        sort_order,
+        is_cover,
+        created_at: Utc.with_ymd_and_hms(2024, 12, 1, 8, 0, 0).unwrap() + Duration::minutes(sort_order as i64),
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_order_names_every_photo_once() {
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        assert!(validate_photo_order(&ids, &[ids[2], ids[0], ids[1]]).is_some());
+        assert!(validate_photo_order(&ids, &[ids[2], ids[0]]).is_none());
+        assert!(validate_photo_order(&ids, &[ids[2], ids[0], ids[0]]).is_none());
+        assert!(validate_photo_order(&ids, &[ids[2], ids[0], ids[1], Uuid::new_v4()]).is_none());
+        assert!(validate_photo_order(&[], &[]).is_some());
+    }
+
+    #[test]
+    fn test_trace_page_shows_public_photos_in_order() {
+        let photos = vec![
+            photo(3, PhotoVisibility::Public, false),
+            photo(1, PhotoVisibility::Internal, false),
+            photo(2, PhotoVisibility::Public, false),
+        ];
+        let shown: Vec<i32> = public_gallery(&photos).iter().map(|p| p.sort_order).collect();
+        assert_eq!(shown, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_cover_comes_first() {
+        let photos = vec![
+            photo(1, PhotoVisibility::Public, false),
+            photo(2, PhotoVisibility::Public, false),
+            photo(3, PhotoVisibility::Public, true),
+        ];
+        let shown: Vec<i32> = public_gallery(&photos).iter().map(|p| p.sort_order).collect();
+        assert_eq!(shown, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_internal_defect_photos_stay_off_the_page() {
+        let photos = vec![photo(1, PhotoVisibility::Internal, false), photo(2, PhotoVisibility::Internal, false)];
+        assert!(public_gallery(&photos).is_empty());
+    }
+
+    #[test]
+    fn test_photos_stored_under_the_lot() {
+        let (business, lot, photo) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(lot_photo_key(business, lot, photo, "image/webp"), format!("lots/{}/{}/{}.webp", business, lot, photo));
+        assert!(lot_photo_key(business, lot, photo, "image/jpeg").ends_with(".jpg"));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_gallery_keeps_public_photos_with_one_cover_first(
+        photos in prop::collection::vec((any::<bool>(), 1i32..50), 0..20),
+        cover in any::<prop::sample::Index>()
+    ) {
+        let mut photos: Vec<LotPhoto> = photos
+            .iter()
+            .map(|(public, order)| {
+                photo(*order, if *public { PhotoVisibility::Public } else { PhotoVisibility::Internal }, false)
+            })
+            .collect();
+        let public_count = photos.iter().filter(|p| p.visibility == PhotoVisibility::Public).count();
+        if public_count > 0 {
+            let index = cover.index(public_count);
+            let cover_photo = photos.iter_mut().filter(|p| p.visibility == PhotoVisibility::Public).nth(index).unwrap();
+            cover_photo.is_cover = true;
+        }
+
+        let shown = public_gallery(&photos);
+        prop_assert_eq!(shown.len(), public_count);
+        prop_assert!(shown.iter().all(|p| p.visibility == PhotoVisibility::Public));
+        if let Some(first) = shown.first() {
+            prop_assert!(first.is_cover);
+        }
+        prop_assert!(shown.iter().skip(1).collect::<Vec<_>>().windows(2).all(|w| w[0].sort_order <= w[1].sort_order));
+    }
+
+    #[test]
+    fn prop_any_permutation_is_a_valid_order(n in 0usize..12, seed in any::<u64>()) {
+        let ids: Vec<Uuid> = (0..n).map(|_| Uuid::new_v4()).collect();
+        let mut order = ids.clone();
+        if n > 1 {
+            order.rotate_left((seed % n as u64) as usize);
+        }
+        prop_assert!(validate_photo_order(&ids, &order).is_some());
+        if n > 0 {
+            prop_assert!(validate_photo_order(&ids, &order[1..]).is_none());
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Lot insurance tests
+//!
+//! Tests for insurance policies per lot and shipment:
+//! - Status over the validity period (upcoming, active, expiring, expired)
+//! - Policy input validation (amounts, deductible, dates, currency)
+//! - Expiry reminders are sent once, inside the reminder window
+//! - Only valid policies marked for buyers go on the spec sheet
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+/// Mirrors `EXPIRY_ALERT_DAYS`
+const EXPIRY_ALERT_DAYS: i64 = 30;
+
+/// Mirrors `InsuranceStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsuranceStatus {
+    Upcoming,
+    Active,
+    Expiring,
+    Expired,
+}
+
+/// Mirrors `policy_status`
+fn policy_status(valid_from: NaiveDate, valid_until: NaiveDate, today: NaiveDate) -> InsuranceStatus {
+    if today < valid_from {
+        InsuranceStatus::Upcoming
+    } else if today > valid_until {
+        InsuranceStatus::Expired
+    } else if (valid_until - today).num_days() <= EXPIRY_ALERT_DAYS {
+        InsuranceStatus::Expiring
+    } else {
+        InsuranceStatus::Active
+    }
+}
+
+/// Mirrors the checks in `LotInsuranceService::record`, returning the field
+/// of the first failure
+fn validate_policy(
+    policy_number: &str,
+    insurer: &str,
+    insured_amount: Decimal,
+    deductible: Option<Decimal>,
+    valid_from: NaiveDate,
+    valid_until: NaiveDate,
+    currency: Option<&str>,
+) -> Result<String, &'static str> {
+    if policy_number.trim().is_empty() {
+        return Err("policy_number");
+    }
+    if insurer.trim().is_empty() {
+        return Err("insurer");
+    }
+    if insured_amount <= Decimal::ZERO {
+        return Err("insured_amount");
+    }
+    if deductible.is_some_and(|d| d < Decimal::ZERO || d > insured_amount) {
+        return Err("deductible_amount");
+    }
+    if valid_until < valid_from {
+        return Err("valid_until");
+    }
+    let currency = currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "THB".to_string());
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("currency");
+    }
+    Ok(currency)
+}
+
+/// Mirrors the selection in `trigger_insurance_expiry_alerts`
+fn needs_reminder(valid_until: NaiveDate, today: NaiveDate, already_sent: bool) -> bool {
+    !already_sent && valid_until >= today && (valid_until - today).num_days() <= EXPIRY_ALERT_DAYS
+}
+
+/// Mirrors the priority of `create_insurance_expiring_notification`
+fn reminder_priority(days_until: i32) -> i32 {
+    if days_until <= 7 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Mirrors the filter of `buyer_pack_policies`
+fn in_buyer_pack(include: bool, valid_from: NaiveDate, valid_until: NaiveDate, today: NaiveDate) -> bool {
+    include && valid_from <= today && today <= valid_until
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_status_over_validity_period() {
+        let (from, until) = (date(2025, 1, 1), date(2025, 12, 31));
+        assert_eq!(policy_status(from, until, date(2024, 12, 31)), InsuranceStatus::Upcoming);
+        assert_eq!(policy_status(from, until, date(2025, 1, 1)), InsuranceStatus::Active);
+        assert_eq!(policy_status(from, until, date(2025, 12, 1)), InsuranceStatus::Expiring);
+        assert_eq!(policy_status(from, until, date(2025, 12, 31)), InsuranceStatus::Expiring);
+        assert_eq!(policy_status(from, until, date(2026, 1, 1)), InsuranceStatus::Expired);
+    }
+
+    #[test]
+    fn test_short_policy_is_expiring_from_the_start() {
+        let from = date(2025, 3, 1);
+        assert_eq!(policy_status(from, date(2025, 3, 20), from), InsuranceStatus::Expiring);
+    }
+
+    #[test]
+    fn test_valid_policy_defaults_to_thb() {
+        let result = validate_policy("P-1", "Thaivivat", Decimal::from(500_000), None, date(2025, 1, 1), date(2025, 6, 30), None);
+        assert_eq!(result, Ok("THB".to_string()));
+        let result = validate_policy("P-1", "Thaivivat", Decimal::from(1), None, date(2025, 1, 1), date(2025, 1, 1), Some(" usd "));
+        assert_eq!(result, Ok("USD".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_policies_name_the_field() {
+        let (from, until) = (date(2025, 1, 1), date(2025, 6, 30));
+        let amount = Decimal::from(100_000);
+        assert_eq!(validate_policy(" ", "X", amount, None, from, until, None), Err("policy_number"));
+        assert_eq!(validate_policy("P", "", amount, None, from, until, None), Err("insurer"));
+        assert_eq!(validate_policy("P", "X", Decimal::ZERO, None, from, until, None), Err("insured_amount"));
+        assert_eq!(
+            validate_policy("P", "X", amount, Some(Decimal::from(200_000)), from, until, None),
+            Err("deductible_amount")
+        );
+        assert_eq!(validate_policy("P", "X", amount, None, until, from, None), Err("valid_until"));
+        assert_eq!(validate_policy("P", "X", amount, None, from, until, Some("BAHT")), Err("currency"));
+    }
+
+    #[test]
+    fn test_reminder_window_and_once_only() {
+        let today = date(2025, 6, 1);
+        assert!(needs_reminder(date(2025, 7, 1), today, false));
+        assert!(needs_reminder(today, today, false));
+        assert!(!needs_reminder(date(2025, 7, 2), today, false));
+        assert!(!needs_reminder(date(2025, 5, 31), today, false));
+        assert!(!needs_reminder(date(2025, 6, 10), today, true));
+    }
+
+    #[test]
+    fn test_last_week_reminder_is_high_priority() {
+        assert_eq!(reminder_priority(7), 2);
+        assert_eq!(reminder_priority(8), 1);
+    }
+
+    #[test]
+    fn test_buyer_pack_shows_only_current_marked_policies() {
+        let today = date(2025, 6, 1);
+        assert!(in_buyer_pack(true, date(2025, 1, 1), date(2025, 12, 31), today));
+        assert!(!in_buyer_pack(false, date(2025, 1, 1), date(2025, 12, 31), today));
+        assert!(!in_buyer_pack(true, date(2024, 1, 1), date(2025, 5, 31), today));
+        assert!(!in_buyer_pack(true, date(2025, 7, 1), date(2025, 12, 31), today));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_reminded_policies_are_expiring(length in 0i64..400, offset in -30i64..430) {
+        let from = date(2025, 1, 1);
+        let until = from + chrono::Duration::days(length);
+        let today = from + chrono::Duration::days(offset);
+        if needs_reminder(until, today, false) && today >= from {
+            prop_assert_eq!(policy_status(from, until, today), InsuranceStatus::Expiring);
+        }
+    }
+
+    #[test]
+    fn prop_buyer_pack_policies_are_not_expired_or_upcoming(length in 0i64..400, offset in -30i64..430) {
+        let from = date(2025, 1, 1);
+        let until = from + chrono::Duration::days(length);
+        let today = from + chrono::Duration::days(offset);
+        let status = policy_status(from, until, today);
+        prop_assert_eq!(
+            in_buyer_pack(true, from, until, today),
+            matches!(status, InsuranceStatus::Active | InsuranceStatus::Expiring)
+        );
+    }
+}
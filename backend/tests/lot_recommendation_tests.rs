@@ -0,0 +1,294 @@
+//! Lot recommendation tests
+//!
+//! Tests for matching lots to an order:
+//! - Single lots must cover the whole quantity and meet every requirement
+//! - Current-crop lots rank ahead of past-crop lots, then by score
+//! - Blends fill the order from their best lots and reach the minimum score
+//! - Blends that would leave a component unused are not proposed
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+const PAST_CROP_DAYS: i32 = 365;
+
+/// Mirrors `LotCandidate`
+#[derive(Debug, Clone)]
+struct Candidate {
+    code: &'static str,
+    available_kg: Decimal,
+    cupping_score: Option<Decimal>,
+    process: Option<&'static str>,
+    certifications: Vec<&'static str>,
+    age_days: i32,
+}
+
+/// Mirrors `OrderSpec`
+#[derive(Debug, Clone, Default)]
+struct Order {
+    quantity_kg: Decimal,
+    min_cupping_score: Option<Decimal>,
+    process: Option<&'static str>,
+    certifications: Vec<&'static str>,
+    max_age_days: Option<i32>,
+}
+
+fn lot(code: &'static str, available_kg: &str, score: &str, age_days: i32) -> Candidate {
+    Candidate {
+        code,
+        available_kg: dec(available_kg),
+        cupping_score: Some(dec(score)),
+        process: Some("washed"),
+        certifications: Vec::new(),
+        age_days,
+    }
+}
+
+fn order(quantity_kg: &str, min_score: &str) -> Order {
+    Order {
+        quantity_kg: dec(quantity_kg),
+        min_cupping_score: Some(dec(min_score)),
+        ..Default::default()
+    }
+}
+
+/// Mirrors `meets_requirements`
+fn meets_requirements(lot: &Candidate, order: &Order) -> bool {
+    let process_ok = match order.process {
+        Some(process) => lot.process.is_some_and(|p| p.eq_ignore_ascii_case(process)),
+        None => true,
+    };
+    let certified = order.certifications.iter().all(|c| lot.certifications.contains(c));
+    let fresh = order.max_age_days.iter().all(|max| lot.age_days <= *max);
+    process_ok && certified && fresh
+}
+
+/// Mirrors `rank_lots`; returns lot codes in rank order
+fn rank_lots(candidates: &[Candidate], order: &Order) -> Vec<&'static str> {
+    let mut lots: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|lot| meets_requirements(lot, order) && lot.available_kg >= order.quantity_kg)
+        .filter(|lot| match order.min_cupping_score {
+            Some(min) => lot.cupping_score.is_some_and(|s| s >= min),
+            None => true,
+        })
+        .collect();
+    lots.sort_by(|a, b| {
+        (a.age_days > PAST_CROP_DAYS)
+            .cmp(&(b.age_days > PAST_CROP_DAYS))
+            .then(b.cupping_score.cmp(&a.cupping_score))
+            .then(a.age_days.cmp(&b.age_days))
+    });
+    lots.into_iter().map(|lot| lot.code).collect()
+}
+
+/// Mirrors `combinations`
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 || k > n {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let mut indices: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(indices.clone());
+        let Some(i) = (0..k).rev().find(|&i| indices[i] < n - k + i) else {
+            return result;
+        };
+        indices[i] += 1;
+        for j in i + 1..k {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+/// Mirrors `propose_blends`; returns (blended score, [(code, kg)])
+fn propose_blends(
+    candidates: &[Candidate],
+    order: &Order,
+    max_components: usize,
+) -> Vec<(Decimal, Vec<(&'static str, Decimal)>)> {
+    let mut scored: Vec<(&Candidate, Decimal)> = candidates
+        .iter()
+        .filter(|lot| meets_requirements(lot, order) && lot.available_kg > Decimal::ZERO)
+        .filter_map(|lot| lot.cupping_score.map(|score| (lot, score)))
+        .collect();
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.age_days.cmp(&b.0.age_days)));
+
+    let mut blends = Vec::new();
+    for size in 2..=max_components {
+        for combo in combinations(scored.len(), size) {
+            let mut remaining = order.quantity_kg;
+            let mut weighted = Decimal::ZERO;
+            let mut components = Vec::new();
+            for &i in &combo {
+                let (lot, score) = scored[i];
+                let quantity = lot.available_kg.min(remaining);
+                if quantity <= Decimal::ZERO {
+                    break;
+                }
+                remaining -= quantity;
+                weighted += quantity * score;
+                components.push((lot.code, quantity));
+            }
+            if remaining > Decimal::ZERO || components.len() < size {
+                continue;
+            }
+            let blended = (weighted / order.quantity_kg).round_dp(2);
+            if order.min_cupping_score.iter().all(|min| blended >= *min) {
+                blends.push((blended, components));
+            }
+        }
+    }
+    blends.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())));
+    blends
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_single_lots_need_full_quantity_and_score() {
+        let candidates = vec![
+            lot("A", "500", "86", 60),
+            lot("B", "200", "88", 60),
+            lot("C", "800", "82", 60),
+        ];
+        assert_eq!(rank_lots(&candidates, &order("300", "84")), vec!["A"]);
+        assert_eq!(rank_lots(&candidates, &order("300", "80")), vec!["A", "C"]);
+    }
+
+    #[test]
+    fn test_past_crop_ranks_after_current_crop() {
+        let candidates = vec![
+            lot("OLD", "500", "89", 420),
+            lot("NEW", "500", "85", 90),
+            lot("NEWER", "500", "85", 30),
+        ];
+        assert_eq!(rank_lots(&candidates, &order("100", "80")), vec!["NEWER", "NEW", "OLD"]);
+    }
+
+    #[test]
+    fn test_process_certification_and_age_filters() {
+        let mut organic = lot("ORG", "500", "85", 200);
+        organic.certifications = vec!["organic_thailand", "thai_gap"];
+        let mut natural = lot("NAT", "500", "87", 30);
+        natural.process = Some("natural");
+        let candidates = vec![organic, natural];
+
+        let mut spec = order("100", "80");
+        spec.process = Some("Washed");
+        assert_eq!(rank_lots(&candidates, &spec), vec!["ORG"]);
+
+        spec.certifications = vec!["organic_thailand"];
+        spec.process = None;
+        assert_eq!(rank_lots(&candidates, &spec), vec!["ORG"]);
+
+        spec.max_age_days = Some(180);
+        assert!(rank_lots(&candidates, &spec).is_empty());
+    }
+
+    #[test]
+    fn test_unscored_lots_only_without_minimum() {
+        let mut unscored = lot("U", "500", "0", 30);
+        unscored.cupping_score = None;
+        let candidates = vec![unscored, lot("S", "500", "83", 30)];
+
+        let mut spec = order("100", "80");
+        assert_eq!(rank_lots(&candidates, &spec), vec!["S"]);
+        spec.min_cupping_score = None;
+        assert_eq!(rank_lots(&candidates, &spec), vec!["S", "U"]);
+    }
+
+    #[test]
+    fn test_blend_takes_best_lots_first() {
+        let candidates = vec![
+            lot("A", "200", "88", 30),
+            lot("B", "300", "82", 30),
+            lot("C", "400", "80", 30),
+        ];
+        let blends = propose_blends(&candidates, &order("400", "84"), 2);
+        // A+B: 200 x 88 + 200 x 82 = 85.00; A+C: 84.00; B+C: 81.50 fails
+        assert_eq!(blends.len(), 2);
+        assert_eq!(blends[0].0, dec("85"));
+        assert_eq!(blends[0].1, vec![("A", dec("200")), ("B", dec("200"))]);
+        assert_eq!(blends[1].0, dec("84"));
+    }
+
+    #[test]
+    fn test_blend_skips_unused_components() {
+        let candidates = vec![lot("BIG", "1000", "86", 30), lot("SMALL", "50", "84", 30)];
+        assert!(propose_blends(&candidates, &order("400", "80"), 3).is_empty());
+    }
+
+    #[test]
+    fn test_blend_needs_enough_stock() {
+        let candidates = vec![lot("A", "100", "86", 30), lot("B", "100", "85", 30)];
+        assert!(propose_blends(&candidates, &order("250", "80"), 2).is_empty());
+        assert_eq!(propose_blends(&candidates, &order("200", "80"), 2).len(), 1);
+    }
+
+    #[test]
+    fn test_combinations_count() {
+        assert_eq!(combinations(5, 2).len(), 10);
+        assert_eq!(combinations(12, 4).len(), 495);
+        assert_eq!(combinations(3, 3), vec![vec![0, 1, 2]]);
+        assert!(combinations(2, 3).is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+
+    fn candidates() -> impl Strategy<Value = Vec<Candidate>> {
+        proptest::collection::vec((1i64..=500, 7800i64..=9200, 0i32..=600), 2..=6).prop_map(|lots| {
+            lots.into_iter()
+                .map(|(kg, score, age)| Candidate {
+                    code: "L",
+                    available_kg: Decimal::from(kg),
+                    cupping_score: Some(Decimal::new(score, 2)),
+                    process: Some("washed"),
+                    certifications: Vec::new(),
+                    age_days: age,
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        /// Every proposed blend fills the order exactly, stays within each
+        /// lot's stock and meets the minimum score
+        #[test]
+        fn prop_blends_fill_order(
+            lots in candidates(),
+            quantity in 1i64..=1500,
+            min_score in 8000i64..=8800,
+        ) {
+            let spec = Order {
+                quantity_kg: Decimal::from(quantity),
+                min_cupping_score: Some(Decimal::new(min_score, 2)),
+                ..Default::default()
+            };
+            for (score, components) in propose_blends(&lots, &spec, 3) {
+                let total: Decimal = components.iter().map(|(_, kg)| *kg).sum();
+                prop_assert_eq!(total, spec.quantity_kg);
+                prop_assert!(components.iter().all(|(_, kg)| *kg > Decimal::ZERO));
+                prop_assert!(score >= spec.min_cupping_score.unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Lot stage reconciliation tests
+//!
+//! Tests for re-deriving a lot's stage from its records:
+//! - Completed processing or a grading means green bean, a completed roast
+//!   roasted bean
+//! - Only stages behind the records are flagged; sold lots and stages ahead
+//!   of the records are left alone
+//! - The record shown is the one implying the furthest stage, the latest on
+//!   a tie
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use shared::LotStage;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StageEvidence {
+    ProcessingCompleted,
+    Graded,
+    RoastCompleted,
+}
+
+impl StageEvidence {
+    /// Mirrors `StageEvidence::implied_stage`
+    fn implied_stage(&self) -> LotStage {
+        match self {
+            StageEvidence::ProcessingCompleted | StageEvidence::Graded => LotStage::GreenBean,
+            StageEvidence::RoastCompleted => LotStage::RoastedBean,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StageRecord {
+    evidence: StageEvidence,
+    record_id: Uuid,
+    date: NaiveDate,
+}
+
+/// Mirrors `furthest_record`
+fn furthest_record(records: &[StageRecord]) -> Option<&StageRecord> {
+    records
+        .iter()
+        .max_by_key(|record| (record.evidence.implied_stage(), record.date))
+}
+
+/// Mirrors `expected_stage`
+fn expected_stage(stage: LotStage, records: &[StageRecord]) -> Option<(LotStage, &StageRecord)> {
+    let record = furthest_record(records)?;
+    let expected = record.evidence.implied_stage();
+    (stage < expected).then_some((expected, record))
+}
+
+fn record(evidence: StageEvidence, y: i32, m: u32, d: u32) -> StageRecord {
+    StageRecord {
+        evidence,
+        record_id: Uuid::new_v4(),
+        date: NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_completed_processing_means_green_bean() {
+        let records = vec![record(StageEvidence::ProcessingCompleted, 2024, 2, 10)];
+        let (expected, shown) = expected_stage(LotStage::Cherry, &records).unwrap();
+        assert_eq!(expected, LotStage::GreenBean);
+        assert_eq!(shown, &records[0]);
+        assert_eq!(expected_stage(LotStage::Parchment, &records).map(|(stage, _)| stage), Some(LotStage::GreenBean));
+    }
+
+    #[test]
+    fn test_completed_roast_means_roasted_bean() {
+        let records = vec![
+            record(StageEvidence::ProcessingCompleted, 2024, 2, 10),
+            record(StageEvidence::Graded, 2024, 2, 20),
+            record(StageEvidence::RoastCompleted, 2024, 3, 1),
+        ];
+        let (expected, shown) = expected_stage(LotStage::GreenBean, &records).unwrap();
+        assert_eq!(expected, LotStage::RoastedBean);
+        assert_eq!(shown.evidence, StageEvidence::RoastCompleted);
+    }
+
+    #[test]
+    fn test_matching_stage_not_flagged() {
+        let records = vec![record(StageEvidence::Graded, 2024, 2, 20)];
+        assert!(expected_stage(LotStage::GreenBean, &records).is_none());
+    }
+
+    #[test]
+    fn test_stage_ahead_of_records_not_flagged() {
+        // Green coffee bought in and roasted without processing records
+        let records = vec![record(StageEvidence::Graded, 2024, 2, 20)];
+        assert!(expected_stage(LotStage::RoastedBean, &records).is_none());
+        assert!(expected_stage(LotStage::Sold, &records).is_none());
+    }
+
+    #[test]
+    fn test_lot_without_records_not_flagged() {
+        assert!(expected_stage(LotStage::Cherry, &[]).is_none());
+    }
+
+    #[test]
+    fn test_latest_record_shown_on_tie() {
+        let records = vec![
+            record(StageEvidence::Graded, 2024, 2, 20),
+            record(StageEvidence::ProcessingCompleted, 2024, 2, 10),
+        ];
+        assert_eq!(furthest_record(&records), Some(&records[0]));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_expected_stage_is_ahead_and_repair_settles(
+        stage in prop::sample::select(LotStage::ALL.to_vec()),
+        evidence in prop::collection::vec((0usize..3, 1u32..=28), 0..6),
+    ) {
+        let kinds = [StageEvidence::ProcessingCompleted, StageEvidence::Graded, StageEvidence::RoastCompleted];
+        let records: Vec<StageRecord> = evidence
+            .into_iter()
+            .map(|(kind, day)| record(kinds[kind], 2024, 3, day))
+            .collect();
+        if let Some((expected, _)) = expected_stage(stage, &records) {
+            prop_assert!(stage < expected);
+            prop_assert!(expected_stage(expected, &records).is_none());
+        }
+    }
+}
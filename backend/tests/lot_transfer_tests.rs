@@ -0,0 +1,169 @@
+//! Lot transfer tests
+//!
+//! Tests for the handshake selling a lot between two businesses:
+//! - Transferred quantity defaulting to what is not already offered
+//! - Only the buyer accepts or declines, only the seller cancels
+//! - Pending transfers only
+//! - Seller's lot marked sold once empty
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Mirrors `TransferStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Cancelled,
+}
+
+impl TransferStatus {
+    /// Mirrors `TransferStatus::as_str`
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransferStatus::Pending => "pending",
+            TransferStatus::Accepted => "accepted",
+            TransferStatus::Declined => "declined",
+            TransferStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Mirrors `TransferAction`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+/// Mirrors `TransferParty`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferParty {
+    Seller,
+    Buyer,
+}
+
+/// Mirrors `transfer_quantity`, with the error as None
+fn transfer_quantity(requested: Option<Decimal>, available: Decimal) -> Option<Decimal> {
+    let quantity = requested.unwrap_or(available);
+    if quantity <= Decimal::ZERO || quantity > available {
+        return None;
+    }
+    Some(quantity)
+}
+
+/// Mirrors `next_status`
+fn next_status(status: &str, party: TransferParty, action: TransferAction) -> Option<TransferStatus> {
+    if status != TransferStatus::Pending.as_str() {
+        return None;
+    }
+    match (party, action) {
+        (TransferParty::Buyer, TransferAction::Accept) => Some(TransferStatus::Accepted),
+        (TransferParty::Buyer, TransferAction::Decline) => Some(TransferStatus::Declined),
+        (TransferParty::Seller, TransferAction::Cancel) => Some(TransferStatus::Cancelled),
+        _ => None,
+    }
+}
+
+/// Mirrors `stage_after_transfer`
+fn stage_after_transfer(stage: &str, remaining_kg: Decimal) -> &str {
+    if remaining_kg <= Decimal::ZERO {
+        "sold"
+    } else {
+        stage
+    }
+}
+
+fn kg(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_whole_lot_by_default() {
+        assert_eq!(transfer_quantity(None, kg("480.5")), Some(kg("480.5")));
+        assert_eq!(transfer_quantity(Some(kg("200")), kg("480.5")), Some(kg("200")));
+    }
+
+    #[test]
+    fn test_quantity_within_available() {
+        assert_eq!(transfer_quantity(Some(kg("480.6")), kg("480.5")), None);
+        assert_eq!(transfer_quantity(Some(kg("0")), kg("480.5")), None);
+        assert_eq!(transfer_quantity(Some(kg("-5")), kg("480.5")), None);
+        // Everything already offered elsewhere
+        assert_eq!(transfer_quantity(None, kg("0")), None);
+    }
+
+    #[test]
+    fn test_buyer_accepts_or_declines() {
+        assert_eq!(
+            next_status("pending", TransferParty::Buyer, TransferAction::Accept),
+            Some(TransferStatus::Accepted)
+        );
+        assert_eq!(
+            next_status("pending", TransferParty::Buyer, TransferAction::Decline),
+            Some(TransferStatus::Declined)
+        );
+        assert_eq!(next_status("pending", TransferParty::Buyer, TransferAction::Cancel), None);
+    }
+
+    #[test]
+    fn test_seller_only_cancels() {
+        assert_eq!(
+            next_status("pending", TransferParty::Seller, TransferAction::Cancel),
+            Some(TransferStatus::Cancelled)
+        );
+        assert_eq!(next_status("pending", TransferParty::Seller, TransferAction::Accept), None);
+        assert_eq!(next_status("pending", TransferParty::Seller, TransferAction::Decline), None);
+    }
+
+    #[test]
+    fn test_closed_transfers_stay_closed() {
+        for status in ["accepted", "declined", "cancelled"] {
+            assert_eq!(next_status(status, TransferParty::Buyer, TransferAction::Accept), None);
+            assert_eq!(next_status(status, TransferParty::Seller, TransferAction::Cancel), None);
+        }
+    }
+
+    #[test]
+    fn test_seller_lot_sold_when_empty() {
+        assert_eq!(stage_after_transfer("green_bean", kg("0")), "sold");
+        assert_eq!(stage_after_transfer("green_bean", kg("12.5")), "green_bean");
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_transferred_quantity_never_exceeds_available(available in 0i64..100_000, requested in proptest::option::of(-1000i64..200_000)) {
+        let available = Decimal::new(available, 1);
+        if let Some(quantity) = transfer_quantity(requested.map(|r| Decimal::new(r, 1)), available) {
+            prop_assert!(quantity > Decimal::ZERO);
+            prop_assert!(quantity <= available);
+        }
+    }
+
+    #[test]
+    fn prop_one_response_per_transfer(first in 0usize..3, second in 0usize..3) {
+        let moves = [
+            (TransferParty::Buyer, TransferAction::Accept),
+            (TransferParty::Buyer, TransferAction::Decline),
+            (TransferParty::Seller, TransferAction::Cancel),
+        ];
+        let status = next_status("pending", moves[first].0, moves[first].1).unwrap();
+        prop_assert_eq!(next_status(status.as_str(), moves[second].0, moves[second].1), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Marketplace tests
+//!
+//! Tests for public lot listings and inquiries:
+//! - Cupping scores are shown as 2-point bands
+//! - Search text cannot inject LIKE wildcards
+//! - Inquiries need a name and an email or phone number
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const SCORE_BAND_WIDTH: Decimal = Decimal::TWO;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Mirrors `score_band`, returning (min, max)
+fn score_band(score: Decimal) -> (Decimal, Decimal) {
+    let min = (score / SCORE_BAND_WIDTH).floor() * SCORE_BAND_WIDTH;
+    (min, min + SCORE_BAND_WIDTH)
+}
+
+/// Mirrors `contains_pattern`
+fn contains_pattern(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| format!("%{}%", v.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")))
+}
+
+/// Mirrors the currency check in `create_listing`
+fn valid_currency(currency: &str) -> bool {
+    let currency = currency.trim().to_uppercase();
+    currency.len() == 3 && currency.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn non_blank(value: Option<&str>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Mirrors the contact checks in `create_lead`
+fn lead_contact_error(name: &str, email: Option<&str>, phone: Option<&str>) -> Option<&'static str> {
+    if name.trim().is_empty() {
+        return Some("buyer_name");
+    }
+    let email = non_blank(email);
+    if email.is_none() && non_blank(phone).is_none() {
+        return Some("buyer_email");
+    }
+    if email.is_some_and(|e| !e.contains('@')) {
+        return Some("buyer_email");
+    }
+    None
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_score_bands() {
+        assert_eq!(score_band(dec("85.37")), (dec("84"), dec("86")));
+        assert_eq!(score_band(dec("86")), (dec("86"), dec("88")));
+        assert_eq!(score_band(dec("79.99")), (dec("78"), dec("80")));
+    }
+
+    #[test]
+    fn test_search_pattern_escapes_wildcards() {
+        assert_eq!(contains_pattern(&Some("Doi Chang".into())), Some("%Doi Chang%".into()));
+        assert_eq!(contains_pattern(&Some("100%_".into())), Some("%100\\%\\_%".into()));
+        assert_eq!(contains_pattern(&Some("   ".into())), None);
+        assert_eq!(contains_pattern(&None), None);
+    }
+
+    #[test]
+    fn test_currency_codes() {
+        assert!(valid_currency("THB"));
+        assert!(valid_currency(" usd "));
+        assert!(!valid_currency("BAHT"));
+        assert!(!valid_currency("U$D"));
+    }
+
+    #[test]
+    fn test_inquiry_needs_contact() {
+        assert_eq!(lead_contact_error("Aiko", Some("aiko@roastery.jp"), None), None);
+        assert_eq!(lead_contact_error("Aiko", None, Some("+81 3 1234 5678")), None);
+        assert_eq!(lead_contact_error("Aiko", Some(" "), Some("")), Some("buyer_email"));
+        assert_eq!(lead_contact_error("Aiko", Some("roastery.jp"), None), Some("buyer_email"));
+        assert_eq!(lead_contact_error(" ", Some("aiko@roastery.jp"), None), Some("buyer_name"));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_score_within_its_band(hundredths in 6000u32..10000) {
+        let score = Decimal::new(hundredths as i64, 2);
+        let (min, max) = score_band(score);
+        prop_assert!(min <= score && score < max);
+        prop_assert_eq!(min % SCORE_BAND_WIDTH, Decimal::ZERO);
+    }
+
+    #[test]
+    fn prop_search_pattern_has_no_bare_wildcards(text in "[a-z%_ ]{1,20}") {
+        if let Some(pattern) = contains_pattern(&Some(text)) {
+            let inner = &pattern[1..pattern.len() - 1];
+            let bare = inner.char_indices().any(|(i, c)| {
+                (c == '%' || c == '_') && !inner[..i].ends_with('\\')
+            });
+            prop_assert!(!bare);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Team member invitation tests
+//!
+//! Tests for inviting coworkers:
+//! - Invited emails are trimmed, lowercased and checked for a domain
+//! - LINE share links percent-encode the invitation message as UTF-8
+//! - Invitation messages follow the invitee's language
+
+use proptest::prelude::*;
+
+const INVITATION_TTL_DAYS: i64 = 7;
+
+/// Mirrors `normalize_email`
+fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let valid = !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.chars().any(char::is_whitespace)
+        && email.len() <= 255;
+    valid.then_some(email)
+}
+
+/// Mirrors `percent_encode`
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Mirrors `invitation_message` subjects
+fn invitation_subject(business_name: &str, thai: bool) -> String {
+    if thai {
+        format!("คำเชิญเข้าร่วม {}", business_name)
+    } else {
+        format!("Invitation to join {}", business_name)
+    }
+}
+
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            out.push(u8::from_str_radix(&text[i + 1..i + 3], 16).unwrap());
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_email_normalized() {
+        assert_eq!(normalize_email("  Somchai@Farm.CO.TH "), Some("somchai@farm.co.th".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_emails_rejected() {
+        assert_eq!(normalize_email("somchai"), None);
+        assert_eq!(normalize_email("@farm.co.th"), None);
+        assert_eq!(normalize_email("somchai@farm"), None);
+        assert_eq!(normalize_email("som chai@farm.co.th"), None);
+    }
+
+    #[test]
+    fn test_percent_encode_thai_and_link() {
+        assert_eq!(percent_encode("a b/c"), "a%20b%2Fc");
+        assert_eq!(percent_encode("ก"), "%E0%B8%81");
+    }
+
+    #[test]
+    fn test_subject_follows_language() {
+        assert_eq!(invitation_subject("Doi Farm", false), "Invitation to join Doi Farm");
+        assert!(invitation_subject("Doi Farm", true).starts_with("คำเชิญ"));
+    }
+
+    #[test]
+    fn test_invitation_ttl_is_a_week() {
+        assert_eq!(INVITATION_TTL_DAYS, 7);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_percent_encode_round_trips(text in "\\PC{0,60}") {
+        let encoded = percent_encode(&text);
+        prop_assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || "-_.~%".contains(c)));
+        prop_assert_eq!(percent_decode(&encoded), text.as_bytes().to_vec());
+    }
+
+    #[test]
+    fn prop_normalized_email_is_stable(local in "[a-zA-Z0-9._]{1,20}", domain in "[a-z]{1,10}\\.[a-z]{2,4}") {
+        let email = format!(" {}@{} ", local, domain);
+        let normalized = normalize_email(&email).unwrap();
+        prop_assert_eq!(normalize_email(&normalized), Some(normalized.clone()));
+    }
+}
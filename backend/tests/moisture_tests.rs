@@ -0,0 +1,188 @@
+//! Moisture reading tests
+//!
+//! Tests for the moisture log of lots:
+//! - Warnings for green beans above 12.5%
+//! - Readings taking the lot's stage unless one is given
+//! - Chart series grouped by stage in time order
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use shared::LotStage;
+
+/// Mirrors `GREEN_BEAN_MAX_MOISTURE_PERCENT`
+const GREEN_BEAN_MAX_MOISTURE_PERCENT: Decimal = Decimal::from_parts(125, 0, 0, false, 1);
+
+/// Mirrors `MoisturePoint`
+#[derive(Debug, Clone, PartialEq)]
+struct MoisturePoint {
+    measured_at: DateTime<Utc>,
+    stage: LotStage,
+    moisture_percent: Decimal,
+}
+
+/// Mirrors `MoistureStageSeries`
+#[derive(Debug, Clone, PartialEq)]
+struct MoistureStageSeries {
+    stage: LotStage,
+    first_percent: Decimal,
+    latest_percent: Decimal,
+    min_percent: Decimal,
+    max_percent: Decimal,
+    points: Vec<MoisturePoint>,
+}
+
+/// Mirrors `moisture_warning`, with whether it warns
+fn moisture_warning(stage: LotStage, moisture_percent: Decimal) -> bool {
+    stage == LotStage::GreenBean && moisture_percent > GREEN_BEAN_MAX_MOISTURE_PERCENT
+}
+
+/// Mirrors `reading_stage`, with the error as None
+fn reading_stage(requested: Option<LotStage>, lot_stage: LotStage) -> Option<LotStage> {
+    match requested.unwrap_or(lot_stage) {
+        LotStage::Sold => None,
+        stage => Some(stage),
+    }
+}
+
+/// Mirrors `stage_series`
+fn stage_series(points: &[MoisturePoint]) -> Vec<MoistureStageSeries> {
+    let mut ordered = points.to_vec();
+    ordered.sort_by(|a, b| a.stage.cmp(&b.stage).then(a.measured_at.cmp(&b.measured_at)));
+
+    let mut series: Vec<MoistureStageSeries> = Vec::new();
+    for point in ordered {
+        match series.last_mut() {
+            Some(current) if current.stage == point.stage => {
+                current.latest_percent = point.moisture_percent;
+                current.min_percent = current.min_percent.min(point.moisture_percent);
+                current.max_percent = current.max_percent.max(point.moisture_percent);
+                current.points.push(point);
+            }
+            _ => series.push(MoistureStageSeries {
+                stage: point.stage,
+                first_percent: point.moisture_percent,
+                latest_percent: point.moisture_percent,
+                min_percent: point.moisture_percent,
+                max_percent: point.moisture_percent,
+                points: vec![point],
+            }),
+        }
+    }
+    series
+}
+
+fn pct(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn point(day: i64, stage: LotStage, moisture: &str) -> MoisturePoint {
+    MoisturePoint {
+        measured_at: Utc.with_ymd_and_hms(2024, 12, 1, 8, 0, 0).unwrap() + Duration::days(day),
+        stage,
+        moisture_percent: pct(moisture),
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_green_beans_above_limit_warn() {
+        assert!(moisture_warning(LotStage::GreenBean, pct("12.6")));
+        assert!(!moisture_warning(LotStage::GreenBean, pct("12.5")));
+        assert!(!moisture_warning(LotStage::GreenBean, pct("10.8")));
+    }
+
+    #[test]
+    fn test_drying_stages_do_not_warn() {
+        assert!(!moisture_warning(LotStage::Cherry, pct("55")));
+        assert!(!moisture_warning(LotStage::Parchment, pct("18.2")));
+    }
+
+    #[test]
+    fn test_stage_defaults_to_lot() {
+        assert_eq!(reading_stage(None, LotStage::Parchment), Some(LotStage::Parchment));
+        assert_eq!(
+            reading_stage(Some(LotStage::GreenBean), LotStage::Parchment),
+            Some(LotStage::GreenBean)
+        );
+    }
+
+    #[test]
+    fn test_sold_lot_needs_stage() {
+        assert_eq!(reading_stage(None, LotStage::Sold), None);
+        assert_eq!(reading_stage(Some(LotStage::Sold), LotStage::GreenBean), None);
+        assert_eq!(reading_stage(Some(LotStage::GreenBean), LotStage::Sold), Some(LotStage::GreenBean));
+    }
+
+    #[test]
+    fn test_series_per_stage() {
+        let series = stage_series(&[
+            point(9, LotStage::GreenBean, "11.2"),
+            point(2, LotStage::Parchment, "30.5"),
+            point(0, LotStage::Parchment, "45"),
+            point(5, LotStage::Parchment, "12.1"),
+            point(12, LotStage::GreenBean, "12.8"),
+        ]);
+        assert_eq!(series.len(), 2);
+        let parchment = &series[0];
+        assert_eq!(parchment.stage, LotStage::Parchment);
+        assert_eq!(parchment.first_percent, pct("45"));
+        assert_eq!(parchment.latest_percent, pct("12.1"));
+        assert_eq!(parchment.min_percent, pct("12.1"));
+        assert_eq!(parchment.max_percent, pct("45"));
+        let green = &series[1];
+        assert_eq!(green.points.len(), 2);
+        assert_eq!(green.latest_percent, pct("12.8"));
+    }
+
+    #[test]
+    fn test_no_readings_no_series() {
+        assert!(stage_series(&[]).is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_series_keep_every_point_in_order(
+        readings in prop::collection::vec((0i64..60, 0usize..4, 0i64..=6000), 0..40)
+    ) {
+        let points: Vec<MoisturePoint> = readings
+            .iter()
+            .map(|(day, stage, moisture)| MoisturePoint {
+                measured_at: Utc.with_ymd_and_hms(2024, 12, 1, 8, 0, 0).unwrap() + Duration::days(*day),
+                stage: LotStage::ALL[*stage],
+                moisture_percent: Decimal::new(*moisture, 2),
+            })
+            .collect();
+        let series = stage_series(&points);
+        prop_assert_eq!(series.iter().map(|s| s.points.len()).sum::<usize>(), points.len());
+        prop_assert!(series.windows(2).all(|w| w[0].stage < w[1].stage));
+        for s in &series {
+            prop_assert!(s.points.windows(2).all(|w| w[0].measured_at <= w[1].measured_at));
+            prop_assert!(s.points.iter().all(|p| p.stage == s.stage));
+            prop_assert!(s.min_percent <= s.first_percent && s.first_percent <= s.max_percent);
+            prop_assert!(s.min_percent <= s.latest_percent && s.latest_percent <= s.max_percent);
+        }
+    }
+
+    #[test]
+    fn prop_only_green_beans_warn(stage in 0usize..5, moisture in 0i64..=10_000) {
+        let stage = LotStage::ALL[stage];
+        let moisture = Decimal::new(moisture, 2);
+        if moisture_warning(stage, moisture) {
+            prop_assert_eq!(stage, LotStage::GreenBean);
+            prop_assert!(moisture > GREEN_BEAN_MAX_MOISTURE_PERCENT);
+        }
+    }
+}
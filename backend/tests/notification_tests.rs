@@ -2,8 +2,11 @@
 //!
 //! Tests for notification management including:
 //! - Property 24: Notification Preference Respect
+//! - Severity levels routed to channels per user
+//! - Deep links from notifications to the page of their entity
 
 use proptest::prelude::*;
+use uuid::Uuid;
 
 // ============================================================================
 // Unit Tests
@@ -137,6 +140,132 @@ mod unit_tests {
         assert!(formatted.contains(title));
         assert!(formatted.contains(message));
     }
+
+    /// Test LINE acknowledgement keeps the first read time and marks as read
+    #[test]
+    fn test_line_acknowledgement_read_receipt() {
+        let sent = (Some("sent"), None::<i64>);
+
+        let first = acknowledge(sent, 1_000);
+        assert_eq!(first, (Some("read"), Some(1_000)));
+
+        // Tapping the quick reply again does not move read_at
+        let again = acknowledge(first, 2_000);
+        assert_eq!(again, (Some("read"), Some(1_000)));
+    }
+
+    /// Test escalation uses a different channel than the unanswered one
+    #[test]
+    fn test_escalation_alternate_channel() {
+        assert_eq!(escalation_channel("line", None), "in_app");
+        assert_eq!(escalation_channel("in_app", None), "line");
+        assert_eq!(escalation_channel("email", None), "line");
+        // An explicit policy channel wins
+        assert_eq!(escalation_channel("line", Some("line")), "line");
+    }
+
+    fn escalation_channel(original: &'static str, policy: Option<&'static str>) -> &'static str {
+        match policy {
+            Some(channel) => channel,
+            None => match original {
+                "line" => "in_app",
+                _ => "line",
+            },
+        }
+    }
+
+    /// Test only overdue, unread, high-priority notifications are escalated once
+    #[test]
+    fn test_escalation_eligibility() {
+        let policy = Policy { notification_type: None, min_priority: 2, after_hours: 4 };
+
+        let overdue = Sent { notification_type: "weather_alert", priority: 2, hours_ago: 5, read: false, escalated: false };
+        assert!(should_escalate(&overdue, &policy));
+
+        assert!(!should_escalate(&Sent { hours_ago: 3, ..overdue }, &policy));
+        assert!(!should_escalate(&Sent { priority: 1, ..overdue }, &policy));
+        assert!(!should_escalate(&Sent { read: true, ..overdue }, &policy));
+        assert!(!should_escalate(&Sent { escalated: true, ..overdue }, &policy));
+
+        let typed = Policy { notification_type: Some("low_inventory"), min_priority: 1, after_hours: 2 };
+        assert!(!should_escalate(&overdue, &typed));
+        assert!(should_escalate(&Sent { notification_type: "low_inventory", priority: 1, ..overdue }, &typed));
+    }
+
+    struct Policy {
+        notification_type: Option<&'static str>,
+        min_priority: i32,
+        after_hours: i32,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sent {
+        notification_type: &'static str,
+        priority: i32,
+        hours_ago: i32,
+        read: bool,
+        escalated: bool,
+    }
+
+    fn should_escalate(sent: &Sent, policy: &Policy) -> bool {
+        !sent.read
+            && !sent.escalated
+            && sent.priority >= policy.min_priority
+            && match policy.notification_type {
+                Some(t) => t == sent.notification_type,
+                None => true,
+            }
+            && sent.hours_ago >= policy.after_hours
+    }
+
+    /// Test severities derived from priorities and back
+    #[test]
+    fn test_severity_from_priority() {
+        assert_eq!(severity_from_priority(0), "info");
+        assert_eq!(severity_from_priority(-1), "info");
+        assert_eq!(severity_from_priority(1), "warning");
+        assert_eq!(severity_from_priority(2), "critical");
+        // Escalations raise the priority past 2
+        assert_eq!(severity_from_priority(3), "critical");
+        for severity in ["info", "warning", "critical"] {
+            assert_eq!(severity_from_priority(severity_priority(severity)), severity);
+        }
+    }
+
+    /// Test default routing: critical to LINE and email, warnings to LINE,
+    /// info only in-app
+    #[test]
+    fn test_default_severity_routing() {
+        assert_eq!(route_channels(default_channels("critical"), true, true), vec!["line", "email"]);
+        assert_eq!(route_channels(default_channels("warning"), true, true), vec!["line"]);
+        assert!(route_channels(default_channels("info"), true, true).is_empty());
+    }
+
+    /// Test the weekly digest follows its own channels, not its severity
+    #[test]
+    fn test_digest_routing() {
+        assert_eq!(channels_for_notification("weekly_digest", "info"), DIGEST_CHANNELS);
+        assert_eq!(route_channels(channels_for_notification("weekly_digest", "info"), true, true), vec!["line", "email"]);
+        assert_eq!(channels_for_notification("low_inventory", "info"), default_channels("info"));
+    }
+
+    /// Test channels switched off are skipped whatever the routing
+    #[test]
+    fn test_routing_skips_disabled_channels() {
+        assert_eq!(route_channels(default_channels("critical"), false, true), vec!["email"]);
+        assert_eq!(route_channels(default_channels("critical"), true, false), vec!["line"]);
+        assert!(route_channels(default_channels("critical"), false, false).is_empty());
+        // Listing in_app changes nothing: the inbox copy is always made
+        assert_eq!(route_channels(&["in_app", "email"], true, true), vec!["email"]);
+    }
+
+    /// Mirrors `SET read_at = COALESCE(read_at, NOW()), status = 'read'`
+    fn acknowledge(
+        entry: (Option<&'static str>, Option<i64>),
+        now: i64,
+    ) -> (Option<&'static str>, Option<i64>) {
+        (Some("read"), entry.1.or(Some(now)))
+    }
 }
 
 // ============================================================================
@@ -210,6 +339,75 @@ pub fn should_send_notification(
     (true, channel)
 }
 
+/// Mirrors `AlertSeverity::from_priority`
+pub fn severity_from_priority(priority: i32) -> &'static str {
+    match priority {
+        p if p >= 2 => "critical",
+        1 => "warning",
+        _ => "info",
+    }
+}
+
+/// Mirrors `AlertSeverity::priority`
+pub fn severity_priority(severity: &str) -> i32 {
+    match severity {
+        "critical" => 2,
+        "warning" => 1,
+        _ => 0,
+    }
+}
+
+/// Mirrors the `*_channels` column defaults
+pub fn default_channels(severity: &str) -> &'static [&'static str] {
+    match severity {
+        "critical" => &["line", "email", "in_app"],
+        "warning" => &["line", "in_app"],
+        _ => &["in_app"],
+    }
+}
+
+/// Mirrors the `digest_channels` column default
+pub const DIGEST_CHANNELS: &[&str] = &["line", "email", "in_app"];
+
+/// Mirrors `NotificationPreferences::channels_for_notification` with default routing
+pub fn channels_for_notification(notification_type: &str, severity: &str) -> &'static [&'static str] {
+    match notification_type {
+        "weekly_digest" => DIGEST_CHANNELS,
+        _ => default_channels(severity),
+    }
+}
+
+/// Mirrors `route_channels`
+pub fn route_channels(routed: &[&'static str], line_enabled: bool, email_enabled: bool) -> Vec<&'static str> {
+    [("line", line_enabled), ("email", email_enabled)]
+        .into_iter()
+        .filter(|(channel, enabled)| *enabled && routed.contains(channel))
+        .map(|(channel, _)| channel)
+        .collect()
+}
+
+/// Mirrors `notification_action_url`
+fn notification_action_url(notification_type: &str, entity_type: Option<&str>, entity_id: Option<Uuid>) -> Option<String> {
+    if notification_type == "weekly_digest" {
+        return Some("/reports/weekly-digest".to_string());
+    }
+    let path = match entity_type? {
+        "kpi_targets" => return Some("/reports/kpi".to_string()),
+        "lot" => "/lots",
+        "plot" => "/plots",
+        "certification" => "/certifications",
+        "lot_insurance_policy" => "/insurance-policies",
+        "storage_location" => "/storage-locations",
+        "sales_lead" => "/sales/leads",
+        "roast_session" => "/roasting/sessions",
+        "cupping_session" => "/cupping/sessions",
+        "shipment" => "/shipments",
+        "lot_transfer" => "/lot-transfers",
+        _ => return None,
+    };
+    Some(format!("{}/{}", path, entity_id?))
+}
+
 // ============================================================================
 // Property-Based Tests
 // ============================================================================
@@ -372,6 +570,24 @@ mod property_tests {
             prop_assert_eq!(low_inv, prefs.low_inventory_enabled);
             prop_assert_eq!(cert_exp, prefs.certification_expiring_enabled);
         }
+
+        /// Routed channels are only ever ones configured for the severity and
+        /// switched on, LINE before email
+        #[test]
+        fn prop_routing_respects_configuration(
+            routed in proptest::sample::subsequence(vec!["line", "email", "in_app"], 0..=3),
+            line_enabled in any::<bool>(),
+            email_enabled in any::<bool>(),
+        ) {
+            let channels = route_channels(&routed, line_enabled, email_enabled);
+            prop_assert!(channels.iter().all(|c| routed.contains(c)));
+            prop_assert!(!channels.contains(&"in_app"));
+            prop_assert_eq!(channels.contains(&"line"), line_enabled && routed.contains(&"line"));
+            prop_assert_eq!(channels.contains(&"email"), email_enabled && routed.contains(&"email"));
+            if channels.len() == 2 {
+                prop_assert_eq!(channels[0], "line");
+            }
+        }
     }
 }
 
@@ -444,3 +660,53 @@ mod integration_helpers {
         assert!(result.is_ok());
     }
 }
+
+// ============================================================================
+// Action URL Tests
+// ============================================================================
+
+#[cfg(test)]
+mod action_url_tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_deep_links() {
+        let id = Uuid::nil();
+        assert_eq!(
+            notification_action_url("certification_expiring", Some("certification"), Some(id)),
+            Some(format!("/certifications/{}", id))
+        );
+        assert_eq!(
+            notification_action_url("low_inventory", Some("lot"), Some(id)),
+            Some(format!("/lots/{}", id))
+        );
+        assert_eq!(
+            notification_action_url("system", Some("roast_session"), Some(id)),
+            Some(format!("/roasting/sessions/{}", id))
+        );
+        assert_eq!(
+            notification_action_url("system", Some("lot_transfer"), Some(id)),
+            Some(format!("/lot-transfers/{}", id))
+        );
+    }
+
+    #[test]
+    fn test_summaries_link_to_their_report() {
+        let id = Some(Uuid::nil());
+        assert_eq!(
+            notification_action_url("weekly_digest", Some("business"), id),
+            Some("/reports/weekly-digest".to_string())
+        );
+        assert_eq!(
+            notification_action_url("system", Some("kpi_targets"), id),
+            Some("/reports/kpi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_link_without_a_known_entity() {
+        assert_eq!(notification_action_url("low_inventory", Some("lot"), None), None);
+        assert_eq!(notification_action_url("system", None, Some(Uuid::nil())), None);
+        assert_eq!(notification_action_url("system", Some("business"), Some(Uuid::nil())), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Route permission enforcement tests
+//!
+//! Tests for the permission layer on protected routers:
+//! - The action follows the HTTP method unless the route names it
+//! - A request passes only when the user's role grants the permission
+//! - Synced changes need the permission of their entity and operation
+//! - Pulled changes are limited to entities the role may view
+
+use proptest::prelude::*;
+
+/// Mirrors `action_for_method`
+fn action_for_method(method: &str) -> &'static str {
+    match method {
+        "POST" => "create",
+        "PUT" | "PATCH" => "edit",
+        "DELETE" => "delete",
+        _ => "view",
+    }
+}
+
+/// Mirrors `required_permission`
+fn required_permission<'a>(permission: &'a str, method: &str) -> (&'a str, &'a str) {
+    match permission.split_once(':') {
+        Some((resource, action)) => (resource, action),
+        None => (permission, action_for_method(method)),
+    }
+}
+
+/// Mirrors `AuthUser::has_permission`
+fn has_permission(permissions: &[String], resource: &str, action: &str) -> bool {
+    permissions.contains(&format!("{}:{}", resource, action))
+}
+
+/// Mirrors the decision made by `require_permission`
+fn is_allowed(permissions: &[String], permission: &str, method: &str) -> bool {
+    let (resource, action) = required_permission(permission, method);
+    has_permission(permissions, resource, action)
+}
+
+/// Mirrors `SyncService::entity_resource`
+fn entity_resource(entity_type: &str) -> Option<&'static str> {
+    match entity_type {
+        "plots" => Some("plot"),
+        "harvests" => Some("harvest"),
+        "processing_records" => Some("processing"),
+        "green_bean_grades" => Some("grading"),
+        "cupping_sessions" | "cupping_samples" => Some("cupping"),
+        "lots" | "inventory_transactions" => Some("inventory"),
+        "roast_sessions" => Some("roast_profile"),
+        _ => None,
+    }
+}
+
+/// Mirrors `SyncService::operation_action`
+fn operation_action(operation: &str) -> &'static str {
+    match operation {
+        "create" => "create",
+        "delete" => "delete",
+        _ => "edit",
+    }
+}
+
+/// Mirrors the check in `apply_changes`: all changes allowed or none applied
+fn may_apply(permissions: &[String], changes: &[(&str, &str)]) -> bool {
+    changes.iter().all(|(entity_type, operation)| {
+        entity_resource(entity_type)
+            .is_none_or(|resource| has_permission(permissions, resource, operation_action(operation)))
+    })
+}
+
+/// Mirrors the filter in `get_changes`
+fn visible_changes<'a>(permissions: &[String], entity_types: &[&'a str]) -> Vec<&'a str> {
+    entity_types
+        .iter()
+        .copied()
+        .filter(|t| entity_resource(t).is_none_or(|resource| has_permission(permissions, resource, "view")))
+        .collect()
+}
+
+fn perms(list: &[&str]) -> Vec<String> {
+    list.iter().map(|p| p.to_string()).collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_action_follows_method() {
+        assert_eq!(required_permission("cupping", "GET"), ("cupping", "view"));
+        assert_eq!(required_permission("cupping", "POST"), ("cupping", "create"));
+        assert_eq!(required_permission("cupping", "PUT"), ("cupping", "edit"));
+        assert_eq!(required_permission("cupping", "PATCH"), ("cupping", "edit"));
+        assert_eq!(required_permission("cupping", "DELETE"), ("cupping", "delete"));
+    }
+
+    #[test]
+    fn test_named_action_overrides_method() {
+        assert_eq!(required_permission("report:export", "POST"), ("report", "export"));
+        assert_eq!(required_permission("inventory:view", "POST"), ("inventory", "view"));
+    }
+
+    #[test]
+    fn test_viewer_reads_but_cannot_write() {
+        let viewer = perms(&["cupping:view", "report:view"]);
+        assert!(is_allowed(&viewer, "cupping", "GET"));
+        assert!(!is_allowed(&viewer, "cupping", "POST"));
+        assert!(!is_allowed(&viewer, "report:export", "GET"));
+    }
+
+    #[test]
+    fn test_permission_of_another_resource_does_not_apply() {
+        let cupper = perms(&["cupping:view", "cupping:create"]);
+        assert!(!is_allowed(&cupper, "roast_profile", "POST"));
+    }
+
+    #[test]
+    fn test_sync_changes_need_entity_permission() {
+        let worker = perms(&["harvest:view", "harvest:create"]);
+        assert!(may_apply(&worker, &[("harvests", "create")]));
+        assert!(!may_apply(&worker, &[("harvests", "update")]));
+        assert!(!may_apply(&worker, &[("harvests", "create"), ("plots", "delete")]));
+    }
+
+    #[test]
+    fn test_pulled_changes_limited_to_viewable_entities() {
+        let cupper = perms(&["cupping:view", "grading:view"]);
+        assert_eq!(
+            visible_changes(&cupper, &["plots", "cupping_samples", "green_bean_grades", "lots"]),
+            vec!["cupping_samples", "green_bean_grades"]
+        );
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+fn method_strategy() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just("GET"), Just("POST"), Just("PUT"), Just("PATCH"), Just("DELETE")]
+}
+
+proptest! {
+    #[test]
+    fn prop_owner_with_all_actions_is_allowed(resource in "[a-z_]{1,15}", method in method_strategy()) {
+        let owner: Vec<String> = ["view", "create", "edit", "delete"]
+            .iter()
+            .map(|a| format!("{}:{}", resource, a))
+            .collect();
+        prop_assert!(is_allowed(&owner, &resource, method));
+    }
+
+    #[test]
+    fn prop_no_permissions_denies_everything(resource in "[a-z_]{1,15}", method in method_strategy()) {
+        prop_assert!(!is_allowed(&[], &resource, method));
+    }
+
+    #[test]
+    fn prop_named_action_ignores_method(
+        resource in "[a-z_]{1,15}",
+        action in "[a-z]{1,10}",
+        method in method_strategy(),
+    ) {
+        let spec = format!("{}:{}", resource, action);
+        prop_assert_eq!(required_permission(&spec, method), (resource.as_str(), action.as_str()));
+    }
+}
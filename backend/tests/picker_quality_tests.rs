@@ -0,0 +1,320 @@
+//! Picker quality tests
+//!
+//! Tests for scoring pickers and crews over a season:
+//! - Pickers matched by name regardless of case and spacing
+//! - Ripeness averaged by cherry weight, lot defects shared by cherry
+//! - Scores out of 100 and ranking, ripeness alone before grading
+//! - Bonus eligibility and per-kg bonuses
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+const DEFECT_SAMPLE_GRAMS: Decimal = Decimal::from_parts(350, 0, 0, false, 0);
+const RIPENESS_WEIGHT: Decimal = Decimal::from_parts(6, 0, 0, false, 1);
+const POINTS_PER_DEFECT: Decimal = Decimal::from_parts(4, 0, 0, false, 0);
+
+/// Mirrors `PickedHarvest`
+#[derive(Debug, Clone, PartialEq)]
+struct PickedHarvest {
+    picker: String,
+    lot_id: Uuid,
+    cherry_kg: Decimal,
+    underripe_percent: i32,
+    ripe_percent: i32,
+    overripe_percent: i32,
+}
+
+/// Mirrors `PickerQuality`
+#[derive(Debug, Clone, PartialEq)]
+struct PickerQuality {
+    rank: usize,
+    picker_name: String,
+    harvests: usize,
+    cherry_kg: Decimal,
+    underripe_percent: Decimal,
+    ripe_percent: Decimal,
+    overripe_percent: Decimal,
+    graded_cherry_kg: Decimal,
+    defects_per_350g: Option<Decimal>,
+    defect_score: Option<Decimal>,
+    quality_score: Decimal,
+    bonus_eligible: bool,
+    bonus_thb: Option<Decimal>,
+}
+
+/// Mirrors `picker_key`
+fn picker_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Mirrors `defects_per_350g`
+fn defects_per_350g(full_defects: i32, sample_weight_grams: Decimal) -> Option<Decimal> {
+    (sample_weight_grams > Decimal::ZERO).then(|| Decimal::from(full_defects) * DEFECT_SAMPLE_GRAMS / sample_weight_grams)
+}
+
+/// Mirrors `defect_score`
+fn defect_score(defects_per_350g: Decimal) -> Decimal {
+    (Decimal::ONE_HUNDRED - defects_per_350g * POINTS_PER_DEFECT).clamp(Decimal::ZERO, Decimal::ONE_HUNDRED)
+}
+
+/// Mirrors `quality_score`
+fn quality_score(ripe_percent: Decimal, defect_score: Option<Decimal>) -> Decimal {
+    let ripeness = ripe_percent.clamp(Decimal::ZERO, Decimal::ONE_HUNDRED);
+    match defect_score {
+        Some(defects) => ripeness * RIPENESS_WEIGHT + defects * (Decimal::ONE - RIPENESS_WEIGHT),
+        None => ripeness,
+    }
+}
+
+#[derive(Default)]
+struct PickerTotals {
+    name: String,
+    harvests: usize,
+    cherry_kg: Decimal,
+    underripe_kg: Decimal,
+    ripe_kg: Decimal,
+    overripe_kg: Decimal,
+    graded_kg: Decimal,
+    defect_kg: Decimal,
+}
+
+/// Mirrors `score_pickers`
+fn score_pickers(harvests: &[PickedHarvest], lot_defects: &HashMap<Uuid, Decimal>) -> Vec<PickerQuality> {
+    let mut order: Vec<String> = Vec::new();
+    let mut totals: HashMap<String, PickerTotals> = HashMap::new();
+    for harvest in harvests {
+        if harvest.cherry_kg <= Decimal::ZERO {
+            continue;
+        }
+        let key = picker_key(&harvest.picker);
+        if key.is_empty() {
+            continue;
+        }
+        let entry = totals.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            PickerTotals {
+                name: harvest.picker.trim().to_string(),
+                ..Default::default()
+            }
+        });
+        let kg = harvest.cherry_kg;
+        entry.harvests += 1;
+        entry.cherry_kg += kg;
+        entry.underripe_kg += kg * Decimal::from(harvest.underripe_percent);
+        entry.ripe_kg += kg * Decimal::from(harvest.ripe_percent);
+        entry.overripe_kg += kg * Decimal::from(harvest.overripe_percent);
+        if let Some(defects) = lot_defects.get(&harvest.lot_id) {
+            entry.graded_kg += kg;
+            entry.defect_kg += kg * defects;
+        }
+    }
+
+    let mut pickers: Vec<PickerQuality> = order
+        .into_iter()
+        .filter_map(|key| totals.remove(&key))
+        .map(|t| {
+            let ripe_percent = t.ripe_kg / t.cherry_kg;
+            let defects = (t.graded_kg > Decimal::ZERO).then(|| t.defect_kg / t.graded_kg);
+            let defect_score = defects.map(defect_score);
+            PickerQuality {
+                rank: 0,
+                picker_name: t.name,
+                harvests: t.harvests,
+                cherry_kg: t.cherry_kg,
+                underripe_percent: (t.underripe_kg / t.cherry_kg).round_dp(1),
+                ripe_percent: ripe_percent.round_dp(1),
+                overripe_percent: (t.overripe_kg / t.cherry_kg).round_dp(1),
+                graded_cherry_kg: t.graded_kg,
+                defects_per_350g: defects.map(|d| d.round_dp(1)),
+                defect_score: defect_score.map(|s| s.round_dp(1)),
+                quality_score: quality_score(ripe_percent, defect_score).round_dp(1),
+                bonus_eligible: false,
+                bonus_thb: None,
+            }
+        })
+        .collect();
+
+    pickers.sort_by_key(|picker| std::cmp::Reverse(picker.quality_score));
+    for (index, picker) in pickers.iter_mut().enumerate() {
+        picker.rank = index + 1;
+    }
+    pickers
+}
+
+/// Mirrors `apply_bonuses`
+fn apply_bonuses(pickers: &mut [PickerQuality], min_score: Decimal, bonus_per_kg: Option<Decimal>) -> Option<Decimal> {
+    for picker in pickers.iter_mut() {
+        picker.bonus_eligible = picker.quality_score >= min_score;
+        picker.bonus_thb = bonus_per_kg.map(|rate| {
+            if picker.bonus_eligible {
+                (picker.cherry_kg * rate).round_dp(2)
+            } else {
+                Decimal::ZERO
+            }
+        });
+    }
+    bonus_per_kg.map(|_| pickers.iter().filter_map(|p| p.bonus_thb).sum())
+}
+
+fn d(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+fn harvest(picker: &str, lot_id: Uuid, kg: &str, ripe: i32) -> PickedHarvest {
+    PickedHarvest {
+        picker: picker.to_string(),
+        lot_id,
+        cherry_kg: d(kg),
+        underripe_percent: 100 - ripe,
+        ripe_percent: ripe,
+        overripe_percent: 0,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_picker_names_match_loosely() {
+        assert_eq!(picker_key("  Somchai   Kaewmala "), "somchai kaewmala");
+        assert_eq!(picker_key("SOMCHAI kaewmala"), picker_key("Somchai Kaewmala"));
+        assert_eq!(picker_key("   "), "");
+    }
+
+    #[test]
+    fn test_defects_scaled_to_350g() {
+        assert_eq!(defects_per_350g(4, d("350")), Some(d("4")));
+        assert_eq!(defects_per_350g(3, d("300")), Some(d("3.5")));
+        assert_eq!(defects_per_350g(3, Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn test_defect_score() {
+        assert_eq!(defect_score(Decimal::ZERO), d("100"));
+        assert_eq!(defect_score(d("5")), d("80"));
+        // Below-grade coffee scores nothing
+        assert_eq!(defect_score(d("86")), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_quality_score_weights_ripeness_and_defects() {
+        assert_eq!(quality_score(d("90"), Some(d("80"))), d("86.0"));
+        assert_eq!(quality_score(d("90"), None), d("90"));
+    }
+
+    #[test]
+    fn test_ripeness_weighted_by_cherry() {
+        let lot = Uuid::new_v4();
+        let pickers = score_pickers(
+            &[harvest("Nok", lot, "30", 90), harvest("nok ", lot, "10", 50)],
+            &HashMap::new(),
+        );
+        assert_eq!(pickers.len(), 1);
+        assert_eq!(pickers[0].picker_name, "Nok");
+        assert_eq!(pickers[0].harvests, 2);
+        assert_eq!(pickers[0].cherry_kg, d("40"));
+        assert_eq!(pickers[0].ripe_percent, d("80.0"));
+        assert_eq!(pickers[0].defect_score, None);
+        assert_eq!(pickers[0].quality_score, d("80.0"));
+    }
+
+    #[test]
+    fn test_lot_defects_shared_by_cherry() {
+        let clean = Uuid::new_v4();
+        let dirty = Uuid::new_v4();
+        let ungraded = Uuid::new_v4();
+        let defects = HashMap::from([(clean, d("2")), (dirty, d("10"))]);
+        let pickers = score_pickers(
+            &[
+                harvest("Nok", clean, "30", 90),
+                harvest("Nok", dirty, "10", 90),
+                harvest("Nok", ungraded, "20", 90),
+            ],
+            &defects,
+        );
+        assert_eq!(pickers[0].graded_cherry_kg, d("40"));
+        // (30 × 2 + 10 × 10) / 40
+        assert_eq!(pickers[0].defects_per_350g, Some(d("4.0")));
+        assert_eq!(pickers[0].defect_score, Some(d("84.0")));
+        assert_eq!(pickers[0].quality_score, d("87.6"));
+    }
+
+    #[test]
+    fn test_ranked_by_score() {
+        let lot = Uuid::new_v4();
+        let pickers = score_pickers(
+            &[
+                harvest("Nok", lot, "10", 70),
+                harvest("Dao", lot, "10", 95),
+                harvest("", lot, "10", 100),
+                harvest("Ploy", lot, "0", 100),
+            ],
+            &HashMap::new(),
+        );
+        let ranking: Vec<_> = pickers.iter().map(|p| (p.rank, p.picker_name.as_str())).collect();
+        assert_eq!(ranking, vec![(1, "Dao"), (2, "Nok")]);
+    }
+
+    #[test]
+    fn test_bonuses_above_threshold() {
+        let lot = Uuid::new_v4();
+        let mut pickers = score_pickers(
+            &[harvest("Dao", lot, "120.5", 95), harvest("Nok", lot, "80", 70)],
+            &HashMap::new(),
+        );
+        let total = apply_bonuses(&mut pickers, d("80"), Some(d("2")));
+        assert!(pickers[0].bonus_eligible);
+        assert_eq!(pickers[0].bonus_thb, Some(d("241.00")));
+        assert!(!pickers[1].bonus_eligible);
+        assert_eq!(pickers[1].bonus_thb, Some(Decimal::ZERO));
+        assert_eq!(total, Some(d("241.00")));
+    }
+
+    #[test]
+    fn test_eligibility_without_bonus_rate() {
+        let lot = Uuid::new_v4();
+        let mut pickers = score_pickers(&[harvest("Dao", lot, "10", 80)], &HashMap::new());
+        assert_eq!(apply_bonuses(&mut pickers, d("80"), None), None);
+        assert!(pickers[0].bonus_eligible);
+        assert_eq!(pickers[0].bonus_thb, None);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_scores_stay_within_100(ripe in -10i64..=110, defects in 0i64..=200, graded in any::<bool>()) {
+        let defect = graded.then(|| defect_score(Decimal::from(defects)));
+        let score = quality_score(Decimal::from(ripe), defect);
+        prop_assert!(score >= Decimal::ZERO && score <= Decimal::ONE_HUNDRED);
+    }
+
+    #[test]
+    fn prop_ranks_follow_scores(
+        entries in prop::collection::vec((0usize..5, 1u32..500, 0i32..=100), 1..20),
+    ) {
+        let lot = Uuid::new_v4();
+        let harvests: Vec<_> = entries
+            .iter()
+            .map(|(picker, kg, ripe)| harvest(&format!("Picker {}", picker), lot, &kg.to_string(), *ripe))
+            .collect();
+        let pickers = score_pickers(&harvests, &HashMap::new());
+        let total: Decimal = pickers.iter().map(|p| p.cherry_kg).sum();
+        prop_assert_eq!(total, harvests.iter().map(|h| h.cherry_kg).sum::<Decimal>());
+        for (index, pair) in pickers.windows(2).enumerate() {
+            prop_assert!(pair[0].quality_score >= pair[1].quality_score);
+            prop_assert_eq!(pair[0].rank, index + 1);
+        }
+    }
+}
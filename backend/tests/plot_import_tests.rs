@@ -0,0 +1,211 @@
+//! Plot import tests
+//!
+//! Tests for GeoJSON plot outlines:
+//! - Self-crossing rings are rejected
+//! - Area in rai follows from the outline
+//! - Outlines sharing only an edge do not overlap; nested or crossing ones do
+//! - Variety properties split into distinct names
+
+use proptest::prelude::*;
+
+type Position = (f64, f64);
+
+const EPSILON: f64 = 1e-7;
+const SQUARE_METRES_PER_RAI: f64 = 1600.0;
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+fn signed_area2(ring: &[Position]) -> f64 {
+    ring.windows(2).map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1).sum()
+}
+
+/// Mirrors `orientation`
+fn orientation(a: Position, b: Position, c: Position) -> i8 {
+    let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    let scale = ((b.0 - a.0).abs() + (b.1 - a.1).abs()).max(EPSILON);
+    if cross.abs() <= EPSILON * scale {
+        0
+    } else if cross > 0.0 {
+        1
+    } else {
+        -1
+    }
+}
+
+fn on_segment(a: Position, b: Position, p: Position) -> bool {
+    orientation(a, b, p) == 0
+        && p.0 >= a.0.min(b.0) - EPSILON
+        && p.0 <= a.0.max(b.0) + EPSILON
+        && p.1 >= a.1.min(b.1) - EPSILON
+        && p.1 <= a.1.max(b.1) + EPSILON
+}
+
+fn segments_cross(a: Position, b: Position, c: Position, d: Position) -> bool {
+    let (o1, o2, o3, o4) = (orientation(a, b, c), orientation(a, b, d), orientation(c, d, a), orientation(c, d, b));
+    o1 != 0 && o2 != 0 && o3 != 0 && o4 != 0 && o1 != o2 && o3 != o4
+}
+
+fn segments_touch(a: Position, b: Position, c: Position, d: Position) -> bool {
+    segments_cross(a, b, c, d) || on_segment(a, b, c) || on_segment(a, b, d) || on_segment(c, d, a) || on_segment(c, d, b)
+}
+
+/// Mirrors `inside_ring`
+fn inside_ring(ring: &[Position], p: Position) -> bool {
+    if ring.windows(2).any(|w| on_segment(w[0], w[1], p)) {
+        return false;
+    }
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Mirrors `self_intersects`
+fn self_intersects(ring: &[Position]) -> bool {
+    let segments = ring.len() - 1;
+    for i in 0..segments {
+        for j in (i + 1)..segments {
+            if j == i + 1 || (i == 0 && j == segments - 1) {
+                continue;
+            }
+            if segments_touch(ring[i], ring[i + 1], ring[j], ring[j + 1]) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Mirrors `area_rai` for a single ring without holes
+fn area_rai(ring: &[Position]) -> f64 {
+    let mean_lat = ring.iter().map(|p| p.1).sum::<f64>() / ring.len() as f64;
+    let x_scale = EARTH_RADIUS_M * mean_lat.to_radians().cos() * std::f64::consts::PI / 180.0;
+    let y_scale = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+    let projected: Vec<Position> = ring.iter().map(|(lon, lat)| (lon * x_scale, lat * y_scale)).collect();
+    signed_area2(&projected).abs() / 2.0 / SQUARE_METRES_PER_RAI
+}
+
+fn centroid(ring: &[Position]) -> Position {
+    let (mut area, mut x, mut y) = (0.0, 0.0, 0.0);
+    for w in ring.windows(2) {
+        let cross = w[0].0 * w[1].1 - w[1].0 * w[0].1;
+        area += cross;
+        x += (w[0].0 + w[1].0) * cross;
+        y += (w[0].1 + w[1].1) * cross;
+    }
+    (x / (3.0 * area), y / (3.0 * area))
+}
+
+/// Mirrors `outlines_overlap` for single rings without holes
+fn outlines_overlap(a: &[Position], b: &[Position]) -> bool {
+    for wa in a.windows(2) {
+        for wb in b.windows(2) {
+            if segments_cross(wa[0], wa[1], wb[0], wb[1]) {
+                return true;
+            }
+        }
+    }
+    let interior = |ring: &[Position]| Some(centroid(ring)).filter(|c| inside_ring(ring, *c));
+    a.iter().any(|v| inside_ring(b, *v))
+        || b.iter().any(|v| inside_ring(a, *v))
+        || interior(a).is_some_and(|c| inside_ring(b, c))
+        || interior(b).is_some_and(|c| inside_ring(a, c))
+}
+
+/// Mirrors `split_varieties`
+fn split_varieties(value: &str) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    value
+        .split([',', ';', '/', '|'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && seen.insert(v.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn square(lon: f64, lat: f64, size: f64) -> Vec<Position> {
+    vec![(lon, lat), (lon + size, lat), (lon + size, lat + size), (lon, lat + size), (lon, lat)]
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_bow_tie_crosses_itself() {
+        let bow_tie = vec![(98.9, 18.8), (98.91, 18.81), (98.91, 18.8), (98.9, 18.81), (98.9, 18.8)];
+        assert!(self_intersects(&bow_tie));
+        assert!(!self_intersects(&square(98.9, 18.8, 0.01)));
+    }
+
+    #[test]
+    fn test_area_of_hundred_metre_square_is_six_and_a_quarter_rai() {
+        // 100 m north-south and east-west at 18.8°N
+        let dlat = 100.0 / (EARTH_RADIUS_M * std::f64::consts::PI / 180.0);
+        let dlon = dlat / 18.8f64.to_radians().cos();
+        let ring = vec![(98.9, 18.8), (98.9 + dlon, 18.8), (98.9 + dlon, 18.8 + dlat), (98.9, 18.8 + dlat), (98.9, 18.8)];
+        assert!((area_rai(&ring) - 6.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_neighbours_sharing_an_edge_do_not_overlap() {
+        assert!(!outlines_overlap(&square(98.9, 18.8, 0.01), &square(98.91, 18.8, 0.01)));
+    }
+
+    #[test]
+    fn test_crossing_outlines_overlap() {
+        assert!(outlines_overlap(&square(98.9, 18.8, 0.01), &square(98.905, 18.805, 0.01)));
+    }
+
+    #[test]
+    fn test_nested_outline_overlaps() {
+        assert!(outlines_overlap(&square(98.9, 18.8, 0.01), &square(98.902, 18.802, 0.002)));
+    }
+
+    #[test]
+    fn test_identical_outlines_overlap() {
+        assert!(outlines_overlap(&square(98.9, 18.8, 0.01), &square(98.9, 18.8, 0.01)));
+    }
+
+    #[test]
+    fn test_split_varieties() {
+        assert_eq!(split_varieties("Typica, Catimor; typica / SL28"), vec!["Typica", "Catimor", "SL28"]);
+        assert!(split_varieties(" , ").is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_area_independent_of_ring_direction(lon in 97.0f64..105.0, lat in 6.0f64..20.0, size in 0.0005f64..0.05) {
+        let ring = square(lon, lat, size);
+        let reversed: Vec<Position> = ring.iter().rev().copied().collect();
+        prop_assert!((area_rai(&ring) - area_rai(&reversed)).abs() < 1e-6 * area_rai(&ring).max(1.0));
+        prop_assert!(area_rai(&ring) > 0.0);
+    }
+
+    #[test]
+    fn prop_overlap_is_symmetric(dx in -0.02f64..0.02, dy in -0.02f64..0.02) {
+        prop_assume!((dx.abs() - 0.01).abs() > 1e-6 && (dy.abs() - 0.01).abs() > 1e-6);
+        let a = square(98.9, 18.8, 0.01);
+        let b = square(98.9 + dx, 18.8 + dy, 0.01);
+        prop_assert_eq!(outlines_overlap(&a, &b), outlines_overlap(&b, &a));
+        // Equal squares overlap exactly when offset by less than a side both ways
+        prop_assert_eq!(outlines_overlap(&a, &b), dx.abs() < 0.01 && dy.abs() < 0.01);
+    }
+
+    #[test]
+    fn prop_squares_do_not_cross_themselves(lon in 97.0f64..105.0, lat in 6.0f64..20.0, size in 0.0005f64..0.05) {
+        prop_assert!(!self_intersects(&square(lon, lat, size)));
+    }
+}
@@ -0,0 +1,254 @@
+//! Plot profitability tests
+//!
+//! Tests for tracing lot revenue back to plots and setting it against costs:
+//! - Shares of a lot's coffee per plot and season, through blends
+//! - Spreading shared costs over plots by weight
+//! - Margin and cost per kg of cherry
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Mirrors `Origin`
+type Origin = (Uuid, i32);
+
+/// Mirrors `LotOrigins`
+#[derive(Debug, Clone, Default)]
+struct LotOrigins {
+    harvests: HashMap<Uuid, Vec<(Uuid, i32, Decimal)>>,
+    sources: HashMap<Uuid, Vec<(Uuid, Decimal)>>,
+}
+
+/// Mirrors `lot_shares`
+fn lot_shares(lot_id: Uuid, origins: &LotOrigins) -> BTreeMap<Origin, Decimal> {
+    fn walk(
+        lot_id: Uuid,
+        origins: &LotOrigins,
+        memo: &mut HashMap<Uuid, BTreeMap<Origin, Decimal>>,
+        visiting: &mut HashSet<Uuid>,
+    ) -> BTreeMap<Origin, Decimal> {
+        if let Some(shares) = memo.get(&lot_id) {
+            return shares.clone();
+        }
+        if !visiting.insert(lot_id) {
+            return BTreeMap::new();
+        }
+
+        let mut shares: BTreeMap<Origin, Decimal> = BTreeMap::new();
+        let harvests = origins.harvests.get(&lot_id).map(Vec::as_slice).unwrap_or_default();
+        let cherry: Decimal = harvests.iter().map(|(_, _, kg)| *kg).sum();
+        if cherry > Decimal::ZERO {
+            for (plot_id, season, kg) in harvests {
+                *shares.entry((*plot_id, *season)).or_default() += kg / cherry;
+            }
+        } else {
+            for (source_lot_id, percent) in origins.sources.get(&lot_id).map(Vec::as_slice).unwrap_or_default() {
+                let proportion = percent / Decimal::ONE_HUNDRED;
+                for (origin, share) in walk(*source_lot_id, origins, memo, visiting) {
+                    *shares.entry(origin).or_default() += share * proportion;
+                }
+            }
+        }
+
+        visiting.remove(&lot_id);
+        memo.insert(lot_id, shares.clone());
+        shares
+    }
+
+    walk(lot_id, origins, &mut HashMap::new(), &mut HashSet::new())
+}
+
+/// Mirrors `allocate`
+fn allocate(amount: Decimal, weights: &[(Uuid, Decimal)]) -> Vec<(Uuid, Decimal)> {
+    let total: Decimal = weights.iter().map(|(_, w)| *w).filter(|w| *w > Decimal::ZERO).sum();
+    if total <= Decimal::ZERO {
+        return Vec::new();
+    }
+    weights
+        .iter()
+        .filter(|(_, w)| *w > Decimal::ZERO)
+        .map(|(plot_id, w)| (*plot_id, amount * w / total))
+        .collect()
+}
+
+/// Mirrors `SeasonFigures`
+#[derive(Debug, Clone, Copy, Default)]
+struct SeasonFigures {
+    cherry_kg: Decimal,
+    revenue: Decimal,
+    direct_cost: Decimal,
+    shared_cost: Decimal,
+}
+
+/// Mirrors `Profit`
+#[derive(Debug, Clone, PartialEq)]
+struct Profit {
+    total_cost_thb: Decimal,
+    profit_thb: Decimal,
+    margin_percent: Option<Decimal>,
+    cost_per_kg_cherry: Option<Decimal>,
+}
+
+/// Mirrors `profit`
+fn profit(figures: &SeasonFigures) -> Profit {
+    let total_cost = figures.direct_cost + figures.shared_cost;
+    let profit = figures.revenue - total_cost;
+    Profit {
+        total_cost_thb: total_cost.round_dp(2),
+        profit_thb: profit.round_dp(2),
+        margin_percent: (figures.revenue > Decimal::ZERO)
+            .then(|| (profit / figures.revenue * Decimal::ONE_HUNDRED).round_dp(1)),
+        cost_per_kg_cherry: (figures.cherry_kg > Decimal::ZERO).then(|| (total_cost / figures.cherry_kg).round_dp(2)),
+    }
+}
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn id(n: u128) -> Uuid {
+    Uuid::from_u128(n)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_harvest_lot_splits_by_cherry() {
+        let mut origins = LotOrigins::default();
+        origins
+            .harvests
+            .insert(id(1), vec![(id(10), 2024, dec("300")), (id(11), 2024, dec("100"))]);
+
+        let shares = lot_shares(id(1), &origins);
+        assert_eq!(shares[&(id(10), 2024)], dec("0.75"));
+        assert_eq!(shares[&(id(11), 2024)], dec("0.25"));
+    }
+
+    #[test]
+    fn test_same_plot_in_two_seasons_is_kept_apart() {
+        let mut origins = LotOrigins::default();
+        origins
+            .harvests
+            .insert(id(1), vec![(id(10), 2023, dec("50")), (id(10), 2024, dec("150"))]);
+
+        let shares = lot_shares(id(1), &origins);
+        assert_eq!(shares[&(id(10), 2023)], dec("0.25"));
+        assert_eq!(shares[&(id(10), 2024)], dec("0.75"));
+    }
+
+    #[test]
+    fn test_blend_passes_shares_through_sources() {
+        let mut origins = LotOrigins::default();
+        origins.harvests.insert(id(1), vec![(id(10), 2024, dec("100"))]);
+        origins
+            .harvests
+            .insert(id(2), vec![(id(11), 2024, dec("100")), (id(12), 2024, dec("300"))]);
+        origins
+            .sources
+            .insert(id(3), vec![(id(1), dec("60")), (id(2), dec("40"))]);
+
+        let shares = lot_shares(id(3), &origins);
+        assert_eq!(shares[&(id(10), 2024)], dec("0.6"));
+        assert_eq!(shares[&(id(11), 2024)], dec("0.1"));
+        assert_eq!(shares[&(id(12), 2024)], dec("0.3"));
+    }
+
+    #[test]
+    fn test_blend_cycle_traces_to_nothing() {
+        let mut origins = LotOrigins::default();
+        origins.sources.insert(id(1), vec![(id(2), dec("100"))]);
+        origins.sources.insert(id(2), vec![(id(1), dec("100"))]);
+
+        assert!(lot_shares(id(1), &origins).is_empty());
+    }
+
+    #[test]
+    fn test_lot_without_harvests_or_sources_has_no_shares() {
+        assert!(lot_shares(id(1), &LotOrigins::default()).is_empty());
+    }
+
+    #[test]
+    fn test_shared_cost_spreads_by_weight() {
+        let shares = allocate(dec("9000"), &[(id(10), dec("2")), (id(11), dec("1")), (id(12), Decimal::ZERO)]);
+        assert_eq!(shares, vec![(id(10), dec("6000")), (id(11), dec("3000"))]);
+    }
+
+    #[test]
+    fn test_shared_cost_without_weights_is_not_spread() {
+        assert!(allocate(dec("500"), &[(id(10), Decimal::ZERO)]).is_empty());
+        assert!(allocate(dec("500"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_profit_margin_and_cost_per_kg() {
+        let figures = SeasonFigures {
+            cherry_kg: dec("1000"),
+            revenue: dec("40000"),
+            direct_cost: dec("12000"),
+            shared_cost: dec("3000"),
+        };
+        let result = profit(&figures);
+        assert_eq!(result.total_cost_thb, dec("15000"));
+        assert_eq!(result.profit_thb, dec("25000"));
+        assert_eq!(result.margin_percent, Some(dec("62.5")));
+        assert_eq!(result.cost_per_kg_cherry, Some(dec("15")));
+    }
+
+    #[test]
+    fn test_plot_without_revenue_or_cherry() {
+        let figures = SeasonFigures {
+            direct_cost: dec("2500"),
+            ..Default::default()
+        };
+        let result = profit(&figures);
+        assert_eq!(result.profit_thb, dec("-2500"));
+        assert_eq!(result.margin_percent, None);
+        assert_eq!(result.cost_per_kg_cherry, None);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_harvest_lot_shares_sum_to_one(
+        harvests in proptest::collection::vec((0u128..5, 2022i32..2026, 1u32..5000), 1..20),
+    ) {
+        let mut origins = LotOrigins::default();
+        origins.harvests.insert(
+            id(1),
+            harvests.iter().map(|(plot, season, kg)| (id(100 + plot), *season, Decimal::from(*kg))).collect(),
+        );
+        let total: Decimal = lot_shares(id(1), &origins).values().sum();
+        prop_assert!((total - Decimal::ONE).abs() < dec("0.000001"));
+    }
+
+    #[test]
+    fn prop_allocation_keeps_the_amount(
+        amount in 1u32..1_000_000,
+        weights in proptest::collection::vec(0u32..100, 1..10),
+    ) {
+        let weights: Vec<(Uuid, Decimal)> = weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (id(i as u128), Decimal::from(*w)))
+            .collect();
+        let shares = allocate(Decimal::from(amount), &weights);
+        if weights.iter().any(|(_, w)| *w > Decimal::ZERO) {
+            let spread: Decimal = shares.iter().map(|(_, s)| *s).sum();
+            prop_assert!((spread - Decimal::from(amount)).abs() < dec("0.0001"));
+        } else {
+            prop_assert!(shares.is_empty());
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Plot validation tests
+//!
+//! Tests for flagging harvests above a plausible yield:
+//! - Harvests count towards the crop season starting in October
+//! - A plot is flagged per season once its cherry per rai exceeds the limit
+//! - Plots without an area are not flagged
+
+use chrono::{Datelike, NaiveDate};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+const SEASON_START_MONTH: u32 = 10;
+
+/// Mirrors `crop_season`
+fn crop_season(date: NaiveDate) -> i32 {
+    if date.month() >= SEASON_START_MONTH {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
+/// Mirrors `season_label`
+fn season_label(season: i32) -> String {
+    format!("{}/{:02}", season, (season + 1).rem_euclid(100))
+}
+
+/// Mirrors `yield_flags` as (plot, season, cherry kg per rai)
+fn yield_flags(
+    areas: &[(u32, Option<Decimal>)],
+    harvests: &[(u32, NaiveDate, Decimal)],
+    max_kg_per_rai: Decimal,
+) -> Vec<(u32, i32, Decimal)> {
+    let mut totals: BTreeMap<(u32, i32), Decimal> = BTreeMap::new();
+    for (plot, date, kg) in harvests {
+        *totals.entry((*plot, crop_season(*date))).or_default() += *kg;
+    }
+    let mut flags: Vec<(u32, i32, Decimal)> = totals
+        .into_iter()
+        .filter_map(|((plot, season), kg)| {
+            let area = areas.iter().find(|(p, _)| *p == plot)?.1.filter(|a| *a > Decimal::ZERO)?;
+            let per_rai = kg / area;
+            (per_rai > max_kg_per_rai).then_some((plot, season, per_rai.round_dp(1)))
+        })
+        .collect();
+    flags.sort_by(|a, b| b.2.cmp(&a.2).then(a.1.cmp(&b.1)));
+    flags
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_season_spans_october_to_september() {
+        assert_eq!(crop_season(date(2024, 10, 1)), 2024);
+        assert_eq!(crop_season(date(2025, 3, 15)), 2024);
+        assert_eq!(crop_season(date(2025, 9, 30)), 2024);
+        assert_eq!(crop_season(date(2024, 9, 30)), 2023);
+    }
+
+    #[test]
+    fn test_season_label() {
+        assert_eq!(season_label(2024), "2024/25");
+        assert_eq!(season_label(1999), "1999/00");
+    }
+
+    #[test]
+    fn test_harvests_summed_within_season() {
+        // 2 rai, 2,600 + 2,600 kg in one season is 2,600 kg per rai
+        let areas = [(1, Some(Decimal::from(2)))];
+        let harvests = [
+            (1, date(2024, 11, 20), Decimal::from(2600)),
+            (1, date(2025, 1, 10), Decimal::from(2600)),
+            (1, date(2025, 11, 20), Decimal::from(2600)),
+        ];
+        let flags = yield_flags(&areas, &harvests, Decimal::from(2500));
+        assert_eq!(flags, vec![(1, 2024, Decimal::from(2600))]);
+    }
+
+    #[test]
+    fn test_plot_without_area_not_flagged() {
+        let areas = [(1, None), (2, Some(Decimal::ZERO))];
+        let harvests = [(1, date(2024, 12, 1), Decimal::from(100_000)), (2, date(2024, 12, 1), Decimal::from(100_000))];
+        assert!(yield_flags(&areas, &harvests, Decimal::from(2500)).is_empty());
+    }
+
+    #[test]
+    fn test_yield_at_limit_not_flagged() {
+        let areas = [(1, Some(Decimal::ONE))];
+        let harvests = [(1, date(2024, 12, 1), Decimal::from(2500))];
+        assert!(yield_flags(&areas, &harvests, Decimal::from(2500)).is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_season_contains_date(y in 2000i32..2100, ordinal in 1u32..366) {
+        let d = NaiveDate::from_yo_opt(y, ordinal).unwrap();
+        let season = crop_season(d);
+        prop_assert!(d >= date(season, SEASON_START_MONTH, 1));
+        prop_assert!(d < date(season + 1, SEASON_START_MONTH, 1));
+    }
+
+    #[test]
+    fn prop_flags_exceed_limit(
+        harvests in prop::collection::vec((0u32..3, 0i64..1000, 1u32..5000), 0..30),
+        area in 1u32..20,
+        max in 100u32..5000,
+    ) {
+        let areas: Vec<(u32, Option<Decimal>)> = (0..3).map(|p| (p, Some(Decimal::from(area)))).collect();
+        let harvests: Vec<(u32, NaiveDate, Decimal)> = harvests
+            .into_iter()
+            .map(|(p, days, kg)| (p, date(2023, 1, 1) + chrono::Duration::days(days), Decimal::from(kg)))
+            .collect();
+        let max = Decimal::from(max);
+        let flags = yield_flags(&areas, &harvests, max);
+        for (_, _, per_rai) in &flags {
+            prop_assert!(*per_rai >= max);
+        }
+        // Raising the limit never adds flags
+        prop_assert!(yield_flags(&areas, &harvests, max * Decimal::from(2)).len() <= flags.len());
+    }
+}
@@ -0,0 +1,249 @@
+//! Sale price and market reference tests
+//!
+//! Tests for realized prices against market references:
+//! - Conversion of C price, USD and THB quotes to THB per kg
+//! - The reference a sale is compared with (latest, not too old)
+//! - Quantity-weighted averages and premium over the market
+
+use chrono::{Duration, NaiveDate};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Mirrors `LB_PER_KG`
+const LB_PER_KG: Decimal = Decimal::from_parts(220_462_262, 0, 0, false, 8);
+
+/// Mirrors `MAX_REFERENCE_AGE_DAYS`
+const MAX_REFERENCE_AGE_DAYS: i64 = 31;
+
+/// Mirrors `PriceUnit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PriceUnit {
+    UscPerLb,
+    UsdPerKg,
+    ThbPerKg,
+}
+
+/// Mirrors `to_thb_per_kg`
+fn to_thb_per_kg(price: Decimal, unit: PriceUnit, thb_per_usd: Option<Decimal>) -> Option<Decimal> {
+    let thb = match unit {
+        PriceUnit::ThbPerKg => price,
+        PriceUnit::UsdPerKg => price * thb_per_usd?,
+        PriceUnit::UscPerLb => price / Decimal::ONE_HUNDRED * LB_PER_KG * thb_per_usd?,
+    };
+    Some(thb.round_dp(2))
+}
+
+/// Mirrors `reference_on`
+fn reference_on(references: &[(NaiveDate, Decimal)], date: NaiveDate) -> Option<Decimal> {
+    let earliest = date - Duration::days(MAX_REFERENCE_AGE_DAYS);
+    let index = references.partition_point(|(day, _)| *day <= date);
+    references[..index]
+        .last()
+        .filter(|(day, _)| *day >= earliest)
+        .map(|(_, price)| *price)
+}
+
+/// Mirrors `PricedSale`
+#[derive(Debug, Clone, Copy)]
+struct PricedSale {
+    quantity_kg: Decimal,
+    price_thb_per_kg: Decimal,
+    reference_thb_per_kg: Option<Decimal>,
+}
+
+/// Mirrors the premium fields of `PriceSummary`
+#[derive(Debug, Clone, PartialEq)]
+struct PriceSummary {
+    sold_kg: Decimal,
+    average_price_thb_per_kg: Option<Decimal>,
+    referenced_kg: Decimal,
+    average_reference_thb_per_kg: Option<Decimal>,
+    premium_thb_per_kg: Option<Decimal>,
+    premium_percent: Option<Decimal>,
+}
+
+/// Mirrors `summarize`
+fn summarize(sales: &[PricedSale]) -> PriceSummary {
+    let sold_kg: Decimal = sales.iter().map(|s| s.quantity_kg).sum();
+    let revenue: Decimal = sales.iter().map(|s| s.quantity_kg * s.price_thb_per_kg).sum();
+
+    let referenced: Vec<(Decimal, Decimal, Decimal)> = sales
+        .iter()
+        .filter_map(|s| s.reference_thb_per_kg.map(|r| (s.quantity_kg, s.price_thb_per_kg, r)))
+        .collect();
+    let referenced_kg: Decimal = referenced.iter().map(|(kg, _, _)| *kg).sum();
+    let (reference, premium) = if referenced_kg > Decimal::ZERO {
+        let reference = referenced.iter().map(|(kg, _, r)| kg * r).sum::<Decimal>() / referenced_kg;
+        let price = referenced.iter().map(|(kg, p, _)| kg * p).sum::<Decimal>() / referenced_kg;
+        (Some(reference), Some(price - reference))
+    } else {
+        (None, None)
+    };
+
+    PriceSummary {
+        sold_kg,
+        average_price_thb_per_kg: (sold_kg > Decimal::ZERO).then(|| (revenue / sold_kg).round_dp(2)),
+        referenced_kg,
+        average_reference_thb_per_kg: reference.map(|r| r.round_dp(2)),
+        premium_thb_per_kg: premium.map(|p| p.round_dp(2)),
+        premium_percent: reference
+            .zip(premium)
+            .filter(|(r, _)| *r > Decimal::ZERO)
+            .map(|(r, p)| (p / r * Decimal::ONE_HUNDRED).round_dp(1)),
+    }
+}
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn sale(quantity_kg: Decimal, price: Decimal, reference: Option<Decimal>) -> PricedSale {
+    PricedSale {
+        quantity_kg,
+        price_thb_per_kg: price,
+        reference_thb_per_kg: reference,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_c_price_converts_to_thb_per_kg() {
+        // 250 US cents per lb at 35 THB per USD
+        assert_eq!(to_thb_per_kg(dec("250"), PriceUnit::UscPerLb, Some(dec("35"))), Some(dec("192.90")));
+        assert_eq!(to_thb_per_kg(dec("5.5"), PriceUnit::UsdPerKg, Some(dec("36"))), Some(dec("198.00")));
+        assert_eq!(to_thb_per_kg(dec("180"), PriceUnit::ThbPerKg, None), Some(dec("180")));
+    }
+
+    #[test]
+    fn test_usd_prices_need_an_exchange_rate() {
+        assert_eq!(to_thb_per_kg(dec("250"), PriceUnit::UscPerLb, None), None);
+        assert_eq!(to_thb_per_kg(dec("5.5"), PriceUnit::UsdPerKg, None), None);
+    }
+
+    #[test]
+    fn test_sale_uses_latest_reference_on_or_before_its_date() {
+        let references = vec![
+            (date(2025, 3, 1), dec("190")),
+            (date(2025, 3, 10), dec("200")),
+            (date(2025, 3, 20), dec("210")),
+        ];
+        assert_eq!(reference_on(&references, date(2025, 2, 28)), None);
+        assert_eq!(reference_on(&references, date(2025, 3, 1)), Some(dec("190")));
+        assert_eq!(reference_on(&references, date(2025, 3, 15)), Some(dec("200")));
+        assert_eq!(reference_on(&references, date(2025, 3, 20)), Some(dec("210")));
+    }
+
+    #[test]
+    fn test_stale_reference_is_not_compared() {
+        let references = vec![(date(2025, 3, 1), dec("190"))];
+        assert_eq!(reference_on(&references, date(2025, 4, 1)), Some(dec("190")));
+        assert_eq!(reference_on(&references, date(2025, 4, 2)), None);
+    }
+
+    #[test]
+    fn test_premium_is_quantity_weighted() {
+        let summary = summarize(&[
+            sale(dec("100"), dec("300"), Some(dec("200"))),
+            sale(dec("300"), dec("260"), Some(dec("200"))),
+        ]);
+        assert_eq!(summary.sold_kg, dec("400"));
+        assert_eq!(summary.average_price_thb_per_kg, Some(dec("270")));
+        assert_eq!(summary.average_reference_thb_per_kg, Some(dec("200")));
+        assert_eq!(summary.premium_thb_per_kg, Some(dec("70")));
+        assert_eq!(summary.premium_percent, Some(dec("35.0")));
+    }
+
+    #[test]
+    fn test_premium_ignores_sales_without_reference() {
+        let summary = summarize(&[
+            sale(dec("100"), dec("300"), Some(dec("250"))),
+            sale(dec("100"), dec("100"), None),
+        ]);
+        assert_eq!(summary.average_price_thb_per_kg, Some(dec("200")));
+        assert_eq!(summary.referenced_kg, dec("100"));
+        assert_eq!(summary.premium_thb_per_kg, Some(dec("50")));
+        assert_eq!(summary.premium_percent, Some(dec("20.0")));
+    }
+
+    #[test]
+    fn test_no_sales_or_references() {
+        let empty = summarize(&[]);
+        assert_eq!(empty.sold_kg, Decimal::ZERO);
+        assert_eq!(empty.average_price_thb_per_kg, None);
+        assert_eq!(empty.premium_thb_per_kg, None);
+
+        let unreferenced = summarize(&[sale(dec("60"), dec("220"), None)]);
+        assert_eq!(unreferenced.average_price_thb_per_kg, Some(dec("220")));
+        assert_eq!(unreferenced.average_reference_thb_per_kg, None);
+        assert_eq!(unreferenced.premium_percent, None);
+    }
+
+    #[test]
+    fn test_below_market_sale_has_negative_premium() {
+        let summary = summarize(&[sale(dec("50"), dec("180"), Some(dec("200")))]);
+        assert_eq!(summary.premium_thb_per_kg, Some(dec("-20")));
+        assert_eq!(summary.premium_percent, Some(dec("-10.0")));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_reference_is_never_after_sale_or_too_old(
+        offsets in proptest::collection::btree_set(0i64..200, 0..10),
+        sale_offset in 0i64..220,
+    ) {
+        let start = date(2025, 1, 1);
+        let references: Vec<(NaiveDate, Decimal)> = offsets
+            .iter()
+            .map(|o| (start + Duration::days(*o), Decimal::from(*o)))
+            .collect();
+        let sale_date = start + Duration::days(sale_offset);
+        if let Some(price) = reference_on(&references, sale_date) {
+            let day = start + Duration::days(i64::try_from(price).unwrap());
+            prop_assert!(day <= sale_date);
+            prop_assert!((sale_date - day).num_days() <= MAX_REFERENCE_AGE_DAYS);
+            prop_assert!(!references.iter().any(|(d, _)| *d > day && *d <= sale_date));
+        }
+    }
+
+    #[test]
+    fn prop_average_price_is_between_min_and_max(
+        sales in proptest::collection::vec((1u32..5000, 50u32..1500), 1..20),
+    ) {
+        let priced: Vec<PricedSale> = sales
+            .iter()
+            .map(|(kg, price)| sale(Decimal::from(*kg), Decimal::from(*price), None))
+            .collect();
+        let average = summarize(&priced).average_price_thb_per_kg.unwrap();
+        let min = sales.iter().map(|(_, p)| *p).min().unwrap();
+        let max = sales.iter().map(|(_, p)| *p).max().unwrap();
+        prop_assert!(average >= Decimal::from(min) && average <= Decimal::from(max));
+    }
+
+    #[test]
+    fn prop_selling_at_reference_has_no_premium(
+        sales in proptest::collection::vec((1u32..5000, 50u32..1500), 1..20),
+    ) {
+        let priced: Vec<PricedSale> = sales
+            .iter()
+            .map(|(kg, price)| sale(Decimal::from(*kg), Decimal::from(*price), Some(Decimal::from(*price))))
+            .collect();
+        prop_assert_eq!(summarize(&priced).premium_thb_per_kg, Some(Decimal::ZERO));
+    }
+}
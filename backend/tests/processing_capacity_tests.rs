@@ -0,0 +1,211 @@
+//! Processing capacity tests
+//!
+//! Tests for planning tank and bed load:
+//! - Fermented batches sit in tanks, then on beds; naturals go straight to beds
+//! - Each week reports its peak load and utilization
+//! - A warning is raised once per week and resource type over capacity
+
+use chrono::{Duration, NaiveDate};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// Mirrors `PlannedBatch`: (start date, cherry kg, fermented)
+type Batch = (NaiveDate, Decimal, bool);
+
+/// Mirrors `CapacityWeek`: (week start, cherry in, peak tank, peak bed)
+type Week = (NaiveDate, Decimal, Decimal, Decimal);
+
+/// Mirrors `CapacityWarning`: (week start, is tank, first date, peak)
+type Warning = (NaiveDate, bool, NaiveDate, Decimal);
+
+/// Mirrors `plan_weeks`
+fn plan_weeks(
+    start: NaiveDate,
+    weeks: u32,
+    batches: &[Batch],
+    tank_capacity: Decimal,
+    bed_capacity: Decimal,
+    fermentation_days: u32,
+    drying_days: u32,
+) -> (Vec<Week>, Vec<Warning>) {
+    let fermentation = Duration::days(fermentation_days as i64);
+    let drying = Duration::days(drying_days as i64);
+    let mut plan = Vec::new();
+    let mut warnings = Vec::new();
+
+    for week in 0..weeks {
+        let week_start = start + Duration::days(7 * week as i64);
+        let week_end = week_start + Duration::days(7);
+        let (mut peak_tank, mut peak_bed) = (Decimal::ZERO, Decimal::ZERO);
+        let (mut tank_over, mut bed_over) = (None, None);
+
+        for offset in 0..7 {
+            let day = week_start + Duration::days(offset);
+            let (mut tank_load, mut bed_load) = (Decimal::ZERO, Decimal::ZERO);
+            for &(batch_start, kg, fermented) in batches {
+                let drying_start = if fermented {
+                    if batch_start <= day && day < batch_start + fermentation {
+                        tank_load += kg;
+                    }
+                    batch_start + fermentation
+                } else {
+                    batch_start
+                };
+                if drying_start <= day && day < drying_start + drying {
+                    bed_load += kg;
+                }
+            }
+            peak_tank = peak_tank.max(tank_load);
+            peak_bed = peak_bed.max(bed_load);
+            if tank_load > tank_capacity && tank_over.is_none() {
+                tank_over = Some(day);
+            }
+            if bed_load > bed_capacity && bed_over.is_none() {
+                bed_over = Some(day);
+            }
+        }
+
+        if let Some(first) = tank_over {
+            warnings.push((week_start, true, first, peak_tank));
+        }
+        if let Some(first) = bed_over {
+            warnings.push((week_start, false, first, peak_bed));
+        }
+
+        let cherry_in = batches
+            .iter()
+            .filter(|b| week_start <= b.0 && b.0 < week_end)
+            .map(|b| b.1)
+            .sum();
+        plan.push((week_start, cherry_in, peak_tank, peak_bed));
+    }
+
+    (plan, warnings)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_fermented_batch_moves_from_tank_to_bed() {
+        let batches = vec![(date("2024-11-04"), dec("500"), true)];
+        let (weeks, warnings) =
+            plan_weeks(date("2024-11-04"), 1, &batches, dec("1000"), dec("1000"), 2, 14);
+
+        assert_eq!(weeks[0], (date("2024-11-04"), dec("500"), dec("500"), dec("500")));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_natural_skips_tanks() {
+        let batches = vec![(date("2024-11-04"), dec("500"), false)];
+        let (weeks, _) = plan_weeks(date("2024-11-04"), 1, &batches, dec("100"), dec("1000"), 2, 14);
+
+        assert_eq!(weeks[0].2, Decimal::ZERO);
+        assert_eq!(weeks[0].3, dec("500"));
+    }
+
+    #[test]
+    fn test_overlapping_batches_exceed_tanks() {
+        // Two days' picking overlap in the tanks on the second day
+        let batches = vec![
+            (date("2024-11-04"), dec("600"), true),
+            (date("2024-11-05"), dec("600"), true),
+        ];
+        let (weeks, warnings) =
+            plan_weeks(date("2024-11-04"), 1, &batches, dec("1000"), dec("5000"), 2, 14);
+
+        assert_eq!(weeks[0].2, dec("1200"));
+        assert_eq!(warnings, vec![(date("2024-11-04"), true, date("2024-11-05"), dec("1200"))]);
+    }
+
+    #[test]
+    fn test_no_capacity_warns_on_any_load() {
+        let batches = vec![(date("2024-11-06"), dec("50"), true)];
+        let (_, warnings) = plan_weeks(date("2024-11-04"), 1, &batches, Decimal::ZERO, Decimal::ZERO, 2, 14);
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].2, date("2024-11-06"));
+        assert_eq!(warnings[1].2, date("2024-11-08"));
+    }
+
+    #[test]
+    fn test_drying_carries_into_next_week() {
+        let batches = vec![(date("2024-11-04"), dec("300"), true)];
+        let (weeks, _) = plan_weeks(date("2024-11-04"), 3, &batches, dec("1000"), dec("1000"), 2, 14);
+
+        // Beds hold the batch from Nov 6 up to Nov 20
+        assert_eq!(weeks[1].1, Decimal::ZERO);
+        assert_eq!(weeks[1].3, dec("300"));
+        assert_eq!(weeks[2].3, dec("300"));
+    }
+
+    #[test]
+    fn test_batch_started_before_plan_counted() {
+        // Still on the beds when the plan starts
+        let batches = vec![(date("2024-10-30"), dec("400"), false)];
+        let (weeks, _) = plan_weeks(date("2024-11-04"), 1, &batches, dec("1000"), dec("1000"), 2, 14);
+
+        assert_eq!(weeks[0].1, Decimal::ZERO);
+        assert_eq!(weeks[0].3, dec("400"));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_warnings_match_peaks(
+        loads in prop::collection::vec((0i64..28, 1u32..2000, any::<bool>()), 0..20),
+        tank_capacity in 0u32..3000,
+        bed_capacity in 0u32..10000,
+    ) {
+        let start = date("2024-11-04");
+        let batches: Vec<Batch> = loads
+            .iter()
+            .map(|&(offset, kg, fermented)| (start + Duration::days(offset), Decimal::from(kg), fermented))
+            .collect();
+        let (tank_capacity, bed_capacity) = (Decimal::from(tank_capacity), Decimal::from(bed_capacity));
+        let (weeks, warnings) = plan_weeks(start, 4, &batches, tank_capacity, bed_capacity, 2, 14);
+
+        prop_assert_eq!(weeks.len(), 4);
+        for (week_start, _, peak_tank, peak_bed) in &weeks {
+            let tank_warned = warnings.iter().any(|w| w.0 == *week_start && w.1);
+            let bed_warned = warnings.iter().any(|w| w.0 == *week_start && !w.1);
+            prop_assert_eq!(tank_warned, *peak_tank > tank_capacity);
+            prop_assert_eq!(bed_warned, *peak_bed > bed_capacity);
+        }
+    }
+
+    #[test]
+    fn prop_cherry_in_covers_batches_in_horizon(
+        loads in prop::collection::vec((0i64..28, 1u32..2000), 0..20),
+    ) {
+        let start = date("2024-11-04");
+        let batches: Vec<Batch> = loads
+            .iter()
+            .map(|&(offset, kg)| (start + Duration::days(offset), Decimal::from(kg), true))
+            .collect();
+        let (weeks, _) = plan_weeks(start, 4, &batches, dec("1000"), dec("1000"), 2, 14);
+
+        let total: Decimal = batches.iter().map(|b| b.1).sum();
+        prop_assert_eq!(weeks.iter().map(|w| w.1).sum::<Decimal>(), total);
+    }
+}
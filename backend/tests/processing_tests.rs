@@ -12,15 +12,9 @@ fn dec(s: &str) -> Decimal {
     Decimal::from_str(s).unwrap()
 }
 
-/// Calculate processing yield percentage
+/// Processing yield shared with the processing service and WASM client
 /// Yield = (green_bean_weight / cherry_weight) × 100
-fn calculate_processing_yield(cherry_weight: Decimal, green_bean_weight: Decimal) -> Decimal {
-    if cherry_weight.is_zero() {
-        Decimal::ZERO
-    } else {
-        (green_bean_weight / cherry_weight) * Decimal::from(100)
-    }
-}
+use shared::calculate_processing_yield;
 
 // ============================================================================
 // Property 8: Processing Yield Calculation
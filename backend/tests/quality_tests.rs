@@ -0,0 +1,212 @@
+//! Quality spec and evaluation tests
+//!
+//! Tests for combined grading + cupping decisions:
+//! - Only limits set on a spec are checked
+//! - Limits are inclusive; an evaluation passes only if every check passes
+//! - Missing measurements fail their checks
+//! - Specs apply to the buyers and markets they are assigned to
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Mirrors the limits of `QualitySpec`
+#[derive(Debug, Default, Clone)]
+struct SpecLimits {
+    min_cupping_score: Option<Decimal>,
+    max_category1_defects: Option<i32>,
+    max_category2_defects: Option<i32>,
+    min_moisture_percent: Option<Decimal>,
+    max_moisture_percent: Option<Decimal>,
+    /// (screen size, minimum percent of the sample on it or larger)
+    min_screen: Option<(u8, Decimal)>,
+    buyers: Vec<String>,
+    markets: Vec<String>,
+}
+
+/// Mirrors `MeasuredQuality`; screen shares are 18+, 17, 16, 15, 14-
+#[derive(Debug, Default, Clone)]
+struct Measured {
+    cupping_score: Option<Decimal>,
+    category1: Option<i32>,
+    category2: Option<i32>,
+    moisture: Option<Decimal>,
+    screen: Option<[Decimal; 5]>,
+}
+
+fn measured(cupping_score: &str, category1: i32, category2: i32, moisture: &str) -> Measured {
+    Measured {
+        cupping_score: Some(dec(cupping_score)),
+        category1: Some(category1),
+        category2: Some(category2),
+        moisture: Some(dec(moisture)),
+        screen: None,
+    }
+}
+
+/// Mirrors `check_spec`; returns (criterion, passed)
+fn check_spec(spec: &SpecLimits, measured: &Measured) -> Vec<(&'static str, bool)> {
+    let within = |actual: Option<Decimal>, min: Option<Decimal>, max: Option<Decimal>| {
+        actual.is_some_and(|a| min.iter().all(|m| a >= *m) && max.iter().all(|m| a <= *m))
+    };
+
+    let mut checks = Vec::new();
+    if spec.min_cupping_score.is_some() {
+        checks.push(("cupping_score", within(measured.cupping_score, spec.min_cupping_score, None)));
+    }
+    if let Some(max) = spec.max_category1_defects {
+        checks.push(("category1_defects", measured.category1.is_some_and(|c| c <= max)));
+    }
+    if let Some(max) = spec.max_category2_defects {
+        checks.push(("category2_defects", measured.category2.is_some_and(|c| c <= max)));
+    }
+    if spec.min_moisture_percent.is_some() || spec.max_moisture_percent.is_some() {
+        checks.push((
+            "moisture_percent",
+            within(measured.moisture, spec.min_moisture_percent, spec.max_moisture_percent),
+        ));
+    }
+    if let Some((size, percent)) = spec.min_screen {
+        // Bands from 18+ down; screen N keeps every band of N and above
+        let bands = (18 - size.clamp(14, 18)) as usize + 1;
+        let share = measured.screen.map(|s| s.iter().take(bands).sum::<Decimal>());
+        checks.push(("screen_size", within(share, Some(percent), None)));
+    }
+    checks
+}
+
+/// Mirrors `spec_applies`
+fn spec_applies(spec: &SpecLimits, buyer: Option<&str>, market: Option<&str>) -> bool {
+    let listed = |names: &[String], wanted: Option<&str>| match wanted {
+        Some(wanted) => names.iter().any(|n| n.trim().eq_ignore_ascii_case(wanted.trim())),
+        None => true,
+    };
+    listed(&spec.buyers, buyer) && listed(&spec.markets, market)
+}
+
+/// SCA specialty grade: 80+ points, no category 1 and at most 5 category 2
+/// defects, 10-12% moisture
+fn specialty() -> SpecLimits {
+    SpecLimits {
+        min_cupping_score: Some(dec("80")),
+        max_category1_defects: Some(0),
+        max_category2_defects: Some(5),
+        min_moisture_percent: Some(dec("10")),
+        max_moisture_percent: Some(dec("12")),
+        ..Default::default()
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_specialty_lot_passes_all_checks() {
+        let checks = check_spec(&specialty(), &measured("84.5", 0, 3, "11.2"));
+        assert_eq!(checks.len(), 4);
+        assert!(checks.iter().all(|(_, passed)| *passed));
+    }
+
+    #[test]
+    fn test_limits_are_inclusive() {
+        let checks = check_spec(&specialty(), &measured("80", 0, 5, "12"));
+        assert!(checks.iter().all(|(_, passed)| *passed));
+    }
+
+    #[test]
+    fn test_each_failure_reported() {
+        let checks = check_spec(&specialty(), &measured("79.75", 1, 5, "12.5"));
+        let failed: Vec<&str> = checks.iter().filter(|(_, p)| !p).map(|(c, _)| *c).collect();
+        assert_eq!(failed, vec!["cupping_score", "category1_defects", "moisture_percent"]);
+    }
+
+    #[test]
+    fn test_unset_limits_not_checked() {
+        let spec = SpecLimits {
+            min_cupping_score: Some(dec("85")),
+            ..Default::default()
+        };
+        assert_eq!(check_spec(&spec, &measured("86", 12, 40, "16")), vec![("cupping_score", true)]);
+        assert!(check_spec(&SpecLimits::default(), &measured("50", 99, 99, "30")).is_empty());
+    }
+
+    #[test]
+    fn test_missing_measurements_fail() {
+        // Graded but never cupped
+        let graded_only = Measured {
+            cupping_score: None,
+            ..measured("0", 0, 2, "11")
+        };
+        let failed: Vec<&str> = check_spec(&specialty(), &graded_only)
+            .into_iter()
+            .filter(|(_, p)| !p)
+            .map(|(c, _)| c)
+            .collect();
+        assert_eq!(failed, vec!["cupping_score"]);
+    }
+
+    #[test]
+    fn test_screen_size_requirement() {
+        let spec = SpecLimits {
+            min_screen: Some((16, dec("80"))),
+            ..Default::default()
+        };
+        let mut lot = measured("85", 0, 0, "11");
+        assert_eq!(check_spec(&spec, &lot), vec![("screen_size", false)]);
+
+        lot.screen = Some([dec("20"), dec("35"), dec("25"), dec("15"), dec("5")]);
+        assert_eq!(check_spec(&spec, &lot), vec![("screen_size", true)]);
+
+        lot.screen = Some([dec("20"), dec("35"), dec("24.5"), dec("15.5"), dec("5")]);
+        assert_eq!(check_spec(&spec, &lot), vec![("screen_size", false)]);
+    }
+
+    #[test]
+    fn test_spec_assignment_filters() {
+        let spec = SpecLimits {
+            buyers: vec!["Nordic Roasters".to_string()],
+            markets: vec!["EU".to_string(), "Japan".to_string()],
+            ..Default::default()
+        };
+        assert!(spec_applies(&spec, None, None));
+        assert!(spec_applies(&spec, Some("nordic roasters"), Some("japan")));
+        assert!(!spec_applies(&spec, Some("Other Buyer"), None));
+        assert!(!spec_applies(&spec, None, Some("US")));
+        // Unassigned specs only match when no filter is given
+        assert!(!spec_applies(&SpecLimits::default(), Some("Nordic Roasters"), None));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+
+    proptest! {
+        /// Raising the cupping score never turns a passing check into a failure
+        #[test]
+        fn prop_higher_score_never_fails(score in 6000i64..10000, bump in 0i64..500) {
+            let spec = specialty();
+            let at = |score: i64| Measured {
+                cupping_score: Some(Decimal::new(score, 2)),
+                ..measured("0", 0, 0, "11")
+            };
+            let before = check_spec(&spec, &at(score));
+            let after = check_spec(&spec, &at(score + bump));
+            prop_assert!(!before[0].1 || after[0].1);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Recycle bin tests
+//!
+//! Tests for the helpers behind keeping and restoring deleted records:
+//! - Entity names in the bin and the tables they map to
+//! - Lot photos stay out of the bin
+//! - Ids of captured rows
+//! - Retention periods a business can choose
+
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Mirrors `MAX_RETENTION_DAYS`
+const MAX_RETENTION_DAYS: i32 = 365;
+
+/// Mirrors `DeletedEntity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeletedEntity {
+    Plot,
+    Harvest,
+    FarmActivity,
+    Certification,
+    LabResult,
+    Shipment,
+    InsurancePolicy,
+    WaterQuality,
+    MoistureReading,
+    CuppingSample,
+    Grading,
+}
+
+const ALL: [DeletedEntity; 11] = [
+    DeletedEntity::Plot,
+    DeletedEntity::Harvest,
+    DeletedEntity::FarmActivity,
+    DeletedEntity::Certification,
+    DeletedEntity::LabResult,
+    DeletedEntity::Shipment,
+    DeletedEntity::InsurancePolicy,
+    DeletedEntity::WaterQuality,
+    DeletedEntity::MoistureReading,
+    DeletedEntity::CuppingSample,
+    DeletedEntity::Grading,
+];
+
+impl DeletedEntity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plot => "plot",
+            Self::Harvest => "harvest",
+            Self::FarmActivity => "farm_activity",
+            Self::Certification => "certification",
+            Self::LabResult => "lab_result",
+            Self::Shipment => "shipment",
+            Self::InsurancePolicy => "insurance_policy",
+            Self::WaterQuality => "water_quality",
+            Self::MoistureReading => "moisture_reading",
+            Self::CuppingSample => "cupping_sample",
+            Self::Grading => "grading",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "plot" => Some(Self::Plot),
+            "harvest" => Some(Self::Harvest),
+            "farm_activity" => Some(Self::FarmActivity),
+            "certification" => Some(Self::Certification),
+            "lab_result" => Some(Self::LabResult),
+            "shipment" => Some(Self::Shipment),
+            "insurance_policy" => Some(Self::InsurancePolicy),
+            "water_quality" => Some(Self::WaterQuality),
+            "moisture_reading" => Some(Self::MoistureReading),
+            "cupping_sample" => Some(Self::CuppingSample),
+            "grading" => Some(Self::Grading),
+            _ => None,
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Plot => "plots",
+            Self::Harvest => "harvests",
+            Self::FarmActivity => "farm_activities",
+            Self::Certification => "certifications",
+            Self::LabResult => "lab_results",
+            Self::Shipment => "shipments",
+            Self::InsurancePolicy => "lot_insurance_policies",
+            Self::WaterQuality => "water_quality_measurements",
+            Self::MoistureReading => "moisture_readings",
+            Self::CuppingSample => "cupping_samples",
+            Self::Grading => "green_bean_grades",
+        }
+    }
+}
+
+/// Mirrors `row_id`
+fn row_id(row: &Value) -> Option<Uuid> {
+    row.get("id")?.as_str()?.parse().ok()
+}
+
+/// Mirrors the range check of `validate_retention_days`
+fn retention_is_valid(days: i32) -> bool {
+    (1..=MAX_RETENTION_DAYS).contains(&days)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_entity_names_round_trip() {
+        for entity in ALL {
+            assert_eq!(DeletedEntity::from_str(entity.as_str()), Some(entity));
+        }
+    }
+
+    #[test]
+    fn test_unknown_entity_is_rejected() {
+        assert_eq!(DeletedEntity::from_str("lot"), None);
+        assert_eq!(DeletedEntity::from_str("Plot"), None);
+        assert_eq!(DeletedEntity::from_str(""), None);
+    }
+
+    #[test]
+    fn test_each_entity_has_its_own_table() {
+        let tables: HashSet<&str> = ALL.iter().map(|e| e.table()).collect();
+        assert_eq!(tables.len(), ALL.len());
+        assert_eq!(DeletedEntity::InsurancePolicy.table(), "lot_insurance_policies");
+        assert_eq!(DeletedEntity::Grading.table(), "green_bean_grades");
+    }
+
+    #[test]
+    fn test_lot_photos_stay_out_of_the_bin() {
+        // A deleted photo's file is removed from storage, so its row is not kept
+        assert_eq!(DeletedEntity::from_str("lot_photo"), None);
+        assert_eq!(DeletedEntity::from_str("media"), None);
+        assert!(ALL.iter().all(|e| e.table() != "media"));
+    }
+
+    #[test]
+    fn test_row_id_reads_the_id_column() {
+        let id = Uuid::from_u128(42);
+        let row = json!({ "id": id.to_string(), "name": "Doi Chang A" });
+        assert_eq!(row_id(&row), Some(id));
+    }
+
+    #[test]
+    fn test_row_without_id_has_none() {
+        // Junction tables such as plot varieties may have no id column
+        assert_eq!(row_id(&json!({ "plot_id": Uuid::nil().to_string() })), None);
+        assert_eq!(row_id(&json!({ "id": 7 })), None);
+        assert_eq!(row_id(&json!({ "id": "not-a-uuid" })), None);
+    }
+
+    #[test]
+    fn test_retention_bounds() {
+        assert!(!retention_is_valid(0));
+        assert!(retention_is_valid(1));
+        assert!(retention_is_valid(30));
+        assert!(retention_is_valid(365));
+        assert!(!retention_is_valid(366));
+        assert!(!retention_is_valid(-5));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_row_id_round_trips(n in any::<u128>()) {
+        let id = Uuid::from_u128(n);
+        prop_assert_eq!(row_id(&json!({ "id": id.to_string() })), Some(id));
+    }
+
+    #[test]
+    fn prop_retention_in_range_is_valid(days in -1000i32..1000) {
+        prop_assert_eq!(retention_is_valid(days), (1..=365).contains(&days));
+    }
+}
@@ -0,0 +1,113 @@
+//! Reference data tests
+//!
+//! Tests for versioning reference catalogs:
+//! - A catalog's version depends only on its content
+//! - Items are left out when the client already holds the version
+//! - Bundle versions split back into catalog versions, or match nothing
+
+use proptest::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const VERSION_SEPARATOR: char = '.';
+
+/// Mirrors `catalog_version`
+fn catalog_version<T: Serialize>(items: &[T]) -> String {
+    let json = serde_json::to_vec(items).unwrap_or_default();
+    Sha256::digest(&json)
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Mirrors `build_catalog`: (version, changed, items)
+fn build_catalog<T: Serialize>(items: Vec<T>, known: Option<&str>) -> (String, bool, Option<Vec<T>>) {
+    let version = catalog_version(&items);
+    let changed = known != Some(version.as_str());
+    let items = changed.then_some(items);
+    (version, changed, items)
+}
+
+/// Mirrors `split_bundle_version`
+fn split_bundle_version(since: Option<&str>, catalogs: usize) -> Vec<Option<&str>> {
+    let parts: Vec<&str> = since.map(|s| s.split(VERSION_SEPARATOR).collect()).unwrap_or_default();
+    if parts.len() != catalogs {
+        return vec![None; catalogs];
+    }
+    parts.into_iter().map(Some).collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_version_is_16_hex_digits() {
+        let version = catalog_version(&["typica", "catimor"]);
+        assert_eq!(version.len(), 16);
+        assert!(version.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_version_changes_with_content() {
+        let before = catalog_version(&["typica", "catimor"]);
+        let after = catalog_version(&["typica", "catimor", "geisha"]);
+        assert_ne!(before, after);
+        assert_eq!(before, catalog_version(&["typica", "catimor"]));
+    }
+
+    #[test]
+    fn test_unchanged_catalog_has_no_items() {
+        let version = catalog_version(&["floral", "nutty"]);
+        let (_, changed, items) = build_catalog(vec!["floral", "nutty"], Some(&version));
+
+        assert!(!changed);
+        assert!(items.is_none());
+    }
+
+    #[test]
+    fn test_first_sync_returns_everything() {
+        let (_, changed, items) = build_catalog(vec!["floral", "nutty"], None);
+
+        assert!(changed);
+        assert_eq!(items, Some(vec!["floral", "nutty"]));
+    }
+
+    #[test]
+    fn test_split_bundle_version() {
+        assert_eq!(
+            split_bundle_version(Some("a.b.c.d.e"), 5),
+            vec![Some("a"), Some("b"), Some("c"), Some("d"), Some("e")]
+        );
+    }
+
+    #[test]
+    fn test_bundle_version_from_older_catalog_set_matches_nothing() {
+        assert_eq!(split_bundle_version(Some("a.b.c.d"), 5), vec![None; 5]);
+        assert_eq!(split_bundle_version(None, 5), vec![None; 5]);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_bundle_version_round_trips(items in prop::collection::vec(prop::collection::vec("[a-z]{1,8}", 0..6), 5)) {
+        let versions: Vec<String> = items.iter().map(|i| catalog_version(i)).collect();
+        let bundle = versions.join(&VERSION_SEPARATOR.to_string());
+
+        let known = split_bundle_version(Some(&bundle), 5);
+        for (catalog, known) in items.into_iter().zip(known) {
+            let (_, changed, returned) = build_catalog(catalog, known);
+            prop_assert!(!changed);
+            prop_assert!(returned.is_none());
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! Report builder tests
+//!
+//! Tests for the configurable report builder including:
+//! - Filter value validation per column type
+//! - LIKE wildcard escaping for `contains` filters
+//! - Grouped report output columns
+//! - XLSX column widths for Thai text and sheet naming
+//! - Monthly financial totals and stage shares
+//! - Next run calculation for scheduled delivery
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Text,
+    Number,
+    Integer,
+    Date,
+    Uuid,
+}
+
+/// Mirrors `normalize_filter_value` in the report builder service
+fn normalize_filter_value(column_type: ColumnType, value: &serde_json::Value) -> Option<String> {
+    let text = match value {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+
+    let valid = match column_type {
+        ColumnType::Text => true,
+        ColumnType::Number => Decimal::from_str(&text).is_ok(),
+        ColumnType::Integer => text.parse::<i64>().is_ok(),
+        ColumnType::Date => NaiveDate::parse_from_str(&text, "%Y-%m-%d").is_ok(),
+        ColumnType::Uuid => uuid::Uuid::parse_str(&text).is_ok(),
+    };
+
+    valid.then_some(text)
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Mirrors `display_width` in the XLSX writer
+fn display_width(text: &str) -> usize {
+    text.lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !matches!(*c as u32, 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Mirrors `unique_sheet_name` in the XLSX writer
+fn unique_sheet_name(name: &str, taken: &[String]) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .take(31)
+        .collect();
+    let base = if cleaned.trim().is_empty() { "Sheet".to_string() } else { cleaned };
+
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.to_lowercase() == candidate.to_lowercase());
+    let mut candidate = base.clone();
+    let mut suffix = 2;
+    while is_taken(&candidate) {
+        let tag = format!(" ({})", suffix);
+        let stem: String = base.chars().take(31 - tag.chars().count()).collect();
+        candidate = format!("{}{}", stem, tag);
+        suffix += 1;
+    }
+    candidate
+}
+
+fn percent_of(part: Decimal, total: Decimal) -> Decimal {
+    if total.is_zero() {
+        Decimal::ZERO
+    } else {
+        (part * Decimal::from(100) / total).round_dp(2)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MonthlyTotals {
+    sold_kg: Decimal,
+    sales: Decimal,
+    purchased_kg: Decimal,
+    purchases: Decimal,
+}
+
+/// Mirrors `monthly_totals` in the XLSX template service
+fn monthly_totals(
+    transactions: &[(NaiveDate, &str, bool, Decimal, Decimal)],
+) -> BTreeMap<(NaiveDate, String), MonthlyTotals> {
+    let mut months: BTreeMap<(NaiveDate, String), MonthlyTotals> = BTreeMap::new();
+    for (date, currency, is_sale, quantity_kg, amount) in transactions {
+        let month = date.with_day(1).unwrap_or(*date);
+        let totals = months.entry((month, currency.to_string())).or_default();
+        if *is_sale {
+            totals.sold_kg += quantity_kg;
+            totals.sales += amount;
+        } else {
+            totals.purchased_kg += quantity_kg;
+            totals.purchases += amount;
+        }
+    }
+    months
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// Output key of an aggregate column
+fn aggregate_key(function: &str, column: &str) -> String {
+    format!("{}_{}", function, column.replace('*', "rows"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScheduleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Mirrors `next_run_after` in the report schedule service
+fn next_run_after(
+    frequency: ScheduleFrequency,
+    day_of_week: Option<i16>,
+    day_of_month: Option<i16>,
+    time_of_day: NaiveTime,
+    utc_offset_minutes: i32,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let offset = Duration::minutes(utc_offset_minutes as i64);
+    let local_today = (after + offset).date_naive();
+
+    (0..=62)
+        .map(|days| local_today + Duration::days(days))
+        .filter(|date| match frequency {
+            ScheduleFrequency::Daily => true,
+            ScheduleFrequency::Weekly => {
+                day_of_week == Some(date.weekday().number_from_monday() as i16)
+            }
+            ScheduleFrequency::Monthly => day_of_month == Some(date.day() as i16),
+        })
+        .map(|date| (date.and_time(time_of_day) - offset).and_utc())
+        .find(|candidate| *candidate > after)
+}
+
+const BANGKOK: i32 = 420;
+
+fn seven_am() -> NaiveTime {
+    NaiveTime::from_hms_opt(7, 0, 0).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter_values_validated_by_type() {
+        assert_eq!(
+            normalize_filter_value(ColumnType::Number, &json!(12.5)),
+            Some("12.5".to_string())
+        );
+        assert_eq!(
+            normalize_filter_value(ColumnType::Number, &json!("12.5")),
+            Some("12.5".to_string())
+        );
+        assert_eq!(normalize_filter_value(ColumnType::Number, &json!("12,5")), None);
+        assert_eq!(normalize_filter_value(ColumnType::Integer, &json!("80.5")), None);
+        assert_eq!(
+            normalize_filter_value(ColumnType::Date, &json!("2024-12-01")),
+            Some("2024-12-01".to_string())
+        );
+        assert_eq!(normalize_filter_value(ColumnType::Date, &json!("01/12/2024")), None);
+        assert_eq!(normalize_filter_value(ColumnType::Uuid, &json!("not-a-uuid")), None);
+    }
+
+    #[test]
+    fn test_injection_attempts_rejected_for_typed_columns() {
+        let payload = json!("1; DROP TABLE lots; --");
+        assert_eq!(normalize_filter_value(ColumnType::Number, &payload), None);
+        assert_eq!(normalize_filter_value(ColumnType::Integer, &payload), None);
+        assert_eq!(normalize_filter_value(ColumnType::Date, &payload), None);
+
+        // Text values are accepted but only ever bound as parameters
+        assert!(normalize_filter_value(ColumnType::Text, &payload).is_some());
+    }
+
+    #[test]
+    fn test_non_scalar_filter_values_rejected() {
+        assert_eq!(normalize_filter_value(ColumnType::Text, &json!(null)), None);
+        assert_eq!(normalize_filter_value(ColumnType::Text, &json!(["a"])), None);
+        assert_eq!(normalize_filter_value(ColumnType::Text, &json!({"a": 1})), None);
+    }
+
+    #[test]
+    fn test_escape_like_wildcards() {
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("lot_a"), "lot\\_a");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(escape_like("กาแฟ"), "กาแฟ");
+    }
+
+    #[test]
+    fn test_aggregate_output_keys() {
+        assert_eq!(aggregate_key("sum", "cherry_weight_kg"), "sum_cherry_weight_kg");
+        assert_eq!(aggregate_key("count", "*"), "count_rows");
+    }
+
+    #[test]
+    fn test_display_width_ignores_thai_marks() {
+        assert_eq!(display_width("Lot"), 3);
+        // น้ำหนัก: ้ and ั stack on their consonants
+        assert_eq!(display_width("น้ำหนัก"), 5);
+        assert_eq!(display_width("ล็อต"), 3);
+        assert_eq!(display_width("first\nlonger line"), 11);
+    }
+
+    #[test]
+    fn test_sheet_names_sanitized_and_unique() {
+        assert_eq!(unique_sheet_name("Sales 2024/12", &[]), "Sales 2024_12");
+        assert_eq!(unique_sheet_name("  ", &[]), "Sheet");
+        assert_eq!(unique_sheet_name("sales", &["Sales".to_string()]), "sales (2)");
+
+        let long = "ก".repeat(40);
+        let first = unique_sheet_name(&long, &[]);
+        assert_eq!(first.chars().count(), 31);
+        let second = unique_sheet_name(&long, std::slice::from_ref(&first));
+        assert_eq!(second.chars().count(), 31);
+        assert!(second.ends_with(" (2)"));
+    }
+
+    #[test]
+    fn test_monthly_totals_split_by_month_and_currency() {
+        let months = monthly_totals(&[
+            (date(2024, 11, 3), "THB", true, Decimal::from(10), Decimal::from(5000)),
+            (date(2024, 11, 20), "THB", false, Decimal::from(40), Decimal::from(8000)),
+            (date(2024, 11, 21), "USD", true, Decimal::from(5), Decimal::from(150)),
+            (date(2024, 12, 1), "THB", true, Decimal::from(2), Decimal::from(1200)),
+        ]);
+
+        assert_eq!(months.len(), 3);
+        let november = &months[&(date(2024, 11, 1), "THB".to_string())];
+        assert_eq!(november.sold_kg, Decimal::from(10));
+        assert_eq!(november.sales - november.purchases, Decimal::from(-3000));
+        assert_eq!(months[&(date(2024, 11, 1), "USD".to_string())].sales, Decimal::from(150));
+        assert_eq!(months.keys().last().unwrap().0, date(2024, 12, 1));
+    }
+
+    #[test]
+    fn test_stage_share_percent() {
+        assert_eq!(percent_of(Decimal::from(25), Decimal::from(200)), Decimal::new(1250, 2));
+        assert_eq!(percent_of(Decimal::from(5), Decimal::ZERO), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_weekly_schedule_monday_morning_bangkok() {
+        // Friday 2026-10-16 12:00 Bangkok
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 5, 0, 0).unwrap();
+        let next = next_run_after(ScheduleFrequency::Weekly, Some(1), None, seven_am(), BANGKOK, now);
+
+        // Monday 07:00 Bangkok = 00:00 UTC
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_schedule_due_exactly_now_moves_to_next_period() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap();
+        let next = next_run_after(ScheduleFrequency::Weekly, Some(1), None, seven_am(), BANGKOK, now);
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2026, 10, 26, 0, 0, 0).unwrap()));
+
+        let daily = next_run_after(ScheduleFrequency::Daily, None, None, seven_am(), BANGKOK, now);
+        assert_eq!(daily, Some(Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_local_date_used_across_utc_midnight() {
+        // 23:30 UTC Sunday is already Monday 06:30 in Bangkok
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 23, 30, 0).unwrap();
+        let next = next_run_after(ScheduleFrequency::Weekly, Some(1), None, seven_am(), BANGKOK, now);
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_monthly_schedule_rolls_into_next_month() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 29, 0, 0, 0).unwrap();
+        let next = next_run_after(ScheduleFrequency::Monthly, None, Some(28), seven_am(), BANGKOK, now);
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2026, 2, 28, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_csv_bom_prefix() {
+        let mut wtr = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        wtr.write_record(["ล็อต", "น้ำหนัก (กก.)"]).unwrap();
+        let bytes = wtr.into_inner().unwrap();
+
+        assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+        assert!(String::from_utf8(bytes[3..].to_vec()).unwrap().starts_with("ล็อต,"));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+
+    proptest! {
+        /// Sheet names stay within Excel's limits and never collide
+        #[test]
+        fn prop_sheet_names_valid_and_unique(names in prop::collection::vec(".{0,40}", 1..8)) {
+            let mut taken: Vec<String> = Vec::new();
+            for name in &names {
+                let unique = unique_sheet_name(name, &taken);
+                prop_assert!(unique.chars().count() <= 31);
+                prop_assert!(!unique.chars().any(|c| "[]:*?/\\".contains(c)));
+                prop_assert!(!taken.iter().any(|t| t.to_lowercase() == unique.to_lowercase()));
+                taken.push(unique);
+            }
+        }
+
+        /// Escaped values contain no unescaped wildcards
+        #[test]
+        fn prop_escaped_like_has_no_bare_wildcards(value in ".*") {
+            let escaped = escape_like(&value);
+            let mut chars = escaped.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    prop_assert!(chars.next().is_some());
+                } else {
+                    prop_assert!(c != '%' && c != '_');
+                }
+            }
+        }
+
+        /// The next run is always in the future and on the requested weekday
+        #[test]
+        fn prop_weekly_next_run_matches_weekday(
+            minutes in 0i64..2_000_000,
+            day in 1i16..=7,
+            hour in 0u32..24,
+            offset in -720i32..=840,
+        ) {
+            let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes);
+            let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+            let next = next_run_after(ScheduleFrequency::Weekly, Some(day), None, time, offset, now).unwrap();
+
+            prop_assert!(next > now);
+            prop_assert!(next - now <= Duration::days(7));
+            let local = next + Duration::minutes(offset as i64);
+            prop_assert_eq!(local.weekday().number_from_monday() as i16, day);
+            prop_assert_eq!(local.time(), time);
+        }
+
+        /// Stage shares of non-negative balances add up to 100%
+        #[test]
+        fn prop_stage_shares_sum_to_hundred(balances in prop::collection::vec(1i64..1_000_000, 1..6)) {
+            let balances: Vec<Decimal> = balances.into_iter().map(|b| Decimal::new(b, 3)).collect();
+            let total: Decimal = balances.iter().sum();
+            let shares: Decimal = balances.iter().map(|b| percent_of(*b, total)).sum();
+            prop_assert!((shares - Decimal::from(100)).abs() <= Decimal::new(5, 2) * Decimal::from(balances.len()));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Reporting period tests
+//!
+//! Tests for business reporting calendars and `period` parameters:
+//! - Seasons and fiscal years run twelve months from their start month
+//! - Season labels span two years unless the year starts in January
+//! - `season:2024/25`, `fy:current`, `quarter:2024-Q2` and `month:2024-06`
+//!   resolve to their first and last day
+//! - Malformed periods and mismatched season labels are rejected
+
+use chrono::{Datelike, Months, NaiveDate};
+use proptest::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReportingCalendar {
+    season_start_month: u32,
+    fiscal_year_start_month: u32,
+}
+
+const DEFAULT_CALENDAR: ReportingCalendar = ReportingCalendar {
+    season_start_month: 10,
+    fiscal_year_start_month: 1,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportPeriod {
+    Season(i32),
+    FiscalYear(i32),
+    Year(i32),
+    Quarter(i32, u32),
+    Month(i32, u32),
+}
+
+/// Mirrors `year_label`
+fn year_label(year: i32, start_month: u32) -> String {
+    if start_month == 1 {
+        year.to_string()
+    } else {
+        format!("{}/{:02}", year, (year + 1).rem_euclid(100))
+    }
+}
+
+/// Mirrors `year_starting`
+fn year_starting(date: NaiveDate, start_month: u32) -> i32 {
+    if date.month() >= start_month {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
+/// Mirrors `year_bounds`
+fn year_bounds(year: i32, start_month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, start_month, 1)?;
+    Some((start, start.checked_add_months(Months::new(12))?.pred_opt()?))
+}
+
+/// Mirrors `month_span`
+fn month_span(year: i32, month: u32, months: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some((start, start.checked_add_months(Months::new(months))?.pred_opt()?))
+}
+
+/// Mirrors `parse_year_label`
+fn parse_year_label(value: &str) -> Option<i32> {
+    let (start, end) = match value.split_once('/') {
+        Some((start, end)) => (start, Some(end)),
+        None => (value, None),
+    };
+    let year: i32 = start.trim().parse().ok()?;
+    match end.map(str::trim) {
+        None => Some(year),
+        Some(end) if end.len() == 2 => (end.parse::<i32>().ok()? == (year + 1).rem_euclid(100)).then_some(year),
+        Some(end) => (end.parse::<i32>().ok()? == year + 1).then_some(year),
+    }
+}
+
+/// Mirrors `parse_period`
+fn parse_period(value: &str, calendar: &ReportingCalendar, today: NaiveDate) -> Option<ReportPeriod> {
+    let (kind, rest) = value.trim().split_once(':')?;
+    let rest = rest.trim();
+    let relative = |current: i32| match rest {
+        "current" => Some(current),
+        "previous" => Some(current - 1),
+        _ => parse_year_label(rest),
+    };
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "season" => relative(year_starting(today, calendar.season_start_month)).map(ReportPeriod::Season),
+        "fy" | "fiscal_year" => {
+            relative(year_starting(today, calendar.fiscal_year_start_month)).map(ReportPeriod::FiscalYear)
+        }
+        "year" => rest.parse().ok().map(ReportPeriod::Year),
+        "quarter" => rest.split_once(['-', ' ']).and_then(|(year, quarter)| {
+            let quarter = quarter.trim().trim_start_matches(['Q', 'q']).parse().ok()?;
+            (1..=4).contains(&quarter).then_some(ReportPeriod::Quarter(year.parse().ok()?, quarter))
+        }),
+        "month" => rest.split_once('-').and_then(|(year, month)| {
+            let month = month.parse().ok()?;
+            (1..=12).contains(&month).then_some(ReportPeriod::Month(year.parse().ok()?, month))
+        }),
+        _ => None,
+    }
+}
+
+/// Mirrors `ReportPeriod::bounds`
+fn bounds(period: ReportPeriod, calendar: &ReportingCalendar) -> Option<(NaiveDate, NaiveDate)> {
+    match period {
+        ReportPeriod::Season(season) => year_bounds(season, calendar.season_start_month),
+        ReportPeriod::FiscalYear(year) => year_bounds(year, calendar.fiscal_year_start_month),
+        ReportPeriod::Year(year) => year_bounds(year, 1),
+        ReportPeriod::Quarter(year, quarter) => month_span(year, (quarter - 1) * 3 + 1, 3),
+        ReportPeriod::Month(year, month) => month_span(year, month, 1),
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn resolve(value: &str, calendar: &ReportingCalendar, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    bounds(parse_period(value, calendar, today)?, calendar)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_default_season_runs_october_to_september() {
+        let today = date(2025, 1, 15);
+        assert_eq!(
+            resolve("season:2024/25", &DEFAULT_CALENDAR, today),
+            Some((date(2024, 10, 1), date(2025, 9, 30)))
+        );
+        assert_eq!(resolve("season:2024", &DEFAULT_CALENDAR, today), resolve("season:2024/25", &DEFAULT_CALENDAR, today));
+    }
+
+    #[test]
+    fn test_business_season_from_november() {
+        let calendar = ReportingCalendar {
+            season_start_month: 11,
+            ..DEFAULT_CALENDAR
+        };
+        assert_eq!(
+            resolve("season:2024/25", &calendar, date(2025, 1, 1)),
+            Some((date(2024, 11, 1), date(2025, 10, 31)))
+        );
+        // October belongs to the season before
+        assert_eq!(year_starting(date(2025, 10, 20), 11), 2024);
+        assert_eq!(year_starting(date(2025, 11, 1), 11), 2025);
+    }
+
+    #[test]
+    fn test_current_and_previous_season() {
+        let today = date(2025, 2, 10);
+        assert_eq!(parse_period("season:current", &DEFAULT_CALENDAR, today), Some(ReportPeriod::Season(2024)));
+        assert_eq!(parse_period("season:previous", &DEFAULT_CALENDAR, today), Some(ReportPeriod::Season(2023)));
+    }
+
+    #[test]
+    fn test_fiscal_year() {
+        let calendar = ReportingCalendar {
+            fiscal_year_start_month: 4,
+            ..DEFAULT_CALENDAR
+        };
+        assert_eq!(
+            resolve("fy:2024/25", &calendar, date(2025, 1, 1)),
+            Some((date(2024, 4, 1), date(2025, 3, 31)))
+        );
+        assert_eq!(parse_period("fy:current", &calendar, date(2025, 3, 31)), Some(ReportPeriod::FiscalYear(2024)));
+        assert_eq!(
+            resolve("fy:2024", &DEFAULT_CALENDAR, date(2025, 1, 1)),
+            Some((date(2024, 1, 1), date(2024, 12, 31)))
+        );
+    }
+
+    #[test]
+    fn test_quarters_and_months() {
+        let today = date(2025, 1, 1);
+        assert_eq!(
+            resolve("quarter:2024-Q2", &DEFAULT_CALENDAR, today),
+            Some((date(2024, 4, 1), date(2024, 6, 30)))
+        );
+        assert_eq!(
+            resolve("month:2024-02", &DEFAULT_CALENDAR, today),
+            Some((date(2024, 2, 1), date(2024, 2, 29)))
+        );
+        assert_eq!(
+            resolve("year:2023", &DEFAULT_CALENDAR, today),
+            Some((date(2023, 1, 1), date(2023, 12, 31)))
+        );
+    }
+
+    #[test]
+    fn test_invalid_periods_rejected() {
+        let today = date(2025, 1, 1);
+        for value in ["2024", "season:2024/26", "season:2024/2026", "quarter:2024-Q5", "month:2024-13", "week:2024-01", "season:"] {
+            assert_eq!(parse_period(value, &DEFAULT_CALENDAR, today), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(year_label(2024, 10), "2024/25");
+        assert_eq!(year_label(2099, 11), "2099/00");
+        assert_eq!(year_label(2024, 1), "2024");
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_date_falls_in_its_year(
+        year in 1990i32..2100,
+        ordinal in 1u32..366,
+        start_month in 1u32..=12,
+    ) {
+        let day = NaiveDate::from_yo_opt(year, ordinal).unwrap();
+        let (start, end) = year_bounds(year_starting(day, start_month), start_month).unwrap();
+        prop_assert!(start <= day && day <= end);
+    }
+
+    #[test]
+    fn prop_years_are_contiguous(year in 1990i32..2100, start_month in 1u32..=12) {
+        let (_, end) = year_bounds(year, start_month).unwrap();
+        let (next_start, _) = year_bounds(year + 1, start_month).unwrap();
+        prop_assert_eq!(end.succ_opt().unwrap(), next_start);
+    }
+
+    #[test]
+    fn prop_season_label_round_trips(season in 1990i32..2100, start_month in 2u32..=12) {
+        let calendar = ReportingCalendar { season_start_month: start_month, ..DEFAULT_CALENDAR };
+        let label = format!("season:{}", year_label(season, start_month));
+        prop_assert_eq!(parse_period(&label, &calendar, date(2025, 1, 1)), Some(ReportPeriod::Season(season)));
+    }
+}
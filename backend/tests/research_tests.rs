@@ -0,0 +1,199 @@
+//! Research API tests
+//!
+//! Tests for the anonymized research partner datasets:
+//! - Lot pseudonyms are stable for a partner but differ between partners
+//! - Dates are reduced to quarters, altitudes and weights to bands
+//! - Records are only released for groups spanning enough businesses
+//! - Partners over their hourly limit wait for the oldest request to expire
+//! - The admin token never matches when none is configured
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use proptest::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use uuid::Uuid;
+
+const MIN_GROUP_BUSINESSES: usize = 5;
+const ALTITUDE_BAND_METERS: i64 = 200;
+const PSEUDONYM_LENGTH: usize = 16;
+
+/// Mirrors `pseudonym`
+fn pseudonym(salt: &str, id: Uuid) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(id.as_bytes());
+    let digest = format!("{:x}", mac.finalize().into_bytes());
+    digest[..PSEUDONYM_LENGTH].to_string()
+}
+
+/// Mirrors `quarter`
+fn quarter(date: NaiveDate) -> String {
+    format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1)
+}
+
+/// Mirrors `value_band`, returning (min, max)
+fn value_band(value: i64, width: i64) -> (i64, i64) {
+    let min = value.div_euclid(width) * width;
+    (min, min + width)
+}
+
+/// Mirrors `suppress_small_groups`
+fn suppress_small_groups<K: Eq + Hash, T>(rows: Vec<(Uuid, K, T)>, min_businesses: usize) -> Vec<T> {
+    let mut businesses: HashMap<&K, HashSet<Uuid>> = HashMap::new();
+    for (business_id, key, _) in &rows {
+        businesses.entry(key).or_default().insert(*business_id);
+    }
+    let released: HashSet<usize> = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, key, _))| businesses[key].len() >= min_businesses)
+        .map(|(index, _)| index)
+        .collect();
+    rows.into_iter()
+        .enumerate()
+        .filter(|(index, _)| released.contains(index))
+        .map(|(_, (_, _, record))| record)
+        .collect()
+}
+
+/// Mirrors `rate_limit_retry_after`
+fn rate_limit_retry_after(
+    requests: i64,
+    limit: i32,
+    oldest_request: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    if requests < limit as i64 {
+        return None;
+    }
+    let frees_at = oldest_request.unwrap_or(now) + Duration::hours(1);
+    Some((frees_at - now).num_seconds().max(1))
+}
+
+/// Mirrors `admin_token_matches`
+fn admin_token_matches(configured: &str, presented: &str) -> bool {
+    !configured.is_empty() && Sha256::digest(configured.as_bytes()) == Sha256::digest(presented.trim().as_bytes())
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudonym_stable_per_partner() {
+        let lot = Uuid::new_v4();
+        assert_eq!(pseudonym("salt-a", lot), pseudonym("salt-a", lot));
+        assert_eq!(pseudonym("salt-a", lot).len(), PSEUDONYM_LENGTH);
+    }
+
+    #[test]
+    fn test_pseudonym_differs_between_partners() {
+        let lot = Uuid::new_v4();
+        assert_ne!(pseudonym("salt-a", lot), pseudonym("salt-b", lot));
+        assert!(!pseudonym("salt-a", lot).contains(&lot.simple().to_string()[..8]));
+    }
+
+    #[test]
+    fn test_quarters() {
+        assert_eq!(quarter(date(2024, 1, 1)), "2024-Q1");
+        assert_eq!(quarter(date(2024, 3, 31)), "2024-Q1");
+        assert_eq!(quarter(date(2024, 4, 1)), "2024-Q2");
+        assert_eq!(quarter(date(2024, 12, 31)), "2024-Q4");
+    }
+
+    #[test]
+    fn test_altitude_bands() {
+        assert_eq!(value_band(1199, ALTITUDE_BAND_METERS), (1000, 1200));
+        assert_eq!(value_band(1200, ALTITUDE_BAND_METERS), (1200, 1400));
+        assert_eq!(value_band(0, ALTITUDE_BAND_METERS), (0, 200));
+    }
+
+    #[test]
+    fn test_small_groups_suppressed() {
+        let businesses: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let mut rows: Vec<(Uuid, &str, i32)> = businesses.iter().enumerate().map(|(i, b)| (*b, "chiang-rai", i as i32)).collect();
+        // One business alone in its group, however many records it has
+        rows.push((businesses[0], "nan", 10));
+        rows.push((businesses[0], "nan", 11));
+
+        assert_eq!(suppress_small_groups(rows, MIN_GROUP_BUSINESSES), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_records_of_one_business_count_once() {
+        let business = Uuid::new_v4();
+        let rows: Vec<(Uuid, &str, i32)> = (0..10).map(|i| (business, "chiang-mai", i)).collect();
+        assert!(suppress_small_groups(rows, MIN_GROUP_BUSINESSES).is_empty());
+    }
+
+    #[test]
+    fn test_under_limit_not_throttled() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(rate_limit_retry_after(99, 100, Some(now), now), None);
+    }
+
+    #[test]
+    fn test_at_limit_waits_for_oldest_request() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let oldest = now - Duration::minutes(45);
+        assert_eq!(rate_limit_retry_after(100, 100, Some(oldest), now), Some(15 * 60));
+    }
+
+    #[test]
+    fn test_admin_token() {
+        assert!(admin_token_matches("secret", "secret"));
+        assert!(admin_token_matches("secret", " secret "));
+        assert!(!admin_token_matches("secret", "other"));
+        assert!(!admin_token_matches("", ""));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_value_within_its_band(value in -1000i64..10000, width in 1i64..500) {
+        let (min, max) = value_band(value, width);
+        prop_assert!(min <= value && value < max);
+        prop_assert_eq!(min % width, 0);
+    }
+
+    #[test]
+    fn prop_released_groups_span_enough_businesses(
+        rows in prop::collection::vec((0u8..8, 0u8..3), 0..60)
+    ) {
+        let ids: Vec<Uuid> = (0..8).map(|_| Uuid::new_v4()).collect();
+        let rows: Vec<(Uuid, u8, (usize, u8))> = rows
+            .into_iter()
+            .map(|(business, group)| (ids[business as usize], group, (business as usize, group)))
+            .collect();
+        let released = suppress_small_groups(rows, MIN_GROUP_BUSINESSES);
+
+        let mut businesses: HashMap<u8, HashSet<usize>> = HashMap::new();
+        for (business, group) in &released {
+            businesses.entry(*group).or_default().insert(*business);
+        }
+        prop_assert!(businesses.values().all(|b| b.len() >= MIN_GROUP_BUSINESSES));
+    }
+
+    #[test]
+    fn prop_retry_within_an_hour(requests in 0i64..200, limit in 1i32..150, age_secs in 0i64..3600) {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let retry = rate_limit_retry_after(requests, limit, Some(now - Duration::seconds(age_secs)), now);
+        prop_assert_eq!(retry.is_some(), requests >= limit as i64);
+        if let Some(secs) = retry {
+            prop_assert!((1..=3600).contains(&secs));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Ripeness estimate tests
+//!
+//! Tests for pre-filling harvest ripeness from basket photo estimates:
+//! - Estimated shares round to whole percentages summing to 100
+//! - Shares that are negative, not numbers or all zero give no estimate
+//! - A harvest whose ripeness differs from its estimate is overridden
+//! - Chatbot photo estimates pre-fill harvests for an hour
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::prelude::*;
+
+const RIPENESS_ESTIMATE_VALID_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RipenessAssessment {
+    underripe_percent: i32,
+    ripe_percent: i32,
+    overripe_percent: i32,
+}
+
+/// Mirrors `whole_percentages`
+fn whole_percentages(underripe: f64, ripe: f64, overripe: f64) -> Option<RipenessAssessment> {
+    let shares = [underripe, ripe, overripe];
+    if shares.iter().any(|share| !share.is_finite() || *share < 0.0) {
+        return None;
+    }
+    let total: f64 = shares.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let scaled = shares.map(|share| share * 100.0 / total);
+    let mut percents = scaled.map(|share| share.floor() as i32);
+    let mut by_remainder = [0, 1, 2];
+    by_remainder.sort_by(|a, b| (scaled[*b] - scaled[*b].floor()).total_cmp(&(scaled[*a] - scaled[*a].floor())));
+    let left_over = 100 - percents.iter().sum::<i32>();
+    for index in by_remainder.iter().cycle().take(left_over.max(0) as usize) {
+        percents[*index] += 1;
+    }
+
+    Some(RipenessAssessment {
+        underripe_percent: percents[0],
+        ripe_percent: percents[1],
+        overripe_percent: percents[2],
+    })
+}
+
+/// Mirrors `ripeness_overridden`
+fn ripeness_overridden(estimate: &RipenessAssessment, entered: &RipenessAssessment) -> bool {
+    estimate != entered
+}
+
+/// Mirrors `estimate_still_valid`
+fn estimate_still_valid(estimated_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - estimated_at <= Duration::minutes(RIPENESS_ESTIMATE_VALID_MINUTES)
+}
+
+fn ripeness(underripe_percent: i32, ripe_percent: i32, overripe_percent: i32) -> RipenessAssessment {
+    RipenessAssessment {
+        underripe_percent,
+        ripe_percent,
+        overripe_percent,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_whole_shares_kept() {
+        assert_eq!(whole_percentages(10.0, 80.0, 10.0), Some(ripeness(10, 80, 10)));
+    }
+
+    #[test]
+    fn test_fractions_round_to_100() {
+        assert_eq!(whole_percentages(12.3, 80.6, 7.1), Some(ripeness(12, 81, 7)));
+        // Thirds give the point left over to the first largest remainder
+        let thirds = whole_percentages(1.0, 1.0, 1.0).unwrap();
+        assert_eq!(thirds.underripe_percent + thirds.ripe_percent + thirds.overripe_percent, 100);
+    }
+
+    #[test]
+    fn test_counts_scale_to_percentages() {
+        // The service may report cherry counts rather than percentages
+        assert_eq!(whole_percentages(30.0, 150.0, 20.0), Some(ripeness(15, 75, 10)));
+    }
+
+    #[test]
+    fn test_no_estimate_without_cherries() {
+        assert_eq!(whole_percentages(0.0, 0.0, 0.0), None);
+        assert_eq!(whole_percentages(-5.0, 90.0, 15.0), None);
+        assert_eq!(whole_percentages(f64::NAN, 90.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_override_detection() {
+        let estimate = ripeness(12, 81, 7);
+        assert!(!ripeness_overridden(&estimate, &ripeness(12, 81, 7)));
+        assert!(ripeness_overridden(&estimate, &ripeness(10, 85, 5)));
+    }
+
+    #[test]
+    fn test_chatbot_estimate_valid_for_an_hour() {
+        let taken = Utc.with_ymd_and_hms(2024, 12, 1, 7, 30, 0).unwrap();
+        assert!(estimate_still_valid(taken, taken + Duration::minutes(60)));
+        assert!(!estimate_still_valid(taken, taken + Duration::minutes(61)));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_percentages_sum_to_100(
+        underripe in 0.0f64..1000.0,
+        ripe in 0.0f64..1000.0,
+        overripe in 0.0f64..1000.0,
+    ) {
+        prop_assume!(underripe + ripe + overripe > 0.0);
+        let estimate = whole_percentages(underripe, ripe, overripe).unwrap();
+        prop_assert_eq!(estimate.underripe_percent + estimate.ripe_percent + estimate.overripe_percent, 100);
+        let total = underripe + ripe + overripe;
+        for (share, percent) in [
+            (underripe, estimate.underripe_percent),
+            (ripe, estimate.ripe_percent),
+            (overripe, estimate.overripe_percent),
+        ] {
+            prop_assert!((share * 100.0 / total - percent as f64).abs() < 1.0);
+        }
+    }
+}
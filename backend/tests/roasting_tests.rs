@@ -2,6 +2,7 @@
 //!
 //! Tests for roasting operations including:
 //! - Property 16: Roast Weight Loss Calculation
+//! - Roast curve downsampling
 
 use proptest::prelude::*;
 use rust_decimal::Decimal;
@@ -196,20 +197,8 @@ mod unit_tests {
         assert!(dark_loss >= dec("18.0") && dark_loss <= dec("22.0"));
     }
 
-    // Helper functions for tests
-    pub fn calculate_weight_loss(green_weight: Decimal, roasted_weight: Decimal) -> Decimal {
-        if green_weight.is_zero() {
-            return Decimal::ZERO;
-        }
-        ((green_weight - roasted_weight) / green_weight) * Decimal::from(100)
-    }
-
-    pub fn calculate_dtr(development_time: i32, total_time: i32) -> Decimal {
-        if total_time <= 0 {
-            return Decimal::ZERO;
-        }
-        (Decimal::from(development_time) / Decimal::from(total_time)) * Decimal::from(100)
-    }
+    // Roast calculations shared with the roasting service and WASM client
+    pub use shared::{calculate_dtr, calculate_weight_loss};
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum RoastStatus {
@@ -563,3 +552,133 @@ mod integration_helpers {
         assert!(result.is_err());
     }
 }
+
+// ============================================================================
+// Roast Curve Downsampling
+// ============================================================================
+
+#[cfg(test)]
+mod curve_tests {
+    use super::*;
+
+    const MAX_RESOLUTION_SECONDS: i32 = 600;
+
+    /// Mirrors `TemperatureCheckpoint`: (time, temp, notes)
+    type Reading = (i32, Decimal, Option<String>);
+
+    /// Mirrors `parse_resolution`
+    fn parse_resolution(resolution: &str) -> Option<i32> {
+        let resolution = resolution.trim().to_ascii_lowercase();
+        let (number, unit) = match resolution.strip_suffix('m') {
+            Some(minutes) => (minutes, 60),
+            None => (resolution.strip_suffix('s').unwrap_or(&resolution), 1),
+        };
+        let seconds = number.trim().parse::<i32>().ok()?.checked_mul(unit)?;
+        (1..=MAX_RESOLUTION_SECONDS).contains(&seconds).then_some(seconds)
+    }
+
+    /// Mirrors `downsample`
+    fn downsample(readings: &[Reading], resolution_seconds: i32) -> Vec<Reading> {
+        let mut buckets: Vec<(i32, Reading, Decimal)> = Vec::new();
+        for reading in readings {
+            let bucket = reading.0 / resolution_seconds;
+            match buckets.last_mut() {
+                Some((b, point, count)) if *b == bucket => {
+                    point.1 += reading.1;
+                    *count += Decimal::ONE;
+                    if let Some(notes) = &reading.2 {
+                        point.2 = Some(match point.2.take() {
+                            Some(existing) => format!("{}; {}", existing, notes),
+                            None => notes.clone(),
+                        });
+                    }
+                }
+                _ => buckets.push((bucket, reading.clone(), Decimal::ONE)),
+            }
+        }
+        buckets
+            .into_iter()
+            .map(|(_, mut point, count)| {
+                point.1 = (point.1 / count).round_dp(1);
+                point
+            })
+            .collect()
+    }
+
+    fn reading(time: i32, temp: &str) -> Reading {
+        (time, dec(temp), None)
+    }
+
+    #[test]
+    fn test_parse_resolution_units() {
+        assert_eq!(parse_resolution("10s"), Some(10));
+        assert_eq!(parse_resolution("1m"), Some(60));
+        assert_eq!(parse_resolution("30"), Some(30));
+        assert_eq!(parse_resolution(" 5S "), Some(5));
+    }
+
+    #[test]
+    fn test_parse_resolution_rejects_out_of_range() {
+        assert_eq!(parse_resolution("0s"), None);
+        assert_eq!(parse_resolution("11m"), None);
+        assert_eq!(parse_resolution("-5s"), None);
+        assert_eq!(parse_resolution("fast"), None);
+    }
+
+    #[test]
+    fn test_downsample_averages_buckets() {
+        let readings = vec![
+            reading(0, "200"),
+            reading(1, "198"),
+            reading(2, "196"),
+            reading(10, "150"),
+            reading(11, "148"),
+        ];
+        let curve = downsample(&readings, 10);
+
+        assert_eq!(curve, vec![reading(0, "198"), reading(10, "149")]);
+    }
+
+    #[test]
+    fn test_downsample_keeps_first_crack_note() {
+        let readings = vec![
+            reading(480, "195"),
+            (482, dec("196"), Some("First crack".to_string())),
+            reading(485, "197"),
+        ];
+        let curve = downsample(&readings, 10);
+
+        assert_eq!(curve.len(), 1);
+        assert_eq!(curve[0].2.as_deref(), Some("First crack"));
+    }
+
+    #[test]
+    fn test_downsample_skips_empty_buckets() {
+        let readings = vec![reading(0, "200"), reading(95, "100")];
+        let curve = downsample(&readings, 10);
+
+        assert_eq!(curve, vec![reading(0, "200"), reading(95, "100")]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_downsampled_curve_is_bounded(
+            temps in prop::collection::vec(80u32..240, 1..900),
+            resolution in 1i32..120,
+        ) {
+            let readings: Vec<Reading> = temps
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (i as i32, Decimal::from(*t), None))
+                .collect();
+            let curve = downsample(&readings, resolution);
+
+            prop_assert_eq!(curve.len(), (readings.len() as i32 + resolution - 1) as usize / resolution as usize);
+            let min = Decimal::from(*temps.iter().min().unwrap());
+            let max = Decimal::from(*temps.iter().max().unwrap());
+            for point in &curve {
+                prop_assert!(point.1 >= min && point.1 <= max);
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Sales negotiation tests
+//!
+//! Tests for offers on sales leads:
+//! - A new offer counters the other side's open offer or withdraws one's own
+//! - Only the other side can answer an open offer
+//! - Accepting closes the lead and totals the order
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Party {
+    Buyer,
+    Producer,
+}
+
+/// Mirrors `superseded_status`
+fn superseded_status(open_party: Party, actor: Party) -> &'static str {
+    if open_party == actor {
+        "withdrawn"
+    } else {
+        "countered"
+    }
+}
+
+/// Mirrors `lead_is_open`
+fn lead_is_open(status: &str) -> bool {
+    !matches!(status, "won" | "lost")
+}
+
+/// Mirrors `order_total`
+fn order_total(quantity_kg: Decimal, price_per_kg: Decimal) -> Decimal {
+    (quantity_kg * price_per_kg).round_dp(2)
+}
+
+/// Mirrors `order_number`
+fn order_number(year: i32, sequence: i64) -> String {
+    format!("SO-{}-{:04}", year, sequence)
+}
+
+/// In-memory thread following the rules of `make_offer` and `respond`
+#[derive(Debug, Default)]
+struct Thread {
+    lead_status: String,
+    offers: Vec<(Party, &'static str)>,
+}
+
+impl Thread {
+    fn new() -> Self {
+        Thread {
+            lead_status: "new".to_string(),
+            offers: Vec::new(),
+        }
+    }
+
+    fn offer(&mut self, actor: Party) -> Result<(), &'static str> {
+        if !lead_is_open(&self.lead_status) {
+            return Err("closed");
+        }
+        if let Some(open) = self.offers.iter_mut().find(|(_, status)| *status == "open") {
+            open.1 = superseded_status(open.0, actor);
+        }
+        self.offers.push((actor, "open"));
+        self.lead_status = "negotiating".to_string();
+        Ok(())
+    }
+
+    fn respond(&mut self, actor: Party, accept: bool) -> Result<(), &'static str> {
+        if !lead_is_open(&self.lead_status) {
+            return Err("closed");
+        }
+        let open = self
+            .offers
+            .iter_mut()
+            .find(|(_, status)| *status == "open")
+            .ok_or("no open offer")?;
+        if open.0 == actor {
+            return Err("own offer");
+        }
+        if accept {
+            open.1 = "accepted";
+            self.lead_status = "won".to_string();
+        } else {
+            open.1 = "rejected";
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_and_withdraw() {
+        assert_eq!(superseded_status(Party::Buyer, Party::Producer), "countered");
+        assert_eq!(superseded_status(Party::Producer, Party::Producer), "withdrawn");
+    }
+
+    #[test]
+    fn test_closed_leads() {
+        assert!(lead_is_open("new"));
+        assert!(lead_is_open("negotiating"));
+        assert!(!lead_is_open("won"));
+        assert!(!lead_is_open("lost"));
+    }
+
+    #[test]
+    fn test_order_total_and_number() {
+        assert_eq!(order_total(dec("120.5"), dec("385.75")), dec("46482.88"));
+        assert_eq!(order_number(2024, 7), "SO-2024-0007");
+        assert_eq!(order_number(2024, 12345), "SO-2024-12345");
+    }
+
+    #[test]
+    fn test_negotiation_to_acceptance() {
+        let mut thread = Thread::new();
+        thread.offer(Party::Buyer).unwrap();
+        thread.offer(Party::Producer).unwrap();
+        assert_eq!(thread.respond(Party::Producer, true), Err("own offer"));
+        thread.respond(Party::Buyer, true).unwrap();
+
+        assert_eq!(thread.lead_status, "won");
+        assert_eq!(thread.offers, vec![(Party::Buyer, "countered"), (Party::Producer, "accepted")]);
+        assert_eq!(thread.offer(Party::Buyer), Err("closed"));
+    }
+
+    #[test]
+    fn test_rejection_keeps_thread_open() {
+        let mut thread = Thread::new();
+        thread.offer(Party::Producer).unwrap();
+        thread.respond(Party::Buyer, false).unwrap();
+        assert_eq!(thread.lead_status, "negotiating");
+        assert_eq!(thread.respond(Party::Buyer, true), Err("no open offer"));
+        thread.offer(Party::Buyer).unwrap();
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_at_most_one_open_offer(moves in prop::collection::vec((any::<bool>(), 0u8..3), 0..30)) {
+        let mut thread = Thread::new();
+        for (buyer, action) in moves {
+            let actor = if buyer { Party::Buyer } else { Party::Producer };
+            let _ = match action {
+                0 => thread.offer(actor),
+                1 => thread.respond(actor, true),
+                _ => thread.respond(actor, false),
+            };
+            let open = thread.offers.iter().filter(|(_, s)| *s == "open").count();
+            prop_assert!(open <= 1);
+            let accepted = thread.offers.iter().filter(|(_, s)| *s == "accepted").count();
+            prop_assert_eq!(accepted == 1, thread.lead_status == "won");
+        }
+    }
+}
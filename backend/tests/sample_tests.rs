@@ -0,0 +1,119 @@
+//! Lab sample tests
+//!
+//! Tests for samples drawn from a lot:
+//! - A sample's grams are taken off the lot in kilograms
+//! - A sample can't weigh nothing or more than the lot holds
+//! - A sample only moves forward in the lab workflow
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+/// Mirrors `SampleStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SampleStatus {
+    PendingGrading,
+    Graded,
+    Cupped,
+    SentToBuyer,
+}
+
+const STATUSES: [SampleStatus; 4] = [
+    SampleStatus::PendingGrading,
+    SampleStatus::Graded,
+    SampleStatus::Cupped,
+    SampleStatus::SentToBuyer,
+];
+
+/// Mirrors `sample_quantity_kg`
+fn sample_quantity_kg(weight_grams: i32) -> Decimal {
+    Decimal::new(i64::from(weight_grams), 3)
+}
+
+/// Mirrors `validate_sample_weight`, with the error as None
+fn validate_sample_weight(weight_grams: i32, lot_weight_kg: Decimal) -> Option<Decimal> {
+    if weight_grams <= 0 {
+        return None;
+    }
+    let kg = sample_quantity_kg(weight_grams);
+    (kg <= lot_weight_kg).then_some(kg)
+}
+
+/// Mirrors `validate_status_change`, with the error as None
+fn validate_status_change(from: SampleStatus, to: SampleStatus) -> Option<()> {
+    (to > from).then_some(())
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_grams_become_kilograms() {
+        assert_eq!(sample_quantity_kg(300), Decimal::new(3, 1));
+        assert_eq!(sample_quantity_kg(1), Decimal::new(1, 3));
+        assert_eq!(sample_quantity_kg(2500), Decimal::new(25, 1));
+    }
+
+    #[test]
+    fn test_sample_weight_within_the_lot() {
+        assert_eq!(validate_sample_weight(350, Decimal::from(60)), Some(Decimal::new(35, 2)));
+        assert_eq!(validate_sample_weight(500, Decimal::new(5, 1)), Some(Decimal::new(5, 1)));
+        assert!(validate_sample_weight(501, Decimal::new(5, 1)).is_none());
+        assert!(validate_sample_weight(0, Decimal::from(60)).is_none());
+        assert!(validate_sample_weight(-100, Decimal::from(60)).is_none());
+    }
+
+    #[test]
+    fn test_empty_lot_gives_no_sample() {
+        assert!(validate_sample_weight(1, Decimal::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_workflow_moves_forward() {
+        assert!(validate_status_change(SampleStatus::PendingGrading, SampleStatus::Graded).is_some());
+        assert!(validate_status_change(SampleStatus::Graded, SampleStatus::Cupped).is_some());
+        assert!(validate_status_change(SampleStatus::Cupped, SampleStatus::SentToBuyer).is_some());
+    }
+
+    #[test]
+    fn test_buyer_sample_can_skip_the_lab() {
+        assert!(validate_status_change(SampleStatus::PendingGrading, SampleStatus::SentToBuyer).is_some());
+    }
+
+    #[test]
+    fn test_workflow_never_goes_back() {
+        assert!(validate_status_change(SampleStatus::Cupped, SampleStatus::Graded).is_none());
+        assert!(validate_status_change(SampleStatus::SentToBuyer, SampleStatus::PendingGrading).is_none());
+        assert!(validate_status_change(SampleStatus::Graded, SampleStatus::Graded).is_none());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_drawn_sample_never_exceeds_the_lot(grams in -1000i32..100_000, lot_grams in 0i64..100_000) {
+        let lot_weight_kg = Decimal::new(lot_grams, 3);
+        if let Some(kg) = validate_sample_weight(grams, lot_weight_kg) {
+            prop_assert!(kg > Decimal::ZERO);
+            prop_assert!(lot_weight_kg - kg >= Decimal::ZERO);
+            prop_assert_eq!(kg * Decimal::from(1000), Decimal::from(grams));
+        }
+    }
+
+    #[test]
+    fn prop_status_changes_only_move_forward(
+        from in prop::sample::select(STATUSES.to_vec()),
+        to in prop::sample::select(STATUSES.to_vec())
+    ) {
+        let from_step = STATUSES.iter().position(|s| *s == from).unwrap();
+        let to_step = STATUSES.iter().position(|s| *s == to).unwrap();
+        prop_assert_eq!(validate_status_change(from, to).is_some(), to_step > from_step);
+    }
+}
@@ -0,0 +1,414 @@
+//! Shipment conditions tests
+//!
+//! Tests for data logger imports and the transit-conditions report:
+//! - Logger export headers map to fields, whatever the units or spelling
+//! - Reading times with or without a zone, day-first and B.E. dates
+//! - Logger serials are found in export preambles
+//! - Excursions run from the first reading outside a limit to the first
+//!   reading back inside
+//! - Excursions are marked when they overlap the transit
+//! - Mean kinetic temperature
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use proptest::prelude::*;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+const BUDDHIST_ERA_OFFSET: i32 = 543;
+const MKT_ACTIVATION_KELVIN: f64 = 10_000.0;
+const KELVIN_OFFSET: f64 = 273.15;
+
+/// Mirrors `LoggerField`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoggerField {
+    Timestamp,
+    Date,
+    Time,
+    TemperatureC,
+    TemperatureF,
+    Humidity,
+}
+
+/// Mirrors `header_field`
+fn header_field(header: &str) -> Option<LoggerField> {
+    let key: String = header
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    let field = match key.as_str() {
+        "timestamp" | "date_time" | "datetime" | "recorded_at" | "time_stamp" | "date_and_time" | "local_time"
+        | "utc_time" => LoggerField::Timestamp,
+        "date" => LoggerField::Date,
+        "time" => LoggerField::Time,
+        "temperature" | "temperature_c" | "temp" | "temp_c" | "celsius" | "t_c" => LoggerField::TemperatureC,
+        "temperature_f" | "temp_f" | "fahrenheit" | "t_f" => LoggerField::TemperatureF,
+        "humidity" | "humidity_rh" | "rh" | "relative_humidity" | "humidity_percent" | "rh_percent" => {
+            LoggerField::Humidity
+        }
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Mirrors `parse_sheet_date`
+fn parse_sheet_date(value: &str) -> Option<NaiveDate> {
+    let parts: Vec<u32> = value
+        .trim()
+        .split(['-', '/', '.'])
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, month, day) = match parts[..] {
+        [year, month, day] if year > 31 => (year as i32, month, day),
+        [day, month, year] if year > 31 => (year as i32, month, day),
+        _ => return None,
+    };
+    let year = if year > 2400 { year - BUDDHIST_ERA_OFFSET } else { year };
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Mirrors `parse_clock`
+fn parse_clock(value: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value.trim(), format).ok())
+}
+
+/// Mirrors `parse_reading_time`
+fn parse_reading_time(value: &str, utc_offset_hours: i32) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let (date, clock) = value.split_once(['T', ' '])?;
+    let local = parse_sheet_date(date)?.and_time(parse_clock(clock)?);
+    FixedOffset::east_opt(utc_offset_hours * 3600)?
+        .from_local_datetime(&local)
+        .single()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Mirrors `preamble_serial`
+fn preamble_serial(line: &str) -> Option<String> {
+    if !line.to_lowercase().contains("serial") {
+        return None;
+    }
+    let (_, value) = line.split_once([':', ',', ';', '\t'])?;
+    let value = value.trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '"' | '\t'));
+    (!value.is_empty()).then(|| value.chars().take(100).collect())
+}
+
+/// Mirrors `LoggerReading`
+#[derive(Debug, Clone)]
+struct LoggerReading {
+    recorded_at: DateTime<Utc>,
+    temperature_c: Decimal,
+    relative_humidity: Option<Decimal>,
+}
+
+/// Mirrors `ConditionLimits`
+struct ConditionLimits {
+    temp_min_c: Decimal,
+    temp_max_c: Decimal,
+    humidity_max_pct: Decimal,
+}
+
+fn default_limits() -> ConditionLimits {
+    ConditionLimits {
+        temp_min_c: Decimal::from(5),
+        temp_max_c: Decimal::from(30),
+        humidity_max_pct: Decimal::from(70),
+    }
+}
+
+/// Mirrors `ExcursionKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExcursionKind {
+    TemperatureHigh,
+    TemperatureLow,
+    HumidityHigh,
+}
+
+impl ExcursionKind {
+    fn breach(&self, reading: &LoggerReading, limits: &ConditionLimits) -> Option<Decimal> {
+        match self {
+            ExcursionKind::TemperatureHigh => Some(reading.temperature_c).filter(|t| *t > limits.temp_max_c),
+            ExcursionKind::TemperatureLow => Some(reading.temperature_c).filter(|t| *t < limits.temp_min_c),
+            ExcursionKind::HumidityHigh => reading.relative_humidity.filter(|rh| *rh > limits.humidity_max_pct),
+        }
+    }
+
+    fn worse(&self, value: Decimal, peak: Decimal) -> bool {
+        match self {
+            ExcursionKind::TemperatureLow => value < peak,
+            ExcursionKind::TemperatureHigh | ExcursionKind::HumidityHigh => value > peak,
+        }
+    }
+}
+
+/// Mirrors `Excursion`
+#[derive(Debug, Clone, PartialEq)]
+struct Excursion {
+    kind: ExcursionKind,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    duration_minutes: i64,
+    peak: Decimal,
+    readings: usize,
+}
+
+/// Mirrors `find_excursions`
+fn find_excursions(readings: &[LoggerReading], limits: &ConditionLimits) -> Vec<Excursion> {
+    let mut excursions = Vec::new();
+    for kind in [
+        ExcursionKind::TemperatureHigh,
+        ExcursionKind::TemperatureLow,
+        ExcursionKind::HumidityHigh,
+    ] {
+        let mut current: Option<Excursion> = None;
+        for reading in readings {
+            let Some(value) = kind.breach(reading, limits) else {
+                if let Some(mut excursion) = current.take() {
+                    excursion.ended_at = reading.recorded_at;
+                    excursions.push(excursion);
+                }
+                continue;
+            };
+            match current.as_mut() {
+                Some(excursion) => {
+                    excursion.ended_at = reading.recorded_at;
+                    excursion.readings += 1;
+                    if kind.worse(value, excursion.peak) {
+                        excursion.peak = value;
+                    }
+                }
+                None => {
+                    current = Some(Excursion {
+                        kind,
+                        started_at: reading.recorded_at,
+                        ended_at: reading.recorded_at,
+                        duration_minutes: 0,
+                        peak: value,
+                        readings: 1,
+                    });
+                }
+            }
+        }
+        excursions.extend(current);
+    }
+
+    for excursion in &mut excursions {
+        excursion.duration_minutes = (excursion.ended_at - excursion.started_at).num_minutes();
+    }
+    excursions.sort_by_key(|e| e.started_at);
+    excursions
+}
+
+/// Mirrors `overlaps_transit`
+fn overlaps_transit(
+    excursion: &Excursion,
+    departed_at: Option<DateTime<Utc>>,
+    arrived_at: Option<DateTime<Utc>>,
+) -> bool {
+    departed_at.is_none_or(|departed| excursion.ended_at >= departed)
+        && arrived_at.is_none_or(|arrived| excursion.started_at <= arrived)
+}
+
+/// Mirrors `mean_kinetic_temperature`
+fn mean_kinetic_temperature(temperatures_c: &[Decimal]) -> Option<Decimal> {
+    if temperatures_c.is_empty() {
+        return None;
+    }
+    let sum: f64 = temperatures_c
+        .iter()
+        .filter_map(|t| t.to_f64())
+        .map(|t| (-MKT_ACTIVATION_KELVIN / (t + KELVIN_OFFSET)).exp())
+        .sum();
+    let kelvin = MKT_ACTIVATION_KELVIN / -(sum / temperatures_c.len() as f64).ln();
+    Decimal::from_f64(kelvin - KELVIN_OFFSET).map(|t| t.round_dp(1))
+}
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap()
+}
+
+/// Hourly readings from `start()`
+fn hourly(temperatures: &[i64], humidities: &[Option<i64>]) -> Vec<LoggerReading> {
+    temperatures
+        .iter()
+        .zip(humidities.iter().chain(std::iter::repeat(&None)))
+        .enumerate()
+        .map(|(i, (t, rh))| LoggerReading {
+            recorded_at: start() + Duration::hours(i as i64),
+            temperature_c: Decimal::from(*t),
+            relative_humidity: rh.map(Decimal::from),
+        })
+        .collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_logger_headers() {
+        assert_eq!(header_field("\u{feff}Date Time"), Some(LoggerField::Timestamp));
+        assert_eq!(header_field("Timestamp"), Some(LoggerField::Timestamp));
+        assert_eq!(header_field("Date"), Some(LoggerField::Date));
+        assert_eq!(header_field("TIME"), Some(LoggerField::Time));
+        assert_eq!(header_field("Temperature (°C)"), Some(LoggerField::TemperatureC));
+        assert_eq!(header_field("Temp(C)"), Some(LoggerField::TemperatureC));
+        assert_eq!(header_field("Temperature [°F]"), Some(LoggerField::TemperatureF));
+        assert_eq!(header_field("RH (%)"), Some(LoggerField::Humidity));
+        assert_eq!(header_field("Humidity %RH"), Some(LoggerField::Humidity));
+        assert_eq!(header_field("Dew Point"), None);
+    }
+
+    #[test]
+    fn test_reading_time_with_zone() {
+        assert_eq!(parse_reading_time("2025-03-01T07:00:00+07:00", 0), Some(start()));
+        assert_eq!(parse_reading_time("2025-03-01T00:00:00Z", 7), Some(start()));
+    }
+
+    #[test]
+    fn test_reading_time_in_logger_zone() {
+        assert_eq!(parse_reading_time("2025-03-01 07:00", 7), Some(start()));
+        assert_eq!(parse_reading_time("01/03/2025 07:00:00", 7), Some(start()));
+        assert_eq!(parse_reading_time("01/03/2568 07:00:00 AM", 7), Some(start()));
+        assert_eq!(parse_reading_time("2025-03-01 00:00:00", 0), Some(start()));
+    }
+
+    #[test]
+    fn test_unreadable_reading_times() {
+        assert_eq!(parse_reading_time("2025-03-01", 7), None);
+        assert_eq!(parse_reading_time("03/2025 07:00", 7), None);
+        assert_eq!(parse_reading_time("2025-03-01 25:00", 7), None);
+        assert_eq!(parse_reading_time("", 7), None);
+    }
+
+    #[test]
+    fn test_preamble_serial() {
+        assert_eq!(preamble_serial("Serial Number:,A1B2C3"), Some("A1B2C3".to_string()));
+        assert_eq!(preamble_serial("\"Logger serial\";\"TT-4410\""), Some("TT-4410".to_string()));
+        assert_eq!(preamble_serial("Serial: 88120"), Some("88120".to_string()));
+        assert_eq!(preamble_serial("Serial Number:,"), None);
+        assert_eq!(preamble_serial("Device: TempTale 4"), None);
+    }
+
+    #[test]
+    fn test_excursion_ends_at_first_reading_back_in_range() {
+        let readings = hourly(&[25, 31, 33, 32, 28, 26], &[]);
+        let excursions = find_excursions(&readings, &default_limits());
+        assert_eq!(excursions.len(), 1);
+        let excursion = &excursions[0];
+        assert_eq!(excursion.kind, ExcursionKind::TemperatureHigh);
+        assert_eq!(excursion.started_at, start() + Duration::hours(1));
+        assert_eq!(excursion.ended_at, start() + Duration::hours(4));
+        assert_eq!(excursion.duration_minutes, 180);
+        assert_eq!(excursion.peak, Decimal::from(33));
+        assert_eq!(excursion.readings, 3);
+    }
+
+    #[test]
+    fn test_excursion_open_at_end_of_log() {
+        let readings = hourly(&[20, 20, 3, 2], &[]);
+        let excursions = find_excursions(&readings, &default_limits());
+        assert_eq!(excursions.len(), 1);
+        assert_eq!(excursions[0].kind, ExcursionKind::TemperatureLow);
+        assert_eq!(excursions[0].ended_at, start() + Duration::hours(3));
+        assert_eq!(excursions[0].duration_minutes, 60);
+        assert_eq!(excursions[0].peak, Decimal::from(2));
+    }
+
+    #[test]
+    fn test_limits_are_inclusive() {
+        let readings = hourly(&[5, 30, 30], &[Some(70), Some(70)]);
+        assert!(find_excursions(&readings, &default_limits()).is_empty());
+    }
+
+    #[test]
+    fn test_humidity_and_temperature_excursions_in_time_order() {
+        let readings = hourly(&[25, 25, 31, 25], &[Some(60), Some(85), Some(90), Some(65)]);
+        let kinds: Vec<ExcursionKind> = find_excursions(&readings, &default_limits())
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(kinds, vec![ExcursionKind::HumidityHigh, ExcursionKind::TemperatureHigh]);
+    }
+
+    #[test]
+    fn test_temperature_only_loggers_have_no_humidity_excursions() {
+        let readings = hourly(&[20, 20, 20], &[]);
+        assert!(find_excursions(&readings, &default_limits()).is_empty());
+    }
+
+    #[test]
+    fn test_transit_overlap() {
+        let readings = hourly(&[25, 35, 25], &[]);
+        let excursion = &find_excursions(&readings, &default_limits())[0];
+        let departed = start() + Duration::hours(2);
+        let arrived = start() + Duration::hours(10);
+        // Back in range exactly at departure still overlaps
+        assert!(overlaps_transit(excursion, Some(departed), Some(arrived)));
+        assert!(!overlaps_transit(excursion, Some(departed + Duration::minutes(1)), None));
+        assert!(!overlaps_transit(excursion, None, Some(start())));
+        // Before departure is recorded the whole log counts
+        assert!(overlaps_transit(excursion, None, None));
+    }
+
+    #[test]
+    fn test_mean_kinetic_temperature() {
+        assert_eq!(mean_kinetic_temperature(&[]), None);
+        let constant = vec![Decimal::from(25); 10];
+        assert_eq!(mean_kinetic_temperature(&constant), Some(Decimal::from(25)));
+        // Heat weighs more than cold: above the arithmetic mean of 25 °C
+        let swinging = [Decimal::from(15), Decimal::from(35)];
+        assert!(mean_kinetic_temperature(&swinging).unwrap() > Decimal::from(27));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_excursion_readings_match_out_of_range_count(
+        temperatures in proptest::collection::vec(-5i64..45, 1..200),
+    ) {
+        let readings = hourly(&temperatures, &[]);
+        let limits = default_limits();
+        let excursions = find_excursions(&readings, &limits);
+        let outside = temperatures.iter().filter(|t| **t < 5 || **t > 30).count();
+        prop_assert_eq!(excursions.iter().map(|e| e.readings).sum::<usize>(), outside);
+        for excursion in &excursions {
+            prop_assert!(excursion.ended_at >= excursion.started_at);
+            prop_assert!(excursion.duration_minutes >= 0);
+        }
+    }
+
+    #[test]
+    fn prop_mkt_between_mean_and_max(temperatures in proptest::collection::vec(-10i64..45, 1..100)) {
+        let values: Vec<Decimal> = temperatures.iter().map(|t| Decimal::from(*t)).collect();
+        let mkt = mean_kinetic_temperature(&values).unwrap();
+        let mean = values.iter().sum::<Decimal>() / Decimal::from(values.len());
+        let max = *values.iter().max().unwrap();
+        prop_assert!(mkt >= mean.round_dp(1) - Decimal::new(1, 1));
+        prop_assert!(mkt <= max + Decimal::new(1, 1));
+    }
+
+    #[test]
+    fn prop_reading_time_offset_shifts_utc(hour in 0u32..24, offset in -12i32..=14) {
+        let local = format!("2025-03-15 {:02}:30", hour);
+        let utc = parse_reading_time(&local, offset).unwrap();
+        let as_utc = parse_reading_time(&local, 0).unwrap();
+        prop_assert_eq!(as_utc - utc, Duration::hours(offset as i64));
+    }
+}
@@ -0,0 +1,202 @@
+//! Shipment tests
+//!
+//! Tests for shipment and logistics tracking:
+//! - Carrier event codes map to milestones
+//! - Status is the furthest milestone reached, whatever the arrival order
+//! - Milestone times must follow the milestone order
+//! - Shipment numbering
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::prelude::*;
+
+/// Mirrors `Milestone`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Milestone {
+    Booked,
+    Loaded,
+    Departed,
+    Arrived,
+    Cleared,
+}
+
+const ALL_MILESTONES: [Milestone; 5] = [
+    Milestone::Booked,
+    Milestone::Loaded,
+    Milestone::Departed,
+    Milestone::Arrived,
+    Milestone::Cleared,
+];
+
+/// Mirrors `ShipmentStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShipmentStatus {
+    Planned,
+    Booked,
+    Loaded,
+    Departed,
+    Arrived,
+    Cleared,
+}
+
+impl From<Milestone> for ShipmentStatus {
+    fn from(milestone: Milestone) -> Self {
+        match milestone {
+            Milestone::Booked => ShipmentStatus::Booked,
+            Milestone::Loaded => ShipmentStatus::Loaded,
+            Milestone::Departed => ShipmentStatus::Departed,
+            Milestone::Arrived => ShipmentStatus::Arrived,
+            Milestone::Cleared => ShipmentStatus::Cleared,
+        }
+    }
+}
+
+/// Mirrors `milestone_from_event`
+fn milestone_from_event(event: &str) -> Option<Milestone> {
+    let code = event.trim().to_lowercase().replace([' ', '-'], "_");
+    match code.as_str() {
+        "booked" | "booking_confirmed" | "bkd" => Some(Milestone::Booked),
+        "loaded" | "loaded_on_vessel" | "container_loaded" | "stuffed" | "lod" => Some(Milestone::Loaded),
+        "departed" | "departure" | "vessel_departed" | "flight_departed" | "dep" | "atd" => {
+            Some(Milestone::Departed)
+        }
+        "arrived" | "arrival" | "vessel_arrived" | "flight_arrived" | "arr" | "ata" => Some(Milestone::Arrived),
+        "cleared" | "customs_cleared" | "customs_released" | "released" | "clr" => Some(Milestone::Cleared),
+        _ => None,
+    }
+}
+
+/// Mirrors `shipment_status`
+fn shipment_status(milestones: &[Milestone]) -> ShipmentStatus {
+    milestones
+        .iter()
+        .max()
+        .map(|m| ShipmentStatus::from(*m))
+        .unwrap_or(ShipmentStatus::Planned)
+}
+
+/// Mirrors `out_of_sequence`
+fn out_of_sequence(
+    recorded: &[(Milestone, DateTime<Utc>)],
+    milestone: Milestone,
+    occurred_at: DateTime<Utc>,
+) -> Option<Milestone> {
+    recorded
+        .iter()
+        .find(|(m, at)| (*m < milestone && *at > occurred_at) || (*m > milestone && *at < occurred_at))
+        .map(|(m, _)| *m)
+}
+
+/// Mirrors `shipment_number`
+fn shipment_number(year: i32, sequence: i64) -> String {
+    format!("SH-{}-{:04}", year, sequence)
+}
+
+fn at(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_carrier_event_codes() {
+        assert_eq!(milestone_from_event("booked"), Some(Milestone::Booked));
+        assert_eq!(milestone_from_event("ATD"), Some(Milestone::Departed));
+        assert_eq!(milestone_from_event("VESSEL_DEPARTED"), Some(Milestone::Departed));
+        assert_eq!(milestone_from_event("Vessel Arrived"), Some(Milestone::Arrived));
+        assert_eq!(milestone_from_event(" customs-released "), Some(Milestone::Cleared));
+        assert_eq!(milestone_from_event("container loaded"), Some(Milestone::Loaded));
+        assert_eq!(milestone_from_event("delayed"), None);
+        assert_eq!(milestone_from_event(""), None);
+    }
+
+    #[test]
+    fn test_status_is_furthest_milestone() {
+        assert_eq!(shipment_status(&[]), ShipmentStatus::Planned);
+        assert_eq!(shipment_status(&[Milestone::Booked]), ShipmentStatus::Booked);
+        // A departure posted before the loading event still counts as departed
+        assert_eq!(
+            shipment_status(&[Milestone::Departed, Milestone::Loaded]),
+            ShipmentStatus::Departed
+        );
+        assert_eq!(shipment_status(&ALL_MILESTONES), ShipmentStatus::Cleared);
+    }
+
+    #[test]
+    fn test_milestones_in_order_are_accepted() {
+        let recorded = [(Milestone::Booked, at(1, 9)), (Milestone::Loaded, at(3, 14))];
+        assert_eq!(out_of_sequence(&recorded, Milestone::Departed, at(4, 6)), None);
+        // Same time as the previous step is allowed
+        assert_eq!(out_of_sequence(&recorded, Milestone::Departed, at(3, 14)), None);
+    }
+
+    #[test]
+    fn test_departure_before_loading_is_rejected() {
+        let recorded = [(Milestone::Booked, at(1, 9)), (Milestone::Loaded, at(3, 14))];
+        assert_eq!(
+            out_of_sequence(&recorded, Milestone::Departed, at(2, 10)),
+            Some(Milestone::Loaded)
+        );
+    }
+
+    #[test]
+    fn test_late_step_recorded_first_bounds_earlier_steps() {
+        let recorded = [(Milestone::Arrived, at(20, 8))];
+        assert_eq!(out_of_sequence(&recorded, Milestone::Departed, at(5, 8)), None);
+        assert_eq!(
+            out_of_sequence(&recorded, Milestone::Departed, at(21, 8)),
+            Some(Milestone::Arrived)
+        );
+    }
+
+    #[test]
+    fn test_shipment_number_format() {
+        assert_eq!(shipment_number(2025, 7), "SH-2025-0007");
+        assert_eq!(shipment_number(2025, 12345), "SH-2025-12345");
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_status_ignores_arrival_order(mask in 1u8..32, rotate in 0usize..5) {
+        let mut reached: Vec<Milestone> = ALL_MILESTONES
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, m)| *m)
+            .collect();
+        let furthest = *reached.iter().max().unwrap();
+        let len = reached.len();
+        reached.rotate_left(rotate % len);
+        prop_assert_eq!(shipment_status(&reached), ShipmentStatus::from(furthest));
+    }
+
+    #[test]
+    fn prop_chronological_milestones_are_never_out_of_sequence(
+        gaps in proptest::collection::vec(0i64..240, 5),
+        index in 0usize..5,
+    ) {
+        let start = at(1, 0);
+        let mut time = start;
+        let timeline: Vec<(Milestone, DateTime<Utc>)> = ALL_MILESTONES
+            .iter()
+            .zip(gaps.iter())
+            .map(|(m, gap)| {
+                time += Duration::hours(*gap);
+                (*m, time)
+            })
+            .collect();
+        let (milestone, occurred_at) = timeline[index];
+        let others: Vec<_> = timeline.iter().copied().filter(|(m, _)| *m != milestone).collect();
+        prop_assert_eq!(out_of_sequence(&others, milestone, occurred_at), None);
+    }
+}
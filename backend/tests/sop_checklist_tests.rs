@@ -0,0 +1,206 @@
+//! SOP checklist tests
+//!
+//! Tests for checklists made from SOPs and their execution records:
+//! - Numbered and bulleted step lines
+//! - English and Thai steps paired in order
+//! - Steps picked by ID or number
+//! - Required steps left and missed days of daily checklists
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Mirrors the fields of `ExecutionStep` used here
+#[derive(Debug, Clone)]
+struct ExecutionStep {
+    item_id: Uuid,
+    position: i32,
+    required: bool,
+    checked: bool,
+}
+
+/// Mirrors `strip_step_marker`
+fn strip_step_marker(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        rest
+    } else {
+        let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        line[digits..].strip_prefix(['.', ')'])?
+    };
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim()).filter(|text| !text.is_empty())
+}
+
+/// Mirrors `checklist_steps`, with the error as None
+fn checklist_steps(body: &str, body_th: &str) -> Option<Vec<(String, String)>> {
+    let steps: Vec<&str> = body.lines().filter_map(strip_step_marker).collect();
+    let steps_th: Vec<&str> = body_th.lines().filter_map(strip_step_marker).collect();
+    if steps.is_empty() || steps.len() != steps_th.len() {
+        return None;
+    }
+    Some(
+        steps
+            .into_iter()
+            .zip(steps_th)
+            .map(|(text, text_th)| (text.to_string(), text_th.to_string()))
+            .collect(),
+    )
+}
+
+/// Mirrors `required_remaining`
+fn required_remaining(steps: &[ExecutionStep]) -> usize {
+    steps.iter().filter(|s| s.required && !s.checked).count()
+}
+
+/// Mirrors `missed_dates`
+fn missed_dates(from: NaiveDate, to: NaiveDate, completed: &BTreeSet<NaiveDate>) -> Vec<NaiveDate> {
+    from.iter_days()
+        .take_while(|day| *day <= to)
+        .filter(|day| !completed.contains(day))
+        .collect()
+}
+
+/// Mirrors `select_steps`, with the error as None
+fn select_steps(steps: &[ExecutionStep], item_ids: &[Uuid], positions: &[i32]) -> Option<Vec<Uuid>> {
+    let mut selected = BTreeSet::new();
+    for id in item_ids {
+        if !steps.iter().any(|s| s.item_id == *id) {
+            return None;
+        }
+        selected.insert(*id);
+    }
+    for position in positions {
+        let step = steps.iter().find(|s| s.position == *position)?;
+        selected.insert(step.item_id);
+    }
+    if selected.is_empty() {
+        return None;
+    }
+    Some(selected.into_iter().collect())
+}
+
+fn steps(count: i32) -> Vec<ExecutionStep> {
+    (1..=count)
+        .map(|position| ExecutionStep {
+            item_id: Uuid::new_v4(),
+            position,
+            required: true,
+            checked: false,
+        })
+        .collect()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_step_markers() {
+        assert_eq!(strip_step_marker("1. Float the cherry"), Some("Float the cherry"));
+        assert_eq!(strip_step_marker("  12) Turn the beds "), Some("Turn the beds"));
+        assert_eq!(strip_step_marker("- Cover at night"), Some("Cover at night"));
+        assert_eq!(strip_step_marker("• คลุมในเวลากลางคืน"), Some("คลุมในเวลากลางคืน"));
+        assert_eq!(strip_step_marker("Dry to 10-12% moisture"), None);
+        assert_eq!(strip_step_marker("2024 harvest"), None);
+        assert_eq!(strip_step_marker("1.5 kg per bed"), None);
+        assert_eq!(strip_step_marker("3. "), None);
+    }
+
+    #[test]
+    fn test_sop_steps_paired() {
+        let body = "Drying routine:\n1. Spread cherry thinly.\n2. Turn every hour.\nKeep a log.";
+        let body_th = "1. เกลี่ยเชอร์รี่ให้บาง\n2. พลิกทุกชั่วโมง";
+        let steps = checklist_steps(body, body_th).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                ("Spread cherry thinly.".to_string(), "เกลี่ยเชอร์รี่ให้บาง".to_string()),
+                ("Turn every hour.".to_string(), "พลิกทุกชั่วโมง".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sop_steps_must_match() {
+        assert!(checklist_steps("1. One\n2. Two", "1. หนึ่ง").is_none());
+        assert!(checklist_steps("No steps here", "ไม่มีขั้นตอน").is_none());
+    }
+
+    #[test]
+    fn test_required_remaining() {
+        let mut list = steps(3);
+        list[0].checked = true;
+        list[2].required = false;
+        assert_eq!(required_remaining(&list), 1);
+        list[1].checked = true;
+        assert_eq!(required_remaining(&list), 0);
+    }
+
+    #[test]
+    fn test_select_steps_by_id_and_number() {
+        let list = steps(4);
+        let selected = select_steps(&list, &[list[0].item_id], &[1, 3]).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&list[0].item_id));
+        assert!(selected.contains(&list[2].item_id));
+        assert!(select_steps(&list, &[], &[5]).is_none());
+        assert!(select_steps(&list, &[Uuid::new_v4()], &[]).is_none());
+        assert!(select_steps(&list, &[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_missed_days() {
+        let completed = BTreeSet::from([date("2024-12-01"), date("2024-12-03"), date("2024-12-09")]);
+        assert_eq!(
+            missed_dates(date("2024-12-01"), date("2024-12-04"), &completed),
+            vec![date("2024-12-02"), date("2024-12-04")]
+        );
+        assert!(missed_dates(date("2024-12-03"), date("2024-12-03"), &completed).is_empty());
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_numbered_lines_become_steps(texts in prop::collection::vec("[a-zA-Z][a-zA-Z ]{0,30}[a-zA-Z]", 1..15)) {
+        let body: String = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| format!("{}. {}\n", i + 1, text))
+            .collect();
+        let steps = checklist_steps(&body, &body).unwrap();
+        prop_assert_eq!(steps.len(), texts.len());
+        for ((text, _), expected) in steps.iter().zip(&texts) {
+            prop_assert_eq!(text, expected.trim());
+        }
+    }
+
+    #[test]
+    fn prop_missed_and_completed_cover_period(days in 0i64..60, completed in prop::collection::btree_set(0i64..60, 0..60)) {
+        let from = date("2024-11-01");
+        let to = from + chrono::Duration::days(days);
+        let completed: BTreeSet<NaiveDate> = completed.into_iter().map(|d| from + chrono::Duration::days(d)).collect();
+        let missed = missed_dates(from, to, &completed);
+        let completed_in_period = completed.iter().filter(|d| **d <= to).count();
+        prop_assert_eq!(missed.len() + completed_in_period, days as usize + 1);
+        prop_assert!(missed.iter().all(|d| !completed.contains(d)));
+    }
+}
@@ -0,0 +1,227 @@
+//! Storage heat advisory tests
+//!
+//! Tests for finding hot spells at storage locations:
+//! - Forecast items are grouped by local day, keeping the day's high
+//! - A hot spell is at least three consecutive days above 32°C; a day at
+//!   exactly 32°C or a gap in the forecast ends it
+//! - The first qualifying spell is reported with its peak
+//! - Coordinates are validated
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const HOT_STORAGE_CELSIUS: i64 = 32;
+const HOT_SPELL_MIN_DAYS: usize = 3;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// Mirrors `DailyHigh`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DailyHigh {
+    date: NaiveDate,
+    max_celsius: Decimal,
+}
+
+/// Mirrors `HotSpell`
+#[derive(Debug, Clone, PartialEq)]
+struct HotSpell {
+    start: NaiveDate,
+    end: NaiveDate,
+    days: usize,
+    peak_celsius: Decimal,
+}
+
+/// Mirrors `daily_highs` over (timestamp, temp_max) forecast items
+fn daily_highs(items: &[(DateTime<Utc>, Decimal)], timezone_offset_seconds: i32) -> Vec<DailyHigh> {
+    let offset = Duration::seconds(timezone_offset_seconds as i64);
+    let mut highs: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+    for (timestamp, temp_max) in items {
+        let date = (*timestamp + offset).date_naive();
+        let high = highs.entry(date).or_insert(*temp_max);
+        *high = (*high).max(*temp_max);
+    }
+    highs
+        .into_iter()
+        .map(|(date, max_celsius)| DailyHigh { date, max_celsius })
+        .collect()
+}
+
+/// Mirrors `find_hot_spell`
+fn find_hot_spell(highs: &[DailyHigh], threshold: Decimal, min_days: usize) -> Option<HotSpell> {
+    let mut run: Vec<&DailyHigh> = Vec::new();
+    for high in highs {
+        let hot = high.max_celsius > threshold;
+        let continues = run
+            .last()
+            .is_some_and(|last| last.date + Duration::days(1) == high.date);
+        if run.len() >= min_days && !(hot && continues) {
+            break;
+        }
+        if !hot || !continues {
+            run.clear();
+        }
+        if hot {
+            run.push(high);
+        }
+    }
+    if run.len() < min_days.max(1) {
+        return None;
+    }
+    Some(HotSpell {
+        start: run[0].date,
+        end: run[run.len() - 1].date,
+        days: run.len(),
+        peak_celsius: run.iter().map(|h| h.max_celsius).max().unwrap_or_default(),
+    })
+}
+
+/// Mirrors the coordinate checks of `validate_location`
+fn valid_coordinates(latitude: Decimal, longitude: Decimal) -> bool {
+    latitude.abs() <= Decimal::from(90) && longitude.abs() <= Decimal::from(180)
+}
+
+/// Consecutive daily highs from a start date
+fn highs_from(start: &str, temps: &[&str]) -> Vec<DailyHigh> {
+    temps
+        .iter()
+        .enumerate()
+        .map(|(i, t)| DailyHigh {
+            date: date(start) + Duration::days(i as i64),
+            max_celsius: dec(t),
+        })
+        .collect()
+}
+
+fn spell(highs: &[DailyHigh]) -> Option<HotSpell> {
+    find_hot_spell(highs, Decimal::from(HOT_STORAGE_CELSIUS), HOT_SPELL_MIN_DAYS)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    // ========================================================================
+    // Daily highs
+    // ========================================================================
+
+    #[test]
+    fn test_daily_highs_use_local_days() {
+        // 18:00 UTC is 01:00 the next day in Bangkok
+        let items = [
+            (Utc.with_ymd_and_hms(2024, 4, 10, 6, 0, 0).unwrap(), dec("34.5")),
+            (Utc.with_ymd_and_hms(2024, 4, 10, 9, 0, 0).unwrap(), dec("35.2")),
+            (Utc.with_ymd_and_hms(2024, 4, 10, 18, 0, 0).unwrap(), dec("27.0")),
+        ];
+        let highs = daily_highs(&items, 7 * 3600);
+        assert_eq!(
+            highs,
+            vec![
+                DailyHigh { date: date("2024-04-10"), max_celsius: dec("35.2") },
+                DailyHigh { date: date("2024-04-11"), max_celsius: dec("27.0") },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_daily_highs_empty_forecast() {
+        assert!(daily_highs(&[], 0).is_empty());
+    }
+
+    // ========================================================================
+    // Hot spells
+    // ========================================================================
+
+    #[test]
+    fn test_three_hot_days_make_a_spell() {
+        let highs = highs_from("2024-04-10", &["31", "33", "34.5", "33.1", "30"]);
+        assert_eq!(
+            spell(&highs),
+            Some(HotSpell {
+                start: date("2024-04-11"),
+                end: date("2024-04-13"),
+                days: 3,
+                peak_celsius: dec("34.5"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_two_hot_days_are_not_a_spell() {
+        let highs = highs_from("2024-04-10", &["33", "34", "31", "35", "36"]);
+        assert_eq!(spell(&highs), None);
+    }
+
+    #[test]
+    fn test_exactly_threshold_is_not_hot() {
+        let highs = highs_from("2024-04-10", &["33", "32", "33", "33"]);
+        assert_eq!(spell(&highs), None);
+    }
+
+    #[test]
+    fn test_spell_runs_to_end_of_forecast() {
+        let highs = highs_from("2024-04-10", &["30", "33", "33", "34", "35"]);
+        let found = spell(&highs).unwrap();
+        assert_eq!(found.start, date("2024-04-11"));
+        assert_eq!(found.end, date("2024-04-14"));
+        assert_eq!(found.days, 4);
+        assert_eq!(found.peak_celsius, dec("35"));
+    }
+
+    #[test]
+    fn test_first_spell_is_reported() {
+        let highs = highs_from("2024-04-01", &["33", "33", "33", "30", "36", "37", "38"]);
+        let found = spell(&highs).unwrap();
+        assert_eq!(found.start, date("2024-04-01"));
+        assert_eq!(found.end, date("2024-04-03"));
+    }
+
+    #[test]
+    fn test_missing_day_breaks_the_run() {
+        let mut highs = highs_from("2024-04-10", &["33", "33"]);
+        highs.extend(highs_from("2024-04-13", &["33"]));
+        assert_eq!(spell(&highs), None);
+    }
+
+    // ========================================================================
+    // Validation
+    // ========================================================================
+
+    #[test]
+    fn test_coordinates() {
+        assert!(valid_coordinates(dec("18.7883"), dec("98.9853")));
+        assert!(valid_coordinates(dec("-90"), dec("180")));
+        assert!(!valid_coordinates(dec("90.1"), dec("98")));
+        assert!(!valid_coordinates(dec("18"), dec("-180.5")));
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_spell_days_are_all_hot_and_consecutive(temps in prop::collection::vec(25i64..40, 0..10)) {
+        let highs: Vec<DailyHigh> = temps
+            .iter()
+            .enumerate()
+            .map(|(i, t)| DailyHigh { date: date("2024-04-01") + Duration::days(i as i64), max_celsius: Decimal::from(*t) })
+            .collect();
+        if let Some(found) = spell(&highs) {
+            prop_assert!(found.days >= HOT_SPELL_MIN_DAYS);
+            prop_assert_eq!((found.end - found.start).num_days() + 1, found.days as i64);
+            let days: Vec<&DailyHigh> = highs.iter().filter(|h| found.start <= h.date && h.date <= found.end).collect();
+            prop_assert!(days.iter().all(|h| h.max_celsius > Decimal::from(HOT_STORAGE_CELSIUS)));
+            prop_assert_eq!(days.iter().map(|h| h.max_celsius).max(), Some(found.peak_celsius));
+        } else {
+            // No run of three hot days anywhere
+            prop_assert!(!temps.windows(HOT_SPELL_MIN_DAYS).any(|w| w.iter().all(|t| *t > HOT_STORAGE_CELSIUS)));
+        }
+    }
+}
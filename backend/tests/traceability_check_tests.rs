@@ -0,0 +1,177 @@
+//! Traceability timeline check tests
+//!
+//! Tests for flagging impossible sequences on a lot's timeline:
+//! - Negative durations and harvests after processing started
+//! - Downstream events before processing was complete
+//! - Roast milestones out of order
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// Mirrors `ProcessingSpan` (start, end)
+type Span = (NaiveDate, Option<NaiveDate>);
+
+/// Mirrors the issue kinds raised by `check_timeline`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Issue {
+    FutureDate,
+    NegativeDuration,
+    HarvestAfterProcessingStart,
+    BeforeHarvest,
+    BeforeProcessingComplete,
+}
+
+/// Mirrors `check_timeline` for harvests, processing and downstream dates
+fn check_timeline(
+    harvests: &[NaiveDate],
+    processing: &[Span],
+    downstream: &[NaiveDate],
+    today: NaiveDate,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let first_harvest = harvests.iter().min().copied();
+    let processing_start = processing.iter().map(|p| p.0).min();
+    let processing_end = if processing.iter().all(|p| p.1.is_some()) {
+        processing.iter().filter_map(|p| p.1).max()
+    } else {
+        None
+    };
+
+    for harvest in harvests.iter().chain(downstream) {
+        if *harvest > today {
+            issues.push(Issue::FutureDate);
+        }
+    }
+    for (start, end) in processing {
+        if end.is_some_and(|end| end < *start) {
+            issues.push(Issue::NegativeDuration);
+        }
+    }
+    if let Some(start) = processing_start {
+        for _ in harvests.iter().filter(|h| **h > start) {
+            issues.push(Issue::HarvestAfterProcessingStart);
+        }
+    }
+    for record in downstream {
+        if first_harvest.is_some_and(|h| *record < h) {
+            issues.push(Issue::BeforeHarvest);
+        }
+        if processing_end.or(processing_start).is_some_and(|c| *record < c) {
+            issues.push(Issue::BeforeProcessingComplete);
+        }
+    }
+    issues
+}
+
+/// Mirrors the roast milestone check
+fn roast_milestones_out_of_order(milestones: [Option<i32>; 4]) -> bool {
+    let milestones: Vec<i32> = milestones.into_iter().flatten().collect();
+    milestones.iter().any(|s| *s < 0) || milestones.windows(2).any(|w| w[1] <= w[0])
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_consistent_lot() {
+        let issues = check_timeline(
+            &[date("2024-11-02"), date("2024-11-05")],
+            &[(date("2024-11-06"), Some(date("2024-12-01")))],
+            &[date("2024-12-10"), date("2025-01-15")],
+            date("2025-02-01"),
+        );
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_processing_ending_before_start() {
+        let issues = check_timeline(
+            &[date("2024-11-02")],
+            &[(date("2024-12-01"), Some(date("2024-11-06")))],
+            &[],
+            date("2025-02-01"),
+        );
+        assert_eq!(issues, vec![Issue::NegativeDuration]);
+    }
+
+    #[test]
+    fn test_harvest_after_processing_start() {
+        let issues = check_timeline(
+            &[date("2024-11-02"), date("2024-11-20")],
+            &[(date("2024-11-06"), None)],
+            &[],
+            date("2025-02-01"),
+        );
+        assert_eq!(issues, vec![Issue::HarvestAfterProcessingStart]);
+    }
+
+    #[test]
+    fn test_roast_before_processing_complete() {
+        let issues = check_timeline(
+            &[date("2024-11-02")],
+            &[(date("2024-11-06"), Some(date("2024-12-01")))],
+            &[date("2024-11-20")],
+            date("2025-02-01"),
+        );
+        assert_eq!(issues, vec![Issue::BeforeProcessingComplete]);
+    }
+
+    #[test]
+    fn test_wrong_year_is_caught_twice() {
+        // Roast typed as 2023 instead of 2024
+        let issues = check_timeline(
+            &[date("2024-11-02")],
+            &[(date("2024-11-06"), Some(date("2024-12-01")))],
+            &[date("2023-12-20")],
+            date("2025-02-01"),
+        );
+        assert_eq!(issues, vec![Issue::BeforeHarvest, Issue::BeforeProcessingComplete]);
+    }
+
+    #[test]
+    fn test_future_dates() {
+        let issues = check_timeline(&[date("2025-11-02")], &[], &[], date("2025-02-01"));
+        assert_eq!(issues, vec![Issue::FutureDate]);
+    }
+
+    #[test]
+    fn test_roast_milestones() {
+        assert!(!roast_milestones_out_of_order([Some(90), Some(480), None, Some(600)]));
+        assert!(roast_milestones_out_of_order([Some(90), Some(620), None, Some(600)]));
+        assert!(roast_milestones_out_of_order([Some(-5), None, None, None]));
+        assert!(!roast_milestones_out_of_order([None, None, None, None]));
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_ordered_timeline_is_consistent(
+        harvest_days in prop::collection::vec(0i64..30, 1..5),
+        gap in 0i64..10,
+        processing_days in 0i64..60,
+        after in prop::collection::vec(0i64..200, 0..5),
+    ) {
+        let base = date("2024-11-01");
+        let day = |d: i64| base + chrono::Duration::days(d);
+        let harvests: Vec<NaiveDate> = harvest_days.iter().map(|d| day(*d)).collect();
+        let start = day(harvest_days.iter().max().unwrap() + gap);
+        let end = start + chrono::Duration::days(processing_days);
+        let downstream: Vec<NaiveDate> = after.iter().map(|d| end + chrono::Duration::days(*d)).collect();
+
+        let issues = check_timeline(&harvests, &[(start, Some(end))], &downstream, day(1000));
+        prop_assert!(issues.is_empty());
+    }
+}
@@ -2,8 +2,73 @@
 //!
 //! Tests for lot traceability including:
 //! - Property 12: Traceability View Completeness
+//! - Spec sheet text wrapping and screen size summary
 
 use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+/// Mirrors `display_width`: Thai vowel and tone marks take no space
+fn display_width(text: &str) -> usize {
+    text.lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !matches!(*c as u32, 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Mirrors `wrap_text` in the PDF service
+fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = if line.is_empty() {
+                display_width(word)
+            } else {
+                display_width(&line) + 1 + display_width(word)
+            };
+            if needed <= max_width {
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                if display_width(&line) >= max_width && display_width(&c.to_string()) > 0 {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Mirrors `screen_size_summary` in the spec sheet service
+fn screen_size_summary(bands: [Decimal; 5]) -> String {
+    ["18+", "17", "16", "15", "14-"]
+        .into_iter()
+        .zip(bands)
+        .filter(|(_, share)| *share > Decimal::ZERO)
+        .map(|(band, share)| format!("S{} {}%", band, share.round_dp(1).normalize()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
 // ============================================================================
 // Unit Tests
@@ -11,6 +76,8 @@ use proptest::prelude::*;
 
 #[cfg(test)]
 mod unit_tests {
+    use super::*;
+
     /// Test traceability code format
     #[test]
     fn test_traceability_code_format() {
@@ -59,7 +126,8 @@ mod unit_tests {
         // - grading (optional - grade, defects)
         // - cupping (optional - score, notes)
         // - sources (for blended lots)
-        // - certifications (optional)
+        // - certifications (optional - only those backing the lot's claims)
+        // - certification_claims (each certification type held, with its derivation)
         
         // This is a structural test - actual data tests require database
         assert!(true);
@@ -103,6 +171,33 @@ mod unit_tests {
             assert!(lang == "en" || lang == "th");
         }
     }
+
+    #[test]
+    fn test_spec_sheet_wraps_on_words() {
+        let lines = wrap_text("Red apple, caramel, almond and a long honey finish", 20);
+        assert_eq!(lines, vec!["Red apple, caramel,", "almond and a long", "honey finish"]);
+    }
+
+    #[test]
+    fn test_spec_sheet_breaks_thai_without_spaces() {
+        // Thai runs have no spaces; marks stay attached to their consonant
+        let lines = wrap_text("กาแฟดอยช้างคั่วกลาง", 6);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| display_width(l) <= 6));
+        assert_eq!(lines.concat(), "กาแฟดอยช้างคั่วกลาง");
+    }
+
+    #[test]
+    fn test_screen_size_summary_skips_empty_bands() {
+        let summary = screen_size_summary([
+            Decimal::from(45),
+            Decimal::new(305, 1),
+            Decimal::ZERO,
+            Decimal::new(245, 1),
+            Decimal::ZERO,
+        ]);
+        assert_eq!(summary, "S18+ 45%, S17 30.5%, S15 24.5%");
+    }
 }
 
 // ============================================================================
@@ -179,5 +274,20 @@ mod property_tests {
                 prop_assert_ne!(code1, code2);
             }
         }
+
+        /// Wrapped lines fit the width and keep every non-space character
+        #[test]
+        fn prop_wrapped_text_fits_and_preserves_content(
+            text in "[a-z ]{0,120}",
+            width in 5usize..40
+        ) {
+            let lines = wrap_text(&text, width);
+            for line in &lines {
+                prop_assert!(display_width(line) <= width);
+            }
+            let original: String = text.split_whitespace().collect();
+            let wrapped: String = lines.iter().flat_map(|l| l.split_whitespace()).collect();
+            prop_assert_eq!(original, wrapped);
+        }
     }
 }
@@ -0,0 +1,76 @@
+//! Water quality log tests
+//!
+//! Tests for SCA water standard checks:
+//! - TDS 75-250 ppm and pH 6.5-7.5 are within the standard, bounds included
+//! - Each parameter outside its range produces one warning
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+// Helper to create Decimal from string
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Replicates the range check of `water_quality_warnings` in the water
+/// quality service; returns the parameters out of range
+fn out_of_range(tds_ppm: Decimal, ph: Decimal) -> Vec<&'static str> {
+    let mut parameters = Vec::new();
+    if tds_ppm < dec("75") || tds_ppm > dec("250") {
+        parameters.push("tds_ppm");
+    }
+    if ph < dec("6.5") || ph > dec("7.5") {
+        parameters.push("ph");
+    }
+    parameters
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_sca_target_water_passes() {
+        assert!(out_of_range(dec("150"), dec("7.0")).is_empty());
+    }
+
+    #[test]
+    fn test_range_bounds_are_inclusive() {
+        assert!(out_of_range(dec("75"), dec("6.5")).is_empty());
+        assert!(out_of_range(dec("250"), dec("7.5")).is_empty());
+        assert_eq!(out_of_range(dec("74.9"), dec("7.0")), vec!["tds_ppm"]);
+        assert_eq!(out_of_range(dec("150"), dec("7.51")), vec!["ph"]);
+    }
+
+    #[test]
+    fn test_distilled_water_fails_both() {
+        // Distilled water is nearly mineral-free and slightly acidic
+        assert_eq!(out_of_range(dec("2"), dec("5.8")), vec!["tds_ppm", "ph"]);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+
+    proptest! {
+        /// A TDS warning appears exactly when TDS is outside 75-250 ppm,
+        /// whatever the pH
+        #[test]
+        fn prop_tds_warning_independent_of_ph(tds in 0i64..5000, ph in 0i64..140) {
+            let tds = Decimal::new(tds, 1);
+            let warnings = out_of_range(tds, Decimal::new(ph, 1));
+            let expected = tds < dec("75") || tds > dec("250");
+            prop_assert_eq!(warnings.contains(&"tds_ppm"), expected);
+        }
+    }
+}
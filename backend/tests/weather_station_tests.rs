@@ -0,0 +1,305 @@
+//! Weather station tests
+//!
+//! Tests for readings posted by farm weather stations:
+//! - Field mappings need a temperature, one entry per field and known units
+//! - Payload paths through objects and arrays
+//! - Imperial readings converted to metric units
+//! - Vendor payloads read with their templates, and readings out of range rejected
+
+use proptest::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+/// Mirrors `FieldMapping`, with the field by name
+#[derive(Debug, Clone, PartialEq)]
+struct FieldMapping {
+    field: &'static str,
+    path: String,
+    unit: Option<String>,
+}
+
+/// Mirrors `StationField::units`
+fn units(field: &str) -> &'static [&'static str] {
+    match field {
+        "recorded_at" => &["unix", "unix_ms", "datetime"],
+        "temperature_celsius" => &["c", "f"],
+        "humidity_percent" => &["percent"],
+        "pressure_hpa" => &["hpa", "inhg", "mmhg"],
+        "wind_speed_mps" => &["mps", "kmh", "mph", "knots"],
+        "wind_direction_deg" => &["deg"],
+        _ => &["mm", "in"],
+    }
+}
+
+fn mapping(field: &'static str, path: &str, unit: Option<&str>) -> FieldMapping {
+    FieldMapping {
+        field,
+        path: path.to_string(),
+        unit: unit.map(str::to_string),
+    }
+}
+
+/// Mirrors `station_template` for Ecowitt
+fn ecowitt_template() -> Vec<FieldMapping> {
+    vec![
+        mapping("recorded_at", "dateutc", Some("datetime")),
+        mapping("temperature_celsius", "tempf", Some("f")),
+        mapping("humidity_percent", "humidity", Some("percent")),
+        mapping("pressure_hpa", "baromrelin", Some("inhg")),
+        mapping("wind_speed_mps", "windspeedmph", Some("mph")),
+        mapping("wind_direction_deg", "winddir", Some("deg")),
+        mapping("rain_1h_mm", "hourlyrainin", Some("in")),
+    ]
+}
+
+/// Mirrors `station_template` for Davis
+fn davis_template() -> Vec<FieldMapping> {
+    vec![
+        mapping("recorded_at", "data.ts", Some("unix")),
+        mapping("temperature_celsius", "data.conditions.0.temp", Some("f")),
+        mapping("humidity_percent", "data.conditions.0.hum", Some("percent")),
+        mapping("wind_speed_mps", "data.conditions.0.wind_speed_last", Some("mph")),
+        mapping("wind_direction_deg", "data.conditions.0.wind_dir_last", Some("deg")),
+        mapping("pressure_hpa", "data.conditions.2.bar_sea_level", Some("inhg")),
+    ]
+}
+
+/// Mirrors `validate_mapping`, with the error as None
+fn validate_mapping(mapping: &[FieldMapping]) -> Option<Vec<FieldMapping>> {
+    if !mapping.iter().any(|m| m.field == "temperature_celsius") {
+        return None;
+    }
+    let mut checked: Vec<FieldMapping> = Vec::new();
+    for entry in mapping {
+        if checked.iter().any(|c| c.field == entry.field) {
+            return None;
+        }
+        let path = entry.path.trim();
+        if path.is_empty() || path.split('.').any(str::is_empty) {
+            return None;
+        }
+        let units = units(entry.field);
+        let unit = entry.unit.as_deref().map(str::trim).unwrap_or(units[0]).to_lowercase();
+        if !units.contains(&unit.as_str()) {
+            return None;
+        }
+        checked.push(FieldMapping {
+            field: entry.field,
+            path: path.to_string(),
+            unit: Some(unit),
+        });
+    }
+    Some(checked)
+}
+
+/// Mirrors `lookup`
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(payload, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// Mirrors `number`
+fn number(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => n.to_string().parse::<Decimal>().ok(),
+        Value::String(s) => s.trim().parse::<Decimal>().ok(),
+        _ => None,
+    }
+}
+
+/// Mirrors `to_metric`
+fn to_metric(value: Decimal, unit: &str) -> Decimal {
+    match unit {
+        "f" => (value - Decimal::from(32)) * Decimal::from(5) / Decimal::from(9),
+        "inhg" => value * Decimal::new(338639, 4),
+        "mmhg" => value * Decimal::new(133322, 5),
+        "kmh" => value / Decimal::new(36, 1),
+        "mph" => value * Decimal::new(44704, 5),
+        "knots" => value * Decimal::new(514444, 6),
+        "in" => value * Decimal::new(254, 1),
+        _ => value,
+    }
+}
+
+/// Mirrors `StationReading`, without the time
+#[derive(Debug, Clone, PartialEq, Default)]
+struct StationReading {
+    temperature_celsius: Decimal,
+    humidity_percent: Option<i32>,
+    pressure_hpa: Option<i32>,
+    wind_speed_mps: Option<Decimal>,
+    wind_direction_deg: Option<i32>,
+    rain_1h_mm: Option<Decimal>,
+}
+
+/// Mirrors `read_payload`, without the time and with the error as None
+fn read_payload(payload: &Value, mapping: &[FieldMapping]) -> Option<StationReading> {
+    let mut reading = StationReading::default();
+    let mut temperature = None;
+    for entry in mapping {
+        let unit = entry.unit.as_deref().unwrap_or(units(entry.field)[0]);
+        let Some(raw) = lookup(payload, &entry.path).and_then(number) else {
+            continue;
+        };
+        if entry.field == "recorded_at" {
+            continue;
+        }
+        let metric = to_metric(raw, unit);
+        let within = |min: i32, max: i32| {
+            if metric < Decimal::from(min) || metric > Decimal::from(max) {
+                None
+            } else {
+                Some(metric)
+            }
+        };
+        match entry.field {
+            "temperature_celsius" => temperature = Some(within(-60, 70)?.round_dp(2)),
+            "humidity_percent" => reading.humidity_percent = within(0, 100)?.round().to_i32(),
+            "pressure_hpa" => reading.pressure_hpa = within(500, 1100)?.round().to_i32(),
+            "wind_speed_mps" => reading.wind_speed_mps = Some(within(0, 120)?.round_dp(2)),
+            "wind_direction_deg" => reading.wind_direction_deg = within(0, 360)?.round().to_i32(),
+            _ => reading.rain_1h_mm = Some(within(0, 500)?.round_dp(2)),
+        }
+    }
+    reading.temperature_celsius = temperature?;
+    Some(reading)
+}
+
+fn dec(value: &str) -> Decimal {
+    value.parse().unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_are_valid_mappings() {
+        assert_eq!(validate_mapping(&ecowitt_template()), Some(ecowitt_template()));
+        assert_eq!(validate_mapping(&davis_template()), Some(davis_template()));
+    }
+
+    #[test]
+    fn test_mapping_needs_temperature() {
+        assert_eq!(validate_mapping(&[]), None);
+        assert_eq!(validate_mapping(&[mapping("humidity_percent", "hum", None)]), None);
+    }
+
+    #[test]
+    fn test_mapping_rejects_repeated_fields_and_empty_paths() {
+        let repeated = [
+            mapping("temperature_celsius", "temp", None),
+            mapping("temperature_celsius", "temp2", None),
+        ];
+        assert_eq!(validate_mapping(&repeated), None);
+        assert_eq!(validate_mapping(&[mapping("temperature_celsius", "  ", None)]), None);
+        assert_eq!(validate_mapping(&[mapping("temperature_celsius", "data..temp", None)]), None);
+    }
+
+    #[test]
+    fn test_mapping_fills_default_units_and_rejects_unknown_ones() {
+        let checked = validate_mapping(&[mapping("temperature_celsius", " temp ", None)]).unwrap();
+        assert_eq!(checked, vec![mapping("temperature_celsius", "temp", Some("c"))]);
+        let upper = validate_mapping(&[mapping("temperature_celsius", "temp", Some("F"))]).unwrap();
+        assert_eq!(upper[0].unit.as_deref(), Some("f"));
+        assert_eq!(validate_mapping(&[mapping("temperature_celsius", "temp", Some("kelvin"))]), None);
+    }
+
+    #[test]
+    fn test_lookup_walks_objects_and_arrays() {
+        let payload = json!({"data": {"conditions": [{"temp": 71.2}, {}, {"bar_sea_level": 29.9}]}});
+        assert_eq!(lookup(&payload, "data.conditions.0.temp"), Some(&json!(71.2)));
+        assert_eq!(lookup(&payload, "data.conditions.2.bar_sea_level"), Some(&json!(29.9)));
+        assert_eq!(lookup(&payload, "data.conditions.5.temp"), None);
+        assert_eq!(lookup(&payload, "data.conditions.first"), None);
+        assert_eq!(lookup(&payload, "data.missing"), None);
+    }
+
+    #[test]
+    fn test_conversions_to_metric() {
+        assert_eq!(to_metric(dec("212"), "f"), dec("100"));
+        assert_eq!(to_metric(dec("32"), "f"), Decimal::ZERO);
+        assert_eq!(to_metric(dec("36"), "kmh"), dec("10"));
+        assert_eq!(to_metric(dec("1"), "in"), dec("25.4"));
+        assert_eq!(to_metric(dec("10"), "mph"), dec("4.4704"));
+        assert_eq!(to_metric(dec("29.92"), "inhg").round(), dec("1013"));
+        assert_eq!(to_metric(dec("760"), "mmhg").round(), dec("1013"));
+        assert_eq!(to_metric(dec("5"), "mps"), dec("5"));
+    }
+
+    #[test]
+    fn test_ecowitt_form_upload() {
+        let payload = json!({
+            "PASSKEY": "ABC", "dateutc": "2024-12-20 03:15:00", "tempf": "77.0", "humidity": "82",
+            "baromrelin": "29.85", "windspeedmph": "4.5", "winddir": "212", "hourlyrainin": "0.12"
+        });
+        let reading = read_payload(&payload, &ecowitt_template()).unwrap();
+        assert_eq!(reading.temperature_celsius, dec("25"));
+        assert_eq!(reading.humidity_percent, Some(82));
+        assert_eq!(reading.pressure_hpa, Some(1011));
+        assert_eq!(reading.wind_speed_mps, Some(dec("2.01")));
+        assert_eq!(reading.wind_direction_deg, Some(212));
+        assert_eq!(reading.rain_1h_mm, Some(dec("3.05")));
+    }
+
+    #[test]
+    fn test_davis_upload_leaves_missing_fields_empty() {
+        let payload = json!({"data": {"ts": 1734664500, "conditions": [{"temp": 68.0, "hum": 75.3}]}});
+        let reading = read_payload(&payload, &davis_template()).unwrap();
+        assert_eq!(reading.temperature_celsius, dec("20"));
+        assert_eq!(reading.humidity_percent, Some(75));
+        assert_eq!(reading.pressure_hpa, None);
+        assert_eq!(reading.wind_speed_mps, None);
+        assert_eq!(reading.rain_1h_mm, None);
+    }
+
+    #[test]
+    fn test_upload_without_temperature_is_rejected() {
+        assert_eq!(read_payload(&json!({"humidity": "80"}), &ecowitt_template()), None);
+        assert_eq!(read_payload(&json!({"tempf": "warm"}), &ecowitt_template()), None);
+    }
+
+    #[test]
+    fn test_readings_out_of_range_are_rejected() {
+        assert_eq!(read_payload(&json!({"tempf": "200"}), &ecowitt_template()), None);
+        assert_eq!(read_payload(&json!({"tempf": "77", "humidity": "120"}), &ecowitt_template()), None);
+        assert_eq!(read_payload(&json!({"tempf": "77", "baromrelin": "1013"}), &ecowitt_template()), None);
+        assert_eq!(read_payload(&json!({"tempf": "77", "winddir": "-5"}), &ecowitt_template()), None);
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_fahrenheit_round_trips(tenths in -760i64..=1580) {
+        let celsius = Decimal::new(tenths, 1);
+        let fahrenheit = celsius * Decimal::from(9) / Decimal::from(5) + Decimal::from(32);
+        prop_assert_eq!(to_metric(fahrenheit, "f").round_dp(6), celsius);
+    }
+
+    #[test]
+    fn prop_conversions_keep_order(a in 0i64..100_000, b in 0i64..100_000, unit in 0usize..7) {
+        let unit = ["f", "inhg", "mmhg", "kmh", "mph", "knots", "in"][unit];
+        let (a, b) = (Decimal::new(a.min(b), 2), Decimal::new(a.max(b), 2));
+        prop_assert!(to_metric(a, unit) <= to_metric(b, unit));
+    }
+
+    #[test]
+    fn prop_in_range_celsius_is_read(hundredths in -6000i64..=7000) {
+        let celsius = Decimal::new(hundredths, 2);
+        let payload = json!({"t": celsius.to_string()});
+        let reading = read_payload(&payload, &[mapping("temperature_celsius", "t", Some("c"))]).unwrap();
+        prop_assert_eq!(reading.temperature_celsius, celsius);
+    }
+}
@@ -228,6 +228,102 @@ mod unit_tests {
         matches!(source, "openweathermap" | "manual" | "tmd")
     }
 
+    /// Test daily rainfall merge: gauge readings take precedence over API data
+    #[test]
+    fn test_merge_rainfall_prefers_manual() {
+        assert_eq!(merge_rainfall(Some(dec("4.2")), Some(dec("12.5"))), (Some(dec("12.5")), Some("manual")));
+        assert_eq!(merge_rainfall(Some(dec("4.2")), None), (Some(dec("4.2")), Some("api")));
+        assert_eq!(merge_rainfall(None, Some(dec("0"))), (Some(dec("0")), Some("manual")));
+        assert_eq!(merge_rainfall(None, None), (None, None));
+    }
+
+    fn merge_rainfall(api: Option<Decimal>, manual: Option<Decimal>) -> (Option<Decimal>, Option<&'static str>) {
+        match (manual, api) {
+            (Some(manual), _) => (Some(manual), Some("manual")),
+            (None, Some(api)) => (Some(api), Some("api")),
+            (None, None) => (None, None),
+        }
+    }
+
+    /// Test per-plot gauge totals are averaged across plots for the daily aggregate
+    #[test]
+    fn test_daily_manual_rainfall_average() {
+        // plot A: two readings (morning + evening), plot B: one reading
+        let readings = [("A", dec("5.0")), ("A", dec("7.5")), ("B", dec("20.0"))];
+        assert_eq!(daily_manual_rainfall(&readings), Some(dec("16.25")));
+        assert_eq!(daily_manual_rainfall(&[]), None);
+    }
+
+    fn daily_manual_rainfall(readings: &[(&str, Decimal)]) -> Option<Decimal> {
+        let mut per_plot: std::collections::BTreeMap<&str, Decimal> = std::collections::BTreeMap::new();
+        for (plot, mm) in readings {
+            *per_plot.entry(plot).or_insert(Decimal::ZERO) += *mm;
+        }
+        if per_plot.is_empty() {
+            return None;
+        }
+        Some(per_plot.values().sum::<Decimal>() / Decimal::from(per_plot.len()))
+    }
+
+    /// Test manual rain gauge reading bounds (0 - 500 mm)
+    #[test]
+    fn test_rainfall_reading_bounds() {
+        assert!(is_valid_rainfall(dec("0")));
+        assert!(is_valid_rainfall(dec("12.5")));
+        assert!(is_valid_rainfall(dec("500")));
+        assert!(!is_valid_rainfall(dec("-0.1")));
+        assert!(!is_valid_rainfall(dec("500.01")));
+    }
+
+    fn is_valid_rainfall(mm: Decimal) -> bool {
+        mm >= Decimal::ZERO && mm <= Decimal::from(500)
+    }
+
+    /// Test snooze duration resolution (days or explicit end, 1 - 120 days)
+    #[test]
+    fn test_resolve_snooze_until() {
+        let now: DateTime<Utc> = "2024-08-01T00:00:00Z".parse().unwrap();
+        let until: DateTime<Utc> = "2024-08-15T06:00:00Z".parse().unwrap();
+
+        assert_eq!(
+            resolve_snooze_until(now, Some(3), None),
+            Some("2024-08-04T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(resolve_snooze_until(now, None, Some(until)), Some(until));
+        assert_eq!(resolve_snooze_until(now, Some(0), None), None);
+        assert_eq!(resolve_snooze_until(now, Some(121), None), None);
+        assert_eq!(resolve_snooze_until(now, None, Some(now)), None);
+        assert_eq!(resolve_snooze_until(now, Some(3), Some(until)), None);
+        assert_eq!(resolve_snooze_until(now, None, None), None);
+    }
+
+    fn resolve_snooze_until(
+        now: DateTime<Utc>,
+        days: Option<i64>,
+        until: Option<DateTime<Utc>>,
+    ) -> Option<DateTime<Utc>> {
+        match (days, until) {
+            (Some(days), None) if (1..=120).contains(&days) => Some(now + chrono::Duration::days(days)),
+            (None, Some(until)) if until > now && until <= now + chrono::Duration::days(120) => Some(until),
+            _ => None,
+        }
+    }
+
+    /// Test that snoozed alerts unmute automatically when the snooze expires
+    #[test]
+    fn test_snoozed_alert_unmutes_automatically() {
+        let now: DateTime<Utc> = "2024-08-10T12:00:00Z".parse().unwrap();
+
+        assert!(!is_muted(None, now));
+        assert!(is_muted(Some("2024-08-11T00:00:00Z".parse().unwrap()), now));
+        assert!(!is_muted(Some(now), now));
+        assert!(!is_muted(Some("2024-08-09T00:00:00Z".parse().unwrap()), now));
+    }
+
+    fn is_muted(muted_until: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        muted_until.map(|until| until > now).unwrap_or(false)
+    }
+
     // Test data structures
     #[derive(Debug, Clone)]
     struct WeatherSnapshot {
@@ -0,0 +1,153 @@
+//! Weekly digest tests
+//!
+//! Tests for the weekly owner digest:
+//! - Weeks run Monday to Sunday
+//! - Last week's digest is due from 07:00 local time on Monday
+//! - Weeks with nothing to report are not sent
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use proptest::prelude::*;
+
+const DIGEST_HOUR: i64 = 7;
+const BANGKOK_OFFSET_MINUTES: i32 = 7 * 60;
+
+/// Mirrors `week_start`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Mirrors `digest_week`
+fn digest_week(now: DateTime<Utc>, utc_offset_minutes: i32) -> NaiveDate {
+    let local = now + Duration::minutes(utc_offset_minutes as i64) - Duration::hours(DIGEST_HOUR);
+    week_start(local.date_naive()) - Duration::days(7)
+}
+
+/// Mirrors the counts checked by `WeeklyDigest::is_empty`
+#[derive(Debug, Clone, Default)]
+struct DigestCounts {
+    harvest_count: i64,
+    batches_finished: i64,
+    roast_sessions: i64,
+    samples_cupped: i64,
+    warning_alerts: i64,
+    critical_alerts: i64,
+    expirations: usize,
+    orders_to_ship: i64,
+    batches_in_progress: i64,
+}
+
+impl DigestCounts {
+    fn is_empty(&self) -> bool {
+        self.harvest_count == 0
+            && self.batches_finished == 0
+            && self.roast_sessions == 0
+            && self.samples_cupped == 0
+            && self.warning_alerts == 0
+            && self.critical_alerts == 0
+            && self.expirations == 0
+            && self.orders_to_ship == 0
+            && self.batches_in_progress == 0
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    // ========================================================================
+    // Weeks
+    // ========================================================================
+
+    #[test]
+    fn test_week_start_is_monday() {
+        // 2024-06-10 is a Monday
+        assert_eq!(week_start(date(2024, 6, 10)), date(2024, 6, 10));
+        assert_eq!(week_start(date(2024, 6, 12)), date(2024, 6, 10));
+        assert_eq!(week_start(date(2024, 6, 16)), date(2024, 6, 10));
+        assert_eq!(week_start(date(2024, 6, 17)), date(2024, 6, 17));
+    }
+
+    #[test]
+    fn test_week_start_across_year_end() {
+        // 2025-01-01 is a Wednesday
+        assert_eq!(week_start(date(2025, 1, 1)), date(2024, 12, 30));
+    }
+
+    // ========================================================================
+    // Due week
+    // ========================================================================
+
+    #[test]
+    fn test_digest_due_from_seven_on_monday_bangkok() {
+        // Monday 2024-06-17 07:00 in Bangkok is 00:00 UTC
+        assert_eq!(digest_week(utc(2024, 6, 17, 0, 0), BANGKOK_OFFSET_MINUTES), date(2024, 6, 10));
+        // A minute earlier the week before last is still the latest due
+        assert_eq!(digest_week(utc(2024, 6, 16, 23, 59), BANGKOK_OFFSET_MINUTES), date(2024, 6, 3));
+    }
+
+    #[test]
+    fn test_digest_week_stays_until_next_monday() {
+        assert_eq!(digest_week(utc(2024, 6, 20, 12, 0), BANGKOK_OFFSET_MINUTES), date(2024, 6, 10));
+        assert_eq!(digest_week(utc(2024, 6, 23, 23, 0), BANGKOK_OFFSET_MINUTES), date(2024, 6, 10));
+    }
+
+    #[test]
+    fn test_digest_week_utc() {
+        assert_eq!(digest_week(utc(2024, 6, 17, 6, 59), 0), date(2024, 6, 3));
+        assert_eq!(digest_week(utc(2024, 6, 17, 7, 0), 0), date(2024, 6, 10));
+    }
+
+    // ========================================================================
+    // Empty digests
+    // ========================================================================
+
+    #[test]
+    fn test_quiet_week_is_empty() {
+        assert!(DigestCounts::default().is_empty());
+    }
+
+    #[test]
+    fn test_any_activity_or_pending_work_is_reported() {
+        let cases = [
+            DigestCounts { harvest_count: 1, ..Default::default() },
+            DigestCounts { roast_sessions: 1, ..Default::default() },
+            DigestCounts { samples_cupped: 1, ..Default::default() },
+            DigestCounts { critical_alerts: 1, ..Default::default() },
+            DigestCounts { expirations: 1, ..Default::default() },
+            DigestCounts { orders_to_ship: 1, ..Default::default() },
+            DigestCounts { batches_in_progress: 1, ..Default::default() },
+        ];
+        for counts in cases {
+            assert!(!counts.is_empty(), "{:?}", counts);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_week_start_is_monday_within_a_week(days in 0i64..40_000) {
+        let day = date(2000, 1, 1) + Duration::days(days);
+        let start = week_start(day);
+        prop_assert_eq!(start.weekday(), Weekday::Mon);
+        prop_assert!(start <= day && day - start < Duration::days(7));
+    }
+
+    #[test]
+    fn prop_digest_week_has_ended(minutes in 0i64..20_000_000, offset in -720i32..=840) {
+        let now = utc(2000, 1, 1, 0, 0) + Duration::minutes(minutes);
+        let week = digest_week(now, offset);
+        let local_today = (now + Duration::minutes(offset as i64)).date_naive();
+        prop_assert_eq!(week.weekday(), Weekday::Mon);
+        // The week is over locally and is at most two weeks back
+        prop_assert!(week + Duration::days(7) <= local_today);
+        prop_assert!(local_today - week < Duration::days(15));
+    }
+}
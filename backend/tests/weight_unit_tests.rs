@@ -0,0 +1,132 @@
+//! Weight unit tests
+//!
+//! Tests for entering weights in local units:
+//! - Unit codes as stored
+//! - Converting to kilograms with the precision weights are stored at
+//! - Kilograms a business may set for a unit
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+/// Mirrors `DEFAULT_WEIGHT_UNIT`
+const DEFAULT_WEIGHT_UNIT: &str = "kg";
+
+/// Mirrors `MAX_KG_PER_UNIT`
+const MAX_KG_PER_UNIT: Decimal = Decimal::from_parts(1000, 0, 0, false, 0);
+
+/// Default kilograms of the seeded units
+const SEEDED_UNITS: [(&str, &str); 4] = [("kg", "1"), ("lb", "0.453592"), ("tang", "12"), ("kasop", "50")];
+
+/// Mirrors `normalize_unit`
+fn normalize_unit(unit: Option<&str>) -> String {
+    match unit.map(str::trim) {
+        Some(unit) if !unit.is_empty() => unit.to_lowercase(),
+        _ => DEFAULT_WEIGHT_UNIT.to_string(),
+    }
+}
+
+/// Mirrors `convert_to_kg`
+fn convert_to_kg(value: Decimal, kg_per_unit: Decimal) -> Decimal {
+    (value * kg_per_unit).round_dp(3)
+}
+
+/// Mirrors the range check of `validate_kg_per_unit`
+fn kg_per_unit_is_valid(kg_per_unit: Decimal) -> bool {
+    kg_per_unit > Decimal::ZERO && kg_per_unit <= MAX_KG_PER_UNIT
+}
+
+fn seeded_kg_per_unit(code: &str) -> Decimal {
+    SEEDED_UNITS.iter().find(|(c, _)| *c == code).map(|(_, kg)| dec(kg)).unwrap()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_or_blank_unit_is_kilograms() {
+        assert_eq!(normalize_unit(None), "kg");
+        assert_eq!(normalize_unit(Some("")), "kg");
+        assert_eq!(normalize_unit(Some("   ")), "kg");
+    }
+
+    #[test]
+    fn test_unit_code_is_trimmed_and_lowercase() {
+        assert_eq!(normalize_unit(Some(" Tang ")), "tang");
+        assert_eq!(normalize_unit(Some("LB")), "lb");
+    }
+
+    #[test]
+    fn test_tang_and_kasop_to_kilograms() {
+        assert_eq!(convert_to_kg(dec("3.5"), seeded_kg_per_unit("tang")), dec("42.0"));
+        assert_eq!(convert_to_kg(dec("2"), seeded_kg_per_unit("kasop")), dec("100"));
+    }
+
+    #[test]
+    fn test_pounds_round_to_grams() {
+        assert_eq!(convert_to_kg(dec("100"), seeded_kg_per_unit("lb")), dec("45.359"));
+        assert_eq!(convert_to_kg(dec("1"), seeded_kg_per_unit("lb")), dec("0.454"));
+    }
+
+    #[test]
+    fn test_business_kilograms_for_a_local_unit() {
+        // A village weighing cherry in 15 kg tang
+        assert_eq!(convert_to_kg(dec("4"), dec("15")), dec("60"));
+    }
+
+    #[test]
+    fn test_kg_per_unit_bounds() {
+        assert!(!kg_per_unit_is_valid(Decimal::ZERO));
+        assert!(!kg_per_unit_is_valid(dec("-1")));
+        assert!(kg_per_unit_is_valid(dec("0.001")));
+        assert!(kg_per_unit_is_valid(MAX_KG_PER_UNIT));
+        assert!(!kg_per_unit_is_valid(dec("1000.5")));
+    }
+
+    #[test]
+    fn test_seeded_units_are_valid() {
+        for (code, kg) in SEEDED_UNITS {
+            assert_eq!(normalize_unit(Some(code)), code);
+            assert!(kg_per_unit_is_valid(dec(kg)), "{}", code);
+        }
+    }
+}
+
+// ============================================================================
+// Property Tests
+// ============================================================================
+
+proptest! {
+    /// Kilograms convert to themselves
+    #[test]
+    fn prop_kilograms_are_unchanged(grams in 1i64..10_000_000) {
+        let kg = Decimal::new(grams, 3);
+        prop_assert_eq!(convert_to_kg(kg, Decimal::ONE), kg);
+    }
+
+    /// A positive weight in a valid unit stays positive in kilograms once
+    /// it is at least a gram
+    #[test]
+    fn prop_positive_weight_stays_positive(value in 1i64..100_000, factor in 1i64..1_000_000) {
+        let kg = convert_to_kg(Decimal::new(value, 1), Decimal::new(factor, 3));
+        prop_assert!(kg > Decimal::ZERO);
+        prop_assert!(kg.scale() <= 3);
+    }
+
+    /// Converting is monotonic in the weight entered
+    #[test]
+    fn prop_more_units_weigh_more(a in 1i64..10_000, b in 1i64..10_000, code in 0usize..4) {
+        let factor = dec(SEEDED_UNITS[code].1);
+        let (low, high) = (a.min(b), a.max(b));
+        prop_assert!(convert_to_kg(Decimal::from(low), factor) <= convert_to_kg(Decimal::from(high), factor));
+    }
+}
@@ -0,0 +1,117 @@
+//! Calendar-aware date formatting
+//!
+//! Thai government paperwork uses Buddhist Era (B.E.) years, 543 years ahead
+//! of the Gregorian year. Each business picks a calendar and human-readable
+//! dates in PDFs, exports and notification texts are formatted with these
+//! helpers. API payloads and stored values stay ISO 8601 / Gregorian.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::types::Language;
+
+/// Years between the Gregorian and Buddhist Era calendars
+pub const BUDDHIST_ERA_OFFSET: i32 = 543;
+
+const MONTHS_EN: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+
+const MONTHS_TH: [&str; 12] = [
+    "มกราคม", "กุมภาพันธ์", "มีนาคม", "เมษายน", "พฤษภาคม", "มิถุนายน", "กรกฎาคม", "สิงหาคม",
+    "กันยายน", "ตุลาคม", "พฤศจิกายน", "ธันวาคม",
+];
+
+/// Calendar used for displayed dates
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarSystem {
+    #[default]
+    Gregorian,
+    /// Thai solar calendar with Buddhist Era years
+    Buddhist,
+}
+
+impl CalendarSystem {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CalendarSystem::Gregorian => "gregorian",
+            CalendarSystem::Buddhist => "buddhist",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "gregorian" => Some(CalendarSystem::Gregorian),
+            "buddhist" => Some(CalendarSystem::Buddhist),
+            _ => None,
+        }
+    }
+
+    /// Year of a date in this calendar
+    pub fn year(&self, date: NaiveDate) -> i32 {
+        self.year_of(date.year())
+    }
+
+    /// Convert a Gregorian year to this calendar
+    pub fn year_of(&self, gregorian_year: i32) -> i32 {
+        match self {
+            CalendarSystem::Gregorian => gregorian_year,
+            CalendarSystem::Buddhist => gregorian_year + BUDDHIST_ERA_OFFSET,
+        }
+    }
+}
+
+/// Short numeric date: `2024-11-23` (Gregorian, ISO) or `23/11/2567` (B.E.,
+/// as written on Thai forms)
+pub fn format_date(date: NaiveDate, calendar: CalendarSystem) -> String {
+    match calendar {
+        CalendarSystem::Gregorian => date.format("%Y-%m-%d").to_string(),
+        CalendarSystem::Buddhist => format!(
+            "{:02}/{:02}/{}",
+            date.day(),
+            date.month(),
+            calendar.year(date)
+        ),
+    }
+}
+
+/// Date with time of day: `2024-11-23 14:05` or `23/11/2567 14:05`
+pub fn format_datetime(datetime: NaiveDateTime, calendar: CalendarSystem) -> String {
+    format!(
+        "{} {}",
+        format_date(datetime.date(), calendar),
+        datetime.format("%H:%M")
+    )
+}
+
+/// Date with the month spelled out, e.g. `23 พฤศจิกายน 2567` or
+/// `23 November 2567 B.E.`
+pub fn format_date_long(date: NaiveDate, calendar: CalendarSystem, language: &Language) -> String {
+    let month = date.month0() as usize;
+    let year = calendar.year(date);
+    match (language, calendar) {
+        // Thai readers assume B.E.; mark Gregorian years explicitly
+        (Language::Thai, CalendarSystem::Buddhist) => {
+            format!("{} {} {}", date.day(), MONTHS_TH[month], year)
+        }
+        (Language::Thai, CalendarSystem::Gregorian) => {
+            format!("{} {} ค.ศ. {}", date.day(), MONTHS_TH[month], year)
+        }
+        (Language::English, CalendarSystem::Buddhist) => {
+            format!("{} {} {} B.E.", date.day(), MONTHS_EN[month], year)
+        }
+        (Language::English, CalendarSystem::Gregorian) => {
+            format!("{} {} {}", date.day(), MONTHS_EN[month], year)
+        }
+    }
+}
+
+/// Month label for period summaries: `2024-11` or `11/2567`
+pub fn format_month(date: NaiveDate, calendar: CalendarSystem) -> String {
+    match calendar {
+        CalendarSystem::Gregorian => date.format("%Y-%m").to_string(),
+        CalendarSystem::Buddhist => format!("{:02}/{}", date.month(), calendar.year(date)),
+    }
+}
@@ -0,0 +1,243 @@
+//! Flavor descriptors from the SCA coffee taster's flavor wheel
+//!
+//! The wheel has three tiers: nine categories (fruity, sweet, ...), groups
+//! within them (berry, brown sugar, ...) and specific descriptors
+//! (blueberry, honey, ...). A descriptor's code is its path of slugs, such
+//! as `fruity.berry.blueberry`, so the parent of any entry is its code
+//! without the last segment. Cupping samples store these codes instead of
+//! free-text notes so that descriptors can be counted and compared.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Language;
+
+/// One entry of the flavor wheel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlavorDescriptor {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub name_th: &'static str,
+}
+
+const fn d(code: &'static str, name: &'static str, name_th: &'static str) -> FlavorDescriptor {
+    FlavorDescriptor { code, name, name_th }
+}
+
+/// The whole wheel in wheel order, each entry after its parent
+pub const FLAVOR_WHEEL: &[FlavorDescriptor] = &[
+    d("floral", "Floral", "ดอกไม้"),
+    d("floral.black_tea", "Black Tea", "ชาดำ"),
+    d("floral.floral", "Floral", "ดอกไม้"),
+    d("floral.floral.chamomile", "Chamomile", "คาโมมายล์"),
+    d("floral.floral.rose", "Rose", "กุหลาบ"),
+    d("floral.floral.jasmine", "Jasmine", "มะลิ"),
+    d("fruity", "Fruity", "ผลไม้"),
+    d("fruity.berry", "Berry", "เบอร์รี"),
+    d("fruity.berry.blackberry", "Blackberry", "แบล็กเบอร์รี"),
+    d("fruity.berry.raspberry", "Raspberry", "ราสป์เบอร์รี"),
+    d("fruity.berry.blueberry", "Blueberry", "บลูเบอร์รี"),
+    d("fruity.berry.strawberry", "Strawberry", "สตรอว์เบอร์รี"),
+    d("fruity.dried_fruit", "Dried Fruit", "ผลไม้แห้ง"),
+    d("fruity.dried_fruit.raisin", "Raisin", "ลูกเกด"),
+    d("fruity.dried_fruit.prune", "Prune", "พรุน"),
+    d("fruity.other_fruit", "Other Fruit", "ผลไม้อื่น ๆ"),
+    d("fruity.other_fruit.coconut", "Coconut", "มะพร้าว"),
+    d("fruity.other_fruit.cherry", "Cherry", "เชอร์รี"),
+    d("fruity.other_fruit.pomegranate", "Pomegranate", "ทับทิม"),
+    d("fruity.other_fruit.pineapple", "Pineapple", "สับปะรด"),
+    d("fruity.other_fruit.grape", "Grape", "องุ่น"),
+    d("fruity.other_fruit.apple", "Apple", "แอปเปิล"),
+    d("fruity.other_fruit.peach", "Peach", "พีช"),
+    d("fruity.other_fruit.pear", "Pear", "ลูกแพร์"),
+    d("fruity.citrus_fruit", "Citrus Fruit", "ผลไม้ตระกูลส้ม"),
+    d("fruity.citrus_fruit.grapefruit", "Grapefruit", "เกรปฟรุต"),
+    d("fruity.citrus_fruit.orange", "Orange", "ส้ม"),
+    d("fruity.citrus_fruit.lemon", "Lemon", "เลมอน"),
+    d("fruity.citrus_fruit.lime", "Lime", "มะนาว"),
+    d("sour_fermented", "Sour/Fermented", "เปรี้ยว/หมัก"),
+    d("sour_fermented.sour", "Sour", "เปรี้ยว"),
+    d("sour_fermented.sour.sour_aromatics", "Sour Aromatics", "กลิ่นเปรี้ยว"),
+    d("sour_fermented.sour.acetic_acid", "Acetic Acid", "กรดอะซิติก"),
+    d("sour_fermented.sour.butyric_acid", "Butyric Acid", "กรดบิวทิริก"),
+    d("sour_fermented.sour.isovaleric_acid", "Isovaleric Acid", "กรดไอโซวาเลอริก"),
+    d("sour_fermented.sour.citric_acid", "Citric Acid", "กรดซิตริก"),
+    d("sour_fermented.sour.malic_acid", "Malic Acid", "กรดมาลิก"),
+    d("sour_fermented.alcohol_fermented", "Alcohol/Fermented", "แอลกอฮอล์/หมัก"),
+    d("sour_fermented.alcohol_fermented.winey", "Winey", "ไวน์"),
+    d("sour_fermented.alcohol_fermented.whiskey", "Whiskey", "วิสกี้"),
+    d("sour_fermented.alcohol_fermented.fermented", "Fermented", "หมัก"),
+    d("sour_fermented.alcohol_fermented.overripe", "Overripe", "สุกงอม"),
+    d("green_vegetative", "Green/Vegetative", "เขียว/พืชผัก"),
+    d("green_vegetative.olive_oil", "Olive Oil", "น้ำมันมะกอก"),
+    d("green_vegetative.raw", "Raw", "ดิบ"),
+    d("green_vegetative.green_vegetative", "Green/Vegetative", "เขียว/พืชผัก"),
+    d("green_vegetative.green_vegetative.under_ripe", "Under-ripe", "ยังไม่สุก"),
+    d("green_vegetative.green_vegetative.peapod", "Peapod", "ฝักถั่ว"),
+    d("green_vegetative.green_vegetative.fresh", "Fresh", "สดชื่น"),
+    d("green_vegetative.green_vegetative.dark_green", "Dark Green", "ผักใบเขียวเข้ม"),
+    d("green_vegetative.green_vegetative.vegetative", "Vegetative", "พืชผัก"),
+    d("green_vegetative.green_vegetative.hay_like", "Hay-like", "ฟาง"),
+    d("green_vegetative.green_vegetative.herb_like", "Herb-like", "สมุนไพร"),
+    d("green_vegetative.beany", "Beany", "ถั่วดิบ"),
+    d("other", "Other", "อื่น ๆ"),
+    d("other.papery_musty", "Papery/Musty", "กระดาษ/อับ"),
+    d("other.papery_musty.stale", "Stale", "เก่าค้าง"),
+    d("other.papery_musty.cardboard", "Cardboard", "กระดาษลัง"),
+    d("other.papery_musty.papery", "Papery", "กระดาษ"),
+    d("other.papery_musty.woody", "Woody", "ไม้"),
+    d("other.papery_musty.moldy_damp", "Moldy/Damp", "ราชื้น"),
+    d("other.papery_musty.musty_dusty", "Musty/Dusty", "อับฝุ่น"),
+    d("other.papery_musty.musty_earthy", "Musty/Earthy", "อับดิน"),
+    d("other.papery_musty.animalic", "Animalic", "กลิ่นสัตว์"),
+    d("other.papery_musty.meaty_brothy", "Meaty Brothy", "เนื้อ/น้ำซุป"),
+    d("other.papery_musty.phenolic", "Phenolic", "ฟีนอล"),
+    d("other.chemical", "Chemical", "สารเคมี"),
+    d("other.chemical.bitter", "Bitter", "ขม"),
+    d("other.chemical.salty", "Salty", "เค็ม"),
+    d("other.chemical.medicinal", "Medicinal", "ยา"),
+    d("other.chemical.petroleum", "Petroleum", "ปิโตรเลียม"),
+    d("other.chemical.skunky", "Skunky", "กลิ่นสกังก์"),
+    d("other.chemical.rubber", "Rubber", "ยาง"),
+    d("roasted", "Roasted", "คั่ว"),
+    d("roasted.pipe_tobacco", "Pipe Tobacco", "ยาเส้นไปป์"),
+    d("roasted.tobacco", "Tobacco", "ยาสูบ"),
+    d("roasted.burnt", "Burnt", "ไหม้"),
+    d("roasted.burnt.acrid", "Acrid", "ฉุนไหม้"),
+    d("roasted.burnt.ashy", "Ashy", "ขี้เถ้า"),
+    d("roasted.burnt.smoky", "Smoky", "ควัน"),
+    d("roasted.burnt.brown_roast", "Brown, Roast", "คั่วเข้ม"),
+    d("roasted.cereal", "Cereal", "ธัญพืช"),
+    d("roasted.cereal.grain", "Grain", "เมล็ดธัญพืช"),
+    d("roasted.cereal.malt", "Malt", "มอลต์"),
+    d("spices", "Spices", "เครื่องเทศ"),
+    d("spices.pungent", "Pungent", "ฉุน"),
+    d("spices.pepper", "Pepper", "พริกไทย"),
+    d("spices.brown_spice", "Brown Spice", "เครื่องเทศสีน้ำตาล"),
+    d("spices.brown_spice.anise", "Anise", "เทียนสัตตบุษย์"),
+    d("spices.brown_spice.nutmeg", "Nutmeg", "ลูกจันทน์เทศ"),
+    d("spices.brown_spice.cinnamon", "Cinnamon", "อบเชย"),
+    d("spices.brown_spice.clove", "Clove", "กานพลู"),
+    d("nutty_cocoa", "Nutty/Cocoa", "ถั่ว/โกโก้"),
+    d("nutty_cocoa.nutty", "Nutty", "ถั่ว"),
+    d("nutty_cocoa.nutty.peanuts", "Peanuts", "ถั่วลิสง"),
+    d("nutty_cocoa.nutty.hazelnut", "Hazelnut", "เฮเซลนัท"),
+    d("nutty_cocoa.nutty.almond", "Almond", "อัลมอนด์"),
+    d("nutty_cocoa.cocoa", "Cocoa", "โกโก้"),
+    d("nutty_cocoa.cocoa.chocolate", "Chocolate", "ช็อกโกแลต"),
+    d("nutty_cocoa.cocoa.dark_chocolate", "Dark Chocolate", "ดาร์กช็อกโกแลต"),
+    d("sweet", "Sweet", "หวาน"),
+    d("sweet.brown_sugar", "Brown Sugar", "น้ำตาลทรายแดง"),
+    d("sweet.brown_sugar.molasses", "Molasses", "กากน้ำตาล"),
+    d("sweet.brown_sugar.maple_syrup", "Maple Syrup", "เมเปิลไซรัป"),
+    d("sweet.brown_sugar.caramelized", "Caramelized", "คาราเมล"),
+    d("sweet.brown_sugar.honey", "Honey", "น้ำผึ้ง"),
+    d("sweet.vanilla", "Vanilla", "วานิลลา"),
+    d("sweet.vanillin", "Vanillin", "วานิลลิน"),
+    d("sweet.overall_sweet", "Overall Sweet", "หวานโดยรวม"),
+    d("sweet.sweet_aromatics", "Sweet Aromatics", "กลิ่นหวาน"),
+];
+
+impl FlavorDescriptor {
+    /// 1 for a category, 2 for a group, 3 for a specific descriptor
+    pub fn level(&self) -> usize {
+        self.code.split('.').count()
+    }
+
+    /// Entry one tier closer to the center of the wheel
+    pub fn parent(&self) -> Option<&'static FlavorDescriptor> {
+        let (parent_code, _) = self.code.rsplit_once('.')?;
+        flavor_descriptor(parent_code)
+    }
+
+    /// Entries from the category down to this one
+    pub fn path(&self) -> Vec<&'static FlavorDescriptor> {
+        let mut path: Vec<&'static FlavorDescriptor> = Vec::new();
+        let mut current = flavor_descriptor(self.code);
+        while let Some(entry) = current {
+            path.push(entry);
+            current = entry.parent();
+        }
+        path.reverse();
+        path
+    }
+
+    /// Entries one tier further out
+    pub fn children(&self) -> Vec<&'static FlavorDescriptor> {
+        FLAVOR_WHEEL
+            .iter()
+            .filter(|entry| entry.parent().is_some_and(|parent| parent.code == self.code))
+            .collect()
+    }
+
+    pub fn name_in(&self, language: &Language) -> &'static str {
+        match language {
+            Language::Thai => self.name_th,
+            Language::English => self.name,
+        }
+    }
+
+    /// Serializable form with the path names for display
+    pub fn entry(&self) -> FlavorDescriptorEntry {
+        let path = self.path();
+        FlavorDescriptorEntry {
+            code: self.code.to_string(),
+            name: self.name.to_string(),
+            name_th: self.name_th.to_string(),
+            level: self.level() as u8,
+            path: path.iter().map(|entry| entry.name.to_string()).collect(),
+            path_th: path.iter().map(|entry| entry.name_th.to_string()).collect(),
+        }
+    }
+}
+
+/// A flavor wheel entry as sent to clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlavorDescriptorEntry {
+    pub code: String,
+    pub name: String,
+    pub name_th: String,
+    /// 1 category, 2 group, 3 specific descriptor
+    pub level: u8,
+    /// Names from the category down to this entry
+    pub path: Vec<String>,
+    pub path_th: Vec<String>,
+}
+
+/// Look up a wheel entry by code
+pub fn flavor_descriptor(code: &str) -> Option<&'static FlavorDescriptor> {
+    FLAVOR_WHEEL.iter().find(|entry| entry.code == code)
+}
+
+/// Wheel entries matching what a cupper has typed, in English or Thai.
+/// Names starting with the query come first, then names with a word
+/// starting with it, then any other match; ties keep wheel order
+pub fn search_flavor_descriptors(query: &str, limit: usize) -> Vec<&'static FlavorDescriptor> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return FLAVOR_WHEEL.iter().take(limit).collect();
+    }
+
+    let rank = |entry: &FlavorDescriptor| -> Option<u8> {
+        let name = entry.name.to_lowercase();
+        if name.starts_with(&query) || entry.name_th.starts_with(&query) {
+            Some(0)
+        } else if name
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word.starts_with(&query))
+        {
+            Some(1)
+        } else if name.contains(&query) || entry.name_th.contains(&query) || entry.code.contains(&query) {
+            Some(2)
+        } else {
+            None
+        }
+    };
+
+    let mut matches: Vec<(u8, usize, &'static FlavorDescriptor)> = FLAVOR_WHEEL
+        .iter()
+        .enumerate()
+        .filter_map(|(position, entry)| rank(entry).map(|r| (r, position, entry)))
+        .collect();
+    matches.sort_by_key(|(r, position, _)| (*r, *position));
+    matches.into_iter().take(limit).map(|(_, _, entry)| entry).collect()
+}
@@ -3,10 +3,16 @@
 //! This crate contains types shared between the backend, frontend (via WASM),
 //! and other components of the system.
 
+pub mod calendar;
+pub mod flavor;
+pub mod locale;
 pub mod models;
 pub mod types;
 pub mod validation;
 
+pub use calendar::*;
+pub use flavor::*;
+pub use locale::*;
 pub use models::*;
 pub use types::*;
 pub use validation::*;
@@ -0,0 +1,152 @@
+//! Locale-aware number formatting
+//!
+//! Numbers in generated documents and notification texts get thousands
+//! separators and a fixed number of decimals, and businesses may opt into
+//! Thai digits (๐-๙) for Thai-language output. The backend and the WASM
+//! frontend both format through these helpers so they render identically.
+
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::{format_date, format_date_long, format_month, CalendarSystem};
+use crate::types::Language;
+
+/// Digits used for displayed numbers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DigitSystem {
+    /// 0-9
+    #[default]
+    Arabic,
+    /// ๐-๙, used on some official Thai documents
+    Thai,
+}
+
+impl DigitSystem {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DigitSystem::Arabic => "arabic",
+            DigitSystem::Thai => "thai",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "arabic" => Some(DigitSystem::Arabic),
+            "thai" => Some(DigitSystem::Thai),
+            _ => None,
+        }
+    }
+}
+
+/// Replace ASCII digits with Thai digits
+pub fn to_thai_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) if c.is_ascii_digit() => char::from_u32(0x0E50 + d).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Write ASCII digits in the given digit system
+pub fn localize_digits(text: &str, digits: DigitSystem) -> String {
+    match digits {
+        DigitSystem::Arabic => text.to_string(),
+        DigitSystem::Thai => to_thai_digits(text),
+    }
+}
+
+/// Group the integer part of a plain decimal string with commas
+fn group_thousands(plain: &str) -> String {
+    let (sign, unsigned) = match plain.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", plain),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// Decimal with thousands separators and exactly `places` decimals, rounded
+/// half away from zero: `12,345.68`
+pub fn format_decimal(value: Decimal, places: u32, digits: DigitSystem) -> String {
+    let rounded = value.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+    // Avoid "-0.00" for small negatives that round to zero
+    let rounded = if rounded.is_zero() { Decimal::ZERO } else { rounded };
+    let plain = format!("{:.*}", places as usize, rounded);
+    localize_digits(&group_thousands(&plain), digits)
+}
+
+/// Integer with thousands separators: `1,250`
+pub fn format_integer(value: i64, digits: DigitSystem) -> String {
+    localize_digits(&group_thousands(&value.to_string()), digits)
+}
+
+/// Percentage given in percent units (82.5 = 82.5%): `82.5%`
+pub fn format_percent(value: Decimal, places: u32, digits: DigitSystem) -> String {
+    format!("{}%", format_decimal(value, places, digits))
+}
+
+/// A business's display preferences for dates and numbers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct DisplayFormat {
+    pub calendar: CalendarSystem,
+    pub digits: DigitSystem,
+}
+
+impl DisplayFormat {
+    pub fn new(calendar: CalendarSystem, digits: DigitSystem) -> Self {
+        Self { calendar, digits }
+    }
+
+    /// Format for text in `language`; Thai digits are only used in Thai text
+    pub fn for_language(&self, language: &Language) -> Self {
+        match language {
+            Language::Thai => *self,
+            Language::English => Self {
+                digits: DigitSystem::Arabic,
+                ..*self
+            },
+        }
+    }
+
+    pub fn date(&self, date: NaiveDate) -> String {
+        localize_digits(&format_date(date, self.calendar), self.digits)
+    }
+
+    pub fn date_long(&self, date: NaiveDate, language: &Language) -> String {
+        localize_digits(&format_date_long(date, self.calendar, language), self.digits)
+    }
+
+    pub fn month(&self, date: NaiveDate) -> String {
+        localize_digits(&format_month(date, self.calendar), self.digits)
+    }
+
+    pub fn decimal(&self, value: Decimal, places: u32) -> String {
+        format_decimal(value, places, self.digits)
+    }
+
+    pub fn integer(&self, value: i64) -> String {
+        format_integer(value, self.digits)
+    }
+
+    pub fn percent(&self, value: Decimal, places: u32) -> String {
+        format_percent(value, places, self.digits)
+    }
+}
@@ -32,7 +32,7 @@ pub struct CuppingSample {
 /// SCA Cupping Protocol Scores
 /// Each attribute is scored on a 6.0-10.0 scale with 0.25 increments
 /// Uniformity, Clean Cup, and Sweetness are scored 0-10 (2 points per cup)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CuppingScores {
     pub fragrance_aroma: Decimal,
     pub flavor: Decimal,
@@ -35,18 +35,167 @@ pub struct DefectCount {
 }
 
 impl DefectCount {
+    /// Counts whose category totals are the breakdown's full defect
+    /// equivalents
+    pub fn from_breakdown(breakdown: DefectBreakdown) -> Self {
+        DefectCount {
+            category1_count: breakdown.full_defects(DefectCategory::Primary),
+            category2_count: breakdown.full_defects(DefectCategory::Secondary),
+            defect_breakdown: Some(breakdown),
+        }
+    }
+
+    /// Category 1 full defects: from the breakdown when there is one, else
+    /// the count entered
+    pub fn category1_defects(&self) -> i32 {
+        match &self.defect_breakdown {
+            Some(breakdown) => breakdown.full_defects(DefectCategory::Primary),
+            None => self.category1_count,
+        }
+    }
+
+    /// Category 2 full defects: from the breakdown when there is one, else
+    /// the count entered
+    pub fn category2_defects(&self) -> i32 {
+        match &self.defect_breakdown {
+            Some(breakdown) => breakdown.full_defects(DefectCategory::Secondary),
+            None => self.category2_count,
+        }
+    }
+
     pub fn total(&self) -> i32 {
-        self.category1_count + self.category2_count
+        self.category1_defects() + self.category2_defects()
     }
 }
 
-/// Detailed defect breakdown by type
+/// SCA defect category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefectCategory {
+    /// Category 1 (primary) defects
+    Primary,
+    /// Category 2 (secondary) defects
+    Secondary,
+}
+
+/// Occurrences of a defect that make up a number of full defects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DefectEquivalent {
+    pub occurrences: i32,
+    pub full_defects: i32,
+}
+
+/// Defect types counted in a green sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefectType {
+    FullBlack,
+    FullSour,
+    PodCherry,
+    FungusDamaged,
+    ForeignMatter,
+    SevereInsectDamage,
+    LargeStones,
+    MediumStones,
+    LargeSticks,
+    MediumSticks,
+    PartialBlack,
+    PartialSour,
+    Parchment,
+    Floater,
+    Immature,
+    Withered,
+    Shell,
+    Broken,
+    Chipped,
+    Cut,
+    InsectDamage,
+    Husk,
+}
+
+impl DefectType {
+    pub const ALL: [DefectType; 22] = [
+        DefectType::FullBlack,
+        DefectType::FullSour,
+        DefectType::PodCherry,
+        DefectType::FungusDamaged,
+        DefectType::ForeignMatter,
+        DefectType::SevereInsectDamage,
+        DefectType::LargeStones,
+        DefectType::MediumStones,
+        DefectType::LargeSticks,
+        DefectType::MediumSticks,
+        DefectType::PartialBlack,
+        DefectType::PartialSour,
+        DefectType::Parchment,
+        DefectType::Floater,
+        DefectType::Immature,
+        DefectType::Withered,
+        DefectType::Shell,
+        DefectType::Broken,
+        DefectType::Chipped,
+        DefectType::Cut,
+        DefectType::InsectDamage,
+        DefectType::Husk,
+    ];
+
+    pub fn category(&self) -> DefectCategory {
+        match self {
+            DefectType::FullBlack
+            | DefectType::FullSour
+            | DefectType::PodCherry
+            | DefectType::FungusDamaged
+            | DefectType::ForeignMatter
+            | DefectType::SevereInsectDamage
+            | DefectType::LargeStones
+            | DefectType::MediumStones
+            | DefectType::LargeSticks
+            | DefectType::MediumSticks => DefectCategory::Primary,
+            _ => DefectCategory::Secondary,
+        }
+    }
+
+    /// SCA full defect equivalent: e.g. 3 partial blacks or 5 broken beans
+    /// make one full defect, a large stone two
+    pub fn equivalent(&self) -> DefectEquivalent {
+        let (occurrences, full_defects) = match self {
+            DefectType::FullBlack
+            | DefectType::FullSour
+            | DefectType::PodCherry
+            | DefectType::FungusDamaged
+            | DefectType::ForeignMatter
+            | DefectType::MediumStones
+            | DefectType::MediumSticks => (1, 1),
+            DefectType::LargeStones | DefectType::LargeSticks => (1, 2),
+            DefectType::PartialBlack | DefectType::PartialSour => (3, 1),
+            DefectType::InsectDamage => (10, 1),
+            _ => (5, 1),
+        };
+        DefectEquivalent {
+            occurrences,
+            full_defects,
+        }
+    }
+
+    /// Full defects `occurrences` of this defect count as, rounded down
+    pub fn full_defects(&self, occurrences: i32) -> i32 {
+        let equivalent = self.equivalent();
+        occurrences.max(0) / equivalent.occurrences * equivalent.full_defects
+    }
+}
+
+/// Detailed defect breakdown by type: occurrences counted in the sample
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct DefectBreakdown {
     // Category 1 (Primary) Defects
     pub full_black: i32,
     pub full_sour: i32,
     pub pod_cherry: i32,
+    pub fungus_damaged: i32,
+    pub foreign_matter: i32,
+    /// Beans with three or more insect (broca) holes
+    pub severe_insect_damage: i32,
     pub large_stones: i32,
     pub medium_stones: i32,
     pub large_sticks: i32,
@@ -62,10 +211,56 @@ pub struct DefectBreakdown {
     pub broken: i32,
     pub chipped: i32,
     pub cut: i32,
+    /// Beans with one or two insect (broca) holes
+    #[serde(alias = "broca")]
     pub insect_damage: i32,
     pub husk: i32,
 }
 
+impl DefectBreakdown {
+    /// Occurrences of a defect type
+    pub fn count(&self, defect: DefectType) -> i32 {
+        match defect {
+            DefectType::FullBlack => self.full_black,
+            DefectType::FullSour => self.full_sour,
+            DefectType::PodCherry => self.pod_cherry,
+            DefectType::FungusDamaged => self.fungus_damaged,
+            DefectType::ForeignMatter => self.foreign_matter,
+            DefectType::SevereInsectDamage => self.severe_insect_damage,
+            DefectType::LargeStones => self.large_stones,
+            DefectType::MediumStones => self.medium_stones,
+            DefectType::LargeSticks => self.large_sticks,
+            DefectType::MediumSticks => self.medium_sticks,
+            DefectType::PartialBlack => self.partial_black,
+            DefectType::PartialSour => self.partial_sour,
+            DefectType::Parchment => self.parchment,
+            DefectType::Floater => self.floater,
+            DefectType::Immature => self.immature,
+            DefectType::Withered => self.withered,
+            DefectType::Shell => self.shell,
+            DefectType::Broken => self.broken,
+            DefectType::Chipped => self.chipped,
+            DefectType::Cut => self.cut,
+            DefectType::InsectDamage => self.insect_damage,
+            DefectType::Husk => self.husk,
+        }
+    }
+
+    /// Full defects of a category, each defect type rounded down on its own
+    pub fn full_defects(&self, category: DefectCategory) -> i32 {
+        DefectType::ALL
+            .iter()
+            .filter(|defect| defect.category() == category)
+            .map(|defect| defect.full_defects(self.count(*defect)))
+            .sum()
+    }
+
+    /// First defect type with a negative count
+    pub fn negative_count(&self) -> Option<DefectType> {
+        DefectType::ALL.into_iter().find(|defect| self.count(*defect) < 0)
+    }
+}
+
 /// AI defect detection result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiDefectDetection {
@@ -90,6 +285,78 @@ pub struct ScreenSizeDistribution {
     pub screen_14_below: Decimal,
 }
 
+impl ScreenSizeDistribution {
+    /// Percentage of the sample retained on screen `size` or larger. The
+    /// outer bands are open-ended: sizes above 18 count the 18+ band and
+    /// sizes of 14 or below cover the whole sample
+    pub fn share_at_or_above(&self, size: u8) -> Decimal {
+        let size = size.min(18);
+        let bands = [
+            (18, self.screen_18_plus),
+            (17, self.screen_17),
+            (16, self.screen_16),
+            (15, self.screen_15),
+            (14, self.screen_14_below),
+        ];
+        bands
+            .iter()
+            .take_while(|(band, _)| *band >= size)
+            .map(|(_, share)| *share)
+            .sum()
+    }
+}
+
+/// Percent of a green sample retained on each screen from 19 down to 13;
+/// what passes screen 13 falls to the pan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenAnalysis {
+    pub screen_19: Decimal,
+    pub screen_18: Decimal,
+    pub screen_17: Decimal,
+    pub screen_16: Decimal,
+    pub screen_15: Decimal,
+    pub screen_14: Decimal,
+    pub screen_13: Decimal,
+}
+
+impl ScreenAnalysis {
+    /// Percent retained per screen, largest screen first
+    pub fn screens(&self) -> [(u8, Decimal); 7] {
+        [
+            (19, self.screen_19),
+            (18, self.screen_18),
+            (17, self.screen_17),
+            (16, self.screen_16),
+            (15, self.screen_15),
+            (14, self.screen_14),
+            (13, self.screen_13),
+        ]
+    }
+
+    /// Percent retained on all screens together
+    pub fn retained_percent(&self) -> Decimal {
+        self.screens().iter().map(|(_, share)| *share).sum()
+    }
+
+    /// Percent passing screen 13
+    pub fn pan_percent(&self) -> Decimal {
+        (Decimal::ONE_HUNDRED - self.retained_percent()).max(Decimal::ZERO)
+    }
+
+    /// The analysis in the bands of a screen size distribution: 19 joins
+    /// 18+, and 13 and the pan join 14 and below
+    pub fn distribution(&self) -> ScreenSizeDistribution {
+        ScreenSizeDistribution {
+            screen_18_plus: self.screen_19 + self.screen_18,
+            screen_17: self.screen_17,
+            screen_16: self.screen_16,
+            screen_15: self.screen_15,
+            screen_14_below: self.screen_14 + self.screen_13 + self.pan_percent(),
+        }
+    }
+}
+
 /// SCA grade classification
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -118,14 +385,82 @@ impl std::fmt::Display for GradeClassification {
     }
 }
 
-/// Classify grade based on defect counts (SCA rules)
-pub fn classify_grade(defects: &DefectCount) -> GradeClassification {
-    let total = defects.total();
-    match (defects.category1_count, total) {
-        (0, 0..=5) => GradeClassification::SpecialtyGrade,
-        (_, 0..=8) => GradeClassification::PremiumGrade,
-        (_, 9..=23) => GradeClassification::ExchangeGrade,
-        (_, 24..=86) => GradeClassification::BelowStandard,
-        _ => GradeClassification::OffGrade,
+impl GradeClassification {
+    /// Grades from best to worst
+    pub const ALL: [GradeClassification; 5] = [
+        GradeClassification::SpecialtyGrade,
+        GradeClassification::PremiumGrade,
+        GradeClassification::ExchangeGrade,
+        GradeClassification::BelowStandard,
+        GradeClassification::OffGrade,
+    ];
+}
+
+/// Limits a sample must stay within to reach a grade under a grading
+/// standard; limits left out are not checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradeThreshold {
+    pub grade: GradeClassification,
+    /// Most full defects, both categories together
+    pub max_total_defects: i32,
+    pub max_category1_defects: Option<i32>,
+    pub min_moisture_percent: Option<Decimal>,
+    pub max_moisture_percent: Option<Decimal>,
+}
+
+impl GradeThreshold {
+    /// Whether a sample reaches the grade; moisture limits only apply when
+    /// the moisture was measured
+    pub fn admits(&self, defects: &DefectCount, moisture_percent: Option<Decimal>) -> bool {
+        defects.total() <= self.max_total_defects
+            && self
+                .max_category1_defects
+                .is_none_or(|max| defects.category1_defects() <= max)
+            && moisture_percent.is_none_or(|moisture| {
+                self.min_moisture_percent.is_none_or(|min| moisture >= min)
+                    && self.max_moisture_percent.is_none_or(|max| moisture <= max)
+            })
     }
 }
+
+/// Thresholds of the SCA green grading rules: specialty up to 5 full
+/// defects and no category 1, premium up to 8, exchange up to 23 and below
+/// standard up to 86
+pub fn sca_thresholds() -> Vec<GradeThreshold> {
+    [
+        (GradeClassification::SpecialtyGrade, 5, Some(0)),
+        (GradeClassification::PremiumGrade, 8, None),
+        (GradeClassification::ExchangeGrade, 23, None),
+        (GradeClassification::BelowStandard, 86, None),
+    ]
+    .into_iter()
+    .map(|(grade, max_total_defects, max_category1_defects)| GradeThreshold {
+        grade,
+        max_total_defects,
+        max_category1_defects,
+        min_moisture_percent: None,
+        max_moisture_percent: None,
+    })
+    .collect()
+}
+
+/// Classify a sample under a grading standard: the best grade whose
+/// thresholds it meets, off grade when it meets none
+pub fn classify_grade_with(
+    defects: &DefectCount,
+    moisture_percent: Option<Decimal>,
+    thresholds: &[GradeThreshold],
+) -> GradeClassification {
+    GradeClassification::ALL
+        .iter()
+        .filter_map(|grade| thresholds.iter().find(|t| t.grade == *grade))
+        .find(|t| t.admits(defects, moisture_percent))
+        .map(|t| t.grade.clone())
+        .unwrap_or(GradeClassification::OffGrade)
+}
+
+/// Classify grade based on full defects (SCA rules); a breakdown's full
+/// defect equivalents take the place of the category counts
+pub fn classify_grade(defects: &DefectCount) -> GradeClassification {
+    classify_grade_with(defects, None, &sca_thresholds())
+}
@@ -5,6 +5,8 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::types::Language;
+
 /// A coffee lot tracked through the supply chain
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Lot {
@@ -22,8 +24,9 @@ pub struct Lot {
     pub updated_at: DateTime<Utc>,
 }
 
-/// Stage of a lot in the supply chain
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Stage of a lot in the supply chain, in the order lots pass through them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type), sqlx(type_name = "VARCHAR", rename_all = "snake_case"))]
 #[serde(rename_all = "snake_case")]
 pub enum LotStage {
     Cherry,
@@ -33,16 +36,71 @@ pub enum LotStage {
     Sold,
 }
 
-impl std::fmt::Display for LotStage {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl LotStage {
+    pub const ALL: [LotStage; 5] = [
+        LotStage::Cherry,
+        LotStage::Parchment,
+        LotStage::GreenBean,
+        LotStage::RoastedBean,
+        LotStage::Sold,
+    ];
+
+    /// Stored and serialized name, such as "green_bean"
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LotStage::Cherry => "cherry",
+            LotStage::Parchment => "parchment",
+            LotStage::GreenBean => "green_bean",
+            LotStage::RoastedBean => "roasted_bean",
+            LotStage::Sold => "sold",
+        }
+    }
+
+    /// Stage by its stored name
+    pub fn from_name(name: &str) -> Option<Self> {
+        LotStage::ALL.into_iter().find(|stage| stage.as_str() == name)
+    }
+
+    pub fn name_in(&self, language: &Language) -> &'static str {
+        match (language, self) {
+            (Language::English, LotStage::Cherry) => "Cherry",
+            (Language::English, LotStage::Parchment) => "Parchment",
+            (Language::English, LotStage::GreenBean) => "Green Bean",
+            (Language::English, LotStage::RoastedBean) => "Roasted Bean",
+            (Language::English, LotStage::Sold) => "Sold",
+            (Language::Thai, LotStage::Cherry) => "เชอร์รี่",
+            (Language::Thai, LotStage::Parchment) => "กะลา",
+            (Language::Thai, LotStage::GreenBean) => "สารกาแฟ",
+            (Language::Thai, LotStage::RoastedBean) => "เมล็ดคั่ว",
+            (Language::Thai, LotStage::Sold) => "ขายแล้ว",
+        }
+    }
+
+    /// Stages a lot at this stage may move on to. Lots only move forward:
+    /// cherry may be processed straight to green bean (natural and honey
+    /// lots are hulled once dry), only green coffee is roasted, and a lot
+    /// may be sold at any stage but never moves on after.
+    pub fn next_stages(&self) -> &'static [LotStage] {
         match self {
-            LotStage::Cherry => write!(f, "Cherry"),
-            LotStage::Parchment => write!(f, "Parchment"),
-            LotStage::GreenBean => write!(f, "Green Bean"),
-            LotStage::RoastedBean => write!(f, "Roasted Bean"),
-            LotStage::Sold => write!(f, "Sold"),
+            LotStage::Cherry => &[LotStage::Parchment, LotStage::GreenBean, LotStage::Sold],
+            LotStage::Parchment => &[LotStage::GreenBean, LotStage::Sold],
+            LotStage::GreenBean => &[LotStage::RoastedBean, LotStage::Sold],
+            LotStage::RoastedBean => &[LotStage::Sold],
+            LotStage::Sold => &[],
         }
     }
+
+    /// Whether a lot at this stage may be set to `next`; staying at the
+    /// same stage is always allowed
+    pub fn can_transition_to(&self, next: LotStage) -> bool {
+        *self == next || self.next_stages().contains(&next)
+    }
+}
+
+impl std::fmt::Display for LotStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name_in(&Language::English))
+    }
 }
 
 /// Source lot reference for blended lots
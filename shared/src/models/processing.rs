@@ -22,9 +22,36 @@ pub struct ProcessingRecord {
     pub created_at: DateTime<Utc>,
 }
 
+/// Mucilage left on a honey process unless given
+pub const DEFAULT_HONEY_MUCILAGE_PERCENT: i32 = 50;
+
+/// Anaerobic fermentation hours unless given
+pub const DEFAULT_ANAEROBIC_HOURS: i32 = 72;
+
+/// Longest anaerobic fermentation accepted (30 days)
+pub const MAX_ANAEROBIC_HOURS: i32 = 720;
+
+/// Longest custom method name accepted
+pub const MAX_CUSTOM_METHOD_NAME_LENGTH: usize = 100;
+
 /// Coffee processing methods
+///
+/// Serialized as an object tagged by the method code, with the method's
+/// parameters alongside, the same code and details processing records are
+/// stored and returned with:
+///
+/// ```json
+/// {"type": "natural"}
+/// {"type": "honey", "mucilage_percent": 50}
+/// {"type": "anaerobic", "hours": 72}
+/// {"type": "custom", "name": "Carbonic maceration"}
+/// ```
+///
+/// Deserializing also accepts a bare code such as `"washed"` (honey and
+/// anaerobic take their default parameters) and the earlier
+/// `{"honey": {"mucilage_percent": 50}}` form.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
+#[serde(into = "TaggedMethod", try_from = "MethodRepr")]
 pub enum ProcessingMethod {
     Natural,
     Washed,
@@ -36,6 +63,140 @@ pub enum ProcessingMethod {
     Custom(String),
 }
 
+/// Stable serialized form of `ProcessingMethod`
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TaggedMethod {
+    Natural,
+    Washed,
+    Honey { mucilage_percent: i32 },
+    WetHulled,
+    Anaerobic { hours: i32 },
+    Custom { name: String },
+}
+
+/// Earlier externally tagged form, still accepted
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LegacyMethod {
+    Natural,
+    Washed,
+    Honey { mucilage_percent: i32 },
+    WetHulled,
+    Anaerobic { hours: i32 },
+    Custom(String),
+}
+
+/// Any accepted form of a processing method
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MethodRepr {
+    Tagged(TaggedMethod),
+    Code(String),
+    Legacy(LegacyMethod),
+}
+
+impl From<ProcessingMethod> for TaggedMethod {
+    fn from(method: ProcessingMethod) -> Self {
+        match method {
+            ProcessingMethod::Natural => TaggedMethod::Natural,
+            ProcessingMethod::Washed => TaggedMethod::Washed,
+            ProcessingMethod::Honey { mucilage_percent } => TaggedMethod::Honey { mucilage_percent },
+            ProcessingMethod::WetHulled => TaggedMethod::WetHulled,
+            ProcessingMethod::Anaerobic { hours } => TaggedMethod::Anaerobic { hours },
+            ProcessingMethod::Custom(name) => TaggedMethod::Custom { name },
+        }
+    }
+}
+
+impl TryFrom<MethodRepr> for ProcessingMethod {
+    type Error = String;
+
+    fn try_from(repr: MethodRepr) -> Result<Self, Self::Error> {
+        Ok(match repr {
+            MethodRepr::Tagged(TaggedMethod::Natural) | MethodRepr::Legacy(LegacyMethod::Natural) => {
+                ProcessingMethod::Natural
+            }
+            MethodRepr::Tagged(TaggedMethod::Washed) | MethodRepr::Legacy(LegacyMethod::Washed) => {
+                ProcessingMethod::Washed
+            }
+            MethodRepr::Tagged(TaggedMethod::Honey { mucilage_percent })
+            | MethodRepr::Legacy(LegacyMethod::Honey { mucilage_percent }) => ProcessingMethod::Honey { mucilage_percent },
+            MethodRepr::Tagged(TaggedMethod::WetHulled) | MethodRepr::Legacy(LegacyMethod::WetHulled) => {
+                ProcessingMethod::WetHulled
+            }
+            MethodRepr::Tagged(TaggedMethod::Anaerobic { hours })
+            | MethodRepr::Legacy(LegacyMethod::Anaerobic { hours }) => ProcessingMethod::Anaerobic { hours },
+            MethodRepr::Tagged(TaggedMethod::Custom { name }) | MethodRepr::Legacy(LegacyMethod::Custom(name)) => {
+                ProcessingMethod::Custom(name)
+            }
+            MethodRepr::Code(code) => ProcessingMethod::from_code(&code)
+                .ok_or_else(|| format!("unknown processing method `{}`", code))?,
+        })
+    }
+}
+
+impl ProcessingMethod {
+    /// Stored and serialized method code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProcessingMethod::Natural => "natural",
+            ProcessingMethod::Washed => "washed",
+            ProcessingMethod::Honey { .. } => "honey",
+            ProcessingMethod::WetHulled => "wet_hulled",
+            ProcessingMethod::Anaerobic { .. } => "anaerobic",
+            ProcessingMethod::Custom(_) => "custom",
+        }
+    }
+
+    /// Method by its code, honey and anaerobic with their default
+    /// parameters; custom methods need a name and have no code
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "natural" => Some(ProcessingMethod::Natural),
+            "washed" => Some(ProcessingMethod::Washed),
+            "honey" => Some(ProcessingMethod::Honey {
+                mucilage_percent: DEFAULT_HONEY_MUCILAGE_PERCENT,
+            }),
+            "wet_hulled" => Some(ProcessingMethod::WetHulled),
+            "anaerobic" => Some(ProcessingMethod::Anaerobic {
+                hours: DEFAULT_ANAEROBIC_HOURS,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Method from text a farmer types, in English or Thai, such as
+    /// "washed", "wet hulled", "honey 30%", "ฮันนี่ 30" or "anaerobic 48h".
+    /// The number sets the mucilage percent or fermentation hours; None
+    /// for unknown methods and numbers given to methods without one
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        let (name, rest) = text.split_at(text.find(|c: char| c.is_ascii_digit()).unwrap_or(text.len()));
+        let number = match rest.split(|c: char| !c.is_ascii_digit()).next() {
+            Some(digits) if !digits.is_empty() => Some(digits.parse::<i32>().ok()?),
+            _ => None,
+        };
+        let name = name.split(|c: char| c.is_whitespace() || c == '-' || c == '_').filter(|w| !w.is_empty());
+        let method = match name.collect::<Vec<_>>().join("-").as_str() {
+            "natural" | "ธรรมชาติ" => ProcessingMethod::Natural,
+            "washed" | "ล้าง" => ProcessingMethod::Washed,
+            "honey" | "ฮันนี่" => ProcessingMethod::Honey {
+                mucilage_percent: number.unwrap_or(DEFAULT_HONEY_MUCILAGE_PERCENT),
+            },
+            "wet-hulled" | "wethulled" | "กะลาเปียก" => ProcessingMethod::WetHulled,
+            "anaerobic" | "ไร้อากาศ" => ProcessingMethod::Anaerobic {
+                hours: number.unwrap_or(DEFAULT_ANAEROBIC_HOURS),
+            },
+            _ => return None,
+        };
+        match method {
+            ProcessingMethod::Honey { .. } | ProcessingMethod::Anaerobic { .. } => Some(method),
+            _ => number.is_none().then_some(method),
+        }
+    }
+}
+
 impl std::fmt::Display for ProcessingMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
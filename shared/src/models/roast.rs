@@ -92,3 +92,14 @@ pub fn calculate_weight_loss(green_weight: Decimal, roasted_weight: Decimal) ->
         ((green_weight - roasted_weight) / green_weight) * Decimal::from(100)
     }
 }
+
+/// Calculate development time ratio (DTR) percentage
+/// Formula: (development_time / total_time) × 100
+pub fn calculate_dtr(development_time_seconds: i32, total_time_seconds: i32) -> Decimal {
+    if total_time_seconds <= 0 {
+        Decimal::ZERO
+    } else {
+        (Decimal::from(development_time_seconds) / Decimal::from(total_time_seconds))
+            * Decimal::from(100)
+    }
+}
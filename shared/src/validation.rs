@@ -4,7 +4,10 @@
 
 use rust_decimal::Decimal;
 
-use crate::models::{DefectCount, GradeClassification, RipenessAssessment};
+use crate::models::{
+    DefectCount, GradeClassification, ProcessingMethod, RipenessAssessment, MAX_ANAEROBIC_HOURS,
+    MAX_CUSTOM_METHOD_NAME_LENGTH,
+};
 
 // ============================================================================
 // Coffee Quality Validations
@@ -65,6 +68,23 @@ pub fn validate_moisture_content(moisture: Decimal) -> Result<(), &'static str>
     Ok(())
 }
 
+/// Validate the parameters of a processing method
+pub fn validate_processing_method(method: &ProcessingMethod) -> Result<(), &'static str> {
+    match method {
+        ProcessingMethod::Honey { mucilage_percent } if !(0..=100).contains(mucilage_percent) => {
+            Err("Honey mucilage must be between 0 and 100%")
+        }
+        ProcessingMethod::Anaerobic { hours } if !(1..=MAX_ANAEROBIC_HOURS).contains(hours) => {
+            Err("Anaerobic fermentation must last between 1 and 720 hours")
+        }
+        ProcessingMethod::Custom(name) if name.trim().is_empty() => Err("Custom processing method needs a name"),
+        ProcessingMethod::Custom(name) if name.trim().chars().count() > MAX_CUSTOM_METHOD_NAME_LENGTH => {
+            Err("Custom processing method name must be at most 100 characters")
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Check if moisture content is in ideal range for green beans
 pub fn is_ideal_moisture(moisture: Decimal) -> bool {
     moisture >= Decimal::from(10) && moisture <= Decimal::from(12)
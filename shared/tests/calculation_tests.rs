@@ -0,0 +1,315 @@
+//! Property-based invariants for shared quality calculations
+//!
+//! These functions are used by both the backend services and the WASM client,
+//! so the invariants below hold for every platform:
+//! - Cupping totals are bounded and monotonic in each attribute
+//! - Processing yield, roast weight loss and DTR are bounded percentages
+//! - Grade classification never improves when defects are added
+//! - Grading standards with tighter limits never give a better grade
+//! - Screen size shares accumulate from the largest screen down
+
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use shared::{
+    calculate_dtr, calculate_processing_yield, calculate_weight_loss, classify_by_score,
+    classify_grade, classify_grade_with, sca_thresholds, CoffeeClassification, CuppingScores, DefectCount,
+    GradeClassification, GradeThreshold, ScreenSizeDistribution,
+};
+
+// ============================================================================
+// Strategies
+// ============================================================================
+
+/// Attribute score on the SCA 6.00-10.00 scale in 0.25 increments
+fn attribute_score() -> impl Strategy<Value = Decimal> {
+    (24i64..=40).prop_map(|q| Decimal::new(q * 25, 2))
+}
+
+/// Uniformity, clean cup and sweetness: 0-10 in 2 point increments (5 cups)
+fn cup_score() -> impl Strategy<Value = Decimal> {
+    (0i64..=5).prop_map(|c| Decimal::from(c * 2))
+}
+
+fn cupping_scores() -> impl Strategy<Value = CuppingScores> {
+    (
+        proptest::collection::vec(attribute_score(), 7),
+        proptest::collection::vec(cup_score(), 3),
+    )
+        .prop_map(|(attrs, cups)| CuppingScores {
+            fragrance_aroma: attrs[0],
+            flavor: attrs[1],
+            aftertaste: attrs[2],
+            acidity: attrs[3],
+            body: attrs[4],
+            balance: attrs[5],
+            uniformity: cups[0],
+            clean_cup: cups[1],
+            sweetness: cups[2],
+            overall: attrs[6],
+        })
+}
+
+/// Positive weight in kg with 3 decimal places (0.001 - 10,000 kg)
+fn weight_kg() -> impl Strategy<Value = Decimal> {
+    (1i64..=10_000_000).prop_map(|g| Decimal::new(g, 3))
+}
+
+/// Fraction in [0, 1] with 3 decimal places
+fn fraction() -> impl Strategy<Value = Decimal> {
+    (0i64..=1000).prop_map(|f| Decimal::new(f, 3))
+}
+
+fn classification_rank(classification: &CoffeeClassification) -> i32 {
+    match classification {
+        CoffeeClassification::Outstanding => 4,
+        CoffeeClassification::Excellent => 3,
+        CoffeeClassification::VeryGood => 2,
+        CoffeeClassification::BelowSpecialty => 1,
+    }
+}
+
+fn grade_rank(grade: &GradeClassification) -> i32 {
+    match grade {
+        GradeClassification::SpecialtyGrade => 5,
+        GradeClassification::PremiumGrade => 4,
+        GradeClassification::ExchangeGrade => 3,
+        GradeClassification::BelowStandard => 2,
+        GradeClassification::OffGrade => 1,
+    }
+}
+
+fn defects(category1: i32, category2: i32) -> DefectCount {
+    DefectCount {
+        category1_count: category1,
+        category2_count: category2,
+        defect_breakdown: None,
+    }
+}
+
+// ============================================================================
+// Cupping Totals
+// ============================================================================
+
+proptest! {
+    /// Valid SCA scores always total between 42 and 100 points
+    #[test]
+    fn prop_cupping_total_bounded(scores in cupping_scores()) {
+        prop_assert!(scores.is_valid());
+        let total = scores.total();
+        prop_assert!(total >= Decimal::from(42));
+        prop_assert!(total <= Decimal::from(100));
+    }
+
+    /// Raising a single attribute raises the total by exactly that amount
+    #[test]
+    fn prop_cupping_total_monotonic(scores in cupping_scores(), steps in 0i64..=16) {
+        let delta = Decimal::new(steps * 25, 2);
+        let mut raised = scores.clone();
+        raised.flavor += delta;
+
+        prop_assert_eq!(raised.total(), scores.total() + delta);
+    }
+
+    /// A higher score never receives a lower classification
+    #[test]
+    fn prop_classification_monotonic(a in 0i64..=10_000, b in 0i64..=10_000) {
+        let (low, high) = (Decimal::new(a.min(b), 2), Decimal::new(a.max(b), 2));
+        prop_assert!(
+            classification_rank(&classify_by_score(low)) <= classification_rank(&classify_by_score(high))
+        );
+    }
+}
+
+// ============================================================================
+// Processing Yield
+// ============================================================================
+
+proptest! {
+    /// Yield is a percentage in [0, 100] when green bean weight <= cherry weight
+    #[test]
+    fn prop_processing_yield_bounded(cherry in weight_kg(), ratio in fraction()) {
+        let green = cherry * ratio;
+        let yield_percent = calculate_processing_yield(cherry, green);
+
+        prop_assert!(yield_percent >= Decimal::ZERO);
+        prop_assert!(yield_percent <= Decimal::from(100));
+    }
+
+    /// More green bean from the same cherry never lowers the yield
+    #[test]
+    fn prop_processing_yield_monotonic(cherry in weight_kg(), a in fraction(), b in fraction()) {
+        let (low, high) = (cherry * a.min(b), cherry * a.max(b));
+        prop_assert!(calculate_processing_yield(cherry, low) <= calculate_processing_yield(cherry, high));
+    }
+
+    /// Zero cherry weight yields zero instead of dividing by zero
+    #[test]
+    fn prop_processing_yield_zero_cherry(green in weight_kg()) {
+        prop_assert_eq!(calculate_processing_yield(Decimal::ZERO, green), Decimal::ZERO);
+    }
+}
+
+// ============================================================================
+// Roast Weight Loss
+// ============================================================================
+
+proptest! {
+    /// Weight loss is a percentage in [0, 100] when roasted weight <= green weight
+    #[test]
+    fn prop_weight_loss_bounded(green in weight_kg(), ratio in fraction()) {
+        let roasted = green * ratio;
+        let loss = calculate_weight_loss(green, roasted);
+
+        prop_assert!(loss >= Decimal::ZERO);
+        prop_assert!(loss <= Decimal::from(100));
+    }
+
+    /// Weight loss and retained weight percentages sum to 100
+    #[test]
+    fn prop_weight_loss_complements_yield(green in weight_kg(), ratio in fraction()) {
+        let roasted = green * ratio;
+        let total = calculate_weight_loss(green, roasted) + calculate_processing_yield(green, roasted);
+
+        prop_assert!((total - Decimal::from(100)).abs() < Decimal::new(1, 10));
+    }
+
+    /// A heavier roasted batch never reports more weight loss
+    #[test]
+    fn prop_weight_loss_monotonic(green in weight_kg(), a in fraction(), b in fraction()) {
+        let (light, heavy) = (green * a.min(b), green * a.max(b));
+        prop_assert!(calculate_weight_loss(green, heavy) <= calculate_weight_loss(green, light));
+    }
+}
+
+// ============================================================================
+// Development Time Ratio
+// ============================================================================
+
+proptest! {
+    /// DTR is a percentage in [0, 100] when development time <= total time
+    #[test]
+    fn prop_dtr_bounded(total in 1i32..=3600, development in 0i32..=3600) {
+        let dtr = calculate_dtr(development.min(total), total);
+
+        prop_assert!(dtr >= Decimal::ZERO);
+        prop_assert!(dtr <= Decimal::from(100));
+    }
+
+    /// Longer development in the same roast never lowers DTR
+    #[test]
+    fn prop_dtr_monotonic(total in 1i32..=3600, a in 0i32..=3600, b in 0i32..=3600) {
+        let (short, long) = (a.min(b).min(total), a.max(b).min(total));
+        prop_assert!(calculate_dtr(short, total) <= calculate_dtr(long, total));
+    }
+
+    /// A missing or invalid total time yields zero
+    #[test]
+    fn prop_dtr_invalid_total(total in -3600i32..=0, development in 0i32..=3600) {
+        prop_assert_eq!(calculate_dtr(development, total), Decimal::ZERO);
+    }
+}
+
+// ============================================================================
+// Defect Equivalence and Grade Classification
+// ============================================================================
+
+proptest! {
+    /// Total defects equal the sum of category 1 and category 2 counts
+    #[test]
+    fn prop_defect_total(category1 in 0i32..=500, category2 in 0i32..=500) {
+        prop_assert_eq!(defects(category1, category2).total(), category1 + category2);
+    }
+
+    /// Adding defects of either category never improves the grade
+    #[test]
+    fn prop_grade_monotonic(
+        category1 in 0i32..=100,
+        category2 in 0i32..=100,
+        extra1 in 0i32..=20,
+        extra2 in 0i32..=20,
+    ) {
+        let base = classify_grade(&defects(category1, category2));
+        let worse = classify_grade(&defects(category1 + extra1, category2 + extra2));
+        prop_assert!(grade_rank(&worse) <= grade_rank(&base));
+    }
+
+    /// Samples with equal totals and category 1 defects grade identically,
+    /// regardless of how the remaining defects are split
+    #[test]
+    fn prop_grade_depends_on_totals(category1 in 1i32..=50, category2 in 0i32..=100, shift in 0i32..=50) {
+        let shift = shift.min(category1 - 1);
+        let original = classify_grade(&defects(category1, category2));
+        let rebalanced = classify_grade(&defects(category1 - shift, category2 + shift));
+        prop_assert_eq!(original, rebalanced);
+    }
+
+    /// Specialty grade requires zero category 1 defects
+    #[test]
+    fn prop_specialty_requires_no_primary_defects(category1 in 0i32..=10, category2 in 0i32..=10) {
+        if classify_grade(&defects(category1, category2)) == GradeClassification::SpecialtyGrade {
+            prop_assert_eq!(category1, 0);
+        }
+    }
+
+    /// Tightening every defect limit of a standard never improves a grade
+    #[test]
+    fn prop_stricter_standard_never_grades_better(
+        category1 in 0i32..=60,
+        category2 in 0i32..=60,
+        tighten in 0i32..=10,
+    ) {
+        let stricter: Vec<GradeThreshold> = sca_thresholds()
+            .into_iter()
+            .map(|t| GradeThreshold { max_total_defects: (t.max_total_defects - tighten).max(0), ..t })
+            .collect();
+        let sample = defects(category1, category2);
+        let sca = classify_grade(&sample);
+        let strict = classify_grade_with(&sample, None, &stricter);
+        prop_assert!(grade_rank(&strict) <= grade_rank(&sca));
+    }
+
+    /// Moisture outside a grade's range keeps the sample out of that grade
+    #[test]
+    fn prop_moisture_limits_apply(moisture in 50i64..=160) {
+        let moisture = Decimal::new(moisture, 1);
+        let thresholds = vec![GradeThreshold {
+            grade: GradeClassification::SpecialtyGrade,
+            max_total_defects: 5,
+            max_category1_defects: Some(0),
+            min_moisture_percent: Some(Decimal::new(90, 1)),
+            max_moisture_percent: Some(Decimal::new(120, 1)),
+        }];
+        let grade = classify_grade_with(&defects(0, 0), Some(moisture), &thresholds);
+        let in_range = (Decimal::new(90, 1)..=Decimal::new(120, 1)).contains(&moisture);
+        prop_assert_eq!(grade == GradeClassification::SpecialtyGrade, in_range);
+        // Unmeasured moisture is not held against the sample
+        prop_assert_eq!(classify_grade_with(&defects(0, 0), None, &thresholds), GradeClassification::SpecialtyGrade);
+    }
+}
+
+// ============================================================================
+// Screen Size
+// ============================================================================
+
+proptest! {
+    /// Shares grow as the screen gets smaller and reach the whole sample at 14
+    #[test]
+    fn prop_screen_share_cumulative(shares in proptest::collection::vec(0i64..=2000, 5)) {
+        let screen = ScreenSizeDistribution {
+            screen_18_plus: Decimal::new(shares[0], 2),
+            screen_17: Decimal::new(shares[1], 2),
+            screen_16: Decimal::new(shares[2], 2),
+            screen_15: Decimal::new(shares[3], 2),
+            screen_14_below: Decimal::new(shares[4], 2),
+        };
+        let total: Decimal = shares.iter().map(|s| Decimal::new(*s, 2)).sum();
+
+        prop_assert_eq!(screen.share_at_or_above(18), screen.screen_18_plus);
+        prop_assert_eq!(screen.share_at_or_above(20), screen.screen_18_plus);
+        for size in 15u8..18 {
+            prop_assert!(screen.share_at_or_above(size) >= screen.share_at_or_above(size + 1));
+        }
+        prop_assert_eq!(screen.share_at_or_above(14), total);
+        prop_assert_eq!(screen.share_at_or_above(12), total);
+    }
+}
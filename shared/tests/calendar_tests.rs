@@ -0,0 +1,98 @@
+//! Calendar-aware date formatting
+//!
+//! Buddhist Era dates appear on government paperwork, so the formats below
+//! are checked against the forms they are copied onto:
+//! - B.E. years are exactly 543 ahead of Gregorian years
+//! - Short dates are ISO (Gregorian) or dd/mm/yyyy (B.E.)
+//! - Long dates spell the month in the reader's language
+
+use chrono::{Datelike, NaiveDate};
+use proptest::prelude::*;
+use shared::{
+    format_date, format_date_long, format_datetime, format_month, CalendarSystem, Language,
+    BUDDHIST_ERA_OFFSET,
+};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+// ============================================================================
+// Formats
+// ============================================================================
+
+#[test]
+fn test_short_dates() {
+    let day = date(2024, 11, 3);
+    assert_eq!(format_date(day, CalendarSystem::Gregorian), "2024-11-03");
+    assert_eq!(format_date(day, CalendarSystem::Buddhist), "03/11/2567");
+    assert_eq!(format_month(day, CalendarSystem::Gregorian), "2024-11");
+    assert_eq!(format_month(day, CalendarSystem::Buddhist), "11/2567");
+}
+
+#[test]
+fn test_datetime_keeps_time_of_day() {
+    let at = date(2024, 1, 15).and_hms_opt(14, 5, 59).unwrap();
+    assert_eq!(format_datetime(at, CalendarSystem::Buddhist), "15/01/2567 14:05");
+    assert_eq!(format_datetime(at, CalendarSystem::Gregorian), "2024-01-15 14:05");
+}
+
+#[test]
+fn test_long_dates() {
+    let day = date(2024, 11, 23);
+    assert_eq!(
+        format_date_long(day, CalendarSystem::Buddhist, &Language::Thai),
+        "23 พฤศจิกายน 2567"
+    );
+    assert_eq!(
+        format_date_long(day, CalendarSystem::Gregorian, &Language::Thai),
+        "23 พฤศจิกายน ค.ศ. 2024"
+    );
+    assert_eq!(
+        format_date_long(day, CalendarSystem::Buddhist, &Language::English),
+        "23 November 2567 B.E."
+    );
+    assert_eq!(
+        format_date_long(day, CalendarSystem::Gregorian, &Language::English),
+        "23 November 2024"
+    );
+}
+
+#[test]
+fn test_calendar_codes() {
+    for calendar in [CalendarSystem::Gregorian, CalendarSystem::Buddhist] {
+        assert_eq!(CalendarSystem::from_code(calendar.code()), Some(calendar));
+    }
+    assert_eq!(CalendarSystem::from_code("lunar"), None);
+    assert_eq!(CalendarSystem::default(), CalendarSystem::Gregorian);
+    assert_eq!(
+        serde_json::from_str::<CalendarSystem>("\"buddhist\"").unwrap(),
+        CalendarSystem::Buddhist
+    );
+}
+
+// ============================================================================
+// Invariants
+// ============================================================================
+
+proptest! {
+    /// B.E. years are a fixed offset; day and month never change
+    #[test]
+    fn prop_buddhist_short_date(days in 0i64..100_000) {
+        let day = date(1900, 1, 1) + chrono::Duration::days(days);
+        let formatted = format_date(day, CalendarSystem::Buddhist);
+        let parts: Vec<i32> = formatted.split('/').map(|p| p.parse().unwrap()).collect();
+
+        prop_assert_eq!(parts[0] as u32, day.day());
+        prop_assert_eq!(parts[1] as u32, day.month());
+        prop_assert_eq!(parts[2] - BUDDHIST_ERA_OFFSET, day.year());
+    }
+
+    /// Gregorian short dates stay ISO 8601 and parse back to the same day
+    #[test]
+    fn prop_gregorian_round_trip(days in 0i64..100_000) {
+        let day = date(1900, 1, 1) + chrono::Duration::days(days);
+        let formatted = format_date(day, CalendarSystem::Gregorian);
+        prop_assert_eq!(NaiveDate::parse_from_str(&formatted, "%Y-%m-%d").unwrap(), day);
+    }
+}
@@ -0,0 +1,119 @@
+//! Defect breakdowns and SCA full defect equivalents
+//!
+//! Grades are classified on full defects:
+//! - Each defect type has its category and full defect equivalent
+//! - A breakdown's types are rounded down one by one and summed per category
+//! - A breakdown takes the place of the category counts entered
+//! - Breakdowns without the newer types, or with `broca`, still deserialize
+
+use proptest::prelude::*;
+use shared::{classify_grade, DefectBreakdown, DefectCategory, DefectCount, DefectType, GradeClassification};
+
+fn counts(category1_count: i32, category2_count: i32, defect_breakdown: Option<DefectBreakdown>) -> DefectCount {
+    DefectCount {
+        category1_count,
+        category2_count,
+        defect_breakdown,
+    }
+}
+
+// ============================================================================
+// Equivalents
+// ============================================================================
+
+#[test]
+fn test_sca_equivalents() {
+    assert_eq!(DefectType::FullBlack.full_defects(1), 1);
+    assert_eq!(DefectType::PartialBlack.full_defects(3), 1);
+    assert_eq!(DefectType::PartialSour.full_defects(2), 0);
+    assert_eq!(DefectType::Broken.full_defects(14), 2);
+    assert_eq!(DefectType::SevereInsectDamage.full_defects(5), 1);
+    assert_eq!(DefectType::InsectDamage.full_defects(9), 0);
+    assert_eq!(DefectType::InsectDamage.full_defects(10), 1);
+    assert_eq!(DefectType::LargeStones.full_defects(1), 2);
+}
+
+#[test]
+fn test_categories() {
+    assert_eq!(DefectType::SevereInsectDamage.category(), DefectCategory::Primary);
+    assert_eq!(DefectType::ForeignMatter.category(), DefectCategory::Primary);
+    assert_eq!(DefectType::InsectDamage.category(), DefectCategory::Secondary);
+    assert_eq!(DefectType::Husk.category(), DefectCategory::Secondary);
+}
+
+#[test]
+fn test_each_type_rounded_down_on_its_own() {
+    // 2 partial blacks and 2 partial sours are 4 occurrences of 3:1 defects,
+    // but neither type reaches a full defect
+    let breakdown = DefectBreakdown {
+        partial_black: 2,
+        partial_sour: 2,
+        ..Default::default()
+    };
+    assert_eq!(breakdown.full_defects(DefectCategory::Secondary), 0);
+}
+
+// ============================================================================
+// Classification
+// ============================================================================
+
+#[test]
+fn test_breakdown_replaces_counts() {
+    let breakdown = DefectBreakdown {
+        full_black: 1,
+        partial_black: 6,
+        broken: 12,
+        insect_damage: 25,
+        ..Default::default()
+    };
+    let defects = counts(0, 0, Some(breakdown.clone()));
+    assert_eq!(defects.category1_defects(), 1);
+    assert_eq!(defects.category2_defects(), 2 + 2 + 2);
+    assert_eq!(defects.total(), 7);
+    assert_eq!(classify_grade(&defects), GradeClassification::PremiumGrade);
+
+    let from_breakdown = DefectCount::from_breakdown(breakdown);
+    assert_eq!((from_breakdown.category1_count, from_breakdown.category2_count), (1, 6));
+}
+
+#[test]
+fn test_raw_counts_would_overstate_grade() {
+    // 20 broken beans are 4 full defects, not 20
+    let breakdown = DefectBreakdown {
+        broken: 20,
+        ..Default::default()
+    };
+    assert_eq!(classify_grade(&counts(0, 20, Some(breakdown))), GradeClassification::SpecialtyGrade);
+    assert_eq!(classify_grade(&counts(0, 20, None)), GradeClassification::ExchangeGrade);
+}
+
+#[test]
+fn test_older_breakdowns_deserialize() {
+    let breakdown: DefectBreakdown = serde_json::from_str(r#"{"full_black": 2, "broca": 10}"#).unwrap();
+    assert_eq!(breakdown.full_black, 2);
+    assert_eq!(breakdown.insect_damage, 10);
+    assert_eq!(breakdown.severe_insect_damage, 0);
+}
+
+#[test]
+fn test_negative_count_found() {
+    let breakdown = DefectBreakdown {
+        shell: -1,
+        ..Default::default()
+    };
+    assert_eq!(breakdown.negative_count(), Some(DefectType::Shell));
+    assert_eq!(DefectBreakdown::default().negative_count(), None);
+}
+
+proptest! {
+    #[test]
+    fn prop_full_defects_never_exceed_occurrences(
+        defect in prop::sample::select(DefectType::ALL.to_vec()),
+        occurrences in 0i32..1000,
+    ) {
+        let equivalent = defect.equivalent();
+        let full = defect.full_defects(occurrences);
+        prop_assert!(full * equivalent.occurrences <= occurrences * equivalent.full_defects);
+        prop_assert!(defect.full_defects(occurrences + equivalent.occurrences) == full + equivalent.full_defects);
+    }
+}
@@ -0,0 +1,147 @@
+//! SCA flavor wheel taxonomy
+//!
+//! Stored descriptor codes must keep resolving, so the wheel is checked for
+//! shape rather than content:
+//! - Codes are unique and every entry's parent is on the wheel before it
+//! - Three tiers, with nine categories in the center
+//! - Every entry has a Thai name
+//! - Autocomplete ranks names starting with the query first
+
+use proptest::prelude::*;
+use shared::{flavor_descriptor, search_flavor_descriptors, Language, FLAVOR_WHEEL};
+use std::collections::HashSet;
+
+// ============================================================================
+// Wheel Shape
+// ============================================================================
+
+#[test]
+fn test_codes_are_unique() {
+    let codes: HashSet<&str> = FLAVOR_WHEEL.iter().map(|d| d.code).collect();
+    assert_eq!(codes.len(), FLAVOR_WHEEL.len());
+}
+
+#[test]
+fn test_parents_come_before_children() {
+    for (position, descriptor) in FLAVOR_WHEEL.iter().enumerate() {
+        if let Some((parent_code, _)) = descriptor.code.rsplit_once('.') {
+            let parent_position = FLAVOR_WHEEL.iter().position(|d| d.code == parent_code);
+            assert!(
+                parent_position.is_some_and(|p| p < position),
+                "{} has no parent before it",
+                descriptor.code
+            );
+        }
+    }
+}
+
+#[test]
+fn test_nine_categories_and_three_tiers() {
+    let categories: Vec<&str> = FLAVOR_WHEEL.iter().filter(|d| d.level() == 1).map(|d| d.name).collect();
+    assert_eq!(
+        categories,
+        vec![
+            "Floral",
+            "Fruity",
+            "Sour/Fermented",
+            "Green/Vegetative",
+            "Other",
+            "Roasted",
+            "Spices",
+            "Nutty/Cocoa",
+            "Sweet"
+        ]
+    );
+    assert!(FLAVOR_WHEEL.iter().all(|d| (1..=3).contains(&d.level())));
+}
+
+#[test]
+fn test_every_entry_has_a_thai_name() {
+    for descriptor in FLAVOR_WHEEL {
+        assert!(!descriptor.name_th.is_empty(), "{} has no Thai name", descriptor.code);
+        assert_ne!(descriptor.name_th, descriptor.name, "{} is not translated", descriptor.code);
+    }
+}
+
+#[test]
+fn test_path_and_children() {
+    let blueberry = flavor_descriptor("fruity.berry.blueberry").unwrap();
+    let path: Vec<&str> = blueberry.path().iter().map(|d| d.name).collect();
+    assert_eq!(path, vec!["Fruity", "Berry", "Blueberry"]);
+    assert_eq!(blueberry.parent().unwrap().code, "fruity.berry");
+
+    let berry = flavor_descriptor("fruity.berry").unwrap();
+    assert_eq!(berry.children().len(), 4);
+    assert!(blueberry.children().is_empty());
+}
+
+#[test]
+fn test_entry_carries_names_in_both_languages() {
+    let entry = flavor_descriptor("sweet.brown_sugar.honey").unwrap().entry();
+    assert_eq!(entry.level, 3);
+    assert_eq!(entry.path, vec!["Sweet", "Brown Sugar", "Honey"]);
+    assert_eq!(entry.path_th, vec!["หวาน", "น้ำตาลทรายแดง", "น้ำผึ้ง"]);
+    assert_eq!(flavor_descriptor("sweet").unwrap().name_in(&Language::Thai), "หวาน");
+}
+
+#[test]
+fn test_unknown_code() {
+    assert!(flavor_descriptor("fruity.berry.durian").is_none());
+    assert!(flavor_descriptor("").is_none());
+}
+
+// ============================================================================
+// Autocomplete
+// ============================================================================
+
+#[test]
+fn test_name_prefix_ranks_first() {
+    let codes: Vec<&str> = search_flavor_descriptors("ch", 10).iter().map(|d| d.code).collect();
+    // Chamomile, Cherry, Chemical, Chocolate start with "ch"; Dark Chocolate
+    // only has a word that does
+    let dark = codes.iter().position(|c| *c == "nutty_cocoa.cocoa.dark_chocolate").unwrap();
+    for prefix in ["floral.floral.chamomile", "fruity.other_fruit.cherry", "other.chemical", "nutty_cocoa.cocoa.chocolate"] {
+        assert!(codes.iter().position(|c| *c == prefix).unwrap() < dark);
+    }
+}
+
+#[test]
+fn test_search_is_case_insensitive() {
+    assert_eq!(search_flavor_descriptors("HONEY", 5)[0].code, "sweet.brown_sugar.honey");
+}
+
+#[test]
+fn test_search_in_thai() {
+    let results = search_flavor_descriptors("มะลิ", 5);
+    assert_eq!(results[0].code, "floral.floral.jasmine");
+}
+
+#[test]
+fn test_blank_query_starts_at_the_center() {
+    let results = search_flavor_descriptors("  ", 3);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].code, "floral");
+}
+
+#[test]
+fn test_no_match() {
+    assert!(search_flavor_descriptors("durian", 10).is_empty());
+}
+
+// ============================================================================
+// Properties
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn prop_search_respects_limit(query in "[a-z]{0,3}", limit in 1usize..20) {
+        prop_assert!(search_flavor_descriptors(&query, limit).len() <= limit);
+    }
+
+    #[test]
+    fn prop_every_name_finds_itself(index in 0..FLAVOR_WHEEL.len()) {
+        let descriptor = &FLAVOR_WHEEL[index];
+        let results = search_flavor_descriptors(descriptor.name, FLAVOR_WHEEL.len());
+        prop_assert!(results.iter().any(|d| d.code == descriptor.code));
+    }
+}
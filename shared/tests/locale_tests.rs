@@ -0,0 +1,113 @@
+//! Locale-aware number formatting
+//!
+//! Numbers printed on documents must read back to the value they came from:
+//! - Thousands separators group the integer part only
+//! - Rounding is half away from zero at a fixed number of decimals
+//! - Thai digits map one-to-one onto 0-9 and nothing else changes
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use shared::{
+    format_decimal, format_integer, format_percent, to_thai_digits, CalendarSystem, DigitSystem,
+    DisplayFormat, Language,
+};
+
+// ============================================================================
+// Formats
+// ============================================================================
+
+#[test]
+fn test_thousands_separators() {
+    assert_eq!(format_integer(0, DigitSystem::Arabic), "0");
+    assert_eq!(format_integer(999, DigitSystem::Arabic), "999");
+    assert_eq!(format_integer(1_000, DigitSystem::Arabic), "1,000");
+    assert_eq!(format_integer(-1_234_567, DigitSystem::Arabic), "-1,234,567");
+    assert_eq!(format_decimal(Decimal::new(123456789, 3), 2, DigitSystem::Arabic), "123,456.79");
+}
+
+#[test]
+fn test_fixed_decimals_and_rounding() {
+    assert_eq!(format_decimal(Decimal::from(5), 2, DigitSystem::Arabic), "5.00");
+    assert_eq!(format_decimal(Decimal::new(125, 2), 1, DigitSystem::Arabic), "1.3");
+    assert_eq!(format_decimal(Decimal::new(-125, 2), 1, DigitSystem::Arabic), "-1.3");
+    assert_eq!(format_decimal(Decimal::new(-4, 3), 2, DigitSystem::Arabic), "0.00");
+    assert_eq!(format_percent(Decimal::new(825, 1), 1, DigitSystem::Arabic), "82.5%");
+}
+
+#[test]
+fn test_thai_digits() {
+    assert_eq!(to_thai_digits("CQM-2024 12.5 kg"), "CQM-๒๐๒๔ ๑๒.๕ kg");
+    assert_eq!(format_decimal(Decimal::new(123456, 2), 2, DigitSystem::Thai), "๑,๒๓๔.๕๖");
+}
+
+#[test]
+fn test_display_format() {
+    let display = DisplayFormat::new(CalendarSystem::Buddhist, DigitSystem::Thai);
+    let date = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap();
+
+    assert_eq!(display.date(date), "๐๓/๑๑/๒๕๖๗");
+    assert_eq!(display.date_long(date, &Language::Thai), "๓ พฤศจิกายน ๒๕๖๗");
+    assert_eq!(display.month(date), "๑๑/๒๕๖๗");
+
+    // English text keeps the calendar but uses Arabic digits
+    let english = display.for_language(&Language::English);
+    assert_eq!(english.date(date), "03/11/2567");
+    assert_eq!(english.decimal(Decimal::new(150000, 2), 2), "1,500.00");
+    assert_eq!(DisplayFormat::default().date(date), "2024-11-03");
+}
+
+#[test]
+fn test_digit_system_codes() {
+    for digits in [DigitSystem::Arabic, DigitSystem::Thai] {
+        assert_eq!(DigitSystem::from_code(digits.code()), Some(digits));
+    }
+    assert_eq!(DigitSystem::from_code("roman"), None);
+}
+
+// ============================================================================
+// Invariants
+// ============================================================================
+
+fn from_thai_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x0E50..=0x0E59 => char::from_digit(c as u32 - 0x0E50, 10).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
+
+proptest! {
+    /// Removing separators gives back the value rounded to `places`
+    #[test]
+    fn prop_decimal_round_trip(mantissa in -10_000_000_000i64..10_000_000_000, scale in 0u32..6, places in 0u32..4) {
+        let value = Decimal::new(mantissa, scale);
+        let formatted = format_decimal(value, places, DigitSystem::Arabic);
+        let parsed: Decimal = formatted.replace(',', "").parse().unwrap();
+
+        let expected = value.round_dp_with_strategy(places, rust_decimal::RoundingStrategy::MidpointAwayFromZero);
+        prop_assert_eq!(parsed, expected);
+        let decimals = formatted.split_once('.').map(|(_, f)| f.len() as u32).unwrap_or(0);
+        prop_assert_eq!(decimals, places);
+    }
+
+    /// Groups between separators are always three digits
+    #[test]
+    fn prop_groups_of_three(value in any::<i64>()) {
+        let formatted = format_integer(value, DigitSystem::Arabic);
+        let groups: Vec<&str> = formatted.trim_start_matches('-').split(',').collect();
+        prop_assert!(!groups[0].is_empty() && groups[0].len() <= 3);
+        prop_assert!(groups[1..].iter().all(|g| g.len() == 3));
+    }
+
+    /// Thai digits are a character-for-character substitution
+    #[test]
+    fn prop_thai_digits_substitution(value in any::<i64>()) {
+        let arabic = format_integer(value, DigitSystem::Arabic);
+        let thai = format_integer(value, DigitSystem::Thai);
+        prop_assert_eq!(thai.chars().count(), arabic.chars().count());
+        prop_assert!(!thai.chars().any(|c| c.is_ascii_digit()));
+        prop_assert_eq!(from_thai_digits(&thai), arabic);
+    }
+}
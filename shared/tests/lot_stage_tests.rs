@@ -0,0 +1,73 @@
+//! Lot stages and their transitions
+//!
+//! A lot's stage is stored, serialized and compared as `LotStage`:
+//! - Stages round-trip through their stored names
+//! - Lots only move forward, and a sold lot never moves again
+//! - Staying at the same stage is always allowed
+
+use proptest::prelude::*;
+use shared::{Language, LotStage};
+
+// ============================================================================
+// Names
+// ============================================================================
+
+#[test]
+fn test_stored_names_round_trip() {
+    for stage in LotStage::ALL {
+        assert_eq!(LotStage::from_name(stage.as_str()), Some(stage));
+        assert_eq!(serde_json::to_value(stage).unwrap(), stage.as_str());
+    }
+    assert_eq!(LotStage::from_name("GreenBean"), None);
+}
+
+#[test]
+fn test_display_names() {
+    assert_eq!(LotStage::GreenBean.to_string(), "Green Bean");
+    assert_eq!(LotStage::GreenBean.name_in(&Language::Thai), "สารกาแฟ");
+}
+
+// ============================================================================
+// Transitions
+// ============================================================================
+
+#[test]
+fn test_forward_transitions() {
+    assert!(LotStage::Cherry.can_transition_to(LotStage::Parchment));
+    assert!(LotStage::Cherry.can_transition_to(LotStage::GreenBean));
+    assert!(LotStage::Parchment.can_transition_to(LotStage::GreenBean));
+    assert!(LotStage::GreenBean.can_transition_to(LotStage::RoastedBean));
+    assert!(LotStage::RoastedBean.can_transition_to(LotStage::Sold));
+}
+
+#[test]
+fn test_cherry_is_not_roasted() {
+    assert!(!LotStage::Cherry.can_transition_to(LotStage::RoastedBean));
+    assert!(!LotStage::Parchment.can_transition_to(LotStage::RoastedBean));
+}
+
+#[test]
+fn test_no_backward_transitions() {
+    assert!(!LotStage::GreenBean.can_transition_to(LotStage::Cherry));
+    assert!(!LotStage::RoastedBean.can_transition_to(LotStage::GreenBean));
+}
+
+#[test]
+fn test_sold_is_final() {
+    assert!(LotStage::Sold.next_stages().is_empty());
+    assert!(LotStage::Sold.can_transition_to(LotStage::Sold));
+}
+
+proptest! {
+    #[test]
+    fn prop_transitions_only_move_forward(
+        from in prop::sample::select(LotStage::ALL.to_vec()),
+        to in prop::sample::select(LotStage::ALL.to_vec()),
+    ) {
+        if from.can_transition_to(to) {
+            prop_assert!(from <= to);
+        }
+        prop_assert!(from.can_transition_to(from));
+        prop_assert!(from == LotStage::Sold || from.can_transition_to(LotStage::Sold));
+    }
+}
@@ -0,0 +1,132 @@
+//! Processing method representation and parsing
+//!
+//! `ProcessingMethod` is exchanged with clients in a stable JSON form:
+//! - Serialized tagged by its code, with its parameters alongside
+//! - Bare codes and the earlier externally tagged form are still read
+//! - Text a farmer types is parsed in English or Thai, with parameters
+//! - Parameters are validated (mucilage %, fermentation hours, custom name)
+
+use proptest::prelude::*;
+use serde_json::json;
+use shared::{validate_processing_method, ProcessingMethod, MAX_ANAEROBIC_HOURS};
+
+// ============================================================================
+// Serialization
+// ============================================================================
+
+#[test]
+fn test_serialized_form() {
+    assert_eq!(serde_json::to_value(ProcessingMethod::Natural).unwrap(), json!({"type": "natural"}));
+    assert_eq!(serde_json::to_value(ProcessingMethod::WetHulled).unwrap(), json!({"type": "wet_hulled"}));
+    assert_eq!(
+        serde_json::to_value(ProcessingMethod::Honey { mucilage_percent: 30 }).unwrap(),
+        json!({"type": "honey", "mucilage_percent": 30})
+    );
+    assert_eq!(
+        serde_json::to_value(ProcessingMethod::Custom("Carbonic maceration".to_string())).unwrap(),
+        json!({"type": "custom", "name": "Carbonic maceration"})
+    );
+}
+
+#[test]
+fn test_reads_bare_codes_and_earlier_form() {
+    let read = |value: serde_json::Value| serde_json::from_value::<ProcessingMethod>(value).unwrap();
+    assert_eq!(read(json!("washed")), ProcessingMethod::Washed);
+    assert_eq!(read(json!("honey")), ProcessingMethod::Honey { mucilage_percent: 50 });
+    assert_eq!(read(json!({"anaerobic": {"hours": 48}})), ProcessingMethod::Anaerobic { hours: 48 });
+    assert_eq!(read(json!({"custom": "Koji"})), ProcessingMethod::Custom("Koji".to_string()));
+    assert_eq!(read(json!("wet_hulled")), ProcessingMethod::WetHulled);
+}
+
+#[test]
+fn test_rejects_unknown_methods() {
+    assert!(serde_json::from_value::<ProcessingMethod>(json!("steamed")).is_err());
+    assert!(serde_json::from_value::<ProcessingMethod>(json!({"type": "steamed"})).is_err());
+    assert!(serde_json::from_value::<ProcessingMethod>(json!({"type": "honey"})).is_err());
+}
+
+#[test]
+fn test_code_matches_serialized_type() {
+    let methods = [
+        ProcessingMethod::Natural,
+        ProcessingMethod::Washed,
+        ProcessingMethod::Honey { mucilage_percent: 10 },
+        ProcessingMethod::WetHulled,
+        ProcessingMethod::Anaerobic { hours: 24 },
+        ProcessingMethod::Custom("Koji".to_string()),
+    ];
+    for method in methods {
+        assert_eq!(serde_json::to_value(&method).unwrap()["type"], method.code());
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+#[test]
+fn test_parse_english_and_thai() {
+    assert_eq!(ProcessingMethod::parse("Washed"), Some(ProcessingMethod::Washed));
+    assert_eq!(ProcessingMethod::parse("ธรรมชาติ"), Some(ProcessingMethod::Natural));
+    assert_eq!(ProcessingMethod::parse("wet hulled"), Some(ProcessingMethod::WetHulled));
+    assert_eq!(ProcessingMethod::parse("กะลาเปียก"), Some(ProcessingMethod::WetHulled));
+}
+
+#[test]
+fn test_parse_parameters() {
+    assert_eq!(ProcessingMethod::parse("honey"), Some(ProcessingMethod::Honey { mucilage_percent: 50 }));
+    assert_eq!(ProcessingMethod::parse("honey 30%"), Some(ProcessingMethod::Honey { mucilage_percent: 30 }));
+    assert_eq!(ProcessingMethod::parse("ฮันนี่ 80"), Some(ProcessingMethod::Honey { mucilage_percent: 80 }));
+    assert_eq!(ProcessingMethod::parse("anaerobic 48h"), Some(ProcessingMethod::Anaerobic { hours: 48 }));
+    assert_eq!(ProcessingMethod::parse("ไร้อากาศ 96 ชั่วโมง"), Some(ProcessingMethod::Anaerobic { hours: 96 }));
+}
+
+#[test]
+fn test_parse_rejects_unknown_and_stray_numbers() {
+    assert_eq!(ProcessingMethod::parse("steamed"), None);
+    assert_eq!(ProcessingMethod::parse("washed 30"), None);
+    assert_eq!(ProcessingMethod::parse(""), None);
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+#[test]
+fn test_validate_parameters() {
+    assert!(validate_processing_method(&ProcessingMethod::Honey { mucilage_percent: 100 }).is_ok());
+    assert!(validate_processing_method(&ProcessingMethod::Honey { mucilage_percent: 120 }).is_err());
+    assert!(validate_processing_method(&ProcessingMethod::Anaerobic { hours: 0 }).is_err());
+    assert!(validate_processing_method(&ProcessingMethod::Anaerobic { hours: MAX_ANAEROBIC_HOURS + 1 }).is_err());
+    assert!(validate_processing_method(&ProcessingMethod::Custom("  ".to_string())).is_err());
+    assert!(validate_processing_method(&ProcessingMethod::Custom("x".repeat(101))).is_err());
+    assert!(validate_processing_method(&ProcessingMethod::Natural).is_ok());
+}
+
+proptest! {
+    #[test]
+    fn prop_serialized_form_round_trips(
+        mucilage_percent in 0i32..=100,
+        hours in 1i32..=MAX_ANAEROBIC_HOURS,
+        name in "[A-Za-z ]{1,30}",
+    ) {
+        let methods = [
+            ProcessingMethod::Honey { mucilage_percent },
+            ProcessingMethod::Anaerobic { hours },
+            ProcessingMethod::Custom(name),
+            ProcessingMethod::WetHulled,
+        ];
+        for method in methods {
+            let json = serde_json::to_string(&method).unwrap();
+            prop_assert_eq!(serde_json::from_str::<ProcessingMethod>(&json).unwrap(), method);
+        }
+    }
+
+    #[test]
+    fn prop_parsed_honey_keeps_percent(mucilage_percent in 0i32..=100) {
+        prop_assert_eq!(
+            ProcessingMethod::parse(&format!("honey {}%", mucilage_percent)),
+            Some(ProcessingMethod::Honey { mucilage_percent })
+        );
+    }
+}
@@ -2,14 +2,18 @@
 //!
 //! Provides client-side computation for:
 //! - Cupping score calculations
-//! - Grade classification
-//! - Yield calculations
+//! - Grade classification, from category counts or a defect breakdown
+//! - Yield, weight loss and development time calculations
+//! - Number formatting matching backend documents
+//! - Lot stage names and allowed stage changes
+//! - Processing method parsing and validation
 //! - Offline data validation
 
 use rust_decimal::Decimal;
 use wasm_bindgen::prelude::*;
 
 // Re-export shared types for use in JavaScript
+pub use shared::locale::*;
 pub use shared::models::*;
 pub use shared::types::*;
 pub use shared::validation::*;
@@ -45,6 +49,15 @@ pub fn classify_coffee_grade(category1: i32, category2: i32) -> String {
     format!("{}", grade)
 }
 
+/// Classify coffee grade from a defect breakdown JSON, counting each defect
+/// type by its SCA full defect equivalent
+#[wasm_bindgen]
+pub fn classify_defect_breakdown(breakdown_json: &str) -> Result<String, JsValue> {
+    let breakdown: DefectBreakdown = serde_json::from_str(breakdown_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid defect breakdown JSON: {}", e)))?;
+    Ok(classify_grade(&DefectCount::from_breakdown(breakdown)).to_string())
+}
+
 /// Calculate processing yield percentage
 #[wasm_bindgen]
 pub fn calculate_processing_yield(cherry_weight: f64, green_bean_weight: f64) -> f64 {
@@ -63,6 +76,13 @@ pub fn calculate_roast_weight_loss(green_weight: f64, roasted_weight: f64) -> f6
     ((green_weight - roasted_weight) / green_weight) * 100.0
 }
 
+/// Calculate roast development time ratio (DTR) percentage
+#[wasm_bindgen]
+pub fn calculate_development_time_ratio(development_time_seconds: i32, total_time_seconds: i32) -> f64 {
+    let dtr = calculate_dtr(development_time_seconds, total_time_seconds);
+    dtr.to_string().parse().unwrap_or(0.0)
+}
+
 /// Validate ripeness assessment (must sum to 100)
 #[wasm_bindgen]
 pub fn validate_ripeness_assessment(underripe: i32, ripe: i32, overripe: i32) -> bool {
@@ -87,9 +107,62 @@ pub fn calculate_harvest_yield(total_weight_kg: f64, area_rai: f64) -> f64 {
     total_weight_kg / area_rai
 }
 
+/// Format a number with thousands separators and fixed decimals, optionally
+/// in Thai digits, exactly as backend PDFs and notifications do
+#[wasm_bindgen]
+pub fn format_number(value: f64, places: u32, thai_digits: bool) -> String {
+    let digits = if thai_digits { DigitSystem::Thai } else { DigitSystem::Arabic };
+    format_decimal(Decimal::try_from(value).unwrap_or(Decimal::ZERO), places, digits)
+}
+
+/// Display name of a lot stage such as "green_bean"; unknown stages are
+/// returned as given
+#[wasm_bindgen]
+pub fn lot_stage_name(stage: &str, thai: bool) -> String {
+    let language = if thai { Language::Thai } else { Language::English };
+    LotStage::from_name(stage).map_or(stage, |stage| stage.name_in(&language)).to_string()
+}
+
+/// Stages a lot at `stage` may be moved on to, as stored names
+#[wasm_bindgen]
+pub fn lot_stage_next_stages(stage: &str) -> Vec<String> {
+    LotStage::from_name(stage)
+        .map(|stage| stage.next_stages().iter().map(|next| next.as_str().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether a lot may be moved from one stage to another, as the backend
+/// checks when a lot is updated
+#[wasm_bindgen]
+pub fn can_transition_lot_stage(from: &str, to: &str) -> bool {
+    match (LotStage::from_name(from), LotStage::from_name(to)) {
+        (Some(from), Some(to)) => from.can_transition_to(to),
+        _ => false,
+    }
+}
+
+/// Check a processing method in any accepted JSON form and return it in
+/// the stable form the backend stores, e.g.
+/// `{"type":"honey","mucilage_percent":30}`
+#[wasm_bindgen]
+pub fn validate_processing_method_json(method_json: &str) -> Result<String, JsValue> {
+    let method: ProcessingMethod = serde_json::from_str(method_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid processing method JSON: {}", e)))?;
+    validate_processing_method(&method).map_err(JsValue::from_str)?;
+    serde_json::to_string(&method).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Processing method typed by a user, such as "honey 30%" or "ล้าง", in
+/// its stable JSON form; None when not recognised
+#[wasm_bindgen]
+pub fn parse_processing_method(text: &str) -> Option<String> {
+    ProcessingMethod::parse(text).and_then(|method| serde_json::to_string(&method).ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_classify_coffee_grade() {
@@ -100,6 +173,16 @@ mod tests {
         assert_eq!(classify_coffee_grade(10, 100), "Off Grade");
     }
 
+    #[test]
+    fn test_classify_defect_breakdown() {
+        // 4 partial blacks and 9 broken beans make 1 + 1 category 2 defects
+        assert_eq!(
+            classify_defect_breakdown(r#"{"partial_black": 4, "broken": 9}"#).unwrap(),
+            "Specialty Grade"
+        );
+        assert_eq!(classify_defect_breakdown(r#"{"full_black": 1}"#).unwrap(), "Premium Grade");
+    }
+
     #[test]
     fn test_validate_ripeness() {
         assert!(validate_ripeness_assessment(10, 80, 10));
@@ -118,4 +201,127 @@ mod tests {
         let loss = calculate_roast_weight_loss(100.0, 85.0);
         assert!((loss - 15.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234567.891, 2, false), "1,234,567.89");
+        assert_eq!(format_number(1234.5, 1, true), "๑,๒๓๔.๕");
+        assert_eq!(format_number(f64::NAN, 0, false), "0");
+    }
+
+    #[test]
+    fn test_development_time_ratio() {
+        let dtr = calculate_development_time_ratio(120, 600);
+        assert!((dtr - 20.0).abs() < 0.001);
+        assert_eq!(calculate_development_time_ratio(120, 0), 0.0);
+    }
+
+    #[test]
+    fn test_lot_stages() {
+        assert_eq!(lot_stage_name("green_bean", false), "Green Bean");
+        assert_eq!(lot_stage_name("green_bean", true), "สารกาแฟ");
+        assert_eq!(lot_stage_next_stages("green_bean"), vec!["roasted_bean", "sold"]);
+        assert!(lot_stage_next_stages("sold").is_empty());
+        assert!(can_transition_lot_stage("cherry", "green_bean"));
+        assert!(!can_transition_lot_stage("roasted_bean", "cherry"));
+        assert!(!can_transition_lot_stage("GreenBean", "sold"));
+    }
+
+    #[test]
+    fn test_processing_methods() {
+        assert_eq!(
+            validate_processing_method_json(r#"{"honey":{"mucilage_percent":30}}"#).unwrap(),
+            r#"{"type":"honey","mucilage_percent":30}"#
+        );
+        assert_eq!(validate_processing_method_json(r#""washed""#).unwrap(), r#"{"type":"washed"}"#);
+        assert_eq!(
+            parse_processing_method("anaerobic 48h").as_deref(),
+            Some(r#"{"type":"anaerobic","hours":48}"#)
+        );
+        assert_eq!(parse_processing_method("steamed"), None);
+    }
+
+    fn to_f64(value: Decimal) -> f64 {
+        value.to_string().parse().unwrap()
+    }
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    // ========================================================================
+    // WASM exports must agree with the shared (backend) implementations
+    // ========================================================================
+
+    proptest! {
+        #[test]
+        fn prop_cupping_total_matches_shared(
+            attrs in proptest::collection::vec(24u32..=40, 7),
+            cups in proptest::collection::vec(0u32..=5, 3),
+        ) {
+            // Attributes in 0.25 steps (6.00-10.00), cup scores in 2 point steps
+            let quarter = |q: u32| Decimal::new(q as i64 * 25, 2);
+            let cup = |c: u32| Decimal::from(c * 2);
+            let scores = CuppingScores {
+                fragrance_aroma: quarter(attrs[0]),
+                flavor: quarter(attrs[1]),
+                aftertaste: quarter(attrs[2]),
+                acidity: quarter(attrs[3]),
+                body: quarter(attrs[4]),
+                balance: quarter(attrs[5]),
+                uniformity: cup(cups[0]),
+                clean_cup: cup(cups[1]),
+                sweetness: cup(cups[2]),
+                overall: quarter(attrs[6]),
+            };
+
+            let json = serde_json::to_string(&scores).unwrap();
+            let wasm_total = calculate_cupping_total(&json).unwrap();
+            prop_assert!(approx_eq(wasm_total, to_f64(scores.total())));
+        }
+
+        #[test]
+        fn prop_processing_yield_matches_shared(cherry in 1u32..100_000, ratio in 0u32..=1000) {
+            let cherry_kg = Decimal::new(cherry as i64, 1);
+            let green_kg = cherry_kg * Decimal::new(ratio as i64, 3);
+
+            let wasm_yield = calculate_processing_yield(to_f64(cherry_kg), to_f64(green_kg));
+            let shared_yield = shared::calculate_processing_yield(cherry_kg, green_kg);
+            prop_assert!(approx_eq(wasm_yield, to_f64(shared_yield)));
+        }
+
+        #[test]
+        fn prop_roast_weight_loss_matches_shared(green in 1u32..100_000, ratio in 0u32..=1000) {
+            let green_kg = Decimal::new(green as i64, 2);
+            let roasted_kg = green_kg * Decimal::new(ratio as i64, 3);
+
+            let wasm_loss = calculate_roast_weight_loss(to_f64(green_kg), to_f64(roasted_kg));
+            let shared_loss = calculate_weight_loss(green_kg, roasted_kg);
+            prop_assert!(approx_eq(wasm_loss, to_f64(shared_loss)));
+        }
+
+        #[test]
+        fn prop_dtr_matches_shared(total in 1i32..3600, development in 0i32..3600) {
+            let development = development.min(total);
+            let wasm_dtr = calculate_development_time_ratio(development, total);
+            prop_assert!(approx_eq(wasm_dtr, to_f64(calculate_dtr(development, total))));
+        }
+
+        #[test]
+        fn prop_format_number_matches_shared(cents in -1_000_000_000i64..1_000_000_000, thai in any::<bool>()) {
+            let value = Decimal::new(cents, 2);
+            let digits = if thai { DigitSystem::Thai } else { DigitSystem::Arabic };
+            prop_assert_eq!(format_number(to_f64(value), 2, thai), format_decimal(value, 2, digits));
+        }
+
+        #[test]
+        fn prop_grade_matches_shared(category1 in 0i32..20, category2 in 0i32..120) {
+            let defects = DefectCount {
+                category1_count: category1,
+                category2_count: category2,
+                defect_breakdown: None,
+            };
+            prop_assert_eq!(classify_coffee_grade(category1, category2), classify_grade(&defects).to_string());
+        }
+    }
 }